@@ -0,0 +1,51 @@
+use docbox_http::error::HttpErrorResponse;
+use reqwest::StatusCode;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DocboxClientError {
+    /// Base URL cannot have path segments appended (i.e data: URLs)
+    #[error("invalid docbox base url")]
+    InvalidBaseUrl,
+
+    /// Failed to send the request or read the response
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+
+    /// Failed to serialize the upload processing config
+    #[error("failed to serialize processing config")]
+    SerializeProcessingConfig(serde_json::Error),
+
+    /// Event message contained a malformed claim check
+    #[error("invalid event claim check")]
+    InvalidClaimCheck(serde_json::Error),
+
+    /// Docbox responded with an error status
+    #[error("docbox responded with {status}: {}", error.reason)]
+    Response {
+        /// HTTP status code of the response
+        status: StatusCode,
+        /// Error response from the server
+        error: HttpErrorResponse,
+    },
+
+    /// Presigned upload response contained an invalid HTTP method
+    #[error("presigned upload method is invalid")]
+    InvalidPresignedMethod,
+
+    /// Storage rejected the presigned file upload
+    #[error("storage rejected the presigned upload: {0}")]
+    PresignedUploadRejected(StatusCode),
+
+    /// Server failed to process the presigned upload
+    #[error("presigned upload failed: {0}")]
+    PresignedUploadFailed(String),
+
+    /// Presigned upload URL expired before the upload completed
+    #[error("presigned upload expired")]
+    PresignedUploadExpired,
+
+    /// Presigned upload did not complete in time
+    #[error("timed out waiting for the presigned upload to complete")]
+    PresignedUploadTimeout,
+}
@@ -0,0 +1,625 @@
+#![forbid(unsafe_code)]
+
+//! # Docbox Client
+//!
+//! Typed client for the docbox HTTP API. Request and response types are
+//! shared with the docbox-http crate so they always match the server.
+//!
+//! ```ignore
+//! let client = DocboxClient::new(base_url, tenant_id, "Development")?
+//!     .with_api_key("my-api-key");
+//!
+//! let document_box = client.create_document_box("user:1:files").await?;
+//! let uploaded = client
+//!     .upload_file(
+//!         "user:1:files",
+//!         FileUpload::new("notes.txt", document_box.root.folder.id, bytes),
+//!     )
+//!     .await?;
+//! ```
+
+use bytes::Bytes;
+use docbox_http::{
+    core::{
+        database::models::{
+            event_payload::{EventPayload, EventPayloadId},
+            file::{FileId, FileWithExtra},
+            folder::FolderId,
+            generated_file::GeneratedFile,
+            link::{LinkId, LinkWithExtra},
+            link_visit_stats::LinkVisitStats,
+            presigned_upload_task::PresignedUploadTaskId,
+            tenant::TenantId,
+        },
+        events::sqs::TenantEventClaimCheck,
+        processing::ProcessingConfig,
+        search::models::{SearchRequest, SearchResultResponse, SuggestRequest, SuggestResults},
+    },
+    error::HttpErrorResponse,
+    middleware::{
+        action_user::{USER_ID_HEADER, USER_IMAGE_ID_HEADER, USER_NAME_HEADER},
+        correlation_id::CORRELATION_ID_HEADER,
+        tenant::{TENANT_ENV_HEADER, TENANT_ID_HEADER},
+    },
+    models::{
+        document_box::{CreateDocumentBoxRequest, DocumentBoxResponse},
+        file::{
+            CreatePresignedRequest, FileResponse, PresignedStatusResponse, PresignedUploadResponse,
+            UploadedFile,
+        },
+        folder::{CreateFolderRequest, FolderResponse},
+        link::{CreateLink, LinkResponse},
+    },
+};
+use mime::Mime;
+use reqwest::{
+    Method, RequestBuilder, Response,
+    multipart::{Form, Part},
+};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+use tokio::time::Instant;
+use url::Url;
+
+mod error;
+
+pub use error::DocboxClientError;
+
+/// Re-exports of the docbox-http request and response models
+pub mod models {
+    pub use docbox_http::{error::HttpErrorResponse, models::*};
+}
+
+/// Re-exports of the docbox-database models included in responses
+pub mod database_models {
+    pub use docbox_http::core::database::models::*;
+}
+
+/// Re-exports of the docbox-search request and response models
+pub mod search_models {
+    pub use docbox_http::core::search::models::*;
+}
+
+/// Header for providing the API key
+const API_KEY_HEADER: &str = "x-docbox-api-key";
+
+/// Client for the docbox HTTP API, scoped to a specific tenant
+#[derive(Clone)]
+pub struct DocboxClient {
+    http: reqwest::Client,
+    base_url: Url,
+    tenant_id: TenantId,
+    tenant_env: String,
+    api_key: Option<String>,
+    user: Option<DocboxUser>,
+}
+
+/// Details about the user performing actions, stored by the server
+/// to track who created and modified resources
+#[derive(Debug, Clone)]
+pub struct DocboxUser {
+    /// Unique ID of the user
+    pub id: String,
+    /// Name of the user
+    pub name: Option<String>,
+    /// ID of the user profile image
+    pub image_id: Option<String>,
+}
+
+/// File to upload through [DocboxClient::upload_file]
+#[derive(Debug, Clone)]
+pub struct FileUpload {
+    /// Name of the file
+    pub name: String,
+    /// ID of the folder to store the file in
+    pub folder_id: FolderId,
+    /// Contents of the file
+    pub file: Bytes,
+    /// Mime type of the file, when not specified the server will attempt
+    /// to determine the mime type from the file name
+    pub mime: Option<Mime>,
+    /// Whether to disable mime sniffing for the file
+    pub disable_mime_sniffing: Option<bool>,
+    /// Fixed file ID the file must use, should only be used when
+    /// migrating existing files
+    pub fixed_id: Option<FileId>,
+    /// ID of the parent file if this file is associated as a child
+    /// of another file
+    pub parent_id: Option<FileId>,
+    /// Optional processing config
+    pub processing_config: Option<ProcessingConfig>,
+}
+
+impl FileUpload {
+    /// Create a new file upload with the default options
+    pub fn new(name: impl Into<String>, folder_id: FolderId, file: impl Into<Bytes>) -> Self {
+        Self {
+            name: name.into(),
+            folder_id,
+            file: file.into(),
+            mime: None,
+            disable_mime_sniffing: None,
+            fixed_id: None,
+            parent_id: None,
+            processing_config: None,
+        }
+    }
+}
+
+/// Outcome of a completed presigned upload
+#[derive(Debug)]
+pub struct PresignedUploadComplete {
+    /// The uploaded file
+    pub file: FileWithExtra,
+    /// Files generated from the file
+    pub generated: Vec<GeneratedFile>,
+}
+
+impl DocboxClient {
+    /// Create a new client for the docbox server at `base_url` targeting
+    /// the tenant with the provided `tenant_id` and `tenant_env`
+    pub fn new(
+        base_url: Url,
+        tenant_id: TenantId,
+        tenant_env: impl Into<String>,
+    ) -> Result<Self, DocboxClientError> {
+        if base_url.cannot_be_a_base() {
+            return Err(DocboxClientError::InvalidBaseUrl);
+        }
+
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base_url,
+            tenant_id,
+            tenant_env: tenant_env.into(),
+            api_key: None,
+            user: None,
+        })
+    }
+
+    /// Use the provided `http` client to send requests
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Authenticate requests using the provided `api_key`
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Perform actions as the provided `user`
+    pub fn with_user(mut self, user: DocboxUser) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// Create a new document box
+    ///
+    /// POST /box
+    pub async fn create_document_box(
+        &self,
+        scope: &str,
+    ) -> Result<DocumentBoxResponse, DocboxClientError> {
+        let request = self
+            .request(Method::POST, &["box"])
+            .json(&CreateDocumentBoxRequest {
+                scope: scope.to_string(),
+            });
+        self.send_json(request).await
+    }
+
+    /// Get a document box by `scope`
+    ///
+    /// GET /box/{scope}
+    pub async fn get_document_box(
+        &self,
+        scope: &str,
+    ) -> Result<DocumentBoxResponse, DocboxClientError> {
+        let request = self.request(Method::GET, &["box", scope]);
+        self.send_json(request).await
+    }
+
+    /// Delete a document box and all of its contents
+    ///
+    /// DELETE /box/{scope}
+    pub async fn delete_document_box(&self, scope: &str) -> Result<(), DocboxClientError> {
+        let request = self.request(Method::DELETE, &["box", scope]);
+        self.send(request).await.map(|_| ())
+    }
+
+    /// Search within a document box
+    ///
+    /// POST /box/{scope}/search
+    pub async fn search(
+        &self,
+        scope: &str,
+        search: &SearchRequest,
+    ) -> Result<SearchResultResponse, DocboxClientError> {
+        let request = self
+            .request(Method::POST, &["box", scope, "search"])
+            .json(search);
+        self.send_json(request).await
+    }
+
+    /// Suggest completions for the names of items within a document box
+    ///
+    /// POST /box/{scope}/suggest
+    pub async fn suggest(
+        &self,
+        scope: &str,
+        suggest: &SuggestRequest,
+    ) -> Result<SuggestResults, DocboxClientError> {
+        let request = self
+            .request(Method::POST, &["box", scope, "suggest"])
+            .json(suggest);
+        self.send_json(request).await
+    }
+
+    /// Create a folder
+    ///
+    /// POST /box/{scope}/folder
+    pub async fn create_folder(
+        &self,
+        scope: &str,
+        create: &CreateFolderRequest,
+    ) -> Result<FolderResponse, DocboxClientError> {
+        let request = self
+            .request(Method::POST, &["box", scope, "folder"])
+            .json(create);
+        self.send_json(request).await
+    }
+
+    /// Get a folder and its children
+    ///
+    /// GET /box/{scope}/folder/{folder_id}
+    pub async fn get_folder(
+        &self,
+        scope: &str,
+        folder_id: FolderId,
+    ) -> Result<FolderResponse, DocboxClientError> {
+        let folder_id = folder_id.to_string();
+        let request = self.request(Method::GET, &["box", scope, "folder", &folder_id]);
+        self.send_json(request).await
+    }
+
+    /// Delete a folder and all of its contents
+    ///
+    /// DELETE /box/{scope}/folder/{folder_id}
+    pub async fn delete_folder(
+        &self,
+        scope: &str,
+        folder_id: FolderId,
+    ) -> Result<(), DocboxClientError> {
+        let folder_id = folder_id.to_string();
+        let request = self.request(Method::DELETE, &["box", scope, "folder", &folder_id]);
+        self.send(request).await.map(|_| ())
+    }
+
+    /// Upload a file directly, waits for the file to finish processing
+    ///
+    /// POST /box/{scope}/file
+    pub async fn upload_file(
+        &self,
+        scope: &str,
+        upload: FileUpload,
+    ) -> Result<UploadedFile, DocboxClientError> {
+        let length = upload.file.len() as u64;
+        let mut file = Part::stream_with_length(upload.file, length).file_name(upload.name.clone());
+        if let Some(mime) = upload.mime.as_ref() {
+            file = file.mime_str(mime.as_ref())?;
+        }
+
+        let mut form = Form::new()
+            .text("name", upload.name)
+            .text("folder_id", upload.folder_id.to_string())
+            .text("asynchronous", "false")
+            .part("file", file);
+
+        if let Some(mime) = upload.mime {
+            form = form.text("mime", mime.to_string());
+        }
+
+        if let Some(disable_mime_sniffing) = upload.disable_mime_sniffing {
+            form = form.text("disable_mime_sniffing", disable_mime_sniffing.to_string());
+        }
+
+        if let Some(fixed_id) = upload.fixed_id {
+            form = form.text("fixed_id", fixed_id.to_string());
+        }
+
+        if let Some(parent_id) = upload.parent_id {
+            form = form.text("parent_id", parent_id.to_string());
+        }
+
+        if let Some(processing_config) = upload.processing_config.as_ref() {
+            let processing_config = serde_json::to_string(processing_config)
+                .map_err(DocboxClientError::SerializeProcessingConfig)?;
+            form = form.text("processing_config", processing_config);
+        }
+
+        let request = self
+            .request(Method::POST, &["box", scope, "file"])
+            .multipart(form);
+        self.send_json(request).await
+    }
+
+    /// Get a file
+    ///
+    /// GET /box/{scope}/file/{file_id}
+    pub async fn get_file(
+        &self,
+        scope: &str,
+        file_id: FileId,
+    ) -> Result<FileResponse, DocboxClientError> {
+        let file_id = file_id.to_string();
+        let request = self.request(Method::GET, &["box", scope, "file", &file_id]);
+        self.send_json(request).await
+    }
+
+    /// Get the raw contents of a file
+    ///
+    /// GET /box/{scope}/file/{file_id}/raw
+    pub async fn get_file_raw(
+        &self,
+        scope: &str,
+        file_id: FileId,
+    ) -> Result<Bytes, DocboxClientError> {
+        let file_id = file_id.to_string();
+        let request = self.request(Method::GET, &["box", scope, "file", &file_id, "raw"]);
+        let response = self.send(request).await?;
+        Ok(response.bytes().await?)
+    }
+
+    /// Delete a file
+    ///
+    /// DELETE /box/{scope}/file/{file_id}
+    pub async fn delete_file(&self, scope: &str, file_id: FileId) -> Result<(), DocboxClientError> {
+        let file_id = file_id.to_string();
+        let request = self.request(Method::DELETE, &["box", scope, "file", &file_id]);
+        self.send(request).await.map(|_| ())
+    }
+
+    /// Create a presigned upload, the file must then be uploaded to the
+    /// returned URL (See [DocboxClient::upload_presigned])
+    ///
+    /// POST /box/{scope}/file/presigned
+    pub async fn create_presigned_upload(
+        &self,
+        scope: &str,
+        create: &CreatePresignedRequest,
+    ) -> Result<PresignedUploadResponse, DocboxClientError> {
+        let request = self
+            .request(Method::POST, &["box", scope, "file", "presigned"])
+            .json(create);
+        self.send_json(request).await
+    }
+
+    /// Get the status of a presigned upload
+    ///
+    /// GET /box/{scope}/file/presigned/{task_id}
+    pub async fn get_presigned_upload(
+        &self,
+        scope: &str,
+        task_id: PresignedUploadTaskId,
+    ) -> Result<PresignedStatusResponse, DocboxClientError> {
+        let task_id = task_id.to_string();
+        let request = self.request(Method::GET, &["box", scope, "file", "presigned", &task_id]);
+        self.send_json(request).await
+    }
+
+    /// Create a presigned upload and upload the `file` contents to storage
+    ///
+    /// The server processes the file once it receives the storage upload
+    /// notification, use [DocboxClient::wait_presigned_upload] with the
+    /// returned task ID to wait for the file
+    pub async fn upload_presigned(
+        &self,
+        scope: &str,
+        create: &CreatePresignedRequest,
+        file: Bytes,
+    ) -> Result<PresignedUploadResponse, DocboxClientError> {
+        let presigned = self.create_presigned_upload(scope, create).await?;
+
+        let method = Method::from_bytes(presigned.method.as_bytes())
+            .map_err(|_| DocboxClientError::InvalidPresignedMethod)?;
+
+        let mut request = self.http.request(method, &presigned.uri).body(file);
+        for (name, value) in &presigned.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(DocboxClientError::PresignedUploadRejected(status));
+        }
+
+        Ok(presigned)
+    }
+
+    /// Poll the status of a presigned upload every `interval` until it completes,
+    /// fails or `timeout` is reached
+    pub async fn wait_presigned_upload(
+        &self,
+        scope: &str,
+        task_id: PresignedUploadTaskId,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<PresignedUploadComplete, DocboxClientError> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            match self.get_presigned_upload(scope, task_id).await? {
+                PresignedStatusResponse::Pending => {}
+                PresignedStatusResponse::Complete { file, generated } => {
+                    return Ok(PresignedUploadComplete { file, generated });
+                }
+                PresignedStatusResponse::Failed { error } => {
+                    return Err(DocboxClientError::PresignedUploadFailed(error));
+                }
+                PresignedStatusResponse::Expired => {
+                    return Err(DocboxClientError::PresignedUploadExpired);
+                }
+            }
+
+            if Instant::now() + interval > deadline {
+                return Err(DocboxClientError::PresignedUploadTimeout);
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Create a link
+    ///
+    /// POST /box/{scope}/link
+    pub async fn create_link(
+        &self,
+        scope: &str,
+        create: &CreateLink,
+    ) -> Result<LinkWithExtra, DocboxClientError> {
+        let request = self
+            .request(Method::POST, &["box", scope, "link"])
+            .json(create);
+        self.send_json(request).await
+    }
+
+    /// Get a link
+    ///
+    /// GET /box/{scope}/link/{link_id}
+    pub async fn get_link(
+        &self,
+        scope: &str,
+        link_id: LinkId,
+    ) -> Result<LinkResponse, DocboxClientError> {
+        let link_id = link_id.to_string();
+        let request = self.request(Method::GET, &["box", scope, "link", &link_id]);
+        self.send_json(request).await
+    }
+
+    /// Record a visit to a link
+    ///
+    /// POST /box/{scope}/link/{link_id}/visit
+    pub async fn visit_link(
+        &self,
+        scope: &str,
+        link_id: LinkId,
+    ) -> Result<LinkVisitStats, DocboxClientError> {
+        let link_id = link_id.to_string();
+        let request = self.request(Method::POST, &["box", scope, "link", &link_id, "visit"]);
+        self.send_json(request).await
+    }
+
+    /// Delete a link
+    ///
+    /// DELETE /box/{scope}/link/{link_id}
+    pub async fn delete_link(&self, scope: &str, link_id: LinkId) -> Result<(), DocboxClientError> {
+        let link_id = link_id.to_string();
+        let request = self.request(Method::DELETE, &["box", scope, "link", &link_id]);
+        self.send(request).await.map(|_| ())
+    }
+
+    /// Get the stored payload of an event that was published as a claim check
+    ///
+    /// GET /admin/event-payloads/{payload_id}
+    pub async fn get_event_payload(
+        &self,
+        payload_id: EventPayloadId,
+    ) -> Result<EventPayload, DocboxClientError> {
+        let payload_id = payload_id.to_string();
+        let request = self.request(Method::GET, &["admin", "event-payloads", &payload_id]);
+        self.send_json(request).await
+    }
+
+    /// Resolve an event `message` received from the tenant event queue, messages
+    /// published as a claim check are replaced with the full stored event
+    pub async fn resolve_event(
+        &self,
+        message: serde_json::Value,
+    ) -> Result<serde_json::Value, DocboxClientError> {
+        let Some(claim_check) = message.get("claim_check") else {
+            return Ok(message);
+        };
+
+        let claim_check: TenantEventClaimCheck = serde_json::from_value(claim_check.clone())
+            .map_err(DocboxClientError::InvalidClaimCheck)?;
+
+        let payload = self.get_event_payload(claim_check.id).await?;
+        Ok(payload.payload)
+    }
+
+    /// Create a request to the path made up of `segments` with the
+    /// tenant, authentication and user headers
+    fn request(&self, method: Method, segments: &[&str]) -> RequestBuilder {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            // Checked when creating the client
+            .expect("base url must be a base")
+            .pop_if_empty()
+            .extend(segments);
+
+        let mut request = self
+            .http
+            .request(method, url)
+            .header(TENANT_ID_HEADER, self.tenant_id.to_string())
+            .header(TENANT_ENV_HEADER, &self.tenant_env);
+
+        if let Some(api_key) = self.api_key.as_ref() {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+
+        if let Some(user) = self.user.as_ref() {
+            request = request.header(USER_ID_HEADER, &user.id);
+
+            if let Some(name) = user.name.as_ref() {
+                request = request.header(USER_NAME_HEADER, name);
+            }
+
+            if let Some(image_id) = user.image_id.as_ref() {
+                request = request.header(USER_IMAGE_ID_HEADER, image_id);
+            }
+        }
+
+        request
+    }
+
+    /// Send the `request` mapping error status codes into [DocboxClientError::Response]
+    async fn send(&self, request: RequestBuilder) -> Result<Response, DocboxClientError> {
+        let response = request.send().await?;
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let correlation_id = response
+            .headers()
+            .get(CORRELATION_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        let body = response.bytes().await?;
+        let error = serde_json::from_slice::<HttpErrorResponse>(&body).unwrap_or_else(|_| {
+            HttpErrorResponse {
+                code: "UNKNOWN".to_string(),
+                reason: String::from_utf8_lossy(&body).into_owned(),
+                retryable: false,
+                correlation_id,
+                details: None,
+            }
+        });
+
+        Err(DocboxClientError::Response { status, error })
+    }
+
+    /// Send the `request` and parse the JSON response body
+    async fn send_json<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, DocboxClientError> {
+        let response = self.send(request).await?;
+        Ok(response.json().await?)
+    }
+}
@@ -0,0 +1,33 @@
+use docbox_client::DocboxClient;
+use docbox_test_utils::{TestEnvironment, TestEnvironmentConfig, TestSearchBackend};
+
+/// Running docbox server along with a client targeting its tenant
+pub struct TestServer {
+    pub client: DocboxClient,
+    _server: docbox_test_utils::TestServer,
+}
+
+/// Starts the docbox server backed by postgres and minio containers, using
+/// the database search backend
+///
+/// Requires that the test runner have docker available
+pub async fn test_server() -> TestServer {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        search: TestSearchBackend::Database,
+        // Office conversion is not used by the tests
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let tenant = &server.environment.tenant;
+    let base_url = server.base_url().parse().unwrap();
+    let client = DocboxClient::new(base_url, tenant.id, tenant.env.clone()).unwrap();
+
+    TestServer {
+        client,
+        _server: server,
+    }
+}
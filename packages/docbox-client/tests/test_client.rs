@@ -0,0 +1,265 @@
+#![recursion_limit = "256"]
+
+use crate::common::test_server;
+use docbox_client::{
+    DocboxClientError, FileUpload,
+    models::{file::CreatePresignedRequest, folder::CreateFolderRequest, link::CreateLink},
+    search_models::{SearchRequest, SearchResultData},
+};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+mod common;
+
+/// Tests that a document box can be created, fetched and deleted
+#[tokio::test]
+async fn test_document_box_lifecycle() {
+    let server = test_server().await;
+    let client = &server.client;
+
+    let created = client.create_document_box("test:1").await.unwrap();
+    assert_eq!(created.document_box.scope, "test:1");
+    assert_eq!(created.root.folder.document_box, "test:1");
+
+    let fetched = client.get_document_box("test:1").await.unwrap();
+    assert_eq!(fetched.root.folder.id, created.root.folder.id);
+
+    client.delete_document_box("test:1").await.unwrap();
+
+    let error = client.get_document_box("test:1").await.unwrap_err();
+    assert!(matches!(
+        error,
+        DocboxClientError::Response { status, .. } if status == StatusCode::NOT_FOUND
+    ));
+}
+
+/// Tests that creating a duplicate document box returns the server error
+#[tokio::test]
+async fn test_error_response() {
+    let server = test_server().await;
+    let client = &server.client;
+
+    client.create_document_box("test").await.unwrap();
+
+    let error = client.create_document_box("test").await.unwrap_err();
+    match error {
+        DocboxClientError::Response { status, error } => {
+            assert_eq!(status, StatusCode::CONFLICT);
+            assert_eq!(
+                error.reason,
+                "document box with matching scope already exists"
+            );
+        }
+        error => panic!("unexpected error {error:?}"),
+    }
+}
+
+/// Tests that a folder can be created and fetched
+#[tokio::test]
+async fn test_create_folder() {
+    let server = test_server().await;
+    let client = &server.client;
+
+    let document_box = client.create_document_box("test").await.unwrap();
+
+    let created = client
+        .create_folder(
+            "test",
+            &CreateFolderRequest {
+                name: "Test Folder".to_string(),
+                folder_id: document_box.root.folder.id,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.folder.folder.name, "Test Folder");
+    assert_eq!(
+        created.folder.folder.folder_id,
+        Some(document_box.root.folder.id)
+    );
+
+    let fetched = client
+        .get_folder("test", created.folder.folder.id)
+        .await
+        .unwrap();
+    assert_eq!(fetched.folder.folder.id, created.folder.folder.id);
+
+    let root = client.get_document_box("test").await.unwrap();
+    assert_eq!(root.children.folders.len(), 1);
+
+    client
+        .delete_folder("test", created.folder.folder.id)
+        .await
+        .unwrap();
+
+    let root = client.get_document_box("test").await.unwrap();
+    assert!(root.children.folders.is_empty());
+}
+
+/// Tests that a file can be uploaded, downloaded and deleted
+#[tokio::test]
+async fn test_upload_file() {
+    let server = test_server().await;
+    let client = &server.client;
+
+    let document_box = client.create_document_box("test").await.unwrap();
+
+    let mut upload = FileUpload::new(
+        "test.txt",
+        document_box.root.folder.id,
+        &b"Test file content"[..],
+    );
+    upload.mime = Some(mime::TEXT_PLAIN);
+
+    let uploaded = client.upload_file("test", upload).await.unwrap();
+    assert_eq!(uploaded.file.file.name, "test.txt");
+    assert_eq!(uploaded.file.file.mime, "text/plain");
+
+    let file_id = uploaded.file.file.id;
+
+    let fetched = client.get_file("test", file_id).await.unwrap();
+    assert_eq!(fetched.file.file.id, file_id);
+
+    let raw = client.get_file_raw("test", file_id).await.unwrap();
+    assert_eq!(&raw[..], b"Test file content");
+
+    client.delete_file("test", file_id).await.unwrap();
+
+    let error = client.get_file("test", file_id).await.unwrap_err();
+    assert!(matches!(
+        error,
+        DocboxClientError::Response { status, .. } if status == StatusCode::NOT_FOUND
+    ));
+}
+
+/// Tests that a link can be created, fetched and deleted
+#[tokio::test]
+async fn test_create_link() {
+    let server = test_server().await;
+    let client = &server.client;
+
+    let document_box = client.create_document_box("test").await.unwrap();
+
+    let created = client
+        .create_link(
+            "test",
+            &CreateLink {
+                name: "Example".to_string(),
+                value: "https://example.com".to_string(),
+                folder_id: document_box.root.folder.id,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.link.name, "Example");
+    assert_eq!(created.link.value, "https://example.com");
+
+    let fetched = client.get_link("test", created.link.id).await.unwrap();
+    assert_eq!(fetched.link.link.id, created.link.id);
+    assert!(fetched.metadata_stale);
+
+    client.delete_link("test", created.link.id).await.unwrap();
+}
+
+/// Tests that uploaded content can be searched
+#[tokio::test]
+async fn test_search() {
+    let server = test_server().await;
+    let client = &server.client;
+
+    let document_box = client.create_document_box("test").await.unwrap();
+
+    let upload = FileUpload::new(
+        "searchable.txt",
+        document_box.root.folder.id,
+        &b"Unique content"[..],
+    );
+    let uploaded = client.upload_file("test", upload).await.unwrap();
+
+    let results = client
+        .search(
+            "test",
+            &SearchRequest {
+                query: Some("searchable".to_string()),
+                include_name: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(results.total_hits, 1);
+    let first = results.results.first().unwrap();
+    assert!(matches!(
+        &first.data,
+        SearchResultData::File(file) if file.file.id == uploaded.file.file.id
+    ));
+}
+
+/// Tests that a presigned upload can be created and the file uploaded to
+/// storage, the upload remains pending as there is no storage notification
+/// queue processing the upload
+#[tokio::test]
+async fn test_presigned_upload() {
+    let server = test_server().await;
+    let client = &server.client;
+
+    let document_box = client.create_document_box("test").await.unwrap();
+
+    let content = &b"Presigned content"[..];
+    let presigned = client
+        .upload_presigned(
+            "test",
+            &CreatePresignedRequest {
+                name: "presigned.txt".to_string(),
+                folder_id: document_box.root.folder.id,
+                size: content.len() as i32,
+                mime: Some(mime::TEXT_PLAIN),
+                parent_id: None,
+                processing_config: None,
+                disable_mime_sniffing: None,
+            },
+            content.into(),
+        )
+        .await
+        .unwrap();
+
+    let error = client
+        .wait_presigned_upload(
+            "test",
+            presigned.task_id,
+            Duration::from_millis(100),
+            Duration::from_millis(500),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(error, DocboxClientError::PresignedUploadTimeout));
+}
+
+/// Tests that event messages are only resolved when published as a claim check
+#[tokio::test]
+async fn test_resolve_event() {
+    let server = test_server().await;
+    let client = &server.client;
+
+    // Messages without a claim check are returned as-is
+    let message = serde_json::json!({ "event": "DOCUMENT_BOX_CREATED", "data": {} });
+    let resolved = client.resolve_event(message.clone()).await.unwrap();
+    assert_eq!(resolved, message);
+
+    // Claim checks referencing an unknown payload are reported
+    let message = serde_json::json!({
+        "event": "FILE_CREATED",
+        "claim_check": { "id": "00000000-0000-0000-0000-000000000000" }
+    });
+    let error = client.resolve_event(message).await.unwrap_err();
+    assert!(matches!(
+        error,
+        DocboxClientError::Response { status, .. } if status == StatusCode::NOT_FOUND
+    ));
+
+    // Malformed claim checks are rejected
+    let message = serde_json::json!({ "event": "FILE_CREATED", "claim_check": {} });
+    let error = client.resolve_event(message).await.unwrap_err();
+    assert!(matches!(error, DocboxClientError::InvalidClaimCheck(_)));
+}
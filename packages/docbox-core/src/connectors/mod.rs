@@ -0,0 +1,174 @@
+//! # Connectors
+//!
+//! Source connectors for bulk importing files from external sources into
+//! a document box.
+//!
+//! - [SourceConnector] Abstraction over an external source of objects
+//! - [S3Connector](s3::S3Connector) Objects within a S3 bucket prefix
+//!
+//! Syncing a connector ([sync_connector](sync_connector::sync_connector)) is
+//! incremental, the ETag and last modified time of each synced object is stored
+//! so only new or changed objects are imported on subsequent syncs
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use docbox_database::models::connector_sync_item::ConnectorSyncItem;
+use std::{error::Error, future::Future};
+use thiserror::Error;
+
+pub mod s3;
+pub mod sync_connector;
+
+#[derive(Debug, Error)]
+pub enum ConnectorError {
+    /// Error from the S3 connector
+    #[error(transparent)]
+    S3(Box<s3::S3ConnectorError>),
+
+    /// Error from a connector implemented outside of docbox
+    #[error(transparent)]
+    Other(Box<dyn Error + Send + Sync + 'static>),
+}
+
+impl From<s3::S3ConnectorError> for ConnectorError {
+    fn from(value: s3::S3ConnectorError) -> Self {
+        Self::S3(Box::new(value))
+    }
+}
+
+/// Object available within a connector source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectorObject {
+    /// Key of the object relative to the connector source, "/" separated
+    /// path components are imported as folders
+    pub key: String,
+    /// ETag of the current object contents
+    pub etag: Option<String>,
+    /// When the object was last modified
+    pub last_modified: Option<DateTime<Utc>>,
+    /// Size of the object in bytes
+    pub size: u64,
+}
+
+impl ConnectorObject {
+    /// Whether the object is unchanged since it was last synced, compares the
+    /// ETag when both are known, falling back to the last modified time.
+    ///
+    /// Objects without either are always considered changed
+    pub fn is_unchanged(&self, item: &ConnectorSyncItem) -> bool {
+        if let (Some(etag), Some(synced_etag)) = (self.etag.as_ref(), item.etag.as_ref()) {
+            return etag == synced_etag;
+        }
+
+        match (self.last_modified, item.last_modified) {
+            (Some(last_modified), Some(synced_last_modified)) => {
+                last_modified == synced_last_modified
+            }
+            _ => false,
+        }
+    }
+
+    /// Splits the object key into the names of the folders the object is
+    /// within and the name of the object itself.
+    ///
+    /// Provides [None] for directory marker objects and keys containing
+    /// unsafe path components
+    pub fn path(&self) -> Option<(Vec<String>, String)> {
+        let mut components: Vec<String> = Vec::new();
+
+        for component in self.key.split('/') {
+            match component {
+                "" => continue,
+                "." | ".." => return None,
+                value => components.push(value.to_string()),
+            }
+        }
+
+        // Keys ending in a "/" are directory markers
+        if self.key.ends_with('/') {
+            return None;
+        }
+
+        let name = components.pop()?;
+        Some((components, name))
+    }
+}
+
+/// Abstraction over an external source of objects that can be synced
+/// into a document box
+pub trait SourceConnector: Send + Sync {
+    /// Identifier for the connector source, used to track the sync state of
+    /// objects between syncs (i.e "s3://bucket/prefix")
+    fn connector_id(&self) -> String;
+
+    /// List all objects available within the source
+    fn list_objects(
+        &self,
+    ) -> impl Future<Output = Result<Vec<ConnectorObject>, ConnectorError>> + Send;
+
+    /// Get the contents of the object with the provided `key`
+    fn get_object(&self, key: &str) -> impl Future<Output = Result<Bytes, ConnectorError>> + Send;
+}
+
+#[cfg(test)]
+mod test {
+    use super::ConnectorObject;
+    use chrono::{TimeZone, Utc};
+    use docbox_database::models::connector_sync_item::ConnectorSyncItem;
+    use uuid::Uuid;
+
+    fn make_object(key: &str, etag: Option<&str>, last_modified: Option<i64>) -> ConnectorObject {
+        ConnectorObject {
+            key: key.to_string(),
+            etag: etag.map(str::to_string),
+            last_modified: last_modified.map(|value| Utc.timestamp_opt(value, 0).unwrap()),
+            size: 0,
+        }
+    }
+
+    fn make_item(etag: Option<&str>, last_modified: Option<i64>) -> ConnectorSyncItem {
+        ConnectorSyncItem {
+            connector_id: "test".to_string(),
+            source_key: "test.txt".to_string(),
+            file_id: Uuid::nil(),
+            etag: etag.map(str::to_string),
+            last_modified: last_modified.map(|value| Utc.timestamp_opt(value, 0).unwrap()),
+            synced_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_is_unchanged() {
+        // ETag is preferred when both are known
+        let object = make_object("test.txt", Some("a"), Some(10));
+        assert!(object.is_unchanged(&make_item(Some("a"), Some(5))));
+        assert!(!object.is_unchanged(&make_item(Some("b"), Some(10))));
+
+        // Last modified used when the ETag is unknown
+        assert!(object.is_unchanged(&make_item(None, Some(10))));
+        assert!(!object.is_unchanged(&make_item(None, Some(5))));
+
+        // Nothing to compare
+        let object = make_object("test.txt", None, None);
+        assert!(!object.is_unchanged(&make_item(Some("a"), Some(10))));
+    }
+
+    #[test]
+    fn test_path() {
+        let object = make_object("reports/2024/summary.pdf", None, None);
+        assert_eq!(
+            object.path(),
+            Some((
+                vec!["reports".to_string(), "2024".to_string()],
+                "summary.pdf".to_string()
+            ))
+        );
+
+        let object = make_object("/readme.txt", None, None);
+        assert_eq!(object.path(), Some((vec![], "readme.txt".to_string())));
+
+        assert_eq!(make_object("reports/", None, None).path(), None);
+        assert_eq!(make_object("../escape.txt", None, None).path(), None);
+        assert_eq!(make_object("", None, None).path(), None);
+    }
+}
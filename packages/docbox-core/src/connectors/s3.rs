@@ -0,0 +1,162 @@
+//! # S3 Connector
+//!
+//! Source connector for objects within a prefix of a [S3](https://docs.aws.amazon.com/s3/)
+//! compatible bucket. Credentials are loaded from the provided AWS config
+
+use super::{ConnectorError, ConnectorObject, SourceConnector};
+use aws_config::SdkConfig;
+use aws_sdk_s3::{
+    error::SdkError,
+    operation::{get_object::GetObjectError, list_objects_v2::ListObjectsV2Error},
+    primitives::ByteStreamError,
+};
+use bytes::Bytes;
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+type S3Client = aws_sdk_s3::Client;
+
+/// Configuration for a S3 source connector
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct S3ConnectorConfig {
+    /// Name of the bucket to sync from
+    pub bucket: String,
+    /// Prefix within the bucket to sync from, only objects within the
+    /// prefix are synced and the prefix is removed from the object keys
+    pub prefix: String,
+    /// Custom endpoint to use for S3 compatible storage
+    pub endpoint: Option<String>,
+    /// Whether to force "path" style bucket access
+    pub force_path_style: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum S3ConnectorError {
+    /// Failed to list the bucket objects
+    #[error("failed to list source objects")]
+    ListObjects(SdkError<ListObjectsV2Error>),
+
+    /// Failed to get an object
+    #[error("failed to get source object")]
+    GetObject(SdkError<GetObjectError>),
+
+    /// Failed to read the object contents
+    #[error("failed to read source object")]
+    ReadObject(ByteStreamError),
+}
+
+/// Connector for objects within a S3 bucket prefix
+#[derive(Clone)]
+pub struct S3Connector {
+    client: S3Client,
+    bucket: String,
+    prefix: String,
+}
+
+impl S3Connector {
+    /// Create a [S3Connector] from the provided `config`
+    pub fn from_config(aws_config: &SdkConfig, config: S3ConnectorConfig) -> Self {
+        let mut config_builder =
+            aws_sdk_s3::config::Builder::from(aws_config).force_path_style(config.force_path_style);
+
+        if let Some(endpoint) = config.endpoint {
+            config_builder = config_builder.endpoint_url(endpoint);
+        }
+
+        Self::new(
+            S3Client::from_conf(config_builder.build()),
+            config.bucket,
+            config.prefix,
+        )
+    }
+
+    /// Create a [S3Connector] using an existing S3 `client`
+    pub fn new(client: S3Client, bucket: String, prefix: String) -> Self {
+        Self {
+            client,
+            bucket,
+            prefix,
+        }
+    }
+}
+
+impl SourceConnector for S3Connector {
+    fn connector_id(&self) -> String {
+        format!("s3://{}/{}", self.bucket, self.prefix)
+    }
+
+    async fn list_objects(&self) -> Result<Vec<ConnectorObject>, ConnectorError> {
+        let mut objects = Vec::new();
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let output = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(&self.prefix)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await
+                .map_err(|error| {
+                    tracing::error!(?error, "failed to list source objects");
+                    S3ConnectorError::ListObjects(error)
+                })?;
+
+            for object in output.contents() {
+                let Some(key) = object.key() else {
+                    continue;
+                };
+
+                let Some(key) = key.strip_prefix(&self.prefix) else {
+                    continue;
+                };
+
+                let last_modified = object
+                    .last_modified()
+                    .and_then(|value| DateTime::from_timestamp(value.secs(), value.subsec_nanos()));
+
+                objects.push(ConnectorObject {
+                    key: key.to_string(),
+                    etag: object.e_tag().map(str::to_string),
+                    last_modified,
+                    size: object.size().unwrap_or_default().max(0) as u64,
+                });
+            }
+
+            match output.next_continuation_token() {
+                Some(token) if output.is_truncated().unwrap_or_default() => {
+                    continuation_token = Some(token.to_string());
+                }
+                _ => break,
+            }
+        }
+
+        Ok(objects)
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Bytes, ConnectorError> {
+        let object = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(format!("{}{}", self.prefix, key))
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to get source object");
+                S3ConnectorError::GetObject(error)
+            })?;
+
+        let bytes = object
+            .body
+            .collect()
+            .await
+            .map_err(S3ConnectorError::ReadObject)?
+            .into_bytes();
+
+        Ok(bytes)
+    }
+}
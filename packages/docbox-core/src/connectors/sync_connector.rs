@@ -0,0 +1,369 @@
+//! # Sync Connector
+//!
+//! Imports the objects from a [SourceConnector] into a document box folder.
+//!
+//! The key of each object is used as its path within the target folder, any
+//! missing folders are created. The state of each synced object is stored
+//! so that subsequent syncs only import new or changed objects, changed
+//! objects replace the file created by the previous sync
+
+use super::{ConnectorError, ConnectorObject, SourceConnector};
+use crate::{
+    events::TenantEventPublisher,
+    files::{
+        delete_file::delete_file,
+        mime_overrides::get_mime_overrides,
+        upload_file::{UploadFile, upload_file},
+    },
+    folders::create_folder::{CreateFolderData, CreateFolderError, safe_create_folder},
+};
+use chrono::Utc;
+use docbox_database::{
+    DbErr, DbPool,
+    models::{
+        connector_sync_item::ConnectorSyncItem,
+        document_box::DocumentBoxScopeRaw,
+        file::{File, FileId},
+        folder::{Folder, FolderId},
+        user::UserId,
+    },
+};
+use docbox_processing::{ProcessingConfig, ProcessingLayer};
+use docbox_search::TenantSearchIndex;
+use docbox_storage::StorageLayer;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+/// Maximum size in bytes of an object that can be synced (1GiB)
+pub const MAX_CONNECTOR_OBJECT_SIZE: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum ConnectorSyncError {
+    /// Failed to list the source objects
+    #[error("failed to list connector objects: {0}")]
+    ListObjects(ConnectorError),
+
+    /// Failed to query the mime type overrides
+    #[error("failed to query mime overrides: {0}")]
+    MimeOverrides(DbErr),
+
+    /// Database error
+    #[error(transparent)]
+    Database(#[from] DbErr),
+
+    /// Failed to create a folder for the source objects
+    #[error("failed to create folder: {0}")]
+    CreateFolder(CreateFolderError),
+}
+
+pub struct ConnectorSyncData {
+    /// Folder to sync the source objects into
+    pub folder: Folder,
+
+    /// User performing the sync
+    pub created_by: Option<UserId>,
+
+    /// Config used when processing the synced files
+    pub processing_config: Option<ProcessingConfig>,
+
+    /// Whether to delete the files of previously synced objects that
+    /// no longer exist within the source
+    pub delete_removed: bool,
+}
+
+/// Outcome of syncing a connector
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ConnectorSyncOutput {
+    /// Number of new objects imported
+    pub created: u64,
+    /// Number of changed objects that replaced their previous file
+    pub updated: u64,
+    /// Number of objects unchanged since the last sync
+    pub unchanged: u64,
+    /// Number of files removed for objects no longer in the source
+    pub deleted: u64,
+    /// Number of folders created
+    pub folders: u64,
+    /// Objects that failed to sync
+    pub failures: Vec<ConnectorSyncFailure>,
+}
+
+/// Object that failed to sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectorSyncFailure {
+    /// Key of the object within the source
+    pub key: String,
+    /// Reason the object failed to sync
+    pub error: String,
+}
+
+/// Sync the objects from the `connector` into the target folder
+///
+/// Objects that fail to sync are recorded in the output and do not stop the
+/// remaining objects from syncing, they are retried on the next sync.
+/// Failing to list the source objects or create a folder stops the sync
+#[tracing::instrument(skip_all, fields(connector_id = %connector.connector_id()))]
+pub async fn sync_connector(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    storage: &StorageLayer,
+    processing: &ProcessingLayer,
+    events: &TenantEventPublisher,
+    connector: &impl SourceConnector,
+    data: ConnectorSyncData,
+) -> Result<ConnectorSyncOutput, ConnectorSyncError> {
+    let connector_id = connector.connector_id();
+    let scope = data.folder.document_box.clone();
+
+    let mime_overrides = get_mime_overrides(db)
+        .await
+        .map_err(ConnectorSyncError::MimeOverrides)?;
+
+    let objects = connector
+        .list_objects()
+        .await
+        .map_err(ConnectorSyncError::ListObjects)?;
+
+    let mut synced: HashMap<String, ConnectorSyncItem> =
+        ConnectorSyncItem::find_by_connector(db, &connector_id)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to query synced objects"))?
+            .into_iter()
+            .map(|item| (item.source_key.clone(), item))
+            .collect();
+
+    let mut output = ConnectorSyncOutput::default();
+    let mut folders = SyncFolders::new(data.folder.clone());
+    let mut source_keys: HashSet<String> = HashSet::with_capacity(objects.len());
+
+    for object in objects {
+        let Some((folder_path, name)) = object.path() else {
+            continue;
+        };
+
+        source_keys.insert(object.key.clone());
+
+        let previous = synced.remove(&object.key);
+        if let Some(previous) = previous.as_ref()
+            && object.is_unchanged(previous)
+        {
+            output.unchanged += 1;
+            continue;
+        }
+
+        if object.size > MAX_CONNECTOR_OBJECT_SIZE {
+            output.failures.push(ConnectorSyncFailure {
+                key: object.key,
+                error: format!(
+                    "object is too large, the maximum is {MAX_CONNECTOR_OBJECT_SIZE} bytes"
+                ),
+            });
+            continue;
+        }
+
+        let folder = folders
+            .resolve(db, search, events, &folder_path, &data.created_by)
+            .await?;
+        let folder_id = folder.id;
+
+        let file = match import_object(
+            db,
+            search,
+            storage,
+            processing,
+            events,
+            connector,
+            &object,
+            UploadFile {
+                fixed_id: None,
+                parent_id: None,
+                folder_id,
+                document_box: scope.clone(),
+                mime: mime_overrides.resolve_file_mime(&name, mime::APPLICATION_OCTET_STREAM, true),
+                name,
+                file_bytes: Default::default(),
+                created_by: data.created_by.clone(),
+                file_key: None,
+                processing_config: data.processing_config.clone(),
+                task_id: None,
+            },
+        )
+        .await
+        {
+            Ok(value) => value,
+            Err(error) => {
+                tracing::error!(%error, key = %object.key, "failed to sync connector object");
+                output.failures.push(ConnectorSyncFailure {
+                    key: object.key,
+                    error,
+                });
+                continue;
+            }
+        };
+
+        ConnectorSyncItem::set(
+            db,
+            &ConnectorSyncItem {
+                connector_id: connector_id.clone(),
+                source_key: object.key.clone(),
+                file_id: file.id,
+                etag: object.etag.clone(),
+                last_modified: object.last_modified,
+                synced_at: Utc::now(),
+            },
+        )
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to store object sync state"))?;
+
+        match previous {
+            Some(previous) => {
+                output.updated += 1;
+                remove_synced_file(db, search, storage, events, &scope, previous.file_id).await;
+            }
+            None => output.created += 1,
+        }
+    }
+
+    output.folders = folders.created;
+
+    if data.delete_removed {
+        // Remaining synced objects are no longer present in the source
+        for (source_key, item) in synced {
+            if source_keys.contains(&source_key) {
+                continue;
+            }
+
+            ConnectorSyncItem::delete(db, &connector_id, &source_key).await?;
+            remove_synced_file(db, search, storage, events, &scope, item.file_id).await;
+            output.deleted += 1;
+        }
+    }
+
+    Ok(output)
+}
+
+/// Download the `object` from the `connector` and upload it as a new file
+#[allow(clippy::too_many_arguments)]
+async fn import_object(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    storage: &StorageLayer,
+    processing: &ProcessingLayer,
+    events: &TenantEventPublisher,
+    connector: &impl SourceConnector,
+    object: &ConnectorObject,
+    mut upload: UploadFile,
+) -> Result<File, String> {
+    upload.file_bytes = connector
+        .get_object(&object.key)
+        .await
+        .map_err(|error| error.to_string())?;
+
+    let data = upload_file(db, search, storage, processing, events, upload)
+        .await
+        .map_err(|error| error.to_string())?;
+
+    Ok(data.file)
+}
+
+/// Remove the file previously created for a synced object, failures
+/// are logged and do not fail the sync
+async fn remove_synced_file(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    storage: &StorageLayer,
+    events: &TenantEventPublisher,
+    scope: &DocumentBoxScopeRaw,
+    file_id: FileId,
+) {
+    let file = match File::find(db, scope, file_id).await {
+        Ok(Some(file)) => file,
+        // File was already removed
+        Ok(None) => return,
+        Err(error) => {
+            tracing::error!(?error, %file_id, "failed to query previously synced file");
+            return;
+        }
+    };
+
+    if let Err(error) = delete_file(db, storage, search, events, file, scope.clone()).await {
+        tracing::error!(?error, %file_id, "failed to delete previously synced file");
+    }
+}
+
+/// Folders resolved while syncing, keyed by their path within the target folder
+struct SyncFolders {
+    folders: HashMap<Vec<String>, Folder>,
+    /// Number of folders created
+    created: u64,
+}
+
+impl SyncFolders {
+    fn new(root: Folder) -> Self {
+        let mut folders = HashMap::new();
+        folders.insert(Vec::new(), root);
+        Self {
+            folders,
+            created: 0,
+        }
+    }
+
+    /// Resolve the folder at `path` finding existing folders by name and
+    /// creating any that are missing
+    async fn resolve(
+        &mut self,
+        db: &DbPool,
+        search: &TenantSearchIndex,
+        events: &TenantEventPublisher,
+        path: &[String],
+        created_by: &Option<UserId>,
+    ) -> Result<&Folder, ConnectorSyncError> {
+        for depth in 1..=path.len() {
+            let current = &path[..depth];
+            if self.folders.contains_key(current) {
+                continue;
+            }
+
+            let parent = self.folders[&path[..depth - 1]].clone();
+            let name = &path[depth - 1];
+
+            let existing = find_child_folder(db, parent.id, name).await?;
+            let folder = match existing {
+                Some(folder) => folder,
+                None => {
+                    self.created += 1;
+                    safe_create_folder(
+                        db,
+                        search.clone(),
+                        events,
+                        CreateFolderData {
+                            folder: parent,
+                            name: name.clone(),
+                            created_by: created_by.clone(),
+                        },
+                    )
+                    .await
+                    .map_err(ConnectorSyncError::CreateFolder)?
+                }
+            };
+
+            self.folders.insert(current.to_vec(), folder);
+        }
+
+        Ok(&self.folders[path])
+    }
+}
+
+/// Find a child folder of `parent_id` by `name`
+async fn find_child_folder(
+    db: &DbPool,
+    parent_id: FolderId,
+    name: &str,
+) -> Result<Option<Folder>, DbErr> {
+    let folders = Folder::find_by_parent(db, parent_id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query child folders"))?;
+
+    Ok(folders.into_iter().find(|folder| folder.name == name))
+}
@@ -0,0 +1,385 @@
+//! # Docbox
+//!
+//! Facade for embedding docbox within another Rust service without running
+//! the HTTP server. The [Docbox] instance wires together the database,
+//! storage, search, secrets, processing and event layers, a [DocboxTenant]
+//! can then be obtained to perform operations against a specific tenant:
+//!
+//! ```ignore
+//! let docbox = Docbox::builder()
+//!     .db_cache(db_cache)
+//!     .secrets(secrets)
+//!     .search(search_index_factory)
+//!     .storage(storage_factory)
+//!     .processing(processing)
+//!     .build()?;
+//!
+//! let tenant = docbox.tenant("Development", tenant_id).await?;
+//! let folder = tenant.create_folder(&scope, root_folder_id, "Invoices".to_string(), None).await?;
+//! ```
+
+use crate::{
+    connectors::{
+        SourceConnector,
+        sync_connector::{
+            ConnectorSyncData, ConnectorSyncError, ConnectorSyncOutput, sync_connector,
+        },
+    },
+    document_box::search_document_box::{
+        DocumentBoxSearchResults, SearchDocumentBoxError, search_document_box,
+    },
+    events::{EventPublisherFactory, TenantEventPublisher, noop::NoopEventPublisher},
+    files::upload_file::{UploadFile, UploadFileError, UploadedFileData, upload_file},
+    folders::create_folder::{CreateFolderData, CreateFolderError, safe_create_folder},
+    tenant::{
+        tenant_cache::TenantCache,
+        tenant_storage_key::{TenantStorageKeyCache, TenantStorageKeyError},
+    },
+};
+use docbox_database::{
+    DatabasePoolCache, DbConnectErr, DbErr, DbPool,
+    models::{
+        document_box::DocumentBoxScopeRaw,
+        folder::{Folder, FolderId},
+        tenant::{Tenant, TenantId},
+        user::UserId,
+    },
+};
+use docbox_processing::{ProcessingConfig, ProcessingLayer};
+use docbox_search::{SearchIndexFactory, TenantSearchIndex, models::SearchRequest};
+use docbox_secrets::SecretManager;
+use docbox_storage::{StorageLayer, StorageLayerFactory};
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DocboxBuildError {
+    /// A required component was not provided to the builder
+    #[error("missing required docbox component: {0}")]
+    MissingComponent(&'static str),
+}
+
+#[derive(Debug, Error)]
+pub enum DocboxError {
+    /// Failed to connect to the database
+    #[error(transparent)]
+    Connect(#[from] DbConnectErr),
+
+    /// Database error occurred
+    #[error(transparent)]
+    Database(#[from] DbErr),
+
+    /// Requested tenant does not exist
+    #[error("tenant not found")]
+    TenantNotFound,
+
+    /// Requested folder does not exist within the document box
+    #[error("folder not found")]
+    UnknownFolder,
+
+    /// Failed to load the tenant storage encryption keys
+    #[error(transparent)]
+    StorageKeys(#[from] TenantStorageKeyError),
+
+    /// Failed to upload a file
+    #[error(transparent)]
+    UploadFile(#[from] UploadFileError),
+
+    /// Failed to create a folder
+    #[error(transparent)]
+    CreateFolder(#[from] CreateFolderError),
+
+    /// Failed to search a document box
+    #[error(transparent)]
+    Search(#[from] SearchDocumentBoxError),
+
+    /// Failed to sync a source connector
+    #[error(transparent)]
+    SyncConnector(#[from] ConnectorSyncError),
+}
+
+/// Builder for creating a [Docbox] instance
+#[derive(Default)]
+pub struct DocboxBuilder {
+    db_cache: Option<Arc<DatabasePoolCache>>,
+    secrets: Option<SecretManager>,
+    search: Option<SearchIndexFactory>,
+    storage: Option<StorageLayerFactory>,
+    processing: Option<ProcessingLayer>,
+    events: Option<EventPublisherFactory>,
+}
+
+impl DocboxBuilder {
+    /// Set the database pool cache used to connect to the root and tenant databases
+    pub fn db_cache(mut self, db_cache: Arc<DatabasePoolCache>) -> Self {
+        self.db_cache = Some(db_cache);
+        self
+    }
+
+    /// Set the secret manager used to load tenant storage encryption keys
+    pub fn secrets(mut self, secrets: SecretManager) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Set the factory for creating tenant search indexes
+    pub fn search(mut self, search: SearchIndexFactory) -> Self {
+        self.search = Some(search);
+        self
+    }
+
+    /// Set the factory for creating tenant storage layers
+    pub fn storage(mut self, storage: StorageLayerFactory) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Set the processing layer used when uploading files
+    pub fn processing(mut self, processing: ProcessingLayer) -> Self {
+        self.processing = Some(processing);
+        self
+    }
+
+    /// Set the factory for creating tenant event publishers, when not
+    /// provided events are not published
+    pub fn events(mut self, events: EventPublisherFactory) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Build the [Docbox] instance
+    pub fn build(self) -> Result<Docbox, DocboxBuildError> {
+        let db_cache = self
+            .db_cache
+            .ok_or(DocboxBuildError::MissingComponent("db_cache"))?;
+        let secrets = self
+            .secrets
+            .ok_or(DocboxBuildError::MissingComponent("secrets"))?;
+        let search = self
+            .search
+            .ok_or(DocboxBuildError::MissingComponent("search"))?;
+        let storage = self
+            .storage
+            .ok_or(DocboxBuildError::MissingComponent("storage"))?;
+        let processing = self
+            .processing
+            .ok_or(DocboxBuildError::MissingComponent("processing"))?;
+
+        Ok(Docbox {
+            db_cache,
+            search,
+            storage,
+            processing,
+            events: self.events,
+            tenant_cache: Arc::new(TenantCache::new()),
+            storage_keys: TenantStorageKeyCache::new(secrets),
+        })
+    }
+}
+
+/// In-process docbox instance
+#[derive(Clone)]
+pub struct Docbox {
+    db_cache: Arc<DatabasePoolCache>,
+    search: SearchIndexFactory,
+    storage: StorageLayerFactory,
+    processing: ProcessingLayer,
+    events: Option<EventPublisherFactory>,
+    tenant_cache: Arc<TenantCache>,
+    storage_keys: TenantStorageKeyCache,
+}
+
+impl Docbox {
+    /// Create a new [DocboxBuilder]
+    pub fn builder() -> DocboxBuilder {
+        DocboxBuilder::default()
+    }
+
+    /// Access the database pool cache
+    pub fn db_cache(&self) -> &Arc<DatabasePoolCache> {
+        &self.db_cache
+    }
+
+    /// Load the tenant with the provided `env` and `tenant_id` creating
+    /// a handle to perform operations against the tenant
+    pub async fn tenant(
+        &self,
+        env: impl Into<String>,
+        tenant_id: TenantId,
+    ) -> Result<DocboxTenant, DocboxError> {
+        let root_db =
+            self.db_cache.get_root_pool().await.inspect_err(|error| {
+                tracing::error!(?error, "failed to connect to root database")
+            })?;
+
+        let tenant = self
+            .tenant_cache
+            .get_tenant(&root_db, env.into(), tenant_id)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to query tenant"))?
+            .ok_or(DocboxError::TenantNotFound)?;
+
+        self.tenant_from(tenant).await
+    }
+
+    /// Create a handle to perform operations against an already loaded `tenant`
+    pub async fn tenant_from(&self, tenant: Tenant) -> Result<DocboxTenant, DocboxError> {
+        let db = self
+            .db_cache
+            .get_tenant_pool(&tenant)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to connect to tenant database"))?;
+
+        let storage_options = self
+            .storage_keys
+            .storage_layer_options(&tenant)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to load tenant storage keys"))?;
+
+        let search = self.search.create_search_index(&tenant);
+        let storage = self.storage.create_layer(storage_options);
+        let events = match self.events.as_ref() {
+            Some(events) => events.create_event_publisher(&tenant),
+            None => TenantEventPublisher::Noop(NoopEventPublisher),
+        };
+
+        Ok(DocboxTenant {
+            tenant,
+            db,
+            search,
+            storage,
+            processing: self.processing.clone(),
+            events,
+        })
+    }
+}
+
+/// Handle for performing operations against a specific tenant
+pub struct DocboxTenant {
+    tenant: Tenant,
+    db: DbPool,
+    search: TenantSearchIndex,
+    storage: StorageLayer,
+    processing: ProcessingLayer,
+    events: TenantEventPublisher,
+}
+
+impl DocboxTenant {
+    /// The tenant this handle operates on
+    pub fn tenant(&self) -> &Tenant {
+        &self.tenant
+    }
+
+    /// Database pool for the tenant
+    pub fn db(&self) -> &DbPool {
+        &self.db
+    }
+
+    /// Upload and process a file into the folder specified by the `upload`
+    pub async fn upload_file(&self, upload: UploadFile) -> Result<UploadedFileData, DocboxError> {
+        // Ensure the destination folder exists within the document box
+        Folder::find_by_id(&self.db, &upload.document_box, upload.folder_id)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to query folder"))?
+            .ok_or(DocboxError::UnknownFolder)?;
+
+        let data = upload_file(
+            &self.db,
+            &self.search,
+            &self.storage,
+            &self.processing,
+            &self.events,
+            upload,
+        )
+        .await?;
+
+        Ok(data)
+    }
+
+    /// Create a folder named `name` within the `parent_id` folder
+    pub async fn create_folder(
+        &self,
+        scope: &DocumentBoxScopeRaw,
+        parent_id: FolderId,
+        name: String,
+        created_by: Option<UserId>,
+    ) -> Result<Folder, DocboxError> {
+        let parent = Folder::find_by_id(&self.db, scope, parent_id)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to query folder"))?
+            .ok_or(DocboxError::UnknownFolder)?;
+
+        let folder = safe_create_folder(
+            &self.db,
+            self.search.clone(),
+            &self.events,
+            CreateFolderData {
+                folder: parent,
+                name,
+                created_by,
+            },
+        )
+        .await?;
+
+        Ok(folder)
+    }
+
+    /// Sync the objects from the source `connector` into the folder `folder_id`
+    /// within the document box `scope`
+    pub async fn sync_connector(
+        &self,
+        scope: &DocumentBoxScopeRaw,
+        folder_id: FolderId,
+        connector: &impl SourceConnector,
+        created_by: Option<UserId>,
+        processing_config: Option<ProcessingConfig>,
+        delete_removed: bool,
+    ) -> Result<ConnectorSyncOutput, DocboxError> {
+        let folder = Folder::find_by_id(&self.db, scope, folder_id)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to query folder"))?
+            .ok_or(DocboxError::UnknownFolder)?;
+
+        let output = sync_connector(
+            &self.db,
+            &self.search,
+            &self.storage,
+            &self.processing,
+            &self.events,
+            connector,
+            ConnectorSyncData {
+                folder,
+                created_by,
+                processing_config,
+                delete_removed,
+            },
+        )
+        .await?;
+
+        Ok(output)
+    }
+
+    /// Search the contents of the document box `scope`
+    pub async fn search(
+        &self,
+        scope: DocumentBoxScopeRaw,
+        request: SearchRequest,
+    ) -> Result<DocumentBoxSearchResults, DocboxError> {
+        let results = search_document_box(&self.db, &self.search, scope, request).await?;
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Docbox, DocboxBuildError};
+
+    #[test]
+    fn test_build_missing_component() {
+        let error = Docbox::builder().build().err().unwrap();
+        assert!(matches!(
+            error,
+            DocboxBuildError::MissingComponent("db_cache")
+        ));
+    }
+}
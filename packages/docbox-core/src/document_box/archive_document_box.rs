@@ -0,0 +1,105 @@
+//! # Archive Document Box
+//!
+//! Archived document boxes are read-only, their contents cannot be
+//! uploaded, modified or deleted and they are excluded from admin
+//! searches unless explicitly requested.
+//!
+//! The stored objects of an archived document box can optionally be
+//! transitioned to a lower cost [StorageClass::Archive] storage class
+
+use docbox_database::{
+    DbErr, DbPool,
+    models::{
+        document_box::{DocumentBox, DocumentBoxScopeRawRef},
+        file::File,
+    },
+};
+use docbox_storage::{StorageClass, StorageLayer};
+use futures::StreamExt;
+use serde::Serialize;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Maximum number of storage class changes to perform at once
+const STORAGE_CLASS_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Error)]
+pub enum ArchiveDocumentBoxError {
+    /// Database error occurred
+    #[error(transparent)]
+    Database(#[from] DbErr),
+
+    #[error("unknown document box scope")]
+    UnknownScope,
+}
+
+/// Outcome of changing the storage class of a document box
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct StorageClassOutcome {
+    /// Total number of stored objects
+    pub total: usize,
+    /// Number of stored objects that were changed
+    pub updated: usize,
+    /// Keys of the stored objects that failed to change
+    pub failed: Vec<String>,
+}
+
+/// Archive or unarchive the document box with the provided `scope`
+#[tracing::instrument(skip(db))]
+pub async fn set_document_box_archived(
+    db: &DbPool,
+    scope: DocumentBoxScopeRawRef<'_>,
+    archived: bool,
+) -> Result<DocumentBox, ArchiveDocumentBoxError> {
+    let document_box = DocumentBox::find_by_scope(db, scope)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query document box"))?
+        .ok_or(ArchiveDocumentBoxError::UnknownScope)?;
+
+    let document_box = document_box
+        .set_archived(db, archived)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to set document box archived"))?;
+
+    Ok(document_box)
+}
+
+/// Change the storage class of all stored objects for files and generated
+/// files within the document box `scope`
+///
+/// Failing to change individual objects does not stop the remaining
+/// objects from being changed, the failed keys are reported in the outcome
+#[tracing::instrument(skip(db, storage))]
+pub async fn set_document_box_storage_class(
+    db: &DbPool,
+    storage: &StorageLayer,
+    scope: DocumentBoxScopeRawRef<'_>,
+    storage_class: StorageClass,
+) -> Result<StorageClassOutcome, ArchiveDocumentBoxError> {
+    let keys = File::storage_keys_within_scope(db, scope)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query storage keys"))?;
+
+    let total = keys.len();
+
+    let failed: Vec<String> = futures::stream::iter(keys)
+        .map(|key| async move {
+            match storage.set_storage_class(&key, storage_class).await {
+                Ok(_) => None,
+                Err(error) => {
+                    tracing::error!(?error, %key, "failed to change storage class");
+                    Some(key)
+                }
+            }
+        })
+        .buffer_unordered(STORAGE_CLASS_CONCURRENCY)
+        .filter_map(std::future::ready)
+        .collect()
+        .await;
+
+    Ok(StorageClassOutcome {
+        total,
+        updated: total - failed.len(),
+        failed,
+    })
+}
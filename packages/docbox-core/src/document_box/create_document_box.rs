@@ -3,7 +3,9 @@ use docbox_database::{
     DbErr, DbPool, DbResult, DbTransaction,
     models::{
         document_box::{DocumentBox, DocumentBoxScopeRaw},
+        document_box_stats::DocumentBoxStatsCounters,
         folder::{CreateFolder, Folder},
+        scope_pattern::ScopePattern,
         user::UserId,
     },
 };
@@ -17,6 +19,9 @@ pub enum CreateDocumentBoxError {
     #[error("document box with matching scope already exists")]
     ScopeAlreadyExists,
 
+    #[error("document box scope does not match any registered scope pattern")]
+    ScopeNotRegistered,
+
     /// Database error occurred
     #[error(transparent)]
     Database(#[from] DbErr),
@@ -34,6 +39,8 @@ pub async fn create_document_box(
     events: &TenantEventPublisher,
     create: CreateDocumentBox,
 ) -> Result<(DocumentBox, Folder), CreateDocumentBoxError> {
+    check_scope_registered(db, &create.scope).await?;
+
     // Enter a database transaction
     let mut transaction = db.begin().await?;
 
@@ -46,6 +53,14 @@ pub async fn create_document_box(
     )
     .await?;
 
+    DocumentBoxStatsCounters::create(
+        transaction.deref_mut(),
+        document_box.scope.clone(),
+        document_box.created_at,
+    )
+    .await
+    .inspect_err(|error| tracing::error!(?error, "failed to create document box stats"))?;
+
     transaction.commit().await?;
 
     // Publish an event
@@ -54,6 +69,20 @@ pub async fn create_document_box(
     Ok((document_box, root))
 }
 
+/// Ensure the `scope` matches one of the registered scope patterns, any
+/// scope is allowed when no patterns are registered
+async fn check_scope_registered(db: &DbPool, scope: &str) -> Result<(), CreateDocumentBoxError> {
+    let patterns = ScopePattern::all(db)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query scope patterns"))?;
+
+    if !patterns.is_empty() && !patterns.iter().any(|pattern| pattern.matches(scope)) {
+        return Err(CreateDocumentBoxError::ScopeNotRegistered);
+    }
+
+    Ok(())
+}
+
 /// Create the database entry for the document box itself
 async fn create_document_box_entry(
     db: &mut DbTransaction<'_>,
@@ -0,0 +1,42 @@
+//! # Document Box Stats
+//!
+//! Document boxes maintain counters for their contents that are updated as
+//! items are created and deleted so that stats can be obtained without
+//! counting the contents. Failures to update the counters after an item was
+//! deleted are not fatal, the counters are corrected by the reconciliation
+//! background task (See [crate::stats::reconcile_document_box_stats])
+
+use chrono::Utc;
+use docbox_database::{
+    DbPool, DbResult,
+    models::{
+        document_box::DocumentBoxScopeRawRef,
+        document_box_stats::{DocumentBoxStatsCounters, DocumentBoxStatsDelta},
+    },
+};
+
+/// Get the stats counters for the document box `scope`, counters are
+/// computed if the document box does not have any yet
+pub async fn get_document_box_stats(
+    db: &DbPool,
+    scope: DocumentBoxScopeRawRef<'_>,
+) -> DbResult<Option<DocumentBoxStatsCounters>> {
+    if let Some(counters) = DocumentBoxStatsCounters::find(db, scope).await? {
+        return Ok(Some(counters));
+    }
+
+    // Document boxes created before the counters existed are populated lazily
+    DocumentBoxStatsCounters::reconcile(db, scope, Utc::now()).await
+}
+
+/// Apply a `delta` to the stats counters of the document box `scope`,
+/// failures are logged and left for reconciliation to correct
+pub async fn safe_apply_document_box_stats_delta(
+    db: &DbPool,
+    scope: DocumentBoxScopeRawRef<'_>,
+    delta: DocumentBoxStatsDelta,
+) {
+    if let Err(error) = DocumentBoxStatsCounters::apply_delta(db, scope, delta).await {
+        tracing::error!(?error, ?delta, "failed to update document box stats");
+    }
+}
@@ -0,0 +1,451 @@
+//! # Import ZIP
+//!
+//! Imports the contents of a ZIP archive into a document box, the inverse of
+//! the folder ZIP download ([create_folder_zip](crate::folders::create_folder_zip)).
+//!
+//! The folder structure within the archive is recreated and each file is uploaded
+//! through the normal processing pipeline. Entries are extracted one at a time
+//! with limits on the number of entries and the total extracted size to protect
+//! against archive bombs
+
+use crate::{
+    events::TenantEventPublisher,
+    files::{
+        mime_overrides::get_mime_overrides,
+        upload_file::{UploadFile, upload_file},
+    },
+    folders::create_folder::{CreateFolderData, CreateFolderError, safe_create_folder},
+};
+use bytes::Bytes;
+use docbox_database::{
+    DbErr, DbPool,
+    models::{folder::Folder, tasks::Task, user::UserId},
+};
+use docbox_processing::{ProcessingConfig, ProcessingLayer};
+use docbox_search::TenantSearchIndex;
+use docbox_storage::StorageLayer;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read},
+    path::Component,
+};
+use thiserror::Error;
+use tokio::task::{JoinError, spawn_blocking};
+use utoipa::ToSchema;
+use zip::{ZipArchive, result::ZipError};
+
+/// Maximum number of entries an imported archive can contain
+pub const MAX_IMPORT_ENTRIES: usize = 1000;
+
+/// Maximum total size in bytes of the extracted archive contents (1GiB)
+pub const MAX_IMPORT_TOTAL_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Number of imported files between each progress report
+const PROGRESS_REPORT_INTERVAL: u64 = 10;
+
+#[derive(Debug, Error)]
+pub enum ImportZipError {
+    /// Archive could not be read
+    #[error("failed to read zip archive: {0}")]
+    ReadArchive(#[from] ZipError),
+
+    /// Archive has more entries than allowed
+    #[error("archive contains too many entries ({0}), the maximum is {1}")]
+    TooManyEntries(usize, usize),
+
+    /// Extracted archive contents exceed the allowed size
+    #[error("archive contents are too large, the maximum is {0} bytes")]
+    TooLarge(u64),
+
+    /// Failed to read an archive entry
+    #[error("failed to read archive entry: {0}")]
+    ReadEntry(std::io::Error),
+
+    /// Failed to query the mime type overrides
+    #[error("failed to query mime overrides: {0}")]
+    MimeOverrides(DbErr),
+
+    /// Failed to create a folder from the archive
+    #[error("failed to create folder: {0}")]
+    CreateFolder(CreateFolderError),
+
+    /// Failed to join the archive extraction task
+    #[error("failed to join archive task")]
+    JoinTask(JoinError),
+}
+
+/// Entry within an archive to import
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportZipEntry {
+    /// Index of the entry within the archive
+    pub index: usize,
+    /// Names of the folders the entry is within
+    pub folders: Vec<String>,
+    /// Name of the file, [None] for directory entries
+    pub name: Option<String>,
+}
+
+impl ImportZipEntry {
+    /// Path of the entry within the archive
+    pub fn path(&self) -> String {
+        self.folders
+            .iter()
+            .chain(self.name.as_ref())
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+pub struct ImportZipData {
+    /// Folder to import the archive contents into
+    pub folder: Folder,
+
+    /// Contents of the archive
+    pub archive: Bytes,
+
+    /// Entries to import from the archive (See [read_import_entries])
+    pub entries: Vec<ImportZipEntry>,
+
+    /// User importing the archive
+    pub created_by: Option<UserId>,
+
+    /// Config used when processing the imported files
+    pub processing_config: Option<ProcessingConfig>,
+}
+
+/// Progress of importing an archive
+#[derive(Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportZipProgress {
+    /// Total number of files to import
+    pub total: u64,
+    /// Number of files imported
+    pub imported: u64,
+    /// Number of files that failed to import
+    pub failed: u64,
+    /// Number of folders created
+    pub folders: u64,
+}
+
+/// Outcome of importing an archive
+#[derive(Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportZipOutput {
+    /// Final import progress
+    #[serde(flatten)]
+    pub progress: ImportZipProgress,
+    /// Files that failed to import
+    pub failures: Vec<ImportZipFailure>,
+}
+
+/// File within the archive that failed to import
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportZipFailure {
+    /// Path of the file within the archive
+    pub path: String,
+    /// Reason the file failed to import
+    pub error: String,
+}
+
+/// Reads the entries to import from the `archive` validating the entry
+/// count and declared size limits.
+///
+/// Entries with unsafe paths (absolute paths, or paths escaping the archive)
+/// and operating system metadata (__MACOSX, .DS_Store) are skipped
+pub fn read_import_entries(archive: &[u8]) -> Result<Vec<ImportZipEntry>, ImportZipError> {
+    let mut archive = ZipArchive::new(Cursor::new(archive))?;
+
+    if archive.len() > MAX_IMPORT_ENTRIES {
+        return Err(ImportZipError::TooManyEntries(
+            archive.len(),
+            MAX_IMPORT_ENTRIES,
+        ));
+    }
+
+    let mut entries = Vec::with_capacity(archive.len());
+    let mut total_size: u64 = 0;
+
+    for index in 0..archive.len() {
+        let file = archive.by_index_raw(index)?;
+
+        total_size = total_size.saturating_add(file.size());
+        if total_size > MAX_IMPORT_TOTAL_SIZE {
+            return Err(ImportZipError::TooLarge(MAX_IMPORT_TOTAL_SIZE));
+        }
+
+        let Some(path) = file.enclosed_name() else {
+            tracing::warn!(
+                name = file.name(),
+                "skipping archive entry with unsafe path"
+            );
+            continue;
+        };
+
+        let mut components: Vec<String> = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(value) => value.to_str().map(str::to_string),
+                _ => None,
+            })
+            .collect();
+
+        // Skip operating system metadata
+        if components
+            .iter()
+            .any(|component| component == "__MACOSX" || component == ".DS_Store")
+        {
+            continue;
+        }
+
+        let name = if file.is_dir() {
+            None
+        } else {
+            match components.pop() {
+                Some(value) => Some(value),
+                None => continue,
+            }
+        };
+
+        entries.push(ImportZipEntry {
+            index,
+            folders: components,
+            name,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Reads the contents of the archive entry at `index`, the entry is not allowed
+/// to extract to more than `remaining` bytes
+fn read_entry(archive: &Bytes, index: usize, remaining: u64) -> Result<Bytes, ImportZipError> {
+    let mut archive = ZipArchive::new(Cursor::new(archive.as_ref()))?;
+    let file = archive.by_index(index)?;
+
+    // Limit the read to detect entries that extract larger than declared
+    let mut bytes = Vec::new();
+    file.take(remaining.saturating_add(1))
+        .read_to_end(&mut bytes)
+        .map_err(ImportZipError::ReadEntry)?;
+
+    if bytes.len() as u64 > remaining {
+        return Err(ImportZipError::TooLarge(MAX_IMPORT_TOTAL_SIZE));
+    }
+
+    Ok(Bytes::from(bytes))
+}
+
+/// Imports the archive contents into the target folder, reporting the
+/// progress to the provided `task`
+///
+/// Files that fail to upload are recorded in the output and do not stop the
+/// remaining files from importing. Failing to create a folder or extract the
+/// archive stops the import, files imported before the failure are kept
+pub async fn import_zip(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    storage: &StorageLayer,
+    processing: &ProcessingLayer,
+    events: &TenantEventPublisher,
+    data: ImportZipData,
+    mut task: Option<&mut Task>,
+) -> Result<ImportZipOutput, ImportZipError> {
+    let mime_overrides = get_mime_overrides(db)
+        .await
+        .map_err(ImportZipError::MimeOverrides)?;
+
+    let mut output = ImportZipOutput {
+        progress: ImportZipProgress {
+            total: data
+                .entries
+                .iter()
+                .filter(|entry| entry.name.is_some())
+                .count() as u64,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    report_progress(db, task.as_deref_mut(), &output.progress).await;
+
+    // Folders created for the archive, keyed by their path within the archive
+    let mut folders: HashMap<Vec<String>, Folder> = HashMap::new();
+    folders.insert(Vec::new(), data.folder.clone());
+
+    let mut remaining = MAX_IMPORT_TOTAL_SIZE;
+
+    for entry in data.entries {
+        // Create any of the missing folders for the entry
+        for depth in 1..=entry.folders.len() {
+            let path = &entry.folders[..depth];
+            if folders.contains_key(path) {
+                continue;
+            }
+
+            let parent = folders[&path[..depth - 1]].clone();
+            let folder = safe_create_folder(
+                db,
+                search.clone(),
+                events,
+                CreateFolderData {
+                    folder: parent,
+                    name: path[depth - 1].clone(),
+                    created_by: data.created_by.clone(),
+                },
+            )
+            .await
+            .map_err(ImportZipError::CreateFolder)?;
+
+            output.progress.folders += 1;
+            folders.insert(path.to_vec(), folder);
+        }
+
+        let Some(name) = entry.name.clone() else {
+            continue;
+        };
+
+        let folder = &folders[&entry.folders];
+
+        let file_bytes = spawn_blocking({
+            let archive = data.archive.clone();
+            move || read_entry(&archive, entry.index, remaining)
+        })
+        .await
+        .map_err(ImportZipError::JoinTask)??;
+
+        remaining -= file_bytes.len() as u64;
+
+        let mime = mime_overrides.resolve_file_mime(&name, mime::APPLICATION_OCTET_STREAM, true);
+
+        let result = upload_file(
+            db,
+            search,
+            storage,
+            processing,
+            events,
+            UploadFile {
+                fixed_id: None,
+                parent_id: None,
+                folder_id: folder.id,
+                document_box: folder.document_box.clone(),
+                name,
+                mime,
+                file_bytes,
+                created_by: data.created_by.clone(),
+                file_key: None,
+                processing_config: data.processing_config.clone(),
+                task_id: None,
+            },
+        )
+        .await;
+
+        match result {
+            Ok(_) => output.progress.imported += 1,
+            Err(error) => {
+                tracing::error!(?error, path = %entry.path(), "failed to import archive file");
+                output.progress.failed += 1;
+                output.failures.push(ImportZipFailure {
+                    path: entry.path(),
+                    error: error.to_string(),
+                });
+            }
+        }
+
+        let processed = output.progress.imported + output.progress.failed;
+        if processed.is_multiple_of(PROGRESS_REPORT_INTERVAL) {
+            report_progress(db, task.as_deref_mut(), &output.progress).await;
+        }
+    }
+
+    report_progress(db, task, &output.progress).await;
+    Ok(output)
+}
+
+/// Store the current `progress` against the `task`, failing to report
+/// progress does not fail the import
+async fn report_progress(db: &DbPool, task: Option<&mut Task>, progress: &ImportZipProgress) {
+    let Some(task) = task else {
+        return;
+    };
+
+    let progress = match serde_json::to_value(progress) {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::error!(?error, "failed to serialize zip import progress");
+            return;
+        }
+    };
+
+    if let Err(error) = task.set_progress(db, progress).await {
+        tracing::warn!(?error, "failed to report zip import progress");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ImportZipEntry, ImportZipError, read_import_entries};
+    use std::io::{Cursor, Write};
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    fn make_archive(files: &[&str]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in files {
+            if let Some(name) = name.strip_suffix('/') {
+                writer
+                    .add_directory(name, SimpleFileOptions::default())
+                    .unwrap();
+                continue;
+            }
+
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(b"test").unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_read_import_entries() {
+        let archive = make_archive(&[
+            "readme.txt",
+            "reports/",
+            "reports/2024/summary.pdf",
+            "__MACOSX/reports/._summary.pdf",
+            "reports/.DS_Store",
+            "../escape.txt",
+        ]);
+
+        let entries = read_import_entries(&archive).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                ImportZipEntry {
+                    index: 0,
+                    folders: vec![],
+                    name: Some("readme.txt".to_string()),
+                },
+                ImportZipEntry {
+                    index: 1,
+                    folders: vec!["reports".to_string()],
+                    name: None,
+                },
+                ImportZipEntry {
+                    index: 2,
+                    folders: vec!["reports".to_string(), "2024".to_string()],
+                    name: Some("summary.pdf".to_string()),
+                },
+            ]
+        );
+
+        assert_eq!(entries[2].path(), "reports/2024/summary.pdf");
+    }
+
+    #[test]
+    fn test_read_import_entries_invalid_archive() {
+        assert!(matches!(
+            read_import_entries(b"not a zip file"),
+            Err(ImportZipError::ReadArchive(_))
+        ));
+    }
+}
@@ -1,3 +1,8 @@
+pub mod archive_document_box;
 pub mod create_document_box;
 pub mod delete_document_box;
+pub mod document_box_stats;
+pub mod import_zip;
+pub mod move_to_scope;
+pub mod pinned_items;
 pub mod search_document_box;
@@ -0,0 +1,513 @@
+//! # Move To Scope
+//!
+//! Moving folders and files between document boxes within the same tenant.
+//!
+//! The scope of the moved item and all of its descendants is updated within
+//! a single transaction. Stored objects keep their existing keys, only the
+//! document box tags of the objects are updated once the move is committed
+//! (Shared content addressed objects keep their tags). The search index is
+//! updated after the move is committed
+
+use crate::events::{FileMoved, FolderMoved, TenantEventMessage, TenantEventPublisher};
+use docbox_database::{
+    DbErr, DbPool, DbTransaction,
+    models::{
+        document_box::{DocumentBox, DocumentBoxScopeRaw, WithScope},
+        document_box_stats::{DocumentBoxStatsCounters, DocumentBoxStatsDelta},
+        edit_history::{
+            CreateEditHistory, CreateEditHistoryType, EditHistory, EditHistoryMetadata,
+        },
+        file::File,
+        folder::{Folder, FolderId},
+        generated_file::GeneratedFile,
+        link::Link,
+        link_generated_file::LinkGeneratedFile,
+        storage_object::StorageObject,
+        user::UserId,
+    },
+};
+use docbox_search::{TenantSearchIndex, models::UpdateSearchIndexData};
+use docbox_storage::{ObjectTag, StorageLayer, StorageLayerError};
+use std::ops::DerefMut;
+use thiserror::Error;
+use tracing::Instrument;
+
+#[derive(Debug, Error)]
+pub enum MoveToScopeError {
+    /// Database related error
+    #[error(transparent)]
+    Database(#[from] DbErr),
+
+    /// Modification of the root folder is not allowed
+    #[error("cannot modify root")]
+    CannotModifyRoot,
+
+    /// Target document box is the document box the item is already within
+    #[error("item is already within the target document box")]
+    SameScope,
+
+    /// Target document box could not be found
+    #[error("unknown target document box")]
+    UnknownTargetDocumentBox,
+
+    /// Target document box is archived
+    #[error("target document box is archived")]
+    TargetDocumentBoxArchived,
+
+    /// Document box the item is being moved out of is archived
+    #[error("source document box is archived")]
+    SourceDocumentBoxArchived,
+
+    /// Target folder could not be found within the target document box
+    #[error("unknown target folder")]
+    UnknownTargetFolder,
+}
+
+/// Target to move an item to
+pub struct MoveToScope {
+    /// Scope of the document box to move the item to
+    pub document_box: DocumentBoxScopeRaw,
+    /// Folder within the target document box to move the item into
+    pub folder_id: FolderId,
+}
+
+/// Move a `folder` from the document box `scope` into a folder within
+/// another document box, all the folders, files and links within the
+/// folder are moved along with it
+#[tracing::instrument(skip_all, fields(%scope, folder_id = %folder.id, target_scope = %target.document_box))]
+#[allow(clippy::too_many_arguments)]
+pub async fn move_folder_to_scope(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    storage: &StorageLayer,
+    events: &TenantEventPublisher,
+    scope: &DocumentBoxScopeRaw,
+    folder: Folder,
+    user_id: Option<UserId>,
+    target: MoveToScope,
+) -> Result<Folder, MoveToScopeError> {
+    // Cannot move the root folder
+    let original_folder_id = folder.folder_id.ok_or(MoveToScopeError::CannotModifyRoot)?;
+
+    let target_folder = find_target_folder(db, scope, &target).await?;
+
+    let (folder, folders, files, links, objects) = move_folder_tree(
+        db,
+        scope,
+        folder,
+        original_folder_id,
+        user_id,
+        &target_folder,
+    )
+    .await?;
+
+    objects.retag_background(storage.clone(), target.document_box.clone());
+
+    for folder in &folders {
+        // Root folders are never moved so there is always a parent
+        let Some(folder_id) = folder.folder_id else {
+            continue;
+        };
+
+        update_search_scope(
+            search,
+            folder.id,
+            UpdateSearchIndexData {
+                folder_id,
+                name: folder.name.clone(),
+                pinned: folder.pinned,
+                content: None,
+                pages: None,
+                document_box: Some(target.document_box.clone()),
+            },
+        )
+        .await;
+    }
+
+    for file in &files {
+        update_search_scope(search, file.id, file_search_data(file, &target)).await;
+    }
+
+    for link in &links {
+        update_search_scope(
+            search,
+            link.id,
+            UpdateSearchIndexData {
+                folder_id: link.folder_id,
+                name: link.name.clone(),
+                pinned: link.pinned,
+                content: Some(link.value.clone()),
+                pages: None,
+                document_box: Some(target.document_box.clone()),
+            },
+        )
+        .await;
+    }
+
+    events.publish_event(TenantEventMessage::FolderMoved(WithScope::new(
+        FolderMoved {
+            folder: folder.clone(),
+            previous_scope: scope.clone(),
+        },
+        target.document_box,
+    )));
+
+    Ok(folder)
+}
+
+/// Moves the `folder` tree into the `target_folder` within a transaction. Provides
+/// the moved folder along with all the moved folders, files, links and the stored
+/// objects of the moved items
+async fn move_folder_tree(
+    db: &DbPool,
+    scope: &DocumentBoxScopeRaw,
+    folder: Folder,
+    original_folder_id: FolderId,
+    user_id: Option<UserId>,
+    target_folder: &Folder,
+) -> Result<(Folder, Vec<Folder>, Vec<File>, Vec<Link>, MovedObjects), MoveToScopeError> {
+    let target_scope = &target_folder.document_box;
+
+    let mut t = db
+        .begin()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to begin transaction"))?;
+
+    Folder::lock_version(t.deref_mut(), folder.id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to lock folder version"))?;
+
+    add_edit_history(
+        &mut t,
+        CreateEditHistoryType::Folder(folder.id),
+        user_id,
+        EditHistoryMetadata::MoveToFolder {
+            original_id: original_folder_id,
+            target_id: target_folder.id,
+        },
+    )
+    .await?;
+
+    let folder = folder
+        .move_to_scope(t.deref_mut(), target_folder)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to move folder"))?;
+
+    Folder::increment_version(t.deref_mut(), folder.id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to increment folder version"))?;
+
+    // Collect the moved items to update their stats, storage tags and search data
+    let folders = folder
+        .tree_all_folders(t.deref_mut())
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query moved folders"))?;
+    let folder_ids: Vec<FolderId> = folders.iter().map(|folder| folder.id).collect();
+    let files = File::find_by_folders(t.deref_mut(), &folder_ids)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query moved files"))?;
+    let links = Link::find_by_folders(t.deref_mut(), &folder_ids)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query moved links"))?;
+
+    let delta = DocumentBoxStatsDelta {
+        files: files.len() as i64,
+        links: links.len() as i64,
+        folders: folders.len() as i64,
+        file_size: files.iter().map(|file| file.size as i64).sum(),
+    };
+
+    move_document_box_stats(&mut t, scope, target_scope, delta).await?;
+
+    let mut objects = MovedObjects::default();
+    for file in &files {
+        objects.add_file(&mut t, file).await?;
+    }
+
+    for link in &links {
+        objects.add_link(&mut t, link).await?;
+    }
+
+    t.commit()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to commit transaction"))?;
+
+    Ok((folder, folders, files, links, objects))
+}
+
+/// Move a `file` from the document box `scope` into a folder within
+/// another document box, child files of the file (i.e attachments of
+/// an email file) are moved along with it
+#[tracing::instrument(skip_all, fields(%scope, file_id = %file.id, target_scope = %target.document_box))]
+#[allow(clippy::too_many_arguments)]
+pub async fn move_file_to_scope(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    storage: &StorageLayer,
+    events: &TenantEventPublisher,
+    scope: &DocumentBoxScopeRaw,
+    file: File,
+    user_id: Option<UserId>,
+    target: MoveToScope,
+) -> Result<File, MoveToScopeError> {
+    let target_folder = find_target_folder(db, scope, &target).await?;
+
+    let (file, children, objects) =
+        move_file_tree(db, scope, file, user_id, &target_folder).await?;
+
+    objects.retag_background(storage.clone(), target.document_box.clone());
+
+    for file in std::iter::once(&file).chain(children.iter()) {
+        update_search_scope(search, file.id, file_search_data(file, &target)).await;
+    }
+
+    events.publish_event(TenantEventMessage::FileMoved(WithScope::new(
+        FileMoved {
+            file: file.clone(),
+            previous_scope: scope.clone(),
+        },
+        target.document_box,
+    )));
+
+    Ok(file)
+}
+
+/// Moves the `file` and its children into the `target_folder` within a transaction.
+/// Provides the moved file along with its moved children and the stored objects of
+/// the moved files
+async fn move_file_tree(
+    db: &DbPool,
+    scope: &DocumentBoxScopeRaw,
+    file: File,
+    user_id: Option<UserId>,
+    target_folder: &Folder,
+) -> Result<(File, Vec<File>, MovedObjects), MoveToScopeError> {
+    let target_scope = &target_folder.document_box;
+
+    let mut t = db
+        .begin()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to begin transaction"))?;
+
+    File::lock_version(t.deref_mut(), file.id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to lock file version"))?;
+
+    add_edit_history(
+        &mut t,
+        CreateEditHistoryType::File(file.id),
+        user_id,
+        EditHistoryMetadata::MoveToFolder {
+            original_id: file.folder_id,
+            target_id: target_folder.id,
+        },
+    )
+    .await?;
+
+    let (file, children) = file
+        .move_tree_to_folder(t.deref_mut(), target_folder.id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to move file"))?;
+
+    File::increment_version(t.deref_mut(), file.id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to increment file version"))?;
+
+    let delta = DocumentBoxStatsDelta {
+        files: 1 + children.len() as i64,
+        file_size: file.size as i64 + children.iter().map(|file| file.size as i64).sum::<i64>(),
+        ..Default::default()
+    };
+
+    move_document_box_stats(&mut t, scope, target_scope, delta).await?;
+
+    let mut objects = MovedObjects::default();
+    for file in std::iter::once(&file).chain(children.iter()) {
+        objects.add_file(&mut t, file).await?;
+    }
+
+    t.commit()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to commit transaction"))?;
+
+    Ok((file, children, objects))
+}
+
+/// Find the folder within the target document box to move an item into
+async fn find_target_folder(
+    db: &DbPool,
+    scope: &DocumentBoxScopeRaw,
+    target: &MoveToScope,
+) -> Result<Folder, MoveToScopeError> {
+    if target.document_box.eq(scope) {
+        return Err(MoveToScopeError::SameScope);
+    }
+
+    // Contents of archived document boxes are read-only
+    let source_document_box = DocumentBox::find_by_scope(db, scope)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query source document box"))?;
+
+    if source_document_box.is_some_and(|document_box| document_box.archived) {
+        return Err(MoveToScopeError::SourceDocumentBoxArchived);
+    }
+
+    let document_box = DocumentBox::find_by_scope(db, &target.document_box)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query target document box"))?
+        .ok_or(MoveToScopeError::UnknownTargetDocumentBox)?;
+
+    if document_box.archived {
+        return Err(MoveToScopeError::TargetDocumentBoxArchived);
+    }
+
+    Folder::find_by_id(db, &target.document_box, target.folder_id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query target folder"))?
+        .ok_or(MoveToScopeError::UnknownTargetFolder)
+}
+
+/// Add a new edit history item for the moved item
+async fn add_edit_history(
+    db: &mut DbTransaction<'_>,
+    ty: CreateEditHistoryType,
+    user_id: Option<UserId>,
+    metadata: EditHistoryMetadata,
+) -> Result<(), DbErr> {
+    EditHistory::create(
+        db.deref_mut(),
+        CreateEditHistory {
+            ty,
+            user_id,
+            metadata,
+        },
+    )
+    .await
+    .inspect_err(|error| tracing::error!(?error, "failed to store edit history entry"))?;
+
+    Ok(())
+}
+
+/// Move the `delta` of the moved items from the stats counters of the
+/// `scope` document box to the `target_scope` document box
+async fn move_document_box_stats(
+    db: &mut DbTransaction<'_>,
+    scope: &DocumentBoxScopeRaw,
+    target_scope: &DocumentBoxScopeRaw,
+    delta: DocumentBoxStatsDelta,
+) -> Result<(), DbErr> {
+    DocumentBoxStatsCounters::apply_delta(db.deref_mut(), scope, delta.negate())
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to update document box stats"))?;
+
+    DocumentBoxStatsCounters::apply_delta(db.deref_mut(), target_scope, delta)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to update document box stats"))?;
+
+    Ok(())
+}
+
+fn file_search_data(file: &File, target: &MoveToScope) -> UpdateSearchIndexData {
+    UpdateSearchIndexData {
+        folder_id: file.folder_id,
+        name: file.name.clone(),
+        pinned: file.pinned,
+        content: None,
+        pages: None,
+        document_box: Some(target.document_box.clone()),
+    }
+}
+
+/// Update the document box of an item within the search index, failures are
+/// logged as the move has already been committed (The search index can be
+/// corrected by re-indexing the tenant)
+async fn update_search_scope(
+    search: &TenantSearchIndex,
+    item_id: uuid::Uuid,
+    data: UpdateSearchIndexData,
+) {
+    if let Err(error) = search.update_data(item_id, data).await {
+        tracing::error!(?error, %item_id, "failed to update search index");
+    }
+}
+
+/// Stored objects of the items moved into the target document box
+#[derive(Default)]
+struct MovedObjects {
+    /// Keys of the stored objects
+    file_keys: Vec<String>,
+}
+
+impl MovedObjects {
+    /// Add the stored objects of a `file` and its generated files
+    ///
+    /// Content addressed objects may be shared with files in other document
+    /// boxes, their tags are left unchanged
+    async fn add_file(&mut self, t: &mut DbTransaction<'_>, file: &File) -> Result<(), DbErr> {
+        let deduplicated = StorageObject::find(t.deref_mut(), &file.file_key)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to query storage object"))?
+            .is_some();
+
+        if !deduplicated {
+            self.file_keys.push(file.file_key.clone());
+        }
+
+        let generated = GeneratedFile::find_all(t.deref_mut(), file.id)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to query generated files"))?;
+
+        self.file_keys
+            .extend(generated.into_iter().map(|generated| generated.file_key));
+
+        Ok(())
+    }
+
+    /// Add the stored objects generated for a `link`
+    async fn add_link(&mut self, t: &mut DbTransaction<'_>, link: &Link) -> Result<(), DbErr> {
+        let generated = LinkGeneratedFile::find_all(t.deref_mut(), link.id)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to query link generated files"))?;
+
+        self.file_keys
+            .extend(generated.into_iter().map(|generated| generated.file_key));
+
+        Ok(())
+    }
+
+    /// Update the document box tag of the objects to the `target_scope` in a
+    /// background task, performed once the move has been committed. Failures
+    /// are logged as the tags are only used to classify objects
+    fn retag_background(self, storage: StorageLayer, target_scope: DocumentBoxScopeRaw) {
+        if self.file_keys.is_empty() {
+            return;
+        }
+
+        let span = tracing::Span::current();
+
+        tokio::spawn(
+            async move {
+                for file_key in self.file_keys {
+                    if let Err(error) = retag_object(&storage, &file_key, &target_scope).await {
+                        tracing::error!(?error, %file_key, "failed to update stored object tags");
+                    }
+                }
+            }
+            .instrument(span),
+        );
+    }
+}
+
+/// Replace the document box tag of the object with `file_key`, the remaining
+/// tags of the object are preserved
+async fn retag_object(
+    storage: &StorageLayer,
+    file_key: &str,
+    target_scope: &DocumentBoxScopeRaw,
+) -> Result<(), StorageLayerError> {
+    let mut tags = storage.get_object_tags(file_key).await?;
+    tags.retain(|tag| tag.key != ObjectTag::DOCUMENT_BOX);
+    tags.push(ObjectTag::new(ObjectTag::DOCUMENT_BOX, target_scope));
+
+    storage.put_object_tags(file_key, tags).await
+}
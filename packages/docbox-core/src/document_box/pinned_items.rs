@@ -0,0 +1,84 @@
+//! # Pinned Items
+//!
+//! Resolves the pinned files, folders and links within a document box
+//! ordered by when they were pinned, most recently pinned first
+
+use chrono::{DateTime, Utc};
+use docbox_database::{
+    DbPool, DbResult,
+    models::{
+        document_box::DocumentBoxScopeRaw, file::File, folder::Folder, link::Link,
+        shared::FolderPathSegment,
+    },
+};
+use docbox_search::models::SearchResultData;
+use std::cmp::Reverse;
+
+/// Pinned item within a document box
+pub struct PinnedItem {
+    /// When the item was pinned
+    pub pinned_at: DateTime<Utc>,
+
+    /// Resolved item from the database
+    pub data: SearchResultData,
+
+    /// Path to the item
+    pub path: Vec<FolderPathSegment>,
+}
+
+/// Get all the pinned items within the document box `scope`
+#[tracing::instrument(skip(db))]
+pub async fn get_pinned_items(
+    db: &DbPool,
+    scope: &DocumentBoxScopeRaw,
+) -> DbResult<Vec<PinnedItem>> {
+    let (files, folders, links) = tokio::try_join!(
+        File::find_pinned(db, scope),
+        Folder::find_pinned(db, scope),
+        Link::find_pinned(db, scope),
+    )
+    .inspect_err(|error| tracing::error!(?error, "failed to query pinned items"))?;
+
+    let (resolved_files, resolved_folders, resolved_links) = tokio::try_join!(
+        File::resolve_with_extra(db, scope, files.iter().map(|item| item.id).collect()),
+        Folder::resolve_with_extra(db, scope, folders.iter().map(|item| item.id).collect()),
+        Link::resolve_with_extra(db, scope, links.iter().map(|item| item.id).collect()),
+    )
+    .inspect_err(|error| tracing::error!(?error, "failed to resolve pinned items"))?;
+
+    let mut items: Vec<PinnedItem> = Vec::new();
+
+    items.extend(resolved_files.into_iter().filter_map(|item| {
+        let pinned = files.iter().find(|pinned| pinned.id == item.data.file.id)?;
+        Some(PinnedItem {
+            pinned_at: pinned.pinned_at,
+            data: SearchResultData::File(item.data),
+            path: item.full_path,
+        })
+    }));
+
+    items.extend(resolved_folders.into_iter().filter_map(|item| {
+        let pinned = folders
+            .iter()
+            .find(|pinned| pinned.id == item.data.folder.id)?;
+        Some(PinnedItem {
+            pinned_at: pinned.pinned_at,
+            data: SearchResultData::Folder(item.data),
+            path: item.full_path,
+        })
+    }));
+
+    items.extend(resolved_links.into_iter().filter_map(|item| {
+        let pinned = links.iter().find(|pinned| pinned.id == item.data.link.id)?;
+        Some(PinnedItem {
+            pinned_at: pinned.pinned_at,
+            data: SearchResultData::Link(item.data),
+            path: item.full_path,
+        })
+    }));
+
+    // Most recently pinned items first
+    items.sort_by_key(|item| Reverse(item.pinned_at));
+
+    Ok(items)
+}
@@ -1,22 +1,27 @@
 use docbox_database::{
     DbErr, DbPool, DbResult,
     models::{
-        document_box::DocumentBoxScopeRaw,
+        document_box::{DocumentBox, DocumentBoxScopeRaw},
         file::{File, FileId, FileWithExtra},
         folder::{Folder, FolderId, FolderWithExtra},
         link::{Link, LinkId, LinkWithExtra},
+        link_visit_stats::LinkVisitStats,
         shared::{DocboxInputPair, FolderPathSegment},
     },
 };
 use docbox_search::{
     SearchError, TenantSearchIndex,
     models::{
-        AdminSearchRequest, FlattenedItemResult, SearchIndexType, SearchRequest, SearchResultData,
+        AdminSearchExportRequest, AdminSearchRequest, FlattenedItemResult, SearchExplain,
+        SearchFacet, SearchIndexType, SearchRequest, SearchResultData, SearchScrollCursor,
     },
 };
 use std::collections::HashMap;
 use thiserror::Error;
 
+/// Weight applied to the visit counts of links when boosting popular links
+const LINK_POPULARITY_WEIGHT: f64 = 0.1;
+
 #[derive(Debug, Error)]
 pub enum SearchDocumentBoxError {
     #[error(transparent)]
@@ -43,6 +48,12 @@ pub struct ResolvedSearchResult {
 pub struct DocumentBoxSearchResults {
     pub results: Vec<ResolvedSearchResult>,
     pub total_hits: u64,
+    /// Whether the search timed out producing partial results
+    pub timed_out: bool,
+    /// Backend query details when explain was requested
+    pub explain: Option<SearchExplain>,
+    /// Counts for the requested facet fields
+    pub facets: Vec<SearchFacet>,
 }
 
 pub async fn search_document_box(
@@ -74,6 +85,8 @@ pub async fn search_document_box(
 
     tracing::debug!(?search_folder_ids, "searching within folders");
 
+    let boost_popular = request.boost_popular_links;
+
     // Query search engine
     let results = search
         .search_index(std::slice::from_ref(&scope), request, search_folder_ids)
@@ -84,11 +97,23 @@ pub async fn search_document_box(
         })?;
 
     let total_hits = results.total_hits;
-    let results = resolve_search_results_same_scope(db, results.results, scope).await?;
+    let timed_out = results.timed_out;
+    let explain = results.explain;
+    let facets = results.facets;
+    let mut results = resolve_search_results_same_scope(db, results.results, scope).await?;
+
+    if boost_popular {
+        boost_popular_links(db, &mut results)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to boost popular links"))?;
+    }
 
     Ok(DocumentBoxSearchResults {
         results,
         total_hits,
+        timed_out,
+        explain,
+        facets,
     })
 }
 
@@ -97,9 +122,42 @@ pub async fn search_document_boxes_admin(
     search: &TenantSearchIndex,
     request: AdminSearchRequest,
 ) -> Result<DocumentBoxSearchResults, SearchDocumentBoxError> {
+    let AdminSearchRequest {
+        scopes,
+        mut request,
+        explain,
+        dry_run,
+        include_archived,
+    } = request;
+
+    // Explain and dry run are only available to administrators
+    request.explain = explain;
+    request.dry_run = dry_run;
+
+    let scopes = if include_archived {
+        scopes
+    } else {
+        exclude_archived_scopes(db, scopes)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to exclude archived scopes"))?
+    };
+
+    // All requested scopes were archived
+    if scopes.is_empty() {
+        return Ok(DocumentBoxSearchResults {
+            results: Vec::new(),
+            total_hits: 0,
+            timed_out: false,
+            explain: None,
+            facets: Vec::new(),
+        });
+    }
+
+    let boost_popular = request.boost_popular_links;
+
     // Query search engine
     let results = search
-        .search_index(&request.scopes, request.request, None)
+        .search_index(&scopes, request, None)
         .await
         .map_err(|error| {
             tracing::error!(?error, "failed to query search index");
@@ -107,14 +165,184 @@ pub async fn search_document_boxes_admin(
         })?;
 
     let total_hits = results.total_hits;
-    let results = resolve_search_results_mixed_scopes(db, results.results).await?;
+    let timed_out = results.timed_out;
+    let explain = results.explain;
+    let facets = results.facets;
+    let mut results = resolve_search_results_mixed_scopes(db, results.results).await?;
+
+    if boost_popular {
+        boost_popular_links(db, &mut results)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to boost popular links"))?;
+    }
 
     Ok(DocumentBoxSearchResults {
         results,
         total_hits,
+        timed_out,
+        explain,
+        facets,
     })
 }
 
+/// Export of the full set of results for a search across multiple
+/// document boxes, results are scrolled from the search index and
+/// resolved from the database one page at a time
+pub struct AdminSearchExport {
+    db: DbPool,
+    search: TenantSearchIndex,
+    scopes: Vec<DocumentBoxScopeRaw>,
+    request: SearchRequest,
+    /// Cursor for the next page of results
+    cursor: Option<SearchScrollCursor>,
+    /// Whether all the results have been read
+    done: bool,
+}
+
+impl AdminSearchExport {
+    pub async fn create(
+        db: DbPool,
+        search: TenantSearchIndex,
+        request: AdminSearchExportRequest,
+    ) -> Result<AdminSearchExport, SearchDocumentBoxError> {
+        let AdminSearchExportRequest {
+            scopes,
+            request,
+            include_archived,
+        } = request;
+
+        let scopes = if include_archived {
+            scopes
+        } else {
+            exclude_archived_scopes(&db, scopes)
+                .await
+                .inspect_err(|error| tracing::error!(?error, "failed to exclude archived scopes"))?
+        };
+
+        Ok(AdminSearchExport {
+            db,
+            search,
+            // Nothing to export when all the requested scopes were archived
+            done: scopes.is_empty(),
+            scopes,
+            request,
+            cursor: None,
+        })
+    }
+
+    /// Get the next page of results, [None] once all the results have
+    /// been returned
+    ///
+    /// Results that no longer exist in the database are skipped so pages
+    /// may be empty
+    pub async fn next_page(
+        &mut self,
+    ) -> Result<Option<Vec<ResolvedSearchResult>>, SearchDocumentBoxError> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let page = self
+            .search
+            .scroll_index(&self.scopes, self.request.clone(), self.cursor.take())
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to scroll search index");
+                SearchDocumentBoxError::QueryIndex(error)
+            })?;
+
+        self.done = page.next.is_none();
+        self.cursor = page.next;
+
+        let results = resolve_search_results_mixed_scopes(&self.db, page.results).await?;
+        Ok(Some(results))
+    }
+}
+
+/// Re-rank the `results` boosting the scores of links by how often they
+/// have been visited, the order of results with equal scores is preserved
+async fn boost_popular_links(db: &DbPool, results: &mut [ResolvedSearchResult]) -> DbResult<()> {
+    let link_ids: Vec<LinkId> = results
+        .iter()
+        .filter(|result| matches!(result.result.item_ty, SearchIndexType::Link))
+        .map(|result| result.result.item_id)
+        .collect();
+
+    if link_ids.is_empty() {
+        return Ok(());
+    }
+
+    let visits: HashMap<LinkId, i64> = LinkVisitStats::find_many(db, &link_ids)
+        .await?
+        .into_iter()
+        .map(|stats| (stats.link_id, stats.visit_count))
+        .collect();
+
+    if visits.is_empty() {
+        return Ok(());
+    }
+
+    let boosted_score = |result: &ResolvedSearchResult| {
+        let score = result.result.score.as_f64();
+        match result.result.item_ty {
+            SearchIndexType::Link => {
+                let visits = visits.get(&result.result.item_id).copied().unwrap_or(0);
+                link_popularity_score(score, visits)
+            }
+            _ => score,
+        }
+    };
+
+    results.sort_by(|a, b| boosted_score(b).total_cmp(&boosted_score(a)));
+    Ok(())
+}
+
+/// Boost the search `score` of a link that has been visited `visits` times,
+/// the boost grows logarithmically so heavily visited links do not drown out
+/// more relevant results
+fn link_popularity_score(score: f64, visits: i64) -> f64 {
+    score * (1.0 + LINK_POPULARITY_WEIGHT * (visits.max(0) as f64).ln_1p())
+}
+
+/// Remove archived document boxes from the search `scopes`
+///
+/// Wildcard scopes that would match an archived document box are expanded
+/// into the matching document boxes that are not archived
+async fn exclude_archived_scopes(
+    db: &DbPool,
+    scopes: Vec<DocumentBoxScopeRaw>,
+) -> DbResult<Vec<DocumentBoxScopeRaw>> {
+    let archived = DocumentBox::archived_scopes(db).await?;
+    if archived.is_empty() {
+        return Ok(scopes);
+    }
+
+    let mut output = Vec::with_capacity(scopes.len());
+
+    for scope in scopes {
+        match scope.strip_suffix('*') {
+            Some(prefix) => {
+                if archived.iter().any(|archived| archived.starts_with(prefix)) {
+                    let expanded = DocumentBox::unarchived_scopes_by_prefix(db, prefix).await?;
+                    output.extend(expanded);
+                } else {
+                    output.push(scope);
+                }
+            }
+            None => {
+                if !archived.contains(&scope) {
+                    output.push(scope);
+                }
+            }
+        }
+    }
+
+    output.sort();
+    output.dedup();
+
+    Ok(output)
+}
+
 pub async fn resolve_search_results_same_scope(
     db: &DbPool,
     results: Vec<FlattenedItemResult>,
@@ -268,3 +496,18 @@ pub async fn resolve_search_results_mixed_scopes(
         })
         .collect())
 }
+
+#[cfg(test)]
+mod test {
+    use super::link_popularity_score;
+
+    /// Tests that visited links are boosted and unvisited links keep their score
+    #[test]
+    fn test_link_popularity_score() {
+        assert_eq!(link_popularity_score(2.0, 0), 2.0);
+        assert!(link_popularity_score(2.0, 1) > 2.0);
+        assert!(link_popularity_score(2.0, 100) > link_popularity_score(2.0, 10));
+        // Negative counts should never reduce the score
+        assert_eq!(link_popularity_score(2.0, -5), 2.0);
+    }
+}
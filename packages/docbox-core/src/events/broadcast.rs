@@ -0,0 +1,62 @@
+//! # Broadcast
+//!
+//! In-process subscription to the events of all tenants, allowing embedders
+//! of docbox to consume events without an external queue.
+//!
+//! Subscribers receive events through a bounded broadcast channel, subscribers
+//! that fall too far behind will miss events and receive a
+//! [broadcast::error::RecvError::Lagged] error reporting how many were missed.
+
+use super::{EventPublisher, TenantEventMessage, TenantEventPublisher};
+use docbox_database::models::tenant::TenantId;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Maximum number of events buffered for slow subscribers
+pub const EVENT_BROADCAST_CAPACITY: usize = 1024;
+
+/// Event received by in-process subscribers
+#[derive(Debug, Clone)]
+pub struct BroadcastTenantEvent {
+    /// ID of the tenant the event occurred within
+    pub tenant_id: TenantId,
+    /// The event message
+    pub message: Arc<TenantEventMessage>,
+}
+
+/// Tenant event publisher that sends events to in-process subscribers
+/// before forwarding them to an inner publisher
+#[derive(Clone)]
+pub struct BroadcastEventPublisher {
+    tenant_id: TenantId,
+    tx: broadcast::Sender<BroadcastTenantEvent>,
+    inner: Box<TenantEventPublisher>,
+}
+
+impl BroadcastEventPublisher {
+    pub fn new(
+        tenant_id: TenantId,
+        tx: broadcast::Sender<BroadcastTenantEvent>,
+        inner: TenantEventPublisher,
+    ) -> Self {
+        Self {
+            tenant_id,
+            tx,
+            inner: Box::new(inner),
+        }
+    }
+}
+
+impl EventPublisher for BroadcastEventPublisher {
+    fn publish_event(&self, event: TenantEventMessage) {
+        // Avoid copying the event when nothing is subscribed
+        if self.tx.receiver_count() > 0 {
+            _ = self.tx.send(BroadcastTenantEvent {
+                tenant_id: self.tenant_id,
+                message: Arc::new(event.clone()),
+            });
+        }
+
+        self.inner.publish_event(event);
+    }
+}
@@ -0,0 +1,31 @@
+use super::{EventPublisher, TenantEventMessage, TenantEventPublisher};
+use docbox_database::models::tenant::TenantEventConfig;
+use std::sync::Arc;
+
+/// Event publisher that only forwards the events allowed by the tenant
+/// [TenantEventConfig] to an inner publisher
+#[derive(Clone)]
+pub struct FilteredEventPublisher {
+    config: Arc<TenantEventConfig>,
+    inner: Box<TenantEventPublisher>,
+}
+
+impl FilteredEventPublisher {
+    pub fn new(config: TenantEventConfig, inner: TenantEventPublisher) -> Self {
+        Self {
+            config: Arc::new(config),
+            inner: Box::new(inner),
+        }
+    }
+}
+
+impl EventPublisher for FilteredEventPublisher {
+    fn publish_event(&self, event: TenantEventMessage) {
+        if !self.config.should_publish(event.name()) {
+            tracing::debug!(?event, "tenant event filtered by event config");
+            return;
+        }
+
+        self.inner.publish_event(event);
+    }
+}
@@ -6,46 +6,118 @@
 //! - [SqsEventPublisherFactory] SQS based event notifications
 //! - [NoopEventPublisher] No-op publishing for tenants without event targets
 //! - [MpscEventPublisher] In memory channel publisher for tests
+//! - [WebhookEventPublisherFactory] Delivery to document box webhooks
+//! - [FilteredEventPublisher] Filtering based on the tenant event config
+//! - [BroadcastEventPublisher] In-process subscribers, see [EventPublisherFactory::subscribe]
 
+use chrono::{DateTime, Utc};
 use docbox_database::models::tenant::Tenant;
 use docbox_database::models::{
-    document_box::{DocumentBox, WithScope},
-    file::File,
-    folder::Folder,
+    document_box::{DocumentBox, DocumentBoxScopeRaw, WithScope},
+    file::{File, FileId},
+    file_processing::ProcessingTimings,
+    folder::{Folder, FolderId},
+    generated_file::GeneratedFileType,
     link::Link,
+    presigned_upload_task::PresignedUploadTask,
 };
 use serde::Serialize;
+use uuid::Uuid;
 
+pub mod broadcast;
+pub mod filtered;
 pub mod mpsc;
 pub mod noop;
 pub mod sqs;
+pub mod webhook;
 
+use broadcast::{BroadcastEventPublisher, BroadcastTenantEvent, EVENT_BROADCAST_CAPACITY};
+use filtered::FilteredEventPublisher;
 use noop::NoopEventPublisher;
 use sqs::{SqsEventPublisherFactory, TenantSqsEventQueue};
+use webhook::WebhookEventPublisherFactory;
 
 #[derive(Clone)]
 pub struct EventPublisherFactory {
     /// Factory for creating SQS based event publishers
     sqs: SqsEventPublisherFactory,
+    /// Factory for creating document box webhook publishers
+    webhooks: Option<WebhookEventPublisherFactory>,
+    /// Channel for in-process event subscribers
+    broadcast: tokio::sync::broadcast::Sender<BroadcastTenantEvent>,
 }
 
 impl EventPublisherFactory {
     pub fn new(sqs: SqsEventPublisherFactory) -> Self {
-        Self { sqs }
+        let (broadcast, _) = tokio::sync::broadcast::channel(EVENT_BROADCAST_CAPACITY);
+
+        Self {
+            sqs,
+            webhooks: None,
+            broadcast,
+        }
+    }
+
+    /// Subscribe to the events published for all tenants from this
+    /// point onwards, events are received regardless of the tenant
+    /// event config
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<BroadcastTenantEvent> {
+        self.broadcast.subscribe()
+    }
+
+    /// Additionally deliver events to the webhooks registered on
+    /// the document box the event occurred within
+    pub fn with_webhooks(mut self, webhooks: WebhookEventPublisherFactory) -> Self {
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    /// Checks that events are allowed to be delivered to the webhook `url`
+    pub async fn is_allowed_webhook_url(&self, url: &str) -> bool {
+        match self.webhooks.as_ref() {
+            Some(webhooks) => webhooks.is_allowed_url(url).await,
+            None => webhook::is_allowed_webhook_url(url, false).await,
+        }
     }
 
     pub fn create_event_publisher(&self, tenant: &Tenant) -> TenantEventPublisher {
-        match tenant.event_queue_url.as_ref() {
+        let publisher = TenantEventPublisher::Broadcast(BroadcastEventPublisher::new(
+            tenant.id,
+            self.broadcast.clone(),
+            self.create_tenant_event_publisher(tenant),
+        ));
+
+        match self.webhooks.as_ref() {
+            Some(webhooks) => TenantEventPublisher::Webhook(
+                webhooks.create_event_publisher(tenant.clone(), publisher),
+            ),
+            None => publisher,
+        }
+    }
+
+    /// Create the publisher for the tenant wide event target, filtered
+    /// based on the tenant event config
+    fn create_tenant_event_publisher(&self, tenant: &Tenant) -> TenantEventPublisher {
+        let config = tenant.event_config.clone().unwrap_or_default();
+
+        let publisher = match tenant.event_queue_url.as_ref() {
             Some(value) => {
                 let target = TenantSqsEventQueue {
                     tenant_id: tenant.id,
                     event_queue_url: value.clone(),
+                    detail: config.detail,
                 };
 
-                TenantEventPublisher::Sqs(self.sqs.create_event_publisher(target))
+                TenantEventPublisher::Sqs(self.sqs.create_event_publisher(tenant, target))
             }
-            None => TenantEventPublisher::Noop(NoopEventPublisher),
+            None => return TenantEventPublisher::Noop(NoopEventPublisher),
+        };
+
+        if config.include.is_empty() && config.exclude.is_empty() {
+            return publisher;
         }
+
+        TenantEventPublisher::Filtered(FilteredEventPublisher::new(config, publisher))
     }
 }
 
@@ -55,6 +127,9 @@ pub enum TenantEventPublisher {
     Sqs(sqs::SqsEventPublisher),
     Noop(noop::NoopEventPublisher),
     Mpsc(mpsc::MpscEventPublisher),
+    Webhook(webhook::WebhookEventPublisher),
+    Filtered(filtered::FilteredEventPublisher),
+    Broadcast(broadcast::BroadcastEventPublisher),
 }
 
 impl TenantEventPublisher {
@@ -63,6 +138,9 @@ impl TenantEventPublisher {
             TenantEventPublisher::Sqs(inner) => inner.publish_event(event),
             TenantEventPublisher::Noop(inner) => inner.publish_event(event),
             TenantEventPublisher::Mpsc(inner) => inner.publish_event(event),
+            TenantEventPublisher::Webhook(inner) => inner.publish_event(event),
+            TenantEventPublisher::Filtered(inner) => inner.publish_event(event),
+            TenantEventPublisher::Broadcast(inner) => inner.publish_event(event),
         }
     }
 }
@@ -70,7 +148,7 @@ impl TenantEventPublisher {
 /// Event inner message type, containing the actual event data
 ///
 /// i.e { "event": "DOCUMENT_BOX_CREATED", "data": { ...document box data }, "tenant_id": "xxxxx-xxxxx-xxxxx-xxxxx" }
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(tag = "event", content = "data", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum TenantEventMessage {
     // Creations (DOCUMENT_BOX_CREATED, ...etc)
@@ -84,6 +162,195 @@ pub enum TenantEventMessage {
     FileDeleted(WithScope<File>),
     FolderDeleted(WithScope<Folder>),
     LinkDeleted(WithScope<Link>),
+
+    // Moves between document boxes, scoped to the new document box
+    FileMoved(WithScope<FileMoved>),
+    FolderMoved(WithScope<FolderMoved>),
+
+    // Presigned uploads
+    PresignedUploadExpired(PresignedUploadTask),
+
+    // File processing outcomes
+    FileProcessingStarted(WithScope<FileProcessingStarted>),
+    FileProcessingCompleted(WithScope<FileProcessingCompleted>),
+    FileProcessingFailed(WithScope<FileProcessingFailed>),
+}
+
+/// File was moved into another document box
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMoved {
+    /// The moved file
+    pub file: File,
+    /// Scope of the document box the file was moved from
+    pub previous_scope: DocumentBoxScopeRaw,
+}
+
+/// Folder and all of its contents were moved into another document box
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderMoved {
+    /// The moved folder
+    pub folder: Folder,
+    /// Scope of the document box the folder was moved from
+    pub previous_scope: DocumentBoxScopeRaw,
+}
+
+/// Processing of an uploaded file has started
+#[derive(Debug, Clone, Serialize)]
+pub struct FileProcessingStarted {
+    /// ID of the background task or presigned upload task processing the
+    /// file, [None] for synchronous uploads
+    pub task_id: Option<Uuid>,
+    /// Name of the uploaded file
+    pub name: String,
+    /// Mime type of the uploaded file
+    pub mime: String,
+    /// Folder the file is being uploaded into
+    pub folder_id: FolderId,
+    /// When processing started
+    pub started_at: DateTime<Utc>,
+}
+
+/// Processing of an uploaded file completed and the file was created
+#[derive(Debug, Clone, Serialize)]
+pub struct FileProcessingCompleted {
+    /// ID of the background task or presigned upload task processing the
+    /// file, [None] for synchronous uploads
+    pub task_id: Option<Uuid>,
+    /// The created file
+    pub file: File,
+    /// Types of the files generated while processing the file
+    pub generated_file_types: Vec<GeneratedFileType>,
+    /// IDs of the additional files created from the file contents
+    /// (i.e email attachments)
+    pub additional_file_ids: Vec<FileId>,
+    /// Time taken by each processing stage
+    pub timings: ProcessingTimings,
+    /// Time taken in milliseconds from the start of processing until the
+    /// file was created, includes storing the file
+    pub duration_ms: i64,
+}
+
+/// Processing of an uploaded file failed, the file was not created
+#[derive(Debug, Clone, Serialize)]
+pub struct FileProcessingFailed {
+    /// ID of the background task or presigned upload task processing the
+    /// file, [None] for synchronous uploads
+    pub task_id: Option<Uuid>,
+    /// Name of the uploaded file
+    pub name: String,
+    /// Mime type of the uploaded file
+    pub mime: String,
+    /// Folder the file was being uploaded into
+    pub folder_id: FolderId,
+    /// Description of the error that caused processing to fail
+    pub error: String,
+    /// Time taken in milliseconds from the start of processing until the
+    /// failure
+    pub duration_ms: i64,
+}
+
+/// Identifiers of the item an event occurred for, published in place of the
+/// full item data for tenants configured with [TenantEventDetail::Ids]
+///
+/// [TenantEventDetail::Ids]: docbox_database::models::tenant::TenantEventDetail::Ids
+#[derive(Debug, Serialize)]
+pub struct TenantEventIds<'a> {
+    /// ID of the file, folder, link or presigned upload task, not present
+    /// for document box events. Processing events use the created file ID
+    /// when completed and the task ID otherwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+    /// Scope of the document box
+    pub scope: &'a str,
+}
+
+/// Names of all the events, matching the serialized "event" field
+pub const TENANT_EVENT_NAMES: [&str; 14] = [
+    "DOCUMENT_BOX_CREATED",
+    "FILE_CREATED",
+    "FOLDER_CREATED",
+    "LINK_CREATED",
+    "DOCUMENT_BOX_DELETED",
+    "FILE_DELETED",
+    "FOLDER_DELETED",
+    "LINK_DELETED",
+    "FILE_MOVED",
+    "FOLDER_MOVED",
+    "PRESIGNED_UPLOAD_EXPIRED",
+    "FILE_PROCESSING_STARTED",
+    "FILE_PROCESSING_COMPLETED",
+    "FILE_PROCESSING_FAILED",
+];
+
+impl TenantEventMessage {
+    /// Name of the event, matches the serialized "event" field
+    pub fn name(&self) -> &'static str {
+        match self {
+            TenantEventMessage::DocumentBoxCreated(_) => "DOCUMENT_BOX_CREATED",
+            TenantEventMessage::FileCreated(_) => "FILE_CREATED",
+            TenantEventMessage::FolderCreated(_) => "FOLDER_CREATED",
+            TenantEventMessage::LinkCreated(_) => "LINK_CREATED",
+            TenantEventMessage::DocumentBoxDeleted(_) => "DOCUMENT_BOX_DELETED",
+            TenantEventMessage::FileDeleted(_) => "FILE_DELETED",
+            TenantEventMessage::FolderDeleted(_) => "FOLDER_DELETED",
+            TenantEventMessage::LinkDeleted(_) => "LINK_DELETED",
+            TenantEventMessage::FileMoved(_) => "FILE_MOVED",
+            TenantEventMessage::FolderMoved(_) => "FOLDER_MOVED",
+            TenantEventMessage::PresignedUploadExpired(_) => "PRESIGNED_UPLOAD_EXPIRED",
+            TenantEventMessage::FileProcessingStarted(_) => "FILE_PROCESSING_STARTED",
+            TenantEventMessage::FileProcessingCompleted(_) => "FILE_PROCESSING_COMPLETED",
+            TenantEventMessage::FileProcessingFailed(_) => "FILE_PROCESSING_FAILED",
+        }
+    }
+
+    /// Identifiers of the item the event occurred for
+    pub fn ids(&self) -> TenantEventIds<'_> {
+        let id = match self {
+            TenantEventMessage::DocumentBoxCreated(_)
+            | TenantEventMessage::DocumentBoxDeleted(_) => None,
+            TenantEventMessage::FileCreated(file) | TenantEventMessage::FileDeleted(file) => {
+                Some(file.data.id)
+            }
+            TenantEventMessage::FolderCreated(folder)
+            | TenantEventMessage::FolderDeleted(folder) => Some(folder.data.id),
+            TenantEventMessage::LinkCreated(link) | TenantEventMessage::LinkDeleted(link) => {
+                Some(link.data.id)
+            }
+            TenantEventMessage::FileMoved(moved) => Some(moved.data.file.id),
+            TenantEventMessage::FolderMoved(moved) => Some(moved.data.folder.id),
+            TenantEventMessage::PresignedUploadExpired(task) => Some(task.id),
+            TenantEventMessage::FileProcessingStarted(started) => started.data.task_id,
+            TenantEventMessage::FileProcessingCompleted(completed) => Some(completed.data.file.id),
+            TenantEventMessage::FileProcessingFailed(failed) => failed.data.task_id,
+        };
+
+        TenantEventIds {
+            id,
+            scope: self.scope(),
+        }
+    }
+
+    /// Scope of the document box the event occurred within
+    pub fn scope(&self) -> &str {
+        match self {
+            TenantEventMessage::DocumentBoxCreated(document_box)
+            | TenantEventMessage::DocumentBoxDeleted(document_box) => &document_box.scope,
+            TenantEventMessage::FileCreated(file) | TenantEventMessage::FileDeleted(file) => {
+                &file.scope
+            }
+            TenantEventMessage::FolderCreated(folder)
+            | TenantEventMessage::FolderDeleted(folder) => &folder.scope,
+            TenantEventMessage::LinkCreated(link) | TenantEventMessage::LinkDeleted(link) => {
+                &link.scope
+            }
+            TenantEventMessage::FileMoved(moved) => &moved.scope,
+            TenantEventMessage::FolderMoved(moved) => &moved.scope,
+            TenantEventMessage::PresignedUploadExpired(task) => &task.document_box,
+            TenantEventMessage::FileProcessingStarted(started) => &started.scope,
+            TenantEventMessage::FileProcessingCompleted(completed) => &completed.scope,
+            TenantEventMessage::FileProcessingFailed(failed) => &failed.scope,
+        }
+    }
 }
 
 /// Abstraction providing the ability to publish an event
@@ -91,3 +358,82 @@ pub trait EventPublisher: Send + Sync + 'static {
     /// Publish an event with the event publisher
     fn publish_event(&self, event: TenantEventMessage);
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        TENANT_EVENT_NAMES, TenantEventMessage, TenantEventPublisher,
+        broadcast::BroadcastEventPublisher, filtered::FilteredEventPublisher,
+        mpsc::MpscEventPublisher,
+    };
+    use chrono::Utc;
+    use docbox_database::models::{document_box::DocumentBox, tenant::TenantEventConfig};
+    use uuid::Uuid;
+
+    fn test_document_box() -> DocumentBox {
+        DocumentBox {
+            scope: "test".to_string(),
+            created_at: Utc::now(),
+            archived: false,
+        }
+    }
+
+    #[test]
+    fn test_event_name_matches_serialized() {
+        let document_box = test_document_box();
+
+        for event in [
+            TenantEventMessage::DocumentBoxCreated(document_box.clone()),
+            TenantEventMessage::DocumentBoxDeleted(document_box),
+        ] {
+            let value = serde_json::to_value(&event).unwrap();
+            assert_eq!(value["event"], event.name());
+            assert!(TENANT_EVENT_NAMES.contains(&event.name()));
+            assert_eq!(event.scope(), "test");
+        }
+    }
+
+    #[test]
+    fn test_filtered_event_publisher() {
+        let (events, mut events_rx) = MpscEventPublisher::new();
+        let publisher = TenantEventPublisher::Filtered(FilteredEventPublisher::new(
+            TenantEventConfig {
+                include: vec!["DOCUMENT_BOX_DELETED".to_string()],
+                ..Default::default()
+            },
+            TenantEventPublisher::Mpsc(events),
+        ));
+
+        publisher.publish_event(TenantEventMessage::DocumentBoxCreated(test_document_box()));
+        publisher.publish_event(TenantEventMessage::DocumentBoxDeleted(test_document_box()));
+
+        let event = events_rx.try_recv().unwrap();
+        assert!(matches!(event, TenantEventMessage::DocumentBoxDeleted(_)));
+        assert!(events_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_broadcast_event_publisher() {
+        let (events, mut events_rx) = MpscEventPublisher::new();
+        let (tx, mut rx) = tokio::sync::broadcast::channel(8);
+        let tenant_id = Uuid::new_v4();
+        let publisher = TenantEventPublisher::Broadcast(BroadcastEventPublisher::new(
+            tenant_id,
+            tx,
+            TenantEventPublisher::Mpsc(events),
+        ));
+
+        publisher.publish_event(TenantEventMessage::DocumentBoxCreated(test_document_box()));
+
+        // Event is received by subscribers and forwarded to the inner publisher
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.tenant_id, tenant_id);
+        assert!(matches!(
+            *event.message,
+            TenantEventMessage::DocumentBoxCreated(_)
+        ));
+
+        let event = events_rx.try_recv().unwrap();
+        assert!(matches!(event, TenantEventMessage::DocumentBoxCreated(_)));
+    }
+}
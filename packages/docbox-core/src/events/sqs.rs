@@ -1,23 +1,59 @@
-use super::{EventPublisher, TenantEventMessage};
+use super::{EventPublisher, TenantEventIds, TenantEventMessage};
+use crate::shutdown::ShutdownCoordinator;
 use aws_sdk_sqs::Client as SqsClient;
-use docbox_database::models::tenant::TenantId;
-use serde::Serialize;
+use docbox_database::{
+    DatabasePoolCache,
+    models::{
+        event_payload::{EventPayload, EventPayloadId},
+        tenant::{Tenant, TenantEventDetail, TenantId},
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use tracing::Instrument;
 
+/// Maximum size in bytes of an SQS message body
+pub const SQS_MAX_MESSAGE_SIZE: usize = 256 * 1024;
+
 #[derive(Clone)]
 pub struct SqsEventPublisherFactory {
     client: SqsClient,
+    /// Shutdown coordinator tracking in-flight event sends
+    shutdown: ShutdownCoordinator,
+    /// Database cache for storing claim check payloads, when
+    /// [None] oversized events cannot be published
+    claim_check: Option<Arc<DatabasePoolCache>>,
 }
 
 impl SqsEventPublisherFactory {
-    pub fn new(client: SqsClient) -> Self {
-        Self { client }
+    pub fn new(client: SqsClient, shutdown: ShutdownCoordinator) -> Self {
+        Self {
+            client,
+            shutdown,
+            claim_check: None,
+        }
     }
 
-    pub fn create_event_publisher(&self, target: TenantSqsEventQueue) -> SqsEventPublisher {
+    /// Store the payload of events exceeding [SQS_MAX_MESSAGE_SIZE] in the
+    /// tenant database and publish a claim check referencing it instead
+    pub fn with_claim_check(mut self, db_cache: Arc<DatabasePoolCache>) -> Self {
+        self.claim_check = Some(db_cache);
+        self
+    }
+
+    pub fn create_event_publisher(
+        &self,
+        tenant: &Tenant,
+        target: TenantSqsEventQueue,
+    ) -> SqsEventPublisher {
         SqsEventPublisher {
             client: self.client.clone(),
+            shutdown: self.shutdown.clone(),
             target,
+            claim_check: self.claim_check.as_ref().map(|db_cache| SqsClaimCheck {
+                db_cache: db_cache.clone(),
+                tenant: Arc::new(tenant.clone()),
+            }),
         }
     }
 }
@@ -26,7 +62,46 @@ impl SqsEventPublisherFactory {
 #[derive(Clone)]
 pub struct SqsEventPublisher {
     client: SqsClient,
+    shutdown: ShutdownCoordinator,
     target: TenantSqsEventQueue,
+    claim_check: Option<SqsClaimCheck>,
+}
+
+/// Details for storing the payloads of oversized events
+#[derive(Clone)]
+struct SqsClaimCheck {
+    db_cache: Arc<DatabasePoolCache>,
+    tenant: Arc<Tenant>,
+}
+
+impl SqsClaimCheck {
+    /// Store the full event `msg` in the tenant database, producing
+    /// the message to publish in its place
+    async fn store(&self, event: &'static str, msg: &str) -> Option<String> {
+        let payload: serde_json::Value = serde_json::from_str(msg)
+            .inspect_err(|error| tracing::error!(?error, "failed to parse event payload"))
+            .ok()?;
+
+        let db = self
+            .db_cache
+            .get_tenant_pool(&self.tenant)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to connect to tenant database"))
+            .ok()?;
+
+        let payload = EventPayload::create(&db, event.to_string(), payload)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to store event payload"))
+            .ok()?;
+
+        serde_json::to_string(&TenantEventClaimCheckContainer {
+            tenant_id: self.tenant.id,
+            event,
+            claim_check: TenantEventClaimCheck { id: payload.id },
+        })
+        .inspect_err(|error| tracing::error!(?error, "failed to serialize claim check"))
+        .ok()
+    }
 }
 
 /// Target SQS details queue
@@ -34,6 +109,8 @@ pub struct SqsEventPublisher {
 pub struct TenantSqsEventQueue {
     pub tenant_id: TenantId,
     pub event_queue_url: String,
+    /// Level of detail to include in the published events
+    pub detail: TenantEventDetail,
 }
 
 /// Container around an event message containing the ID of the
@@ -48,11 +125,57 @@ struct TenantEventMessageContainer {
     message: TenantEventMessage,
 }
 
+/// Container around the identifiers of an event item for tenants
+/// configured with [TenantEventDetail::Ids]
+///
+/// i.e { "event": "FILE_CREATED", "data": { "id": "xxxxx-xxxxx-xxxxx-xxxxx", "scope": "user:1" }, "tenant_id": "xxxxx-xxxxx-xxxxx-xxxxx" }
+#[derive(Debug, Serialize)]
+struct TenantEventIdsContainer<'a> {
+    tenant_id: TenantId,
+    event: &'static str,
+    data: TenantEventIds<'a>,
+}
+
+/// Reference to the stored payload of an event that exceeded the SQS
+/// message size limit, consumers resolve the full event using the
+/// /admin/event-payloads/{payload_id} endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantEventClaimCheck {
+    /// ID of the stored event payload
+    pub id: EventPayloadId,
+}
+
+/// Container published in place of an oversized event message
+///
+/// i.e { "event": "FILE_CREATED", "claim_check": { "id": "xxxxx-xxxxx-xxxxx-xxxxx" }, "tenant_id": "xxxxx-xxxxx-xxxxx-xxxxx" }
+#[derive(Debug, Serialize)]
+struct TenantEventClaimCheckContainer {
+    tenant_id: TenantId,
+    event: &'static str,
+    claim_check: TenantEventClaimCheck,
+}
+
+impl TenantEventMessageContainer {
+    /// Serialize the event message with the provided level of `detail`
+    fn to_json(&self, detail: TenantEventDetail) -> serde_json::Result<String> {
+        match detail {
+            TenantEventDetail::Full => serde_json::to_string(self),
+            TenantEventDetail::Ids => serde_json::to_string(&TenantEventIdsContainer {
+                tenant_id: self.tenant_id,
+                event: self.message.name(),
+                data: self.message.ids(),
+            }),
+        }
+    }
+}
+
 impl EventPublisher for SqsEventPublisher {
     fn publish_event(&self, event: TenantEventMessage) {
         let client = self.client.clone();
         let tenant_id = self.target.tenant_id;
         let event_queue_url = self.target.event_queue_url.clone();
+        let detail = self.target.detail;
+        let claim_check = self.claim_check.clone();
 
         // Wrap the event message providing the tenant_id
         let event = TenantEventMessageContainer {
@@ -62,10 +185,11 @@ impl EventPublisher for SqsEventPublisher {
 
         let span = tracing::Span::current();
 
-        tokio::spawn(
+        // Sends are tracked so they can complete during shutdown
+        self.shutdown.spawn(
             async move {
                 // Serialize the event message
-                let msg = match serde_json::to_string(&event) {
+                let mut msg = match event.to_json(detail) {
                     Ok(value) => value,
                     Err(error) => {
                         tracing::error!(?error, ?event, "failed to serialize tenant event");
@@ -73,6 +197,28 @@ impl EventPublisher for SqsEventPublisher {
                     }
                 };
 
+                // Oversized events would be rejected by SQS, store the full payload
+                // and publish a claim check referencing it instead
+                if msg.len() > SQS_MAX_MESSAGE_SIZE {
+                    let Some(claim_check) = claim_check else {
+                        tracing::error!(
+                            size = msg.len(),
+                            "tenant event exceeds sqs message size limit"
+                        );
+                        return;
+                    };
+
+                    tracing::debug!(
+                        size = msg.len(),
+                        "publishing oversized tenant event as claim check"
+                    );
+
+                    msg = match claim_check.store(event.message.name(), &msg).await {
+                        Some(value) => value,
+                        None => return,
+                    };
+                }
+
                 tracing::debug!(?event, "emitting tenant event");
 
                 // Push the event to the SQS queue
@@ -0,0 +1,305 @@
+//! # Webhooks
+//!
+//! Delivers events to the webhooks registered on the document box the event
+//! occurred within, allowing a single tenant to fan different document boxes
+//! out to different downstream systems.
+//!
+//! Events are forwarded to the tenant wide publisher as well as delivered to
+//! any matching webhooks. Deliveries are signed using the webhook secret, the
+//! [WEBHOOK_SIGNATURE_HEADER] contains the hex encoded HMAC-SHA256 of the body
+//! prefixed with "sha256="
+//!
+//! Webhook URLs are checked to resolve to a publicly reachable address before
+//! every delivery and redirects are not followed, deliveries cannot be used to
+//! reach internal services. The delivery client only connects to publicly
+//! reachable addresses so the domain cannot be rebound to an internal address
+//! after it was checked
+
+use super::{EventPublisher, TenantEventMessage, TenantEventPublisher};
+use crate::shutdown::ShutdownCoordinator;
+use bytes::Bytes;
+use docbox_database::{
+    DatabasePoolCache,
+    models::{
+        document_box_webhook::DocumentBoxWebhook,
+        tenant::{Tenant, TenantId},
+    },
+};
+use docbox_web_scraper::{PublicDomainResolver, is_allowed_public_url};
+use hmac::{Hmac, Mac};
+use reqwest::{Url, header::CONTENT_TYPE, redirect::Policy};
+use serde::Serialize;
+use sha2::Sha256;
+use std::{sync::Arc, time::Duration};
+use tracing::Instrument;
+
+/// Header containing the name of the delivered event
+pub const WEBHOOK_EVENT_HEADER: &str = "x-docbox-event";
+
+/// Header containing the ID of the webhook the event was delivered for
+pub const WEBHOOK_ID_HEADER: &str = "x-docbox-webhook-id";
+
+/// Header containing the signature of the delivered body
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-docbox-signature";
+
+/// Maximum time to wait for a webhook to respond
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+pub struct WebhookEventPublisherFactory {
+    db_cache: Arc<DatabasePoolCache>,
+    client: reqwest::Client,
+    /// Shutdown coordinator tracking in-flight deliveries
+    shutdown: ShutdownCoordinator,
+    /// Whether webhooks may target private and internal addresses
+    allow_private_urls: bool,
+}
+
+impl WebhookEventPublisherFactory {
+    pub fn new(db_cache: Arc<DatabasePoolCache>, shutdown: ShutdownCoordinator) -> Self {
+        Self {
+            db_cache,
+            client: create_webhook_client(false),
+            shutdown,
+            allow_private_urls: false,
+        }
+    }
+
+    /// Allow webhooks to target private and internal addresses, only
+    /// intended for local development and tests
+    pub fn with_allow_private_urls(mut self, allow_private_urls: bool) -> Self {
+        self.allow_private_urls = allow_private_urls;
+        self.client = create_webhook_client(allow_private_urls);
+        self
+    }
+
+    /// Checks that events are allowed to be delivered to the webhook `url`
+    pub async fn is_allowed_url(&self, url: &str) -> bool {
+        is_allowed_webhook_url(url, self.allow_private_urls).await
+    }
+
+    /// Create a publisher for the `tenant` that forwards events to the
+    /// `inner` tenant publisher
+    pub fn create_event_publisher(
+        &self,
+        tenant: Tenant,
+        inner: TenantEventPublisher,
+    ) -> WebhookEventPublisher {
+        WebhookEventPublisher {
+            db_cache: self.db_cache.clone(),
+            client: self.client.clone(),
+            shutdown: self.shutdown.clone(),
+            allow_private_urls: self.allow_private_urls,
+            tenant: Arc::new(tenant),
+            inner: Box::new(inner),
+        }
+    }
+}
+
+/// Tenant event publisher that delivers events to document box webhooks
+#[derive(Clone)]
+pub struct WebhookEventPublisher {
+    db_cache: Arc<DatabasePoolCache>,
+    client: reqwest::Client,
+    shutdown: ShutdownCoordinator,
+    allow_private_urls: bool,
+    tenant: Arc<Tenant>,
+    inner: Box<TenantEventPublisher>,
+}
+
+/// Body of a webhook delivery, matches the format of the tenant wide events
+///
+/// i.e { "event": "FILE_CREATED", "data": { ...file data }, "tenant_id": "xxxxx-xxxxx-xxxxx-xxxxx" }
+#[derive(Serialize)]
+struct WebhookEventMessage<'a> {
+    tenant_id: TenantId,
+    #[serde(flatten)]
+    message: &'a TenantEventMessage,
+}
+
+impl EventPublisher for WebhookEventPublisher {
+    fn publish_event(&self, event: TenantEventMessage) {
+        let name = event.name();
+        let scope = event.scope().to_string();
+
+        let body = serde_json::to_vec(&WebhookEventMessage {
+            tenant_id: self.tenant.id,
+            message: &event,
+        });
+
+        self.inner.publish_event(event);
+
+        let body = match body {
+            Ok(value) => Bytes::from(value),
+            Err(error) => {
+                tracing::error!(?error, "failed to serialize webhook event");
+                return;
+            }
+        };
+
+        let db_cache = self.db_cache.clone();
+        let client = self.client.clone();
+        let allow_private_urls = self.allow_private_urls;
+        let tenant = self.tenant.clone();
+        let span = tracing::Span::current();
+
+        // Deliveries are tracked so they can complete during shutdown
+        self.shutdown.spawn(
+            async move {
+                let db = match db_cache.get_tenant_pool(&tenant).await {
+                    Ok(value) => value,
+                    Err(error) => {
+                        tracing::error!(?error, "failed to connect to tenant database");
+                        return;
+                    }
+                };
+
+                let webhooks = match DocumentBoxWebhook::all_by_scope(&db, &scope).await {
+                    Ok(value) => value,
+                    Err(error) => {
+                        tracing::error!(?error, "failed to query document box webhooks");
+                        return;
+                    }
+                };
+
+                let deliveries = webhooks
+                    .iter()
+                    .filter(|webhook| webhook.accepts_event(name))
+                    .map(|webhook| {
+                        deliver_event(&client, webhook, allow_private_urls, name, body.clone())
+                    });
+
+                futures::future::join_all(deliveries).await;
+
+                // Webhooks are removed with their document box once the deletion is delivered
+                if matches!(name, "DOCUMENT_BOX_DELETED")
+                    && let Err(error) = DocumentBoxWebhook::delete_by_scope(&db, &scope).await
+                {
+                    tracing::error!(?error, "failed to delete document box webhooks");
+                }
+            }
+            .instrument(span),
+        );
+    }
+}
+
+/// Create the HTTP client for delivering webhooks, unless `allow_private_urls` is
+/// set the client only connects to publicly reachable addresses
+fn create_webhook_client(allow_private_urls: bool) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder().redirect(Policy::none());
+
+    if !allow_private_urls {
+        builder = builder.dns_resolver(PublicDomainResolver);
+    }
+
+    builder
+        .build()
+        .expect("failed to build webhook http client")
+}
+
+/// Deliver the event `body` to the `webhook`
+async fn deliver_event(
+    client: &reqwest::Client,
+    webhook: &DocumentBoxWebhook,
+    allow_private_urls: bool,
+    name: &str,
+    body: Bytes,
+) {
+    // Domain may resolve to a different address since the webhook was created
+    if !is_allowed_webhook_url(&webhook.url, allow_private_urls).await {
+        tracing::warn!(webhook_id = %webhook.id, event = name, "webhook url is not allowed, skipping delivery");
+        return;
+    }
+
+    let signature = sign_payload(&webhook.secret, &body);
+
+    tracing::debug!(webhook_id = %webhook.id, event = name, "delivering webhook event");
+
+    let result = client
+        .post(&webhook.url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .header(CONTENT_TYPE, "application/json")
+        .header(WEBHOOK_EVENT_HEADER, name)
+        .header(WEBHOOK_ID_HEADER, webhook.id.to_string())
+        .header(WEBHOOK_SIGNATURE_HEADER, signature)
+        .body(body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    if let Err(error) = result {
+        tracing::error!(?error, webhook_id = %webhook.id, event = name, "failed to deliver webhook event");
+    }
+}
+
+/// Checks that the webhook `url` is an http or https url resolving to a publicly
+/// reachable address, unless `allow_private_urls` is set
+pub(crate) async fn is_allowed_webhook_url(url: &str, allow_private_urls: bool) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+
+    if allow_private_urls {
+        return matches!(url.scheme(), "http" | "https");
+    }
+
+    is_allowed_public_url(&url).await
+}
+
+/// Create the signature for a webhook `body` using the webhook `secret`
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    // HMAC accepts keys of any length
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(body);
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{create_webhook_client, is_allowed_webhook_url, sign_payload};
+    use std::time::Duration;
+
+    #[test]
+    fn test_sign_payload() {
+        assert_eq!(
+            sign_payload("key", b"The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_url_internal_address() {
+        assert!(!is_allowed_webhook_url("http://169.254.169.254/latest/meta-data", false).await);
+        assert!(!is_allowed_webhook_url("http://127.0.0.1:8080/webhook", false).await);
+        assert!(!is_allowed_webhook_url("http://[::1]/webhook", false).await);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_client_rejects_internal_address() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // Delivery client refuses domains resolving to internal addresses even
+        // when the domain passed the earlier url check
+        let result = create_webhook_client(false)
+            .post(format!("http://localhost:{port}/webhook"))
+            .send()
+            .await;
+
+        assert!(result.is_err());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), listener.accept())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_url_private_allowed() {
+        assert!(is_allowed_webhook_url("http://127.0.0.1:8080/webhook", true).await);
+        assert!(!is_allowed_webhook_url("ftp://127.0.0.1/webhook", true).await);
+        assert!(!is_allowed_webhook_url("not a url", true).await);
+    }
+}
@@ -0,0 +1,241 @@
+//! # Access Stats
+//!
+//! Tracks file download and preview counts. Accesses are recorded in memory
+//! and written to the tenant databases in batches by a background task to
+//! avoid a database write for every file access
+//!
+//! Any accumulated accesses are written when shutdown is requested
+
+use crate::shutdown::ShutdownCoordinator;
+use chrono::{DateTime, Utc};
+use docbox_database::{
+    DatabasePoolCache,
+    models::{
+        file::FileId,
+        file_access_stats::{FileAccessIncrement, FileAccessStats},
+        tenant::{Tenant, TenantId},
+    },
+};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::mpsc;
+
+/// Interval between writing the accumulated accesses to the database
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Number of accessed files that will trigger writing the accumulated
+/// accesses before the flush interval
+const FLUSH_THRESHOLD: usize = 1000;
+
+/// Type of file access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAccessKind {
+    /// File contents were downloaded
+    Download,
+    /// File contents were viewed inline
+    Preview,
+}
+
+struct FileAccessEvent {
+    tenant: Tenant,
+    file_id: FileId,
+    kind: FileAccessKind,
+    accessed_at: DateTime<Utc>,
+}
+
+/// Records file accesses, the accesses are written to the tenant
+/// database in batches by a background task
+#[derive(Clone)]
+pub struct FileAccessRecorder {
+    tx: mpsc::UnboundedSender<FileAccessEvent>,
+}
+
+impl FileAccessRecorder {
+    /// Create a new recorder, spawns the background task that writes
+    /// the accesses to the database
+    pub fn new(db_cache: Arc<DatabasePoolCache>, shutdown: &ShutdownCoordinator) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        shutdown.spawn(process_file_accesses(db_cache, rx, shutdown.clone()));
+        Self { tx }
+    }
+
+    /// Create a recorder for the provided `tenant`
+    pub fn tenant_recorder(&self, tenant: &Tenant) -> TenantFileAccessRecorder {
+        TenantFileAccessRecorder {
+            tenant: tenant.clone(),
+            tx: self.tx.clone(),
+        }
+    }
+}
+
+/// Records file accesses for a specific tenant
+#[derive(Clone)]
+pub struct TenantFileAccessRecorder {
+    tenant: Tenant,
+    tx: mpsc::UnboundedSender<FileAccessEvent>,
+}
+
+impl TenantFileAccessRecorder {
+    /// Record an access to the file
+    pub fn record(&self, file_id: FileId, kind: FileAccessKind) {
+        let event = FileAccessEvent {
+            tenant: self.tenant.clone(),
+            file_id,
+            kind,
+            accessed_at: Utc::now(),
+        };
+
+        if self.tx.send(event).is_err() {
+            tracing::warn!(%file_id, "file access recorder is not running, access not recorded");
+        }
+    }
+}
+
+/// Accesses accumulated for a single tenant
+#[derive(Default)]
+struct FileAccessBatch {
+    increments: HashMap<FileId, FileAccessIncrement>,
+}
+
+impl FileAccessBatch {
+    fn record(&mut self, file_id: FileId, kind: FileAccessKind, accessed_at: DateTime<Utc>) {
+        let increment = self
+            .increments
+            .entry(file_id)
+            .or_insert_with(|| FileAccessIncrement {
+                file_id,
+                downloads: 0,
+                previews: 0,
+                accessed_at,
+            });
+
+        match kind {
+            FileAccessKind::Download => increment.downloads += 1,
+            FileAccessKind::Preview => increment.previews += 1,
+        }
+
+        increment.accessed_at = increment.accessed_at.max(accessed_at);
+    }
+
+    fn len(&self) -> usize {
+        self.increments.len()
+    }
+}
+
+/// Background task accumulating the file accesses and periodically
+/// writing them to the tenant databases
+async fn process_file_accesses(
+    db_cache: Arc<DatabasePoolCache>,
+    mut rx: mpsc::UnboundedReceiver<FileAccessEvent>,
+    shutdown: ShutdownCoordinator,
+) {
+    let mut batches: HashMap<TenantId, (Tenant, FileAccessBatch)> = HashMap::new();
+    let mut pending = 0;
+
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => {
+                let Some(event) = event else {
+                    // All recorders have been dropped, write the remaining accesses
+                    flush_file_accesses(&db_cache, std::mem::take(&mut batches)).await;
+                    return;
+                };
+
+                let (_, batch) = batches
+                    .entry(event.tenant.id)
+                    .or_insert_with(|| (event.tenant, FileAccessBatch::default()));
+
+                let previous = batch.len();
+                batch.record(event.file_id, event.kind, event.accessed_at);
+                pending += batch.len() - previous;
+
+                if pending < FLUSH_THRESHOLD {
+                    continue;
+                }
+            }
+            _ = interval.tick() => {}
+            _ = shutdown.cancelled() => {
+                // Write any accesses that were already recorded before exiting
+                rx.close();
+                while let Ok(event) = rx.try_recv() {
+                    let (_, batch) = batches
+                        .entry(event.tenant.id)
+                        .or_insert_with(|| (event.tenant, FileAccessBatch::default()));
+                    batch.record(event.file_id, event.kind, event.accessed_at);
+                }
+
+                flush_file_accesses(&db_cache, std::mem::take(&mut batches)).await;
+                return;
+            }
+        }
+
+        if batches.is_empty() {
+            continue;
+        }
+
+        flush_file_accesses(&db_cache, std::mem::take(&mut batches)).await;
+        pending = 0;
+    }
+}
+
+/// Write the accumulated accesses to the tenant databases
+async fn flush_file_accesses(
+    db_cache: &DatabasePoolCache,
+    batches: HashMap<TenantId, (Tenant, FileAccessBatch)>,
+) {
+    for (tenant, batch) in batches.into_values() {
+        let db = match db_cache.get_tenant_pool(&tenant).await {
+            Ok(value) => value,
+            Err(error) => {
+                tracing::error!(
+                    ?error,
+                    tenant_id = %tenant.id,
+                    "failed to connect to tenant database for file access stats"
+                );
+                continue;
+            }
+        };
+
+        for increment in batch.increments.into_values() {
+            if let Err(error) = FileAccessStats::increment(&db, &increment).await {
+                tracing::error!(
+                    ?error,
+                    tenant_id = %tenant.id,
+                    file_id = %increment.file_id,
+                    "failed to store file access stats"
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FileAccessBatch, FileAccessKind};
+    use chrono::{TimeDelta, Utc};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_file_access_batch() {
+        let mut batch = FileAccessBatch::default();
+        let file_id = Uuid::new_v4();
+        let other_file_id = Uuid::new_v4();
+
+        let first = Utc::now();
+        let latest = first + TimeDelta::seconds(5);
+
+        batch.record(file_id, FileAccessKind::Download, latest);
+        batch.record(file_id, FileAccessKind::Preview, first);
+        batch.record(file_id, FileAccessKind::Preview, first);
+        batch.record(other_file_id, FileAccessKind::Download, first);
+
+        assert_eq!(batch.len(), 2);
+
+        let increment = &batch.increments[&file_id];
+        assert_eq!(increment.downloads, 1);
+        assert_eq!(increment.previews, 2);
+        assert_eq!(increment.accessed_at, latest);
+    }
+}
@@ -0,0 +1,98 @@
+//! # Active Content
+//!
+//! HTML and SVG files can contain scripts that would run within the origin
+//! serving the file. The tenant [ActiveContentPolicy] decides how the raw
+//! contents of these files are served to prevent them from running.
+
+use docbox_database::models::tenant::ActiveContentPolicy;
+use docbox_mime::is_active_content_mime;
+use mime::Mime;
+use std::str::FromStr;
+
+/// Content security policy for serving active content, the document is
+/// sandboxed into a unique origin with scripts, forms and plugins disabled
+pub const ACTIVE_CONTENT_CSP: &str = "sandbox; default-src 'none'; style-src 'unsafe-inline'; img-src data:; base-uri 'none'; form-action 'none'";
+
+/// Content type active content is served as under [ActiveContentPolicy::PlainText]
+const PLAIN_TEXT_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// Headers to serve raw file contents with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveContentHeaders {
+    /// Content type to serve the file as
+    pub content_type: String,
+    /// Whether the file must be served as a download
+    pub download: bool,
+}
+
+/// Determine how a raw file with the provided `mime` should be served under
+/// the tenant `policy`, returns [None] when the file is not active content
+/// and can be served as-is
+pub fn active_content_headers(
+    policy: ActiveContentPolicy,
+    mime: &str,
+    download: bool,
+) -> Option<ActiveContentHeaders> {
+    let parsed = Mime::from_str(mime).ok()?;
+    if !is_active_content_mime(&parsed) {
+        return None;
+    }
+
+    Some(match policy {
+        ActiveContentPolicy::Sandbox => ActiveContentHeaders {
+            content_type: mime.to_string(),
+            download,
+        },
+        ActiveContentPolicy::PlainText => ActiveContentHeaders {
+            content_type: PLAIN_TEXT_CONTENT_TYPE.to_string(),
+            download,
+        },
+        ActiveContentPolicy::Download => ActiveContentHeaders {
+            content_type: mime.to_string(),
+            download: true,
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ActiveContentHeaders, active_content_headers};
+    use docbox_database::models::tenant::ActiveContentPolicy;
+
+    #[test]
+    fn test_inactive_content_unchanged() {
+        assert_eq!(
+            active_content_headers(ActiveContentPolicy::Download, "image/png", false),
+            None
+        );
+        assert_eq!(
+            active_content_headers(ActiveContentPolicy::PlainText, "not a mime", false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_active_content_policies() {
+        assert_eq!(
+            active_content_headers(ActiveContentPolicy::Sandbox, "image/svg+xml", false),
+            Some(ActiveContentHeaders {
+                content_type: "image/svg+xml".to_string(),
+                download: false,
+            })
+        );
+        assert_eq!(
+            active_content_headers(ActiveContentPolicy::PlainText, "text/html", false),
+            Some(ActiveContentHeaders {
+                content_type: "text/plain; charset=utf-8".to_string(),
+                download: false,
+            })
+        );
+        assert_eq!(
+            active_content_headers(ActiveContentPolicy::Download, "text/html", false),
+            Some(ActiveContentHeaders {
+                content_type: "text/html".to_string(),
+                download: true,
+            })
+        );
+    }
+}
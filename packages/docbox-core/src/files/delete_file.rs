@@ -1,18 +1,25 @@
 use crate::{
+    document_box::document_box_stats::safe_apply_document_box_stats_delta,
     events::{TenantEventMessage, TenantEventPublisher},
-    files::generated::{GeneratedFileDeleteResult, delete_generated_files},
+    files::generated::{
+        GENERATED_FILE_SNAPSHOT_LIMIT, GeneratedFileDeleteResult, delete_generated_files,
+        restore_generated_files, snapshot_generated_files,
+    },
 };
 use docbox_database::{
-    DbErr, DbPool,
+    DbErr, DbPool, DbTransaction,
     models::{
         document_box::{DocumentBoxScopeRaw, WithScope},
+        document_box_stats::DocumentBoxStatsDelta,
         file::File,
         generated_file::GeneratedFile,
+        storage_object::StorageObject,
     },
 };
 use docbox_search::{SearchError, TenantSearchIndex};
 use docbox_storage::{StorageLayer, StorageLayerError};
 use futures::{StreamExt, stream::FuturesUnordered};
+use std::ops::DerefMut;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -40,12 +47,14 @@ pub enum DeleteFileError {
 /// prevent dangling files in the bucket. Same goes for the search
 /// index
 ///
-/// This process cannot be rolled back since the changes to storage are
-/// permanent. If a failure occurs after generated files are deleted
-/// they will stay deleted.
+/// The file object reference is released in the same transaction that
+/// removes the file, a failed delete leaves both in place for a retry
 ///
-/// We may choose to revise this to load the generated files into memory
-/// in order to restore them on failure.
+/// Changes to storage are permanent, so generated files are loaded into
+/// memory (up to [GENERATED_FILE_SNAPSHOT_LIMIT]) before they are deleted.
+/// If a failure occurs before their metadata is removed the snapshots are
+/// restored to storage. Generated files that could not be snapshot and
+/// fail to restore have their metadata removed instead.
 pub async fn delete_file(
     db: &DbPool,
     storage: &StorageLayer,
@@ -58,13 +67,20 @@ pub async fn delete_file(
         .await
         .inspect_err(|error| tracing::error!(?error, "failed to query generated files"))?;
 
+    // Snapshot the generated files so they can be restored on failure
+    let snapshots =
+        snapshot_generated_files(storage, &generated, GENERATED_FILE_SNAPSHOT_LIMIT).await;
+
     match delete_generated_files(storage, &generated).await {
         GeneratedFileDeleteResult::Ok => {}
         GeneratedFileDeleteResult::Err(deleted, err) => {
-            // Attempt to delete generated files from db that were deleted from storage
+            // Restore the generated files that were deleted from storage
+            let unrestored = restore_generated_files(storage, &snapshots, &deleted).await;
+
+            // Attempt to delete generated files from db that could not be restored
             let mut delete_files_future = generated
                 .into_iter()
-                .filter(|file| deleted.contains(&file.id))
+                .filter(|file| unrestored.contains(&file.id))
                 .map(|file| file.delete(db))
                 .collect::<FuturesUnordered<_>>();
 
@@ -81,22 +97,33 @@ pub async fn delete_file(
 
     let mut delete_files_future = generated
         .into_iter()
-        .map(|file| file.delete(db))
+        .map(|file| async move {
+            let id = file.id;
+            file.delete(db).await.map_err(|error| (id, error))
+        })
         .collect::<FuturesUnordered<_>>();
 
+    let mut remaining = Vec::new();
+    let mut delete_error = None;
+
     // Delete the generated files from the database
     while let Some(result) = delete_files_future.next().await {
-        if let Err(error) = result {
-            tracing::error!(?error, "failed to delete generated file");
-            return Err(DeleteFileError::Database(error));
+        if let Err((id, error)) = result {
+            tracing::error!(?error, %id, "failed to delete generated file");
+            remaining.push(id);
+            delete_error.get_or_insert(error);
         }
     }
 
-    // Delete the file from storage
-    storage
-        .delete_file(&file.file_key)
-        .await
-        .map_err(DeleteFileError::DeleteFileStorage)?;
+    if let Some(error) = delete_error {
+        // Restore the generated files that still have metadata
+        let unrestored = restore_generated_files(storage, &snapshots, &remaining).await;
+        if !unrestored.is_empty() {
+            tracing::error!(?unrestored, "failed to restore generated files");
+        }
+
+        return Err(DeleteFileError::Database(error));
+    }
 
     // Delete the indexed file contents
     search
@@ -104,19 +131,96 @@ pub async fn delete_file(
         .await
         .map_err(DeleteFileError::DeleteIndex)?;
 
+    let mut t = db
+        .begin()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to begin transaction"))?;
+
     // Delete the file itself
     let result = file
-        .delete(db)
+        .delete(t.deref_mut())
         .await
         .inspect_err(|error| tracing::error!(?error, "failed to delete file from database"))?;
 
-    // Check we actually removed something before emitting an event
+    // Check we actually removed something before releasing the file object,
+    // a retried delete must not release the reference a second time
     if result.rows_affected() < 1 {
         return Ok(());
     }
 
+    // Delete the file from storage, only committed alongside the file removal
+    release_file_object_in(&mut t, storage, &file.file_key).await?;
+
+    t.commit()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to commit transaction"))?;
+
+    safe_apply_document_box_stats_delta(
+        db,
+        &scope,
+        DocumentBoxStatsDelta::file(file.size).negate(),
+    )
+    .await;
+
     // Publish an event
     events.publish_event(TenantEventMessage::FileDeleted(WithScope::new(file, scope)));
 
     Ok(())
 }
+
+/// Removes the stored object for a file. Deduplicated objects shared with other
+/// files only have their reference released and are removed from storage once
+/// no files reference them
+///
+/// The released reference row stays locked until the object is removed from
+/// storage, a concurrent upload of the same contents waits on the lock rather
+/// than taking a reference to an object that is about to be deleted. Failing
+/// to remove the object restores the reference
+pub(crate) async fn release_file_object(
+    db: &DbPool,
+    storage: &StorageLayer,
+    file_key: &str,
+) -> Result<(), DeleteFileError> {
+    let mut t = db
+        .begin()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to begin transaction"))?;
+
+    release_file_object_in(&mut t, storage, file_key).await?;
+
+    t.commit()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to commit transaction"))?;
+
+    Ok(())
+}
+
+/// Removes the stored object for a file within the transaction `t`, the
+/// reference is only released once `t` is committed
+async fn release_file_object_in(
+    t: &mut DbTransaction<'_>,
+    storage: &StorageLayer,
+    file_key: &str,
+) -> Result<(), DeleteFileError> {
+    let remaining = StorageObject::release(t.deref_mut(), file_key)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to release storage object"))?;
+
+    // Object is still referenced by other files
+    if remaining.is_some_and(|count| count > 0) {
+        return Ok(());
+    }
+
+    storage
+        .delete_file(file_key)
+        .await
+        .map_err(DeleteFileError::DeleteFileStorage)?;
+
+    if remaining.is_some() {
+        StorageObject::delete_unreferenced(t.deref_mut(), file_key)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to delete storage object"))?;
+    }
+
+    Ok(())
+}
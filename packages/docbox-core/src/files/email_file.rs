@@ -0,0 +1,227 @@
+//! # Email File
+//!
+//! Sends a file by email, the file is attached to the email unless it is
+//! larger than the mailer max attachment size or a link was requested in
+//! which case a presigned link to download the file is sent instead.
+//!
+//! Each sent email is recorded as a [FileEmail] so there is an audit log of
+//! which files were sent to whom
+
+use crate::{
+    files::create_content_disposition,
+    mailer::{Mailer, MailerError, template::RenderedMail},
+};
+use chrono::{DateTime, Utc};
+use docbox_database::{
+    DbErr, DbPool,
+    models::{
+        file::File,
+        file_email::{CreateFileEmail, FileEmail, FileEmailDelivery},
+        user::User,
+    },
+};
+use docbox_storage::{PresignedDownloadOptions, StorageLayer, StorageLayerError};
+use lettre::{
+    Message,
+    message::{Attachment, Mailbox, MultiPart, SinglePart, header::ContentType},
+};
+use thiserror::Error;
+
+/// Maximum number of recipients a file can be sent to in one email
+pub const MAX_FILE_EMAIL_RECIPIENTS: usize = 20;
+
+#[derive(Debug, Error)]
+pub enum EmailFileError {
+    #[error("invalid recipient email address \"{0}\"")]
+    InvalidRecipient(String),
+
+    #[error("file is too large to attach and links are not available for encrypted storage")]
+    LinkUnavailable,
+
+    #[error(transparent)]
+    Storage(#[from] StorageLayerError),
+
+    #[error("failed to build email: {0}")]
+    Build(lettre::error::Error),
+
+    #[error(transparent)]
+    Mailer(#[from] MailerError),
+
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+/// Request to send a file by email
+pub struct EmailFile {
+    /// Email addresses to send the file to
+    pub recipients: Vec<String>,
+    /// Optional message to include in the email
+    pub message: Option<String>,
+    /// User sending the file
+    pub sender: Option<User>,
+    /// Whether to always send a link instead of attaching the file
+    pub as_link: bool,
+}
+
+/// Send the `file` by email
+pub async fn email_file(
+    db: &DbPool,
+    storage: &StorageLayer,
+    mailer: &Mailer,
+    file: &File,
+    request: EmailFile,
+) -> Result<FileEmail, EmailFileError> {
+    let recipients = request
+        .recipients
+        .iter()
+        .map(|recipient| {
+            recipient
+                .parse::<Mailbox>()
+                .map_err(|_| EmailFileError::InvalidRecipient(recipient.clone()))
+        })
+        .collect::<Result<Vec<Mailbox>, EmailFileError>>()?;
+
+    let delivery = if request.as_link || file.size.max(0) as u64 > mailer.max_attachment_size_bytes
+    {
+        FileEmailDelivery::Link
+    } else {
+        FileEmailDelivery::Attachment
+    };
+
+    let sender = request
+        .sender
+        .as_ref()
+        .and_then(|user| user.name.clone())
+        .unwrap_or_else(|| "Someone".to_string());
+    let file_size = format_file_size(file.size.max(0) as u64);
+    let message = request.message.unwrap_or_default();
+
+    let mut values = vec![
+        ("sender", sender.as_str()),
+        ("file_name", file.name.as_str()),
+        ("file_size", file_size.as_str()),
+        ("message", message.as_str()),
+    ];
+
+    let (rendered, link_expires_at, attachment) = match delivery {
+        FileEmailDelivery::Attachment => {
+            let bytes = storage
+                .get_file(&file.file_key)
+                .await?
+                .collect_bytes()
+                .await?;
+            let rendered = mailer.templates.file_attachment.render(&values);
+            (rendered, None, Some(bytes))
+        }
+        FileEmailDelivery::Link => {
+            if storage.is_encrypted() {
+                return Err(EmailFileError::LinkUnavailable);
+            }
+
+            let options = PresignedDownloadOptions {
+                content_disposition: Some(create_content_disposition(true, &file.name)),
+                ..Default::default()
+            };
+
+            let (signed_request, link_expires_at) = storage
+                .create_presigned_download(&file.file_key, mailer.link_expiry, options)
+                .await?;
+
+            let link = signed_request.uri().to_string();
+            let expires_at = format_expires_at(link_expires_at);
+            values.push(("link", link.as_str()));
+            values.push(("link_expires_at", expires_at.as_str()));
+
+            let rendered = mailer.templates.file_link.render(&values);
+            (rendered, Some(link_expires_at), None)
+        }
+    };
+
+    let RenderedMail { subject, body } = rendered;
+
+    let mut builder = Message::builder()
+        .from(mailer.from().clone())
+        .subject(subject.as_str());
+    for recipient in recipients {
+        builder = builder.to(recipient);
+    }
+
+    let email = match attachment {
+        Some(bytes) => {
+            let content_type = ContentType::parse(&file.mime)
+                .unwrap_or_else(|_| ContentType::parse("application/octet-stream").unwrap());
+
+            builder.multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::plain(body))
+                    .singlepart(
+                        Attachment::new(file.name.clone()).body(bytes.to_vec(), content_type),
+                    ),
+            )
+        }
+        None => builder.singlepart(SinglePart::plain(body)),
+    }
+    .map_err(EmailFileError::Build)?;
+
+    mailer.send(email).await?;
+
+    tracing::info!(
+        file_id = %file.id,
+        recipients = ?request.recipients,
+        %delivery,
+        "sent file by email"
+    );
+
+    let file_email = FileEmail::create(
+        db,
+        CreateFileEmail {
+            file_id: file.id,
+            recipients: request.recipients,
+            subject,
+            delivery,
+            link_expires_at,
+            sent_by: request.sender.map(|user| user.id),
+        },
+    )
+    .await
+    .inspect_err(|error| tracing::error!(?error, "failed to record sent file email"))?;
+
+    Ok(file_email)
+}
+
+/// Format a file size in bytes for display (i.e "1.5 MB")
+fn format_file_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if size < 1024 {
+        return format!("{size} B");
+    }
+
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Format when a link expires for display
+fn format_expires_at(expires_at: DateTime<Utc>) -> String {
+    expires_at.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::format_file_size;
+
+    #[test]
+    fn test_format_file_size() {
+        assert_eq!(format_file_size(0), "0 B");
+        assert_eq!(format_file_size(1023), "1023 B");
+        assert_eq!(format_file_size(1536), "1.5 KB");
+        assert_eq!(format_file_size(10 * 1024 * 1024), "10.0 MB");
+        assert_eq!(format_file_size(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+}
@@ -0,0 +1,164 @@
+//! # Extraction Cache
+//!
+//! Processing output for uploaded files is cached at the tenant level keyed by
+//! the content hash and mime type of the file. When the same content is uploaded
+//! again the extracted pages and generated files from the previous upload are
+//! reused instead of running the conversion and extraction again.
+
+use chrono::Utc;
+use docbox_database::{
+    DbErr, DbPool,
+    models::{
+        extraction_cache::{CreateExtractionCacheEntry, ExtractionCacheEntry},
+        file::FileId,
+        file_pdf_metadata::FilePdfMetadata,
+        file_processing::FileProcessing,
+        generated_file::{GeneratedFile, GeneratedFileType},
+    },
+};
+use docbox_mime::is_mail_mime;
+use docbox_processing::{
+    PROCESSING_PIPELINE_VERSION, ProcessingIndexMetadata, ProcessingOutput, QueuedUpload,
+    text_stats::compute_text_stats,
+};
+use docbox_search::models::DocumentPage;
+use docbox_storage::{StorageLayer, StorageLayerError};
+use mime::Mime;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ExtractionCacheError {
+    /// Failed to query the cache or generated files
+    #[error(transparent)]
+    Database(#[from] DbErr),
+
+    /// Failed to load a cached generated file from storage
+    #[error(transparent)]
+    Storage(#[from] StorageLayerError),
+
+    /// Cached pages could not be decoded
+    #[error("failed to decode cached pages: {0}")]
+    DecodePages(serde_json::Error),
+
+    /// Cached generated file had an invalid mime type
+    #[error("cached generated file has invalid mime type")]
+    InvalidMime,
+}
+
+/// Whether processing output for the provided `mime` can be cached.
+///
+/// Emails are excluded as their output depends on the request processing
+/// config and they produce additional files that must be processed separately
+pub fn is_cacheable_mime(mime: &Mime) -> bool {
+    !is_mail_mime(mime)
+}
+
+/// Load the cached processing output for content with the provided `hash`
+/// that was processed as `mime`
+///
+/// Generated files and PDF metadata are loaded from the source file of the
+/// cache entry so that they can be stored against the new file
+pub async fn load_cached_processing_output(
+    db: &DbPool,
+    storage: &StorageLayer,
+    hash: &str,
+    mime: &Mime,
+) -> Result<Option<ProcessingOutput>, ExtractionCacheError> {
+    let entry = match ExtractionCacheEntry::find(db, hash, mime.essence_str()).await? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    // Output produced by an older version of the processing pipeline is outdated
+    let source_version = FileProcessing::find(db, entry.source_file_id)
+        .await?
+        .map(|processing| processing.version)
+        .unwrap_or_default();
+
+    if source_version < PROCESSING_PIPELINE_VERSION {
+        return Ok(None);
+    }
+
+    let pages: Option<Vec<DocumentPage>> = entry
+        .pages
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(ExtractionCacheError::DecodePages)?;
+
+    let generated_files = GeneratedFile::find_all(db, entry.source_file_id).await?;
+    let mut upload_queue = Vec::with_capacity(generated_files.len());
+
+    for generated_file in generated_files {
+        // Extracted tables depend on the upload processing config
+        if matches!(
+            generated_file.ty,
+            GeneratedFileType::Tables | GeneratedFileType::TableCsv
+        ) {
+            continue;
+        }
+
+        let mime: Mime = generated_file
+            .mime
+            .parse()
+            .map_err(|_| ExtractionCacheError::InvalidMime)?;
+
+        let bytes = storage
+            .get_file(&generated_file.file_key)
+            .await?
+            .collect_bytes()
+            .await?;
+
+        upload_queue
+            .push(QueuedUpload::new(mime, generated_file.ty, bytes).with_page(generated_file.page));
+    }
+
+    // Reuse the summary generated for the source file
+    let summary = upload_queue
+        .iter()
+        .find(|upload| upload.ty == GeneratedFileType::Summary)
+        .and_then(|upload| String::from_utf8(upload.bytes.to_vec()).ok());
+
+    let pdf_metadata = FilePdfMetadata::find(db, entry.source_file_id)
+        .await?
+        .map(Into::into);
+
+    Ok(Some(ProcessingOutput {
+        upload_queue,
+        additional_files: Vec::new(),
+        index_metadata: Some(ProcessingIndexMetadata {
+            text_stats: pages.as_deref().map(compute_text_stats),
+            pages,
+            summary,
+        }),
+        encrypted: entry.encrypted,
+        pdf_metadata,
+        pii_analysis: None,
+        // No processing stages ran for the cached output
+        timings: Default::default(),
+    }))
+}
+
+/// Create the cache entry to store for the processing output of a file
+pub fn make_extraction_cache_entry(
+    source_file_id: FileId,
+    hash: &str,
+    mime: &Mime,
+    encrypted: bool,
+    index_metadata: Option<&ProcessingIndexMetadata>,
+) -> Option<CreateExtractionCacheEntry> {
+    let pages = index_metadata
+        .and_then(|metadata| metadata.pages.as_ref())
+        .map(serde_json::to_value)
+        .transpose()
+        .inspect_err(|error| tracing::error!(?error, "failed to encode pages for caching"))
+        .ok()?;
+
+    Some(CreateExtractionCacheEntry {
+        hash: hash.to_string(),
+        mime: mime.essence_str().to_string(),
+        source_file_id,
+        encrypted,
+        pages,
+        created_at: Utc::now(),
+    })
+}
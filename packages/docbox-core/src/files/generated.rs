@@ -1,10 +1,15 @@
 //! Business logic for working with generated files
 
-use crate::files::create_generated_file_key;
+use crate::{
+    files::create_generated_file_key,
+    utils::file::{StoredObjectType, create_object_tags},
+};
+use bytes::Bytes;
 use chrono::Utc;
 use docbox_database::models::{
     file::FileId,
     generated_file::{CreateGeneratedFile, GeneratedFile, GeneratedFileId},
+    generated_file_policy::GeneratedFilePolicy,
 };
 use docbox_processing::QueuedUpload;
 use docbox_storage::{StorageLayer, StorageLayerError, UploadFileOptions};
@@ -12,6 +17,7 @@ use futures::{
     StreamExt,
     stream::{FuturesOrdered, FuturesUnordered},
 };
+use mime::Mime;
 use tracing::{Instrument, debug, error};
 use uuid::Uuid;
 
@@ -63,6 +69,119 @@ pub async fn delete_generated_files(
     GeneratedFileDeleteResult::Ok
 }
 
+/// Maximum combined size of the generated files that will be loaded into
+/// memory by [snapshot_generated_files]
+pub const GENERATED_FILE_SNAPSHOT_LIMIT: usize = 32 * 1024 * 1024;
+
+/// Contents of a generated file loaded before it was deleted from storage
+pub struct GeneratedFileSnapshot {
+    pub id: GeneratedFileId,
+    pub file_key: String,
+    pub mime: String,
+    pub bytes: Bytes,
+}
+
+/// Loads the contents of the generated `files` into memory so they can be
+/// restored if a later step fails after they are deleted from storage
+///
+/// Files are loaded until their combined size reaches `max_size`, files
+/// past the limit or that fail to load are not snapshot and cannot be
+/// restored
+pub async fn snapshot_generated_files(
+    storage: &StorageLayer,
+    files: &[GeneratedFile],
+    max_size: usize,
+) -> Vec<GeneratedFileSnapshot> {
+    let mut snapshots = Vec::with_capacity(files.len());
+    let mut remaining = max_size;
+
+    for file in files {
+        let bytes = match storage.get_file(&file.file_key).await {
+            Ok(stream) => stream.collect_bytes_max(remaining).await,
+            Err(error) => Err(error),
+        };
+
+        let bytes = match bytes {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => {
+                debug!(id = %file.id, "generated file exceeds snapshot limit");
+                continue;
+            }
+            Err(error) => {
+                error!(id = %file.id, ?error, "failed to snapshot generated file");
+                continue;
+            }
+        };
+
+        remaining -= bytes.len();
+        snapshots.push(GeneratedFileSnapshot {
+            id: file.id,
+            file_key: file.file_key.clone(),
+            mime: file.mime.clone(),
+            bytes,
+        });
+    }
+
+    snapshots
+}
+
+/// Restores the snapshots for the generated files in `ids` back into storage,
+/// returns the IDs of the generated files that could not be restored
+pub async fn restore_generated_files(
+    storage: &StorageLayer,
+    snapshots: &[GeneratedFileSnapshot],
+    ids: &[GeneratedFileId],
+) -> Vec<GeneratedFileId> {
+    let mut failed = Vec::new();
+
+    for id in ids {
+        let Some(snapshot) = snapshots.iter().find(|snapshot| snapshot.id.eq(id)) else {
+            error!(%id, "generated file was not snapshot, cannot restore");
+            failed.push(*id);
+            continue;
+        };
+
+        debug!(%id, file_key = %snapshot.file_key, "restoring generated file");
+
+        if let Err(error) = storage
+            .upload_file(
+                &snapshot.file_key,
+                snapshot.bytes.clone(),
+                UploadFileOptions {
+                    content_type: snapshot.mime.clone(),
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            error!(%id, ?error, "failed to restore generated file");
+            failed.push(*id);
+        }
+    }
+
+    failed
+}
+
+/// Filters the `queued_uploads` generated from a file with the provided `mime`
+/// removing any generated files that the tenant `policies` don't retain
+pub fn apply_generated_file_policies(
+    policies: &[GeneratedFilePolicy],
+    mime: &Mime,
+    queued_uploads: Vec<QueuedUpload>,
+) -> Vec<QueuedUpload> {
+    queued_uploads
+        .into_iter()
+        .filter(|upload| {
+            let retain =
+                GeneratedFilePolicy::should_retain(policies, mime.essence_str(), upload.ty);
+            if !retain {
+                debug!(ty = %upload.ty, "skipping generated file not retained by policy");
+            }
+            retain
+        })
+        .collect()
+}
+
 pub struct PreparedGeneratedFile {
     create: CreateGeneratedFile,
     upload: QueuedUpload,
@@ -79,7 +198,8 @@ pub fn make_create_generated_files(
         .map(|upload| {
             let id = Uuid::new_v4();
             let created_at = Utc::now();
-            let file_key = create_generated_file_key(base_file_key, &upload.mime);
+            let file_key =
+                create_generated_file_key(base_file_key, upload.ty, upload.page, &upload.mime);
 
             let create = CreateGeneratedFile {
                 id,
@@ -89,6 +209,7 @@ pub fn make_create_generated_files(
                 ty: upload.ty,
                 file_key,
                 created_at,
+                page: upload.page,
             };
 
             PreparedGeneratedFile { create, upload }
@@ -100,6 +221,7 @@ pub fn make_create_generated_files(
 /// to persist to the database
 pub async fn upload_generated_files(
     storage: &StorageLayer,
+    document_box: &str,
     prepared: Vec<PreparedGeneratedFile>,
 ) -> Vec<Result<CreateGeneratedFile, StorageLayerError>> {
     prepared
@@ -114,6 +236,10 @@ pub async fn upload_generated_files(
                         upload.bytes,
                         UploadFileOptions {
                             content_type: create.mime.clone(),
+                            object_tags: create_object_tags(
+                                document_box,
+                                StoredObjectType::GeneratedFile,
+                            ),
                             ..Default::default()
                         },
                     )
@@ -28,6 +28,12 @@ pub fn create_file_index(
     document_box: &DocumentBoxScopeRaw,
     index_metadata: Option<ProcessingIndexMetadata>,
 ) -> SearchIndexData {
+    let ProcessingIndexMetadata {
+        pages,
+        summary,
+        text_stats,
+    } = index_metadata.unwrap_or_default();
+
     SearchIndexData {
         ty: SearchIndexType::File,
         item_id: file.id,
@@ -37,7 +43,11 @@ pub fn create_file_index(
         content: None,
         created_at: file.created_at,
         created_by: file.created_by.clone(),
+        // Newly created files are never pinned
+        pinned: false,
         document_box: document_box.clone(),
-        pages: index_metadata.and_then(|value| value.pages),
+        pages,
+        summary,
+        text_stats,
     }
 }
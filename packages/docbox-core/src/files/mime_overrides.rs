@@ -0,0 +1,26 @@
+//! # Mime Overrides
+//!
+//! Loads the mime type overrides configured for a tenant, used to change
+//! the mime type of files with specific extensions (i.e treating ".dat"
+//! files as PDF files)
+
+use docbox_database::{DbExecutor, DbResult, models::mime_override::MimeOverride};
+use docbox_mime::MimeOverrides;
+use mime::Mime;
+
+/// Load the mime type overrides for the tenant, overrides with an invalid
+/// mime type are skipped
+pub async fn get_mime_overrides(db: impl DbExecutor<'_>) -> DbResult<MimeOverrides> {
+    let overrides = MimeOverride::all(db).await?;
+
+    Ok(overrides
+        .into_iter()
+        .filter_map(|mime_override| match mime_override.mime.parse::<Mime>() {
+            Ok(mime) => Some((mime_override.extension, mime)),
+            Err(error) => {
+                tracing::warn!(?error, ?mime_override, "skipping invalid mime override");
+                None
+            }
+        })
+        .collect())
+}
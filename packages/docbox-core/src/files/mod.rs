@@ -1,12 +1,22 @@
+use docbox_database::models::generated_file::GeneratedFileType;
 use mime::Mime;
 use uuid::Uuid;
 
-use crate::utils::file::{get_file_name_ext, get_mime_ext, make_s3_safe};
+use crate::utils::file::make_s3_safe;
+use docbox_mime::{get_file_name_ext, get_mime_ext};
 
+pub mod access_stats;
+pub mod active_content;
 pub mod delete_file;
+pub mod email_file;
+pub mod extraction_cache;
 pub mod generated;
 pub mod index_file;
+pub mod mime_overrides;
+pub mod preview;
+pub mod regenerate_generated_file;
 pub mod reprocess_octet_stream_files;
+pub mod reprocess_outdated_files;
 pub mod update_file;
 pub mod upload_file;
 pub mod upload_file_presigned;
@@ -32,20 +42,116 @@ pub fn create_file_key(document_box: &str, name: &str, mime: &Mime, file_key: Uu
     format!("{document_box}/{file_key}")
 }
 
-pub fn create_generated_file_key(base_file_key: &str, mime: &Mime) -> String {
+/// Create a content addressed file key for a file with the provided content `hash`,
+/// files with identical contents within the same `document_box` share this key
+pub fn create_content_file_key(document_box: &str, hash: &str) -> String {
+    format!("{document_box}/content/{hash}")
+}
+
+/// Suffix identifying the type of a generated file within its file key and
+/// download file name, [None] for types that replace the original file (PDF)
+fn generated_type_suffix(ty: GeneratedFileType) -> Option<&'static str> {
+    match ty {
+        GeneratedFileType::Pdf => None,
+        GeneratedFileType::CoverPage => Some("cover"),
+        GeneratedFileType::SmallThumbnail => Some("thumbnail-small"),
+        GeneratedFileType::LargeThumbnail => Some("thumbnail-large"),
+        GeneratedFileType::TextContent => Some("text"),
+        GeneratedFileType::HtmlContent => Some("content"),
+        GeneratedFileType::Metadata => Some("metadata"),
+        GeneratedFileType::WordBoundingBoxes => Some("words"),
+        GeneratedFileType::Summary => Some("summary"),
+        GeneratedFileType::PagePreview => Some("page"),
+        GeneratedFileType::Tables => Some("tables"),
+        GeneratedFileType::TableCsv => Some("table"),
+    }
+}
+
+/// Full type suffix for a generated file including the page number
+/// for per page generated files
+fn generated_file_suffix(ty: GeneratedFileType, page: Option<i32>) -> Option<String> {
+    let suffix = generated_type_suffix(ty)?;
+    Some(match page {
+        Some(page) => format!("{suffix}-{page}"),
+        None => suffix.to_string(),
+    })
+}
+
+pub fn create_generated_file_key(
+    base_file_key: &str,
+    ty: GeneratedFileType,
+    page: Option<i32>,
+    mime: &Mime,
+) -> String {
     // Mapped file extensions for the generated type
     let file_ext = get_mime_ext(mime).unwrap_or("bin");
 
     // Generate a unique file key
     let file_key = Uuid::new_v4().to_string();
 
+    // Suffix identifying the generated type
+    let suffix = generated_file_suffix(ty, page)
+        .map(|suffix| make_s3_safe(&suffix))
+        .unwrap_or_else(|| "pdf".to_string());
+
     // Prefix the file key with the document box scope and a "generated" suffix
-    format!("{base_file_key}_{file_key}.generated.{file_ext}")
+    format!("{base_file_key}_{file_key}_{suffix}.generated.{file_ext}")
+}
+
+/// Create the download file name for a generated file derived from the
+/// `file_name` of the file it was generated from (i.e "report.docx" produces
+/// "report.pdf" for the PDF and "report-thumbnail-small.jpg" for a thumbnail)
+pub fn create_generated_file_name(
+    file_name: &str,
+    ty: GeneratedFileType,
+    page: Option<i32>,
+    mime: &Mime,
+) -> String {
+    let file_ext = get_mime_ext(mime).unwrap_or("bin");
+
+    // Get the file name with the file extension stripped
+    let file_stem = match get_file_name_ext(file_name) {
+        Some(ext) => file_name
+            .strip_suffix(&ext)
+            .and_then(|name| name.strip_suffix('.'))
+            .unwrap_or(file_name),
+        None => file_name,
+    };
+
+    match generated_file_suffix(ty, page) {
+        Some(suffix) => format!("{file_stem}-{suffix}.{file_ext}"),
+        None => format!("{file_stem}.{file_ext}"),
+    }
+}
+
+/// Create a Content-Disposition header value for a file with the provided `file_name`
+///
+/// Includes an ASCII only `filename` fallback for older clients along with the
+/// UTF-8 encoded `filename*` that preserves the full name
+pub fn create_content_disposition(attachment: bool, file_name: &str) -> String {
+    let ty = if attachment { "attachment" } else { "inline" };
+
+    let fallback: String = file_name
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' => '_',
+            c if c.is_ascii_graphic() || c == ' ' => c,
+            _ => '_',
+        })
+        .collect();
+
+    let encoded = urlencoding::encode(file_name);
+
+    format!("{ty}; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
 }
 
 #[cfg(test)]
 mod test {
-    use crate::files::create_file_key;
+    use crate::files::{
+        create_content_disposition, create_file_key, create_generated_file_key,
+        create_generated_file_name,
+    };
+    use docbox_database::models::generated_file::GeneratedFileType;
     use mime::Mime;
     use uuid::Uuid;
 
@@ -78,4 +184,63 @@ mod test {
 
         assert_eq!(key, format!("scope/{file_key}_some_filename.txt"));
     }
+
+    #[test]
+    fn test_create_generated_file_key() {
+        let key = create_generated_file_key(
+            "scope/a_report.docx",
+            GeneratedFileType::Pdf,
+            None,
+            &mime::APPLICATION_PDF,
+        );
+        assert!(key.starts_with("scope/a_report.docx_"));
+        assert!(key.ends_with("_pdf.generated.pdf"));
+
+        let key = create_generated_file_key(
+            "scope/a_report.docx",
+            GeneratedFileType::PagePreview,
+            Some(2),
+            &mime::IMAGE_JPEG,
+        );
+        assert!(key.ends_with("_page_2.generated.jpg"));
+    }
+
+    #[test]
+    fn test_create_generated_file_name() {
+        let name = create_generated_file_name(
+            "report.docx",
+            GeneratedFileType::Pdf,
+            None,
+            &mime::APPLICATION_PDF,
+        );
+        assert_eq!(name, "report.pdf");
+
+        let name = create_generated_file_name(
+            "report.docx",
+            GeneratedFileType::SmallThumbnail,
+            None,
+            &mime::IMAGE_JPEG,
+        );
+        assert_eq!(name, "report-thumbnail-small.jpg");
+
+        let name = create_generated_file_name(
+            "quarterly report",
+            GeneratedFileType::TableCsv,
+            Some(3),
+            &mime::TEXT_CSV,
+        );
+        assert_eq!(name, "quarterly report-table-3.csv");
+    }
+
+    #[test]
+    fn test_create_content_disposition() {
+        assert_eq!(
+            create_content_disposition(false, "report.pdf"),
+            "inline; filename=\"report.pdf\"; filename*=UTF-8''report.pdf"
+        );
+        assert_eq!(
+            create_content_disposition(true, "rapport \"été\".pdf"),
+            "attachment; filename=\"rapport __t__.pdf\"; filename*=UTF-8''rapport%20%22%C3%A9t%C3%A9%22.pdf"
+        );
+    }
 }
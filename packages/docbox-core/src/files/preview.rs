@@ -0,0 +1,343 @@
+//! # File Preview
+//!
+//! Produces a sanitized HTML rendering of a file for quick in-browser
+//! previews of documents, spreadsheets and emails without requiring a
+//! client side PDF viewer.
+//!
+//! The preview is rendered from the first available source:
+//! - HTML content extracted from the file (i.e emails)
+//! - Text content extracted from the file or its PDF rendition
+//! - The file contents itself for plain text and SVG files
+//!
+//! HTML and SVG content can contain scripts, all content is sanitized to
+//! remove active content before it is included in the preview
+
+use docbox_database::{
+    DbErr, DbPool,
+    models::{
+        document_box::DocumentBoxScopeRaw,
+        file::File,
+        generated_file::{GeneratedFile, GeneratedFileType},
+    },
+};
+use docbox_processing::pdf::split_pdf_text_pages;
+use docbox_storage::{StorageLayer, StorageLayerError};
+use mime::Mime;
+use std::{collections::HashSet, str::FromStr};
+use thiserror::Error;
+
+/// Maximum size in bytes of content that will be rendered as a preview
+pub const MAX_PREVIEW_CONTENT_SIZE: usize = 10 * 1024 * 1024;
+
+/// Styles included in the rendered preview document
+const PREVIEW_STYLES: &str = "body{margin:0;padding:1rem;font-family:sans-serif;}\
+    .page{margin-bottom:1rem;padding-bottom:1rem;border-bottom:1px solid #ddd;}\
+    pre{margin:0;white-space:pre-wrap;word-wrap:break-word;}\
+    img{max-width:100%;}\
+    table{border-collapse:collapse;}\
+    td,th{border:1px solid #ddd;padding:0.25rem;}";
+
+/// Mime type for SVG images
+const SVG_MIME: &str = "image/svg+xml";
+
+/// SVG elements allowed in previews, excludes elements that can run
+/// scripts, embed foreign content or reference external resources
+/// (i.e script, foreignObject, use, image, animate)
+const SVG_TAGS: &[&str] = &[
+    "svg",
+    "g",
+    "defs",
+    "title",
+    "desc",
+    "path",
+    "rect",
+    "circle",
+    "ellipse",
+    "line",
+    "polyline",
+    "polygon",
+    "text",
+    "tspan",
+    "linearGradient",
+    "radialGradient",
+    "stop",
+    "clipPath",
+    "mask",
+    "pattern",
+    "symbol",
+    "marker",
+];
+
+/// SVG attributes allowed in previews, event handler, link and style
+/// attributes are excluded
+const SVG_ATTRIBUTES: &[&str] = &[
+    "id",
+    "class",
+    "width",
+    "height",
+    "viewBox",
+    "preserveAspectRatio",
+    "transform",
+    "d",
+    "x",
+    "y",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "fx",
+    "fy",
+    "points",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "stroke",
+    "stroke-width",
+    "stroke-opacity",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-miterlimit",
+    "opacity",
+    "offset",
+    "stop-color",
+    "stop-opacity",
+    "gradientUnits",
+    "gradientTransform",
+    "patternUnits",
+    "patternTransform",
+    "clip-path",
+    "clip-rule",
+    "mask",
+    "marker-start",
+    "marker-mid",
+    "marker-end",
+    "markerWidth",
+    "markerHeight",
+    "refX",
+    "refY",
+    "orient",
+    "font-family",
+    "font-size",
+    "font-weight",
+    "text-anchor",
+    "dominant-baseline",
+    "dx",
+    "dy",
+];
+
+#[derive(Debug, Error)]
+pub enum FilePreviewError {
+    /// Database error
+    #[error(transparent)]
+    Database(#[from] DbErr),
+
+    /// Failed to load the preview content from storage
+    #[error(transparent)]
+    Storage(#[from] StorageLayerError),
+
+    /// Preview content is larger than [MAX_PREVIEW_CONTENT_SIZE]
+    #[error("file content is too large to preview")]
+    TooLarge,
+}
+
+/// Create a sanitized HTML preview document for the provided `file`,
+/// returns [None] when the file has no content that can be previewed
+#[tracing::instrument(skip_all, fields(%scope, file_id = %file.id))]
+pub async fn create_file_preview(
+    db: &DbPool,
+    storage: &StorageLayer,
+    scope: &DocumentBoxScopeRaw,
+    file: &File,
+) -> Result<Option<String>, FilePreviewError> {
+    // Prefer the extracted HTML content (i.e emails)
+    if let Some(html) =
+        load_generated_content(db, storage, scope, file, GeneratedFileType::HtmlContent).await?
+    {
+        return Ok(Some(create_preview_document(
+            &file.name,
+            &sanitize_html(&html),
+        )));
+    }
+
+    // Fallback to the extracted text content
+    if let Some(text) =
+        load_generated_content(db, storage, scope, file, GeneratedFileType::TextContent).await?
+    {
+        return Ok(Some(create_preview_document(
+            &file.name,
+            &render_text_pages(&text),
+        )));
+    }
+
+    // Fallback to the file itself for text files
+    let Ok(mime) = Mime::from_str(&file.mime) else {
+        return Ok(None);
+    };
+
+    let is_svg = mime.essence_str() == SVG_MIME;
+
+    if (mime.type_() != mime::TEXT && !is_svg) || file.encrypted {
+        return Ok(None);
+    }
+
+    let Some(content) = load_storage_content(storage, &file.file_key).await? else {
+        return Ok(None);
+    };
+
+    let body = if is_svg {
+        sanitize_svg(&content)
+    } else if mime.subtype() == mime::HTML {
+        sanitize_html(&content)
+    } else {
+        render_text_pages(&content)
+    };
+
+    Ok(Some(create_preview_document(&file.name, &body)))
+}
+
+/// Load the text content of the generated file of type `ty` for the `file`
+async fn load_generated_content(
+    db: &DbPool,
+    storage: &StorageLayer,
+    scope: &DocumentBoxScopeRaw,
+    file: &File,
+    ty: GeneratedFileType,
+) -> Result<Option<String>, FilePreviewError> {
+    let generated = GeneratedFile::find(db, scope, file.id, ty)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query generated file"))?;
+
+    let Some(generated) = generated else {
+        return Ok(None);
+    };
+
+    load_storage_content(storage, &generated.file_key).await
+}
+
+/// Load the contents of the file at `file_key` from storage as text,
+/// content that is not valid UTF-8 cannot be previewed
+async fn load_storage_content(
+    storage: &StorageLayer,
+    file_key: &str,
+) -> Result<Option<String>, FilePreviewError> {
+    let bytes = storage
+        .get_file(file_key)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to get preview content"))?
+        .collect_bytes_max(MAX_PREVIEW_CONTENT_SIZE)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to collect preview content"))?
+        .ok_or(FilePreviewError::TooLarge)?;
+
+    match String::from_utf8(bytes.to_vec()) {
+        Ok(value) => Ok(Some(value)),
+        Err(error) => {
+            tracing::warn!(?error, "preview content is not valid utf8");
+            Ok(None)
+        }
+    }
+}
+
+/// Sanitize untrusted `html` removing scripts, styles, event handlers and
+/// any URL schemes other than web links and inline data
+fn sanitize_html(html: &str) -> String {
+    ammonia::Builder::default()
+        .url_schemes(HashSet::from(["http", "https", "mailto", "data"]))
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .clean(html)
+        .to_string()
+}
+
+/// Sanitize an untrusted `svg` document removing scripts, event handlers,
+/// links and any elements or attributes not in the allowed set
+fn sanitize_svg(svg: &str) -> String {
+    ammonia::Builder::empty()
+        .add_tags(SVG_TAGS)
+        .add_generic_attributes(SVG_ATTRIBUTES)
+        .clean(svg)
+        .to_string()
+}
+
+/// Render plain `text` content as escaped HTML, the text is split into
+/// sections using the page separators from the PDF text extraction
+fn render_text_pages(text: &str) -> String {
+    split_pdf_text_pages(text)
+        .filter(|page| !page.trim().is_empty())
+        .map(|page| {
+            format!(
+                r#"<section class="page"><pre>{}</pre></section>"#,
+                ammonia::clean_text(page)
+            )
+        })
+        .collect()
+}
+
+/// Wrap the sanitized `body` in a complete HTML document
+fn create_preview_document(name: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html><html><head><meta charset="utf-8"><title>{}</title><style>{PREVIEW_STYLES}</style></head><body>{body}</body></html>"#,
+        ammonia::clean_text(name)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{create_preview_document, render_text_pages, sanitize_html, sanitize_svg};
+
+    #[test]
+    fn test_sanitize_html_removes_scripts() {
+        let html = r#"<p onclick="alert(1)">Hello</p><script>alert(1)</script>"#;
+        assert_eq!(sanitize_html(html), "<p>Hello</p>");
+    }
+
+    #[test]
+    fn test_sanitize_html_removes_javascript_links() {
+        let html = r#"<a href="javascript:alert(1)">Link</a>"#;
+        assert_eq!(
+            sanitize_html(html),
+            r#"<a rel="noopener noreferrer nofollow">Link</a>"#
+        );
+    }
+
+    #[test]
+    fn test_sanitize_html_keeps_data_images() {
+        let html = r#"<img src="data:image/png;base64,AAAA">"#;
+        assert_eq!(sanitize_html(html), html);
+    }
+
+    #[test]
+    fn test_sanitize_svg_removes_active_content() {
+        let svg = r#"<svg viewBox="0 0 10 10" onload="alert(1)"><script>alert(1)</script><a href="javascript:alert(1)"><rect width="10" height="10" fill="red"/></a><foreignObject><iframe src="https://example.com"></iframe></foreignObject></svg>"#;
+        assert_eq!(
+            sanitize_svg(svg),
+            r#"<svg viewBox="0 0 10 10"><rect width="10" height="10" fill="red"></rect></svg>"#
+        );
+    }
+
+    #[test]
+    fn test_render_text_pages_escapes_html() {
+        assert_eq!(
+            render_text_pages("<b>Hello</b>"),
+            r#"<section class="page"><pre>&lt;b&gt;Hello&lt;&#47;b&gt;</pre></section>"#
+        );
+    }
+
+    #[test]
+    fn test_render_text_pages_splits_pages() {
+        let rendered = render_text_pages("Page 1\u{000C}Page 2\u{000C}");
+        assert_eq!(rendered.matches(r#"<section class="page">"#).count(), 2);
+    }
+
+    #[test]
+    fn test_create_preview_document_escapes_name() {
+        let document = create_preview_document("<script>.txt", "");
+        assert!(!document.contains("<script>"));
+    }
+}
@@ -0,0 +1,157 @@
+//! # Regenerate Generated File
+//!
+//! Generated files may be missing for a file when they were not retained
+//! by the tenant generated file policies at upload time. This allows a
+//! specific generated file type to be recreated on demand by processing
+//! the original file again.
+
+use crate::{
+    files::generated::{make_create_generated_files, upload_generated_files},
+    utils::timing::handle_slow_future,
+};
+use docbox_database::{
+    DbErr, DbPool,
+    models::{
+        document_box::DocumentBoxScopeRawRef,
+        file::File,
+        generated_file::{GeneratedFile, GeneratedFileType},
+    },
+};
+use docbox_processing::{DEFAULT_PROCESS_TIMEOUT, ProcessingError, ProcessingLayer, process_file};
+use docbox_storage::{StorageLayer, StorageLayerError};
+use mime::Mime;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::time::timeout;
+
+#[derive(Debug, Error)]
+pub enum RegenerateGeneratedFileError {
+    /// File has a mime type that cannot be parsed
+    #[error("file has an invalid mime type")]
+    InvalidMime,
+
+    /// Failed to load the file from storage
+    #[error("failed to load file from storage: {0}")]
+    GetFile(StorageLayerError),
+
+    /// Failed to process the file
+    #[error("failed to process file: {0}")]
+    Processing(#[from] ProcessingError),
+
+    /// Processing took too long
+    #[error("timeout occurred while processing file")]
+    ProcessTimeout,
+
+    /// Failed to upload the generated file to storage
+    #[error("failed to upload generated file to storage: {0}")]
+    UploadGeneratedFile(StorageLayerError),
+
+    /// Failed to query the existing generated file
+    #[error("failed to query generated file")]
+    QueryGeneratedFile(DbErr),
+
+    /// Failed to create the generated file database row
+    #[error("failed to create generated file")]
+    CreateGeneratedFile(DbErr),
+}
+
+/// Regenerate the generated file of type `ty` for the provided `file`
+///
+/// Returns [None] if processing the file did not produce a generated
+/// file of the requested type
+pub async fn regenerate_generated_file(
+    db: &DbPool,
+    storage: &StorageLayer,
+    processing: &ProcessingLayer,
+    scope: DocumentBoxScopeRawRef<'_>,
+    file: &File,
+    ty: GeneratedFileType,
+) -> Result<Option<GeneratedFile>, RegenerateGeneratedFileError> {
+    let mime: Mime = file
+        .mime
+        .parse()
+        .map_err(|_| RegenerateGeneratedFileError::InvalidMime)?;
+
+    let bytes = storage
+        .get_file(&file.file_key)
+        .await
+        .map_err(RegenerateGeneratedFileError::GetFile)?
+        .collect_bytes()
+        .await
+        .map_err(RegenerateGeneratedFileError::GetFile)?;
+
+    let process_timeout = processing
+        .config
+        .process_timeout
+        .unwrap_or(DEFAULT_PROCESS_TIMEOUT);
+
+    let process_future = timeout(
+        process_timeout,
+        process_file(&None, processing, bytes, &mime),
+    );
+
+    // Apply a slow future warning to the processing future
+    let processing_output = handle_slow_future(
+        process_future,
+        Duration::from_secs(25),
+        || tracing::warn!(file_id = %file.id, "file regeneration has taken over 25s to complete"),
+    )
+    .await
+    .map_err(|_| RegenerateGeneratedFileError::ProcessTimeout)??;
+
+    // Find the requested generated file within the output
+    let upload = match processing_output.and_then(|output| {
+        output
+            .upload_queue
+            .into_iter()
+            .find(|upload| upload.ty == ty)
+    }) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    let prepared = make_create_generated_files(&file.file_key, &file.id, &file.hash, vec![upload]);
+
+    let mut created = None;
+
+    for result in upload_generated_files(storage, scope, prepared).await {
+        let create = result.map_err(RegenerateGeneratedFileError::UploadGeneratedFile)?;
+        let file_key = create.file_key.clone();
+
+        let generated_file = match GeneratedFile::create_if_missing(db, create).await {
+            Ok(Some(value)) => value,
+
+            // Generated file was created by a concurrent request while processing
+            Ok(None) => {
+                tracing::debug!("generated file was created concurrently");
+
+                // Remove the now orphaned file from storage
+                if let Err(error) = storage.delete_file(&file_key).await {
+                    tracing::error!(?error, "failed to remove duplicate regenerated file");
+                }
+
+                let existing = GeneratedFile::find(db, &scope.to_string(), file.id, ty)
+                    .await
+                    .map_err(RegenerateGeneratedFileError::QueryGeneratedFile)?;
+
+                created = existing;
+                continue;
+            }
+
+            Err(error) => {
+                tracing::error!(?error, "failed to create regenerated file");
+
+                // Remove the now orphaned file from storage
+                if let Err(error) = storage.delete_file(&file_key).await {
+                    tracing::error!(?error, "failed to rollback regenerated file upload");
+                }
+
+                return Err(RegenerateGeneratedFileError::CreateGeneratedFile(error));
+            }
+        };
+
+        created = Some(generated_file);
+    }
+
+    Ok(created)
+}
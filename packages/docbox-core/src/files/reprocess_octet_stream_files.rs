@@ -7,30 +7,46 @@
 //! This migration takes all of the files matching that mime type and attempts
 //! to infer the file mime type based on its extension and perform the processing
 //! step to generate its processed variants and update the file mime type
+//!
+//! For reprocessing files after improvements to the processing pipeline see
+//! [reprocess_outdated_files](crate::files::reprocess_outdated_files) instead
 
 use crate::{
     files::{
+        generated::apply_generated_file_policies,
         index_file::store_file_index,
+        mime_overrides::get_mime_overrides,
         upload_file::{UploadFileError, store_generated_files},
     },
-    utils::{file::get_file_name_ext, timing::handle_slow_future},
+    utils::{rollback::Rollback, timing::handle_slow_future},
 };
+use chrono::Utc;
 use docbox_database::{
     DbPool, DbResult,
     models::{
         file::{CreateFile, FileWithScope},
+        file_pdf_metadata::{FilePdfMetadata, PdfMetadata},
+        file_processing::FileProcessing,
+        file_text_stats::{FileTextStats, TextStats},
         generated_file::{CreateGeneratedFile, GeneratedFile},
+        generated_file_policy::GeneratedFilePolicy,
     },
 };
 use docbox_processing::{
-    DEFAULT_PROCESS_TIMEOUT, ProcessingError, ProcessingIndexMetadata, ProcessingLayer,
-    process_file,
+    DEFAULT_PROCESS_TIMEOUT, PROCESSING_PIPELINE_VERSION, ProcessingError, ProcessingIndexMetadata,
+    ProcessingLayer, elapsed_ms, process_file,
 };
 use docbox_search::TenantSearchIndex;
-use docbox_storage::{StorageLayer, StorageLayerError};
+use docbox_storage::{
+    DEFAULT_PARALLEL_CHUNK_SIZE, DEFAULT_PARALLEL_CONCURRENCY, StorageLayer, StorageLayerError,
+};
 use futures::{StreamExt, future::BoxFuture};
 use mime::Mime;
-use std::{ops::DerefMut, time::Duration};
+use std::{
+    ops::DerefMut,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use thiserror::Error;
 use tokio::time::timeout;
 use tracing::Instrument;
@@ -43,15 +59,17 @@ pub async fn reprocess_octet_stream_files(
 ) -> DbResult<()> {
     _ = search.create_index().await;
 
+    let mime_overrides = get_mime_overrides(db).await?;
+    let generated_file_policies: Arc<[GeneratedFilePolicy]> = GeneratedFilePolicy::all(db)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query generated file policies"))?
+        .into();
     let files = get_files(db).await?;
     let mut skipped = Vec::new();
     let mut processing_files = Vec::new();
 
     for file in files {
-        let guessed_mime = get_file_name_ext(&file.file.name).and_then(|ext| {
-            let guesses = mime_guess::from_ext(&ext);
-            guesses.first()
-        });
+        let guessed_mime = mime_overrides.get_file_name_mime(&file.file.name);
 
         if let Some(mime) = guessed_mime {
             processing_files.push((file, mime));
@@ -69,13 +87,22 @@ pub async fn reprocess_octet_stream_files(
             let search = search.clone();
             let storage = storage.clone();
             let processing = processing.clone();
+            let generated_file_policies = generated_file_policies.clone();
             let span = span.clone();
 
             Box::pin(
                 async move {
                     tracing::debug!(?file, "stating file");
-                    if let Err(error) =
-                        perform_process_file(db, storage, search, processing, file, mime).await
+                    if let Err(error) = perform_process_file(
+                        db,
+                        storage,
+                        search,
+                        processing,
+                        &generated_file_policies,
+                        file,
+                        mime,
+                    )
+                    .await
                     {
                         tracing::error!(?error, "failed to migrate file");
                     };
@@ -156,6 +183,15 @@ pub enum ProcessFileError {
     #[error("failed to update file mime")]
     SetMime,
 
+    #[error("failed to update file processing version")]
+    SetProcessingVersion,
+
+    #[error("failed to store pdf metadata")]
+    SetPdfMetadata,
+
+    #[error("failed to store text stats")]
+    SetTextStats,
+
     #[error("timeout occurred while processing file")]
     ConvertTimeout,
 }
@@ -166,17 +202,23 @@ pub async fn perform_process_file(
     storage: StorageLayer,
     search: TenantSearchIndex,
     processing: ProcessingLayer,
+    generated_file_policies: &[GeneratedFilePolicy],
     mut file: FileWithScope,
     mime: Mime,
 ) -> Result<(), ProcessFileError> {
     let bytes = storage
-        .get_file(&file.file.file_key)
+        .get_file_parallel(
+            &file.file.file_key,
+            DEFAULT_PARALLEL_CHUNK_SIZE,
+            DEFAULT_PARALLEL_CONCURRENCY,
+        )
         .await
         .inspect_err(|error| tracing::error!(?error, "Failed to get storage file"))?
         .collect_bytes()
         .await
         .inspect_err(|error| tracing::error!(?error, "Failed to get storage file"))?;
 
+    let processing_start = Instant::now();
     let process_future = process_file(&None, &processing, bytes, &mime);
 
     let process_timeout = processing
@@ -197,6 +239,8 @@ pub async fn perform_process_file(
     .await
     .map_err(|_| ProcessFileError::ConvertTimeout)??;
 
+    let processing_ms = elapsed_ms(processing_start);
+
     let mut index_metadata: Option<ProcessingIndexMetadata> = None;
 
     let file_in = &file.file;
@@ -216,6 +260,8 @@ pub async fn perform_process_file(
     };
 
     let mut generated_files: Option<Vec<CreateGeneratedFile>> = None;
+    let mut pdf_metadata: Option<PdfMetadata> = None;
+    let mut text_stats: Option<TextStats> = None;
 
     // Get file encryption state
     let encrypted = processing_output
@@ -223,17 +269,34 @@ pub async fn perform_process_file(
         .map(|output| output.encrypted)
         .unwrap_or_default();
 
+    let mut timings = processing_output
+        .as_ref()
+        .map(|output| output.timings)
+        .unwrap_or_default();
+
     if let Some(processing_output) = processing_output {
         index_metadata = processing_output.index_metadata;
+        pdf_metadata = processing_output.pdf_metadata;
+        text_stats = index_metadata
+            .as_ref()
+            .and_then(|metadata| metadata.text_stats);
 
-        let mut s3_upload_keys = Vec::new();
+        let mut rollback = Rollback::default();
+
+        // Only keep the generated files retained by the tenant policies
+        let upload_queue = apply_generated_file_policies(
+            generated_file_policies,
+            &mime,
+            processing_output.upload_queue,
+        );
 
         tracing::debug!("uploading generated files");
         let prepared_files = store_generated_files(
             &storage,
+            &file.scope,
             &created_file,
-            &mut s3_upload_keys,
-            processing_output.upload_queue,
+            &mut rollback,
+            upload_queue,
         )
         .await?;
         generated_files = Some(prepared_files);
@@ -241,8 +304,13 @@ pub async fn perform_process_file(
 
     // Index the file in the search index
     tracing::debug!("indexing file contents");
+    let indexing_start = Instant::now();
     store_file_index(&search, &created_file, &file.scope, index_metadata).await?;
 
+    let indexing_ms = elapsed_ms(indexing_start);
+    timings.indexing_ms = Some(indexing_ms);
+    timings.total_ms = Some(processing_ms + indexing_ms);
+
     // Start a database transaction
     let mut db = db.begin().await.map_err(|error| {
         tracing::error!(?error, "failed to begin transaction");
@@ -258,6 +326,26 @@ pub async fn perform_process_file(
         }
     }
 
+    // Store the extracted PDF metadata
+    if let Some(pdf_metadata) = pdf_metadata.as_ref() {
+        FilePdfMetadata::set(db.deref_mut(), file.file.id, pdf_metadata)
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to store pdf metadata");
+                ProcessFileError::SetPdfMetadata
+            })?;
+    }
+
+    // Store the text stats
+    if let Some(text_stats) = text_stats.as_ref() {
+        FileTextStats::set(db.deref_mut(), file.file.id, text_stats)
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to store text stats");
+                ProcessFileError::SetTextStats
+            })?;
+    }
+
     if encrypted {
         // Mark the file as encrypted
         tracing::debug!("marking file as encrypted");
@@ -282,6 +370,20 @@ pub async fn perform_process_file(
             ProcessFileError::SetMime
         })?;
 
+    // Stamp the processing pipeline version used for the file
+    FileProcessing::set(
+        db.deref_mut(),
+        file.file.id,
+        PROCESSING_PIPELINE_VERSION,
+        Utc::now(),
+        &timings,
+    )
+    .await
+    .map_err(|error| {
+        tracing::error!(?error, "failed to set file processing version");
+        ProcessFileError::SetProcessingVersion
+    })?;
+
     db.commit().await.map_err(|error| {
         tracing::error!(?error, "failed to commit transaction");
         ProcessFileError::CommitTransaction
@@ -0,0 +1,431 @@
+//! # Reprocess Outdated Files
+//!
+//! Files are stamped with the [PROCESSING_PIPELINE_VERSION] used when they were
+//! processed. When the processing pipeline is improved (i.e better OCR or thumbnail
+//! generation) the version is bumped and this operation is used to reprocess only
+//! the files that were processed by an older version of the pipeline.
+//!
+//! Reprocessing replaces the generated files (including extracted tables), PDF metadata, PII analysis and search index data for the file,
+//! additional files produced by processing (i.e email attachments) are not
+//! recreated as they already exist as their own files
+
+use crate::{
+    files::{
+        generated::{
+            GeneratedFileDeleteResult, apply_generated_file_policies, delete_generated_files,
+        },
+        index_file::store_file_index,
+        upload_file::{UploadFileError, resolve_processing_config, store_generated_files},
+    },
+    utils::{rollback::Rollback, timing::handle_slow_future},
+};
+use chrono::Utc;
+use docbox_database::{
+    DbErr, DbPool, DbResult,
+    models::{
+        extraction_cache::ExtractionCacheEntry,
+        file::{CreateFile, FileWithScope},
+        file_pdf_metadata::{FilePdfMetadata, PdfMetadata},
+        file_pii_analysis::{FilePiiAnalysis, PiiAnalysis},
+        file_processing::FileProcessing,
+        file_text_stats::{FileTextStats, TextStats},
+        generated_file::{GeneratedFile, GeneratedFileType},
+        generated_file_policy::GeneratedFilePolicy,
+    },
+};
+use docbox_processing::{
+    DEFAULT_PROCESS_TIMEOUT, PROCESSING_PIPELINE_VERSION, ProcessingConfig, ProcessingError,
+    ProcessingIndexMetadata, ProcessingLayer, elapsed_ms, process_file,
+};
+use docbox_search::{SearchError, TenantSearchIndex};
+use docbox_storage::{
+    DEFAULT_PARALLEL_CHUNK_SIZE, DEFAULT_PARALLEL_CONCURRENCY, StorageLayer, StorageLayerError,
+};
+use futures::{StreamExt, future::BoxFuture};
+use mime::Mime;
+use serde::Serialize;
+use std::{
+    ops::DerefMut,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tokio::time::timeout;
+use tracing::Instrument;
+use utoipa::ToSchema;
+
+/// Size of each page to request from the database
+const DATABASE_PAGE_SIZE: u64 = 1000;
+/// Number of files to process in parallel
+const FILE_PROCESS_SIZE: usize = 50;
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ReprocessOutdatedFilesOutcome {
+    /// Number of files that were reprocessed
+    pub reprocessed: usize,
+    /// Number of files that failed to reprocess
+    pub failed: usize,
+}
+
+#[derive(Debug, Error)]
+pub enum ReprocessFileError {
+    #[error("file has an invalid mime type")]
+    InvalidMime,
+
+    #[error(transparent)]
+    Database(#[from] DbErr),
+
+    #[error(transparent)]
+    Storage(#[from] StorageLayerError),
+
+    #[error(transparent)]
+    Process(#[from] ProcessingError),
+
+    #[error(transparent)]
+    UploadFile(#[from] UploadFileError),
+
+    #[error("failed to clear existing search index data: {0}")]
+    ClearIndex(SearchError),
+
+    #[error("failed to remove previous generated files: {0}")]
+    DeleteGeneratedFiles(StorageLayerError),
+
+    #[error("timeout occurred while processing file")]
+    ProcessTimeout,
+}
+
+/// Reprocess all files within the tenant that were processed using a processing
+/// pipeline version older than the current [PROCESSING_PIPELINE_VERSION]
+pub async fn reprocess_outdated_files(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    storage: &StorageLayer,
+    processing: &ProcessingLayer,
+) -> DbResult<ReprocessOutdatedFilesOutcome> {
+    let generated_file_policies: Arc<[GeneratedFilePolicy]> = GeneratedFilePolicy::all(db)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query generated file policies"))?
+        .into();
+
+    let files = get_outdated_files(db).await?;
+    let span = tracing::Span::current();
+
+    tracing::debug!(total = files.len(), "reprocessing outdated files");
+
+    let results = futures::stream::iter(files)
+        .map(|file| -> BoxFuture<'static, bool> {
+            let db = db.clone();
+            let search = search.clone();
+            let storage = storage.clone();
+            let processing = processing.clone();
+            let generated_file_policies = generated_file_policies.clone();
+            let span = span.clone();
+
+            Box::pin(
+                async move {
+                    let file_id = file.file.id;
+                    match reprocess_file(
+                        &db,
+                        &storage,
+                        &search,
+                        &processing,
+                        &generated_file_policies,
+                        file,
+                    )
+                    .await
+                    {
+                        Ok(()) => true,
+                        Err(error) => {
+                            tracing::error!(?error, %file_id, "failed to reprocess file");
+                            false
+                        }
+                    }
+                }
+                .instrument(span),
+            )
+        })
+        .buffered(FILE_PROCESS_SIZE)
+        .collect::<Vec<bool>>()
+        .await;
+
+    let reprocessed = results.iter().filter(|success| **success).count();
+
+    Ok(ReprocessOutdatedFilesOutcome {
+        reprocessed,
+        failed: results.len() - reprocessed,
+    })
+}
+
+/// Collect all files processed with an outdated pipeline version
+///
+/// (All files are collected upfront as reprocessing changes which files are outdated)
+async fn get_outdated_files(db: &DbPool) -> DbResult<Vec<FileWithScope>> {
+    let mut page_index = 0;
+    let mut data = Vec::new();
+
+    loop {
+        let mut files = FileProcessing::find_outdated_files(
+            db,
+            PROCESSING_PIPELINE_VERSION,
+            page_index * DATABASE_PAGE_SIZE,
+            DATABASE_PAGE_SIZE,
+        )
+        .await
+        .inspect_err(|error| {
+            tracing::error!(?error, ?page_index, "failed to load outdated files page")
+        })?;
+
+        let is_end = (files.len() as u64) < DATABASE_PAGE_SIZE;
+
+        data.append(&mut files);
+
+        if is_end {
+            break;
+        }
+
+        page_index += 1;
+    }
+
+    Ok(data)
+}
+
+/// Reprocess a single file replacing its generated files and search index data,
+/// only the generated files retained by the `generated_file_policies` are stored
+pub async fn reprocess_file(
+    db: &DbPool,
+    storage: &StorageLayer,
+    search: &TenantSearchIndex,
+    processing: &ProcessingLayer,
+    generated_file_policies: &[GeneratedFilePolicy],
+    file: FileWithScope,
+) -> Result<(), ReprocessFileError> {
+    let mut rollback = Rollback::default();
+
+    let previous_generated = match reprocess_file_inner(
+        db,
+        storage,
+        search,
+        processing,
+        generated_file_policies,
+        file,
+        &mut rollback,
+    )
+    .await
+    {
+        Ok(value) => value,
+        Err(error) => {
+            // Remove the generated files uploaded before the failure
+            rollback.run_background(search.clone(), storage.clone());
+            return Err(error);
+        }
+    };
+
+    // Remove the previous generated files from storage
+    if let GeneratedFileDeleteResult::Err(_, error) =
+        delete_generated_files(storage, &previous_generated).await
+    {
+        return Err(ReprocessFileError::DeleteGeneratedFiles(error));
+    }
+
+    Ok(())
+}
+
+/// Processes the file and persists the replacement data, provides the
+/// previous generated files that were replaced
+///
+/// Generated files uploaded to storage are recorded in the `rollback`
+/// so they can be removed if the file fails to reprocess
+async fn reprocess_file_inner(
+    db: &DbPool,
+    storage: &StorageLayer,
+    search: &TenantSearchIndex,
+    processing: &ProcessingLayer,
+    generated_file_policies: &[GeneratedFilePolicy],
+    file: FileWithScope,
+    rollback: &mut Rollback,
+) -> Result<Vec<GeneratedFile>, ReprocessFileError> {
+    let FileWithScope { file, scope } = file;
+
+    let mime: Mime = file
+        .mime
+        .parse()
+        .map_err(|_| ReprocessFileError::InvalidMime)?;
+
+    let bytes = storage
+        .get_file_parallel(
+            &file.file_key,
+            DEFAULT_PARALLEL_CHUNK_SIZE,
+            DEFAULT_PARALLEL_CONCURRENCY,
+        )
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to get storage file"))?
+        .collect_bytes()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to get storage file"))?;
+
+    let process_timeout = processing
+        .config
+        .process_timeout
+        .unwrap_or(DEFAULT_PROCESS_TIMEOUT);
+
+    // Files previously analyzed for PII are analyzed again with the new output
+    let detect_pii = FilePiiAnalysis::find(db, file.id).await?.is_some();
+
+    // Files that previously had tables extracted have them extracted again
+    let extract_tables = GeneratedFile::find(db, &scope, file.id, GeneratedFileType::Tables)
+        .await?
+        .is_some();
+
+    let reprocess_config = (detect_pii || extract_tables).then(|| ProcessingConfig {
+        detect_pii: detect_pii.then_some(true),
+        extract_tables: extract_tables.then_some(true),
+        ..Default::default()
+    });
+
+    // Processing configs of the parent folders apply the same as they did on upload
+    let processing_config = resolve_processing_config(db, file.folder_id, reprocess_config).await?;
+
+    let processing_start = Instant::now();
+    let process_future = timeout(
+        process_timeout,
+        process_file(&processing_config, processing, bytes, &mime),
+    );
+
+    // Apply a slow future warning to the processing future
+    let processing_output = handle_slow_future(
+        process_future,
+        Duration::from_secs(25),
+        || tracing::warn!(file_id = %file.id, "file reprocessing has taken over 25s to complete"),
+    )
+    .await
+    .map_err(|_| ReprocessFileError::ProcessTimeout)??;
+
+    let processing_ms = elapsed_ms(processing_start);
+
+    let created_file = CreateFile {
+        id: file.id,
+        parent_id: file.parent_id,
+        name: file.name.clone(),
+        mime: file.mime.clone(),
+        file_key: file.file_key.clone(),
+        folder_id: file.folder_id,
+        hash: file.hash.clone(),
+        size: file.size,
+        created_by: file.created_by.clone(),
+        created_at: file.created_at,
+        encrypted: file.encrypted,
+    };
+
+    let encrypted = processing_output
+        .as_ref()
+        .map(|output| output.encrypted)
+        .unwrap_or_default();
+
+    let mut timings = processing_output
+        .as_ref()
+        .map(|output| output.timings)
+        .unwrap_or_default();
+
+    let mut index_metadata: Option<ProcessingIndexMetadata> = None;
+    let mut generated_files = Vec::new();
+    let mut pdf_metadata: Option<PdfMetadata> = None;
+    let mut pii_analysis: Option<PiiAnalysis> = None;
+    let mut text_stats: Option<TextStats> = None;
+
+    if let Some(processing_output) = processing_output {
+        index_metadata = processing_output.index_metadata;
+        text_stats = index_metadata
+            .as_ref()
+            .and_then(|metadata| metadata.text_stats);
+        pdf_metadata = processing_output.pdf_metadata;
+        pii_analysis = processing_output.pii_analysis;
+
+        // Only keep the generated files retained by the tenant policies
+        let upload_queue = apply_generated_file_policies(
+            generated_file_policies,
+            &mime,
+            processing_output.upload_queue,
+        );
+
+        tracing::debug!("uploading generated files");
+        generated_files =
+            store_generated_files(storage, &scope, &created_file, rollback, upload_queue).await?;
+    }
+
+    // Replace the indexed file contents
+    search
+        .delete_data(file.id)
+        .await
+        .map_err(ReprocessFileError::ClearIndex)?;
+    let indexing_start = Instant::now();
+    store_file_index(search, &created_file, &scope, index_metadata).await?;
+
+    let indexing_ms = elapsed_ms(indexing_start);
+    timings.indexing_ms = Some(indexing_ms);
+    timings.total_ms = Some(processing_ms + indexing_ms);
+
+    let previous_generated = GeneratedFile::find_all(db, file.id).await?;
+
+    let mut t = db.begin().await?;
+
+    // Swap the previous generated files for the new ones
+    for generated in &previous_generated {
+        generated.clone().delete(t.deref_mut()).await?;
+    }
+
+    for create in generated_files {
+        GeneratedFile::create(t.deref_mut(), create)
+            .await
+            .map_err(UploadFileError::CreateGeneratedFile)?;
+    }
+
+    if encrypted != file.encrypted {
+        file.clone().set_encrypted(t.deref_mut(), encrypted).await?;
+    }
+
+    // Replace the previously extracted PDF metadata
+    match pdf_metadata.as_ref() {
+        Some(pdf_metadata) => {
+            FilePdfMetadata::set(t.deref_mut(), file.id, pdf_metadata).await?;
+        }
+        None => {
+            FilePdfMetadata::delete(t.deref_mut(), file.id).await?;
+        }
+    }
+
+    // Replace the previous PII analysis
+    match pii_analysis.as_ref() {
+        Some(pii_analysis) => {
+            FilePiiAnalysis::set(t.deref_mut(), file.id, pii_analysis, Utc::now()).await?;
+        }
+        None => {
+            FilePiiAnalysis::delete(t.deref_mut(), file.id).await?;
+        }
+    }
+
+    // Replace the previous text stats
+    match text_stats.as_ref() {
+        Some(text_stats) => {
+            FileTextStats::set(t.deref_mut(), file.id, text_stats).await?;
+        }
+        None => {
+            FileTextStats::delete(t.deref_mut(), file.id).await?;
+        }
+    }
+
+    // Previously cached output for the file content is no longer current
+    ExtractionCacheEntry::delete(t.deref_mut(), &file.hash, mime.essence_str()).await?;
+
+    FileProcessing::set(
+        t.deref_mut(),
+        file.id,
+        PROCESSING_PIPELINE_VERSION,
+        Utc::now(),
+        &timings,
+    )
+    .await?;
+
+    t.commit().await?;
+
+    Ok(previous_generated)
+}
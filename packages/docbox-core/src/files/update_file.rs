@@ -24,11 +24,19 @@ pub enum UpdateFileError {
     #[error("unknown target folder")]
     UnknownTargetFolder,
 
+    /// File was modified since the version the update expected
+    #[error("file was modified by another request")]
+    VersionConflict {
+        expected_version: i64,
+        current_version: i64,
+    },
+
     /// Failed to update the search index
     #[error(transparent)]
     SearchIndex(SearchError),
 }
 
+#[derive(Default)]
 pub struct UpdateFile {
     /// Move the file to another folder
     pub folder_id: Option<FolderId>,
@@ -38,6 +46,36 @@ pub struct UpdateFile {
 
     /// Update the file pinned state
     pub pinned: Option<bool>,
+
+    /// Version the file is expected to be at, the update is rejected
+    /// when the file has been modified since this version
+    pub expected_version: Option<i64>,
+}
+
+impl UpdateFile {
+    /// Create an update that reverts the change recorded by an edit
+    /// history entry, [None] if the change cannot be reverted
+    pub fn revert(metadata: &EditHistoryMetadata) -> Option<UpdateFile> {
+        let mut update = UpdateFile::default();
+
+        match metadata {
+            EditHistoryMetadata::MoveToFolder { original_id, .. } => {
+                update.folder_id = Some(*original_id)
+            }
+            EditHistoryMetadata::Rename { original_name, .. } => {
+                update.name = Some(original_name.clone())
+            }
+            EditHistoryMetadata::ChangePinned { previous_value, .. } => {
+                update.pinned = Some(*previous_value)
+            }
+            // Rejected updates made no changes, other types don't apply to files
+            EditHistoryMetadata::LinkValue { .. } | EditHistoryMetadata::UpdateConflict { .. } => {
+                return None;
+            }
+        }
+
+        Some(update)
+    }
 }
 
 pub async fn update_file(
@@ -47,7 +85,7 @@ pub async fn update_file(
     file: File,
     user_id: Option<String>,
     update: UpdateFile,
-) -> Result<(), UpdateFileError> {
+) -> Result<i64, UpdateFileError> {
     let mut file = file;
 
     let mut db = db
@@ -55,6 +93,36 @@ pub async fn update_file(
         .await
         .inspect_err(|error| tracing::error!(?error, "failed to begin transaction"))?;
 
+    // Lock the file and ensure it was not modified since the expected version
+    let current_version = File::lock_version(db.deref_mut(), file.id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to lock file version"))?;
+
+    if let Some(expected_version) = update.expected_version
+        && expected_version != current_version
+    {
+        // Record the rejected update
+        add_edit_history(
+            &mut db,
+            user_id,
+            file.id,
+            EditHistoryMetadata::UpdateConflict {
+                expected_version,
+                current_version,
+            },
+        )
+        .await?;
+
+        db.commit()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to commit transaction"))?;
+
+        return Err(UpdateFileError::VersionConflict {
+            expected_version,
+            current_version,
+        });
+    }
+
     if let Some(target_id) = update.folder_id {
         // Ensure the target folder exists, also ensures the target folder is in the same scope
         // (We may allow across scopes in the future, but would need additional checks for access control of target scope)
@@ -80,6 +148,10 @@ pub async fn update_file(
             .inspect_err(|error| tracing::error!(?error, "failed to update file pinned state"))?;
     }
 
+    let version = File::increment_version(db.deref_mut(), file.id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to increment file version"))?;
+
     // Update search index data for the new name and value
     search
         .update_data(
@@ -87,9 +159,11 @@ pub async fn update_file(
             UpdateSearchIndexData {
                 folder_id: file.folder_id,
                 name: file.name.clone(),
+                pinned: file.pinned,
                 // Don't update unchanged
                 content: None,
                 pages: None,
+                document_box: None,
             },
         )
         .await
@@ -102,7 +176,7 @@ pub async fn update_file(
         tracing::error!(?error, "failed to commit transaction");
     })?;
 
-    Ok(())
+    Ok(version)
 }
 
 /// Add a new edit history item for a file
@@ -1,15 +1,38 @@
 use crate::{
-    events::{TenantEventMessage, TenantEventPublisher},
+    events::{
+        FileProcessingCompleted, FileProcessingFailed, FileProcessingStarted, TenantEventMessage,
+        TenantEventPublisher,
+    },
     files::{
-        create_file_key,
-        generated::{make_create_generated_files, upload_generated_files},
+        create_content_file_key, create_file_key,
+        delete_file::release_file_object,
+        extraction_cache::{
+            is_cacheable_mime, load_cached_processing_output, make_extraction_cache_entry,
+        },
+        generated::{
+            apply_generated_file_policies, make_create_generated_files, upload_generated_files,
+        },
         index_file::store_file_index,
     },
+    utils::{
+        file::{StoredObjectType, create_object_tags},
+        rollback::Rollback,
+    },
 };
 use bytes::Bytes;
 use chrono::Utc;
 use docbox_database::models::{
-    document_box::DocumentBoxScopeRaw, generated_file::CreateGeneratedFile,
+    document_box::DocumentBoxScopeRaw,
+    document_box_stats::{DocumentBoxStatsCounters, DocumentBoxStatsDelta},
+    extraction_cache::{CreateExtractionCacheEntry, ExtractionCacheEntry},
+    file_pdf_metadata::{FilePdfMetadata, PdfMetadata},
+    file_pii_analysis::{FilePiiAnalysis, PiiAnalysis},
+    file_processing::{FileProcessing, ProcessingTimings},
+    file_text_stats::{FileTextStats, TextStats},
+    folder_processing_config::FolderProcessingConfig,
+    generated_file::CreateGeneratedFile,
+    generated_file_policy::GeneratedFilePolicy,
+    storage_object::StorageObject,
 };
 use docbox_database::models::{document_box::DocumentBoxScopeRawRef, folder::FolderId};
 use docbox_database::{
@@ -18,17 +41,19 @@ use docbox_database::{
         document_box::WithScope,
         file::{CreateFile, File, FileId},
         generated_file::GeneratedFile,
+        tasks::TaskId,
         user::UserId,
     },
 };
 use docbox_processing::{
-    ProcessingConfig, ProcessingError, ProcessingIndexMetadata, ProcessingLayer, QueuedUpload,
-    process_file,
+    PROCESSING_PIPELINE_VERSION, ProcessingConfig, ProcessingError, ProcessingIndexMetadata,
+    ProcessingLayer, QueuedUpload, elapsed_ms, pii::analyze_output_pii, process_file,
+    tables::extract_output_tables,
 };
 use docbox_search::{SearchError, TenantSearchIndex};
 use docbox_storage::{StorageLayer, StorageLayerError, UploadFileOptions};
 use mime::Mime;
-use std::ops::DerefMut;
+use std::{collections::HashSet, ops::DerefMut, time::Instant};
 use thiserror::Error;
 use tracing::Instrument;
 use uuid::Uuid;
@@ -57,6 +82,38 @@ pub enum UploadFileError {
     #[error("failed to create generated file")]
     CreateGeneratedFile(DbErr),
 
+    /// Failed to load the generated file retention policies
+    #[error("failed to load generated file policies")]
+    QueryGeneratedFilePolicies(DbErr),
+
+    /// Failed to load the processing configs of the destination folder
+    #[error("failed to load folder processing config")]
+    QueryFolderProcessingConfig(DbErr),
+
+    /// Failed to store the processing output in the extraction cache
+    #[error("failed to store extraction cache")]
+    CreateExtractionCache(DbErr),
+
+    /// Failed to store the extracted PDF metadata
+    #[error("failed to store pdf metadata")]
+    CreatePdfMetadata(DbErr),
+
+    /// Failed to store the PII analysis
+    #[error("failed to store pii analysis")]
+    CreatePiiAnalysis(DbErr),
+
+    /// Failed to store the text stats
+    #[error("failed to store text stats")]
+    CreateTextStats(DbErr),
+
+    /// Failed to update the document box stats counters
+    #[error("failed to update document box stats")]
+    UpdateDocumentBoxStats(DbErr),
+
+    /// Failed to query or reference a deduplicated storage object
+    #[error("failed to reference storage object")]
+    StorageObject(DbErr),
+
     /// Failed to upload file to storage layer
     #[error("failed to upload file to storage layer: {0}")]
     UploadFile(StorageLayerError),
@@ -78,10 +135,46 @@ pub enum UploadFileError {
 /// upload, to help with reverted changes on failure
 #[derive(Default)]
 pub struct UploadFileState {
-    /// Storage file upload keys
-    pub storage_upload_keys: Vec<String>,
-    /// Search index files
-    pub search_index_files: Vec<Uuid>,
+    /// Steps to undo the resources created by each stage of the upload
+    pub rollback: Rollback,
+    /// Content hashes of the additional files created by the upload, used
+    /// to skip duplicate copies (i.e the same attachment across an email thread)
+    pub additional_file_hashes: HashSet<String>,
+    /// Keys of the deduplicated storage objects referenced by the upload, the
+    /// references are released (Removing objects no longer referenced) on failure
+    pub storage_objects: Vec<String>,
+}
+
+impl UploadFileState {
+    /// Undo the changes made by the upload in a background task
+    ///
+    /// Storage object references are released before performing the [Rollback]
+    /// steps, objects are only removed from storage when nothing else has
+    /// referenced them in the meantime
+    pub fn rollback_background(self, db: DbPool, search: TenantSearchIndex, storage: StorageLayer) {
+        if self.storage_objects.is_empty() {
+            self.rollback.run_background(search, storage);
+            return;
+        }
+
+        let span = tracing::Span::current();
+
+        tokio::spawn(
+            async move {
+                for file_key in self.storage_objects.iter().rev() {
+                    if let Err(error) = release_file_object(&db, &storage, file_key).await {
+                        tracing::error!(?error, %file_key, "failed to release storage object");
+                    }
+                }
+
+                let failed = self.rollback.run(&search, &storage).await;
+                if !failed.is_empty() {
+                    tracing::warn!(?failed, "rollback completed with failed steps");
+                }
+            }
+            .instrument(span),
+        );
+    }
 }
 
 pub struct UploadFile {
@@ -116,6 +209,10 @@ pub struct UploadFile {
     /// Config that can be used when processing for additional
     /// configuration to how the file is processed
     pub processing_config: Option<ProcessingConfig>,
+
+    /// ID of the background task or presigned upload task performing the
+    /// upload, included in the file processing events
+    pub task_id: Option<TaskId>,
 }
 
 #[derive(Debug)]
@@ -126,6 +223,8 @@ pub struct UploadedFileData {
     pub generated: Vec<GeneratedFile>,
     /// Additional files created and uploaded from processing the file
     pub additional_files: Vec<UploadedFileData>,
+    /// Time taken by each processing stage
+    pub timings: ProcessingTimings,
 }
 
 pub async fn upload_file(
@@ -136,49 +235,203 @@ pub async fn upload_file(
     events: &TenantEventPublisher,
     upload: UploadFile,
 ) -> Result<UploadedFileData, UploadFileError> {
-    let document_box = upload.document_box.clone();
+    let mut output = upload_files(db, search, storage, processing, events, vec![upload]).await?;
+
+    // Exactly one output is produced for the single upload
+    Ok(output.remove(0))
+}
+
+/// Uploads multiple files, the records for all the files are persisted within a
+/// single database transaction. If any of the files fail to upload none of the
+/// files are created.
+///
+/// Processing started, completed and failed events are published for each of
+/// the `uploads`, as the uploads succeed or fail together a failure publishes
+/// a failed event for every upload
+///
+/// Returns the uploaded file data for each upload in the same order as `uploads`
+pub async fn upload_files(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    storage: &StorageLayer,
+    processing: &ProcessingLayer,
+    events: &TenantEventPublisher,
+    uploads: Vec<UploadFile>,
+) -> Result<Vec<UploadedFileData>, UploadFileError> {
+    let start = Instant::now();
+    let started_at = Utc::now();
+
+    let started: Vec<WithScope<FileProcessingStarted>> = uploads
+        .iter()
+        .map(|upload| {
+            WithScope::new(
+                FileProcessingStarted {
+                    task_id: upload.task_id,
+                    name: upload.name.clone(),
+                    mime: upload.mime.to_string(),
+                    folder_id: upload.folder_id,
+                    started_at,
+                },
+                upload.document_box.clone(),
+            )
+        })
+        .collect();
+
+    for started in &started {
+        events.publish_event(TenantEventMessage::FileProcessingStarted(started.clone()));
+    }
+
+    let outputs = match persist_file_uploads(db, search, storage, processing, uploads).await {
+        Ok(value) => value,
+        Err(error) => {
+            let duration_ms = elapsed_ms(start);
+            for started in started {
+                events.publish_event(TenantEventMessage::FileProcessingFailed(WithScope::new(
+                    FileProcessingFailed {
+                        task_id: started.data.task_id,
+                        name: started.data.name,
+                        mime: started.data.mime,
+                        folder_id: started.data.folder_id,
+                        error: error.to_string(),
+                        duration_ms,
+                    },
+                    started.scope,
+                )));
+            }
+
+            return Err(error);
+        }
+    };
+
+    let duration_ms = elapsed_ms(start);
+
+    // Publish creation events
+    for ((document_box, output), started) in outputs.iter().zip(&started) {
+        publish_file_creation_events(events, document_box, output);
+
+        events.publish_event(TenantEventMessage::FileProcessingCompleted(WithScope::new(
+            FileProcessingCompleted {
+                task_id: started.data.task_id,
+                file: output.file.clone(),
+                generated_file_types: output
+                    .generated
+                    .iter()
+                    .map(|generated| generated.ty)
+                    .collect(),
+                additional_file_ids: output
+                    .additional_files
+                    .iter()
+                    .map(|additional_file| additional_file.file.id)
+                    .collect(),
+                timings: output.timings,
+                duration_ms,
+            },
+            document_box.clone(),
+        )));
+    }
+
+    Ok(outputs.into_iter().map(|(_, output)| output).collect())
+}
+
+/// Processes and persists the `uploads` without publishing any events
+async fn persist_file_uploads(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    storage: &StorageLayer,
+    processing: &ProcessingLayer,
+    uploads: Vec<UploadFile>,
+) -> Result<Vec<(DocumentBoxScopeRaw, UploadedFileData)>, UploadFileError> {
     let mut upload_state = UploadFileState::default();
 
     // Perform the creation of resources and processing
-    let data =
-        match upload_file_inner(search, storage, processing, upload, &mut upload_state, 0).await {
+    let prepared =
+        match prepare_file_uploads(db, search, storage, processing, uploads, &mut upload_state)
+            .await
+        {
             Ok(value) => value,
             Err(error) => {
                 tracing::error!(?error, "failed to complete inner file processing");
-                background_rollback_upload_file(search.clone(), storage.clone(), upload_state);
+                upload_state.rollback_background(db.clone(), search.clone(), storage.clone());
                 return Err(error);
             }
         };
 
     // Persist records to the database
-    let mut db = db.begin().await.map_err(|error| {
-        tracing::error!(?error, "failed to begin transaction");
-        UploadFileError::BeginTransaction(error)
-    })?;
-
-    let output = match persist_file_upload(&mut db, data).await {
+    let mut t = match db.begin().await {
         Ok(value) => value,
         Err(error) => {
-            if let Err(error) = db.rollback().await {
-                tracing::error!(?error, "failed to roll back database transaction");
-            }
-
-            tracing::error!(?error, "failed to complete inner file processing");
-            background_rollback_upload_file(search.clone(), storage.clone(), upload_state);
-            return Err(error);
+            tracing::error!(?error, "failed to begin transaction");
+            upload_state.rollback_background(db.clone(), search.clone(), storage.clone());
+            return Err(UploadFileError::BeginTransaction(error));
         }
     };
 
-    if let Err(error) = db.commit().await {
+    let mut outputs = Vec::with_capacity(prepared.len());
+
+    for (document_box, data) in prepared {
+        match persist_file_upload(&mut t, &document_box, data).await {
+            Ok(value) => outputs.push((document_box, value)),
+            Err(error) => {
+                if let Err(error) = t.rollback().await {
+                    tracing::error!(?error, "failed to roll back database transaction");
+                }
+
+                tracing::error!(?error, "failed to complete inner file processing");
+                upload_state.rollback_background(db.clone(), search.clone(), storage.clone());
+                return Err(error);
+            }
+        }
+    }
+
+    if let Err(error) = t.commit().await {
         tracing::error!(?error, "failed to commit transaction");
-        background_rollback_upload_file(search.clone(), storage.clone(), upload_state);
+        upload_state.rollback_background(db.clone(), search.clone(), storage.clone());
         return Err(UploadFileError::CommitTransaction(error));
     }
 
-    // Publish creation events
-    publish_file_creation_events(events, &document_box, &output);
+    Ok(outputs)
+}
+
+/// Processes and stores the contents of each upload, preparing the records
+/// to persist without persisting them to the database
+async fn prepare_file_uploads(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    storage: &StorageLayer,
+    processing: &ProcessingLayer,
+    uploads: Vec<UploadFile>,
+    upload_state: &mut UploadFileState,
+) -> Result<Vec<(DocumentBoxScopeRaw, PreparedUploadData)>, UploadFileError> {
+    let generated_file_policies = GeneratedFilePolicy::all(db).await.map_err(|error| {
+        tracing::error!(?error, "failed to query generated file policies");
+        UploadFileError::QueryGeneratedFilePolicies(error)
+    })?;
+
+    let mut prepared = Vec::with_capacity(uploads.len());
+
+    for mut upload in uploads {
+        let document_box = upload.document_box.clone();
+
+        upload.processing_config =
+            resolve_processing_config(db, upload.folder_id, upload.processing_config.take())
+                .await?;
+
+        let data = upload_file_inner(
+            db,
+            search,
+            storage,
+            processing,
+            &generated_file_policies,
+            upload,
+            upload_state,
+            0,
+        )
+        .await?;
+
+        prepared.push((document_box, data));
+    }
 
-    Ok(output)
+    Ok(prepared)
 }
 
 /// Publish file creation events for all created files
@@ -208,22 +461,78 @@ pub struct PreparedUploadData {
 
     /// Child additional file records to create
     additional_files: Vec<PreparedUploadData>,
+
+    /// Extraction cache entry to store for the processing output
+    extraction_cache: Option<CreateExtractionCacheEntry>,
+
+    /// PDF metadata extracted while processing the file
+    pdf_metadata: Option<PdfMetadata>,
+
+    /// PII analysis of the extracted text
+    pii_analysis: Option<PiiAnalysis>,
+
+    /// Word count, character count and reading time of the extracted text
+    text_stats: Option<TextStats>,
+
+    /// Time taken by each processing stage
+    timings: ProcessingTimings,
+}
+
+/// Resolve the processing config for a file uploaded into `folder_id`
+///
+/// Configs attached to the folder and its parents are merged from the root
+/// folder down, the `processing_config` provided with the upload takes
+/// priority over all folder configs
+pub(crate) async fn resolve_processing_config(
+    db: &DbPool,
+    folder_id: FolderId,
+    processing_config: Option<ProcessingConfig>,
+) -> Result<Option<ProcessingConfig>, UploadFileError> {
+    let folder_configs = FolderProcessingConfig::find_for_folder_path(db, folder_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query folder processing configs");
+            UploadFileError::QueryFolderProcessingConfig(error)
+        })?;
+
+    let resolved = folder_configs
+        .into_iter()
+        .filter_map(|folder_config| {
+            serde_json::from_value::<ProcessingConfig>(folder_config.config)
+                .inspect_err(|error| {
+                    tracing::warn!(
+                        ?error,
+                        folder_id = %folder_config.folder_id,
+                        "ignoring invalid folder processing config"
+                    );
+                })
+                .ok()
+        })
+        .chain(processing_config)
+        .reduce(ProcessingConfig::merge);
+
+    Ok(resolved)
 }
 
 /// Performs the file uploading, processing and storage. Prepares the data without
 /// persisting data to the database
 ///
 /// Performs the following:
-/// - Process the file
+/// - Process the file (Or load the output from the extraction cache)
 /// - Create a prepared file record database metadata
 /// - Upload generated files and create their metadata
 /// - Perform this function for additional inner files
 /// - Store file metadata in the search index
-/// - Upload the main file to S3 if not already performed
+/// - Upload the main file to S3 if not already performed (Or replace the existing
+///   file with its encrypted contents when the storage is encrypted). Deduplicated
+///   storage reuses an existing object with identical contents
+#[allow(clippy::too_many_arguments)]
 async fn upload_file_inner(
+    db: &DbPool,
     search: &TenantSearchIndex,
     storage: &StorageLayer,
     processing: &ProcessingLayer,
+    generated_file_policies: &[GeneratedFilePolicy],
     upload: UploadFile,
     upload_state: &mut UploadFileState,
     iteration: usize,
@@ -236,11 +545,18 @@ async fn upload_file_inner(
         .unwrap_or(1)
         .min(server_max_iterations);
 
+    let hash = sha256::digest(upload.file_bytes.as_ref() as &[u8]);
+
     // Determine if we need to upload and what the file key is
     let (s3_upload, file_key) = match upload.file_key.as_ref() {
         // Already have a file key, don't want to upload
         Some(file_key) => (false, file_key.clone()),
 
+        // Deduplicated storage, file is keyed by its contents
+        None if storage.is_deduplicated() => {
+            (true, create_content_file_key(&upload.document_box, &hash))
+        }
+
         // No existing file key, we are creating one and uploading the file
         None => (
             true,
@@ -253,14 +569,51 @@ async fn upload_file_inner(
         ),
     };
 
+    let deduplicated = s3_upload && storage.is_deduplicated();
+    let cacheable = is_cacheable_mime(&upload.mime);
+
+    // Attempt to reuse the output from a previous upload of the same content
+    let cached_output = if cacheable {
+        load_cached_processing_output(db, storage, &hash, &upload.mime)
+            .await
+            .unwrap_or_else(|error| {
+                tracing::warn!(?error, "failed to load extraction cache, processing file");
+                None
+            })
+    } else {
+        None
+    };
+
+    let from_cache = cached_output.is_some();
+    let processing_start = Instant::now();
+
     // Process the file
-    let processing_output = process_file(
-        &upload.processing_config,
-        processing,
-        upload.file_bytes.clone(),
-        &upload.mime,
-    )
-    .await?;
+    let processing_output = match cached_output {
+        Some(mut output) => {
+            tracing::debug!("using cached processing output");
+
+            // Cached output is shared across uploads, analyze using this upload's config
+            analyze_output_pii(&upload.processing_config, &mut output);
+            extract_output_tables(
+                &upload.processing_config,
+                &upload.file_bytes,
+                &upload.mime,
+                &mut output,
+            );
+            Some(output)
+        }
+        None => {
+            process_file(
+                &upload.processing_config,
+                processing,
+                upload.file_bytes.clone(),
+                &upload.mime,
+            )
+            .await?
+        }
+    };
+
+    let processing_ms = elapsed_ms(processing_start);
 
     // Get file encryption state
     let encrypted = processing_output
@@ -268,22 +621,57 @@ async fn upload_file_inner(
         .map(|output| output.encrypted)
         .unwrap_or_default();
 
-    let file_record = make_file_record(&upload, &file_key, &upload.file_bytes, encrypted);
+    let mut timings = processing_output
+        .as_ref()
+        .map(|output| output.timings)
+        .unwrap_or_default();
+
+    let file_record = make_file_record(&upload, &file_key, hash, &upload.file_bytes, encrypted);
+
+    // Cache the processing output when it was freshly produced
+    let extraction_cache = processing_output
+        .as_ref()
+        .filter(|output| cacheable && !from_cache && output.additional_files.is_empty())
+        .and_then(|output| {
+            make_extraction_cache_entry(
+                file_record.id,
+                &file_record.hash,
+                &upload.mime,
+                encrypted,
+                output.index_metadata.as_ref(),
+            )
+        });
 
     let mut index_metadata: Option<ProcessingIndexMetadata> = None;
     let mut generated_files: Option<Vec<CreateGeneratedFile>> = None;
     let mut additional_files: Vec<PreparedUploadData> = Vec::new();
+    let mut pdf_metadata: Option<PdfMetadata> = None;
+    let mut pii_analysis: Option<PiiAnalysis> = None;
+    let mut text_stats: Option<TextStats> = None;
 
     if let Some(processing_output) = processing_output {
         index_metadata = processing_output.index_metadata;
+        text_stats = index_metadata
+            .as_ref()
+            .and_then(|metadata| metadata.text_stats);
+        pdf_metadata = processing_output.pdf_metadata;
+        pii_analysis = processing_output.pii_analysis;
+
+        // Only keep the generated files retained by the tenant policies
+        let upload_queue = apply_generated_file_policies(
+            generated_file_policies,
+            &upload.mime,
+            processing_output.upload_queue,
+        );
 
         // Upload generated files and store the metadata
         tracing::debug!("uploading generated files");
         let prepared_files = store_generated_files(
             storage,
+            &upload.document_box,
             &file_record,
-            &mut upload_state.storage_upload_keys,
-            processing_output.upload_queue,
+            &mut upload_state.rollback,
+            upload_queue,
         )
         .await?;
         generated_files = Some(prepared_files);
@@ -297,6 +685,16 @@ async fn upload_file_inner(
         } else {
             // Process additional files
             for additional_file in processing_output.additional_files {
+                // Skip additional files that have already been created by this upload
+                let additional_hash = sha256::digest(additional_file.bytes.as_ref() as &[u8]);
+                if !upload_state.additional_file_hashes.insert(additional_hash) {
+                    tracing::debug!(
+                        name = ?additional_file.name,
+                        "skipping duplicate additional file"
+                    );
+                    continue;
+                }
+
                 let upload = UploadFile {
                     parent_id: Some(file_record.id),
                     fixed_id: additional_file.fixed_id,
@@ -308,13 +706,16 @@ async fn upload_file_inner(
                     created_by: upload.created_by.clone(),
                     file_key: None,
                     processing_config: upload.processing_config.clone(),
+                    task_id: upload.task_id,
                 };
 
                 // Process the child file (Additional file outputs are ignored)
                 let output = Box::pin(upload_file_inner(
+                    db,
                     search,
                     storage,
                     processing,
+                    generated_file_policies,
                     upload,
                     upload_state,
                     next_iteration,
@@ -328,10 +729,25 @@ async fn upload_file_inner(
 
     // Index the file in the search index
     tracing::debug!("indexing file contents");
+    let indexing_start = Instant::now();
     store_file_index(search, &file_record, &upload.document_box, index_metadata).await?;
-    upload_state.search_index_files.push(file_record.id);
+    upload_state.rollback.search_index(file_record.id);
 
-    if s3_upload {
+    let indexing_ms = elapsed_ms(indexing_start);
+    timings.indexing_ms = Some(indexing_ms);
+    timings.total_ms = Some(processing_ms + indexing_ms);
+
+    if deduplicated {
+        acquire_storage_object(
+            db,
+            storage,
+            &upload.document_box,
+            &file_record,
+            upload.file_bytes,
+        )
+        .await?;
+        upload_state.storage_objects.push(file_key);
+    } else if s3_upload {
         // Upload the file itself to S3
         tracing::debug!("uploading main file");
         storage
@@ -340,24 +756,97 @@ async fn upload_file_inner(
                 upload.file_bytes,
                 UploadFileOptions {
                     content_type: file_record.mime.clone(),
+                    object_tags: create_object_tags(&upload.document_box, StoredObjectType::File),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(UploadFileError::UploadFile)?;
+        upload_state.rollback.storage_file(&file_key);
+    } else if storage.is_encrypted() {
+        // Files uploaded directly to storage (presigned uploads) are stored
+        // unencrypted and must be replaced with the encrypted contents
+        tracing::debug!("encrypting existing main file");
+        storage
+            .upload_file(
+                &file_key,
+                upload.file_bytes,
+                UploadFileOptions {
+                    content_type: file_record.mime.clone(),
+                    object_tags: create_object_tags(&upload.document_box, StoredObjectType::File),
                     ..Default::default()
                 },
             )
             .await
             .map_err(UploadFileError::UploadFile)?;
-        upload_state.storage_upload_keys.push(file_key.clone());
     }
 
     Ok(PreparedUploadData {
         file: file_record,
         generated_files,
         additional_files,
+        extraction_cache,
+        pdf_metadata,
+        pii_analysis,
+        text_stats,
+        timings,
     })
 }
 
+/// Takes a reference to the deduplicated storage object for `file`, uploading
+/// the object contents when this is the first reference to the object
+///
+/// The reference row is locked by the upsert until the transaction completes so
+/// the object is stored before a concurrent upload or delete of the same contents
+/// can observe the reference. The reference is not kept if the upload fails
+async fn acquire_storage_object(
+    db: &DbPool,
+    storage: &StorageLayer,
+    document_box: DocumentBoxScopeRawRef<'_>,
+    file: &CreateFile,
+    file_bytes: Bytes,
+) -> Result<(), UploadFileError> {
+    let mut t = db
+        .begin()
+        .await
+        .map_err(UploadFileError::BeginTransaction)?;
+
+    let reference_count =
+        StorageObject::acquire(t.deref_mut(), &file.file_key, &file.hash, file.created_at)
+            .await
+            .map_err(UploadFileError::StorageObject)?;
+
+    if reference_count > 1 {
+        tracing::debug!("main file contents already stored, referencing existing object");
+    } else {
+        tracing::debug!("uploading main file");
+        storage
+            .upload_file(
+                &file.file_key,
+                file_bytes,
+                UploadFileOptions {
+                    content_type: file.mime.clone(),
+                    object_tags: create_object_tags(document_box, StoredObjectType::File),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(UploadFileError::UploadFile)?;
+    }
+
+    // A failed commit leaves the uploaded object unreferenced in storage, it is not
+    // removed as a concurrent upload may already be waiting to reference it
+    t.commit()
+        .await
+        .map_err(UploadFileError::CommitTransaction)?;
+
+    Ok(())
+}
+
 /// Persists the data from [PreparedUploadData] into the database storing any applied changes
 async fn persist_file_upload(
     db: &mut DbTransaction<'_>,
+    document_box: DocumentBoxScopeRawRef<'_>,
     data: PreparedUploadData,
 ) -> Result<UploadedFileData, UploadFileError> {
     // Create file to commit against
@@ -365,6 +854,25 @@ async fn persist_file_upload(
         .await
         .map_err(UploadFileError::CreateFile)?;
 
+    DocumentBoxStatsCounters::apply_delta(
+        db.deref_mut(),
+        document_box,
+        DocumentBoxStatsDelta::file(file.size),
+    )
+    .await
+    .map_err(UploadFileError::UpdateDocumentBoxStats)?;
+
+    // Stamp the processing pipeline version used for the file
+    FileProcessing::set(
+        db.deref_mut(),
+        file.id,
+        PROCESSING_PIPELINE_VERSION,
+        file.created_at,
+        &data.timings,
+    )
+    .await
+    .map_err(UploadFileError::CreateFile)?;
+
     // Create generated file records
     let mut generated_files = Vec::new();
     if let Some(creates) = data.generated_files {
@@ -377,10 +885,38 @@ async fn persist_file_upload(
         }
     }
 
+    // Store the extracted PDF metadata
+    if let Some(pdf_metadata) = data.pdf_metadata.as_ref() {
+        FilePdfMetadata::set(db.deref_mut(), file.id, pdf_metadata)
+            .await
+            .map_err(UploadFileError::CreatePdfMetadata)?;
+    }
+
+    // Store the PII analysis
+    if let Some(pii_analysis) = data.pii_analysis.as_ref() {
+        FilePiiAnalysis::set(db.deref_mut(), file.id, pii_analysis, file.created_at)
+            .await
+            .map_err(UploadFileError::CreatePiiAnalysis)?;
+    }
+
+    // Store the text stats
+    if let Some(text_stats) = data.text_stats.as_ref() {
+        FileTextStats::set(db.deref_mut(), file.id, text_stats)
+            .await
+            .map_err(UploadFileError::CreateTextStats)?;
+    }
+
+    // Store the extraction cache entry now that the generated files exist
+    if let Some(create) = data.extraction_cache {
+        ExtractionCacheEntry::create(db.deref_mut(), create)
+            .await
+            .map_err(UploadFileError::CreateExtractionCache)?;
+    }
+
     // Create records for inner additional files
     let mut additional_files: Vec<UploadedFileData> = Vec::new();
     for additional_file in data.additional_files {
-        let inner = Box::pin(persist_file_upload(db, additional_file)).await?;
+        let inner = Box::pin(persist_file_upload(db, document_box, additional_file)).await?;
         additional_files.push(inner);
     }
 
@@ -388,6 +924,7 @@ async fn persist_file_upload(
         file,
         generated: generated_files,
         additional_files,
+        timings: data.timings,
     })
 }
 
@@ -395,11 +932,11 @@ async fn persist_file_upload(
 fn make_file_record(
     upload: &UploadFile,
     file_key: &str,
+    hash: String,
     file_bytes: &Bytes,
     encrypted: bool,
 ) -> CreateFile {
     let id = upload.fixed_id.unwrap_or_else(Uuid::new_v4);
-    let hash = sha256::digest(file_bytes.as_ref() as &[u8]);
     let size = file_bytes.len().min(i32::MAX as usize) as i32;
     let created_at = Utc::now();
 
@@ -422,20 +959,20 @@ fn make_file_record(
 /// in S3 and returns the [CreateGeneratedFile] structures to be stored
 /// in the database at a later step
 ///
-/// Any uploads that succeed to storage will have their file key pushed
-/// to `storage_upload_keys` so that it can be rolled back if any errors
-/// occur
+/// Any uploads that succeed to storage will have their file key recorded
+/// in the `rollback` so that it can be rolled back if any errors occur
 pub async fn store_generated_files(
     storage: &StorageLayer,
+    document_box: DocumentBoxScopeRawRef<'_>,
     file: &CreateFile,
-    storage_upload_keys: &mut Vec<String>,
+    rollback: &mut Rollback,
     queued_uploads: Vec<QueuedUpload>,
 ) -> Result<Vec<CreateGeneratedFile>, UploadFileError> {
     let prepared_uploads =
         make_create_generated_files(&file.file_key, &file.id, &file.hash, queued_uploads);
 
     // Upload the generated files to S3
-    let upload_results = upload_generated_files(storage, prepared_uploads).await;
+    let upload_results = upload_generated_files(storage, document_box, prepared_uploads).await;
 
     let mut generated_files = Vec::new();
     let mut upload_errors = Vec::new();
@@ -445,7 +982,7 @@ pub async fn store_generated_files(
             // Successful upload, store generated file
             Ok(create) => {
                 // Track uploaded file keys
-                storage_upload_keys.push(file.file_key.clone());
+                rollback.storage_file(&create.file_key);
                 generated_files.push(create);
             }
             // Failed upload
@@ -469,42 +1006,3 @@ pub async fn store_generated_files(
 
     Ok(generated_files)
 }
-
-/// Performs a background rollback task on an uploaded file
-fn background_rollback_upload_file(
-    search: TenantSearchIndex,
-    storage: StorageLayer,
-    upload_state: UploadFileState,
-) {
-    let span = tracing::Span::current();
-
-    // Attempt to rollback any allocated resources in the background
-    tokio::spawn(
-        async move {
-            rollback_upload_file(&search, &storage, upload_state).await;
-        }
-        .instrument(span),
-    );
-}
-
-/// Performs the process of rolling back a file upload based
-/// on the current upload state
-async fn rollback_upload_file(
-    search: &TenantSearchIndex,
-    storage: &StorageLayer,
-    upload_state: UploadFileState,
-) {
-    // Revert upload S3 files
-    for key in upload_state.storage_upload_keys {
-        if let Err(err) = storage.delete_file(&key).await {
-            tracing::error!(?err, "failed to rollback created tenant s3 file");
-        }
-    }
-
-    // Revert file index data
-    for index in upload_state.search_index_files {
-        if let Err(err) = search.delete_data(index).await {
-            tracing::error!(?index, ?err, "failed to rollback created file search index",);
-        }
-    }
-}
@@ -165,6 +165,87 @@ pub async fn create_presigned_upload(
     })
 }
 
+#[derive(Debug, Error)]
+pub enum RetryPresignedUploadError {
+    /// Database error occurred
+    #[error(transparent)]
+    Database(#[from] DbErr),
+
+    #[error("unknown presigned upload task")]
+    UnknownTask,
+
+    #[error("presigned upload task already completed")]
+    AlreadyCompleted,
+
+    #[error("presigned upload task expired")]
+    Expired,
+
+    #[error("presigned upload folder no longer exists")]
+    UnknownFolder,
+}
+
+/// Re-run the completion of a presigned upload that was uploaded to storage
+/// but failed processing, or never had its storage notification processed.
+///
+/// The file must already be uploaded to storage, retrying a pending task whose
+/// file was not uploaded will mark the task as failed. The returned task
+/// contains the outcome of the retry
+#[tracing::instrument(skip(db, search, storage, events, processing))]
+pub async fn retry_presigned_upload(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    storage: &StorageLayer,
+    events: &TenantEventPublisher,
+    processing: &ProcessingLayer,
+    task_id: PresignedUploadTaskId,
+) -> Result<PresignedUploadTask, RetryPresignedUploadError> {
+    let mut task = PresignedUploadTask::find_by_id(db, task_id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query presigned upload task"))?
+        .ok_or(RetryPresignedUploadError::UnknownTask)?;
+
+    match task.status {
+        PresignedTaskStatus::Completed { .. } => {
+            return Err(RetryPresignedUploadError::AlreadyCompleted);
+        }
+        // Expired tasks have already had their uploaded file removed
+        PresignedTaskStatus::Expired => return Err(RetryPresignedUploadError::Expired),
+        PresignedTaskStatus::Pending | PresignedTaskStatus::Failed { .. } => {}
+    }
+
+    let folder = Folder::find_by_id(db, &task.document_box, task.folder_id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query folder"))?
+        .ok_or(RetryPresignedUploadError::UnknownFolder)?;
+
+    // Reset the status while the upload is completing
+    task.set_status(db, PresignedTaskStatus::Pending)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to set presigned task status"))?;
+
+    let mut complete = CompletePresigned { task, folder };
+
+    let status =
+        match complete_presigned(db, search, storage, processing, events, &mut complete).await {
+            Ok(output) => PresignedTaskStatus::Completed {
+                file_id: output.file.id,
+            },
+            Err(error) => {
+                tracing::error!(?error, "failed to complete presigned upload retry");
+                PresignedTaskStatus::Failed {
+                    error: error.to_string(),
+                }
+            }
+        };
+
+    let mut task = complete.task;
+    task.set_status(db, status)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to set presigned task status"))?;
+
+    Ok(task)
+}
+
 pub struct CompletePresigned {
     pub task: PresignedUploadTask,
     pub folder: Folder,
@@ -264,6 +345,7 @@ pub async fn complete_presigned(
         created_by: task.created_by.clone(),
         file_key: Some(task.file_key.clone()),
         processing_config,
+        task_id: Some(task.id),
     };
 
     // Perform the upload
@@ -6,6 +6,7 @@ use docbox_database::{
     DbErr, DbPool,
     models::{
         document_box::WithScope,
+        document_box_stats::{DocumentBoxStatsCounters, DocumentBoxStatsDelta},
         folder::{CreateFolder, Folder},
         user::UserId,
     },
@@ -90,6 +91,14 @@ async fn create_folder(
     .await
     .inspect_err(|error| tracing::error!(?error, "failed to create folder"))?;
 
+    DocumentBoxStatsCounters::apply_delta(
+        db.deref_mut(),
+        &folder.document_box,
+        DocumentBoxStatsDelta::folder(),
+    )
+    .await
+    .inspect_err(|error| tracing::error!(?error, "failed to update document box stats"))?;
+
     // Add folder to search index
     store_folder_index(search, &folder, folder_id).await?;
     create_state.search_index_files.push(folder.id);
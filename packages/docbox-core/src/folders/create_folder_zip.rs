@@ -5,13 +5,14 @@ use std::{
     time::Duration,
 };
 
+use crate::utils::file::{StoredObjectType, create_object_tags};
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use docbox_database::{
     DbErr, DbPool,
     models::folder::{Folder, FolderId},
 };
-use docbox_storage::{StorageLayer, StorageLayerError, UploadFileTag};
+use docbox_storage::{PresignedDownloadOptions, StorageLayer, StorageLayerError, UploadFileTag};
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -21,7 +22,7 @@ use uuid::Uuid;
 use zip::{ZipWriter, result::ZipError, write::SimpleFileOptions};
 
 use crate::{
-    files::create_file_key,
+    files::{create_content_disposition, create_file_key},
     folders::folder_stream::{FolderWalkError, FolderWalkItem, FolderWalkStream},
 };
 
@@ -189,14 +190,25 @@ pub async fn create_folder_zip(
             docbox_storage::UploadFileOptions {
                 content_type: "application/zip".to_string(),
                 tags: Some(vec![UploadFileTag::ExpireDays1]),
+                object_tags: create_object_tags(&folder.document_box, StoredObjectType::FolderZip),
             },
         )
         .await
         .inspect_err(|error| tracing::error!(?error, "failed to upload zip file"))
         .map_err(CreateFolderZipError::UploadZip)?;
 
+    // Download is named after the folder
+    let zip_file_name = format!("{}.zip", folder.name);
+
     let (signed_request, expires_at) = storage
-        .create_presigned_download(&file_key, Duration::from_secs(60 * 60 * 24))
+        .create_presigned_download(
+            &file_key,
+            Duration::from_secs(60 * 60 * 24),
+            PresignedDownloadOptions {
+                content_disposition: Some(create_content_disposition(true, &zip_file_name)),
+                ..Default::default()
+            },
+        )
         .await
         .inspect_err(|error| tracing::error!(?error, "failed to create zip presigned download"))
         .map_err(CreateFolderZipError::CreateZipDownload)?;
@@ -1,15 +1,22 @@
+use crate::document_box::document_box_stats::safe_apply_document_box_stats_delta;
 use crate::events::{TenantEventMessage, TenantEventPublisher};
 use crate::files::delete_file::{DeleteFileError, delete_file};
 use crate::folders::folder_stream::FolderWalkStream;
 use crate::links::delete_link::{DeleteLinkError, delete_link};
 use docbox_database::{
     DbPool,
-    models::{document_box::WithScope, file::File, folder::Folder, link::Link},
+    models::{
+        document_box::WithScope, document_box_stats::DocumentBoxStatsDelta, file::File,
+        folder::Folder, link::Link, tasks::Task,
+    },
 };
 use docbox_search::{SearchError, TenantSearchIndex};
 use docbox_storage::StorageLayer;
 use futures::StreamExt;
+use serde::Serialize;
+use std::time::Duration;
 use thiserror::Error;
+use tokio::time::{Interval, MissedTickBehavior, interval};
 
 use super::folder_stream::FolderWalkItem;
 
@@ -30,6 +37,8 @@ pub enum RemoveStackItem {
 pub enum DeleteFolderError {
     #[error("failed to resolve folder for deletion")]
     ResolveFolder,
+    #[error("failed to count folder contents")]
+    CountChildren,
     #[error(transparent)]
     Folder(#[from] InternalDeleteFolderError),
     #[error(transparent)]
@@ -47,6 +56,38 @@ pub enum InternalDeleteFolderError {
     Database,
 }
 
+/// Number of deleted items between each progress report
+const PROGRESS_REPORT_INTERVAL: u64 = 25;
+
+/// Progress of a folder deletion
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DeleteFolderProgress {
+    /// Total number of items to delete (Including the folder itself),
+    /// counted when the deletion started
+    pub total: u64,
+    /// Number of folders deleted
+    pub deleted_folders: u64,
+    /// Number of files deleted
+    pub deleted_files: u64,
+    /// Number of links deleted
+    pub deleted_links: u64,
+}
+
+impl DeleteFolderProgress {
+    /// Total number of items deleted
+    pub fn deleted(&self) -> u64 {
+        self.deleted_folders + self.deleted_files + self.deleted_links
+    }
+}
+
+/// Options for deleting a folder
+#[derive(Debug, Default, Clone)]
+pub struct DeleteFolderOptions {
+    /// Minimum time between deleting each item, limits the rate of requests
+    /// made against storage and search when deleting large folders
+    pub item_interval: Option<Duration>,
+}
+
 pub async fn delete_folder(
     db: &DbPool,
     storage: &StorageLayer,
@@ -54,30 +95,123 @@ pub async fn delete_folder(
     events: &TenantEventPublisher,
     folder: Folder,
 ) -> Result<(), DeleteFolderError> {
+    delete_folder_with_progress(
+        db,
+        storage,
+        search,
+        events,
+        folder,
+        &DeleteFolderOptions::default(),
+        None,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Deletes a folder and all of its contents, reporting the progress of the
+/// deletion to the provided `task`
+///
+/// The deepest contents are deleted first, if the deletion fails partway
+/// through the already deleted items stay deleted. Deleting the folder
+/// again resumes with the remaining contents
+pub async fn delete_folder_with_progress(
+    db: &DbPool,
+    storage: &StorageLayer,
+    search: &TenantSearchIndex,
+    events: &TenantEventPublisher,
+    folder: Folder,
+    options: &DeleteFolderOptions,
+    mut task: Option<&mut Task>,
+) -> Result<DeleteFolderProgress, DeleteFolderError> {
     let document_box = folder.document_box.clone();
 
+    let mut progress = DeleteFolderProgress::default();
+
+    if task.is_some() {
+        let counts = Folder::count_children(db, folder.id)
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to count folder children");
+                DeleteFolderError::CountChildren
+            })?;
+
+        progress.total = (counts.file_count + counts.link_count + counts.folder_count + 1) as u64;
+        report_progress(db, task.as_deref_mut(), &progress).await;
+    }
+
+    let mut item_interval: Option<Interval> = options.item_interval.map(|period| {
+        let mut item_interval = interval(period);
+        item_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        item_interval
+    });
+
     let mut stream = FolderWalkStream::new(db, folder);
 
-    while let Some(result) = stream.next().await {
-        let item = result.map_err(|error| {
-            tracing::error!(?error, "failed to resolve folder for deletion");
-            DeleteFolderError::ResolveFolder
-        })?;
+    let result = loop {
+        if let Some(item_interval) = item_interval.as_mut() {
+            item_interval.tick().await;
+        }
 
-        match item {
-            FolderWalkItem::Folder(folder) => {
-                internal_delete_folder(db, search, events, folder).await?;
+        let item = match stream.next().await {
+            Some(Ok(item)) => item,
+            Some(Err(error)) => {
+                tracing::error!(?error, "failed to resolve folder for deletion");
+                break Err(DeleteFolderError::ResolveFolder);
             }
+            None => break Ok(()),
+        };
+
+        let result = match item {
+            FolderWalkItem::Folder(folder) => internal_delete_folder(db, search, events, folder)
+                .await
+                .map(|_| progress.deleted_folders += 1)
+                .map_err(DeleteFolderError::from),
             FolderWalkItem::File(file) => {
-                delete_file(db, storage, search, events, file, document_box.clone()).await?;
+                delete_file(db, storage, search, events, file, document_box.clone())
+                    .await
+                    .map(|_| progress.deleted_files += 1)
+                    .map_err(DeleteFolderError::from)
             }
             FolderWalkItem::Link(link) => {
-                delete_link(db, search, events, link, document_box.clone()).await?;
+                delete_link(db, storage, search, events, link, document_box.clone())
+                    .await
+                    .map(|_| progress.deleted_links += 1)
+                    .map_err(DeleteFolderError::from)
             }
+        };
+
+        if let Err(error) = result {
+            break Err(error);
         }
-    }
 
-    Ok(())
+        if progress.deleted() % PROGRESS_REPORT_INTERVAL == 0 {
+            report_progress(db, task.as_deref_mut(), &progress).await;
+        }
+    };
+
+    report_progress(db, task, &progress).await;
+    result.map(|_| progress)
+}
+
+/// Store the current `progress` against the `task`, failing to report
+/// progress does not fail the deletion
+async fn report_progress(db: &DbPool, task: Option<&mut Task>, progress: &DeleteFolderProgress) {
+    let Some(task) = task else {
+        return;
+    };
+
+    let progress = match serde_json::to_value(progress) {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::error!(?error, "failed to serialize folder deletion progress");
+            return;
+        }
+    };
+
+    if let Err(error) = task.set_progress(db, progress).await {
+        tracing::warn!(?error, "failed to report folder deletion progress");
+    }
 }
 
 /// Deletes the folder itself and associated metadata, use [delete_folder]
@@ -103,6 +237,16 @@ async fn internal_delete_folder(
         return Ok(());
     }
 
+    // Root folders are not included in the folder count
+    if folder.folder_id.is_some() {
+        safe_apply_document_box_stats_delta(
+            db,
+            &document_box,
+            DocumentBoxStatsDelta::folder().negate(),
+        )
+        .await;
+    }
+
     // Publish an event
     events.publish_event(TenantEventMessage::FolderDeleted(WithScope::new(
         folder,
@@ -18,8 +18,11 @@ pub async fn store_folder_index(
         mime: None,
         content: None,
         pages: None,
+        summary: None,
+        text_stats: None,
         created_at: folder.created_at,
         created_by: folder.created_by.clone(),
+        pinned: folder.pinned,
         document_box: folder.document_box.clone(),
     };
 
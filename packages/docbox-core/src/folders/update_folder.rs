@@ -36,11 +36,19 @@ pub enum UpdateFolderError {
     #[error("cannot move into child of self")]
     CannotMoveIntoChildOfSelf,
 
+    /// Folder was modified since the version the update expected
+    #[error("folder was modified by another request")]
+    VersionConflict {
+        expected_version: i64,
+        current_version: i64,
+    },
+
     /// Failed to update the search index
     #[error(transparent)]
     SearchIndex(SearchError),
 }
 
+#[derive(Default)]
 pub struct UpdateFolder {
     /// Move the folder to another folder
     pub folder_id: Option<FolderId>,
@@ -50,6 +58,36 @@ pub struct UpdateFolder {
 
     /// Update the pinned state
     pub pinned: Option<bool>,
+
+    /// Version the folder is expected to be at, the update is rejected
+    /// when the folder has been modified since this version
+    pub expected_version: Option<i64>,
+}
+
+impl UpdateFolder {
+    /// Create an update that reverts the change recorded by an edit
+    /// history entry, [None] if the change cannot be reverted
+    pub fn revert(metadata: &EditHistoryMetadata) -> Option<UpdateFolder> {
+        let mut update = UpdateFolder::default();
+
+        match metadata {
+            EditHistoryMetadata::MoveToFolder { original_id, .. } => {
+                update.folder_id = Some(*original_id)
+            }
+            EditHistoryMetadata::Rename { original_name, .. } => {
+                update.name = Some(original_name.clone())
+            }
+            EditHistoryMetadata::ChangePinned { previous_value, .. } => {
+                update.pinned = Some(*previous_value)
+            }
+            // Rejected updates made no changes, other types don't apply to folders
+            EditHistoryMetadata::LinkValue { .. } | EditHistoryMetadata::UpdateConflict { .. } => {
+                return None;
+            }
+        }
+
+        Some(update)
+    }
 }
 
 pub async fn update_folder(
@@ -59,7 +97,7 @@ pub async fn update_folder(
     folder: Folder,
     user_id: Option<String>,
     update: UpdateFolder,
-) -> Result<(), UpdateFolderError> {
+) -> Result<i64, UpdateFolderError> {
     let mut folder = folder;
 
     let mut folder_id = folder
@@ -72,6 +110,36 @@ pub async fn update_folder(
         .await
         .inspect_err(|error| tracing::error!(?error, "failed to begin transaction"))?;
 
+    // Lock the folder and ensure it was not modified since the expected version
+    let current_version = Folder::lock_version(db.deref_mut(), folder.id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to lock folder version"))?;
+
+    if let Some(expected_version) = update.expected_version
+        && expected_version != current_version
+    {
+        // Record the rejected update
+        add_edit_history(
+            &mut db,
+            user_id,
+            folder.id,
+            EditHistoryMetadata::UpdateConflict {
+                expected_version,
+                current_version,
+            },
+        )
+        .await?;
+
+        db.commit()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to commit transaction"))?;
+
+        return Err(UpdateFolderError::VersionConflict {
+            expected_version,
+            current_version,
+        });
+    }
+
     if let Some(target_id) = update.folder_id {
         // Cannot move folder into itself
         if target_id == folder.id {
@@ -96,7 +164,7 @@ pub async fn update_folder(
             return Err(UpdateFolderError::CannotMoveIntoChildOfSelf);
         }
 
-        folder_id = target_folder.folder.id;
+        let target_folder_id = target_folder.folder.id;
 
         folder = move_folder(
             &mut db,
@@ -107,6 +175,8 @@ pub async fn update_folder(
         )
         .await
         .inspect_err(|error| tracing::error!(?error, "failed to move folder"))?;
+
+        folder_id = target_folder_id;
     };
 
     if let Some(new_name) = update.name {
@@ -121,6 +191,10 @@ pub async fn update_folder(
             .inspect_err(|error| tracing::error!(?error, "failed to update folder pinned state"))?;
     }
 
+    let version = Folder::increment_version(db.deref_mut(), folder.id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to increment folder version"))?;
+
     // Update search index data for the new name and value
     search
         .update_data(
@@ -128,8 +202,10 @@ pub async fn update_folder(
             UpdateSearchIndexData {
                 folder_id,
                 name: folder.name.clone(),
+                pinned: folder.pinned,
                 content: None,
                 pages: None,
+                document_box: None,
             },
         )
         .await
@@ -142,7 +218,7 @@ pub async fn update_folder(
         tracing::error!(?error, "failed to commit transaction");
     })?;
 
-    Ok(())
+    Ok(version)
 }
 
 /// Add a new edit history item for a folder
@@ -2,22 +2,35 @@
 #![recursion_limit = "256"]
 
 pub mod aws;
+pub mod connectors;
+pub mod docbox;
 pub mod document_box;
 pub mod events;
 pub mod files;
 pub mod folders;
 pub mod links;
+pub mod mailbox;
+pub mod mailer;
 pub mod notifications;
 pub mod purge;
+pub mod shutdown;
+pub mod stats;
 pub mod tasks;
 pub mod tenant;
 pub mod utils;
 
+pub use docbox::{Docbox, DocboxBuilder};
+
 /// Re-exports of the docbox-database crate
 pub mod database {
     pub use docbox_database::*;
 }
 
+/// Re-exports of the docbox-mime crate
+pub mod mime {
+    pub use docbox_mime::*;
+}
+
 /// Re-exports of the docbox-processing crate
 pub mod processing {
     pub use docbox_processing::*;
@@ -0,0 +1,233 @@
+//! # Bulk Create Links
+//!
+//! Creates many links within a folder at once (i.e importing browser
+//! bookmarks) and prefetches the website metadata for the created links
+
+use crate::{
+    events::{TenantEventMessage, TenantEventPublisher},
+    links::{
+        create_link::CreateLinkError, generated::persist_link_images, index_link::create_link_index,
+    },
+};
+use docbox_database::{
+    DbPool,
+    models::{
+        document_box::{DocumentBoxScopeRaw, WithScope},
+        document_box_stats::{DocumentBoxStatsCounters, DocumentBoxStatsDelta},
+        folder::Folder,
+        link::{CreateLink as DbCreateLink, Link},
+        tasks::Task,
+        user::UserId,
+    },
+};
+use docbox_search::TenantSearchIndex;
+use docbox_storage::StorageLayer;
+use futures::{StreamExt, stream};
+use serde::Serialize;
+use std::ops::DerefMut;
+
+use super::resolve_website::ResolveWebsiteService;
+
+/// Maximum number of link metadata requests to resolve at once
+const METADATA_PREFETCH_CONCURRENCY: usize = 4;
+
+/// Number of resolved links between each progress report
+const PROGRESS_REPORT_INTERVAL: u64 = 10;
+
+pub struct BulkCreateLinksData {
+    /// Folder to create the links within
+    pub folder: Folder,
+
+    /// Links to create
+    pub links: Vec<BulkCreateLinkItem>,
+
+    /// User creating the links
+    pub created_by: Option<UserId>,
+}
+
+pub struct BulkCreateLinkItem {
+    /// Link name
+    pub name: String,
+
+    /// Link value
+    pub value: String,
+}
+
+/// Progress of prefetching the metadata for bulk created links
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct PrefetchLinksMetadataProgress {
+    /// Total number of links to resolve metadata for
+    pub total: u64,
+    /// Number of links where the metadata was resolved
+    pub resolved: u64,
+    /// Number of links where the metadata could not be resolved
+    pub failed: u64,
+}
+
+/// Safely perform [bulk_create_links] ensuring that if an error occurs
+/// the search index data is properly rolled back
+pub async fn safe_bulk_create_links(
+    db: &DbPool,
+    search: TenantSearchIndex,
+    events: &TenantEventPublisher,
+    create: BulkCreateLinksData,
+) -> Result<Vec<Link>, CreateLinkError> {
+    let mut search_index_links = Vec::new();
+    bulk_create_links(db, &search, events, create, &mut search_index_links)
+        .await
+        .inspect_err(|_| {
+            // Attempt to rollback any allocated resources in the background
+            tokio::spawn(rollback_bulk_create_links(search, search_index_links));
+        })
+}
+
+/// Creates all the links within a single transaction, either all the
+/// links are created or none of them are
+async fn bulk_create_links(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    events: &TenantEventPublisher,
+    create: BulkCreateLinksData,
+    search_index_links: &mut Vec<Link>,
+) -> Result<Vec<Link>, CreateLinkError> {
+    tracing::debug!(count = create.links.len(), "bulk creating links");
+
+    let mut db = db
+        .begin()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to being transaction"))?;
+
+    let mut links = Vec::with_capacity(create.links.len());
+
+    for item in create.links {
+        let link = Link::create(
+            db.deref_mut(),
+            DbCreateLink {
+                name: item.name,
+                value: item.value,
+                folder_id: create.folder.id,
+                created_by: create.created_by.clone(),
+            },
+        )
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to create link"))?;
+
+        links.push(link);
+    }
+
+    DocumentBoxStatsCounters::apply_delta(
+        db.deref_mut(),
+        &create.folder.document_box,
+        DocumentBoxStatsDelta::links(links.len() as i64),
+    )
+    .await
+    .inspect_err(|error| tracing::error!(?error, "failed to update document box stats"))?;
+
+    // Add all the links to the search index at once
+    let index_data = links
+        .iter()
+        .map(|link| create_link_index(link, &create.folder.document_box))
+        .collect();
+
+    search
+        .add_data(index_data)
+        .await
+        .map_err(CreateLinkError::CreateIndex)?;
+    search_index_links.extend(links.iter().cloned());
+
+    db.commit()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to commit transaction"))?;
+
+    // Publish the events
+    for link in &links {
+        events.publish_event(TenantEventMessage::LinkCreated(WithScope::new(
+            link.clone(),
+            create.folder.document_box.clone(),
+        )));
+    }
+
+    Ok(links)
+}
+
+async fn rollback_bulk_create_links(search: TenantSearchIndex, links: Vec<Link>) {
+    // Revert link index data
+    for link in links {
+        if let Err(error) = search.delete_data(link.id).await {
+            tracing::error!(
+                ?error, index_id = %link.id,
+                "failed to rollback bulk created link search index"
+            );
+        }
+    }
+}
+
+/// Resolves the website metadata for each of the provided `links` so
+/// the metadata is cached and the website images are persisted ahead
+/// of time, reporting the progress to the provided `task`
+///
+/// Links that fail to resolve are counted as failed and do not stop
+/// the remaining links from resolving
+pub async fn prefetch_links_metadata(
+    db: &DbPool,
+    storage: &StorageLayer,
+    website_service: &ResolveWebsiteService,
+    links: &[Link],
+    scope: &DocumentBoxScopeRaw,
+    mut task: Option<&mut Task>,
+) -> PrefetchLinksMetadataProgress {
+    let mut progress = PrefetchLinksMetadataProgress {
+        total: links.len() as u64,
+        ..Default::default()
+    };
+
+    report_progress(db, task.as_deref_mut(), &progress).await;
+
+    let mut results =
+        stream::iter(links.iter().cloned())
+            .map(|link| async move {
+                persist_link_images(db, storage, website_service, &link, scope).await
+            })
+            .buffer_unordered(METADATA_PREFETCH_CONCURRENCY);
+
+    while let Some(result) = results.next().await {
+        match result {
+            Ok(_) => progress.resolved += 1,
+            Err(_) => progress.failed += 1,
+        }
+
+        if (progress.resolved + progress.failed).is_multiple_of(PROGRESS_REPORT_INTERVAL) {
+            report_progress(db, task.as_deref_mut(), &progress).await;
+        }
+    }
+
+    report_progress(db, task, &progress).await;
+    progress
+}
+
+/// Store the current `progress` against the `task`, failing to report
+/// progress does not fail the prefetch
+async fn report_progress(
+    db: &DbPool,
+    task: Option<&mut Task>,
+    progress: &PrefetchLinksMetadataProgress,
+) {
+    let Some(task) = task else {
+        return;
+    };
+
+    let progress = match serde_json::to_value(progress) {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::error!(
+                ?error,
+                "failed to serialize link metadata prefetch progress"
+            );
+            return;
+        }
+    };
+
+    if let Err(error) = task.set_progress(db, progress).await {
+        tracing::warn!(?error, "failed to report link metadata prefetch progress");
+    }
+}
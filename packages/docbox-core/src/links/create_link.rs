@@ -6,6 +6,7 @@ use docbox_database::{
     DbErr, DbPool,
     models::{
         document_box::WithScope,
+        document_box_stats::{DocumentBoxStatsCounters, DocumentBoxStatsDelta},
         folder::Folder,
         link::{CreateLink as DbCreateLink, Link},
         user::UserId,
@@ -93,6 +94,14 @@ async fn create_link(
     .await
     .inspect_err(|error| tracing::error!(?error, "failed to create link"))?;
 
+    DocumentBoxStatsCounters::apply_delta(
+        db.deref_mut(),
+        &create.folder.document_box,
+        DocumentBoxStatsDelta::links(1),
+    )
+    .await
+    .inspect_err(|error| tracing::error!(?error, "failed to update document box stats"))?;
+
     // Add link to search index
     store_link_index(search, &link, &create.folder.document_box).await?;
     create_state.search_index_files.push(link.id);
@@ -1,12 +1,18 @@
-use crate::events::{TenantEventMessage, TenantEventPublisher};
+use crate::{
+    document_box::document_box_stats::safe_apply_document_box_stats_delta,
+    events::{TenantEventMessage, TenantEventPublisher},
+    links::generated::{PersistLinkImagesError, delete_link_generated_files},
+};
 use docbox_database::{
     DbErr, DbPool,
     models::{
         document_box::{DocumentBoxScopeRaw, WithScope},
+        document_box_stats::DocumentBoxStatsDelta,
         link::Link,
     },
 };
 use docbox_search::{SearchError, TenantSearchIndex};
+use docbox_storage::StorageLayer;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -15,16 +21,24 @@ pub enum DeleteLinkError {
     Database(#[from] DbErr),
     #[error(transparent)]
     Search(SearchError),
+    #[error("failed to delete link generated files: {0}")]
+    DeleteGeneratedFiles(PersistLinkImagesError),
 }
 
 #[tracing::instrument(skip_all, fields(%scope, link_id = %link.id))]
 pub async fn delete_link(
     db: &DbPool,
+    storage: &StorageLayer,
     search: &TenantSearchIndex,
     events: &TenantEventPublisher,
     link: Link,
     scope: DocumentBoxScopeRaw,
 ) -> Result<(), DeleteLinkError> {
+    // Delete the persisted website images
+    delete_link_generated_files(db, storage, link.id)
+        .await
+        .map_err(DeleteLinkError::DeleteGeneratedFiles)?;
+
     // Delete the indexed file contents
     search
         .delete_data(link.id)
@@ -42,6 +56,8 @@ pub async fn delete_link(
         return Ok(());
     }
 
+    safe_apply_document_box_stats_delta(db, &scope, DocumentBoxStatsDelta::links(-1)).await;
+
     // Publish an event
     events.publish_event(TenantEventMessage::LinkDeleted(WithScope::new(link, scope)));
 
@@ -0,0 +1,273 @@
+//! # Link Generated Files
+//!
+//! Resolves the favicon and social image for the website a link points to
+//! and persists them to storage, so they can be served without scraping
+//! the website on every request
+
+use crate::{
+    links::{
+        get_link_metadata::{GetLinkMetadataError, get_link_metadata},
+        resolve_website::ResolveWebsiteService,
+    },
+    utils::file::{StoredObjectType, create_object_tags},
+};
+use bytes::{Bytes, BytesMut};
+use chrono::Utc;
+use docbox_database::{
+    DbErr, DbPool,
+    models::{
+        document_box::DocumentBoxScopeRaw,
+        link::{Link, LinkId},
+        link_generated_file::{CreateLinkGeneratedFile, LinkGeneratedFile, LinkGeneratedFileType},
+    },
+};
+use docbox_mime::get_mime_ext;
+use docbox_storage::{StorageLayer, StorageLayerError, UploadFileOptions};
+use docbox_web_scraper::ResolvedImage;
+use futures::StreamExt;
+use mime::Mime;
+use std::ops::DerefMut;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Maximum size in bytes of an image that will be persisted for a link,
+/// larger images are skipped
+pub const MAX_LINK_IMAGE_SIZE: usize = 5 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum PersistLinkImagesError {
+    /// Failed to resolve the website metadata
+    #[error(transparent)]
+    Metadata(#[from] GetLinkMetadataError),
+
+    /// Database error
+    #[error(transparent)]
+    Database(#[from] DbErr),
+
+    /// Failed to store the image
+    #[error("failed to store link image: {0}")]
+    Storage(StorageLayerError),
+}
+
+/// Create a unique file key for a file generated from a link
+pub fn create_link_generated_file_key(
+    document_box: &DocumentBoxScopeRaw,
+    link_id: LinkId,
+    mime: &Mime,
+) -> String {
+    // Mapped file extensions for the generated type
+    let file_ext = get_mime_ext(mime).unwrap_or("bin");
+
+    // Generate a unique file key
+    let file_key = Uuid::new_v4().to_string();
+
+    format!("{document_box}/links/{link_id}_{file_key}.generated.{file_ext}")
+}
+
+/// Resolve and persist the favicon and social image for a `link`,
+/// replacing any previously persisted images
+///
+/// Images that the website does not provide or that fail to download
+/// are skipped, previously persisted images of that type are kept
+#[tracing::instrument(skip_all, fields(%scope, link_id = %link.id))]
+pub async fn persist_link_images(
+    db: &DbPool,
+    storage: &StorageLayer,
+    website_service: &ResolveWebsiteService,
+    link: &Link,
+    scope: &DocumentBoxScopeRaw,
+) -> Result<Vec<LinkGeneratedFile>, PersistLinkImagesError> {
+    let (url, metadata) = get_link_metadata(db, website_service, link).await?;
+
+    // Track when the metadata for the link was fetched
+    if let Err(error) = Link::set_fetched_at(link.clone(), db, Utc::now()).await {
+        tracing::error!(?error, "failed to set link fetched at");
+    }
+
+    let mut persisted = Vec::new();
+
+    if let Some(favicon) = website_service
+        .service
+        .resolve_favicon(&url, metadata.best_favicon)
+        .await
+        && let Some(file) = persist_link_image(
+            db,
+            storage,
+            link,
+            scope,
+            LinkGeneratedFileType::Favicon,
+            favicon,
+        )
+        .await?
+    {
+        persisted.push(file);
+    }
+
+    if let Some(og_image) = metadata.og_image
+        && let Some(image) = website_service.service.resolve_image(&url, &og_image).await
+        && let Some(file) = persist_link_image(
+            db,
+            storage,
+            link,
+            scope,
+            LinkGeneratedFileType::SocialImage,
+            image,
+        )
+        .await?
+    {
+        persisted.push(file);
+    }
+
+    Ok(persisted)
+}
+
+/// Persist a single resolved `image` as the generated file of type `ty`
+/// for the `link`. Returns [None] if the image could not be downloaded
+async fn persist_link_image(
+    db: &DbPool,
+    storage: &StorageLayer,
+    link: &Link,
+    scope: &DocumentBoxScopeRaw,
+    ty: LinkGeneratedFileType,
+    image: ResolvedImage,
+) -> Result<Option<LinkGeneratedFile>, PersistLinkImagesError> {
+    let Some(bytes) = collect_image_bytes(image).await else {
+        return Ok(None);
+    };
+
+    let ResolvedImageBytes {
+        content_type,
+        bytes,
+    } = bytes;
+
+    let file_key = create_link_generated_file_key(scope, link.id, &content_type);
+
+    storage
+        .upload_file(
+            &file_key,
+            bytes,
+            UploadFileOptions {
+                content_type: content_type.to_string(),
+                object_tags: create_object_tags(scope, StoredObjectType::LinkGeneratedFile),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to upload link image");
+            PersistLinkImagesError::Storage(error)
+        })?;
+
+    let result = replace_link_generated_file(
+        db,
+        scope,
+        CreateLinkGeneratedFile {
+            id: Uuid::new_v4(),
+            link_id: link.id,
+            mime: content_type.to_string(),
+            ty,
+            file_key: file_key.clone(),
+            created_at: Utc::now(),
+        },
+    )
+    .await;
+
+    let (file, previous) = match result {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::error!(?error, "failed to store link generated file");
+
+            // Remove the uploaded image that is no longer referenced
+            if let Err(error) = storage.delete_file(&file_key).await {
+                tracing::error!(?error, "failed to rollback uploaded link image");
+            }
+
+            return Err(error.into());
+        }
+    };
+
+    // Remove the replaced image from storage
+    if let Some(previous) = previous
+        && let Err(error) = storage.delete_file(&previous.file_key).await
+    {
+        tracing::error!(?error, "failed to delete replaced link image");
+    }
+
+    Ok(Some(file))
+}
+
+/// Replaces the existing generated file of the same type for the link with
+/// a new generated file, returning the new file and the replaced file
+async fn replace_link_generated_file(
+    db: &DbPool,
+    scope: &DocumentBoxScopeRaw,
+    create: CreateLinkGeneratedFile,
+) -> Result<(LinkGeneratedFile, Option<LinkGeneratedFile>), DbErr> {
+    let mut t = db.begin().await?;
+
+    let previous = LinkGeneratedFile::find(t.deref_mut(), scope, create.link_id, create.ty).await?;
+    if let Some(previous) = previous.as_ref() {
+        previous.delete(t.deref_mut()).await?;
+    }
+
+    let file = LinkGeneratedFile::create(t.deref_mut(), create).await?;
+
+    t.commit().await?;
+
+    Ok((file, previous))
+}
+
+/// Removes all the generated files for a link from storage, the database
+/// records are removed alongside the link itself
+pub async fn delete_link_generated_files(
+    db: &DbPool,
+    storage: &StorageLayer,
+    link_id: LinkId,
+) -> Result<(), PersistLinkImagesError> {
+    let generated = LinkGeneratedFile::find_all(db, link_id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query link generated files"))?;
+
+    for file in generated {
+        storage.delete_file(&file.file_key).await.map_err(|error| {
+            tracing::error!(?error, "failed to delete link generated file from storage");
+            PersistLinkImagesError::Storage(error)
+        })?;
+    }
+
+    Ok(())
+}
+
+struct ResolvedImageBytes {
+    content_type: Mime,
+    bytes: Bytes,
+}
+
+/// Collect the bytes of a resolved image, images that fail to download
+/// or exceed [MAX_LINK_IMAGE_SIZE] are skipped
+async fn collect_image_bytes(image: ResolvedImage) -> Option<ResolvedImageBytes> {
+    let ResolvedImage {
+        content_type,
+        mut stream,
+    } = image;
+
+    let mut bytes = BytesMut::new();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk
+            .inspect_err(|error| tracing::warn!(?error, "failed to download link image"))
+            .ok()?;
+
+        if bytes.len() + chunk.len() > MAX_LINK_IMAGE_SIZE {
+            tracing::warn!("link image exceeds maximum size, skipping");
+            return None;
+        }
+
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Some(ResolvedImageBytes {
+        content_type,
+        bytes: bytes.freeze(),
+    })
+}
@@ -11,7 +11,19 @@ pub async fn store_link_index(
     link: &Link,
     scope: &DocumentBoxScopeRaw,
 ) -> Result<(), CreateLinkError> {
-    let index = SearchIndexData {
+    let index = create_link_index(link, scope);
+
+    search
+        .add_data(vec![index])
+        .await
+        .map_err(CreateLinkError::CreateIndex)?;
+
+    Ok(())
+}
+
+/// Create the search index data for a link
+pub fn create_link_index(link: &Link, scope: &DocumentBoxScopeRaw) -> SearchIndexData {
+    SearchIndexData {
         ty: SearchIndexType::Link,
         item_id: link.id,
         folder_id: link.folder_id,
@@ -19,15 +31,11 @@ pub async fn store_link_index(
         mime: None,
         content: Some(link.value.clone()),
         pages: None,
+        summary: None,
+        text_stats: None,
         created_at: link.created_at,
         created_by: link.created_by.clone(),
+        pinned: link.pinned,
         document_box: scope.clone(),
-    };
-
-    search
-        .add_data(vec![index])
-        .await
-        .map_err(CreateLinkError::CreateIndex)?;
-
-    Ok(())
+    }
 }
@@ -1,6 +1,9 @@
+pub mod bulk_create_links;
 pub mod create_link;
 pub mod delete_link;
+pub mod generated;
 pub mod get_link_metadata;
 pub mod index_link;
+pub mod refresh_link_metadata;
 pub mod resolve_website;
 pub mod update_link;
@@ -0,0 +1,51 @@
+//! # Refresh Link Metadata
+//!
+//! Forces the website metadata for a link to be resolved again, replacing
+//! the stored metadata and marking when the metadata was fetched
+
+use crate::links::resolve_website::ResolveWebsiteService;
+use chrono::Utc;
+use docbox_database::{DbErr, DbPool, models::link::Link};
+use docbox_web_scraper::ResolvedWebsiteMetadata;
+use thiserror::Error;
+use url::Url;
+
+#[derive(Debug, Error)]
+pub enum RefreshLinkMetadataError {
+    #[error("failed to parse link url")]
+    ParseUrl(#[from] url::ParseError),
+
+    #[error("failed to resolve website metadata")]
+    FailedResolve,
+
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+/// Re-resolve the website metadata for the provided `link` bypassing any
+/// stored metadata, returns the link with its updated fetch timestamp
+/// alongside the refreshed metadata
+#[tracing::instrument(skip_all, fields(link_id = %link.id))]
+pub async fn refresh_link_metadata(
+    db: &DbPool,
+    website_service: &ResolveWebsiteService,
+    link: Link,
+) -> Result<(Link, ResolvedWebsiteMetadata), RefreshLinkMetadataError> {
+    let url = Url::parse(&link.value)
+        .inspect_err(|error| tracing::warn!(?error, "failed to parse link website"))?;
+
+    let resolved = website_service
+        .refresh_website(db, &url)
+        .await
+        .ok_or_else(|| {
+            tracing::warn!("failed to refresh link site metadata");
+            RefreshLinkMetadataError::FailedResolve
+        })?;
+
+    let link = link
+        .set_fetched_at(db, Utc::now())
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to set link fetched at"))?;
+
+    Ok((link, resolved))
+}
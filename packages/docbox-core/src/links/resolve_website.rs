@@ -1,4 +1,4 @@
-use chrono::{TimeDelta, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 use docbox_database::{
     DbPool,
     models::link_resolved_metadata::{
@@ -135,6 +135,39 @@ impl ResolveWebsiteService {
         resolved
     }
 
+    /// Resolves the metadata for the website at the provided URL bypassing
+    /// any existing stored metadata, the stored metadata is replaced with
+    /// the newly resolved metadata
+    pub async fn refresh_website(&self, db: &DbPool, url: &Url) -> Option<ResolvedWebsiteMetadata> {
+        // Acquire lock before attempting to resolve
+        let lock = self.locks.acquire(url).await;
+        let _guard = lock.lock().await;
+
+        // Resolve the metadata
+        let resolved = self.service.resolve_website(url).await;
+        if let Some(resolved) = resolved.as_ref() {
+            // Persist the resolved metadata to the database
+            self.persist_resolved_metadata(db, url.as_str(), resolved)
+                .await;
+        }
+
+        self.locks.remove(url).await;
+        resolved
+    }
+
+    /// Checks whether metadata fetched at `fetched_at` is older than the
+    /// metadata cache duration, metadata that was never fetched is stale
+    pub fn is_metadata_stale(&self, fetched_at: Option<DateTime<Utc>>) -> bool {
+        let Some(fetched_at) = fetched_at else {
+            return true;
+        };
+
+        match fetched_at.checked_add_signed(self.config.metadata_cache_duration) {
+            Some(expires_at) => expires_at <= Utc::now(),
+            None => false,
+        }
+    }
+
     /// Query the database for resolved link metadata
     async fn resolve_website_db(&self, db: &DbPool, url: &Url) -> Option<ResolvedWebsiteMetadata> {
         if let Some(resolved) = LinkResolvedMetadata::query(db, url.as_str())
@@ -24,11 +24,19 @@ pub enum UpdateLinkError {
     #[error("unknown target folder")]
     UnknownTargetFolder,
 
+    /// Link was modified since the version the update expected
+    #[error("link was modified by another request")]
+    VersionConflict {
+        expected_version: i64,
+        current_version: i64,
+    },
+
     /// Failed to update the search index
     #[error(transparent)]
     SearchIndex(SearchError),
 }
 
+#[derive(Default)]
 pub struct UpdateLink {
     /// Move the link to another folder
     pub folder_id: Option<FolderId>,
@@ -41,6 +49,36 @@ pub struct UpdateLink {
 
     /// Update the pinned state
     pub pinned: Option<bool>,
+
+    /// Version the link is expected to be at, the update is rejected
+    /// when the link has been modified since this version
+    pub expected_version: Option<i64>,
+}
+
+impl UpdateLink {
+    /// Create an update that reverts the change recorded by an edit
+    /// history entry, [None] if the change cannot be reverted
+    pub fn revert(metadata: &EditHistoryMetadata) -> Option<UpdateLink> {
+        let mut update = UpdateLink::default();
+
+        match metadata {
+            EditHistoryMetadata::MoveToFolder { original_id, .. } => {
+                update.folder_id = Some(*original_id)
+            }
+            EditHistoryMetadata::Rename { original_name, .. } => {
+                update.name = Some(original_name.clone())
+            }
+            EditHistoryMetadata::ChangePinned { previous_value, .. } => {
+                update.pinned = Some(*previous_value)
+            }
+            EditHistoryMetadata::LinkValue { previous_value, .. } => {
+                update.value = Some(previous_value.clone())
+            }
+            EditHistoryMetadata::UpdateConflict { .. } => return None,
+        }
+
+        Some(update)
+    }
 }
 
 pub async fn update_link(
@@ -50,7 +88,7 @@ pub async fn update_link(
     link: Link,
     user_id: Option<String>,
     update: UpdateLink,
-) -> Result<(), UpdateLinkError> {
+) -> Result<i64, UpdateLinkError> {
     let mut link = link;
 
     let mut db = db
@@ -58,6 +96,36 @@ pub async fn update_link(
         .await
         .inspect_err(|error| tracing::error!(?error, "failed to begin transaction"))?;
 
+    // Lock the link and ensure it was not modified since the expected version
+    let current_version = Link::lock_version(db.deref_mut(), link.id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to lock link version"))?;
+
+    if let Some(expected_version) = update.expected_version
+        && expected_version != current_version
+    {
+        // Record the rejected update
+        add_edit_history(
+            &mut db,
+            user_id,
+            link.id,
+            EditHistoryMetadata::UpdateConflict {
+                expected_version,
+                current_version,
+            },
+        )
+        .await?;
+
+        db.commit()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to commit transaction"))?;
+
+        return Err(UpdateLinkError::VersionConflict {
+            expected_version,
+            current_version,
+        });
+    }
+
     if let Some(target_id) = update.folder_id {
         // Ensure the target folder exists, also ensures the target folder is in the same scope
         // (We may allow across scopes in the future, but would need additional checks for access control of target scope)
@@ -89,6 +157,10 @@ pub async fn update_link(
             .inspect_err(|error| tracing::error!(?error, "failed to update link pinned state"))?;
     }
 
+    let version = Link::increment_version(db.deref_mut(), link.id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to increment link version"))?;
+
     // Update search index data for the new name and value
     search
         .update_data(
@@ -96,8 +168,10 @@ pub async fn update_link(
             UpdateSearchIndexData {
                 folder_id: link.folder_id,
                 name: link.name.clone(),
+                pinned: link.pinned,
                 content: Some(link.value.clone()),
                 pages: None,
+                document_box: None,
             },
         )
         .await
@@ -108,7 +182,7 @@ pub async fn update_link(
         .await
         .inspect_err(|error| tracing::error!(?error, "failed to commit transaction"))?;
 
-    Ok(())
+    Ok(version)
 }
 
 /// Add a new edit history item for a link
@@ -0,0 +1,290 @@
+//! # IMAP
+//!
+//! Minimal IMAP4rev1 client supporting the commands required to ingest
+//! messages from a mailbox (LOGIN, SELECT, UID SEARCH, UID FETCH, LOGOUT)
+
+use bytes::Bytes;
+use rustls::{ClientConfig, RootCertStore, pki_types::ServerName};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+use tokio_rustls::TlsConnector;
+
+/// Maximum length of a single response line (Excluding literals)
+const MAX_LINE_LENGTH: u64 = 64 * 1024;
+
+/// Maximum size of a literal within a response (i.e a message body)
+pub const MAX_LITERAL_SIZE: usize = 100 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum ImapError {
+    #[error("imap connection error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid imap server name")]
+    InvalidServerName,
+
+    #[error("failed to configure tls: {0}")]
+    Tls(rustls::Error),
+
+    #[error("imap server closed the connection")]
+    Closed,
+
+    #[error("invalid imap response: {0}")]
+    InvalidResponse(String),
+
+    #[error("imap command failed: {0}")]
+    Command(String),
+
+    #[error("imap response exceeds the maximum size")]
+    TooLarge,
+
+    #[error("imap argument contains invalid characters")]
+    InvalidArgument,
+}
+
+trait ImapStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> ImapStream for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+/// Untagged response from the server
+#[derive(Debug, Default)]
+pub struct ImapResponse {
+    /// Text of the response, literals are replaced by their `{size}` marker
+    pub line: String,
+    /// Literals contained within the response
+    pub literals: Vec<Bytes>,
+}
+
+pub struct ImapClient {
+    stream: BufReader<Box<dyn ImapStream>>,
+    next_tag: u32,
+}
+
+impl ImapClient {
+    /// Connect to the IMAP server at `host`:`port` using implicit TLS
+    /// when `tls` is enabled
+    pub async fn connect(host: &str, port: u16, tls: bool) -> Result<Self, ImapError> {
+        let stream = TcpStream::connect((host, port)).await?;
+
+        let stream: Box<dyn ImapStream> = if tls {
+            let roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+            let config = ClientConfig::builder_with_provider(Arc::new(
+                rustls::crypto::aws_lc_rs::default_provider(),
+            ))
+            .with_safe_default_protocol_versions()
+            .map_err(ImapError::Tls)?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+            let server_name =
+                ServerName::try_from(host.to_string()).map_err(|_| ImapError::InvalidServerName)?;
+
+            let stream = TlsConnector::from(Arc::new(config))
+                .connect(server_name, stream)
+                .await?;
+
+            Box::new(stream)
+        } else {
+            Box::new(stream)
+        };
+
+        let mut client = Self {
+            stream: BufReader::new(stream),
+            next_tag: 1,
+        };
+
+        let greeting = client.read_line().await?;
+        if !greeting.starts_with("* OK") && !greeting.starts_with("* PREAUTH") {
+            return Err(ImapError::InvalidResponse(greeting));
+        }
+
+        Ok(client)
+    }
+
+    /// Authenticate using the `username` and `password`
+    pub async fn login(&mut self, username: &str, password: &str) -> Result<(), ImapError> {
+        let command = format!("LOGIN {} {}", quote(username)?, quote(password)?);
+        self.command(&command).await?;
+        Ok(())
+    }
+
+    /// Select the `mailbox`, provides the UIDVALIDITY of the mailbox
+    pub async fn select(&mut self, mailbox: &str) -> Result<u32, ImapError> {
+        let responses = self.command(&format!("SELECT {}", quote(mailbox)?)).await?;
+
+        responses
+            .iter()
+            .find_map(|response| parse_uid_validity(&response.line))
+            .ok_or_else(|| ImapError::InvalidResponse("missing UIDVALIDITY".to_string()))
+    }
+
+    /// Find the UIDs of messages with a UID greater than `last_uid`, in
+    /// ascending order
+    pub async fn uid_search_after(&mut self, last_uid: u32) -> Result<Vec<u32>, ImapError> {
+        let start = last_uid.saturating_add(1);
+        let responses = self.command(&format!("UID SEARCH UID {start}:*")).await?;
+
+        let mut uids: Vec<u32> = responses
+            .iter()
+            .filter_map(|response| response.line.strip_prefix("* SEARCH"))
+            .flat_map(|values| values.split_ascii_whitespace())
+            .filter_map(|value| value.parse::<u32>().ok())
+            // "n:*" always includes the highest UID even when below n
+            .filter(|uid| *uid > last_uid)
+            .collect();
+
+        uids.sort_unstable();
+        uids.dedup();
+        Ok(uids)
+    }
+
+    /// Fetch the full raw contents of the message with the provided `uid`
+    /// without marking the message as seen
+    pub async fn uid_fetch(&mut self, uid: u32) -> Result<Option<Bytes>, ImapError> {
+        let responses = self
+            .command(&format!("UID FETCH {uid} (BODY.PEEK[])"))
+            .await?;
+
+        Ok(responses
+            .into_iter()
+            .filter(|response| response.line.contains("FETCH"))
+            .find_map(|response| response.literals.into_iter().next()))
+    }
+
+    /// End the session
+    pub async fn logout(mut self) -> Result<(), ImapError> {
+        self.command("LOGOUT").await?;
+        Ok(())
+    }
+
+    /// Send a command and collect the untagged responses until the tagged
+    /// completion response, fails when the command does not complete with OK
+    async fn command(&mut self, command: &str) -> Result<Vec<ImapResponse>, ImapError> {
+        let tag = format!("A{}", self.next_tag);
+        self.next_tag += 1;
+
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(format!("{tag} {command}\r\n").as_bytes())
+            .await?;
+        stream.flush().await?;
+
+        let mut responses = Vec::new();
+
+        loop {
+            let response = self.read_response().await?;
+
+            if let Some(status) = response.line.strip_prefix(&tag) {
+                let status = status.trim_start();
+                if status.starts_with("OK") {
+                    return Ok(responses);
+                }
+
+                return Err(ImapError::Command(status.to_string()));
+            }
+
+            responses.push(response);
+        }
+    }
+
+    /// Read a complete response including any literals
+    async fn read_response(&mut self) -> Result<ImapResponse, ImapError> {
+        let mut response = ImapResponse::default();
+
+        loop {
+            let line = self.read_line().await?;
+            response.line.push_str(&line);
+
+            let Some(size) = literal_size(&line) else {
+                return Ok(response);
+            };
+
+            if size > MAX_LITERAL_SIZE {
+                return Err(ImapError::TooLarge);
+            }
+
+            let mut literal = vec![0; size];
+            self.stream.read_exact(&mut literal).await?;
+            response.literals.push(Bytes::from(literal));
+        }
+    }
+
+    /// Read a single line excluding the trailing CRLF
+    async fn read_line(&mut self) -> Result<String, ImapError> {
+        let mut line = Vec::new();
+        let read = (&mut self.stream)
+            .take(MAX_LINE_LENGTH)
+            .read_until(b'\n', &mut line)
+            .await?;
+
+        if read == 0 {
+            return Err(ImapError::Closed);
+        }
+
+        if !line.ends_with(b"\n") {
+            return Err(ImapError::TooLarge);
+        }
+
+        let line = String::from_utf8_lossy(&line);
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+/// Quote a string argument, fails for values that cannot be quoted
+fn quote(value: &str) -> Result<String, ImapError> {
+    if value.contains(['\r', '\n', '\0']) {
+        return Err(ImapError::InvalidArgument);
+    }
+
+    Ok(format!(
+        "\"{}\"",
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+/// Get the size of the literal that follows a line ending in `{size}`
+fn literal_size(line: &str) -> Option<usize> {
+    let value = line.strip_suffix('}')?;
+    let (_, size) = value.rsplit_once('{')?;
+    // Non-synchronizing literals (LITERAL+) are not sent by servers
+    size.parse().ok()
+}
+
+/// Parse the UIDVALIDITY from a "* OK [UIDVALIDITY n]" response
+fn parse_uid_validity(line: &str) -> Option<u32> {
+    let (_, value) = line.split_once("[UIDVALIDITY ")?;
+    let (value, _) = value.split_once(']')?;
+    value.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{literal_size, parse_uid_validity, quote};
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("user").unwrap(), "\"user\"");
+        assert_eq!(quote("pa\"ss\\").unwrap(), "\"pa\\\"ss\\\\\"");
+        assert!(quote("pass\r\nA2 LOGOUT").is_err());
+    }
+
+    #[test]
+    fn test_literal_size() {
+        assert_eq!(literal_size("* 1 FETCH (UID 5 BODY[] {342}"), Some(342));
+        assert_eq!(literal_size("* 1 FETCH (UID 5 FLAGS (\\Seen))"), None);
+        assert_eq!(literal_size("* OK {abc}"), None);
+    }
+
+    #[test]
+    fn test_parse_uid_validity() {
+        assert_eq!(
+            parse_uid_validity("* OK [UIDVALIDITY 3857529045] UIDs valid"),
+            Some(3857529045)
+        );
+        assert_eq!(parse_uid_validity("* 172 EXISTS"), None);
+    }
+}
@@ -0,0 +1,286 @@
+//! # Mailbox
+//!
+//! Ingestion of emails from IMAP mailboxes into document boxes. Each configured
+//! mailbox is polled for new messages which are uploaded as `.eml` files and
+//! processed through the standard email processing pipeline (metadata, content
+//! and attachments).
+//!
+//! The UID of the last ingested message is stored per mailbox so that each
+//! message is only ingested once. Files are created with an ID derived from the
+//! mailbox, UIDVALIDITY and UID of the message, a message that was uploaded
+//! before its state could be stored is detected and skipped on the next poll
+
+use crate::{
+    docbox::{Docbox, DocboxError},
+    files::upload_file::UploadFile,
+    shutdown::ShutdownCoordinator,
+};
+use chrono::Utc;
+use docbox_database::{
+    DbErr,
+    models::{
+        document_box::DocumentBoxScopeRaw,
+        file::{File, FileId},
+        folder::{Folder, FolderId},
+        imap_mailbox_state::ImapMailboxState,
+        tenant::TenantId,
+    },
+};
+use docbox_processing::email::email_file_name;
+use docbox_secrets::{Secret, SecretManager, SecretManagerError};
+use imap::{ImapClient, ImapError};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use thiserror::Error;
+
+pub mod imap;
+
+/// Default time between polls of the mailboxes
+const DEFAULT_POLL_INTERVAL_SECONDS: u64 = 60;
+
+/// Maximum number of messages ingested from a mailbox in a single poll
+const MAX_MESSAGES_PER_POLL: usize = 100;
+
+fn default_poll_interval_seconds() -> u64 {
+    DEFAULT_POLL_INTERVAL_SECONDS
+}
+
+fn default_port() -> u16 {
+    993
+}
+
+fn default_tls() -> bool {
+    true
+}
+
+fn default_mailbox() -> String {
+    "INBOX".to_string()
+}
+
+/// Configuration for IMAP mailbox ingestion
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImapIngestConfig {
+    /// Time in seconds between polls of the mailboxes
+    #[serde(default = "default_poll_interval_seconds")]
+    pub poll_interval_seconds: u64,
+
+    /// Mailboxes to ingest messages from
+    #[serde(default)]
+    pub mailboxes: Vec<ImapMailboxConfig>,
+}
+
+/// Mailbox to ingest messages from and the document box to store them in
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImapMailboxConfig {
+    /// Host of the IMAP server
+    pub host: String,
+    /// Port of the IMAP server
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Whether to connect using implicit TLS
+    #[serde(default = "default_tls")]
+    pub tls: bool,
+    /// Username to login with
+    pub username: String,
+    /// Name of the secret containing the password to login with
+    pub password_secret_name: String,
+    /// Name of the mailbox to ingest from
+    #[serde(default = "default_mailbox")]
+    pub mailbox: String,
+    /// Environment of the tenant to store the messages in
+    pub env: String,
+    /// ID of the tenant to store the messages in
+    pub tenant_id: TenantId,
+    /// Scope of the document box to store the messages in
+    pub scope: DocumentBoxScopeRaw,
+    /// Folder to store the messages in, defaults to the document box root folder
+    #[serde(default)]
+    pub folder_id: Option<FolderId>,
+}
+
+impl ImapMailboxConfig {
+    /// Identifier for the mailbox used to track its ingestion state
+    pub fn mailbox_id(&self) -> String {
+        format!(
+            "imap://{}@{}:{}/{}",
+            self.username, self.host, self.port, self.mailbox
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MailboxPollError {
+    #[error(transparent)]
+    Docbox(#[from] DocboxError),
+
+    #[error(transparent)]
+    Database(#[from] DbErr),
+
+    #[error(transparent)]
+    Imap(#[from] ImapError),
+
+    #[error("failed to load mailbox password: {0}")]
+    Secret(SecretManagerError),
+
+    #[error("mailbox password secret is missing")]
+    MissingPassword,
+
+    #[error("document box not found")]
+    UnknownDocumentBox,
+}
+
+/// Poll the configured mailboxes for new messages until shutdown is requested
+pub async fn process_imap_mailboxes(
+    config: ImapIngestConfig,
+    docbox: Docbox,
+    secrets: SecretManager,
+    shutdown: ShutdownCoordinator,
+) {
+    let interval = Duration::from_secs(config.poll_interval_seconds);
+
+    loop {
+        for mailbox in &config.mailboxes {
+            if shutdown.is_shutting_down() {
+                return;
+            }
+
+            match poll_mailbox(&docbox, &secrets, mailbox).await {
+                Ok(0) => {}
+                Ok(ingested) => {
+                    tracing::debug!(mailbox_id = %mailbox.mailbox_id(), ingested, "ingested mailbox messages")
+                }
+                Err(error) => {
+                    tracing::error!(?error, mailbox_id = %mailbox.mailbox_id(), "failed to poll mailbox")
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = shutdown.cancelled() => return,
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+}
+
+/// Ingest new messages from a single mailbox, provides the number of
+/// messages that were ingested
+pub async fn poll_mailbox(
+    docbox: &Docbox,
+    secrets: &SecretManager,
+    config: &ImapMailboxConfig,
+) -> Result<usize, MailboxPollError> {
+    let mailbox_id = config.mailbox_id();
+    let tenant = docbox.tenant(config.env.clone(), config.tenant_id).await?;
+    let db = tenant.db();
+
+    let folder_id = match config.folder_id {
+        Some(folder_id) => folder_id,
+        None => {
+            Folder::find_root(db, &config.scope)
+                .await?
+                .ok_or(MailboxPollError::UnknownDocumentBox)?
+                .id
+        }
+    };
+
+    let password = match secrets
+        .get_secret(&config.password_secret_name)
+        .await
+        .map_err(MailboxPollError::Secret)?
+        .ok_or(MailboxPollError::MissingPassword)?
+    {
+        Secret::String(value) => value,
+        Secret::Binary(value) => String::from_utf8_lossy(&value).to_string(),
+    };
+
+    let mut client = ImapClient::connect(&config.host, config.port, config.tls).await?;
+    client.login(&config.username, &password).await?;
+    let uid_validity = client.select(&config.mailbox).await?;
+
+    // UIDs from a previous UIDVALIDITY are not comparable, start from the beginning
+    let last_uid = ImapMailboxState::find(db, &mailbox_id)
+        .await?
+        .filter(|state| state.uid_validity == uid_validity as i64)
+        .map(|state| state.last_uid as u32)
+        .unwrap_or_default();
+
+    let uids = client.uid_search_after(last_uid).await?;
+    let mut ingested = 0;
+
+    for uid in uids.into_iter().take(MAX_MESSAGES_PER_POLL) {
+        let file_id = message_file_id(&mailbox_id, uid_validity, uid);
+
+        // Message was uploaded before its state was stored
+        let exists = File::find(db, &config.scope, file_id).await?.is_some();
+
+        if !exists && let Some(message) = client.uid_fetch(uid).await? {
+            tenant
+                .upload_file(UploadFile {
+                    fixed_id: Some(file_id),
+                    parent_id: None,
+                    folder_id,
+                    document_box: config.scope.clone(),
+                    name: email_file_name(&message),
+                    mime: "message/rfc822".parse().expect("valid email mime type"),
+                    file_bytes: message,
+                    created_by: None,
+                    file_key: None,
+                    processing_config: None,
+                    task_id: None,
+                })
+                .await?;
+
+            ingested += 1;
+        }
+
+        ImapMailboxState::set(
+            db,
+            &ImapMailboxState {
+                mailbox_id: mailbox_id.clone(),
+                uid_validity: uid_validity as i64,
+                last_uid: uid as i64,
+                updated_at: Utc::now(),
+            },
+        )
+        .await?;
+    }
+
+    if let Err(error) = client.logout().await {
+        tracing::warn!(?error, "failed to logout of mailbox");
+    }
+
+    Ok(ingested)
+}
+
+/// Create the ID of the file for a message, derived from the mailbox and the
+/// UIDVALIDITY and UID of the message
+fn message_file_id(mailbox_id: &str, uid_validity: u32, uid: u32) -> FileId {
+    let digest = Sha256::digest(format!("{mailbox_id}\n{uid_validity}\n{uid}"));
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+
+    uuid::Builder::from_custom_bytes(bytes).into_uuid()
+}
+
+#[cfg(test)]
+mod test {
+    use super::message_file_id;
+
+    #[test]
+    fn test_message_file_id() {
+        let mailbox_id = "imap://user@localhost:993/INBOX";
+
+        let first = message_file_id(mailbox_id, 1, 10);
+        assert_eq!(first, message_file_id(mailbox_id, 1, 10));
+        assert_ne!(first, message_file_id(mailbox_id, 1, 11));
+        assert_ne!(first, message_file_id(mailbox_id, 2, 10));
+        assert_ne!(
+            first,
+            message_file_id("imap://other@localhost:993/INBOX", 1, 10)
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//! # Mailer
+//!
+//! Outbound email delivery, used for sending files by email. Emails are sent
+//! through an SMTP server, Amazon SES is supported through its SMTP interface
+//! using SES SMTP credentials
+
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::Mailbox,
+    transport::smtp::{Error as SmtpError, authentication::Credentials},
+};
+use serde::{Deserialize, Serialize};
+use std::{num::ParseIntError, sync::Arc, time::Duration};
+use template::MailTemplates;
+use thiserror::Error;
+
+pub mod template;
+
+/// Default maximum size of a file that is attached to an email (10MB), larger
+/// files are sent as a link instead
+const DEFAULT_MAX_ATTACHMENT_SIZE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Default time links sent by email remain valid (7 days)
+const DEFAULT_LINK_EXPIRY_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// Maximum time links sent by email can remain valid, presigned links
+/// cannot be valid for longer than 7 days
+pub const MAX_LINK_EXPIRY_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+fn default_max_attachment_size_bytes() -> u64 {
+    DEFAULT_MAX_ATTACHMENT_SIZE_BYTES
+}
+
+fn default_link_expiry_seconds() -> u64 {
+    DEFAULT_LINK_EXPIRY_SECONDS
+}
+
+/// Configuration for sending emails
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MailerConfig {
+    /// Provider emails are sent through
+    #[serde(flatten)]
+    pub provider: MailProviderConfig,
+
+    /// Address emails are sent from (i.e "Docbox <docbox@example.com>")
+    pub from: String,
+
+    /// Maximum size in bytes of a file that is attached to an email, larger
+    /// files are sent as a link instead
+    #[serde(default = "default_max_attachment_size_bytes")]
+    pub max_attachment_size_bytes: u64,
+
+    /// Time in seconds links sent by email remain valid
+    #[serde(default = "default_link_expiry_seconds")]
+    pub link_expiry_seconds: u64,
+
+    /// Templates for the sent emails
+    #[serde(default)]
+    pub templates: MailTemplates,
+}
+
+/// Provider emails are sent through
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "provider", rename_all = "snake_case")]
+pub enum MailProviderConfig {
+    /// SMTP server
+    Smtp {
+        /// Host of the SMTP server
+        host: String,
+        /// Port of the SMTP server, defaults to the port for the `tls` mode
+        #[serde(default)]
+        port: Option<u16>,
+        /// TLS mode for the connection
+        #[serde(default)]
+        tls: SmtpTls,
+        /// Username to authenticate with
+        #[serde(default)]
+        username: Option<String>,
+        /// Password to authenticate with
+        #[serde(default)]
+        password: Option<String>,
+    },
+    /// Amazon SES through its SMTP interface
+    Ses {
+        /// AWS region to send from (i.e "ap-southeast-2")
+        region: String,
+        /// SES SMTP credentials username
+        username: String,
+        /// SES SMTP credentials password
+        password: String,
+    },
+}
+
+/// TLS mode for an SMTP connection
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpTls {
+    /// Connection is encrypted from the start (Port 465)
+    Tls,
+    /// Connection is upgraded using STARTTLS (Port 587)
+    #[default]
+    StartTls,
+    /// Connection is not encrypted (Port 25), only for local testing
+    None,
+}
+
+#[derive(Debug, Error)]
+pub enum MailerConfigError {
+    #[error("DOCBOX_MAIL_PROVIDER must be either \"smtp\" or \"ses\"")]
+    InvalidProvider,
+
+    #[error("DOCBOX_MAIL_FROM must be set when DOCBOX_MAIL_PROVIDER is provided")]
+    MissingFrom,
+
+    #[error("DOCBOX_MAIL_SMTP_HOST must be set when using the smtp provider")]
+    MissingHost,
+
+    #[error("DOCBOX_MAIL_SES_REGION must be set when using the ses provider")]
+    MissingRegion,
+
+    #[error(
+        "DOCBOX_MAIL_SMTP_USERNAME and DOCBOX_MAIL_SMTP_PASSWORD must be set when using the ses provider"
+    )]
+    MissingCredentials,
+
+    #[error("DOCBOX_MAIL_SMTP_PORT must be a valid port number")]
+    InvalidPort(ParseIntError),
+
+    #[error("DOCBOX_MAIL_SMTP_TLS must be one of \"tls\", \"start_tls\" or \"none\"")]
+    InvalidTls,
+
+    #[error("DOCBOX_MAIL_MAX_ATTACHMENT_SIZE_BYTES must be a number")]
+    InvalidMaxAttachmentSize(ParseIntError),
+
+    #[error("DOCBOX_MAIL_LINK_EXPIRY_SECONDS must be a number")]
+    InvalidLinkExpiry(ParseIntError),
+
+    #[error("mail from address is not a valid email address")]
+    InvalidFrom,
+
+    #[error("mail link expiry must not be longer than 7 days")]
+    LinkExpiryTooLong,
+
+    #[error("failed to create smtp transport: {0}")]
+    Transport(SmtpError),
+}
+
+impl MailerConfig {
+    /// Load the mailer config from the environment, [None] is returned when
+    /// sending emails is not enabled
+    pub fn from_env() -> Result<Option<MailerConfig>, MailerConfigError> {
+        let provider = match std::env::var("DOCBOX_MAIL_PROVIDER") {
+            Ok(value) if !value.is_empty() => value,
+            _ => return Ok(None),
+        };
+
+        let from = std::env::var("DOCBOX_MAIL_FROM").map_err(|_| MailerConfigError::MissingFrom)?;
+        let username = std::env::var("DOCBOX_MAIL_SMTP_USERNAME").ok();
+        let password = std::env::var("DOCBOX_MAIL_SMTP_PASSWORD").ok();
+
+        let provider = match provider.as_str() {
+            "smtp" => {
+                let host = std::env::var("DOCBOX_MAIL_SMTP_HOST")
+                    .map_err(|_| MailerConfigError::MissingHost)?;
+
+                let port = std::env::var("DOCBOX_MAIL_SMTP_PORT")
+                    .ok()
+                    .map(|value| value.parse::<u16>().map_err(MailerConfigError::InvalidPort))
+                    .transpose()?;
+
+                let tls = match std::env::var("DOCBOX_MAIL_SMTP_TLS").ok().as_deref() {
+                    None => SmtpTls::default(),
+                    Some("tls") => SmtpTls::Tls,
+                    Some("start_tls") => SmtpTls::StartTls,
+                    Some("none") => SmtpTls::None,
+                    Some(_) => return Err(MailerConfigError::InvalidTls),
+                };
+
+                MailProviderConfig::Smtp {
+                    host,
+                    port,
+                    tls,
+                    username,
+                    password,
+                }
+            }
+            "ses" => {
+                let region = std::env::var("DOCBOX_MAIL_SES_REGION")
+                    .map_err(|_| MailerConfigError::MissingRegion)?;
+                let (username, password) = username
+                    .zip(password)
+                    .ok_or(MailerConfigError::MissingCredentials)?;
+
+                MailProviderConfig::Ses {
+                    region,
+                    username,
+                    password,
+                }
+            }
+            _ => return Err(MailerConfigError::InvalidProvider),
+        };
+
+        let max_attachment_size_bytes = std::env::var("DOCBOX_MAIL_MAX_ATTACHMENT_SIZE_BYTES")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(MailerConfigError::InvalidMaxAttachmentSize)
+            })
+            .transpose()?
+            .unwrap_or(DEFAULT_MAX_ATTACHMENT_SIZE_BYTES);
+
+        let link_expiry_seconds = std::env::var("DOCBOX_MAIL_LINK_EXPIRY_SECONDS")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(MailerConfigError::InvalidLinkExpiry)
+            })
+            .transpose()?
+            .unwrap_or(DEFAULT_LINK_EXPIRY_SECONDS);
+
+        Ok(Some(MailerConfig {
+            provider,
+            from,
+            max_attachment_size_bytes,
+            link_expiry_seconds,
+            templates: MailTemplates::default(),
+        }))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MailerError {
+    #[error("failed to send email: {0}")]
+    Send(SmtpError),
+}
+
+/// Sends emails through the configured provider
+#[derive(Clone)]
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    /// Maximum size in bytes of a file that is attached to an email
+    pub max_attachment_size_bytes: u64,
+    /// Time links sent by email remain valid
+    pub link_expiry: Duration,
+    /// Templates for the sent emails
+    pub templates: Arc<MailTemplates>,
+}
+
+impl Mailer {
+    /// Create a mailer from the provided `config`
+    pub fn from_config(config: MailerConfig) -> Result<Mailer, MailerConfigError> {
+        let from: Mailbox = config
+            .from
+            .parse()
+            .map_err(|_| MailerConfigError::InvalidFrom)?;
+
+        if config.link_expiry_seconds > MAX_LINK_EXPIRY_SECONDS {
+            return Err(MailerConfigError::LinkExpiryTooLong);
+        }
+
+        let transport = match config.provider {
+            MailProviderConfig::Smtp {
+                host,
+                port,
+                tls,
+                username,
+                password,
+            } => {
+                let mut builder = match tls {
+                    SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&host),
+                    SmtpTls::StartTls => {
+                        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)
+                    }
+                    SmtpTls::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                        &host,
+                    )),
+                }
+                .map_err(MailerConfigError::Transport)?;
+
+                if let Some(port) = port {
+                    builder = builder.port(port);
+                }
+
+                if let (Some(username), Some(password)) = (username, password) {
+                    builder = builder.credentials(Credentials::new(username, password));
+                }
+
+                builder.build()
+            }
+            MailProviderConfig::Ses {
+                region,
+                username,
+                password,
+            } => {
+                let host = format!("email-smtp.{region}.amazonaws.com");
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)
+                    .map_err(MailerConfigError::Transport)?
+                    .credentials(Credentials::new(username, password))
+                    .build()
+            }
+        };
+
+        Ok(Mailer {
+            transport,
+            from,
+            max_attachment_size_bytes: config.max_attachment_size_bytes,
+            link_expiry: Duration::from_secs(config.link_expiry_seconds),
+            templates: Arc::new(config.templates),
+        })
+    }
+
+    /// Address emails are sent from
+    pub fn from(&self) -> &Mailbox {
+        &self.from
+    }
+
+    /// Send an email
+    pub async fn send(&self, message: Message) -> Result<(), MailerError> {
+        self.transport
+            .send(message)
+            .await
+            .map_err(MailerError::Send)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MailProviderConfig, Mailer, MailerConfig, MailerConfigError, SmtpTls};
+
+    fn smtp_config() -> MailerConfig {
+        serde_json::from_value(serde_json::json!({
+            "provider": "smtp",
+            "host": "smtp.example.com",
+            "from": "Docbox <docbox@example.com>"
+        }))
+        .unwrap()
+    }
+
+    /// Tests that the defaults are applied when parsing the config
+    #[test]
+    fn test_parse_config_defaults() {
+        let config = smtp_config();
+
+        assert!(matches!(
+            config.provider,
+            MailProviderConfig::Smtp {
+                port: None,
+                tls: SmtpTls::StartTls,
+                ..
+            }
+        ));
+        assert_eq!(config.max_attachment_size_bytes, 10 * 1024 * 1024);
+        assert_eq!(config.link_expiry_seconds, 7 * 24 * 60 * 60);
+    }
+
+    /// Tests that a mailer can be created from a valid config
+    #[tokio::test]
+    async fn test_mailer_from_config() {
+        let mailer = Mailer::from_config(smtp_config()).unwrap();
+        assert_eq!(mailer.from().email.to_string(), "docbox@example.com");
+    }
+
+    /// Tests that invalid from addresses and link expiry are rejected
+    #[test]
+    fn test_mailer_from_config_invalid() {
+        let mut config = smtp_config();
+        config.from = "not an address".to_string();
+        assert!(matches!(
+            Mailer::from_config(config),
+            Err(MailerConfigError::InvalidFrom)
+        ));
+
+        let mut config = smtp_config();
+        config.link_expiry_seconds = 8 * 24 * 60 * 60;
+        assert!(matches!(
+            Mailer::from_config(config),
+            Err(MailerConfigError::LinkExpiryTooLong)
+        ));
+    }
+}
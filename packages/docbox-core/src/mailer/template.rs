@@ -0,0 +1,163 @@
+//! # Mail Templates
+//!
+//! Templates for the emails sent by docbox. Templates contain `{{name}}`
+//! placeholders that are replaced with values when rendered, placeholders
+//! without a value are left as-is
+
+use serde::{Deserialize, Serialize};
+
+/// Template for the subject and body of an email
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct MailTemplate {
+    /// Subject line of the email
+    pub subject: String,
+    /// Plain text body of the email
+    pub body: String,
+}
+
+/// Subject and body of a rendered [MailTemplate]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedMail {
+    pub subject: String,
+    pub body: String,
+}
+
+impl MailTemplate {
+    /// Render the template replacing placeholders with the provided `values`
+    pub fn render(&self, values: &[(&str, &str)]) -> RenderedMail {
+        let subject = render_template(&self.subject, values);
+
+        // Values are user provided, line breaks must not end up in the subject header
+        let subject = subject.replace(['\r', '\n'], " ");
+
+        RenderedMail {
+            subject,
+            body: render_template(&self.body, values),
+        }
+    }
+}
+
+/// Templates for the emails sent when sending a file by email
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct MailTemplates {
+    /// Email with the file attached
+    ///
+    /// Available values: `sender`, `file_name`, `file_size`, `message`
+    pub file_attachment: MailTemplate,
+
+    /// Email with a link to download the file
+    ///
+    /// Available values: `sender`, `file_name`, `file_size`, `message`,
+    /// `link`, `link_expires_at`
+    pub file_link: MailTemplate,
+}
+
+impl Default for MailTemplates {
+    fn default() -> Self {
+        Self {
+            file_attachment: MailTemplate {
+                subject: "{{sender}} sent you {{file_name}}".to_string(),
+                body: "{{sender}} sent you the file \"{{file_name}}\" ({{file_size}}), \
+                    it is attached to this email.\n\n{{message}}"
+                    .to_string(),
+            },
+            file_link: MailTemplate {
+                subject: "{{sender}} shared {{file_name}} with you".to_string(),
+                body: "{{sender}} shared the file \"{{file_name}}\" ({{file_size}}) with you.\n\n\
+                    Download the file: {{link}}\n\n\
+                    This link expires at {{link_expires_at}}.\n\n{{message}}"
+                    .to_string(),
+            },
+        }
+    }
+}
+
+/// Replace the `{{name}}` placeholders within `template` with their `values`
+fn render_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            // Unclosed placeholder
+            rest = &rest[start..];
+            break;
+        };
+
+        let name = after[..end].trim();
+        match values.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => output.push_str(value),
+            None => output.push_str(&rest[start..start + 2 + end + 2]),
+        }
+
+        rest = &after[end + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MailTemplate, MailTemplates, render_template};
+
+    /// Tests that placeholders are replaced with their values
+    #[test]
+    fn test_render_template() {
+        let values = [("name", "Jacob"), ("file", "report.pdf")];
+
+        assert_eq!(
+            render_template("Hi {{name}}, see {{ file }}", &values),
+            "Hi Jacob, see report.pdf"
+        );
+        assert_eq!(render_template("{{name}}{{name}}", &values), "JacobJacob");
+        assert_eq!(
+            render_template("No placeholders", &values),
+            "No placeholders"
+        );
+    }
+
+    /// Tests that unknown and unclosed placeholders are left as-is
+    #[test]
+    fn test_render_template_unknown() {
+        let values = [("name", "Jacob")];
+
+        assert_eq!(
+            render_template("Hi {{unknown}} {{name}}", &values),
+            "Hi {{unknown}} Jacob"
+        );
+        assert_eq!(render_template("Hi {{name", &values), "Hi {{name");
+    }
+
+    /// Tests that line breaks from values are removed from the subject
+    #[test]
+    fn test_render_subject_line_breaks() {
+        let template = MailTemplate {
+            subject: "Sent {{file_name}}".to_string(),
+            body: "{{file_name}}".to_string(),
+        };
+
+        let rendered = template.render(&[("file_name", "a\r\nBcc: other@example.com")]);
+        assert_eq!(rendered.subject, "Sent a  Bcc: other@example.com");
+        assert_eq!(rendered.body, "a\r\nBcc: other@example.com");
+    }
+
+    /// Tests that partially configured templates use the defaults
+    #[test]
+    fn test_parse_templates_defaults() {
+        let templates: MailTemplates = serde_json::from_value(serde_json::json!({
+            "file_link": { "subject": "Link", "body": "{{link}}" }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            templates.file_attachment,
+            MailTemplates::default().file_attachment
+        );
+        assert_eq!(templates.file_link.subject, "Link");
+    }
+}
@@ -0,0 +1,207 @@
+//! # Inbound Email
+//!
+//! Parsing for the notifications SES publishes (through SNS) when an email is
+//! received by a receipt rule. The email content is either included within the
+//! notification (SNS action) or stored in a bucket (S3 action)
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use bytes::Bytes;
+
+/// Email received through an SES receipt rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboundEmail {
+    /// Unique ID SES assigned to the received email
+    pub message_id: String,
+    /// Recipients of the email that matched the receipt rule
+    pub recipients: Vec<String>,
+    /// Location of the raw email content
+    pub content: InboundEmailContent,
+}
+
+/// Location of the raw content for an [InboundEmail]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InboundEmailContent {
+    /// Content was included within the notification
+    Inline(Bytes),
+    /// Content was stored in a bucket by the receipt rule
+    S3 {
+        bucket_name: String,
+        object_key: String,
+    },
+}
+
+/// Parse an SES "Received" notification, the notification may be wrapped
+/// within an SNS envelope (When SNS raw message delivery is not enabled)
+pub fn parse_inbound_email_message(value: &serde_json::Value) -> Option<InboundEmail> {
+    // Unwrap the SNS envelope
+    let unwrapped: serde_json::Value;
+    let value = match value.get("Type").and_then(|value| value.as_str()) {
+        Some("Notification") => {
+            let message = value.get("Message")?.as_str()?;
+            unwrapped = serde_json::from_str(message).ok()?;
+            &unwrapped
+        }
+        _ => value,
+    };
+
+    if value.get("notificationType")?.as_str()? != "Received" {
+        return None;
+    }
+
+    let mail = value.get("mail")?;
+    let receipt = value.get("receipt")?;
+    let action = receipt.get("action")?;
+
+    let message_id = mail.get("messageId")?.as_str()?.to_string();
+
+    // Recipients matching the receipt rule, falling back to all destinations
+    let recipients = receipt
+        .get("recipients")
+        .or_else(|| mail.get("destination"))?
+        .as_array()?
+        .iter()
+        .filter_map(|value| value.as_str())
+        .map(|value| value.to_string())
+        .collect();
+
+    let content = match action.get("type")?.as_str()? {
+        "SNS" => {
+            let content = value.get("content")?.as_str()?;
+            let content = match action.get("encoding").and_then(|value| value.as_str()) {
+                Some("BASE64") => BASE64_STANDARD.decode(content).ok()?,
+                _ => content.as_bytes().to_vec(),
+            };
+
+            InboundEmailContent::Inline(Bytes::from(content))
+        }
+        "S3" => InboundEmailContent::S3 {
+            bucket_name: action.get("bucketName")?.as_str()?.to_string(),
+            object_key: action.get("objectKey")?.as_str()?.to_string(),
+        },
+        _ => return None,
+    };
+
+    Some(InboundEmail {
+        message_id,
+        recipients,
+        content,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{InboundEmail, InboundEmailContent, parse_inbound_email_message};
+    use bytes::Bytes;
+    use serde_json::json;
+
+    fn notification(action: serde_json::Value) -> serde_json::Value {
+        json!({
+            "notificationType": "Received",
+            "mail": {
+                "messageId": "message-1",
+                "destination": ["box@example.com", "other@example.com"]
+            },
+            "receipt": {
+                "recipients": ["box@example.com"],
+                "action": action
+            },
+            "content": "U3ViamVjdDogVGVzdA0KDQpIZWxsbw=="
+        })
+    }
+
+    /// Tests that notifications with inline base64 content are parsed
+    #[test]
+    fn test_parse_inline_base64() {
+        let value = notification(json!({ "type": "SNS", "encoding": "BASE64" }));
+
+        assert_eq!(
+            parse_inbound_email_message(&value),
+            Some(InboundEmail {
+                message_id: "message-1".to_string(),
+                recipients: vec!["box@example.com".to_string()],
+                content: InboundEmailContent::Inline(Bytes::from_static(
+                    b"Subject: Test\r\n\r\nHello"
+                )),
+            })
+        );
+    }
+
+    /// Tests that notifications with inline UTF-8 content are parsed
+    #[test]
+    fn test_parse_inline_utf8() {
+        let mut value = notification(json!({ "type": "SNS", "encoding": "UTF8" }));
+        value["content"] = json!("Subject: Test\r\n\r\nHello");
+
+        let email = parse_inbound_email_message(&value).unwrap();
+        assert_eq!(
+            email.content,
+            InboundEmailContent::Inline(Bytes::from_static(b"Subject: Test\r\n\r\nHello"))
+        );
+    }
+
+    /// Tests that notifications for content stored in S3 are parsed
+    #[test]
+    fn test_parse_s3() {
+        let value = notification(json!({
+            "type": "S3",
+            "bucketName": "inbound-email",
+            "objectKeyPrefix": "emails",
+            "objectKey": "emails/message-1"
+        }));
+
+        let email = parse_inbound_email_message(&value).unwrap();
+        assert_eq!(
+            email.content,
+            InboundEmailContent::S3 {
+                bucket_name: "inbound-email".to_string(),
+                object_key: "emails/message-1".to_string(),
+            }
+        );
+    }
+
+    /// Tests that notifications wrapped in an SNS envelope are parsed
+    #[test]
+    fn test_parse_sns_envelope() {
+        let inner = notification(json!({ "type": "SNS", "encoding": "BASE64" }));
+        let value = json!({
+            "Type": "Notification",
+            "MessageId": "sns-message-1",
+            "Message": inner.to_string()
+        });
+
+        let email = parse_inbound_email_message(&value).unwrap();
+        assert_eq!(email.message_id, "message-1");
+    }
+
+    /// Tests that recipients fall back to the mail destination
+    #[test]
+    fn test_parse_destination_fallback() {
+        let mut value = notification(json!({ "type": "SNS", "encoding": "BASE64" }));
+        value["receipt"]
+            .as_object_mut()
+            .unwrap()
+            .remove("recipients");
+
+        let email = parse_inbound_email_message(&value).unwrap();
+        assert_eq!(
+            email.recipients,
+            vec![
+                "box@example.com".to_string(),
+                "other@example.com".to_string()
+            ]
+        );
+    }
+
+    /// Tests that other messages are not parsed as received emails
+    #[test]
+    fn test_parse_other_messages() {
+        let mut value = notification(json!({ "type": "SNS", "encoding": "BASE64" }));
+        value["notificationType"] = json!("Bounce");
+        assert!(parse_inbound_email_message(&value).is_none());
+
+        let value = json!({
+            "Records": [{ "s3": { "bucket": { "name": "test" }, "object": { "key": "test" } } }]
+        });
+        assert!(parse_inbound_email_message(&value).is_none());
+    }
+}
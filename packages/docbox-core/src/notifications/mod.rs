@@ -2,17 +2,20 @@
 //!
 //! Notifications queue system handling notifications for the app
 
+pub mod inbound_email;
 mod mpsc;
 mod noop;
 pub mod process;
 mod sqs;
 
 use crate::aws::SqsClient;
+use inbound_email::InboundEmail;
 pub use mpsc::MpscNotificationQueueSender;
 
 use serde::Deserialize;
 
 // Pretty common utility function
+pub use inbound_email::parse_inbound_email_message;
 pub use sqs::parse_bucket_message;
 
 #[derive(Debug, Clone, Deserialize)]
@@ -67,6 +70,16 @@ impl AppNotificationQueue {
             AppNotificationQueue::Mpsc(queue) => queue.next_message().await,
         }
     }
+
+    /// Stop receiving new messages, messages that have already been
+    /// received can still be obtained from [Self::next_message]
+    pub fn close(&mut self) {
+        match self {
+            AppNotificationQueue::Sqs(queue) => queue.close(),
+            AppNotificationQueue::Noop(queue) => queue.close(),
+            AppNotificationQueue::Mpsc(queue) => queue.close(),
+        }
+    }
 }
 
 /// Type of message from the notification queue
@@ -75,9 +88,14 @@ pub enum NotificationQueueMessage {
         bucket_name: String,
         object_key: String,
     },
+    /// Email was received by an SES receipt rule
+    EmailReceived(InboundEmail),
 }
 
 pub(crate) trait NotificationQueue: Send + Sync + 'static {
     /// Request the next message from the notification queue
     async fn next_message(&mut self) -> Option<NotificationQueueMessage>;
+
+    /// Stop receiving new messages from the notification queue
+    fn close(&mut self);
 }
@@ -39,4 +39,8 @@ impl NotificationQueue for MpscNotificationQueue {
     async fn next_message(&mut self) -> Option<NotificationQueueMessage> {
         self.rx.recv().await
     }
+
+    fn close(&mut self) {
+        self.rx.close();
+    }
 }
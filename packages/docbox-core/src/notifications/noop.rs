@@ -8,4 +8,6 @@ impl NotificationQueue for NoopNotificationQueue {
     async fn next_message(&mut self) -> Option<NotificationQueueMessage> {
         None
     }
+
+    fn close(&mut self) {}
 }
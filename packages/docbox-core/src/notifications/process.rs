@@ -2,46 +2,101 @@
 //!
 //! Logic for processing notifications from the notification queue
 
-use super::{AppNotificationQueue, NotificationQueueMessage};
+use super::{
+    AppNotificationQueue, NotificationQueueMessage,
+    inbound_email::{InboundEmail, InboundEmailContent},
+};
 use crate::{
     events::EventPublisherFactory,
-    files::upload_file_presigned::{CompletePresigned, safe_complete_presigned},
-    tenant::tenant_options_ext::TenantOptionsExt,
+    files::{
+        upload_file::{UploadFile, upload_file},
+        upload_file_presigned::{CompletePresigned, safe_complete_presigned},
+    },
+    shutdown::ShutdownCoordinator,
+    tenant::tenant_storage_key::TenantStorageKeyCache,
 };
+use bytes::Bytes;
 use docbox_database::{
     DatabasePoolCache,
-    models::{folder::Folder, presigned_upload_task::PresignedUploadTask, tenant::Tenant},
+    models::{
+        file::{File, FileId},
+        folder::Folder,
+        inbound_email_address::InboundEmailAddress,
+        presigned_upload_task::PresignedUploadTask,
+        tenant::Tenant,
+    },
 };
-use docbox_processing::ProcessingLayer;
+use docbox_processing::{ProcessingLayer, email::email_file_name};
 use docbox_search::SearchIndexFactory;
-use docbox_storage::StorageLayerFactory;
-use std::sync::Arc;
+use docbox_storage::{StorageLayerFactory, StorageLayerOptions};
+use sha2::{Digest, Sha256};
+use std::{sync::Arc, time::Duration};
 use tracing::Instrument;
 
+/// Interval between checks of whether a tenant is still in maintenance mode
+/// while processing for the tenant is paused
+const MAINTENANCE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
 #[derive(Clone)]
 pub struct NotificationQueueData {
     pub db_cache: Arc<DatabasePoolCache>,
     pub search: SearchIndexFactory,
     pub storage: StorageLayerFactory,
+    pub storage_keys: TenantStorageKeyCache,
     pub events: EventPublisherFactory,
     pub processing: ProcessingLayer,
 }
 
 /// Processes events coming from the notification queue. This will be
 /// things like successful file uploads that need to be processed
+///
+/// When `shutdown` is requested the queue stops receiving new messages,
+/// messages that were already received are still handled and handling is
+/// tracked by the `shutdown` coordinator so it can complete before exiting
 pub async fn process_notification_queue(
     mut notification_queue: AppNotificationQueue,
     data: NotificationQueueData,
+    shutdown: ShutdownCoordinator,
 ) {
     // Process messages from the notification queue
+    loop {
+        let message = tokio::select! {
+            message = notification_queue.next_message() => message,
+            _ = shutdown.cancelled() => break,
+        };
+
+        let Some(message) = message else {
+            return;
+        };
+
+        handle_message(&data, &shutdown, message);
+    }
+
+    tracing::debug!("shutdown requested, draining notification queue");
+
+    // Stop receiving and handle any messages that were already received
+    notification_queue.close();
+
     while let Some(message) = notification_queue.next_message().await {
-        match message {
-            NotificationQueueMessage::FileCreated {
-                bucket_name,
-                object_key,
-            } => {
-                tokio::spawn(handle_file_uploaded(data.clone(), bucket_name, object_key));
-            }
+        handle_message(&data, &shutdown, message);
+    }
+}
+
+/// Spawn the handler for a notification queue `message`
+fn handle_message(
+    data: &NotificationQueueData,
+    shutdown: &ShutdownCoordinator,
+    message: NotificationQueueMessage,
+) {
+    match message {
+        NotificationQueueMessage::FileCreated {
+            bucket_name,
+            object_key,
+        } => {
+            shutdown.spawn(handle_file_uploaded(data.clone(), bucket_name, object_key));
+        }
+        NotificationQueueMessage::EmailReceived(email) => {
+            shutdown.spawn(handle_email_received(data.clone(), email));
         }
     }
 }
@@ -77,6 +132,19 @@ pub async fn handle_file_uploaded(
         }
     };
 
+    // Pause processing until the tenant leaves maintenance mode
+    let tenant = if tenant.maintenance_mode {
+        match wait_for_maintenance_end(&data.db_cache, tenant).await {
+            Some(value) => value,
+            None => {
+                tracing::warn!("tenant was removed while processing was paused");
+                return;
+            }
+        }
+    } else {
+        tenant
+    };
+
     // Provide a span that contains the tenant metadata
     let span = tracing::info_span!("tenant", tenant_id = %tenant.id, tenant_env = %tenant.env);
 
@@ -85,6 +153,38 @@ pub async fn handle_file_uploaded(
         .await;
 }
 
+/// Wait until the `tenant` is no longer in maintenance mode, provides the
+/// latest tenant state or [None] if the tenant no longer exists
+async fn wait_for_maintenance_end(
+    db_cache: &DatabasePoolCache,
+    mut tenant: Tenant,
+) -> Option<Tenant> {
+    tracing::info!(tenant_id = %tenant.id, tenant_env = %tenant.env, "tenant is in maintenance mode, pausing processing");
+
+    while tenant.maintenance_mode {
+        tokio::time::sleep(MAINTENANCE_POLL_INTERVAL).await;
+
+        let db = match db_cache.get_root_pool().await {
+            Ok(value) => value,
+            Err(error) => {
+                tracing::error!(?error, "failed to acquire root database pool");
+                continue;
+            }
+        };
+
+        tenant = match Tenant::find_by_id(&db, tenant.id, &tenant.env).await {
+            Ok(Some(value)) => value,
+            Ok(None) => return None,
+            Err(error) => {
+                tracing::error!(?error, "failed to query tenant maintenance mode");
+                continue;
+            }
+        };
+    }
+
+    Some(tenant)
+}
+
 /// Handle file upload notification once the tenant has been identified
 #[tracing::instrument(skip(data))]
 pub async fn handle_file_uploaded_tenant(
@@ -146,7 +246,15 @@ pub async fn handle_file_uploaded_tenant(
     let complete = CompletePresigned { task, folder };
 
     let search = data.search.create_search_index(&tenant);
-    let storage = data.storage.create_layer(tenant.storage_layer_options());
+    let storage_options = match data.storage_keys.storage_layer_options(&tenant).await {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::error!(?error, "failed to load tenant storage keys");
+            return;
+        }
+    };
+
+    let storage = data.storage.create_layer(storage_options);
     let events = data.events.create_event_publisher(&tenant);
 
     // Create task future that performs the file upload
@@ -156,3 +264,222 @@ pub async fn handle_file_uploaded_tenant(
         tracing::error!(?error, "failed to complete presigned file upload");
     }
 }
+
+/// Handle emails received through an SES receipt rule, the email is stored
+/// in the document box of each recipient that has an inbound email address
+#[tracing::instrument(skip_all, fields(message_id = %email.message_id))]
+pub async fn handle_email_received(data: NotificationQueueData, email: InboundEmail) {
+    let addresses = {
+        let db = match data.db_cache.get_root_pool().await {
+            Ok(value) => value,
+            Err(error) => {
+                tracing::error!(?error, "failed to acquire root database pool");
+                return;
+            }
+        };
+
+        let mut addresses = Vec::new();
+
+        for recipient in &email.recipients {
+            match InboundEmailAddress::find_by_address(&db, recipient).await {
+                Ok(Some(value)) => addresses.push(value),
+                Ok(None) => {
+                    tracing::debug!(%recipient, "email recipient has no matching inbound email address");
+                }
+                Err(error) => {
+                    tracing::error!(?error, %recipient, "failed to query inbound email address");
+                }
+            }
+        }
+
+        addresses
+    };
+
+    if addresses.is_empty() {
+        tracing::warn!("email was received but no recipients had a matching inbound email address");
+        return;
+    }
+
+    let content = match email.content {
+        InboundEmailContent::Inline(value) => value,
+        InboundEmailContent::S3 {
+            bucket_name,
+            object_key,
+        } => {
+            // Receipt rule bucket is not owned by a tenant
+            let storage = data.storage.create_layer(StorageLayerOptions {
+                bucket_name,
+                encryption: None,
+                deduplicate: false,
+                tags: Vec::new(),
+            });
+
+            let content = match storage.get_file(&object_key).await {
+                Ok(value) => value.collect_bytes().await,
+                Err(error) => Err(error),
+            };
+
+            match content {
+                Ok(value) => value,
+                Err(error) => {
+                    tracing::error!(?error, "failed to get received email content");
+                    return;
+                }
+            }
+        }
+    };
+
+    for address in addresses {
+        let tenant = {
+            let db = match data.db_cache.get_root_pool().await {
+                Ok(value) => value,
+                Err(error) => {
+                    tracing::error!(?error, "failed to acquire root database pool");
+                    return;
+                }
+            };
+
+            match Tenant::find_by_id(&db, address.tenant_id, &address.env).await {
+                Ok(Some(value)) => value,
+                Ok(None) => {
+                    tracing::warn!("inbound email address tenant no longer exists");
+                    continue;
+                }
+                Err(error) => {
+                    tracing::error!(?error, "failed to query tenant for inbound email address");
+                    continue;
+                }
+            }
+        };
+
+        // Pause processing until the tenant leaves maintenance mode
+        let tenant = if tenant.maintenance_mode {
+            match wait_for_maintenance_end(&data.db_cache, tenant).await {
+                Some(value) => value,
+                None => {
+                    tracing::warn!("tenant was removed while processing was paused");
+                    continue;
+                }
+            }
+        } else {
+            tenant
+        };
+
+        // Provide a span that contains the tenant metadata
+        let span = tracing::info_span!("tenant", tenant_id = %tenant.id, tenant_env = %tenant.env);
+
+        handle_email_received_tenant(
+            tenant,
+            data.clone(),
+            &email.message_id,
+            address,
+            content.clone(),
+        )
+        .instrument(span)
+        .await;
+    }
+}
+
+/// Store a received email in the document box of the inbound email `address`
+/// once the tenant has been identified
+#[tracing::instrument(skip(data, content))]
+pub async fn handle_email_received_tenant(
+    tenant: Tenant,
+    data: NotificationQueueData,
+    message_id: &str,
+    address: InboundEmailAddress,
+    content: Bytes,
+) {
+    let db = match data.db_cache.get_tenant_pool(&tenant).await {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::error!(?error, "failed to get tenant database pool");
+            return;
+        }
+    };
+
+    let scope = address.document_box;
+
+    // Emails are stored in the root folder of the document box
+    let folder = match Folder::find_root(&db, &scope).await {
+        Ok(Some(value)) => value,
+        Ok(None) => {
+            tracing::warn!("inbound email address document box no longer exists");
+            return;
+        }
+        Err(error) => {
+            tracing::error!(?error, "unable to query root folder");
+            return;
+        }
+    };
+
+    // Notifications can be delivered more than once, skip emails that were already stored
+    let file_id = email_file_id(message_id, &scope);
+    match File::find(&db, &scope, file_id).await {
+        Ok(Some(_)) => {
+            tracing::debug!("received email was already stored");
+            return;
+        }
+        Ok(None) => {}
+        Err(error) => {
+            tracing::error!(?error, "unable to query existing email file");
+            return;
+        }
+    }
+
+    let search = data.search.create_search_index(&tenant);
+    let storage_options = match data.storage_keys.storage_layer_options(&tenant).await {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::error!(?error, "failed to load tenant storage keys");
+            return;
+        }
+    };
+
+    let storage = data.storage.create_layer(storage_options);
+    let events = data.events.create_event_publisher(&tenant);
+
+    // Email attachments are extracted when the email is processed
+    let upload = UploadFile {
+        fixed_id: Some(file_id),
+        parent_id: None,
+        folder_id: folder.id,
+        document_box: scope,
+        name: email_file_name(&content),
+        mime: "message/rfc822".parse().expect("valid email mime type"),
+        file_bytes: content,
+        created_by: None,
+        file_key: None,
+        processing_config: None,
+        task_id: None,
+    };
+
+    if let Err(error) = upload_file(&db, &search, &storage, &data.processing, &events, upload).await
+    {
+        tracing::error!(?error, "failed to store received email");
+    }
+}
+
+/// Create the ID of the file for a received email, derived from the SES
+/// message ID and the document box it is stored in
+fn email_file_id(message_id: &str, scope: &str) -> FileId {
+    let digest = Sha256::digest(format!("{message_id}\n{scope}"));
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+
+    uuid::Builder::from_custom_bytes(bytes).into_uuid()
+}
+
+#[cfg(test)]
+mod test {
+    use super::email_file_id;
+
+    #[test]
+    fn test_email_file_id() {
+        let first = email_file_id("message-1", "box-1");
+        assert_eq!(first, email_file_id("message-1", "box-1"));
+        assert_ne!(first, email_file_id("message-2", "box-1"));
+        assert_ne!(first, email_file_id("message-1", "box-2"));
+    }
+}
@@ -1,4 +1,4 @@
-use super::{NotificationQueue, NotificationQueueMessage};
+use super::{NotificationQueue, NotificationQueueMessage, parse_inbound_email_message};
 use crate::aws::SqsClient;
 use std::time::Duration;
 use tokio::{spawn, sync::mpsc, time::sleep};
@@ -25,6 +25,11 @@ impl NotificationQueue for SqsNotificationQueue {
     async fn next_message(&mut self) -> Option<NotificationQueueMessage> {
         self.rx.recv().await
     }
+
+    fn close(&mut self) {
+        // Closing the receiver will stop the task polling SQS
+        self.rx.close();
+    }
 }
 
 pub struct SqsNotificationQueueTask {
@@ -54,6 +59,12 @@ pub fn parse_bucket_message(value: &serde_json::Value) -> Option<(String, String
 
 async fn process_sqs_queue(task: SqsNotificationQueueTask) {
     loop {
+        // Queue has been closed, stop polling for messages
+        if task.tx.is_closed() {
+            tracing::debug!("notification queue closed, stopped polling sqs");
+            return;
+        }
+
         // Receive messages from the SQS queue
         let receive_messages = match task
             .client
@@ -108,16 +119,26 @@ async fn process_sqs_queue(task: SqsNotificationQueueTask) {
 
             tracing::debug!(?parsed, "got message from sqs");
 
-            if let Some((bucket_name, object_key)) = parse_bucket_message(&parsed) {
+            let message = if let Some((bucket_name, object_key)) = parse_bucket_message(&parsed) {
                 tracing::debug!(?bucket_name, ?object_key, "got file upload message");
+                Some(NotificationQueueMessage::FileCreated {
+                    bucket_name,
+                    object_key,
+                })
+            } else if let Some(email) = parse_inbound_email_message(&parsed) {
+                tracing::debug!(message_id = ?email.message_id, "got inbound email message");
+                Some(NotificationQueueMessage::EmailReceived(email))
+            } else {
+                None
+            };
 
-                _ = task
-                    .tx
-                    .send(NotificationQueueMessage::FileCreated {
-                        bucket_name,
-                        object_key,
-                    })
-                    .await;
+            if let Some(message) = message
+                && task.tx.send(message).await.is_err()
+            {
+                // Queue was closed, leave the message in SQS so that it
+                // becomes visible again and can be handled later
+                tracing::debug!("notification queue closed, message left in sqs");
+                continue;
             }
 
             if let Err(error) = task
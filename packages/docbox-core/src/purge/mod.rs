@@ -1,3 +1,4 @@
+pub mod purge_expired_event_payloads;
 pub mod purge_expired_presigned_tasks;
 pub mod purge_expired_tasks;
 pub mod purge_expired_website_metadata;
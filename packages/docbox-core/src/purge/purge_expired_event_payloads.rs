@@ -0,0 +1,70 @@
+use chrono::{Days, Utc};
+use docbox_database::{
+    DatabasePoolCache,
+    models::{event_payload::EventPayload, tenant::Tenant},
+};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Number of days claim check event payloads are kept for consumers to resolve
+pub const EVENT_PAYLOAD_RETENTION_DAYS: u64 = 7;
+
+#[derive(Debug, Error)]
+pub enum PurgeExpiredEventPayloadsError {
+    #[error("failed to connect to database")]
+    ConnectDatabase,
+
+    #[error("failed to query available tenants")]
+    QueryTenants,
+}
+
+pub async fn safe_purge_expired_event_payloads(db_cache: Arc<DatabasePoolCache>) {
+    if let Err(error) = purge_expired_event_payloads(db_cache).await {
+        tracing::error!(?error, "failed to purge expired event payloads for tenants");
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn purge_expired_event_payloads(
+    db_cache: Arc<DatabasePoolCache>,
+) -> Result<(), PurgeExpiredEventPayloadsError> {
+    let tenants = {
+        let db = db_cache.get_root_pool().await.map_err(|error| {
+            tracing::error!(?error, "failed to connect to root database");
+            PurgeExpiredEventPayloadsError::ConnectDatabase
+        })?;
+
+        Tenant::all(&db).await.map_err(|error| {
+            tracing::error!(?error, "failed to query available tenants");
+            PurgeExpiredEventPayloadsError::QueryTenants
+        })?
+    };
+
+    for tenant in tenants {
+        // Create the database connection pool
+        let db = db_cache.get_tenant_pool(&tenant).await.map_err(|error| {
+            tracing::error!(?error, "failed to connect to tenant database");
+            PurgeExpiredEventPayloadsError::ConnectDatabase
+        })?;
+
+        let before = match Utc::now().checked_sub_days(Days::new(EVENT_PAYLOAD_RETENTION_DAYS)) {
+            Some(value) => value,
+            None => {
+                tracing::error!(
+                    "time underflow while attempting to compute event payload expiry date"
+                );
+                continue;
+            }
+        };
+
+        if let Err(error) = EventPayload::delete_expired(&db, before).await {
+            tracing::error!(
+                ?error,
+                ?tenant,
+                "failed to purge expired event payloads for tenant"
+            );
+        }
+    }
+
+    Ok(())
+}
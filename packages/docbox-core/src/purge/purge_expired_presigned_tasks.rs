@@ -1,4 +1,4 @@
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use docbox_database::{
     DatabasePoolCache, DbPool, DbResult,
     models::{
@@ -10,13 +10,21 @@ use docbox_storage::{StorageLayer, StorageLayerFactory};
 use std::sync::Arc;
 use thiserror::Error;
 
-use crate::tenant::tenant_options_ext::TenantOptionsExt;
+use crate::{
+    events::{EventPublisherFactory, TenantEventMessage, TenantEventPublisher},
+    tenant::tenant_options_ext::TenantOptionsExt,
+};
+
+/// Duration tasks marked as [PresignedTaskStatus::Expired] are kept before
+/// being deleted, allowing clients polling the task to observe the expiry
+const EXPIRED_TASK_RETENTION: Duration = Duration::days(1);
 
 pub async fn safe_purge_expired_presigned_tasks(
     db_cache: Arc<DatabasePoolCache>,
     storage: StorageLayerFactory,
+    events: EventPublisherFactory,
 ) {
-    if let Err(error) = purge_expired_presigned_tasks(db_cache, storage).await {
+    if let Err(error) = purge_expired_presigned_tasks(db_cache, storage, events).await {
         tracing::error!(?error, "failed to purge presigned tasks");
     }
 }
@@ -34,6 +42,7 @@ pub enum PurgeExpiredPresignedError {
 pub async fn purge_expired_presigned_tasks(
     db_cache: Arc<DatabasePoolCache>,
     storage: StorageLayerFactory,
+    events: EventPublisherFactory,
 ) -> Result<(), PurgeExpiredPresignedError> {
     let db = db_cache.get_root_pool().await.map_err(|error| {
         tracing::error!(?error, "failed to connect to root database");
@@ -49,6 +58,16 @@ pub async fn purge_expired_presigned_tasks(
     drop(db);
 
     for tenant in tenants {
+        // Processing is paused for tenants in maintenance mode, uploads that
+        // are waiting to be processed must not be expired
+        if tenant.maintenance_mode {
+            tracing::debug!(
+                ?tenant,
+                "skipping presigned task purge for tenant in maintenance"
+            );
+            continue;
+        }
+
         // Create the database connection pool
         let db = db_cache.get_tenant_pool(&tenant).await.map_err(|error| {
             tracing::error!(?error, "failed to connect to tenant database");
@@ -56,8 +75,9 @@ pub async fn purge_expired_presigned_tasks(
         })?;
 
         let storage = storage.create_layer(tenant.storage_layer_options());
+        let events = events.create_event_publisher(&tenant);
 
-        if let Err(error) = purge_expired_presigned_tasks_tenant(&db, &storage).await {
+        if let Err(error) = purge_expired_presigned_tasks_tenant(&db, &storage, &events).await {
             tracing::error!(
                 ?error,
                 ?tenant,
@@ -69,9 +89,17 @@ pub async fn purge_expired_presigned_tasks(
     Ok(())
 }
 
+/// Purge the expired presigned upload tasks for a tenant
+///
+/// Incomplete tasks have their uploaded file deleted and are marked as
+/// [PresignedTaskStatus::Expired] publishing a
+/// [TenantEventMessage::PresignedUploadExpired] event. Expired tasks are
+/// deleted after [EXPIRED_TASK_RETENTION], completed tasks are deleted
+/// immediately
 pub async fn purge_expired_presigned_tasks_tenant(
     db: &DbPool,
     storage: &StorageLayer,
+    events: &TenantEventPublisher,
 ) -> DbResult<()> {
     let current_date = Utc::now();
     let tasks = PresignedUploadTask::find_expired(db, current_date).await?;
@@ -79,24 +107,39 @@ pub async fn purge_expired_presigned_tasks_tenant(
         return Ok(());
     }
 
-    for task in tasks {
-        // Delete the task itself
-        if let Err(error) = PresignedUploadTask::delete(db, task.id).await {
-            tracing::error!(?error, "failed to delete presigned upload task");
-        }
-
-        // Delete incomplete file uploads
+    for mut task in tasks {
         match task.status {
             PresignedTaskStatus::Completed { .. } => {
                 // Upload completed, nothing to revert
+                if let Err(error) = PresignedUploadTask::delete(db, task.id).await {
+                    tracing::error!(?error, "failed to delete presigned upload task");
+                }
+            }
+            PresignedTaskStatus::Expired => {
+                // Keep expired tasks until the retention period has passed
+                if task.expires_at + EXPIRED_TASK_RETENTION > current_date {
+                    continue;
+                }
+
+                if let Err(error) = PresignedUploadTask::delete(db, task.id).await {
+                    tracing::error!(?error, "failed to delete presigned upload task");
+                }
             }
             PresignedTaskStatus::Failed { .. } | PresignedTaskStatus::Pending => {
+                // Delete incomplete file uploads
                 if let Err(error) = storage.delete_file(&task.file_key).await {
                     tracing::error!(
                         ?error,
                         "failed to delete expired presigned task file from tenant"
                     );
                 }
+
+                if let Err(error) = task.set_status(db, PresignedTaskStatus::Expired).await {
+                    tracing::error!(?error, "failed to mark presigned upload task expired");
+                    continue;
+                }
+
+                events.publish_event(TenantEventMessage::PresignedUploadExpired(task));
             }
         }
     }
@@ -0,0 +1,131 @@
+//! # Shutdown
+//!
+//! Coordinates graceful shutdown of the background workers. When shutdown is
+//! requested workers stop accepting new work (i.e polling the notification
+//! queue) and the coordinator waits for any in-flight work that was spawned
+//! through it to complete before the server exits
+
+use std::{future::Future, time::Duration};
+use tokio::task::JoinHandle;
+use tokio_util::{
+    sync::CancellationToken,
+    task::{TaskTracker, task_tracker::TaskTrackerToken},
+};
+
+/// Shared handle for coordinating a graceful shutdown
+#[derive(Clone, Default)]
+pub struct ShutdownCoordinator {
+    /// Token cancelled when shutdown is requested
+    token: CancellationToken,
+    /// Tracker for in-flight work that must complete before exiting
+    tracker: TaskTracker,
+}
+
+impl ShutdownCoordinator {
+    /// Create a new shutdown coordinator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check if shutdown has been requested
+    pub fn is_shutting_down(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Wait until shutdown has been requested
+    pub async fn cancelled(&self) {
+        self.token.cancelled().await
+    }
+
+    /// Spawn a task that will be waited on before the server exits
+    ///
+    /// Tasks can still be spawned after shutdown is requested so that work
+    /// that is already in-flight can complete
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.tracker.spawn(future)
+    }
+
+    /// Track work that isn't spawned through the coordinator (i.e tasks spawned
+    /// by a library), shutdown waits until the provided guard is dropped
+    pub fn guard(&self) -> ShutdownGuard {
+        ShutdownGuard {
+            _token: self.tracker.token(),
+        }
+    }
+
+    /// Request shutdown and wait up to `timeout` for the in-flight tasks to
+    /// complete, provides whether all the tasks completed in time
+    pub async fn shutdown(&self, timeout: Duration) -> bool {
+        self.token.cancel();
+        self.tracker.close();
+
+        tokio::time::timeout(timeout, self.tracker.wait())
+            .await
+            .is_ok()
+    }
+}
+
+/// Guard for in-flight work, see [ShutdownCoordinator::guard]
+pub struct ShutdownGuard {
+    /// Token keeping the tracker from completing
+    _token: TaskTrackerToken,
+}
+
+#[cfg(test)]
+mod test {
+    use super::ShutdownCoordinator;
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_tasks() {
+        let shutdown = ShutdownCoordinator::new();
+        let completed = Arc::new(AtomicBool::new(false));
+
+        shutdown.spawn({
+            let shutdown = shutdown.clone();
+            let completed = completed.clone();
+            async move {
+                shutdown.cancelled().await;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                completed.store(true, Ordering::SeqCst);
+            }
+        });
+
+        assert!(!shutdown.is_shutting_down());
+        assert!(shutdown.shutdown(Duration::from_secs(5)).await);
+        assert!(shutdown.is_shutting_down());
+        assert!(completed.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_guards() {
+        let shutdown = ShutdownCoordinator::new();
+        let guard = shutdown.guard();
+
+        assert!(!shutdown.shutdown(Duration::from_millis(10)).await);
+
+        drop(guard);
+        assert!(shutdown.shutdown(Duration::from_secs(5)).await);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_timeout() {
+        let shutdown = ShutdownCoordinator::new();
+
+        shutdown.spawn(async move {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+
+        assert!(!shutdown.shutdown(Duration::from_millis(10)).await);
+    }
+}
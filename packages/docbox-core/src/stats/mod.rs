@@ -0,0 +1,3 @@
+pub mod reconcile_document_box_stats;
+pub mod request_usage;
+pub mod rollup_usage_stats;
@@ -0,0 +1,76 @@
+//! # Reconcile Document Box Stats
+//!
+//! The document box stats counters are updated incrementally as items are
+//! created and deleted, updates that fail (Or contents removed indirectly such
+//! as cascading deletes) cause the counters to drift from the actual contents.
+//! This task recomputes the counters for every document box to correct them
+
+use chrono::Utc;
+use docbox_database::{
+    DatabasePoolCache, DbErr,
+    models::{document_box_stats::DocumentBoxStatsCounters, tenant::Tenant},
+};
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ReconcileDocumentBoxStatsError {
+    #[error("failed to connect to database")]
+    ConnectDatabase,
+
+    #[error("failed to query available tenants")]
+    QueryTenants,
+
+    #[error("failed to reconcile document box stats: {0}")]
+    Reconcile(DbErr),
+}
+
+pub async fn safe_reconcile_document_box_stats(db_cache: Arc<DatabasePoolCache>) {
+    if let Err(error) = reconcile_document_box_stats(db_cache).await {
+        tracing::error!(?error, "failed to reconcile document box stats for tenants");
+    }
+}
+
+/// Reconcile the document box stats counters for all tenants
+#[tracing::instrument(skip_all)]
+pub async fn reconcile_document_box_stats(
+    db_cache: Arc<DatabasePoolCache>,
+) -> Result<(), ReconcileDocumentBoxStatsError> {
+    let tenants = {
+        let db = db_cache.get_root_pool().await.map_err(|error| {
+            tracing::error!(?error, "failed to connect to root database");
+            ReconcileDocumentBoxStatsError::ConnectDatabase
+        })?;
+
+        Tenant::all(&db).await.map_err(|error| {
+            tracing::error!(?error, "failed to query available tenants");
+            ReconcileDocumentBoxStatsError::QueryTenants
+        })?
+    };
+
+    for tenant in tenants {
+        let db = match db_cache.get_tenant_pool(&tenant).await {
+            Ok(value) => value,
+            Err(error) => {
+                tracing::error!(?error, ?tenant, "failed to connect to tenant database");
+                continue;
+            }
+        };
+
+        match DocumentBoxStatsCounters::reconcile_all(&db, Utc::now()).await {
+            Ok(result) => {
+                tracing::debug!(
+                    ?tenant,
+                    reconciled = result.rows_affected(),
+                    "reconciled document box stats"
+                );
+            }
+            Err(error) => {
+                let error = ReconcileDocumentBoxStatsError::Reconcile(error);
+                tracing::error!(?error, ?tenant, "failed to reconcile document box stats");
+            }
+        }
+    }
+
+    Ok(())
+}
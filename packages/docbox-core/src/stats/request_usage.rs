@@ -0,0 +1,273 @@
+//! # Request Usage
+//!
+//! Meters the request and response bytes of each tenant for billing. Usage
+//! is recorded in memory and added to the daily rollup in the root database
+//! in batches by a background task to avoid a database write for every
+//! request
+//!
+//! Any accumulated usage is written when shutdown is requested
+
+use crate::shutdown::ShutdownCoordinator;
+use chrono::{NaiveDate, Utc};
+use docbox_database::{
+    DatabasePoolCache,
+    models::{
+        request_usage::{RequestUsage, RequestUsageIncrement},
+        tenant::TenantId,
+    },
+};
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::mpsc;
+
+/// Interval between writing the accumulated usage to the database
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Number of pending usage rows that will trigger writing the accumulated
+/// usage before the flush interval
+const FLUSH_THRESHOLD: usize = 1000;
+
+/// API key identifier used for requests made without an API key
+pub const NO_API_KEY_ID: &str = "none";
+
+/// Create the identifier stored for an API key, the key itself is never
+/// stored only a short fingerprint of it
+pub fn api_key_id(api_key: &str) -> String {
+    let digest = Sha256::digest(api_key.as_bytes());
+    hex::encode(&digest[..8])
+}
+
+/// Usage from a single request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestUsageEvent {
+    /// Environment of the tenant
+    pub env: String,
+    /// ID of the tenant
+    pub tenant_id: TenantId,
+    /// Identifier of the API key, see [api_key_id]
+    pub api_key_id: String,
+    /// Bytes received in the request body
+    pub request_bytes: u64,
+    /// Bytes sent in the response body
+    pub response_bytes: u64,
+    /// Whether the request body was a file upload
+    pub upload: bool,
+    /// Whether the response body was a file download
+    pub download: bool,
+}
+
+/// Records request usage, the usage is written to the root database
+/// in batches by a background task
+#[derive(Clone)]
+pub struct RequestUsageRecorder {
+    tx: mpsc::UnboundedSender<(NaiveDate, RequestUsageEvent)>,
+}
+
+impl RequestUsageRecorder {
+    /// Create a new recorder, spawns the background task that writes
+    /// the usage to the database
+    pub fn new(db_cache: Arc<DatabasePoolCache>, shutdown: &ShutdownCoordinator) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        shutdown.spawn(process_request_usage(db_cache, rx, shutdown.clone()));
+        Self { tx }
+    }
+
+    /// Record the usage of a request
+    pub fn record(&self, event: RequestUsageEvent) {
+        let day = Utc::now().date_naive();
+        if self.tx.send((day, event)).is_err() {
+            tracing::warn!("request usage recorder is not running, usage not recorded");
+        }
+    }
+}
+
+/// Key the usage is accumulated under
+type UsageKey = (String, TenantId, String, NaiveDate);
+
+/// Usage accumulated for each tenant, API key and day
+#[derive(Default)]
+struct RequestUsageBatch {
+    increments: HashMap<UsageKey, RequestUsageIncrement>,
+}
+
+impl RequestUsageBatch {
+    fn record(&mut self, day: NaiveDate, event: RequestUsageEvent) {
+        let request_bytes = i64::try_from(event.request_bytes).unwrap_or(i64::MAX);
+        let response_bytes = i64::try_from(event.response_bytes).unwrap_or(i64::MAX);
+
+        let key = (
+            event.env.clone(),
+            event.tenant_id,
+            event.api_key_id.clone(),
+            day,
+        );
+
+        let increment = self
+            .increments
+            .entry(key)
+            .or_insert_with(|| RequestUsageIncrement {
+                env: event.env,
+                tenant_id: event.tenant_id,
+                api_key_id: event.api_key_id,
+                day,
+                requests: 0,
+                request_bytes: 0,
+                response_bytes: 0,
+                upload_bytes: 0,
+                download_bytes: 0,
+            });
+
+        increment.requests += 1;
+        increment.request_bytes = increment.request_bytes.saturating_add(request_bytes);
+        increment.response_bytes = increment.response_bytes.saturating_add(response_bytes);
+
+        if event.upload {
+            increment.upload_bytes = increment.upload_bytes.saturating_add(request_bytes);
+        }
+
+        if event.download {
+            increment.download_bytes = increment.download_bytes.saturating_add(response_bytes);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.increments.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.increments.is_empty()
+    }
+}
+
+/// Background task accumulating the request usage and periodically
+/// writing it to the root database
+async fn process_request_usage(
+    db_cache: Arc<DatabasePoolCache>,
+    mut rx: mpsc::UnboundedReceiver<(NaiveDate, RequestUsageEvent)>,
+    shutdown: ShutdownCoordinator,
+) {
+    let mut batch = RequestUsageBatch::default();
+
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => {
+                let Some((day, event)) = event else {
+                    // All recorders have been dropped, write the remaining usage
+                    flush_request_usage(&db_cache, std::mem::take(&mut batch)).await;
+                    return;
+                };
+
+                batch.record(day, event);
+
+                if batch.len() < FLUSH_THRESHOLD {
+                    continue;
+                }
+            }
+            _ = interval.tick() => {}
+            _ = shutdown.cancelled() => {
+                // Write any usage that was already recorded before exiting
+                rx.close();
+                while let Ok((day, event)) = rx.try_recv() {
+                    batch.record(day, event);
+                }
+
+                flush_request_usage(&db_cache, std::mem::take(&mut batch)).await;
+                return;
+            }
+        }
+
+        if batch.is_empty() {
+            continue;
+        }
+
+        flush_request_usage(&db_cache, std::mem::take(&mut batch)).await;
+    }
+}
+
+/// Write the accumulated usage to the root database
+async fn flush_request_usage(db_cache: &DatabasePoolCache, batch: RequestUsageBatch) {
+    if batch.is_empty() {
+        return;
+    }
+
+    let db = match db_cache.get_root_pool().await {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::error!(
+                ?error,
+                "failed to connect to root database for request usage"
+            );
+            return;
+        }
+    };
+
+    for increment in batch.increments.into_values() {
+        if let Err(error) = RequestUsage::increment(&db, &increment).await {
+            tracing::error!(
+                ?error,
+                tenant_id = %increment.tenant_id,
+                "failed to store request usage"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RequestUsageBatch, RequestUsageEvent, api_key_id};
+    use chrono::NaiveDate;
+    use uuid::Uuid;
+
+    fn make_event(tenant_id: Uuid, upload: bool, download: bool) -> RequestUsageEvent {
+        RequestUsageEvent {
+            env: "Development".to_string(),
+            tenant_id,
+            api_key_id: "test".to_string(),
+            request_bytes: 10,
+            response_bytes: 20,
+            upload,
+            download,
+        }
+    }
+
+    #[test]
+    fn test_request_usage_batch() {
+        let mut batch = RequestUsageBatch::default();
+        let tenant_id = Uuid::new_v4();
+        let other_tenant_id = Uuid::new_v4();
+        let day = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let next_day = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+
+        batch.record(day, make_event(tenant_id, true, false));
+        batch.record(day, make_event(tenant_id, false, true));
+        batch.record(day, make_event(tenant_id, false, false));
+        batch.record(next_day, make_event(tenant_id, false, false));
+        batch.record(day, make_event(other_tenant_id, false, false));
+
+        assert_eq!(batch.len(), 3);
+
+        let increment = &batch.increments[&(
+            "Development".to_string(),
+            tenant_id,
+            "test".to_string(),
+            day,
+        )];
+        assert_eq!(increment.requests, 3);
+        assert_eq!(increment.request_bytes, 30);
+        assert_eq!(increment.response_bytes, 60);
+        assert_eq!(increment.upload_bytes, 10);
+        assert_eq!(increment.download_bytes, 20);
+    }
+
+    #[test]
+    fn test_api_key_id() {
+        let id = api_key_id("secret-key");
+        assert_eq!(id.len(), 16);
+        assert_eq!(id, api_key_id("secret-key"));
+        assert_ne!(id, api_key_id("other-key"));
+        assert!(!id.contains("secret"));
+    }
+}
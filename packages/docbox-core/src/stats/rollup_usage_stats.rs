@@ -0,0 +1,143 @@
+//! # Rollup Usage Stats
+//!
+//! Maintains the daily usage statistics rollup for each tenant. The current
+//! and previous day are recomputed on every run so that the previous day is
+//! finalized after midnight, days that were missed since the last run (Or the
+//! last [BACKFILL_DAYS] when the tenant has no statistics) are filled in
+
+use chrono::{Days, NaiveDate, Utc};
+use docbox_database::{
+    DatabasePoolCache, DbErr, DbPool,
+    models::{tenant::Tenant, usage_stats::UsageStatsDay},
+};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Number of days to compute statistics for when a tenant has
+/// no existing statistics
+pub const BACKFILL_DAYS: u64 = 90;
+
+#[derive(Debug, Error)]
+pub enum RollupUsageStatsError {
+    #[error("failed to connect to database")]
+    ConnectDatabase,
+
+    #[error("failed to query available tenants")]
+    QueryTenants,
+
+    #[error("failed to compute usage stats: {0}")]
+    Rollup(DbErr),
+}
+
+pub async fn safe_rollup_usage_stats(db_cache: Arc<DatabasePoolCache>) {
+    if let Err(error) = rollup_usage_stats(db_cache).await {
+        tracing::error!(?error, "failed to rollup usage stats for tenants");
+    }
+}
+
+/// Update the usage statistics rollup for all tenants
+#[tracing::instrument(skip_all)]
+pub async fn rollup_usage_stats(
+    db_cache: Arc<DatabasePoolCache>,
+) -> Result<(), RollupUsageStatsError> {
+    let tenants = {
+        let db = db_cache.get_root_pool().await.map_err(|error| {
+            tracing::error!(?error, "failed to connect to root database");
+            RollupUsageStatsError::ConnectDatabase
+        })?;
+
+        Tenant::all(&db).await.map_err(|error| {
+            tracing::error!(?error, "failed to query available tenants");
+            RollupUsageStatsError::QueryTenants
+        })?
+    };
+
+    let today = Utc::now().date_naive();
+
+    for tenant in tenants {
+        let db = match db_cache.get_tenant_pool(&tenant).await {
+            Ok(value) => value,
+            Err(error) => {
+                tracing::error!(?error, ?tenant, "failed to connect to tenant database");
+                continue;
+            }
+        };
+
+        if let Err(error) = rollup_tenant_usage_stats(&db, today).await {
+            tracing::error!(?error, ?tenant, "failed to rollup usage stats for tenant");
+        }
+    }
+
+    Ok(())
+}
+
+/// Update the usage statistics rollup for a tenant up to and including `today`
+pub async fn rollup_tenant_usage_stats(
+    db: &DbPool,
+    today: NaiveDate,
+) -> Result<(), RollupUsageStatsError> {
+    let latest_day = UsageStatsDay::latest_day(db)
+        .await
+        .map_err(RollupUsageStatsError::Rollup)?;
+
+    for day in rollup_days(latest_day, today) {
+        UsageStatsDay::rollup(db, day, Utc::now())
+            .await
+            .map_err(RollupUsageStatsError::Rollup)?;
+    }
+
+    Ok(())
+}
+
+/// Determine the days that must be computed given the `latest_day` that
+/// has statistics
+fn rollup_days(latest_day: Option<NaiveDate>, today: NaiveDate) -> Vec<NaiveDate> {
+    let yesterday = today.checked_sub_days(Days::new(1)).unwrap_or(today);
+
+    let start = match latest_day {
+        // Always recompute the previous day to finalize it
+        Some(latest_day) => latest_day.min(yesterday),
+        None => today
+            .checked_sub_days(Days::new(BACKFILL_DAYS))
+            .unwrap_or(today),
+    };
+
+    start.iter_days().take_while(|day| *day <= today).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BACKFILL_DAYS, rollup_days};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_rollup_days_recent() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 2).unwrap();
+        let days = rollup_days(Some(today), today);
+
+        assert_eq!(
+            days,
+            vec![NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(), today]
+        );
+    }
+
+    #[test]
+    fn test_rollup_days_missed() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 2).unwrap();
+        let latest = NaiveDate::from_ymd_opt(2025, 2, 27).unwrap();
+        let days = rollup_days(Some(latest), today);
+
+        assert_eq!(days.len(), 4);
+        assert_eq!(days.first(), Some(&latest));
+        assert_eq!(days.last(), Some(&today));
+    }
+
+    #[test]
+    fn test_rollup_days_backfill() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 2).unwrap();
+        let days = rollup_days(None, today);
+
+        assert_eq!(days.len() as u64, BACKFILL_DAYS + 1);
+        assert_eq!(days.last(), Some(&today));
+    }
+}
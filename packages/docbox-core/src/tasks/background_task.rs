@@ -17,6 +17,20 @@ pub async fn background_task<Fut>(
 ) -> DbResult<(TaskId, DateTime<Utc>)>
 where
     Fut: Future<Output = (TaskStatus, serde_json::Value)> + Send + 'static,
+{
+    background_task_with(db, scope, |_task| future).await
+}
+
+/// Variant of [background_task] where the future is created from the
+/// created [Task], allowing the future to report its progress
+pub async fn background_task_with<F, Fut>(
+    db: DbPool,
+    scope: DocumentBoxScopeRaw,
+    create_future: F,
+) -> DbResult<(TaskId, DateTime<Utc>)>
+where
+    F: FnOnce(Task) -> Fut,
+    Fut: Future<Output = (TaskStatus, serde_json::Value)> + Send + 'static,
 {
     // Create task for progression
     let mut task = Task::create(&db, scope).await?;
@@ -24,6 +38,7 @@ where
     let task_id = task.id;
     let created_at = task.created_at;
 
+    let future = create_future(task.clone());
     let span = tracing::Span::current();
 
     // Swap background task
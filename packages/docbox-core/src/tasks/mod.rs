@@ -1 +1,2 @@
 pub mod background_task;
+pub mod stuck_tasks;
@@ -0,0 +1,150 @@
+//! # Stuck Tasks
+//!
+//! Background tasks and presigned upload tasks are expected to leave the pending
+//! status shortly after being created. Tasks can become stuck in the pending status
+//! when the server is stopped while the task is running or when an upload
+//! notification is never received.
+//!
+//! Stuck tasks can be force failed so that clients polling the task stop waiting,
+//! presigned upload tasks can instead be retried using
+//! [retry_presigned_upload](crate::files::upload_file_presigned::retry_presigned_upload)
+
+use chrono::{Duration, Utc};
+use docbox_database::{
+    DbErr, DbPool, DbResult,
+    models::{
+        presigned_upload_task::{
+            PresignedTaskStatus, PresignedTaskStatusKind, PresignedUploadTask,
+            PresignedUploadTaskId,
+        },
+        tasks::{Task, TaskId, TaskStatus, TaskStatusCount},
+    },
+};
+use serde::Serialize;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Error message stored on tasks that are force failed
+const FORCE_FAILED_MESSAGE: &str = "task was force failed by an administrator";
+
+/// Tasks that have been pending for longer than a threshold
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StuckTasks {
+    /// Stuck background tasks
+    pub tasks: Vec<Task>,
+    /// Stuck presigned upload tasks
+    pub presigned_tasks: Vec<PresignedUploadTask>,
+}
+
+/// Number of tasks of each type with each status
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TaskCounts {
+    /// Number of background tasks with each status
+    pub tasks: Vec<TaskStatusCount>,
+    /// Number of presigned upload tasks with each status
+    pub presigned_tasks: Vec<PresignedTaskStatusCount>,
+}
+
+/// Number of presigned upload tasks with a specific status
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PresignedTaskStatusCount {
+    /// Status of the tasks
+    pub status: PresignedTaskStatusKind,
+    /// Number of tasks with the status
+    pub count: i64,
+}
+
+#[derive(Debug, Error)]
+pub enum ForceFailTaskError {
+    /// Database error occurred
+    #[error(transparent)]
+    Database(#[from] DbErr),
+
+    #[error("unknown task")]
+    UnknownTask,
+
+    #[error("task is not pending")]
+    NotPending,
+}
+
+/// Find the tasks that have been pending for longer than the `threshold`
+pub async fn find_stuck_tasks(db: &DbPool, threshold: Duration) -> DbResult<StuckTasks> {
+    let before = Utc::now() - threshold;
+
+    let tasks = Task::find_stuck(db, before).await?;
+    let presigned_tasks = PresignedUploadTask::find_stuck(db, before).await?;
+
+    Ok(StuckTasks {
+        tasks,
+        presigned_tasks,
+    })
+}
+
+/// Get the number of tasks of each type with each status
+pub async fn get_task_counts(db: &DbPool) -> DbResult<TaskCounts> {
+    let tasks = Task::status_counts(db).await?;
+
+    let mut presigned_tasks = Vec::new();
+    for status in [
+        PresignedTaskStatusKind::Pending,
+        PresignedTaskStatusKind::Completed,
+        PresignedTaskStatusKind::Failed,
+        PresignedTaskStatusKind::Expired,
+    ] {
+        let count = PresignedUploadTask::total(db, Some(status)).await?;
+        presigned_tasks.push(PresignedTaskStatusCount { status, count });
+    }
+
+    Ok(TaskCounts {
+        tasks,
+        presigned_tasks,
+    })
+}
+
+/// Force a pending background task into the failed status
+pub async fn force_fail_task(db: &DbPool, task_id: TaskId) -> Result<Task, ForceFailTaskError> {
+    let mut task = Task::find_by_id(db, task_id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query task"))?
+        .ok_or(ForceFailTaskError::UnknownTask)?;
+
+    if task.status != TaskStatus::Pending {
+        return Err(ForceFailTaskError::NotPending);
+    }
+
+    task.complete_task(
+        db,
+        TaskStatus::Failed,
+        Some(serde_json::json!({ "error": FORCE_FAILED_MESSAGE })),
+    )
+    .await
+    .inspect_err(|error| tracing::error!(?error, "failed to fail task"))?;
+
+    Ok(task)
+}
+
+/// Force a pending presigned upload task into the failed status
+pub async fn force_fail_presigned_task(
+    db: &DbPool,
+    task_id: PresignedUploadTaskId,
+) -> Result<PresignedUploadTask, ForceFailTaskError> {
+    let mut task = PresignedUploadTask::find_by_id(db, task_id)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query presigned upload task"))?
+        .ok_or(ForceFailTaskError::UnknownTask)?;
+
+    if task.status != PresignedTaskStatus::Pending {
+        return Err(ForceFailTaskError::NotPending);
+    }
+
+    task.set_status(
+        db,
+        PresignedTaskStatus::Failed {
+            error: FORCE_FAILED_MESSAGE.to_string(),
+        },
+    )
+    .await
+    .inspect_err(|error| tracing::error!(?error, "failed to fail presigned upload task"))?;
+
+    Ok(task)
+}
@@ -0,0 +1,71 @@
+use crate::{
+    tasks::background_task::background_task,
+    tenant::rebuild_tenant_index::{apply_rebuilt_tenant_index, recreate_search_index_data},
+};
+use docbox_database::{
+    DbErr, DbPool,
+    models::{document_box::DocumentBoxScopeRaw, tasks::TaskStatus},
+};
+use docbox_search::{SearchError, TenantSearchIndex};
+use docbox_storage::StorageLayer;
+use thiserror::Error;
+use tracing::Instrument;
+
+#[derive(Debug, Error)]
+pub enum HealTenantIndexError {
+    #[error(transparent)]
+    Search(#[from] SearchError),
+    #[error("failed to create re-index task: {0}")]
+    CreateTask(DbErr),
+}
+
+/// Heal the tenant search index after a write to the index failed, when the
+/// index no longer exists (i.e it was deleted out-of-band) the index is
+/// recreated with the current schema and a background task is started to
+/// re-index the existing tenant data
+///
+/// The background task is tracked against the document box `scope` the failed
+/// write was made in, returns whether the index was recreated
+pub async fn heal_tenant_index(
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    storage: &StorageLayer,
+    scope: &DocumentBoxScopeRaw,
+) -> Result<bool, HealTenantIndexError> {
+    if !search.heal_index().await? {
+        return Ok(false);
+    }
+
+    let span = tracing::Span::current();
+    let (task_id, _created_at) = background_task(db.clone(), scope.clone(), {
+        let db = db.clone();
+        let search = search.clone();
+        let storage = storage.clone();
+
+        async move {
+            let result = match recreate_search_index_data(&db, &storage).await {
+                Ok(data) => apply_rebuilt_tenant_index(&search, data)
+                    .await
+                    .map_err(|error| error.to_string()),
+                Err(error) => Err(error.to_string()),
+            };
+
+            match result {
+                Ok(()) => (TaskStatus::Completed, serde_json::Value::Null),
+                Err(error) => {
+                    tracing::error!(%error, "failed to re-index healed search index");
+                    (TaskStatus::Failed, serde_json::json!({ "error": error }))
+                }
+            }
+        }
+        // Ensure the logging span is passed onto the background task so that
+        // logging context continues
+        .instrument(span)
+    })
+    .await
+    .map_err(HealTenantIndexError::CreateTask)?;
+
+    tracing::info!(%task_id, "recreated missing search index, re-indexing in background");
+
+    Ok(true)
+}
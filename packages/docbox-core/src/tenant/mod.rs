@@ -1,3 +1,6 @@
+pub mod heal_tenant_index;
 pub mod rebuild_tenant_index;
 pub mod tenant_cache;
 pub mod tenant_options_ext;
+pub mod tenant_region;
+pub mod tenant_storage_key;
@@ -8,7 +8,9 @@ use docbox_database::{
         link::{Link, LinkWithScope},
     },
 };
-use docbox_processing::{office::is_pdf_compatible, pdf::split_pdf_text_pages};
+use docbox_processing::{
+    office::is_pdf_compatible, pdf::split_pdf_text_pages, text_stats::compute_text_stats,
+};
 use docbox_search::{
     SearchError, TenantSearchIndex,
     models::{DocumentPage, SearchIndexData, SearchIndexType},
@@ -124,8 +126,11 @@ pub async fn create_links_index_data(db: &DbPool) -> DbResult<Vec<SearchIndexDat
                 mime: None,
                 content: Some(link.value.clone()),
                 pages: None,
+                summary: None,
+                text_stats: None,
                 created_at: link.created_at,
                 created_by: link.created_by.clone(),
+                pinned: link.pinned,
                 document_box: scope.clone(),
             })
         }
@@ -173,8 +178,11 @@ pub async fn create_folders_index_data(db: &DbPool) -> DbResult<Vec<SearchIndexD
                 mime: None,
                 content: None,
                 pages: None,
+                summary: None,
+                text_stats: None,
                 created_at: folder.created_at,
                 created_by: folder.created_by.clone(),
+                pinned: folder.pinned,
                 document_box: folder.document_box.clone(),
             })
         }
@@ -227,8 +235,11 @@ pub async fn create_files_index_data(
                     content: None,
                     created_at: file.created_at,
                     created_by: file.created_by,
+                    pinned: file.pinned,
                     document_box: scope,
                     pages: None,
+                    summary: None,
+                    text_stats: None,
                 })
             } else {
                 // File needs additional processing
@@ -262,12 +273,18 @@ pub async fn create_files_index_data(
                                     content: None,
                                     created_at: file.created_at,
                                     created_by: file.created_by.clone(),
+                                    pinned: file.pinned,
                                     document_box: scope.clone(),
                                     pages: None,
+                                    summary: None,
+                                    text_stats: None,
                                 };
                             }
                         };
 
+                    let summary = try_file_summary(db, storage, scope, file).await;
+                    let text_stats = compute_text_stats(&pages);
+
                     SearchIndexData {
                         ty: SearchIndexType::File,
                         item_id: file.id,
@@ -277,8 +294,11 @@ pub async fn create_files_index_data(
                         content: None,
                         created_at: file.created_at,
                         created_by: file.created_by.clone(),
+                        pinned: file.pinned,
                         document_box: scope.clone(),
                         pages: Some(pages),
+                        summary,
+                        text_stats: Some(text_stats),
                     }
                 })
             })
@@ -346,8 +366,37 @@ pub async fn try_pdf_compatible_document_pages(
         .map(|(page, content)| DocumentPage {
             page: page as u64,
             content: content.to_string(),
+            words: None,
         })
         .collect();
 
     Ok(pages)
 }
+
+/// Attempts to load the generated summary for a file, files without a summary
+/// or where the summary fails to load are indexed without a summary
+pub async fn try_file_summary(
+    db: &DbPool,
+    storage: &StorageLayer,
+    scope: &DocumentBoxScopeRaw,
+    file: &File,
+) -> Option<String> {
+    let summary_file = GeneratedFile::find(db, scope, file.id, GeneratedFileType::Summary)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to query file summary"))
+        .ok()??;
+
+    let summary = storage
+        .get_file(&summary_file.file_key)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to load file summary"))
+        .ok()?
+        .collect_bytes()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to load file summary"))
+        .ok()?;
+
+    String::from_utf8(summary.to_vec())
+        .inspect_err(|error| tracing::error!(?error, "file summary is not valid utf8"))
+        .ok()
+}
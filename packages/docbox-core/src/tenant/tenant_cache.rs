@@ -2,24 +2,113 @@
 //!
 //! Provides caching for tenants to ensure we don't have to fetch the tenant
 //! from the database for every request
+//!
+//! Cached tenants are expired after a fixed duration (TTL) even when they are
+//! still in use, so changes made to a tenant outside of this server (i.e through
+//! the management tools) are picked up without a restart. Individual tenants can
+//! be removed from the cache immediately using [TenantCache::invalidate_tenant]
+//!
+//! ## Environment Variables
+//!
+//! * `DOCBOX_TENANT_CACHE_TTL` - Duration in seconds tenants remain cached for after being loaded
+//! * `DOCBOX_TENANT_CACHE_IDLE` - Duration in seconds unused tenants remain cached for
+//! * `DOCBOX_TENANT_CACHE_CAPACITY` - Maximum number of tenants to cache
 
 use docbox_database::{
     DbPool, DbResult,
     models::tenant::{Tenant, TenantId},
 };
-use moka::{future::Cache, policy::EvictionPolicy};
-use std::time::Duration;
+use moka::{future::Cache, notification::RemovalCause, policy::EvictionPolicy};
+use serde::{Deserialize, Serialize};
+use std::{
+    num::ParseIntError,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Default duration tenants remain cached for after being loaded (5 minutes)
+const DEFAULT_TENANT_CACHE_TTL: Duration = Duration::from_secs(60 * 5);
+
+/// Default duration unused tenants remain cached for (15 minutes)
+const DEFAULT_TENANT_CACHE_IDLE: Duration = Duration::from_secs(60 * 15);
+
+/// Default maximum tenants to keep in cache
+const DEFAULT_TENANT_CACHE_CAPACITY: u64 = 50;
+
+/// Configuration for the tenant cache
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenantCacheConfig {
+    /// Duration in seconds tenants remain cached for after being loaded,
+    /// bounds how long changes to a tenant take to apply
+    pub ttl_seconds: Option<u64>,
+    /// Duration in seconds unused tenants remain cached for
+    pub idle_seconds: Option<u64>,
+    /// Maximum number of tenants to cache
+    pub capacity: Option<u64>,
+}
+
+#[derive(Debug, Error)]
+pub enum TenantCacheConfigError {
+    /// Invalid TTL seconds
+    #[error("DOCBOX_TENANT_CACHE_TTL must be a number in seconds")]
+    InvalidTtl(ParseIntError),
+    /// Invalid idle seconds
+    #[error("DOCBOX_TENANT_CACHE_IDLE must be a number in seconds")]
+    InvalidIdle(ParseIntError),
+    /// Invalid capacity
+    #[error("DOCBOX_TENANT_CACHE_CAPACITY must be a number")]
+    InvalidCapacity(ParseIntError),
+}
+
+impl TenantCacheConfig {
+    /// Load the tenant cache config from the environment
+    pub fn from_env() -> Result<TenantCacheConfig, TenantCacheConfigError> {
+        let ttl_seconds = std::env::var("DOCBOX_TENANT_CACHE_TTL")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(TenantCacheConfigError::InvalidTtl)
+            })
+            .transpose()?;
+
+        let idle_seconds = std::env::var("DOCBOX_TENANT_CACHE_IDLE")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(TenantCacheConfigError::InvalidIdle)
+            })
+            .transpose()?;
 
-/// Duration to maintain tenant caches (15 minutes)
-const TENANT_CACHE_DURATION: Duration = Duration::from_secs(60 * 15);
+        let capacity = std::env::var("DOCBOX_TENANT_CACHE_CAPACITY")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(TenantCacheConfigError::InvalidCapacity)
+            })
+            .transpose()?;
 
-/// Maximum tenants to keep in cache
-const TENANT_CACHE_CAPACITY: u64 = 50;
+        Ok(TenantCacheConfig {
+            ttl_seconds,
+            idle_seconds,
+            capacity,
+        })
+    }
+}
 
 /// Cache for recently used tenants
 #[derive(Clone)]
 pub struct TenantCache {
     cache: Cache<TenantCacheKey, Tenant>,
+    metrics: Arc<TenantCacheMetrics>,
 }
 
 /// Cache key to identify a tenant
@@ -29,6 +118,47 @@ struct TenantCacheKey {
     tenant_id: TenantId,
 }
 
+/// Counters for the usage of the tenant cache
+#[derive(Default)]
+struct TenantCacheMetrics {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    capacity_evictions: AtomicU64,
+    expired_evictions: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl TenantCacheMetrics {
+    fn record_removal(&self, cause: RemovalCause) {
+        let counter = match cause {
+            RemovalCause::Size => &self.capacity_evictions,
+            RemovalCause::Expired => &self.expired_evictions,
+            RemovalCause::Explicit => &self.invalidations,
+            // Replacing a tenant is not a removal
+            RemovalCause::Replaced => return,
+        };
+
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Statistics about the tenant cache
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct TenantCacheStats {
+    /// Number of tenants currently held in the cache
+    pub tenants: u64,
+    /// Number of tenant lookups served from the cache
+    pub hits: u64,
+    /// Number of tenant lookups that loaded the tenant from the database
+    pub misses: u64,
+    /// Number of tenants removed to stay within the cache capacity
+    pub capacity_evictions: u64,
+    /// Number of tenants removed after reaching the TTL or being idle
+    pub expired_evictions: u64,
+    /// Number of tenants removed by invalidating or flushing the cache
+    pub invalidations: u64,
+}
+
 impl Default for TenantCache {
     fn default() -> Self {
         Self::new()
@@ -36,15 +166,37 @@ impl Default for TenantCache {
 }
 
 impl TenantCache {
-    /// Create a new tenant cache
+    /// Create a new tenant cache using the default config
     pub fn new() -> Self {
+        Self::from_config(TenantCacheConfig::default())
+    }
+
+    /// Create a new tenant cache from the provided `config`
+    pub fn from_config(config: TenantCacheConfig) -> Self {
+        let ttl = config
+            .ttl_seconds
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TENANT_CACHE_TTL);
+        let idle = config
+            .idle_seconds
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TENANT_CACHE_IDLE);
+        let capacity = config.capacity.unwrap_or(DEFAULT_TENANT_CACHE_CAPACITY);
+
+        let metrics = Arc::new(TenantCacheMetrics::default());
+
         let cache = Cache::builder()
-            .time_to_idle(TENANT_CACHE_DURATION)
-            .max_capacity(TENANT_CACHE_CAPACITY)
+            .time_to_live(ttl)
+            .time_to_idle(idle)
+            .max_capacity(capacity)
             .eviction_policy(EvictionPolicy::tiny_lfu())
+            .eviction_listener({
+                let metrics = metrics.clone();
+                move |_key, _tenant, cause| metrics.record_removal(cause)
+            })
             .build();
 
-        Self { cache }
+        Self { cache, metrics }
     }
 
     /// Get a tenant by ID
@@ -57,9 +209,12 @@ impl TenantCache {
         let cache_key = TenantCacheKey { env, tenant_id };
 
         if let Some(tenant) = self.cache.get(&cache_key).await {
+            self.metrics.hits.fetch_add(1, Ordering::Relaxed);
             return Ok(Some(tenant.clone()));
         }
 
+        self.metrics.misses.fetch_add(1, Ordering::Relaxed);
+
         let tenant = Tenant::find_by_id(db, tenant_id, &cache_key.env).await?;
 
         if let Some(tenant) = tenant.as_ref() {
@@ -69,8 +224,111 @@ impl TenantCache {
         Ok(tenant)
     }
 
+    /// Remove a specific tenant from the cache
+    pub async fn invalidate(&self, env: String, tenant_id: TenantId) {
+        self.cache
+            .invalidate(&TenantCacheKey { env, tenant_id })
+            .await;
+    }
+
+    /// Remove all tenants with the provided `tenant_id` from the cache
+    /// regardless of the environment they belong to
+    ///
+    /// Returns the number of cached tenants that were removed
+    pub async fn invalidate_tenant(&self, tenant_id: TenantId) -> usize {
+        let keys: Vec<Arc<TenantCacheKey>> = self
+            .cache
+            .iter()
+            .filter(|(key, _)| key.tenant_id == tenant_id)
+            .map(|(key, _)| key)
+            .collect();
+
+        for key in &keys {
+            self.cache.invalidate(key.as_ref()).await;
+        }
+
+        keys.len()
+    }
+
+    /// Get statistics about the usage of the cache
+    pub async fn stats(&self) -> TenantCacheStats {
+        self.cache.run_pending_tasks().await;
+
+        TenantCacheStats {
+            tenants: self.cache.entry_count(),
+            hits: self.metrics.hits.load(Ordering::Relaxed),
+            misses: self.metrics.misses.load(Ordering::Relaxed),
+            capacity_evictions: self.metrics.capacity_evictions.load(Ordering::Relaxed),
+            expired_evictions: self.metrics.expired_evictions.load(Ordering::Relaxed),
+            invalidations: self.metrics.invalidations.load(Ordering::Relaxed),
+        }
+    }
+
     /// Clear the cache
     pub async fn flush(&self) {
         self.cache.invalidate_all();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{TenantCache, TenantCacheConfig, TenantCacheKey};
+    use docbox_database::models::tenant::{Tenant, TenantId};
+
+    fn tenant(id: TenantId, env: &str) -> Tenant {
+        Tenant {
+            id,
+            name: "test".to_string(),
+            db_name: "test".to_string(),
+            db_secret_name: Some("test".to_string()),
+            db_iam_user_name: None,
+            s3_name: "test".to_string(),
+            os_index_name: "test".to_string(),
+            env: env.to_string(),
+            event_queue_url: None,
+            storage_key_secret_name: None,
+            storage_deduplication: false,
+            event_config: None,
+            data_region: None,
+            maintenance_mode: false,
+            active_content_policy: Default::default(),
+            deleted_at: None,
+            search_language: None,
+        }
+    }
+
+    async fn insert(cache: &TenantCache, tenant: Tenant) {
+        cache
+            .cache
+            .insert(
+                TenantCacheKey {
+                    env: tenant.env.clone(),
+                    tenant_id: tenant.id,
+                },
+                tenant,
+            )
+            .await;
+    }
+
+    /// Tests that invalidating a tenant removes it from every environment
+    /// without affecting other tenants
+    #[tokio::test]
+    async fn test_invalidate_tenant() {
+        let cache = TenantCache::from_config(TenantCacheConfig::default());
+        let tenant_id = TenantId::new_v4();
+        let other_id = TenantId::new_v4();
+
+        insert(&cache, tenant(tenant_id, "Development")).await;
+        insert(&cache, tenant(tenant_id, "Production")).await;
+        insert(&cache, tenant(other_id, "Development")).await;
+
+        assert_eq!(cache.invalidate_tenant(tenant_id).await, 2);
+        assert_eq!(cache.invalidate_tenant(tenant_id).await, 0);
+
+        let stats = cache.stats().await;
+        assert_eq!(stats.tenants, 1);
+        assert_eq!(stats.invalidations, 2);
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.misses, 0);
+    }
+}
@@ -1,8 +1,14 @@
 use docbox_database::models::tenant::Tenant;
-use docbox_storage::StorageLayerOptions;
+use docbox_storage::{ObjectTag, StorageLayerOptions};
 
 /// Extension trait for [Tenant] to provide storage layer options
 /// to initialize a [`docbox_storage::StorageLayer`]
+///
+/// Files uploaded through the layer are tagged with the tenant ID and environment
+///
+/// The provided options do not include the tenant storage encryption keys,
+/// use [TenantStorageKeyCache](super::tenant_storage_key::TenantStorageKeyCache)
+/// when the layer is used to read or write file contents
 pub trait TenantOptionsExt {
     fn storage_layer_options(&self) -> StorageLayerOptions;
 }
@@ -11,6 +17,12 @@ impl TenantOptionsExt for Tenant {
     fn storage_layer_options(&self) -> StorageLayerOptions {
         StorageLayerOptions {
             bucket_name: self.s3_name.clone(),
+            encryption: None,
+            deduplicate: self.storage_deduplication,
+            tags: vec![
+                ObjectTag::new(ObjectTag::TENANT_ID, &self.id.to_string()),
+                ObjectTag::new(ObjectTag::TENANT_ENV, &self.env),
+            ],
         }
     }
 }
@@ -0,0 +1,105 @@
+//! # Tenant Region
+//!
+//! Tenants can be pinned to a data residency region (i.e `eu-west-1`), tenants
+//! with a region can only be provisioned onto and served from storage and search
+//! backends configured for the same region.
+//!
+//! Backends declare their region through their configuration:
+//! - Storage: `DOCBOX_S3_DATA_REGION`
+//! - Search: `DOCBOX_SEARCH_DATA_REGION`
+//!
+//! Tenants without a region are not restricted
+
+use docbox_search::SearchIndexFactory;
+use docbox_storage::StorageLayerFactory;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TenantRegionError {
+    /// Backend is configured for a different region
+    #[error("{backend} backend region {actual} does not match tenant region {expected}")]
+    Mismatch {
+        backend: &'static str,
+        expected: String,
+        actual: String,
+    },
+
+    /// Backend does not declare a region
+    #[error("{backend} backend has no configured region, tenant requires region {expected}")]
+    Unconfigured {
+        backend: &'static str,
+        expected: String,
+    },
+}
+
+/// Verify that the storage and search backends are configured for the
+/// tenant `region`
+pub fn verify_tenant_region(
+    region: Option<&str>,
+    storage: &StorageLayerFactory,
+    search: &SearchIndexFactory,
+) -> Result<(), TenantRegionError> {
+    let Some(region) = region else {
+        return Ok(());
+    };
+
+    verify_backend_region(region, "storage", storage.data_region())?;
+    verify_backend_region(region, "search", search.data_region())?;
+    Ok(())
+}
+
+/// Verify that the `actual` region of a `backend` matches the `expected` region
+fn verify_backend_region(
+    expected: &str,
+    backend: &'static str,
+    actual: Option<&str>,
+) -> Result<(), TenantRegionError> {
+    match actual {
+        Some(actual) if actual == expected => Ok(()),
+        Some(actual) => Err(TenantRegionError::Mismatch {
+            backend,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }),
+        None => Err(TenantRegionError::Unconfigured {
+            backend,
+            expected: expected.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TenantRegionError, verify_backend_region};
+
+    #[test]
+    fn test_matching_region() {
+        assert_eq!(
+            verify_backend_region("eu-west-1", "storage", Some("eu-west-1")),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_mismatched_region() {
+        assert_eq!(
+            verify_backend_region("eu-west-1", "storage", Some("us-east-1")),
+            Err(TenantRegionError::Mismatch {
+                backend: "storage",
+                expected: "eu-west-1".to_string(),
+                actual: "us-east-1".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unconfigured_region() {
+        assert_eq!(
+            verify_backend_region("eu-west-1", "search", None),
+            Err(TenantRegionError::Unconfigured {
+                backend: "search",
+                expected: "eu-west-1".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! # Tenant Storage Key
+//!
+//! Per-tenant data keys used to encrypt file contents at rest. The keys are
+//! stored as a JSON secret within the secret manager (Which in turn is encrypted
+//! using KMS when using AWS secrets manager) in the following format:
+//!
+//! ```json
+//! {
+//!     "current": 2,
+//!     "keys": {
+//!         "1": "<BASE64 ENCODED KEY>",
+//!         "2": "<BASE64 ENCODED KEY>"
+//!     }
+//! }
+//! ```
+//!
+//! Previous keys are retained after a rotation so that existing files can
+//! continue to be decrypted until they are re-encrypted with the current key
+
+use crate::tenant::tenant_options_ext::TenantOptionsExt;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use docbox_database::models::tenant::Tenant;
+use docbox_secrets::{SecretManager, SecretManagerError};
+use docbox_storage::{
+    StorageLayerOptions,
+    encryption::{
+        STORAGE_KEY_LENGTH, StorageEncryptionError, StorageEncryptionKey, StorageEncryptionKeys,
+    },
+};
+use moka::{future::Cache, policy::EvictionPolicy};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use thiserror::Error;
+
+/// Duration to maintain storage key caches (15 minutes)
+const STORAGE_KEY_CACHE_DURATION: Duration = Duration::from_secs(60 * 15);
+
+/// Maximum storage keys to keep in cache
+const STORAGE_KEY_CACHE_CAPACITY: u64 = 50;
+
+#[derive(Debug, Error)]
+pub enum TenantStorageKeyError {
+    /// Failed to load the secret from the secret manager
+    #[error("failed to load storage key secret")]
+    GetSecret(SecretManagerError),
+
+    /// Storage key secret is missing from the secret manager
+    #[error("storage key secret is missing")]
+    MissingSecret,
+
+    /// Secret contained a key that was not valid
+    #[error("storage key version {0} is invalid")]
+    InvalidKey(u32),
+
+    /// Secret key ring was not valid
+    #[error(transparent)]
+    InvalidKeys(StorageEncryptionError),
+}
+
+/// Storage key secret stored in the secret manager
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TenantStorageKeySecret {
+    /// Current key version used to encrypt new files
+    pub current: u32,
+    /// Base64 encoded keys by version
+    pub keys: BTreeMap<u32, String>,
+}
+
+impl TenantStorageKeySecret {
+    /// Create a new secret with a freshly generated key
+    pub fn generate() -> Self {
+        let mut secret = Self {
+            current: 0,
+            keys: BTreeMap::new(),
+        };
+        secret.rotate();
+        secret
+    }
+
+    /// Generate a new key and make it the current key, previous keys
+    /// are retained. Provides the version of the new key
+    pub fn rotate(&mut self) -> u32 {
+        let version = self
+            .keys
+            .keys()
+            .next_back()
+            .copied()
+            .unwrap_or_default()
+            .max(self.current)
+            + 1;
+
+        let key = StorageEncryptionKey::generate();
+        self.keys
+            .insert(version, BASE64_STANDARD.encode(key.as_bytes()));
+        self.current = version;
+        version
+    }
+
+    /// Remove all keys other than the current key
+    pub fn prune(&mut self) {
+        let current = self.current;
+        self.keys.retain(|version, _| *version == current);
+    }
+
+    /// Decode the secret into the storage encryption keys
+    pub fn to_keys(&self) -> Result<StorageEncryptionKeys, TenantStorageKeyError> {
+        let keys = self
+            .keys
+            .iter()
+            .map(|(version, key)| {
+                let key: [u8; STORAGE_KEY_LENGTH] = BASE64_STANDARD
+                    .decode(key)
+                    .ok()
+                    .and_then(|key| key.try_into().ok())
+                    .ok_or(TenantStorageKeyError::InvalidKey(*version))?;
+
+                Ok((*version, StorageEncryptionKey::from_bytes(key)))
+            })
+            .collect::<Result<BTreeMap<_, _>, TenantStorageKeyError>>()?;
+
+        StorageEncryptionKeys::new(self.current, keys).map_err(TenantStorageKeyError::InvalidKeys)
+    }
+}
+
+/// Cache for loaded tenant storage encryption keys, provides storage layer
+/// options that include the tenant encryption keys
+#[derive(Clone)]
+pub struct TenantStorageKeyCache {
+    secrets: SecretManager,
+    cache: Cache<String, Arc<StorageEncryptionKeys>>,
+}
+
+impl TenantStorageKeyCache {
+    /// Create a new storage key cache
+    pub fn new(secrets: SecretManager) -> Self {
+        let cache = Cache::builder()
+            .time_to_idle(STORAGE_KEY_CACHE_DURATION)
+            .max_capacity(STORAGE_KEY_CACHE_CAPACITY)
+            .eviction_policy(EvictionPolicy::tiny_lfu())
+            .build();
+
+        Self { secrets, cache }
+    }
+
+    /// Get the storage layer options for the `tenant` including the
+    /// encryption keys if the tenant has encryption enabled
+    pub async fn storage_layer_options(
+        &self,
+        tenant: &Tenant,
+    ) -> Result<StorageLayerOptions, TenantStorageKeyError> {
+        let mut options = tenant.storage_layer_options();
+
+        if let Some(secret_name) = tenant.storage_key_secret_name.as_ref() {
+            options.encryption = Some(self.get_keys(secret_name).await?);
+        }
+
+        Ok(options)
+    }
+
+    /// Get the storage encryption keys stored in `secret_name`
+    async fn get_keys(
+        &self,
+        secret_name: &str,
+    ) -> Result<Arc<StorageEncryptionKeys>, TenantStorageKeyError> {
+        if let Some(keys) = self.cache.get(secret_name).await {
+            return Ok(keys);
+        }
+
+        let keys = load_storage_keys(&self.secrets, secret_name).await?;
+        let keys = Arc::new(keys);
+
+        self.cache
+            .insert(secret_name.to_string(), keys.clone())
+            .await;
+
+        Ok(keys)
+    }
+
+    /// Clear the cache
+    pub async fn flush(&self) {
+        self.cache.invalidate_all();
+    }
+}
+
+/// Load the storage encryption keys stored in `secret_name` from the secret manager
+pub async fn load_storage_keys(
+    secrets: &SecretManager,
+    secret_name: &str,
+) -> Result<StorageEncryptionKeys, TenantStorageKeyError> {
+    let secret: TenantStorageKeySecret = secrets
+        .parsed_secret(secret_name)
+        .await
+        .map_err(TenantStorageKeyError::GetSecret)?
+        .ok_or(TenantStorageKeyError::MissingSecret)?;
+
+    secret.to_keys()
+}
+
+#[cfg(test)]
+mod test {
+    use super::TenantStorageKeySecret;
+
+    #[test]
+    fn test_rotate_storage_key() {
+        let mut secret = TenantStorageKeySecret::generate();
+        assert_eq!(secret.current, 1);
+
+        let keys = secret.to_keys().unwrap();
+        let encrypted = keys.encrypt(b"test").unwrap();
+
+        assert_eq!(secret.rotate(), 2);
+        assert_eq!(secret.keys.len(), 2);
+
+        // Previous key can still decrypt
+        let keys = secret.to_keys().unwrap();
+        assert_eq!(keys.current_version(), 2);
+        assert_eq!(keys.decrypt(encrypted).unwrap().as_ref(), b"test");
+
+        secret.prune();
+        assert_eq!(secret.keys.len(), 1);
+        assert!(secret.keys.contains_key(&2));
+    }
+
+    #[test]
+    fn test_invalid_storage_key() {
+        let mut secret = TenantStorageKeySecret::generate();
+        secret.keys.insert(1, "invalid".to_string());
+        assert!(secret.to_keys().is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! Compensating actions for operations that create resources across multiple
+//! services (storage, search) that cannot share a single transaction.
+//!
+//! Each stage of an operation records the step required to undo the resource
+//! it created once the resource exists. When a later stage fails the recorded
+//! steps are performed in reverse order to clean up after the operation.
+
+use docbox_search::TenantSearchIndex;
+use docbox_storage::StorageLayer;
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Step performed to undo a single created resource
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RollbackStep {
+    /// Delete a file that was uploaded to the storage layer
+    DeleteStorageFile { key: String },
+    /// Delete an item that was added to the search index
+    DeleteSearchIndex { item_id: Uuid },
+}
+
+/// Recorded rollback steps for an operation
+#[derive(Debug, Default)]
+pub struct Rollback {
+    steps: Vec<RollbackStep>,
+}
+
+impl Rollback {
+    /// Record a file uploaded to storage at `key`
+    pub fn storage_file(&mut self, key: impl Into<String>) {
+        self.steps
+            .push(RollbackStep::DeleteStorageFile { key: key.into() });
+    }
+
+    /// Record an item added to the search index
+    pub fn search_index(&mut self, item_id: Uuid) {
+        self.steps.push(RollbackStep::DeleteSearchIndex { item_id });
+    }
+
+    /// Recorded steps in the order they were recorded
+    pub fn steps(&self) -> &[RollbackStep] {
+        &self.steps
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Perform the recorded steps in reverse order
+    ///
+    /// Failing steps are logged and do not prevent the remaining steps from
+    /// being attempted, returns the steps that failed
+    pub async fn run(
+        self,
+        search: &TenantSearchIndex,
+        storage: &StorageLayer,
+    ) -> Vec<RollbackStep> {
+        let mut failed = Vec::new();
+
+        for step in self.steps.into_iter().rev() {
+            let result = match &step {
+                RollbackStep::DeleteStorageFile { key } => storage
+                    .delete_file(key)
+                    .await
+                    .inspect_err(|error| {
+                        tracing::error!(?error, %key, "failed to rollback created storage file");
+                    })
+                    .is_ok(),
+                RollbackStep::DeleteSearchIndex { item_id } => search
+                    .delete_data(*item_id)
+                    .await
+                    .inspect_err(|error| {
+                        tracing::error!(?error, %item_id, "failed to rollback created search index");
+                    })
+                    .is_ok(),
+            };
+
+            if !result {
+                failed.push(step);
+            }
+        }
+
+        failed
+    }
+
+    /// Perform the recorded steps in a background task
+    pub fn run_background(self, search: TenantSearchIndex, storage: StorageLayer) {
+        if self.is_empty() {
+            return;
+        }
+
+        let span = tracing::Span::current();
+
+        tokio::spawn(
+            async move {
+                let failed = self.run(&search, &storage).await;
+                if !failed.is_empty() {
+                    tracing::warn!(?failed, "rollback completed with failed steps");
+                }
+            }
+            .instrument(span),
+        );
+    }
+}
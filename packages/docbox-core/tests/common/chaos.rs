@@ -0,0 +1,42 @@
+use docbox_core::tenant::tenant_options_ext::TenantOptionsExt;
+use docbox_database::models::tenant::Tenant;
+use docbox_search::{
+    ChaosSearchConfig, ChaosSearchIndexFactory, MemorySearchIndexFactory, SearchIndexFactory,
+    TenantSearchIndex,
+};
+use docbox_storage::{
+    StorageLayer, StorageLayerFactory,
+    chaos::{ChaosStorageConfig, ChaosStorageLayerFactory},
+    memory::MemoryStorageLayerFactory,
+};
+
+/// Create an in-memory storage layer for the tenant wrapped with fault injection,
+/// faults can be controlled through the returned factory
+#[allow(dead_code)]
+pub async fn test_chaos_storage(tenant: &Tenant) -> (StorageLayer, ChaosStorageLayerFactory) {
+    let factory = ChaosStorageLayerFactory::new(
+        StorageLayerFactory::Memory(MemoryStorageLayerFactory::new()),
+        ChaosStorageConfig::default(),
+    );
+
+    let storage =
+        StorageLayerFactory::Chaos(factory.clone()).create_layer(tenant.storage_layer_options());
+    storage.create_bucket().await.unwrap();
+
+    (storage, factory)
+}
+
+/// Create an in-memory search index for the tenant wrapped with fault injection,
+/// faults can be controlled through the returned factory
+#[allow(dead_code)]
+pub async fn test_chaos_search(tenant: &Tenant) -> (TenantSearchIndex, ChaosSearchIndexFactory) {
+    let factory = ChaosSearchIndexFactory::new(
+        SearchIndexFactory::Memory(MemorySearchIndexFactory::new()),
+        ChaosSearchConfig::default(),
+    );
+
+    let search = SearchIndexFactory::Chaos(factory.clone()).create_search_index(tenant);
+    search.create_index().await.unwrap();
+
+    (search, factory)
+}
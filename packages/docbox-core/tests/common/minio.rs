@@ -45,9 +45,13 @@ pub async fn test_storage_factory(container: &ContainerAsync<MinIO>) -> StorageL
         access_key_secret: TEST_MINIO_PASSWORD.to_string(),
     };
 
-    let config = S3StorageLayerFactoryConfig { endpoint };
+    let config = S3StorageLayerFactoryConfig {
+        endpoint,
+        data_region: None,
+        ..Default::default()
+    };
 
-    StorageLayerFactory::S3(S3StorageLayerFactory::from_config(&aws_config, config))
+    StorageLayerFactory::S3(S3StorageLayerFactory::from_config(&aws_config, config).unwrap())
 }
 
 #[allow(dead_code)]
@@ -1,3 +1,4 @@
+pub mod chaos;
 pub mod database;
 pub mod minio;
 pub mod processing;
@@ -37,7 +37,29 @@ pub async fn test_processing_layer(
     let converter = OfficeConverter::ConverterServer(converter_server);
 
     ProcessingLayer {
-        office: OfficeProcessingLayer { converter },
+        office: OfficeProcessingLayer {
+            converter,
+            circuit_breaker: Default::default(),
+        },
         config,
+        summary: None,
+    }
+}
+
+/// Create a processing layer with a converter that cannot be reached, for
+/// tests that do not perform office conversion
+#[allow(dead_code)]
+pub fn test_unavailable_processing_layer(config: ProcessingLayerConfig) -> ProcessingLayer {
+    let converter_server =
+        OfficeConverterServer::from_addresses(["http://127.0.0.1:1"], false).unwrap();
+    let converter = OfficeConverter::ConverterServer(converter_server);
+
+    ProcessingLayer {
+        office: OfficeProcessingLayer {
+            converter,
+            circuit_breaker: Default::default(),
+        },
+        config,
+        summary: None,
     }
 }
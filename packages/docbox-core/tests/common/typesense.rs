@@ -41,6 +41,8 @@ pub async fn test_search_factory(container: &ContainerAsync<GenericImage>) -> Se
         url,
         api_key: Some(TypesenseApiKey::new(TEST_API_KEY.to_string())),
         api_key_secret_name: None,
+        data_region: None,
+        language: None,
     };
 
     let secrets = SecretManager::Memory(MemorySecretManager::default());
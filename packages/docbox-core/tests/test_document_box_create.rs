@@ -1,5 +1,6 @@
 use crate::common::database::test_tenant_db;
 use docbox_core::{
+    database::models::scope_pattern::{CreateScopePattern, ScopePattern},
     document_box::create_document_box::{
         CreateDocumentBox, CreateDocumentBoxError, create_document_box,
     },
@@ -88,3 +89,49 @@ async fn test_create_document_box_duplicate_scope() {
 
     assert!(matches!(error, CreateDocumentBoxError::ScopeAlreadyExists))
 }
+
+/// Creating a document box with a scope that doesn't match any of the
+/// registered scope patterns should produce an error
+#[tokio::test]
+async fn test_create_document_box_unregistered_scope() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    let events = TenantEventPublisher::Noop(NoopEventPublisher);
+
+    ScopePattern::create(
+        &db,
+        CreateScopePattern {
+            pattern: "org:*".to_string(),
+            parent: None,
+            description: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    // Should succeed
+    create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "org:1".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    // Should fail
+    let error = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "user:1".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap_err();
+
+    assert!(matches!(error, CreateDocumentBoxError::ScopeNotRegistered))
+}
@@ -0,0 +1,429 @@
+use crate::common::{
+    chaos::{test_chaos_search, test_chaos_storage},
+    database::test_tenant_db,
+    processing::test_unavailable_processing_layer,
+    tenant::test_tenant,
+};
+use docbox_core::{
+    document_box::create_document_box::{CreateDocumentBox, create_document_box},
+    events::TenantEventPublisher,
+    files::{
+        delete_file::{DeleteFileError, delete_file},
+        generated::{restore_generated_files, snapshot_generated_files},
+        upload_file::{UploadFile, UploadFileError, upload_file},
+    },
+    utils::rollback::{Rollback, RollbackStep},
+};
+use docbox_database::models::{
+    file::File,
+    folder::FolderId,
+    generated_file::{GeneratedFile, GeneratedFileType},
+    storage_object::StorageObject,
+};
+use docbox_processing::ProcessingLayerConfig;
+use docbox_search::{ChaosSearchConfig, SearchOperation, models::SearchRequest};
+use docbox_storage::{
+    UploadFileOptions,
+    chaos::{ChaosStorageConfig, StorageOperation},
+};
+use std::time::Duration;
+use uuid::Uuid;
+
+mod common;
+
+fn test_upload(document_box: &str, folder_id: FolderId, file_id: Uuid) -> UploadFile {
+    UploadFile {
+        fixed_id: Some(file_id),
+        parent_id: None,
+        folder_id,
+        document_box: document_box.to_string(),
+        name: "test.txt".to_string(),
+        mime: mime::TEXT_PLAIN,
+        file_bytes: "test".into(),
+        created_by: None,
+        file_key: None,
+        processing_config: None,
+        task_id: None,
+    }
+}
+
+/// Tests that a failure to store the file in storage does not leave
+/// the file in the database
+#[tokio::test]
+async fn test_upload_storage_failure() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, _search_faults) = test_chaos_search(&tenant).await;
+    let (storage, storage_faults) = test_chaos_storage(&tenant).await;
+    let processing = test_unavailable_processing_layer(ProcessingLayerConfig::default());
+
+    let events = TenantEventPublisher::Noop(Default::default());
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    storage_faults.set_config(ChaosStorageConfig::fail([StorageOperation::UploadFile]));
+
+    let file_id = Uuid::new_v4();
+    let error = upload_file(
+        &db,
+        &search,
+        &storage,
+        &processing,
+        &events,
+        test_upload(&document_box.scope, root.id, file_id),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, UploadFileError::UploadFile(_)));
+
+    let file = File::find(&db, &document_box.scope, file_id).await.unwrap();
+    assert!(file.is_none());
+
+    // Search index entry created before the storage upload is rolled back
+    let mut total_hits = 1;
+    for _ in 0..50 {
+        total_hits = search
+            .search_index(
+                std::slice::from_ref(&document_box.scope),
+                SearchRequest {
+                    query: Some("test".to_string()),
+                    include_name: true,
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .unwrap()
+            .total_hits;
+
+        if total_hits == 0 {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(total_hits, 0);
+}
+
+/// Tests that rollback steps which fail are reported while the remaining
+/// steps are still performed
+#[tokio::test]
+async fn test_rollback_failed_steps() {
+    let tenant = test_tenant();
+
+    let (search, _search_faults) = test_chaos_search(&tenant).await;
+    let (storage, storage_faults) = test_chaos_storage(&tenant).await;
+
+    storage_faults.set_config(ChaosStorageConfig::fail([StorageOperation::DeleteFile]));
+
+    let item_id = Uuid::new_v4();
+    let mut rollback = Rollback::default();
+    rollback.storage_file("test.txt");
+    rollback.search_index(item_id);
+
+    let failed = rollback.run(&search, &storage).await;
+    assert_eq!(
+        failed,
+        vec![RollbackStep::DeleteStorageFile {
+            key: "test.txt".to_string()
+        }]
+    );
+}
+
+/// Tests that a failure to index the file rolls back the upload, the
+/// stored file is removed in the background
+#[tokio::test]
+async fn test_upload_search_failure_rollback() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, search_faults) = test_chaos_search(&tenant).await;
+    let (storage, storage_faults) = test_chaos_storage(&tenant).await;
+    let processing = test_unavailable_processing_layer(ProcessingLayerConfig::default());
+
+    let events = TenantEventPublisher::Noop(Default::default());
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    search_faults.set_config(ChaosSearchConfig::fail([SearchOperation::AddData]));
+    // Slow down storage to ensure the rollback is not racing the upload
+    storage_faults.set_config(ChaosStorageConfig {
+        latency: Some(Duration::from_millis(10)..=Duration::from_millis(50)),
+        ..Default::default()
+    });
+
+    let file_id = Uuid::new_v4();
+    let error = upload_file(
+        &db,
+        &search,
+        &storage,
+        &processing,
+        &events,
+        test_upload(&document_box.scope, root.id, file_id),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, UploadFileError::CreateIndex(_)));
+
+    let file = File::find(&db, &document_box.scope, file_id).await.unwrap();
+    assert!(file.is_none());
+}
+
+/// Tests that a failure to delete the file from storage keeps the file
+/// in the database so the deletion can be retried
+#[tokio::test]
+async fn test_delete_storage_failure_retry() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, _search_faults) = test_chaos_search(&tenant).await;
+    let (storage, storage_faults) = test_chaos_storage(&tenant).await;
+    let processing = test_unavailable_processing_layer(ProcessingLayerConfig::default());
+
+    let events = TenantEventPublisher::Noop(Default::default());
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let file_id = Uuid::new_v4();
+    let upload = upload_file(
+        &db,
+        &search,
+        &storage,
+        &processing,
+        &events,
+        test_upload(&document_box.scope, root.id, file_id),
+    )
+    .await
+    .unwrap();
+
+    storage_faults.set_config(ChaosStorageConfig::fail([StorageOperation::DeleteFile]));
+
+    let error = delete_file(
+        &db,
+        &storage,
+        &search,
+        &events,
+        upload.file.clone(),
+        document_box.scope.clone(),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, DeleteFileError::DeleteFileStorage(_)));
+
+    let file = File::find(&db, &document_box.scope, file_id).await.unwrap();
+    assert!(file.is_some());
+
+    // Retry once storage has recovered
+    storage_faults.set_config(ChaosStorageConfig::default());
+
+    delete_file(
+        &db,
+        &storage,
+        &search,
+        &events,
+        upload.file,
+        document_box.scope.clone(),
+    )
+    .await
+    .unwrap();
+
+    let file = File::find(&db, &document_box.scope, file_id).await.unwrap();
+    assert!(file.is_none());
+}
+
+/// Tests that a failed delete of a deduplicated file followed by a retry only
+/// releases the file's reference once, the object shared with another file
+/// must remain in storage
+#[tokio::test]
+async fn test_delete_deduplicated_failure_retry() {
+    let mut tenant = test_tenant();
+    tenant.storage_deduplication = true;
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, search_faults) = test_chaos_search(&tenant).await;
+    let (storage, _storage_faults) = test_chaos_storage(&tenant).await;
+    let processing = test_unavailable_processing_layer(ProcessingLayerConfig::default());
+
+    let events = TenantEventPublisher::Noop(Default::default());
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let first = upload_file(
+        &db,
+        &search,
+        &storage,
+        &processing,
+        &events,
+        test_upload(&document_box.scope, root.id, Uuid::new_v4()),
+    )
+    .await
+    .unwrap()
+    .file;
+
+    let second = upload_file(
+        &db,
+        &search,
+        &storage,
+        &processing,
+        &events,
+        test_upload(&document_box.scope, root.id, Uuid::new_v4()),
+    )
+    .await
+    .unwrap()
+    .file;
+
+    assert_eq!(first.file_key, second.file_key);
+
+    search_faults.set_config(ChaosSearchConfig::fail([SearchOperation::DeleteData]));
+
+    let error = delete_file(
+        &db,
+        &storage,
+        &search,
+        &events,
+        first.clone(),
+        document_box.scope.clone(),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, DeleteFileError::DeleteIndex(_)));
+
+    // Reference must not be released by the failed delete
+    let object = StorageObject::find(&db, &first.file_key)
+        .await
+        .unwrap()
+        .expect("expected storage object");
+    assert_eq!(object.reference_count, 2);
+
+    // Retry once search has recovered
+    search_faults.set_config(ChaosSearchConfig::default());
+
+    delete_file(
+        &db,
+        &storage,
+        &search,
+        &events,
+        first.clone(),
+        document_box.scope.clone(),
+    )
+    .await
+    .unwrap();
+
+    // Deleting an already deleted file must not release the reference again
+    delete_file(
+        &db,
+        &storage,
+        &search,
+        &events,
+        first.clone(),
+        document_box.scope.clone(),
+    )
+    .await
+    .unwrap();
+
+    let object = StorageObject::find(&db, &first.file_key)
+        .await
+        .unwrap()
+        .expect("expected storage object");
+    assert_eq!(object.reference_count, 1);
+
+    // Object should still exist for the remaining file
+    storage.get_file(&second.file_key).await.unwrap();
+}
+
+fn test_generated_file(file_key: &str) -> GeneratedFile {
+    GeneratedFile {
+        id: Uuid::new_v4(),
+        file_id: Uuid::new_v4(),
+        mime: "application/pdf".to_string(),
+        ty: GeneratedFileType::Pdf,
+        hash: String::new(),
+        file_key: file_key.to_string(),
+        created_at: chrono::Utc::now(),
+        page: None,
+    }
+}
+
+/// Tests that generated files within the snapshot limit can be restored after
+/// being deleted from storage
+#[tokio::test]
+async fn test_generated_file_snapshot_restore() {
+    let tenant = test_tenant();
+    let (storage, storage_faults) = test_chaos_storage(&tenant).await;
+
+    let small = test_generated_file("small.pdf");
+    let large = test_generated_file("large.pdf");
+
+    for (file, contents) in [(&small, "small"), (&large, "larger file")] {
+        storage
+            .upload_file(
+                &file.file_key,
+                contents.into(),
+                UploadFileOptions {
+                    content_type: file.mime.clone(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+    }
+
+    // Only the small file fits within the limit
+    let files = [small.clone(), large.clone()];
+    let snapshots = snapshot_generated_files(&storage, &files, 8).await;
+    assert_eq!(snapshots.len(), 1);
+
+    storage.delete_file(&small.file_key).await.unwrap();
+    storage.delete_file(&large.file_key).await.unwrap();
+
+    // Restoring fails while storage is unavailable
+    storage_faults.set_config(ChaosStorageConfig::fail([StorageOperation::UploadFile]));
+    let failed = restore_generated_files(&storage, &snapshots, &[small.id]).await;
+    assert_eq!(failed, vec![small.id]);
+
+    storage_faults.set_config(ChaosStorageConfig::default());
+    let failed = restore_generated_files(&storage, &snapshots, &[small.id, large.id]).await;
+    assert_eq!(failed, vec![large.id]);
+
+    let bytes = storage
+        .get_file(&small.file_key)
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert_eq!(bytes.as_ref(), b"small");
+}
@@ -7,10 +7,12 @@ use crate::common::{
 };
 use docbox_core::{
     document_box::create_document_box::{CreateDocumentBox, create_document_box},
-    events::TenantEventPublisher,
-    files::upload_file::{UploadFile, upload_file},
+    events::{TenantEventMessage, TenantEventPublisher, mpsc::MpscEventPublisher},
+    files::upload_file::{UploadFile, upload_file, upload_files},
 };
+use docbox_database::models::file::File;
 use docbox_processing::ProcessingLayerConfig;
+use uuid::Uuid;
 
 mod common;
 
@@ -56,8 +58,144 @@ async fn test_file_create_success() {
             created_by: None,
             file_key: None,
             processing_config: None,
+            task_id: None,
         },
     )
     .await
     .unwrap();
 }
+
+/// Tests that multiple files can be uploaded together
+#[tokio::test]
+async fn test_file_create_multiple_success() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, _search_container) = test_tenant_search(&tenant).await;
+    let (storage, _storage_container) = test_tenant_storage(&tenant).await;
+
+    let converter_container = test_office_convert_server_container().await;
+    let processing =
+        test_processing_layer(&converter_container, ProcessingLayerConfig::default()).await;
+
+    let events = TenantEventPublisher::Noop(Default::default());
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let uploads = ["first.txt", "second.txt"]
+        .into_iter()
+        .map(|name| UploadFile {
+            fixed_id: None,
+            parent_id: None,
+            folder_id: root.id,
+            document_box: document_box.scope.clone(),
+            name: name.to_string(),
+            mime: mime::TEXT_PLAIN,
+            file_bytes: name.as_bytes().to_vec().into(),
+            created_by: None,
+            file_key: None,
+            processing_config: None,
+            task_id: None,
+        })
+        .collect();
+
+    let output = upload_files(&db, &search, &storage, &processing, &events, uploads)
+        .await
+        .unwrap();
+
+    // Outputs are in the same order as the uploads
+    let names: Vec<&str> = output.iter().map(|data| data.file.name.as_str()).collect();
+    assert_eq!(names, vec!["first.txt", "second.txt"]);
+
+    for data in &output {
+        let file = File::find(&db, &document_box.scope, data.file.id)
+            .await
+            .unwrap();
+        assert!(file.is_some());
+    }
+}
+
+/// Tests that processing started and completed events are published with the
+/// task ID when a file upload succeeds
+#[tokio::test]
+async fn test_file_create_processing_events() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, _search_container) = test_tenant_search(&tenant).await;
+    let (storage, _storage_container) = test_tenant_storage(&tenant).await;
+
+    let converter_container = test_office_convert_server_container().await;
+    let processing =
+        test_processing_layer(&converter_container, ProcessingLayerConfig::default()).await;
+
+    let (events, mut events_rx) = MpscEventPublisher::new();
+    let events = TenantEventPublisher::Mpsc(events);
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    // Consume creation event
+    _ = events_rx.recv().await.unwrap();
+
+    let task_id = Uuid::new_v4();
+    let output = upload_file(
+        &db,
+        &search,
+        &storage,
+        &processing,
+        &events,
+        UploadFile {
+            fixed_id: None,
+            parent_id: None,
+            folder_id: root.id,
+            document_box: document_box.scope.clone(),
+            name: "test.txt".to_string(),
+            mime: mime::TEXT_PLAIN,
+            file_bytes: "test".into(),
+            created_by: None,
+            file_key: None,
+            processing_config: None,
+            task_id: Some(task_id),
+        },
+    )
+    .await
+    .unwrap();
+
+    let event = events_rx.recv().await.unwrap();
+    assert!(matches!(
+        event,
+        TenantEventMessage::FileProcessingStarted(started)
+            if started.data.task_id == Some(task_id) && started.data.name == "test.txt"
+    ));
+
+    let event = events_rx.recv().await.unwrap();
+    assert!(matches!(
+        event,
+        TenantEventMessage::FileCreated(created) if created.data.id == output.file.id
+    ));
+
+    let event = events_rx.recv().await.unwrap();
+    assert!(matches!(
+        event,
+        TenantEventMessage::FileProcessingCompleted(completed)
+            if completed.data.task_id == Some(task_id)
+                && completed.data.file.id == output.file.id
+                && completed.scope == "test"
+    ));
+}
@@ -13,7 +13,7 @@ use docbox_core::{
         upload_file::{UploadFile, upload_file},
     },
 };
-use docbox_database::models::file::File;
+use docbox_database::models::{file::File, storage_object::StorageObject};
 use docbox_processing::ProcessingLayerConfig;
 use uuid::Uuid;
 
@@ -61,6 +61,7 @@ async fn test_file_delete_success() {
             created_by: None,
             file_key: None,
             processing_config: None,
+            task_id: None,
         },
     )
     .await
@@ -129,3 +130,95 @@ async fn test_file_delete_unknown_no_event() {
     // Should have nothing to consume
     assert!(events_rx.try_recv().is_err());
 }
+
+/// Tests that files with identical contents share a single object when
+/// deduplication is enabled and that the object is only removed once the
+/// last file referencing it is deleted
+#[tokio::test]
+async fn test_file_delete_deduplicated() {
+    let mut tenant = test_tenant();
+    tenant.storage_deduplication = true;
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, _search_container) = test_tenant_search(&tenant).await;
+    let (storage, _storage_container) = test_tenant_storage(&tenant).await;
+
+    let converter_container = test_office_convert_server_container().await;
+    let processing =
+        test_processing_layer(&converter_container, ProcessingLayerConfig::default()).await;
+
+    let events = TenantEventPublisher::Noop(Default::default());
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut files = Vec::new();
+    for name in ["test.txt", "test-copy.txt"] {
+        let file = upload_file(
+            &db,
+            &search,
+            &storage,
+            &processing,
+            &events,
+            UploadFile {
+                fixed_id: None,
+                parent_id: None,
+                folder_id: root.id,
+                document_box: document_box.scope.clone(),
+                name: name.to_string(),
+                mime: mime::TEXT_PLAIN,
+                file_bytes: "test".into(),
+                created_by: None,
+                file_key: None,
+                processing_config: None,
+                task_id: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        files.push(file.file);
+    }
+
+    let first = files.remove(0);
+    let second = files.remove(0);
+
+    // Both files should share the same object
+    assert_eq!(first.file_key, second.file_key);
+    let object = StorageObject::find(&db, &first.file_key)
+        .await
+        .unwrap()
+        .expect("expected storage object");
+    assert_eq!(object.reference_count, 2);
+
+    let file_key = first.file_key.clone();
+
+    delete_file(
+        &db,
+        &storage,
+        &search,
+        &events,
+        first,
+        document_box.scope.clone(),
+    )
+    .await
+    .unwrap();
+
+    // Object should still exist for the remaining file
+    storage.get_file(&file_key).await.unwrap();
+
+    delete_file(&db, &storage, &search, &events, second, document_box.scope)
+        .await
+        .unwrap();
+
+    // Object should be removed once unreferenced
+    storage.get_file(&file_key).await.unwrap_err();
+    assert!(StorageObject::find(&db, &file_key).await.unwrap().is_none());
+}
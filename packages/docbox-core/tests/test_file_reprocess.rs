@@ -0,0 +1,195 @@
+use crate::common::{
+    chaos::{test_chaos_search, test_chaos_storage},
+    database::test_tenant_db,
+    processing::test_unavailable_processing_layer,
+    tenant::test_tenant,
+};
+use chrono::Utc;
+use docbox_core::{
+    document_box::create_document_box::{CreateDocumentBox, create_document_box},
+    events::TenantEventPublisher,
+    files::{
+        reprocess_outdated_files::reprocess_outdated_files,
+        upload_file::{UploadFile, upload_file},
+    },
+};
+use docbox_database::models::{
+    file_processing::{FileProcessing, ProcessingTimings},
+    folder::FolderId,
+    generated_file::GeneratedFile,
+};
+use docbox_processing::{PROCESSING_PIPELINE_VERSION, ProcessingLayerConfig};
+use docbox_search::{ChaosSearchConfig, SearchOperation};
+
+mod common;
+
+const TEST_EMAIL: &str = "From: sender@example.com\r\n\
+    To: recipient@example.com\r\n\
+    Subject: Test\r\n\
+    Content-Type: text/plain\r\n\
+    \r\n\
+    Test email body\r\n";
+
+fn test_email_upload(document_box: &str, folder_id: FolderId) -> UploadFile {
+    UploadFile {
+        fixed_id: None,
+        parent_id: None,
+        folder_id,
+        document_box: document_box.to_string(),
+        name: "test.eml".to_string(),
+        mime: "message/rfc822".parse().unwrap(),
+        file_bytes: TEST_EMAIL.into(),
+        created_by: None,
+        file_key: None,
+        processing_config: None,
+        task_id: None,
+    }
+}
+
+/// Tests that files processed with an outdated processing pipeline version
+/// are reprocessed and have their generated files replaced
+#[tokio::test]
+async fn test_reprocess_outdated_file() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, _search_faults) = test_chaos_search(&tenant).await;
+    let (storage, _storage_faults) = test_chaos_storage(&tenant).await;
+    let processing = test_unavailable_processing_layer(ProcessingLayerConfig::default());
+
+    let events = TenantEventPublisher::Noop(Default::default());
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let upload = upload_file(
+        &db,
+        &search,
+        &storage,
+        &processing,
+        &events,
+        test_email_upload(&document_box.scope, root.id),
+    )
+    .await
+    .unwrap();
+    let file_id = upload.file.id;
+    assert!(!upload.generated.is_empty());
+
+    // Processing version of a file processed by an older pipeline
+    FileProcessing::set(
+        &db,
+        file_id,
+        PROCESSING_PIPELINE_VERSION - 1,
+        Utc::now(),
+        &ProcessingTimings::default(),
+    )
+    .await
+    .unwrap();
+
+    let outcome = reprocess_outdated_files(&db, &search, &storage, &processing)
+        .await
+        .unwrap();
+    assert_eq!(outcome.reprocessed, 1);
+    assert_eq!(outcome.failed, 0);
+
+    let file_processing = FileProcessing::find(&db, file_id).await.unwrap().unwrap();
+    assert_eq!(file_processing.version, PROCESSING_PIPELINE_VERSION);
+
+    // Generated files are replaced with newly generated files
+    let generated = GeneratedFile::find_all(&db, file_id).await.unwrap();
+    assert_eq!(generated.len(), upload.generated.len());
+    for previous in &upload.generated {
+        assert!(
+            generated
+                .iter()
+                .all(|generated| generated.id != previous.id)
+        );
+        assert!(storage.get_file(&previous.file_key).await.is_err());
+    }
+
+    for generated in &generated {
+        assert!(storage.get_file(&generated.file_key).await.is_ok());
+    }
+
+    // Files using the current version are not reprocessed again
+    let outcome = reprocess_outdated_files(&db, &search, &storage, &processing)
+        .await
+        .unwrap();
+    assert_eq!(outcome.reprocessed, 0);
+    assert_eq!(outcome.failed, 0);
+}
+
+/// Tests that a file failing to reprocess keeps its previous generated files
+/// and remains outdated so it can be reprocessed again
+#[tokio::test]
+async fn test_reprocess_outdated_file_failure() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, search_faults) = test_chaos_search(&tenant).await;
+    let (storage, _storage_faults) = test_chaos_storage(&tenant).await;
+    let processing = test_unavailable_processing_layer(ProcessingLayerConfig::default());
+
+    let events = TenantEventPublisher::Noop(Default::default());
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let upload = upload_file(
+        &db,
+        &search,
+        &storage,
+        &processing,
+        &events,
+        test_email_upload(&document_box.scope, root.id),
+    )
+    .await
+    .unwrap();
+    let file_id = upload.file.id;
+
+    FileProcessing::set(
+        &db,
+        file_id,
+        PROCESSING_PIPELINE_VERSION - 1,
+        Utc::now(),
+        &ProcessingTimings::default(),
+    )
+    .await
+    .unwrap();
+
+    // Fail after the new generated files have been uploaded
+    search_faults.set_config(ChaosSearchConfig::fail([SearchOperation::DeleteData]));
+
+    let outcome = reprocess_outdated_files(&db, &search, &storage, &processing)
+        .await
+        .unwrap();
+    assert_eq!(outcome.reprocessed, 0);
+    assert_eq!(outcome.failed, 1);
+
+    let file_processing = FileProcessing::find(&db, file_id).await.unwrap().unwrap();
+    assert_eq!(file_processing.version, PROCESSING_PIPELINE_VERSION - 1);
+
+    let mut generated = GeneratedFile::find_all(&db, file_id).await.unwrap();
+    let mut previous = upload.generated.clone();
+    generated.sort_by_key(|generated| generated.id);
+    previous.sort_by_key(|generated| generated.id);
+    assert_eq!(generated, previous);
+
+    for generated in &generated {
+        assert!(storage.get_file(&generated.file_key).await.is_ok());
+    }
+}
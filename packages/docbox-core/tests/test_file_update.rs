@@ -63,6 +63,7 @@ async fn test_update_file_name_success() {
             created_by: None,
             file_key: None,
             processing_config: None,
+            task_id: None,
         },
     )
     .await
@@ -80,6 +81,7 @@ async fn test_update_file_name_success() {
             folder_id: None,
             name: Some("Other Name Which Should Never Match.txt".to_string()),
             pinned: None,
+            expected_version: None,
         },
     )
     .await
@@ -200,6 +202,7 @@ async fn test_update_file_folder_success() {
             created_by: None,
             file_key: None,
             processing_config: None,
+            task_id: None,
         },
     )
     .await
@@ -230,6 +233,7 @@ async fn test_update_file_folder_success() {
             folder_id: Some(new_folder.id),
             name: None,
             pinned: None,
+            expected_version: None,
         },
     )
     .await
@@ -332,6 +336,7 @@ async fn test_update_file_pinned_success() {
             created_by: None,
             file_key: None,
             processing_config: None,
+            task_id: None,
         },
     )
     .await
@@ -349,6 +354,7 @@ async fn test_update_file_pinned_success() {
             folder_id: None,
             name: None,
             pinned: Some(true),
+            expected_version: None,
         },
     )
     .await
@@ -407,6 +413,7 @@ async fn test_update_file_folder_unknown_error() {
             created_by: None,
             file_key: None,
             processing_config: None,
+            task_id: None,
         },
     )
     .await
@@ -422,6 +429,7 @@ async fn test_update_file_folder_unknown_error() {
             folder_id: Some(Uuid::nil()),
             name: None,
             pinned: None,
+            expected_version: None,
         },
     )
     .await
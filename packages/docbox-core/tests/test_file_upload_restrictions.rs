@@ -63,6 +63,7 @@ async fn test_email_unpack_limiting_defaults() {
             created_by: None,
             file_key: None,
             processing_config: None,
+            task_id: None,
         },
     )
     .await
@@ -130,6 +131,7 @@ async fn test_email_unpack_limiting_increased_limit_2() {
                 max_unpack_iterations: Some(2),
                 ..Default::default()
             }),
+            task_id: None,
         },
     )
     .await
@@ -195,6 +197,7 @@ async fn test_email_unpack_limiting_increased_limit_3() {
                 max_unpack_iterations: Some(3),
                 ..Default::default()
             }),
+            task_id: None,
         },
     )
     .await
@@ -260,6 +263,7 @@ async fn test_email_unpack_limiting_zero() {
             created_by: None,
             file_key: None,
             processing_config: None,
+            task_id: None,
         },
     )
     .await
@@ -324,6 +328,7 @@ async fn test_email_unpack_limiting_zero_request() {
                 max_unpack_iterations: Some(0),
                 ..Default::default()
             }),
+            task_id: None,
         },
     )
     .await
@@ -6,7 +6,10 @@ use docbox_core::{
         update_folder::{UpdateFolder, UpdateFolderError, update_folder},
     },
 };
-use docbox_database::models::folder::Folder;
+use docbox_database::models::{
+    edit_history::{EditHistory, EditHistoryMetadata},
+    folder::Folder,
+};
 use docbox_search::models::{SearchIndexType, SearchRequest};
 use uuid::Uuid;
 
@@ -58,6 +61,7 @@ async fn test_update_folder_name_success() {
             folder_id: None,
             name: Some("Other Name Which Should Never Match".to_string()),
             pinned: None,
+            expected_version: None,
         },
     )
     .await
@@ -195,6 +199,7 @@ async fn test_update_folder_folder_success() {
             folder_id: Some(new_folder.id),
             name: None,
             pinned: None,
+            expected_version: None,
         },
     )
     .await
@@ -297,6 +302,7 @@ async fn test_update_folder_pinned_success() {
             folder_id: None,
             name: None,
             pinned: Some(true),
+            expected_version: None,
         },
     )
     .await
@@ -320,6 +326,7 @@ async fn test_update_folder_pinned_success() {
             folder_id: None,
             name: None,
             pinned: Some(false),
+            expected_version: None,
         },
     )
     .await
@@ -379,6 +386,7 @@ async fn test_update_folder_folder_unknown() {
             folder_id: Some(Uuid::nil()),
             name: None,
             pinned: None,
+            expected_version: None,
         },
     )
     .await
@@ -436,6 +444,7 @@ async fn test_update_folder_folder_self() {
             folder_id: Some(folder.id),
             name: None,
             pinned: None,
+            expected_version: None,
         },
     )
     .await
@@ -471,6 +480,7 @@ async fn test_update_folder_folder_self() {
             folder_id: Some(child_folder.id),
             name: None,
             pinned: None,
+            expected_version: None,
         },
     )
     .await
@@ -513,6 +523,7 @@ async fn test_update_folder_folder_root() {
             folder_id: None,
             name: None,
             pinned: None,
+            expected_version: None,
         },
     )
     .await
@@ -523,3 +534,110 @@ async fn test_update_folder_folder_root() {
         "modifying root should result in a failure"
     );
 }
+
+/// Tests that a folder move can be reverted using its edit history entry
+#[tokio::test]
+async fn test_update_folder_revert_move() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, _search_container) = test_tenant_search(&tenant).await;
+
+    let events = TenantEventPublisher::Noop(NoopEventPublisher);
+    let (_document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut folders = Vec::new();
+    for name in ["Source", "Target"] {
+        let folder = safe_create_folder(
+            &db,
+            search.clone(),
+            &events,
+            CreateFolderData {
+                folder: root.clone(),
+                name: name.to_string(),
+                created_by: None,
+            },
+        )
+        .await
+        .unwrap();
+        folders.push(folder);
+    }
+
+    let source = folders.remove(0);
+    let target = folders.remove(0);
+
+    let folder = safe_create_folder(
+        &db,
+        search.clone(),
+        &events,
+        CreateFolderData {
+            folder: source.clone(),
+            name: "Moved".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    update_folder(
+        &db,
+        &search,
+        &"test".to_string(),
+        folder.clone(),
+        None,
+        UpdateFolder {
+            folder_id: Some(target.id),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let history = EditHistory::all_by_folder(&db, folder.id).await.unwrap();
+    let entry = history.first().unwrap();
+    assert_eq!(
+        entry.metadata.0,
+        EditHistoryMetadata::MoveToFolder {
+            original_id: source.id,
+            target_id: target.id,
+        }
+    );
+
+    let folder = Folder::find_by_id(&db, &"test".to_string(), folder.id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let update = UpdateFolder::revert(&entry.metadata).unwrap();
+    update_folder(&db, &search, &"test".to_string(), folder, None, update)
+        .await
+        .unwrap();
+
+    let reverted = Folder::find_by_id(&db, &"test".to_string(), entry.folder_id.unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(reverted.folder_id, Some(source.id));
+
+    // Revert is recorded as a new change
+    let history = EditHistory::all_by_folder(&db, reverted.id).await.unwrap();
+    assert_eq!(history.len(), 2);
+
+    // Rejected updates cannot be reverted
+    assert!(
+        UpdateFolder::revert(&EditHistoryMetadata::UpdateConflict {
+            expected_version: 0,
+            current_version: 1,
+        })
+        .is_none()
+    );
+}
@@ -1,4 +1,7 @@
-use crate::common::{database::test_tenant_db, tenant::test_tenant, typesense::test_tenant_search};
+use crate::common::{
+    database::test_tenant_db, minio::test_tenant_storage, tenant::test_tenant,
+    typesense::test_tenant_search,
+};
 use docbox_core::{
     document_box::create_document_box::{CreateDocumentBox, create_document_box},
     events::{TenantEventMessage, TenantEventPublisher, mpsc::MpscEventPublisher},
@@ -7,7 +10,7 @@ use docbox_core::{
         delete_link::delete_link,
     },
 };
-use docbox_database::models::link::Link;
+use docbox_database::models::{document_box_stats::DocumentBoxStatsCounters, link::Link};
 use docbox_search::models::SearchRequest;
 use uuid::Uuid;
 
@@ -20,6 +23,7 @@ async fn test_delete_link_success() {
 
     let (db, _db_container) = test_tenant_db().await;
     let (search, _search_container) = test_tenant_search(&tenant).await;
+    let (storage, _storage_container) = test_tenant_storage(&tenant).await;
 
     let (events, mut events_rx) = MpscEventPublisher::new();
     let events = TenantEventPublisher::Mpsc(events);
@@ -59,12 +63,28 @@ async fn test_delete_link_success() {
     assert_eq!(link.value, "http://example.com");
     assert_eq!(link.created_by, None);
 
+    // Ensure the link is counted in the document box stats
+    {
+        let counters = DocumentBoxStatsCounters::find(&db, &document_box.scope)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(counters.total_links, 1);
+    }
+
     let link_id = link.id;
 
     // Delete the link
-    delete_link(&db, &search, &events, link, document_box.scope.to_string())
-        .await
-        .unwrap();
+    delete_link(
+        &db,
+        &storage,
+        &search,
+        &events,
+        link,
+        document_box.scope.to_string(),
+    )
+    .await
+    .unwrap();
 
     // Expect deletion event
     let event = events_rx.recv().await.unwrap();
@@ -82,6 +102,15 @@ async fn test_delete_link_success() {
         assert!(!has_link);
     }
 
+    // Ensure the link is no longer counted in the document box stats
+    {
+        let counters = DocumentBoxStatsCounters::find(&db, &document_box.scope)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(counters.total_links, 0);
+    }
+
     // Ensure the name is correctly removed from the index and is not searchable
     {
         let request = SearchRequest {
@@ -125,6 +154,7 @@ async fn test_delete_unknown_link() {
 
     let (db, _db_container) = test_tenant_db().await;
     let (search, _search_container) = test_tenant_search(&tenant).await;
+    let (storage, _storage_container) = test_tenant_storage(&tenant).await;
 
     let (events, mut events_rx) = MpscEventPublisher::new();
     let events = TenantEventPublisher::Mpsc(events);
@@ -150,11 +180,13 @@ async fn test_delete_unknown_link() {
         created_at: Default::default(),
         created_by: Default::default(),
         pinned: Default::default(),
+        fetched_at: Default::default(),
     };
 
     // Delete the link
     delete_link(
         &db,
+        &storage,
         &search,
         &events,
         fake_link,
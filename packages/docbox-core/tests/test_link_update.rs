@@ -7,7 +7,10 @@ use docbox_core::{
         update_link::{UpdateLink, UpdateLinkError, update_link},
     },
 };
-use docbox_database::models::link::Link;
+use docbox_database::models::{
+    edit_history::{EditHistory, EditHistoryMetadata},
+    link::Link,
+};
 use docbox_search::models::{SearchIndexType, SearchRequest};
 use uuid::Uuid;
 
@@ -61,6 +64,7 @@ async fn test_update_link_name_success() {
             name: Some("Other Name Which Should Never Match".to_string()),
             value: None,
             pinned: None,
+            expected_version: None,
         },
     )
     .await
@@ -172,6 +176,7 @@ async fn test_update_link_value_success() {
             name: None,
             value: Some("http://test.com".to_string()),
             pinned: None,
+            expected_version: None,
         },
     )
     .await
@@ -280,6 +285,7 @@ async fn test_update_link_pinned_success() {
             name: None,
             value: None,
             pinned: Some(true),
+            expected_version: None,
         },
     )
     .await
@@ -304,6 +310,7 @@ async fn test_update_link_pinned_success() {
             name: None,
             value: None,
             pinned: Some(false),
+            expected_version: None,
         },
     )
     .await
@@ -391,6 +398,7 @@ async fn test_update_link_folder_success() {
             name: None,
             value: None,
             pinned: None,
+            expected_version: None,
         },
     )
     .await
@@ -497,6 +505,7 @@ async fn test_update_link_folder_unknown() {
             name: None,
             value: None,
             pinned: None,
+            expected_version: None,
         },
     )
     .await
@@ -507,3 +516,104 @@ async fn test_update_link_folder_unknown() {
         "unknown folder should result in a failure"
     );
 }
+
+/// Tests that updating a link with an outdated expected version is rejected
+/// and the rejected attempt is recorded in the edit history
+#[tokio::test]
+async fn test_update_link_version_conflict() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, _search_container) = test_tenant_search(&tenant).await;
+
+    let events = TenantEventPublisher::Noop(NoopEventPublisher);
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let link = safe_create_link(
+        &db,
+        search.clone(),
+        &events,
+        CreateLinkData {
+            folder: root,
+            name: "Test Link".to_string(),
+            value: "http://example.com".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(Link::version(&db, link.id).await.unwrap(), Some(0));
+
+    // First client updates the link from the version it loaded
+    let version = update_link(
+        &db,
+        &search,
+        &document_box.scope,
+        link.clone(),
+        None,
+        UpdateLink {
+            folder_id: None,
+            name: Some("First Name".to_string()),
+            value: None,
+            pinned: None,
+            expected_version: Some(0),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(version, 1);
+
+    // Second client updates the link from the same outdated version
+    let err = update_link(
+        &db,
+        &search,
+        &document_box.scope,
+        link.clone(),
+        None,
+        UpdateLink {
+            folder_id: None,
+            name: Some("Second Name".to_string()),
+            value: None,
+            pinned: None,
+            expected_version: Some(0),
+        },
+    )
+    .await
+    .unwrap_err();
+
+    assert!(matches!(
+        err,
+        UpdateLinkError::VersionConflict {
+            expected_version: 0,
+            current_version: 1
+        }
+    ));
+
+    let updated_link = Link::find(&db, &document_box.scope, link.id)
+        .await
+        .unwrap()
+        .expect("link should exist");
+    assert_eq!(updated_link.name, "First Name");
+    assert_eq!(Link::version(&db, link.id).await.unwrap(), Some(1));
+
+    // Both attempts are recorded
+    let history = EditHistory::all_by_link(&db, link.id).await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert!(history.iter().any(|item| matches!(
+        item.metadata.0,
+        EditHistoryMetadata::UpdateConflict {
+            expected_version: 0,
+            current_version: 1
+        }
+    )));
+}
@@ -0,0 +1,315 @@
+use crate::common::{
+    database::test_tenant_db,
+    minio::test_tenant_storage,
+    processing::{test_office_convert_server_container, test_processing_layer},
+    tenant::test_tenant,
+    typesense::test_tenant_search,
+};
+use docbox_core::{
+    document_box::{
+        archive_document_box::set_document_box_archived,
+        create_document_box::{CreateDocumentBox, create_document_box},
+        move_to_scope::{MoveToScope, MoveToScopeError, move_file_to_scope},
+    },
+    events::TenantEventPublisher,
+    files::upload_file::{UploadFile, upload_file},
+};
+use docbox_database::models::{file::File, storage_object::StorageObject};
+use docbox_processing::ProcessingLayerConfig;
+use docbox_storage::ObjectTag;
+use std::time::Duration;
+
+mod common;
+
+/// Tests that moving a file to another document box keeps its stored
+/// object and tags the object with the target document box
+#[tokio::test]
+async fn test_move_file_to_scope() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, _search_container) = test_tenant_search(&tenant).await;
+    let (storage, _storage_container) = test_tenant_storage(&tenant).await;
+
+    let converter_container = test_office_convert_server_container().await;
+    let processing =
+        test_processing_layer(&converter_container, ProcessingLayerConfig::default()).await;
+
+    let events = TenantEventPublisher::Noop(Default::default());
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let (target_document_box, target_root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "target".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let file = upload_file(
+        &db,
+        &search,
+        &storage,
+        &processing,
+        &events,
+        UploadFile {
+            fixed_id: None,
+            parent_id: None,
+            folder_id: root.id,
+            document_box: document_box.scope.clone(),
+            name: "test.txt".to_string(),
+            mime: mime::TEXT_PLAIN,
+            file_bytes: "test".into(),
+            created_by: None,
+            file_key: None,
+            processing_config: None,
+            task_id: None,
+        },
+    )
+    .await
+    .unwrap()
+    .file;
+
+    let file_key = file.file_key.clone();
+
+    let file = move_file_to_scope(
+        &db,
+        &search,
+        &storage,
+        &events,
+        &document_box.scope,
+        file,
+        None,
+        MoveToScope {
+            document_box: target_document_box.scope.clone(),
+            folder_id: target_root.id,
+        },
+    )
+    .await
+    .unwrap();
+
+    // Object should be kept under its original key
+    assert_eq!(file.file_key, file_key);
+    let bytes = storage
+        .get_file(&file.file_key)
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert_eq!(bytes.as_ref(), b"test");
+
+    let result = File::find(&db, &target_document_box.scope, file.id)
+        .await
+        .unwrap()
+        .expect("file should exist");
+    assert_eq!(result.file_key, file.file_key);
+
+    // Document box tag is updated in the background
+    let mut document_box_tag = None;
+    for _ in 0..50 {
+        document_box_tag = storage
+            .get_object_tags(&file.file_key)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|tag| tag.key == ObjectTag::DOCUMENT_BOX)
+            .map(|tag| tag.value);
+
+        if document_box_tag.as_deref() == Some("target") {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(document_box_tag.as_deref(), Some("target"));
+}
+
+/// Tests that moving a deduplicated file keeps referencing the shared object
+/// used by the remaining files
+#[tokio::test]
+async fn test_move_file_to_scope_deduplicated() {
+    let mut tenant = test_tenant();
+    tenant.storage_deduplication = true;
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, _search_container) = test_tenant_search(&tenant).await;
+    let (storage, _storage_container) = test_tenant_storage(&tenant).await;
+
+    let converter_container = test_office_convert_server_container().await;
+    let processing =
+        test_processing_layer(&converter_container, ProcessingLayerConfig::default()).await;
+
+    let events = TenantEventPublisher::Noop(Default::default());
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let (target_document_box, target_root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "target".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut files = Vec::new();
+    for name in ["test.txt", "test-copy.txt"] {
+        let file = upload_file(
+            &db,
+            &search,
+            &storage,
+            &processing,
+            &events,
+            UploadFile {
+                fixed_id: None,
+                parent_id: None,
+                folder_id: root.id,
+                document_box: document_box.scope.clone(),
+                name: name.to_string(),
+                mime: mime::TEXT_PLAIN,
+                file_bytes: "test".into(),
+                created_by: None,
+                file_key: None,
+                processing_config: None,
+                task_id: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        files.push(file.file);
+    }
+
+    let first = files.remove(0);
+    let second = files.remove(0);
+
+    let file = move_file_to_scope(
+        &db,
+        &search,
+        &storage,
+        &events,
+        &document_box.scope,
+        first,
+        None,
+        MoveToScope {
+            document_box: target_document_box.scope.clone(),
+            folder_id: target_root.id,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(file.file_key, second.file_key);
+    let object = StorageObject::find(&db, &file.file_key)
+        .await
+        .unwrap()
+        .expect("expected storage object");
+    assert_eq!(object.reference_count, 2);
+    storage.get_file(&file.file_key).await.unwrap();
+}
+
+/// Tests that files cannot be moved out of an archived document box
+#[tokio::test]
+async fn test_move_file_to_scope_source_archived() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (search, _search_container) = test_tenant_search(&tenant).await;
+    let (storage, _storage_container) = test_tenant_storage(&tenant).await;
+
+    let converter_container = test_office_convert_server_container().await;
+    let processing =
+        test_processing_layer(&converter_container, ProcessingLayerConfig::default()).await;
+
+    let events = TenantEventPublisher::Noop(Default::default());
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let (target_document_box, target_root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "target".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let file = upload_file(
+        &db,
+        &search,
+        &storage,
+        &processing,
+        &events,
+        UploadFile {
+            fixed_id: None,
+            parent_id: None,
+            folder_id: root.id,
+            document_box: document_box.scope.clone(),
+            name: "test.txt".to_string(),
+            mime: mime::TEXT_PLAIN,
+            file_bytes: "test".into(),
+            created_by: None,
+            file_key: None,
+            processing_config: None,
+            task_id: None,
+        },
+    )
+    .await
+    .unwrap()
+    .file;
+
+    set_document_box_archived(&db, &document_box.scope, true)
+        .await
+        .unwrap();
+
+    let error = move_file_to_scope(
+        &db,
+        &search,
+        &storage,
+        &events,
+        &document_box.scope,
+        file,
+        None,
+        MoveToScope {
+            document_box: target_document_box.scope.clone(),
+            folder_id: target_root.id,
+        },
+    )
+    .await
+    .unwrap_err();
+
+    assert!(matches!(error, MoveToScopeError::SourceDocumentBoxArchived));
+}
@@ -0,0 +1,148 @@
+use crate::common::{database::test_tenant_db, minio::test_tenant_storage, tenant::test_tenant};
+use chrono::{Days, Utc};
+use docbox_core::{
+    document_box::create_document_box::{CreateDocumentBox, create_document_box},
+    events::{TenantEventMessage, TenantEventPublisher, mpsc::MpscEventPublisher},
+    purge::purge_expired_presigned_tasks::purge_expired_presigned_tasks_tenant,
+};
+use docbox_database::models::presigned_upload_task::{
+    CreatePresignedUploadTask, PresignedTaskStatus, PresignedUploadTask,
+};
+
+mod common;
+
+/// Tests that incomplete expired presigned tasks are marked as expired
+/// and publish an expiry event
+#[tokio::test]
+async fn test_purge_expired_presigned_tasks_marks_expired() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (storage, _storage_container) = test_tenant_storage(&tenant).await;
+
+    let (events, mut events_rx) = MpscEventPublisher::new();
+    let events = TenantEventPublisher::Mpsc(events);
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+    _ = events_rx.recv().await.unwrap();
+
+    let create = |expires_at| CreatePresignedUploadTask {
+        name: "test.txt".to_string(),
+        mime: "text/plain".to_string(),
+        document_box: document_box.scope.clone(),
+        folder_id: root.id,
+        file_key: "test/test.txt".to_string(),
+        expires_at,
+        ..Default::default()
+    };
+
+    let expired = PresignedUploadTask::create(&db, create(Utc::now()))
+        .await
+        .unwrap();
+    let pending = PresignedUploadTask::create(
+        &db,
+        create(Utc::now().checked_add_days(Days::new(1)).unwrap()),
+    )
+    .await
+    .unwrap();
+
+    purge_expired_presigned_tasks_tenant(&db, &storage, &events)
+        .await
+        .unwrap();
+
+    let task = PresignedUploadTask::find_by_id(&db, expired.id)
+        .await
+        .unwrap()
+        .expect("expired task should be kept");
+    assert_eq!(task.status, PresignedTaskStatus::Expired);
+
+    let event = events_rx.recv().await.unwrap();
+    assert!(matches!(
+        event,
+        TenantEventMessage::PresignedUploadExpired(task) if task.id == expired.id
+    ));
+
+    // Tasks that have not expired are left pending
+    let task = PresignedUploadTask::find_by_id(&db, pending.id)
+        .await
+        .unwrap()
+        .expect("pending task should be kept");
+    assert_eq!(task.status, PresignedTaskStatus::Pending);
+    assert!(events_rx.try_recv().is_err());
+}
+
+/// Tests that completed tasks and tasks that have been expired for longer
+/// than the retention period are deleted
+#[tokio::test]
+async fn test_purge_expired_presigned_tasks_deletes() {
+    let tenant = test_tenant();
+
+    let (db, _db_container) = test_tenant_db().await;
+    let (storage, _storage_container) = test_tenant_storage(&tenant).await;
+
+    let (events, mut events_rx) = MpscEventPublisher::new();
+    let events = TenantEventPublisher::Mpsc(events);
+    let (document_box, root) = create_document_box(
+        &db,
+        &events,
+        CreateDocumentBox {
+            scope: "test".to_string(),
+            created_by: None,
+        },
+    )
+    .await
+    .unwrap();
+    _ = events_rx.recv().await.unwrap();
+
+    let create = || CreatePresignedUploadTask {
+        name: "test.txt".to_string(),
+        mime: "text/plain".to_string(),
+        document_box: document_box.scope.clone(),
+        folder_id: root.id,
+        file_key: "test/test.txt".to_string(),
+        expires_at: Utc::now().checked_sub_days(Days::new(2)).unwrap(),
+        ..Default::default()
+    };
+
+    let mut completed = PresignedUploadTask::create(&db, create()).await.unwrap();
+    completed
+        .set_status(
+            &db,
+            PresignedTaskStatus::Completed {
+                file_id: uuid::Uuid::new_v4(),
+            },
+        )
+        .await
+        .unwrap();
+
+    let mut expired = PresignedUploadTask::create(&db, create()).await.unwrap();
+    expired
+        .set_status(&db, PresignedTaskStatus::Expired)
+        .await
+        .unwrap();
+
+    purge_expired_presigned_tasks_tenant(&db, &storage, &events)
+        .await
+        .unwrap();
+
+    let task = PresignedUploadTask::find_by_id(&db, completed.id)
+        .await
+        .unwrap();
+    assert!(task.is_none());
+
+    let task = PresignedUploadTask::find_by_id(&db, expired.id)
+        .await
+        .unwrap();
+    assert!(task.is_none());
+
+    // Already expired tasks do not publish another event
+    assert!(events_rx.try_recv().is_err());
+}
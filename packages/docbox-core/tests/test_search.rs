@@ -14,7 +14,7 @@ use docbox_database::{
 use docbox_processing::ProcessingIndexMetadata;
 use docbox_search::{
     DatabaseSearchIndex, TenantSearchIndex,
-    models::{DocumentPage, FileSearchRequest, SearchRequest},
+    models::{AdvancedSearchQuery, DocumentPage, FileSearchRequest, SearchRequest},
 };
 use itertools::Itertools;
 use testcontainers::{ContainerAsync, GenericImage};
@@ -77,10 +77,15 @@ async fn seed_index_data(
             pages.push(DocumentPage {
                 page: i,
                 content: Words(50..5000).fake::<Vec<String>>().join(" "),
+                words: None,
             });
         }
 
-        let index_metadata = ProcessingIndexMetadata { pages: Some(pages) };
+        let index_metadata = ProcessingIndexMetadata {
+            pages: Some(pages),
+            summary: None,
+            text_stats: None,
+        };
 
         let data = create_file_index(&create_file, &scope, Some(index_metadata));
         files.push(create_file);
@@ -152,6 +157,7 @@ async fn create_test_tenant() -> TestTenant {
             os_index_name: "test".to_string(),
             env: "Development".to_string(),
             event_queue_url: None,
+            data_region: None,
         },
     )
     .await
@@ -253,7 +259,10 @@ async fn test_search() {
             pages: Some(vec![DocumentPage {
                 page: 0,
                 content: SEARCH_TEXT_CONTENT.to_string(),
+                words: None,
             }]),
+            summary: None,
+            text_stats: None,
         }),
     );
     File::create(db, create_file).await.unwrap();
@@ -289,6 +298,116 @@ async fn test_search() {
     // dbg!(&results);
 }
 
+/// Tests filtering database search results using an advanced query, covers
+/// the PL/pgSQL advanced query function applied by the search migrations
+#[tokio::test]
+async fn test_search_database_advanced_query() {
+    let test_tenant = create_test_tenant().await;
+    let search = create_search_index_database(&test_tenant).await;
+
+    let db = &test_tenant.tenant_db;
+
+    let scope = "test".to_string();
+    let _document_box = DocumentBox::create(db, scope.clone()).await.unwrap();
+    let root = Folder::create(
+        db,
+        CreateFolder {
+            name: "Root".to_string(),
+            document_box: scope.clone(),
+            folder_id: None,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut file_ids = Vec::new();
+    for (name, mime) in [
+        ("Quarterly Invoice.txt", "text/plain"),
+        ("Quarterly Report.pdf", "application/pdf"),
+        ("Quarterly Report Notes.txt", "text/plain"),
+    ] {
+        let create_file = CreateFile {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            mime: mime.to_string(),
+            folder_id: root.id,
+            ..Default::default()
+        };
+        file_ids.push(create_file.id);
+
+        let data = create_file_index(
+            &create_file,
+            &scope,
+            Some(ProcessingIndexMetadata {
+                pages: Some(vec![DocumentPage {
+                    page: 0,
+                    content: format!("Contents of {name}"),
+                    words: None,
+                }]),
+                summary: None,
+                text_stats: None,
+            }),
+        );
+        File::create(db, create_file).await.unwrap();
+        search.add_data(vec![data]).await.unwrap();
+    }
+
+    let scopes = ["test".to_string()];
+    let search_advanced = |advanced| {
+        search.search_index(
+            &scopes,
+            SearchRequest {
+                query: Some("Quarterly".to_string()),
+                include_name: true,
+                include_content: true,
+                advanced: Some(advanced),
+                ..Default::default()
+            },
+            None,
+        )
+    };
+
+    // Name contains "report" and is not a pdf
+    let results = search_advanced(AdvancedSearchQuery::And {
+        queries: vec![
+            AdvancedSearchQuery::NameContains {
+                value: "REPORT".to_string(),
+            },
+            AdvancedSearchQuery::Not {
+                query: Box::new(AdvancedSearchQuery::MimeIn {
+                    values: vec!["application/pdf".to_string()],
+                }),
+            },
+        ],
+    })
+    .await
+    .unwrap();
+    assert_eq!(results.total_hits, 1);
+    assert_eq!(results.results[0].item_id, file_ids[2]);
+
+    // Mime is a pdf or name contains "invoice"
+    let results = search_advanced(AdvancedSearchQuery::Or {
+        queries: vec![
+            AdvancedSearchQuery::MimeIn {
+                values: vec!["application/pdf".to_string()],
+            },
+            AdvancedSearchQuery::NameContains {
+                value: "invoice".to_string(),
+            },
+        ],
+    })
+    .await
+    .unwrap();
+    assert_eq!(results.total_hits, 2);
+    assert!(
+        results
+            .results
+            .iter()
+            .all(|item| item.item_id == file_ids[0] || item.item_id == file_ids[1])
+    );
+}
+
 #[tokio::test]
 #[ignore = "Benchmarking test for search performance"]
 async fn test_search_database() {
@@ -314,7 +433,10 @@ async fn test_search_database() {
             pages: Some(vec![DocumentPage {
                 page: 0,
                 content: SEARCH_TEXT_CONTENT.to_string(),
+                words: None,
             }]),
+            summary: None,
+            text_stats: None,
         }),
     );
     File::create(&test_tenant.tenant_db, create_file)
@@ -337,7 +459,10 @@ async fn test_search_database() {
             pages: Some(vec![DocumentPage {
                 page: 0,
                 content: SEARCH_TEXT_CONTENT_2.to_string(),
+                words: None,
             }]),
+            summary: None,
+            text_stats: None,
         }),
     );
     File::create(&test_tenant.tenant_db, create_file)
@@ -447,7 +572,10 @@ async fn test_search_typesense() {
             pages: Some(vec![DocumentPage {
                 page: 0,
                 content: SEARCH_TEXT_CONTENT.to_string(),
+                words: None,
             }]),
+            summary: None,
+            text_stats: None,
         }),
     );
     File::create(&test_tenant.tenant_db, create_file)
@@ -470,7 +598,10 @@ async fn test_search_typesense() {
             pages: Some(vec![DocumentPage {
                 page: 0,
                 content: SEARCH_TEXT_CONTENT_2.to_string(),
+                words: None,
             }]),
+            summary: None,
+            text_stats: None,
         }),
     );
     File::create(&test_tenant.tenant_db, create_file)
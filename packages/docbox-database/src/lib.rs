@@ -3,8 +3,8 @@
 
 // Pool re-exports
 pub use pool::{
-    DatabasePoolCache, DatabasePoolCacheConfig, DatabasePoolCacheConfigError, DbConnectErr,
-    DbSecrets,
+    DatabasePoolCache, DatabasePoolCacheConfig, DatabasePoolCacheConfigError,
+    DatabasePoolCacheStats, DatabasePoolInfo, DbConnectErr, DbSecrets,
 };
 
 /// SQLx re-exports for other projects
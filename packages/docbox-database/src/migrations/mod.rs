@@ -6,7 +6,8 @@ use crate::{
         tenant_migration::{CreateTenantMigration, TenantMigration},
     },
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::ops::DerefMut;
 
 pub const ROOT_MIGRATIONS: &[(&str, &str)] = &[
@@ -30,6 +31,46 @@ pub const ROOT_MIGRATIONS: &[(&str, &str)] = &[
         "m5_tenant_iam_support",
         include_str!("./root/m5_tenant_iam_support.sql"),
     ),
+    (
+        "m6_tenant_storage_encryption",
+        include_str!("./root/m6_tenant_storage_encryption.sql"),
+    ),
+    (
+        "m7_tenant_storage_deduplication",
+        include_str!("./root/m7_tenant_storage_deduplication.sql"),
+    ),
+    (
+        "m8_tenant_event_config",
+        include_str!("./root/m8_tenant_event_config.sql"),
+    ),
+    (
+        "m9_tenant_data_region",
+        include_str!("./root/m9_tenant_data_region.sql"),
+    ),
+    (
+        "m10_tenant_maintenance_mode",
+        include_str!("./root/m10_tenant_maintenance_mode.sql"),
+    ),
+    (
+        "m11_tenant_soft_delete",
+        include_str!("./root/m11_tenant_soft_delete.sql"),
+    ),
+    (
+        "m12_create_inbound_email_addresses_table",
+        include_str!("./root/m12_create_inbound_email_addresses_table.sql"),
+    ),
+    (
+        "m13_tenant_active_content_policy",
+        include_str!("./root/m13_tenant_active_content_policy.sql"),
+    ),
+    (
+        "m14_create_request_usage_table",
+        include_str!("./root/m14_create_request_usage_table.sql"),
+    ),
+    (
+        "m15_tenant_search_language",
+        include_str!("./root/m15_tenant_search_language.sql"),
+    ),
 ];
 
 pub const TENANT_MIGRATIONS: &[(&str, &str)] = &[
@@ -97,6 +138,134 @@ pub const TENANT_MIGRATIONS: &[(&str, &str)] = &[
         "m16_docbox_tasks_constraint",
         include_str!("./tenant/m16_docbox_tasks_constraint.sql"),
     ),
+    (
+        "m17_create_extraction_cache_table",
+        include_str!("./tenant/m17_create_extraction_cache_table.sql"),
+    ),
+    (
+        "m18_create_files_processing_table",
+        include_str!("./tenant/m18_create_files_processing_table.sql"),
+    ),
+    (
+        "m19_create_generated_file_policies_table",
+        include_str!("./tenant/m19_create_generated_file_policies_table.sql"),
+    ),
+    (
+        "m20_create_files_pdf_metadata_table",
+        include_str!("./tenant/m20_create_files_pdf_metadata_table.sql"),
+    ),
+    (
+        "m21_create_storage_objects_table",
+        include_str!("./tenant/m21_create_storage_objects_table.sql"),
+    ),
+    (
+        "m22_create_upload_rules_table",
+        include_str!("./tenant/m22_create_upload_rules_table.sql"),
+    ),
+    (
+        "m23_create_folder_processing_configs_table",
+        include_str!("./tenant/m23_create_folder_processing_configs_table.sql"),
+    ),
+    (
+        "m24_create_files_access_stats_table",
+        include_str!("./tenant/m24_create_files_access_stats_table.sql"),
+    ),
+    (
+        "m25_create_usage_stats_table",
+        include_str!("./tenant/m25_create_usage_stats_table.sql"),
+    ),
+    (
+        "m26_add_tasks_progress_column",
+        include_str!("./tenant/m26_add_tasks_progress_column.sql"),
+    ),
+    (
+        "m27_add_item_versions",
+        include_str!("./tenant/m27_add_item_versions.sql"),
+    ),
+    (
+        "m28_add_document_box_archived",
+        include_str!("./tenant/m28_add_document_box_archived.sql"),
+    ),
+    (
+        "m29_create_scope_patterns_table",
+        include_str!("./tenant/m29_create_scope_patterns_table.sql"),
+    ),
+    (
+        "m30_create_document_box_webhooks_table",
+        include_str!("./tenant/m30_create_document_box_webhooks_table.sql"),
+    ),
+    (
+        "m31_create_event_payloads_table",
+        include_str!("./tenant/m31_create_event_payloads_table.sql"),
+    ),
+    (
+        "m32_add_pinned_at_columns",
+        include_str!("./tenant/m32_add_pinned_at_columns.sql"),
+    ),
+    (
+        "m33_add_edit_history_filter_indexes",
+        include_str!("./tenant/m33_add_edit_history_filter_indexes.sql"),
+    ),
+    (
+        "m34_create_link_generated_files_table",
+        include_str!("./tenant/m34_create_link_generated_files_table.sql"),
+    ),
+    (
+        "m35_add_link_fetched_at_column",
+        include_str!("./tenant/m35_add_link_fetched_at_column.sql"),
+    ),
+    (
+        "m36_create_mime_overrides_table",
+        include_str!("./tenant/m36_create_mime_overrides_table.sql"),
+    ),
+    (
+        "m37_create_files_pii_analysis_table",
+        include_str!("./tenant/m37_create_files_pii_analysis_table.sql"),
+    ),
+    (
+        "m38_create_recent_searches_table",
+        include_str!("./tenant/m38_create_recent_searches_table.sql"),
+    ),
+    (
+        "m39_add_files_processing_timings",
+        include_str!("./tenant/m39_add_files_processing_timings.sql"),
+    ),
+    (
+        "m40_add_generated_files_page",
+        include_str!("./tenant/m40_add_generated_files_page.sql"),
+    ),
+    (
+        "m41_create_files_text_stats_table",
+        include_str!("./tenant/m41_create_files_text_stats_table.sql"),
+    ),
+    (
+        "m42_create_connector_sync_items_table",
+        include_str!("./tenant/m42_create_connector_sync_items_table.sql"),
+    ),
+    (
+        "m43_create_imap_mailbox_state_table",
+        include_str!("./tenant/m43_create_imap_mailbox_state_table.sql"),
+    ),
+    (
+        "m44_create_file_emails_table",
+        include_str!("./tenant/m44_create_file_emails_table.sql"),
+    ),
+    (
+        "m45_create_links_visit_stats_table",
+        include_str!("./tenant/m45_create_links_visit_stats_table.sql"),
+    ),
+    (
+        "m46_create_boxes_stats_table",
+        include_str!("./tenant/m46_create_boxes_stats_table.sql"),
+    ),
+    (
+        "m47_add_folders_path_column",
+        include_str!("./tenant/m47_add_folders_path_column.sql"),
+    ),
+    (
+        "m48_add_generated_files_unique_type_index",
+        include_str!("./tenant/m48_add_generated_files_unique_type_index.sql"),
+    ),
 ];
 
 /// Initialize the table used for root migration tracking
@@ -131,6 +300,33 @@ pub async fn get_pending_tenant_migrations(
     Ok(pending)
 }
 
+/// Status of a migration
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// Name of the migration
+    pub name: String,
+    /// When the migration was applied, [None] if the migration is pending
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
+/// Get the status of every root migration, in the order the migrations are applied
+pub async fn get_root_migrations_status(db: impl DbExecutor<'_>) -> DbResult<Vec<MigrationStatus>> {
+    let migrations = RootMigration::all(db).await?;
+
+    let status = ROOT_MIGRATIONS
+        .iter()
+        .map(|(migration_name, _migration)| MigrationStatus {
+            name: migration_name.to_string(),
+            applied_at: migrations
+                .iter()
+                .find(|migration| migration.name.eq(migration_name))
+                .map(|migration| migration.applied_at),
+        })
+        .collect();
+
+    Ok(status)
+}
+
 /// Get all pending migrations for the root that have not been applied yet
 pub async fn get_pending_root_migrations(db: impl DbExecutor<'_>) -> DbResult<Vec<String>> {
     let migrations = RootMigration::all(db).await?;
@@ -267,13 +463,7 @@ pub async fn apply_migration(
     migration_name: &str,
     migration: &str,
 ) -> DbResult<()> {
-    // Split the SQL queries into multiple queries
-    let queries = migration
-        .split(';')
-        .map(|query| query.trim())
-        .filter(|query| !query.is_empty());
-
-    for query in queries {
+    for query in split_migration_queries(migration) {
         let result = sqlx::query(query)
             .execute(db.deref_mut())
             .await
@@ -288,3 +478,126 @@ pub async fn apply_migration(
 
     Ok(())
 }
+
+/// Split the SQL queries of a migration into multiple queries
+///
+/// Queries are separated by `;`, separators within dollar quoted strings
+/// (i.e the `$$` body of a PL/pgSQL function) are not treated as the end
+/// of a query
+fn split_migration_queries(migration: &str) -> Vec<&str> {
+    let mut queries = Vec::new();
+    let mut start = 0;
+    // Tag of the dollar quoted string currently within (i.e "$$" or "$body$")
+    let mut dollar_tag: Option<&str> = None;
+
+    let mut index = 0;
+    while index < migration.len() {
+        let rest = &migration[index..];
+
+        if let Some(tag) = dollar_tag {
+            if rest.starts_with(tag) {
+                dollar_tag = None;
+                index += tag.len();
+            } else {
+                index += rest.chars().next().map_or(1, char::len_utf8);
+            }
+            continue;
+        }
+
+        if let Some(after) = rest.strip_prefix('$') {
+            let tag_end = after.find(|value: char| !(value.is_alphanumeric() || value == '_'));
+            if let Some(tag_end) = tag_end
+                && after[tag_end..].starts_with('$')
+            {
+                let tag = &rest[..tag_end + 2];
+                dollar_tag = Some(tag);
+                index += tag.len();
+                continue;
+            }
+        }
+
+        if rest.starts_with(';') {
+            queries.push(migration[start..index].trim());
+            start = index + 1;
+        }
+
+        index += rest.chars().next().map_or(1, char::len_utf8);
+    }
+
+    queries.push(migration[start..].trim());
+    queries.retain(|query| !query.is_empty());
+    queries
+}
+
+#[cfg(test)]
+mod test {
+    use super::split_migration_queries;
+
+    #[test]
+    fn test_split_migration_queries() {
+        let queries = split_migration_queries(
+            "CREATE TABLE \"a\" (\"id\" INT);\n\nALTER TABLE \"a\" ADD COLUMN \"b\" INT;\n",
+        );
+        assert_eq!(
+            queries,
+            vec![
+                "CREATE TABLE \"a\" (\"id\" INT)",
+                "ALTER TABLE \"a\" ADD COLUMN \"b\" INT"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_migration_queries_dollar_quoted() {
+        let queries = split_migration_queries(
+            "CREATE FUNCTION a() RETURNS INT LANGUAGE plpgsql AS $$ BEGIN RETURN 1; END; $$;\n\
+             SELECT 1",
+        );
+        assert_eq!(
+            queries,
+            vec![
+                "CREATE FUNCTION a() RETURNS INT LANGUAGE plpgsql AS $$ BEGIN RETURN 1; END; $$",
+                "SELECT 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_migration_queries_tagged_dollar_quoted() {
+        let queries = split_migration_queries(
+            "CREATE FUNCTION a() RETURNS INT LANGUAGE plpgsql AS $body$ BEGIN RETURN 1; END; $body$;\n\
+             CREATE FUNCTION b() RETURNS INT LANGUAGE plpgsql AS $fn_2$ BEGIN RETURN 2; END; $fn_2$;",
+        );
+        assert_eq!(
+            queries,
+            vec![
+                "CREATE FUNCTION a() RETURNS INT LANGUAGE plpgsql AS $body$ BEGIN RETURN 1; END; $body$",
+                "CREATE FUNCTION b() RETURNS INT LANGUAGE plpgsql AS $fn_2$ BEGIN RETURN 2; END; $fn_2$"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_migration_queries_nested_dollar_quoted() {
+        // Only the tag that opened the outer string closes it, the inner
+        // "$$" strings and their separators are part of the outer body
+        let queries = split_migration_queries(
+            "DO $outer$ BEGIN EXECUTE $$ SELECT 1; $$; EXECUTE $inner$ SELECT 2; $inner$; END $outer$;\n\
+             SELECT 3",
+        );
+        assert_eq!(
+            queries,
+            vec![
+                "DO $outer$ BEGIN EXECUTE $$ SELECT 1; $$; EXECUTE $inner$ SELECT 2; $inner$; END $outer$",
+                "SELECT 3"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_migration_queries_parameters() {
+        // Positional parameters are not dollar quote tags
+        let queries = split_migration_queries("SELECT $1, $2; SELECT $1");
+        assert_eq!(queries, vec!["SELECT $1, $2", "SELECT $1"]);
+    }
+}
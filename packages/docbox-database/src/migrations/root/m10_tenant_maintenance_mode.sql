@@ -0,0 +1,3 @@
+-- Add column for whether the tenant is in maintenance mode
+ALTER TABLE "docbox_tenants"
+ADD COLUMN "maintenance_mode" BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,4 @@
+-- Add column for when the tenant was soft deleted, soft deleted tenants are
+-- disabled but can be restored until they are permanently deleted
+ALTER TABLE "docbox_tenants"
+ADD COLUMN "deleted_at" TIMESTAMPTZ NULL;
@@ -0,0 +1,16 @@
+-- Setup the inbound email addresses table, received emails are routed into
+-- the document box of the address they were sent to
+CREATE TABLE IF NOT EXISTS "docbox_inbound_email_addresses"
+(
+    "address"      VARCHAR NOT NULL PRIMARY KEY,
+    "env"          VARCHAR NOT NULL,
+    "tenant_id"    UUID NOT NULL,
+    "document_box" VARCHAR NOT NULL,
+    "created_at"   TIMESTAMP WITH TIME ZONE NOT NULL,
+
+    -- Each document box has at most one inbound email address
+    UNIQUE ("env", "tenant_id", "document_box"),
+    FOREIGN KEY ("env", "tenant_id")
+        REFERENCES "docbox_tenants" ( "env", "id")
+        ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- Add column for how raw HTML and SVG files are served for the tenant
+ALTER TABLE "docbox_tenants"
+ADD COLUMN "active_content_policy" VARCHAR NOT NULL DEFAULT 'Sandbox';
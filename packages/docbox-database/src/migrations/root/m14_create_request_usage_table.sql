@@ -0,0 +1,21 @@
+-- Setup the request usage table, stores the daily request and response byte
+-- counts for each tenant and API key. Usage is kept when a tenant is deleted
+-- so that it can still be billed
+CREATE TABLE IF NOT EXISTS "docbox_request_usage"
+(
+    "env"            VARCHAR NOT NULL,
+    "tenant_id"      UUID NOT NULL,
+    "api_key_id"     VARCHAR NOT NULL,
+    "day"            DATE NOT NULL,
+    "requests"       BIGINT NOT NULL DEFAULT 0,
+    "request_bytes"  BIGINT NOT NULL DEFAULT 0,
+    "response_bytes" BIGINT NOT NULL DEFAULT 0,
+    "upload_bytes"   BIGINT NOT NULL DEFAULT 0,
+    "download_bytes" BIGINT NOT NULL DEFAULT 0,
+
+    PRIMARY KEY ("env", "tenant_id", "api_key_id", "day")
+);
+
+-- Index for exporting the usage of all tenants for a range of days
+CREATE INDEX IF NOT EXISTS "idx_docbox_request_usage_day"
+    ON "docbox_request_usage" ("day");
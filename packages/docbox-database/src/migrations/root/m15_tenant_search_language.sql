@@ -0,0 +1,4 @@
+-- Add column for the language used to analyze the tenant search index content,
+-- the search backend default language is used when not set
+ALTER TABLE "docbox_tenants"
+ADD COLUMN "search_language" VARCHAR NULL;
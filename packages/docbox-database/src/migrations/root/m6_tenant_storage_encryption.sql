@@ -0,0 +1,4 @@
+-- Add column to store the name of the secret containing the tenant
+-- storage encryption keys, null when storage is not encrypted
+ALTER TABLE "docbox_tenants"
+ADD COLUMN "storage_key_secret_name" VARCHAR NULL;
@@ -0,0 +1,3 @@
+-- Add column to enable content addressed deduplicated file storage
+ALTER TABLE "docbox_tenants"
+ADD COLUMN "storage_deduplication" BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+-- Add column to configure which events the tenant publishes
+ALTER TABLE "docbox_tenants"
+ADD COLUMN "event_config" JSONB NULL;
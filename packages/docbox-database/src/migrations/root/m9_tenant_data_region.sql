@@ -0,0 +1,3 @@
+-- Add column for the data residency region of the tenant
+ALTER TABLE "docbox_tenants"
+ADD COLUMN "data_region" VARCHAR NULL;
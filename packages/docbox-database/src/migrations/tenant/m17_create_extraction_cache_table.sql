@@ -0,0 +1,16 @@
+CREATE TABLE "docbox_extraction_cache"
+(
+    "hash"           VARCHAR                  NOT NULL,
+    "mime"           VARCHAR                  NOT NULL,
+    "source_file_id" UUID                     NOT NULL
+        CONSTRAINT "FK_extraction_cache_file"
+            REFERENCES "docbox_files" ("id")
+            ON DELETE CASCADE,
+    "encrypted"      BOOLEAN                  NOT NULL DEFAULT FALSE,
+    "pages"          JSONB,
+    "created_at"     TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY ("hash", "mime")
+);
+
+CREATE INDEX idx_extraction_cache_source_file_id
+ON "docbox_extraction_cache" ("source_file_id");
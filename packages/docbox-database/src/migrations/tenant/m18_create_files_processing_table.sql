@@ -0,0 +1,13 @@
+CREATE TABLE "docbox_files_processing"
+(
+    "file_id"      UUID                     NOT NULL
+        PRIMARY KEY
+        CONSTRAINT "FK_files_processing_file"
+            REFERENCES "docbox_files" ("id")
+            ON DELETE CASCADE,
+    "version"      INTEGER                  NOT NULL,
+    "processed_at" TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX idx_files_processing_version
+ON "docbox_files_processing" ("version");
@@ -0,0 +1,7 @@
+CREATE TABLE "docbox_generated_file_policies"
+(
+    "mime"   VARCHAR NOT NULL,
+    "type"   VARCHAR NOT NULL,
+    "retain" BOOLEAN NOT NULL,
+    PRIMARY KEY ("mime", "type")
+);
@@ -0,0 +1,11 @@
+CREATE TABLE "docbox_files_pdf_metadata"
+(
+    "file_id"    UUID    NOT NULL
+        PRIMARY KEY
+        CONSTRAINT "FK_files_pdf_metadata_file"
+            REFERENCES "docbox_files" ("id")
+            ON DELETE CASCADE,
+    "page_count" INTEGER NOT NULL,
+    "pages"      JSONB   NOT NULL,
+    "outline"    JSONB   NOT NULL
+);
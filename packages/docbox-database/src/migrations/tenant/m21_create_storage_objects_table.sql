@@ -0,0 +1,8 @@
+CREATE TABLE "docbox_storage_objects"
+(
+    "file_key"        VARCHAR                  NOT NULL
+        PRIMARY KEY,
+    "hash"            VARCHAR                  NOT NULL,
+    "reference_count" INTEGER                  NOT NULL,
+    "created_at"      TIMESTAMP WITH TIME ZONE NOT NULL
+);
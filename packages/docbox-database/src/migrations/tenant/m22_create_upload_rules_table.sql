@@ -0,0 +1,8 @@
+CREATE TABLE "docbox_upload_rules"
+(
+    "kind"     VARCHAR NOT NULL,
+    "pattern"  VARCHAR NOT NULL,
+    "action"   VARCHAR NOT NULL,
+    "max_size" BIGINT  NULL,
+    PRIMARY KEY ("kind", "pattern")
+);
@@ -0,0 +1,9 @@
+CREATE TABLE "docbox_folder_processing_configs"
+(
+    "folder_id" UUID  NOT NULL
+        PRIMARY KEY
+        CONSTRAINT "FK_folder_processing_configs_folder"
+            REFERENCES "docbox_folders" ("id")
+            ON DELETE CASCADE,
+    "config"    JSONB NOT NULL
+);
@@ -0,0 +1,14 @@
+CREATE TABLE "docbox_files_access_stats"
+(
+    "file_id"          UUID        NOT NULL
+        PRIMARY KEY
+        CONSTRAINT "FK_files_access_stats_file"
+            REFERENCES "docbox_files" ("id")
+            ON DELETE CASCADE,
+    "download_count"   BIGINT      NOT NULL DEFAULT 0,
+    "preview_count"    BIGINT      NOT NULL DEFAULT 0,
+    "last_accessed_at" TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX "IDX_files_access_stats_last_accessed_at"
+    ON "docbox_files_access_stats" ("last_accessed_at");
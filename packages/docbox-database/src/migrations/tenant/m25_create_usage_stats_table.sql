@@ -0,0 +1,11 @@
+CREATE TABLE "docbox_usage_stats"
+(
+    "day"                 DATE        NOT NULL
+        PRIMARY KEY,
+    "files_created"       BIGINT      NOT NULL,
+    "bytes_uploaded"      BIGINT      NOT NULL,
+    "processing_failures" BIGINT      NOT NULL,
+    "total_files"         BIGINT      NOT NULL,
+    "total_bytes"         BIGINT      NOT NULL,
+    "updated_at"          TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,3 @@
+-- Progress reported by a task while it is pending
+ALTER TABLE "docbox_tasks"
+ADD COLUMN "progress" JSONB;
@@ -0,0 +1,33 @@
+-- Version of each item, incremented on every update and used to
+-- detect concurrent modifications
+ALTER TABLE "docbox_files"
+ADD COLUMN "version" BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE "docbox_folders"
+ADD COLUMN "version" BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE "docbox_links"
+ADD COLUMN "version" BIGINT NOT NULL DEFAULT 0;
+
+-- ================================================================
+-- Rejected conflicting updates are recorded in the edit history
+-- but did not modify the item
+-- ================================================================
+
+CREATE OR REPLACE VIEW "docbox_latest_edit_per_file" AS
+SELECT DISTINCT ON ("file_id") "file_id", "user_id", "created_at"
+FROM "docbox_edit_history"
+WHERE "type" <> 'UpdateConflict'
+ORDER BY "file_id", "created_at" DESC;
+
+CREATE OR REPLACE VIEW "docbox_latest_edit_per_folder" AS
+SELECT DISTINCT ON ("folder_id") "folder_id", "user_id", "created_at"
+FROM "docbox_edit_history"
+WHERE "type" <> 'UpdateConflict'
+ORDER BY "folder_id", "created_at" DESC;
+
+CREATE OR REPLACE VIEW "docbox_latest_edit_per_link" AS
+SELECT DISTINCT ON ("link_id") "link_id", "user_id", "created_at"
+FROM "docbox_edit_history"
+WHERE "type" <> 'UpdateConflict'
+ORDER BY "link_id", "created_at" DESC;
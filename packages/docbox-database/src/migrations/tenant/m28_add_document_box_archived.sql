@@ -0,0 +1,3 @@
+-- Archived document boxes are read-only and excluded from default searches
+ALTER TABLE "docbox_boxes"
+ADD COLUMN "archived" BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,11 @@
+CREATE TABLE "docbox_scope_patterns"
+(
+    "pattern"     VARCHAR     NOT NULL
+        PRIMARY KEY,
+    "parent"      VARCHAR     NULL
+        CONSTRAINT "FK_scope_patterns_parent"
+            REFERENCES "docbox_scope_patterns" ("pattern")
+            ON DELETE CASCADE,
+    "description" VARCHAR     NULL,
+    "created_at"  TIMESTAMPTZ NOT NULL
+);
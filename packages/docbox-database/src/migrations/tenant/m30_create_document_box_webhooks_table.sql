@@ -0,0 +1,13 @@
+CREATE TABLE "docbox_box_webhooks"
+(
+    "id"           UUID        NOT NULL
+        PRIMARY KEY,
+    "document_box" VARCHAR     NOT NULL,
+    "url"          VARCHAR     NOT NULL,
+    "secret"       VARCHAR     NOT NULL,
+    "events"       VARCHAR[]   NOT NULL,
+    "created_at"   TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX "IDX_box_webhooks_document_box"
+    ON "docbox_box_webhooks" ("document_box");
@@ -0,0 +1,11 @@
+CREATE TABLE "docbox_event_payloads"
+(
+    "id"         UUID        NOT NULL
+        PRIMARY KEY,
+    "event"      VARCHAR     NOT NULL,
+    "payload"    JSONB       NOT NULL,
+    "created_at" TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX "IDX_event_payloads_created_at"
+    ON "docbox_event_payloads" ("created_at");
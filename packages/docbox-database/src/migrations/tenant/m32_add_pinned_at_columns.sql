@@ -0,0 +1,16 @@
+-- When each item was pinned, used to order pinned listings
+ALTER TABLE "docbox_files"
+ADD COLUMN "pinned_at" TIMESTAMP WITH TIME ZONE NULL;
+
+ALTER TABLE "docbox_folders"
+ADD COLUMN "pinned_at" TIMESTAMP WITH TIME ZONE NULL;
+
+ALTER TABLE "docbox_links"
+ADD COLUMN "pinned_at" TIMESTAMP WITH TIME ZONE NULL;
+
+-- Items pinned before the column existed use their creation date
+UPDATE "docbox_files" SET "pinned_at" = "created_at" WHERE "pinned";
+
+UPDATE "docbox_folders" SET "pinned_at" = "created_at" WHERE "pinned";
+
+UPDATE "docbox_links" SET "pinned_at" = "created_at" WHERE "pinned";
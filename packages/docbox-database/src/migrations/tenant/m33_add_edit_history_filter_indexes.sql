@@ -0,0 +1,15 @@
+-- Index file results for fast edit history filtered by type
+CREATE INDEX idx_edit_history_file_type_created_at_desc
+ON "docbox_edit_history" ("file_id", "type", "created_at" DESC);
+
+-- Index folder results for fast edit history filtered by type
+CREATE INDEX idx_edit_history_folder_type_created_at_desc
+ON "docbox_edit_history" ("folder_id", "type", "created_at" DESC);
+
+-- Index link results for fast edit history filtered by type
+CREATE INDEX idx_edit_history_link_type_created_at_desc
+ON "docbox_edit_history" ("link_id", "type", "created_at" DESC);
+
+-- Index edit history by user for filtering by the user who made the change
+CREATE INDEX idx_edit_history_user_id_created_at_desc
+ON "docbox_edit_history" ("user_id", "created_at" DESC);
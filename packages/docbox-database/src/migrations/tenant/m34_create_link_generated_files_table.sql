@@ -0,0 +1,17 @@
+CREATE TABLE "docbox_link_generated_files"
+(
+    "id"         UUID                     NOT NULL
+        PRIMARY KEY,
+    "link_id"    UUID                     NOT NULL
+        CONSTRAINT "FK_link_generated_file_link"
+            REFERENCES "docbox_links" ("id")
+            ON DELETE CASCADE,
+    "mime"       VARCHAR                  NOT NULL,
+    "type"       TEXT                     NOT NULL,
+    "file_key"   VARCHAR                  NOT NULL,
+    "created_at" TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+-- Links only store a single generated file of each type
+CREATE UNIQUE INDEX idx_link_generated_files_link_id_type
+ON "docbox_link_generated_files" ("link_id", "type");
@@ -0,0 +1,28 @@
+-- When the website metadata for each link was last fetched
+ALTER TABLE "docbox_links"
+ADD COLUMN "fetched_at" TIMESTAMP WITH TIME ZONE NULL;
+
+ALTER TYPE docbox_link
+ADD ATTRIBUTE "fetched_at" TIMESTAMP WITH TIME ZONE;
+
+-- ================================================================
+-- Helper function to construct a docbox_link from a row of the
+-- docbox_links table
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION mk_docbox_link(p_link docbox_links)
+RETURNS docbox_link
+LANGUAGE sql
+IMMUTABLE
+AS $$
+    SELECT ROW(
+        p_link."id",
+        p_link."name",
+        p_link."value",
+        p_link."pinned",
+        p_link."folder_id",
+        p_link."created_at",
+        p_link."created_by",
+        p_link."fetched_at"
+    )::docbox_link
+$$;
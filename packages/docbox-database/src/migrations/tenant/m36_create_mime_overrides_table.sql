@@ -0,0 +1,6 @@
+CREATE TABLE "docbox_mime_overrides"
+(
+    "extension" VARCHAR NOT NULL
+        PRIMARY KEY,
+    "mime"      VARCHAR NOT NULL
+);
@@ -0,0 +1,15 @@
+CREATE TABLE "docbox_files_pii_analysis"
+(
+    "file_id"      UUID        NOT NULL
+        PRIMARY KEY
+        CONSTRAINT "FK_files_pii_analysis_file"
+            REFERENCES "docbox_files" ("id")
+            ON DELETE CASCADE,
+    "contains_pii" BOOLEAN     NOT NULL,
+    "findings"     JSONB       NOT NULL,
+    "analyzed_at"  TIMESTAMPTZ NOT NULL
+);
+
+-- Index files containing PII for fast compliance reports
+CREATE INDEX idx_files_pii_analysis_contains_pii
+ON "docbox_files_pii_analysis" ("file_id") WHERE "contains_pii";
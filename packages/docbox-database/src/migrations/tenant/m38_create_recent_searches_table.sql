@@ -0,0 +1,21 @@
+CREATE TABLE "docbox_recent_searches"
+(
+    "id"           UUID                     NOT NULL
+        PRIMARY KEY,
+    "user_id"      VARCHAR                  NOT NULL
+        CONSTRAINT "FK_recent_searches_user"
+            REFERENCES "docbox_users" ("id")
+            ON DELETE CASCADE,
+    "document_box" VARCHAR                  NOT NULL
+        CONSTRAINT "FK_recent_searches_document_box"
+            REFERENCES "docbox_boxes" ("scope")
+            ON DELETE CASCADE,
+    "query"        VARCHAR                  NOT NULL,
+    "searched_at"  TIMESTAMP WITH TIME ZONE NOT NULL,
+    -- Repeated searches update the existing entry
+    CONSTRAINT "UQ_recent_searches_user_query"
+        UNIQUE ("user_id", "document_box", "query")
+);
+
+CREATE INDEX idx_recent_searches_user_searched_at
+ON "docbox_recent_searches" ("user_id", "document_box", "searched_at" DESC);
@@ -0,0 +1,11 @@
+-- Duration in milliseconds of each stage of processing a file, stages
+-- that did not run for the file are NULL
+ALTER TABLE "docbox_files_processing"
+ADD COLUMN "conversion_ms" BIGINT NULL,
+ADD COLUMN "text_extraction_ms" BIGINT NULL,
+ADD COLUMN "thumbnail_ms" BIGINT NULL,
+ADD COLUMN "indexing_ms" BIGINT NULL,
+ADD COLUMN "total_ms" BIGINT NULL;
+
+CREATE INDEX idx_files_processing_processed_at
+ON "docbox_files_processing" ("processed_at");
@@ -0,0 +1,4 @@
+-- Page number for generated files produced per page (i.e page previews),
+-- NULL for generated files that represent the whole file
+ALTER TABLE "docbox_generated_files"
+ADD COLUMN "page" INTEGER NULL;
@@ -0,0 +1,11 @@
+CREATE TABLE "docbox_files_text_stats"
+(
+    "file_id"              UUID   NOT NULL
+        PRIMARY KEY
+        CONSTRAINT "FK_files_text_stats_file"
+            REFERENCES "docbox_files" ("id")
+            ON DELETE CASCADE,
+    "word_count"           BIGINT NOT NULL,
+    "character_count"      BIGINT NOT NULL,
+    "reading_time_seconds" BIGINT NOT NULL
+);
@@ -0,0 +1,16 @@
+CREATE TABLE "docbox_connector_sync_items"
+(
+    "connector_id"  VARCHAR                  NOT NULL,
+    "source_key"    VARCHAR                  NOT NULL,
+    "file_id"       UUID                     NOT NULL
+        CONSTRAINT "FK_connector_sync_items_file"
+            REFERENCES "docbox_files" ("id")
+            ON DELETE CASCADE,
+    "etag"          VARCHAR                  NULL,
+    "last_modified" TIMESTAMP WITH TIME ZONE NULL,
+    "synced_at"     TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY ("connector_id", "source_key")
+);
+
+CREATE INDEX idx_connector_sync_items_file_id
+ON "docbox_connector_sync_items" ("file_id");
@@ -0,0 +1,8 @@
+CREATE TABLE "docbox_imap_mailbox_state"
+(
+    "mailbox_id"   VARCHAR                  NOT NULL
+        PRIMARY KEY,
+    "uid_validity" BIGINT                   NOT NULL,
+    "last_uid"     BIGINT                   NOT NULL,
+    "updated_at"   TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1,23 @@
+-- Audit log of files sent by email
+CREATE TABLE "docbox_file_emails"
+(
+    "id"              UUID                     NOT NULL
+        PRIMARY KEY,
+    "file_id"         UUID                     NOT NULL
+        CONSTRAINT "FK_file_emails_file"
+            REFERENCES "docbox_files" ("id")
+            ON DELETE CASCADE,
+    "recipients"      VARCHAR[]                NOT NULL,
+    "subject"         VARCHAR                  NOT NULL,
+    -- How the file was delivered ("Attachment" or "Link")
+    "delivery"        VARCHAR                  NOT NULL,
+    "link_expires_at" TIMESTAMP WITH TIME ZONE NULL,
+    "sent_by"         VARCHAR                  NULL
+        CONSTRAINT "FK_file_emails_sent_by"
+            REFERENCES "docbox_users" ("id")
+            ON DELETE SET NULL,
+    "sent_at"         TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX idx_file_emails_file_id
+ON "docbox_file_emails" ("file_id", "sent_at" DESC);
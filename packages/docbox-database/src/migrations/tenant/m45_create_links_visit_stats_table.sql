@@ -0,0 +1,13 @@
+CREATE TABLE "docbox_links_visit_stats"
+(
+    "link_id"         UUID        NOT NULL
+        PRIMARY KEY
+        CONSTRAINT "FK_links_visit_stats_link"
+            REFERENCES "docbox_links" ("id")
+            ON DELETE CASCADE,
+    "visit_count"     BIGINT      NOT NULL DEFAULT 0,
+    "last_visited_at" TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX "IDX_links_visit_stats_visit_count"
+    ON "docbox_links_visit_stats" ("visit_count" DESC);
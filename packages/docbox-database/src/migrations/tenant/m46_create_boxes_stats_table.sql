@@ -0,0 +1,14 @@
+CREATE TABLE "docbox_boxes_stats"
+(
+    "document_box"  VARCHAR     NOT NULL
+        PRIMARY KEY
+        CONSTRAINT "FK_boxes_stats_document_box"
+            REFERENCES "docbox_boxes" ("scope")
+            ON DELETE CASCADE,
+    "total_files"   BIGINT      NOT NULL DEFAULT 0,
+    "total_links"   BIGINT      NOT NULL DEFAULT 0,
+    "total_folders" BIGINT      NOT NULL DEFAULT 0,
+    "file_size"     BIGINT      NOT NULL DEFAULT 0,
+    -- Last time the counters were recomputed from the contents
+    "reconciled_at" TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,296 @@
+-- Materialized path of parent folder IDs ordered from the root folder down
+-- to the direct parent of the folder (Empty for root folders)
+ALTER TABLE "docbox_folders"
+ADD COLUMN "path" UUID[] NOT NULL DEFAULT '{}';
+
+-- Populate the path for existing folders
+WITH RECURSIVE "folder_hierarchy" AS (
+    SELECT "id", ARRAY[]::UUID[] AS "path"
+    FROM "docbox_folders"
+    WHERE "folder_id" IS NULL
+
+    UNION ALL
+
+    SELECT "folder"."id", "fh"."path" || "folder"."folder_id"
+    FROM "docbox_folders" AS "folder"
+    INNER JOIN "folder_hierarchy" "fh" ON "folder"."folder_id" = "fh"."id"
+)
+UPDATE "docbox_folders"
+SET "path" = "fh"."path"
+FROM "folder_hierarchy" "fh"
+WHERE "docbox_folders"."id" = "fh"."id";
+
+-- Index for finding all folders within a folder
+CREATE INDEX "IDX_folders_path" ON "docbox_folders" USING GIN ("path");
+
+-- ================================================================
+-- Resolve the path segments for a materialized path of folder IDs
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION resolve_materialized_path(p_path UUID[])
+RETURNS docbox_path_segment[]
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT ARRAY(
+        SELECT ROW("folder"."id", "folder"."name")::docbox_path_segment
+        FROM UNNEST(p_path) WITH ORDINALITY AS "segment"("id", "depth")
+        INNER JOIN "docbox_folders" "folder" ON "folder"."id" = "segment"."id"
+        ORDER BY "segment"."depth"
+    )
+$$;
+
+COMMENT ON FUNCTION resolve_materialized_path(UUID[])
+IS 'Resolve the path segments for the folder IDs of a materialized path';
+
+-- ================================================================
+-- Resolve the ID and names of the parent folder tree using the ID
+-- of the desired folder
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION resolve_folder_path(p_folder_id UUID)
+RETURNS SETOF docbox_path_segment
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT UNNEST(resolve_materialized_path("folder"."path"))
+    FROM "docbox_folders" "folder"
+    WHERE "folder"."id" = p_folder_id
+$$;
+
+-- ================================================================
+-- Resolve a collection of folder paths for `p_folder_ids` within
+-- `p_document_box`
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION resolve_folders_paths(p_document_box VARCHAR, p_folder_ids UUID[])
+RETURNS TABLE (item_id UUID, path docbox_path_segment[])
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT "folder"."id", resolve_materialized_path("folder"."path")
+    FROM "docbox_folders" "folder"
+    WHERE "folder"."id" = ANY(p_folder_ids)
+        AND "folder"."document_box" = p_document_box
+        -- Root folders do not have a path
+        AND "folder"."folder_id" IS NOT NULL
+$$;
+
+-- ================================================================
+-- Recursively resolve all the children folder ID's within the
+-- folder with the ID `p_folder_id` (Including the folder itself)
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION recursive_folder_children_ids(p_folder_id UUID)
+RETURNS TABLE (id UUID)
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT "folder"."id"
+    FROM "docbox_folders" "folder"
+    WHERE "folder"."id" = p_folder_id
+        OR "folder"."path" @> ARRAY[p_folder_id]
+$$;
+
+-- ================================================================
+-- Resolve a collection of folders with extra data using a
+-- collection of input scope and folder ID pairs.
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION resolve_folders_with_extra_mixed_scopes(
+    p_input docbox_input_pair[]
+)
+RETURNS TABLE (
+    folder docbox_folder,
+    created_by docbox_user,
+    last_modified_by docbox_user,
+    last_modified_at TIMESTAMP WITH TIME ZONE,
+    full_path docbox_path_segment[]
+)
+LANGUAGE sql
+STABLE
+AS $$
+    WITH "input_folders" AS (
+        SELECT folder_id, document_box
+        FROM UNNEST(p_input) AS t(document_box, folder_id)
+    )
+    SELECT
+        mk_docbox_folder("folder") AS "folder",
+        mk_docbox_user("cu") AS "created_by",
+        mk_docbox_user("mu") AS "last_modified_by",
+        "ehl"."created_at" AS "last_modified_at",
+        CASE
+            WHEN "folder"."folder_id" IS NULL THEN NULL
+            ELSE resolve_materialized_path("folder"."path")
+        END AS "full_path"
+    FROM "docbox_folders" AS "folder"
+    INNER JOIN "input_folders" "i"
+        ON "folder"."id" = "i"."folder_id"
+        AND "folder"."document_box" = "i"."document_box"
+    LEFT JOIN "docbox_users" AS "cu"
+        ON "folder"."created_by" = "cu"."id"
+    LEFT JOIN "docbox_latest_edit_per_folder" AS "ehl"
+        ON "folder"."id" = "ehl"."folder_id"
+    LEFT JOIN "docbox_users" AS "mu"
+        ON "ehl"."user_id" = "mu"."id"
+$$;
+
+-- ================================================================
+-- Resolve the path of a link by ID
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION resolve_link_path(p_link_id UUID)
+RETURNS SETOF docbox_path_segment
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT UNNEST(resolve_materialized_path("folder"."path" || "folder"."id"))
+    FROM "docbox_links" "link"
+    INNER JOIN "docbox_folders" "folder" ON "link"."folder_id" = "folder"."id"
+    WHERE "link"."id" = p_link_id
+$$;
+
+-- ================================================================
+-- Resolve a collection of link paths for `p_link_ids` within
+-- `p_document_box`
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION resolve_links_paths(
+    p_document_box VARCHAR,
+    p_link_ids UUID[]
+)
+RETURNS TABLE (item_id UUID, path docbox_path_segment[])
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT "link"."id", resolve_materialized_path("folder"."path" || "folder"."id")
+    FROM "docbox_links" "link"
+    INNER JOIN "docbox_folders" "folder" ON "link"."folder_id" = "folder"."id"
+    WHERE "link"."id" = ANY(p_link_ids)
+        AND "folder"."document_box" = p_document_box
+$$;
+
+-- ================================================================
+-- Resolve links by scope and ID pairs with extra additional data
+-- like the folder path and the user details for last modified
+-- and creator
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION resolve_links_with_extra_mixed_scopes(
+    p_input docbox_input_pair[]
+)
+RETURNS TABLE (
+    link docbox_link,
+    created_by docbox_user,
+    last_modified_by docbox_user,
+    last_modified_at TIMESTAMP WITH TIME ZONE,
+    full_path docbox_path_segment[],
+    document_box VARCHAR
+)
+LANGUAGE sql
+STABLE
+AS $$
+    WITH "input_links" AS (
+        SELECT document_box, link_id
+        FROM UNNEST(p_input) AS t(document_box, link_id)
+    )
+    SELECT
+        mk_docbox_link("link") AS "link",
+        mk_docbox_user("cu") AS "created_by",
+        mk_docbox_user("mu") AS "last_modified_by",
+        "ehl"."created_at" AS "last_modified_at",
+        resolve_materialized_path("folder"."path" || "folder"."id") AS "full_path",
+        "folder"."document_box" AS "document_box"
+    FROM "docbox_links" AS "link"
+    INNER JOIN "docbox_folders" "folder"
+        ON "link"."folder_id" = "folder"."id"
+    INNER JOIN "input_links" "i"
+        ON "link"."id" = "i"."link_id"
+        AND "folder"."document_box" = "i"."document_box"
+    LEFT JOIN "docbox_users" AS "cu"
+        ON "link"."created_by" = "cu"."id"
+    LEFT JOIN "docbox_latest_edit_per_link" AS "ehl"
+        ON "link"."id" = "ehl"."link_id"
+    LEFT JOIN "docbox_users" AS "mu"
+        ON "ehl"."user_id" = "mu"."id"
+$$;
+
+-- ================================================================
+-- Resolve the path of a file by ID
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION resolve_file_path(p_file_id UUID)
+RETURNS SETOF docbox_path_segment
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT UNNEST(resolve_materialized_path("folder"."path" || "folder"."id"))
+    FROM "docbox_files" "file"
+    INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+    WHERE "file"."id" = p_file_id
+$$;
+
+-- ================================================================
+-- Resolve a collection of file paths for `p_file_ids` within
+-- `p_document_box`
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION resolve_files_paths(
+    p_document_box VARCHAR,
+    p_file_ids UUID[]
+)
+RETURNS TABLE (item_id UUID, path docbox_path_segment[])
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT "file"."id", resolve_materialized_path("folder"."path" || "folder"."id")
+    FROM "docbox_files" "file"
+    INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+    WHERE "file"."id" = ANY(p_file_ids)
+        AND "folder"."document_box" = p_document_box
+$$;
+
+-- ================================================================
+-- Resolve files by scope and ID pairs with extra additional data
+-- like the folder path and the user details for last modified and
+-- creator
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION resolve_files_with_extra_mixed_scopes(
+    p_input docbox_input_pair[]
+)
+RETURNS TABLE (
+    file docbox_file,
+    created_by docbox_user,
+    last_modified_by docbox_user,
+    last_modified_at TIMESTAMP WITH TIME ZONE,
+    full_path docbox_path_segment[],
+    document_box VARCHAR
+)
+LANGUAGE sql
+STABLE
+AS $$
+    WITH "input_files" AS (
+        SELECT document_box, file_id
+        FROM UNNEST(p_input) AS t(document_box, file_id)
+    )
+    SELECT
+        mk_docbox_file("file") AS "file",
+        mk_docbox_user("cu") AS "created_by",
+        mk_docbox_user("mu") AS "last_modified_by",
+        "ehl"."created_at" AS "last_modified_at",
+        resolve_materialized_path("folder"."path" || "folder"."id") AS "full_path",
+        "folder"."document_box" AS "document_box"
+    FROM "docbox_files" AS "file"
+    INNER JOIN "docbox_folders" "folder"
+        ON "file"."folder_id" = "folder"."id"
+    INNER JOIN "input_files" "i"
+        ON "file"."id" = "i"."file_id"
+        AND "folder"."document_box" = "i"."document_box"
+    LEFT JOIN "docbox_users" AS "cu"
+        ON "file"."created_by" = "cu"."id"
+    LEFT JOIN "docbox_latest_edit_per_file" AS "ehl"
+        ON "file"."id" = "ehl"."file_id"
+    LEFT JOIN "docbox_users" AS "mu"
+        ON "ehl"."user_id" = "mu"."id"
+$$;
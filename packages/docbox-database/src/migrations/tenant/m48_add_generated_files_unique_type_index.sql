@@ -0,0 +1,12 @@
+-- Remove duplicate generated files created by concurrent regeneration
+-- requests, keeping the earliest generated file of each type and page
+DELETE FROM "docbox_generated_files" "gen"
+USING "docbox_generated_files" "other"
+WHERE "gen"."file_id" = "other"."file_id"
+    AND "gen"."type" = "other"."type"
+    AND COALESCE("gen"."page", -1) = COALESCE("other"."page", -1)
+    AND ("gen"."created_at", "gen"."id") > ("other"."created_at", "other"."id");
+
+-- Only allow a single generated file of each type (and page) per file
+CREATE UNIQUE INDEX idx_generated_files_file_id_type_page
+ON "docbox_generated_files" ("file_id", "type", COALESCE("page", -1));
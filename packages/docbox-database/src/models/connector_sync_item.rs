@@ -0,0 +1,76 @@
+use super::file::FileId;
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+
+/// Object from an external source connector that has been synced
+/// into a document box as a file
+#[derive(Debug, Clone, FromRow, PartialEq, Eq)]
+pub struct ConnectorSyncItem {
+    /// Identifier of the connector source the object came from
+    pub connector_id: String,
+    /// Key of the object within the source
+    pub source_key: String,
+    /// ID of the file created from the object
+    pub file_id: FileId,
+    /// ETag of the object when it was last synced
+    pub etag: Option<String>,
+    /// Last modified time of the object when it was last synced
+    pub last_modified: Option<DateTime<Utc>>,
+    /// When the object was last synced
+    pub synced_at: DateTime<Utc>,
+}
+
+impl ConnectorSyncItem {
+    /// Store the sync state of an object, replacing any previous state
+    /// for the same object
+    pub async fn set(db: impl DbExecutor<'_>, item: &ConnectorSyncItem) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_connector_sync_items"
+                ("connector_id", "source_key", "file_id", "etag", "last_modified", "synced_at")
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT ("connector_id", "source_key") DO UPDATE
+            SET
+                "file_id" = EXCLUDED."file_id",
+                "etag" = EXCLUDED."etag",
+                "last_modified" = EXCLUDED."last_modified",
+                "synced_at" = EXCLUDED."synced_at"
+        "#,
+        )
+        .bind(&item.connector_id)
+        .bind(&item.source_key)
+        .bind(item.file_id)
+        .bind(&item.etag)
+        .bind(item.last_modified)
+        .bind(item.synced_at)
+        .execute(db)
+        .await
+    }
+
+    /// Find the sync state of all objects synced from a connector
+    pub async fn find_by_connector(
+        db: impl DbExecutor<'_>,
+        connector_id: &str,
+    ) -> DbResult<Vec<ConnectorSyncItem>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_connector_sync_items" WHERE "connector_id" = $1"#)
+            .bind(connector_id)
+            .fetch_all(db)
+            .await
+    }
+
+    /// Delete the sync state of an object
+    pub async fn delete(
+        db: impl DbExecutor<'_>,
+        connector_id: &str,
+        source_key: &str,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"DELETE FROM "docbox_connector_sync_items" WHERE "connector_id" = $1 AND "source_key" = $2"#,
+        )
+        .bind(connector_id)
+        .bind(source_key)
+        .execute(db)
+        .await
+    }
+}
@@ -7,12 +7,15 @@ use utoipa::ToSchema;
 pub type DocumentBoxScopeRaw = String;
 pub type DocumentBoxScopeRawRef<'a> = &'a str;
 
-#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct DocumentBox {
     /// Scope for the document box
     pub scope: DocumentBoxScopeRaw,
     /// Date of creation for the document box
     pub created_at: DateTime<Utc>,
+    /// Whether the document box is archived, archived document
+    /// boxes are read-only
+    pub archived: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -114,6 +117,7 @@ impl DocumentBox {
         let document_box = DocumentBox {
             scope,
             created_at: Utc::now(),
+            archived: false,
         };
 
         sqlx::query(r#"INSERT INTO "docbox_boxes" ("scope", "created_at") VALUES ($1, $2)"#)
@@ -125,6 +129,94 @@ impl DocumentBox {
         Ok(document_box)
     }
 
+    /// Set whether the document box is archived
+    pub async fn set_archived(
+        mut self,
+        db: impl DbExecutor<'_>,
+        archived: bool,
+    ) -> DbResult<DocumentBox> {
+        sqlx::query(r#"UPDATE "docbox_boxes" SET "archived" = $1 WHERE "scope" = $2"#)
+            .bind(archived)
+            .bind(&self.scope)
+            .execute(db)
+            .await?;
+
+        self.archived = archived;
+        Ok(self)
+    }
+
+    /// Check if the document box with the provided scope is archived,
+    /// [None] if the document box does not exist
+    pub async fn is_archived(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+    ) -> DbResult<Option<bool>> {
+        sqlx::query_scalar(r#"SELECT "archived" FROM "docbox_boxes" WHERE "scope" = $1"#)
+            .bind(scope)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Get the scopes of all archived document boxes
+    pub async fn archived_scopes(db: impl DbExecutor<'_>) -> DbResult<Vec<DocumentBoxScopeRaw>> {
+        sqlx::query_scalar(r#"SELECT "scope" FROM "docbox_boxes" WHERE "archived""#)
+            .fetch_all(db)
+            .await
+    }
+
+    /// Get the scopes of all document boxes starting with `prefix` that
+    /// are not archived
+    pub async fn unarchived_scopes_by_prefix(
+        db: impl DbExecutor<'_>,
+        prefix: &str,
+    ) -> DbResult<Vec<DocumentBoxScopeRaw>> {
+        sqlx::query_scalar(
+            r#"
+            SELECT "scope" FROM "docbox_boxes"
+            WHERE NOT "archived" AND starts_with("scope", $1)
+            "#,
+        )
+        .bind(prefix)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Get a page of document box scopes starting with `prefix`
+    pub async fn scopes_by_prefix(
+        db: impl DbExecutor<'_>,
+        prefix: &str,
+        offset: u64,
+        limit: u64,
+    ) -> DbResult<Vec<DocumentBoxScopeRaw>> {
+        sqlx::query_scalar(
+            r#"
+            SELECT "scope" FROM "docbox_boxes"
+            WHERE starts_with("scope", $3)
+            ORDER BY "scope" ASC
+            OFFSET $1 LIMIT $2"#,
+        )
+        .bind(offset as i64)
+        .bind(limit as i64)
+        .bind(prefix)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Get the total number of document box scopes starting with `prefix`
+    pub async fn scopes_by_prefix_total(db: impl DbExecutor<'_>, prefix: &str) -> DbResult<i64> {
+        let result: CountResult = sqlx::query_as(
+            r#"
+            SELECT COUNT(*) as "count" FROM "docbox_boxes"
+            WHERE starts_with("scope", $1)
+            "#,
+        )
+        .bind(prefix)
+        .fetch_one(db)
+        .await?;
+
+        Ok(result.count)
+    }
+
     /// Deletes the document box
     pub async fn delete(&self, db: impl DbExecutor<'_>) -> DbResult<PgQueryResult> {
         sqlx::query(r#"DELETE FROM "docbox_boxes" WHERE "scope" = $1"#)
@@ -0,0 +1,206 @@
+use super::document_box::{DocumentBoxScopeRaw, DocumentBoxScopeRawRef};
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+
+/// Incrementally maintained counters for the contents of a document box,
+/// updated as items are created and deleted and periodically reconciled
+/// against the actual contents
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DocumentBoxStatsCounters {
+    /// Scope of the document box the counters are for
+    pub document_box: DocumentBoxScopeRaw,
+    /// Total number of files within the document box
+    pub total_files: i64,
+    /// Total number of links within the document box
+    pub total_links: i64,
+    /// Total number of folders within the document box (Excluding the root)
+    pub total_folders: i64,
+    /// Total size in bytes of all files within the document box
+    pub file_size: i64,
+    /// When the counters were last recomputed from the contents
+    pub reconciled_at: DateTime<Utc>,
+}
+
+/// Change to apply to the counters of a document box
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DocumentBoxStatsDelta {
+    pub files: i64,
+    pub links: i64,
+    pub folders: i64,
+    pub file_size: i64,
+}
+
+impl DocumentBoxStatsDelta {
+    /// Delta for a single file of `size` bytes
+    pub fn file(size: i32) -> Self {
+        Self {
+            files: 1,
+            file_size: size as i64,
+            ..Default::default()
+        }
+    }
+
+    /// Delta for `count` links
+    pub fn links(count: i64) -> Self {
+        Self {
+            links: count,
+            ..Default::default()
+        }
+    }
+
+    /// Delta for a single folder
+    pub fn folder() -> Self {
+        Self {
+            folders: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Negate the delta, used when items are removed
+    pub fn negate(self) -> Self {
+        Self {
+            files: -self.files,
+            links: -self.links,
+            folders: -self.folders,
+            file_size: -self.file_size,
+        }
+    }
+}
+
+/// Recomputes the counters from the actual contents of the document boxes,
+/// binds the optional scope to restrict to (`$1`) and reconciliation time (`$2`)
+const RECONCILE_QUERY: &str = r#"
+    INSERT INTO "docbox_boxes_stats" (
+        "document_box",
+        "total_files",
+        "total_links",
+        "total_folders",
+        "file_size",
+        "reconciled_at"
+    )
+    SELECT
+        "box"."scope",
+        (
+            SELECT COUNT(*)
+            FROM "docbox_files" "file"
+            INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+            WHERE "folder"."document_box" = "box"."scope"
+        ),
+        (
+            SELECT COUNT(*)
+            FROM "docbox_links" "link"
+            INNER JOIN "docbox_folders" "folder" ON "link"."folder_id" = "folder"."id"
+            WHERE "folder"."document_box" = "box"."scope"
+        ),
+        (
+            SELECT COUNT(*)
+            FROM "docbox_folders" "folder"
+            WHERE "folder"."document_box" = "box"."scope"
+                AND "folder"."folder_id" IS NOT NULL
+        ),
+        (
+            SELECT COALESCE(SUM("file"."size"), 0)
+            FROM "docbox_files" "file"
+            INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+            WHERE "folder"."document_box" = "box"."scope"
+        ),
+        $2
+    FROM "docbox_boxes" "box"
+    WHERE $1::VARCHAR IS NULL OR "box"."scope" = $1
+    ON CONFLICT ("document_box") DO UPDATE
+    SET
+        "total_files" = EXCLUDED."total_files",
+        "total_links" = EXCLUDED."total_links",
+        "total_folders" = EXCLUDED."total_folders",
+        "file_size" = EXCLUDED."file_size",
+        "reconciled_at" = EXCLUDED."reconciled_at"
+    RETURNING *
+"#;
+
+impl DocumentBoxStatsCounters {
+    /// Create the counters for a newly created and empty document box
+    pub async fn create(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRaw,
+        created_at: DateTime<Utc>,
+    ) -> DbResult<DocumentBoxStatsCounters> {
+        sqlx::query_as(
+            r#"
+            INSERT INTO "docbox_boxes_stats" ("document_box", "reconciled_at")
+            VALUES ($1, $2)
+            RETURNING *
+        "#,
+        )
+        .bind(scope)
+        .bind(created_at)
+        .fetch_one(db)
+        .await
+    }
+
+    /// Apply a `delta` to the counters of the document box `scope`, boxes
+    /// without counters are left for reconciliation to populate
+    pub async fn apply_delta(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+        delta: DocumentBoxStatsDelta,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            UPDATE "docbox_boxes_stats"
+            SET
+                "total_files" = GREATEST("total_files" + $2, 0),
+                "total_links" = GREATEST("total_links" + $3, 0),
+                "total_folders" = GREATEST("total_folders" + $4, 0),
+                "file_size" = GREATEST("file_size" + $5, 0)
+            WHERE "document_box" = $1
+        "#,
+        )
+        .bind(scope)
+        .bind(delta.files)
+        .bind(delta.links)
+        .bind(delta.folders)
+        .bind(delta.file_size)
+        .execute(db)
+        .await
+    }
+
+    /// Find the counters for the document box `scope`
+    pub async fn find(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+    ) -> DbResult<Option<DocumentBoxStatsCounters>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_boxes_stats" WHERE "document_box" = $1"#)
+            .bind(scope)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Recompute the counters for the document box `scope` from its
+    /// actual contents, creating the counters if they are missing
+    pub async fn reconcile(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+        reconciled_at: DateTime<Utc>,
+    ) -> DbResult<Option<DocumentBoxStatsCounters>> {
+        sqlx::query_as(RECONCILE_QUERY)
+            .bind(Some(scope))
+            .bind(reconciled_at)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Recompute the counters for every document box from their
+    /// actual contents, creating any counters that are missing
+    pub async fn reconcile_all(
+        db: impl DbExecutor<'_>,
+        reconciled_at: DateTime<Utc>,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(RECONCILE_QUERY)
+            .bind(None::<DocumentBoxScopeRawRef<'_>>)
+            .bind(reconciled_at)
+            .execute(db)
+            .await
+    }
+}
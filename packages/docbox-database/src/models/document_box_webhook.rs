@@ -0,0 +1,127 @@
+use super::document_box::{DocumentBoxScopeRaw, DocumentBoxScopeRawRef};
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub type DocumentBoxWebhookId = Uuid;
+
+/// Webhook receiving the events that occur within a document box
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct DocumentBoxWebhook {
+    /// Unique ID of the webhook
+    #[schema(value_type = Uuid)]
+    pub id: DocumentBoxWebhookId,
+    /// Scope of the document box the webhook receives events for
+    pub document_box: DocumentBoxScopeRaw,
+    /// URL the events are delivered to
+    pub url: String,
+    /// Secret used to sign the delivered events, never exposed
+    /// after the webhook is created
+    #[serde(skip_serializing)]
+    #[schema(ignore)]
+    pub secret: String,
+    /// Names of the events the webhook receives, empty to receive all events
+    pub events: Vec<String>,
+    /// When the webhook was created
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct CreateDocumentBoxWebhook {
+    pub document_box: DocumentBoxScopeRaw,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<String>,
+}
+
+impl DocumentBoxWebhook {
+    /// Create a new webhook
+    pub async fn create(
+        db: impl DbExecutor<'_>,
+        create: CreateDocumentBoxWebhook,
+    ) -> DbResult<DocumentBoxWebhook> {
+        let webhook = DocumentBoxWebhook {
+            id: Uuid::new_v4(),
+            document_box: create.document_box,
+            url: create.url,
+            secret: create.secret,
+            events: create.events,
+            created_at: Utc::now(),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_box_webhooks" ("id", "document_box", "url", "secret", "events", "created_at")
+            VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        )
+        .bind(webhook.id)
+        .bind(webhook.document_box.as_str())
+        .bind(webhook.url.as_str())
+        .bind(webhook.secret.as_str())
+        .bind(&webhook.events)
+        .bind(webhook.created_at)
+        .execute(db)
+        .await?;
+
+        Ok(webhook)
+    }
+
+    /// Find a specific webhook within a document box
+    pub async fn find(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+        id: DocumentBoxWebhookId,
+    ) -> DbResult<Option<DocumentBoxWebhook>> {
+        sqlx::query_as(
+            r#"SELECT * FROM "docbox_box_webhooks" WHERE "document_box" = $1 AND "id" = $2"#,
+        )
+        .bind(scope)
+        .bind(id)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Get all the webhooks for a document box
+    pub async fn all_by_scope(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+    ) -> DbResult<Vec<DocumentBoxWebhook>> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM "docbox_box_webhooks"
+            WHERE "document_box" = $1
+            ORDER BY "created_at" ASC
+            "#,
+        )
+        .bind(scope)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Delete all the webhooks for a document box
+    pub async fn delete_by_scope(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(r#"DELETE FROM "docbox_box_webhooks" WHERE "document_box" = $1"#)
+            .bind(scope)
+            .execute(db)
+            .await
+    }
+
+    /// Delete the webhook
+    pub async fn delete(&self, db: impl DbExecutor<'_>) -> DbResult<PgQueryResult> {
+        sqlx::query(r#"DELETE FROM "docbox_box_webhooks" WHERE "id" = $1"#)
+            .bind(self.id)
+            .execute(db)
+            .await
+    }
+
+    /// Check if the webhook should receive the event with the provided `name`
+    pub fn accepts_event(&self, name: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|event| event == name)
+    }
+}
@@ -9,6 +9,7 @@ use uuid::Uuid;
 
 use super::{file::FileId, folder::FolderId, user::UserId};
 use crate::models::link::LinkId;
+use crate::models::shared::CountResult;
 use crate::models::user::User;
 use crate::{DbErr, DbExecutor, DbResult};
 
@@ -35,6 +36,8 @@ pub enum EditHistoryType {
     LinkValue,
     /// Pinned state changed
     ChangePinned,
+    /// Update was rejected as the item was modified concurrently
+    UpdateConflict,
 }
 
 impl TryFrom<String> for EditHistoryType {
@@ -78,6 +81,13 @@ pub enum EditHistoryMetadata {
         // New pinned state
         new_value: bool,
     },
+
+    UpdateConflict {
+        /// Version the update expected the item to be at
+        expected_version: i64,
+        /// Version the item was actually at
+        current_version: i64,
+    },
 }
 
 #[derive(Debug, Serialize, FromRow, ToSchema)]
@@ -122,6 +132,31 @@ pub struct CreateEditHistory {
     pub metadata: EditHistoryMetadata,
 }
 
+/// Order to return edit history entries in
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, ToSchema, PartialEq, Eq)]
+pub enum EditHistoryOrder {
+    /// Most recent changes first
+    #[default]
+    Newest,
+    /// Oldest changes first
+    Oldest,
+}
+
+/// Filters to apply when querying edit history entries
+#[derive(Debug, Default, Clone)]
+pub struct EditHistoryFilter {
+    /// Only include entries of this type
+    pub ty: Option<EditHistoryType>,
+    /// Only include entries made by this user
+    pub user_id: Option<UserId>,
+    /// Only include entries made at or after this time
+    pub start: Option<DateTime<Utc>>,
+    /// Only include entries made at or before this time
+    pub end: Option<DateTime<Utc>>,
+    /// Order to return the entries in
+    pub order: EditHistoryOrder,
+}
+
 #[derive(PartialEq, Eq)]
 pub enum CreateEditHistoryType {
     File(FileId),
@@ -156,6 +191,7 @@ impl EditHistory {
             EditHistoryMetadata::Rename { .. } => EditHistoryType::Rename,
             EditHistoryMetadata::LinkValue { .. } => EditHistoryType::LinkValue,
             EditHistoryMetadata::ChangePinned { .. } => EditHistoryType::ChangePinned,
+            EditHistoryMetadata::UpdateConflict { .. } => EditHistoryType::UpdateConflict,
         };
 
         let metadata = serde_json::to_value(&metadata).map_err(|err| DbErr::Encode(err.into()))?;
@@ -236,4 +272,195 @@ impl EditHistory {
         .fetch_all(db)
         .await
     }
+
+    /// Query a page of the edit history for a file matching `filter`
+    pub async fn query_by_file(
+        db: impl DbExecutor<'_>,
+        file_id: FileId,
+        filter: &EditHistoryFilter,
+        offset: u64,
+        limit: u64,
+    ) -> DbResult<Vec<EditHistory>> {
+        Self::query_by_item(db, r#""file_id""#, file_id, filter, offset, limit).await
+    }
+
+    /// Query a page of the edit history for a folder matching `filter`
+    pub async fn query_by_folder(
+        db: impl DbExecutor<'_>,
+        folder_id: FolderId,
+        filter: &EditHistoryFilter,
+        offset: u64,
+        limit: u64,
+    ) -> DbResult<Vec<EditHistory>> {
+        Self::query_by_item(db, r#""folder_id""#, folder_id, filter, offset, limit).await
+    }
+
+    /// Query a page of the edit history for a link matching `filter`
+    pub async fn query_by_link(
+        db: impl DbExecutor<'_>,
+        link_id: LinkId,
+        filter: &EditHistoryFilter,
+        offset: u64,
+        limit: u64,
+    ) -> DbResult<Vec<EditHistory>> {
+        Self::query_by_item(db, r#""link_id""#, link_id, filter, offset, limit).await
+    }
+
+    /// Count the edit history entries for a file matching `filter`
+    pub async fn total_by_file(
+        db: impl DbExecutor<'_>,
+        file_id: FileId,
+        filter: &EditHistoryFilter,
+    ) -> DbResult<i64> {
+        Self::total_by_item(db, r#""file_id""#, file_id, filter).await
+    }
+
+    /// Count the edit history entries for a folder matching `filter`
+    pub async fn total_by_folder(
+        db: impl DbExecutor<'_>,
+        folder_id: FolderId,
+        filter: &EditHistoryFilter,
+    ) -> DbResult<i64> {
+        Self::total_by_item(db, r#""folder_id""#, folder_id, filter).await
+    }
+
+    /// Count the edit history entries for a link matching `filter`
+    pub async fn total_by_link(
+        db: impl DbExecutor<'_>,
+        link_id: LinkId,
+        filter: &EditHistoryFilter,
+    ) -> DbResult<i64> {
+        Self::total_by_item(db, r#""link_id""#, link_id, filter).await
+    }
+
+    /// Count the edit history entries where the `item_column` matches the
+    /// `item_id`, the order of the `filter` is ignored
+    async fn total_by_item(
+        db: impl DbExecutor<'_>,
+        item_column: &str,
+        item_id: Uuid,
+        filter: &EditHistoryFilter,
+    ) -> DbResult<i64> {
+        let result: CountResult = sqlx::query_as(&format!(
+            r#"
+            SELECT COUNT(*) AS "count"
+            FROM "docbox_edit_history" "history"
+            WHERE "history".{item_column} = $1
+                AND ($2::TEXT IS NULL OR "history"."type" = $2)
+                AND ($3::VARCHAR IS NULL OR "history"."user_id" = $3)
+                AND ($4::TIMESTAMP WITH TIME ZONE IS NULL OR "history"."created_at" >= $4)
+                AND ($5::TIMESTAMP WITH TIME ZONE IS NULL OR "history"."created_at" <= $5)
+        "#
+        ))
+        .bind(item_id)
+        .bind(filter.ty.map(|ty| ty.to_string()))
+        .bind(filter.user_id.as_deref())
+        .bind(filter.start)
+        .bind(filter.end)
+        .fetch_one(db)
+        .await?;
+
+        Ok(result.count)
+    }
+
+    /// Query a page of the edit history where the `item_column` matches
+    /// the `item_id`, the filters are covered by the item indexes
+    async fn query_by_item(
+        db: impl DbExecutor<'_>,
+        item_column: &str,
+        item_id: Uuid,
+        filter: &EditHistoryFilter,
+        offset: u64,
+        limit: u64,
+    ) -> DbResult<Vec<EditHistory>> {
+        let order_by = match filter.order {
+            EditHistoryOrder::Newest => r#""history"."created_at" DESC"#,
+            EditHistoryOrder::Oldest => r#""history"."created_at" ASC"#,
+        };
+
+        sqlx::query_as(&format!(
+            r#"
+            SELECT "history".*, mk_docbox_user("user") AS "user"
+            FROM "docbox_edit_history" "history"
+            LEFT JOIN "docbox_users" "user" ON "history"."user_id" = "user"."id"
+            WHERE "history".{item_column} = $1
+                AND ($2::TEXT IS NULL OR "history"."type" = $2)
+                AND ($3::VARCHAR IS NULL OR "history"."user_id" = $3)
+                AND ($4::TIMESTAMP WITH TIME ZONE IS NULL OR "history"."created_at" >= $4)
+                AND ($5::TIMESTAMP WITH TIME ZONE IS NULL OR "history"."created_at" <= $5)
+            ORDER BY {order_by}
+            OFFSET $6
+            LIMIT $7
+        "#
+        ))
+        .bind(item_id)
+        .bind(filter.ty.map(|ty| ty.to_string()))
+        .bind(filter.user_id.as_deref())
+        .bind(filter.start)
+        .bind(filter.end)
+        .bind(offset as i64)
+        .bind(limit as i64)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Find a specific edit history entry for a file
+    pub async fn find_by_file(
+        db: impl DbExecutor<'_>,
+        file_id: FileId,
+        id: EditHistoryId,
+    ) -> DbResult<Option<EditHistory>> {
+        sqlx::query_as(
+            r#"
+            SELECT "history".*, mk_docbox_user("user") AS "user"
+            FROM "docbox_edit_history" "history"
+            LEFT JOIN "docbox_users" "user" ON "history"."user_id" = "user"."id"
+            WHERE "history"."file_id" = $1 AND "history"."id" = $2
+        "#,
+        )
+        .bind(file_id)
+        .bind(id)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Find a specific edit history entry for a folder
+    pub async fn find_by_folder(
+        db: impl DbExecutor<'_>,
+        folder_id: FolderId,
+        id: EditHistoryId,
+    ) -> DbResult<Option<EditHistory>> {
+        sqlx::query_as(
+            r#"
+            SELECT "history".*, mk_docbox_user("user") AS "user"
+            FROM "docbox_edit_history" "history"
+            LEFT JOIN "docbox_users" "user" ON "history"."user_id" = "user"."id"
+            WHERE "history"."folder_id" = $1 AND "history"."id" = $2
+        "#,
+        )
+        .bind(folder_id)
+        .bind(id)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Find a specific edit history entry for a link
+    pub async fn find_by_link(
+        db: impl DbExecutor<'_>,
+        link_id: LinkId,
+        id: EditHistoryId,
+    ) -> DbResult<Option<EditHistory>> {
+        sqlx::query_as(
+            r#"
+            SELECT "history".*, mk_docbox_user("user") AS "user"
+            FROM "docbox_edit_history" "history"
+            LEFT JOIN "docbox_users" "user" ON "history"."user_id" = "user"."id"
+            WHERE "history"."link_id" = $1 AND "history"."id" = $2
+        "#,
+        )
+        .bind(link_id)
+        .bind(id)
+        .fetch_optional(db)
+        .await
+    }
 }
@@ -0,0 +1,77 @@
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub type EventPayloadId = Uuid;
+
+/// Full payload of an event that was too large to be published directly,
+/// the published event contains a reference to the payload that consumers
+/// resolve to obtain the full event
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct EventPayload {
+    /// Unique ID of the payload
+    #[schema(value_type = Uuid)]
+    pub id: EventPayloadId,
+    /// Name of the event the payload is for
+    pub event: String,
+    /// The full event payload
+    pub payload: serde_json::Value,
+    /// When the payload was stored
+    pub created_at: DateTime<Utc>,
+}
+
+impl EventPayload {
+    /// Store the full `payload` for the `event`
+    pub async fn create(
+        db: impl DbExecutor<'_>,
+        event: String,
+        payload: serde_json::Value,
+    ) -> DbResult<EventPayload> {
+        let event_payload = EventPayload {
+            id: Uuid::new_v4(),
+            event,
+            payload,
+            created_at: Utc::now(),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_event_payloads" ("id", "event", "payload", "created_at")
+            VALUES ($1, $2, $3, $4)
+        "#,
+        )
+        .bind(event_payload.id)
+        .bind(event_payload.event.as_str())
+        .bind(&event_payload.payload)
+        .bind(event_payload.created_at)
+        .execute(db)
+        .await?;
+
+        Ok(event_payload)
+    }
+
+    /// Find a stored event payload by `id`
+    pub async fn find(
+        db: impl DbExecutor<'_>,
+        id: EventPayloadId,
+    ) -> DbResult<Option<EventPayload>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_event_payloads" WHERE "id" = $1"#)
+            .bind(id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Delete all event payloads stored before the provided `before` date
+    pub async fn delete_expired(
+        db: impl DbExecutor<'_>,
+        before: DateTime<Utc>,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(r#"DELETE FROM "docbox_event_payloads" WHERE "created_at" < $1"#)
+            .bind(before)
+            .execute(db)
+            .await
+    }
+}
@@ -0,0 +1,90 @@
+use super::file::FileId;
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+
+/// Cached output from processing a file, keyed by the content hash and
+/// mime type of the file that was processed.
+///
+/// Generated files are not duplicated within the cache, they are instead
+/// loaded from the generated files of the `source_file_id` file
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ExtractionCacheEntry {
+    /// SHA256 hash of the processed file content
+    pub hash: String,
+    /// Mime type the file was processed as
+    pub mime: String,
+    /// File that produced the cached output
+    pub source_file_id: FileId,
+    /// Whether the file was detected as encrypted
+    pub encrypted: bool,
+    /// JSON encoded page text extracted from the file
+    pub pages: Option<serde_json::Value>,
+    /// When the cache entry was created
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct CreateExtractionCacheEntry {
+    pub hash: String,
+    pub mime: String,
+    pub source_file_id: FileId,
+    pub encrypted: bool,
+    pub pages: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ExtractionCacheEntry {
+    /// Store a new cache entry, when an entry already exists for the
+    /// hash and mime the existing entry is kept
+    pub async fn create(
+        db: impl DbExecutor<'_>,
+        create: CreateExtractionCacheEntry,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_extraction_cache"
+            ("hash", "mime", "source_file_id", "encrypted", "pages", "created_at")
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT ("hash", "mime") DO NOTHING
+        "#,
+        )
+        .bind(create.hash)
+        .bind(create.mime)
+        .bind(create.source_file_id)
+        .bind(create.encrypted)
+        .bind(create.pages)
+        .bind(create.created_at)
+        .execute(db)
+        .await
+    }
+
+    /// Find the cache entry for a file `hash` processed as `mime`
+    pub async fn find(
+        db: impl DbExecutor<'_>,
+        hash: &str,
+        mime: &str,
+    ) -> DbResult<Option<ExtractionCacheEntry>> {
+        sqlx::query_as(
+            r#"SELECT * FROM "docbox_extraction_cache" WHERE "hash" = $1 AND "mime" = $2"#,
+        )
+        .bind(hash)
+        .bind(mime)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Delete the cache entry for a file `hash` processed as `mime`
+    pub async fn delete(
+        db: impl DbExecutor<'_>,
+        hash: &str,
+        mime: &str,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(r#"DELETE FROM "docbox_extraction_cache" WHERE "hash" = $1 AND "mime" = $2"#)
+            .bind(hash)
+            .bind(mime)
+            .execute(db)
+            .await
+    }
+}
@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgQueryResult, prelude::FromRow};
 use utoipa::ToSchema;
 use uuid::Uuid;
@@ -14,15 +14,15 @@ use crate::{
     models::{
         document_box::DocumentBoxScopeRawRef,
         shared::{
-            CountResult, DocboxInputPair, FolderPathSegment, TotalSizeResult, WithFullPath,
-            WithFullPathScope,
+            CountResult, DocboxInputPair, FolderPathSegment, PinnedItemRef, TotalSizeResult,
+            WithFullPath, WithFullPathScope,
         },
     },
 };
 
 pub type FileId = Uuid;
 
-#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type, ToSchema)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "docbox_file")]
 pub struct File {
     /// Unique identifier for the file
@@ -89,7 +89,7 @@ pub struct FileWithScope {
 }
 
 /// File with the resolved creator and last modified data
-#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct FileWithExtra {
     #[serde(flatten)]
     pub file: File,
@@ -225,6 +225,44 @@ impl File {
         Ok(self)
     }
 
+    /// Move the file along with all of its child files (i.e attachments
+    /// of an email file) into the folder with `folder_id`, provides the
+    /// moved child files
+    pub async fn move_tree_to_folder(
+        mut self,
+        db: impl DbExecutor<'_>,
+        folder_id: FolderId,
+    ) -> DbResult<(File, Vec<File>)> {
+        let moved: Vec<File> = sqlx::query_as(
+            r#"
+            WITH RECURSIVE "tree" AS (
+                SELECT "id" FROM "docbox_files" WHERE "id" = $2
+                UNION
+                SELECT "child"."id"
+                FROM "docbox_files" "child"
+                INNER JOIN "tree" ON "child"."parent_id" = "tree"."id"
+            )
+            UPDATE "docbox_files"
+            SET "folder_id" = $1
+            WHERE "id" IN (SELECT "id" FROM "tree")
+            RETURNING *
+        "#,
+        )
+        .bind(folder_id)
+        .bind(self.id)
+        .fetch_all(db)
+        .await?;
+
+        self.folder_id = folder_id;
+
+        let children = moved
+            .into_iter()
+            .filter(|file| file.id != self.id)
+            .collect();
+
+        Ok((self, children))
+    }
+
     pub async fn rename(mut self, db: impl DbExecutor<'_>, name: String) -> DbResult<File> {
         sqlx::query(r#"UPDATE "docbox_files" SET "name" = $1 WHERE "id" = $2"#)
             .bind(name.as_str())
@@ -237,19 +275,73 @@ impl File {
         Ok(self)
     }
 
+    /// Get the current version of the file
+    pub async fn version(db: impl DbExecutor<'_>, file_id: FileId) -> DbResult<Option<i64>> {
+        sqlx::query_scalar(r#"SELECT "version" FROM "docbox_files" WHERE "id" = $1"#)
+            .bind(file_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Get the current version of the file locking the file row until
+    /// the end of the current transaction
+    pub async fn lock_version(db: impl DbExecutor<'_>, file_id: FileId) -> DbResult<i64> {
+        sqlx::query_scalar(r#"SELECT "version" FROM "docbox_files" WHERE "id" = $1 FOR UPDATE"#)
+            .bind(file_id)
+            .fetch_one(db)
+            .await
+    }
+
+    /// Increments the version of the file returning the new version
+    pub async fn increment_version(db: impl DbExecutor<'_>, file_id: FileId) -> DbResult<i64> {
+        sqlx::query_scalar(
+            r#"UPDATE "docbox_files" SET "version" = "version" + 1 WHERE "id" = $1 RETURNING "version""#,
+        )
+        .bind(file_id)
+        .fetch_one(db)
+        .await
+    }
+
     /// Updates the pinned state of the file
     pub async fn set_pinned(mut self, db: impl DbExecutor<'_>, pinned: bool) -> DbResult<File> {
-        sqlx::query(r#"UPDATE "docbox_files" SET "pinned" = $1 WHERE "id" = $2"#)
-            .bind(pinned)
-            .bind(self.id)
-            .execute(db)
-            .await?;
+        sqlx::query(
+            r#"
+            UPDATE "docbox_files"
+            SET "pinned" = $1,
+                "pinned_at" = CASE WHEN $1 THEN COALESCE("pinned_at", NOW()) ELSE NULL END
+            WHERE "id" = $2
+            "#,
+        )
+        .bind(pinned)
+        .bind(self.id)
+        .execute(db)
+        .await?;
 
         self.pinned = pinned;
 
         Ok(self)
     }
 
+    /// Finds all the pinned files within the document box `scope`, most
+    /// recently pinned first
+    pub async fn find_pinned(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+    ) -> DbResult<Vec<PinnedItemRef>> {
+        sqlx::query_as(
+            r#"
+            SELECT "file"."id", COALESCE("file"."pinned_at", "file"."created_at") AS "pinned_at"
+            FROM "docbox_files" "file"
+            INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+            WHERE "folder"."document_box" = $1 AND "file"."pinned"
+            ORDER BY "pinned_at" DESC
+            "#,
+        )
+        .bind(scope)
+        .fetch_all(db)
+        .await
+    }
+
     /// Updates the encryption state of the file
     pub async fn set_encrypted(
         mut self,
@@ -374,6 +466,17 @@ impl File {
             .await
     }
 
+    /// Find all files within any of the folders in `folder_ids`
+    pub async fn find_by_folders(
+        db: impl DbExecutor<'_>,
+        folder_ids: &[FolderId],
+    ) -> DbResult<Vec<File>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_files" WHERE "folder_id" = ANY($1)"#)
+            .bind(folder_ids)
+            .fetch_all(db)
+            .await
+    }
+
     /// Deletes the file
     pub async fn delete(&self, db: impl DbExecutor<'_>) -> DbResult<PgQueryResult> {
         sqlx::query(r#"DELETE FROM "docbox_files" WHERE "id" = $1"#)
@@ -498,4 +601,29 @@ impl File {
 
         Ok(size_result.total_size)
     }
+
+    /// Get the storage keys of all files and generated files within a
+    /// specific scope
+    pub async fn storage_keys_within_scope(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+    ) -> DbResult<Vec<String>> {
+        sqlx::query_scalar(
+            r#"
+            SELECT "file"."file_key"
+            FROM "docbox_files" "file"
+            INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+            WHERE "folder"."document_box" = $1
+            UNION
+            SELECT "generated"."file_key"
+            FROM "docbox_generated_files" "generated"
+            INNER JOIN "docbox_files" "file" ON "generated"."file_id" = "file"."id"
+            INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+            WHERE "folder"."document_box" = $1
+        "#,
+        )
+        .bind(scope)
+        .fetch_all(db)
+        .await
+    }
 }
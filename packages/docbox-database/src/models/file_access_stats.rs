@@ -0,0 +1,149 @@
+use super::file::{File, FileId};
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+use utoipa::ToSchema;
+
+/// Download and preview statistics for a file
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct FileAccessStats {
+    /// ID of the file the statistics are for
+    #[serde(skip)]
+    pub file_id: FileId,
+    /// Number of times the file was downloaded
+    pub download_count: i64,
+    /// Number of times the file was previewed
+    pub preview_count: i64,
+    /// When the file was last accessed
+    pub last_accessed_at: DateTime<Utc>,
+}
+
+/// Accumulated accesses for a file that should be added
+/// to its stored statistics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileAccessIncrement {
+    /// ID of the file that was accessed
+    pub file_id: FileId,
+    /// Number of downloads to add
+    pub downloads: i64,
+    /// Number of previews to add
+    pub previews: i64,
+    /// Time of the latest access
+    pub accessed_at: DateTime<Utc>,
+}
+
+/// Ordering for the file access report
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FileAccessReportOrder {
+    /// Most accessed files first
+    #[default]
+    MostAccessed,
+    /// Least accessed files first, files that have never been
+    /// accessed are included
+    LeastAccessed,
+}
+
+/// File within the file access report
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct FileAccessReportItem {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub file: File,
+    /// Scope of the document box the file is within
+    pub scope: String,
+    /// Number of times the file was downloaded
+    pub download_count: i64,
+    /// Number of times the file was previewed
+    pub preview_count: i64,
+    /// When the file was last accessed, [None] if the
+    /// file has never been accessed
+    pub last_accessed_at: Option<DateTime<Utc>>,
+}
+
+impl FileAccessStats {
+    /// Add the accesses from `increment` to the statistics for the file
+    pub async fn increment(
+        db: impl DbExecutor<'_>,
+        increment: &FileAccessIncrement,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_files_access_stats" (
+                "file_id",
+                "download_count",
+                "preview_count",
+                "last_accessed_at"
+            )
+            -- File may have been deleted before the accesses were stored
+            SELECT $1, $2, $3, $4
+            WHERE EXISTS (SELECT 1 FROM "docbox_files" WHERE "id" = $1)
+            ON CONFLICT ("file_id") DO UPDATE
+            SET
+                "download_count" = "docbox_files_access_stats"."download_count" + EXCLUDED."download_count",
+                "preview_count" = "docbox_files_access_stats"."preview_count" + EXCLUDED."preview_count",
+                "last_accessed_at" = GREATEST(
+                    "docbox_files_access_stats"."last_accessed_at",
+                    EXCLUDED."last_accessed_at"
+                )
+        "#,
+        )
+        .bind(increment.file_id)
+        .bind(increment.downloads)
+        .bind(increment.previews)
+        .bind(increment.accessed_at)
+        .execute(db)
+        .await
+    }
+
+    /// Find the access statistics for a file
+    pub async fn find(
+        db: impl DbExecutor<'_>,
+        file_id: FileId,
+    ) -> DbResult<Option<FileAccessStats>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_files_access_stats" WHERE "file_id" = $1"#)
+            .bind(file_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Query a page of files ordered by how often they are accessed
+    pub async fn report(
+        db: impl DbExecutor<'_>,
+        order: FileAccessReportOrder,
+        offset: u64,
+        limit: u64,
+    ) -> DbResult<Vec<FileAccessReportItem>> {
+        let order_by = match order {
+            FileAccessReportOrder::MostAccessed => {
+                r#""total_count" DESC, "last_accessed_at" DESC NULLS LAST"#
+            }
+            FileAccessReportOrder::LeastAccessed => {
+                r#""total_count" ASC, "last_accessed_at" ASC NULLS FIRST"#
+            }
+        };
+
+        sqlx::query_as(&format!(
+            r#"
+            SELECT
+                "file".*,
+                "folder"."document_box" AS "scope",
+                COALESCE("stats"."download_count", 0) AS "download_count",
+                COALESCE("stats"."preview_count", 0) AS "preview_count",
+                "stats"."last_accessed_at" AS "last_accessed_at",
+                COALESCE("stats"."download_count", 0) + COALESCE("stats"."preview_count", 0) AS "total_count"
+            FROM "docbox_files" AS "file"
+            INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+            LEFT JOIN "docbox_files_access_stats" "stats" ON "stats"."file_id" = "file"."id"
+            ORDER BY {order_by}, "file"."created_at" ASC
+            OFFSET $1
+            LIMIT $2
+        "#
+        ))
+        .bind(offset as i64)
+        .bind(limit as i64)
+        .fetch_all(db)
+        .await
+    }
+}
@@ -0,0 +1,131 @@
+use super::{file::FileId, user::UserId};
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use std::str::FromStr;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub type FileEmailId = Uuid;
+
+/// How a file was delivered by email
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    strum::EnumString,
+    strum::Display,
+    Deserialize,
+    Serialize,
+    ToSchema,
+    PartialEq,
+    Eq,
+)]
+pub enum FileEmailDelivery {
+    /// File was attached to the email
+    Attachment,
+    /// Email contained a presigned link to download the file
+    Link,
+}
+
+impl TryFrom<String> for FileEmailDelivery {
+    type Error = strum::ParseError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        FileEmailDelivery::from_str(&value)
+    }
+}
+
+/// Record of a file that was sent by email
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema, PartialEq, Eq)]
+pub struct FileEmail {
+    /// Unique ID of the email record
+    #[schema(value_type = Uuid)]
+    pub id: FileEmailId,
+    /// ID of the file that was sent
+    #[schema(value_type = Uuid)]
+    pub file_id: FileId,
+    /// Addresses the email was sent to
+    pub recipients: Vec<String>,
+    /// Subject of the sent email
+    pub subject: String,
+    /// How the file was delivered
+    #[sqlx(try_from = "String")]
+    pub delivery: FileEmailDelivery,
+    /// When the link in the email expires if the file was delivered as a link
+    pub link_expires_at: Option<DateTime<Utc>>,
+    /// ID of the user that sent the email
+    pub sent_by: Option<UserId>,
+    /// When the email was sent
+    pub sent_at: DateTime<Utc>,
+}
+
+pub struct CreateFileEmail {
+    pub file_id: FileId,
+    pub recipients: Vec<String>,
+    pub subject: String,
+    pub delivery: FileEmailDelivery,
+    pub link_expires_at: Option<DateTime<Utc>>,
+    pub sent_by: Option<UserId>,
+}
+
+impl FileEmail {
+    /// Record a file that was sent by email
+    pub async fn create(db: impl DbExecutor<'_>, create: CreateFileEmail) -> DbResult<FileEmail> {
+        let email = FileEmail {
+            id: Uuid::new_v4(),
+            file_id: create.file_id,
+            recipients: create.recipients,
+            subject: create.subject,
+            delivery: create.delivery,
+            link_expires_at: create.link_expires_at,
+            sent_by: create.sent_by,
+            sent_at: Utc::now(),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_file_emails" (
+                "id",
+                "file_id",
+                "recipients",
+                "subject",
+                "delivery",
+                "link_expires_at",
+                "sent_by",
+                "sent_at"
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        )
+        .bind(email.id)
+        .bind(email.file_id)
+        .bind(&email.recipients)
+        .bind(email.subject.as_str())
+        .bind(email.delivery.to_string())
+        .bind(email.link_expires_at)
+        .bind(email.sent_by.as_ref())
+        .bind(email.sent_at)
+        .execute(db)
+        .await?;
+
+        Ok(email)
+    }
+
+    /// Find all the emails a file was sent in, most recent first
+    pub async fn find_by_file(
+        db: impl DbExecutor<'_>,
+        file_id: FileId,
+    ) -> DbResult<Vec<FileEmail>> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM "docbox_file_emails"
+            WHERE "file_id" = $1
+            ORDER BY "sent_at" DESC
+        "#,
+        )
+        .bind(file_id)
+        .fetch_all(db)
+        .await
+    }
+}
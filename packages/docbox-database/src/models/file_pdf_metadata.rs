@@ -0,0 +1,111 @@
+use super::file::FileId;
+use crate::{DbExecutor, DbResult};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgQueryResult, prelude::FromRow, types::Json};
+use utoipa::ToSchema;
+
+/// Metadata extracted from a PDF (Or a file converted to PDF) during
+/// processing, allows viewers to build navigation without first
+/// downloading the PDF
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct PdfMetadata {
+    /// Total number of pages in the PDF
+    pub page_count: i32,
+    /// Dimensions of each page in page order
+    pub pages: Vec<PdfPageSize>,
+    /// Bookmarks / outline of the document
+    pub outline: Vec<PdfOutlineItem>,
+}
+
+/// Dimensions of a single PDF page
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct PdfPageSize {
+    /// Width of the page in points
+    pub width: f64,
+    /// Height of the page in points
+    pub height: f64,
+    /// Rotation of the page in degrees
+    pub rotation: i32,
+}
+
+/// Entry within the outline (bookmarks) of a PDF
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct PdfOutlineItem {
+    /// Title of the outline entry
+    pub title: String,
+    /// Page number (Starting at 1) the entry links to, not present
+    /// when the entry does not link to a page
+    pub page: Option<i32>,
+    /// Nested outline entries
+    #[schema(no_recursion)]
+    pub children: Vec<PdfOutlineItem>,
+}
+
+/// PDF metadata stored for a file
+#[derive(Debug, Clone, FromRow)]
+pub struct FilePdfMetadata {
+    /// ID of the file the metadata is for
+    pub file_id: FileId,
+    /// Total number of pages in the PDF
+    pub page_count: i32,
+    /// Dimensions of each page in page order
+    pub pages: Json<Vec<PdfPageSize>>,
+    /// Bookmarks / outline of the document
+    pub outline: Json<Vec<PdfOutlineItem>>,
+}
+
+impl From<FilePdfMetadata> for PdfMetadata {
+    fn from(value: FilePdfMetadata) -> Self {
+        PdfMetadata {
+            page_count: value.page_count,
+            pages: value.pages.0,
+            outline: value.outline.0,
+        }
+    }
+}
+
+impl FilePdfMetadata {
+    /// Store the PDF `metadata` for a file, replacing any previous metadata
+    pub async fn set(
+        db: impl DbExecutor<'_>,
+        file_id: FileId,
+        metadata: &PdfMetadata,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_files_pdf_metadata" ("file_id", "page_count", "pages", "outline")
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT ("file_id") DO UPDATE
+            SET
+                "page_count" = EXCLUDED."page_count",
+                "pages" = EXCLUDED."pages",
+                "outline" = EXCLUDED."outline"
+        "#,
+        )
+        .bind(file_id)
+        .bind(metadata.page_count)
+        .bind(Json(&metadata.pages))
+        .bind(Json(&metadata.outline))
+        .execute(db)
+        .await
+    }
+
+    /// Find the PDF metadata for a file
+    pub async fn find(
+        db: impl DbExecutor<'_>,
+        file_id: FileId,
+    ) -> DbResult<Option<FilePdfMetadata>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_files_pdf_metadata" WHERE "file_id" = $1"#)
+            .bind(file_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Delete the PDF metadata for a file
+    pub async fn delete(db: impl DbExecutor<'_>, file_id: FileId) -> DbResult<PgQueryResult> {
+        sqlx::query(r#"DELETE FROM "docbox_files_pdf_metadata" WHERE "file_id" = $1"#)
+            .bind(file_id)
+            .execute(db)
+            .await
+    }
+}
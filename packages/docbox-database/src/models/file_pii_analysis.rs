@@ -0,0 +1,184 @@
+use super::{document_box::DocumentBoxScopeRawRef, file::FileId, folder::FolderId};
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgQueryResult, prelude::FromRow, types::Json};
+use utoipa::ToSchema;
+
+/// Kind of personally identifiable information detected within a file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq, Hash)]
+pub enum PiiKind {
+    /// Email address
+    EmailAddress,
+    /// Phone number
+    PhoneNumber,
+    /// Payment card number (Validated using the Luhn checksum)
+    CreditCardNumber,
+    /// New Zealand IRD number (Validated using the IRD checksum)
+    IrdNumber,
+}
+
+/// Occurrence of a unique PII value within a page of a file
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct PiiFinding {
+    /// Kind of PII that was found
+    pub kind: PiiKind,
+    /// Page the value was found on (Starting at 0)
+    pub page: u64,
+    /// Masked form of the value, the full value is not stored
+    pub value: String,
+    /// Number of times the value occurs on the page
+    pub count: u32,
+}
+
+/// Result of analyzing the extracted text of a file for PII
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct PiiAnalysis {
+    /// Whether any PII was found within the file
+    pub contains_pii: bool,
+    /// Findings within the file
+    pub findings: Vec<PiiFinding>,
+}
+
+/// PII analysis stored for a file
+#[derive(Debug, Clone, FromRow)]
+pub struct FilePiiAnalysis {
+    /// ID of the file the analysis is for
+    pub file_id: FileId,
+    /// Whether any PII was found within the file
+    pub contains_pii: bool,
+    /// Findings within the file
+    pub findings: Json<Vec<PiiFinding>>,
+    /// When the file was analyzed
+    pub analyzed_at: DateTime<Utc>,
+}
+
+impl From<FilePiiAnalysis> for PiiAnalysis {
+    fn from(value: FilePiiAnalysis) -> Self {
+        PiiAnalysis {
+            contains_pii: value.contains_pii,
+            findings: value.findings.0,
+        }
+    }
+}
+
+/// PII analysis for a file containing PII along with details about the file
+#[derive(Debug, Clone, FromRow)]
+pub struct FilePiiAnalysisWithFile {
+    /// ID of the file
+    pub file_id: FileId,
+    /// Name of the file
+    pub name: String,
+    /// Mime type of the file
+    pub mime: String,
+    /// ID of the folder the file is within
+    pub folder_id: FolderId,
+    /// Findings within the file
+    pub findings: Json<Vec<PiiFinding>>,
+    /// When the file was analyzed
+    pub analyzed_at: DateTime<Utc>,
+}
+
+/// Counts of analyzed files within a scope
+#[derive(Debug, Clone, Copy, FromRow)]
+pub struct PiiAnalysisCounts {
+    /// Total number of files that have been analyzed
+    pub analyzed_files: i64,
+    /// Total number of analyzed files that contain PII
+    pub files_with_pii: i64,
+}
+
+impl FilePiiAnalysis {
+    /// Store the PII `analysis` for a file, replacing any previous analysis
+    pub async fn set(
+        db: impl DbExecutor<'_>,
+        file_id: FileId,
+        analysis: &PiiAnalysis,
+        analyzed_at: DateTime<Utc>,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_files_pii_analysis" ("file_id", "contains_pii", "findings", "analyzed_at")
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT ("file_id") DO UPDATE
+            SET
+                "contains_pii" = EXCLUDED."contains_pii",
+                "findings" = EXCLUDED."findings",
+                "analyzed_at" = EXCLUDED."analyzed_at"
+        "#,
+        )
+        .bind(file_id)
+        .bind(analysis.contains_pii)
+        .bind(Json(&analysis.findings))
+        .bind(analyzed_at)
+        .execute(db)
+        .await
+    }
+
+    /// Find the PII analysis for a file
+    pub async fn find(
+        db: impl DbExecutor<'_>,
+        file_id: FileId,
+    ) -> DbResult<Option<FilePiiAnalysis>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_files_pii_analysis" WHERE "file_id" = $1"#)
+            .bind(file_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Delete the PII analysis for a file
+    pub async fn delete(db: impl DbExecutor<'_>, file_id: FileId) -> DbResult<PgQueryResult> {
+        sqlx::query(r#"DELETE FROM "docbox_files_pii_analysis" WHERE "file_id" = $1"#)
+            .bind(file_id)
+            .execute(db)
+            .await
+    }
+
+    /// Find all files within the `scope` that were found to contain PII,
+    /// most recently analyzed first
+    pub async fn find_with_pii_within_scope(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+    ) -> DbResult<Vec<FilePiiAnalysisWithFile>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                "file"."id" AS "file_id",
+                "file"."name",
+                "file"."mime",
+                "file"."folder_id",
+                "analysis"."findings",
+                "analysis"."analyzed_at"
+            FROM "docbox_files_pii_analysis" "analysis"
+            INNER JOIN "docbox_files" "file" ON "analysis"."file_id" = "file"."id"
+            INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+            WHERE "folder"."document_box" = $1 AND "analysis"."contains_pii"
+            ORDER BY "analysis"."analyzed_at" DESC
+        "#,
+        )
+        .bind(scope)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Count the analyzed files within the `scope`
+    pub async fn counts_within_scope(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+    ) -> DbResult<PiiAnalysisCounts> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(*) AS "analyzed_files",
+                COUNT(*) FILTER (WHERE "analysis"."contains_pii") AS "files_with_pii"
+            FROM "docbox_files_pii_analysis" "analysis"
+            INNER JOIN "docbox_files" "file" ON "analysis"."file_id" = "file"."id"
+            INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+            WHERE "folder"."document_box" = $1
+        "#,
+        )
+        .bind(scope)
+        .fetch_one(db)
+        .await
+    }
+}
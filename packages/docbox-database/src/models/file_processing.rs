@@ -0,0 +1,178 @@
+use super::file::{FileId, FileWithScope};
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+use utoipa::ToSchema;
+
+/// Version of the processing pipeline that was used to process a file
+///
+/// Files without a processing record were processed before version
+/// tracking was introduced and are treated as version 0
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct FileProcessing {
+    /// ID of the file that was processed
+    pub file_id: FileId,
+    /// Processing pipeline version used
+    pub version: i32,
+    /// When the file was last processed
+    pub processed_at: DateTime<Utc>,
+    /// Time taken by each processing stage
+    #[sqlx(flatten)]
+    pub timings: ProcessingTimings,
+}
+
+/// Duration in milliseconds of each stage of processing a file, stages
+/// that did not run for the file are [None]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, FromRow, Serialize)]
+pub struct ProcessingTimings {
+    /// Converting the file to a PDF
+    pub conversion_ms: Option<i64>,
+    /// Extracting the text content
+    pub text_extraction_ms: Option<i64>,
+    /// Generating thumbnails
+    pub thumbnail_ms: Option<i64>,
+    /// Storing the file in the search index
+    pub indexing_ms: Option<i64>,
+    /// Processing the file from start to finish
+    pub total_ms: Option<i64>,
+}
+
+/// Aggregate durations for a processing stage
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct ProcessingStageStats {
+    /// Name of the stage (conversion, text_extraction, thumbnail, indexing or total)
+    pub stage: String,
+    /// Number of files the stage ran for
+    pub count: i64,
+    /// Median duration in milliseconds
+    pub p50_ms: f64,
+    /// 95th percentile duration in milliseconds
+    pub p95_ms: f64,
+    /// 99th percentile duration in milliseconds
+    pub p99_ms: f64,
+    /// Longest duration in milliseconds
+    pub max_ms: f64,
+}
+
+impl FileProcessing {
+    /// Stamp the processing `version` and stage `timings` used for a file,
+    /// replacing any previous version and timings
+    pub async fn set(
+        db: impl DbExecutor<'_>,
+        file_id: FileId,
+        version: i32,
+        processed_at: DateTime<Utc>,
+        timings: &ProcessingTimings,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_files_processing" (
+                "file_id",
+                "version",
+                "processed_at",
+                "conversion_ms",
+                "text_extraction_ms",
+                "thumbnail_ms",
+                "indexing_ms",
+                "total_ms"
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT ("file_id") DO UPDATE
+            SET
+                "version" = EXCLUDED."version",
+                "processed_at" = EXCLUDED."processed_at",
+                "conversion_ms" = EXCLUDED."conversion_ms",
+                "text_extraction_ms" = EXCLUDED."text_extraction_ms",
+                "thumbnail_ms" = EXCLUDED."thumbnail_ms",
+                "indexing_ms" = EXCLUDED."indexing_ms",
+                "total_ms" = EXCLUDED."total_ms"
+        "#,
+        )
+        .bind(file_id)
+        .bind(version)
+        .bind(processed_at)
+        .bind(timings.conversion_ms)
+        .bind(timings.text_extraction_ms)
+        .bind(timings.thumbnail_ms)
+        .bind(timings.indexing_ms)
+        .bind(timings.total_ms)
+        .execute(db)
+        .await
+    }
+
+    /// Get the aggregate durations of each processing stage for files
+    /// processed since `since`, stages that have not run are omitted
+    pub async fn stage_stats(
+        db: impl DbExecutor<'_>,
+        since: DateTime<Utc>,
+    ) -> DbResult<Vec<ProcessingStageStats>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                "stages"."stage",
+                COUNT(*) AS "count",
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY "stages"."duration_ms") AS "p50_ms",
+                percentile_cont(0.95) WITHIN GROUP (ORDER BY "stages"."duration_ms") AS "p95_ms",
+                percentile_cont(0.99) WITHIN GROUP (ORDER BY "stages"."duration_ms") AS "p99_ms",
+                MAX("stages"."duration_ms")::DOUBLE PRECISION AS "max_ms"
+            FROM "docbox_files_processing" AS "processing"
+            CROSS JOIN LATERAL (
+                VALUES
+                    ('conversion', "processing"."conversion_ms"),
+                    ('text_extraction', "processing"."text_extraction_ms"),
+                    ('thumbnail', "processing"."thumbnail_ms"),
+                    ('indexing', "processing"."indexing_ms"),
+                    ('total', "processing"."total_ms")
+            ) AS "stages" ("stage", "duration_ms")
+            WHERE "processing"."processed_at" >= $1
+                AND "stages"."duration_ms" IS NOT NULL
+            GROUP BY "stages"."stage"
+            ORDER BY "stages"."stage"
+        "#,
+        )
+        .bind(since)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Find the processing record for a file
+    pub async fn find(
+        db: impl DbExecutor<'_>,
+        file_id: FileId,
+    ) -> DbResult<Option<FileProcessing>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_files_processing" WHERE "file_id" = $1"#)
+            .bind(file_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Find files that were processed with a pipeline version older than
+    /// `version` (Or were never stamped with a version)
+    pub async fn find_outdated_files(
+        db: impl DbExecutor<'_>,
+        version: i32,
+        offset: u64,
+        page_size: u64,
+    ) -> DbResult<Vec<FileWithScope>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                "file".*,
+                "folder"."document_box" AS "scope"
+            FROM "docbox_files" AS "file"
+            INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+            LEFT JOIN "docbox_files_processing" "processing" ON "processing"."file_id" = "file"."id"
+            WHERE "processing"."version" IS NULL OR "processing"."version" < $1
+            ORDER BY "file"."created_at" ASC
+            OFFSET $2
+            LIMIT $3
+        "#,
+        )
+        .bind(version)
+        .bind(offset as i64)
+        .bind(page_size as i64)
+        .fetch_all(db)
+        .await
+    }
+}
@@ -0,0 +1,76 @@
+use super::file::FileId;
+use crate::{DbExecutor, DbResult};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+use utoipa::ToSchema;
+
+/// Statistics about the text extracted from a file during processing
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema, FromRow, PartialEq, Eq)]
+pub struct TextStats {
+    /// Number of words within the text
+    pub word_count: i64,
+    /// Number of characters within the text (Excluding whitespace)
+    pub character_count: i64,
+    /// Estimated time to read the text in seconds
+    pub reading_time_seconds: i64,
+}
+
+/// Text statistics stored for a file
+#[derive(Debug, Clone, FromRow)]
+pub struct FileTextStats {
+    /// ID of the file the statistics are for
+    pub file_id: FileId,
+    /// The statistics themselves
+    #[sqlx(flatten)]
+    pub stats: TextStats,
+}
+
+impl From<FileTextStats> for TextStats {
+    fn from(value: FileTextStats) -> Self {
+        value.stats
+    }
+}
+
+impl FileTextStats {
+    /// Store the text `stats` for a file, replacing any previous statistics
+    pub async fn set(
+        db: impl DbExecutor<'_>,
+        file_id: FileId,
+        stats: &TextStats,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_files_text_stats"
+                ("file_id", "word_count", "character_count", "reading_time_seconds")
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT ("file_id") DO UPDATE
+            SET
+                "word_count" = EXCLUDED."word_count",
+                "character_count" = EXCLUDED."character_count",
+                "reading_time_seconds" = EXCLUDED."reading_time_seconds"
+        "#,
+        )
+        .bind(file_id)
+        .bind(stats.word_count)
+        .bind(stats.character_count)
+        .bind(stats.reading_time_seconds)
+        .execute(db)
+        .await
+    }
+
+    /// Find the text statistics for a file
+    pub async fn find(db: impl DbExecutor<'_>, file_id: FileId) -> DbResult<Option<FileTextStats>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_files_text_stats" WHERE "file_id" = $1"#)
+            .bind(file_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Delete the text statistics for a file
+    pub async fn delete(db: impl DbExecutor<'_>, file_id: FileId) -> DbResult<PgQueryResult> {
+        sqlx::query(r#"DELETE FROM "docbox_files_text_stats" WHERE "file_id" = $1"#)
+            .bind(file_id)
+            .execute(db)
+            .await
+    }
+}
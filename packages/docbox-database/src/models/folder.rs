@@ -1,15 +1,17 @@
 use super::{
-    document_box::DocumentBoxScopeRaw,
+    document_box::{DocumentBoxScopeRaw, DocumentBoxScopeRawRef},
     file::{File, FileWithExtra},
     link::{Link, LinkWithExtra},
     user::{User, UserId},
 };
 use crate::{
     DbExecutor, DbPool, DbResult,
-    models::shared::{CountResult, DocboxInputPair, FolderPathSegment, WithFullPath},
+    models::shared::{
+        CountResult, DocboxInputPair, FolderPathSegment, PinnedItemRef, WithFullPath,
+    },
 };
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgQueryResult, prelude::FromRow};
 use tokio::try_join;
 use utoipa::ToSchema;
@@ -46,7 +48,7 @@ impl ResolvedFolder {
 
 /// Folder with all the children resolved, children also
 /// resolve the user and last modified data
-#[derive(Debug, Default, Serialize, ToSchema)]
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
 pub struct ResolvedFolderWithExtra {
     /// Path to the resolved folder
     pub path: Vec<FolderPathSegment>,
@@ -79,7 +81,7 @@ impl ResolvedFolderWithExtra {
     }
 }
 
-#[derive(Debug, Clone, Serialize, ToSchema, FromRow, sqlx::Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow, sqlx::Type)]
 #[sqlx(type_name = "docbox_folder")]
 pub struct Folder {
     /// Unique identifier for the folder
@@ -123,7 +125,7 @@ impl PartialEq for Folder {
     }
 }
 
-#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct FolderWithExtra {
     #[serde(flatten)]
     pub folder: Folder,
@@ -174,9 +176,16 @@ impl Folder {
             r#"
             INSERT INTO "docbox_folders" (
                 "id", "name", "document_box",  "folder_id",
-                "created_by", "created_at"
+                "created_by", "created_at", "path"
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6,
+                -- Materialized path extends the path of the parent folder
+                COALESCE(
+                    (SELECT "path" || "id" FROM "docbox_folders" WHERE "id" = $4),
+                    '{}'
+                )
             )
-            VALUES ($1, $2, $3, $4, $5, $6)
         "#,
         )
         .bind(folder.id)
@@ -211,6 +220,14 @@ impl Folder {
         Ok(results.into_iter().map(|value| value.id).collect())
     }
 
+    /// Find the folder and all of its descendant folders
+    pub async fn tree_all_folders(&self, db: impl DbExecutor<'_>) -> DbResult<Vec<Folder>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_folders" WHERE "id" = $1 OR "path" @> ARRAY[$1]"#)
+            .bind(self.id)
+            .fetch_all(db)
+            .await
+    }
+
     /// Uses a recursive query to count all the children in the provided
     /// folder
     pub async fn count_children(
@@ -250,17 +267,78 @@ impl Folder {
         // Should never try moving a root folder
         debug_assert!(self.folder_id.is_some());
 
-        sqlx::query(r#"UPDATE "docbox_folders" SET "folder_id" = $1 WHERE "id" = $2"#)
-            .bind(folder_id)
-            .bind(self.id)
-            .execute(db)
-            .await?;
+        // Replace the path prefix of the folder and all its descendants
+        sqlx::query(
+            r#"
+            WITH
+                "previous" AS (SELECT "path" FROM "docbox_folders" WHERE "id" = $2),
+                "target" AS (SELECT "path" || "id" AS "path" FROM "docbox_folders" WHERE "id" = $1)
+            UPDATE "docbox_folders"
+            SET
+                "folder_id" = CASE
+                    WHEN "docbox_folders"."id" = $2 THEN $1
+                    ELSE "docbox_folders"."folder_id"
+                END,
+                "path" = "target"."path"
+                    || "docbox_folders"."path"[cardinality("previous"."path") + 1:]
+            FROM "previous", "target"
+            WHERE "docbox_folders"."id" = $2 OR "docbox_folders"."path" @> ARRAY[$2]
+        "#,
+        )
+        .bind(folder_id)
+        .bind(self.id)
+        .execute(db)
+        .await?;
 
         self.folder_id = Some(folder_id);
 
         Ok(self)
     }
 
+    /// Move the folder into the `target` folder within another document box,
+    /// the folder and all its descendant folders are moved into the document
+    /// box of the target folder
+    pub async fn move_to_scope(
+        mut self,
+        db: impl DbExecutor<'_>,
+        target: &Folder,
+    ) -> DbResult<Folder> {
+        // Should never try moving a root folder
+        debug_assert!(self.folder_id.is_some());
+
+        sqlx::query(
+            r#"
+            WITH
+                "previous" AS (SELECT "path" FROM "docbox_folders" WHERE "id" = $2),
+                "target" AS (
+                    SELECT "path" || "id" AS "path", "document_box"
+                    FROM "docbox_folders"
+                    WHERE "id" = $1
+                )
+            UPDATE "docbox_folders"
+            SET
+                "folder_id" = CASE
+                    WHEN "docbox_folders"."id" = $2 THEN $1
+                    ELSE "docbox_folders"."folder_id"
+                END,
+                "document_box" = "target"."document_box",
+                "path" = "target"."path"
+                    || "docbox_folders"."path"[cardinality("previous"."path") + 1:]
+            FROM "previous", "target"
+            WHERE "docbox_folders"."id" = $2 OR "docbox_folders"."path" @> ARRAY[$2]
+        "#,
+        )
+        .bind(target.id)
+        .bind(self.id)
+        .execute(db)
+        .await?;
+
+        self.folder_id = Some(target.id);
+        self.document_box = target.document_box.clone();
+
+        Ok(self)
+    }
+
     pub async fn rename(mut self, db: impl DbExecutor<'_>, name: String) -> DbResult<Folder> {
         sqlx::query(r#"UPDATE "docbox_folders" SET "name" = $1 WHERE "id" = $2"#)
             .bind(name.as_str())
@@ -273,18 +351,71 @@ impl Folder {
         Ok(self)
     }
 
+    /// Get the current version of the folder
+    pub async fn version(db: impl DbExecutor<'_>, folder_id: FolderId) -> DbResult<Option<i64>> {
+        sqlx::query_scalar(r#"SELECT "version" FROM "docbox_folders" WHERE "id" = $1"#)
+            .bind(folder_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Get the current version of the folder locking the folder row until
+    /// the end of the current transaction
+    pub async fn lock_version(db: impl DbExecutor<'_>, folder_id: FolderId) -> DbResult<i64> {
+        sqlx::query_scalar(r#"SELECT "version" FROM "docbox_folders" WHERE "id" = $1 FOR UPDATE"#)
+            .bind(folder_id)
+            .fetch_one(db)
+            .await
+    }
+
+    /// Increments the version of the folder returning the new version
+    pub async fn increment_version(db: impl DbExecutor<'_>, folder_id: FolderId) -> DbResult<i64> {
+        sqlx::query_scalar(
+            r#"UPDATE "docbox_folders" SET "version" = "version" + 1 WHERE "id" = $1 RETURNING "version""#,
+        )
+        .bind(folder_id)
+        .fetch_one(db)
+        .await
+    }
+
     pub async fn set_pinned(mut self, db: impl DbExecutor<'_>, pinned: bool) -> DbResult<Folder> {
-        sqlx::query(r#"UPDATE "docbox_folders" SET "pinned" = $1 WHERE "id" = $2"#)
-            .bind(pinned)
-            .bind(self.id)
-            .execute(db)
-            .await?;
+        sqlx::query(
+            r#"
+            UPDATE "docbox_folders"
+            SET "pinned" = $1,
+                "pinned_at" = CASE WHEN $1 THEN COALESCE("pinned_at", NOW()) ELSE NULL END
+            WHERE "id" = $2
+            "#,
+        )
+        .bind(pinned)
+        .bind(self.id)
+        .execute(db)
+        .await?;
 
         self.pinned = pinned;
 
         Ok(self)
     }
 
+    /// Finds all the pinned folders within the document box `scope`, most
+    /// recently pinned first
+    pub async fn find_pinned(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+    ) -> DbResult<Vec<PinnedItemRef>> {
+        sqlx::query_as(
+            r#"
+            SELECT "id", COALESCE("pinned_at", "created_at") AS "pinned_at"
+            FROM "docbox_folders"
+            WHERE "document_box" = $1 AND "pinned"
+            ORDER BY "pinned_at" DESC
+            "#,
+        )
+        .bind(scope)
+        .fetch_all(db)
+        .await
+    }
+
     pub async fn find_by_id(
         db: impl DbExecutor<'_>,
         scope: &DocumentBoxScopeRaw,
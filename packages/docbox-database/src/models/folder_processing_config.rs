@@ -0,0 +1,95 @@
+use super::folder::FolderId;
+use crate::{DbExecutor, DbResult};
+use serde::Serialize;
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+
+/// Processing configuration override attached to a folder, applies to
+/// files uploaded into the folder and any of its descendant folders
+///
+/// The config is stored as raw JSON as the processing config structure
+/// is owned by the processing layer
+#[derive(Debug, Clone, FromRow, Serialize, PartialEq)]
+pub struct FolderProcessingConfig {
+    /// ID of the folder the config is attached to
+    pub folder_id: FolderId,
+    /// Processing config JSON
+    pub config: serde_json::Value,
+}
+
+impl FolderProcessingConfig {
+    /// Set the processing `config` for a folder, replacing any existing config
+    pub async fn set(
+        db: impl DbExecutor<'_>,
+        folder_id: FolderId,
+        config: serde_json::Value,
+    ) -> DbResult<FolderProcessingConfig> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_folder_processing_configs" ("folder_id", "config")
+            VALUES ($1, $2)
+            ON CONFLICT ("folder_id") DO UPDATE
+            SET "config" = EXCLUDED."config"
+        "#,
+        )
+        .bind(folder_id)
+        .bind(&config)
+        .execute(db)
+        .await?;
+
+        Ok(FolderProcessingConfig { folder_id, config })
+    }
+
+    /// Find the processing config attached directly to a folder
+    pub async fn find(
+        db: impl DbExecutor<'_>,
+        folder_id: FolderId,
+    ) -> DbResult<Option<FolderProcessingConfig>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_folder_processing_configs" WHERE "folder_id" = $1"#)
+            .bind(folder_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Find the processing configs attached to a folder and all of its
+    /// parent folders, ordered from the root folder down to the folder
+    /// itself
+    pub async fn find_for_folder_path(
+        db: impl DbExecutor<'_>,
+        folder_id: FolderId,
+    ) -> DbResult<Vec<FolderProcessingConfig>> {
+        sqlx::query_as(
+            r#"
+            WITH RECURSIVE "folder_hierarchy" AS (
+                SELECT "id", "folder_id", 0 AS "depth"
+                FROM "docbox_folders"
+                WHERE "docbox_folders"."id" = $1
+
+                UNION ALL
+
+                SELECT
+                    "folder"."id",
+                    "folder"."folder_id",
+                    "fh"."depth" + 1 AS "depth"
+                FROM "docbox_folders" AS "folder"
+                INNER JOIN "folder_hierarchy" "fh" ON "folder"."id" = "fh"."folder_id"
+            )
+            SELECT "config".*
+            FROM "folder_hierarchy" "fh"
+            INNER JOIN "docbox_folder_processing_configs" "config"
+                ON "config"."folder_id" = "fh"."id"
+            ORDER BY "fh"."depth" DESC
+        "#,
+        )
+        .bind(folder_id)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Remove the processing config attached to a folder
+    pub async fn delete(db: impl DbExecutor<'_>, folder_id: FolderId) -> DbResult<PgQueryResult> {
+        sqlx::query(r#"DELETE FROM "docbox_folder_processing_configs" WHERE "folder_id" = $1"#)
+            .bind(folder_id)
+            .execute(db)
+            .await
+    }
+}
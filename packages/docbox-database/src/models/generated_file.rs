@@ -6,7 +6,7 @@ use sqlx::{postgres::PgQueryResult, prelude::FromRow};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use super::{document_box::DocumentBoxScopeRaw, file::FileId};
+use super::{document_box::DocumentBoxScopeRaw, file::FileId, shared::CountResult};
 use crate::{DbExecutor, DbResult};
 
 pub type GeneratedFileId = Uuid;
@@ -39,6 +39,19 @@ pub enum GeneratedFileType {
     /// JSON encoded metadata for the file
     /// (Used by emails to store the email metadata in an accessible ways)
     Metadata,
+    /// JSON encoded per-page word bounding boxes for PDF compatible files
+    /// (Used to overlay search match highlights on rendered pages)
+    WordBoundingBoxes,
+    /// Short summary of the text content generated by a language model
+    Summary,
+    /// Preview image of a single page, generated for the first few pages
+    /// of PDF compatible files (See [GeneratedFile::page] for the page)
+    PagePreview,
+    /// JSON encoded tables extracted from PDF compatible files and spreadsheets
+    Tables,
+    /// CSV encoded content of a single extracted table
+    /// (See [GeneratedFile::page] for the table number)
+    TableCsv,
 }
 
 impl TryFrom<String> for GeneratedFileType {
@@ -49,7 +62,7 @@ impl TryFrom<String> for GeneratedFileType {
 }
 
 /// File generated as an artifact of an uploaded file
-#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct GeneratedFile {
     /// Unique identifier for the file
     #[schema(value_type = Uuid)]
@@ -71,6 +84,9 @@ pub struct GeneratedFile {
     pub file_key: String,
     /// When the file was created
     pub created_at: DateTime<Utc>,
+    /// Page number (Starting at 1) for generated files that are
+    /// produced per page, for extracted tables this is the table number
+    pub page: Option<i32>,
 }
 
 impl Eq for GeneratedFile {}
@@ -83,6 +99,7 @@ impl PartialEq for GeneratedFile {
             && self.ty.eq(&other.ty)
             && self.hash.eq(&other.hash)
             && self.file_key.eq(&other.file_key)
+            && self.page.eq(&other.page)
             // Reduce precision when checking creation timestamp
             // (Database does not store the full precision)
             && self
@@ -101,6 +118,7 @@ pub struct CreateGeneratedFile {
     pub hash: String,
     pub file_key: String,
     pub created_at: DateTime<Utc>,
+    pub page: Option<i32>,
 }
 
 impl GeneratedFile {
@@ -114,13 +132,14 @@ impl GeneratedFile {
             file_key,
             mime,
             created_at,
+            page,
         }: CreateGeneratedFile,
     ) -> DbResult<GeneratedFile> {
         sqlx::query(
             r#"
             INSERT INTO "docbox_generated_files"
-            ("id", "file_id", "mime", "type", "hash", "file_key", "created_at")
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ("id", "file_id", "mime", "type", "hash", "file_key", "created_at", "page")
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         "#,
         )
         .bind(id)
@@ -130,6 +149,7 @@ impl GeneratedFile {
         .bind(hash.as_str())
         .bind(file_key.as_str())
         .bind(created_at)
+        .bind(page)
         .execute(db)
         .await?;
 
@@ -141,9 +161,60 @@ impl GeneratedFile {
             hash,
             file_key,
             created_at,
+            page,
         })
     }
 
+    /// Create the generated file unless the file already has a generated file
+    /// of the same type and page, provides [None] when one already exists
+    pub async fn create_if_missing(
+        db: impl DbExecutor<'_>,
+        CreateGeneratedFile {
+            id,
+            file_id,
+            ty,
+            hash,
+            file_key,
+            mime,
+            created_at,
+            page,
+        }: CreateGeneratedFile,
+    ) -> DbResult<Option<GeneratedFile>> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO "docbox_generated_files"
+            ("id", "file_id", "mime", "type", "hash", "file_key", "created_at", "page")
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT ("file_id", "type", COALESCE("page", -1)) DO NOTHING
+        "#,
+        )
+        .bind(id)
+        .bind(file_id)
+        .bind(mime.as_str())
+        .bind(ty.to_string())
+        .bind(hash.as_str())
+        .bind(file_key.as_str())
+        .bind(created_at)
+        .bind(page)
+        .execute(db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(GeneratedFile {
+            id,
+            file_id,
+            mime,
+            ty,
+            hash,
+            file_key,
+            created_at,
+            page,
+        }))
+    }
+
     /// Deletes the generated file
     pub async fn delete(self, db: impl DbExecutor<'_>) -> DbResult<PgQueryResult> {
         sqlx::query(r#"DELETE FROM "docbox_generated_files" WHERE "id" = $1"#)
@@ -152,6 +223,16 @@ impl GeneratedFile {
             .await
     }
 
+    /// Get the total number of generated files in the tenant
+    pub async fn total_count(db: impl DbExecutor<'_>) -> DbResult<i64> {
+        let count_result: CountResult =
+            sqlx::query_as(r#"SELECT COUNT(*) AS "count" FROM "docbox_generated_files""#)
+                .fetch_one(db)
+                .await?;
+
+        Ok(count_result.count)
+    }
+
     pub async fn find_all(
         db: impl DbExecutor<'_>,
         file_id: FileId,
@@ -179,11 +260,70 @@ impl GeneratedFile {
             INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
             -- Only find the matching type for the specified file
             WHERE "file"."id" = $1 AND "folder"."document_box" = $2 AND "gen"."type" = $3
+            -- Per page generated files resolve to the first page
+            ORDER BY "gen"."page" ASC NULLS FIRST
+            LIMIT 1
+        "#,
+        )
+        .bind(file_id)
+        .bind(scope)
+        .bind(ty.to_string())
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Finds all the per page generated files of type `ty` for a file
+    /// ordered by page number
+    pub async fn find_pages(
+        db: impl DbExecutor<'_>,
+        scope: &DocumentBoxScopeRaw,
+        file_id: FileId,
+        ty: GeneratedFileType,
+    ) -> DbResult<Vec<GeneratedFile>> {
+        sqlx::query_as(
+            r#"
+            SELECT "gen".*
+            FROM "docbox_generated_files" "gen"
+            INNER JOIN "docbox_files" "file" ON "gen".file_id = "file"."id"
+            INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+            WHERE "file"."id" = $1
+                AND "folder"."document_box" = $2
+                AND "gen"."type" = $3
+                AND "gen"."page" IS NOT NULL
+            ORDER BY "gen"."page" ASC
+        "#,
+        )
+        .bind(file_id)
+        .bind(scope)
+        .bind(ty.to_string())
+        .fetch_all(db)
+        .await
+    }
+
+    /// Finds a specific page of a per page generated file
+    pub async fn find_page(
+        db: impl DbExecutor<'_>,
+        scope: &DocumentBoxScopeRaw,
+        file_id: FileId,
+        ty: GeneratedFileType,
+        page: i32,
+    ) -> DbResult<Option<GeneratedFile>> {
+        sqlx::query_as(
+            r#"
+            SELECT "gen".*
+            FROM "docbox_generated_files" "gen"
+            INNER JOIN "docbox_files" "file" ON "gen".file_id = "file"."id"
+            INNER JOIN "docbox_folders" "folder" ON "file"."folder_id" = "folder"."id"
+            WHERE "file"."id" = $1
+                AND "folder"."document_box" = $2
+                AND "gen"."type" = $3
+                AND "gen"."page" = $4
         "#,
         )
         .bind(file_id)
         .bind(scope)
         .bind(ty.to_string())
+        .bind(page)
         .fetch_optional(db)
         .await
     }
@@ -0,0 +1,143 @@
+use super::generated_file::GeneratedFileType;
+use crate::{DbExecutor, DbResult, DbTransaction};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use std::ops::DerefMut;
+use utoipa::ToSchema;
+
+/// Policy controlling whether a specific type of generated file is
+/// kept for files matching a mime type
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct GeneratedFilePolicy {
+    /// Mime type of the source file the policy applies to. Either an exact
+    /// mime type ("image/png"), all subtypes of a type ("image/*") or all
+    /// files ("*")
+    pub mime: String,
+    /// Type of generated file the policy applies to
+    #[sqlx(rename = "type")]
+    #[serde(rename = "type")]
+    #[sqlx(try_from = "String")]
+    pub ty: GeneratedFileType,
+    /// Whether the generated file should be kept
+    pub retain: bool,
+}
+
+impl GeneratedFilePolicy {
+    /// Get all the generated file policies
+    pub async fn all(db: impl DbExecutor<'_>) -> DbResult<Vec<GeneratedFilePolicy>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_generated_file_policies""#)
+            .fetch_all(db)
+            .await
+    }
+
+    /// Replace all the existing policies with `policies`
+    pub async fn replace_all(
+        db: &mut DbTransaction<'_>,
+        policies: &[GeneratedFilePolicy],
+    ) -> DbResult<()> {
+        sqlx::query(r#"DELETE FROM "docbox_generated_file_policies""#)
+            .execute(db.deref_mut())
+            .await?;
+
+        for policy in policies {
+            sqlx::query(
+                r#"
+                INSERT INTO "docbox_generated_file_policies" ("mime", "type", "retain")
+                VALUES ($1, $2, $3)
+                ON CONFLICT ("mime", "type") DO UPDATE
+                SET "retain" = EXCLUDED."retain"
+            "#,
+            )
+            .bind(policy.mime.as_str())
+            .bind(policy.ty.to_string())
+            .bind(policy.retain)
+            .execute(db.deref_mut())
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the specificity of the policy when matching the provided `mime`
+    /// essence, [None] when the policy does not apply.
+    ///
+    /// Exact matches are more specific than type wildcards which are more
+    /// specific than the catch-all "*"
+    pub fn match_specificity(&self, mime: &str) -> Option<u8> {
+        if self.mime == "*" {
+            return Some(0);
+        }
+
+        if let Some(ty) = self.mime.strip_suffix("/*") {
+            let (mime_ty, _) = mime.split_once('/')?;
+            return mime_ty.eq_ignore_ascii_case(ty).then_some(1);
+        }
+
+        self.mime.eq_ignore_ascii_case(mime).then_some(2)
+    }
+
+    /// Check if the generated file type `ty` should be retained for a file
+    /// with the provided `mime` essence based on the most specific policy
+    ///
+    /// Generated files are retained when no policies apply
+    pub fn should_retain(
+        policies: &[GeneratedFilePolicy],
+        mime: &str,
+        ty: GeneratedFileType,
+    ) -> bool {
+        policies
+            .iter()
+            .filter(|policy| policy.ty == ty)
+            .filter_map(|policy| {
+                policy
+                    .match_specificity(mime)
+                    .map(|specificity| (specificity, policy.retain))
+            })
+            .max_by_key(|(specificity, _)| *specificity)
+            .is_none_or(|(_, retain)| retain)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GeneratedFilePolicy;
+    use crate::models::generated_file::GeneratedFileType;
+
+    fn policy(mime: &str, ty: GeneratedFileType, retain: bool) -> GeneratedFilePolicy {
+        GeneratedFilePolicy {
+            mime: mime.to_string(),
+            ty,
+            retain,
+        }
+    }
+
+    #[test]
+    fn test_should_retain_most_specific() {
+        let policies = vec![
+            policy("*", GeneratedFileType::Pdf, false),
+            policy("image/*", GeneratedFileType::Pdf, true),
+            policy("image/png", GeneratedFileType::Pdf, false),
+        ];
+
+        assert!(!GeneratedFilePolicy::should_retain(
+            &policies,
+            "image/png",
+            GeneratedFileType::Pdf
+        ));
+        assert!(GeneratedFilePolicy::should_retain(
+            &policies,
+            "image/jpeg",
+            GeneratedFileType::Pdf
+        ));
+        assert!(!GeneratedFilePolicy::should_retain(
+            &policies,
+            "text/plain",
+            GeneratedFileType::Pdf
+        ));
+        assert!(GeneratedFilePolicy::should_retain(
+            &policies,
+            "text/plain",
+            GeneratedFileType::SmallThumbnail
+        ));
+    }
+}
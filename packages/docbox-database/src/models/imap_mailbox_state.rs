@@ -0,0 +1,53 @@
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+
+/// Ingestion state of an IMAP mailbox, tracks the UID of the last
+/// message that was ingested
+#[derive(Debug, Clone, FromRow, PartialEq, Eq)]
+pub struct ImapMailboxState {
+    /// Identifier of the mailbox (i.e "imap://user@host:993/INBOX")
+    pub mailbox_id: String,
+    /// UIDVALIDITY of the mailbox when the last message was ingested, UIDs
+    /// are only comparable while this value is unchanged
+    pub uid_validity: i64,
+    /// UID of the last ingested message
+    pub last_uid: i64,
+    /// When the state was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ImapMailboxState {
+    /// Store the ingestion state for a mailbox, replacing any previous state
+    pub async fn set(db: impl DbExecutor<'_>, state: &ImapMailboxState) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_imap_mailbox_state"
+                ("mailbox_id", "uid_validity", "last_uid", "updated_at")
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT ("mailbox_id") DO UPDATE
+            SET
+                "uid_validity" = EXCLUDED."uid_validity",
+                "last_uid" = EXCLUDED."last_uid",
+                "updated_at" = EXCLUDED."updated_at"
+        "#,
+        )
+        .bind(&state.mailbox_id)
+        .bind(state.uid_validity)
+        .bind(state.last_uid)
+        .bind(state.updated_at)
+        .execute(db)
+        .await
+    }
+
+    /// Find the ingestion state for a mailbox
+    pub async fn find(
+        db: impl DbExecutor<'_>,
+        mailbox_id: &str,
+    ) -> DbResult<Option<ImapMailboxState>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_imap_mailbox_state" WHERE "mailbox_id" = $1"#)
+            .bind(mailbox_id)
+            .fetch_optional(db)
+            .await
+    }
+}
@@ -0,0 +1,110 @@
+use super::{
+    document_box::{DocumentBoxScopeRaw, DocumentBoxScopeRawRef},
+    tenant::TenantId,
+};
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+use utoipa::ToSchema;
+
+/// Email address that routes received emails into a document box
+///
+/// Stored in the root database as the receiving address must be resolved
+/// to a tenant before the tenant database is known
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema, PartialEq, Eq)]
+pub struct InboundEmailAddress {
+    /// The email address (Always lowercase)
+    pub address: String,
+    /// Environment of the tenant
+    #[serde(skip_serializing)]
+    #[schema(ignore)]
+    pub env: String,
+    /// ID of the tenant the document box belongs to
+    #[serde(skip_serializing)]
+    #[schema(ignore)]
+    pub tenant_id: TenantId,
+    /// Scope of the document box received emails are stored in
+    pub document_box: DocumentBoxScopeRaw,
+    /// When the address was created
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct CreateInboundEmailAddress {
+    pub address: String,
+    pub env: String,
+    pub tenant_id: TenantId,
+    pub document_box: DocumentBoxScopeRaw,
+}
+
+impl InboundEmailAddress {
+    /// Create a new inbound email address
+    pub async fn create(
+        db: impl DbExecutor<'_>,
+        create: CreateInboundEmailAddress,
+    ) -> DbResult<InboundEmailAddress> {
+        let address = InboundEmailAddress {
+            address: create.address.to_lowercase(),
+            env: create.env,
+            tenant_id: create.tenant_id,
+            document_box: create.document_box,
+            created_at: Utc::now(),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_inbound_email_addresses"
+                ("address", "env", "tenant_id", "document_box", "created_at")
+            VALUES ($1, $2, $3, $4, $5)
+        "#,
+        )
+        .bind(address.address.as_str())
+        .bind(address.env.as_str())
+        .bind(address.tenant_id)
+        .bind(address.document_box.as_str())
+        .bind(address.created_at)
+        .execute(db)
+        .await?;
+
+        Ok(address)
+    }
+
+    /// Find the inbound email address matching `address` (Case insensitive)
+    pub async fn find_by_address(
+        db: impl DbExecutor<'_>,
+        address: &str,
+    ) -> DbResult<Option<InboundEmailAddress>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_inbound_email_addresses" WHERE "address" = $1"#)
+            .bind(address.to_lowercase())
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Find the inbound email address of a document box
+    pub async fn find_by_document_box(
+        db: impl DbExecutor<'_>,
+        env: &str,
+        tenant_id: TenantId,
+        scope: DocumentBoxScopeRawRef<'_>,
+    ) -> DbResult<Option<InboundEmailAddress>> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM "docbox_inbound_email_addresses"
+            WHERE "env" = $1 AND "tenant_id" = $2 AND "document_box" = $3
+        "#,
+        )
+        .bind(env)
+        .bind(tenant_id)
+        .bind(scope)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Delete the inbound email address
+    pub async fn delete(&self, db: impl DbExecutor<'_>) -> DbResult<PgQueryResult> {
+        sqlx::query(r#"DELETE FROM "docbox_inbound_email_addresses" WHERE "address" = $1"#)
+            .bind(self.address.as_str())
+            .execute(db)
+            .await
+    }
+}
@@ -8,19 +8,20 @@ use crate::{
     models::{
         document_box::DocumentBoxScopeRawRef,
         shared::{
-            CountResult, DocboxInputPair, FolderPathSegment, WithFullPath, WithFullPathScope,
+            CountResult, DocboxInputPair, FolderPathSegment, PinnedItemRef, WithFullPath,
+            WithFullPathScope,
         },
     },
 };
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgQueryResult, prelude::FromRow};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 pub type LinkId = Uuid;
 
-#[derive(Debug, Clone, FromRow, Serialize, sqlx::Type, ToSchema)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "docbox_link")]
 pub struct Link {
     /// Unique identifier for the link
@@ -40,6 +41,8 @@ pub struct Link {
     /// User who created the link
     #[serde(skip)]
     pub created_by: Option<UserId>,
+    /// When the website metadata for the link was last fetched
+    pub fetched_at: Option<DateTime<Utc>>,
 }
 
 impl Eq for Link {}
@@ -68,7 +71,7 @@ pub struct LinkWithScope {
     pub scope: String,
 }
 
-#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct LinkWithExtra {
     #[serde(flatten)]
     pub link: Link,
@@ -139,6 +142,7 @@ impl Link {
             created_by,
             created_at,
             pinned: false,
+            fetched_at: None,
         })
     }
 
@@ -169,17 +173,87 @@ impl Link {
         Ok(self)
     }
 
-    pub async fn set_pinned(mut self, db: impl DbExecutor<'_>, pinned: bool) -> DbResult<Link> {
-        sqlx::query(r#"UPDATE "docbox_links" SET "pinned" = $1 WHERE "id" = $2"#)
-            .bind(pinned)
+    /// Set when the website metadata for the link was last fetched
+    pub async fn set_fetched_at(
+        mut self,
+        db: impl DbExecutor<'_>,
+        fetched_at: DateTime<Utc>,
+    ) -> DbResult<Link> {
+        sqlx::query(r#"UPDATE "docbox_links" SET "fetched_at" = $1 WHERE "id" = $2"#)
+            .bind(fetched_at)
             .bind(self.id)
             .execute(db)
             .await?;
 
+        self.fetched_at = Some(fetched_at);
+        Ok(self)
+    }
+
+    /// Get the current version of the link
+    pub async fn version(db: impl DbExecutor<'_>, link_id: LinkId) -> DbResult<Option<i64>> {
+        sqlx::query_scalar(r#"SELECT "version" FROM "docbox_links" WHERE "id" = $1"#)
+            .bind(link_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Get the current version of the link locking the link row until
+    /// the end of the current transaction
+    pub async fn lock_version(db: impl DbExecutor<'_>, link_id: LinkId) -> DbResult<i64> {
+        sqlx::query_scalar(r#"SELECT "version" FROM "docbox_links" WHERE "id" = $1 FOR UPDATE"#)
+            .bind(link_id)
+            .fetch_one(db)
+            .await
+    }
+
+    /// Increments the version of the link returning the new version
+    pub async fn increment_version(db: impl DbExecutor<'_>, link_id: LinkId) -> DbResult<i64> {
+        sqlx::query_scalar(
+            r#"UPDATE "docbox_links" SET "version" = "version" + 1 WHERE "id" = $1 RETURNING "version""#,
+        )
+        .bind(link_id)
+        .fetch_one(db)
+        .await
+    }
+
+    pub async fn set_pinned(mut self, db: impl DbExecutor<'_>, pinned: bool) -> DbResult<Link> {
+        sqlx::query(
+            r#"
+            UPDATE "docbox_links"
+            SET "pinned" = $1,
+                "pinned_at" = CASE WHEN $1 THEN COALESCE("pinned_at", NOW()) ELSE NULL END
+            WHERE "id" = $2
+            "#,
+        )
+        .bind(pinned)
+        .bind(self.id)
+        .execute(db)
+        .await?;
+
         self.pinned = pinned;
         Ok(self)
     }
 
+    /// Finds all the pinned links within the document box `scope`, most
+    /// recently pinned first
+    pub async fn find_pinned(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+    ) -> DbResult<Vec<PinnedItemRef>> {
+        sqlx::query_as(
+            r#"
+            SELECT "link"."id", COALESCE("link"."pinned_at", "link"."created_at") AS "pinned_at"
+            FROM "docbox_links" "link"
+            INNER JOIN "docbox_folders" "folder" ON "link"."folder_id" = "folder"."id"
+            WHERE "folder"."document_box" = $1 AND "link"."pinned"
+            ORDER BY "pinned_at" DESC
+            "#,
+        )
+        .bind(scope)
+        .fetch_all(db)
+        .await
+    }
+
     pub async fn update_value(mut self, db: impl DbExecutor<'_>, value: String) -> DbResult<Link> {
         sqlx::query(r#"UPDATE "docbox_links" SET "value" = $1 WHERE "id" = $2"#)
             .bind(value.as_str())
@@ -256,6 +330,17 @@ impl Link {
             .await
     }
 
+    /// Finds all links within any of the folders in `folder_ids`
+    pub async fn find_by_folders(
+        db: impl DbExecutor<'_>,
+        folder_ids: &[FolderId],
+    ) -> DbResult<Vec<Link>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_links" WHERE "folder_id" = ANY($1)"#)
+            .bind(folder_ids)
+            .fetch_all(db)
+            .await
+    }
+
     /// Deletes the link
     pub async fn delete(&self, db: impl DbExecutor<'_>) -> DbResult<PgQueryResult> {
         sqlx::query(r#"DELETE FROM "docbox_links" WHERE "id" = $1"#)
@@ -0,0 +1,160 @@
+//! Files generated from the website a link points to (i.e favicon and
+//! social images) persisted to storage so they don't need to be scraped
+//! on every request
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{document_box::DocumentBoxScopeRaw, link::LinkId};
+use crate::{DbExecutor, DbResult};
+
+pub type LinkGeneratedFileId = Uuid;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    strum::EnumString,
+    strum::Display,
+    Deserialize,
+    Serialize,
+    ToSchema,
+    PartialEq,
+    Eq,
+)]
+pub enum LinkGeneratedFileType {
+    /// Favicon image for the website
+    Favicon,
+    /// Social image from the website OGP metadata
+    SocialImage,
+}
+
+impl TryFrom<String> for LinkGeneratedFileType {
+    type Error = strum::ParseError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        LinkGeneratedFileType::from_str(&value)
+    }
+}
+
+/// File generated from the website of a link
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct LinkGeneratedFile {
+    /// Unique identifier for the file
+    #[schema(value_type = Uuid)]
+    pub id: LinkGeneratedFileId,
+    /// Link this generated file belongs to
+    #[schema(value_type = Uuid)]
+    pub link_id: LinkId,
+    /// Mime type of the generated file content
+    pub mime: String,
+    /// Type of the generated file
+    #[sqlx(rename = "type")]
+    #[serde(rename = "type")]
+    #[sqlx(try_from = "String")]
+    pub ty: LinkGeneratedFileType,
+    /// S3 key pointing to the file
+    #[serde(skip)]
+    pub file_key: String,
+    /// When the file was created
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct CreateLinkGeneratedFile {
+    pub id: Uuid,
+    pub link_id: LinkId,
+    pub mime: String,
+    pub ty: LinkGeneratedFileType,
+    pub file_key: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LinkGeneratedFile {
+    pub async fn create(
+        db: impl DbExecutor<'_>,
+        CreateLinkGeneratedFile {
+            id,
+            link_id,
+            mime,
+            ty,
+            file_key,
+            created_at,
+        }: CreateLinkGeneratedFile,
+    ) -> DbResult<LinkGeneratedFile> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_link_generated_files"
+            ("id", "link_id", "mime", "type", "file_key", "created_at")
+            VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        )
+        .bind(id)
+        .bind(link_id)
+        .bind(mime.as_str())
+        .bind(ty.to_string())
+        .bind(file_key.as_str())
+        .bind(created_at)
+        .execute(db)
+        .await?;
+
+        Ok(LinkGeneratedFile {
+            id,
+            link_id,
+            mime,
+            ty,
+            file_key,
+            created_at,
+        })
+    }
+
+    /// Deletes the generated file
+    pub async fn delete(&self, db: impl DbExecutor<'_>) -> DbResult<PgQueryResult> {
+        sqlx::query(r#"DELETE FROM "docbox_link_generated_files" WHERE "id" = $1"#)
+            .bind(self.id)
+            .execute(db)
+            .await
+    }
+
+    /// Finds all the generated files for a link
+    pub async fn find_all(
+        db: impl DbExecutor<'_>,
+        link_id: LinkId,
+    ) -> DbResult<Vec<LinkGeneratedFile>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_link_generated_files" WHERE "link_id" = $1"#)
+            .bind(link_id)
+            .fetch_all(db)
+            .await
+    }
+
+    /// Finds a specific generated file type for a link using its full
+    /// path scope -> folder -> link
+    pub async fn find(
+        db: impl DbExecutor<'_>,
+        scope: &DocumentBoxScopeRaw,
+        link_id: LinkId,
+        ty: LinkGeneratedFileType,
+    ) -> DbResult<Option<LinkGeneratedFile>> {
+        sqlx::query_as(
+            r#"
+            SELECT "gen".*
+            FROM "docbox_link_generated_files" "gen"
+            -- Join on the link itself
+            INNER JOIN "docbox_links" "link" ON "gen"."link_id" = "link"."id"
+            -- Join to the link parent folder
+            INNER JOIN "docbox_folders" "folder" ON "link"."folder_id" = "folder"."id"
+            -- Only find the matching type for the specified link
+            WHERE "link"."id" = $1 AND "folder"."document_box" = $2 AND "gen"."type" = $3
+        "#,
+        )
+        .bind(link_id)
+        .bind(scope)
+        .bind(ty.to_string())
+        .fetch_optional(db)
+        .await
+    }
+}
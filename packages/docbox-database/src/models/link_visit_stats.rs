@@ -0,0 +1,121 @@
+use super::{
+    document_box::DocumentBoxScopeRawRef,
+    link::{Link, LinkId},
+};
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+
+/// Visit statistics for a link
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct LinkVisitStats {
+    /// ID of the link the statistics are for
+    #[serde(skip)]
+    pub link_id: LinkId,
+    /// Number of times the link was visited
+    pub visit_count: i64,
+    /// When the link was last visited
+    pub last_visited_at: DateTime<Utc>,
+}
+
+/// Link within the most visited links listing
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct MostVisitedLink {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub link: Link,
+    /// Number of times the link was visited
+    pub visit_count: i64,
+    /// When the link was last visited
+    pub last_visited_at: DateTime<Utc>,
+}
+
+impl LinkVisitStats {
+    /// Record a visit to the link, returns the updated statistics or
+    /// [None] if the link no longer exists
+    pub async fn record_visit(
+        db: impl DbExecutor<'_>,
+        link_id: LinkId,
+        visited_at: DateTime<Utc>,
+    ) -> DbResult<Option<LinkVisitStats>> {
+        sqlx::query_as(
+            r#"
+            INSERT INTO "docbox_links_visit_stats" (
+                "link_id",
+                "visit_count",
+                "last_visited_at"
+            )
+            -- Link may have been deleted before the visit was stored
+            SELECT $1, 1, $2
+            WHERE EXISTS (SELECT 1 FROM "docbox_links" WHERE "id" = $1)
+            ON CONFLICT ("link_id") DO UPDATE
+            SET
+                "visit_count" = "docbox_links_visit_stats"."visit_count" + 1,
+                "last_visited_at" = GREATEST(
+                    "docbox_links_visit_stats"."last_visited_at",
+                    EXCLUDED."last_visited_at"
+                )
+            RETURNING *
+        "#,
+        )
+        .bind(link_id)
+        .bind(visited_at)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Find the visit statistics for a link
+    pub async fn find(
+        db: impl DbExecutor<'_>,
+        link_id: LinkId,
+    ) -> DbResult<Option<LinkVisitStats>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_links_visit_stats" WHERE "link_id" = $1"#)
+            .bind(link_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Find the visit statistics for many links, links that have never
+    /// been visited are not included
+    pub async fn find_many(
+        db: impl DbExecutor<'_>,
+        link_ids: &[LinkId],
+    ) -> DbResult<Vec<LinkVisitStats>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_links_visit_stats" WHERE "link_id" = ANY($1)"#)
+            .bind(link_ids)
+            .fetch_all(db)
+            .await
+    }
+
+    /// Query a page of the most visited links within the document box
+    /// `scope`, links that have never been visited are not included
+    pub async fn most_visited(
+        db: impl DbExecutor<'_>,
+        scope: DocumentBoxScopeRawRef<'_>,
+        offset: u64,
+        limit: u64,
+    ) -> DbResult<Vec<MostVisitedLink>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                "link".*,
+                "stats"."visit_count" AS "visit_count",
+                "stats"."last_visited_at" AS "last_visited_at"
+            FROM "docbox_links_visit_stats" AS "stats"
+            INNER JOIN "docbox_links" "link" ON "stats"."link_id" = "link"."id"
+            INNER JOIN "docbox_folders" "folder" ON "link"."folder_id" = "folder"."id"
+            WHERE "folder"."document_box" = $1
+            ORDER BY "stats"."visit_count" DESC, "stats"."last_visited_at" DESC, "link"."created_at" ASC
+            OFFSET $2
+            LIMIT $3
+        "#,
+        )
+        .bind(scope)
+        .bind(offset as i64)
+        .bind(limit as i64)
+        .fetch_all(db)
+        .await
+    }
+}
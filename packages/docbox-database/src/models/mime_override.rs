@@ -0,0 +1,50 @@
+use crate::{DbExecutor, DbResult, DbTransaction};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use std::ops::DerefMut;
+use utoipa::ToSchema;
+
+/// Override for the mime type used for files with a specific extension
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct MimeOverride {
+    /// File extension the override applies to without the leading dot ("dat")
+    pub extension: String,
+    /// Mime type to use for files with the extension ("application/pdf")
+    pub mime: String,
+}
+
+impl MimeOverride {
+    /// Get all the mime overrides
+    pub async fn all(db: impl DbExecutor<'_>) -> DbResult<Vec<MimeOverride>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_mime_overrides""#)
+            .fetch_all(db)
+            .await
+    }
+
+    /// Replace all the existing overrides with `overrides`
+    pub async fn replace_all(
+        db: &mut DbTransaction<'_>,
+        overrides: &[MimeOverride],
+    ) -> DbResult<()> {
+        sqlx::query(r#"DELETE FROM "docbox_mime_overrides""#)
+            .execute(db.deref_mut())
+            .await?;
+
+        for mime_override in overrides {
+            sqlx::query(
+                r#"
+                INSERT INTO "docbox_mime_overrides" ("extension", "mime")
+                VALUES ($1, $2)
+                ON CONFLICT ("extension") DO UPDATE
+                SET "mime" = EXCLUDED."mime"
+            "#,
+            )
+            .bind(mime_override.extension.to_lowercase())
+            .bind(mime_override.mime.as_str())
+            .execute(db.deref_mut())
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,15 +1,39 @@
+pub mod connector_sync_item;
 pub mod document_box;
+pub mod document_box_stats;
+pub mod document_box_webhook;
 pub mod edit_history;
+pub mod event_payload;
+pub mod extraction_cache;
 pub mod file;
+pub mod file_access_stats;
+pub mod file_email;
+pub mod file_pdf_metadata;
+pub mod file_pii_analysis;
+pub mod file_processing;
+pub mod file_text_stats;
 pub mod folder;
+pub mod folder_processing_config;
 pub mod generated_file;
+pub mod generated_file_policy;
+pub mod imap_mailbox_state;
+pub mod inbound_email_address;
 pub mod link;
+pub mod link_generated_file;
 pub mod link_resolved_metadata;
+pub mod link_visit_stats;
+pub mod mime_override;
 pub mod presigned_upload_task;
+pub mod recent_search;
+pub mod request_usage;
 pub mod root_migration;
+pub mod scope_pattern;
 pub mod search;
 pub mod shared;
+pub mod storage_object;
 pub mod tasks;
 pub mod tenant;
 pub mod tenant_migration;
+pub mod upload_rule;
+pub mod usage_stats;
 pub mod user;
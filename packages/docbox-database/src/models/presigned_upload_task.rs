@@ -7,17 +7,22 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgQueryResult, prelude::FromRow, types::Json};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-use super::{document_box::DocumentBoxScopeRaw, file::FileId, folder::FolderId, user::UserId};
+use super::{
+    document_box::DocumentBoxScopeRaw, file::FileId, folder::FolderId, shared::CountResult,
+    user::UserId,
+};
 use crate::{DbErr, DbExecutor, DbResult};
 
 pub type PresignedUploadTaskId = Uuid;
 
 /// Task storing the details for a presigned upload task
-#[derive(Debug, Clone, FromRow, Serialize)]
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
 pub struct PresignedUploadTask {
     /// ID of the upload task
+    #[schema(value_type = Uuid)]
     pub id: PresignedUploadTaskId,
     /// File created from the outcome of this task
     #[sqlx(json)]
@@ -33,6 +38,7 @@ pub struct PresignedUploadTask {
     /// ID of the document box the folder belongs to
     pub document_box: DocumentBoxScopeRaw,
     /// Target folder to store the file in
+    #[schema(value_type = Uuid)]
     pub folder_id: FolderId,
     /// S3 key where the file should be stored
     pub file_key: String,
@@ -46,10 +52,12 @@ pub struct PresignedUploadTask {
     pub created_by: Option<UserId>,
 
     /// Optional file to make the parent of this file
+    #[schema(value_type = Option<Uuid>)]
     pub parent_id: Option<FileId>,
 
     /// Config that can be used when processing for additional
     /// configuration to how the file is processed
+    #[schema(value_type = Option<Object>)]
     pub processing_config: Option<Json<serde_json::Value>>,
 }
 
@@ -83,12 +91,53 @@ impl PartialEq for PresignedUploadTask {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
 #[serde(tag = "status")]
 pub enum PresignedTaskStatus {
     Pending,
-    Completed { file_id: FileId },
-    Failed { error: String },
+    Completed {
+        #[schema(value_type = Uuid)]
+        file_id: FileId,
+    },
+    Failed {
+        error: String,
+    },
+    /// Presigned URL expired before the upload completed, terminal
+    /// status set by the background purge
+    Expired,
+}
+
+impl PresignedTaskStatus {
+    /// Get the kind of status without the status details
+    pub fn kind(&self) -> PresignedTaskStatusKind {
+        match self {
+            PresignedTaskStatus::Pending => PresignedTaskStatusKind::Pending,
+            PresignedTaskStatus::Completed { .. } => PresignedTaskStatusKind::Completed,
+            PresignedTaskStatus::Failed { .. } => PresignedTaskStatusKind::Failed,
+            PresignedTaskStatus::Expired => PresignedTaskStatusKind::Expired,
+        }
+    }
+}
+
+/// Kind of [PresignedTaskStatus] used for filtering tasks by their status
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub enum PresignedTaskStatusKind {
+    Pending,
+    Completed,
+    Failed,
+    Expired,
+}
+
+impl PresignedTaskStatusKind {
+    /// Name of the status, matches the "status" tag of [PresignedTaskStatus]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PresignedTaskStatusKind::Pending => "Pending",
+            PresignedTaskStatusKind::Completed => "Completed",
+            PresignedTaskStatusKind::Failed => "Failed",
+            PresignedTaskStatusKind::Expired => "Expired",
+        }
+    }
 }
 
 /// Required data to create a presigned upload task
@@ -211,6 +260,80 @@ impl PresignedUploadTask {
         .await
     }
 
+    /// Find a specific presigned upload task by ID regardless
+    /// of the document box it belongs to
+    pub async fn find_by_id(
+        db: impl DbExecutor<'_>,
+        task_id: PresignedUploadTaskId,
+    ) -> DbResult<Option<PresignedUploadTask>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_presigned_upload_tasks" WHERE "id" = $1"#)
+            .bind(task_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Query a page of presigned upload tasks optionally filtered to a
+    /// specific `status`, most recently created tasks first
+    pub async fn query(
+        db: impl DbExecutor<'_>,
+        status: Option<PresignedTaskStatusKind>,
+        offset: u64,
+        limit: u64,
+    ) -> DbResult<Vec<PresignedUploadTask>> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM "docbox_presigned_upload_tasks"
+            WHERE $1::VARCHAR IS NULL OR "status"->>'status' = $1
+            ORDER BY "created_at" DESC
+            OFFSET $2
+            LIMIT $3
+            "#,
+        )
+        .bind(status.map(|status| status.as_str()))
+        .bind(offset as i64)
+        .bind(limit as i64)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Get the total number of presigned upload tasks optionally
+    /// filtered to a specific `status`
+    pub async fn total(
+        db: impl DbExecutor<'_>,
+        status: Option<PresignedTaskStatusKind>,
+    ) -> DbResult<i64> {
+        let result: CountResult = sqlx::query_as(
+            r#"
+            SELECT COUNT(*) as "count" FROM "docbox_presigned_upload_tasks"
+            WHERE $1::VARCHAR IS NULL OR "status"->>'status' = $1
+            "#,
+        )
+        .bind(status.map(|status| status.as_str()))
+        .fetch_one(db)
+        .await?;
+
+        Ok(result.count)
+    }
+
+    /// Find all presigned upload tasks that are still pending and were
+    /// created before the `before` date, oldest tasks first
+    pub async fn find_stuck(
+        db: impl DbExecutor<'_>,
+        before: DateTime<Utc>,
+    ) -> DbResult<Vec<PresignedUploadTask>> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM "docbox_presigned_upload_tasks"
+            WHERE "status"->>'status' = $1 AND "created_at" < $2
+            ORDER BY "created_at" ASC
+            "#,
+        )
+        .bind(PresignedTaskStatusKind::Pending.as_str())
+        .bind(before)
+        .fetch_all(db)
+        .await
+    }
+
     /// Finds all presigned uploads that have expired based on the current date
     pub async fn find_expired(
         db: impl DbExecutor<'_>,
@@ -0,0 +1,125 @@
+use super::{document_box::DocumentBoxScopeRaw, user::UserId};
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub type RecentSearchId = Uuid;
+
+/// Search query previously made by a user within a document box
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct RecentSearch {
+    /// Unique ID of the recent search
+    #[schema(value_type = Uuid)]
+    pub id: RecentSearchId,
+    /// ID of the user that made the search
+    #[serde(skip)]
+    pub user_id: UserId,
+    /// Scope of the document box the search was made in
+    #[serde(skip)]
+    pub document_box: DocumentBoxScopeRaw,
+    /// The search query
+    pub query: String,
+    /// When the query was last searched
+    pub searched_at: DateTime<Utc>,
+}
+
+impl RecentSearch {
+    /// Record a search made by a user, searching the same query again
+    /// moves the existing entry to the front instead of adding another
+    pub async fn record(
+        db: impl DbExecutor<'_>,
+        user_id: &str,
+        document_box: &str,
+        query: &str,
+        searched_at: DateTime<Utc>,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_recent_searches" (
+                "id",
+                "user_id",
+                "document_box",
+                "query",
+                "searched_at"
+            )
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT ("user_id", "document_box", "query") DO UPDATE
+            SET "searched_at" = EXCLUDED."searched_at"
+        "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(document_box)
+        .bind(query)
+        .bind(searched_at)
+        .execute(db)
+        .await
+    }
+
+    /// Delete all but the `keep` most recent searches for a user within
+    /// a document box
+    pub async fn prune(
+        db: impl DbExecutor<'_>,
+        user_id: &str,
+        document_box: &str,
+        keep: u64,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            DELETE FROM "docbox_recent_searches"
+            WHERE "id" IN (
+                SELECT "id" FROM "docbox_recent_searches"
+                WHERE "user_id" = $1 AND "document_box" = $2
+                ORDER BY "searched_at" DESC
+                OFFSET $3
+            )
+        "#,
+        )
+        .bind(user_id)
+        .bind(document_box)
+        .bind(keep as i64)
+        .execute(db)
+        .await
+    }
+
+    /// Find the most recent searches for a user within a document box,
+    /// most recent first
+    pub async fn find_by_user(
+        db: impl DbExecutor<'_>,
+        user_id: &str,
+        document_box: &str,
+        limit: u64,
+    ) -> DbResult<Vec<RecentSearch>> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM "docbox_recent_searches"
+            WHERE "user_id" = $1 AND "document_box" = $2
+            ORDER BY "searched_at" DESC
+            LIMIT $3
+        "#,
+        )
+        .bind(user_id)
+        .bind(document_box)
+        .bind(limit as i64)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Delete all the recent searches for a user within a document box
+    pub async fn delete_by_user(
+        db: impl DbExecutor<'_>,
+        user_id: &str,
+        document_box: &str,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"DELETE FROM "docbox_recent_searches" WHERE "user_id" = $1 AND "document_box" = $2"#,
+        )
+        .bind(user_id)
+        .bind(document_box)
+        .execute(db)
+        .await
+    }
+}
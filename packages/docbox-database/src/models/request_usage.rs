@@ -0,0 +1,136 @@
+use super::tenant::TenantId;
+use crate::{DbExecutor, DbResult};
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+use utoipa::ToSchema;
+
+/// Request and response byte counts for a tenant and API key on a
+/// specific day, used for billing tenants for their API usage
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema, PartialEq, Eq)]
+pub struct RequestUsage {
+    /// Environment of the tenant
+    pub env: String,
+    /// ID of the tenant
+    #[schema(value_type = Uuid)]
+    pub tenant_id: TenantId,
+    /// Identifier of the API key the requests were made with, this is
+    /// a fingerprint of the key rather than the key itself
+    pub api_key_id: String,
+    /// Day the usage is for
+    pub day: NaiveDate,
+    /// Number of requests made
+    pub requests: i64,
+    /// Total bytes received in request bodies
+    pub request_bytes: i64,
+    /// Total bytes sent in response bodies
+    pub response_bytes: i64,
+    /// Bytes of the request bodies that were file uploads
+    pub upload_bytes: i64,
+    /// Bytes of the response bodies that were file downloads
+    pub download_bytes: i64,
+}
+
+/// Accumulated usage that should be added to the stored usage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestUsageIncrement {
+    pub env: String,
+    pub tenant_id: TenantId,
+    pub api_key_id: String,
+    pub day: NaiveDate,
+    pub requests: i64,
+    pub request_bytes: i64,
+    pub response_bytes: i64,
+    pub upload_bytes: i64,
+    pub download_bytes: i64,
+}
+
+impl RequestUsage {
+    /// Add the usage from `increment` to the stored usage for the day
+    pub async fn increment(
+        db: impl DbExecutor<'_>,
+        increment: &RequestUsageIncrement,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_request_usage" (
+                "env",
+                "tenant_id",
+                "api_key_id",
+                "day",
+                "requests",
+                "request_bytes",
+                "response_bytes",
+                "upload_bytes",
+                "download_bytes"
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT ("env", "tenant_id", "api_key_id", "day") DO UPDATE
+            SET
+                "requests" = "docbox_request_usage"."requests" + EXCLUDED."requests",
+                "request_bytes" = "docbox_request_usage"."request_bytes" + EXCLUDED."request_bytes",
+                "response_bytes" = "docbox_request_usage"."response_bytes" + EXCLUDED."response_bytes",
+                "upload_bytes" = "docbox_request_usage"."upload_bytes" + EXCLUDED."upload_bytes",
+                "download_bytes" = "docbox_request_usage"."download_bytes" + EXCLUDED."download_bytes"
+        "#,
+        )
+        .bind(increment.env.as_str())
+        .bind(increment.tenant_id)
+        .bind(increment.api_key_id.as_str())
+        .bind(increment.day)
+        .bind(increment.requests)
+        .bind(increment.request_bytes)
+        .bind(increment.response_bytes)
+        .bind(increment.upload_bytes)
+        .bind(increment.download_bytes)
+        .execute(db)
+        .await
+    }
+
+    /// Find the usage for all tenants between the `start` and `end`
+    /// days (inclusive), optionally filtered to a single `env`
+    pub async fn find_range(
+        db: impl DbExecutor<'_>,
+        start: NaiveDate,
+        end: NaiveDate,
+        env: Option<&str>,
+    ) -> DbResult<Vec<RequestUsage>> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM "docbox_request_usage"
+            WHERE "day" BETWEEN $1 AND $2
+                AND ($3::VARCHAR IS NULL OR "env" = $3)
+            ORDER BY "day" ASC, "env" ASC, "tenant_id" ASC, "api_key_id" ASC
+        "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(env)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Find the usage for a specific tenant between the `start` and
+    /// `end` days (inclusive)
+    pub async fn find_tenant_range(
+        db: impl DbExecutor<'_>,
+        env: &str,
+        tenant_id: TenantId,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> DbResult<Vec<RequestUsage>> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM "docbox_request_usage"
+            WHERE "env" = $1 AND "tenant_id" = $2 AND "day" BETWEEN $3 AND $4
+            ORDER BY "day" ASC, "api_key_id" ASC
+        "#,
+        )
+        .bind(env)
+        .bind(tenant_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(db)
+        .await
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+use utoipa::ToSchema;
+
+/// Registered pattern of document box scopes, patterns are either an exact
+/// scope ("org:1") or a prefix followed by a single trailing wildcard ("org:1:*")
+///
+/// When any patterns are registered, document boxes can only be created
+/// with scopes matching at least one of the patterns
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct ScopePattern {
+    /// The scope pattern
+    pub pattern: String,
+    /// Pattern of the parent this pattern is nested within
+    pub parent: Option<String>,
+    /// Optional description of the scopes matched by this pattern
+    pub description: Option<String>,
+    /// When the pattern was registered
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct CreateScopePattern {
+    pub pattern: String,
+    pub parent: Option<String>,
+    pub description: Option<String>,
+}
+
+impl ScopePattern {
+    /// Get all the registered scope patterns
+    pub async fn all(db: impl DbExecutor<'_>) -> DbResult<Vec<ScopePattern>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_scope_patterns" ORDER BY "pattern""#)
+            .fetch_all(db)
+            .await
+    }
+
+    /// Find a specific scope pattern
+    pub async fn find(db: impl DbExecutor<'_>, pattern: &str) -> DbResult<Option<ScopePattern>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_scope_patterns" WHERE "pattern" = $1"#)
+            .bind(pattern)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Get the direct children of the `parent` pattern
+    pub async fn children(db: impl DbExecutor<'_>, parent: &str) -> DbResult<Vec<ScopePattern>> {
+        sqlx::query_as(
+            r#"SELECT * FROM "docbox_scope_patterns" WHERE "parent" = $1 ORDER BY "pattern""#,
+        )
+        .bind(parent)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Register a new scope pattern
+    pub async fn create(
+        db: impl DbExecutor<'_>,
+        create: CreateScopePattern,
+    ) -> DbResult<ScopePattern> {
+        let pattern = ScopePattern {
+            pattern: create.pattern,
+            parent: create.parent,
+            description: create.description,
+            created_at: Utc::now(),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_scope_patterns" ("pattern", "parent", "description", "created_at")
+            VALUES ($1, $2, $3, $4)
+        "#,
+        )
+        .bind(pattern.pattern.as_str())
+        .bind(pattern.parent.as_deref())
+        .bind(pattern.description.as_deref())
+        .bind(pattern.created_at)
+        .execute(db)
+        .await?;
+
+        Ok(pattern)
+    }
+
+    /// Delete the scope pattern, child patterns are deleted along with it
+    pub async fn delete(&self, db: impl DbExecutor<'_>) -> DbResult<PgQueryResult> {
+        sqlx::query(r#"DELETE FROM "docbox_scope_patterns" WHERE "pattern" = $1"#)
+            .bind(&self.pattern)
+            .execute(db)
+            .await
+    }
+
+    /// Prefix the pattern matches when it ends with a wildcard
+    fn prefix(&self) -> Option<&str> {
+        self.pattern.strip_suffix('*')
+    }
+
+    /// Check if the pattern matches the provided document box `scope`
+    pub fn matches(&self, scope: &str) -> bool {
+        match self.prefix() {
+            Some(prefix) => scope.starts_with(prefix),
+            None => self.pattern == scope,
+        }
+    }
+
+    /// Check if every scope matched by the `pattern` is also matched
+    /// by this pattern, used to validate child patterns
+    pub fn covers(&self, pattern: &str) -> bool {
+        match self.prefix() {
+            Some(prefix) => pattern.starts_with(prefix) && pattern != self.pattern,
+            None => false,
+        }
+    }
+
+    /// Check if the `pattern` is a valid scope pattern, wildcards are only
+    /// supported as a single trailing "*" following a non-empty prefix
+    pub fn is_valid_pattern(pattern: &str) -> bool {
+        match pattern.find('*') {
+            None => !pattern.trim().is_empty(),
+            Some(index) => index > 0 && index == pattern.len() - 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ScopePattern;
+    use chrono::Utc;
+
+    fn pattern(pattern: &str) -> ScopePattern {
+        ScopePattern {
+            pattern: pattern.to_string(),
+            parent: None,
+            description: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(pattern("org:1").matches("org:1"));
+        assert!(!pattern("org:1").matches("org:10"));
+        assert!(pattern("org:*").matches("org:1"));
+        assert!(pattern("org:*").matches("org:1:files"));
+        assert!(!pattern("org:*").matches("user:1"));
+    }
+
+    #[test]
+    fn test_covers() {
+        assert!(pattern("org:*").covers("org:1:*"));
+        assert!(pattern("org:*").covers("org:1"));
+        assert!(!pattern("org:*").covers("org:*"));
+        assert!(!pattern("org:*").covers("user:*"));
+        assert!(!pattern("org:1").covers("org:1"));
+    }
+
+    #[test]
+    fn test_is_valid_pattern() {
+        assert!(ScopePattern::is_valid_pattern("org:1"));
+        assert!(ScopePattern::is_valid_pattern("org:1:*"));
+        assert!(!ScopePattern::is_valid_pattern("*"));
+        assert!(!ScopePattern::is_valid_pattern(""));
+        assert!(!ScopePattern::is_valid_pattern("org:*:files"));
+        assert!(!ScopePattern::is_valid_pattern("org:**"));
+    }
+}
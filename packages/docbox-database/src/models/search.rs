@@ -4,7 +4,7 @@ use sqlx::prelude::FromRow;
 use uuid::Uuid;
 
 use crate::{
-    DbPool, DbResult,
+    DbExecutor, DbPool, DbResult, DbTransaction,
     models::document_box::{DocumentBoxScopeRaw, DocumentBoxScopeRawRef},
 };
 
@@ -46,6 +46,10 @@ pub struct DocboxSearchFilters {
     pub created_at: Option<DocboxSearchDateRange>,
     pub created_by: Option<String>,
     pub mime: Option<String>,
+    pub pinned: Option<bool>,
+    /// Advanced query the items must match, evaluated by the
+    /// `docbox_search_advanced_matches` function
+    pub advanced: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
@@ -87,26 +91,233 @@ pub struct SearchOptions {
     pub pages_offset: i64,
     pub limit: i64,
     pub offset: i64,
+    /// Optional statement timeout in milliseconds, when exceeded the query
+    /// is canceled by postgres
+    pub timeout_ms: Option<u64>,
 }
 
+/// SQL query used by [search], parameters are bound in the order:
+/// query, filters, max pages, pages offset, limit, offset
+pub const SEARCH_QUERY: &str = r#"
+    SELECT * FROM docbox_search($1, plainto_tsquery(docbox_search_text_config(), $1), $2, $3, $4)
+    LIMIT $5
+    OFFSET $6
+"#;
+
 pub async fn search(db: &DbPool, options: SearchOptions) -> DbResult<Vec<DocboxSearchMatchRanked>> {
+    let mut t = db.begin().await?;
+
+    // Apply the statement timeout to only this transaction
+    if let Some(timeout_ms) = options.timeout_ms {
+        set_transaction_statement_timeout(&mut t, timeout_ms).await?;
+    }
+
+    let results = sqlx::query_as(SEARCH_QUERY)
+        .bind(options.query)
+        .bind(options.filters)
+        .bind(options.max_pages)
+        .bind(options.pages_offset)
+        .bind(options.limit)
+        .bind(options.offset)
+        .fetch_all(t.as_mut())
+        .await?;
+
+    t.commit().await?;
+
+    Ok(results)
+}
+
+/// Set the statement timeout for the remainder of the transaction `t`
+async fn set_transaction_statement_timeout(
+    t: &mut DbTransaction<'_>,
+    timeout_ms: u64,
+) -> DbResult<()> {
+    sqlx::query(r#"SELECT set_config('statement_timeout', $1, TRUE)"#)
+        .bind(timeout_ms.to_string())
+        .execute(t.as_mut())
+        .await?;
+    Ok(())
+}
+
+/// Number of matching search items with a value for a field from [search_facets]
+#[derive(Debug, Clone, FromRow)]
+pub struct DocboxSearchFacetCount {
+    /// Name of the field (mime, item_type, created_by, folder_id)
+    pub field: String,
+    pub value: String,
+    pub count: i64,
+}
+
+pub struct SearchFacetOptions {
+    pub query: String,
+    pub filters: DocboxSearchFilters,
+    /// Names of the fields to count the values of
+    pub fields: Vec<String>,
+    /// Maximum number of values to count for each field
+    pub limit: i64,
+    /// Optional statement timeout in milliseconds, when exceeded the query
+    /// is canceled by postgres
+    pub timeout_ms: Option<u64>,
+}
+
+/// SQL query used by [search_facets], parameters are bound in the order:
+/// query, filters, fields, limit
+pub const SEARCH_FACETS_QUERY: &str = r#"
+    WITH "matches" AS (
+        SELECT
+            ("result"."search_match")."item_type" AS "item_type",
+            ("result"."search_match")."item_id" AS "item_id"
+        FROM docbox_search($1, plainto_tsquery(docbox_search_text_config(), $1), $2, 0, 0) "result"
+    ),
+    "items" AS (
+        SELECT
+            'File' AS "item_type",
+            "file"."mime"::TEXT AS "mime",
+            "file"."created_by"::TEXT AS "created_by",
+            "file"."folder_id"::TEXT AS "folder_id"
+        FROM "matches" "match"
+        INNER JOIN "docbox_files" "file"
+            ON "match"."item_type" = 'File' AND "file"."id" = "match"."item_id"
+        UNION ALL
+        SELECT 'Link', NULL, "link"."created_by"::TEXT, "link"."folder_id"::TEXT
+        FROM "matches" "match"
+        INNER JOIN "docbox_links" "link"
+            ON "match"."item_type" = 'Link' AND "link"."id" = "match"."item_id"
+        UNION ALL
+        SELECT 'Folder', NULL, "folder"."created_by"::TEXT, "folder"."folder_id"::TEXT
+        FROM "matches" "match"
+        INNER JOIN "docbox_folders" "folder"
+            ON "match"."item_type" = 'Folder' AND "folder"."id" = "match"."item_id"
+    ),
+    "counts" AS (
+        SELECT "facet"."field", "facet"."value", COUNT(*) AS "count"
+        FROM "items"
+        CROSS JOIN LATERAL (
+            VALUES
+                ('mime', "items"."mime"),
+                ('item_type', "items"."item_type"),
+                ('created_by', "items"."created_by"),
+                ('folder_id', "items"."folder_id")
+        ) AS "facet"("field", "value")
+        WHERE "facet"."field" = ANY($3) AND "facet"."value" IS NOT NULL
+        GROUP BY "facet"."field", "facet"."value"
+    )
+    SELECT "field", "value", "count"
+    FROM (
+        SELECT
+            "counts".*,
+            ROW_NUMBER() OVER (
+                PARTITION BY "field"
+                ORDER BY "count" DESC, "value" ASC
+            ) AS "position"
+        FROM "counts"
+    ) "ranked"
+    WHERE "position" <= $4
+    ORDER BY "field" ASC, "count" DESC, "value" ASC
+"#;
+
+/// Count the values of the facet fields for all the items matching the
+/// search query and filters
+pub async fn search_facets(
+    db: &DbPool,
+    options: SearchFacetOptions,
+) -> DbResult<Vec<DocboxSearchFacetCount>> {
+    let mut t = db.begin().await?;
+
+    if let Some(timeout_ms) = options.timeout_ms {
+        set_transaction_statement_timeout(&mut t, timeout_ms).await?;
+    }
+
+    let results = sqlx::query_as(SEARCH_FACETS_QUERY)
+        .bind(options.query)
+        .bind(options.filters)
+        .bind(options.fields)
+        .bind(options.limit)
+        .fetch_all(t.as_mut())
+        .await?;
+
+    t.commit().await?;
+
+    Ok(results)
+}
+
+/// Item name completion from [suggest]
+#[derive(Debug, Clone, FromRow)]
+pub struct DocboxSuggestMatch {
+    pub item_type: DocboxSearchItemType,
+    pub item_id: Uuid,
+    pub document_box: String,
+    pub name: String,
+}
+
+/// Find items within the `scopes` with a name, or a word within the name,
+/// starting with the `prefix`. Scopes ending with a wildcard match all scopes
+/// starting with the prefix
+///
+/// Full name matches are ordered first followed by the trigram similarity
+/// of the name to the prefix
+pub async fn suggest(
+    db: &DbPool,
+    scopes: &[DocumentBoxScopeRaw],
+    prefix: &str,
+    limit: i64,
+) -> DbResult<Vec<DocboxSuggestMatch>> {
+    let mut exact_scopes = Vec::new();
+    let mut wildcard_scopes = Vec::new();
+    for scope in scopes {
+        match scope.strip_suffix('*') {
+            Some(prefix) => wildcard_scopes.push(prefix),
+            None => exact_scopes.push(scope.as_str()),
+        }
+    }
+
     sqlx::query_as(
         r#"
-        SELECT * FROM docbox_search($1, plainto_tsquery('english', $1), $2, $3, $4)
+        WITH "items" AS (
+            SELECT 'File'::docbox_search_item_type AS "item_type", "file"."id" AS "item_id", "file"."name", "file"."folder_id"
+            FROM "docbox_files" "file"
+            UNION ALL
+            SELECT 'Link'::docbox_search_item_type, "link"."id", "link"."name", "link"."folder_id"
+            FROM "docbox_links" "link"
+            UNION ALL
+            -- Root folders are not searchable
+            SELECT 'Folder'::docbox_search_item_type, "folder"."id", "folder"."name", "folder"."folder_id"
+            FROM "docbox_folders" "folder"
+            WHERE "folder"."folder_id" IS NOT NULL
+        )
+        SELECT "items"."item_type", "items"."item_id", "folder"."document_box", "items"."name"
+        FROM "items"
+        INNER JOIN "docbox_folders" "folder" ON "items"."folder_id" = "folder"."id"
+        WHERE (
+            "folder"."document_box" = ANY($1)
+            OR EXISTS (SELECT 1 FROM unnest($2::TEXT[]) "prefix" WHERE starts_with("folder"."document_box", "prefix"))
+        )
+        AND ("items"."name" ILIKE ($3 || '%') OR "items"."name" ILIKE ('% ' || $3 || '%'))
+        ORDER BY
+            "items"."name" ILIKE ($3 || '%') DESC,
+            similarity("items"."name", $4) DESC,
+            length("items"."name") ASC,
+            "items"."name" ASC
         LIMIT $5
-        OFFSET $6
-    "#,
+        "#,
     )
-    .bind(options.query)
-    .bind(options.filters)
-    .bind(options.max_pages)
-    .bind(options.pages_offset)
-    .bind(options.limit)
-    .bind(options.offset)
+    .bind(exact_scopes)
+    .bind(wildcard_scopes)
+    .bind(escape_like_pattern(prefix))
+    .bind(prefix)
+    .bind(limit)
     .fetch_all(db)
     .await
 }
 
+/// Escape the LIKE pattern characters within `value`
+fn escape_like_pattern(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
 pub async fn search_file_pages(
     db: &DbPool,
     scope: &DocumentBoxScopeRaw,
@@ -116,7 +327,7 @@ pub async fn search_file_pages(
     offset: i64,
 ) -> DbResult<Vec<DocboxSearchPageMatch>> {
     sqlx::query_as(r#"
-        SELECT * FROM docbox_search_file_pages_with_scope($1, $2, $3, plainto_tsquery('english', $3))
+        SELECT * FROM docbox_search_file_pages_with_scope($1, $2, $3, plainto_tsquery(docbox_search_text_config(), $3))
         LIMIT $4
         OFFSET $5
     "#)
@@ -159,3 +370,54 @@ pub async fn delete_file_pages_by_file_id(db: &DbPool, file_id: Uuid) -> DbResul
     .await?;
     Ok(())
 }
+
+pub async fn delete_file_summaries_by_scope(
+    db: &DbPool,
+    scope: DocumentBoxScopeRawRef<'_>,
+) -> DbResult<()> {
+    sqlx::query(
+        r#"
+        DELETE FROM "docbox_files_summaries" AS "summary"
+        USING "docbox_files" AS "file"
+        JOIN "docbox_folders" AS "folder" ON "file"."folder_id" = "folder"."id"
+        WHERE "summary"."file_id" = "file"."id" AND "folder"."document_box" = $1;
+    "#,
+    )
+    .bind(scope)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_file_summary_by_file_id(db: &DbPool, file_id: Uuid) -> DbResult<()> {
+    sqlx::query(
+        r#"
+        DELETE FROM "docbox_files_summaries" AS "summary"
+        WHERE "summary"."file_id" = $1;
+    "#,
+    )
+    .bind(file_id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Find the text search configuration (i.e "english") used to analyze
+/// the searchable content
+pub async fn get_text_search_config(db: impl DbExecutor<'_>) -> DbResult<String> {
+    sqlx::query_scalar(r#"SELECT docbox_search_text_config()::TEXT"#)
+        .fetch_one(db)
+        .await
+}
+
+/// Set the text search configuration (i.e "german") used to analyze the
+/// searchable content, the stored search vectors are recomputed when the
+/// configuration changes
+///
+/// Returns whether the configuration was changed
+pub async fn set_text_search_config(db: impl DbExecutor<'_>, config: &str) -> DbResult<bool> {
+    sqlx::query_scalar(r#"SELECT docbox_search_set_text_config($1::regconfig)"#)
+        .bind(config)
+        .fetch_one(db)
+        .await
+}
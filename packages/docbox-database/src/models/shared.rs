@@ -3,6 +3,7 @@ use ::sqlx::{
     encode::{Encode, IsNull},
     postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo},
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{postgres::types::PgRecordEncoder, prelude::FromRow};
 use utoipa::ToSchema;
@@ -23,6 +24,13 @@ pub struct CountResult {
     pub count: i64,
 }
 
+/// Reference to a pinned item along with when it was pinned
+#[derive(Debug, FromRow)]
+pub struct PinnedItemRef {
+    pub id: Uuid,
+    pub pinned_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq, FromRow, sqlx::Type)]
 #[sqlx(type_name = "docbox_path_segment")]
 pub struct FolderPathSegment {
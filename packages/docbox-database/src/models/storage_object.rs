@@ -0,0 +1,82 @@
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+
+/// Content addressed storage object shared between files with identical
+/// contents, tracks how many files reference the object so the object is
+/// only removed from storage once nothing references it
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct StorageObject {
+    /// Key of the object within storage
+    pub file_key: String,
+    /// SHA256 hash of the object contents
+    pub hash: String,
+    /// Number of files referencing the object
+    pub reference_count: i32,
+    /// When the object was first stored
+    pub created_at: DateTime<Utc>,
+}
+
+impl StorageObject {
+    /// Add a reference to the object with `file_key`, creating the object
+    /// record if it does not exist. Provides the new reference count
+    pub async fn acquire(
+        db: impl DbExecutor<'_>,
+        file_key: &str,
+        hash: &str,
+        created_at: DateTime<Utc>,
+    ) -> DbResult<i32> {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO "docbox_storage_objects" ("file_key", "hash", "reference_count", "created_at")
+            VALUES ($1, $2, 1, $3)
+            ON CONFLICT ("file_key") DO UPDATE
+            SET "reference_count" = "docbox_storage_objects"."reference_count" + 1
+            RETURNING "reference_count"
+        "#,
+        )
+        .bind(file_key)
+        .bind(hash)
+        .bind(created_at)
+        .fetch_one(db)
+        .await
+    }
+
+    /// Remove a reference to the object with `file_key`. Provides the remaining
+    /// reference count or [None] if the key is not a content addressed object
+    pub async fn release(db: impl DbExecutor<'_>, file_key: &str) -> DbResult<Option<i32>> {
+        sqlx::query_scalar(
+            r#"
+            UPDATE "docbox_storage_objects"
+            SET "reference_count" = GREATEST("reference_count" - 1, 0)
+            WHERE "file_key" = $1
+            RETURNING "reference_count"
+        "#,
+        )
+        .bind(file_key)
+        .fetch_optional(db)
+        .await
+    }
+
+    /// Find the object with `file_key`
+    pub async fn find(db: impl DbExecutor<'_>, file_key: &str) -> DbResult<Option<StorageObject>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_storage_objects" WHERE "file_key" = $1"#)
+            .bind(file_key)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Delete the object record with `file_key` if it is no longer referenced
+    pub async fn delete_unreferenced(
+        db: impl DbExecutor<'_>,
+        file_key: &str,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"DELETE FROM "docbox_storage_objects" WHERE "file_key" = $1 AND "reference_count" = 0"#,
+        )
+        .bind(file_key)
+        .execute(db)
+        .await
+    }
+}
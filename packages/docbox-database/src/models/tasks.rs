@@ -23,6 +23,9 @@ pub struct Task {
     /// Output data from the task completion
     pub output_data: Option<serde_json::Value>,
 
+    /// Progress reported by the task while it is pending
+    pub progress: Option<serde_json::Value>,
+
     /// When the task was created
     pub created_at: DateTime<Utc>,
 
@@ -44,6 +47,7 @@ impl PartialEq for Task {
             && self.document_box.eq(&other.document_box)
             && self.status.eq(&other.status)
             && self.output_data.eq(&self.output_data)
+            && self.progress.eq(&other.progress)
             // Reduce precision when checking creation timestamp
             // (Database does not store the full precision)
             && self
@@ -56,6 +60,15 @@ impl PartialEq for Task {
     }
 }
 
+/// Number of tasks with a specific status
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct TaskStatusCount {
+    /// Status of the tasks
+    pub status: TaskStatus,
+    /// Number of tasks with the status
+    pub count: i64,
+}
+
 #[derive(
     Debug,
     Clone,
@@ -121,6 +134,7 @@ impl Task {
             document_box,
             status,
             output_data: None,
+            progress: None,
             created_at,
             completed_at: None,
         })
@@ -138,6 +152,43 @@ impl Task {
             .await
     }
 
+    /// Find a specific task by ID regardless of the document box it belongs to
+    pub async fn find_by_id(db: impl DbExecutor<'_>, id: TaskId) -> DbResult<Option<Task>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_tasks" WHERE "id" = $1"#)
+            .bind(id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Find all tasks that are still pending and were created before
+    /// the `before` date, oldest tasks first
+    pub async fn find_stuck(db: impl DbExecutor<'_>, before: DateTime<Utc>) -> DbResult<Vec<Task>> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM "docbox_tasks"
+            WHERE "status" = $1 AND "created_at" < $2
+            ORDER BY "created_at" ASC
+            "#,
+        )
+        .bind(TaskStatus::Pending.to_string())
+        .bind(before)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Get the number of tasks with each status
+    pub async fn status_counts(db: impl DbExecutor<'_>) -> DbResult<Vec<TaskStatusCount>> {
+        sqlx::query_as(
+            r#"
+            SELECT "status", COUNT(*) AS "count" FROM "docbox_tasks"
+            GROUP BY "status"
+            ORDER BY "status"
+            "#,
+        )
+        .fetch_all(db)
+        .await
+    }
+
     /// Mark the task as completed and set its output data
     pub async fn complete_task(
         &mut self,
@@ -168,6 +219,23 @@ impl Task {
         Ok(())
     }
 
+    /// Update the progress reported by the task
+    pub async fn set_progress(
+        &mut self,
+        db: impl DbExecutor<'_>,
+        progress: serde_json::Value,
+    ) -> DbResult<()> {
+        sqlx::query(r#"UPDATE "docbox_tasks" SET "progress" = $1 WHERE "id" = $2"#)
+            .bind(&progress)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        self.progress = Some(progress);
+
+        Ok(())
+    }
+
     /// Deletes all tasks where the creation date is older than the `before` date
     pub async fn delete_expired(
         db: impl DbExecutor<'_>,
@@ -1,6 +1,9 @@
 use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use std::str::FromStr;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 pub type TenantId = Uuid;
@@ -30,6 +33,105 @@ pub struct Tenant {
     pub env: String,
     /// Optional event queue (SQS) to send docbox events to
     pub event_queue_url: Option<String>,
+    /// Name of the secret containing the storage encryption keys when
+    /// the tenant files are encrypted at rest
+    #[sqlx(default)]
+    pub storage_key_secret_name: Option<String>,
+    /// Whether files with identical contents within a document box
+    /// share a single stored object
+    #[sqlx(default)]
+    pub storage_deduplication: bool,
+    /// Configuration for which events are published to the event queue,
+    /// all events are published in full when not configured
+    #[sqlx(default, json(nullable))]
+    pub event_config: Option<TenantEventConfig>,
+    /// Data residency region the tenant data must be stored within (i.e "eu-west"),
+    /// the storage and search backends must be configured for the same region
+    #[sqlx(default)]
+    pub data_region: Option<String>,
+    /// Whether the tenant is in maintenance mode, while in maintenance mode
+    /// requests that modify the tenant data are rejected and background
+    /// processing for the tenant is paused
+    #[sqlx(default)]
+    pub maintenance_mode: bool,
+    /// How raw HTML and SVG files that may contain active content
+    /// (i.e scripts) are served to clients
+    #[sqlx(default, try_from = "String")]
+    pub active_content_policy: ActiveContentPolicy,
+    /// When the tenant was soft deleted, soft deleted tenants are disabled
+    /// but can be restored until they are permanently deleted
+    #[sqlx(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Language used to analyze the tenant search index content (i.e "german"),
+    /// overrides the language configured for the search backend
+    #[sqlx(default)]
+    pub search_language: Option<String>,
+}
+
+/// Configuration for the events a tenant publishes
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct TenantEventConfig {
+    /// Names of the events to publish (i.e "FILE_DELETED"), all events
+    /// are published when empty
+    pub include: Vec<String>,
+    /// Names of the events to never publish
+    pub exclude: Vec<String>,
+    /// Level of detail included in the published events
+    pub detail: TenantEventDetail,
+}
+
+/// Level of detail included in published events
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TenantEventDetail {
+    /// Events contain the full item data
+    #[default]
+    Full,
+    /// Events only contain the identifiers of the item
+    Ids,
+}
+
+impl TenantEventConfig {
+    /// Check if the event with the provided `name` should be published
+    pub fn should_publish(&self, name: &str) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|event| event == name);
+        let excluded = self.exclude.iter().any(|event| event == name);
+        included && !excluded
+    }
+}
+
+/// Policy for serving raw files containing active content such as HTML
+/// and SVG files which can contain scripts
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    strum::EnumString,
+    strum::Display,
+    Deserialize,
+    Serialize,
+    ToSchema,
+    PartialEq,
+    Eq,
+)]
+pub enum ActiveContentPolicy {
+    /// Files are served with their own content type under a sandboxed
+    /// content security policy that prevents scripts from running
+    #[default]
+    Sandbox,
+    /// Files are served as plain text showing the source without
+    /// rendering it
+    PlainText,
+    /// Files are always served as a download and never rendered
+    Download,
+}
+
+impl TryFrom<String> for ActiveContentPolicy {
+    type Error = strum::ParseError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        ActiveContentPolicy::from_str(&value)
+    }
 }
 
 /// Structure for fields required when creating a
@@ -44,6 +146,7 @@ pub struct CreateTenant {
     pub os_index_name: String,
     pub event_queue_url: Option<String>,
     pub env: String,
+    pub data_region: Option<String>,
 }
 
 /// Bulk update for tenant fields
@@ -59,6 +162,14 @@ pub struct UpdateTenant {
     pub os_index_name: Option<String>,
     pub env: Option<String>,
     pub event_queue_url: Option<Option<String>>,
+    pub storage_key_secret_name: Option<Option<String>>,
+    pub storage_deduplication: Option<bool>,
+    pub event_config: Option<Option<TenantEventConfig>>,
+    pub data_region: Option<Option<String>>,
+    pub maintenance_mode: Option<bool>,
+    pub active_content_policy: Option<ActiveContentPolicy>,
+    pub deleted_at: Option<Option<DateTime<Utc>>>,
+    pub search_language: Option<Option<String>>,
 }
 
 impl Tenant {
@@ -75,9 +186,10 @@ impl Tenant {
                 "s3_name",
                 "os_index_name",
                 "env",
-                "event_queue_url"
+                "event_queue_url",
+                "data_region"
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
         "#,
         )
         .bind(create.id)
@@ -89,6 +201,7 @@ impl Tenant {
         .bind(create.os_index_name.as_str())
         .bind(create.env.as_str())
         .bind(create.event_queue_url.as_ref())
+        .bind(create.data_region.as_ref())
         .execute(db)
         .await?;
 
@@ -102,6 +215,14 @@ impl Tenant {
             os_index_name: create.os_index_name,
             env: create.env,
             event_queue_url: create.event_queue_url,
+            storage_key_secret_name: None,
+            storage_deduplication: false,
+            event_config: None,
+            data_region: create.data_region,
+            maintenance_mode: false,
+            active_content_policy: ActiveContentPolicy::default(),
+            deleted_at: None,
+            search_language: None,
         })
     }
 
@@ -119,6 +240,14 @@ impl Tenant {
             os_index_name,
             env,
             event_queue_url,
+            storage_key_secret_name,
+            storage_deduplication,
+            event_config,
+            data_region,
+            maintenance_mode,
+            active_content_policy,
+            deleted_at,
+            search_language,
         }: UpdateTenant,
     ) -> DbResult<()> {
         sqlx::query(
@@ -133,7 +262,15 @@ impl Tenant {
                 "s3_name" = COALESCE($8, "s3_name"),
                 "os_index_name" = COALESCE($9, "os_index_name"),
                 "env" = COALESCE($10, "env"),
-                "event_queue_url" = COALESCE($11, "event_queue_url")
+                "event_queue_url" = COALESCE($11, "event_queue_url"),
+                "storage_key_secret_name" = COALESCE($12, "storage_key_secret_name"),
+                "storage_deduplication" = COALESCE($13, "storage_deduplication"),
+                "event_config" = CASE WHEN $14 THEN $15 ELSE "event_config" END,
+                "data_region" = CASE WHEN $16 THEN $17 ELSE "data_region" END,
+                "maintenance_mode" = COALESCE($18, "maintenance_mode"),
+                "deleted_at" = CASE WHEN $19 THEN $20 ELSE "deleted_at" END,
+                "active_content_policy" = COALESCE($21, "active_content_policy"),
+                "search_language" = CASE WHEN $22 THEN $23 ELSE "search_language" END
             WHERE "id" = $1 AND "env" = $2
             "#,
         )
@@ -150,6 +287,18 @@ impl Tenant {
         .bind(os_index_name.clone())
         .bind(env.clone())
         .bind(event_queue_url.clone())
+        .bind(storage_key_secret_name.clone())
+        .bind(storage_deduplication)
+        .bind(event_config.is_some())
+        .bind(event_config.clone().flatten().map(sqlx::types::Json))
+        .bind(data_region.is_some())
+        .bind(data_region.clone().flatten())
+        .bind(maintenance_mode)
+        .bind(deleted_at.is_some())
+        .bind(deleted_at.flatten())
+        .bind(active_content_policy.map(|policy| policy.to_string()))
+        .bind(search_language.is_some())
+        .bind(search_language.clone().flatten())
         .fetch_optional(db)
         .await?;
 
@@ -164,6 +313,14 @@ impl Tenant {
             os_index_name,
             env,
             event_queue_url,
+            storage_key_secret_name,
+            storage_deduplication,
+            event_config,
+            data_region,
+            maintenance_mode,
+            active_content_policy,
+            deleted_at,
+            search_language,
         );
 
         Ok(())
@@ -198,6 +355,19 @@ impl Tenant {
             .await
     }
 
+    /// Finds all soft deleted tenants that were deleted before `before`
+    pub async fn find_deleted_before(
+        db: impl DbExecutor<'_>,
+        before: DateTime<Utc>,
+    ) -> DbResult<Vec<Tenant>> {
+        sqlx::query_as(
+            r#"SELECT * FROM "docbox_tenants" WHERE "deleted_at" < $1 ORDER BY "deleted_at""#,
+        )
+        .bind(before)
+        .fetch_all(db)
+        .await
+    }
+
     /// Finds all tenants
     pub async fn all(db: impl DbExecutor<'_>) -> DbResult<Vec<Tenant>> {
         sqlx::query_as(r#"SELECT * FROM "docbox_tenants" ORDER BY "name""#)
@@ -215,3 +385,27 @@ impl Tenant {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::TenantEventConfig;
+
+    #[test]
+    fn test_should_publish_default() {
+        let config = TenantEventConfig::default();
+        assert!(config.should_publish("FILE_CREATED"));
+    }
+
+    #[test]
+    fn test_should_publish_include_exclude() {
+        let config = TenantEventConfig {
+            include: vec!["FILE_DELETED".to_string(), "FOLDER_DELETED".to_string()],
+            exclude: vec!["FOLDER_DELETED".to_string()],
+            ..Default::default()
+        };
+
+        assert!(config.should_publish("FILE_DELETED"));
+        assert!(!config.should_publish("FOLDER_DELETED"));
+        assert!(!config.should_publish("FILE_CREATED"));
+    }
+}
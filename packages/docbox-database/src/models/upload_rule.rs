@@ -0,0 +1,335 @@
+use crate::{DbExecutor, DbResult, DbTransaction};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use std::{fmt::Display, ops::DerefMut, str::FromStr};
+use utoipa::ToSchema;
+
+/// What an [UploadRule] pattern is matched against
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    strum::EnumString,
+    strum::Display,
+    Deserialize,
+    Serialize,
+    ToSchema,
+    PartialEq,
+    Eq,
+)]
+pub enum UploadRuleKind {
+    /// Pattern matches the file mime type, either an exact mime type
+    /// ("image/png"), all subtypes of a type ("image/*") or all files ("*")
+    Mime,
+    /// Pattern matches the file name extension without the leading dot ("pdf")
+    /// or all files ("*")
+    Extension,
+}
+
+impl TryFrom<String> for UploadRuleKind {
+    type Error = strum::ParseError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        UploadRuleKind::from_str(&value)
+    }
+}
+
+/// Action taken for files matching an [UploadRule]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    strum::EnumString,
+    strum::Display,
+    Deserialize,
+    Serialize,
+    ToSchema,
+    PartialEq,
+    Eq,
+)]
+pub enum UploadRuleAction {
+    /// Files matching the rule are allowed. Once any allow rule exists for a
+    /// kind, files must match at least one allow rule of that kind
+    Allow,
+    /// Files matching the rule are rejected
+    Deny,
+    /// Files matching the rule are only restricted by the rule max size
+    Limit,
+}
+
+impl TryFrom<String> for UploadRuleAction {
+    type Error = strum::ParseError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        UploadRuleAction::from_str(&value)
+    }
+}
+
+/// Rule restricting which files can be uploaded to a tenant
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct UploadRule {
+    /// What the pattern is matched against
+    #[sqlx(try_from = "String")]
+    pub kind: UploadRuleKind,
+    /// Pattern to match
+    pub pattern: String,
+    /// Action to take for matching files
+    #[sqlx(try_from = "String")]
+    pub action: UploadRuleAction,
+    /// Maximum size in bytes for matching files
+    pub max_size: Option<i64>,
+}
+
+/// Violated upload rule
+#[derive(Debug, Clone, Serialize, ToSchema, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UploadRuleViolation {
+    /// File matched a deny rule
+    Denied {
+        /// The deny rule that matched
+        rule: UploadRule,
+    },
+    /// Allow rules exist for the kind but none of them matched the file
+    NotAllowed {
+        /// Kind of rule that was not matched
+        kind: UploadRuleKind,
+        /// Value that did not match any allow rule
+        value: String,
+    },
+    /// File is larger than the max size of a matching rule
+    TooLarge {
+        /// The rule with the exceeded max size
+        rule: UploadRule,
+        /// Size of the file in bytes
+        size: i64,
+    },
+}
+
+impl Display for UploadRuleViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UploadRuleViolation::Denied { rule } => write!(
+                f,
+                "file is denied by the {} rule \"{}\"",
+                rule.kind, rule.pattern
+            ),
+            UploadRuleViolation::NotAllowed { kind, value } => {
+                write!(f, "{kind} \"{value}\" is not allowed")
+            }
+            UploadRuleViolation::TooLarge { rule, size } => write!(
+                f,
+                "file size {size} exceeds the maximum size {} of the {} rule \"{}\"",
+                rule.max_size.unwrap_or_default(),
+                rule.kind,
+                rule.pattern
+            ),
+        }
+    }
+}
+
+impl UploadRule {
+    /// Get all the upload rules
+    pub async fn all(db: impl DbExecutor<'_>) -> DbResult<Vec<UploadRule>> {
+        sqlx::query_as(r#"SELECT * FROM "docbox_upload_rules""#)
+            .fetch_all(db)
+            .await
+    }
+
+    /// Replace all the existing rules with `rules`
+    pub async fn replace_all(db: &mut DbTransaction<'_>, rules: &[UploadRule]) -> DbResult<()> {
+        sqlx::query(r#"DELETE FROM "docbox_upload_rules""#)
+            .execute(db.deref_mut())
+            .await?;
+
+        for rule in rules {
+            sqlx::query(
+                r#"
+                INSERT INTO "docbox_upload_rules" ("kind", "pattern", "action", "max_size")
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT ("kind", "pattern") DO UPDATE
+                SET
+                    "action" = EXCLUDED."action",
+                    "max_size" = EXCLUDED."max_size"
+            "#,
+            )
+            .bind(rule.kind.to_string())
+            .bind(rule.pattern.as_str())
+            .bind(rule.action.to_string())
+            .bind(rule.max_size)
+            .execute(db.deref_mut())
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Check if the rule matches a file with the provided `mime` essence
+    /// and file name `extension`
+    pub fn matches(&self, mime: &str, extension: Option<&str>) -> bool {
+        if self.pattern == "*" {
+            return true;
+        }
+
+        match self.kind {
+            UploadRuleKind::Mime => match self.pattern.strip_suffix("/*") {
+                Some(ty) => mime
+                    .split_once('/')
+                    .is_some_and(|(mime_ty, _)| mime_ty.eq_ignore_ascii_case(ty)),
+                None => self.pattern.eq_ignore_ascii_case(mime),
+            },
+            UploadRuleKind::Extension => extension.is_some_and(|extension| {
+                self.pattern
+                    .trim_start_matches('.')
+                    .eq_ignore_ascii_case(extension.trim_start_matches('.'))
+            }),
+        }
+    }
+
+    /// Check a file with the provided `mime` essence, file name `extension`
+    /// and `size` in bytes against the `rules`
+    ///
+    /// Deny rules are checked first, followed by allow rules then the
+    /// max size of any matching allow or limit rules
+    pub fn check(
+        rules: &[UploadRule],
+        mime: &str,
+        extension: Option<&str>,
+        size: i64,
+    ) -> Result<(), UploadRuleViolation> {
+        let mut matching = rules.iter().filter(|rule| rule.matches(mime, extension));
+
+        if let Some(rule) = matching
+            .clone()
+            .find(|rule| rule.action == UploadRuleAction::Deny)
+        {
+            return Err(UploadRuleViolation::Denied { rule: rule.clone() });
+        }
+
+        for (kind, value) in [
+            (UploadRuleKind::Mime, mime),
+            (UploadRuleKind::Extension, extension.unwrap_or_default()),
+        ] {
+            let mut allow_rules = rules
+                .iter()
+                .filter(|rule| rule.kind == kind && rule.action == UploadRuleAction::Allow)
+                .peekable();
+
+            if allow_rules.peek().is_some()
+                && !allow_rules.any(|rule| rule.matches(mime, extension))
+            {
+                return Err(UploadRuleViolation::NotAllowed {
+                    kind,
+                    value: value.to_string(),
+                });
+            }
+        }
+
+        if let Some(rule) = matching.find(|rule| {
+            rule.action != UploadRuleAction::Deny
+                && rule.max_size.is_some_and(|max_size| size > max_size)
+        }) {
+            return Err(UploadRuleViolation::TooLarge {
+                rule: rule.clone(),
+                size,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{UploadRule, UploadRuleAction, UploadRuleKind, UploadRuleViolation};
+
+    fn rule(
+        kind: UploadRuleKind,
+        pattern: &str,
+        action: UploadRuleAction,
+        max_size: Option<i64>,
+    ) -> UploadRule {
+        UploadRule {
+            kind,
+            pattern: pattern.to_string(),
+            action,
+            max_size,
+        }
+    }
+
+    #[test]
+    fn test_check_no_rules() {
+        assert!(UploadRule::check(&[], "text/plain", Some("txt"), 100).is_ok());
+    }
+
+    #[test]
+    fn test_check_deny() {
+        let rules = vec![rule(
+            UploadRuleKind::Extension,
+            "exe",
+            UploadRuleAction::Deny,
+            None,
+        )];
+
+        assert_eq!(
+            UploadRule::check(&rules, "application/octet-stream", Some("EXE"), 100),
+            Err(UploadRuleViolation::Denied {
+                rule: rules[0].clone()
+            })
+        );
+        assert!(UploadRule::check(&rules, "text/plain", Some("txt"), 100).is_ok());
+    }
+
+    #[test]
+    fn test_check_allow() {
+        let rules = vec![
+            rule(
+                UploadRuleKind::Mime,
+                "image/*",
+                UploadRuleAction::Allow,
+                None,
+            ),
+            rule(
+                UploadRuleKind::Mime,
+                "application/pdf",
+                UploadRuleAction::Allow,
+                None,
+            ),
+        ];
+
+        assert!(UploadRule::check(&rules, "image/png", Some("png"), 100).is_ok());
+        assert!(UploadRule::check(&rules, "application/pdf", None, 100).is_ok());
+        assert_eq!(
+            UploadRule::check(&rules, "text/plain", Some("txt"), 100),
+            Err(UploadRuleViolation::NotAllowed {
+                kind: UploadRuleKind::Mime,
+                value: "text/plain".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_max_size() {
+        let rules = vec![
+            rule(
+                UploadRuleKind::Mime,
+                "video/*",
+                UploadRuleAction::Limit,
+                Some(1000),
+            ),
+            rule(
+                UploadRuleKind::Extension,
+                "*",
+                UploadRuleAction::Limit,
+                None,
+            ),
+        ];
+
+        assert!(UploadRule::check(&rules, "video/mp4", Some("mp4"), 1000).is_ok());
+        assert!(UploadRule::check(&rules, "text/plain", Some("txt"), 5000).is_ok());
+        assert_eq!(
+            UploadRule::check(&rules, "video/mp4", Some("mp4"), 1001),
+            Err(UploadRuleViolation::TooLarge {
+                rule: rules[0].clone(),
+                size: 1001
+            })
+        );
+    }
+}
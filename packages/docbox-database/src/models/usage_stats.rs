@@ -0,0 +1,187 @@
+use crate::{DbExecutor, DbResult};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgQueryResult, prelude::FromRow};
+use utoipa::ToSchema;
+
+/// Usage statistics for a tenant on a single day, the rollup is
+/// maintained by a background task
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema, PartialEq, Eq)]
+pub struct UsageStatsDay {
+    /// Day the statistics are for (UTC)
+    pub day: NaiveDate,
+    /// Number of files created on the day
+    pub files_created: i64,
+    /// Total size in bytes of the files created on the day
+    pub bytes_uploaded: i64,
+    /// Number of tasks or presigned uploads that failed on the day
+    pub processing_failures: i64,
+    /// Total number of files at the end of the day
+    pub total_files: i64,
+    /// Total size in bytes of all files at the end of the day
+    pub total_bytes: i64,
+    /// When the statistics for the day were last computed
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Granularity of the usage statistics time-series
+#[derive(
+    Debug, Default, Clone, Copy, strum::Display, Deserialize, Serialize, ToSchema, PartialEq, Eq,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum UsageStatsGranularity {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+/// Usage statistics for a single period of the time-series
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema, PartialEq, Eq)]
+pub struct UsageStatsPoint {
+    /// First day of the period
+    pub period_start: NaiveDate,
+    /// Number of files created within the period
+    pub files_created: i64,
+    /// Total size in bytes of the files created within the period
+    pub bytes_uploaded: i64,
+    /// Number of tasks or presigned uploads that failed within the period
+    pub processing_failures: i64,
+    /// Total number of files at the end of the period
+    pub total_files: i64,
+    /// Total size in bytes of all files at the end of the period
+    pub total_bytes: i64,
+}
+
+impl UsageStatsDay {
+    /// Compute and store the statistics for `day` from the current
+    /// files and tasks, replacing any previously computed statistics
+    pub async fn rollup(
+        db: impl DbExecutor<'_>,
+        day: NaiveDate,
+        now: DateTime<Utc>,
+    ) -> DbResult<PgQueryResult> {
+        sqlx::query(
+            r#"
+            INSERT INTO "docbox_usage_stats" (
+                "day",
+                "files_created",
+                "bytes_uploaded",
+                "processing_failures",
+                "total_files",
+                "total_bytes",
+                "updated_at"
+            )
+            SELECT
+                $1,
+                "created"."count",
+                "created"."size",
+                "failed_tasks"."count" + "failed_presigned"."count",
+                "totals"."count",
+                "totals"."size",
+                $2
+            FROM
+                (
+                    SELECT COUNT(*) AS "count", COALESCE(SUM("size"), 0)::BIGINT AS "size"
+                    FROM "docbox_files"
+                    WHERE ("created_at" AT TIME ZONE 'UTC')::DATE = $1
+                ) AS "created",
+                (
+                    SELECT COUNT(*) AS "count", COALESCE(SUM("size"), 0)::BIGINT AS "size"
+                    FROM "docbox_files"
+                    WHERE ("created_at" AT TIME ZONE 'UTC')::DATE <= $1
+                ) AS "totals",
+                (
+                    SELECT COUNT(*) AS "count"
+                    FROM "docbox_tasks"
+                    WHERE "status" = 'Failed'
+                        AND ("created_at" AT TIME ZONE 'UTC')::DATE = $1
+                ) AS "failed_tasks",
+                (
+                    SELECT COUNT(*) AS "count"
+                    FROM "docbox_presigned_upload_tasks"
+                    WHERE "status"->>'status' = 'Failed'
+                        AND ("created_at" AT TIME ZONE 'UTC')::DATE = $1
+                ) AS "failed_presigned"
+            ON CONFLICT ("day") DO UPDATE
+            SET
+                "files_created" = EXCLUDED."files_created",
+                "bytes_uploaded" = EXCLUDED."bytes_uploaded",
+                -- Failed tasks are purged over time, keep the highest known count
+                "processing_failures" = GREATEST(
+                    "docbox_usage_stats"."processing_failures",
+                    EXCLUDED."processing_failures"
+                ),
+                "total_files" = EXCLUDED."total_files",
+                "total_bytes" = EXCLUDED."total_bytes",
+                "updated_at" = EXCLUDED."updated_at"
+        "#,
+        )
+        .bind(day)
+        .bind(now)
+        .execute(db)
+        .await
+    }
+
+    /// Get the most recent day that has computed statistics
+    pub async fn latest_day(db: impl DbExecutor<'_>) -> DbResult<Option<NaiveDate>> {
+        let result: (Option<NaiveDate>,) =
+            sqlx::query_as(r#"SELECT MAX("day") FROM "docbox_usage_stats""#)
+                .fetch_one(db)
+                .await?;
+
+        Ok(result.0)
+    }
+
+    /// Find the daily statistics between `start` and `end` (inclusive)
+    pub async fn find_range(
+        db: impl DbExecutor<'_>,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> DbResult<Vec<UsageStatsDay>> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM "docbox_usage_stats"
+            WHERE "day" BETWEEN $1 AND $2
+            ORDER BY "day" ASC
+        "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(db)
+        .await
+    }
+}
+
+impl UsageStatsPoint {
+    /// Query the time-series of statistics between `start` and `end` (inclusive)
+    /// grouped into periods of the provided `granularity`
+    pub async fn series(
+        db: impl DbExecutor<'_>,
+        granularity: UsageStatsGranularity,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> DbResult<Vec<UsageStatsPoint>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                DATE_TRUNC($1, "day"::TIMESTAMP)::DATE AS "period_start",
+                SUM("files_created")::BIGINT AS "files_created",
+                SUM("bytes_uploaded")::BIGINT AS "bytes_uploaded",
+                SUM("processing_failures")::BIGINT AS "processing_failures",
+                (ARRAY_AGG("total_files" ORDER BY "day" DESC))[1] AS "total_files",
+                (ARRAY_AGG("total_bytes" ORDER BY "day" DESC))[1] AS "total_bytes"
+            FROM "docbox_usage_stats"
+            WHERE "day" BETWEEN $2 AND $3
+            GROUP BY "period_start"
+            ORDER BY "period_start" ASC
+        "#,
+        )
+        .bind(granularity.to_string())
+        .bind(start)
+        .bind(end)
+        .fetch_all(db)
+        .await
+    }
+}
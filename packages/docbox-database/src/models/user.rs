@@ -1,11 +1,11 @@
 use crate::{DbExecutor, DbResult, models::shared::CountResult};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgQueryResult, prelude::FromRow};
 use utoipa::ToSchema;
 
 pub type UserId = String;
 
-#[derive(Debug, Clone, Serialize, FromRow, ToSchema, sqlx::Type, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema, sqlx::Type, PartialEq, Eq)]
 #[sqlx(type_name = "docbox_user")]
 pub struct User {
     /// Unique ID of the user
@@ -6,8 +6,15 @@
 //! Pools are held in a cache with an expiry time to ensure they don't
 //! hog too many database connections.
 //!
-//! Database pools and credentials are stored in a Tiny LFU cache these caches
-//! can be flushed using [DatabasePoolCache::flush]
+//! Database pools are stored in a LRU cache, when the cache reaches its capacity
+//! the least recently used pool is closed to make room for the new pool. Pools that
+//! are idle for longer than the cache duration are also closed. Counts of the evicted
+//! pools are available through [DatabasePoolCache::stats] and the individual
+//! pools held in the cache can be listed using [DatabasePoolCache::pools]
+//!
+//! Database credentials are stored in a Tiny LFU cache, both caches can be flushed
+//! using [DatabasePoolCache::flush] or the pool and credentials for a single tenant
+//! can be removed using [DatabasePoolCache::close_tenant_pool]
 //!
 //! ## Environment Variables
 //!
@@ -21,9 +28,34 @@
 //! * `DOCBOX_DB_POOL_TIMEOUT` - Maximum time a connection can live in the cache for
 //! * `DOCBOX_DB_IDLE_TIMEOUT` - Timeout before a idle connection is closed to save resources
 //! * `DOCBOX_DB_CACHE_DURATION` - Duration pools can remain in the cache for untouched before they are closed and removed
-//! * `DOCBOX_DB_CACHE_CAPACITY` - Maximum database pools to hold at once
+//! * `DOCBOX_DB_CACHE_CAPACITY` - Maximum database pools to hold at once, least recently used pools are closed when exceeded
 //! * `DOCBOX_DB_CREDENTIALS_CACHE_DURATION` - Duration database credentials should be cached for
 //! * `DOCBOX_DB_CREDENTIALS_CACHE_CAPACITY` - Maximum database credentials to cache
+//! * `DOCBOX_DB_STATEMENT_TIMEOUT` - Timeout in milliseconds before statements on tenant databases are cancelled
+//! * `DOCBOX_DB_STATEMENT_TIMEOUT_ROOT` - Timeout in milliseconds before statements on the root "docbox" database are cancelled
+//! * `DOCBOX_DB_SLOW_QUERY_THRESHOLD` - Duration in milliseconds after which a query is logged as a slow query
+//! * `DOCBOX_DB_EXTERNAL_POOLER` - Whether connections are made through a transaction pooling pooler (i.e PgBouncer)
+//!
+//! ## External Poolers
+//!
+//! When connecting through a transaction pooling pooler such as PgBouncer set
+//! `DOCBOX_DB_EXTERNAL_POOLER=true`. In this mode prepared statements are not
+//! cached (A cached statement may not exist on the server connection the pooler
+//! assigns to the next transaction) and no session settings are sent as startup
+//! parameters, which poolers reject.
+//!
+//! Session level `SET` commands cannot be used either, each transaction may run
+//! on a different server connection so the settings would leak to other clients
+//! of the pooler. The search path and statement timeout must instead be set on
+//! the database roles (Or within the pooler configuration):
+//!
+//! ```sql
+//! ALTER ROLE <role> SET search_path TO public;
+//! ALTER ROLE <role> SET statement_timeout = 30000;
+//! ```
+//!
+//! `DOCBOX_DB_STATEMENT_TIMEOUT` and `DOCBOX_DB_STATEMENT_TIMEOUT_ROOT` are
+//! ignored in this mode
 
 use crate::{DbErr, DbPool, ROOT_DATABASE_NAME, ROOT_DATABASE_ROLE_NAME, models::tenant::Tenant};
 use aws_config::SdkConfig;
@@ -32,18 +64,22 @@ use aws_sigv4::{
     http_request::{SignableBody, SignableRequest, SigningError, SigningSettings, sign},
     sign::v4::signing_params,
 };
+use chrono::{DateTime, Utc};
 use docbox_secrets::{SecretManager, SecretManagerError};
-use moka::{future::Cache, policy::EvictionPolicy};
+use log::LevelFilter;
+use moka::{future::Cache, notification::RemovalCause, policy::EvictionPolicy};
 use serde::{Deserialize, Serialize};
 use sqlx::{
-    PgPool,
+    ConnectOptions, PgPool,
     postgres::{PgConnectOptions, PgPoolOptions},
 };
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use std::time::Duration;
 use std::{num::ParseIntError, str::ParseBoolError};
 use std::{sync::Arc, time::SystemTime};
 use thiserror::Error;
 use tokio::time::sleep;
+use utoipa::ToSchema;
 
 ///  Config for the database pool
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -113,8 +149,8 @@ pub struct DatabasePoolCacheConfig {
     pub cache_duration: Option<u64>,
 
     /// Maximum database pools to maintain in the cache at once. If the
-    /// cache capacity is exceeded old pools will be closed and removed
-    /// from the cache
+    /// cache capacity is exceeded the least recently used pools will be
+    /// closed and removed from the cache
     ///
     /// This capacity should be aligned with your expected number of
     /// tenants along with your `max_connections` to ensure your database
@@ -135,6 +171,38 @@ pub struct DatabasePoolCacheConfig {
     ///
     /// Default: 50
     pub credentials_cache_capacity: Option<u64>,
+
+    /// Timeout in milliseconds before a statement running against a tenant
+    /// database is cancelled, prevents runaway queries (i.e large searches
+    /// or listings) from holding tenant database resources
+    ///
+    /// Default: No timeout
+    pub statement_timeout: Option<u64>,
+
+    /// Timeout in milliseconds before a statement running against the root
+    /// "docbox" database is cancelled
+    ///
+    /// Default: No timeout
+    pub statement_timeout_root: Option<u64>,
+
+    /// Duration in milliseconds after which a completed query is logged as a
+    /// slow query. Slow queries are logged at the WARN level within the span
+    /// of the request that performed them (Including the tenant and route)
+    ///
+    /// Default: 1000ms
+    pub slow_query_threshold: Option<u64>,
+
+    /// Whether connections are made through an external transaction pooling
+    /// pooler (i.e PgBouncer) rather than directly to the database
+    ///
+    /// Disables prepared statement caching and the statement timeout startup
+    /// parameter. The pooler server connections are shared between clients so
+    /// the `search_path` and `statement_timeout` must be set on the database
+    /// roles instead (i.e `ALTER ROLE <role> SET search_path TO public`), the
+    /// [DatabasePoolCacheConfig::statement_timeout] and
+    /// [DatabasePoolCacheConfig::statement_timeout_root] are ignored
+    #[serde(default)]
+    pub external_pooler: bool,
 }
 
 impl Default for DatabasePoolCacheConfig {
@@ -153,6 +221,10 @@ impl Default for DatabasePoolCacheConfig {
             cache_capacity: None,
             credentials_cache_duration: None,
             credentials_cache_capacity: None,
+            statement_timeout: None,
+            statement_timeout_root: None,
+            slow_query_threshold: None,
+            external_pooler: false,
         }
     }
 }
@@ -183,6 +255,14 @@ pub enum DatabasePoolCacheConfigError {
     InvalidCredentialsCacheCapacity(ParseIntError),
     #[error("invalid DOCBOX_DB_ROOT_IAM environment variable")]
     InvalidRootIam(ParseBoolError),
+    #[error("invalid DOCBOX_DB_STATEMENT_TIMEOUT environment variable")]
+    InvalidStatementTimeout(ParseIntError),
+    #[error("invalid DOCBOX_DB_STATEMENT_TIMEOUT_ROOT environment variable")]
+    InvalidStatementTimeoutRoot(ParseIntError),
+    #[error("invalid DOCBOX_DB_SLOW_QUERY_THRESHOLD environment variable")]
+    InvalidSlowQueryThreshold(ParseIntError),
+    #[error("invalid DOCBOX_DB_EXTERNAL_POOLER environment variable")]
+    InvalidExternalPooler(ParseBoolError),
 }
 
 impl DatabasePoolCacheConfig {
@@ -281,6 +361,42 @@ impl DatabasePoolCacheConfig {
                 Err(_) => None,
             };
 
+        let statement_timeout: Option<u64> = match std::env::var("DOCBOX_DB_STATEMENT_TIMEOUT") {
+            Ok(value) => Some(
+                value
+                    .parse::<u64>()
+                    .map_err(DatabasePoolCacheConfigError::InvalidStatementTimeout)?,
+            ),
+            Err(_) => None,
+        };
+
+        let statement_timeout_root: Option<u64> =
+            match std::env::var("DOCBOX_DB_STATEMENT_TIMEOUT_ROOT") {
+                Ok(value) => Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(DatabasePoolCacheConfigError::InvalidStatementTimeoutRoot)?,
+                ),
+                Err(_) => None,
+            };
+
+        let slow_query_threshold: Option<u64> =
+            match std::env::var("DOCBOX_DB_SLOW_QUERY_THRESHOLD") {
+                Ok(value) => Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(DatabasePoolCacheConfigError::InvalidSlowQueryThreshold)?,
+                ),
+                Err(_) => None,
+            };
+
+        let external_pooler = std::env::var("DOCBOX_DB_EXTERNAL_POOLER")
+            .ok()
+            .map(|value| value.parse::<bool>())
+            .transpose()
+            .map_err(DatabasePoolCacheConfigError::InvalidExternalPooler)?
+            .unwrap_or_default();
+
         Ok(DatabasePoolCacheConfig {
             host: db_host,
             port: db_port,
@@ -295,6 +411,10 @@ impl DatabasePoolCacheConfig {
             cache_capacity,
             credentials_cache_duration,
             credentials_cache_capacity,
+            statement_timeout,
+            statement_timeout_root,
+            slow_query_threshold,
+            external_pooler,
         })
     }
 }
@@ -321,7 +441,10 @@ pub struct DatabasePoolCache {
     root_iam: bool,
 
     /// Cache from the database name to the pool for that database
-    cache: Cache<String, DbPool>,
+    cache: Cache<String, CachedPool>,
+
+    /// Counts of the pools that have been evicted from the cache
+    evictions: Arc<PoolEvictionCounters>,
 
     /// Cache for the connection info details, stores the last known
     /// credentials and the instant that they were obtained at
@@ -337,6 +460,203 @@ pub struct DatabasePoolCache {
 
     acquire_timeout: Duration,
     idle_timeout: Duration,
+
+    /// Connection settings for tenant database pools
+    tenant_connect_settings: PoolConnectSettings,
+    /// Connection settings for root database pools
+    root_connect_settings: PoolConnectSettings,
+}
+
+/// Settings applied to the connect options of each connection within a pool
+#[derive(Debug, Clone, Copy)]
+struct PoolConnectSettings {
+    /// Timeout before a statement is cancelled by the database
+    statement_timeout: Option<Duration>,
+    /// Duration after which a query is logged as a slow query
+    slow_query_threshold: Option<Duration>,
+    /// Whether connections are made through an external transaction pooler
+    external_pooler: bool,
+}
+
+impl PoolConnectSettings {
+    /// Apply the settings to the connect `options`
+    fn apply(&self, mut options: PgConnectOptions) -> PgConnectOptions {
+        if self.external_pooler {
+            // Statements cannot be reused across the server connections assigned
+            // by the pooler, the timeout must be configured on the database role
+            options = options.statement_cache_capacity(0);
+        } else if let Some(statement_timeout) = self.statement_timeout {
+            options = options.options([(
+                "statement_timeout",
+                statement_timeout.as_millis().to_string(),
+            )]);
+        }
+
+        if let Some(slow_query_threshold) = self.slow_query_threshold {
+            options = options.log_slow_statements(LevelFilter::Warn, slow_query_threshold);
+        }
+
+        options
+    }
+}
+
+/// Database pool held within the cache along with details about the pool
+#[derive(Clone)]
+struct CachedPool {
+    /// The database pool
+    pool: DbPool,
+    /// Name of the database the pool is connected to
+    db_name: String,
+    /// Credentials used to connect to the database
+    credential: PoolCredential,
+    /// When the pool was created
+    created_at: DateTime<Utc>,
+    /// Usage tracking shared between clones of the cached pool
+    usage: Arc<PoolUsage>,
+}
+
+/// Credentials a pool was created with
+#[derive(Clone)]
+enum PoolCredential {
+    /// Secrets manager secret containing the credentials
+    Secret(String),
+    /// Database role authenticated using IAM
+    Iam(String),
+}
+
+/// Usage of a cached pool
+struct PoolUsage {
+    /// Number of times the pool has been obtained from the cache
+    acquired: AtomicU64,
+    /// Timestamp in milliseconds of when the pool was last obtained
+    last_used: AtomicI64,
+}
+
+impl CachedPool {
+    fn new(pool: DbPool, db_name: &str, credential: PoolCredential) -> Self {
+        let created_at = Utc::now();
+        Self {
+            pool,
+            db_name: db_name.to_string(),
+            credential,
+            created_at,
+            usage: Arc::new(PoolUsage {
+                acquired: AtomicU64::new(0),
+                last_used: AtomicI64::new(created_at.timestamp_millis()),
+            }),
+        }
+    }
+
+    /// Record a use of the pool and obtain the underlying pool
+    fn acquire(self) -> DbPool {
+        self.usage.acquired.fetch_add(1, Ordering::Relaxed);
+        self.usage
+            .last_used
+            .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+        self.pool
+    }
+
+    fn info(&self, now: DateTime<Utc>) -> DatabasePoolInfo {
+        let (secret_name, iam_user_name) = match &self.credential {
+            PoolCredential::Secret(secret_name) => (Some(secret_name.clone()), None),
+            PoolCredential::Iam(iam_user_name) => (None, Some(iam_user_name.clone())),
+        };
+
+        let last_used_at =
+            DateTime::from_timestamp_millis(self.usage.last_used.load(Ordering::Relaxed))
+                .unwrap_or(self.created_at);
+
+        DatabasePoolInfo {
+            db_name: self.db_name.clone(),
+            secret_name,
+            iam_user_name,
+            created_at: self.created_at,
+            age_seconds: (now - self.created_at).num_seconds().max(0) as u64,
+            last_used_at,
+            acquired: self.usage.acquired.load(Ordering::Relaxed),
+            connections: self.pool.size(),
+            idle_connections: self.pool.num_idle() as u32,
+        }
+    }
+}
+
+/// Details about a database pool held in the cache
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DatabasePoolInfo {
+    /// Name of the database the pool is connected to
+    pub db_name: String,
+    /// Name of the secret containing the pool credentials when using
+    /// secrets based authentication
+    pub secret_name: Option<String>,
+    /// Name of the database user when using IAM authentication
+    pub iam_user_name: Option<String>,
+    /// When the pool was created
+    pub created_at: DateTime<Utc>,
+    /// Number of seconds since the pool was created
+    pub age_seconds: u64,
+    /// When the pool was last obtained from the cache
+    pub last_used_at: DateTime<Utc>,
+    /// Number of times the pool has been obtained from the cache
+    pub acquired: u64,
+    /// Number of open connections within the pool
+    pub connections: u32,
+    /// Number of open connections that are currently idle
+    pub idle_connections: u32,
+}
+
+impl DatabasePoolInfo {
+    /// Check whether this pool is the pool used by the provided `tenant`
+    pub fn is_tenant_pool(&self, tenant: &Tenant) -> bool {
+        if self.db_name != tenant.db_name {
+            return false;
+        }
+
+        // Matches the authentication preference of [DatabasePoolCache::get_tenant_pool]
+        match (
+            tenant.db_iam_user_name.as_ref(),
+            tenant.db_secret_name.as_ref(),
+        ) {
+            (Some(db_iam_user_name), _) => self.iam_user_name.as_ref() == Some(db_iam_user_name),
+            (_, Some(db_secret_name)) => self.secret_name.as_ref() == Some(db_secret_name),
+            _ => false,
+        }
+    }
+}
+
+/// Counts of the pools evicted from the cache by the cause of eviction
+#[derive(Default)]
+struct PoolEvictionCounters {
+    capacity: AtomicU64,
+    expired: AtomicU64,
+    explicit: AtomicU64,
+}
+
+impl PoolEvictionCounters {
+    fn record(&self, cause: RemovalCause) {
+        let counter = match cause {
+            RemovalCause::Size => &self.capacity,
+            RemovalCause::Expired => &self.expired,
+            RemovalCause::Explicit => &self.explicit,
+            // Pools are never replaced within the cache
+            RemovalCause::Replaced => return,
+        };
+
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Statistics about the database pool cache
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct DatabasePoolCacheStats {
+    /// Number of database pools currently held in the cache
+    pub pools: u64,
+    /// Number of pools closed to stay within the cache capacity
+    pub capacity_evictions: u64,
+    /// Number of pools closed after being idle for longer than the cache
+    /// duration or reaching the pool timeout
+    pub expired_evictions: u64,
+    /// Number of pools closed by flushing the cache or closing a tenant pool
+    pub explicit_evictions: u64,
 }
 
 /// Username and password for a specific database
@@ -388,7 +708,7 @@ impl DatabasePoolCache {
         config: DatabasePoolCacheConfig,
         secrets_manager: SecretManager,
     ) -> Self {
-        let mut pool_timeout = Duration::from_secs(config.cache_duration.unwrap_or(60 * 60 * 48));
+        let mut pool_timeout = Duration::from_secs(config.pool_timeout.unwrap_or(60 * 60 * 48));
         let cache_duration = Duration::from_secs(config.cache_duration.unwrap_or(60 * 60 * 48));
         let credentials_cache_duration =
             Duration::from_secs(config.credentials_cache_duration.unwrap_or(60 * 60 * 12));
@@ -403,18 +723,42 @@ impl DatabasePoolCache {
         }
 
         let cache_capacity = config.cache_capacity.unwrap_or(50);
+        let slow_query_threshold = config.slow_query_threshold.map(Duration::from_millis);
         let credentials_cache_capacity = config.credentials_cache_capacity.unwrap_or(50);
 
+        if config.external_pooler
+            && (config.statement_timeout.is_some() || config.statement_timeout_root.is_some())
+        {
+            tracing::warn!(
+                "statement timeouts are ignored when using an external pooler, set the \
+                 statement_timeout on the database roles instead"
+            );
+        }
+
+        let evictions = Arc::new(PoolEvictionCounters::default());
+
+        // LRU is used over Tiny LFU as Tiny LFU may reject admitting a newly
+        // created pool when at capacity, closing a pool that is still in use
         let cache = Cache::builder()
             .time_to_live(pool_timeout)
             .time_to_idle(cache_duration)
             .max_capacity(cache_capacity)
-            .eviction_policy(EvictionPolicy::tiny_lfu())
-            .async_eviction_listener(|cache_key: Arc<String>, pool: DbPool, _cause| {
-                Box::pin(async move {
-                    tracing::debug!(?cache_key, "database pool is no longer in use, closing");
-                    pool.close().await
-                })
+            .eviction_policy(EvictionPolicy::lru())
+            .async_eviction_listener({
+                let evictions = evictions.clone();
+                move |cache_key: Arc<String>, pool: CachedPool, cause: RemovalCause| {
+                    evictions.record(cause);
+                    let pool = pool.pool;
+
+                    Box::pin(async move {
+                        tracing::debug!(
+                            ?cache_key,
+                            ?cause,
+                            "database pool is no longer in use, closing"
+                        );
+                        pool.close().await
+                    })
+                }
             })
             .build();
 
@@ -431,12 +775,23 @@ impl DatabasePoolCache {
             root_secret_name: config.root_secret_name,
             root_iam: config.root_iam,
             cache,
+            evictions,
             connect_info_cache,
             secrets_manager,
             max_connections: config.max_connections.unwrap_or(10),
             max_connections_root: config.max_connections_root.unwrap_or(2),
             idle_timeout: Duration::from_secs(config.idle_timeout.unwrap_or(60 * 10)),
             acquire_timeout: Duration::from_secs(config.acquire_timeout.unwrap_or(60)),
+            tenant_connect_settings: PoolConnectSettings {
+                statement_timeout: config.statement_timeout.map(Duration::from_millis),
+                slow_query_threshold,
+                external_pooler: config.external_pooler,
+            },
+            root_connect_settings: PoolConnectSettings {
+                statement_timeout: config.statement_timeout_root.map(Duration::from_millis),
+                slow_query_threshold,
+                external_pooler: config.external_pooler,
+            },
         }
     }
 
@@ -471,34 +826,65 @@ impl DatabasePoolCache {
 
     /// Closes the database pool for the specific tenant if one is
     /// available and removes the pool from the cache
-    pub async fn close_tenant_pool(&self, tenant: &Tenant) {
+    ///
+    /// The cached credentials for the tenant are also removed so that
+    /// the next pool created for the tenant loads the latest credentials
+    ///
+    /// Returns whether a pool was present in the cache
+    pub async fn close_tenant_pool(&self, tenant: &Tenant) -> bool {
         let cache_key = Self::tenant_cache_key(tenant);
-        if let Some(pool) = self.cache.remove(&cache_key).await {
-            pool.close().await;
+        let removed = match self.cache.remove(&cache_key).await {
+            Some(pool) => {
+                pool.pool.close().await;
+                true
+            }
+            None => false,
+        };
+
+        if tenant.db_iam_user_name.is_none()
+            && let Some(db_secret_name) = tenant.db_secret_name.as_ref()
+        {
+            self.connect_info_cache.remove(db_secret_name).await;
         }
 
         // Run cache async shutdown jobs
         self.cache.run_pending_tasks().await;
+
+        removed
     }
 
     /// Compute the pool cache key for a tenant based on the specific
     /// authentication methods for that tenant
+    ///
+    /// Matches the authentication preference of [DatabasePoolCache::get_tenant_pool]
     fn tenant_cache_key(tenant: &Tenant) -> String {
         match (
-            tenant.db_secret_name.as_ref(),
             tenant.db_iam_user_name.as_ref(),
+            tenant.db_secret_name.as_ref(),
         ) {
-            (Some(db_secret_name), _) => {
-                format!("secret-{}-{}", &tenant.db_name, db_secret_name)
-            }
-            (_, Some(db_iam_user_name)) => {
+            (Some(db_iam_user_name), _) => {
                 format!("user-{}-{}", &tenant.db_name, db_iam_user_name)
             }
+            (_, Some(db_secret_name)) => {
+                format!("secret-{}-{}", &tenant.db_name, db_secret_name)
+            }
 
             _ => format!("db-{}", &tenant.db_name),
         }
     }
 
+    /// Get details about each of the pools currently held in the cache
+    pub async fn pools(&self) -> Vec<DatabasePoolInfo> {
+        self.cache.run_pending_tasks().await;
+
+        let now = Utc::now();
+        let mut pools: Vec<DatabasePoolInfo> =
+            self.cache.iter().map(|(_, pool)| pool.info(now)).collect();
+
+        pools.sort_by(|a, b| a.db_name.cmp(&b.db_name));
+        pools
+    }
+
     /// Empties all the caches
     pub async fn flush(&self) {
         // Clear cache
@@ -507,10 +893,33 @@ impl DatabasePoolCache {
         self.cache.run_pending_tasks().await;
     }
 
+    /// Close and remove any pools that have been idle for longer than the
+    /// cache duration or have reached the pool timeout
+    ///
+    /// Expired pools are otherwise only removed while the cache is in use,
+    /// this should be called periodically to release the connections of
+    /// pools that are no longer being used
+    pub async fn evict_expired_pools(&self) {
+        self.cache.run_pending_tasks().await;
+    }
+
+    /// Get statistics about the pools held in the cache and the pools that
+    /// have been evicted
+    pub async fn stats(&self) -> DatabasePoolCacheStats {
+        self.cache.run_pending_tasks().await;
+
+        DatabasePoolCacheStats {
+            pools: self.cache.entry_count(),
+            capacity_evictions: self.evictions.capacity.load(Ordering::Relaxed),
+            expired_evictions: self.evictions.expired.load(Ordering::Relaxed),
+            explicit_evictions: self.evictions.explicit.load(Ordering::Relaxed),
+        }
+    }
+
     /// Close all connections in the pool and invalidate the cache
     pub async fn close_all(&self) {
         for (_, value) in self.cache.iter() {
-            value.close().await;
+            value.pool.close().await;
         }
 
         self.flush().await;
@@ -531,11 +940,15 @@ impl DatabasePoolCache {
                     .await
                     .map_err(Arc::new)?;
 
-                Ok(pool)
+                Ok(CachedPool::new(
+                    pool,
+                    db_name,
+                    PoolCredential::Secret(secret_name.to_string()),
+                ))
             })
             .await?;
 
-        Ok(pool)
+        Ok(pool.acquire())
     }
 
     /// Obtains a database pool connection to the database with the provided name
@@ -557,11 +970,15 @@ impl DatabasePoolCache {
                     .await
                     .map_err(Arc::new)?;
 
-                Ok(pool)
+                Ok(CachedPool::new(
+                    pool,
+                    db_name,
+                    PoolCredential::Iam(db_role_name.to_string()),
+                ))
             })
             .await?;
 
-        Ok(pool)
+        Ok(pool.acquire())
     }
 
     /// Obtains database connection info
@@ -586,6 +1003,29 @@ impl DatabasePoolCache {
         Ok(credentials)
     }
 
+    /// Get the connection settings for pools connecting to `db_name`
+    fn connect_settings(&self, db_name: &str) -> PoolConnectSettings {
+        match db_name {
+            ROOT_DATABASE_NAME => self.root_connect_settings,
+            _ => self.tenant_connect_settings,
+        }
+    }
+
+    /// Create the options for pools connecting to `db_name`
+    fn pool_options(&self, db_name: &str) -> PgPoolOptions {
+        let max_connections = match db_name {
+            ROOT_DATABASE_NAME => self.max_connections_root,
+            _ => self.max_connections,
+        };
+
+        PgPoolOptions::new()
+            .max_connections(max_connections)
+            // Slightly larger acquire timeout for times when lots of files are being processed
+            .acquire_timeout(self.acquire_timeout)
+            // Close any connections that have been idle for more than 30min
+            .idle_timeout(self.idle_timeout)
+    }
+
     /// Creates a database pool connection using IAM based authentication
     async fn create_pool_iam(
         &self,
@@ -594,6 +1034,7 @@ impl DatabasePoolCache {
     ) -> Result<DbPool, DbConnectErr> {
         tracing::debug!(?db_name, ?db_role_name, "creating db pool connection");
 
+        let connect_settings = self.connect_settings(db_name);
         let options = iam_pool_connect_options(
             &self.aws_config,
             &self.host,
@@ -602,18 +1043,10 @@ impl DatabasePoolCache {
             db_role_name,
         )
         .await?;
+        let options = connect_settings.apply(options);
 
-        let max_connections = match db_name {
-            ROOT_DATABASE_NAME => self.max_connections_root,
-            _ => self.max_connections,
-        };
-
-        let pool = PgPoolOptions::new()
-            .max_connections(max_connections)
-            // Slightly larger acquire timeout for times when lots of files are being processed
-            .acquire_timeout(self.acquire_timeout)
-            // Close any connections that have been idle for more than 30min
-            .idle_timeout(self.idle_timeout)
+        let pool = self
+            .pool_options(db_name)
             .connect_with(options)
             .await
             .map_err(DbConnectErr::Db)?;
@@ -625,6 +1058,7 @@ impl DatabasePoolCache {
             self.port,
             db_name.to_string(),
             db_role_name.to_string(),
+            connect_settings,
         ));
 
         Ok(pool)
@@ -641,21 +1075,9 @@ impl DatabasePoolCache {
             .username(&credentials.username)
             .password(&credentials.password)
             .database(db_name);
+        let options = self.connect_settings(db_name).apply(options);
 
-        let max_connections = match db_name {
-            ROOT_DATABASE_NAME => self.max_connections_root,
-            _ => self.max_connections,
-        };
-
-        match PgPoolOptions::new()
-            .max_connections(max_connections)
-            // Slightly larger acquire timeout for times when lots of files are being processed
-            .acquire_timeout(self.acquire_timeout)
-            // Close any connections that have been idle for more than 30min
-            .idle_timeout(self.idle_timeout)
-            .connect_with(options)
-            .await
-        {
+        match self.pool_options(db_name).connect_with(options).await {
             // Success case
             Ok(value) => Ok(value),
             Err(err) => {
@@ -737,6 +1159,7 @@ async fn iam_pool_maintenance_task(
     port: u16,
     db_name: String,
     db_role_name: String,
+    connect_settings: PoolConnectSettings,
 ) {
     let interval = Duration::from_secs(60 * 10);
 
@@ -747,7 +1170,7 @@ async fn iam_pool_maintenance_task(
 
         match iam_pool_connect_options(&aws_config, &host, port, &db_name, &db_role_name).await {
             Ok(options) => {
-                db.set_connect_options(options);
+                db.set_connect_options(connect_settings.apply(options));
             }
             Err(error) => {
                 tracing::error!(?error, "failed to refresh IAM pool connect options");
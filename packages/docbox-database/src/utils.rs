@@ -16,6 +16,8 @@ pub trait DatabaseErrorExt {
     fn is_duplicate_record(&self) -> bool;
 
     fn is_restrict(&self) -> bool;
+
+    fn is_query_canceled(&self) -> bool;
 }
 
 impl DatabaseErrorExt for &dyn DatabaseError {
@@ -43,6 +45,10 @@ impl DatabaseErrorExt for &dyn DatabaseError {
     fn is_restrict(&self) -> bool {
         self.is_error_code("23001" /* Foreign key RESTRICT violation */)
     }
+
+    fn is_query_canceled(&self) -> bool {
+        self.is_error_code("57014" /* Query canceled (statement_timeout) */)
+    }
 }
 
 impl DatabaseErrorExt for DbErr {
@@ -75,6 +81,11 @@ impl DatabaseErrorExt for DbErr {
         self.as_database_error()
             .is_some_and(|error| error.is_restrict())
     }
+
+    fn is_query_canceled(&self) -> bool {
+        self.as_database_error()
+            .is_some_and(|error| error.is_query_canceled())
+    }
 }
 
 macro_rules! update_if_some {
@@ -0,0 +1,58 @@
+use crate::common::{database::test_tenant_db, make_test_document_box, make_test_file};
+use chrono::{SubsecRound, Utc};
+use docbox_database::models::connector_sync_item::ConnectorSyncItem;
+
+mod common;
+
+/// Tests that storing the sync state replaces the previous state and
+/// that it can be deleted
+#[tokio::test]
+async fn test_connector_sync_item_set_replaces() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let file = make_test_file(&db, &root, "test.pdf", None).await;
+    let replacement = make_test_file(&db, &root, "test-2.pdf", None).await;
+
+    let mut item = ConnectorSyncItem {
+        connector_id: "s3://bucket/prefix".to_string(),
+        source_key: "reports/test.pdf".to_string(),
+        file_id: file.id,
+        etag: Some("\"abc\"".to_string()),
+        last_modified: Some(Utc::now().trunc_subsecs(0)),
+        synced_at: Utc::now().trunc_subsecs(0),
+    };
+
+    ConnectorSyncItem::set(&db, &item).await.unwrap();
+
+    item.file_id = replacement.id;
+    item.etag = Some("\"def\"".to_string());
+    ConnectorSyncItem::set(&db, &item).await.unwrap();
+
+    let items = ConnectorSyncItem::find_by_connector(&db, "s3://bucket/prefix")
+        .await
+        .unwrap();
+    assert_eq!(items, vec![item.clone()]);
+
+    let items = ConnectorSyncItem::find_by_connector(&db, "s3://other")
+        .await
+        .unwrap();
+    assert!(items.is_empty());
+
+    ConnectorSyncItem::delete(&db, &item.connector_id, &item.source_key)
+        .await
+        .unwrap();
+
+    let items = ConnectorSyncItem::find_by_connector(&db, "s3://bucket/prefix")
+        .await
+        .unwrap();
+    assert!(items.is_empty());
+
+    // Sync state is removed along with the file
+    ConnectorSyncItem::set(&db, &item).await.unwrap();
+    replacement.delete(&db).await.unwrap();
+
+    let items = ConnectorSyncItem::find_by_connector(&db, "s3://bucket/prefix")
+        .await
+        .unwrap();
+    assert!(items.is_empty());
+}
@@ -216,3 +216,80 @@ async fn test_document_box_search_total() {
     let results = DocumentBox::search_total(&db, "1test:%").await.unwrap();
     assert_eq!(results, 0);
 }
+
+/// Tests that archiving a document box excludes it from the unarchived scopes
+#[tokio::test]
+async fn test_archive_document_box() {
+    let (db, _db_container) = test_tenant_db().await;
+    let document_box = DocumentBox::create(&db, "user:1".to_string())
+        .await
+        .unwrap();
+    DocumentBox::create(&db, "user:2".to_string())
+        .await
+        .unwrap();
+    DocumentBox::create(&db, "other".to_string()).await.unwrap();
+    assert!(!document_box.archived);
+    assert_eq!(
+        DocumentBox::is_archived(&db, "user:1").await.unwrap(),
+        Some(false)
+    );
+
+    let document_box = document_box.set_archived(&db, true).await.unwrap();
+    assert!(document_box.archived);
+    assert_eq!(
+        DocumentBox::is_archived(&db, "user:1").await.unwrap(),
+        Some(true)
+    );
+    assert_eq!(
+        DocumentBox::is_archived(&db, "unknown").await.unwrap(),
+        None
+    );
+
+    let archived = DocumentBox::archived_scopes(&db).await.unwrap();
+    assert_eq!(archived, vec!["user:1".to_string()]);
+
+    let unarchived = DocumentBox::unarchived_scopes_by_prefix(&db, "user:")
+        .await
+        .unwrap();
+    assert_eq!(unarchived, vec!["user:2".to_string()]);
+
+    document_box.set_archived(&db, false).await.unwrap();
+    assert!(DocumentBox::archived_scopes(&db).await.unwrap().is_empty());
+}
+
+/// Tests that document box scopes can be listed by prefix
+#[tokio::test]
+async fn test_document_box_scopes_by_prefix() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    DocumentBox::create(&db, "org:1:a".to_string())
+        .await
+        .unwrap();
+    DocumentBox::create(&db, "org:1:b".to_string())
+        .await
+        .unwrap();
+    DocumentBox::create(&db, "org:2:a".to_string())
+        .await
+        .unwrap();
+
+    let scopes = DocumentBox::scopes_by_prefix(&db, "org:1:", 0, 5)
+        .await
+        .unwrap();
+    assert_eq!(scopes, vec!["org:1:a".to_string(), "org:1:b".to_string()]);
+
+    let scopes = DocumentBox::scopes_by_prefix(&db, "org:", 1, 1)
+        .await
+        .unwrap();
+    assert_eq!(scopes, vec!["org:1:b".to_string()]);
+
+    let total = DocumentBox::scopes_by_prefix_total(&db, "org:")
+        .await
+        .unwrap();
+    assert_eq!(total, 3);
+
+    // Prefix wildcard characters are matched literally
+    let total = DocumentBox::scopes_by_prefix_total(&db, "org%")
+        .await
+        .unwrap();
+    assert_eq!(total, 0);
+}
@@ -0,0 +1,148 @@
+use crate::common::{
+    database::test_tenant_db, make_test_document_box, make_test_file, make_test_folder,
+    make_test_link,
+};
+use chrono::Utc;
+use docbox_database::models::document_box_stats::{
+    DocumentBoxStatsCounters, DocumentBoxStatsDelta,
+};
+
+mod common;
+
+/// Tests that created counters start empty and deltas are applied to them
+#[tokio::test]
+async fn test_document_box_stats_apply_delta() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, _root) = make_test_document_box(&db, "test", None).await;
+
+    let counters = DocumentBoxStatsCounters::create(&db, document_box.scope.clone(), Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(counters.total_files, 0);
+    assert_eq!(counters.total_links, 0);
+    assert_eq!(counters.total_folders, 0);
+    assert_eq!(counters.file_size, 0);
+
+    DocumentBoxStatsCounters::apply_delta(&db, "test", DocumentBoxStatsDelta::file(100))
+        .await
+        .unwrap();
+    DocumentBoxStatsCounters::apply_delta(&db, "test", DocumentBoxStatsDelta::links(3))
+        .await
+        .unwrap();
+    DocumentBoxStatsCounters::apply_delta(&db, "test", DocumentBoxStatsDelta::folder())
+        .await
+        .unwrap();
+    DocumentBoxStatsCounters::apply_delta(&db, "test", DocumentBoxStatsDelta::links(1).negate())
+        .await
+        .unwrap();
+
+    let counters = DocumentBoxStatsCounters::find(&db, "test")
+        .await
+        .unwrap()
+        .expect("counters should exist");
+    assert_eq!(counters.total_files, 1);
+    assert_eq!(counters.total_links, 2);
+    assert_eq!(counters.total_folders, 1);
+    assert_eq!(counters.file_size, 100);
+
+    // Counters should never become negative
+    DocumentBoxStatsCounters::apply_delta(&db, "test", DocumentBoxStatsDelta::file(500).negate())
+        .await
+        .unwrap();
+
+    let counters = DocumentBoxStatsCounters::find(&db, "test")
+        .await
+        .unwrap()
+        .expect("counters should exist");
+    assert_eq!(counters.total_files, 0);
+    assert_eq!(counters.file_size, 0);
+}
+
+/// Tests that applying a delta to a document box without counters does
+/// not create the counters
+#[tokio::test]
+async fn test_document_box_stats_apply_delta_missing() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, _root) = make_test_document_box(&db, "test", None).await;
+
+    let result =
+        DocumentBoxStatsCounters::apply_delta(&db, "test", DocumentBoxStatsDelta::folder())
+            .await
+            .unwrap();
+    assert_eq!(result.rows_affected(), 0);
+
+    assert!(
+        DocumentBoxStatsCounters::find(&db, "test")
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+/// Tests that reconciling computes the counters from the document box contents
+#[tokio::test]
+async fn test_document_box_stats_reconcile() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let (_other_box, other_root) = make_test_document_box(&db, "other", None).await;
+
+    let folder = make_test_folder(&db, &root, "folder", None).await;
+    let file = make_test_file(&db, &folder, "file", None).await;
+    let _link = make_test_link(&db, &root, "link", None).await;
+    let _other_link = make_test_link(&db, &other_root, "other", None).await;
+
+    // Counters that have drifted should be corrected
+    DocumentBoxStatsCounters::create(&db, "test".to_string(), Utc::now())
+        .await
+        .unwrap();
+    DocumentBoxStatsCounters::apply_delta(&db, "test", DocumentBoxStatsDelta::links(5))
+        .await
+        .unwrap();
+
+    let counters = DocumentBoxStatsCounters::reconcile(&db, "test", Utc::now())
+        .await
+        .unwrap()
+        .expect("counters should exist");
+    assert_eq!(counters.total_files, 1);
+    assert_eq!(counters.total_links, 1);
+    assert_eq!(counters.total_folders, 1);
+    assert_eq!(counters.file_size, file.size as i64);
+
+    // Unknown document boxes should not have counters
+    assert!(
+        DocumentBoxStatsCounters::reconcile(&db, "unknown", Utc::now())
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+/// Tests that reconciling all creates the missing counters for every document box
+#[tokio::test]
+async fn test_document_box_stats_reconcile_all() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let (_other_box, other_root) = make_test_document_box(&db, "other", None).await;
+
+    let _link = make_test_link(&db, &root, "link", None).await;
+    let _other_folder = make_test_folder(&db, &other_root, "folder", None).await;
+
+    let result = DocumentBoxStatsCounters::reconcile_all(&db, Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(result.rows_affected(), 2);
+
+    let counters = DocumentBoxStatsCounters::find(&db, "test")
+        .await
+        .unwrap()
+        .expect("counters should exist");
+    assert_eq!(counters.total_links, 1);
+    assert_eq!(counters.total_folders, 0);
+
+    let counters = DocumentBoxStatsCounters::find(&db, "other")
+        .await
+        .unwrap()
+        .expect("counters should exist");
+    assert_eq!(counters.total_links, 0);
+    assert_eq!(counters.total_folders, 1);
+}
@@ -0,0 +1,72 @@
+use docbox_database::models::document_box_webhook::{CreateDocumentBoxWebhook, DocumentBoxWebhook};
+
+use crate::common::database::test_tenant_db;
+
+mod common;
+
+fn create_webhook(scope: &str, events: Vec<String>) -> CreateDocumentBoxWebhook {
+    CreateDocumentBoxWebhook {
+        document_box: scope.to_string(),
+        url: "http://localhost/webhook".to_string(),
+        secret: "secret".to_string(),
+        events,
+    }
+}
+
+/// Tests that webhooks can be created and found within their document box
+#[tokio::test]
+async fn test_create_document_box_webhook() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    let webhook = DocumentBoxWebhook::create(
+        &db,
+        create_webhook("test", vec!["FILE_CREATED".to_string()]),
+    )
+    .await
+    .unwrap();
+
+    let found = DocumentBoxWebhook::find(&db, "test", webhook.id)
+        .await
+        .unwrap()
+        .expect("webhook should exist");
+    assert_eq!(found.url, webhook.url);
+    assert_eq!(found.secret, webhook.secret);
+    assert_eq!(found.events, webhook.events);
+
+    // Webhooks are not accessible from other document boxes
+    let found = DocumentBoxWebhook::find(&db, "other", webhook.id)
+        .await
+        .unwrap();
+    assert!(found.is_none());
+}
+
+/// Tests that webhooks can be listed and deleted by document box
+#[tokio::test]
+async fn test_document_box_webhooks_by_scope() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    DocumentBoxWebhook::create(&db, create_webhook("test", vec![]))
+        .await
+        .unwrap();
+    DocumentBoxWebhook::create(&db, create_webhook("test", vec![]))
+        .await
+        .unwrap();
+    DocumentBoxWebhook::create(&db, create_webhook("other", vec![]))
+        .await
+        .unwrap();
+
+    let webhooks = DocumentBoxWebhook::all_by_scope(&db, "test").await.unwrap();
+    assert_eq!(webhooks.len(), 2);
+
+    DocumentBoxWebhook::delete_by_scope(&db, "test")
+        .await
+        .unwrap();
+
+    let webhooks = DocumentBoxWebhook::all_by_scope(&db, "test").await.unwrap();
+    assert!(webhooks.is_empty());
+
+    let webhooks = DocumentBoxWebhook::all_by_scope(&db, "other")
+        .await
+        .unwrap();
+    assert_eq!(webhooks.len(), 1);
+}
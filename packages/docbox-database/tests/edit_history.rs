@@ -1,11 +1,12 @@
 use docbox_database::models::edit_history::{
-    CreateEditHistory, CreateEditHistoryType, EditHistory, EditHistoryMetadata, EditHistoryType,
+    CreateEditHistory, CreateEditHistoryType, EditHistory, EditHistoryFilter, EditHistoryMetadata,
+    EditHistoryOrder, EditHistoryType,
 };
 use sqlx::types::Json;
 
 use crate::common::{
     database::test_tenant_db, make_test_document_box, make_test_file, make_test_folder,
-    make_test_link,
+    make_test_link, make_test_user,
 };
 
 mod common;
@@ -174,3 +175,169 @@ async fn test_all_by_link_edit_history() {
         })
     );
 }
+
+/// Tests that a specific edit history item can only be found through the
+/// item it belongs to
+#[tokio::test]
+async fn test_find_by_file_edit_history() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let file = make_test_file(&db, &root, "test", None).await;
+    let other_file = make_test_file(&db, &root, "other", None).await;
+
+    EditHistory::create(
+        &db,
+        CreateEditHistory {
+            ty: CreateEditHistoryType::File(file.id),
+            user_id: None,
+            metadata: EditHistoryMetadata::Rename {
+                original_name: "a".to_string(),
+                new_name: "b".to_string(),
+            },
+        },
+    )
+    .await
+    .unwrap();
+
+    let history = EditHistory::all_by_file(&db, file.id).await.unwrap();
+    let entry_id = history.first().unwrap().id;
+
+    let item = EditHistory::find_by_file(&db, file.id, entry_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(item.id, entry_id);
+    assert_eq!(item.ty, EditHistoryType::Rename);
+
+    let item = EditHistory::find_by_file(&db, other_file.id, entry_id)
+        .await
+        .unwrap();
+    assert!(item.is_none());
+
+    let item = EditHistory::find_by_link(&db, file.id, entry_id)
+        .await
+        .unwrap();
+    assert!(item.is_none());
+}
+
+/// Tests that the edit history for an item can be filtered, ordered and paginated
+#[tokio::test]
+async fn test_query_by_file_edit_history() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let file = make_test_file(&db, &root, "test", None).await;
+    let user = make_test_user(&db, "test").await;
+
+    for (user_id, metadata) in [
+        (
+            None,
+            EditHistoryMetadata::Rename {
+                original_name: "a".to_string(),
+                new_name: "b".to_string(),
+            },
+        ),
+        (
+            Some(user.id.clone()),
+            EditHistoryMetadata::ChangePinned {
+                previous_value: false,
+                new_value: true,
+            },
+        ),
+        (
+            Some(user.id.clone()),
+            EditHistoryMetadata::Rename {
+                original_name: "b".to_string(),
+                new_name: "c".to_string(),
+            },
+        ),
+    ] {
+        EditHistory::create(
+            &db,
+            CreateEditHistory {
+                ty: CreateEditHistoryType::File(file.id),
+                user_id,
+                metadata,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let all = EditHistory::query_by_file(&db, file.id, &EditHistoryFilter::default(), 0, 100)
+        .await
+        .unwrap();
+    assert_eq!(all.len(), 3);
+    assert!(
+        all.windows(2)
+            .all(|items| items[0].created_at >= items[1].created_at)
+    );
+
+    let oldest = EditHistory::query_by_file(
+        &db,
+        file.id,
+        &EditHistoryFilter {
+            order: EditHistoryOrder::Oldest,
+            ..Default::default()
+        },
+        0,
+        100,
+    )
+    .await
+    .unwrap();
+    let oldest_ids: Vec<_> = oldest.iter().rev().map(|item| item.id).collect();
+    let all_ids: Vec<_> = all.iter().map(|item| item.id).collect();
+    assert_eq!(oldest_ids, all_ids);
+
+    let renames = EditHistory::query_by_file(
+        &db,
+        file.id,
+        &EditHistoryFilter {
+            ty: Some(EditHistoryType::Rename),
+            ..Default::default()
+        },
+        0,
+        100,
+    )
+    .await
+    .unwrap();
+    assert_eq!(renames.len(), 2);
+    assert!(
+        renames
+            .iter()
+            .all(|item| item.ty == EditHistoryType::Rename)
+    );
+
+    let by_user = EditHistory::query_by_file(
+        &db,
+        file.id,
+        &EditHistoryFilter {
+            user_id: Some(user.id.clone()),
+            ..Default::default()
+        },
+        0,
+        100,
+    )
+    .await
+    .unwrap();
+    assert_eq!(by_user.len(), 2);
+
+    let after_end = EditHistory::query_by_file(
+        &db,
+        file.id,
+        &EditHistoryFilter {
+            start: Some(all[0].created_at + chrono::Duration::seconds(1)),
+            ..Default::default()
+        },
+        0,
+        100,
+    )
+    .await
+    .unwrap();
+    assert!(after_end.is_empty());
+
+    let page = EditHistory::query_by_file(&db, file.id, &EditHistoryFilter::default(), 1, 1)
+        .await
+        .unwrap();
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].id, all[1].id);
+}
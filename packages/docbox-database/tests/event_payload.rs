@@ -0,0 +1,55 @@
+use chrono::{Days, Utc};
+use docbox_database::models::event_payload::EventPayload;
+use serde_json::json;
+
+use crate::common::database::test_tenant_db;
+
+mod common;
+
+/// Tests that stored event payloads can be found
+#[tokio::test]
+async fn test_create_event_payload() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    let payload = EventPayload::create(
+        &db,
+        "FILE_CREATED".to_string(),
+        json!({ "event": "FILE_CREATED", "data": { "name": "test.txt" } }),
+    )
+    .await
+    .unwrap();
+
+    let found = EventPayload::find(&db, payload.id)
+        .await
+        .unwrap()
+        .expect("payload should exist");
+    assert_eq!(found.id, payload.id);
+    assert_eq!(found.event, payload.event);
+    assert_eq!(found.payload, payload.payload);
+}
+
+/// Tests that expired event payloads are deleted correctly
+#[tokio::test]
+async fn test_event_payload_delete_expired() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    let payload = EventPayload::create(&db, "FILE_CREATED".to_string(), json!({}))
+        .await
+        .unwrap();
+
+    // Deleting in the past should not delete our payload
+    EventPayload::delete_expired(&db, Utc::now().checked_sub_days(Days::new(1)).unwrap())
+        .await
+        .unwrap();
+
+    let found = EventPayload::find(&db, payload.id).await.unwrap();
+    assert!(found.is_some());
+
+    // Deleting in the future should delete our payload
+    EventPayload::delete_expired(&db, Utc::now().checked_add_days(Days::new(1)).unwrap())
+        .await
+        .unwrap();
+
+    let found = EventPayload::find(&db, payload.id).await.unwrap();
+    assert!(found.is_none());
+}
@@ -171,6 +171,49 @@ async fn test_file_move_to_folder() {
     assert_eq!(base_result.folder_id, base_folder.id);
 }
 
+/// Tests that moving a file tree moves the child files of the file
+/// into the target folder along with the file
+#[tokio::test]
+async fn test_file_move_tree_to_folder() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_, root_1) = make_test_document_box(&db, "test_1", None).await;
+    let (document_box_2, root_2) = make_test_document_box(&db, "test_2", None).await;
+
+    let base_file = make_test_file(&db, &root_1, "base", None).await;
+    let child_file = File::create(
+        &db,
+        CreateFile {
+            id: Uuid::new_v4(),
+            name: "child".to_string(),
+            parent_id: Some(base_file.id),
+            folder_id: root_1.id,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let other_file = make_test_file(&db, &root_1, "other", None).await;
+
+    let (base_file, children) = base_file.move_tree_to_folder(&db, root_2.id).await.unwrap();
+    assert_eq!(base_file.folder_id, root_2.id);
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].id, child_file.id);
+    assert_eq!(children[0].folder_id, root_2.id);
+
+    let files = File::find_by_folders(&db, &[root_2.id]).await.unwrap();
+    assert_eq!(files.len(), 2);
+
+    // Files outside the tree should not be moved
+    let files = File::find_by_folders(&db, &[root_1.id]).await.unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].id, other_file.id);
+
+    let child_result = File::find(&db, &document_box_2.scope, child_file.id)
+        .await
+        .unwrap();
+    assert!(child_result.is_some());
+}
+
 #[tokio::test]
 async fn test_file_rename() {
     let (db, _db_container) = test_tenant_db().await;
@@ -239,6 +282,39 @@ async fn test_file_set_pinned() {
     assert!(!base_result.pinned);
 }
 
+#[tokio::test]
+async fn test_file_find_pinned() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, root) = make_test_document_box(&db, "test_1", None).await;
+    let (_other_document_box, other_root) = make_test_document_box(&db, "test_2", None).await;
+
+    let file_1 = make_test_file(&db, &root, "file_1", None).await;
+    let file_2 = make_test_file(&db, &root, "file_2", None).await;
+    let _file_3 = make_test_file(&db, &root, "file_3", None).await;
+    let other_file = make_test_file(&db, &other_root, "other", None).await;
+
+    let file_1 = file_1.set_pinned(&db, true).await.unwrap();
+    let file_2 = file_2.set_pinned(&db, true).await.unwrap();
+    let _other_file = other_file.set_pinned(&db, true).await.unwrap();
+
+    // Most recently pinned files should be first
+    let pinned = File::find_pinned(&db, &document_box.scope).await.unwrap();
+    let pinned_ids: Vec<Uuid> = pinned.iter().map(|item| item.id).collect();
+    assert_eq!(pinned_ids, vec![file_2.id, file_1.id]);
+
+    // Pinning an already pinned file should not change the pin time
+    let pinned_at = pinned[1].pinned_at;
+    let file_1 = file_1.set_pinned(&db, true).await.unwrap();
+    let pinned = File::find_pinned(&db, &document_box.scope).await.unwrap();
+    assert_eq!(pinned[1].pinned_at, pinned_at);
+
+    // Unpinned files should be removed
+    let _file_1 = file_1.set_pinned(&db, false).await.unwrap();
+    let pinned = File::find_pinned(&db, &document_box.scope).await.unwrap();
+    let pinned_ids: Vec<Uuid> = pinned.iter().map(|item| item.id).collect();
+    assert_eq!(pinned_ids, vec![file_2.id]);
+}
+
 #[tokio::test]
 async fn test_file_set_encrypted() {
     let (db, _db_container) = test_tenant_db().await;
@@ -0,0 +1,115 @@
+use crate::common::{database::test_tenant_db, make_test_document_box, make_test_file};
+use chrono::{TimeDelta, Utc};
+use docbox_database::models::file_access_stats::{
+    FileAccessIncrement, FileAccessReportOrder, FileAccessStats,
+};
+use uuid::Uuid;
+
+mod common;
+
+/// Tests that access increments are accumulated onto the stored statistics
+#[tokio::test]
+async fn test_file_access_stats_increment() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let file = make_test_file(&db, &root, "test.txt", None).await;
+
+    let first = Utc::now();
+    let latest = first + TimeDelta::seconds(10);
+
+    FileAccessStats::increment(
+        &db,
+        &FileAccessIncrement {
+            file_id: file.id,
+            downloads: 2,
+            previews: 1,
+            accessed_at: latest,
+        },
+    )
+    .await
+    .unwrap();
+
+    FileAccessStats::increment(
+        &db,
+        &FileAccessIncrement {
+            file_id: file.id,
+            downloads: 1,
+            previews: 3,
+            accessed_at: first,
+        },
+    )
+    .await
+    .unwrap();
+
+    let stats = FileAccessStats::find(&db, file.id)
+        .await
+        .unwrap()
+        .expect("stats should exist");
+
+    assert_eq!(stats.download_count, 3);
+    assert_eq!(stats.preview_count, 4);
+    assert_eq!(
+        stats.last_accessed_at.timestamp_millis(),
+        latest.timestamp_millis()
+    );
+}
+
+/// Tests that accesses for files that no longer exist are ignored
+#[tokio::test]
+async fn test_file_access_stats_increment_unknown_file() {
+    let (db, _db_container) = test_tenant_db().await;
+    let file_id = Uuid::new_v4();
+
+    FileAccessStats::increment(
+        &db,
+        &FileAccessIncrement {
+            file_id,
+            downloads: 1,
+            previews: 0,
+            accessed_at: Utc::now(),
+        },
+    )
+    .await
+    .unwrap();
+
+    assert!(FileAccessStats::find(&db, file_id).await.unwrap().is_none());
+}
+
+/// Tests the report orders files by their access counts including
+/// files that have never been accessed
+#[tokio::test]
+async fn test_file_access_stats_report() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let popular = make_test_file(&db, &root, "popular.txt", None).await;
+    let rare = make_test_file(&db, &root, "rare.txt", None).await;
+    let unused = make_test_file(&db, &root, "unused.txt", None).await;
+
+    for (file_id, downloads) in [(popular.id, 10), (rare.id, 1)] {
+        FileAccessStats::increment(
+            &db,
+            &FileAccessIncrement {
+                file_id,
+                downloads,
+                previews: 0,
+                accessed_at: Utc::now(),
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let most = FileAccessStats::report(&db, FileAccessReportOrder::MostAccessed, 0, 10)
+        .await
+        .unwrap();
+    let most: Vec<_> = most.iter().map(|item| item.file.id).collect();
+    assert_eq!(most, vec![popular.id, rare.id, unused.id]);
+
+    let least = FileAccessStats::report(&db, FileAccessReportOrder::LeastAccessed, 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(least[0].file.id, unused.id);
+    assert_eq!(least[0].download_count, 0);
+    assert!(least[0].last_accessed_at.is_none());
+    assert_eq!(least[1].file.id, rare.id);
+}
@@ -0,0 +1,88 @@
+use crate::common::{
+    database::test_tenant_db, make_test_document_box, make_test_file, make_test_user,
+};
+use docbox_database::models::file_email::{CreateFileEmail, FileEmail, FileEmailDelivery};
+
+mod common;
+
+/// Tests that sent file emails are recorded and found most recent first
+#[tokio::test]
+async fn test_file_email_create_find() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let user = make_test_user(&db, "test").await;
+    let file = make_test_file(&db, &root, "test.pdf", None).await;
+    let other_file = make_test_file(&db, &root, "other.pdf", None).await;
+
+    let first = FileEmail::create(
+        &db,
+        CreateFileEmail {
+            file_id: file.id,
+            recipients: vec!["first@example.com".to_string()],
+            subject: "First".to_string(),
+            delivery: FileEmailDelivery::Attachment,
+            link_expires_at: None,
+            sent_by: Some(user.id.clone()),
+        },
+    )
+    .await
+    .unwrap();
+
+    let second = FileEmail::create(
+        &db,
+        CreateFileEmail {
+            file_id: file.id,
+            recipients: vec![
+                "first@example.com".to_string(),
+                "second@example.com".to_string(),
+            ],
+            subject: "Second".to_string(),
+            delivery: FileEmailDelivery::Link,
+            link_expires_at: Some(chrono::Utc::now()),
+            sent_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let emails = FileEmail::find_by_file(&db, file.id).await.unwrap();
+    let ids: Vec<_> = emails.iter().map(|email| email.id).collect();
+    assert_eq!(ids, vec![second.id, first.id]);
+
+    assert_eq!(emails[0].recipients, second.recipients);
+    assert_eq!(emails[0].delivery, FileEmailDelivery::Link);
+    assert!(emails[0].link_expires_at.is_some());
+    assert_eq!(emails[1].delivery, FileEmailDelivery::Attachment);
+    assert_eq!(emails[1].sent_by.as_deref(), Some(user.id.as_str()));
+
+    let emails = FileEmail::find_by_file(&db, other_file.id).await.unwrap();
+    assert!(emails.is_empty());
+}
+
+/// Tests that sent file emails are removed along with the file
+#[tokio::test]
+async fn test_file_email_delete_with_file() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let file = make_test_file(&db, &root, "test.pdf", None).await;
+
+    FileEmail::create(
+        &db,
+        CreateFileEmail {
+            file_id: file.id,
+            recipients: vec!["first@example.com".to_string()],
+            subject: "First".to_string(),
+            delivery: FileEmailDelivery::Attachment,
+            link_expires_at: None,
+            sent_by: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let file_id = file.id;
+    file.delete(&db).await.unwrap();
+
+    let emails = FileEmail::find_by_file(&db, file_id).await.unwrap();
+    assert!(emails.is_empty());
+}
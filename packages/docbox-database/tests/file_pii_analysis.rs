@@ -0,0 +1,96 @@
+use crate::common::{database::test_tenant_db, make_test_document_box, make_test_file};
+use chrono::Utc;
+use docbox_database::models::file_pii_analysis::{
+    FilePiiAnalysis, PiiAnalysis, PiiFinding, PiiKind,
+};
+
+mod common;
+
+fn email_finding() -> PiiFinding {
+    PiiFinding {
+        kind: PiiKind::EmailAddress,
+        page: 0,
+        value: "j***@example.com".to_string(),
+        count: 2,
+    }
+}
+
+/// Tests that storing an analysis replaces the previous analysis
+#[tokio::test]
+async fn test_file_pii_analysis_set_replaces() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let file = make_test_file(&db, &root, "test.txt", None).await;
+
+    let analysis = PiiAnalysis {
+        contains_pii: true,
+        findings: vec![email_finding()],
+    };
+
+    FilePiiAnalysis::set(&db, file.id, &analysis, Utc::now())
+        .await
+        .unwrap();
+
+    let stored: PiiAnalysis = FilePiiAnalysis::find(&db, file.id)
+        .await
+        .unwrap()
+        .expect("analysis should exist")
+        .into();
+    assert_eq!(stored, analysis);
+
+    FilePiiAnalysis::set(&db, file.id, &PiiAnalysis::default(), Utc::now())
+        .await
+        .unwrap();
+
+    let stored: PiiAnalysis = FilePiiAnalysis::find(&db, file.id)
+        .await
+        .unwrap()
+        .expect("analysis should exist")
+        .into();
+    assert_eq!(stored, PiiAnalysis::default());
+
+    FilePiiAnalysis::delete(&db, file.id).await.unwrap();
+    assert!(FilePiiAnalysis::find(&db, file.id).await.unwrap().is_none());
+}
+
+/// Tests that only analyzed files within the scope are reported
+#[tokio::test]
+async fn test_file_pii_analysis_within_scope() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let (_other_box, other_root) = make_test_document_box(&db, "other", None).await;
+
+    let with_pii = make_test_file(&db, &root, "pii.txt", None).await;
+    let without_pii = make_test_file(&db, &root, "clean.txt", None).await;
+    let _not_analyzed = make_test_file(&db, &root, "unknown.txt", None).await;
+    let other_file = make_test_file(&db, &other_root, "other.txt", None).await;
+
+    let analysis = PiiAnalysis {
+        contains_pii: true,
+        findings: vec![email_finding()],
+    };
+
+    FilePiiAnalysis::set(&db, with_pii.id, &analysis, Utc::now())
+        .await
+        .unwrap();
+    FilePiiAnalysis::set(&db, without_pii.id, &PiiAnalysis::default(), Utc::now())
+        .await
+        .unwrap();
+    FilePiiAnalysis::set(&db, other_file.id, &analysis, Utc::now())
+        .await
+        .unwrap();
+
+    let counts = FilePiiAnalysis::counts_within_scope(&db, "test")
+        .await
+        .unwrap();
+    assert_eq!(counts.analyzed_files, 2);
+    assert_eq!(counts.files_with_pii, 1);
+
+    let files = FilePiiAnalysis::find_with_pii_within_scope(&db, "test")
+        .await
+        .unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].file_id, with_pii.id);
+    assert_eq!(files[0].name, "pii.txt");
+    assert_eq!(files[0].findings.0, vec![email_finding()]);
+}
@@ -0,0 +1,83 @@
+use crate::common::{database::test_tenant_db, make_test_document_box, make_test_file};
+use chrono::{TimeDelta, Utc};
+use docbox_database::models::file_processing::{FileProcessing, ProcessingTimings};
+
+mod common;
+
+/// Tests that setting the processing record replaces the previous timings
+#[tokio::test]
+async fn test_file_processing_set_replaces_timings() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let file = make_test_file(&db, &root, "test.pdf", None).await;
+
+    let timings = ProcessingTimings {
+        conversion_ms: Some(100),
+        text_extraction_ms: Some(20),
+        ..Default::default()
+    };
+
+    FileProcessing::set(&db, file.id, 1, Utc::now(), &timings)
+        .await
+        .unwrap();
+
+    let timings = ProcessingTimings {
+        thumbnail_ms: Some(30),
+        ..Default::default()
+    };
+
+    FileProcessing::set(&db, file.id, 2, Utc::now(), &timings)
+        .await
+        .unwrap();
+
+    let processing = FileProcessing::find(&db, file.id)
+        .await
+        .unwrap()
+        .expect("processing record should exist");
+    assert_eq!(processing.version, 2);
+    assert_eq!(processing.timings, timings);
+}
+
+/// Tests aggregating the durations of each processing stage
+#[tokio::test]
+async fn test_file_processing_stage_stats() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+
+    let now = Utc::now();
+
+    for (index, indexing_ms) in [10, 20, 30, 40].into_iter().enumerate() {
+        let file = make_test_file(&db, &root, format!("test-{index}.pdf"), None).await;
+        let timings = ProcessingTimings {
+            indexing_ms: Some(indexing_ms),
+            total_ms: Some(indexing_ms * 2),
+            ..Default::default()
+        };
+
+        FileProcessing::set(&db, file.id, 1, now, &timings)
+            .await
+            .unwrap();
+    }
+
+    // Files processed before the window are excluded
+    let file = make_test_file(&db, &root, "old.pdf", None).await;
+    let timings = ProcessingTimings {
+        conversion_ms: Some(1000),
+        ..Default::default()
+    };
+    FileProcessing::set(&db, file.id, 1, now - TimeDelta::days(30), &timings)
+        .await
+        .unwrap();
+
+    let stats = FileProcessing::stage_stats(&db, now - TimeDelta::days(7))
+        .await
+        .unwrap();
+
+    let stages: Vec<&str> = stats.iter().map(|stats| stats.stage.as_str()).collect();
+    assert_eq!(stages, vec!["indexing", "total"]);
+
+    let indexing = &stats[0];
+    assert_eq!(indexing.count, 4);
+    assert_eq!(indexing.p50_ms, 25.0);
+    assert_eq!(indexing.max_ms, 40.0);
+}
@@ -0,0 +1,41 @@
+use crate::common::{database::test_tenant_db, make_test_document_box, make_test_file};
+use docbox_database::models::file_text_stats::{FileTextStats, TextStats};
+
+mod common;
+
+/// Tests that storing text stats replaces the previous stats and that
+/// they can be deleted
+#[tokio::test]
+async fn test_file_text_stats_set_replaces() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let file = make_test_file(&db, &root, "test.pdf", None).await;
+
+    let stats = TextStats {
+        word_count: 500,
+        character_count: 2500,
+        reading_time_seconds: 126,
+    };
+
+    FileTextStats::set(&db, file.id, &stats).await.unwrap();
+
+    let stats = TextStats {
+        word_count: 10,
+        character_count: 50,
+        reading_time_seconds: 3,
+    };
+
+    FileTextStats::set(&db, file.id, &stats).await.unwrap();
+
+    let stored: TextStats = FileTextStats::find(&db, file.id)
+        .await
+        .unwrap()
+        .expect("text stats should exist")
+        .into();
+    assert_eq!(stored, stats);
+
+    FileTextStats::delete(&db, file.id).await.unwrap();
+
+    let stored = FileTextStats::find(&db, file.id).await.unwrap();
+    assert!(stored.is_none());
+}
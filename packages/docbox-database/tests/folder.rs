@@ -223,6 +223,125 @@ async fn test_folder_move_to_folder() {
     assert_eq!(base_result.folder_id, Some(base_folder_2.id));
 }
 
+/// Tests that moving a folder updates the paths of the folder and all
+/// of its nested folders and items
+#[tokio::test]
+async fn test_folder_move_to_folder_nested_paths() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, root) = make_test_document_box(&db, "test", None).await;
+
+    let base_folder = make_test_folder(&db, &root, "base", None).await;
+    let target_folder = make_test_folder(&db, &root, "target", None).await;
+    let nested_folder = make_test_folder(&db, &base_folder, "nested", None).await;
+    let nested_link = make_test_link(&db, &nested_folder, "link", None).await;
+
+    let base_folder = base_folder
+        .move_to_folder(&db, target_folder.id)
+        .await
+        .unwrap();
+
+    let nested_path = Folder::resolve_path(&db, nested_folder.id).await.unwrap();
+    assert_eq!(
+        nested_path,
+        vec![
+            FolderPathSegment::from(&root),
+            FolderPathSegment::from(&target_folder),
+            FolderPathSegment::from(&base_folder),
+        ]
+    );
+
+    let link_path = Link::resolve_path(&db, nested_link.id).await.unwrap();
+    assert_eq!(
+        link_path,
+        vec![
+            FolderPathSegment::from(&root),
+            FolderPathSegment::from(&target_folder),
+            FolderPathSegment::from(&base_folder),
+            FolderPathSegment::from(&nested_folder),
+        ]
+    );
+
+    // Renamed folders should be reflected in the resolved paths
+    let target_folder = target_folder
+        .rename(&db, "renamed".to_string())
+        .await
+        .unwrap();
+
+    let nested_path = Folder::resolve_path(&db, nested_folder.id).await.unwrap();
+    assert_eq!(nested_path[1], FolderPathSegment::from(&target_folder));
+
+    // Nested folders should be included in the target subtree
+    let mut children = target_folder.tree_all_children(&db).await.unwrap();
+    children.sort();
+    let mut expected = vec![target_folder.id, base_folder.id, nested_folder.id];
+    expected.sort();
+    assert_eq!(children, expected);
+
+    let base_result = Folder::find_by_id(&db, &document_box.scope, base_folder.id)
+        .await
+        .unwrap()
+        .expect("folder should exist");
+    assert_eq!(base_result.folder_id, Some(target_folder.id));
+}
+
+/// Tests that moving a folder into another document box moves the folder
+/// and all of its nested folders and items into the target scope
+#[tokio::test]
+async fn test_folder_move_to_scope() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_, root_1) = make_test_document_box(&db, "test_1", None).await;
+    let (document_box_2, root_2) = make_test_document_box(&db, "test_2", None).await;
+
+    let base_folder = make_test_folder(&db, &root_1, "base", None).await;
+    let nested_folder = make_test_folder(&db, &base_folder, "nested", None).await;
+    let nested_link = make_test_link(&db, &nested_folder, "link", None).await;
+    let target_folder = make_test_folder(&db, &root_2, "target", None).await;
+
+    let base_folder = base_folder
+        .move_to_scope(&db, &target_folder)
+        .await
+        .unwrap();
+    assert_eq!(base_folder.document_box, document_box_2.scope);
+    assert_eq!(base_folder.folder_id, Some(target_folder.id));
+
+    let nested_result = Folder::find_by_id(&db, &document_box_2.scope, nested_folder.id)
+        .await
+        .unwrap()
+        .expect("nested folder should be within the target scope");
+    assert_eq!(nested_result.folder_id, Some(base_folder.id));
+
+    let link_result = Link::find(&db, &document_box_2.scope, nested_link.id)
+        .await
+        .unwrap();
+    assert!(link_result.is_some());
+
+    let nested_path = Folder::resolve_path(&db, nested_folder.id).await.unwrap();
+    assert_eq!(
+        nested_path,
+        vec![
+            FolderPathSegment::from(&root_2),
+            FolderPathSegment::from(&target_folder),
+            FolderPathSegment::from(&base_folder),
+        ]
+    );
+
+    let mut folder_ids: Vec<_> = base_folder
+        .tree_all_folders(&db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|folder| folder.id)
+        .collect();
+    folder_ids.sort();
+    let mut expected = vec![base_folder.id, nested_folder.id];
+    expected.sort();
+    assert_eq!(folder_ids, expected);
+
+    // Source root should no longer contain the moved folder
+    let children = Folder::find_by_parent(&db, root_1.id).await.unwrap();
+    assert!(children.is_empty());
+}
+
 /// Tests that a folder can be renamed
 #[tokio::test]
 async fn test_folder_rename() {
@@ -0,0 +1,63 @@
+use crate::common::{database::test_tenant_db, make_test_document_box, make_test_folder};
+use docbox_database::models::folder_processing_config::FolderProcessingConfig;
+use serde_json::json;
+
+mod common;
+
+/// Tests that a folder processing config can be set, replaced and deleted
+#[tokio::test]
+async fn test_folder_processing_config_set() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+
+    FolderProcessingConfig::set(&db, root.id, json!({ "max_unpack_iterations": 2 }))
+        .await
+        .unwrap();
+
+    FolderProcessingConfig::set(&db, root.id, json!({ "max_unpack_iterations": 3 }))
+        .await
+        .unwrap();
+
+    let config = FolderProcessingConfig::find(&db, root.id)
+        .await
+        .unwrap()
+        .expect("config should exist");
+    assert_eq!(config.config, json!({ "max_unpack_iterations": 3 }));
+
+    FolderProcessingConfig::delete(&db, root.id).await.unwrap();
+
+    assert!(
+        FolderProcessingConfig::find(&db, root.id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+/// Tests that the configs along a folder path are provided from the root
+/// folder down to the folder itself
+#[tokio::test]
+async fn test_folder_processing_config_find_for_folder_path() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let parent = make_test_folder(&db, &root, "parent", None).await;
+    let child = make_test_folder(&db, &parent, "child", None).await;
+    let other = make_test_folder(&db, &root, "other", None).await;
+
+    FolderProcessingConfig::set(&db, root.id, json!({ "max_unpack_iterations": 1 }))
+        .await
+        .unwrap();
+    FolderProcessingConfig::set(&db, child.id, json!({ "max_unpack_iterations": 2 }))
+        .await
+        .unwrap();
+    FolderProcessingConfig::set(&db, other.id, json!({ "max_unpack_iterations": 3 }))
+        .await
+        .unwrap();
+
+    let configs = FolderProcessingConfig::find_for_folder_path(&db, child.id)
+        .await
+        .unwrap();
+
+    let folder_ids: Vec<_> = configs.iter().map(|config| config.folder_id).collect();
+    assert_eq!(folder_ids, vec![root.id, child.id]);
+}
@@ -26,6 +26,7 @@ async fn test_generated_file_create() {
             hash: "aabbcc".to_string(),
             file_key: "test/key".to_string(),
             created_at: Utc::now(),
+            page: None,
         },
     )
     .await
@@ -39,45 +40,104 @@ async fn test_generated_file_create() {
 }
 
 /// Tests that multiple generated files of the same type cannot be added
-///
-/// TODO: This is not currently enforced on the DB level, do we want to enforce this?
 #[tokio::test]
 async fn test_generated_file_create_duplicate_error() {
-    // let (db, _db_container) = test_tenant_db().await;
-    // let (_document_box, root) = make_test_document_box(&db, "test", None).await;
-    // let file = make_test_file(&db, &root, "test", None).await;
-
-    // let _generated_file_1 = GeneratedFile::create(
-    //     &db,
-    //     CreateGeneratedFile {
-    //         id: Uuid::new_v4(),
-    //         file_id: file.id,
-    //         mime: "application/pdf".to_string(),
-    //         ty: GeneratedFileType::Pdf,
-    //         hash: "aabbcc".to_string(),
-    //         file_key: "test/key".to_string(),
-    //         created_at: Utc::now(),
-    //     },
-    // )
-    // .await
-    // .unwrap();
-
-    // let error = GeneratedFile::create(
-    //     &db,
-    //     CreateGeneratedFile {
-    //         id: Uuid::new_v4(),
-    //         file_id: file.id,
-    //         mime: "application/pdf".to_string(),
-    //         ty: GeneratedFileType::Pdf,
-    //         hash: "aabbcc".to_string(),
-    //         file_key: "test/key".to_string(),
-    //         created_at: Utc::now(),
-    //     },
-    // )
-    // .await
-    // .unwrap_err();
-
-    // assert!(error.is_duplicate_record());
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let file = make_test_file(&db, &root, "test", None).await;
+
+    let _generated_file_1 = GeneratedFile::create(
+        &db,
+        CreateGeneratedFile {
+            id: Uuid::new_v4(),
+            file_id: file.id,
+            mime: "application/pdf".to_string(),
+            ty: GeneratedFileType::Pdf,
+            hash: "aabbcc".to_string(),
+            file_key: "test/key".to_string(),
+            created_at: Utc::now(),
+            page: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let error = GeneratedFile::create(
+        &db,
+        CreateGeneratedFile {
+            id: Uuid::new_v4(),
+            file_id: file.id,
+            mime: "application/pdf".to_string(),
+            ty: GeneratedFileType::Pdf,
+            hash: "aabbcc".to_string(),
+            file_key: "test/key-2".to_string(),
+            created_at: Utc::now(),
+            page: None,
+        },
+    )
+    .await
+    .unwrap_err();
+
+    assert!(error.is_duplicate_record());
+}
+
+/// Tests that creating a generated file that already exists is skipped
+/// and that per page generated files are unique by page
+#[tokio::test]
+async fn test_generated_file_create_if_missing() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, root) = make_test_document_box(&db, "test", None).await;
+    let file = make_test_file(&db, &root, "test", None).await;
+
+    let create = |ty: GeneratedFileType, page: Option<i32>| CreateGeneratedFile {
+        id: Uuid::new_v4(),
+        file_id: file.id,
+        mime: "image/jpeg".to_string(),
+        ty,
+        hash: "aabbcc".to_string(),
+        file_key: Uuid::new_v4().to_string(),
+        created_at: Utc::now(),
+        page,
+    };
+
+    let generated_file =
+        GeneratedFile::create_if_missing(&db, create(GeneratedFileType::CoverPage, None))
+            .await
+            .unwrap();
+    assert!(generated_file.is_some());
+
+    let duplicate =
+        GeneratedFile::create_if_missing(&db, create(GeneratedFileType::CoverPage, None))
+            .await
+            .unwrap();
+    assert!(duplicate.is_none());
+
+    let result = GeneratedFile::find(
+        &db,
+        &document_box.scope,
+        file.id,
+        GeneratedFileType::CoverPage,
+    )
+    .await
+    .unwrap();
+    assert_eq!(result, generated_file);
+
+    // Different pages of the same type are allowed
+    for page in [1, 2] {
+        let page_file = GeneratedFile::create_if_missing(
+            &db,
+            create(GeneratedFileType::PagePreview, Some(page)),
+        )
+        .await
+        .unwrap();
+        assert!(page_file.is_some());
+    }
+
+    let duplicate_page =
+        GeneratedFile::create_if_missing(&db, create(GeneratedFileType::PagePreview, Some(2)))
+            .await
+            .unwrap();
+    assert!(duplicate_page.is_none());
 }
 
 /// Tests that a generated file can be deleted
@@ -97,6 +157,7 @@ async fn test_delete_generated_file() {
             hash: "aabbcc".to_string(),
             file_key: "test/key".to_string(),
             created_at: Utc::now(),
+            page: None,
         },
     )
     .await
@@ -112,6 +173,7 @@ async fn test_delete_generated_file() {
             hash: "aabbcc".to_string(),
             file_key: "test/key2".to_string(),
             created_at: Utc::now(),
+            page: None,
         },
     )
     .await
@@ -169,6 +231,7 @@ async fn test_find_all_generated_file() {
             hash: "aabbcc".to_string(),
             file_key: "test/key".to_string(),
             created_at: Utc::now(),
+            page: None,
         },
     )
     .await
@@ -184,6 +247,7 @@ async fn test_find_all_generated_file() {
             hash: "aabbcc".to_string(),
             file_key: "test/key2".to_string(),
             created_at: Utc::now(),
+            page: None,
         },
     )
     .await
@@ -212,6 +276,7 @@ async fn test_find_generated_file() {
             hash: "aabbcc".to_string(),
             file_key: "test/key".to_string(),
             created_at: Utc::now(),
+            page: None,
         },
     )
     .await
@@ -227,6 +292,7 @@ async fn test_find_generated_file() {
             hash: "aabbcc".to_string(),
             file_key: "test/key2".to_string(),
             created_at: Utc::now(),
+            page: None,
         },
     )
     .await
@@ -249,3 +315,66 @@ async fn test_find_generated_file() {
 
     assert_eq!(result, Some(other_generated_file));
 }
+
+/// Tests that per page generated files are found in page order
+#[tokio::test]
+async fn test_find_generated_file_pages() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, root) = make_test_document_box(&db, "test", None).await;
+    let file = make_test_file(&db, &root, "test", None).await;
+
+    let mut previews = Vec::new();
+
+    for page in [3, 1, 2] {
+        let generated_file = GeneratedFile::create(
+            &db,
+            CreateGeneratedFile {
+                id: Uuid::new_v4(),
+                file_id: file.id,
+                mime: "image/jpeg".to_string(),
+                ty: GeneratedFileType::PagePreview,
+                hash: "aabbcc".to_string(),
+                file_key: format!("test/preview-{page}"),
+                created_at: Utc::now(),
+                page: Some(page),
+            },
+        )
+        .await
+        .unwrap();
+        previews.push(generated_file);
+    }
+
+    let result = GeneratedFile::find_pages(
+        &db,
+        &document_box.scope,
+        file.id,
+        GeneratedFileType::PagePreview,
+    )
+    .await
+    .unwrap();
+
+    let pages: Vec<Option<i32>> = result.iter().map(|value| value.page).collect();
+    assert_eq!(pages, vec![Some(1), Some(2), Some(3)]);
+
+    let result = GeneratedFile::find_page(
+        &db,
+        &document_box.scope,
+        file.id,
+        GeneratedFileType::PagePreview,
+        2,
+    )
+    .await
+    .unwrap();
+    assert_eq!(result.as_ref(), previews.get(2));
+
+    // Finding by type resolves to the first page
+    let result = GeneratedFile::find(
+        &db,
+        &document_box.scope,
+        file.id,
+        GeneratedFileType::PagePreview,
+    )
+    .await
+    .unwrap();
+    assert_eq!(result.as_ref(), previews.get(1));
+}
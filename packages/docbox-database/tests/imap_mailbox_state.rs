@@ -0,0 +1,31 @@
+use crate::common::database::test_tenant_db;
+use chrono::{SubsecRound, Utc};
+use docbox_database::models::imap_mailbox_state::ImapMailboxState;
+
+mod common;
+
+/// Tests that storing the mailbox state replaces the previous state
+#[tokio::test]
+async fn test_imap_mailbox_state_set_replaces() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    let mailbox_id = "imap://user@localhost:993/INBOX";
+
+    let state = ImapMailboxState::find(&db, mailbox_id).await.unwrap();
+    assert!(state.is_none());
+
+    let mut state = ImapMailboxState {
+        mailbox_id: mailbox_id.to_string(),
+        uid_validity: 1,
+        last_uid: 10,
+        updated_at: Utc::now().trunc_subsecs(0),
+    };
+
+    ImapMailboxState::set(&db, &state).await.unwrap();
+
+    state.last_uid = 25;
+    ImapMailboxState::set(&db, &state).await.unwrap();
+
+    let stored = ImapMailboxState::find(&db, mailbox_id).await.unwrap();
+    assert_eq!(stored, Some(state));
+}
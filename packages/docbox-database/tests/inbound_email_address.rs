@@ -0,0 +1,148 @@
+use docbox_database::{
+    DbPool,
+    models::{
+        inbound_email_address::{CreateInboundEmailAddress, InboundEmailAddress},
+        tenant::{CreateTenant, Tenant},
+    },
+    utils::DatabaseErrorExt,
+};
+use uuid::Uuid;
+
+use crate::common::database::test_root_db;
+
+mod common;
+
+async fn make_test_tenant(db: &DbPool) -> Tenant {
+    let tenant_id = Uuid::new_v4();
+    Tenant::create(
+        db,
+        CreateTenant {
+            id: tenant_id,
+            name: "test".to_string(),
+            db_name: "test".to_string(),
+            db_secret_name: Some("test".to_string()),
+            db_iam_user_name: None,
+            s3_name: "test".to_string(),
+            os_index_name: "test".to_string(),
+            event_queue_url: None,
+            env: "Development".to_string(),
+            data_region: None,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+/// Tests that an inbound email address can be created and found
+/// by its address and document box
+#[tokio::test]
+async fn test_create_find_inbound_email_address() {
+    let (db, _db_container) = test_root_db().await;
+    let tenant = make_test_tenant(&db).await;
+
+    let address = InboundEmailAddress::create(
+        &db,
+        CreateInboundEmailAddress {
+            address: "Box-Inbox@Example.com".to_string(),
+            env: tenant.env.clone(),
+            tenant_id: tenant.id,
+            document_box: "test".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(address.address, "box-inbox@example.com");
+
+    // Addresses are matched case insensitively
+    let found = InboundEmailAddress::find_by_address(&db, "BOX-inbox@example.com")
+        .await
+        .unwrap();
+    assert_eq!(found.as_ref(), Some(&address));
+
+    let found = InboundEmailAddress::find_by_document_box(&db, &tenant.env, tenant.id, "test")
+        .await
+        .unwrap();
+    assert_eq!(found.as_ref(), Some(&address));
+
+    let found = InboundEmailAddress::find_by_document_box(&db, &tenant.env, tenant.id, "other")
+        .await
+        .unwrap();
+    assert!(found.is_none());
+}
+
+/// Tests that a document box cannot have multiple inbound email addresses
+#[tokio::test]
+async fn test_create_inbound_email_address_duplicate_document_box() {
+    let (db, _db_container) = test_root_db().await;
+    let tenant = make_test_tenant(&db).await;
+
+    InboundEmailAddress::create(
+        &db,
+        CreateInboundEmailAddress {
+            address: "first@example.com".to_string(),
+            env: tenant.env.clone(),
+            tenant_id: tenant.id,
+            document_box: "test".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let error = InboundEmailAddress::create(
+        &db,
+        CreateInboundEmailAddress {
+            address: "second@example.com".to_string(),
+            env: tenant.env.clone(),
+            tenant_id: tenant.id,
+            document_box: "test".to_string(),
+        },
+    )
+    .await
+    .unwrap_err();
+    assert!(error.is_duplicate_record());
+}
+
+/// Tests that inbound email addresses can be deleted and are removed
+/// along with their tenant
+#[tokio::test]
+async fn test_delete_inbound_email_address() {
+    let (db, _db_container) = test_root_db().await;
+    let tenant = make_test_tenant(&db).await;
+
+    let address = InboundEmailAddress::create(
+        &db,
+        CreateInboundEmailAddress {
+            address: "first@example.com".to_string(),
+            env: tenant.env.clone(),
+            tenant_id: tenant.id,
+            document_box: "first".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    address.delete(&db).await.unwrap();
+    let found = InboundEmailAddress::find_by_address(&db, "first@example.com")
+        .await
+        .unwrap();
+    assert!(found.is_none());
+
+    InboundEmailAddress::create(
+        &db,
+        CreateInboundEmailAddress {
+            address: "second@example.com".to_string(),
+            env: tenant.env.clone(),
+            tenant_id: tenant.id,
+            document_box: "second".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    tenant.delete(&db).await.unwrap();
+    let found = InboundEmailAddress::find_by_address(&db, "second@example.com")
+        .await
+        .unwrap();
+    assert!(found.is_none());
+}
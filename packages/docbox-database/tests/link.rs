@@ -2,6 +2,7 @@ use crate::common::{
     database::test_tenant_db, make_test_document_box, make_test_folder, make_test_link,
     make_test_user,
 };
+use chrono::Utc;
 use docbox_database::{
     models::{
         link::{CreateLink, Link},
@@ -144,6 +145,45 @@ async fn test_link_set_pinned() {
     assert!(!base_result.pinned);
 }
 
+/// Tests that the metadata fetch timestamp of a link can be set
+#[tokio::test]
+async fn test_link_set_fetched_at() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, root) = make_test_document_box(&db, "test_1", None).await;
+
+    let base_link = make_test_link(&db, &root, "base", None).await;
+    assert!(base_link.fetched_at.is_none());
+
+    let fetched_at = Utc::now();
+    let base_link = base_link.set_fetched_at(&db, fetched_at).await.unwrap();
+
+    // Change should be applied to the returned value
+    assert_eq!(base_link.fetched_at, Some(fetched_at));
+
+    // Change should also apply to find results
+    let base_result = Link::find(&db, &document_box.scope, base_link.id)
+        .await
+        .unwrap()
+        .expect("link should exist");
+    assert_eq!(
+        base_result.fetched_at.map(|value| value.timestamp_millis()),
+        Some(fetched_at.timestamp_millis())
+    );
+
+    // Change should also apply to the composite link type
+    let base_result = Link::find_with_extra(&db, &document_box.scope, base_link.id)
+        .await
+        .unwrap()
+        .expect("link should exist");
+    assert_eq!(
+        base_result
+            .link
+            .fetched_at
+            .map(|value| value.timestamp_millis()),
+        Some(fetched_at.timestamp_millis())
+    );
+}
+
 /// Tests that a link value can be updated
 #[tokio::test]
 async fn test_link_update_value() {
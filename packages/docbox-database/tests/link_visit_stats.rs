@@ -0,0 +1,90 @@
+use crate::common::{database::test_tenant_db, make_test_document_box, make_test_link};
+use chrono::{TimeDelta, Utc};
+use docbox_database::models::link_visit_stats::LinkVisitStats;
+use uuid::Uuid;
+
+mod common;
+
+/// Tests that visits are accumulated onto the stored statistics
+#[tokio::test]
+async fn test_link_visit_stats_record_visit() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let link = make_test_link(&db, &root, "test", None).await;
+
+    let first = Utc::now();
+    let latest = first + TimeDelta::seconds(10);
+
+    let stats = LinkVisitStats::record_visit(&db, link.id, latest)
+        .await
+        .unwrap()
+        .expect("stats should exist");
+    assert_eq!(stats.visit_count, 1);
+
+    // Visit recorded out of order should not move the last visit backwards
+    let stats = LinkVisitStats::record_visit(&db, link.id, first)
+        .await
+        .unwrap()
+        .expect("stats should exist");
+    assert_eq!(stats.visit_count, 2);
+    assert_eq!(
+        stats.last_visited_at.timestamp_millis(),
+        latest.timestamp_millis()
+    );
+
+    let found = LinkVisitStats::find(&db, link.id)
+        .await
+        .unwrap()
+        .expect("stats should exist");
+    assert_eq!(found, stats);
+}
+
+/// Tests that visits for links that no longer exist are ignored
+#[tokio::test]
+async fn test_link_visit_stats_record_visit_unknown_link() {
+    let (db, _db_container) = test_tenant_db().await;
+    let link_id = Uuid::new_v4();
+
+    let stats = LinkVisitStats::record_visit(&db, link_id, Utc::now())
+        .await
+        .unwrap();
+    assert!(stats.is_none());
+
+    assert!(LinkVisitStats::find(&db, link_id).await.unwrap().is_none());
+}
+
+/// Tests the most visited listing orders the visited links within the
+/// document box by their visit counts
+#[tokio::test]
+async fn test_link_visit_stats_most_visited() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    let (_other_box, other_root) = make_test_document_box(&db, "other", None).await;
+
+    let popular = make_test_link(&db, &root, "popular", None).await;
+    let rare = make_test_link(&db, &root, "rare", None).await;
+    let _unvisited = make_test_link(&db, &root, "unvisited", None).await;
+    let other = make_test_link(&db, &other_root, "other", None).await;
+
+    for (link_id, visits) in [(popular.id, 3), (rare.id, 1), (other.id, 5)] {
+        for _ in 0..visits {
+            LinkVisitStats::record_visit(&db, link_id, Utc::now())
+                .await
+                .unwrap();
+        }
+    }
+
+    let most = LinkVisitStats::most_visited(&db, "test", 0, 10)
+        .await
+        .unwrap();
+    let most: Vec<_> = most
+        .iter()
+        .map(|item| (item.link.id, item.visit_count))
+        .collect();
+    assert_eq!(most, vec![(popular.id, 3), (rare.id, 1)]);
+
+    let stats = LinkVisitStats::find_many(&db, &[popular.id, other.id])
+        .await
+        .unwrap();
+    assert_eq!(stats.len(), 2);
+}
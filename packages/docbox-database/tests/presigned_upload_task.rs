@@ -1,7 +1,7 @@
 use crate::common::{database::test_tenant_db, make_test_document_box};
 use chrono::{Days, Utc};
 use docbox_database::models::presigned_upload_task::{
-    CreatePresignedUploadTask, PresignedTaskStatus, PresignedUploadTask,
+    CreatePresignedUploadTask, PresignedTaskStatus, PresignedTaskStatusKind, PresignedUploadTask,
 };
 use sqlx::types::Json;
 use uuid::Uuid;
@@ -155,6 +155,60 @@ async fn test_presigned_upload_task_find() {
     assert_eq!(result, None);
 }
 
+/// Tests that presigned tasks can be found by ID and queried by status
+#[tokio::test]
+async fn test_presigned_upload_task_query_by_status() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, root) = make_test_document_box(&db, "test", None).await;
+
+    let create = || CreatePresignedUploadTask {
+        name: "test".to_string(),
+        mime: "text/plain".to_string(),
+        document_box: document_box.scope.clone(),
+        folder_id: root.id,
+        ..Default::default()
+    };
+
+    let pending = PresignedUploadTask::create(&db, create()).await.unwrap();
+    let mut failed = PresignedUploadTask::create(&db, create()).await.unwrap();
+    failed
+        .set_status(
+            &db,
+            PresignedTaskStatus::Failed {
+                error: "test".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+    let result = PresignedUploadTask::find_by_id(&db, pending.id)
+        .await
+        .unwrap();
+    assert_eq!(result, Some(pending.clone()));
+
+    let result = PresignedUploadTask::query(&db, Some(PresignedTaskStatusKind::Failed), 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(result, vec![failed.clone()]);
+
+    let total = PresignedUploadTask::total(&db, Some(PresignedTaskStatusKind::Failed))
+        .await
+        .unwrap();
+    assert_eq!(total, 1);
+
+    let total = PresignedUploadTask::total(&db, Some(PresignedTaskStatusKind::Completed))
+        .await
+        .unwrap();
+    assert_eq!(total, 0);
+
+    // Most recently created tasks are first
+    let result = PresignedUploadTask::query(&db, None, 0, 10).await.unwrap();
+    assert_eq!(result, vec![failed, pending]);
+
+    let total = PresignedUploadTask::total(&db, None).await.unwrap();
+    assert_eq!(total, 2);
+}
+
 /// Tests that expired presigned upload tasks can be found
 #[tokio::test]
 async fn test_presigned_upload_task_find_expired() {
@@ -0,0 +1,115 @@
+use crate::common::{database::test_tenant_db, make_test_document_box, make_test_user};
+use chrono::{TimeDelta, Utc};
+use docbox_database::models::recent_search::RecentSearch;
+
+mod common;
+
+/// Tests that repeated searches are moved to the front instead of duplicated
+#[tokio::test]
+async fn test_recent_search_record() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, _root) = make_test_document_box(&db, "test", None).await;
+    let user = make_test_user(&db, "test").await;
+
+    let now = Utc::now();
+    let queries = [("first", 0), ("second", 1), ("first", 2)];
+
+    for (query, offset) in queries {
+        RecentSearch::record(
+            &db,
+            &user.id,
+            &document_box.scope,
+            query,
+            now + TimeDelta::seconds(offset),
+        )
+        .await
+        .unwrap();
+    }
+
+    let searches = RecentSearch::find_by_user(&db, &user.id, &document_box.scope, 10)
+        .await
+        .unwrap();
+
+    let queries: Vec<&str> = searches
+        .iter()
+        .map(|search| search.query.as_str())
+        .collect();
+    assert_eq!(queries, vec!["first", "second"]);
+}
+
+/// Tests that pruning only keeps the most recent searches for the user
+#[tokio::test]
+async fn test_recent_search_prune() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, _root) = make_test_document_box(&db, "test", None).await;
+    let user = make_test_user(&db, "test").await;
+    let other_user = make_test_user(&db, "other").await;
+
+    let now = Utc::now();
+    for offset in 0..5 {
+        RecentSearch::record(
+            &db,
+            &user.id,
+            &document_box.scope,
+            &format!("query {offset}"),
+            now + TimeDelta::seconds(offset),
+        )
+        .await
+        .unwrap();
+    }
+
+    RecentSearch::record(&db, &other_user.id, &document_box.scope, "other", now)
+        .await
+        .unwrap();
+
+    RecentSearch::prune(&db, &user.id, &document_box.scope, 2)
+        .await
+        .unwrap();
+
+    let searches = RecentSearch::find_by_user(&db, &user.id, &document_box.scope, 10)
+        .await
+        .unwrap();
+
+    let queries: Vec<&str> = searches
+        .iter()
+        .map(|search| search.query.as_str())
+        .collect();
+    assert_eq!(queries, vec!["query 4", "query 3"]);
+
+    // Other users searches should be untouched
+    let searches = RecentSearch::find_by_user(&db, &other_user.id, &document_box.scope, 10)
+        .await
+        .unwrap();
+    assert_eq!(searches.len(), 1);
+}
+
+/// Tests that clearing the recent searches only affects the target user
+#[tokio::test]
+async fn test_recent_search_delete_by_user() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, _root) = make_test_document_box(&db, "test", None).await;
+    let user = make_test_user(&db, "test").await;
+    let other_user = make_test_user(&db, "other").await;
+
+    let now = Utc::now();
+    RecentSearch::record(&db, &user.id, &document_box.scope, "query", now)
+        .await
+        .unwrap();
+    RecentSearch::record(&db, &other_user.id, &document_box.scope, "query", now)
+        .await
+        .unwrap();
+
+    RecentSearch::delete_by_user(&db, &user.id, &document_box.scope)
+        .await
+        .unwrap();
+
+    let searches = RecentSearch::find_by_user(&db, &user.id, &document_box.scope, 10)
+        .await
+        .unwrap();
+    assert!(searches.is_empty());
+
+    let searches = RecentSearch::find_by_user(&db, &other_user.id, &document_box.scope, 10)
+        .await
+        .unwrap();
+    assert_eq!(searches.len(), 1);
+}
@@ -0,0 +1,95 @@
+use chrono::NaiveDate;
+use docbox_database::models::request_usage::{RequestUsage, RequestUsageIncrement};
+use uuid::Uuid;
+
+use crate::common::database::test_root_db;
+
+mod common;
+
+fn make_increment(tenant_id: Uuid, day: NaiveDate) -> RequestUsageIncrement {
+    RequestUsageIncrement {
+        env: "Development".to_string(),
+        tenant_id,
+        api_key_id: "test-key".to_string(),
+        day,
+        requests: 2,
+        request_bytes: 100,
+        response_bytes: 200,
+        upload_bytes: 50,
+        download_bytes: 150,
+    }
+}
+
+/// Tests that usage increments for the same day are added together
+#[tokio::test]
+async fn test_increment_request_usage() {
+    let (db, _db_container) = test_root_db().await;
+
+    let tenant_id = Uuid::new_v4();
+    let day = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+    let increment = make_increment(tenant_id, day);
+
+    RequestUsage::increment(&db, &increment).await.unwrap();
+    RequestUsage::increment(&db, &increment).await.unwrap();
+
+    let usage = RequestUsage::find_tenant_range(&db, "Development", tenant_id, day, day)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        usage,
+        vec![RequestUsage {
+            env: "Development".to_string(),
+            tenant_id,
+            api_key_id: "test-key".to_string(),
+            day,
+            requests: 4,
+            request_bytes: 200,
+            response_bytes: 400,
+            upload_bytes: 100,
+            download_bytes: 300,
+        }]
+    );
+}
+
+/// Tests that usage is only returned within the requested range and env
+#[tokio::test]
+async fn test_find_request_usage_range() {
+    let (db, _db_container) = test_root_db().await;
+
+    let tenant_id = Uuid::new_v4();
+    let other_tenant_id = Uuid::new_v4();
+    let first = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+    let second = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+    let third = NaiveDate::from_ymd_opt(2025, 1, 3).unwrap();
+
+    RequestUsage::increment(&db, &make_increment(tenant_id, first))
+        .await
+        .unwrap();
+    RequestUsage::increment(&db, &make_increment(tenant_id, second))
+        .await
+        .unwrap();
+    RequestUsage::increment(&db, &make_increment(other_tenant_id, second))
+        .await
+        .unwrap();
+    RequestUsage::increment(&db, &make_increment(tenant_id, third))
+        .await
+        .unwrap();
+
+    let usage = RequestUsage::find_range(&db, first, second, None)
+        .await
+        .unwrap();
+    assert_eq!(usage.len(), 3);
+    assert!(usage.iter().all(|usage| usage.day <= second));
+
+    let usage = RequestUsage::find_range(&db, first, third, Some("Production"))
+        .await
+        .unwrap();
+    assert!(usage.is_empty());
+
+    let usage = RequestUsage::find_tenant_range(&db, "Development", other_tenant_id, first, third)
+        .await
+        .unwrap();
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].day, second);
+}
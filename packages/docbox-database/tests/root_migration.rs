@@ -1,6 +1,6 @@
 use chrono::Utc;
 use docbox_database::{
-    migrations::initialize_root_migrations,
+    migrations::{ROOT_MIGRATIONS, get_root_migrations_status, initialize_root_migrations},
     models::root_migration::{CreateRootMigration, RootMigration},
 };
 
@@ -102,3 +102,39 @@ async fn test_root_migration_all() {
     let migration = migrations.get(1).unwrap();
     assert_eq!(migration.name, "test_2");
 }
+
+/// Tests that the status of root migrations reflects the applied migrations
+#[tokio::test]
+async fn test_root_migrations_status() {
+    let db_container = test_database_container().await;
+    let db = test_database(&db_container).await;
+    initialize_root_migrations(&db).await.unwrap();
+
+    let status = get_root_migrations_status(&db).await.unwrap();
+    assert_eq!(status.len(), ROOT_MIGRATIONS.len());
+    assert!(
+        status
+            .iter()
+            .all(|migration| migration.applied_at.is_none())
+    );
+
+    let (first_migration, _) = ROOT_MIGRATIONS[0];
+    RootMigration::create(
+        &db,
+        CreateRootMigration {
+            name: first_migration.to_string(),
+            applied_at: Utc::now(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let status = get_root_migrations_status(&db).await.unwrap();
+    assert_eq!(status[0].name, first_migration);
+    assert!(status[0].applied_at.is_some());
+    assert!(
+        status[1..]
+            .iter()
+            .all(|migration| migration.applied_at.is_none())
+    );
+}
@@ -0,0 +1,87 @@
+use docbox_database::models::scope_pattern::{CreateScopePattern, ScopePattern};
+
+use crate::common::database::test_tenant_db;
+
+mod common;
+
+/// Tests that scope patterns can be created and found
+#[tokio::test]
+async fn test_create_scope_pattern() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    let pattern = ScopePattern::create(
+        &db,
+        CreateScopePattern {
+            pattern: "org:*".to_string(),
+            parent: None,
+            description: Some("Organizations".to_string()),
+        },
+    )
+    .await
+    .unwrap();
+
+    let found = ScopePattern::find(&db, "org:*")
+        .await
+        .unwrap()
+        .expect("pattern should exist");
+    assert_eq!(found.pattern, pattern.pattern);
+    assert_eq!(found.description, pattern.description);
+
+    let all = ScopePattern::all(&db).await.unwrap();
+    assert_eq!(all.len(), 1);
+}
+
+/// Tests that child patterns are listed under their parent and are
+/// deleted along with the parent
+#[tokio::test]
+async fn test_scope_pattern_children() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    let parent = ScopePattern::create(
+        &db,
+        CreateScopePattern {
+            pattern: "org:*".to_string(),
+            parent: None,
+            description: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    ScopePattern::create(
+        &db,
+        CreateScopePattern {
+            pattern: "org:1:*".to_string(),
+            parent: Some(parent.pattern.clone()),
+            description: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let children = ScopePattern::children(&db, "org:*").await.unwrap();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].pattern, "org:1:*");
+
+    parent.delete(&db).await.unwrap();
+
+    let all = ScopePattern::all(&db).await.unwrap();
+    assert!(all.is_empty());
+}
+
+/// Tests that child patterns cannot reference an unknown parent
+#[tokio::test]
+async fn test_scope_pattern_unknown_parent() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    let result = ScopePattern::create(
+        &db,
+        CreateScopePattern {
+            pattern: "org:1:*".to_string(),
+            parent: Some("org:*".to_string()),
+            description: None,
+        },
+    )
+    .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,125 @@
+use chrono::Utc;
+use docbox_database::models::storage_object::StorageObject;
+use std::time::Duration;
+
+use crate::common::database::test_tenant_db;
+
+mod common;
+
+/// Tests that acquiring and releasing object references tracks the count
+#[tokio::test]
+async fn test_storage_object_reference_count() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    let count = StorageObject::acquire(&db, "scope/content/hash", "hash", Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let count = StorageObject::acquire(&db, "scope/content/hash", "hash", Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+
+    let count = StorageObject::release(&db, "scope/content/hash")
+        .await
+        .unwrap();
+    assert_eq!(count, Some(1));
+
+    // Object should not be deleted while still referenced
+    StorageObject::delete_unreferenced(&db, "scope/content/hash")
+        .await
+        .unwrap();
+    assert!(
+        StorageObject::find(&db, "scope/content/hash")
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    let count = StorageObject::release(&db, "scope/content/hash")
+        .await
+        .unwrap();
+    assert_eq!(count, Some(0));
+
+    StorageObject::delete_unreferenced(&db, "scope/content/hash")
+        .await
+        .unwrap();
+    assert!(
+        StorageObject::find(&db, "scope/content/hash")
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+/// Tests that releasing a key that is not a content addressed object
+/// provides no reference count
+#[tokio::test]
+async fn test_storage_object_release_unknown() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    let count = StorageObject::release(&db, "scope/unknown").await.unwrap();
+    assert_eq!(count, None);
+}
+
+/// Tests that acquiring a reference waits for a transaction holding the
+/// reference row lock, so the object can be stored before it is observed
+#[tokio::test]
+async fn test_storage_object_acquire_waits_for_lock() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    let mut t = db.begin().await.unwrap();
+    let count = StorageObject::acquire(t.as_mut(), "scope/content/hash", "hash", Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let acquire = tokio::spawn({
+        let db = db.clone();
+        async move { StorageObject::acquire(&db, "scope/content/hash", "hash", Utc::now()).await }
+    });
+
+    // Reference cannot be taken while the row is locked
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert!(!acquire.is_finished());
+
+    t.commit().await.unwrap();
+
+    let count = acquire.await.unwrap().unwrap();
+    assert_eq!(count, 2);
+}
+
+/// Tests that acquiring a reference to an object that is being deleted
+/// waits for the delete and creates a new object record
+#[tokio::test]
+async fn test_storage_object_acquire_during_delete() {
+    let (db, _db_container) = test_tenant_db().await;
+
+    StorageObject::acquire(&db, "scope/content/hash", "hash", Utc::now())
+        .await
+        .unwrap();
+
+    let mut t = db.begin().await.unwrap();
+    let count = StorageObject::release(t.as_mut(), "scope/content/hash")
+        .await
+        .unwrap();
+    assert_eq!(count, Some(0));
+
+    let acquire = tokio::spawn({
+        let db = db.clone();
+        async move { StorageObject::acquire(&db, "scope/content/hash", "hash", Utc::now()).await }
+    });
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert!(!acquire.is_finished());
+
+    StorageObject::delete_unreferenced(t.as_mut(), "scope/content/hash")
+        .await
+        .unwrap();
+    t.commit().await.unwrap();
+
+    // Reference is taken on a new record, the acquirer must store the object again
+    let count = acquire.await.unwrap().unwrap();
+    assert_eq!(count, 1);
+}
@@ -125,6 +125,33 @@ async fn test_task_complete() {
     assert_eq!(task, found_task);
 }
 
+/// Tests that progress can be reported for a task
+#[tokio::test]
+async fn test_task_set_progress() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, _root) = make_test_document_box(&db, "test", None).await;
+
+    let mut task = Task::create(&db, document_box.scope.clone()).await.unwrap();
+    assert_eq!(task.progress, None);
+
+    let test_progress_value = serde_json::json!({
+        "completed": 1
+    });
+
+    task.set_progress(&db, test_progress_value.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(task.status, TaskStatus::Pending);
+    assert_eq!(task.progress, Some(test_progress_value));
+
+    let found_task = Task::find(&db, task.id, &document_box.scope)
+        .await
+        .unwrap()
+        .expect("task should exist");
+    assert_eq!(task, found_task);
+}
+
 /// Tests that expired tasks are deleted correctly
 #[tokio::test]
 async fn test_task_delete_expired() {
@@ -153,3 +180,54 @@ async fn test_task_delete_expired() {
         .expect("task should exist");
     assert_eq!(found_task, task);
 }
+
+/// Tests that pending tasks created before a date are found as stuck
+#[tokio::test]
+async fn test_task_find_stuck() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, _root) = make_test_document_box(&db, "test", None).await;
+    let task = Task::create(&db, document_box.scope.clone()).await.unwrap();
+    let mut completed_task = Task::create(&db, document_box.scope.clone()).await.unwrap();
+    completed_task
+        .complete_task(&db, TaskStatus::Completed, None)
+        .await
+        .unwrap();
+
+    // Tasks created before the date are stuck if still pending
+    let stuck = Task::find_stuck(&db, Utc::now().checked_add_days(Days::new(1)).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(stuck, vec![task]);
+
+    // Recently created tasks are not stuck
+    let stuck = Task::find_stuck(&db, Utc::now().checked_sub_days(Days::new(1)).unwrap())
+        .await
+        .unwrap();
+    assert!(stuck.is_empty());
+}
+
+/// Tests that the number of tasks with each status are counted
+#[tokio::test]
+async fn test_task_status_counts() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (document_box, _root) = make_test_document_box(&db, "test", None).await;
+    Task::create(&db, document_box.scope.clone()).await.unwrap();
+    Task::create(&db, document_box.scope.clone()).await.unwrap();
+    let mut failed_task = Task::create(&db, document_box.scope.clone()).await.unwrap();
+    failed_task
+        .complete_task(&db, TaskStatus::Failed, None)
+        .await
+        .unwrap();
+
+    let counts: Vec<(TaskStatus, i64)> = Task::status_counts(&db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|count| (count.status, count.count))
+        .collect();
+
+    assert_eq!(
+        counts,
+        vec![(TaskStatus::Failed, 1), (TaskStatus::Pending, 2)]
+    );
+}
@@ -1,5 +1,6 @@
+use chrono::{SubsecRound, Utc};
 use docbox_database::{
-    models::tenant::{CreateTenant, Tenant, UpdateTenant},
+    models::tenant::{ActiveContentPolicy, CreateTenant, Tenant, TenantEventConfig, UpdateTenant},
     utils::DatabaseErrorExt,
 };
 use uuid::Uuid;
@@ -27,6 +28,7 @@ async fn test_create_tenant() {
             os_index_name: "test".to_string(),
             event_queue_url: None,
             env: "Development".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -52,6 +54,7 @@ async fn test_create_tenant_duplicate() {
             os_index_name: "test".to_string(),
             event_queue_url: None,
             env: "Development".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -70,6 +73,7 @@ async fn test_create_tenant_duplicate() {
             os_index_name: "test".to_string(),
             event_queue_url: None,
             env: "Development".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -89,6 +93,7 @@ async fn test_create_tenant_duplicate() {
             os_index_name: "test-dev".to_string(),
             event_queue_url: None,
             env: "Production".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -108,6 +113,7 @@ async fn test_create_tenant_duplicate() {
             os_index_name: "test-dev".to_string(),
             event_queue_url: None,
             env: "Production".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -127,6 +133,7 @@ async fn test_create_tenant_duplicate() {
             os_index_name: "test-dev".to_string(),
             event_queue_url: None,
             env: "Production".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -147,6 +154,7 @@ async fn test_create_tenant_duplicate() {
             os_index_name: "test".to_string(),
             event_queue_url: None,
             env: "Production".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -173,6 +181,7 @@ async fn test_find_by_id_with_env() {
             os_index_name: "test".to_string(),
             event_queue_url: None,
             env: "Development".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -223,6 +232,7 @@ async fn test_find_tenant_by_bucket() {
             os_index_name: "test".to_string(),
             event_queue_url: None,
             env: "Development".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -271,6 +281,7 @@ async fn test_find_tenant_by_env() {
                 os_index_name: format!("test-{i}"),
                 event_queue_url: None,
                 env: "Development".to_string(),
+                data_region: None,
             },
         )
         .await
@@ -303,6 +314,7 @@ async fn test_find_tenant_by_env() {
                 os_index_name: format!("test-{i}-prod"),
                 event_queue_url: None,
                 env: "Production".to_string(),
+                data_region: None,
             },
         )
         .await
@@ -345,6 +357,7 @@ async fn test_all_tenants() {
                 os_index_name: format!("test-{i}"),
                 event_queue_url: None,
                 env: "Development".to_string(),
+                data_region: None,
             },
         )
         .await
@@ -377,6 +390,7 @@ async fn test_delete_tenant() {
             os_index_name: "test".to_string(),
             event_queue_url: None,
             env: "Development".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -394,6 +408,7 @@ async fn test_delete_tenant() {
             os_index_name: "test-2".to_string(),
             event_queue_url: None,
             env: "Development".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -438,6 +453,7 @@ async fn test_update_tenant() {
             os_index_name: "dont-match-test".to_string(),
             event_queue_url: None,
             env: "Development".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -457,6 +473,7 @@ async fn test_update_tenant() {
             os_index_name: "test".to_string(),
             event_queue_url: None,
             env: "Development".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -487,6 +504,9 @@ async fn test_update_tenant() {
         .expect("expected to find tenant");
     assert_eq!(found_tenant, tenant);
 
+    // Truncated to match the precision stored by the database
+    let deleted_at = Utc::now().trunc_subsecs(0);
+
     // Update other tenant fields
     tenant
         .update(
@@ -501,6 +521,17 @@ async fn test_update_tenant() {
                 os_index_name: Some("test-search-2".to_string()),
                 event_queue_url: Some(Some("test-event-queue-2".to_string())),
                 env: Some("Production".to_string()),
+                storage_key_secret_name: Some(Some("test-storage-key-2".to_string())),
+                storage_deduplication: Some(true),
+                event_config: Some(Some(TenantEventConfig {
+                    include: vec!["FILE_DELETED".to_string()],
+                    ..Default::default()
+                })),
+                data_region: Some(Some("eu-west-1".to_string())),
+                maintenance_mode: Some(true),
+                active_content_policy: Some(ActiveContentPolicy::Download),
+                deleted_at: Some(Some(deleted_at)),
+                search_language: Some(Some("german".to_string())),
             },
         )
         .await
@@ -521,6 +552,23 @@ async fn test_update_tenant() {
         Some("test-event-queue-2".to_string())
     );
     assert_eq!(tenant.env, "Production");
+    assert_eq!(tenant.data_region, Some("eu-west-1".to_string()));
+    assert!(tenant.maintenance_mode);
+    assert_eq!(tenant.active_content_policy, ActiveContentPolicy::Download);
+    assert_eq!(tenant.deleted_at, Some(deleted_at));
+    assert_eq!(tenant.search_language, Some("german".to_string()));
+    assert_eq!(
+        tenant.storage_key_secret_name,
+        Some("test-storage-key-2".to_string())
+    );
+    assert!(tenant.storage_deduplication);
+    assert_eq!(
+        tenant.event_config,
+        Some(TenantEventConfig {
+            include: vec!["FILE_DELETED".to_string()],
+            ..Default::default()
+        })
+    );
 
     // Should be able to query the updated tenant and get back the same one we have after the update
     let found_tenant = Tenant::find_by_id(&db, tenant.id, &tenant.env)
@@ -31,6 +31,7 @@ async fn test_create_tenant_migration() {
             os_index_name: "test".to_string(),
             event_queue_url: None,
             env: "Development".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -68,6 +69,7 @@ async fn test_create_tenant_migration_duplicate() {
             os_index_name: "test-dev".to_string(),
             event_queue_url: None,
             env: "Development".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -85,6 +87,7 @@ async fn test_create_tenant_migration_duplicate() {
             os_index_name: "test-prod".to_string(),
             event_queue_url: None,
             env: "Production".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -164,6 +167,7 @@ async fn test_find_by_tenant_tenant_migration() {
             os_index_name: "test-dev".to_string(),
             event_queue_url: None,
             env: "Development".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -217,6 +221,7 @@ async fn test_find_by_tenant_tenant_migration() {
             os_index_name: "test-prod".to_string(),
             event_queue_url: None,
             env: "Production".to_string(),
+            data_region: None,
         },
     )
     .await
@@ -0,0 +1,49 @@
+use crate::common::{database::test_tenant_db, make_test_document_box, make_test_file};
+use chrono::{Days, Utc};
+use docbox_database::models::usage_stats::{UsageStatsDay, UsageStatsGranularity, UsageStatsPoint};
+
+mod common;
+
+/// Tests that the daily rollup counts the files created on the day
+#[tokio::test]
+async fn test_usage_stats_rollup() {
+    let (db, _db_container) = test_tenant_db().await;
+    let (_document_box, root) = make_test_document_box(&db, "test", None).await;
+    make_test_file(&db, &root, "test_1.txt", None).await;
+    make_test_file(&db, &root, "test_2.txt", None).await;
+
+    let today = Utc::now().date_naive();
+    let yesterday = today.checked_sub_days(Days::new(1)).unwrap();
+
+    assert_eq!(UsageStatsDay::latest_day(&db).await.unwrap(), None);
+
+    UsageStatsDay::rollup(&db, yesterday, Utc::now())
+        .await
+        .unwrap();
+    UsageStatsDay::rollup(&db, today, Utc::now()).await.unwrap();
+
+    // Rolling up again should replace the existing day
+    UsageStatsDay::rollup(&db, today, Utc::now()).await.unwrap();
+
+    assert_eq!(UsageStatsDay::latest_day(&db).await.unwrap(), Some(today));
+
+    let days = UsageStatsDay::find_range(&db, yesterday, today)
+        .await
+        .unwrap();
+    assert_eq!(days.len(), 2);
+
+    assert_eq!(days[0].day, yesterday);
+    assert_eq!(days[0].files_created, 0);
+    assert_eq!(days[0].total_files, 0);
+
+    assert_eq!(days[1].day, today);
+    assert_eq!(days[1].files_created, 2);
+    assert_eq!(days[1].total_files, 2);
+
+    let series = UsageStatsPoint::series(&db, UsageStatsGranularity::Month, yesterday, today)
+        .await
+        .unwrap();
+    let files_created: i64 = series.iter().map(|point| point.files_created).sum();
+    assert_eq!(files_created, 2);
+    assert_eq!(series.last().map(|point| point.total_files), Some(2));
+}
@@ -1,15 +1,22 @@
-use utoipa::OpenApi;
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
+};
 
 use crate::{
+    error::HttpErrorResponse,
+    middleware::api_key::API_KEY_HEADER,
     models::document_box::DocumentBoxScope,
     routes::{
         admin::{self, ADMIN_TAG},
         document_box::{self, DOCUMENT_BOX_TAG},
         file::{self, FILE_TAG},
         folder::{self, FOLDER_TAG},
+        inbound_email::{self, INBOUND_EMAIL_TAG},
         link::{self, LINK_TAG},
         task::{self, TASK_TAG},
         utils::{self, UTILS_TAG},
+        webhook::{self, WEBHOOK_TAG},
     },
 };
 
@@ -29,21 +36,63 @@ use crate::{
         (name = LINK_TAG, description = "Link related APIs"),
         (name = FOLDER_TAG, description = "Folder related APIs"),
         (name = TASK_TAG, description = "Background task related APIs"),
+        (name = WEBHOOK_TAG, description = "Document box webhook related APIs"),
+        (name = INBOUND_EMAIL_TAG, description = "Document box inbound email related APIs"),
         (name = ADMIN_TAG, description = "Administrator and higher privilege APIs"),
         (name = UTILS_TAG, description = "Utility APIs")
     ),
     components(
-        schemas(DocumentBoxScope)
+        schemas(DocumentBoxScope, HttpErrorResponse)
+    ),
+    modifiers(&SecurityAddon),
+    security(
+        (),
+        ("api_key" = [])
     ),
     paths(
         // Admin routes
         admin::tenant_stats,
+        admin::processing_stats,
+        admin::get_maintenance_mode,
+        admin::set_maintenance_mode,
+        admin::get_active_content_policy,
+        admin::request_usage,
+        admin::set_active_content_policy,
         admin::tenant_boxes,
+        admin::archive_document_box,
+        admin::unarchive_document_box,
+        admin::tenant_scopes,
+        admin::get_scope_patterns,
+        admin::create_scope_pattern,
+        admin::delete_scope_pattern,
+        admin::get_event_payload,
+        admin::tenant_presigned_tasks,
+        admin::retry_presigned_task,
+        admin::fail_presigned_task,
+        admin::stuck_tasks,
+        admin::task_counts,
+        admin::fail_task,
         admin::search_tenant,
+        admin::export_search_tenant,
         admin::reprocess_octet_stream_files_tenant,
+        admin::reprocess_outdated_files_tenant,
+        admin::get_generated_file_policies,
+        admin::set_generated_file_policies,
+        admin::file_access_report,
+        admin::get_upload_rules,
+        admin::set_upload_rules,
+        admin::get_mime_overrides,
+        admin::set_mime_overrides,
         admin::rebuild_search_index_tenant,
         admin::flush_database_pool_cache,
+        admin::database_pool_cache_stats,
+        admin::database_pools,
+        admin::invalidate_database_pool,
         admin::flush_tenant_cache,
+        admin::invalidate_tenant_cache,
+        admin::tenant_cache_stats,
+        admin::flush_search_credentials,
+        admin::reload_config,
         admin::http_purge_expired_presigned_tasks,
         admin::list_users,
         admin::delete_user,
@@ -51,55 +100,126 @@ use crate::{
         document_box::create,
         document_box::get,
         document_box::stats,
+        document_box::pii_report,
+        document_box::pinned,
+        document_box::import,
         document_box::delete,
         document_box::search,
+        document_box::suggest,
+        document_box::recent_searches,
+        document_box::clear_recent_searches,
         // File routes
         file::upload,
+        file::upload_multiple,
         file::create_presigned,
         file::get_presigned,
         file::get,
         file::get_children,
         file::get_edit_history,
+        file::revert_edit_history,
+        file::move_to_scope,
+        file::pin,
+        file::unpin,
         file::update,
         file::get_raw,
         file::get_raw_presigned,
+        file::email,
+        file::get_emails,
         file::get_raw_named,
+        file::get_preview,
         file::delete,
         file::get_generated,
         file::get_generated_raw,
         file::get_generated_raw_presigned,
+        file::regenerate_generated,
         file::get_generated_raw_named,
+        file::get_page_previews,
+        file::get_page_preview_raw,
+        file::get_table_csv_raw,
         file::search,
         // Folder routes
         folder::create,
         folder::get,
+        folder::get_children,
         folder::get_edit_history,
+        folder::revert_edit_history,
+        folder::move_to_scope,
+        folder::pin,
+        folder::unpin,
         folder::update,
         folder::delete,
+        folder::get_processing_config,
+        folder::set_processing_config,
+        folder::delete_processing_config,
         folder::create_zip,
         // Link routes
         link::create,
+        link::bulk_create,
         link::get,
         link::get_metadata,
+        link::refresh_metadata,
         link::get_favicon,
         link::get_image,
+        link::get_generated,
+        link::get_generated_raw,
         link::get_edit_history,
+        link::revert_edit_history,
+        link::pin,
+        link::unpin,
+        link::visit,
+        link::get_most_visited,
         link::update,
         link::delete,
         // Task routes
         task::get,
+        // Webhook routes
+        webhook::create,
+        webhook::get_all,
+        webhook::delete,
+        // Inbound email routes
+        inbound_email::create,
+        inbound_email::get,
+        inbound_email::delete,
         // Utils routes
         utils::get_options,
         utils::health,
+        utils::health_ready,
         utils::server_details,
+        utils::openapi_docs,
     )
 )]
-#[allow(unused)]
 pub struct ApiDoc;
 
+/// Adds the security schemes accepted by the API
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                API_KEY_HEADER,
+                "API key, required when the server is configured with an API key",
+            ))),
+        );
+    }
+}
+
 #[test]
 #[ignore = "generates api documentation"]
 fn generate_api_docs() {
     let docs = ApiDoc::openapi().to_pretty_json().unwrap();
     std::fs::write("docbox.json", docs).unwrap();
 }
+
+/// Ensures the committed api documentation matches the routes, regenerate
+/// using the ignored [generate_api_docs] test when this fails
+#[test]
+fn test_api_docs_up_to_date() {
+    let docs = ApiDoc::openapi().to_pretty_json().unwrap();
+    assert!(
+        docs == include_str!("../docbox.json"),
+        "docbox.json is outdated, run `cargo test -p docbox-http generate_api_docs -- --ignored`"
+    );
+}
@@ -1,9 +1,10 @@
+use crate::middleware::correlation_id::current_correlation_id;
 use axum::{
     Json,
     http::{StatusCode, header::InvalidHeaderValue},
     response::{IntoResponse, Response},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{
     error::Error,
     fmt::{Debug, Display},
@@ -43,7 +44,11 @@ impl IntoResponse for DynHttpError {
     fn into_response(self) -> Response {
         // Create the response body
         let body = Json(HttpErrorResponse {
+            code: self.inner.code().to_string(),
             reason: self.inner.reason(),
+            retryable: self.inner.retryable(),
+            correlation_id: current_correlation_id(),
+            details: self.inner.details(),
         });
         let status = self.inner.status();
 
@@ -64,6 +69,29 @@ pub trait HttpError: Error + Send + Sync + 'static {
         self.to_string()
     }
 
+    /// Provides the machine-readable error code to use in the error response,
+    /// codes are fixed SCREAMING_SNAKE_CASE strings that clients may branch on
+    /// so an existing code must never be changed
+    fn code(&self) -> &'static str;
+
+    /// Whether the request can be retried without modification and may
+    /// succeed, by default only for temporary unavailability statuses
+    fn retryable(&self) -> bool {
+        matches!(
+            self.status(),
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+
+    /// Provides optional structured details about the error to include
+    /// in the error response
+    fn details(&self) -> Option<serde_json::Value> {
+        None
+    }
+
     /// Provides the full type name for the actual error type thats been
     /// erased by dynamic typing (For better error source clarity)
     fn type_name(&self) -> &str {
@@ -87,19 +115,42 @@ impl HttpError for axum::http::Error {
     fn status(&self) -> StatusCode {
         StatusCode::INTERNAL_SERVER_ERROR
     }
+
+    fn code(&self) -> &'static str {
+        "SERVER_ERROR"
+    }
 }
 
 impl HttpError for InvalidHeaderValue {
     fn status(&self) -> StatusCode {
         StatusCode::INTERNAL_SERVER_ERROR
     }
+
+    fn code(&self) -> &'static str {
+        "SERVER_ERROR"
+    }
 }
 
 /// HTTP error JSON format for serializing responses
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct HttpErrorResponse {
+    /// Machine-readable code identifying the error, stable across releases
+    /// and safe to branch on (i.e "UNKNOWN_FILE")
+    #[serde(default)]
+    pub code: String,
+    /// Human readable message describing the error
     pub reason: String,
+    /// Whether the request may succeed if retried without modification
+    #[serde(default)]
+    pub retryable: bool,
+    /// Correlation ID of the request, matches the "x-correlation-id" response
+    /// header and the server logs for the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Additional structured details about the error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Error)]
@@ -117,4 +168,368 @@ impl HttpError for HttpCommonError {
             HttpCommonError::Unsupported => StatusCode::NOT_IMPLEMENTED,
         }
     }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpCommonError::ServerError => "SERVER_ERROR",
+            HttpCommonError::Unsupported => "UNSUPPORTED",
+        }
+    }
+
+    fn retryable(&self) -> bool {
+        // Server errors are mostly temporary database or storage failures
+        matches!(self, HttpCommonError::ServerError)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HttpCommonError, HttpError};
+    use crate::{
+        middleware::{
+            action_user::InvalidUserId, if_match::InvalidIfMatch,
+            maintenance::HttpMaintenanceError, tenant::ExtractTenantError,
+        },
+        models::{
+            admin::HttpAdminError, document_box::HttpDocumentBoxError,
+            edit_history::HttpEditHistoryError, file::HttpFileError, folder::HttpFolderError,
+            inbound_email::HttpInboundEmailError, link::HttpLinkError, page::HttpPageError,
+            search::HttpSearchError, task::HttpTaskError, webhook::HttpWebhookError,
+        },
+    };
+    use docbox_core::{
+        database::{
+            DbErr,
+            models::upload_rule::{UploadRuleKind, UploadRuleViolation},
+        },
+        document_box::import_zip::ImportZipError,
+        files::upload_file::UploadFileError,
+        folders::create_folder::CreateFolderError,
+        links::create_link::CreateLinkError,
+        processing::{ProcessingError, office::PdfConvertError},
+        search::{query_dsl::QueryDslError, validation::SearchValidationError},
+    };
+
+    /// Error codes are part of the public API, changing any of these
+    /// is a breaking change for clients matching on them
+    #[test]
+    fn test_error_codes() {
+        let cases: Vec<(Box<dyn HttpError>, &str)> = vec![
+            (Box::new(HttpCommonError::ServerError), "SERVER_ERROR"),
+            (Box::new(HttpCommonError::Unsupported), "UNSUPPORTED"),
+            (Box::new(InvalidUserId), "INVALID_USER_ID"),
+            (Box::new(InvalidIfMatch), "INVALID_IF_MATCH"),
+            (
+                Box::new(HttpMaintenanceError::TenantInMaintenance),
+                "TENANT_IN_MAINTENANCE",
+            ),
+            (
+                Box::new(ExtractTenantError::MissingTenantId),
+                "MISSING_TENANT_ID",
+            ),
+            (
+                Box::new(ExtractTenantError::InvalidTenantId),
+                "INVALID_TENANT_ID",
+            ),
+            (
+                Box::new(ExtractTenantError::MissingTenantEnv),
+                "MISSING_TENANT_ENV",
+            ),
+            (
+                Box::new(ExtractTenantError::InvalidTenantEnv),
+                "INVALID_TENANT_ENV",
+            ),
+            (
+                Box::new(ExtractTenantError::TenantNotFound),
+                "TENANT_NOT_FOUND",
+            ),
+            (
+                Box::new(ExtractTenantError::TenantDeleted),
+                "TENANT_DELETED",
+            ),
+            (Box::new(HttpAdminError::UnknownUser), "UNKNOWN_USER"),
+            (
+                Box::new(HttpAdminError::UserResourcesAttached),
+                "USER_RESOURCES_ATTACHED",
+            ),
+            (
+                Box::new(HttpAdminError::InvalidPolicyMime(String::new())),
+                "INVALID_POLICY_MIME",
+            ),
+            (
+                Box::new(HttpAdminError::InvalidUploadRulePattern(
+                    UploadRuleKind::Mime,
+                    String::new(),
+                )),
+                "INVALID_UPLOAD_RULE_PATTERN",
+            ),
+            (
+                Box::new(HttpAdminError::InvalidMimeOverride(String::new())),
+                "INVALID_MIME_OVERRIDE",
+            ),
+            (
+                Box::new(HttpAdminError::ConfigReload(String::new())),
+                "CONFIG_RELOAD",
+            ),
+            (
+                Box::new(HttpAdminError::InvalidScopePattern(String::new())),
+                "INVALID_SCOPE_PATTERN",
+            ),
+            (
+                Box::new(HttpAdminError::UnknownScopePattern),
+                "UNKNOWN_SCOPE_PATTERN",
+            ),
+            (
+                Box::new(HttpAdminError::ScopePatternAlreadyExists),
+                "SCOPE_PATTERN_ALREADY_EXISTS",
+            ),
+            (
+                Box::new(HttpAdminError::UnknownParentScopePattern(String::new())),
+                "UNKNOWN_PARENT_SCOPE_PATTERN",
+            ),
+            (
+                Box::new(HttpAdminError::ParentScopePatternMismatch(String::new())),
+                "PARENT_SCOPE_PATTERN_MISMATCH",
+            ),
+            (
+                Box::new(HttpAdminError::UnknownEventPayload),
+                "UNKNOWN_EVENT_PAYLOAD",
+            ),
+            (
+                Box::new(HttpAdminError::UnknownPresignedTask),
+                "UNKNOWN_PRESIGNED_TASK",
+            ),
+            (
+                Box::new(HttpAdminError::PresignedTaskAlreadyCompleted),
+                "PRESIGNED_TASK_ALREADY_COMPLETED",
+            ),
+            (
+                Box::new(HttpAdminError::PresignedTaskFolderMissing),
+                "PRESIGNED_TASK_FOLDER_MISSING",
+            ),
+            (
+                Box::new(HttpAdminError::PresignedTaskExpired),
+                "PRESIGNED_TASK_EXPIRED",
+            ),
+            (Box::new(HttpAdminError::UnknownTask), "UNKNOWN_TASK"),
+            (Box::new(HttpAdminError::TaskNotPending), "TASK_NOT_PENDING"),
+            (
+                Box::new(HttpAdminError::InvalidUsageRange),
+                "INVALID_USAGE_RANGE",
+            ),
+            (Box::new(HttpAdminError::UnknownTenant), "UNKNOWN_TENANT"),
+            (
+                Box::new(HttpDocumentBoxError::ScopeAlreadyExists),
+                "SCOPE_ALREADY_EXISTS",
+            ),
+            (
+                Box::new(HttpDocumentBoxError::UnknownDocumentBox),
+                "UNKNOWN_DOCUMENT_BOX",
+            ),
+            (
+                Box::new(HttpDocumentBoxError::ScopeNotRegistered),
+                "SCOPE_NOT_REGISTERED",
+            ),
+            (
+                Box::new(HttpDocumentBoxError::DocumentBoxArchived),
+                "DOCUMENT_BOX_ARCHIVED",
+            ),
+            (
+                Box::new(HttpDocumentBoxError::InvalidImportArchive(
+                    ImportZipError::TooLarge(0),
+                )),
+                "INVALID_IMPORT_ARCHIVE",
+            ),
+            (
+                Box::new(HttpDocumentBoxError::SameDocumentBox),
+                "SAME_DOCUMENT_BOX",
+            ),
+            (
+                Box::new(HttpEditHistoryError::UnknownEntry),
+                "UNKNOWN_ENTRY",
+            ),
+            (
+                Box::new(HttpEditHistoryError::CannotRevert),
+                "CANNOT_REVERT",
+            ),
+            (Box::new(HttpFileError::UnknownFile), "UNKNOWN_FILE"),
+            (Box::new(HttpFileError::UnknownTask), "UNKNOWN_TASK"),
+            (
+                Box::new(HttpFileError::FileTooLarge(0, 0)),
+                "FILE_TOO_LARGE",
+            ),
+            (Box::new(HttpFileError::FileIdInUse), "FILE_ID_IN_USE"),
+            (
+                Box::new(HttpFileError::InvalidMimeType),
+                "INVALID_MIME_TYPE",
+            ),
+            (
+                Box::new(HttpFileError::InvalidFileName),
+                "INVALID_FILE_NAME",
+            ),
+            (
+                Box::new(HttpFileError::NoMatchingGenerated),
+                "NO_MATCHING_GENERATED",
+            ),
+            (Box::new(HttpFileError::NoPreview), "NO_PREVIEW"),
+            (
+                Box::new(HttpFileError::PreviewTooLarge),
+                "PREVIEW_TOO_LARGE",
+            ),
+            (
+                Box::new(HttpFileError::UnsupportedFileType),
+                "UNSUPPORTED_FILE_TYPE",
+            ),
+            (
+                Box::new(HttpFileError::UploadRuleViolation(
+                    UploadRuleViolation::NotAllowed {
+                        kind: UploadRuleKind::Mime,
+                        value: String::new(),
+                    },
+                )),
+                "UPLOAD_RULE_VIOLATION",
+            ),
+            (
+                Box::new(HttpFileError::PresignedDownloadEncrypted),
+                "PRESIGNED_DOWNLOAD_ENCRYPTED",
+            ),
+            (Box::new(HttpFileError::VersionConflict), "VERSION_CONFLICT"),
+            (
+                Box::new(HttpFileError::EmailNotEnabled),
+                "EMAIL_NOT_ENABLED",
+            ),
+            (
+                Box::new(HttpFileError::InvalidEmailRecipient(String::new())),
+                "INVALID_EMAIL_RECIPIENT",
+            ),
+            (
+                Box::new(HttpFileError::EmailLinkUnavailable),
+                "EMAIL_LINK_UNAVAILABLE",
+            ),
+            (
+                Box::new(HttpFileError::UploadFileError(UploadFileError::Processing(
+                    ProcessingError::MalformedFile(String::new()),
+                ))),
+                "UNPROCESSABLE_FILE",
+            ),
+            (
+                Box::new(HttpFileError::UploadFileError(UploadFileError::Processing(
+                    ProcessingError::ConvertFile(PdfConvertError::ConverterUnavailable),
+                ))),
+                "CONVERTER_UNAVAILABLE",
+            ),
+            (
+                Box::new(HttpFileError::UploadFileError(UploadFileError::CreateFile(
+                    DbErr::RowNotFound,
+                ))),
+                "UPLOAD_FILE_FAILED",
+            ),
+            (Box::new(HttpFolderError::UnknownFolder), "UNKNOWN_FOLDER"),
+            (
+                Box::new(HttpFolderError::CreateError(CreateFolderError::Database(
+                    DbErr::RowNotFound,
+                ))),
+                "CREATE_FOLDER_FAILED",
+            ),
+            (
+                Box::new(HttpFolderError::UnknownTargetFolder),
+                "UNKNOWN_TARGET_FOLDER",
+            ),
+            (
+                Box::new(HttpFolderError::CannotDeleteRoot),
+                "CANNOT_DELETE_ROOT",
+            ),
+            (
+                Box::new(HttpFolderError::CannotModifyRoot),
+                "CANNOT_MODIFY_ROOT",
+            ),
+            (
+                Box::new(HttpFolderError::CannotMoveIntoSelf),
+                "CANNOT_MOVE_INTO_SELF",
+            ),
+            (Box::new(HttpFolderError::CreateZipFile), "CREATE_ZIP_FILE"),
+            (
+                Box::new(HttpFolderError::VersionConflict),
+                "VERSION_CONFLICT",
+            ),
+            (Box::new(HttpInboundEmailError::NotEnabled), "NOT_ENABLED"),
+            (
+                Box::new(HttpInboundEmailError::UnknownAddress),
+                "UNKNOWN_ADDRESS",
+            ),
+            (
+                Box::new(HttpInboundEmailError::AddressExists),
+                "ADDRESS_EXISTS",
+            ),
+            (Box::new(HttpLinkError::UnknownLink), "UNKNOWN_LINK"),
+            (Box::new(HttpLinkError::InvalidLinkUrl), "INVALID_LINK_URL"),
+            (
+                Box::new(HttpLinkError::CreateError(CreateLinkError::Database(
+                    DbErr::RowNotFound,
+                ))),
+                "CREATE_LINK_FAILED",
+            ),
+            (Box::new(HttpLinkError::FailedResolve), "FAILED_RESOLVE"),
+            (Box::new(HttpLinkError::NoFavicon), "NO_FAVICON"),
+            (Box::new(HttpLinkError::NoImage), "NO_IMAGE"),
+            (
+                Box::new(HttpLinkError::NoMatchingGenerated),
+                "NO_MATCHING_GENERATED",
+            ),
+            (Box::new(HttpLinkError::VersionConflict), "VERSION_CONFLICT"),
+            (Box::new(HttpPageError::InvalidCursor), "INVALID_CURSOR"),
+            (Box::new(HttpSearchError::MissingUser), "MISSING_USER"),
+            (Box::new(HttpTaskError::UnknownTask), "UNKNOWN_TASK"),
+            (
+                Box::new(HttpWebhookError::UnknownWebhook),
+                "UNKNOWN_WEBHOOK",
+            ),
+            (Box::new(HttpWebhookError::InvalidUrl), "INVALID_URL"),
+            (
+                Box::new(HttpWebhookError::UnknownEvent(String::new())),
+                "UNKNOWN_EVENT",
+            ),
+        ];
+
+        for (error, code) in cases {
+            assert_eq!(error.code(), code, "unexpected code for {error:?}");
+        }
+
+        let search_cases = [
+            (SearchValidationError::QueryTooLong, "QUERY_TOO_LONG"),
+            (SearchValidationError::SizeTooLarge, "SIZE_TOO_LARGE"),
+            (
+                SearchValidationError::SuggestSizeTooLarge,
+                "SUGGEST_SIZE_TOO_LARGE",
+            ),
+            (SearchValidationError::OffsetTooLarge, "OFFSET_TOO_LARGE"),
+            (SearchValidationError::TimeoutTooLarge, "TIMEOUT_TOO_LARGE"),
+            (SearchValidationError::MissingScopes, "MISSING_SCOPES"),
+            (SearchValidationError::TooManyScopes, "TOO_MANY_SCOPES"),
+            (
+                SearchValidationError::InvalidWildcardScope(String::new()),
+                "INVALID_WILDCARD_SCOPE",
+            ),
+            (
+                SearchValidationError::AdvancedQueryTooDeep,
+                "ADVANCED_QUERY_TOO_DEEP",
+            ),
+            (
+                SearchValidationError::AdvancedQueryTooLarge,
+                "ADVANCED_QUERY_TOO_LARGE",
+            ),
+            (
+                SearchValidationError::InvalidAdvancedQuery(""),
+                "INVALID_ADVANCED_QUERY",
+            ),
+            (
+                SearchValidationError::InvalidQueryDsl(QueryDslError::Empty),
+                "INVALID_QUERY_DSL",
+            ),
+        ];
+
+        for (error, code) in search_cases {
+            assert_eq!(HttpSearchError::InvalidRequest(error).code(), code);
+        }
+    }
 }
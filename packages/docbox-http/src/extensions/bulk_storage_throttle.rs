@@ -0,0 +1,7 @@
+use docbox_core::storage::throttle::StorageThrottle;
+
+/// Rate limit applied to the storage layers used by bulk admin operations
+/// (i.e rebuilding the search index or reprocessing files), [None] when
+/// bulk operations are not limited
+#[derive(Clone, Default)]
+pub struct BulkStorageThrottle(pub Option<StorageThrottle>);
@@ -0,0 +1,32 @@
+use serde::Deserialize;
+
+/// Cache-Control header values to respond with for each class of route,
+/// see [RouteClass](crate::middleware::security_headers::RouteClass)
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheControlPolicy {
+    /// Metadata and listing responses, these change whenever the
+    /// document box is modified
+    pub api: String,
+    /// Raw file contents
+    pub raw: String,
+    /// Sanitized HTML previews of files
+    pub preview: String,
+    /// Raw contents of generated files (i.e PDF conversions, page previews),
+    /// generated files are only replaced when explicitly regenerated
+    pub generated: String,
+    /// Favicons and social images resolved from the website of a link
+    pub website: String,
+}
+
+impl Default for CacheControlPolicy {
+    fn default() -> Self {
+        Self {
+            api: "no-store".to_string(),
+            raw: "private, no-cache".to_string(),
+            preview: "private, no-cache".to_string(),
+            generated: "private, max-age=31536000, immutable".to_string(),
+            website: "public, max-age=3600, stale-while-revalidate=86400".to_string(),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use serde::Serialize;
+use std::{error::Error, sync::Arc};
+use utoipa::ToSchema;
+
+/// Settings that were applied by reloading the server config
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReloadedSettings {
+    /// Maximum allowed file size in bytes
+    pub max_file_size: i32,
+    /// Logging filter directives, [None] when using the default filter
+    pub log_filter: Option<String>,
+}
+
+/// Reloads the server config, implemented by the server that owns
+/// the config file
+pub trait ConfigReloader: Send + Sync + 'static {
+    /// Re-read the config file and apply the settings that can be
+    /// changed while the server is running
+    fn reload_config(&self) -> Result<ReloadedSettings, Box<dyn Error + Send + Sync>>;
+}
+
+/// Extension providing access to the server config reloader
+#[derive(Clone)]
+pub struct ConfigReloadHandle(pub Arc<dyn ConfigReloader>);
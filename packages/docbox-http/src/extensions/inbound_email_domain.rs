@@ -0,0 +1,4 @@
+/// Domain that inbound email addresses for document boxes are created
+/// at (i.e "inbox.example.com"), inbound email is disabled when not set
+#[derive(Clone, Default)]
+pub struct InboundEmailDomain(pub Option<String>);
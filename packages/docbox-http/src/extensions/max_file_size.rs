@@ -1,2 +1,25 @@
-#[derive(Clone, Copy)]
-pub struct MaxFileSizeBytes(pub i32);
+use std::sync::{
+    Arc,
+    atomic::{AtomicI32, Ordering},
+};
+
+/// Maximum allowed file size in bytes, shared so that the value can
+/// be updated when the server config is reloaded
+#[derive(Clone)]
+pub struct MaxFileSizeBytes(Arc<AtomicI32>);
+
+impl MaxFileSizeBytes {
+    pub fn new(value: i32) -> Self {
+        Self(Arc::new(AtomicI32::new(value)))
+    }
+
+    /// Get the current maximum file size
+    pub fn get(&self) -> i32 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Replace the maximum file size
+    pub fn set(&self, value: i32) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+}
@@ -1,2 +1,7 @@
+pub mod bulk_storage_throttle;
+pub mod cache_control;
+pub mod config_reload;
+pub mod inbound_email_domain;
 pub mod max_file_size;
+pub mod search_auto_heal;
 pub mod server_version;
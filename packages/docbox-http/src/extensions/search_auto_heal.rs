@@ -0,0 +1,33 @@
+use docbox_core::{
+    database::{DbPool, models::document_box::DocumentBoxScopeRaw},
+    search::TenantSearchIndex,
+    storage::StorageLayer,
+    tenant::heal_tenant_index::heal_tenant_index,
+};
+
+/// Whether tenant search indexes that were deleted out-of-band should be
+/// recreated and re-indexed when writing to the index fails
+#[derive(Clone, Copy, Default)]
+pub struct SearchAutoHeal(pub bool);
+
+impl SearchAutoHeal {
+    /// Heal the tenant search index after a failed write in the document
+    /// box `scope`, does nothing when auto healing is disabled
+    ///
+    /// The failed write itself is not retried
+    pub async fn heal(
+        &self,
+        db: &DbPool,
+        search: &TenantSearchIndex,
+        storage: &StorageLayer,
+        scope: &DocumentBoxScopeRaw,
+    ) {
+        if !self.0 {
+            return;
+        }
+
+        if let Err(error) = heal_tenant_index(db, search, storage, scope).await {
+            tracing::error!(?error, "failed to heal tenant search index");
+        }
+    }
+}
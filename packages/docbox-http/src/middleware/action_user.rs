@@ -59,12 +59,16 @@ pub struct UserParams {
 
 #[derive(Debug, Error)]
 #[error("user id was not a valid utf8 string")]
-struct InvalidUserId;
+pub(crate) struct InvalidUserId;
 
 impl HttpError for InvalidUserId {
     fn status(&self) -> axum::http::StatusCode {
         StatusCode::BAD_REQUEST
     }
+
+    fn code(&self) -> &'static str {
+        "INVALID_USER_ID"
+    }
 }
 
 impl<S> FromRequestParts<S> for ActionUser
@@ -9,6 +9,9 @@ use std::{
 };
 use tower::{Layer, Service};
 
+/// Header containing the API key
+pub const API_KEY_HEADER: &str = "x-docbox-api-key";
+
 #[derive(Clone)]
 pub struct ApiKeyLayer {
     key: String,
@@ -52,7 +55,7 @@ where
     }
 
     fn call(&mut self, request: Request) -> Self::Future {
-        let header = match request.headers().get("x-docbox-api-key") {
+        let header = match request.headers().get(API_KEY_HEADER) {
             Some(value) => value,
             None => {
                 return Box::pin(async move {
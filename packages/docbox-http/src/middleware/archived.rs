@@ -0,0 +1,74 @@
+//! Middleware rejecting modifications to archived document boxes
+
+use crate::{
+    error::{DynHttpError, HttpCommonError},
+    middleware::tenant::TenantDb,
+    models::document_box::HttpDocumentBoxError,
+};
+use axum::{
+    extract::{MatchedPath, Path, Request},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use docbox_core::database::models::document_box::DocumentBox;
+use std::collections::HashMap;
+
+/// Suffixes of POST routes that don't modify the document box contents
+const READ_ONLY_POST_ROUTES: &[&str] = &["/search", "/search/export", "/zip", "/raw-presigned"];
+
+/// Suffixes of routes that only modify data belonging to the current user
+/// rather than the document box contents
+const USER_DATA_ROUTES: &[&str] = &["/search/recent"];
+
+/// Check if the request using `method` on the `route` would modify the
+/// contents of the document box
+pub(crate) fn is_modifying_request(method: &Method, route: &str) -> bool {
+    if USER_DATA_ROUTES
+        .iter()
+        .any(|suffix| route.ends_with(suffix))
+    {
+        return false;
+    }
+
+    match *method {
+        Method::GET | Method::HEAD | Method::OPTIONS => false,
+        Method::POST => !READ_ONLY_POST_ROUTES
+            .iter()
+            .any(|suffix| route.ends_with(suffix)),
+        _ => true,
+    }
+}
+
+/// Rejects requests that would modify the contents of an archived document
+/// box, must be added as a route layer so the scope path parameter is available
+pub async fn archived_document_box_middleware(
+    TenantDb(db): TenantDb,
+    matched_path: MatchedPath,
+    Path(params): Path<HashMap<String, String>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, DynHttpError> {
+    if !is_modifying_request(request.method(), matched_path.as_str()) {
+        return Ok(next.run(request).await);
+    }
+
+    let Some(scope) = params.get("scope") else {
+        return Ok(next.run(request).await);
+    };
+
+    let archived = DocumentBox::is_archived(&db, scope)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query document box archived state");
+            HttpCommonError::ServerError
+        })?
+        // Unknown document boxes are handled by the route itself
+        .unwrap_or_default();
+
+    if archived {
+        return Err(HttpDocumentBoxError::DocumentBoxArchived.into());
+    }
+
+    Ok(next.run(request).await)
+}
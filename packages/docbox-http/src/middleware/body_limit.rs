@@ -0,0 +1,80 @@
+use crate::extensions::max_file_size::MaxFileSizeBytes;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{StatusCode, header::CONTENT_LENGTH},
+    response::{IntoResponse, Response},
+};
+use http_body_util::Limited;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// Layer limiting the size of request bodies to the current [MaxFileSizeBytes],
+/// unlike a fixed limit this applies changes made when the config is reloaded
+#[derive(Clone)]
+pub struct MaxFileSizeLayer {
+    max_file_size: MaxFileSizeBytes,
+}
+
+impl MaxFileSizeLayer {
+    pub fn new(max_file_size: MaxFileSizeBytes) -> Self {
+        Self { max_file_size }
+    }
+}
+
+impl<S> Layer<S> for MaxFileSizeLayer {
+    type Service = MaxFileSizeMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MaxFileSizeMiddleware {
+            inner,
+            max_file_size: self.max_file_size.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MaxFileSizeMiddleware<S> {
+    inner: S,
+    max_file_size: MaxFileSizeBytes,
+}
+
+impl<S> Service<Request> for MaxFileSizeMiddleware<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let limit = usize::try_from(self.max_file_size.get()).unwrap_or_default();
+
+        let content_length = request
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<usize>().ok());
+
+        // Reject requests that declare a body larger than the limit up front
+        if content_length.is_some_and(|length| length > limit) {
+            return Box::pin(async move {
+                Ok((StatusCode::PAYLOAD_TOO_LARGE, "length limit exceeded").into_response())
+            });
+        }
+
+        let (parts, body) = request.into_parts();
+        let request = Request::from_parts(parts, Body::new(Limited::new(body, limit)));
+
+        Box::pin(self.inner.call(request))
+    }
+}
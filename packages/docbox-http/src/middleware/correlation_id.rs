@@ -0,0 +1,56 @@
+//! Middleware assigning a correlation ID to each request
+//!
+//! The correlation ID is taken from the "x-correlation-id" request header when
+//! provided by the client, otherwise a new ID is generated. The ID is included
+//! in the request logs, the response headers and any error response bodies.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Header containing the request correlation ID
+pub const CORRELATION_ID_HEADER: HeaderName = HeaderName::from_static("x-correlation-id");
+
+/// Maximum length of a client provided correlation ID
+const MAX_CORRELATION_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    /// Correlation ID of the request currently being handled
+    static CORRELATION_ID: String;
+}
+
+/// Get the correlation ID of the request currently being handled, [None]
+/// when called outside of [correlation_id_middleware]
+pub fn current_correlation_id() -> Option<String> {
+    CORRELATION_ID.try_with(Clone::clone).ok()
+}
+
+/// Assigns a correlation ID to the request, available to handlers
+/// through [current_correlation_id]
+pub async fn correlation_id_middleware(request: Request, next: Next) -> Response {
+    let correlation_id = request
+        .headers()
+        .get(&CORRELATION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= MAX_CORRELATION_ID_LENGTH)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let span = tracing::info_span!("request", %correlation_id);
+
+    let mut response = CORRELATION_ID
+        .scope(correlation_id.clone(), next.run(request))
+        .instrument(span)
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&correlation_id) {
+        response.headers_mut().insert(CORRELATION_ID_HEADER, value);
+    }
+
+    response
+}
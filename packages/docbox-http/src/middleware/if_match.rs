@@ -0,0 +1,90 @@
+//! Extractor for the `If-Match` header used for optimistic concurrency
+//! control when updating items
+//!
+//! Item versions are exposed to clients through the `ETag` header as
+//! a quoted version number (e.g `"3"`) providing the value back in the
+//! `If-Match` header will reject the update if the item was modified
+
+use crate::error::{DynHttpError, HttpError};
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderName, HeaderValue, StatusCode, header, request::Parts},
+};
+use thiserror::Error;
+use utoipa::IntoParams;
+
+/// Version the item is expected to be at, [None] when no `If-Match`
+/// header was provided or the header was the `*` wildcard
+pub struct IfMatch(pub Option<i64>);
+
+/// OpenAPI param for the optional `If-Match` header
+#[derive(IntoParams)]
+#[into_params(parameter_in = Header)]
+#[allow(unused)]
+pub struct IfMatchParams {
+    /// Optional ETag of the item from a previous request, the update is
+    /// rejected with a 409 if the item was modified since
+    #[param(rename = "if-match")]
+    pub if_match: Option<String>,
+}
+
+#[derive(Debug, Error)]
+#[error("if-match header was not a valid item version")]
+pub(crate) struct InvalidIfMatch;
+
+impl HttpError for InvalidIfMatch {
+    fn status(&self) -> axum::http::StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    fn code(&self) -> &'static str {
+        "INVALID_IF_MATCH"
+    }
+}
+
+/// Response headers containing the `ETag` of an item
+pub type ETagHeader = [(HeaderName, HeaderValue); 1];
+
+/// Create the `ETag` response header for the provided item `version`
+pub fn version_etag(version: i64) -> ETagHeader {
+    // Formatted integers are always valid header values
+    let value =
+        HeaderValue::from_str(&format!("\"{version}\"")).expect("etag should be a valid header");
+
+    [(header::ETAG, value)]
+}
+
+/// Parse the item version from an `If-Match` header value, accepts
+/// both quoted and unquoted versions
+fn parse_if_match(value: &str) -> Option<Option<i64>> {
+    let value = value.trim();
+    if value == "*" {
+        return Some(None);
+    }
+
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+
+    value.parse::<i64>().ok().map(Some)
+}
+
+impl<S> FromRequestParts<S> for IfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = DynHttpError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let value = match parts.headers.get(header::IF_MATCH) {
+            Some(value) => value,
+            None => return Ok(IfMatch(None)),
+        };
+
+        let value = value.to_str().map_err(|_| InvalidIfMatch)?;
+        let version = parse_if_match(value).ok_or(InvalidIfMatch)?;
+
+        Ok(IfMatch(version))
+    }
+}
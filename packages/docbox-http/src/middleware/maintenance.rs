@@ -0,0 +1,61 @@
+//! Middleware rejecting modifications to tenants in maintenance mode
+
+use crate::{
+    error::{DynHttpError, HttpError},
+    middleware::archived::is_modifying_request,
+};
+use axum::{
+    Extension,
+    extract::{MatchedPath, Request},
+    http::{HeaderValue, StatusCode, header::RETRY_AFTER},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use docbox_core::database::models::tenant::Tenant;
+use thiserror::Error;
+
+/// Number of seconds clients are asked to wait before retrying requests
+/// rejected while the tenant is in maintenance mode
+const MAINTENANCE_RETRY_AFTER_SECONDS: u64 = 60;
+
+#[derive(Debug, Error)]
+pub enum HttpMaintenanceError {
+    #[error("tenant is in maintenance mode, try again later")]
+    TenantInMaintenance,
+}
+
+impl HttpError for HttpMaintenanceError {
+    fn status(&self) -> StatusCode {
+        match self {
+            HttpMaintenanceError::TenantInMaintenance => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpMaintenanceError::TenantInMaintenance => "TENANT_IN_MAINTENANCE",
+        }
+    }
+}
+
+/// Rejects requests that would modify the data of a tenant that is in
+/// maintenance mode, must be added as a route layer within the tenant
+/// auth middleware so the matched path and tenant are available
+pub async fn tenant_maintenance_middleware(
+    Extension(tenant): Extension<Tenant>,
+    matched_path: MatchedPath,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !tenant.maintenance_mode || !is_modifying_request(request.method(), matched_path.as_str()) {
+        return next.run(request).await;
+    }
+
+    let mut response =
+        DynHttpError::from(HttpMaintenanceError::TenantInMaintenance).into_response();
+    response.headers_mut().insert(
+        RETRY_AFTER,
+        HeaderValue::from(MAINTENANCE_RETRY_AFTER_SECONDS),
+    );
+    response
+}
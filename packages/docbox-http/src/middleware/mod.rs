@@ -1,3 +1,10 @@
 pub mod action_user;
 pub mod api_key;
+pub mod archived;
+pub mod body_limit;
+pub mod correlation_id;
+pub mod if_match;
+pub mod maintenance;
+pub mod request_usage;
+pub mod security_headers;
 pub mod tenant;
@@ -0,0 +1,164 @@
+//! Middleware metering the request and response bytes of each tenant
+//!
+//! The request and response bodies are counted as they are streamed, the
+//! usage is recorded once both bodies have been dropped. Requests with a
+//! multipart body are counted as uploads and responses with a
+//! `Content-Disposition` header (File contents) are counted as downloads.
+//!
+//! Must be applied after the tenant has been loaded by the tenant middleware,
+//! requests are not metered when the [RequestUsageRecorder] extension is not
+//! present
+//!
+//! Presigned uploads and downloads are transferred directly with the storage
+//! backend and are not metered
+
+use crate::middleware::api_key::API_KEY_HEADER;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::Response,
+};
+use docbox_core::{
+    database::models::tenant::{Tenant, TenantId},
+    stats::request_usage::{NO_API_KEY_ID, RequestUsageEvent, RequestUsageRecorder, api_key_id},
+};
+use http_body_util::BodyExt;
+use std::{
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+#[derive(Clone, Default)]
+pub struct RequestUsageLayer;
+
+impl<S> Layer<S> for RequestUsageLayer {
+    type Service = RequestUsageMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestUsageMiddleware { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct RequestUsageMiddleware<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for RequestUsageMiddleware<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let extensions = request.extensions();
+        let (Some(recorder), Some(tenant)) = (
+            extensions.get::<RequestUsageRecorder>(),
+            extensions.get::<Tenant>(),
+        ) else {
+            return Box::pin(self.inner.call(request));
+        };
+
+        let api_key_id = request
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(api_key_id)
+            .unwrap_or_else(|| NO_API_KEY_ID.to_string());
+
+        let upload = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("multipart/"));
+
+        let meter = Arc::new(UsageMeter {
+            recorder: recorder.clone(),
+            env: tenant.env.clone(),
+            tenant_id: tenant.id,
+            api_key_id,
+            upload,
+            download: AtomicBool::new(false),
+            request_bytes: AtomicU64::new(0),
+            response_bytes: AtomicU64::new(0),
+        });
+
+        let (parts, body) = request.into_parts();
+        let request_meter = meter.clone();
+        let body = metered_body(body, move |length| {
+            request_meter
+                .request_bytes
+                .fetch_add(length, Ordering::Relaxed);
+        });
+        let future = self.inner.call(Request::from_parts(parts, body));
+
+        Box::pin(async move {
+            let response = future.await?;
+
+            let download = response.headers().contains_key(CONTENT_DISPOSITION);
+            meter.download.store(download, Ordering::Relaxed);
+
+            let (parts, body) = response.into_parts();
+            let body = metered_body(body, move |length| {
+                meter.response_bytes.fetch_add(length, Ordering::Relaxed);
+            });
+
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
+
+/// Wrap the `body` calling `count` with the length of each data frame
+fn metered_body<F>(body: Body, count: F) -> Body
+where
+    F: Fn(u64) + Send + 'static,
+{
+    Body::new(body.map_frame(move |frame| {
+        if let Some(data) = frame.data_ref() {
+            count(data.len() as u64);
+        }
+
+        frame
+    }))
+}
+
+/// Usage of a single request, shared between the request and response
+/// bodies. The usage is recorded when both bodies have been dropped
+struct UsageMeter {
+    recorder: RequestUsageRecorder,
+    env: String,
+    tenant_id: TenantId,
+    api_key_id: String,
+    upload: bool,
+    download: AtomicBool,
+    request_bytes: AtomicU64,
+    response_bytes: AtomicU64,
+}
+
+impl Drop for UsageMeter {
+    fn drop(&mut self) {
+        self.recorder.record(RequestUsageEvent {
+            env: std::mem::take(&mut self.env),
+            tenant_id: self.tenant_id,
+            api_key_id: std::mem::take(&mut self.api_key_id),
+            request_bytes: *self.request_bytes.get_mut(),
+            response_bytes: *self.response_bytes.get_mut(),
+            upload: self.upload,
+            download: *self.download.get_mut(),
+        });
+    }
+}
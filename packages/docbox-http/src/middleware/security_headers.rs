@@ -0,0 +1,159 @@
+//! Middleware applying the response header policy for each class of route
+//!
+//! All responses are given `X-Content-Type-Options: nosniff` along with the
+//! `Cache-Control` header configured for the [RouteClass] of the route and
+//! the default `Content-Security-Policy` for the class. Headers already set
+//! by the handler (i.e a content security policy chosen based on the type of
+//! file being served) are left as-is.
+//!
+//! Unsuccessful responses always use the `api` cache control policy so that
+//! errors from routes serving cacheable content are not cached.
+
+use crate::extensions::cache_control::CacheControlPolicy;
+use axum::{
+    extract::Request,
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{CACHE_CONTROL, CONTENT_SECURITY_POLICY, X_CONTENT_TYPE_OPTIONS},
+    },
+    response::Response,
+};
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// Class of route used to determine the headers to apply to its responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteClass {
+    /// Metadata and listing routes
+    Api,
+    /// Routes serving the raw contents of files
+    Raw,
+    /// Routes serving sanitized HTML previews of files
+    Preview,
+    /// Routes serving the raw contents of generated files
+    Generated,
+    /// Routes serving images resolved from the website of a link
+    Website,
+}
+
+impl RouteClass {
+    /// Get the Cache-Control header value for the class from the `policy`
+    fn cache_control(self, policy: &CacheControlPolicy) -> &str {
+        match self {
+            RouteClass::Api => &policy.api,
+            RouteClass::Raw => &policy.raw,
+            RouteClass::Preview => &policy.preview,
+            RouteClass::Generated => &policy.generated,
+            RouteClass::Website => &policy.website,
+        }
+    }
+
+    /// Get the default Content-Security-Policy header value for the class
+    fn content_security_policy(self) -> &'static str {
+        match self {
+            RouteClass::Api => "default-src 'none'; frame-ancestors 'none'",
+            RouteClass::Raw | RouteClass::Generated => {
+                "script-src 'none'; object-src 'none'; base-uri 'none'; form-action 'none'"
+            }
+            // Preview content is sanitized but scripts are still blocked as a precaution
+            RouteClass::Preview => {
+                "default-src 'none'; style-src 'unsafe-inline'; img-src data:; base-uri 'none'; form-action 'none'"
+            }
+            RouteClass::Website => "default-src 'none'; img-src 'self' data:;",
+        }
+    }
+}
+
+/// Layer applying the response headers for a [RouteClass], the cache control
+/// values are taken from the [CacheControlPolicy] request extension falling
+/// back to the defaults when not provided
+#[derive(Clone)]
+pub struct SecurityHeadersLayer {
+    class: RouteClass,
+}
+
+impl SecurityHeadersLayer {
+    pub fn new(class: RouteClass) -> Self {
+        Self { class }
+    }
+}
+
+impl<S> Layer<S> for SecurityHeadersLayer {
+    type Service = SecurityHeadersMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SecurityHeadersMiddleware {
+            inner,
+            class: self.class,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SecurityHeadersMiddleware<S> {
+    inner: S,
+    class: RouteClass,
+}
+
+impl<S> Service<Request> for SecurityHeadersMiddleware<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let policy = request
+            .extensions()
+            .get::<Arc<CacheControlPolicy>>()
+            .cloned()
+            .unwrap_or_default();
+        let class = self.class;
+        let future = self.inner.call(request);
+
+        Box::pin(async move {
+            let mut response = future.await?;
+            let status = response.status();
+            apply_headers(class, &policy, status, response.headers_mut());
+            Ok(response)
+        })
+    }
+}
+
+/// Apply the headers for the route `class` to the response `headers`,
+/// existing headers are not replaced
+fn apply_headers(
+    class: RouteClass,
+    policy: &CacheControlPolicy,
+    status: StatusCode,
+    headers: &mut HeaderMap,
+) {
+    headers
+        .entry(X_CONTENT_TYPE_OPTIONS)
+        .or_insert(HeaderValue::from_static("nosniff"));
+
+    headers
+        .entry(CONTENT_SECURITY_POLICY)
+        .or_insert(HeaderValue::from_static(class.content_security_policy()));
+
+    let cache_class = if status.is_success() {
+        class
+    } else {
+        RouteClass::Api
+    };
+
+    if let Ok(value) = HeaderValue::from_str(cache_class.cache_control(policy)) {
+        headers.entry(CACHE_CONTROL).or_insert(value);
+    }
+}
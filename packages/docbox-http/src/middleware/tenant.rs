@@ -13,9 +13,10 @@ use axum::{
 use docbox_core::{
     database::{DatabasePoolCache, DbPool, models::tenant::Tenant},
     events::{EventPublisherFactory, TenantEventPublisher},
+    files::access_stats::{FileAccessRecorder, TenantFileAccessRecorder},
     search::{SearchIndexFactory, TenantSearchIndex},
     storage::{StorageLayer, StorageLayerFactory},
-    tenant::{tenant_cache::TenantCache, tenant_options_ext::TenantOptionsExt},
+    tenant::{tenant_cache::TenantCache, tenant_storage_key::TenantStorageKeyCache},
 };
 use thiserror::Error;
 use tracing::Instrument;
@@ -86,12 +87,25 @@ pub enum ExtractTenantError {
     InvalidTenantEnv,
     #[error("tenant not found")]
     TenantNotFound,
+    #[error("tenant has been deleted")]
+    TenantDeleted,
 }
 
 impl HttpError for ExtractTenantError {
     fn status(&self) -> axum::http::StatusCode {
         StatusCode::BAD_REQUEST
     }
+
+    fn code(&self) -> &'static str {
+        match self {
+            ExtractTenantError::MissingTenantId => "MISSING_TENANT_ID",
+            ExtractTenantError::InvalidTenantId => "INVALID_TENANT_ID",
+            ExtractTenantError::MissingTenantEnv => "MISSING_TENANT_ENV",
+            ExtractTenantError::InvalidTenantEnv => "INVALID_TENANT_ENV",
+            ExtractTenantError::TenantNotFound => "TENANT_NOT_FOUND",
+            ExtractTenantError::TenantDeleted => "TENANT_DELETED",
+        }
+    }
 }
 
 /// Extracts the target tenant for the provided request
@@ -131,6 +145,11 @@ pub async fn extract_tenant(
         })?
         .ok_or(ExtractTenantError::TenantNotFound)?;
 
+    // Soft deleted tenants are disabled until restored
+    if tenant.deleted_at.is_some() {
+        return Err(ExtractTenantError::TenantDeleted.into());
+    }
+
     Ok(tenant)
 }
 
@@ -217,8 +236,23 @@ where
             HttpCommonError::ServerError
         })?;
 
+        // Extract tenant storage key cache
+        let storage_keys: &TenantStorageKeyCache = parts.extensions.get().ok_or_else(|| {
+            tracing::error!("storage key cache is missing");
+            HttpCommonError::ServerError
+        })?;
+
+        // Load the tenant storage options (Including encryption keys)
+        let options = storage_keys
+            .storage_layer_options(tenant)
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to load tenant storage keys");
+                HttpCommonError::ServerError
+            })?;
+
         // Create tenant storage layer
-        let storage = factory.create_layer(tenant.storage_layer_options());
+        let storage = factory.create_layer(options);
 
         Ok(TenantStorage(storage))
     }
@@ -249,3 +283,29 @@ where
         Ok(TenantEvents(events.create_event_publisher(tenant)))
     }
 }
+
+/// Tenant file access recorder
+pub struct TenantFileAccess(pub TenantFileAccessRecorder);
+
+impl<S> FromRequestParts<S> for TenantFileAccess
+where
+    S: Send + Sync,
+{
+    type Rejection = DynHttpError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // Extract current tenant
+        let tenant: &Tenant = parts.extensions.get().ok_or_else(|| {
+            tracing::error!("tenant not available within this scope");
+            HttpCommonError::ServerError
+        })?;
+
+        // Get the file access recorder
+        let recorder: &FileAccessRecorder = parts.extensions.get().ok_or_else(|| {
+            tracing::error!("file access recorder is missing");
+            HttpCommonError::ServerError
+        })?;
+
+        Ok(TenantFileAccess(recorder.tenant_recorder(tenant)))
+    }
+}
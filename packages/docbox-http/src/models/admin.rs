@@ -1,9 +1,24 @@
 use axum::http::StatusCode;
-use docbox_core::database::models::document_box::DocumentBox;
+use chrono::NaiveDate;
+use docbox_core::database::DatabasePoolInfo;
+use docbox_core::database::models::{
+    document_box::DocumentBox,
+    file_access_stats::{FileAccessReportItem, FileAccessReportOrder},
+    file_processing::ProcessingStageStats,
+    generated_file_policy::GeneratedFilePolicy,
+    mime_override::MimeOverride,
+    presigned_upload_task::{PresignedTaskStatusKind, PresignedUploadTask},
+    request_usage::RequestUsage,
+    scope_pattern::ScopePattern,
+    tasks::TaskId,
+    tenant::{ActiveContentPolicy, TenantId},
+    upload_rule::{UploadRule, UploadRuleKind},
+    usage_stats::{UsageStatsGranularity, UsageStatsPoint},
+};
 use garde::Validate;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::HttpError;
 
@@ -21,14 +36,230 @@ pub struct TenantDocumentBoxesRequest {
     /// Offset to start results from
     #[garde(skip)]
     pub offset: Option<u64>,
+
+    /// Cursor from a previous page to continue from, takes priority
+    /// over the offset
+    #[garde(skip)]
+    pub cursor: Option<String>,
+}
+
+#[derive(Default, Debug, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct TenantScopesRequest {
+    /// Prefix the scopes must start with, wildcards are not supported
+    #[garde(skip)]
+    pub prefix: String,
+
+    /// Number of items to include in the response
+    #[garde(skip)]
+    pub size: Option<u16>,
+
+    /// Offset to start results from
+    #[garde(skip)]
+    pub offset: Option<u64>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
-pub struct TenantDocumentBoxesResponse {
-    /// The document boxes
-    pub results: Vec<DocumentBox>,
-    /// The total number of document boxes available to query
+pub struct TenantScopesResponse {
+    /// Scopes of the matching document boxes
+    pub scopes: Vec<String>,
+    /// The total number of scopes matching the prefix
     pub total: i64,
+    /// Registered scope patterns overlapping the prefix, either patterns
+    /// starting with the prefix or wildcard patterns matching the prefix
+    pub patterns: Vec<ScopePattern>,
+}
+
+#[derive(Debug, Validate, Deserialize, ToSchema)]
+pub struct CreateScopePatternRequest {
+    /// The scope pattern, either an exact scope ("org:1") or a prefix
+    /// followed by a trailing wildcard ("org:1:*")
+    #[garde(length(min = 1))]
+    #[schema(min_length = 1)]
+    pub pattern: String,
+
+    /// Optional parent pattern, the parent must be a wildcard pattern
+    /// matching every scope matched by the new pattern
+    #[garde(skip)]
+    pub parent: Option<String>,
+
+    /// Optional description of the scopes matched by the pattern
+    #[garde(skip)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScopePatternsResponse {
+    /// The registered scope patterns
+    pub patterns: Vec<ScopePattern>,
+}
+
+#[derive(Default, Debug, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct FileAccessReportRequest {
+    /// Order to provide the files in
+    #[garde(skip)]
+    pub order: FileAccessReportOrder,
+
+    /// Number of items to include in the response
+    #[garde(skip)]
+    pub size: Option<u16>,
+
+    /// Offset to start results from
+    #[garde(skip)]
+    pub offset: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FileAccessReportResponse {
+    /// Files with their access statistics
+    pub results: Vec<FileAccessReportItem>,
+}
+
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub struct TenantStatsQuery {
+    /// Granularity of the usage time-series, the time-series is only
+    /// included when a granularity is provided
+    pub granularity: Option<UsageStatsGranularity>,
+
+    /// First day to include in the time-series (Default: 90 days before the end)
+    pub start: Option<NaiveDate>,
+
+    /// Last day to include in the time-series (Default: Today)
+    pub end: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProcessingStatsQuery {
+    /// Number of days of processed files to include (Default: 7)
+    #[serde(default = "default_processing_stats_days")]
+    pub days: u32,
+}
+
+fn default_processing_stats_days() -> u32 {
+    7
+}
+
+/// Format to export request usage in
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestUsageFormat {
+    /// JSON response body
+    #[default]
+    Json,
+    /// Comma separated values with a header row
+    Csv,
+}
+
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub struct RequestUsageQuery {
+    /// First day to include (Default: 30 days before the end)
+    pub start: Option<NaiveDate>,
+
+    /// Last day to include (Default: Today)
+    pub end: Option<NaiveDate>,
+
+    /// Only include usage for tenants within this environment
+    pub env: Option<String>,
+
+    /// Format to export the usage in (Default: json)
+    pub format: RequestUsageFormat,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RequestUsageResponse {
+    /// Daily usage for each tenant and API key
+    pub usage: Vec<RequestUsage>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DatabasePoolsResponse {
+    /// Database pools currently held in the cache
+    pub pools: Vec<CachedDatabasePool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CachedDatabasePool {
+    /// Details about the pool
+    #[serde(flatten)]
+    pub pool: DatabasePoolInfo,
+    /// Tenants using the pool, empty for the root database pool
+    pub tenants: Vec<DatabasePoolTenant>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DatabasePoolTenant {
+    /// ID of the tenant
+    #[schema(value_type = Uuid)]
+    pub id: TenantId,
+    /// Environment of the tenant
+    pub env: String,
+}
+
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub struct InvalidateDatabasePoolQuery {
+    /// Only invalidate the pool of the tenant within this environment
+    /// (Default: Tenants with the ID in any environment)
+    pub env: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvalidateDatabasePoolResponse {
+    /// Number of cached pools that were closed
+    pub closed: usize,
+}
+
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub struct InvalidateTenantCacheQuery {
+    /// Only invalidate the tenant within this environment
+    /// (Default: Tenants with the ID in any environment)
+    pub env: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProcessingStatsResponse {
+    /// Aggregate durations for each processing stage
+    pub stages: Vec<ProcessingStageStats>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StuckTasksQuery {
+    /// Minutes a task must have been pending for to be considered stuck (Default: 60)
+    #[serde(default = "default_stuck_threshold_minutes")]
+    pub threshold_minutes: u32,
+}
+
+fn default_stuck_threshold_minutes() -> u32 {
+    60
+}
+
+/// Format to export search results in
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchExportFormat {
+    /// Comma separated values with a header row
+    Csv,
+    /// Newline delimited JSON, one search result per line
+    #[default]
+    Ndjson,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchExportQuery {
+    /// Format to export the results in (Default: ndjson)
+    #[serde(default)]
+    pub format: SearchExportFormat,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -41,6 +272,118 @@ pub struct TenantStatsResponse {
     pub total_folders: i64,
     /// Total size of all files within the tenant
     pub file_size: i64,
+    /// Time-series of usage statistics, only present when
+    /// a granularity was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<Vec<UsageStatsPoint>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GeneratedFilePoliciesResponse {
+    /// The generated file policies for the tenant
+    pub policies: Vec<GeneratedFilePolicy>,
+}
+
+#[derive(Debug, Validate, Deserialize, ToSchema)]
+pub struct SetGeneratedFilePoliciesRequest {
+    /// Policies to replace the existing policies with
+    #[garde(skip)]
+    pub policies: Vec<GeneratedFilePolicy>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UploadRulesResponse {
+    /// The upload rules for the tenant
+    pub rules: Vec<UploadRule>,
+}
+
+#[derive(Debug, Validate, Deserialize, ToSchema)]
+pub struct SetUploadRulesRequest {
+    /// Rules to replace the existing rules with
+    #[garde(skip)]
+    pub rules: Vec<UploadRule>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MimeOverridesResponse {
+    /// The mime overrides for the tenant
+    pub overrides: Vec<MimeOverride>,
+}
+
+#[derive(Debug, Validate, Deserialize, ToSchema)]
+pub struct SetMimeOverridesRequest {
+    /// Overrides to replace the existing overrides with
+    #[garde(skip)]
+    pub overrides: Vec<MimeOverride>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MaintenanceModeResponse {
+    /// Whether the tenant is in maintenance mode
+    pub enabled: bool,
+}
+
+#[derive(Debug, Validate, Deserialize, ToSchema)]
+pub struct SetMaintenanceModeRequest {
+    /// Whether the tenant should be in maintenance mode
+    #[garde(skip)]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ActiveContentPolicyResponse {
+    /// How raw HTML and SVG files are served for the tenant
+    pub policy: ActiveContentPolicy,
+}
+
+#[derive(Debug, Validate, Deserialize, ToSchema)]
+pub struct SetActiveContentPolicyRequest {
+    /// How raw HTML and SVG files should be served for the tenant
+    #[garde(skip)]
+    pub policy: ActiveContentPolicy,
+}
+
+#[derive(Default, Debug, Validate, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct ArchiveDocumentBoxRequest {
+    /// Transition the stored files to the archive storage class when
+    /// archiving, or back to the standard storage class when unarchiving
+    #[garde(skip)]
+    pub transition_storage: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ArchiveDocumentBoxResponse {
+    /// The updated document box
+    pub document_box: DocumentBox,
+    /// ID of the background task transitioning the stored files, only
+    /// present when a storage transition was requested
+    #[schema(value_type = Option<Uuid>)]
+    pub task_id: Option<TaskId>,
+}
+
+#[derive(Default, Debug, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct TenantPresignedTasksRequest {
+    /// Optional status to filter the tasks by
+    #[garde(skip)]
+    pub status: Option<PresignedTaskStatusKind>,
+
+    /// Number of items to include in the response
+    #[garde(skip)]
+    pub size: Option<u16>,
+
+    /// Offset to start results from
+    #[garde(skip)]
+    pub offset: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TenantPresignedTasksResponse {
+    /// The presigned upload tasks, most recently created first
+    pub results: Vec<PresignedUploadTask>,
+    /// The total number of presigned upload tasks matching the status
+    pub total: i64,
 }
 
 #[derive(Debug, Error)]
@@ -51,13 +394,92 @@ pub enum HttpAdminError {
         "user is attached to resources, all resources must be deleted or detached before the user can be deleted"
     )]
     UserResourcesAttached,
+    #[error("invalid generated file policy mime pattern \"{0}\"")]
+    InvalidPolicyMime(String),
+    #[error("invalid upload rule {0} pattern \"{1}\"")]
+    InvalidUploadRulePattern(UploadRuleKind, String),
+    #[error("invalid mime override for extension \"{0}\"")]
+    InvalidMimeOverride(String),
+    #[error("failed to reload config: {0}")]
+    ConfigReload(String),
+    #[error("invalid scope pattern \"{0}\"")]
+    InvalidScopePattern(String),
+    #[error("scope pattern not found")]
+    UnknownScopePattern,
+    #[error("scope pattern already exists")]
+    ScopePatternAlreadyExists,
+    #[error("parent scope pattern \"{0}\" does not exist")]
+    UnknownParentScopePattern(String),
+    #[error("parent scope pattern \"{0}\" does not cover the scope pattern")]
+    ParentScopePatternMismatch(String),
+    #[error("event payload not found")]
+    UnknownEventPayload,
+    #[error("presigned upload task not found")]
+    UnknownPresignedTask,
+    #[error("presigned upload task already completed")]
+    PresignedTaskAlreadyCompleted,
+    #[error("presigned upload folder no longer exists")]
+    PresignedTaskFolderMissing,
+    #[error("presigned upload task expired")]
+    PresignedTaskExpired,
+    #[error("task not found")]
+    UnknownTask,
+    #[error("task is not pending")]
+    TaskNotPending,
+    #[error("start of the range must not be after the end")]
+    InvalidUsageRange,
+    #[error("tenant not found")]
+    UnknownTenant,
 }
 
 impl HttpError for HttpAdminError {
     fn status(&self) -> axum::http::StatusCode {
         match self {
-            HttpAdminError::UnknownUser => StatusCode::NOT_FOUND,
-            HttpAdminError::UserResourcesAttached => StatusCode::BAD_REQUEST,
+            HttpAdminError::UnknownUser
+            | HttpAdminError::UnknownScopePattern
+            | HttpAdminError::UnknownEventPayload
+            | HttpAdminError::UnknownPresignedTask
+            | HttpAdminError::UnknownTask
+            | HttpAdminError::UnknownTenant => StatusCode::NOT_FOUND,
+            HttpAdminError::PresignedTaskAlreadyCompleted
+            | HttpAdminError::PresignedTaskFolderMissing
+            | HttpAdminError::PresignedTaskExpired
+            | HttpAdminError::TaskNotPending => StatusCode::CONFLICT,
+            HttpAdminError::ScopePatternAlreadyExists => StatusCode::CONFLICT,
+            HttpAdminError::UserResourcesAttached
+            | HttpAdminError::InvalidPolicyMime(_)
+            | HttpAdminError::InvalidUploadRulePattern(_, _)
+            | HttpAdminError::InvalidMimeOverride(_)
+            | HttpAdminError::ConfigReload(_)
+            | HttpAdminError::InvalidScopePattern(_)
+            | HttpAdminError::UnknownParentScopePattern(_)
+            | HttpAdminError::ParentScopePatternMismatch(_)
+            | HttpAdminError::InvalidUsageRange => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpAdminError::UnknownUser => "UNKNOWN_USER",
+            HttpAdminError::UserResourcesAttached => "USER_RESOURCES_ATTACHED",
+            HttpAdminError::InvalidPolicyMime(_) => "INVALID_POLICY_MIME",
+            HttpAdminError::InvalidUploadRulePattern(_, _) => "INVALID_UPLOAD_RULE_PATTERN",
+            HttpAdminError::InvalidMimeOverride(_) => "INVALID_MIME_OVERRIDE",
+            HttpAdminError::ConfigReload(_) => "CONFIG_RELOAD",
+            HttpAdminError::InvalidScopePattern(_) => "INVALID_SCOPE_PATTERN",
+            HttpAdminError::UnknownScopePattern => "UNKNOWN_SCOPE_PATTERN",
+            HttpAdminError::ScopePatternAlreadyExists => "SCOPE_PATTERN_ALREADY_EXISTS",
+            HttpAdminError::UnknownParentScopePattern(_) => "UNKNOWN_PARENT_SCOPE_PATTERN",
+            HttpAdminError::ParentScopePatternMismatch(_) => "PARENT_SCOPE_PATTERN_MISMATCH",
+            HttpAdminError::UnknownEventPayload => "UNKNOWN_EVENT_PAYLOAD",
+            HttpAdminError::UnknownPresignedTask => "UNKNOWN_PRESIGNED_TASK",
+            HttpAdminError::PresignedTaskAlreadyCompleted => "PRESIGNED_TASK_ALREADY_COMPLETED",
+            HttpAdminError::PresignedTaskFolderMissing => "PRESIGNED_TASK_FOLDER_MISSING",
+            HttpAdminError::PresignedTaskExpired => "PRESIGNED_TASK_EXPIRED",
+            HttpAdminError::UnknownTask => "UNKNOWN_TASK",
+            HttpAdminError::TaskNotPending => "TASK_NOT_PENDING",
+            HttpAdminError::InvalidUsageRange => "INVALID_USAGE_RANGE",
+            HttpAdminError::UnknownTenant => "UNKNOWN_TENANT",
         }
     }
 }
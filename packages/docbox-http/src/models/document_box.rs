@@ -2,9 +2,20 @@ use std::{fmt::Display, str::FromStr};
 
 use crate::error::HttpError;
 use axum::http::StatusCode;
-use docbox_core::database::models::{
-    document_box::DocumentBox,
-    folder::{FolderWithExtra, ResolvedFolderWithExtra},
+use axum_typed_multipart::{FieldData, TryFromMultipart};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use docbox_core::{
+    database::models::{
+        document_box::DocumentBox,
+        file::FileId,
+        file_pii_analysis::{PiiFinding, PiiKind},
+        folder::{FolderId, FolderWithExtra, ResolvedFolderWithExtra},
+        shared::FolderPathSegment,
+        tasks::TaskId,
+    },
+    document_box::import_zip::ImportZipError,
+    search::models::SearchResultData,
 };
 use garde::Validate;
 use serde::{Deserialize, Deserializer, Serialize};
@@ -84,14 +95,37 @@ impl FromStr for DocumentBoxScope {
 }
 
 /// Request to create a document box
-#[derive(Debug, Validate, Deserialize, ToSchema)]
+#[derive(Debug, Validate, Deserialize, Serialize, ToSchema)]
 pub struct CreateDocumentBoxRequest {
     /// Scope for the document box to use
-    #[garde(length(min = 1))]
+    #[garde(length(min = 1), custom(validate_scope_name))]
     #[schema(min_length = 1)]
     pub scope: String,
 }
 
+/// Request to move a folder or file into another document box
+#[derive(Debug, Validate, Deserialize, Serialize, ToSchema)]
+pub struct MoveToScopeRequest {
+    /// Scope of the document box to move the item into
+    #[garde(length(min = 1), custom(validate_scope_name))]
+    #[schema(min_length = 1)]
+    pub document_box: String,
+
+    /// ID of the folder within the target document box to move the item into
+    #[garde(skip)]
+    #[schema(value_type = Uuid)]
+    pub folder_id: FolderId,
+}
+
+/// Validates the scope of a new document box only contains allowed characters
+fn validate_scope_name(value: &str, _ctx: &()) -> garde::Result {
+    if !DocumentBoxScope::validate_scope(value) {
+        return Err(garde::Error::new("document box scope is invalid"));
+    }
+
+    Ok(())
+}
+
 /// Response to an options request
 #[derive(Debug, Serialize, ToSchema)]
 pub struct DocumentBoxOptions {
@@ -100,7 +134,7 @@ pub struct DocumentBoxOptions {
 }
 
 /// Response for requesting a document box
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct DocumentBoxResponse {
     /// The created document box
     pub document_box: DocumentBox,
@@ -122,6 +156,118 @@ pub struct DocumentBoxStats {
     pub file_size: i64,
 }
 
+/// Pinned item within a document box
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PinnedItem {
+    /// When the item was pinned
+    pub pinned_at: DateTime<Utc>,
+    /// Path to the pinned item
+    pub path: Vec<FolderPathSegment>,
+    /// The item itself
+    #[serde(flatten)]
+    pub data: SearchResultData,
+}
+
+/// Response for requesting the pinned items within a document box
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DocumentBoxPinnedResponse {
+    /// Pinned items, most recently pinned first
+    pub items: Vec<PinnedItem>,
+}
+
+/// Total number of PII occurrences of a specific kind
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PiiKindCount {
+    /// Kind of PII
+    pub kind: PiiKind,
+    /// Total number of occurrences
+    pub count: u64,
+}
+
+/// File containing PII within a PII report
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PiiReportFile {
+    /// ID of the file
+    #[schema(value_type = Uuid)]
+    pub file_id: FileId,
+    /// Name of the file
+    pub name: String,
+    /// Mime type of the file
+    pub mime: String,
+    /// ID of the folder the file is within
+    #[schema(value_type = Uuid)]
+    pub folder_id: FolderId,
+    /// When the file was analyzed
+    pub analyzed_at: DateTime<Utc>,
+    /// Total occurrences of each kind of PII within the file
+    pub counts: Vec<PiiKindCount>,
+    /// Findings within the file
+    pub findings: Vec<PiiFinding>,
+}
+
+/// Report of the PII found within a document box for compliance reviews
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DocumentBoxPiiReport {
+    /// Total number of files that have been analyzed for PII
+    pub analyzed_files: i64,
+    /// Total number of analyzed files that contain PII
+    pub files_with_pii: i64,
+    /// Total occurrences of each kind of PII within the document box
+    pub counts: Vec<PiiKindCount>,
+    /// Files containing PII, most recently analyzed first
+    pub files: Vec<PiiReportFile>,
+}
+
+impl PiiKindCount {
+    /// Total the occurrences of each kind of PII within the `findings`
+    pub fn from_findings<'a>(findings: impl IntoIterator<Item = &'a PiiFinding>) -> Vec<Self> {
+        let mut counts: Vec<PiiKindCount> = Vec::new();
+
+        for finding in findings {
+            match counts.iter_mut().find(|count| count.kind == finding.kind) {
+                Some(count) => count.count += finding.count as u64,
+                None => counts.push(PiiKindCount {
+                    kind: finding.kind,
+                    count: finding.count as u64,
+                }),
+            }
+        }
+
+        counts
+    }
+}
+
+/// Request to import the contents of a ZIP archive into a document box
+#[derive(TryFromMultipart, Validate, ToSchema)]
+pub struct ImportDocumentBoxRequest {
+    /// The ZIP archive to import
+    #[garde(skip)]
+    #[form_data(limit = "unlimited")]
+    #[schema(format = Binary, value_type = Vec<u8>)]
+    pub file: FieldData<Bytes>,
+
+    /// Optional ID of the folder to import the archive into, when not
+    /// specified the archive is imported into the root folder
+    #[garde(skip)]
+    #[schema(value_type = Option<Uuid>)]
+    pub folder_id: Option<FolderId>,
+
+    /// Optional JSON encoded processing config applied to the imported files
+    #[garde(skip)]
+    pub processing_config: Option<String>,
+}
+
+/// Response for starting an import
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportDocumentBoxResponse {
+    /// ID of the task tracking the import progress, the task output contains
+    /// the files that failed to import once the import completes
+    #[schema(value_type = Uuid)]
+    pub task_id: TaskId,
+    /// Creation timestamp of the task
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Error)]
 pub enum HttpDocumentBoxError {
     #[error("document box with matching scope already exists")]
@@ -129,6 +275,18 @@ pub enum HttpDocumentBoxError {
 
     #[error("unknown document box")]
     UnknownDocumentBox,
+
+    #[error("document box scope does not match any registered scope pattern")]
+    ScopeNotRegistered,
+
+    #[error("document box is archived and cannot be modified")]
+    DocumentBoxArchived,
+
+    #[error("import archive is invalid: {0}")]
+    InvalidImportArchive(ImportZipError),
+
+    #[error("item is already within the target document box")]
+    SameDocumentBox,
 }
 
 impl HttpError for HttpDocumentBoxError {
@@ -136,6 +294,21 @@ impl HttpError for HttpDocumentBoxError {
         match self {
             HttpDocumentBoxError::ScopeAlreadyExists => StatusCode::CONFLICT,
             HttpDocumentBoxError::UnknownDocumentBox => StatusCode::NOT_FOUND,
+            HttpDocumentBoxError::ScopeNotRegistered => StatusCode::BAD_REQUEST,
+            HttpDocumentBoxError::DocumentBoxArchived => StatusCode::LOCKED,
+            HttpDocumentBoxError::InvalidImportArchive(_)
+            | HttpDocumentBoxError::SameDocumentBox => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpDocumentBoxError::ScopeAlreadyExists => "SCOPE_ALREADY_EXISTS",
+            HttpDocumentBoxError::UnknownDocumentBox => "UNKNOWN_DOCUMENT_BOX",
+            HttpDocumentBoxError::ScopeNotRegistered => "SCOPE_NOT_REGISTERED",
+            HttpDocumentBoxError::DocumentBoxArchived => "DOCUMENT_BOX_ARCHIVED",
+            HttpDocumentBoxError::InvalidImportArchive(_) => "INVALID_IMPORT_ARCHIVE",
+            HttpDocumentBoxError::SameDocumentBox => "SAME_DOCUMENT_BOX",
         }
     }
 }
@@ -0,0 +1,80 @@
+use crate::error::HttpError;
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use docbox_core::database::models::{
+    edit_history::{EditHistoryFilter, EditHistoryOrder, EditHistoryType},
+    user::UserId,
+};
+use serde::Deserialize;
+use thiserror::Error;
+use utoipa::IntoParams;
+
+/// Query for requesting the edit history of an item
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub struct EditHistoryQuery {
+    /// Only include entries of this type
+    #[serde(rename = "type")]
+    pub ty: Option<EditHistoryType>,
+
+    /// Only include entries made by this user
+    pub user_id: Option<UserId>,
+
+    /// Only include entries made at or after this time
+    pub start: Option<DateTime<Utc>>,
+
+    /// Only include entries made at or before this time
+    pub end: Option<DateTime<Utc>>,
+
+    /// Order to return the entries in (Default: Newest)
+    pub order: Option<EditHistoryOrder>,
+
+    /// Number of items to include in the response (Default: 100)
+    pub size: Option<u16>,
+
+    /// Offset to start results from
+    pub offset: Option<u64>,
+
+    /// Cursor from a previous page to continue from, takes priority
+    /// over the offset
+    pub cursor: Option<String>,
+}
+
+impl EditHistoryQuery {
+    /// Create the database filter for this query
+    pub fn filter(&self) -> EditHistoryFilter {
+        EditHistoryFilter {
+            ty: self.ty,
+            user_id: self.user_id.clone(),
+            start: self.start,
+            end: self.end,
+            order: self.order.unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum HttpEditHistoryError {
+    #[error("unknown edit history entry")]
+    UnknownEntry,
+
+    #[error("edit history entry cannot be reverted")]
+    CannotRevert,
+}
+
+impl HttpError for HttpEditHistoryError {
+    fn status(&self) -> axum::http::StatusCode {
+        match self {
+            HttpEditHistoryError::UnknownEntry => StatusCode::NOT_FOUND,
+            HttpEditHistoryError::CannotRevert => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpEditHistoryError::UnknownEntry => "UNKNOWN_ENTRY",
+            HttpEditHistoryError::CannotRevert => "CANNOT_REVERT",
+        }
+    }
+}
@@ -3,16 +3,21 @@ use axum::http::StatusCode;
 use axum_typed_multipart::{FieldData, TryFromMultipart};
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
-use docbox_core::processing::{ProcessingConfig, ProcessingError};
+use docbox_core::processing::{ProcessingConfig, ProcessingError, office::PdfConvertError};
 use docbox_core::{
     database::models::{
         file::{FileId, FileWithExtra},
+        file_access_stats::FileAccessStats,
+        file_pdf_metadata::PdfMetadata,
+        file_pii_analysis::PiiAnalysis,
+        file_text_stats::TextStats,
         folder::FolderId,
         generated_file::GeneratedFile,
         presigned_upload_task::PresignedUploadTaskId,
         tasks::TaskId,
+        upload_rule::UploadRuleViolation,
     },
-    files::upload_file::UploadFileError,
+    files::{email_file::MAX_FILE_EMAIL_RECIPIENTS, upload_file::UploadFileError},
 };
 use garde::Validate;
 use mime::Mime;
@@ -24,7 +29,7 @@ use utoipa::ToSchema;
 
 /// Request to create a new presigned file upload
 #[serde_as]
-#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
 pub struct CreatePresignedRequest {
     /// Name of the file being uploaded
     #[garde(length(min = 1, max = 255))]
@@ -67,7 +72,7 @@ pub struct CreatePresignedRequest {
 
 /// Response describing how to upload the presigned file and the ID
 /// for polling the progress
-#[derive(Serialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct PresignedUploadResponse {
     /// ID of the file upload task to poll
     #[schema(value_type = Uuid)]
@@ -80,7 +85,7 @@ pub struct PresignedUploadResponse {
     pub headers: HashMap<String, String>,
 }
 
-#[derive(Serialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(tag = "status")]
 #[allow(clippy::large_enum_variant)]
 pub enum PresignedStatusResponse {
@@ -98,6 +103,8 @@ pub enum PresignedStatusResponse {
         /// The error that occurred
         error: String,
     },
+    /// Presigned upload URL expired before the upload completed
+    Expired,
 }
 
 #[derive(TryFromMultipart, Validate, ToSchema)]
@@ -154,6 +161,53 @@ pub struct UploadFileRequest {
     pub processing_config: Option<String>,
 }
 
+/// Maximum number of files that can be uploaded in a single multiple file upload
+pub const MAX_UPLOAD_FILES: usize = 50;
+
+#[derive(TryFromMultipart, Validate, ToSchema)]
+pub struct UploadFilesRequest {
+    /// ID of the folder to store the files in
+    #[garde(skip)]
+    #[schema(value_type = Uuid)]
+    pub folder_id: FolderId,
+
+    /// The files you are uploading, the name of each file is taken from
+    /// its file name and the mime type from its content type
+    #[garde(length(min = 1, max = MAX_UPLOAD_FILES))]
+    #[form_data(limit = "unlimited")]
+    #[schema(value_type = Vec<Vec<u8>>, min_items = 1, max_items = 50)]
+    pub files: Vec<FieldData<Bytes>>,
+
+    /// Whether to process the files asynchronously returning a task
+    /// response instead of waiting for the upload
+    #[garde(skip)]
+    pub asynchronous: Option<bool>,
+
+    /// Whether to disable mime sniffing for the files. When false/not specified
+    /// files provided with a application/octet-stream mime type will use the
+    /// file name to attempt to determine the real mime type
+    #[garde(skip)]
+    pub disable_mime_sniffing: Option<bool>,
+
+    /// Optional JSON encoded processing config applied to all the files
+    #[garde(skip)]
+    pub processing_config: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum FilesUploadResponse {
+    Sync(UploadedFilesResponse),
+    Async(UploadTaskResponse),
+}
+
+/// Response for a completed multiple file upload
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UploadedFilesResponse {
+    /// The uploaded files in the same order as the request
+    pub files: Vec<UploadedFile>,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 #[serde(untagged)]
 pub enum FileUploadResponse {
@@ -161,7 +215,7 @@ pub enum FileUploadResponse {
     Async(UploadTaskResponse),
 }
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UploadedFile {
     /// The uploaded file itself
     pub file: FileWithExtra,
@@ -192,12 +246,30 @@ pub struct UpdateFileRequest {
 }
 
 /// Response for requesting a document box
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct FileResponse {
     /// The file itself
     pub file: FileWithExtra,
     /// Files generated from the file (thumbnails, pdf, etc)
     pub generated: Vec<GeneratedFile>,
+    /// Page sizes and outline for PDF (and PDF converted) files
+    pub pdf_metadata: Option<PdfMetadata>,
+    /// PII found within the extracted text of the file, not present
+    /// when the file has not been analyzed for PII
+    pub pii_analysis: Option<PiiAnalysis>,
+    /// Word count, character count and reading time of the extracted
+    /// text, not present for files without extracted text
+    pub text_stats: Option<TextStats>,
+    /// Download and preview statistics for the file, not present when
+    /// the file has never been accessed
+    pub access_stats: Option<FileAccessStats>,
+}
+
+/// Response for requesting the page previews of a file
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FilePagePreviewsResponse {
+    /// Preview images for the first pages of the file in page order
+    pub previews: Vec<GeneratedFile>,
 }
 
 #[derive(Default, Debug, Deserialize)]
@@ -207,13 +279,32 @@ pub struct RawFileQuery {
 }
 
 /// Response from creating an upload
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UploadTaskResponse {
     #[schema(value_type = Uuid)]
     pub task_id: TaskId,
     pub created_at: DateTime<Utc>,
 }
 
+/// Request to send a file by email
+#[derive(Debug, Validate, Deserialize, ToSchema)]
+pub struct EmailFileRequest {
+    /// Email addresses to send the file to
+    #[garde(length(min = 1, max = MAX_FILE_EMAIL_RECIPIENTS))]
+    #[schema(min_items = 1, max_items = 20)]
+    pub recipients: Vec<String>,
+    /// Optional message to include in the email
+    #[garde(inner(length(max = 5000)))]
+    #[schema(max_length = 5000)]
+    pub message: Option<String>,
+    /// Whether to send a link to download the file instead of attaching
+    /// it, files larger than the server max attachment size are always
+    /// sent as a link
+    #[garde(skip)]
+    #[serde(default)]
+    pub as_link: bool,
+}
+
 /// Request to rename and or move a file
 #[derive(Debug, Validate, Deserialize, ToSchema)]
 pub struct GetPresignedRequest {
@@ -221,6 +312,11 @@ pub struct GetPresignedRequest {
     #[garde(skip)]
     #[schema(default = 900)]
     pub expires_at: Option<i64>,
+    /// Whether the presigned URL should download the file as an attachment
+    /// instead of displaying it inline
+    #[garde(skip)]
+    #[serde(default)]
+    pub download: bool,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -253,13 +349,42 @@ pub enum HttpFileError {
     #[error("request file mime content type is invalid")]
     InvalidMimeType,
 
+    #[error("uploaded file is missing a valid file name")]
+    InvalidFileName,
+
     #[error("no matching generated file")]
     NoMatchingGenerated,
 
+    #[error("file has no content that can be previewed")]
+    NoPreview,
+
+    #[error("file content is too large to preview")]
+    PreviewTooLarge,
+
     #[allow(unused)]
     #[error("unsupported file type")]
     UnsupportedFileType,
 
+    #[error("file violates the tenant upload rules: {0}")]
+    UploadRuleViolation(UploadRuleViolation),
+
+    #[error(
+        "presigned downloads are not available for encrypted storage, download the raw file instead"
+    )]
+    PresignedDownloadEncrypted,
+
+    #[error("file was modified by another request")]
+    VersionConflict,
+
+    #[error("sending files by email is not enabled on this server")]
+    EmailNotEnabled,
+
+    #[error("invalid recipient email address \"{0}\"")]
+    InvalidEmailRecipient(String),
+
+    #[error("file is too large to attach and links are not available for encrypted storage")]
+    EmailLinkUnavailable,
+
     #[error(transparent)]
     UploadFileError(UploadFileError),
 }
@@ -268,17 +393,25 @@ impl HttpError for HttpFileError {
     fn status(&self) -> axum::http::StatusCode {
         match self {
             HttpFileError::FileTooLarge(_, _) => StatusCode::BAD_REQUEST,
-            HttpFileError::FileIdInUse => StatusCode::CONFLICT,
+            HttpFileError::FileIdInUse | HttpFileError::VersionConflict => StatusCode::CONFLICT,
             HttpFileError::UnknownFile
             | HttpFileError::NoMatchingGenerated
+            | HttpFileError::NoPreview
             | HttpFileError::UnknownTask => StatusCode::NOT_FOUND,
-            HttpFileError::UnsupportedFileType | HttpFileError::InvalidMimeType => {
-                StatusCode::BAD_REQUEST
-            }
+            HttpFileError::UnsupportedFileType
+            | HttpFileError::PreviewTooLarge
+            | HttpFileError::InvalidMimeType
+            | HttpFileError::InvalidFileName
+            | HttpFileError::PresignedDownloadEncrypted
+            | HttpFileError::InvalidEmailRecipient(_)
+            | HttpFileError::EmailLinkUnavailable
+            | HttpFileError::UploadRuleViolation(_) => StatusCode::BAD_REQUEST,
+            HttpFileError::EmailNotEnabled => StatusCode::NOT_IMPLEMENTED,
             HttpFileError::UploadFileError(error) => match error {
                 // Some processing errors can be assumed as the files fault
                 UploadFileError::Processing(
                     ProcessingError::MalformedFile(_)
+                    | ProcessingError::ConvertLimit(_)
                     | ProcessingError::ReadPdfInfo(_)
                     | ProcessingError::ExtractFileText(_)
                     | ProcessingError::DecodeImage(_)
@@ -286,8 +419,56 @@ impl HttpError for HttpFileError {
                     | ProcessingError::Email(_),
                 ) => StatusCode::UNPROCESSABLE_ENTITY,
 
+                // Converter is failing, the upload can be retried once it recovers
+                UploadFileError::Processing(ProcessingError::ConvertFile(
+                    PdfConvertError::ConverterUnavailable,
+                )) => StatusCode::SERVICE_UNAVAILABLE,
+
                 _ => StatusCode::INTERNAL_SERVER_ERROR,
             },
         }
     }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpFileError::UnknownFile => "UNKNOWN_FILE",
+            HttpFileError::UnknownTask => "UNKNOWN_TASK",
+            HttpFileError::FileTooLarge(_, _) => "FILE_TOO_LARGE",
+            HttpFileError::FileIdInUse => "FILE_ID_IN_USE",
+            HttpFileError::InvalidMimeType => "INVALID_MIME_TYPE",
+            HttpFileError::InvalidFileName => "INVALID_FILE_NAME",
+            HttpFileError::NoMatchingGenerated => "NO_MATCHING_GENERATED",
+            HttpFileError::NoPreview => "NO_PREVIEW",
+            HttpFileError::PreviewTooLarge => "PREVIEW_TOO_LARGE",
+            HttpFileError::UnsupportedFileType => "UNSUPPORTED_FILE_TYPE",
+            HttpFileError::UploadRuleViolation(_) => "UPLOAD_RULE_VIOLATION",
+            HttpFileError::PresignedDownloadEncrypted => "PRESIGNED_DOWNLOAD_ENCRYPTED",
+            HttpFileError::VersionConflict => "VERSION_CONFLICT",
+            HttpFileError::EmailNotEnabled => "EMAIL_NOT_ENABLED",
+            HttpFileError::InvalidEmailRecipient(_) => "INVALID_EMAIL_RECIPIENT",
+            HttpFileError::EmailLinkUnavailable => "EMAIL_LINK_UNAVAILABLE",
+            HttpFileError::UploadFileError(error) => match error {
+                UploadFileError::Processing(
+                    ProcessingError::MalformedFile(_)
+                    | ProcessingError::ConvertLimit(_)
+                    | ProcessingError::ReadPdfInfo(_)
+                    | ProcessingError::ExtractFileText(_)
+                    | ProcessingError::DecodeImage(_)
+                    | ProcessingError::GenerateThumbnail(_)
+                    | ProcessingError::Email(_),
+                ) => "UNPROCESSABLE_FILE",
+                UploadFileError::Processing(ProcessingError::ConvertFile(
+                    PdfConvertError::ConverterUnavailable,
+                )) => "CONVERTER_UNAVAILABLE",
+                _ => "UPLOAD_FILE_FAILED",
+            },
+        }
+    }
+
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            HttpFileError::UploadRuleViolation(violation) => serde_json::to_value(violation).ok(),
+            _ => None,
+        }
+    }
 }
@@ -3,15 +3,16 @@ use axum::http::StatusCode;
 use docbox_core::{
     database::models::folder::{FolderId, FolderWithExtra, ResolvedFolderWithExtra},
     folders::create_folder::CreateFolderError,
+    processing::ProcessingConfig,
 };
 use garde::Validate;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 /// Request to create a folder
-#[derive(Debug, Validate, Deserialize, ToSchema)]
+#[derive(Debug, Validate, Deserialize, Serialize, ToSchema)]
 pub struct CreateFolderRequest {
     /// Name for the folder
     #[garde(length(min = 1, max = 255))]
@@ -24,8 +25,34 @@ pub struct CreateFolderRequest {
     pub folder_id: FolderId,
 }
 
+/// Query for deleting a folder
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteFolderQuery {
+    /// Whether to delete the folder asynchronously returning a task
+    /// response instead of waiting for the deletion
+    pub asynchronous: Option<bool>,
+}
+
+/// Query for listing a page of the children of a folder
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub struct FolderChildrenQuery {
+    /// Number of items to include in the response (Default: 100)
+    pub size: Option<u16>,
+
+    /// Offset to start results from
+    pub offset: Option<u64>,
+
+    /// Cursor from a previous page to continue from, takes priority
+    /// over the offset
+    pub cursor: Option<String>,
+}
+
 /// Response for requesting a document box
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct FolderResponse {
     /// The folder itself
     pub folder: FolderWithExtra,
@@ -34,6 +61,22 @@ pub struct FolderResponse {
     pub children: ResolvedFolderWithExtra,
 }
 
+/// Response for requesting the processing config of a folder
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FolderProcessingConfigResponse {
+    /// Processing config attached directly to the folder, [None] when
+    /// the folder does not have a config
+    pub config: Option<ProcessingConfig>,
+}
+
+/// Request to set the processing config of a folder
+#[derive(Debug, Validate, Deserialize, ToSchema)]
+pub struct SetFolderProcessingConfigRequest {
+    /// Processing config to apply to files uploaded into the folder
+    #[garde(skip)]
+    pub config: ProcessingConfig,
+}
+
 /// Request to rename and or move a folder
 #[derive(Debug, Validate, Deserialize, ToSchema)]
 pub struct UpdateFolderRequest {
@@ -97,6 +140,9 @@ pub enum HttpFolderError {
 
     #[error("failed to create zip file")]
     CreateZipFile,
+
+    #[error("folder was modified by another request")]
+    VersionConflict,
 }
 
 impl HttpError for HttpFolderError {
@@ -108,9 +154,23 @@ impl HttpError for HttpFolderError {
             HttpFolderError::CannotModifyRoot
             | HttpFolderError::CannotDeleteRoot
             | HttpFolderError::CannotMoveIntoSelf => StatusCode::BAD_REQUEST,
+            HttpFolderError::VersionConflict => StatusCode::CONFLICT,
             HttpFolderError::CreateError(_) | HttpFolderError::CreateZipFile => {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
         }
     }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpFolderError::UnknownFolder => "UNKNOWN_FOLDER",
+            HttpFolderError::CreateError(_) => "CREATE_FOLDER_FAILED",
+            HttpFolderError::UnknownTargetFolder => "UNKNOWN_TARGET_FOLDER",
+            HttpFolderError::CannotDeleteRoot => "CANNOT_DELETE_ROOT",
+            HttpFolderError::CannotModifyRoot => "CANNOT_MODIFY_ROOT",
+            HttpFolderError::CannotMoveIntoSelf => "CANNOT_MOVE_INTO_SELF",
+            HttpFolderError::CreateZipFile => "CREATE_ZIP_FILE",
+            HttpFolderError::VersionConflict => "VERSION_CONFLICT",
+        }
+    }
 }
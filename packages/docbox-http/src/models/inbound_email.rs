@@ -0,0 +1,33 @@
+use crate::error::HttpError;
+use axum::http::StatusCode;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum HttpInboundEmailError {
+    #[error("inbound email is not enabled on this server")]
+    NotEnabled,
+
+    #[error("document box does not have an inbound email address")]
+    UnknownAddress,
+
+    #[error("document box already has an inbound email address")]
+    AddressExists,
+}
+
+impl HttpError for HttpInboundEmailError {
+    fn status(&self) -> axum::http::StatusCode {
+        match self {
+            HttpInboundEmailError::NotEnabled => StatusCode::NOT_IMPLEMENTED,
+            HttpInboundEmailError::UnknownAddress => StatusCode::NOT_FOUND,
+            HttpInboundEmailError::AddressExists => StatusCode::CONFLICT,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpInboundEmailError::NotEnabled => "NOT_ENABLED",
+            HttpInboundEmailError::UnknownAddress => "UNKNOWN_ADDRESS",
+            HttpInboundEmailError::AddressExists => "ADDRESS_EXISTS",
+        }
+    }
+}
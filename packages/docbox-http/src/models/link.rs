@@ -1,13 +1,19 @@
 use crate::error::HttpError;
 use axum::http::StatusCode;
-use docbox_core::{database::models::folder::FolderId, links::create_link::CreateLinkError};
+use chrono::{DateTime, Utc};
+use docbox_core::{
+    database::models::{
+        folder::FolderId, link::LinkWithExtra, link_visit_stats::MostVisitedLink, tasks::TaskId,
+    },
+    links::create_link::CreateLinkError,
+};
 use garde::Validate;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 /// Request to create a document box
-#[derive(Debug, Validate, Deserialize, ToSchema)]
+#[derive(Debug, Validate, Deserialize, Serialize, ToSchema)]
 pub struct CreateLink {
     /// Name for the link
     #[garde(length(min = 1, max = 255))]
@@ -25,6 +31,51 @@ pub struct CreateLink {
     pub folder_id: FolderId,
 }
 
+/// Maximum number of links that can be created in a single bulk request
+pub const MAX_BULK_CREATE_LINKS: usize = 1000;
+
+/// Request to create many links at once
+#[derive(Debug, Validate, Deserialize, Serialize, ToSchema)]
+pub struct BulkCreateLinksRequest {
+    /// Links to create
+    #[garde(length(min = 1, max = MAX_BULK_CREATE_LINKS), dive)]
+    #[schema(min_items = 1, max_items = 1000)]
+    pub links: Vec<BulkCreateLinkItem>,
+
+    /// ID of the folder to store the links in
+    #[garde(skip)]
+    #[schema(value_type = Uuid)]
+    pub folder_id: FolderId,
+}
+
+/// Link to create as part of a bulk request
+#[derive(Debug, Validate, Deserialize, Serialize, ToSchema)]
+pub struct BulkCreateLinkItem {
+    /// Name for the link, the URL is used when not provided
+    #[garde(inner(length(min = 1, max = 255)))]
+    #[schema(min_length = 1, max_length = 255)]
+    pub name: Option<String>,
+
+    /// Link URL
+    #[garde(length(min = 1))]
+    #[schema(min_length = 1)]
+    pub value: String,
+}
+
+/// Response for bulk creating links
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BulkCreateLinksResponse {
+    /// The created links
+    pub links: Vec<LinkWithExtra>,
+
+    /// ID of the task resolving the metadata for the created links
+    #[schema(value_type = Uuid)]
+    pub task_id: TaskId,
+
+    /// When the metadata task was created
+    pub created_at: DateTime<Utc>,
+}
+
 /// Request to rename a file
 #[derive(Debug, Validate, Deserialize, ToSchema)]
 pub struct UpdateLinkRequest {
@@ -49,6 +100,17 @@ pub struct UpdateLinkRequest {
     pub pinned: Option<bool>,
 }
 
+/// Response for a link including the state of its website metadata
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LinkResponse {
+    #[serde(flatten)]
+    pub link: LinkWithExtra,
+
+    /// Whether the website metadata for the link is older than the metadata
+    /// cache duration (or was never fetched) and should be refreshed
+    pub metadata_stale: bool,
+}
+
 /// Response metadata for a resolved link
 #[derive(Debug, Serialize, ToSchema)]
 pub struct LinkMetadataResponse {
@@ -63,6 +125,27 @@ pub struct LinkMetadataResponse {
     pub favicon: bool,
     /// Whether the metadata resolved a image
     pub image: bool,
+
+    /// When the website metadata for the link was last fetched
+    pub fetched_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Default, Debug, Deserialize, IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub struct MostVisitedLinksQuery {
+    /// Number of links to include in the response (Default: 20, Max: 100)
+    pub size: Option<u16>,
+
+    /// Offset to start results from
+    pub offset: Option<u64>,
+}
+
+/// Response for the most visited links of a document box
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MostVisitedLinksResponse {
+    /// Links with their visit statistics, most visited first
+    pub results: Vec<MostVisitedLink>,
 }
 
 #[derive(Debug, Error)]
@@ -87,6 +170,12 @@ pub enum HttpLinkError {
 
     #[error("website image not present")]
     NoImage,
+
+    #[error("no matching generated file")]
+    NoMatchingGenerated,
+
+    #[error("link was modified by another request")]
+    VersionConflict,
 }
 
 impl HttpError for HttpLinkError {
@@ -95,9 +184,24 @@ impl HttpError for HttpLinkError {
             HttpLinkError::UnknownLink
             | HttpLinkError::NoFavicon
             | HttpLinkError::NoImage
+            | HttpLinkError::NoMatchingGenerated
             | HttpLinkError::FailedResolve => StatusCode::NOT_FOUND,
             HttpLinkError::InvalidLinkUrl => StatusCode::BAD_REQUEST,
+            HttpLinkError::VersionConflict => StatusCode::CONFLICT,
             HttpLinkError::CreateError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpLinkError::UnknownLink => "UNKNOWN_LINK",
+            HttpLinkError::InvalidLinkUrl => "INVALID_LINK_URL",
+            HttpLinkError::CreateError(_) => "CREATE_LINK_FAILED",
+            HttpLinkError::FailedResolve => "FAILED_RESOLVE",
+            HttpLinkError::NoFavicon => "NO_FAVICON",
+            HttpLinkError::NoImage => "NO_IMAGE",
+            HttpLinkError::NoMatchingGenerated => "NO_MATCHING_GENERATED",
+            HttpLinkError::VersionConflict => "VERSION_CONFLICT",
+        }
+    }
 }
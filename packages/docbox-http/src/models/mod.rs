@@ -1,7 +1,12 @@
 pub mod admin;
 pub mod document_box;
+pub mod edit_history;
 pub mod file;
 pub mod folder;
+pub mod inbound_email;
 pub mod link;
+pub mod page;
+pub mod search;
 pub mod task;
 pub mod utils;
+pub mod webhook;
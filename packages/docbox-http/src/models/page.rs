@@ -0,0 +1,65 @@
+use crate::error::HttpError;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Default number of items to include in a page when no size is requested
+pub const DEFAULT_PAGE_SIZE: u64 = 100;
+
+/// Page of items from a paginated listing
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Page<T> {
+    /// Items within the page
+    pub items: Vec<T>,
+    /// Total number of items available across all pages
+    pub total: i64,
+    /// Cursor to provide to request the next page, [None] when
+    /// this is the last page
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Create a page of `items` that started at `offset` within
+    /// the `total` items
+    pub fn from_offset(items: Vec<T>, total: i64, offset: u64) -> Self {
+        let next_offset = offset.saturating_add(items.len() as u64);
+        let next_cursor =
+            (!items.is_empty() && (next_offset as i64) < total).then(|| next_offset.to_string());
+
+        Self {
+            items,
+            total,
+            next_cursor,
+        }
+    }
+}
+
+/// Resolve the offset to start a page from, the `cursor` from a previous
+/// page takes priority over the `offset`
+pub fn page_offset(cursor: Option<&str>, offset: Option<u64>) -> Result<u64, HttpPageError> {
+    match cursor {
+        Some(cursor) => cursor.parse().map_err(|_| HttpPageError::InvalidCursor),
+        None => Ok(offset.unwrap_or(0)),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum HttpPageError {
+    #[error("invalid page cursor")]
+    InvalidCursor,
+}
+
+impl HttpError for HttpPageError {
+    fn status(&self) -> axum::http::StatusCode {
+        match self {
+            HttpPageError::InvalidCursor => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpPageError::InvalidCursor => "INVALID_CURSOR",
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::error::HttpError;
+use axum::http::StatusCode;
+use docbox_core::{
+    database::models::recent_search::RecentSearch, search::validation::SearchValidationError,
+};
+use serde::Serialize;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Maximum number of recent searches stored for each user within
+/// a document box
+pub const MAX_RECENT_SEARCHES: u64 = 20;
+
+/// Response for requesting the recent searches of a user
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecentSearchesResponse {
+    /// Recent searches, most recent first
+    pub searches: Vec<RecentSearch>,
+}
+
+#[derive(Debug, Error)]
+pub enum HttpSearchError {
+    #[error(transparent)]
+    InvalidRequest(SearchValidationError),
+
+    #[error("user ID header is required to access recent searches")]
+    MissingUser,
+}
+
+impl HttpError for HttpSearchError {
+    fn status(&self) -> axum::http::StatusCode {
+        match self {
+            HttpSearchError::InvalidRequest(_) | HttpSearchError::MissingUser => {
+                StatusCode::BAD_REQUEST
+            }
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpSearchError::InvalidRequest(error) => match error {
+                SearchValidationError::QueryTooLong => "QUERY_TOO_LONG",
+                SearchValidationError::SizeTooLarge => "SIZE_TOO_LARGE",
+                SearchValidationError::SuggestSizeTooLarge => "SUGGEST_SIZE_TOO_LARGE",
+                SearchValidationError::OffsetTooLarge => "OFFSET_TOO_LARGE",
+                SearchValidationError::TimeoutTooLarge => "TIMEOUT_TOO_LARGE",
+                SearchValidationError::MissingScopes => "MISSING_SCOPES",
+                SearchValidationError::TooManyScopes => "TOO_MANY_SCOPES",
+                SearchValidationError::InvalidWildcardScope(_) => "INVALID_WILDCARD_SCOPE",
+                SearchValidationError::AdvancedQueryTooDeep => "ADVANCED_QUERY_TOO_DEEP",
+                SearchValidationError::AdvancedQueryTooLarge => "ADVANCED_QUERY_TOO_LARGE",
+                SearchValidationError::InvalidAdvancedQuery(_) => "INVALID_ADVANCED_QUERY",
+                SearchValidationError::InvalidQueryDsl(_) => "INVALID_QUERY_DSL",
+            },
+            HttpSearchError::MissingUser => "MISSING_USER",
+        }
+    }
+}
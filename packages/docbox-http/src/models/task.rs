@@ -14,4 +14,10 @@ impl HttpError for HttpTaskError {
             HttpTaskError::UnknownTask => StatusCode::NOT_FOUND,
         }
     }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpTaskError::UnknownTask => "UNKNOWN_TASK",
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use docbox_core::processing::office::circuit_breaker::ConverterCircuitStatus;
 use serde::Serialize;
 use utoipa::ToSchema;
 
@@ -6,3 +7,12 @@ pub struct DocboxServerResponse {
     /// Version of the docbox server
     pub version: &'static str,
 }
+
+/// Readiness of the server and its dependencies
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthReadyResponse {
+    /// Whether the server is ready to process requests
+    pub ready: bool,
+    /// State of the office converter circuit breaker
+    pub office_converter: ConverterCircuitStatus,
+}
@@ -0,0 +1,63 @@
+use crate::error::HttpError;
+use axum::http::StatusCode;
+use garde::Validate;
+use serde::Deserialize;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Minimum length of a webhook signing secret
+pub const MIN_WEBHOOK_SECRET_LENGTH: usize = 16;
+
+/// Request to create a document box webhook
+#[derive(Debug, Validate, Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    /// HTTP or HTTPS URL to deliver events to, must resolve to a publicly
+    /// reachable address
+    #[garde(length(min = 1))]
+    #[schema(min_length = 1)]
+    pub url: String,
+
+    /// Secret used to sign delivered events, the signature is provided in the
+    /// "x-docbox-signature" header as "sha256=" followed by the hex encoded
+    /// HMAC-SHA256 of the request body
+    #[garde(length(min = MIN_WEBHOOK_SECRET_LENGTH))]
+    #[schema(min_length = 16)]
+    pub secret: String,
+
+    /// Names of the events to deliver (i.e "FILE_CREATED"), all events
+    /// are delivered when empty
+    #[garde(skip)]
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum HttpWebhookError {
+    #[error("unknown webhook")]
+    UnknownWebhook,
+
+    #[error("webhook url must be a publicly reachable http or https url")]
+    InvalidUrl,
+
+    #[error("unknown event \"{0}\"")]
+    UnknownEvent(String),
+}
+
+impl HttpError for HttpWebhookError {
+    fn status(&self) -> axum::http::StatusCode {
+        match self {
+            HttpWebhookError::UnknownWebhook => StatusCode::NOT_FOUND,
+            HttpWebhookError::InvalidUrl | HttpWebhookError::UnknownEvent(_) => {
+                StatusCode::BAD_REQUEST
+            }
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            HttpWebhookError::UnknownWebhook => "UNKNOWN_WEBHOOK",
+            HttpWebhookError::InvalidUrl => "INVALID_URL",
+            HttpWebhookError::UnknownEvent(_) => "UNKNOWN_EVENT",
+        }
+    }
+}
@@ -2,41 +2,114 @@
 
 use crate::{
     error::{DynHttpError, HttpCommonError, HttpErrorResponse, HttpResult, HttpStatusResult},
-    middleware::tenant::{TenantDb, TenantParams, TenantSearch, TenantStorage},
-    models::admin::{
-        HttpAdminError, TenantDocumentBoxesRequest, TenantDocumentBoxesResponse,
-        TenantStatsResponse,
+    extensions::{
+        bulk_storage_throttle::BulkStorageThrottle,
+        config_reload::{ConfigReloadHandle, ReloadedSettings},
+    },
+    middleware::tenant::{TenantDb, TenantEvents, TenantParams, TenantSearch, TenantStorage},
+    models::{
+        admin::{
+            ActiveContentPolicyResponse, ArchiveDocumentBoxRequest, ArchiveDocumentBoxResponse,
+            CachedDatabasePool, CreateScopePatternRequest, DatabasePoolTenant,
+            DatabasePoolsResponse, FileAccessReportRequest, FileAccessReportResponse,
+            GeneratedFilePoliciesResponse, HttpAdminError, InvalidateDatabasePoolQuery,
+            InvalidateDatabasePoolResponse, InvalidateTenantCacheQuery, MaintenanceModeResponse,
+            MimeOverridesResponse, ProcessingStatsQuery, ProcessingStatsResponse,
+            RequestUsageFormat, RequestUsageQuery, RequestUsageResponse, ScopePatternsResponse,
+            SearchExportFormat, SearchExportQuery, SetActiveContentPolicyRequest,
+            SetGeneratedFilePoliciesRequest, SetMaintenanceModeRequest, SetMimeOverridesRequest,
+            SetUploadRulesRequest, StuckTasksQuery, TenantDocumentBoxesRequest,
+            TenantPresignedTasksRequest, TenantPresignedTasksResponse, TenantScopesRequest,
+            TenantScopesResponse, TenantStatsQuery, TenantStatsResponse, UploadRulesResponse,
+        },
+        document_box::{DocumentBoxScope, HttpDocumentBoxError},
+        page::{DEFAULT_PAGE_SIZE, Page, page_offset},
+        search::HttpSearchError,
     },
 };
-use axum::{Extension, Json, extract::Path, http::StatusCode};
+use axum::{
+    BoxError, Extension, Json,
+    body::Body,
+    extract::{Path, Query},
+    http::{Response, StatusCode, header},
+    response::IntoResponse,
+};
 use axum_valid::Garde;
+use bytes::Bytes;
+use chrono::{Days, TimeDelta, Utc};
 use docbox_core::{
     database::{
-        DatabasePoolCache,
+        DatabasePoolCache, DatabasePoolCacheStats, DbPool,
         models::{
             document_box::{DocumentBox, WithScope},
+            event_payload::{EventPayload, EventPayloadId},
             file::File,
+            file_access_stats::FileAccessStats,
+            file_processing::FileProcessing,
             folder::Folder,
+            generated_file_policy::GeneratedFilePolicy,
             link::Link,
+            mime_override::MimeOverride,
+            presigned_upload_task::{PresignedUploadTask, PresignedUploadTaskId},
+            request_usage::RequestUsage,
+            scope_pattern::{CreateScopePattern, ScopePattern},
+            tasks::{Task, TaskId, TaskStatus},
+            tenant::{Tenant, TenantId, UpdateTenant},
+            upload_rule::{UploadRule, UploadRuleKind},
+            usage_stats::UsageStatsPoint,
             user::User,
         },
         utils::DatabaseErrorExt,
     },
-    document_box::search_document_box::{ResolvedSearchResult, search_document_boxes_admin},
+    document_box::{
+        archive_document_box::{
+            ArchiveDocumentBoxError, set_document_box_archived, set_document_box_storage_class,
+        },
+        search_document_box::{
+            AdminSearchExport, ResolvedSearchResult, SearchDocumentBoxError,
+            search_document_boxes_admin,
+        },
+    },
+    events::EventPublisherFactory,
+    files::{
+        reprocess_outdated_files::{ReprocessOutdatedFilesOutcome, reprocess_outdated_files},
+        upload_file_presigned::{RetryPresignedUploadError, retry_presigned_upload},
+    },
     processing::ProcessingLayer,
     purge::purge_expired_presigned_tasks::purge_expired_presigned_tasks,
-    search::models::{
-        AdminSearchRequest, AdminSearchResultResponse, AdminUsersResults, SearchResultItem,
-        UsersRequest,
+    search::{
+        SearchError, SearchIndexFactory,
+        models::{
+            AdminSearchExportRequest, AdminSearchRequest, AdminSearchResultResponse,
+            SearchResultData, SearchResultItem, UsersRequest,
+        },
+    },
+    storage::{StorageClass, StorageLayer, StorageLayerFactory},
+    tasks::{
+        background_task::background_task,
+        stuck_tasks::{
+            ForceFailTaskError, StuckTasks, TaskCounts, find_stuck_tasks,
+            force_fail_presigned_task, force_fail_task, get_task_counts,
+        },
+    },
+    tenant::{
+        tenant_cache::{TenantCache, TenantCacheStats},
+        tenant_storage_key::TenantStorageKeyCache,
     },
-    storage::StorageLayerFactory,
-    tenant::tenant_cache::TenantCache,
 };
+use futures::StreamExt;
 use std::sync::Arc;
 use tokio::{join, try_join};
+use tracing::Instrument;
 
 pub const ADMIN_TAG: &str = "Admin";
 
+/// Default number of days included in the tenant stats time-series
+const DEFAULT_STATS_SERIES_DAYS: u64 = 90;
+
+/// Default number of days included in the request usage export
+const DEFAULT_REQUEST_USAGE_DAYS: u64 = 30;
+
 /// Admin Boxes
 ///
 /// Requests a list of document boxes within the tenant optionally filtered to
@@ -47,7 +120,7 @@ pub const ADMIN_TAG: &str = "Admin";
     tag = ADMIN_TAG,
     path = "/admin/boxes",
     responses(
-        (status = 201, description = "Searched successfully", body = TenantDocumentBoxesResponse),
+        (status = 201, description = "Searched successfully", body = Page<DocumentBox>),
         (status = 400, description = "Malformed or invalid request not meeting validation requirements", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
@@ -57,9 +130,9 @@ pub const ADMIN_TAG: &str = "Admin";
 pub async fn tenant_boxes(
     TenantDb(db): TenantDb,
     Garde(Json(req)): Garde<Json<TenantDocumentBoxesRequest>>,
-) -> HttpResult<TenantDocumentBoxesResponse> {
-    let offset = req.offset.unwrap_or(0);
-    let limit = req.size.unwrap_or(100);
+) -> HttpResult<Page<DocumentBox>> {
+    let offset = page_offset(req.cursor.as_deref(), req.offset)?;
+    let limit = req.size.map(u64::from).unwrap_or(DEFAULT_PAGE_SIZE);
 
     let (document_boxes, total) = match req.query {
         Some(query) if !query.is_empty() => {
@@ -77,7 +150,7 @@ pub async fn tenant_boxes(
                 query.push('%');
             }
 
-            let document_boxes = DocumentBox::search_query(&db, &query, offset, limit as u64)
+            let document_boxes = DocumentBox::search_query(&db, &query, offset, limit)
                 .await
                 .map_err(|error| {
                     tracing::error!(?error, "failed to query document boxes");
@@ -94,7 +167,7 @@ pub async fn tenant_boxes(
             (document_boxes, total)
         }
         _ => {
-            let document_boxes = DocumentBox::query(&db, offset, limit as u64)
+            let document_boxes = DocumentBox::query(&db, offset, limit)
                 .await
                 .map_err(|error| {
                     tracing::error!(?error, "failed to query document boxes");
@@ -110,9 +183,162 @@ pub async fn tenant_boxes(
         }
     };
 
-    Ok(Json(TenantDocumentBoxesResponse {
-        results: document_boxes,
-        total,
+    Ok(Json(Page::from_offset(document_boxes, total, offset)))
+}
+
+/// Get maintenance mode
+///
+/// Get whether the tenant is in maintenance mode
+#[utoipa::path(
+    get,
+    operation_id = "admin_get_maintenance_mode",
+    tag = ADMIN_TAG,
+    path = "/admin/maintenance",
+    responses(
+        (status = 200, description = "Obtained maintenance mode successfully", body = MaintenanceModeResponse),
+    ),
+    params(TenantParams)
+)]
+pub async fn get_maintenance_mode(
+    Extension(tenant): Extension<Tenant>,
+) -> HttpResult<MaintenanceModeResponse> {
+    Ok(Json(MaintenanceModeResponse {
+        enabled: tenant.maintenance_mode,
+    }))
+}
+
+/// Set maintenance mode
+///
+/// Enable or disable maintenance mode for the tenant. While in maintenance
+/// mode reads continue to work but requests that would modify the tenant
+/// data are rejected with a 503 status and a Retry-After header, processing
+/// of presigned uploads for the tenant is paused until maintenance mode is
+/// disabled. Intended for use while migrating the tenant search index or
+/// storage.
+///
+/// The change is applied immediately on the server handling the request,
+/// other servers apply it once their tenant cache is flushed
+#[utoipa::path(
+    put,
+    operation_id = "admin_set_maintenance_mode",
+    tag = ADMIN_TAG,
+    path = "/admin/maintenance",
+    request_body = SetMaintenanceModeRequest,
+    responses(
+        (status = 200, description = "Updated maintenance mode successfully", body = MaintenanceModeResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+#[tracing::instrument(skip_all, fields(?req))]
+pub async fn set_maintenance_mode(
+    Extension(mut tenant): Extension<Tenant>,
+    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
+    Extension(tenant_cache): Extension<Arc<TenantCache>>,
+    Garde(Json(req)): Garde<Json<SetMaintenanceModeRequest>>,
+) -> HttpResult<MaintenanceModeResponse> {
+    let db = db_cache.get_root_pool().await.map_err(|error| {
+        tracing::error!(?error, "failed to connect to root database");
+        HttpCommonError::ServerError
+    })?;
+
+    tenant
+        .update(
+            &db,
+            UpdateTenant {
+                maintenance_mode: Some(req.enabled),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to update tenant maintenance mode");
+            HttpCommonError::ServerError
+        })?;
+
+    // Ensure the next request uses the updated tenant
+    tenant_cache.invalidate(tenant.env.clone(), tenant.id).await;
+
+    Ok(Json(MaintenanceModeResponse {
+        enabled: tenant.maintenance_mode,
+    }))
+}
+
+/// Get active content policy
+///
+/// Get how raw HTML and SVG files are served for the tenant
+#[utoipa::path(
+    get,
+    operation_id = "admin_get_active_content_policy",
+    tag = ADMIN_TAG,
+    path = "/admin/active-content-policy",
+    responses(
+        (status = 200, description = "Obtained active content policy successfully", body = ActiveContentPolicyResponse),
+    ),
+    params(TenantParams)
+)]
+pub async fn get_active_content_policy(
+    Extension(tenant): Extension<Tenant>,
+) -> HttpResult<ActiveContentPolicyResponse> {
+    Ok(Json(ActiveContentPolicyResponse {
+        policy: tenant.active_content_policy,
+    }))
+}
+
+/// Set active content policy
+///
+/// Set how raw HTML and SVG files, which can contain scripts, are served
+/// for the tenant:
+/// - Sandbox: Served as-is under a sandboxed content security policy (Default)
+/// - PlainText: Served as plain text showing the source of the file
+/// - Download: Always served as a download
+///
+/// Previews of these files are always sanitized regardless of the policy.
+/// The change is applied immediately on the server handling the request,
+/// other servers apply it once their tenant cache is flushed
+#[utoipa::path(
+    put,
+    operation_id = "admin_set_active_content_policy",
+    tag = ADMIN_TAG,
+    path = "/admin/active-content-policy",
+    request_body = SetActiveContentPolicyRequest,
+    responses(
+        (status = 200, description = "Updated active content policy successfully", body = ActiveContentPolicyResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+#[tracing::instrument(skip_all, fields(?req))]
+pub async fn set_active_content_policy(
+    Extension(mut tenant): Extension<Tenant>,
+    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
+    Extension(tenant_cache): Extension<Arc<TenantCache>>,
+    Garde(Json(req)): Garde<Json<SetActiveContentPolicyRequest>>,
+) -> HttpResult<ActiveContentPolicyResponse> {
+    let db = db_cache.get_root_pool().await.map_err(|error| {
+        tracing::error!(?error, "failed to connect to root database");
+        HttpCommonError::ServerError
+    })?;
+
+    tenant
+        .update(
+            &db,
+            UpdateTenant {
+                active_content_policy: Some(req.policy),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to update tenant active content policy");
+            HttpCommonError::ServerError
+        })?;
+
+    // Ensure the next request uses the updated tenant
+    tenant_cache.invalidate(tenant.env.clone(), tenant.id).await;
+
+    Ok(Json(ActiveContentPolicyResponse {
+        policy: tenant.active_content_policy,
     }))
 }
 
@@ -120,6 +346,11 @@ pub async fn tenant_boxes(
 ///
 /// Requests stats about a tenant such as the total of each item type as
 /// well as the total file size consumed
+///
+/// When a granularity is provided a time-series of the files created, bytes
+/// uploaded and processing failures is included for capacity planning. The
+/// time-series is built from a daily rollup that is updated hourly by a
+/// background task
 #[utoipa::path(
     get,
     operation_id = "admin_tenant_stats",
@@ -129,10 +360,13 @@ pub async fn tenant_boxes(
         (status = 201, description = "Got stats successfully", body = TenantStatsResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
-    params(TenantParams)
+    params(TenantParams, TenantStatsQuery)
 )]
-#[tracing::instrument(skip_all)]
-pub async fn tenant_stats(TenantDb(db): TenantDb) -> HttpResult<TenantStatsResponse> {
+#[tracing::instrument(skip_all, fields(?query))]
+pub async fn tenant_stats(
+    TenantDb(db): TenantDb,
+    Query(query): Query<TenantStatsQuery>,
+) -> HttpResult<TenantStatsResponse> {
     let total_files_future = File::total_count(&db);
     let total_links_future = Link::total_count(&db);
     let total_folders_future = Folder::total_count(&db);
@@ -165,14 +399,70 @@ pub async fn tenant_stats(TenantDb(db): TenantDb) -> HttpResult<TenantStatsRespo
         HttpCommonError::ServerError
     })?;
 
+    let series = match query.granularity {
+        Some(granularity) => {
+            let end = query.end.unwrap_or_else(|| Utc::now().date_naive());
+            let start = query
+                .start
+                .or_else(|| end.checked_sub_days(Days::new(DEFAULT_STATS_SERIES_DAYS)))
+                .unwrap_or(end);
+
+            let series = UsageStatsPoint::series(&db, granularity, start, end)
+                .await
+                .map_err(|error| {
+                    tracing::error!(?error, "failed to query tenant usage stats");
+                    HttpCommonError::ServerError
+                })?;
+
+            Some(series)
+        }
+        None => None,
+    };
+
     Ok(Json(TenantStatsResponse {
         total_files,
         total_folders,
         total_links,
         file_size,
+        series,
     }))
 }
 
+/// Admin Processing Stats
+///
+/// Requests the median, 95th and 99th percentile durations of each file
+/// processing stage (conversion, text extraction, thumbnailing, indexing
+/// and the total) for files processed within the requested number of days.
+/// Useful for determining whether the converter or the search index is
+/// the bottleneck when processing files
+#[utoipa::path(
+    get,
+    operation_id = "admin_processing_stats",
+    tag = ADMIN_TAG,
+    path = "/admin/processing-stats",
+    responses(
+        (status = 200, description = "Got processing stats successfully", body = ProcessingStatsResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams, ProcessingStatsQuery)
+)]
+#[tracing::instrument(skip_all, fields(?query))]
+pub async fn processing_stats(
+    TenantDb(db): TenantDb,
+    Query(query): Query<ProcessingStatsQuery>,
+) -> HttpResult<ProcessingStatsResponse> {
+    let since = Utc::now() - TimeDelta::days(query.days as i64);
+
+    let stages = FileProcessing::stage_stats(&db, since)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query processing stats");
+            HttpCommonError::ServerError
+        })?;
+
+    Ok(Json(ProcessingStatsResponse { stages }))
+}
+
 /// Admin Search
 ///
 /// Performs a search across multiple document box scopes. This
@@ -202,39 +492,228 @@ pub async fn search_tenant(
         return Ok(Json(AdminSearchResultResponse {
             total_hits: 0,
             results: vec![],
+            timed_out: false,
+            explain: None,
+            facets: Vec::new(),
         }));
     }
 
     let resolved = search_document_boxes_admin(&db, &search, req)
         .await
-        .map_err(|error| {
-            tracing::error!(?error, "failed to perform admin search");
-            HttpCommonError::ServerError
-        })?;
+        .map_err(map_admin_search_error)?;
 
     let out: Vec<WithScope<SearchResultItem>> = resolved
         .results
         .into_iter()
-        .map(|ResolvedSearchResult { result, data, path }| WithScope {
-            data: SearchResultItem {
-                path,
-                score: result.score,
-                data,
-                page_matches: result.page_matches,
-                total_hits: result.total_hits,
-                name_match: result.name_match,
-                content_match: result.content_match,
-            },
-            scope: result.document_box,
-        })
+        .map(admin_search_result_item)
         .collect();
 
     Ok(Json(AdminSearchResultResponse {
         total_hits: resolved.total_hits,
         results: out,
+        timed_out: resolved.timed_out,
+        explain: resolved.explain,
+        facets: resolved.facets,
     }))
 }
 
+/// Create a search result item for an admin search result
+fn admin_search_result_item(
+    ResolvedSearchResult { result, data, path }: ResolvedSearchResult,
+) -> WithScope<SearchResultItem> {
+    WithScope {
+        data: SearchResultItem {
+            path,
+            score: result.score,
+            data,
+            page_matches: result.page_matches,
+            total_hits: result.total_hits,
+            name_match: result.name_match,
+            content_match: result.content_match,
+            explanation: result.explanation,
+        },
+        scope: result.document_box,
+    }
+}
+
+/// Admin Search Export
+///
+/// Performs a search across multiple document box scopes streaming the
+/// full set of results as CSV or NDJSON. Unlike the admin search the
+/// results are not limited by the maximum pagination offset, the `size`
+/// controls the number of results read from the search index at a time
+#[utoipa::path(
+    post,
+    operation_id = "admin_search_export_tenant",
+    tag = ADMIN_TAG,
+    path = "/admin/search/export",
+    request_body = AdminSearchExportRequest,
+    responses(
+        (status = 200, description = "Exporting search results", content_type = "application/x-ndjson", body = String),
+        (status = 400, description = "Malformed or invalid request not meeting validation requirements", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams, SearchExportQuery)
+)]
+#[tracing::instrument(skip_all, fields(?query, ?req))]
+pub async fn export_search_tenant(
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    Query(query): Query<SearchExportQuery>,
+    Garde(Json(req)): Garde<Json<AdminSearchExportRequest>>,
+) -> Result<Response<Body>, DynHttpError> {
+    let format = query.format;
+
+    let mut export = AdminSearchExport::create(db, search, req)
+        .await
+        .map_err(map_admin_search_error)?;
+
+    // Read the first page before responding so that invalid requests are
+    // reported with an error status instead of a truncated export
+    let first_page = export.next_page().await.map_err(map_admin_search_error)?;
+
+    let header = futures::stream::iter(
+        search_export_header(format).map(|header| Ok(Bytes::from_static(header.as_bytes()))),
+    );
+
+    let pages =
+        futures::stream::try_unfold((export, first_page), move |(mut export, page)| async move {
+            let Some(page) = page else {
+                return Ok::<_, BoxError>(None);
+            };
+
+            let mut chunk = Vec::new();
+            for result in page {
+                write_search_export_row(format, &mut chunk, admin_search_result_item(result))?;
+            }
+
+            let next_page = export.next_page().await.inspect_err(|error| {
+                tracing::error!(?error, "failed to read search export page");
+            })?;
+
+            Ok(Some((Bytes::from(chunk), (export, next_page))))
+        });
+
+    let content_type = match format {
+        SearchExportFormat::Csv => "text/csv",
+        SearchExportFormat::Ndjson => "application/x-ndjson",
+    };
+
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from_stream(header.chain(pages)))?)
+}
+
+fn map_admin_search_error(error: SearchDocumentBoxError) -> DynHttpError {
+    match error {
+        SearchDocumentBoxError::QueryIndex(SearchError::Validation(error)) => {
+            DynHttpError::from(HttpSearchError::InvalidRequest(error))
+        }
+        error => {
+            tracing::error!(?error, "failed to perform admin search");
+            DynHttpError::from(HttpCommonError::ServerError)
+        }
+    }
+}
+
+/// Header row to start a search export with
+fn search_export_header(format: SearchExportFormat) -> Option<&'static str> {
+    match format {
+        SearchExportFormat::Csv => Some(
+            "scope,type,id,name,mime,path,created_at,created_by,name_match,content_match,total_hits\n",
+        ),
+        SearchExportFormat::Ndjson => None,
+    }
+}
+
+/// Write a single search result to a search export
+fn write_search_export_row(
+    format: SearchExportFormat,
+    out: &mut Vec<u8>,
+    item: WithScope<SearchResultItem>,
+) -> Result<(), BoxError> {
+    match format {
+        SearchExportFormat::Ndjson => {
+            serde_json::to_writer(&mut *out, &item)?;
+            out.push(b'\n');
+        }
+        SearchExportFormat::Csv => {
+            let (ty, id, name, mime, created_at, created_by) = match &item.data.data {
+                SearchResultData::File(file) => (
+                    "File",
+                    file.file.id,
+                    file.file.name.as_str(),
+                    file.file.mime.as_str(),
+                    file.file.created_at,
+                    file.created_by.as_ref(),
+                ),
+                SearchResultData::Folder(folder) => (
+                    "Folder",
+                    folder.folder.id,
+                    folder.folder.name.as_str(),
+                    "",
+                    folder.folder.created_at,
+                    folder.created_by.as_ref(),
+                ),
+                SearchResultData::Link(link) => (
+                    "Link",
+                    link.link.id,
+                    link.link.name.as_str(),
+                    "",
+                    link.link.created_at,
+                    link.created_by.as_ref(),
+                ),
+            };
+
+            let path = item
+                .data
+                .path
+                .iter()
+                .map(|segment| segment.name.as_str())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            let fields = [
+                item.scope.as_str(),
+                ty,
+                &id.to_string(),
+                name,
+                mime,
+                &path,
+                &created_at.to_rfc3339(),
+                created_by.map(|user| user.id.as_str()).unwrap_or_default(),
+                &item.data.name_match.to_string(),
+                &item.data.content_match.to_string(),
+                &item.data.total_hits.to_string(),
+            ];
+
+            write_csv_row(out, &fields);
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a row of CSV `fields` to `out`
+fn write_csv_row(out: &mut Vec<u8>, fields: &[&str]) {
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 {
+            out.push(b',');
+        }
+
+        // Quote fields containing separators, quotes or line breaks
+        if field.contains([',', '"', '\n', '\r']) {
+            out.push(b'"');
+            out.extend_from_slice(field.replace('"', "\"\"").as_bytes());
+            out.push(b'"');
+        } else {
+            out.extend_from_slice(field.as_bytes());
+        }
+    }
+
+    out.push(b'\n');
+}
+
 /// Reprocess octet-stream files
 ///
 /// Useful if a files were previously accepted into the tenant with some unknown
@@ -261,7 +740,10 @@ pub async fn reprocess_octet_stream_files_tenant(
     TenantSearch(search): TenantSearch,
     TenantStorage(storage): TenantStorage,
     Extension(processing): Extension<ProcessingLayer>,
+    Extension(throttle): Extension<BulkStorageThrottle>,
 ) -> HttpStatusResult {
+    let storage = storage.with_throttle(throttle.0);
+
     docbox_core::files::reprocess_octet_stream_files::reprocess_octet_stream_files(
         &db,
         &search,
@@ -277,106 +759,1217 @@ pub async fn reprocess_octet_stream_files_tenant(
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// Rebuild search index
+/// Reprocess outdated files
 ///
-/// Rebuild the tenant search index from the data stored in the database
-/// and in storage
+/// Reprocesses files that were processed using an older version of the file
+/// processing pipeline, replacing their generated files and search index data.
+///
+/// Useful after improvements to processing (i.e OCR or thumbnail generation)
+/// to apply the improvements to existing files.
 ///
 /// This endpoint is not supported on serverless
 #[utoipa::path(
     post,
-    operation_id = "admin_rebuild_search_index",
+    operation_id = "admin_reprocess_outdated_files",
     tag = ADMIN_TAG,
-    path = "/admin/rebuild-search-index",
+    path = "/admin/reprocess-outdated-files",
     responses(
-        (status = 204, description = "Rebuilt successfully", body = ()),
+        (status = 200, description = "Reprocessed successfully", body = ReprocessOutdatedFilesOutcome),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
     params(TenantParams)
 )]
 #[tracing::instrument(skip_all)]
-pub async fn rebuild_search_index_tenant(
+pub async fn reprocess_outdated_files_tenant(
     TenantDb(db): TenantDb,
     TenantSearch(search): TenantSearch,
     TenantStorage(storage): TenantStorage,
-) -> HttpStatusResult {
-    docbox_core::tenant::rebuild_tenant_index::rebuild_tenant_index(&db, &search, &storage)
+    Extension(processing): Extension<ProcessingLayer>,
+    Extension(throttle): Extension<BulkStorageThrottle>,
+) -> HttpResult<ReprocessOutdatedFilesOutcome> {
+    let storage = storage.with_throttle(throttle.0);
+
+    let outcome = reprocess_outdated_files(&db, &search, &storage, &processing)
         .await
         .map_err(|error| {
-            tracing::error!(?error, "failed to rebuilt tenant search index");
+            tracing::error!(?error, "failed to reprocess outdated files");
             HttpCommonError::ServerError
         })?;
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(Json(outcome))
 }
 
-/// Flush database cache
+/// Presigned upload tasks
 ///
-/// Empties all the database pool and credentials caches, you can use this endpoint
-/// if you rotate your database credentials to refresh the database pool without
-/// needing to restart the server
+/// Lists the presigned upload tasks within the tenant optionally filtered
+/// to a specific status, used to find uploads that failed processing
 #[utoipa::path(
     post,
-    operation_id = "admin_flush_database_pool_cache",
+    operation_id = "admin_tenant_presigned_tasks",
     tag = ADMIN_TAG,
-    path = "/admin/flush-db-cache",
+    path = "/admin/presigned-tasks",
     responses(
-        (status = 204, description = "Database cache flushed"),
-    )
+        (status = 200, description = "Listed presigned upload tasks successfully", body = TenantPresignedTasksResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
 )]
-pub async fn flush_database_pool_cache(
-    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
-) -> HttpStatusResult {
-    db_cache.flush().await;
-    Ok(StatusCode::NO_CONTENT)
+#[tracing::instrument(skip_all, fields(?req))]
+pub async fn tenant_presigned_tasks(
+    TenantDb(db): TenantDb,
+    Garde(Json(req)): Garde<Json<TenantPresignedTasksRequest>>,
+) -> HttpResult<TenantPresignedTasksResponse> {
+    let offset = req.offset.unwrap_or(0);
+    let limit = req.size.unwrap_or(100) as u64;
+
+    let (results, total) = try_join!(
+        PresignedUploadTask::query(&db, req.status, offset, limit),
+        PresignedUploadTask::total(&db, req.status)
+    )
+    .map_err(|error| {
+        tracing::error!(?error, "failed to query presigned upload tasks");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(TenantPresignedTasksResponse { results, total }))
 }
 
-/// Flush tenant cache
+/// Retry presigned upload task
 ///
-/// Clears the tenant cache, you can use this endpoint if you've updated the
-/// tenant configuration and want it to be applied immediately without
-/// restarting the server
+/// Re-runs the processing of a presigned upload that was uploaded to storage
+/// but failed processing or never had its upload notification processed.
+///
+/// The file must already be uploaded to storage. The response contains the
+/// task with the outcome of the retry.
 #[utoipa::path(
     post,
-    operation_id = "admin_flush_tenant_cache",
+    operation_id = "admin_retry_presigned_task",
     tag = ADMIN_TAG,
-    path = "/admin/flush-tenant-cache",
+    path = "/admin/presigned-tasks/{task_id}/retry",
     responses(
-        (status = 204, description = "Tenant cache flushed"),
+        (status = 200, description = "Retried presigned upload task", body = PresignedUploadTask),
+        (status = 404, description = "Presigned upload task not found", body = HttpErrorResponse),
+        (status = 409, description = "Presigned upload task cannot be retried", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("task_id" = Uuid, Path, description = "ID of the presigned upload task to retry"),
+        TenantParams
     )
 )]
-pub async fn flush_tenant_cache(
-    Extension(tenant_cache): Extension<Arc<TenantCache>>,
-) -> HttpStatusResult {
-    tenant_cache.flush().await;
-    Ok(StatusCode::NO_CONTENT)
+#[tracing::instrument(skip_all, fields(%task_id))]
+pub async fn retry_presigned_task(
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    TenantStorage(storage): TenantStorage,
+    TenantEvents(events): TenantEvents,
+    Extension(processing): Extension<ProcessingLayer>,
+    Path(task_id): Path<PresignedUploadTaskId>,
+) -> HttpResult<PresignedUploadTask> {
+    let task = retry_presigned_upload(&db, &search, &storage, &events, &processing, task_id)
+        .await
+        .map_err(|error| match error {
+            RetryPresignedUploadError::UnknownTask => {
+                DynHttpError::from(HttpAdminError::UnknownPresignedTask)
+            }
+            RetryPresignedUploadError::AlreadyCompleted => {
+                DynHttpError::from(HttpAdminError::PresignedTaskAlreadyCompleted)
+            }
+            RetryPresignedUploadError::Expired => {
+                DynHttpError::from(HttpAdminError::PresignedTaskExpired)
+            }
+            RetryPresignedUploadError::UnknownFolder => {
+                DynHttpError::from(HttpAdminError::PresignedTaskFolderMissing)
+            }
+            RetryPresignedUploadError::Database(_) => {
+                DynHttpError::from(HttpCommonError::ServerError)
+            }
+        })?;
+
+    Ok(Json(task))
 }
 
-/// Purge Presigned Tasks
+/// Force fail presigned upload task
 ///
-/// Purges all expired presigned tasks, this operation deletes any presigned uploads
-/// that have not yet been completed but have passed the expiration date
+/// Marks a pending presigned upload task as failed, use this for tasks that
+/// are stuck pending so that clients waiting on the upload stop polling.
+/// Failed tasks can still be retried once the file is uploaded
 #[utoipa::path(
     post,
-    operation_id = "admin_purge_expired_presigned_tasks",
+    operation_id = "admin_fail_presigned_task",
     tag = ADMIN_TAG,
-    path = "/admin/purge-expired-presigned-tasks",
+    path = "/admin/presigned-tasks/{task_id}/fail",
     responses(
-        (status = 204, description = "Database cache flushed"),
-        (status = 500, description = "Failed to purge presigned cache", body = HttpErrorResponse),
+        (status = 200, description = "Failed presigned upload task", body = PresignedUploadTask),
+        (status = 404, description = "Presigned upload task not found", body = HttpErrorResponse),
+        (status = 409, description = "Presigned upload task is not pending", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("task_id" = Uuid, Path, description = "ID of the presigned upload task to fail"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%task_id))]
+pub async fn fail_presigned_task(
+    TenantDb(db): TenantDb,
+    Path(task_id): Path<PresignedUploadTaskId>,
+) -> HttpResult<PresignedUploadTask> {
+    let task = force_fail_presigned_task(&db, task_id)
+        .await
+        .map_err(|error| match error {
+            ForceFailTaskError::UnknownTask => {
+                DynHttpError::from(HttpAdminError::UnknownPresignedTask)
+            }
+            ForceFailTaskError::NotPending => DynHttpError::from(HttpAdminError::TaskNotPending),
+            ForceFailTaskError::Database(_) => DynHttpError::from(HttpCommonError::ServerError),
+        })?;
+
+    Ok(Json(task))
+}
+
+/// Get stuck tasks
+///
+/// Get the background tasks and presigned upload tasks that have been
+/// pending for longer than the threshold
+#[utoipa::path(
+    get,
+    operation_id = "admin_stuck_tasks",
+    tag = ADMIN_TAG,
+    path = "/admin/tasks/stuck",
+    responses(
+        (status = 200, description = "Obtained stuck tasks successfully", body = StuckTasks),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams, StuckTasksQuery)
+)]
+#[tracing::instrument(skip_all, fields(?query))]
+pub async fn stuck_tasks(
+    TenantDb(db): TenantDb,
+    Query(query): Query<StuckTasksQuery>,
+) -> HttpResult<StuckTasks> {
+    let threshold = chrono::Duration::minutes(query.threshold_minutes as i64);
+    let tasks = find_stuck_tasks(&db, threshold).await.map_err(|error| {
+        tracing::error!(?error, "failed to query stuck tasks");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(tasks))
+}
+
+/// Get task counts
+///
+/// Get the number of background tasks and presigned upload tasks
+/// with each status
+#[utoipa::path(
+    get,
+    operation_id = "admin_task_counts",
+    tag = ADMIN_TAG,
+    path = "/admin/tasks/counts",
+    responses(
+        (status = 200, description = "Obtained task counts successfully", body = TaskCounts),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+pub async fn task_counts(TenantDb(db): TenantDb) -> HttpResult<TaskCounts> {
+    let counts = get_task_counts(&db).await.map_err(|error| {
+        tracing::error!(?error, "failed to query task counts");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(counts))
+}
+
+/// Force fail task
+///
+/// Marks a pending background task as failed, use this for tasks that are
+/// stuck pending (i.e the server was stopped while the task was running)
+/// so that clients waiting on the task stop polling
+#[utoipa::path(
+    post,
+    operation_id = "admin_fail_task",
+    tag = ADMIN_TAG,
+    path = "/admin/tasks/{task_id}/fail",
+    responses(
+        (status = 200, description = "Failed task", body = Task),
+        (status = 404, description = "Task not found", body = HttpErrorResponse),
+        (status = 409, description = "Task is not pending", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("task_id" = Uuid, Path, description = "ID of the task to fail"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%task_id))]
+pub async fn fail_task(TenantDb(db): TenantDb, Path(task_id): Path<TaskId>) -> HttpResult<Task> {
+    let task = force_fail_task(&db, task_id)
+        .await
+        .map_err(|error| match error {
+            ForceFailTaskError::UnknownTask => DynHttpError::from(HttpAdminError::UnknownTask),
+            ForceFailTaskError::NotPending => DynHttpError::from(HttpAdminError::TaskNotPending),
+            ForceFailTaskError::Database(_) => DynHttpError::from(HttpCommonError::ServerError),
+        })?;
+
+    Ok(Json(task))
+}
+
+/// Rebuild search index
+///
+/// Rebuild the tenant search index from the data stored in the database
+/// and in storage
+///
+/// This endpoint is not supported on serverless
+#[utoipa::path(
+    post,
+    operation_id = "admin_rebuild_search_index",
+    tag = ADMIN_TAG,
+    path = "/admin/rebuild-search-index",
+    responses(
+        (status = 204, description = "Rebuilt successfully", body = ()),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+#[tracing::instrument(skip_all)]
+pub async fn rebuild_search_index_tenant(
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    TenantStorage(storage): TenantStorage,
+    Extension(throttle): Extension<BulkStorageThrottle>,
+) -> HttpStatusResult {
+    let storage = storage.with_throttle(throttle.0);
+
+    docbox_core::tenant::rebuild_tenant_index::rebuild_tenant_index(&db, &search, &storage)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to rebuilt tenant search index");
+            HttpCommonError::ServerError
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Flush database cache
+///
+/// Empties all the database pool and credentials caches, you can use this endpoint
+/// if you rotate your database credentials to refresh the database pool without
+/// needing to restart the server
+#[utoipa::path(
+    post,
+    operation_id = "admin_flush_database_pool_cache",
+    tag = ADMIN_TAG,
+    path = "/admin/flush-db-cache",
+    responses(
+        (status = 204, description = "Database cache flushed"),
+    )
+)]
+pub async fn flush_database_pool_cache(
+    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
+) -> HttpStatusResult {
+    db_cache.flush().await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Database cache stats
+///
+/// Get the number of database pools held in the cache along with counts of
+/// the pools that have been closed and evicted from the cache. A growing
+/// number of capacity evictions indicates the cache capacity is too small
+/// for the number of active tenants
+#[utoipa::path(
+    get,
+    operation_id = "admin_database_pool_cache_stats",
+    tag = ADMIN_TAG,
+    path = "/admin/db-cache-stats",
+    responses(
+        (status = 200, description = "Obtained stats successfully", body = DatabasePoolCacheStats),
+    )
+)]
+pub async fn database_pool_cache_stats(
+    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
+) -> HttpResult<DatabasePoolCacheStats> {
+    Ok(Json(db_cache.stats().await))
+}
+
+/// Database pools
+///
+/// List the database pools currently held in the cache along with the
+/// age and usage of each pool and the tenants the pool belongs to
+#[utoipa::path(
+    get,
+    operation_id = "admin_database_pools",
+    tag = ADMIN_TAG,
+    path = "/admin/db-pools",
+    responses(
+        (status = 200, description = "Obtained database pools successfully", body = DatabasePoolsResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    )
+)]
+pub async fn database_pools(
+    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
+) -> HttpResult<DatabasePoolsResponse> {
+    let db = db_cache.get_root_pool().await.map_err(|error| {
+        tracing::error!(?error, "failed to connect to root database");
+        HttpCommonError::ServerError
+    })?;
+
+    let tenants = Tenant::all(&db).await.map_err(|error| {
+        tracing::error!(?error, "failed to query tenants");
+        HttpCommonError::ServerError
+    })?;
+
+    let pools = db_cache
+        .pools()
+        .await
+        .into_iter()
+        .map(|pool| {
+            let tenants = tenants
+                .iter()
+                .filter(|tenant| pool.is_tenant_pool(tenant))
+                .map(|tenant| DatabasePoolTenant {
+                    id: tenant.id,
+                    env: tenant.env.clone(),
+                })
+                .collect();
+
+            CachedDatabasePool { pool, tenants }
+        })
+        .collect();
+
+    Ok(Json(DatabasePoolsResponse { pools }))
+}
+
+/// Invalidate tenant database pool
+///
+/// Closes the cached database pool of a single tenant and removes its cached
+/// database credentials, you can use this endpoint after rotating the database
+/// credentials of a tenant to connect with the new credentials without flushing
+/// the pools of every other tenant
+#[utoipa::path(
+    delete,
+    operation_id = "admin_invalidate_database_pool",
+    tag = ADMIN_TAG,
+    path = "/admin/db-pools/{tenant_id}",
+    responses(
+        (status = 200, description = "Tenant database pool invalidated", body = InvalidateDatabasePoolResponse),
+        (status = 404, description = "Tenant not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("tenant_id" = Uuid, Path, description = "ID of the tenant to invalidate the pool of"),
+        InvalidateDatabasePoolQuery
+    )
+)]
+#[tracing::instrument(skip(db_cache))]
+pub async fn invalidate_database_pool(
+    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
+    Path(tenant_id): Path<TenantId>,
+    Query(query): Query<InvalidateDatabasePoolQuery>,
+) -> HttpResult<InvalidateDatabasePoolResponse> {
+    let db = db_cache.get_root_pool().await.map_err(|error| {
+        tracing::error!(?error, "failed to connect to root database");
+        HttpCommonError::ServerError
+    })?;
+
+    let tenants = match query.env.as_deref() {
+        Some(env) => Tenant::find_by_id(&db, tenant_id, env)
+            .await
+            .map(|tenant| tenant.into_iter().collect::<Vec<_>>()),
+        None => Tenant::all(&db).await.map(|tenants| {
+            tenants
+                .into_iter()
+                .filter(|tenant| tenant.id == tenant_id)
+                .collect()
+        }),
+    }
+    .map_err(|error| {
+        tracing::error!(?error, "failed to query tenants");
+        HttpCommonError::ServerError
+    })?;
+
+    if tenants.is_empty() {
+        return Err(HttpAdminError::UnknownTenant.into());
+    }
+
+    let mut closed = 0;
+    for tenant in &tenants {
+        if db_cache.close_tenant_pool(tenant).await {
+            closed += 1;
+        }
+    }
+
+    Ok(Json(InvalidateDatabasePoolResponse { closed }))
+}
+
+/// Request usage
+///
+/// Export the daily request and response byte counts of each tenant and
+/// API key for billing. Uploads are the multipart request bodies and
+/// downloads are the response bodies containing file contents, presigned
+/// transfers are made directly with the storage backend and are not
+/// included. API keys are identified by a fingerprint of the key.
+///
+/// Usage is written to the database in batches so the most recent minute
+/// of usage may not be included yet
+#[utoipa::path(
+    get,
+    operation_id = "admin_request_usage",
+    tag = ADMIN_TAG,
+    path = "/admin/request-usage",
+    responses(
+        (status = 200, description = "Obtained request usage successfully", body = RequestUsageResponse),
+        (status = 200, description = "Obtained request usage successfully", content_type = "text/csv", body = String),
+        (status = 400, description = "Start of the range is after the end", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(RequestUsageQuery)
+)]
+#[tracing::instrument(skip_all, fields(?query))]
+pub async fn request_usage(
+    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
+    Query(query): Query<RequestUsageQuery>,
+) -> Result<Response<Body>, DynHttpError> {
+    let end = query.end.unwrap_or_else(|| Utc::now().date_naive());
+    let start = query
+        .start
+        .or_else(|| end.checked_sub_days(Days::new(DEFAULT_REQUEST_USAGE_DAYS)))
+        .unwrap_or(end);
+
+    if start > end {
+        return Err(HttpAdminError::InvalidUsageRange.into());
+    }
+
+    let db = db_cache.get_root_pool().await.map_err(|error| {
+        tracing::error!(?error, "failed to connect to root database");
+        HttpCommonError::ServerError
+    })?;
+
+    let usage = RequestUsage::find_range(&db, start, end, query.env.as_deref())
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query request usage");
+            HttpCommonError::ServerError
+        })?;
+
+    match query.format {
+        RequestUsageFormat::Json => Ok(Json(RequestUsageResponse { usage }).into_response()),
+        RequestUsageFormat::Csv => {
+            let mut out = Vec::new();
+            write_csv_row(
+                &mut out,
+                &[
+                    "day",
+                    "env",
+                    "tenant_id",
+                    "api_key_id",
+                    "requests",
+                    "request_bytes",
+                    "response_bytes",
+                    "upload_bytes",
+                    "download_bytes",
+                ],
+            );
+
+            for usage in usage {
+                write_csv_row(
+                    &mut out,
+                    &[
+                        &usage.day.to_string(),
+                        &usage.env,
+                        &usage.tenant_id.to_string(),
+                        &usage.api_key_id,
+                        &usage.requests.to_string(),
+                        &usage.request_bytes.to_string(),
+                        &usage.response_bytes.to_string(),
+                        &usage.upload_bytes.to_string(),
+                        &usage.download_bytes.to_string(),
+                    ],
+                );
+            }
+
+            Ok(Response::builder()
+                .header(header::CONTENT_TYPE, "text/csv")
+                .body(Body::from(out))?)
+        }
+    }
+}
+
+/// Flush tenant cache
+///
+/// Clears the tenant cache, you can use this endpoint if you've updated the
+/// tenant configuration and want it to be applied immediately without
+/// restarting the server. Also clears the cached tenant storage encryption
+/// keys so that rotated keys are applied
+#[utoipa::path(
+    post,
+    operation_id = "admin_flush_tenant_cache",
+    tag = ADMIN_TAG,
+    path = "/admin/flush-tenant-cache",
+    responses(
+        (status = 204, description = "Tenant cache flushed"),
+    )
+)]
+pub async fn flush_tenant_cache(
+    Extension(tenant_cache): Extension<Arc<TenantCache>>,
+    Extension(storage_keys): Extension<TenantStorageKeyCache>,
+) -> HttpStatusResult {
+    tenant_cache.flush().await;
+    storage_keys.flush().await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Invalidate cached tenant
+///
+/// Removes a single tenant from the tenant cache so that the next request
+/// loads the current tenant configuration. Use this endpoint after updating
+/// a tenant through the management tools (i.e changing the event queue URL)
+/// to apply the change immediately without flushing every tenant
+#[utoipa::path(
+    delete,
+    operation_id = "admin_invalidate_tenant_cache",
+    tag = ADMIN_TAG,
+    path = "/admin/tenant-cache/{tenant_id}",
+    responses(
+        (status = 204, description = "Tenant removed from the cache"),
+    ),
+    params(
+        ("tenant_id" = Uuid, Path, description = "ID of the tenant to invalidate"),
+        InvalidateTenantCacheQuery
+    )
+)]
+#[tracing::instrument(skip(tenant_cache))]
+pub async fn invalidate_tenant_cache(
+    Extension(tenant_cache): Extension<Arc<TenantCache>>,
+    Path(tenant_id): Path<TenantId>,
+    Query(query): Query<InvalidateTenantCacheQuery>,
+) -> HttpStatusResult {
+    match query.env {
+        Some(env) => tenant_cache.invalidate(env, tenant_id).await,
+        None => {
+            tenant_cache.invalidate_tenant(tenant_id).await;
+        }
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Tenant cache stats
+///
+/// Get the number of tenants held in the cache along with the cache hit
+/// and miss counts and the number of tenants removed from the cache
+#[utoipa::path(
+    get,
+    operation_id = "admin_tenant_cache_stats",
+    tag = ADMIN_TAG,
+    path = "/admin/tenant-cache-stats",
+    responses(
+        (status = 200, description = "Obtained stats successfully", body = TenantCacheStats),
+    )
+)]
+pub async fn tenant_cache_stats(
+    Extension(tenant_cache): Extension<Arc<TenantCache>>,
+) -> HttpResult<TenantCacheStats> {
+    Ok(Json(tenant_cache.stats().await))
+}
+
+/// Flush search credentials
+///
+/// Clears the cached search backend credentials (i.e the typesense API key
+/// loaded from the secret manager), you can use this endpoint after rotating
+/// the search credentials to apply them without restarting the server
+#[utoipa::path(
+    post,
+    operation_id = "admin_flush_search_credentials",
+    tag = ADMIN_TAG,
+    path = "/admin/flush-search-credentials",
+    responses(
+        (status = 204, description = "Search credentials flushed"),
+    )
+)]
+pub async fn flush_search_credentials(
+    Extension(search): Extension<SearchIndexFactory>,
+) -> HttpStatusResult {
+    search.flush_credentials().await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Reload config
+///
+/// Re-reads the server config file and applies the settings that can be
+/// changed without restarting the server (max file size and logging filter),
+/// in-flight requests are not affected
+#[utoipa::path(
+    post,
+    operation_id = "admin_reload_config",
+    tag = ADMIN_TAG,
+    path = "/admin/reload-config",
+    responses(
+        (status = 200, description = "Config reloaded", body = ReloadedSettings),
+        (status = 400, description = "Config could not be reloaded", body = HttpErrorResponse),
+    )
+)]
+pub async fn reload_config(
+    Extension(ConfigReloadHandle(reloader)): Extension<ConfigReloadHandle>,
+) -> HttpResult<ReloadedSettings> {
+    let settings = reloader.reload_config().map_err(|error| {
+        tracing::error!(?error, "failed to reload config");
+        HttpAdminError::ConfigReload(error.to_string())
+    })?;
+
+    Ok(Json(settings))
+}
+
+/// Purge Presigned Tasks
+///
+/// Purges all expired presigned tasks, this operation deletes any presigned uploads
+/// that have not yet been completed but have passed the expiration date
+#[utoipa::path(
+    post,
+    operation_id = "admin_purge_expired_presigned_tasks",
+    tag = ADMIN_TAG,
+    path = "/admin/purge-expired-presigned-tasks",
+    responses(
+        (status = 204, description = "Database cache flushed"),
+        (status = 500, description = "Failed to purge presigned cache", body = HttpErrorResponse),
+    )
+)]
+pub async fn http_purge_expired_presigned_tasks(
+    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
+    Extension(storage_factory): Extension<StorageLayerFactory>,
+    Extension(events): Extension<EventPublisherFactory>,
+) -> HttpStatusResult {
+    purge_expired_presigned_tasks(db_cache, storage_factory, events)
+        .await
+        .map_err(|_| HttpCommonError::ServerError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get generated file policies
+///
+/// Get the policies controlling which generated file types are kept
+/// for files within the tenant
+#[utoipa::path(
+    get,
+    operation_id = "admin_get_generated_file_policies",
+    tag = ADMIN_TAG,
+    path = "/admin/generated-file-policies",
+    responses(
+        (status = 200, description = "Obtained policies successfully", body = GeneratedFilePoliciesResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+#[tracing::instrument(skip_all)]
+pub async fn get_generated_file_policies(
+    TenantDb(db): TenantDb,
+) -> HttpResult<GeneratedFilePoliciesResponse> {
+    let policies = GeneratedFilePolicy::all(&db).await.map_err(|error| {
+        tracing::error!(?error, "failed to query generated file policies");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(GeneratedFilePoliciesResponse { policies }))
+}
+
+/// Set generated file policies
+///
+/// Replace the policies controlling which generated file types are kept
+/// for files uploaded to the tenant. Generated files that are not kept
+/// can be recreated on demand using the file regenerate endpoint.
+///
+/// Policies match files by mime type using either an exact mime type
+/// ("image/png"), a type wildcard ("image/*") or all files ("*"). When
+/// multiple policies apply the most specific policy is used
+#[utoipa::path(
+    put,
+    operation_id = "admin_set_generated_file_policies",
+    tag = ADMIN_TAG,
+    path = "/admin/generated-file-policies",
+    request_body = SetGeneratedFilePoliciesRequest,
+    responses(
+        (status = 200, description = "Updated policies successfully", body = GeneratedFilePoliciesResponse),
+        (status = 400, description = "Malformed or invalid request not meeting validation requirements", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+#[tracing::instrument(skip_all, fields(?req))]
+pub async fn set_generated_file_policies(
+    TenantDb(db): TenantDb,
+    Garde(Json(req)): Garde<Json<SetGeneratedFilePoliciesRequest>>,
+) -> HttpResult<GeneratedFilePoliciesResponse> {
+    if let Some(policy) = req
+        .policies
+        .iter()
+        .find(|policy| !is_valid_policy_mime(&policy.mime))
+    {
+        return Err(HttpAdminError::InvalidPolicyMime(policy.mime.clone()).into());
+    }
+
+    let mut t = db.begin().await.map_err(|error| {
+        tracing::error!(?error, "failed to begin transaction");
+        HttpCommonError::ServerError
+    })?;
+
+    GeneratedFilePolicy::replace_all(&mut t, &req.policies)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to store generated file policies");
+            HttpCommonError::ServerError
+        })?;
+
+    t.commit().await.map_err(|error| {
+        tracing::error!(?error, "failed to commit transaction");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(GeneratedFilePoliciesResponse {
+        policies: req.policies,
+    }))
+}
+
+/// File access report
+///
+/// Requests a page of files within the tenant ordered by how often they
+/// have been downloaded or previewed. Ordering by least accessed includes
+/// files that have never been accessed, useful for finding files to archive
+#[utoipa::path(
+    post,
+    operation_id = "admin_file_access_report",
+    tag = ADMIN_TAG,
+    path = "/admin/file-access-report",
+    request_body = FileAccessReportRequest,
+    responses(
+        (status = 200, description = "Obtained report successfully", body = FileAccessReportResponse),
+        (status = 400, description = "Malformed or invalid request not meeting validation requirements", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+#[tracing::instrument(skip_all, fields(?req))]
+pub async fn file_access_report(
+    TenantDb(db): TenantDb,
+    Garde(Json(req)): Garde<Json<FileAccessReportRequest>>,
+) -> HttpResult<FileAccessReportResponse> {
+    let offset = req.offset.unwrap_or(0);
+    let limit = req.size.unwrap_or(100);
+
+    let results = FileAccessStats::report(&db, req.order, offset, limit as u64)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query file access report");
+            HttpCommonError::ServerError
+        })?;
+
+    Ok(Json(FileAccessReportResponse { results }))
+}
+
+/// Get upload rules
+///
+/// Get the rules restricting which files can be uploaded to the tenant
+#[utoipa::path(
+    get,
+    operation_id = "admin_get_upload_rules",
+    tag = ADMIN_TAG,
+    path = "/admin/upload-rules",
+    responses(
+        (status = 200, description = "Obtained rules successfully", body = UploadRulesResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+#[tracing::instrument(skip_all)]
+pub async fn get_upload_rules(TenantDb(db): TenantDb) -> HttpResult<UploadRulesResponse> {
+    let rules = UploadRule::all(&db).await.map_err(|error| {
+        tracing::error!(?error, "failed to query upload rules");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(UploadRulesResponse { rules }))
+}
+
+/// Set upload rules
+///
+/// Replace the rules restricting which files can be uploaded to the tenant.
+/// Rules apply to both direct and presigned uploads.
+///
+/// Rules match files by either mime type (An exact mime type "image/png", a
+/// type wildcard "image/*" or all files "*") or by file extension ("pdf" or
+/// all files "*"):
+/// - "Deny" rules reject any matching files
+/// - "Allow" rules restrict uploads to only matching files once any allow
+///   rule exists for the same kind
+/// - "Limit" rules only apply their max size
+///
+/// Any matching non deny rule with a max size will reject larger files
+#[utoipa::path(
+    put,
+    operation_id = "admin_set_upload_rules",
+    tag = ADMIN_TAG,
+    path = "/admin/upload-rules",
+    request_body = SetUploadRulesRequest,
+    responses(
+        (status = 200, description = "Updated rules successfully", body = UploadRulesResponse),
+        (status = 400, description = "Malformed or invalid request not meeting validation requirements", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+#[tracing::instrument(skip_all, fields(?req))]
+pub async fn set_upload_rules(
+    TenantDb(db): TenantDb,
+    Garde(Json(req)): Garde<Json<SetUploadRulesRequest>>,
+) -> HttpResult<UploadRulesResponse> {
+    if let Some(rule) = req.rules.iter().find(|rule| match rule.kind {
+        UploadRuleKind::Mime => !is_valid_policy_mime(&rule.pattern),
+        UploadRuleKind::Extension => rule.pattern.is_empty() || rule.pattern.contains('/'),
+    }) {
+        return Err(
+            HttpAdminError::InvalidUploadRulePattern(rule.kind, rule.pattern.clone()).into(),
+        );
+    }
+
+    let mut t = db.begin().await.map_err(|error| {
+        tracing::error!(?error, "failed to begin transaction");
+        HttpCommonError::ServerError
+    })?;
+
+    UploadRule::replace_all(&mut t, &req.rules)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to store upload rules");
+            HttpCommonError::ServerError
+        })?;
+
+    t.commit().await.map_err(|error| {
+        tracing::error!(?error, "failed to commit transaction");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(UploadRulesResponse { rules: req.rules }))
+}
+
+/// Get mime overrides
+///
+/// Get the mime types used for files with specific extensions within the tenant
+#[utoipa::path(
+    get,
+    operation_id = "admin_get_mime_overrides",
+    tag = ADMIN_TAG,
+    path = "/admin/mime-overrides",
+    responses(
+        (status = 200, description = "Obtained overrides successfully", body = MimeOverridesResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+#[tracing::instrument(skip_all)]
+pub async fn get_mime_overrides(TenantDb(db): TenantDb) -> HttpResult<MimeOverridesResponse> {
+    let overrides = MimeOverride::all(&db).await.map_err(|error| {
+        tracing::error!(?error, "failed to query mime overrides");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(MimeOverridesResponse { overrides }))
+}
+
+/// Set mime overrides
+///
+/// Replace the mime types used for files with specific extensions within
+/// the tenant (i.e treating "dat" files as "application/pdf").
+///
+/// Overrides take priority over the mime type provided when uploading and
+/// the mime type that would be guessed from the file extension. Overrides
+/// only apply to files uploaded after the override is set
+#[utoipa::path(
+    put,
+    operation_id = "admin_set_mime_overrides",
+    tag = ADMIN_TAG,
+    path = "/admin/mime-overrides",
+    request_body = SetMimeOverridesRequest,
+    responses(
+        (status = 200, description = "Updated overrides successfully", body = MimeOverridesResponse),
+        (status = 400, description = "Malformed or invalid request not meeting validation requirements", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+#[tracing::instrument(skip_all, fields(?req))]
+pub async fn set_mime_overrides(
+    TenantDb(db): TenantDb,
+    Garde(Json(req)): Garde<Json<SetMimeOverridesRequest>>,
+) -> HttpResult<MimeOverridesResponse> {
+    if let Some(mime_override) = req.overrides.iter().find(|mime_override| {
+        mime_override.extension.is_empty()
+            || mime_override.extension.contains(['.', '/', '*'])
+            || mime_override.mime.parse::<mime::Mime>().is_err()
+    }) {
+        return Err(HttpAdminError::InvalidMimeOverride(mime_override.extension.clone()).into());
+    }
+
+    let mut t = db.begin().await.map_err(|error| {
+        tracing::error!(?error, "failed to begin transaction");
+        HttpCommonError::ServerError
+    })?;
+
+    MimeOverride::replace_all(&mut t, &req.overrides)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to store mime overrides");
+            HttpCommonError::ServerError
+        })?;
+
+    t.commit().await.map_err(|error| {
+        tracing::error!(?error, "failed to commit transaction");
+        HttpCommonError::ServerError
+    })?;
+
+    let overrides = MimeOverride::all(&db).await.map_err(|error| {
+        tracing::error!(?error, "failed to query mime overrides");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(MimeOverridesResponse { overrides }))
+}
+
+/// Checks a policy mime is either "*", a "type/*" wildcard or a valid mime type
+fn is_valid_policy_mime(mime: &str) -> bool {
+    if mime == "*" {
+        return true;
+    }
+
+    match mime.strip_suffix("/*") {
+        Some(ty) => !ty.is_empty() && !ty.contains(['/', '*']),
+        None => mime.parse::<mime::Mime>().is_ok(),
+    }
+}
+
+/// Tenant Scopes
+///
+/// Lists the scopes of document boxes within the tenant starting with a
+/// prefix along with the registered scope patterns overlapping the prefix
+#[utoipa::path(
+    post,
+    operation_id = "admin_tenant_scopes",
+    tag = ADMIN_TAG,
+    path = "/admin/scopes",
+    responses(
+        (status = 200, description = "Listed scopes successfully", body = TenantScopesResponse),
+        (status = 400, description = "Malformed or invalid request not meeting validation requirements", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+#[tracing::instrument(skip_all, fields(?req))]
+pub async fn tenant_scopes(
+    TenantDb(db): TenantDb,
+    Garde(Json(req)): Garde<Json<TenantScopesRequest>>,
+) -> HttpResult<TenantScopesResponse> {
+    let offset = req.offset.unwrap_or(0);
+    let limit = req.size.unwrap_or(100) as u64;
+    let prefix = req.prefix.as_str();
+
+    let (scopes, total, patterns) = try_join!(
+        DocumentBox::scopes_by_prefix(&db, prefix, offset, limit),
+        DocumentBox::scopes_by_prefix_total(&db, prefix),
+        ScopePattern::all(&db)
     )
+    .map_err(|error| {
+        tracing::error!(?error, "failed to query scopes");
+        HttpCommonError::ServerError
+    })?;
+
+    let patterns = patterns
+        .into_iter()
+        .filter(|pattern| pattern.pattern.starts_with(prefix) || pattern.matches(prefix))
+        .collect();
+
+    Ok(Json(TenantScopesResponse {
+        scopes,
+        total,
+        patterns,
+    }))
+}
+
+/// Get scope patterns
+///
+/// Get the registered scope patterns for the tenant, when any patterns
+/// are registered document boxes can only be created with a scope matching
+/// at least one of the patterns
+#[utoipa::path(
+    get,
+    operation_id = "admin_get_scope_patterns",
+    tag = ADMIN_TAG,
+    path = "/admin/scope-patterns",
+    responses(
+        (status = 200, description = "Got scope patterns successfully", body = ScopePatternsResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
 )]
-pub async fn http_purge_expired_presigned_tasks(
-    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
-    Extension(storage_factory): Extension<StorageLayerFactory>,
+#[tracing::instrument(skip_all)]
+pub async fn get_scope_patterns(TenantDb(db): TenantDb) -> HttpResult<ScopePatternsResponse> {
+    let patterns = ScopePattern::all(&db).await.map_err(|error| {
+        tracing::error!(?error, "failed to query scope patterns");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(ScopePatternsResponse { patterns }))
+}
+
+/// Create scope pattern
+///
+/// Register a new scope pattern, patterns are either an exact scope ("org:1")
+/// or a prefix followed by a trailing wildcard ("org:1:*").
+///
+/// Patterns can be nested within a parent wildcard pattern that matches every
+/// scope the pattern matches ("org:1:*" within "org:*"), deleting a parent
+/// pattern deletes all of its children
+#[utoipa::path(
+    post,
+    operation_id = "admin_create_scope_pattern",
+    tag = ADMIN_TAG,
+    path = "/admin/scope-patterns",
+    request_body = CreateScopePatternRequest,
+    responses(
+        (status = 201, description = "Created scope pattern successfully", body = ScopePattern),
+        (status = 400, description = "Malformed or invalid request not meeting validation requirements", body = HttpErrorResponse),
+        (status = 409, description = "Scope pattern already exists", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(TenantParams)
+)]
+#[tracing::instrument(skip_all, fields(?req))]
+pub async fn create_scope_pattern(
+    TenantDb(db): TenantDb,
+    Garde(Json(req)): Garde<Json<CreateScopePatternRequest>>,
+) -> Result<(StatusCode, Json<ScopePattern>), DynHttpError> {
+    if !is_valid_scope_pattern(&req.pattern) {
+        return Err(HttpAdminError::InvalidScopePattern(req.pattern).into());
+    }
+
+    if let Some(parent) = req.parent.as_deref() {
+        let parent_pattern = ScopePattern::find(&db, parent)
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to query parent scope pattern");
+                HttpCommonError::ServerError
+            })?
+            .ok_or_else(|| HttpAdminError::UnknownParentScopePattern(parent.to_string()))?;
+
+        if !parent_pattern.covers(&req.pattern) {
+            return Err(HttpAdminError::ParentScopePatternMismatch(parent.to_string()).into());
+        }
+    }
+
+    let pattern = ScopePattern::create(
+        &db,
+        CreateScopePattern {
+            pattern: req.pattern,
+            parent: req.parent,
+            description: req.description,
+        },
+    )
+    .await
+    .map_err(|error| {
+        if error
+            .as_database_error()
+            .is_some_and(|error| error.is_unique_violation())
+        {
+            return DynHttpError::from(HttpAdminError::ScopePatternAlreadyExists);
+        }
+
+        tracing::error!(?error, "failed to create scope pattern");
+        DynHttpError::from(HttpCommonError::ServerError)
+    })?;
+
+    Ok((StatusCode::CREATED, Json(pattern)))
+}
+
+/// Delete scope pattern
+///
+/// Delete a registered scope pattern along with all of its child patterns
+#[utoipa::path(
+    delete,
+    operation_id = "admin_delete_scope_pattern",
+    tag = ADMIN_TAG,
+    path = "/admin/scope-patterns/{pattern}",
+    responses(
+        (status = 204, description = "Deleted scope pattern successfully"),
+        (status = 404, description = "Scope pattern not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("pattern" = String, Path, description = "The scope pattern to delete"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%pattern))]
+pub async fn delete_scope_pattern(
+    TenantDb(db): TenantDb,
+    Path(pattern): Path<String>,
 ) -> HttpStatusResult {
-    purge_expired_presigned_tasks(db_cache, storage_factory)
+    let pattern = ScopePattern::find(&db, &pattern)
         .await
-        .map_err(|_| HttpCommonError::ServerError)?;
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query scope pattern");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpAdminError::UnknownScopePattern)?;
+
+    pattern.delete(&db).await.map_err(|error| {
+        tracing::error!(?error, "failed to delete scope pattern");
+        HttpCommonError::ServerError
+    })?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Get event payload
+///
+/// Resolve the full payload of an event that was published as a claim check
+/// because it exceeded the event queue message size limit
+#[utoipa::path(
+    get,
+    operation_id = "admin_get_event_payload",
+    tag = ADMIN_TAG,
+    path = "/admin/event-payloads/{payload_id}",
+    responses(
+        (status = 200, description = "Event payload obtained successfully", body = EventPayload),
+        (status = 404, description = "Event payload not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("payload_id" = Uuid, Path, description = "ID from the claim check of the published event"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%payload_id))]
+pub async fn get_event_payload(
+    TenantDb(db): TenantDb,
+    Path(payload_id): Path<EventPayloadId>,
+) -> HttpResult<EventPayload> {
+    let payload = EventPayload::find(&db, payload_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query event payload");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpAdminError::UnknownEventPayload)?;
+
+    Ok(Json(payload))
+}
+
+/// Checks a scope pattern is valid and the scopes it matches only
+/// contain the characters allowed in document box scopes
+fn is_valid_scope_pattern(pattern: &str) -> bool {
+    ScopePattern::is_valid_pattern(pattern)
+        && DocumentBoxScope::validate_scope(pattern.strip_suffix('*').unwrap_or(pattern))
+}
+
 /// List Users
 ///
 /// Request lists of users stored in the docbox database
@@ -386,7 +1979,7 @@ pub async fn http_purge_expired_presigned_tasks(
     tag = ADMIN_TAG,
     path = "/admin/users",
     responses(
-        (status = 200, description = "Listed users successfully", body = AdminSearchResultResponse),
+        (status = 200, description = "Listed users successfully", body = Page<User>),
         (status = 400, description = "Malformed or invalid request not meeting validation requirements", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
@@ -396,9 +1989,9 @@ pub async fn http_purge_expired_presigned_tasks(
 pub async fn list_users(
     TenantDb(db): TenantDb,
     Garde(Json(req)): Garde<Json<UsersRequest>>,
-) -> HttpResult<AdminUsersResults> {
-    let offset = req.offset.unwrap_or(0);
-    let limit = req.size.unwrap_or(100) as u64;
+) -> HttpResult<Page<User>> {
+    let offset = page_offset(req.cursor.as_deref(), req.offset)?;
+    let limit = req.size.map(u64::from).unwrap_or(DEFAULT_PAGE_SIZE);
 
     let total_future = User::total(&db);
     let results_future = User::query(&db, offset, limit);
@@ -407,7 +2000,7 @@ pub async fn list_users(
         HttpCommonError::ServerError
     })?;
 
-    Ok(Json(AdminUsersResults { total, results }))
+    Ok(Json(Page::from_offset(results, total, offset)))
 }
 
 /// Delete User
@@ -451,3 +2044,143 @@ pub async fn delete_user(TenantDb(db): TenantDb, Path(id): Path<String>) -> Http
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Archive Document Box
+///
+/// Archives a document box making its contents read-only, archived document
+/// boxes are excluded from admin searches unless explicitly included.
+///
+/// When `transition_storage` is requested the stored files are moved to the
+/// archive storage class in a background task
+#[utoipa::path(
+    post,
+    operation_id = "admin_archive_document_box",
+    tag = ADMIN_TAG,
+    path = "/admin/boxes/{scope}/archive",
+    responses(
+        (status = 200, description = "Archived document box successfully", body = ArchiveDocumentBoxResponse),
+        (status = 404, description = "Document box not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = String, Path, description = "Scope of the document box"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, ?req))]
+pub async fn archive_document_box(
+    TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
+    Path(scope): Path<String>,
+    Garde(Json(req)): Garde<Json<ArchiveDocumentBoxRequest>>,
+) -> HttpResult<ArchiveDocumentBoxResponse> {
+    set_archived(db, storage, scope, true, req).await
+}
+
+/// Unarchive Document Box
+///
+/// Unarchives a document box allowing its contents to be modified again.
+///
+/// When `transition_storage` is requested the stored files are moved back to
+/// the standard storage class in a background task
+#[utoipa::path(
+    post,
+    operation_id = "admin_unarchive_document_box",
+    tag = ADMIN_TAG,
+    path = "/admin/boxes/{scope}/unarchive",
+    responses(
+        (status = 200, description = "Unarchived document box successfully", body = ArchiveDocumentBoxResponse),
+        (status = 404, description = "Document box not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = String, Path, description = "Scope of the document box"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, ?req))]
+pub async fn unarchive_document_box(
+    TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
+    Path(scope): Path<String>,
+    Garde(Json(req)): Garde<Json<ArchiveDocumentBoxRequest>>,
+) -> HttpResult<ArchiveDocumentBoxResponse> {
+    set_archived(db, storage, scope, false, req).await
+}
+
+/// Shared logic for archiving and unarchiving a document box
+async fn set_archived(
+    db: DbPool,
+    storage: StorageLayer,
+    scope: String,
+    archived: bool,
+    req: ArchiveDocumentBoxRequest,
+) -> HttpResult<ArchiveDocumentBoxResponse> {
+    let document_box = set_document_box_archived(&db, &scope, archived)
+        .await
+        .map_err(|error| match error {
+            ArchiveDocumentBoxError::UnknownScope => {
+                DynHttpError::from(HttpDocumentBoxError::UnknownDocumentBox)
+            }
+            ArchiveDocumentBoxError::Database(_) => {
+                DynHttpError::from(HttpCommonError::ServerError)
+            }
+        })?;
+
+    if !req.transition_storage {
+        return Ok(Json(ArchiveDocumentBoxResponse {
+            document_box,
+            task_id: None,
+        }));
+    }
+
+    let storage_class = if archived {
+        StorageClass::Archive
+    } else {
+        StorageClass::Standard
+    };
+
+    let span = tracing::Span::current();
+
+    // Spawn background task to transition the stored files
+    let (task_id, _created_at) = background_task(
+        db.clone(),
+        scope.clone(),
+        async move {
+            let result = set_document_box_storage_class(&db, &storage, &scope, storage_class)
+                .await
+                .map_err(|error| {
+                    tracing::error!(?error, "failed to change document box storage class");
+                    DynHttpError::from(HttpCommonError::ServerError)
+                })
+                // Serialize the response for storage
+                .and_then(|value| {
+                    serde_json::to_value(&value).map_err(|error| {
+                        tracing::error!(?error, "failed to serialize storage class outcome");
+                        DynHttpError::from(HttpCommonError::ServerError)
+                    })
+                });
+
+            match result {
+                Ok(value) => (TaskStatus::Completed, value),
+                Err(error) => (
+                    TaskStatus::Failed,
+                    serde_json::json!({ "error": error.to_string() }),
+                ),
+            }
+        }
+        // Ensure the logging span is passed onto the background task so that
+        // logging context continues
+        .instrument(span),
+    )
+    .await
+    .map_err(|error| {
+        tracing::error!(?error, "failed to create background task");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(ArchiveDocumentBoxResponse {
+        document_box,
+        task_id: Some(task_id),
+    }))
+}
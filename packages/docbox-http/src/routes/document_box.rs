@@ -6,28 +6,52 @@ use crate::{
         action_user::{ActionUser, UserParams},
         tenant::{TenantDb, TenantEvents, TenantParams, TenantSearch, TenantStorage},
     },
-    models::document_box::{
-        CreateDocumentBoxRequest, DocumentBoxResponse, DocumentBoxScope, DocumentBoxStats,
-        HttpDocumentBoxError,
+    models::{
+        document_box::{
+            CreateDocumentBoxRequest, DocumentBoxPiiReport, DocumentBoxPinnedResponse,
+            DocumentBoxResponse, DocumentBoxScope, DocumentBoxStats, HttpDocumentBoxError,
+            ImportDocumentBoxRequest, ImportDocumentBoxResponse, PiiKindCount, PiiReportFile,
+            PinnedItem,
+        },
+        folder::HttpFolderError,
+        search::{HttpSearchError, MAX_RECENT_SEARCHES, RecentSearchesResponse},
     },
 };
-use axum::{Json, extract::Path, http::StatusCode};
+use axum::{Extension, Json, extract::Path, http::StatusCode};
+use axum_typed_multipart::TypedMultipart;
 use axum_valid::Garde;
+use chrono::Utc;
 use docbox_core::{
-    database::models::{
-        document_box::DocumentBox,
-        file::File,
-        folder::{Folder, FolderWithExtra, ResolvedFolderWithExtra},
-        shared::WithFullPath,
+    database::{
+        DbPool,
+        models::{
+            document_box::DocumentBox,
+            file_pii_analysis::FilePiiAnalysis,
+            folder::{Folder, FolderWithExtra, ResolvedFolderWithExtra},
+            recent_search::RecentSearch,
+            shared::WithFullPath,
+            tasks::TaskStatus,
+        },
     },
     document_box::{
         create_document_box::{CreateDocumentBox, CreateDocumentBoxError, create_document_box},
         delete_document_box::{DeleteDocumentBoxError, delete_document_box},
-        search_document_box::{ResolvedSearchResult, search_document_box},
+        document_box_stats::get_document_box_stats,
+        import_zip::{ImportZipData, import_zip, read_import_entries},
+        pinned_items::get_pinned_items,
+        search_document_box::{ResolvedSearchResult, SearchDocumentBoxError, search_document_box},
     },
-    search::models::{SearchRequest, SearchResultItem, SearchResultResponse},
+    processing::{ProcessingConfig, ProcessingLayer},
+    search::{
+        SearchError,
+        models::{
+            SearchRequest, SearchResultItem, SearchResultResponse, SuggestRequest, SuggestResults,
+        },
+    },
+    tasks::background_task::background_task_with,
 };
 use tokio::join;
+use tracing::Instrument;
 
 pub const DOCUMENT_BOX_TAG: &str = "Document Box";
 
@@ -41,6 +65,7 @@ pub const DOCUMENT_BOX_TAG: &str = "Document Box";
     path = "/box",
     responses(
         (status = 201, description = "Document box created successfully", body = DocumentBoxResponse),
+        (status = 400, description = "Invalid scope or scope not matching any registered scope pattern", body = HttpErrorResponse),
         (status = 409, description = "Scope already exists", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
@@ -71,6 +96,9 @@ pub async fn create(
                 CreateDocumentBoxError::ScopeAlreadyExists => {
                     DynHttpError::from(HttpDocumentBoxError::ScopeAlreadyExists)
                 }
+                CreateDocumentBoxError::ScopeNotRegistered => {
+                    DynHttpError::from(HttpDocumentBoxError::ScopeNotRegistered)
+                }
                 error => {
                     tracing::error!(?error, "failed to create document box");
                     DynHttpError::from(HttpCommonError::ServerError)
@@ -179,50 +207,141 @@ pub async fn stats(
     TenantDb(db): TenantDb,
     Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
 ) -> HttpResult<DocumentBoxStats> {
-    // Assert that the document box exists
-    let _document_box = DocumentBox::find_by_scope(&db, &scope)
+    // Stats are only missing when the document box does not exist
+    let counters = get_document_box_stats(&db, &scope)
         .await
         .map_err(|error| {
-            tracing::error!(?error, "failed to query document box");
+            tracing::error!(?error, "failed to query document box stats");
             HttpCommonError::ServerError
         })?
         .ok_or(HttpDocumentBoxError::UnknownDocumentBox)?;
 
-    let root = Folder::find_root(&db, &scope)
+    Ok(Json(DocumentBoxStats {
+        total_files: counters.total_files,
+        total_links: counters.total_links,
+        total_folders: counters.total_folders,
+        file_size: counters.file_size,
+    }))
+}
+
+/// Get PII report
+///
+/// Reports the PII found within the files of a document box for compliance
+/// reviews. Only includes files that were analyzed for PII, PII detection
+/// is enabled using the `detect_pii` processing config option
+#[utoipa::path(
+    get,
+    operation_id = "document_box_pii_report",
+    tag = DOCUMENT_BOX_TAG,
+    path = "/box/{scope}/pii-report",
+    responses(
+        (status = 200, description = "PII report obtained successfully", body = DocumentBoxPiiReport),
+        (status = 404, description = "Document box not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope of the document box"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope))]
+pub async fn pii_report(
+    TenantDb(db): TenantDb,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+) -> HttpResult<DocumentBoxPiiReport> {
+    // Assert that the document box exists
+    let _document_box = DocumentBox::find_by_scope(&db, &scope)
         .await
         .map_err(|error| {
-            tracing::error!(?error, "failed to query folder");
+            tracing::error!(?error, "failed to query document box");
             HttpCommonError::ServerError
         })?
-        .ok_or_else(|| {
-            tracing::error!("document box missing root");
-            HttpCommonError::ServerError
-        })?;
+        .ok_or(HttpDocumentBoxError::UnknownDocumentBox)?;
 
-    let children_future = Folder::count_children(&db, root.id);
-    let file_size_future = File::total_size_within_scope(&db, &scope);
+    let counts_future = FilePiiAnalysis::counts_within_scope(&db, &scope);
+    let files_future = FilePiiAnalysis::find_with_pii_within_scope(&db, &scope);
 
-    // Load the children count and file sizes in parallel
-    let (children, file_size) = join!(children_future, file_size_future);
+    // Load the counts and files in parallel
+    let (counts, files) = join!(counts_future, files_future);
 
-    let children = children.map_err(|error| {
-        tracing::error!(?error, "failed to query document box children count");
+    let counts = counts.map_err(|error| {
+        tracing::error!(?error, "failed to query pii analysis counts");
         HttpCommonError::ServerError
     })?;
 
-    let file_size = file_size.map_err(|error| {
-        tracing::error!(?error, "failed to query document box file size");
+    let files = files.map_err(|error| {
+        tracing::error!(?error, "failed to query files containing pii");
         HttpCommonError::ServerError
     })?;
 
-    Ok(Json(DocumentBoxStats {
-        total_files: children.file_count,
-        total_links: children.link_count,
-        total_folders: children.folder_count,
-        file_size,
+    let files: Vec<PiiReportFile> = files
+        .into_iter()
+        .map(|file| PiiReportFile {
+            file_id: file.file_id,
+            name: file.name,
+            mime: file.mime,
+            folder_id: file.folder_id,
+            analyzed_at: file.analyzed_at,
+            counts: PiiKindCount::from_findings(&file.findings.0),
+            findings: file.findings.0,
+        })
+        .collect();
+
+    Ok(Json(DocumentBoxPiiReport {
+        analyzed_files: counts.analyzed_files,
+        files_with_pii: counts.files_with_pii,
+        counts: PiiKindCount::from_findings(files.iter().flat_map(|file| &file.findings)),
+        files,
     }))
 }
 
+/// Get pinned items
+///
+/// Requests all the pinned files, folders and links within a document
+/// box, most recently pinned first
+#[utoipa::path(
+    get,
+    operation_id = "document_box_pinned",
+    tag = DOCUMENT_BOX_TAG,
+    path = "/box/{scope}/pinned",
+    responses(
+        (status = 200, description = "Pinned items obtained successfully", body = DocumentBoxPinnedResponse),
+        (status = 404, description = "Document box not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope of the document box"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope))]
+pub async fn pinned(
+    TenantDb(db): TenantDb,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+) -> HttpResult<DocumentBoxPinnedResponse> {
+    // Assert that the document box exists
+    let _document_box = DocumentBox::find_by_scope(&db, &scope)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query document box");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpDocumentBoxError::UnknownDocumentBox)?;
+
+    let items = get_pinned_items(&db, &scope)
+        .await
+        .map_err(|_| HttpCommonError::ServerError)?
+        .into_iter()
+        .map(|item| PinnedItem {
+            pinned_at: item.pinned_at,
+            path: item.path,
+            data: item.data,
+        })
+        .collect();
+
+    Ok(Json(DocumentBoxPinnedResponse { items }))
+}
+
 /// Delete document box by scope
 ///
 /// Deletes a specific document box by scope and all its contents
@@ -282,21 +401,35 @@ pub async fn delete(
     ),
     params(
         ("scope" = DocumentBoxScope, Path, description = "Scope of the document box"),
-        TenantParams
+        TenantParams,
+        UserParams
     )
 )]
 #[tracing::instrument(skip_all, fields(%scope, ?req))]
 pub async fn search(
+    action_user: ActionUser,
     TenantDb(db): TenantDb,
     TenantSearch(search): TenantSearch,
     Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
     Garde(Json(req)): Garde<Json<SearchRequest>>,
 ) -> HttpResult<SearchResultResponse> {
-    let resolved = search_document_box(&db, &search, scope, req)
+    let query = req
+        .query
+        .as_deref()
+        .map(str::trim)
+        .filter(|query| !query.is_empty())
+        .map(str::to_string);
+
+    let resolved = search_document_box(&db, &search, scope.clone(), req)
         .await
-        .map_err(|error| {
-            tracing::error!(?error, "failed to search document box");
-            HttpCommonError::ServerError
+        .map_err(|error| match error {
+            SearchDocumentBoxError::QueryIndex(SearchError::Validation(error)) => {
+                DynHttpError::from(HttpSearchError::InvalidRequest(error))
+            }
+            error => {
+                tracing::error!(?error, "failed to search document box");
+                DynHttpError::from(HttpCommonError::ServerError)
+            }
         })?;
 
     let out: Vec<SearchResultItem> = resolved
@@ -311,12 +444,281 @@ pub async fn search(
                 total_hits: result.total_hits,
                 name_match: result.name_match,
                 content_match: result.content_match,
+                explanation: result.explanation,
             },
         )
         .collect();
 
+    // Remember the query in the users recent searches
+    if let Some(query) = query
+        && let Some(user) = action_user.store_user(&db).await?
+    {
+        record_recent_search(&db, &user.id, &scope, &query).await;
+    }
+
     Ok(Json(SearchResultResponse {
         total_hits: resolved.total_hits,
         results: out,
+        timed_out: resolved.timed_out,
+        facets: resolved.facets,
+    }))
+}
+
+/// Store a search made by a user, failing to store the search is logged
+/// but does not fail the search
+async fn record_recent_search(db: &DbPool, user_id: &str, scope: &str, query: &str) {
+    if let Err(error) = RecentSearch::record(db, user_id, scope, query, Utc::now()).await {
+        tracing::error!(?error, "failed to record recent search");
+        return;
+    }
+
+    if let Err(error) = RecentSearch::prune(db, user_id, scope, MAX_RECENT_SEARCHES).await {
+        tracing::error!(?error, "failed to prune recent searches");
+    }
+}
+
+/// Suggest item names
+///
+/// Complete the names of files, folders and links within the document box
+/// that start with the query, intended for search autocomplete
+#[utoipa::path(
+    post,
+    operation_id = "document_box_suggest",
+    tag = DOCUMENT_BOX_TAG,
+    path = "/box/{scope}/suggest",
+    responses(
+        (status = 200, description = "Suggestions obtained successfully", body = SuggestResults),
+        (status = 400, description = "Malformed or invalid request not meeting validation requirements", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope of the document box"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, ?req))]
+pub async fn suggest(
+    TenantSearch(search): TenantSearch,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+    Garde(Json(req)): Garde<Json<SuggestRequest>>,
+) -> HttpResult<SuggestResults> {
+    let results = search
+        .suggest_index(&[scope], req)
+        .await
+        .map_err(|error| match error {
+            SearchError::Validation(error) => {
+                DynHttpError::from(HttpSearchError::InvalidRequest(error))
+            }
+            error => {
+                tracing::error!(?error, "failed to query suggestions");
+                DynHttpError::from(HttpCommonError::ServerError)
+            }
+        })?;
+
+    Ok(Json(results))
+}
+
+/// Get recent searches
+///
+/// Requests the recent search queries made by the current user within
+/// the document box, most recent first
+#[utoipa::path(
+    get,
+    operation_id = "document_box_recent_searches",
+    tag = DOCUMENT_BOX_TAG,
+    path = "/box/{scope}/search/recent",
+    responses(
+        (status = 200, description = "Recent searches obtained successfully", body = RecentSearchesResponse),
+        (status = 400, description = "Missing user ID header", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope of the document box"),
+        TenantParams,
+        UserParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope))]
+pub async fn recent_searches(
+    ActionUser(user): ActionUser,
+    TenantDb(db): TenantDb,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+) -> HttpResult<RecentSearchesResponse> {
+    let user = user.ok_or(HttpSearchError::MissingUser)?;
+
+    let searches = RecentSearch::find_by_user(&db, &user.id, &scope, MAX_RECENT_SEARCHES)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query recent searches");
+            HttpCommonError::ServerError
+        })?;
+
+    Ok(Json(RecentSearchesResponse { searches }))
+}
+
+/// Clear recent searches
+///
+/// Deletes all the recent search queries made by the current user within
+/// the document box
+#[utoipa::path(
+    delete,
+    operation_id = "document_box_clear_recent_searches",
+    tag = DOCUMENT_BOX_TAG,
+    path = "/box/{scope}/search/recent",
+    responses(
+        (status = 204, description = "Recent searches cleared successfully"),
+        (status = 400, description = "Missing user ID header", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope of the document box"),
+        TenantParams,
+        UserParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope))]
+pub async fn clear_recent_searches(
+    ActionUser(user): ActionUser,
+    TenantDb(db): TenantDb,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+) -> HttpStatusResult {
+    let user = user.ok_or(HttpSearchError::MissingUser)?;
+
+    RecentSearch::delete_by_user(&db, &user.id, &scope)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to clear recent searches");
+            HttpCommonError::ServerError
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Import ZIP archive
+///
+/// Imports the contents of a ZIP archive into the document box, the folder
+/// structure within the archive is recreated and each file is processed the
+/// same as a normal upload. The import runs in the background, use the task
+/// from the response to track its progress.
+///
+/// Archives are limited to 1000 entries and 1GiB of extracted content. Files
+/// that fail to import are listed in the task output and do not stop the
+/// remaining files from importing
+///
+/// This endpoint is not available in the serverless version of docbox
+#[utoipa::path(
+    post,
+    operation_id = "document_box_import",
+    tag = DOCUMENT_BOX_TAG,
+    path = "/box/{scope}/import",
+    responses(
+        (status = 200, description = "Import task created successfully", body = ImportDocumentBoxResponse),
+        (status = 400, description = "Archive is invalid or exceeds the import limits", body = HttpErrorResponse),
+        (status = 404, description = "Target folder could not be found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    request_body(content = ImportDocumentBoxRequest, description = "Multipart upload", content_type = "multipart/form-data"),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope to import the archive into"),
+        TenantParams,
+        UserParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope))]
+#[allow(clippy::too_many_arguments)]
+pub async fn import(
+    action_user: ActionUser,
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    TenantStorage(storage): TenantStorage,
+    TenantEvents(events): TenantEvents,
+    Extension(processing): Extension<ProcessingLayer>,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+    Garde(TypedMultipart(req)): Garde<TypedMultipart<ImportDocumentBoxRequest>>,
+) -> HttpResult<ImportDocumentBoxResponse> {
+    let folder = match req.folder_id {
+        Some(folder_id) => Folder::find_by_id(&db, &scope, folder_id).await,
+        None => Folder::find_root(&db, &scope).await,
+    }
+    .map_err(|error| {
+        tracing::error!(?error, "failed to query folder");
+        HttpCommonError::ServerError
+    })?
+    .ok_or(HttpFolderError::UnknownTargetFolder)?;
+
+    // Validate the archive before starting the import
+    let archive = req.file.contents;
+    let entries = read_import_entries(&archive).map_err(|error| {
+        tracing::warn!(?error, "invalid import archive");
+        HttpDocumentBoxError::InvalidImportArchive(error)
+    })?;
+
+    // Parse task processing config
+    let processing_config: Option<ProcessingConfig> = match &req.processing_config {
+        Some(value) => match serde_json::from_str(value) {
+            Ok(value) => value,
+            Err(error) => {
+                tracing::error!(?error, "failed to deserialize processing config");
+                None
+            }
+        },
+        None => None,
+    };
+
+    // Update stored editing user data
+    let created_by = action_user.store_user(&db).await?;
+
+    let data = ImportZipData {
+        folder,
+        archive,
+        entries,
+        created_by: created_by.as_ref().map(|value| value.id.to_string()),
+        processing_config,
+    };
+
+    let span = tracing::Span::current();
+
+    // Spawn background task to perform the import
+    let (task_id, created_at) = background_task_with(db.clone(), scope.clone(), |mut task| {
+        async move {
+            let result = import_zip(
+                &db,
+                &search,
+                &storage,
+                &processing,
+                &events,
+                data,
+                Some(&mut task),
+            )
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to import archive");
+                error.to_string()
+            })
+            .and_then(|output| {
+                serde_json::to_value(&output).map_err(|error| {
+                    tracing::error!(?error, "failed to serialize import task outcome");
+                    error.to_string()
+                })
+            });
+
+            match result {
+                Ok(value) => (TaskStatus::Completed, value),
+                Err(error) => (TaskStatus::Failed, serde_json::json!({ "error": error })),
+            }
+        }
+        // Ensure the logging span is passed onto the background task so that
+        // logging context continues
+        .instrument(span)
+    })
+    .await
+    .map_err(|error| {
+        tracing::error!(?error, "failed to create background task");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(ImportDocumentBoxResponse {
+        task_id,
+        created_at,
     }))
 }
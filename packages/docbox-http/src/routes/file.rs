@@ -2,20 +2,27 @@
 
 use crate::{
     error::{DynHttpError, HttpCommonError, HttpErrorResponse, HttpResult, HttpStatusResult},
-    extensions::max_file_size::MaxFileSizeBytes,
+    extensions::{max_file_size::MaxFileSizeBytes, search_auto_heal::SearchAutoHeal},
     middleware::{
         action_user::{ActionUser, UserParams},
-        tenant::{TenantDb, TenantEvents, TenantParams, TenantSearch, TenantStorage},
+        if_match::{ETagHeader, IfMatch, IfMatchParams, version_etag},
+        tenant::{
+            TenantDb, TenantEvents, TenantFileAccess, TenantParams, TenantSearch, TenantStorage,
+        },
     },
     models::{
-        document_box::DocumentBoxScope,
+        document_box::{DocumentBoxScope, HttpDocumentBoxError, MoveToScopeRequest},
+        edit_history::{EditHistoryQuery, HttpEditHistoryError},
         file::{
-            BinaryResponse, CreatePresignedRequest, FileResponse, FileUploadResponse,
-            GetPresignedRequest, HttpFileError, PresignedDownloadResponse, PresignedStatusResponse,
+            BinaryResponse, CreatePresignedRequest, EmailFileRequest, FilePagePreviewsResponse,
+            FileResponse, FileUploadResponse, FilesUploadResponse, GetPresignedRequest,
+            HttpFileError, PresignedDownloadResponse, PresignedStatusResponse,
             PresignedUploadResponse, RawFileQuery, UpdateFileRequest, UploadFileRequest,
-            UploadTaskResponse, UploadedFile,
+            UploadFilesRequest, UploadTaskResponse, UploadedFile, UploadedFilesResponse,
         },
         folder::HttpFolderError,
+        page::{DEFAULT_PAGE_SIZE, Page, page_offset},
+        search::HttpSearchError,
     },
 };
 use axum::{
@@ -27,28 +34,55 @@ use axum::{
 use axum_typed_multipart::TypedMultipart;
 use axum_valid::Garde;
 use docbox_core::{
-    database::models::{
-        edit_history::EditHistory,
-        file::{File, FileId, FileWithExtra},
-        folder::Folder,
-        generated_file::{GeneratedFile, GeneratedFileType},
-        presigned_upload_task::{PresignedTaskStatus, PresignedUploadTask, PresignedUploadTaskId},
-        tasks::TaskStatus,
-        user::User,
+    database::{
+        DbPool,
+        models::{
+            document_box::DocumentBoxScopeRaw,
+            edit_history::{EditHistory, EditHistoryId},
+            file::{File, FileId, FileWithExtra},
+            file_access_stats::FileAccessStats,
+            file_email::FileEmail,
+            file_pdf_metadata::FilePdfMetadata,
+            file_pii_analysis::FilePiiAnalysis,
+            file_text_stats::FileTextStats,
+            folder::Folder,
+            generated_file::{GeneratedFile, GeneratedFileType},
+            presigned_upload_task::{
+                PresignedTaskStatus, PresignedUploadTask, PresignedUploadTaskId,
+            },
+            tasks::TaskStatus,
+            tenant::{ActiveContentPolicy, Tenant},
+            upload_rule::UploadRule,
+            user::User,
+        },
     },
+    document_box::move_to_scope::{MoveToScope, MoveToScopeError, move_file_to_scope},
     files::{
+        access_stats::FileAccessKind,
+        active_content::{ACTIVE_CONTENT_CSP, active_content_headers},
+        create_content_disposition, create_generated_file_name,
         delete_file::delete_file,
+        email_file::{EmailFile, EmailFileError, email_file},
+        mime_overrides::get_mime_overrides,
+        preview::{FilePreviewError, create_file_preview},
+        regenerate_generated_file::regenerate_generated_file,
         update_file::{UpdateFile, UpdateFileError},
-        upload_file::{UploadFile, UploadedFileData, upload_file},
+        upload_file::{UploadFile, UploadFileError, UploadedFileData, upload_file, upload_files},
         upload_file_presigned::{CreatePresigned, create_presigned_upload},
     },
+    mailer::Mailer,
+    mime::get_file_name_ext,
     processing::{ProcessingConfig, ProcessingLayer},
-    search::models::{FileSearchRequest, FileSearchResultResponse},
-    tasks::background_task::background_task,
-    utils::file::get_file_name_ext,
+    search::{
+        SearchError, TenantSearchIndex,
+        models::{FileSearchRequest, FileSearchResultResponse},
+    },
+    storage::{PresignedDownloadOptions, StorageLayer},
+    tasks::background_task::background_task_with,
 };
 use mime::Mime;
 use std::{str::FromStr, time::Duration};
+use tokio::try_join;
 use tracing::Instrument;
 
 pub const FILE_TAG: &str = "File";
@@ -72,6 +106,9 @@ pub const FILE_TAG: &str = "File";
 /// This endpoint is not available in the serverless version of docbox, instead use
 /// the presigned endpoint /box/{scope}/file/presigned
 ///
+/// Files that violate the tenant upload rules are rejected with the violated
+/// rule provided in the error details
+///
 #[utoipa::path(
     post,
     operation_id = "file_upload",
@@ -101,6 +138,7 @@ pub async fn upload(
     TenantEvents(events): TenantEvents,
     //
     Extension(processing): Extension<ProcessingLayer>,
+    Extension(auto_heal): Extension<SearchAutoHeal>,
     //
     Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
     Garde(TypedMultipart(req)): Garde<TypedMultipart<UploadFileRequest>>,
@@ -127,26 +165,16 @@ pub async fn upload(
 
     let content_type = req.mime.or(req.file.metadata.content_type);
 
-    let mut mime = match content_type {
+    let mime = match content_type {
         Some(value) => Mime::from_str(&value).map_err(|_| HttpFileError::InvalidMimeType)?,
         // Fallback to default mime type when none is provided
         None => mime::APPLICATION_OCTET_STREAM,
     };
 
-    // Attempt to guess the file mime type when application/octet-stream is specified
-    // (Likely from old browsers)
-    if mime == mime::APPLICATION_OCTET_STREAM
-        && req.disable_mime_sniffing.is_none_or(|value| !value)
-    {
-        let guessed_mime = get_file_name_ext(&req.name).and_then(|ext| {
-            let guesses = mime_guess::from_ext(&ext);
-            guesses.first()
-        });
+    let sniff = req.disable_mime_sniffing.is_none_or(|value| !value);
+    let mime = resolve_upload_mime(&db, &req.name, mime, sniff).await?;
 
-        if let Some(guessed_mime) = guessed_mime {
-            mime = guessed_mime
-        }
-    }
+    check_upload_rules(&db, &req.name, &mime, req.file.contents.len() as i64).await?;
 
     // Parse task processing config
     let processing_config: Option<ProcessingConfig> = match &req.processing_config {
@@ -164,7 +192,7 @@ pub async fn upload(
     let created_by = action_user.store_user(&db).await?;
 
     // Create the upload configuration
-    let upload = UploadFile {
+    let mut upload = UploadFile {
         fixed_id: req.fixed_id,
         parent_id: req.parent_id,
         folder_id: folder.id,
@@ -175,16 +203,22 @@ pub async fn upload(
         created_by: created_by.as_ref().map(|value| value.id.to_string()),
         file_key: None,
         processing_config,
+        task_id: None,
     };
 
     // Handle synchronous request waiting for the task to complete before responding
     if !req.asynchronous.unwrap_or_default() {
-        let data = upload_file(&db, &search, &storage, &processing, &events, upload)
-            .await
-            .map_err(|error| {
-                tracing::error!(?error, "failed to upload file");
-                HttpFileError::UploadFileError(error)
-            })?;
+        let result = upload_file(&db, &search, &storage, &processing, &events, upload).await;
+
+        // Recreate the search index if it was deleted out-of-band
+        if let Err(UploadFileError::CreateIndex(_)) = &result {
+            auto_heal.heal(&db, &search, &storage, &scope).await;
+        }
+
+        let data = result.map_err(|error| {
+            tracing::error!(?error, "failed to upload file");
+            HttpFileError::UploadFileError(error)
+        })?;
         let result = map_uploaded_file(data, &created_by);
         return Ok(Json(FileUploadResponse::Sync(Box::new(result))));
     }
@@ -192,12 +226,19 @@ pub async fn upload(
     let span = tracing::Span::current();
 
     // Spawn background task
-    let (task_id, created_at) = background_task(
-        db.clone(),
-        scope.clone(),
+    let (task_id, created_at) = background_task_with(db.clone(), scope.clone(), |task| {
+        // Include the task in the processing events
+        upload.task_id = Some(task.id);
+
         async move {
-            let result = upload_file(&db, &search, &storage, &processing, &events, upload)
-                .await
+            let result = upload_file(&db, &search, &storage, &processing, &events, upload).await;
+
+            // Recreate the search index if it was deleted out-of-band
+            if let Err(UploadFileError::CreateIndex(_)) = &result {
+                auto_heal.heal(&db, &search, &storage, &scope).await;
+            }
+
+            let result = result
                 .map_err(|error| {
                     tracing::error!(?error, "failed to upload file");
                     DynHttpError::from(HttpFileError::UploadFileError(error))
@@ -222,8 +263,8 @@ pub async fn upload(
         }
         // Ensure the logging span is passed onto the background task so that
         // logging context continues
-        .instrument(span),
-    )
+        .instrument(span)
+    })
     .await
     .map_err(|error| {
         tracing::error!(?error, "failed to create background task");
@@ -236,6 +277,197 @@ pub async fn upload(
     })))
 }
 
+/// Upload multiple files
+///
+/// Uploads multiple documents to the provided document box folder in a single
+/// request. The name and mime type of each file are taken from the multipart
+/// file name and content type. At most 50 files can be uploaded at once and the
+/// combined size of the files must be within the maximum file size
+///
+/// The files are created together, if any of the files fail to upload then
+/// none of the files will be created. Asynchronous uploads create a single task
+/// that completes once all the files are uploaded
+///
+/// Synchronous uploads return [UploadedFilesResponse]
+/// Asynchronous uploads return [UploadTaskResponse]
+///
+/// This endpoint is not available in the serverless version of docbox, instead use
+/// the presigned endpoint /box/{scope}/file/presigned
+#[utoipa::path(
+    post,
+    operation_id = "file_upload_multiple",
+    tag = FILE_TAG,
+    path = "/box/{scope}/file/multiple",
+    responses(
+        (status = 200, description = "Uploads or task created successfully", body = FilesUploadResponse),
+        (status = 400, description = "Malformed or invalid request not meeting validation requirements", body = HttpErrorResponse),
+        (status = 404, description = "Target folder could not be found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    request_body(content = UploadFilesRequest, description = "Multipart upload", content_type = "multipart/form-data"),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope to create the files within"),
+        TenantParams,
+        UserParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope))]
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_multiple(
+    action_user: ActionUser,
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    TenantStorage(storage): TenantStorage,
+    TenantEvents(events): TenantEvents,
+    //
+    Extension(processing): Extension<ProcessingLayer>,
+    Extension(auto_heal): Extension<SearchAutoHeal>,
+    //
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+    Garde(TypedMultipart(req)): Garde<TypedMultipart<UploadFilesRequest>>,
+) -> HttpResult<FilesUploadResponse> {
+    let folder = Folder::find_by_id(&db, &scope, req.folder_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query folder");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFolderError::UnknownTargetFolder)?;
+
+    // Parse task processing config
+    let processing_config: Option<ProcessingConfig> = match &req.processing_config {
+        Some(value) => match serde_json::from_str(value) {
+            Ok(value) => value,
+            Err(error) => {
+                tracing::error!(?error, "failed to deserialize processing config");
+                None
+            }
+        },
+        None => None,
+    };
+
+    let sniff = req.disable_mime_sniffing.is_none_or(|value| !value);
+
+    // Update stored editing user data
+    let created_by = action_user.store_user(&db).await?;
+
+    let mut uploads = Vec::with_capacity(req.files.len());
+
+    for file in req.files {
+        let name = file
+            .metadata
+            .file_name
+            .filter(|name| (1..=255).contains(&name.len()))
+            .ok_or(HttpFileError::InvalidFileName)?;
+
+        let mime = match file.metadata.content_type {
+            Some(value) => Mime::from_str(&value).map_err(|_| HttpFileError::InvalidMimeType)?,
+            // Fallback to default mime type when none is provided
+            None => mime::APPLICATION_OCTET_STREAM,
+        };
+
+        let mime = resolve_upload_mime(&db, &name, mime, sniff).await?;
+
+        check_upload_rules(&db, &name, &mime, file.contents.len() as i64).await?;
+
+        uploads.push(UploadFile {
+            fixed_id: None,
+            parent_id: None,
+            folder_id: folder.id,
+            document_box: folder.document_box.clone(),
+            name,
+            mime,
+            file_bytes: file.contents,
+            created_by: created_by.as_ref().map(|value| value.id.to_string()),
+            file_key: None,
+            processing_config: processing_config.clone(),
+            task_id: None,
+        });
+    }
+
+    // Handle synchronous request waiting for the uploads to complete before responding
+    if !req.asynchronous.unwrap_or_default() {
+        let result = upload_files(&db, &search, &storage, &processing, &events, uploads).await;
+
+        // Recreate the search index if it was deleted out-of-band
+        if let Err(UploadFileError::CreateIndex(_)) = &result {
+            auto_heal.heal(&db, &search, &storage, &scope).await;
+        }
+
+        let data = result.map_err(|error| {
+            tracing::error!(?error, "failed to upload files");
+            HttpFileError::UploadFileError(error)
+        })?;
+        let files = data
+            .into_iter()
+            .map(|data| map_uploaded_file(data, &created_by))
+            .collect();
+        return Ok(Json(FilesUploadResponse::Sync(UploadedFilesResponse {
+            files,
+        })));
+    }
+
+    let span = tracing::Span::current();
+
+    // Spawn background task
+    let (task_id, created_at) = background_task_with(db.clone(), scope.clone(), |task| {
+        // Include the task in the processing events
+        for upload in &mut uploads {
+            upload.task_id = Some(task.id);
+        }
+
+        async move {
+            let result = upload_files(&db, &search, &storage, &processing, &events, uploads).await;
+
+            // Recreate the search index if it was deleted out-of-band
+            if let Err(UploadFileError::CreateIndex(_)) = &result {
+                auto_heal.heal(&db, &search, &storage, &scope).await;
+            }
+
+            let result = result
+                .map_err(|error| {
+                    tracing::error!(?error, "failed to upload files");
+                    DynHttpError::from(HttpFileError::UploadFileError(error))
+                })
+                // Map the response into the desired format
+                .map(|data| UploadedFilesResponse {
+                    files: data
+                        .into_iter()
+                        .map(|data| map_uploaded_file(data, &created_by))
+                        .collect(),
+                })
+                // Serialize the response for storage
+                .and_then(|value| {
+                    serde_json::to_value(&value).map_err(|error| {
+                        tracing::error!(?error, "failed to serialize upload task outcome");
+                        DynHttpError::from(HttpCommonError::ServerError)
+                    })
+                });
+
+            match result {
+                Ok(value) => (TaskStatus::Completed, value),
+                Err(error) => (
+                    TaskStatus::Failed,
+                    serde_json::json!({ "error": error.to_string() }),
+                ),
+            }
+        }
+        // Ensure the logging span is passed onto the background task so that
+        // logging context continues
+        .instrument(span)
+    })
+    .await
+    .map_err(|error| {
+        tracing::error!(?error, "failed to create background task");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(FilesUploadResponse::Async(UploadTaskResponse {
+        task_id,
+        created_at,
+    })))
+}
+
 /// Map a [UploadedFileData] output from the core layer into the [UploadedFile]
 /// HTTP response format
 fn map_uploaded_file(data: UploadedFileData, created_by: &Option<User>) -> UploadedFile {
@@ -243,6 +475,7 @@ fn map_uploaded_file(data: UploadedFileData, created_by: &Option<User>) -> Uploa
         file,
         generated,
         additional_files,
+        ..
     } = data;
 
     UploadedFile {
@@ -269,6 +502,9 @@ fn map_uploaded_file(data: UploadedFileData, created_by: &Option<User>) -> Uploa
 ///
 /// Use the task ID from the response to poll the file processing
 /// progress.
+///
+/// Files that violate the tenant upload rules are rejected with the violated
+/// rule provided in the error details
 #[utoipa::path(
     post,
     operation_id = "file_create_presigned",
@@ -289,12 +525,13 @@ fn map_uploaded_file(data: UploadedFileData, created_by: &Option<User>) -> Uploa
 #[tracing::instrument(skip_all, fields(%scope, ?req))]
 pub async fn create_presigned(
     action_user: ActionUser,
-    Extension(MaxFileSizeBytes(max_file_size)): Extension<MaxFileSizeBytes>,
+    Extension(max_file_size): Extension<MaxFileSizeBytes>,
     TenantDb(db): TenantDb,
     TenantStorage(storage): TenantStorage,
     Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
     Garde(Json(req)): Garde<Json<CreatePresignedRequest>>,
 ) -> Result<(StatusCode, Json<PresignedUploadResponse>), DynHttpError> {
+    let max_file_size = max_file_size.get();
     if req.size > max_file_size {
         return Err(HttpFileError::FileTooLarge(req.size, max_file_size).into());
     }
@@ -310,22 +547,12 @@ pub async fn create_presigned(
     // Update stored editing user data
     let created_by = action_user.store_user(&db).await?;
 
-    let mut mime = req.mime.unwrap_or(mime::APPLICATION_OCTET_STREAM);
+    let mime = req.mime.unwrap_or(mime::APPLICATION_OCTET_STREAM);
 
-    // Attempt to guess the file mime type when application/octet-stream is specified
-    // (Likely from old browsers)
-    if mime == mime::APPLICATION_OCTET_STREAM
-        && req.disable_mime_sniffing.is_none_or(|value| !value)
-    {
-        let guessed_mime = get_file_name_ext(&req.name).and_then(|ext| {
-            let guesses = mime_guess::from_ext(&ext);
-            guesses.first()
-        });
+    let sniff = req.disable_mime_sniffing.is_none_or(|value| !value);
+    let mime = resolve_upload_mime(&db, &req.name, mime, sniff).await?;
 
-        if let Some(guessed_mime) = guessed_mime {
-            mime = guessed_mime
-        }
-    }
+    check_upload_rules(&db, &req.name, &mime, req.size as i64).await?;
 
     let response = create_presigned_upload(
         &db,
@@ -400,6 +627,7 @@ pub async fn get_presigned(
         PresignedTaskStatus::Failed { error } => {
             return Ok(Json(PresignedStatusResponse::Failed { error }));
         }
+        PresignedTaskStatus::Expired => return Ok(Json(PresignedStatusResponse::Expired)),
     };
 
     let file = File::find_with_extra(&db, &scope, file_id)
@@ -430,7 +658,8 @@ pub async fn get_presigned(
     tag = FILE_TAG,
     path = "/box/{scope}/file/{file_id}",
     responses(
-        (status = 200, description = "Obtained file successfully", body = FileResponse),
+        (status = 200, description = "Obtained file successfully", body = FileResponse,
+            headers(("etag" = String, description = "Current version of the file"))),
         (status = 404, description = "File not found", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
@@ -444,8 +673,18 @@ pub async fn get_presigned(
 pub async fn get(
     TenantDb(db): TenantDb,
     Path((scope, file_id)): Path<(DocumentBoxScope, FileId)>,
-) -> HttpResult<FileResponse> {
+) -> Result<(ETagHeader, Json<FileResponse>), DynHttpError> {
     let DocumentBoxScope(scope) = scope;
+
+    // Version is queried before the file so the ETag is never newer than the data
+    let version = File::version(&db, file_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query file version");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFileError::UnknownFile)?;
+
     let file = File::find_with_extra(&db, &scope, file_id)
         .await
         .map_err(|error| {
@@ -461,7 +700,46 @@ pub async fn get(
             HttpCommonError::ServerError
         })?;
 
-    Ok(Json(FileResponse { file, generated }))
+    let pdf_metadata = FilePdfMetadata::find(&db, file_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query pdf metadata");
+            HttpCommonError::ServerError
+        })?
+        .map(Into::into);
+
+    let pii_analysis = FilePiiAnalysis::find(&db, file_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query pii analysis");
+            HttpCommonError::ServerError
+        })?
+        .map(Into::into);
+
+    let text_stats = FileTextStats::find(&db, file_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query text stats");
+            HttpCommonError::ServerError
+        })?
+        .map(Into::into);
+
+    let access_stats = FileAccessStats::find(&db, file_id).await.map_err(|error| {
+        tracing::error!(?error, "failed to query file access stats");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok((
+        version_etag(version),
+        Json(FileResponse {
+            file,
+            generated,
+            pdf_metadata,
+            pii_analysis,
+            text_stats,
+            access_stats,
+        }),
+    ))
 }
 
 /// Get file children
@@ -513,27 +791,34 @@ pub async fn get_children(
 /// Get file edit history
 ///
 /// Gets the edit history for the provided file
+///
+/// Entries can be filtered by type, user and date range and are
+/// paginated using the offset and size query parameters or the
+/// cursor from a previous page
 #[utoipa::path(
     get,
     operation_id = "file_edit_history",
     tag = FILE_TAG,
     path = "/box/{scope}/file/{file_id}/edit-history",
     responses(
-        (status = 200, description = "Obtained edit-history successfully", body = [EditHistory]),
+        (status = 200, description = "Obtained edit-history successfully", body = Page<EditHistory>),
+        (status = 400, description = "Invalid page cursor", body = HttpErrorResponse),
         (status = 404, description = "File not found", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
     params(
         ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
         ("file_id" = Uuid, Path, description = "ID of the file to query"),
-        TenantParams
+        TenantParams,
+        EditHistoryQuery
     )
 )]
-#[tracing::instrument(skip_all, fields(%scope, %file_id))]
+#[tracing::instrument(skip_all, fields(%scope, %file_id, ?query))]
 pub async fn get_edit_history(
     TenantDb(db): TenantDb,
     Path((scope, file_id)): Path<(DocumentBoxScope, FileId)>,
-) -> HttpResult<Vec<EditHistory>> {
+    Query(query): Query<EditHistoryQuery>,
+) -> HttpResult<Page<EditHistory>> {
     let DocumentBoxScope(scope) = scope;
 
     _ = File::find(&db, &scope, file_id)
@@ -544,44 +829,56 @@ pub async fn get_edit_history(
         })?
         .ok_or(HttpFileError::UnknownFile)?;
 
-    let edit_history = EditHistory::all_by_file(&db, file_id)
-        .await
-        .map_err(|error| {
-            tracing::error!(?error, "failed to query file history");
-            HttpCommonError::ServerError
-        })?;
+    let offset = page_offset(query.cursor.as_deref(), query.offset)?;
+    let limit = query.size.map(u64::from).unwrap_or(DEFAULT_PAGE_SIZE);
+    let filter = query.filter();
+
+    let total_future = EditHistory::total_by_file(&db, file_id, &filter);
+    let history_future = EditHistory::query_by_file(&db, file_id, &filter, offset, limit);
+    let (total, history) = try_join!(total_future, history_future).map_err(|error| {
+        tracing::error!(?error, "failed to query file history");
+        HttpCommonError::ServerError
+    })?;
 
-    Ok(Json(edit_history))
+    Ok(Json(Page::from_offset(history, total, offset)))
 }
 
 /// Update file
 ///
 /// Updates a file, can be a name change, a folder move, or both
+///
+/// Providing the ETag from a previous request in the If-Match header
+/// rejects the update if the file was modified since
 #[utoipa::path(
     put,
     operation_id = "file_update",
     tag = FILE_TAG,
     path = "/box/{scope}/file/{file_id}",
     responses(
-        (status = 200, description = "Obtained edit-history successfully", body = [EditHistory]),
+        (status = 200, description = "Updated file successfully",
+            headers(("etag" = String, description = "New version of the file"))),
+        (status = 400, description = "Invalid If-Match header", body = HttpErrorResponse),
         (status = 404, description = "File not found", body = HttpErrorResponse),
+        (status = 409, description = "File was modified since the If-Match version", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
     params(
         ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
         ("file_id" = Uuid, Path, description = "ID of the file to query"),
         TenantParams,
-        UserParams
+        UserParams,
+        IfMatchParams
     )
 )]
 #[tracing::instrument(skip_all, fields(%scope, %file_id, ?req))]
 pub async fn update(
     action_user: ActionUser,
+    IfMatch(expected_version): IfMatch,
     TenantDb(db): TenantDb,
     TenantSearch(search): TenantSearch,
     Path((scope, file_id)): Path<(DocumentBoxScope, FileId)>,
     Garde(Json(req)): Garde<Json<UpdateFileRequest>>,
-) -> HttpStatusResult {
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
     let DocumentBoxScope(scope) = scope;
 
     let file = File::find(&db, &scope, file_id)
@@ -600,47 +897,58 @@ pub async fn update(
         folder_id: req.folder_id,
         name: req.name,
         pinned: req.pinned,
+        expected_version,
     };
 
-    docbox_core::files::update_file::update_file(&db, &search, &scope, file, user_id, update)
-        .await
-        .map_err(|error| match error {
-            UpdateFileError::UnknownTargetFolder => {
-                DynHttpError::from(HttpFolderError::UnknownTargetFolder)
-            }
-            _ => DynHttpError::from(HttpCommonError::ServerError),
-        })?;
+    let version =
+        docbox_core::files::update_file::update_file(&db, &search, &scope, file, user_id, update)
+            .await
+            .map_err(|error| match error {
+                UpdateFileError::UnknownTargetFolder => {
+                    DynHttpError::from(HttpFolderError::UnknownTargetFolder)
+                }
+                UpdateFileError::VersionConflict { .. } => {
+                    DynHttpError::from(HttpFileError::VersionConflict)
+                }
+                _ => DynHttpError::from(HttpCommonError::ServerError),
+            })?;
 
-    Ok(StatusCode::OK)
+    Ok((StatusCode::OK, version_etag(version)))
 }
 
-/// Get file raw
+/// Move file to document box
 ///
-/// Requests the raw contents of a file, this is used for downloading
-/// the file or viewing it in the browser or simply requesting its content
+/// Moves a file into a folder within another document box of the tenant,
+/// child files of the file (i.e email attachments) are moved along with it
 #[utoipa::path(
-    get,
-    operation_id = "file_get_raw",
+    post,
+    operation_id = "file_move_to_scope",
     tag = FILE_TAG,
-    path = "/box/{scope}/file/{file_id}/raw",
+    path = "/box/{scope}/file/{file_id}/move-scope",
     responses(
-        (status = 200, description = "Obtained raw file successfully", content_type = "application/octet-stream", body = BinaryResponse),
-        (status = 404, description = "File not found", body = HttpErrorResponse),
+        (status = 200, description = "Moved file successfully", body = File),
+        (status = 400, description = "File is already within the target document box", body = HttpErrorResponse),
+        (status = 404, description = "File, target document box or target folder not found", body = HttpErrorResponse),
+        (status = 423, description = "Source or target document box is archived", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
     params(
         ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
-        ("file_id" = Uuid, Path, description = "ID of the file to query"),
-        TenantParams
+        ("file_id" = Uuid, Path, description = "ID of the file to move"),
+        TenantParams,
+        UserParams
     )
 )]
-#[tracing::instrument(skip_all, fields(%scope, %file_id, ?query))]
-pub async fn get_raw(
+#[tracing::instrument(skip_all, fields(%scope, %file_id, ?req))]
+pub async fn move_to_scope(
+    action_user: ActionUser,
     TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
     TenantStorage(storage): TenantStorage,
+    TenantEvents(events): TenantEvents,
     Path((scope, file_id)): Path<(DocumentBoxScope, FileId)>,
-    Query(query): Query<RawFileQuery>,
-) -> Result<Response<Body>, DynHttpError> {
+    Garde(Json(req)): Garde<Json<MoveToScopeRequest>>,
+) -> HttpResult<File> {
     let DocumentBoxScope(scope) = scope;
 
     let file = File::find(&db, &scope, file_id)
@@ -651,69 +959,387 @@ pub async fn get_raw(
         })?
         .ok_or(HttpFileError::UnknownFile)?;
 
-    let byte_stream = storage.get_file(&file.file_key).await.map_err(|error| {
-        tracing::error!(?error, "failed to get file from storage");
-        HttpCommonError::ServerError
-    })?;
-
-    let body = axum::body::Body::from_stream(byte_stream);
+    // Update stored editing user data
+    let user = action_user.store_user(&db).await?;
+    let user_id = user.as_ref().map(|value| value.id.to_string());
 
-    let ty = if query.download {
-        "attachment"
-    } else {
-        "inline"
+    let target = MoveToScope {
+        document_box: req.document_box,
+        folder_id: req.folder_id,
     };
 
-    let disposition = format!("{};filename=\"{}\"", ty, file.name);
-
-    let csp = match mime::Mime::from_str(&file.mime) {
-        // Images are served with a strict image only content security policy
-        Ok(mime) if mime.type_() == mime::IMAGE => {
-            "default-src 'none'; style-src 'self' 'unsafe-inline'; img-src 'self' data:;"
+    let file = move_file_to_scope(
+        &db, &search, &storage, &events, &scope, file, user_id, target,
+    )
+    .await
+    .map_err(|error| match error {
+        MoveToScopeError::SameScope => DynHttpError::from(HttpDocumentBoxError::SameDocumentBox),
+        MoveToScopeError::UnknownTargetDocumentBox => {
+            DynHttpError::from(HttpDocumentBoxError::UnknownDocumentBox)
         }
-        // Default policy
-        _ => "script-src 'none'; object-src 'none'; base-uri 'none'; form-action 'none'",
-    };
+        MoveToScopeError::TargetDocumentBoxArchived
+        | MoveToScopeError::SourceDocumentBoxArchived => {
+            DynHttpError::from(HttpDocumentBoxError::DocumentBoxArchived)
+        }
+        MoveToScopeError::UnknownTargetFolder => {
+            DynHttpError::from(HttpFolderError::UnknownTargetFolder)
+        }
+        _ => DynHttpError::from(HttpCommonError::ServerError),
+    })?;
 
-    Ok(Response::builder()
-        .header(header::CONTENT_TYPE, file.mime)
-        .header(header::CONTENT_SECURITY_POLICY, csp)
-        .header(
-            header::CONTENT_DISPOSITION,
-            HeaderValue::from_str(&disposition)?,
-        )
-        .body(body)?)
+    Ok(Json(file))
 }
 
-/// Get file raw presigned
+/// Pin file
 ///
-/// Requests the raw contents of a file as a presigned URL, used for
-/// letting the client directly download a file from AWS instead of
-/// downloading through the server
+/// Pins a file, pinned items are included in the pinned items
+/// of the document box. Pinning an already pinned file keeps the
+/// original pin time
+///
+/// Providing the ETag from a previous request in the If-Match header
+/// rejects the change if the file was modified since
 #[utoipa::path(
-    post,
-    operation_id = "file_get_raw_presigned",
+    put,
+    operation_id = "file_pin",
     tag = FILE_TAG,
-    path = "/box/{scope}/file/{file_id}/raw-presigned",
+    path = "/box/{scope}/file/{file_id}/pin",
     responses(
-        (status = 200, description = "Obtained raw file successfully"),
+        (status = 200, description = "Pinned file successfully",
+            headers(("etag" = String, description = "New version of the file"))),
+        (status = 400, description = "Invalid If-Match header", body = HttpErrorResponse),
         (status = 404, description = "File not found", body = HttpErrorResponse),
+        (status = 409, description = "File was modified since the If-Match version", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
     params(
         ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
-        ("file_id" = Uuid, Path, description = "ID of the file to download"),
-        TenantParams
+        ("file_id" = Uuid, Path, description = "ID of the file to pin"),
+        TenantParams,
+        UserParams,
+        IfMatchParams
     )
 )]
-#[tracing::instrument(skip_all, fields(%scope, %file_id, ?req))]
-pub async fn get_raw_presigned(
+#[tracing::instrument(skip_all, fields(%scope, %file_id))]
+pub async fn pin(
+    action_user: ActionUser,
+    IfMatch(expected_version): IfMatch,
     TenantDb(db): TenantDb,
-    TenantStorage(storage): TenantStorage,
+    TenantSearch(search): TenantSearch,
     Path((scope, file_id)): Path<(DocumentBoxScope, FileId)>,
-    Json(req): Json<GetPresignedRequest>,
-) -> HttpResult<PresignedDownloadResponse> {
-    let DocumentBoxScope(scope) = scope;
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
+    set_pinned(
+        action_user,
+        expected_version,
+        &db,
+        &search,
+        scope,
+        file_id,
+        true,
+    )
+    .await
+}
+
+/// Unpin file
+///
+/// Unpins a file, removing it from the pinned items of the document box
+///
+/// Providing the ETag from a previous request in the If-Match header
+/// rejects the change if the file was modified since
+#[utoipa::path(
+    delete,
+    operation_id = "file_unpin",
+    tag = FILE_TAG,
+    path = "/box/{scope}/file/{file_id}/pin",
+    responses(
+        (status = 200, description = "Unpinned file successfully",
+            headers(("etag" = String, description = "New version of the file"))),
+        (status = 400, description = "Invalid If-Match header", body = HttpErrorResponse),
+        (status = 404, description = "File not found", body = HttpErrorResponse),
+        (status = 409, description = "File was modified since the If-Match version", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
+        ("file_id" = Uuid, Path, description = "ID of the file to unpin"),
+        TenantParams,
+        UserParams,
+        IfMatchParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %file_id))]
+pub async fn unpin(
+    action_user: ActionUser,
+    IfMatch(expected_version): IfMatch,
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    Path((scope, file_id)): Path<(DocumentBoxScope, FileId)>,
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
+    set_pinned(
+        action_user,
+        expected_version,
+        &db,
+        &search,
+        scope,
+        file_id,
+        false,
+    )
+    .await
+}
+
+/// Set the pinned state of a file, the change is recorded in the
+/// edit history of the file
+async fn set_pinned(
+    action_user: ActionUser,
+    expected_version: Option<i64>,
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    DocumentBoxScope(scope): DocumentBoxScope,
+    file_id: FileId,
+    pinned: bool,
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
+    let file = File::find(db, &scope, file_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query file");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFileError::UnknownFile)?;
+
+    // Update stored editing user data
+    let user = action_user.store_user(db).await?;
+    let user_id = user.as_ref().map(|value| value.id.to_string());
+
+    let update = UpdateFile {
+        pinned: Some(pinned),
+        expected_version,
+        ..Default::default()
+    };
+
+    let version =
+        docbox_core::files::update_file::update_file(db, search, &scope, file, user_id, update)
+            .await
+            .map_err(|error| match error {
+                UpdateFileError::VersionConflict { .. } => {
+                    DynHttpError::from(HttpFileError::VersionConflict)
+                }
+                _ => DynHttpError::from(HttpCommonError::ServerError),
+            })?;
+
+    Ok((StatusCode::OK, version_etag(version)))
+}
+
+/// Revert file edit history entry
+///
+/// Reverts the change recorded by an edit history entry for the file by
+/// applying the inverse change (i.e renaming back or moving back). The revert
+/// is recorded in the edit history as a new change
+///
+/// Providing the ETag from a previous request in the If-Match header
+/// rejects the revert if the file was modified since
+#[utoipa::path(
+    post,
+    operation_id = "file_revert_edit_history",
+    tag = FILE_TAG,
+    path = "/box/{scope}/file/{file_id}/edit-history/{entry_id}/revert",
+    responses(
+        (status = 200, description = "Reverted the change successfully",
+            headers(("etag" = String, description = "New version of the file"))),
+        (status = 400, description = "Edit history entry cannot be reverted or invalid If-Match header", body = HttpErrorResponse),
+        (status = 404, description = "File or edit history entry not found", body = HttpErrorResponse),
+        (status = 409, description = "File was modified since the If-Match version", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
+        ("file_id" = Uuid, Path, description = "ID of the file to revert"),
+        ("entry_id" = Uuid, Path, description = "ID of the edit history entry to revert"),
+        TenantParams,
+        UserParams,
+        IfMatchParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %file_id, %entry_id))]
+pub async fn revert_edit_history(
+    action_user: ActionUser,
+    IfMatch(expected_version): IfMatch,
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    Path((scope, file_id, entry_id)): Path<(DocumentBoxScope, FileId, EditHistoryId)>,
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
+    let DocumentBoxScope(scope) = scope;
+
+    let file = File::find(&db, &scope, file_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query file");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFileError::UnknownFile)?;
+
+    let entry = EditHistory::find_by_file(&db, file_id, entry_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query file history entry");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpEditHistoryError::UnknownEntry)?;
+
+    let update = UpdateFile {
+        expected_version,
+        ..UpdateFile::revert(&entry.metadata).ok_or(HttpEditHistoryError::CannotRevert)?
+    };
+
+    // Update stored editing user data
+    let user = action_user.store_user(&db).await?;
+    let user_id = user.as_ref().map(|value| value.id.to_string());
+
+    let version =
+        docbox_core::files::update_file::update_file(&db, &search, &scope, file, user_id, update)
+            .await
+            .map_err(|error| match error {
+                UpdateFileError::UnknownTargetFolder => {
+                    DynHttpError::from(HttpFolderError::UnknownTargetFolder)
+                }
+                UpdateFileError::VersionConflict { .. } => {
+                    DynHttpError::from(HttpFileError::VersionConflict)
+                }
+                _ => DynHttpError::from(HttpCommonError::ServerError),
+            })?;
+
+    Ok((StatusCode::OK, version_etag(version)))
+}
+
+/// Get file raw
+///
+/// Requests the raw contents of a file, this is used for downloading
+/// the file or viewing it in the browser or simply requesting its content.
+///
+/// HTML and SVG files are served according to the active content policy
+/// of the tenant, by default they are sandboxed so scripts cannot run
+#[utoipa::path(
+    get,
+    operation_id = "file_get_raw",
+    tag = FILE_TAG,
+    path = "/box/{scope}/file/{file_id}/raw",
+    responses(
+        (status = 200, description = "Obtained raw file successfully", content_type = "application/octet-stream", body = BinaryResponse),
+        (status = 404, description = "File not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
+        ("file_id" = Uuid, Path, description = "ID of the file to query"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %file_id, ?query))]
+pub async fn get_raw(
+    Extension(tenant): Extension<Tenant>,
+    TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
+    TenantFileAccess(file_access): TenantFileAccess,
+    Path((scope, file_id)): Path<(DocumentBoxScope, FileId)>,
+    Query(query): Query<RawFileQuery>,
+) -> Result<Response<Body>, DynHttpError> {
+    let DocumentBoxScope(scope) = scope;
+
+    let file = File::find(&db, &scope, file_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query file");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFileError::UnknownFile)?;
+
+    let byte_stream = storage.get_file(&file.file_key).await.map_err(|error| {
+        tracing::error!(?error, "failed to get file from storage");
+        HttpCommonError::ServerError
+    })?;
+
+    let body = axum::body::Body::from_stream(byte_stream);
+
+    let access_kind = if query.download {
+        FileAccessKind::Download
+    } else {
+        FileAccessKind::Preview
+    };
+
+    file_access.record(file.id, access_kind);
+
+    // The default content security policy for raw routes is applied
+    // by the security headers middleware when not set here
+    let (content_type, csp, download) = match active_content_headers(
+        tenant.active_content_policy,
+        &file.mime,
+        query.download,
+    ) {
+        // HTML and SVG files are served under a sandboxed policy
+        Some(active) => (
+            active.content_type,
+            Some(ACTIVE_CONTENT_CSP),
+            active.download,
+        ),
+        None => {
+            let csp = match mime::Mime::from_str(&file.mime) {
+                // Images are served with a strict image only content security policy
+                Ok(mime) if mime.type_() == mime::IMAGE => Some(
+                    "default-src 'none'; style-src 'self' 'unsafe-inline'; img-src 'self' data:;",
+                ),
+                _ => None,
+            };
+
+            (file.mime, csp, query.download)
+        }
+    };
+
+    let disposition = create_content_disposition(download, &file.name);
+
+    let mut response = Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(
+            header::CONTENT_DISPOSITION,
+            HeaderValue::from_str(&disposition)?,
+        );
+
+    if let Some(csp) = csp {
+        response = response.header(header::CONTENT_SECURITY_POLICY, csp);
+    }
+
+    Ok(response.body(body)?)
+}
+
+/// Get file raw presigned
+///
+/// Requests the raw contents of a file as a presigned URL, used for
+/// letting the client directly download a file from AWS instead of
+/// downloading through the server
+#[utoipa::path(
+    post,
+    operation_id = "file_get_raw_presigned",
+    tag = FILE_TAG,
+    path = "/box/{scope}/file/{file_id}/raw-presigned",
+    responses(
+        (status = 200, description = "Obtained raw file successfully"),
+        (status = 400, description = "Presigned downloads are not available for encrypted storage", body = HttpErrorResponse),
+        (status = 404, description = "File not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
+        ("file_id" = Uuid, Path, description = "ID of the file to download"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %file_id, ?req))]
+pub async fn get_raw_presigned(
+    Extension(tenant): Extension<Tenant>,
+    TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
+    TenantFileAccess(file_access): TenantFileAccess,
+    Path((scope, file_id)): Path<(DocumentBoxScope, FileId)>,
+    Json(req): Json<GetPresignedRequest>,
+) -> HttpResult<PresignedDownloadResponse> {
+    let DocumentBoxScope(scope) = scope;
 
     let file = File::find(&db, &scope, file_id)
         .await
@@ -726,14 +1352,27 @@ pub async fn get_raw_presigned(
     let expires_at = req.expires_at.unwrap_or(900);
     let expires_at = Duration::from_secs(expires_at as u64);
 
+    if storage.is_encrypted() {
+        return Err(HttpFileError::PresignedDownloadEncrypted.into());
+    }
+
+    let options = presigned_download_options(
+        tenant.active_content_policy,
+        &file.mime,
+        &file.name,
+        req.download,
+    );
+
     let (signed_request, expires_at) = storage
-        .create_presigned_download(&file.file_key, expires_at)
+        .create_presigned_download(&file.file_key, expires_at, options)
         .await
         .map_err(|error| {
             tracing::error!(?error, "failed to created file presigned download");
             HttpCommonError::ServerError
         })?;
 
+    file_access.record(file.id, FileAccessKind::Download);
+
     Ok(Json(PresignedDownloadResponse {
         method: signed_request.method().to_string(),
         uri: signed_request.uri().to_string(),
@@ -745,6 +1384,126 @@ pub async fn get_raw_presigned(
     }))
 }
 
+/// Send file by email
+///
+/// Sends the file by email to the provided recipients. The file is attached
+/// to the email unless it is larger than the server max attachment size or a
+/// link was requested, in which case a link to download the file is sent
+///
+/// Sent emails are recorded and can be listed using the GET endpoint
+#[utoipa::path(
+    post,
+    operation_id = "file_email",
+    tag = FILE_TAG,
+    path = "/box/{scope}/file/{file_id}/email",
+    request_body = EmailFileRequest,
+    responses(
+        (status = 201, description = "Sent file successfully", body = FileEmail),
+        (status = 400, description = "Invalid recipient or link unavailable", body = HttpErrorResponse),
+        (status = 404, description = "File not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse),
+        (status = 501, description = "Sending files by email is not enabled", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
+        ("file_id" = Uuid, Path, description = "ID of the file to send"),
+        TenantParams,
+        UserParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %file_id))]
+pub async fn email(
+    action_user: ActionUser,
+    maybe_mailer: Option<Extension<Mailer>>,
+    TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
+    Path((scope, file_id)): Path<(DocumentBoxScope, FileId)>,
+    Garde(Json(req)): Garde<Json<EmailFileRequest>>,
+) -> Result<(StatusCode, Json<FileEmail>), DynHttpError> {
+    let Extension(mailer) = maybe_mailer.ok_or(HttpFileError::EmailNotEnabled)?;
+    let DocumentBoxScope(scope) = scope;
+
+    let file = File::find(&db, &scope, file_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query file");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFileError::UnknownFile)?;
+
+    let sender = action_user.store_user(&db).await?;
+
+    let file_email = email_file(
+        &db,
+        &storage,
+        &mailer,
+        &file,
+        EmailFile {
+            recipients: req.recipients,
+            message: req.message,
+            sender,
+            as_link: req.as_link,
+        },
+    )
+    .await
+    .map_err(|error| match error {
+        EmailFileError::InvalidRecipient(recipient) => {
+            DynHttpError::from(HttpFileError::InvalidEmailRecipient(recipient))
+        }
+        EmailFileError::LinkUnavailable => DynHttpError::from(HttpFileError::EmailLinkUnavailable),
+        error => {
+            tracing::error!(?error, "failed to send file by email");
+            DynHttpError::from(HttpCommonError::ServerError)
+        }
+    })?;
+
+    Ok((StatusCode::CREATED, Json(file_email)))
+}
+
+/// Get file emails
+///
+/// Get the emails the file was sent in, most recent first
+#[utoipa::path(
+    get,
+    operation_id = "file_get_emails",
+    tag = FILE_TAG,
+    path = "/box/{scope}/file/{file_id}/email",
+    responses(
+        (status = 200, description = "Obtained file emails successfully", body = [FileEmail]),
+        (status = 404, description = "File not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
+        ("file_id" = Uuid, Path, description = "ID of the file to query"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %file_id))]
+pub async fn get_emails(
+    TenantDb(db): TenantDb,
+    Path((scope, file_id)): Path<(DocumentBoxScope, FileId)>,
+) -> HttpResult<Vec<FileEmail>> {
+    let DocumentBoxScope(scope) = scope;
+
+    let file = File::find(&db, &scope, file_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query file");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFileError::UnknownFile)?;
+
+    let emails = FileEmail::find_by_file(&db, file.id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query file emails");
+            HttpCommonError::ServerError
+        })?;
+
+    Ok(Json(emails))
+}
+
 /// Get file raw named
 ///
 /// Requests the raw contents of a file, this is used for downloading
@@ -773,12 +1532,80 @@ pub async fn get_raw_presigned(
 )]
 #[tracing::instrument(skip_all, fields(%scope, %file_id, ?query))]
 pub async fn get_raw_named(
+    tenant: Extension<Tenant>,
     db: TenantDb,
     storage: TenantStorage,
+    file_access: TenantFileAccess,
     Path((scope, file_id, _tail)): Path<(DocumentBoxScope, FileId, String)>,
     query: Query<RawFileQuery>,
 ) -> Result<Response<Body>, DynHttpError> {
-    get_raw(db, storage, Path((scope, file_id)), query).await
+    get_raw(
+        tenant,
+        db,
+        storage,
+        file_access,
+        Path((scope, file_id)),
+        query,
+    )
+    .await
+}
+
+/// Get file preview
+///
+/// Requests a sanitized HTML rendering of the file contents for quickly
+/// previewing documents, spreadsheets and emails in the browser without
+/// needing a PDF viewer. The preview is rendered from the extracted HTML
+/// or text content of the file
+#[utoipa::path(
+    get,
+    operation_id = "file_get_preview",
+    tag = FILE_TAG,
+    path = "/box/{scope}/file/{file_id}/preview",
+    responses(
+        (status = 200, description = "Obtained file preview successfully", content_type = "text/html", body = String),
+        (status = 404, description = "File not found or file has no previewable content", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
+        ("file_id" = Uuid, Path, description = "ID of the file to preview"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %file_id))]
+pub async fn get_preview(
+    TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
+    TenantFileAccess(file_access): TenantFileAccess,
+    Path((scope, file_id)): Path<(DocumentBoxScope, FileId)>,
+) -> Result<Response<Body>, DynHttpError> {
+    let DocumentBoxScope(scope) = scope;
+
+    let file = File::find(&db, &scope, file_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query file");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFileError::UnknownFile)?;
+
+    let preview = create_file_preview(&db, &storage, &scope, &file)
+        .await
+        .map_err(|error| match error {
+            FilePreviewError::TooLarge => DynHttpError::from(HttpFileError::PreviewTooLarge),
+            error => {
+                tracing::error!(?error, "failed to create file preview");
+                DynHttpError::from(HttpCommonError::ServerError)
+            }
+        })?
+        .ok_or(HttpFileError::NoPreview)?;
+
+    file_access.record(file.id, FileAccessKind::Preview);
+
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .header(header::CONTENT_DISPOSITION, "inline")
+        .body(Body::from(preview))?)
 }
 
 /// Search
@@ -822,9 +1649,14 @@ pub async fn search(
     let result = search
         .search_index_file(&scope, file_id, req)
         .await
-        .map_err(|error| {
-            tracing::error!(?error, "failed to search document box");
-            HttpCommonError::ServerError
+        .map_err(|error| match error {
+            SearchError::Validation(error) => {
+                DynHttpError::from(HttpSearchError::InvalidRequest(error))
+            }
+            error => {
+                tracing::error!(?error, "failed to search document box");
+                DynHttpError::from(HttpCommonError::ServerError)
+            }
         })?;
 
     Ok(Json(FileSearchResultResponse {
@@ -943,12 +1775,17 @@ pub async fn get_generated(
 )]
 #[tracing::instrument(skip_all, fields(%scope, %file_id, %generated_type))]
 pub async fn get_generated_raw(
+    Extension(tenant): Extension<Tenant>,
     TenantDb(db): TenantDb,
     TenantStorage(storage): TenantStorage,
+    TenantFileAccess(file_access): TenantFileAccess,
     Path((scope, file_id, generated_type)): Path<(DocumentBoxScope, FileId, GeneratedFileType)>,
+    Query(query): Query<RawFileQuery>,
 ) -> Result<Response<Body>, DynHttpError> {
     let DocumentBoxScope(scope) = scope;
 
+    let source_file = find_source_file(&db, &scope, file_id).await?;
+
     let file = GeneratedFile::find(&db, &scope, file_id, generated_type)
         .await
         .map_err(|error| {
@@ -957,6 +1794,71 @@ pub async fn get_generated_raw(
         })?
         .ok_or(HttpFileError::NoMatchingGenerated)?;
 
+    // Viewing the PDF conversion of a file counts as previewing the file
+    if generated_type == GeneratedFileType::Pdf {
+        file_access.record(file_id, FileAccessKind::Preview);
+    }
+
+    generated_file_response(
+        &storage,
+        tenant.active_content_policy,
+        &source_file,
+        file,
+        query.download,
+    )
+    .await
+}
+
+/// Finds the file that generated files are being requested for
+async fn find_source_file(
+    db: &DbPool,
+    scope: &DocumentBoxScopeRaw,
+    file_id: FileId,
+) -> Result<File, DynHttpError> {
+    let file = File::find(db, scope, file_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query file");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFileError::UnknownFile)?;
+
+    Ok(file)
+}
+
+/// Creates the options for a presigned download of a file with the provided
+/// `mime` and `file_name`, applying the active content `policy` of the tenant.
+///
+/// Presigned downloads are served by the storage backend so the sandboxing
+/// content security policy cannot be applied, only the content type and
+/// disposition overrides of the policy take effect
+fn presigned_download_options(
+    policy: ActiveContentPolicy,
+    mime: &str,
+    file_name: &str,
+    download: bool,
+) -> PresignedDownloadOptions {
+    match active_content_headers(policy, mime, download) {
+        Some(active) => PresignedDownloadOptions {
+            content_disposition: Some(create_content_disposition(active.download, file_name)),
+            content_type: Some(active.content_type),
+        },
+        None => PresignedDownloadOptions {
+            content_disposition: Some(create_content_disposition(download, file_name)),
+            ..Default::default()
+        },
+    }
+}
+
+/// Creates a response streaming the contents of the generated `file`, the download
+/// file name is derived from the name of the `source_file`
+async fn generated_file_response(
+    storage: &StorageLayer,
+    policy: ActiveContentPolicy,
+    source_file: &File,
+    file: GeneratedFile,
+    download: bool,
+) -> Result<Response<Body>, DynHttpError> {
     let byte_stream = storage.get_file(&file.file_key).await.map_err(|error| {
         tracing::error!(?error, "failed to file from storage");
         HttpCommonError::ServerError
@@ -964,21 +1866,190 @@ pub async fn get_generated_raw(
 
     let body = axum::body::Body::from_stream(byte_stream);
 
-    let csp = match mime::Mime::from_str(&file.mime) {
-        // Images are served with a strict image only content security policy
-        Ok(mime) if mime.type_() == mime::IMAGE => "default-src 'none'; img-src 'self' data:;",
-        // Default policy
-        _ => "script-src 'none'; object-src 'none'; base-uri 'none'; form-action 'none'",
+    let mime = mime::Mime::from_str(&file.mime).ok();
+
+    // The default content security policy for generated routes is applied
+    // by the security headers middleware when not set here
+    let (content_type, csp, download) = match active_content_headers(policy, &file.mime, download) {
+        // Generated HTML content (i.e from emails) is served under a sandboxed policy
+        Some(active) => (
+            active.content_type,
+            Some(ACTIVE_CONTENT_CSP),
+            active.download,
+        ),
+        None => {
+            let csp = match mime.as_ref() {
+                // Images are served with a strict image only content security policy
+                Some(mime) if mime.type_() == mime::IMAGE => {
+                    Some("default-src 'none'; img-src 'self' data:;")
+                }
+                _ => None,
+            };
+
+            (file.mime.clone(), csp, download)
+        }
     };
 
-    Ok(Response::builder()
-        .header(header::CONTENT_TYPE, file.mime)
-        .header(header::CONTENT_SECURITY_POLICY, csp)
+    let file_name = create_generated_file_name(
+        &source_file.name,
+        file.ty,
+        file.page,
+        mime.as_ref().unwrap_or(&mime::APPLICATION_OCTET_STREAM),
+    );
+    let disposition = create_content_disposition(download, &file_name);
+
+    let mut response = Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
         .header(
             header::CONTENT_DISPOSITION,
-            HeaderValue::from_str("inline;filename=\"preview.pdf\"")?,
-        )
-        .body(body)?)
+            HeaderValue::from_str(&disposition)?,
+        );
+
+    if let Some(csp) = csp {
+        response = response.header(header::CONTENT_SECURITY_POLICY, csp);
+    }
+
+    Ok(response.body(body)?)
+}
+
+/// Get file page previews
+///
+/// Requests the preview images generated for the first pages of
+/// a file, the previews are ordered by page number. Files without
+/// page previews will return an empty list
+#[utoipa::path(
+    get,
+    operation_id = "file_get_page_previews",
+    tag = FILE_TAG,
+    path = "/box/{scope}/file/{file_id}/generated/previews",
+    responses(
+        (status = 200, description = "Obtained page previews successfully", body = FilePagePreviewsResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
+        ("file_id" = Uuid, Path, description = "ID of the file to query"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %file_id))]
+pub async fn get_page_previews(
+    TenantDb(db): TenantDb,
+    Path((scope, file_id)): Path<(DocumentBoxScope, FileId)>,
+) -> HttpResult<FilePagePreviewsResponse> {
+    let DocumentBoxScope(scope) = scope;
+
+    let previews = GeneratedFile::find_pages(&db, &scope, file_id, GeneratedFileType::PagePreview)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query page previews");
+            HttpCommonError::ServerError
+        })?;
+
+    Ok(Json(FilePagePreviewsResponse { previews }))
+}
+
+/// Get file page preview raw
+///
+/// Request the contents of the preview image for a specific
+/// page (Starting at 1) of a file
+#[utoipa::path(
+    get,
+    operation_id = "file_get_page_preview_raw",
+    tag = FILE_TAG,
+    path = "/box/{scope}/file/{file_id}/generated/previews/{page}/raw",
+    responses(
+        (status = 200, description = "Obtained raw file successfully", content_type = "image/jpeg", body = BinaryResponse),
+        (status = 404, description = "Page preview not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
+        ("file_id" = Uuid, Path, description = "ID of the file to query"),
+        ("page" = i32, Path, description = "Page number of the preview"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %file_id, %page))]
+pub async fn get_page_preview_raw(
+    Extension(tenant): Extension<Tenant>,
+    TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
+    Path((scope, file_id, page)): Path<(DocumentBoxScope, FileId, i32)>,
+    Query(query): Query<RawFileQuery>,
+) -> Result<Response<Body>, DynHttpError> {
+    let DocumentBoxScope(scope) = scope;
+
+    let source_file = find_source_file(&db, &scope, file_id).await?;
+
+    let file = GeneratedFile::find_page(&db, &scope, file_id, GeneratedFileType::PagePreview, page)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query page preview");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFileError::NoMatchingGenerated)?;
+
+    generated_file_response(
+        &storage,
+        tenant.active_content_policy,
+        &source_file,
+        file,
+        query.download,
+    )
+    .await
+}
+
+/// Get extracted table CSV raw
+///
+/// Request the CSV contents of a specific table (Starting at 1)
+/// extracted from a file. The JSON encoded form of all the tables
+/// is available through the `Tables` generated file
+#[utoipa::path(
+    get,
+    operation_id = "file_get_table_csv_raw",
+    tag = FILE_TAG,
+    path = "/box/{scope}/file/{file_id}/generated/tables/{table}/raw",
+    responses(
+        (status = 200, description = "Obtained raw file successfully", content_type = "text/csv", body = BinaryResponse),
+        (status = 404, description = "Extracted table not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
+        ("file_id" = Uuid, Path, description = "ID of the file to query"),
+        ("table" = i32, Path, description = "Number of the extracted table"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %file_id, %table))]
+pub async fn get_table_csv_raw(
+    Extension(tenant): Extension<Tenant>,
+    TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
+    Path((scope, file_id, table)): Path<(DocumentBoxScope, FileId, i32)>,
+    Query(query): Query<RawFileQuery>,
+) -> Result<Response<Body>, DynHttpError> {
+    let DocumentBoxScope(scope) = scope;
+
+    let source_file = find_source_file(&db, &scope, file_id).await?;
+
+    let file = GeneratedFile::find_page(&db, &scope, file_id, GeneratedFileType::TableCsv, table)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query extracted table");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFileError::NoMatchingGenerated)?;
+
+    generated_file_response(
+        &storage,
+        tenant.active_content_policy,
+        &source_file,
+        file,
+        query.download,
+    )
+    .await
 }
 
 /// Get generated file raw presigned
@@ -993,6 +2064,7 @@ pub async fn get_generated_raw(
     path = "/box/{scope}/file/{file_id}/generated/{type}/raw-presigned",
     responses(
         (status = 200, description = "Obtained raw file successfully"),
+        (status = 400, description = "Presigned downloads are not available for encrypted storage", body = HttpErrorResponse),
         (status = 404, description = "Generated file not found", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
@@ -1005,6 +2077,7 @@ pub async fn get_generated_raw(
 )]
 #[tracing::instrument(skip_all, fields(%scope, %file_id, %generated_type, ?req))]
 pub async fn get_generated_raw_presigned(
+    Extension(tenant): Extension<Tenant>,
     TenantDb(db): TenantDb,
     TenantStorage(storage): TenantStorage,
     Path((scope, file_id, generated_type)): Path<(DocumentBoxScope, FileId, GeneratedFileType)>,
@@ -1023,8 +2096,23 @@ pub async fn get_generated_raw_presigned(
     let expires_at = req.expires_at.unwrap_or(900);
     let expires_at = Duration::from_secs(expires_at as u64);
 
+    if storage.is_encrypted() {
+        return Err(HttpFileError::PresignedDownloadEncrypted.into());
+    }
+
+    let source_file = find_source_file(&db, &scope, file_id).await?;
+    let mime = mime::Mime::from_str(&file.mime).unwrap_or(mime::APPLICATION_OCTET_STREAM);
+    let file_name = create_generated_file_name(&source_file.name, file.ty, file.page, &mime);
+
+    let options = presigned_download_options(
+        tenant.active_content_policy,
+        &file.mime,
+        &file_name,
+        req.download,
+    );
+
     let (signed_request, expires_at) = storage
-        .create_presigned_download(&file.file_key, expires_at)
+        .create_presigned_download(&file.file_key, expires_at, options)
         .await
         .map_err(|_| HttpCommonError::ServerError)?;
 
@@ -1039,6 +2127,71 @@ pub async fn get_generated_raw_presigned(
     }))
 }
 
+/// Regenerate generated file
+///
+/// Requests a specific generated file type for a file, if the generated file
+/// is missing (i.e it was not retained by the tenant generated file policies)
+/// the file will be processed again to recreate it.
+///
+/// Returns the details about the existing or recreated generated file
+#[utoipa::path(
+    post,
+    operation_id = "file_regenerate_generated",
+    tag = FILE_TAG,
+    path = "/box/{scope}/file/{file_id}/generated/{type}/regenerate",
+    responses(
+        (status = 200, description = "Obtained generated file successfully", body = GeneratedFile),
+        (status = 404, description = "File not found or type cannot be generated for the file", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the file resides within"),
+        ("file_id" = Uuid, Path, description = "ID of the file to query"),
+        ("type" = GeneratedFileType, Path, description = "Type of generated file to regenerate"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %file_id, %generated_type))]
+pub async fn regenerate_generated(
+    TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
+    Extension(processing): Extension<ProcessingLayer>,
+    Path((scope, file_id, generated_type)): Path<(DocumentBoxScope, FileId, GeneratedFileType)>,
+) -> HttpResult<GeneratedFile> {
+    let DocumentBoxScope(scope) = scope;
+
+    let file = File::find(&db, &scope, file_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query file");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFileError::UnknownFile)?;
+
+    let existing = GeneratedFile::find(&db, &scope, file_id, generated_type)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query generated file");
+            HttpCommonError::ServerError
+        })?;
+
+    // Generated file already exists
+    if let Some(existing) = existing {
+        return Ok(Json(existing));
+    }
+
+    let generated =
+        regenerate_generated_file(&db, &storage, &processing, &scope, &file, generated_type)
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to regenerate generated file");
+                HttpCommonError::ServerError
+            })?
+            .ok_or(HttpFileError::NoMatchingGenerated)?;
+
+    Ok(Json(generated))
+}
+
 /// Get generated file raw named
 ///
 /// Request the contents of a specific generated file type
@@ -1065,14 +2218,65 @@ pub async fn get_generated_raw_presigned(
 )]
 #[tracing::instrument(skip_all, fields(%scope, %file_id, %generated_type))]
 pub async fn get_generated_raw_named(
+    tenant: Extension<Tenant>,
     db: TenantDb,
     storage: TenantStorage,
+    file_access: TenantFileAccess,
     Path((scope, file_id, generated_type, _tail)): Path<(
         DocumentBoxScope,
         FileId,
         GeneratedFileType,
         String,
     )>,
+    query: Query<RawFileQuery>,
 ) -> Result<Response<Body>, DynHttpError> {
-    get_generated_raw(db, storage, Path((scope, file_id, generated_type))).await
+    get_generated_raw(
+        tenant,
+        db,
+        storage,
+        file_access,
+        Path((scope, file_id, generated_type)),
+        query,
+    )
+    .await
+}
+
+/// Resolves the mime type to use for an uploaded file with the provided
+/// `name` and `mime`, applying the mime overrides of the tenant.
+///
+/// When `sniff` is enabled files uploaded as application/octet-stream
+/// (Likely from old browsers) have their mime type guessed from the name
+async fn resolve_upload_mime(
+    db: &DbPool,
+    name: &str,
+    mime: Mime,
+    sniff: bool,
+) -> Result<Mime, DynHttpError> {
+    let mime_overrides = get_mime_overrides(db).await.map_err(|error| {
+        tracing::error!(?error, "failed to query mime overrides");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(mime_overrides.resolve_file_mime(name, mime, sniff))
+}
+
+/// Checks a file with the provided `name`, `mime` and `size` against the
+/// upload rules of the tenant
+async fn check_upload_rules(
+    db: &DbPool,
+    name: &str,
+    mime: &Mime,
+    size: i64,
+) -> Result<(), DynHttpError> {
+    let rules = UploadRule::all(db).await.map_err(|error| {
+        tracing::error!(?error, "failed to query upload rules");
+        HttpCommonError::ServerError
+    })?;
+
+    let extension = get_file_name_ext(name);
+
+    UploadRule::check(&rules, mime.essence_str(), extension.as_deref(), size)
+        .map_err(HttpFileError::UploadRuleViolation)?;
+
+    Ok(())
 }
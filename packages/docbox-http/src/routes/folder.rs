@@ -2,40 +2,65 @@
 
 use crate::{
     error::{DynHttpError, HttpCommonError, HttpErrorResponse, HttpResult, HttpStatusResult},
+    extensions::search_auto_heal::SearchAutoHeal,
     middleware::{
         action_user::{ActionUser, UserParams},
+        if_match::{ETagHeader, IfMatch, IfMatchParams, version_etag},
         tenant::{TenantDb, TenantEvents, TenantParams, TenantSearch, TenantStorage},
     },
     models::{
-        document_box::DocumentBoxScope,
+        document_box::{DocumentBoxScope, HttpDocumentBoxError, MoveToScopeRequest},
+        edit_history::{EditHistoryQuery, HttpEditHistoryError},
         file::UploadTaskResponse,
         folder::{
-            CreateFolderRequest, FolderResponse, HttpFolderError, UpdateFolderRequest,
-            ZipFolderRequest,
+            CreateFolderRequest, DeleteFolderQuery, FolderChildrenQuery,
+            FolderProcessingConfigResponse, FolderResponse, HttpFolderError,
+            SetFolderProcessingConfigRequest, UpdateFolderRequest, ZipFolderRequest,
         },
+        page::{DEFAULT_PAGE_SIZE, Page, page_offset},
     },
 };
-use axum::{Json, extract::Path, http::StatusCode};
+use axum::{
+    Extension, Json,
+    extract::{Path, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
 use axum_valid::Garde;
 use docbox_core::{
-    database::models::{
-        edit_history::EditHistory,
-        folder::{Folder, FolderId, FolderWithExtra, ResolvedFolderWithExtra},
-        shared::WithFullPath,
-        tasks::TaskStatus,
+    database::{
+        DbPool,
+        models::{
+            document_box::DocumentBoxScopeRaw,
+            edit_history::{EditHistory, EditHistoryId},
+            file::File,
+            folder::{Folder, FolderId, FolderWithExtra, ResolvedFolderWithExtra},
+            folder_processing_config::FolderProcessingConfig,
+            link::Link,
+            shared::WithFullPath,
+            tasks::TaskStatus,
+        },
     },
+    document_box::move_to_scope::{MoveToScope, MoveToScopeError, move_folder_to_scope},
     folders::{
-        create_folder::{CreateFolderData, safe_create_folder},
+        create_folder::{CreateFolderData, CreateFolderError, safe_create_folder},
         create_folder_zip::{CreateFolderZipOptions, create_folder_zip},
-        delete_folder::delete_folder,
+        delete_folder::{DeleteFolderOptions, delete_folder, delete_folder_with_progress},
         update_folder::{UpdateFolder, UpdateFolderError},
     },
-    tasks::background_task::background_task,
+    search::{TenantSearchIndex, models::SearchResultData},
+    tasks::background_task::{background_task, background_task_with},
 };
+use std::time::Duration;
+use tokio::try_join;
 use tracing::Instrument;
 
 pub const FOLDER_TAG: &str = "Folder";
 
+/// Minimum time between deleting each item when deleting a folder
+/// asynchronously, limits the load placed on storage and search
+const ASYNC_DELETE_ITEM_INTERVAL: Duration = Duration::from_millis(10);
+
 /// Create folder
 ///
 /// Creates a new folder in the provided document box folder
@@ -56,11 +81,14 @@ pub const FOLDER_TAG: &str = "Folder";
     )
 )]
 #[tracing::instrument(skip_all, fields(%scope, ?req))]
+#[allow(clippy::too_many_arguments)]
 pub async fn create(
     action_user: ActionUser,
     TenantDb(db): TenantDb,
     TenantSearch(search): TenantSearch,
+    TenantStorage(storage): TenantStorage,
     TenantEvents(events): TenantEvents,
+    Extension(auto_heal): Extension<SearchAutoHeal>,
     Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
     Garde(Json(req)): Garde<Json<CreateFolderRequest>>,
 ) -> Result<(StatusCode, Json<FolderResponse>), DynHttpError> {
@@ -91,12 +119,17 @@ pub async fn create(
     };
 
     // Perform Folder creation
-    let folder = safe_create_folder(&db, search, &events, create)
-        .await
-        .map_err(|error| {
-            tracing::error!(?error, "failed to create link");
-            HttpFolderError::CreateError(error)
-        })?;
+    let result = safe_create_folder(&db, search.clone(), &events, create).await;
+
+    // Recreate the search index if it was deleted out-of-band
+    if let Err(CreateFolderError::CreateIndex(_)) = &result {
+        auto_heal.heal(&db, &search, &storage, &scope).await;
+    }
+
+    let folder = result.map_err(|error| {
+        tracing::error!(?error, "failed to create link");
+        HttpFolderError::CreateError(error)
+    })?;
 
     Ok((
         StatusCode::CREATED,
@@ -122,7 +155,8 @@ pub async fn create(
     tag = FOLDER_TAG,
     path = "/box/{scope}/folder/{folder_id}",
     responses(
-        (status = 200, description = "Folder obtained successfully", body = FolderResponse),
+        (status = 200, description = "Folder obtained successfully", body = FolderResponse,
+            headers(("etag" = String, description = "Current version of the folder"))),
         (status = 404, description = "Folder not found", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
@@ -136,9 +170,18 @@ pub async fn create(
 pub async fn get(
     TenantDb(db): TenantDb,
     Path((scope, folder_id)): Path<(DocumentBoxScope, FolderId)>,
-) -> HttpResult<FolderResponse> {
+) -> Result<(ETagHeader, Json<FolderResponse>), DynHttpError> {
     let DocumentBoxScope(scope) = scope;
 
+    // Version is queried before the folder so the ETag is never newer than the data
+    let version = Folder::version(&db, folder_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query folder version");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFolderError::UnknownFolder)?;
+
     let WithFullPath {
         data: folder,
         full_path,
@@ -159,33 +202,108 @@ pub async fn get(
             HttpCommonError::ServerError
         })?;
 
-    Ok(Json(FolderResponse { folder, children }))
+    Ok((
+        version_etag(version),
+        Json(FolderResponse { folder, children }),
+    ))
+}
+
+/// Get folder children
+///
+/// Request a page of the direct children of the provided folder, folders
+/// are listed first followed by the files and then the links
+#[utoipa::path(
+    get,
+    operation_id = "folder_get_children",
+    tag = FOLDER_TAG,
+    path = "/box/{scope}/folder/{folder_id}/children",
+    responses(
+        (status = 200, description = "Obtained folder children", body = Page<SearchResultData>),
+        (status = 400, description = "Invalid page cursor", body = HttpErrorResponse),
+        (status = 404, description = "Folder not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the folder resides within"),
+        ("folder_id" = Uuid, Path, description = "ID of the folder to request"),
+        TenantParams,
+        FolderChildrenQuery
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %folder_id, ?query))]
+pub async fn get_children(
+    TenantDb(db): TenantDb,
+    Path((scope, folder_id)): Path<(DocumentBoxScope, FolderId)>,
+    Query(query): Query<FolderChildrenQuery>,
+) -> HttpResult<Page<SearchResultData>> {
+    let DocumentBoxScope(scope) = scope;
+
+    _ = Folder::find_by_id(&db, &scope, folder_id)
+        .await
+        // Failed to query folder
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query folder");
+            HttpCommonError::ServerError
+        })?
+        // Folder not found
+        .ok_or(HttpFolderError::UnknownFolder)?;
+
+    let offset = page_offset(query.cursor.as_deref(), query.offset)?;
+    let limit = query.size.map(u64::from).unwrap_or(DEFAULT_PAGE_SIZE);
+
+    let folders_future = Folder::find_by_parent_with_extra(&db, folder_id);
+    let files_future = File::find_by_parent_folder_with_extra(&db, folder_id);
+    let links_future = Link::find_by_parent_with_extra(&db, folder_id);
+    let (folders, files, links) =
+        try_join!(folders_future, files_future, links_future).map_err(|error| {
+            tracing::error!(?error, "failed to query folder children");
+            HttpCommonError::ServerError
+        })?;
+
+    let total = (folders.len() + files.len() + links.len()) as i64;
+    let children: Vec<SearchResultData> = folders
+        .into_iter()
+        .map(SearchResultData::Folder)
+        .chain(files.into_iter().map(SearchResultData::File))
+        .chain(links.into_iter().map(SearchResultData::Link))
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect();
+
+    Ok(Json(Page::from_offset(children, total, offset)))
 }
 
 /// Get folder edit history
 ///
 /// Request the edit history for the provided folder
+///
+/// Entries can be filtered by type, user and date range and are
+/// paginated using the offset and size query parameters or the
+/// cursor from a previous page
 #[utoipa::path(
     get,
     operation_id = "folder_edit_history",
     tag = FOLDER_TAG,
     path = "/box/{scope}/folder/{folder_id}/edit-history",
     responses(
-        (status = 200, description = "Obtained edit history", body = [EditHistory]),
+        (status = 200, description = "Obtained edit history", body = Page<EditHistory>),
+        (status = 400, description = "Invalid page cursor", body = HttpErrorResponse),
         (status = 404, description = "Folder not found", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
     params(
         ("scope" = DocumentBoxScope, Path, description = "Scope the folder resides within"),
         ("folder_id" = Uuid, Path, description = "ID of the folder to request"),
-        TenantParams
+        TenantParams,
+        EditHistoryQuery
     )
 )]
-#[tracing::instrument(skip_all, fields(%scope, %folder_id))]
+#[tracing::instrument(skip_all, fields(%scope, %folder_id, ?query))]
 pub async fn get_edit_history(
     TenantDb(db): TenantDb,
     Path((scope, folder_id)): Path<(DocumentBoxScope, FolderId)>,
-) -> HttpResult<Vec<EditHistory>> {
+    Query(query): Query<EditHistoryQuery>,
+) -> HttpResult<Page<EditHistory>> {
     let DocumentBoxScope(scope) = scope;
 
     _ = Folder::find_by_id(&db, &scope, folder_id)
@@ -198,45 +316,56 @@ pub async fn get_edit_history(
         // Folder not found
         .ok_or(HttpFolderError::UnknownFolder)?;
 
-    let edit_history = EditHistory::all_by_folder(&db, folder_id)
-        .await
-        .map_err(|error| {
-            tracing::error!(?error, "failed to query folder edit history");
-            HttpCommonError::ServerError
-        })?;
+    let offset = page_offset(query.cursor.as_deref(), query.offset)?;
+    let limit = query.size.map(u64::from).unwrap_or(DEFAULT_PAGE_SIZE);
+    let filter = query.filter();
 
-    Ok(Json(edit_history))
+    let total_future = EditHistory::total_by_folder(&db, folder_id, &filter);
+    let history_future = EditHistory::query_by_folder(&db, folder_id, &filter, offset, limit);
+    let (total, history) = try_join!(total_future, history_future).map_err(|error| {
+        tracing::error!(?error, "failed to query folder edit history");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(Page::from_offset(history, total, offset)))
 }
 
 /// Update folder
 ///
 /// Updates a folder, can be a name change, a folder move, or both
+///
+/// Providing the ETag from a previous request in the If-Match header
+/// rejects the update if the folder was modified since
 #[utoipa::path(
     put,
     operation_id = "folder_update",
     tag = FOLDER_TAG,
     path = "/box/{scope}/folder/{folder_id}",
     responses(
-        (status = 200, description = "Updated folder successfully"),
-        (status = 400, description = "Attempted to move a root folder or a folder into itself", body = HttpErrorResponse),
+        (status = 200, description = "Updated folder successfully",
+            headers(("etag" = String, description = "New version of the folder"))),
+        (status = 400, description = "Attempted to move a root folder or a folder into itself or invalid If-Match header", body = HttpErrorResponse),
         (status = 404, description = "Folder not found", body = HttpErrorResponse),
+        (status = 409, description = "Folder was modified since the If-Match version", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
     params(
         ("scope" = DocumentBoxScope, Path, description = "Scope the folder resides within"),
         ("folder_id" = Uuid, Path, description = "ID of the folder to request"),
         TenantParams,
-        UserParams
+        UserParams,
+        IfMatchParams
     )
 )]
 #[tracing::instrument(skip_all, fields(%scope, %folder_id, ?req))]
 pub async fn update(
     action_user: ActionUser,
+    IfMatch(expected_version): IfMatch,
     TenantDb(db): TenantDb,
     TenantSearch(search): TenantSearch,
     Path((scope, folder_id)): Path<(DocumentBoxScope, FolderId)>,
     Garde(Json(req)): Garde<Json<UpdateFolderRequest>>,
-) -> HttpStatusResult {
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
     let DocumentBoxScope(scope) = scope;
 
     let folder = Folder::find_by_id(&db, &scope, folder_id)
@@ -257,9 +386,10 @@ pub async fn update(
         folder_id: req.folder_id,
         name: req.name,
         pinned: req.pinned,
+        expected_version,
     };
 
-    docbox_core::folders::update_folder::update_folder(
+    let version = docbox_core::folders::update_folder::update_folder(
         &db, &search, &scope, folder, user_id, update,
     )
     .await
@@ -267,10 +397,296 @@ pub async fn update(
         UpdateFolderError::UnknownTargetFolder => HttpFolderError::UnknownTargetFolder.into(),
         UpdateFolderError::CannotModifyRoot => HttpFolderError::CannotModifyRoot.into(),
         UpdateFolderError::CannotMoveIntoSelf => HttpFolderError::CannotMoveIntoSelf.into(),
+        UpdateFolderError::VersionConflict { .. } => HttpFolderError::VersionConflict.into(),
         _ => DynHttpError::from(HttpCommonError::ServerError),
     })?;
 
-    Ok(StatusCode::OK)
+    Ok((StatusCode::OK, version_etag(version)))
+}
+
+/// Move folder to document box
+///
+/// Moves a folder along with all of its contents into a folder within
+/// another document box of the tenant
+#[utoipa::path(
+    post,
+    operation_id = "folder_move_to_scope",
+    tag = FOLDER_TAG,
+    path = "/box/{scope}/folder/{folder_id}/move-scope",
+    responses(
+        (status = 200, description = "Moved folder successfully", body = Folder),
+        (status = 400, description = "Attempted to move a root folder or the folder is already within the target document box", body = HttpErrorResponse),
+        (status = 404, description = "Folder, target document box or target folder not found", body = HttpErrorResponse),
+        (status = 423, description = "Source or target document box is archived", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the folder resides within"),
+        ("folder_id" = Uuid, Path, description = "ID of the folder to move"),
+        TenantParams,
+        UserParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %folder_id, ?req))]
+pub async fn move_to_scope(
+    action_user: ActionUser,
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    TenantStorage(storage): TenantStorage,
+    TenantEvents(events): TenantEvents,
+    Path((scope, folder_id)): Path<(DocumentBoxScope, FolderId)>,
+    Garde(Json(req)): Garde<Json<MoveToScopeRequest>>,
+) -> HttpResult<Folder> {
+    let DocumentBoxScope(scope) = scope;
+
+    let folder = find_folder(&db, &scope, folder_id).await?;
+
+    // Update stored editing user data
+    let user = action_user.store_user(&db).await?;
+    let user_id = user.as_ref().map(|value| value.id.to_string());
+
+    let target = MoveToScope {
+        document_box: req.document_box,
+        folder_id: req.folder_id,
+    };
+
+    let folder = move_folder_to_scope(
+        &db, &search, &storage, &events, &scope, folder, user_id, target,
+    )
+    .await
+    .map_err(|error| match error {
+        MoveToScopeError::CannotModifyRoot => HttpFolderError::CannotModifyRoot.into(),
+        MoveToScopeError::SameScope => HttpDocumentBoxError::SameDocumentBox.into(),
+        MoveToScopeError::UnknownTargetDocumentBox => {
+            HttpDocumentBoxError::UnknownDocumentBox.into()
+        }
+        MoveToScopeError::TargetDocumentBoxArchived
+        | MoveToScopeError::SourceDocumentBoxArchived => {
+            HttpDocumentBoxError::DocumentBoxArchived.into()
+        }
+        MoveToScopeError::UnknownTargetFolder => HttpFolderError::UnknownTargetFolder.into(),
+        _ => DynHttpError::from(HttpCommonError::ServerError),
+    })?;
+
+    Ok(Json(folder))
+}
+
+/// Pin folder
+///
+/// Pins a folder, pinned items are included in the pinned items
+/// of the document box. Pinning an already pinned folder keeps the
+/// original pin time
+///
+/// Providing the ETag from a previous request in the If-Match header
+/// rejects the change if the folder was modified since
+#[utoipa::path(
+    put,
+    operation_id = "folder_pin",
+    tag = FOLDER_TAG,
+    path = "/box/{scope}/folder/{folder_id}/pin",
+    responses(
+        (status = 200, description = "Pinned folder successfully",
+            headers(("etag" = String, description = "New version of the folder"))),
+        (status = 400, description = "Invalid If-Match header", body = HttpErrorResponse),
+        (status = 404, description = "Folder not found", body = HttpErrorResponse),
+        (status = 409, description = "Folder was modified since the If-Match version", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the folder resides within"),
+        ("folder_id" = Uuid, Path, description = "ID of the folder to pin"),
+        TenantParams,
+        UserParams,
+        IfMatchParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %folder_id))]
+pub async fn pin(
+    action_user: ActionUser,
+    IfMatch(expected_version): IfMatch,
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    Path((scope, folder_id)): Path<(DocumentBoxScope, FolderId)>,
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
+    set_pinned(
+        action_user,
+        expected_version,
+        &db,
+        &search,
+        scope,
+        folder_id,
+        true,
+    )
+    .await
+}
+
+/// Unpin folder
+///
+/// Unpins a folder, removing it from the pinned items of the document box
+///
+/// Providing the ETag from a previous request in the If-Match header
+/// rejects the change if the folder was modified since
+#[utoipa::path(
+    delete,
+    operation_id = "folder_unpin",
+    tag = FOLDER_TAG,
+    path = "/box/{scope}/folder/{folder_id}/pin",
+    responses(
+        (status = 200, description = "Unpinned folder successfully",
+            headers(("etag" = String, description = "New version of the folder"))),
+        (status = 400, description = "Invalid If-Match header", body = HttpErrorResponse),
+        (status = 404, description = "Folder not found", body = HttpErrorResponse),
+        (status = 409, description = "Folder was modified since the If-Match version", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the folder resides within"),
+        ("folder_id" = Uuid, Path, description = "ID of the folder to unpin"),
+        TenantParams,
+        UserParams,
+        IfMatchParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %folder_id))]
+pub async fn unpin(
+    action_user: ActionUser,
+    IfMatch(expected_version): IfMatch,
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    Path((scope, folder_id)): Path<(DocumentBoxScope, FolderId)>,
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
+    set_pinned(
+        action_user,
+        expected_version,
+        &db,
+        &search,
+        scope,
+        folder_id,
+        false,
+    )
+    .await
+}
+
+/// Set the pinned state of a folder, the change is recorded in the
+/// edit history of the folder
+async fn set_pinned(
+    action_user: ActionUser,
+    expected_version: Option<i64>,
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    DocumentBoxScope(scope): DocumentBoxScope,
+    folder_id: FolderId,
+    pinned: bool,
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
+    let folder = Folder::find_by_id(db, &scope, folder_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query folder");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFolderError::UnknownFolder)?;
+
+    // Update stored editing user data
+    let user = action_user.store_user(db).await?;
+    let user_id = user.as_ref().map(|value| value.id.to_string());
+
+    let update = UpdateFolder {
+        pinned: Some(pinned),
+        expected_version,
+        ..Default::default()
+    };
+
+    let version = docbox_core::folders::update_folder::update_folder(
+        db, search, &scope, folder, user_id, update,
+    )
+    .await
+    .map_err(|error| match error {
+        UpdateFolderError::CannotModifyRoot => HttpFolderError::CannotModifyRoot.into(),
+        UpdateFolderError::VersionConflict { .. } => HttpFolderError::VersionConflict.into(),
+        _ => DynHttpError::from(HttpCommonError::ServerError),
+    })?;
+
+    Ok((StatusCode::OK, version_etag(version)))
+}
+
+/// Revert folder edit history entry
+///
+/// Reverts the change recorded by an edit history entry for the folder by
+/// applying the inverse change (i.e renaming back or moving back). The revert
+/// is recorded in the edit history as a new change
+///
+/// Providing the ETag from a previous request in the If-Match header
+/// rejects the revert if the folder was modified since
+#[utoipa::path(
+    post,
+    operation_id = "folder_revert_edit_history",
+    tag = FOLDER_TAG,
+    path = "/box/{scope}/folder/{folder_id}/edit-history/{entry_id}/revert",
+    responses(
+        (status = 200, description = "Reverted the change successfully",
+            headers(("etag" = String, description = "New version of the folder"))),
+        (status = 400, description = "Edit history entry cannot be reverted or invalid If-Match header", body = HttpErrorResponse),
+        (status = 404, description = "Folder or edit history entry not found", body = HttpErrorResponse),
+        (status = 409, description = "Folder was modified since the If-Match version", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the folder resides within"),
+        ("folder_id" = Uuid, Path, description = "ID of the folder to revert"),
+        ("entry_id" = Uuid, Path, description = "ID of the edit history entry to revert"),
+        TenantParams,
+        UserParams,
+        IfMatchParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %folder_id, %entry_id))]
+pub async fn revert_edit_history(
+    action_user: ActionUser,
+    IfMatch(expected_version): IfMatch,
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    Path((scope, folder_id, entry_id)): Path<(DocumentBoxScope, FolderId, EditHistoryId)>,
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
+    let DocumentBoxScope(scope) = scope;
+
+    let folder = Folder::find_by_id(&db, &scope, folder_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query folder");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpFolderError::UnknownFolder)?;
+
+    let entry = EditHistory::find_by_folder(&db, folder_id, entry_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query folder history entry");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpEditHistoryError::UnknownEntry)?;
+
+    let update = UpdateFolder {
+        expected_version,
+        ..UpdateFolder::revert(&entry.metadata).ok_or(HttpEditHistoryError::CannotRevert)?
+    };
+
+    // Update stored editing user data
+    let user = action_user.store_user(&db).await?;
+    let user_id = user.as_ref().map(|value| value.id.to_string());
+
+    let version = docbox_core::folders::update_folder::update_folder(
+        &db, &search, &scope, folder, user_id, update,
+    )
+    .await
+    .map_err(|error| match error {
+        UpdateFolderError::UnknownTargetFolder => HttpFolderError::UnknownTargetFolder.into(),
+        UpdateFolderError::CannotModifyRoot => HttpFolderError::CannotModifyRoot.into(),
+        UpdateFolderError::CannotMoveIntoSelf => HttpFolderError::CannotMoveIntoSelf.into(),
+        UpdateFolderError::VersionConflict { .. } => HttpFolderError::VersionConflict.into(),
+        _ => DynHttpError::from(HttpCommonError::ServerError),
+    })?;
+
+    Ok((StatusCode::OK, version_etag(version)))
 }
 
 /// Delete a folder by ID
@@ -278,12 +694,18 @@ pub async fn update(
 /// Deletes a document box folder and all its contents. This will
 /// traverse the folder contents as a stack deleting all files and
 /// folders within the folder before deleting itself
+///
+/// If the asynchronous option is specified the folder is deleted in
+/// the background and a task is returned, the task reports the deletion
+/// progress while pending. Deleting a folder again after a failed deletion
+/// resumes with the remaining contents
 #[utoipa::path(
     delete,
     operation_id = "folder_delete",
     tag = FOLDER_TAG,
     path = "/box/{scope}/folder/{folder_id}",
     responses(
+        (status = 202, description = "Folder deletion started", body = UploadTaskResponse),
         (status = 204, description = "Deleted folder successfully"),
         (status = 404, description = "Folder not found", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
@@ -291,17 +713,19 @@ pub async fn update(
     params(
         ("scope" = DocumentBoxScope, Path, description = "Scope the folder resides within"),
         ("folder_id" = Uuid, Path, description = "ID of the folder to delete"),
+        DeleteFolderQuery,
         TenantParams
     )
 )]
-#[tracing::instrument(skip_all, fields(%scope, %folder_id))]
+#[tracing::instrument(skip_all, fields(%scope, %folder_id, ?query))]
 pub async fn delete(
     TenantDb(db): TenantDb,
     TenantStorage(storage): TenantStorage,
     TenantEvents(events): TenantEvents,
     TenantSearch(search): TenantSearch,
     Path((scope, folder_id)): Path<(DocumentBoxScope, FolderId)>,
-) -> HttpStatusResult {
+    Query(query): Query<DeleteFolderQuery>,
+) -> Result<Response, DynHttpError> {
     let DocumentBoxScope(scope) = scope;
 
     let folder = Folder::find_by_id(&db, &scope, folder_id)
@@ -319,16 +743,231 @@ pub async fn delete(
         return Err(HttpFolderError::CannotDeleteRoot.into());
     }
 
-    delete_folder(&db, &storage, &search, &events, folder)
+    // Handle synchronous request waiting for the deletion to complete before responding
+    if !query.asynchronous.unwrap_or_default() {
+        delete_folder(&db, &storage, &search, &events, folder)
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to delete folder");
+                HttpCommonError::ServerError
+            })?;
+
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    }
+
+    let span = tracing::Span::current();
+
+    // Spawn background task
+    let (task_id, created_at) = background_task_with(db.clone(), scope.clone(), |mut task| {
+        async move {
+            let options = DeleteFolderOptions {
+                item_interval: Some(ASYNC_DELETE_ITEM_INTERVAL),
+            };
+
+            let result = delete_folder_with_progress(
+                &db,
+                &storage,
+                &search,
+                &events,
+                folder,
+                &options,
+                Some(&mut task),
+            )
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to delete folder");
+                DynHttpError::from(HttpCommonError::ServerError)
+            })
+            // Serialize the response for storage
+            .and_then(|value| {
+                serde_json::to_value(&value).map_err(|error| {
+                    tracing::error!(?error, "failed to serialize delete task outcome");
+                    DynHttpError::from(HttpCommonError::ServerError)
+                })
+            });
+
+            match result {
+                Ok(value) => (TaskStatus::Completed, value),
+                Err(error) => (
+                    TaskStatus::Failed,
+                    serde_json::json!({ "error": error.to_string() }),
+                ),
+            }
+        }
+        // Ensure the logging span is passed onto the background task so that
+        // logging context continues
+        .instrument(span)
+    })
+    .await
+    .map_err(|error| {
+        tracing::error!(?error, "failed to create background task");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(UploadTaskResponse {
+            task_id,
+            created_at,
+        }),
+    )
+        .into_response())
+}
+
+/// Get folder processing config
+///
+/// Get the processing config attached directly to the folder. Configs
+/// attached to parent folders are not included
+#[utoipa::path(
+    get,
+    operation_id = "folder_get_processing_config",
+    tag = FOLDER_TAG,
+    path = "/box/{scope}/folder/{folder_id}/processing-config",
+    responses(
+        (status = 200, description = "Obtained processing config", body = FolderProcessingConfigResponse),
+        (status = 404, description = "Folder not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the folder resides within"),
+        ("folder_id" = Uuid, Path, description = "ID of the folder to get the config for"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %folder_id))]
+pub async fn get_processing_config(
+    TenantDb(db): TenantDb,
+    Path((scope, folder_id)): Path<(DocumentBoxScope, FolderId)>,
+) -> HttpResult<FolderProcessingConfigResponse> {
+    let DocumentBoxScope(scope) = scope;
+
+    let folder = find_folder(&db, &scope, folder_id).await?;
+
+    let config = FolderProcessingConfig::find(&db, folder.id)
         .await
         .map_err(|error| {
-            tracing::error!(?error, "failed to delete folder");
+            tracing::error!(?error, "failed to query folder processing config");
+            HttpCommonError::ServerError
+        })?
+        .and_then(|config| {
+            serde_json::from_value(config.config)
+                .inspect_err(|error| {
+                    tracing::warn!(?error, "folder has invalid processing config");
+                })
+                .ok()
+        });
+
+    Ok(Json(FolderProcessingConfigResponse { config }))
+}
+
+/// Set folder processing config
+///
+/// Attach a processing config to the folder, replacing any existing config.
+///
+/// Files uploaded into the folder (or any of its children) are processed
+/// using the config. When multiple folders along the path have a config
+/// the configs are merged with the closest folder taking priority. Any
+/// processing config provided with the upload itself takes priority over
+/// folder configs
+#[utoipa::path(
+    put,
+    operation_id = "folder_set_processing_config",
+    tag = FOLDER_TAG,
+    path = "/box/{scope}/folder/{folder_id}/processing-config",
+    request_body = SetFolderProcessingConfigRequest,
+    responses(
+        (status = 200, description = "Updated processing config", body = FolderProcessingConfigResponse),
+        (status = 404, description = "Folder not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the folder resides within"),
+        ("folder_id" = Uuid, Path, description = "ID of the folder to set the config for"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %folder_id, ?req))]
+pub async fn set_processing_config(
+    TenantDb(db): TenantDb,
+    Path((scope, folder_id)): Path<(DocumentBoxScope, FolderId)>,
+    Garde(Json(req)): Garde<Json<SetFolderProcessingConfigRequest>>,
+) -> HttpResult<FolderProcessingConfigResponse> {
+    let DocumentBoxScope(scope) = scope;
+
+    let folder = find_folder(&db, &scope, folder_id).await?;
+
+    // UNWRAP SAFETY: Processing config only contains serializable primitives
+    let config = serde_json::to_value(&req.config).unwrap();
+
+    FolderProcessingConfig::set(&db, folder.id, config)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to store folder processing config");
+            HttpCommonError::ServerError
+        })?;
+
+    Ok(Json(FolderProcessingConfigResponse {
+        config: Some(req.config),
+    }))
+}
+
+/// Delete folder processing config
+///
+/// Remove the processing config attached to the folder
+#[utoipa::path(
+    delete,
+    operation_id = "folder_delete_processing_config",
+    tag = FOLDER_TAG,
+    path = "/box/{scope}/folder/{folder_id}/processing-config",
+    responses(
+        (status = 204, description = "Deleted processing config"),
+        (status = 404, description = "Folder not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the folder resides within"),
+        ("folder_id" = Uuid, Path, description = "ID of the folder to delete the config for"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %folder_id))]
+pub async fn delete_processing_config(
+    TenantDb(db): TenantDb,
+    Path((scope, folder_id)): Path<(DocumentBoxScope, FolderId)>,
+) -> HttpStatusResult {
+    let DocumentBoxScope(scope) = scope;
+
+    let folder = find_folder(&db, &scope, folder_id).await?;
+
+    FolderProcessingConfig::delete(&db, folder.id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to delete folder processing config");
             HttpCommonError::ServerError
         })?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Find a folder by ID within `scope`
+async fn find_folder(
+    db: &DbPool,
+    scope: &DocumentBoxScopeRaw,
+    folder_id: FolderId,
+) -> Result<Folder, DynHttpError> {
+    let folder = Folder::find_by_id(db, scope, folder_id)
+        .await
+        // Failed to query folder
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query folder");
+            HttpCommonError::ServerError
+        })?
+        // Folder not found
+        .ok_or(HttpFolderError::UnknownFolder)?;
+
+    Ok(folder)
+}
+
 /// Create a folder ZIP
 ///
 /// Create a ZIP file from the contents of a folder
@@ -0,0 +1,186 @@
+//! Document box inbound email address related endpoints
+
+use crate::{
+    error::{DynHttpError, HttpCommonError, HttpErrorResponse, HttpResult, HttpStatusResult},
+    extensions::inbound_email_domain::InboundEmailDomain,
+    middleware::tenant::{TenantDb, TenantParams},
+    models::{
+        document_box::{DocumentBoxScope, HttpDocumentBoxError},
+        inbound_email::HttpInboundEmailError,
+    },
+};
+use axum::{Extension, Json, extract::Path, http::StatusCode};
+use docbox_core::database::{
+    DatabasePoolCache,
+    models::{
+        document_box::DocumentBox,
+        inbound_email_address::{CreateInboundEmailAddress, InboundEmailAddress},
+        tenant::Tenant,
+    },
+    utils::DatabaseErrorExt,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const INBOUND_EMAIL_TAG: &str = "Inbound Email";
+
+/// Create inbound email address
+///
+/// Creates an email address for the document box, emails received by the
+/// address are stored in the root folder of the document box and have
+/// their attachments extracted
+#[utoipa::path(
+    post,
+    operation_id = "inbound_email_create",
+    tag = INBOUND_EMAIL_TAG,
+    path = "/box/{scope}/inbound-email",
+    responses(
+        (status = 201, description = "Inbound email address created successfully", body = InboundEmailAddress),
+        (status = 404, description = "Document box not found", body = HttpErrorResponse),
+        (status = 409, description = "Document box already has an inbound email address", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse),
+        (status = 501, description = "Inbound email is not enabled", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope to create the address for"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope))]
+pub async fn create(
+    TenantDb(db): TenantDb,
+    Extension(tenant): Extension<Tenant>,
+    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
+    Extension(InboundEmailDomain(domain)): Extension<InboundEmailDomain>,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+) -> Result<(StatusCode, Json<InboundEmailAddress>), DynHttpError> {
+    let domain = domain.ok_or(HttpInboundEmailError::NotEnabled)?;
+
+    DocumentBox::find_by_scope(&db, &scope)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query document box");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpDocumentBoxError::UnknownDocumentBox)?;
+
+    let root_db = db_cache.get_root_pool().await.map_err(|error| {
+        tracing::error!(?error, "failed to connect to root database");
+        HttpCommonError::ServerError
+    })?;
+
+    // Random local part so addresses cannot be guessed from the scope
+    let address = format!("{}@{domain}", Uuid::new_v4().simple());
+
+    let address = InboundEmailAddress::create(
+        &root_db,
+        CreateInboundEmailAddress {
+            address,
+            env: tenant.env,
+            tenant_id: tenant.id,
+            document_box: scope,
+        },
+    )
+    .await
+    .map_err(|error| {
+        if error.is_duplicate_record() {
+            DynHttpError::from(HttpInboundEmailError::AddressExists)
+        } else {
+            tracing::error!(?error, "failed to create inbound email address");
+            DynHttpError::from(HttpCommonError::ServerError)
+        }
+    })?;
+
+    Ok((StatusCode::CREATED, Json(address)))
+}
+
+/// Get inbound email address
+///
+/// Get the inbound email address of the document box
+#[utoipa::path(
+    get,
+    operation_id = "inbound_email_get",
+    tag = INBOUND_EMAIL_TAG,
+    path = "/box/{scope}/inbound-email",
+    responses(
+        (status = 200, description = "Inbound email address obtained successfully", body = InboundEmailAddress),
+        (status = 404, description = "Inbound email address not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the address belongs to"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope))]
+pub async fn get(
+    Extension(tenant): Extension<Tenant>,
+    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+) -> HttpResult<InboundEmailAddress> {
+    let address = find_address(&db_cache, &tenant, &scope).await?;
+    Ok(Json(address))
+}
+
+/// Delete inbound email address
+///
+/// Deletes the inbound email address of the document box, emails sent
+/// to the address are no longer received
+#[utoipa::path(
+    delete,
+    operation_id = "inbound_email_delete",
+    tag = INBOUND_EMAIL_TAG,
+    path = "/box/{scope}/inbound-email",
+    responses(
+        (status = 204, description = "Deleted inbound email address successfully"),
+        (status = 404, description = "Inbound email address not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the address belongs to"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope))]
+pub async fn delete(
+    Extension(tenant): Extension<Tenant>,
+    Extension(db_cache): Extension<Arc<DatabasePoolCache>>,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+) -> HttpStatusResult {
+    let address = find_address(&db_cache, &tenant, &scope).await?;
+
+    let root_db = db_cache.get_root_pool().await.map_err(|error| {
+        tracing::error!(?error, "failed to connect to root database");
+        HttpCommonError::ServerError
+    })?;
+
+    address.delete(&root_db).await.map_err(|error| {
+        tracing::error!(?error, "failed to delete inbound email address");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Find the inbound email address of the document box `scope`
+async fn find_address(
+    db_cache: &DatabasePoolCache,
+    tenant: &Tenant,
+    scope: &str,
+) -> Result<InboundEmailAddress, DynHttpError> {
+    let root_db = db_cache.get_root_pool().await.map_err(|error| {
+        tracing::error!(?error, "failed to connect to root database");
+        HttpCommonError::ServerError
+    })?;
+
+    let address =
+        InboundEmailAddress::find_by_document_box(&root_db, &tenant.env, tenant.id, scope)
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to query inbound email address");
+                HttpCommonError::ServerError
+            })?
+            .ok_or(HttpInboundEmailError::UnknownAddress)?;
+
+    Ok(address)
+}
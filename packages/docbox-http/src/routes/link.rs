@@ -2,43 +2,69 @@
 
 use crate::{
     error::{DynHttpError, HttpCommonError, HttpErrorResponse, HttpResult, HttpStatusResult},
+    extensions::search_auto_heal::SearchAutoHeal,
     middleware::{
         action_user::{ActionUser, UserParams},
-        tenant::{TenantDb, TenantEvents, TenantParams, TenantSearch},
+        if_match::{ETagHeader, IfMatch, IfMatchParams, version_etag},
+        tenant::{TenantDb, TenantEvents, TenantParams, TenantSearch, TenantStorage},
     },
     models::{
         document_box::DocumentBoxScope,
+        edit_history::{EditHistoryQuery, HttpEditHistoryError},
         file::BinaryResponse,
         folder::HttpFolderError,
-        link::{CreateLink, HttpLinkError, LinkMetadataResponse, UpdateLinkRequest},
+        link::{
+            BulkCreateLinksRequest, BulkCreateLinksResponse, CreateLink, HttpLinkError,
+            LinkMetadataResponse, LinkResponse, MostVisitedLinksQuery, MostVisitedLinksResponse,
+            UpdateLinkRequest,
+        },
+        page::{DEFAULT_PAGE_SIZE, Page, page_offset},
     },
 };
 use axum::{
     Extension, Json,
     body::Body,
-    extract::Path,
+    extract::{Path, Query},
     http::{Response, StatusCode, header},
 };
 use axum_valid::Garde;
+use chrono::Utc;
 use docbox_core::{
     database::models::{
-        edit_history::EditHistory,
+        edit_history::{EditHistory, EditHistoryId},
         folder::Folder,
         link::{Link, LinkId, LinkWithExtra},
+        link_generated_file::{LinkGeneratedFile, LinkGeneratedFileType},
+        link_visit_stats::LinkVisitStats,
+        tasks::TaskStatus,
     },
     links::get_link_metadata::get_link_metadata,
+    search::TenantSearchIndex,
+    tasks::background_task::background_task_with,
 };
 use docbox_core::{
-    database::{DbPool, models::document_box::DocumentBoxScopeRawRef},
+    database::{
+        DbPool,
+        models::document_box::{DocumentBoxScopeRaw, DocumentBoxScopeRawRef},
+    },
     links::{
-        create_link::{CreateLinkData, safe_create_link},
+        bulk_create_links::{
+            BulkCreateLinkItem, BulkCreateLinksData, prefetch_links_metadata,
+            safe_bulk_create_links,
+        },
+        create_link::{CreateLinkData, CreateLinkError, safe_create_link},
         delete_link::delete_link,
+        generated::persist_link_images,
         get_link_metadata::GetLinkMetadataError,
+        refresh_link_metadata::{RefreshLinkMetadataError, refresh_link_metadata},
         resolve_website::ResolveWebsiteService,
         update_link::{UpdateLink, UpdateLinkError},
     },
+    storage::StorageLayer,
 };
 use std::sync::Arc;
+use tokio::try_join;
+use tracing::Instrument;
 
 pub const LINK_TAG: &str = "Link";
 
@@ -62,11 +88,15 @@ pub const LINK_TAG: &str = "Link";
     )
 )]
 #[tracing::instrument(skip_all, fields(%scope))]
+#[allow(clippy::too_many_arguments)]
 pub async fn create(
     action_user: ActionUser,
     TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
     TenantSearch(search): TenantSearch,
     TenantEvents(events): TenantEvents,
+    Extension(website_service): Extension<Arc<ResolveWebsiteService>>,
+    Extension(auto_heal): Extension<SearchAutoHeal>,
     Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
     Garde(Json(req)): Garde<Json<CreateLink>>,
 ) -> Result<(StatusCode, Json<LinkWithExtra>), DynHttpError> {
@@ -93,12 +123,19 @@ pub async fn create(
     };
 
     // Perform Link creation
-    let link = safe_create_link(&db, search, &events, create)
-        .await
-        .map_err(|error| {
-            tracing::error!(?error, "failed to create link");
-            HttpLinkError::CreateError(error)
-        })?;
+    let result = safe_create_link(&db, search.clone(), &events, create).await;
+
+    // Recreate the search index if it was deleted out-of-band
+    if let Err(CreateLinkError::CreateIndex(_)) = &result {
+        auto_heal.heal(&db, &search, &storage, &scope).await;
+    }
+
+    let link = result.map_err(|error| {
+        tracing::error!(?error, "failed to create link");
+        HttpLinkError::CreateError(error)
+    })?;
+
+    spawn_persist_link_images(db, storage, website_service, link.clone(), scope);
 
     Ok((
         StatusCode::CREATED,
@@ -111,6 +148,142 @@ pub async fn create(
     ))
 }
 
+/// Bulk create links
+///
+/// Creates many links within the provided document box at once, either
+/// all the links are created or none of them are. The website metadata
+/// for the links is resolved in the background by the returned task
+#[utoipa::path(
+    post,
+    operation_id = "link_bulk_create",
+    tag = LINK_TAG,
+    path = "/box/{scope}/link/bulk",
+    request_body = BulkCreateLinksRequest,
+    responses(
+        (status = 201, description = "Links created successfully", body = BulkCreateLinksResponse),
+        (status = 404, description = "Destination folder not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope to create the links within"),
+        TenantParams,
+        UserParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, count = req.links.len()))]
+#[allow(clippy::too_many_arguments)]
+pub async fn bulk_create(
+    action_user: ActionUser,
+    TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
+    TenantSearch(search): TenantSearch,
+    TenantEvents(events): TenantEvents,
+    Extension(website_service): Extension<Arc<ResolveWebsiteService>>,
+    Extension(auto_heal): Extension<SearchAutoHeal>,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+    Garde(Json(req)): Garde<Json<BulkCreateLinksRequest>>,
+) -> Result<(StatusCode, Json<BulkCreateLinksResponse>), DynHttpError> {
+    let folder_id = req.folder_id;
+    let folder = Folder::find_by_id(&db, &scope, folder_id)
+        .await
+        // Failed to query destination folder
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query link destination folder");
+            HttpCommonError::ServerError
+        })?
+        // Destination folder was not found
+        .ok_or(HttpFolderError::UnknownFolder)?;
+
+    // Update stored editing user data
+    let created_by = action_user.store_user(&db).await?;
+
+    // Make the create query
+    let create = BulkCreateLinksData {
+        folder,
+        links: req
+            .links
+            .into_iter()
+            .map(|item| BulkCreateLinkItem {
+                name: item.name.unwrap_or_else(|| item.value.clone()),
+                value: item.value,
+            })
+            .collect(),
+        created_by: created_by.as_ref().map(|value| value.id.to_string()),
+    };
+
+    // Perform Link creation
+    let result = safe_bulk_create_links(&db, search.clone(), &events, create).await;
+
+    // Recreate the search index if it was deleted out-of-band
+    if let Err(CreateLinkError::CreateIndex(_)) = &result {
+        auto_heal.heal(&db, &search, &storage, &scope).await;
+    }
+
+    let links = result.map_err(|error| {
+        tracing::error!(?error, "failed to bulk create links");
+        HttpLinkError::CreateError(error)
+    })?;
+
+    let span = tracing::Span::current();
+
+    // Spawn background task to resolve the link metadata
+    let (task_id, created_at) = background_task_with(db.clone(), scope.clone(), {
+        let links = links.clone();
+        let scope = scope.clone();
+        |mut task| {
+            async move {
+                let progress = prefetch_links_metadata(
+                    &db,
+                    &storage,
+                    &website_service,
+                    &links,
+                    &scope,
+                    Some(&mut task),
+                )
+                .await;
+
+                match serde_json::to_value(&progress) {
+                    Ok(value) => (TaskStatus::Completed, value),
+                    Err(error) => {
+                        tracing::error!(?error, "failed to serialize prefetch task outcome");
+                        (
+                            TaskStatus::Failed,
+                            serde_json::json!({ "error": error.to_string() }),
+                        )
+                    }
+                }
+            }
+            // Ensure the logging span is passed onto the background task so that
+            // logging context continues
+            .instrument(span)
+        }
+    })
+    .await
+    .map_err(|error| {
+        tracing::error!(?error, "failed to create background task");
+        HttpCommonError::ServerError
+    })?;
+
+    let links = links
+        .into_iter()
+        .map(|link| LinkWithExtra {
+            link,
+            created_by: created_by.clone(),
+            last_modified_at: None,
+            last_modified_by: None,
+        })
+        .collect();
+
+    Ok((
+        StatusCode::CREATED,
+        Json(BulkCreateLinksResponse {
+            links,
+            task_id,
+            created_at,
+        }),
+    ))
+}
+
 /// Get link by ID
 ///
 /// Request a specific link by ID
@@ -120,7 +293,8 @@ pub async fn create(
     tag = LINK_TAG,
     path = "/box/{scope}/link/{link_id}",
     responses(
-        (status = 200, description = "Link obtained successfully", body = LinkWithExtra),
+        (status = 200, description = "Link obtained successfully", body = LinkResponse,
+            headers(("etag" = String, description = "Current version of the link"))),
         (status = 404, description = "Link not found", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
@@ -133,10 +307,20 @@ pub async fn create(
 #[tracing::instrument(skip_all, fields(%scope, %link_id))]
 pub async fn get(
     TenantDb(db): TenantDb,
+    Extension(website_service): Extension<Arc<ResolveWebsiteService>>,
     Path((scope, link_id)): Path<(DocumentBoxScope, LinkId)>,
-) -> HttpResult<LinkWithExtra> {
+) -> Result<(ETagHeader, Json<LinkResponse>), DynHttpError> {
     let DocumentBoxScope(scope) = scope;
 
+    // Version is queried before the link so the ETag is never newer than the data
+    let version = Link::version(&db, link_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query link version");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpLinkError::UnknownLink)?;
+
     let link = Link::find_with_extra(&db, &scope, link_id)
         .await
         // Failed to query link
@@ -147,7 +331,15 @@ pub async fn get(
         // Link not found
         .ok_or(HttpLinkError::UnknownLink)?;
 
-    Ok(Json(link))
+    let metadata_stale = website_service.is_metadata_stale(link.link.fetched_at);
+
+    Ok((
+        version_etag(version),
+        Json(LinkResponse {
+            link,
+            metadata_stale,
+        }),
+    ))
 }
 
 /// Get link website metadata
@@ -194,13 +386,76 @@ pub async fn get_metadata(
         og_description: resolved.og_description,
         favicon: resolved.best_favicon.is_some(),
         image: resolved.og_image.is_some(),
+        fetched_at: link.fetched_at,
+    }))
+}
+
+/// Refresh link website metadata
+///
+/// Forces the website metadata for the link to be fetched again from
+/// the website, replacing the stored metadata. The persisted website
+/// images are refreshed in the background
+#[utoipa::path(
+    post,
+    operation_id = "link_refresh_metadata",
+    tag = LINK_TAG,
+    path = "/box/{scope}/link/{link_id}/metadata/refresh",
+    responses(
+        (status = 200, description = "Refreshed link metadata successfully", body = LinkMetadataResponse),
+        (status = 404, description = "Link not found or failed to resolve metadata", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the link resides within"),
+        ("link_id" = Uuid, Path, description = "ID of the link to refresh"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %link_id))]
+pub async fn refresh_metadata(
+    TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
+    Extension(website_service): Extension<Arc<ResolveWebsiteService>>,
+    Path((scope, link_id)): Path<(DocumentBoxScope, LinkId)>,
+) -> HttpResult<LinkMetadataResponse> {
+    let DocumentBoxScope(scope) = scope;
+
+    let link = find_link(&db, &scope, link_id).await?;
+
+    let (link, resolved) = refresh_link_metadata(&db, &website_service, link)
+        .await
+        .map_err(|error| match error {
+            RefreshLinkMetadataError::ParseUrl(_) => {
+                DynHttpError::from(HttpLinkError::InvalidLinkUrl)
+            }
+            RefreshLinkMetadataError::FailedResolve => {
+                DynHttpError::from(HttpLinkError::FailedResolve)
+            }
+            RefreshLinkMetadataError::Database(_) => {
+                DynHttpError::from(HttpCommonError::ServerError)
+            }
+        })?;
+
+    let fetched_at = link.fetched_at;
+
+    // Replace the persisted images using the refreshed metadata
+    spawn_persist_link_images(db, storage, website_service, link, scope);
+
+    Ok(Json(LinkMetadataResponse {
+        title: resolved.title,
+        og_title: resolved.og_title,
+        og_description: resolved.og_description,
+        favicon: resolved.best_favicon.is_some(),
+        image: resolved.og_image.is_some(),
+        fetched_at,
     }))
 }
 
 /// Get link favicon
 ///
-/// Obtain the favicon image for the website that the link points to
-/// the image data is streamed directly from the target website
+/// Obtain the favicon image for the website that the link points to.
+/// Serves the persisted favicon when available, otherwise the image data
+/// is streamed directly from the target website
 #[utoipa::path(
     get,
     operation_id = "link_get_favicon",
@@ -220,6 +475,7 @@ pub async fn get_metadata(
 #[tracing::instrument(skip_all, fields(%scope, %link_id))]
 pub async fn get_favicon(
     TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
     Extension(website_service): Extension<Arc<ResolveWebsiteService>>,
     Path((scope, link_id)): Path<(DocumentBoxScope, LinkId)>,
 ) -> Result<Response<Body>, DynHttpError> {
@@ -227,6 +483,19 @@ pub async fn get_favicon(
 
     let link = find_link(&db, &scope, link_id).await?;
 
+    // Serve the persisted favicon when available
+    if let Some(response) = link_generated_raw_response(
+        &db,
+        &storage,
+        &scope,
+        link_id,
+        LinkGeneratedFileType::Favicon,
+    )
+    .await?
+    {
+        return Ok(response);
+    }
+
     let (url, website_metadata) = get_link_metadata(&db, &website_service, &link)
         .await
         .map_err(|error| match error {
@@ -244,14 +513,6 @@ pub async fn get_favicon(
 
     Ok(Response::builder()
         .header(header::CONTENT_TYPE, favicon.content_type.to_string())
-        .header(
-            header::CONTENT_SECURITY_POLICY,
-            "default-src 'none'; img-src 'self' data:;",
-        )
-        .header(
-            header::CACHE_CONTROL,
-            "public, max-age=3600, stale-while-revalidate=86400",
-        )
         .body(body)?)
 }
 
@@ -259,8 +520,8 @@ pub async fn get_favicon(
 ///
 /// Obtain the "Social Image" for the website, this resolves the website
 /// metadata and finds the OGP metadata image responding with the image
-/// directly. The image data is streamed directly from the target
-/// website
+/// directly. Serves the persisted image when available, otherwise the
+/// image data is streamed directly from the target website
 #[utoipa::path(
     get,
     operation_id = "link_get_image",
@@ -280,6 +541,7 @@ pub async fn get_favicon(
 #[tracing::instrument(skip_all, fields(%scope, %link_id))]
 pub async fn get_image(
     TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
     Extension(website_service): Extension<Arc<ResolveWebsiteService>>,
     Path((scope, link_id)): Path<(DocumentBoxScope, LinkId)>,
 ) -> Result<Response<Body>, DynHttpError> {
@@ -287,6 +549,19 @@ pub async fn get_image(
 
     let link = find_link(&db, &scope, link_id).await?;
 
+    // Serve the persisted image when available
+    if let Some(response) = link_generated_raw_response(
+        &db,
+        &storage,
+        &scope,
+        link_id,
+        LinkGeneratedFileType::SocialImage,
+    )
+    .await?
+    {
+        return Ok(response);
+    }
+
     let (url, website_metadata) = get_link_metadata(&db, &website_service, &link)
         .await
         .map_err(|error| match error {
@@ -305,89 +580,226 @@ pub async fn get_image(
 
     Ok(Response::builder()
         .header(header::CONTENT_TYPE, og_image.content_type.to_string())
-        .header(
-            header::CONTENT_SECURITY_POLICY,
-            "default-src 'none'; img-src 'self' data:;",
-        )
-        .header(
-            header::CACHE_CONTROL,
-            "public, max-age=3600, stale-while-revalidate=86400",
-        )
         .body(body)?)
 }
 
+/// Get link generated file
+///
+/// Requests metadata about a specific generated file type for a link,
+/// generated files are the website images persisted for the link
+#[utoipa::path(
+    get,
+    operation_id = "link_get_generated",
+    tag = LINK_TAG,
+    path = "/box/{scope}/link/{link_id}/generated/{type}",
+    responses(
+        (status = 200, description = "Obtained generated file successfully", body = LinkGeneratedFile),
+        (status = 404, description = "Generated file not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the link resides within"),
+        ("link_id" = Uuid, Path, description = "ID of the link to query"),
+        ("type" = LinkGeneratedFileType, Path, description = "Type of generated file to query"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %link_id, %generated_type))]
+pub async fn get_generated(
+    TenantDb(db): TenantDb,
+    Path((scope, link_id, generated_type)): Path<(DocumentBoxScope, LinkId, LinkGeneratedFileType)>,
+) -> HttpResult<LinkGeneratedFile> {
+    let DocumentBoxScope(scope) = scope;
+
+    let file = LinkGeneratedFile::find(&db, &scope, link_id, generated_type)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query link generated file");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpLinkError::NoMatchingGenerated)?;
+
+    Ok(Json(file))
+}
+
+/// Get link generated file raw
+///
+/// Request the contents of a specific generated file type for a link
+#[utoipa::path(
+    get,
+    operation_id = "link_get_generated_raw",
+    tag = LINK_TAG,
+    path = "/box/{scope}/link/{link_id}/generated/{type}/raw",
+    responses(
+        (status = 200, description = "Obtained raw file successfully", content_type = "application/octet-stream", body = BinaryResponse),
+        (status = 404, description = "Generated file not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the link resides within"),
+        ("link_id" = Uuid, Path, description = "ID of the link to query"),
+        ("type" = LinkGeneratedFileType, Path, description = "Type of generated file to query"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %link_id, %generated_type))]
+pub async fn get_generated_raw(
+    TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
+    Path((scope, link_id, generated_type)): Path<(DocumentBoxScope, LinkId, LinkGeneratedFileType)>,
+) -> Result<Response<Body>, DynHttpError> {
+    let DocumentBoxScope(scope) = scope;
+
+    let response = link_generated_raw_response(&db, &storage, &scope, link_id, generated_type)
+        .await?
+        .ok_or(HttpLinkError::NoMatchingGenerated)?;
+
+    Ok(response)
+}
+
+/// Creates a response streaming the persisted generated file of type `ty`
+/// for a link from storage, [None] when the link has no generated file
+/// of that type
+async fn link_generated_raw_response(
+    db: &DbPool,
+    storage: &StorageLayer,
+    scope: &DocumentBoxScopeRaw,
+    link_id: LinkId,
+    ty: LinkGeneratedFileType,
+) -> Result<Option<Response<Body>>, DynHttpError> {
+    let file = LinkGeneratedFile::find(db, scope, link_id, ty)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query link generated file");
+            HttpCommonError::ServerError
+        })?;
+
+    let Some(file) = file else {
+        return Ok(None);
+    };
+
+    let byte_stream = storage.get_file(&file.file_key).await.map_err(|error| {
+        tracing::error!(?error, "failed to get link generated file from storage");
+        HttpCommonError::ServerError
+    })?;
+
+    let body = axum::body::Body::from_stream(byte_stream);
+
+    Ok(Some(
+        Response::builder()
+            .header(header::CONTENT_TYPE, file.mime)
+            // Generated link files are only ever website images
+            .header(
+                header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; img-src 'self' data:;",
+            )
+            .body(body)?,
+    ))
+}
+
 /// Get link edit history
 ///
 /// Request the edit history for the provided link
+///
+/// Entries can be filtered by type, user and date range and are
+/// paginated using the offset and size query parameters or the
+/// cursor from a previous page
 #[utoipa::path(
     get,
     operation_id = "link_get_edit_history",
     tag = LINK_TAG,
     path = "/box/{scope}/link/{link_id}/edit-history",
     responses(
-        (status = 200, description = "Obtained edit history", body = [EditHistory]),
+        (status = 200, description = "Obtained edit history", body = Page<EditHistory>),
+        (status = 400, description = "Invalid page cursor", body = HttpErrorResponse),
         (status = 404, description = "Link not found", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
     params(
         ("scope" = DocumentBoxScope, Path, description = "Scope the link resides within"),
         ("link_id" = Uuid, Path, description = "ID of the link to request"),
-        TenantParams
+        TenantParams,
+        EditHistoryQuery
     )
 )]
-#[tracing::instrument(skip_all, fields(%scope, %link_id))]
+#[tracing::instrument(skip_all, fields(%scope, %link_id, ?query))]
 pub async fn get_edit_history(
     TenantDb(db): TenantDb,
     Path((scope, link_id)): Path<(DocumentBoxScope, LinkId)>,
-) -> HttpResult<Vec<EditHistory>> {
+    Query(query): Query<EditHistoryQuery>,
+) -> HttpResult<Page<EditHistory>> {
     let DocumentBoxScope(scope) = scope;
 
     // Ensure the link itself exists
     _ = find_link(&db, &scope, link_id).await?;
 
-    let history = EditHistory::all_by_link(&db, link_id)
-        .await
-        // Failed to query edit history
-        .map_err(|error| {
-            tracing::error!(?error, "failed to query link edit history");
-            HttpCommonError::ServerError
-        })?;
+    let offset = page_offset(query.cursor.as_deref(), query.offset)?;
+    let limit = query.size.map(u64::from).unwrap_or(DEFAULT_PAGE_SIZE);
+    let filter = query.filter();
 
-    Ok(Json(history))
+    let total_future = EditHistory::total_by_link(&db, link_id, &filter);
+    let history_future = EditHistory::query_by_link(&db, link_id, &filter, offset, limit);
+    let (total, history) = try_join!(total_future, history_future).map_err(|error| {
+        tracing::error!(?error, "failed to query link edit history");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(Json(Page::from_offset(history, total, offset)))
 }
 
 /// Update link
 ///
 /// Updates a link, can be a name change, value change, a folder move, or all
+///
+/// Providing the ETag from a previous request in the If-Match header
+/// rejects the update if the link was modified since
 #[utoipa::path(
     put,
     operation_id = "link_update",
     tag = LINK_TAG,
     path = "/box/{scope}/link/{link_id}",
     responses(
-        (status = 200, description = "Updated link successfully"),
+        (status = 200, description = "Updated link successfully",
+            headers(("etag" = String, description = "New version of the link"))),
+        (status = 400, description = "Invalid If-Match header", body = HttpErrorResponse),
         (status = 404, description = "Link not found", body = HttpErrorResponse),
+        (status = 409, description = "Link was modified since the If-Match version", body = HttpErrorResponse),
         (status = 500, description = "Internal server error", body = HttpErrorResponse)
     ),
     params(
         ("scope" = DocumentBoxScope, Path, description = "Scope the link resides within"),
         ("link_id" = Uuid, Path, description = "ID of the link to request"),
         TenantParams,
-        UserParams
+        UserParams,
+        IfMatchParams
     )
 )]
 #[tracing::instrument(skip_all, fields(%scope, %link_id, ?req))]
+#[allow(clippy::too_many_arguments)]
 pub async fn update(
     action_user: ActionUser,
+    IfMatch(expected_version): IfMatch,
     TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
     TenantSearch(search): TenantSearch,
+    Extension(website_service): Extension<Arc<ResolveWebsiteService>>,
     Path((scope, link_id)): Path<(DocumentBoxScope, LinkId)>,
     Garde(Json(req)): Garde<Json<UpdateLinkRequest>>,
-) -> HttpStatusResult {
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
     let DocumentBoxScope(scope) = scope;
 
     let link = find_link(&db, &scope, link_id).await?;
 
+    // Link with the updated value, used to refresh the persisted images
+    let updated_value_link = req
+        .value
+        .clone()
+        .filter(|value| value.ne(&link.value))
+        .map(|value| Link {
+            value,
+            ..link.clone()
+        });
+
     // Update stored editing user data
     let user = action_user.store_user(&db).await?;
     let user_id = user.as_ref().map(|value| value.id.to_string());
@@ -397,18 +809,235 @@ pub async fn update(
         name: req.name,
         value: req.value,
         pinned: req.pinned,
+        expected_version,
+    };
+
+    let version =
+        docbox_core::links::update_link::update_link(&db, &search, &scope, link, user_id, update)
+            .await
+            .map_err(|error| match error {
+                UpdateLinkError::UnknownTargetFolder => {
+                    DynHttpError::from(HttpFolderError::UnknownTargetFolder)
+                }
+                UpdateLinkError::VersionConflict { .. } => {
+                    DynHttpError::from(HttpLinkError::VersionConflict)
+                }
+                _ => DynHttpError::from(HttpCommonError::ServerError),
+            })?;
+
+    // Website images are for the previous value and must be refreshed
+    if let Some(link) = updated_value_link {
+        spawn_persist_link_images(db, storage, website_service, link, scope);
+    }
+
+    Ok((StatusCode::OK, version_etag(version)))
+}
+
+/// Pin link
+///
+/// Pins a link, pinned items are included in the pinned items
+/// of the document box. Pinning an already pinned link keeps the
+/// original pin time
+///
+/// Providing the ETag from a previous request in the If-Match header
+/// rejects the change if the link was modified since
+#[utoipa::path(
+    put,
+    operation_id = "link_pin",
+    tag = LINK_TAG,
+    path = "/box/{scope}/link/{link_id}/pin",
+    responses(
+        (status = 200, description = "Pinned link successfully",
+            headers(("etag" = String, description = "New version of the link"))),
+        (status = 400, description = "Invalid If-Match header", body = HttpErrorResponse),
+        (status = 404, description = "Link not found", body = HttpErrorResponse),
+        (status = 409, description = "Link was modified since the If-Match version", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the link resides within"),
+        ("link_id" = Uuid, Path, description = "ID of the link to pin"),
+        TenantParams,
+        UserParams,
+        IfMatchParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %link_id))]
+pub async fn pin(
+    action_user: ActionUser,
+    IfMatch(expected_version): IfMatch,
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    Path((scope, link_id)): Path<(DocumentBoxScope, LinkId)>,
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
+    set_pinned(
+        action_user,
+        expected_version,
+        &db,
+        &search,
+        scope,
+        link_id,
+        true,
+    )
+    .await
+}
+
+/// Unpin link
+///
+/// Unpins a link, removing it from the pinned items of the document box
+///
+/// Providing the ETag from a previous request in the If-Match header
+/// rejects the change if the link was modified since
+#[utoipa::path(
+    delete,
+    operation_id = "link_unpin",
+    tag = LINK_TAG,
+    path = "/box/{scope}/link/{link_id}/pin",
+    responses(
+        (status = 200, description = "Unpinned link successfully",
+            headers(("etag" = String, description = "New version of the link"))),
+        (status = 400, description = "Invalid If-Match header", body = HttpErrorResponse),
+        (status = 404, description = "Link not found", body = HttpErrorResponse),
+        (status = 409, description = "Link was modified since the If-Match version", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the link resides within"),
+        ("link_id" = Uuid, Path, description = "ID of the link to unpin"),
+        TenantParams,
+        UserParams,
+        IfMatchParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %link_id))]
+pub async fn unpin(
+    action_user: ActionUser,
+    IfMatch(expected_version): IfMatch,
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    Path((scope, link_id)): Path<(DocumentBoxScope, LinkId)>,
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
+    set_pinned(
+        action_user,
+        expected_version,
+        &db,
+        &search,
+        scope,
+        link_id,
+        false,
+    )
+    .await
+}
+
+/// Set the pinned state of a link, the change is recorded in the
+/// edit history of the link
+async fn set_pinned(
+    action_user: ActionUser,
+    expected_version: Option<i64>,
+    db: &DbPool,
+    search: &TenantSearchIndex,
+    DocumentBoxScope(scope): DocumentBoxScope,
+    link_id: LinkId,
+    pinned: bool,
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
+    let link = find_link(db, &scope, link_id).await?;
+
+    // Update stored editing user data
+    let user = action_user.store_user(db).await?;
+    let user_id = user.as_ref().map(|value| value.id.to_string());
+
+    let update = UpdateLink {
+        pinned: Some(pinned),
+        expected_version,
+        ..Default::default()
     };
 
-    docbox_core::links::update_link::update_link(&db, &search, &scope, link, user_id, update)
+    let version =
+        docbox_core::links::update_link::update_link(db, search, &scope, link, user_id, update)
+            .await
+            .map_err(|error| match error {
+                UpdateLinkError::VersionConflict { .. } => {
+                    DynHttpError::from(HttpLinkError::VersionConflict)
+                }
+                _ => DynHttpError::from(HttpCommonError::ServerError),
+            })?;
+
+    Ok((StatusCode::OK, version_etag(version)))
+}
+
+/// Revert link edit history entry
+///
+/// Reverts the change recorded by an edit history entry for the link by
+/// applying the inverse change (i.e renaming back or moving back). The revert
+/// is recorded in the edit history as a new change
+///
+/// Providing the ETag from a previous request in the If-Match header
+/// rejects the revert if the link was modified since
+#[utoipa::path(
+    post,
+    operation_id = "link_revert_edit_history",
+    tag = LINK_TAG,
+    path = "/box/{scope}/link/{link_id}/edit-history/{entry_id}/revert",
+    responses(
+        (status = 200, description = "Reverted the change successfully",
+            headers(("etag" = String, description = "New version of the link"))),
+        (status = 400, description = "Edit history entry cannot be reverted or invalid If-Match header", body = HttpErrorResponse),
+        (status = 404, description = "Link or edit history entry not found", body = HttpErrorResponse),
+        (status = 409, description = "Link was modified since the If-Match version", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the link resides within"),
+        ("link_id" = Uuid, Path, description = "ID of the link to revert"),
+        ("entry_id" = Uuid, Path, description = "ID of the edit history entry to revert"),
+        TenantParams,
+        UserParams,
+        IfMatchParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %link_id, %entry_id))]
+pub async fn revert_edit_history(
+    action_user: ActionUser,
+    IfMatch(expected_version): IfMatch,
+    TenantDb(db): TenantDb,
+    TenantSearch(search): TenantSearch,
+    Path((scope, link_id, entry_id)): Path<(DocumentBoxScope, LinkId, EditHistoryId)>,
+) -> Result<(StatusCode, ETagHeader), DynHttpError> {
+    let DocumentBoxScope(scope) = scope;
+
+    let link = find_link(&db, &scope, link_id).await?;
+
+    let entry = EditHistory::find_by_link(&db, link_id, entry_id)
         .await
-        .map_err(|error| match error {
-            UpdateLinkError::UnknownTargetFolder => {
-                DynHttpError::from(HttpFolderError::UnknownTargetFolder)
-            }
-            _ => DynHttpError::from(HttpCommonError::ServerError),
-        })?;
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query link history entry");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpEditHistoryError::UnknownEntry)?;
+
+    let update = UpdateLink {
+        expected_version,
+        ..UpdateLink::revert(&entry.metadata).ok_or(HttpEditHistoryError::CannotRevert)?
+    };
 
-    Ok(StatusCode::OK)
+    // Update stored editing user data
+    let user = action_user.store_user(&db).await?;
+    let user_id = user.as_ref().map(|value| value.id.to_string());
+
+    let version =
+        docbox_core::links::update_link::update_link(&db, &search, &scope, link, user_id, update)
+            .await
+            .map_err(|error| match error {
+                UpdateLinkError::UnknownTargetFolder => {
+                    DynHttpError::from(HttpFolderError::UnknownTargetFolder)
+                }
+                UpdateLinkError::VersionConflict { .. } => {
+                    DynHttpError::from(HttpLinkError::VersionConflict)
+                }
+                _ => DynHttpError::from(HttpCommonError::ServerError),
+            })?;
+
+    Ok((StatusCode::OK, version_etag(version)))
 }
 
 /// Delete a link by ID
@@ -433,6 +1062,7 @@ pub async fn update(
 #[tracing::instrument(skip_all, fields(%scope, %link_id))]
 pub async fn delete(
     TenantDb(db): TenantDb,
+    TenantStorage(storage): TenantStorage,
     TenantSearch(search): TenantSearch,
     TenantEvents(events): TenantEvents,
     Path((scope, link_id)): Path<(DocumentBoxScope, LinkId)>,
@@ -441,7 +1071,7 @@ pub async fn delete(
 
     let link = find_link(&db, &scope, link_id).await?;
 
-    delete_link(&db, &search, &events, link, scope)
+    delete_link(&db, &storage, &search, &events, link, scope)
         .await
         .map_err(|error| {
             tracing::error!(?error, "failed to delete folder");
@@ -451,6 +1081,85 @@ pub async fn delete(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Record link visit
+///
+/// Records that the link was opened, used to track how often links are
+/// visited for the most visited links listing and search ranking
+#[utoipa::path(
+    post,
+    operation_id = "link_visit",
+    tag = LINK_TAG,
+    path = "/box/{scope}/link/{link_id}/visit",
+    responses(
+        (status = 200, description = "Recorded visit successfully", body = LinkVisitStats),
+        (status = 404, description = "Link not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the link resides within"),
+        ("link_id" = Uuid, Path, description = "ID of the link that was visited"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %link_id))]
+pub async fn visit(
+    TenantDb(db): TenantDb,
+    Path((scope, link_id)): Path<(DocumentBoxScope, LinkId)>,
+) -> HttpResult<LinkVisitStats> {
+    let DocumentBoxScope(scope) = scope;
+
+    let link = find_link(&db, &scope, link_id).await?;
+
+    let stats = LinkVisitStats::record_visit(&db, link.id, Utc::now())
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to record link visit");
+            HttpCommonError::ServerError
+        })?
+        // Link was deleted before the visit was recorded
+        .ok_or(HttpLinkError::UnknownLink)?;
+
+    Ok(Json(stats))
+}
+
+/// Get most visited links
+///
+/// Requests a page of the most visited links within the document box,
+/// links that have never been visited are not included
+#[utoipa::path(
+    get,
+    operation_id = "link_get_most_visited",
+    tag = LINK_TAG,
+    path = "/box/{scope}/link/most-visited",
+    responses(
+        (status = 200, description = "Obtained most visited links successfully", body = MostVisitedLinksResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope of the document box"),
+        MostVisitedLinksQuery,
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, ?query))]
+pub async fn get_most_visited(
+    TenantDb(db): TenantDb,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+    Query(query): Query<MostVisitedLinksQuery>,
+) -> HttpResult<MostVisitedLinksResponse> {
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.size.unwrap_or(20).min(100);
+
+    let results = LinkVisitStats::most_visited(&db, &scope, offset, limit as u64)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query most visited links");
+            HttpCommonError::ServerError
+        })?;
+
+    Ok(Json(MostVisitedLinksResponse { results }))
+}
+
 /// Resolves a link handles mapping the various link failure
 /// errors into HTTP errors
 async fn find_link(
@@ -470,3 +1179,26 @@ async fn find_link(
 
     Ok(link)
 }
+
+/// Resolves and persists the website images for a link in the background
+/// so that they can be served without scraping the website on request
+fn spawn_persist_link_images(
+    db: DbPool,
+    storage: StorageLayer,
+    website_service: Arc<ResolveWebsiteService>,
+    link: Link,
+    scope: DocumentBoxScopeRaw,
+) {
+    let span = tracing::Span::current();
+
+    tokio::spawn(
+        async move {
+            if let Err(error) =
+                persist_link_images(&db, &storage, &website_service, &link, &scope).await
+            {
+                tracing::warn!(?error, "failed to persist link images");
+            }
+        }
+        .instrument(span),
+    );
+}
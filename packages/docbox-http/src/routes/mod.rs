@@ -1,40 +1,64 @@
 use axum::{
     Router,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
 };
 
 use crate::error::{HttpCommonError, HttpStatusResult};
 
-use super::middleware::tenant::tenant_auth_middleware;
+use super::middleware::{
+    archived::archived_document_box_middleware,
+    correlation_id::correlation_id_middleware,
+    maintenance::tenant_maintenance_middleware,
+    request_usage::RequestUsageLayer,
+    security_headers::{RouteClass, SecurityHeadersLayer},
+    tenant::tenant_auth_middleware,
+};
 
 pub mod admin;
 pub mod document_box;
 pub mod file;
 pub mod folder;
+pub mod inbound_email;
 pub mod link;
 pub mod task;
 pub mod utils;
+pub mod webhook;
 
 pub fn router<
     const DIRECT_FILE_UPLOAD: bool,
     const REPROCESS_OCTET_STREAM_FILES: bool,
+    const REPROCESS_OUTDATED_FILES: bool,
     const REBUILD_SEARCH_INDEX: bool,
 >() -> Router {
     Router::new()
         .nest(
             "/admin",
-            admin_router::<REPROCESS_OCTET_STREAM_FILES, REBUILD_SEARCH_INDEX>(),
+            admin_router::<
+                REPROCESS_OCTET_STREAM_FILES,
+                REPROCESS_OUTDATED_FILES,
+                REBUILD_SEARCH_INDEX,
+            >(),
         )
         .nest("/box", document_box_router::<DIRECT_FILE_UPLOAD>())
         .route("/options", get(utils::get_options))
         .route("/health", get(utils::health))
+        .route("/health/ready", get(utils::health_ready))
         .route("/server-details", get(utils::server_details))
+        .route("/docs/openapi.json", get(utils::openapi_docs))
         .route("/webhook/s3", post(utils::webhook_s3))
+        .route("/webhook/ses", post(utils::webhook_ses))
+        // Layer applying the default response headers, routes serving content
+        // apply the headers for their own route class first
+        .layer(SecurityHeadersLayer::new(RouteClass::Api))
+        .layer(axum::middleware::from_fn(correlation_id_middleware))
 }
 
 /// Routes for /admin/
-pub fn admin_router<const REPROCESS_OCTET_STREAM_FILES: bool, const REBUILD_SEARCH_INDEX: bool>()
--> Router {
+pub fn admin_router<
+    const REPROCESS_OCTET_STREAM_FILES: bool,
+    const REPROCESS_OUTDATED_FILES: bool,
+    const REBUILD_SEARCH_INDEX: bool,
+>() -> Router {
     let rebuild_search_index_tenant = if REBUILD_SEARCH_INDEX {
         post(admin::rebuild_search_index_tenant)
     } else {
@@ -47,10 +71,33 @@ pub fn admin_router<const REPROCESS_OCTET_STREAM_FILES: bool, const REBUILD_SEAR
         post(unsupported)
     };
 
+    let reprocess_outdated_files_tenant = if REPROCESS_OUTDATED_FILES {
+        post(admin::reprocess_outdated_files_tenant)
+    } else {
+        post(unsupported)
+    };
+
     Router::new()
         // Routes that target the server as a whole
         .route("/flush-db-cache", post(admin::flush_database_pool_cache))
+        .route("/db-cache-stats", get(admin::database_pool_cache_stats))
+        .route("/db-pools", get(admin::database_pools))
+        .route(
+            "/db-pools/{tenant_id}",
+            delete(admin::invalidate_database_pool),
+        )
         .route("/flush-tenant-cache", post(admin::flush_tenant_cache))
+        .route("/tenant-cache-stats", get(admin::tenant_cache_stats))
+        .route(
+            "/tenant-cache/{tenant_id}",
+            delete(admin::invalidate_tenant_cache),
+        )
+        .route(
+            "/flush-search-credentials",
+            post(admin::flush_search_credentials),
+        )
+        .route("/reload-config", post(admin::reload_config))
+        .route("/request-usage", get(admin::request_usage))
         .route(
             "/purge-expired-presigned-tasks",
             post(admin::http_purge_expired_presigned_tasks),
@@ -59,19 +106,75 @@ pub fn admin_router<const REPROCESS_OCTET_STREAM_FILES: bool, const REBUILD_SEAR
         .merge(
             Router::new()
                 .route("/tenant-stats", get(admin::tenant_stats))
+                .route("/processing-stats", get(admin::processing_stats))
+                .route(
+                    "/maintenance",
+                    get(admin::get_maintenance_mode).put(admin::set_maintenance_mode),
+                )
+                .route(
+                    "/active-content-policy",
+                    get(admin::get_active_content_policy).put(admin::set_active_content_policy),
+                )
                 .route("/rebuild-search-index", rebuild_search_index_tenant)
                 .route("/boxes", post(admin::tenant_boxes))
+                .route("/boxes/{scope}/archive", post(admin::archive_document_box))
+                .route(
+                    "/boxes/{scope}/unarchive",
+                    post(admin::unarchive_document_box),
+                )
+                .route("/scopes", post(admin::tenant_scopes))
+                .route(
+                    "/scope-patterns",
+                    get(admin::get_scope_patterns).post(admin::create_scope_pattern),
+                )
+                .route(
+                    "/scope-patterns/{pattern}",
+                    delete(admin::delete_scope_pattern),
+                )
+                .route(
+                    "/event-payloads/{payload_id}",
+                    get(admin::get_event_payload),
+                )
+                .route("/presigned-tasks", post(admin::tenant_presigned_tasks))
+                .route(
+                    "/presigned-tasks/{task_id}/retry",
+                    post(admin::retry_presigned_task),
+                )
+                .route(
+                    "/presigned-tasks/{task_id}/fail",
+                    post(admin::fail_presigned_task),
+                )
+                .route("/tasks/stuck", get(admin::stuck_tasks))
+                .route("/tasks/counts", get(admin::task_counts))
+                .route("/tasks/{task_id}/fail", post(admin::fail_task))
+                .route("/file-access-report", post(admin::file_access_report))
                 .route("/search", post(admin::search_tenant))
+                .route("/search/export", post(admin::export_search_tenant))
+                .route(
+                    "/generated-file-policies",
+                    get(admin::get_generated_file_policies).put(admin::set_generated_file_policies),
+                )
+                .route(
+                    "/upload-rules",
+                    get(admin::get_upload_rules).put(admin::set_upload_rules),
+                )
+                .route(
+                    "/mime-overrides",
+                    get(admin::get_mime_overrides).put(admin::set_mime_overrides),
+                )
                 .route(
                     "/reprocess_octet_stream_files_tenant",
                     reprocess_octet_stream_files_tenant,
                 )
+                .route("/reprocess-outdated-files", reprocess_outdated_files_tenant)
                 .nest(
                     "/users",
                     Router::new()
                         .route("/", post(admin::list_users))
                         .route("/{id}", delete(admin::delete_user)),
                 )
+                // Layer to meter the request usage of the tenant
+                .layer(RequestUsageLayer)
                 .layer(axum::middleware::from_fn(tenant_auth_middleware)),
         )
 }
@@ -85,12 +188,40 @@ pub fn document_box_router<const DIRECT_FILE_UPLOAD: bool>() -> Router {
             Router::new()
                 .route("/", get(document_box::get).delete(document_box::delete))
                 .route("/stats", get(document_box::stats))
+                .route("/pii-report", get(document_box::pii_report))
+                .route("/pinned", get(document_box::pinned))
+                .route(
+                    "/import",
+                    if DIRECT_FILE_UPLOAD {
+                        post(document_box::import)
+                    } else {
+                        post(unsupported)
+                    },
+                )
                 .route("/search", post(document_box::search))
+                .route("/suggest", post(document_box::suggest))
+                .route(
+                    "/search/recent",
+                    get(document_box::recent_searches).delete(document_box::clear_recent_searches),
+                )
                 .nest("/file", file_router::<DIRECT_FILE_UPLOAD>())
                 .nest("/task", task_router())
                 .nest("/link", link_router())
-                .nest("/folder", folder_router()),
+                .nest("/folder", folder_router())
+                .nest("/webhook", webhook_router())
+                .route(
+                    "/inbound-email",
+                    get(inbound_email::get)
+                        .post(inbound_email::create)
+                        .delete(inbound_email::delete),
+                )
+                // Layer to reject modifications to archived document boxes
+                .route_layer(axum::middleware::from_fn(archived_document_box_middleware)),
         )
+        // Layer to reject modifications while the tenant is in maintenance mode
+        .route_layer(axum::middleware::from_fn(tenant_maintenance_middleware))
+        // Layer to meter the request usage of the tenant
+        .layer(RequestUsageLayer)
         // Layer to authorize requests
         .layer(axum::middleware::from_fn(tenant_auth_middleware))
 }
@@ -104,7 +235,20 @@ pub fn folder_router() -> Router {
                 "/",
                 get(folder::get).put(folder::update).delete(folder::delete),
             )
+            .route("/children", get(folder::get_children))
             .route("/edit-history", get(folder::get_edit_history))
+            .route(
+                "/edit-history/{entry_id}/revert",
+                post(folder::revert_edit_history),
+            )
+            .route("/move-scope", post(folder::move_to_scope))
+            .route("/pin", put(folder::pin).delete(folder::unpin))
+            .route(
+                "/processing-config",
+                get(folder::get_processing_config)
+                    .put(folder::set_processing_config)
+                    .delete(folder::delete_processing_config),
+            )
             .route("/zip", post(folder::create_zip)),
     )
 }
@@ -120,6 +264,14 @@ pub fn file_router<const DIRECT_FILE_UPLOAD: bool>() -> Router {
                 post(unsupported)
             },
         )
+        .route(
+            "/multiple",
+            if DIRECT_FILE_UPLOAD {
+                post(file::upload_multiple)
+            } else {
+                post(unsupported)
+            },
+        )
         .nest(
             "/presigned",
             Router::new()
@@ -130,31 +282,76 @@ pub fn file_router<const DIRECT_FILE_UPLOAD: bool>() -> Router {
             "/{file_id}",
             Router::new()
                 .route("/", get(file::get).put(file::update).delete(file::delete))
-                .route("/raw", get(file::get_raw))
+                .route(
+                    "/raw",
+                    get(file::get_raw).layer(SecurityHeadersLayer::new(RouteClass::Raw)),
+                )
                 .route("/raw-presigned", post(file::get_raw_presigned))
+                .route("/email", get(file::get_emails).post(file::email))
                 // Named access endpoint, allows specifying some file name after the URL
                 // (Used to work around a Chromium bug which makes inline viewers not respect the filename)
-                .route("/raw/{*name}", get(file::get_raw_named))
+                .route(
+                    "/raw/{*name}",
+                    get(file::get_raw_named).layer(SecurityHeadersLayer::new(RouteClass::Raw)),
+                )
+                .route(
+                    "/preview",
+                    get(file::get_preview).layer(SecurityHeadersLayer::new(RouteClass::Preview)),
+                )
                 .route("/children", get(file::get_children))
                 .route("/edit-history", get(file::get_edit_history))
+                .route(
+                    "/edit-history/{entry_id}/revert",
+                    post(file::revert_edit_history),
+                )
+                .route("/move-scope", post(file::move_to_scope))
+                .route("/pin", put(file::pin).delete(file::unpin))
                 .route("/search", post(file::search))
                 // Generated file instance
                 .nest(
                     "/generated",
-                    Router::new().nest(
-                        "/{generated_type}",
-                        Router::new()
-                            .route("/", get(file::get_generated))
-                            .route("/raw", get(file::get_generated_raw))
-                            .route("/raw-presigned", post(file::get_generated_raw_presigned))
-                            // Named access endpoint, allows specifying some file name after the URL
-                            // (Used to work around a Chromium bug which makes inline viewers not respect the filename)
-                            .route("/raw/{*name}", get(file::get_generated_raw_named)),
-                    ),
+                    Router::new()
+                        .route("/previews", get(file::get_page_previews))
+                        .route(
+                            "/previews/{page}/raw",
+                            get(file::get_page_preview_raw)
+                                .layer(SecurityHeadersLayer::new(RouteClass::Generated)),
+                        )
+                        .route(
+                            "/tables/{table}/raw",
+                            get(file::get_table_csv_raw)
+                                .layer(SecurityHeadersLayer::new(RouteClass::Generated)),
+                        )
+                        .nest(
+                            "/{generated_type}",
+                            Router::new()
+                                .route("/", get(file::get_generated))
+                                .route(
+                                    "/raw",
+                                    get(file::get_generated_raw)
+                                        .layer(SecurityHeadersLayer::new(RouteClass::Generated)),
+                                )
+                                .route("/raw-presigned", post(file::get_generated_raw_presigned))
+                                .route("/regenerate", post(file::regenerate_generated))
+                                // Named access endpoint, allows specifying some file name after the URL
+                                // (Used to work around a Chromium bug which makes inline viewers not respect the filename)
+                                .route(
+                                    "/raw/{*name}",
+                                    get(file::get_generated_raw_named)
+                                        .layer(SecurityHeadersLayer::new(RouteClass::Generated)),
+                                ),
+                        ),
                 ),
         )
 }
 
+/// Routes for /box/:scope/webhook/
+pub fn webhook_router() -> Router {
+    Router::new()
+        .route("/", get(webhook::get_all).post(webhook::create))
+        .route("/{webhook_id}", delete(webhook::delete))
+}
+
 /// Routes for /box/:scope/task/
 pub fn task_router() -> Router {
     Router::new().nest("/{task_id}", Router::new().route("/", get(task::get)))
@@ -162,15 +359,38 @@ pub fn task_router() -> Router {
 
 /// Routes for /box/:scope/link/
 pub fn link_router() -> Router {
-    Router::new().route("/", post(link::create)).nest(
-        "/{link_id}",
-        Router::new()
-            .route("/", get(link::get).put(link::update).delete(link::delete))
-            .route("/metadata", get(link::get_metadata))
-            .route("/favicon", get(link::get_favicon))
-            .route("/image", get(link::get_image))
-            .route("/edit-history", get(link::get_edit_history)),
-    )
+    Router::new()
+        .route("/", post(link::create))
+        .route("/bulk", post(link::bulk_create))
+        .route("/most-visited", get(link::get_most_visited))
+        .nest(
+            "/{link_id}",
+            Router::new()
+                .route("/", get(link::get).put(link::update).delete(link::delete))
+                .route("/metadata", get(link::get_metadata))
+                .route("/metadata/refresh", post(link::refresh_metadata))
+                .route(
+                    "/favicon",
+                    get(link::get_favicon).layer(SecurityHeadersLayer::new(RouteClass::Website)),
+                )
+                .route(
+                    "/image",
+                    get(link::get_image).layer(SecurityHeadersLayer::new(RouteClass::Website)),
+                )
+                .route("/generated/{generated_type}", get(link::get_generated))
+                .route(
+                    "/generated/{generated_type}/raw",
+                    get(link::get_generated_raw)
+                        .layer(SecurityHeadersLayer::new(RouteClass::Generated)),
+                )
+                .route("/edit-history", get(link::get_edit_history))
+                .route(
+                    "/edit-history/{entry_id}/revert",
+                    post(link::revert_edit_history),
+                )
+                .route("/pin", put(link::pin).delete(link::unpin))
+                .route("/visit", post(link::visit)),
+        )
 }
 
 /// Fallback handler for routes that are unsupported
@@ -1,12 +1,21 @@
 use crate::{
+    docs::ApiDoc,
     error::{DynHttpError, HttpCommonError},
     extensions::{max_file_size::MaxFileSizeBytes, server_version::ServerVersion},
-    models::{document_box::DocumentBoxOptions, utils::DocboxServerResponse},
+    models::{
+        document_box::DocumentBoxOptions,
+        utils::{DocboxServerResponse, HealthReadyResponse},
+    },
 };
 use axum::{Extension, Json, http::StatusCode};
-use docbox_core::notifications::{
-    MpscNotificationQueueSender, NotificationQueueMessage, parse_bucket_message,
+use docbox_core::{
+    notifications::{
+        MpscNotificationQueueSender, NotificationQueueMessage, parse_bucket_message,
+        parse_inbound_email_message,
+    },
+    processing::{ProcessingLayer, office::circuit_breaker::CircuitState},
 };
+use utoipa::OpenApi;
 
 pub const UTILS_TAG: &str = "Utils";
 
@@ -28,6 +37,27 @@ pub async fn server_details(
     Json(DocboxServerResponse { version })
 }
 
+/// OpenAPI documentation
+///
+/// Request the OpenAPI documentation for the API, the version of the
+/// documentation matches the version of the server
+#[utoipa::path(
+    get,
+    operation_id = "openapi_docs",
+    tag = UTILS_TAG,
+    path = "/docs/openapi.json",
+    responses(
+        (status = 200, description = "Got the OpenAPI documentation successfully", content_type = "application/json")
+    )
+)]
+pub async fn openapi_docs(
+    Extension(ServerVersion(version)): Extension<ServerVersion>,
+) -> Json<utoipa::openapi::OpenApi> {
+    let mut docs = ApiDoc::openapi();
+    docs.info.version = version.to_string();
+    Json(docs)
+}
+
 /// Health check
 ///
 /// Check that the server is running using this endpoint
@@ -44,6 +74,43 @@ pub async fn health() -> StatusCode {
     StatusCode::OK
 }
 
+/// Readiness check
+///
+/// Check that the server is ready to process requests, reports the server
+/// as unavailable while the office converter circuit breaker is open
+#[utoipa::path(
+    get,
+    operation_id = "health_ready",
+    tag = UTILS_TAG,
+    path = "/health/ready",
+    responses(
+        (status = 200, description = "Server is ready", body = HealthReadyResponse),
+        (status = 503, description = "Office converter is unavailable", body = HealthReadyResponse)
+    )
+)]
+pub async fn health_ready(
+    Extension(processing): Extension<ProcessingLayer>,
+) -> (StatusCode, Json<HealthReadyResponse>) {
+    let settings = processing.config.convert_circuit_breaker();
+    let office_converter = processing.office.circuit_breaker.status(&settings);
+
+    // Half-open circuits are ready, the next conversion probes for recovery
+    let ready = office_converter.state != CircuitState::Open;
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(HealthReadyResponse {
+            ready,
+            office_converter,
+        }),
+    )
+}
+
 /// Get options
 ///
 /// Requests options and settings from docbox
@@ -57,9 +124,11 @@ pub async fn health() -> StatusCode {
     )
 )]
 pub async fn get_options(
-    Extension(MaxFileSizeBytes(max_file_size)): Extension<MaxFileSizeBytes>,
+    Extension(max_file_size): Extension<MaxFileSizeBytes>,
 ) -> Json<DocumentBoxOptions> {
-    Json(DocumentBoxOptions { max_file_size })
+    Json(DocumentBoxOptions {
+        max_file_size: max_file_size.get(),
+    })
 }
 
 /// POST /webhook/s3
@@ -88,3 +157,27 @@ pub async fn webhook_s3(
 
     Ok(StatusCode::OK)
 }
+
+/// POST /webhook/ses
+///
+/// Internal endpoint for handling received email notifications from a webhook
+pub async fn webhook_ses(
+    maybe_tx: Option<Extension<MpscNotificationQueueSender>>,
+    Json(req): Json<serde_json::Value>,
+) -> Result<StatusCode, DynHttpError> {
+    let Extension(tx) = maybe_tx
+        // Should not be calling this endpoint when not using the mpsc notification queue
+        .ok_or(HttpCommonError::ServerError)?;
+
+    tracing::debug!("got webhook ses event");
+
+    let email = parse_inbound_email_message(&req).ok_or_else(|| {
+        tracing::warn!("failed to handle webhook ses event");
+        HttpCommonError::ServerError
+    })?;
+
+    tx.send(NotificationQueueMessage::EmailReceived(email))
+        .await;
+
+    Ok(StatusCode::OK)
+}
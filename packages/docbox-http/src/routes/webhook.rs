@@ -0,0 +1,164 @@
+//! Document box webhook related endpoints
+
+use crate::{
+    error::{DynHttpError, HttpCommonError, HttpErrorResponse, HttpResult, HttpStatusResult},
+    middleware::tenant::{TenantDb, TenantParams},
+    models::{
+        document_box::{DocumentBoxScope, HttpDocumentBoxError},
+        webhook::{CreateWebhookRequest, HttpWebhookError},
+    },
+};
+use axum::{Extension, Json, extract::Path, http::StatusCode};
+use axum_valid::Garde;
+use docbox_core::{
+    database::models::{
+        document_box::DocumentBox,
+        document_box_webhook::{
+            CreateDocumentBoxWebhook, DocumentBoxWebhook, DocumentBoxWebhookId,
+        },
+    },
+    events::{EventPublisherFactory, TENANT_EVENT_NAMES},
+};
+
+pub const WEBHOOK_TAG: &str = "Webhook";
+
+/// Create webhook
+///
+/// Registers a webhook that receives the events occurring within the
+/// document box. Deliveries are signed using the provided secret
+#[utoipa::path(
+    post,
+    operation_id = "webhook_create",
+    tag = WEBHOOK_TAG,
+    path = "/box/{scope}/webhook",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook created successfully", body = DocumentBoxWebhook),
+        (status = 400, description = "Invalid url or unknown event", body = HttpErrorResponse),
+        (status = 404, description = "Document box not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope to create the webhook within"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope))]
+pub async fn create(
+    TenantDb(db): TenantDb,
+    Extension(events): Extension<EventPublisherFactory>,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+    Garde(Json(req)): Garde<Json<CreateWebhookRequest>>,
+) -> Result<(StatusCode, Json<DocumentBoxWebhook>), DynHttpError> {
+    // Webhooks must not target internal addresses
+    if !events.is_allowed_webhook_url(&req.url).await {
+        return Err(HttpWebhookError::InvalidUrl.into());
+    }
+
+    if let Some(event) = req
+        .events
+        .iter()
+        .find(|event| !TENANT_EVENT_NAMES.contains(&event.as_str()))
+    {
+        return Err(HttpWebhookError::UnknownEvent(event.clone()).into());
+    }
+
+    DocumentBox::find_by_scope(&db, &scope)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query document box");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpDocumentBoxError::UnknownDocumentBox)?;
+
+    let webhook = DocumentBoxWebhook::create(
+        &db,
+        CreateDocumentBoxWebhook {
+            document_box: scope,
+            url: req.url,
+            secret: req.secret,
+            events: req.events,
+        },
+    )
+    .await
+    .map_err(|error| {
+        tracing::error!(?error, "failed to create webhook");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+/// Get webhooks
+///
+/// Get all the webhooks registered within the document box
+#[utoipa::path(
+    get,
+    operation_id = "webhook_get_all",
+    tag = WEBHOOK_TAG,
+    path = "/box/{scope}/webhook",
+    responses(
+        (status = 200, description = "Webhooks obtained successfully", body = [DocumentBoxWebhook]),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the webhooks reside within"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope))]
+pub async fn get_all(
+    TenantDb(db): TenantDb,
+    Path(DocumentBoxScope(scope)): Path<DocumentBoxScope>,
+) -> HttpResult<Vec<DocumentBoxWebhook>> {
+    let webhooks = DocumentBoxWebhook::all_by_scope(&db, &scope)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query webhooks");
+            HttpCommonError::ServerError
+        })?;
+
+    Ok(Json(webhooks))
+}
+
+/// Delete webhook
+///
+/// Deletes a specific webhook using its ID
+#[utoipa::path(
+    delete,
+    operation_id = "webhook_delete",
+    tag = WEBHOOK_TAG,
+    path = "/box/{scope}/webhook/{webhook_id}",
+    responses(
+        (status = 204, description = "Deleted webhook successfully"),
+        (status = 404, description = "Webhook not found", body = HttpErrorResponse),
+        (status = 500, description = "Internal server error", body = HttpErrorResponse)
+    ),
+    params(
+        ("scope" = DocumentBoxScope, Path, description = "Scope the webhook resides within"),
+        ("webhook_id" = Uuid, Path, description = "ID of the webhook to delete"),
+        TenantParams
+    )
+)]
+#[tracing::instrument(skip_all, fields(%scope, %webhook_id))]
+pub async fn delete(
+    TenantDb(db): TenantDb,
+    Path((scope, webhook_id)): Path<(DocumentBoxScope, DocumentBoxWebhookId)>,
+) -> HttpStatusResult {
+    let DocumentBoxScope(scope) = scope;
+
+    let webhook = DocumentBoxWebhook::find(&db, &scope, webhook_id)
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to query webhook");
+            HttpCommonError::ServerError
+        })?
+        .ok_or(HttpWebhookError::UnknownWebhook)?;
+
+    webhook.delete(&db).await.map_err(|error| {
+        tracing::error!(?error, "failed to delete webhook");
+        HttpCommonError::ServerError
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
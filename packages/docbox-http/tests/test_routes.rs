@@ -0,0 +1,1107 @@
+#![recursion_limit = "256"]
+
+use docbox_http::{
+    core::events::webhook::{WEBHOOK_EVENT_HEADER, WEBHOOK_SIGNATURE_HEADER, sign_payload},
+    error::HttpErrorResponse,
+    middleware::{
+        action_user::USER_ID_HEADER,
+        tenant::{TENANT_ENV_HEADER, TENANT_ID_HEADER},
+    },
+    models::{
+        document_box::DocumentBoxResponse,
+        file::{PresignedUploadResponse, UploadTaskResponse},
+        folder::FolderResponse,
+        link::BulkCreateLinksResponse,
+    },
+};
+use docbox_test_utils::{TestEnvironment, TestEnvironmentConfig, environment::TEST_SERVER_VERSION};
+use reqwest::{StatusCode, header};
+use serde_json::json;
+use std::time::Duration;
+
+/// Tests that the health check responds without tenant headers
+#[tokio::test]
+async fn test_health() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = reqwest::get(server.url("/health")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+/// Tests that the readiness check reports the office converter circuit
+#[tokio::test]
+async fn test_health_ready() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = reqwest::get(server.url("/health/ready")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["ready"], true);
+    assert_eq!(body["office_converter"]["state"], "closed");
+}
+
+/// Tests that the OpenAPI documentation is served with the server version
+#[tokio::test]
+async fn test_openapi_docs() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = reqwest::get(server.url("/docs/openapi.json"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["info"]["version"], TEST_SERVER_VERSION);
+    assert!(body["paths"]["/docs/openapi.json"].is_object());
+    assert!(body["components"]["schemas"]["HttpErrorResponse"].is_object());
+    assert!(body["components"]["securitySchemes"]["api_key"].is_object());
+}
+
+/// Tests that requests against an unknown tenant are rejected
+#[tokio::test]
+async fn test_unknown_tenant() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = reqwest::Client::new()
+        .get(server.url("/box/test"))
+        .header(TENANT_ID_HEADER, uuid::Uuid::nil().to_string())
+        .header(TENANT_ENV_HEADER, "Development")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_client_error());
+}
+
+/// Tests creating a document box and a folder within it through the routes
+#[tokio::test]
+async fn test_create_document_box_and_folder() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let document_box: DocumentBoxResponse = response.json().await.unwrap();
+    assert_eq!(document_box.document_box.scope, "test");
+
+    // Duplicate scopes are rejected
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let correlation_id = response
+        .headers()
+        .get("x-correlation-id")
+        .expect("response should have a correlation id")
+        .to_str()
+        .unwrap()
+        .to_string();
+    let error: HttpErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        error.reason,
+        "document box with matching scope already exists"
+    );
+    assert_eq!(error.code, "SCOPE_ALREADY_EXISTS");
+    assert!(!error.retryable);
+    assert_eq!(error.correlation_id, Some(correlation_id));
+
+    // Client provided correlation ids are used for the request
+    let response = server
+        .post("/box")
+        .header("x-correlation-id", "test-correlation-id")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    let error: HttpErrorResponse = response.json().await.unwrap();
+    assert_eq!(error.correlation_id.as_deref(), Some("test-correlation-id"));
+
+    let response = server
+        .post("/box/test/folder")
+        .json(&json!({
+            "name": "Test Folder",
+            "folder_id": document_box.root.folder.id,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let folder: FolderResponse = response.json().await.unwrap();
+    assert_eq!(folder.folder.folder.name, "Test Folder");
+
+    let response = server
+        .get(&format!("/box/test/folder/{}", folder.folder.folder.id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+/// Tests deleting a folder asynchronously and polling the task until it completes
+#[tokio::test]
+async fn test_delete_folder_async() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    let document_box: DocumentBoxResponse = response.json().await.unwrap();
+
+    let mut parent_id = document_box.root.folder.id;
+    let mut top_folder_id = None;
+
+    // Create a chain of nested folders
+    for index in 0..3 {
+        let response = server
+            .post("/box/test/folder")
+            .json(&json!({
+                "name": format!("Folder {index}"),
+                "folder_id": parent_id,
+            }))
+            .send()
+            .await
+            .unwrap();
+        let folder: FolderResponse = response.json().await.unwrap();
+        parent_id = folder.folder.folder.id;
+        top_folder_id.get_or_insert(parent_id);
+    }
+
+    let top_folder_id = top_folder_id.unwrap();
+
+    let response = server
+        .delete(&format!(
+            "/box/test/folder/{top_folder_id}?asynchronous=true"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let task: UploadTaskResponse = response.json().await.unwrap();
+
+    let mut task_value = serde_json::Value::Null;
+    for _ in 0..50 {
+        let response = server
+            .get(&format!("/box/test/task/{}", task.task_id))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        task_value = response.json().await.unwrap();
+
+        if task_value["status"] != "Pending" {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    assert_eq!(task_value["status"], "Completed");
+    assert_eq!(task_value["output_data"]["total"], 3);
+    assert_eq!(task_value["output_data"]["deleted_folders"], 3);
+    assert_eq!(task_value["progress"]["deleted_folders"], 3);
+
+    let response = server
+        .get(&format!("/box/test/folder/{top_folder_id}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+/// Tests that updating a folder with an outdated If-Match version is rejected
+#[tokio::test]
+async fn test_update_folder_version_conflict() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    let document_box: DocumentBoxResponse = response.json().await.unwrap();
+
+    let response = server
+        .post("/box/test/folder")
+        .json(&json!({
+            "name": "Test Folder",
+            "folder_id": document_box.root.folder.id,
+        }))
+        .send()
+        .await
+        .unwrap();
+    let folder: FolderResponse = response.json().await.unwrap();
+    let folder_path = format!("/box/test/folder/{}", folder.folder.folder.id);
+
+    let response = server.get(&folder_path).send().await.unwrap();
+    let etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(etag, "\"0\"");
+
+    // First client updates using the current version
+    let response = server
+        .put(&folder_path)
+        .header(header::IF_MATCH, &etag)
+        .json(&json!({ "name": "First" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::ETAG], "\"1\"");
+
+    // Second client updates using the now outdated version
+    let response = server
+        .put(&folder_path)
+        .header(header::IF_MATCH, &etag)
+        .json(&json!({ "name": "Second" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = server.get(&folder_path).send().await.unwrap();
+    let folder: FolderResponse = response.json().await.unwrap();
+    assert_eq!(folder.folder.folder.name, "First");
+
+    let response = server
+        .get(&format!("{folder_path}/edit-history"))
+        .send()
+        .await
+        .unwrap();
+    let history: serde_json::Value = response.json().await.unwrap();
+    let history = history["items"].as_array().unwrap();
+    assert!(history.iter().any(|item| item["type"] == "Rename"));
+    assert!(history.iter().any(|item| item["type"] == "UpdateConflict"));
+}
+
+/// Tests pinning and unpinning items and listing the pinned items
+#[tokio::test]
+async fn test_pinned_items() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    let document_box: DocumentBoxResponse = response.json().await.unwrap();
+
+    let mut folder_ids = Vec::new();
+    for name in ["First", "Second", "Third"] {
+        let response = server
+            .post("/box/test/folder")
+            .json(&json!({
+                "name": name,
+                "folder_id": document_box.root.folder.id,
+            }))
+            .send()
+            .await
+            .unwrap();
+        let folder: FolderResponse = response.json().await.unwrap();
+        folder_ids.push(folder.folder.folder.id);
+    }
+
+    for folder_id in &folder_ids {
+        let response = server
+            .put(&format!("/box/test/folder/{folder_id}/pin"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let response = server
+        .delete(&format!("/box/test/folder/{}/pin", folder_ids[1]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Most recently pinned items are listed first
+    let response = server.get("/box/test/pinned").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let pinned: serde_json::Value = response.json().await.unwrap();
+    let items = pinned["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["type"], "Folder");
+    assert_eq!(items[0]["id"], folder_ids[2].to_string());
+    assert_eq!(items[1]["id"], folder_ids[0].to_string());
+
+    // Pin changes are recorded in the edit history
+    let response = server
+        .get(&format!("/box/test/folder/{}/edit-history", folder_ids[1]))
+        .send()
+        .await
+        .unwrap();
+    let history: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(history["total"], 2);
+    assert_eq!(
+        history["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|item| item["type"] == "ChangePinned")
+            .count(),
+        2
+    );
+
+    let response = server.get("/box/unknown/pinned").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+/// Tests paging through the children of a folder using the page cursor
+#[tokio::test]
+async fn test_folder_children_page() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    let document_box: DocumentBoxResponse = response.json().await.unwrap();
+    let root_id = document_box.root.folder.id;
+
+    for name in ["First", "Second", "Third"] {
+        let response = server
+            .post("/box/test/folder")
+            .json(&json!({ "name": name, "folder_id": root_id }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    let children_path = format!("/box/test/folder/{root_id}/children");
+
+    let response = server
+        .get(&format!("{children_path}?size=2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(page["total"], 3);
+    assert_eq!(page["items"].as_array().unwrap().len(), 2);
+    assert_eq!(page["items"][0]["type"], "Folder");
+    let cursor = page["next_cursor"].as_str().unwrap().to_string();
+
+    // Last page has no next cursor
+    let response = server
+        .get(&format!("{children_path}?size=2&cursor={cursor}"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(page["items"].as_array().unwrap().len(), 1);
+    assert!(page["next_cursor"].is_null());
+
+    let response = server
+        .get(&format!("{children_path}?cursor=invalid"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Tests that searches made by a user are listed in their recent searches
+#[tokio::test]
+async fn test_recent_searches() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+
+    for query in ["first", "second", "first", " "] {
+        let response = server
+            .post("/box/test/search")
+            .header(USER_ID_HEADER, "user")
+            .json(&json!({ "query": query }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Repeated queries are moved to the front and blank queries are ignored
+    let response = server
+        .get("/box/test/search/recent")
+        .header(USER_ID_HEADER, "user")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let recent: serde_json::Value = response.json().await.unwrap();
+    let searches = recent["searches"].as_array().unwrap();
+    assert_eq!(searches.len(), 2);
+    assert_eq!(searches[0]["query"], "first");
+    assert_eq!(searches[1]["query"], "second");
+
+    // Recent searches require a user
+    let response = server.get("/box/test/search/recent").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = server
+        .delete("/box/test/search/recent")
+        .header(USER_ID_HEADER, "user")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = server
+        .get("/box/test/search/recent")
+        .header(USER_ID_HEADER, "user")
+        .send()
+        .await
+        .unwrap();
+    let recent: serde_json::Value = response.json().await.unwrap();
+    assert!(recent["searches"].as_array().unwrap().is_empty());
+}
+
+/// Tests bulk creating links and waiting for the metadata prefetch task
+#[tokio::test]
+async fn test_bulk_create_links() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    let document_box: DocumentBoxResponse = response.json().await.unwrap();
+
+    // Values are not valid URLs so the metadata fails without any requests
+    let response = server
+        .post("/box/test/link/bulk")
+        .json(&json!({
+            "folder_id": document_box.root.folder.id,
+            "links": [
+                { "name": "First", "value": "invalid-link-1" },
+                { "value": "invalid-link-2" },
+            ],
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: BulkCreateLinksResponse = response.json().await.unwrap();
+    assert_eq!(created.links.len(), 2);
+    assert_eq!(created.links[0].link.name, "First");
+    assert_eq!(created.links[1].link.name, "invalid-link-2");
+
+    let mut task_value = serde_json::Value::Null;
+    for _ in 0..50 {
+        let response = server
+            .get(&format!("/box/test/task/{}", created.task_id))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        task_value = response.json().await.unwrap();
+
+        if task_value["status"] != "Pending" {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    assert_eq!(task_value["status"], "Completed");
+    assert_eq!(task_value["output_data"]["total"], 2);
+    assert_eq!(task_value["output_data"]["failed"], 2);
+
+    let response = server
+        .get(&format!("/box/test/folder/{}", document_box.root.folder.id))
+        .send()
+        .await
+        .unwrap();
+    let folder: FolderResponse = response.json().await.unwrap();
+    assert_eq!(folder.children.links.len(), 2);
+
+    let response = server
+        .post("/box/test/link/bulk")
+        .json(&json!({
+            "folder_id": document_box.root.folder.id,
+            "links": [],
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Tests that archived document boxes reject modifications until unarchived
+#[tokio::test]
+async fn test_archived_document_box_read_only() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    let document_box: DocumentBoxResponse = response.json().await.unwrap();
+
+    let response = server
+        .post("/box/test/folder")
+        .json(&json!({
+            "name": "Test Folder",
+            "folder_id": document_box.root.folder.id,
+        }))
+        .send()
+        .await
+        .unwrap();
+    let folder: FolderResponse = response.json().await.unwrap();
+    let folder_path = format!("/box/test/folder/{}", folder.folder.folder.id);
+
+    let response = server
+        .post("/admin/boxes/test/archive")
+        .json(&json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Modifications are rejected
+    let response = server
+        .put(&folder_path)
+        .json(&json!({ "name": "Renamed" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::LOCKED);
+
+    let response = server.delete(&folder_path).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::LOCKED);
+
+    // Reading is still allowed
+    let response = server.get(&folder_path).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = server
+        .post("/admin/boxes/test/unarchive")
+        .json(&json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = server
+        .put(&folder_path)
+        .json(&json!({ "name": "Renamed" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+/// Tests that registered scope patterns restrict the scopes document boxes
+/// can be created with and that scopes can be listed by prefix
+#[tokio::test]
+async fn test_scope_patterns() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = server
+        .post("/admin/scope-patterns")
+        .json(&json!({ "pattern": "org:*" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Child patterns must be covered by their parent
+    let response = server
+        .post("/admin/scope-patterns")
+        .json(&json!({ "pattern": "user:*", "parent": "org:*" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "org:1" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "user:1" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = server
+        .post("/admin/scopes")
+        .json(&json!({ "prefix": "org:" }))
+        .send()
+        .await
+        .unwrap();
+    let scopes: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(scopes["scopes"], json!(["org:1"]));
+    assert_eq!(scopes["total"], 1);
+    assert_eq!(scopes["patterns"][0]["pattern"], "org:*");
+}
+
+/// Tests exporting the full set of admin search results across multiple pages
+#[tokio::test]
+async fn test_admin_search_export() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    let document_box: DocumentBoxResponse = response.json().await.unwrap();
+
+    for name in ["Export 1", "Export 2", "Export, 3"] {
+        let response = server
+            .post("/box/test/folder")
+            .json(&json!({
+                "name": name,
+                "folder_id": document_box.root.folder.id,
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    // Small page size to export across multiple pages
+    let request = json!({ "scopes": ["test"], "query": "Export", "size": 2 });
+
+    let response = server
+        .post("/admin/search/export")
+        .json(&request)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.text().await.unwrap();
+    let results: Vec<serde_json::Value> = body
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(|result| result["scope"] == "test"));
+
+    let response = server
+        .post("/admin/search/export?format=csv")
+        .json(&request)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv");
+    let body = response.text().await.unwrap();
+    let lines: Vec<&str> = body.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("scope,type,id,name"));
+    assert!(body.contains("\"Export, 3\""));
+
+    // Requests are validated before the export starts
+    let response = server
+        .post("/admin/search/export")
+        .json(&json!({ "scopes": ["test"], "size": 1000 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Tests that events within a document box are delivered to its webhooks
+/// with a valid signature
+#[tokio::test]
+async fn test_document_box_webhook_delivery() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    // Local receiver forwarding deliveries to the test
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let receiver = axum::Router::new().route(
+        "/webhook",
+        axum::routing::post(move |headers: axum::http::HeaderMap, body: String| {
+            let tx = tx.clone();
+            async move {
+                _ = tx.send((headers, body));
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let receiver_addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, receiver).await });
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    let document_box: DocumentBoxResponse = response.json().await.unwrap();
+
+    let secret = "test-webhook-secret";
+    let response = server
+        .post("/box/test/webhook")
+        .json(&json!({
+            "url": format!("http://{receiver_addr}/webhook"),
+            "secret": secret,
+            "events": ["FOLDER_CREATED"]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let webhook: serde_json::Value = response.json().await.unwrap();
+    assert!(webhook.get("secret").is_none());
+
+    server
+        .post("/box/test/folder")
+        .json(&json!({
+            "name": "Test Folder",
+            "folder_id": document_box.root.folder.id,
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let (headers, body) = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(headers[WEBHOOK_EVENT_HEADER], "FOLDER_CREATED");
+    assert_eq!(
+        headers[WEBHOOK_SIGNATURE_HEADER],
+        sign_payload(secret, body.as_bytes())
+    );
+
+    let event: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(event["event"], "FOLDER_CREATED");
+    assert_eq!(event["data"]["name"], "Test Folder");
+}
+
+/// Tests that presigned uploads stuck without processing can be listed
+/// and retried by an admin
+#[tokio::test]
+async fn test_retry_presigned_task() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    let document_box: DocumentBoxResponse = response.json().await.unwrap();
+
+    let response = server
+        .post("/box/test/file/presigned")
+        .json(&json!({
+            "name": "test.txt",
+            "folder_id": document_box.root.folder.id,
+            "size": 4,
+            "mime": "text/plain",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let presigned: PresignedUploadResponse = response.json().await.unwrap();
+
+    // Upload the file without the storage notification being processed
+    let mut request =
+        reqwest::Client::new().request(presigned.method.parse().unwrap(), presigned.uri.as_str());
+    for (key, value) in &presigned.headers {
+        request = request.header(key, value);
+    }
+    let response = request.body("test").send().await.unwrap();
+    assert!(response.status().is_success());
+
+    let response = server
+        .post("/admin/presigned-tasks")
+        .json(&json!({ "status": "Pending" }))
+        .send()
+        .await
+        .unwrap();
+    let tasks: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(tasks["total"], 1);
+    assert_eq!(tasks["results"][0]["id"], json!(presigned.task_id));
+
+    let response = server
+        .post(&format!(
+            "/admin/presigned-tasks/{}/retry",
+            presigned.task_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let task: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(task["status"]["status"], "Completed");
+
+    // Completed tasks cannot be retried
+    let response = server
+        .post(&format!(
+            "/admin/presigned-tasks/{}/retry",
+            presigned.task_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = server
+        .post(&format!(
+            "/admin/presigned-tasks/{}/retry",
+            uuid::Uuid::nil()
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+/// Tests that generated files not retained by the tenant policies are skipped
+/// on upload and that concurrent regeneration requests create a single file
+#[tokio::test]
+async fn test_regenerate_generated_file() {
+    let server = TestEnvironment::start_with(TestEnvironmentConfig {
+        office_converter: false,
+        ..Default::default()
+    })
+    .await
+    .serve()
+    .await;
+
+    let response = server
+        .post("/box")
+        .json(&json!({ "scope": "test" }))
+        .send()
+        .await
+        .unwrap();
+    let document_box: DocumentBoxResponse = response.json().await.unwrap();
+
+    let response = server
+        .put("/admin/generated-file-policies")
+        .json(&json!({
+            "policies": [
+                { "mime": "message/rfc822", "type": "TextContent", "retain": false }
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let email = "From: sender@example.com\r\n\
+        To: recipient@example.com\r\n\
+        Subject: Test\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        Test email body\r\n";
+
+    let response = server
+        .post("/box/test/file/presigned")
+        .json(&json!({
+            "name": "test.eml",
+            "folder_id": document_box.root.folder.id,
+            "size": email.len(),
+            "mime": "message/rfc822",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let presigned: PresignedUploadResponse = response.json().await.unwrap();
+
+    let mut request =
+        reqwest::Client::new().request(presigned.method.parse().unwrap(), presigned.uri.as_str());
+    for (key, value) in &presigned.headers {
+        request = request.header(key, value);
+    }
+    let response = request.body(email).send().await.unwrap();
+    assert!(response.status().is_success());
+
+    // Process the uploaded file
+    let response = server
+        .post(&format!(
+            "/admin/presigned-tasks/{}/retry",
+            presigned.task_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let task: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(task["status"]["status"], "Completed");
+    let file_path = format!(
+        "/box/test/file/{}",
+        task["status"]["file_id"].as_str().unwrap()
+    );
+
+    // Text content is not retained by the policy, metadata is
+    let response = server
+        .get(&format!("{file_path}/generated/TextContent"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = server
+        .get(&format!("{file_path}/generated/Metadata"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Concurrent requests resolve to the same generated file
+    let regenerate_path = format!("{file_path}/generated/TextContent/regenerate");
+    let (first, second) = tokio::join!(
+        server.post(&regenerate_path).send(),
+        server.post(&regenerate_path).send()
+    );
+    let first = first.unwrap();
+    let second = second.unwrap();
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(second.status(), StatusCode::OK);
+    let first: serde_json::Value = first.json().await.unwrap();
+    let second: serde_json::Value = second.json().await.unwrap();
+    assert_eq!(first["id"], second["id"]);
+
+    let response = server
+        .get(&format!("{file_path}/generated/TextContent"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let generated: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(generated["id"], first["id"]);
+
+    let response = server
+        .get(&format!("{file_path}/generated/TextContent/raw"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.text().await.unwrap().contains("Test email body"));
+}
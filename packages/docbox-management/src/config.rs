@@ -8,6 +8,7 @@ use docbox_core::{
     storage::StorageLayerFactoryConfig,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use thiserror::Error;
 
 /// Administrative database credentials configuration used for managing the database
@@ -69,6 +70,61 @@ pub struct ServerConfigData {
     pub storage: StorageLayerFactoryConfig,
 }
 
+/// Configuration file for the CLI, either a single server configuration or
+/// a server configuration for each of multiple named environments
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum CliConfig {
+    /// Server configuration for each named environment
+    Environments(EnvironmentsConfig),
+    /// Single server configuration used for all environments
+    Server(Box<ServerConfigData>),
+}
+
+/// Server configurations for multiple named environments
+/// (i.e "dev", "staging", "prod")
+#[derive(Clone, Deserialize, Serialize)]
+pub struct EnvironmentsConfig {
+    /// Name of the environment to use when no environment is selected
+    #[serde(default)]
+    pub default_environment: Option<String>,
+    /// Server configuration for each environment by name
+    pub environments: BTreeMap<String, ServerConfigData>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SelectEnvironmentError {
+    #[error("environment \"{0}\" is not defined in the config")]
+    UnknownEnvironment(String),
+
+    #[error("no environment was selected and the config has no default environment")]
+    MissingEnvironment,
+}
+
+impl CliConfig {
+    /// Get the server configuration for the selected `env`, falling back to
+    /// the default environment when no environment is selected. Configs with
+    /// a single server use that server for every environment
+    pub fn server_config(
+        &self,
+        env: Option<&str>,
+    ) -> Result<&ServerConfigData, SelectEnvironmentError> {
+        let config = match self {
+            CliConfig::Server(config) => return Ok(config),
+            CliConfig::Environments(config) => config,
+        };
+
+        let env = env
+            .or(config.default_environment.as_deref())
+            .ok_or(SelectEnvironmentError::MissingEnvironment)?;
+
+        config
+            .environments
+            .get(env)
+            .ok_or_else(|| SelectEnvironmentError::UnknownEnvironment(env.to_string()))
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ServerConfigDataSecretError {
     #[error("failed to load secret manager from env: {0}")]
@@ -100,3 +156,64 @@ pub async fn load_server_config_data_secret(
         .map_err(ServerConfigDataSecretError::Secret)?
         .ok_or(ServerConfigDataSecretError::SecretNotFound)
 }
+
+#[cfg(test)]
+mod test {
+    use super::{CliConfig, SelectEnvironmentError};
+    use serde_json::json;
+
+    fn server_json(host: &str) -> serde_json::Value {
+        json!({
+            "api": { "url": format!("http://{host}:8080") },
+            "database": { "host": host, "port": 5432 }
+        })
+    }
+
+    #[test]
+    fn test_single_server_config() {
+        let config: CliConfig = serde_json::from_value(server_json("localhost")).unwrap();
+
+        let server = config.server_config(Some("prod")).unwrap();
+        assert_eq!(server.database.host, "localhost");
+
+        let server = config.server_config(None).unwrap();
+        assert_eq!(server.database.host, "localhost");
+    }
+
+    #[test]
+    fn test_environments_config() {
+        let config: CliConfig = serde_json::from_value(json!({
+            "default_environment": "dev",
+            "environments": {
+                "dev": server_json("dev-db"),
+                "prod": server_json("prod-db")
+            }
+        }))
+        .unwrap();
+
+        let server = config.server_config(Some("prod")).unwrap();
+        assert_eq!(server.database.host, "prod-db");
+
+        let server = config.server_config(None).unwrap();
+        assert_eq!(server.database.host, "dev-db");
+
+        let error = config.server_config(Some("staging")).err().unwrap();
+        assert_eq!(
+            error,
+            SelectEnvironmentError::UnknownEnvironment("staging".to_string())
+        );
+    }
+
+    #[test]
+    fn test_environments_config_no_default() {
+        let config: CliConfig = serde_json::from_value(json!({
+            "environments": {
+                "dev": server_json("dev-db")
+            }
+        }))
+        .unwrap();
+
+        let error = config.server_config(None).err().unwrap();
+        assert_eq!(error, SelectEnvironmentError::MissingEnvironment);
+    }
+}
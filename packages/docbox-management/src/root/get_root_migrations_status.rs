@@ -0,0 +1,28 @@
+use crate::database::{DatabaseProvider, close_pool_on_drop};
+use docbox_core::database::{
+    DbResult, ROOT_DATABASE_NAME,
+    create::check_database_table_exists,
+    migrations::{MigrationStatus, ROOT_MIGRATIONS},
+};
+
+/// Get the status of each migration for the root database
+#[tracing::instrument(skip(db_provider))]
+pub async fn get_root_migrations_status(
+    db_provider: &impl DatabaseProvider,
+) -> DbResult<Vec<MigrationStatus>> {
+    let root_db = db_provider.connect(ROOT_DATABASE_NAME).await?;
+    let _guard = close_pool_on_drop(&root_db);
+
+    // Migrations table may not be initialized yet in which case all the migrations are pending
+    if !check_database_table_exists(&root_db, "docbox_root_migrations").await? {
+        return Ok(ROOT_MIGRATIONS
+            .iter()
+            .map(|(migration_name, _migration)| MigrationStatus {
+                name: migration_name.to_string(),
+                applied_at: None,
+            })
+            .collect());
+    }
+
+    docbox_core::database::migrations::get_root_migrations_status(&root_db).await
+}
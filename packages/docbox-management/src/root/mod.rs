@@ -1,3 +1,4 @@
 pub mod get_pending_root_migrations;
+pub mod get_root_migrations_status;
 pub mod initialize;
 pub mod migrate_root;
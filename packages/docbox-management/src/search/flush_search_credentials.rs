@@ -0,0 +1,36 @@
+use crate::config::ApiConfig;
+use reqwest::header::{HeaderName, HeaderValue, InvalidHeaderValue};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FlushSearchCredentialsError {
+    #[error(transparent)]
+    InvalidHeader(#[from] InvalidHeaderValue),
+    #[error(transparent)]
+    MakeRequest(#[from] reqwest::Error),
+}
+
+/// Makes a request to the docbox API server telling it to flush its
+/// cached search credentials
+pub async fn flush_search_credentials(api: &ApiConfig) -> Result<(), FlushSearchCredentialsError> {
+    let client = reqwest::Client::new();
+
+    let url = format!("{}/admin/flush-search-credentials", &api.url);
+    let mut req_builder = client.post(&url);
+
+    if let Some(api_key) = api.api_key.as_ref() {
+        req_builder = req_builder.header(
+            HeaderName::from_static("x-docbox-api-key"),
+            HeaderValue::from_str(api_key)?,
+        );
+    }
+
+    let response = req_builder
+        .send()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to request docbox"))?;
+
+    response.error_for_status()?;
+
+    Ok(())
+}
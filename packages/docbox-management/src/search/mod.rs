@@ -0,0 +1,2 @@
+pub mod flush_search_credentials;
+pub mod rotate_search_credentials;
@@ -0,0 +1,69 @@
+use crate::{
+    config::ApiConfig,
+    search::flush_search_credentials::{FlushSearchCredentialsError, flush_search_credentials},
+};
+use docbox_core::{
+    search::{SearchIndexFactory, TypesenseApiKey, TypesenseSearchError},
+    secrets::{SecretManager, SecretManagerError},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RotateSearchCredentialsError {
+    #[error("search backend does not use rotatable credentials")]
+    UnsupportedBackend,
+
+    #[error("search api key is not loaded from a secret")]
+    NotSecretManaged,
+
+    #[error("search backend rejected the new api key: {0}")]
+    VerifyApiKey(TypesenseSearchError),
+
+    #[error(transparent)]
+    SetSecret(SecretManagerError),
+
+    #[error("failed to flush server search credentials: {0}")]
+    FlushServer(FlushSearchCredentialsError),
+}
+
+/// Rotate the API key used to access the search backend.
+///
+/// The new `api_key` must already be valid on the search backend, it is
+/// verified before being written to the secret manager so that the servers
+/// never load a key that will be rejected. Once stored the cached keys are
+/// flushed locally and on the server. The previous key should only be revoked
+/// after this completes to avoid downtime
+#[tracing::instrument(skip(api, secrets, search))]
+pub async fn rotate_search_credentials(
+    api: &ApiConfig,
+    secrets: &SecretManager,
+    search: &SearchIndexFactory,
+    api_key: TypesenseApiKey,
+) -> Result<(), RotateSearchCredentialsError> {
+    let SearchIndexFactory::Typesense(factory) = search else {
+        return Err(RotateSearchCredentialsError::UnsupportedBackend);
+    };
+
+    let secret_name = factory
+        .api_key_provider()
+        .secret_name()
+        .ok_or(RotateSearchCredentialsError::NotSecretManaged)?;
+
+    factory
+        .verify_api_key(&api_key)
+        .await
+        .map_err(RotateSearchCredentialsError::VerifyApiKey)?;
+
+    secrets
+        .set_secret(secret_name, api_key.as_str())
+        .await
+        .map_err(RotateSearchCredentialsError::SetSecret)?;
+
+    search.flush_credentials().await;
+
+    flush_search_credentials(api)
+        .await
+        .map_err(RotateSearchCredentialsError::FlushServer)?;
+
+    Ok(())
+}
@@ -9,7 +9,8 @@ use docbox_core::{
     events::{EventPublisherFactory, sqs::SqsEventPublisherFactory},
     search::{SearchIndexFactory, SearchIndexFactoryError},
     secrets::{SecretManager, SecretManagerError},
-    storage::StorageLayerFactory,
+    shutdown::ShutdownCoordinator,
+    storage::{StorageLayerFactory, StorageLayerFactoryError},
 };
 use std::sync::Arc;
 use thiserror::Error;
@@ -30,6 +31,9 @@ pub enum LoadManagedServerError {
 
     #[error("failed to create search index factory: {0}")]
     CreateSearchFactory(#[from] SearchIndexFactoryError),
+
+    #[error("failed to create storage layer factory: {0}")]
+    CreateStorageFactory(#[from] StorageLayerFactoryError),
 }
 
 /// Loaded server with all dependencies required to perform
@@ -79,7 +83,7 @@ pub async fn load_managed_server(
     )?;
 
     // Setup storage factory
-    let storage = StorageLayerFactory::from_config(aws_config, config.storage.clone());
+    let storage = StorageLayerFactory::from_config(aws_config, config.storage.clone())?;
 
     let db_provider = match (
         config.database.setup_user.as_ref(),
@@ -114,7 +118,9 @@ pub async fn load_managed_server(
     let sqs_client = SqsClient::new(aws_config);
 
     // Setup event publisher factories
-    let sqs_publisher_factory = SqsEventPublisherFactory::new(sqs_client.clone());
+    let sqs_publisher_factory =
+        SqsEventPublisherFactory::new(sqs_client.clone(), ShutdownCoordinator::new())
+            .with_claim_check(db_cache.clone());
     let events = EventPublisherFactory::new(sqs_publisher_factory);
 
     Ok(ManagedServer {
@@ -1,7 +1,10 @@
 use crate::{
     database::{DatabaseProvider, close_pool_on_drop},
     password::random_password,
-    tenant::migrate_tenant_storage::{MigrateTenantStorageError, migrate_tenant_storage_inner},
+    tenant::{
+        migrate_tenant_storage::{MigrateTenantStorageError, migrate_tenant_storage_inner},
+        update_bucket_policies::tenant_bucket_policy,
+    },
 };
 use docbox_core::{
     database::{
@@ -16,8 +19,14 @@ use docbox_core::{
     },
     search::{SearchError, SearchIndexFactory, TenantSearchIndex},
     secrets::{SecretManager, SecretManagerError},
-    storage::{CreateBucketOutcome, StorageLayer, StorageLayerError, StorageLayerFactory},
-    tenant::tenant_options_ext::TenantOptionsExt,
+    storage::{
+        BucketLifecyclePolicy, CreateBucketOutcome, StorageLayer, StorageLayerError,
+        StorageLayerFactory,
+    },
+    tenant::{
+        tenant_options_ext::TenantOptionsExt,
+        tenant_region::{TenantRegionError, verify_tenant_region},
+    },
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -79,6 +88,10 @@ pub enum CreateTenantError {
     #[error("failed to setup storage origin rules rules: {0}")]
     SetupStorageOrigins(StorageLayerError),
 
+    /// Failed to setup the storage bucket lifecycle rules
+    #[error("failed to setup storage lifecycle rules: {0}")]
+    SetupStorageLifecycle(StorageLayerError),
+
     /// Failed to create the search index
     #[error("failed to create tenant search index: {0}")]
     CreateSearchIndex(SearchError),
@@ -94,6 +107,10 @@ pub enum CreateTenantError {
     /// Missing db_secret_name when not using IAM authentication
     #[error("when not using db_iam_user the db_secret_name must be specified")]
     MissingDatabaseSecretName,
+
+    /// Storage or search backend is not configured for the tenant region
+    #[error("tenant region is not supported: {0}")]
+    DataRegion(TenantRegionError),
 }
 
 /// Request to create a tenant
@@ -127,12 +144,20 @@ pub struct CreateTenantConfig {
     /// ARN for the S3 queue to publish S3 notifications, required
     /// for presigned uploads
     pub storage_s3_queue_arn: Option<String>,
+    /// Lifecycle rules and tags to apply to the tenant storage bucket
+    #[serde(default)]
+    pub storage_lifecycle_policy: BucketLifecyclePolicy,
 
     /// Name of the tenant search index
     pub search_index_name: String,
 
     /// URL for the SQS event queue
     pub event_queue_url: Option<String>,
+
+    /// Data residency region for the tenant (i.e eu-west-1), the storage
+    /// and search backends must be configured for the same region
+    #[serde(default)]
+    pub data_region: Option<String>,
 }
 
 /// Data required to rollback the failed creation of a tenant
@@ -204,6 +229,7 @@ impl CreateTenantRollbackData {
 /// Handles the process of creating a new docbox tenant
 ///
 /// Performs:
+/// - Verify the storage and search backends match the tenant region
 /// - Create tenant database
 /// - Create tenant database role
 /// - Store a secret with the tenant database role credentials
@@ -252,6 +278,14 @@ async fn create_tenant_inner(
     config: CreateTenantConfig,
     rollback: &mut CreateTenantRollbackData,
 ) -> Result<Tenant, CreateTenantError> {
+    // Ensure the tenant is not provisioned onto backends in another region
+    verify_tenant_region(
+        config.data_region.as_deref(),
+        storage_factory,
+        search_factory,
+    )
+    .map_err(CreateTenantError::DataRegion)?;
+
     let (tenant_db, _tenant_db_guard) = {
         // Connect to the "postgres" database to use while creating the tenant database
         let db_postgres = db_provider
@@ -345,6 +379,7 @@ async fn create_tenant_inner(
             os_index_name: config.search_index_name,
             event_queue_url: config.event_queue_url,
             env: config.env,
+            data_region: config.data_region,
         },
     )
     .await
@@ -381,6 +416,7 @@ async fn create_tenant_inner(
         storage_factory,
         config.storage_s3_queue_arn,
         config.storage_cors_origins,
+        config.storage_lifecycle_policy,
         rollback,
     )
     .await?;
@@ -565,6 +601,7 @@ async fn create_tenant_storage(
     storage: &StorageLayerFactory,
     s3_queue_arn: Option<String>,
     origins: Vec<String>,
+    lifecycle_policy: BucketLifecyclePolicy,
     rollback: &mut CreateTenantRollbackData,
 ) -> Result<StorageLayer, CreateTenantError> {
     let storage = storage.create_layer(tenant.storage_layer_options());
@@ -599,6 +636,13 @@ async fn create_tenant_storage(
             .map_err(CreateTenantError::SetupStorageOrigins)?;
     }
 
+    // Setup bucket hygiene rules (Incomplete uploads, expiring prefixes, tags)
+    storage
+        .set_bucket_lifecycle_policy(&tenant_bucket_policy(tenant, lifecycle_policy))
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to set bucket lifecycle policy"))
+        .map_err(CreateTenantError::SetupStorageLifecycle)?;
+
     Ok(storage)
 }
 
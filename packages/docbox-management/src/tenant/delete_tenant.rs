@@ -1,11 +1,17 @@
-use crate::database::{DatabaseProvider, close_pool_on_drop};
+use crate::{
+    database::{DatabaseProvider, close_pool_on_drop},
+    tenant::export_tenant::{ExportTenantError, ExportTenantOutcome, export_tenant},
+};
+use chrono::{DateTime, Utc};
 use docbox_core::{
     database::{
-        DbErr, DbSecrets, ROOT_DATABASE_NAME,
+        DbErr, DbPool, DbSecrets, ROOT_DATABASE_NAME,
         create::{delete_database, delete_role},
         models::{
             document_box::DocumentBox,
-            tenant::{Tenant, TenantId},
+            file::File,
+            generated_file::GeneratedFile,
+            tenant::{Tenant, TenantId, UpdateTenant},
         },
         utils::DatabaseErrorExt,
     },
@@ -13,11 +19,14 @@ use docbox_core::{
     events::{EventPublisherFactory, TenantEventPublisher},
     search::{SearchError, SearchIndexFactory, TenantSearchIndex},
     secrets::{SecretManager, SecretManagerError},
-    storage::{StorageLayer, StorageLayerError, StorageLayerFactory},
+    storage::{
+        StorageLayer, StorageLayerError, StorageLayerFactory, throttle::StorageThrottleConfig,
+    },
     tenant::tenant_options_ext::TenantOptionsExt,
 };
 use futures::{StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use thiserror::Error;
 use tracing::Instrument;
 
@@ -55,6 +64,12 @@ pub enum DeleteTenantError {
 
     #[error("failed to delete database secret: {0}")]
     DeleteDatabaseSecret(SecretManagerError),
+
+    #[error("failed to export tenant: {0}")]
+    ExportTenant(ExportTenantError),
+
+    #[error("failed to soft delete tenant: {0}")]
+    SoftDeleteTenant(DbErr),
 }
 
 /// Number of document boxes to load in each database round trip
@@ -74,9 +89,19 @@ pub struct DeleteTenant {
 ///
 /// Some changes made by enabling these flags can make recovering the tenant
 /// impossible in the case that you want to revert the deletion
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct DeleteTenantOptions {
+    /// Only report what would be deleted without making any changes
+    pub dry_run: bool,
+    /// Soft delete the tenant instead of deleting it. Soft deleted tenants are
+    /// disabled but can be restored until they are permanently deleted, the
+    /// destructive options are ignored when soft deleting
+    pub soft_delete: bool,
+    /// Directory to export the tenant files to before anything is deleted
+    pub export_path: Option<PathBuf>,
+    /// Rate limit for storage access while exporting the tenant files
+    pub export_throttle: StorageThrottleConfig,
     /// Whether to delete data stored within the tenant
     pub delete_contents: bool,
     /// Whether to delete the tenant storage bucket itself (Requires "delete_contents")
@@ -94,6 +119,38 @@ pub struct DeleteTenantOptions {
     pub permanently_delete_secret: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub enum DeleteTenantOutcome {
+    /// Report of what would be deleted from a dry run
+    DryRun(DeleteTenantReport),
+    /// Tenant was soft deleted and can still be restored
+    SoftDeleted { deleted_at: DateTime<Utc> },
+    /// Tenant was deleted
+    Deleted {
+        /// Export created before deletion
+        export: Option<ExportTenantOutcome>,
+    },
+}
+
+/// Report of the resources that would be deleted for a tenant
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeleteTenantReport {
+    /// Number of document boxes that would be deleted
+    pub document_boxes: i64,
+    /// Number of files that would be deleted
+    pub files: i64,
+    /// Number of objects within the bucket that would be deleted (files and generated files)
+    pub bucket_objects: i64,
+    /// Name of the bucket that would be deleted
+    pub bucket: Option<String>,
+    /// Name of the search index that would be deleted
+    pub search_index: Option<String>,
+    /// Name of the database that would be deleted
+    pub database: Option<String>,
+    /// Name of the database secret that would be deleted
+    pub database_secret: Option<String>,
+}
+
 #[tracing::instrument(skip_all, fields(env, tenant_id))]
 pub async fn delete_tenant(
     db_provider: &impl DatabaseProvider,
@@ -102,7 +159,7 @@ pub async fn delete_tenant(
     events: &EventPublisherFactory,
     secrets: &SecretManager,
     config: DeleteTenant,
-) -> Result<(), DeleteTenantError> {
+) -> Result<DeleteTenantOutcome, DeleteTenantError> {
     let db_docbox = db_provider
         .connect(ROOT_DATABASE_NAME)
         .await
@@ -114,36 +171,73 @@ pub async fn delete_tenant(
         .map_err(DeleteTenantError::Database)?
         .ok_or(DeleteTenantError::TenantNotFound)?;
 
+    let options = config.options;
+
+    if options.soft_delete {
+        let deleted_at = Utc::now();
+        let mut tenant = tenant;
+
+        tenant
+            .update(
+                &db_docbox,
+                UpdateTenant {
+                    deleted_at: Some(Some(deleted_at)),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(DeleteTenantError::SoftDeleteTenant)?;
+
+        return Ok(DeleteTenantOutcome::SoftDeleted { deleted_at });
+    }
+
+    if !options.delete_contents
+        && (options.delete_storage || options.delete_search || options.delete_database)
+    {
+        return Err(DeleteTenantError::MissingDeleteContents);
+    }
+
+    if options.dry_run {
+        let report = delete_tenant_report(db_provider, &tenant, &options).await?;
+        return Ok(DeleteTenantOutcome::DryRun(report));
+    }
+
+    let export = match options.export_path.as_ref() {
+        Some(path) => Some(
+            export_tenant(
+                db_provider,
+                secrets,
+                storage_factory,
+                &tenant,
+                path,
+                &options.export_throttle,
+            )
+            .await
+            .map_err(DeleteTenantError::ExportTenant)?,
+        ),
+        None => None,
+    };
+
     let search = search_factory.create_search_index(&tenant);
     let storage = storage_factory.create_layer(tenant.storage_layer_options());
     let events = events.create_event_publisher(&tenant);
 
-    let options = config.options;
-
     if options.delete_contents {
         delete_tenant_contents(db_provider, &search, &storage, &events, &tenant).await?;
     }
 
-    if options.delete_storage {
-        if !options.delete_contents {
-            return Err(DeleteTenantError::MissingDeleteContents);
-        }
-
-        if let Err(error) = storage.delete_bucket().await {
-            tracing::error!(?error, "failed to delete storage bucket");
-            return Err(DeleteTenantError::DeleteBucket(error));
-        }
+    if options.delete_storage
+        && let Err(error) = storage.delete_bucket().await
+    {
+        tracing::error!(?error, "failed to delete storage bucket");
+        return Err(DeleteTenantError::DeleteBucket(error));
     }
 
-    if options.delete_search {
-        if !options.delete_contents {
-            return Err(DeleteTenantError::MissingDeleteContents);
-        }
-
-        if let Err(error) = search.delete_index().await {
-            tracing::error!(?error, "failed to delete storage bucket");
-            return Err(DeleteTenantError::DeleteSearch(error));
-        }
+    if options.delete_search
+        && let Err(error) = search.delete_index().await
+    {
+        tracing::error!(?error, "failed to delete storage bucket");
+        return Err(DeleteTenantError::DeleteSearch(error));
     }
 
     // Database search index must be explicitly closed before performing database operations
@@ -152,10 +246,6 @@ pub async fn delete_tenant(
     }
 
     if options.delete_database {
-        if !options.delete_contents {
-            return Err(DeleteTenantError::MissingDeleteContents);
-        }
-
         if let Err(error) = delete_database(&db_docbox, &tenant.db_name).await {
             // Database already not existing is fine
             if !error.is_database_does_not_exist() {
@@ -199,6 +289,55 @@ pub async fn delete_tenant(
         .await
         .map_err(DeleteTenantError::DeleteTenant)?;
 
+    Ok(DeleteTenantOutcome::Deleted { export })
+}
+
+/// Create a report of what would be deleted for the `tenant` using the
+/// provided delete `options`
+async fn delete_tenant_report(
+    db_provider: &impl DatabaseProvider,
+    tenant: &Tenant,
+    options: &DeleteTenantOptions,
+) -> Result<DeleteTenantReport, DeleteTenantError> {
+    let mut report = DeleteTenantReport {
+        bucket: options.delete_storage.then(|| tenant.s3_name.clone()),
+        search_index: options.delete_search.then(|| tenant.os_index_name.clone()),
+        database: options.delete_database.then(|| tenant.db_name.clone()),
+        database_secret: tenant
+            .db_secret_name
+            .clone()
+            .filter(|_| options.delete_database),
+        ..Default::default()
+    };
+
+    if !options.delete_contents {
+        return Ok(report);
+    }
+
+    let tenant_db = match db_provider.connect(&tenant.db_name).await {
+        Ok(db) => db,
+        // Database already deleted, there are no contents to delete
+        Err(error) if error.is_database_does_not_exist() => return Ok(report),
+        Err(error) => {
+            tracing::error!(?error, "failed to connect to tenant database");
+            return Err(DeleteTenantError::Database(error));
+        }
+    };
+
+    let _guard = close_pool_on_drop(&tenant_db);
+
+    count_tenant_contents(&tenant_db, &mut report)
+        .await
+        .map_err(DeleteTenantError::Database)?;
+
+    Ok(report)
+}
+
+/// Count the contents of the tenant that would be deleted
+async fn count_tenant_contents(db: &DbPool, report: &mut DeleteTenantReport) -> Result<(), DbErr> {
+    report.document_boxes = DocumentBox::total(db).await?;
+    report.files = File::total_count(db).await?;
+    report.bucket_objects = report.files + GeneratedFile::total_count(db).await?;
     Ok(())
 }
 
@@ -0,0 +1,144 @@
+use crate::database::{DatabaseProvider, close_pool_on_drop};
+use docbox_core::{
+    database::{
+        DbErr,
+        models::{file::File, tenant::Tenant},
+        utils::DatabaseErrorExt,
+    },
+    secrets::SecretManager,
+    storage::{
+        StorageLayerError, StorageLayerFactory,
+        throttle::{StorageThrottle, StorageThrottleConfig},
+    },
+    tenant::{
+        tenant_options_ext::TenantOptionsExt,
+        tenant_storage_key::{TenantStorageKeyError, load_storage_keys},
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use thiserror::Error;
+
+/// Number of files to load from the database at once when exporting
+const EXPORT_PAGE_SIZE: u64 = 100;
+
+/// Name of the file listing the exported files
+const EXPORT_MANIFEST_NAME: &str = "manifest.json";
+
+#[derive(Debug, Error)]
+pub enum ExportTenantError {
+    #[error("error connecting to tenant database: {0}")]
+    ConnectTenantDatabase(DbErr),
+
+    #[error("error querying tenant files: {0}")]
+    QueryFiles(DbErr),
+
+    #[error(transparent)]
+    StorageKeys(TenantStorageKeyError),
+
+    #[error("failed to download file: {0}")]
+    DownloadFile(StorageLayerError),
+
+    #[error("failed to write export: {0}")]
+    WriteExport(std::io::Error),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportTenantOutcome {
+    /// Directory the tenant was exported to
+    pub path: PathBuf,
+    /// Number of files that were exported
+    pub files: usize,
+}
+
+/// Export the files of a tenant into the directory at `path`.
+///
+/// Each file is written to `{path}/{file_id}` with its decrypted contents and a
+/// "manifest.json" describing each exported file (name, document box, folder, ..etc)
+/// is written once all the files are exported
+///
+/// Storage access is rate limited according to the `throttle` config
+#[tracing::instrument(skip(db_provider, secrets, storage_factory, tenant), fields(tenant_id = %tenant.id))]
+pub async fn export_tenant(
+    db_provider: &impl DatabaseProvider,
+    secrets: &SecretManager,
+    storage_factory: &StorageLayerFactory,
+    tenant: &Tenant,
+    path: &Path,
+    throttle: &StorageThrottleConfig,
+) -> Result<ExportTenantOutcome, ExportTenantError> {
+    let mut options = tenant.storage_layer_options();
+    if let Some(secret_name) = tenant.storage_key_secret_name.as_ref() {
+        let keys = load_storage_keys(secrets, secret_name)
+            .await
+            .map_err(ExportTenantError::StorageKeys)?;
+        options.encryption = Some(Arc::new(keys));
+    }
+
+    let storage = storage_factory
+        .create_layer(options)
+        .with_throttle(StorageThrottle::from_config(throttle));
+
+    tokio::fs::create_dir_all(path)
+        .await
+        .map_err(ExportTenantError::WriteExport)?;
+
+    let mut outcome = ExportTenantOutcome {
+        path: path.to_path_buf(),
+        files: 0,
+    };
+
+    let tenant_db = match db_provider.connect(&tenant.db_name).await {
+        Ok(db) => db,
+        // Tenant without a database has no files to export
+        Err(error) if error.is_database_does_not_exist() => return Ok(outcome),
+        Err(error) => return Err(ExportTenantError::ConnectTenantDatabase(error)),
+    };
+
+    let _guard = close_pool_on_drop(&tenant_db);
+
+    let mut manifest = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let files = File::all(&tenant_db, offset, EXPORT_PAGE_SIZE)
+            .await
+            .map_err(ExportTenantError::QueryFiles)?;
+
+        if files.is_empty() {
+            break;
+        }
+
+        offset += files.len() as u64;
+
+        for file in files {
+            let bytes = storage
+                .get_file(&file.file.file_key)
+                .await
+                .map_err(ExportTenantError::DownloadFile)?
+                .collect_bytes()
+                .await
+                .map_err(ExportTenantError::DownloadFile)?;
+
+            tokio::fs::write(path.join(file.file.id.to_string()), bytes)
+                .await
+                .map_err(ExportTenantError::WriteExport)?;
+
+            manifest.push(file);
+        }
+    }
+
+    // UNWRAP SAFETY: Manifest only contains serializable primitives
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest).unwrap();
+
+    tokio::fs::write(path.join(EXPORT_MANIFEST_NAME), manifest_bytes)
+        .await
+        .map_err(ExportTenantError::WriteExport)?;
+
+    outcome.files = manifest.len();
+
+    Ok(outcome)
+}
@@ -0,0 +1,44 @@
+use crate::config::ApiConfig;
+use docbox_core::database::models::tenant::TenantId;
+use reqwest::header::{HeaderName, HeaderValue, InvalidHeaderValue};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum InvalidateTenantCacheError {
+    #[error(transparent)]
+    InvalidHeader(#[from] InvalidHeaderValue),
+    #[error(transparent)]
+    MakeRequest(#[from] reqwest::Error),
+}
+
+/// Makes a request to the docbox API server telling it to remove a
+/// specific tenant from its tenant cache
+///
+/// Should be called after updating a tenant so running servers apply
+/// the change immediately instead of once the cached tenant expires
+pub async fn invalidate_tenant_cache(
+    api: &ApiConfig,
+    env: &str,
+    tenant_id: TenantId,
+) -> Result<(), InvalidateTenantCacheError> {
+    let client = reqwest::Client::new();
+
+    let url = format!("{}/admin/tenant-cache/{tenant_id}", &api.url);
+    let mut req_builder = client.delete(&url).query(&[("env", env)]);
+
+    if let Some(api_key) = api.api_key.as_ref() {
+        req_builder = req_builder.header(
+            HeaderName::from_static("x-docbox-api-key"),
+            HeaderValue::from_str(api_key)?,
+        );
+    }
+
+    let response = req_builder
+        .send()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "failed to request docbox"))?;
+
+    response.error_for_status()?;
+
+    Ok(())
+}
@@ -3,12 +3,14 @@ use serde::{Deserialize, Serialize};
 
 pub mod create_tenant;
 pub mod delete_tenant;
+pub mod export_tenant;
 pub mod flush_tenant_cache;
 pub mod get_pending_tenant_migrations;
 pub mod get_pending_tenant_search_migrations;
 pub mod get_pending_tenant_storage_migrations;
 pub mod get_tenant;
 pub mod get_tenants;
+pub mod invalidate_tenant_cache;
 pub mod migrate_tenant;
 pub mod migrate_tenant_search;
 pub mod migrate_tenant_secret_to_iam;
@@ -16,6 +18,14 @@ pub mod migrate_tenant_storage;
 pub mod migrate_tenants;
 pub mod migrate_tenants_search;
 pub mod migrate_tenants_storage;
+pub mod purge_deleted_tenants;
+pub mod restore_tenant;
+pub mod rotate_tenant_storage_key;
+pub mod set_tenant_event_config;
+pub mod set_tenant_search_language;
+pub mod stuck_tasks;
+pub mod sync_tenant_connector;
+pub mod update_bucket_policies;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TenantTarget {
@@ -0,0 +1,108 @@
+use crate::{
+    database::{DatabaseProvider, close_pool_on_drop},
+    tenant::{
+        TenantTarget,
+        delete_tenant::{DeleteTenant, DeleteTenantOptions, delete_tenant},
+    },
+};
+use chrono::{Duration, Utc};
+use docbox_core::{
+    database::{DbErr, ROOT_DATABASE_NAME, models::tenant::Tenant},
+    events::EventPublisherFactory,
+    search::SearchIndexFactory,
+    secrets::SecretManager,
+    storage::StorageLayerFactory,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PurgeDeletedTenantsError {
+    #[error("error connecting to root database: {0}")]
+    ConnectRootDatabase(DbErr),
+
+    #[error("failed to get tenants: {0}")]
+    GetTenants(DbErr),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgeDeletedTenantsConfig {
+    /// Number of days a soft deleted tenant can be restored for
+    /// before it is permanently deleted
+    pub grace_period_days: u32,
+    /// Options used when deleting each tenant, soft deletion and
+    /// dry runs are not supported when purging
+    pub options: DeleteTenantOptions,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PurgeDeletedTenantsOutcome {
+    pub deleted_tenants: Vec<TenantTarget>,
+    pub failed_tenants: Vec<(String, TenantTarget)>,
+}
+
+/// Permanently delete all soft deleted tenants that were deleted
+/// longer than the grace period ago
+#[tracing::instrument(skip(db_provider, search_factory, storage_factory, events, secrets))]
+pub async fn purge_deleted_tenants(
+    db_provider: &impl DatabaseProvider,
+    search_factory: &SearchIndexFactory,
+    storage_factory: &StorageLayerFactory,
+    events: &EventPublisherFactory,
+    secrets: &SecretManager,
+    config: PurgeDeletedTenantsConfig,
+) -> Result<PurgeDeletedTenantsOutcome, PurgeDeletedTenantsError> {
+    let root_db = db_provider
+        .connect(ROOT_DATABASE_NAME)
+        .await
+        .map_err(PurgeDeletedTenantsError::ConnectRootDatabase)?;
+
+    let _guard = close_pool_on_drop(&root_db);
+
+    let before = Utc::now() - Duration::days(config.grace_period_days as i64);
+    let tenants = Tenant::find_deleted_before(&root_db, before)
+        .await
+        .map_err(PurgeDeletedTenantsError::GetTenants)?;
+
+    let options = DeleteTenantOptions {
+        dry_run: false,
+        soft_delete: false,
+        ..config.options
+    };
+
+    let mut outcome = PurgeDeletedTenantsOutcome::default();
+
+    for tenant in tenants {
+        let tenant_target = TenantTarget {
+            env: tenant.env.clone(),
+            name: tenant.name.clone(),
+            tenant_id: tenant.id,
+        };
+
+        let result = delete_tenant(
+            db_provider,
+            search_factory,
+            storage_factory,
+            events,
+            secrets,
+            DeleteTenant {
+                env: tenant.env,
+                tenant_id: tenant.id,
+                options: options.clone(),
+            },
+        )
+        .await;
+
+        match result {
+            Ok(_) => outcome.deleted_tenants.push(tenant_target),
+            Err(error) => {
+                tracing::error!(?error, ?tenant_target, "failed to purge deleted tenant");
+                outcome
+                    .failed_tenants
+                    .push((error.to_string(), tenant_target));
+            }
+        }
+    }
+
+    Ok(outcome)
+}
@@ -0,0 +1,63 @@
+use crate::database::{DatabaseProvider, close_pool_on_drop};
+use docbox_core::database::{
+    DbErr, ROOT_DATABASE_NAME,
+    models::tenant::{Tenant, TenantId, UpdateTenant},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RestoreTenantError {
+    #[error("error connecting to root database: {0}")]
+    ConnectRootDatabase(DbErr),
+
+    #[error("failed to get tenant: {0}")]
+    GetTenant(DbErr),
+
+    #[error("tenant not found")]
+    TenantNotFound,
+
+    #[error("tenant is not soft deleted")]
+    TenantNotDeleted,
+
+    #[error("failed to update tenant: {0}")]
+    UpdateTenant(DbErr),
+}
+
+/// Restore a soft deleted tenant, the tenant must be invalidated from
+/// the server tenant cache (See [invalidate_tenant_cache](super::invalidate_tenant_cache::invalidate_tenant_cache))
+/// for the tenant to become available again before the cached tenant expires
+#[tracing::instrument(skip(db_provider))]
+pub async fn restore_tenant(
+    db_provider: &impl DatabaseProvider,
+    env: &str,
+    tenant_id: TenantId,
+) -> Result<Tenant, RestoreTenantError> {
+    let root_db = db_provider
+        .connect(ROOT_DATABASE_NAME)
+        .await
+        .map_err(RestoreTenantError::ConnectRootDatabase)?;
+
+    let _guard = close_pool_on_drop(&root_db);
+
+    let mut tenant = Tenant::find_by_id(&root_db, tenant_id, env)
+        .await
+        .map_err(RestoreTenantError::GetTenant)?
+        .ok_or(RestoreTenantError::TenantNotFound)?;
+
+    if tenant.deleted_at.is_none() {
+        return Err(RestoreTenantError::TenantNotDeleted);
+    }
+
+    tenant
+        .update(
+            &root_db,
+            UpdateTenant {
+                deleted_at: Some(None),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(RestoreTenantError::UpdateTenant)?;
+
+    Ok(tenant)
+}
@@ -0,0 +1,303 @@
+use crate::database::{DatabaseProvider, close_pool_on_drop};
+use docbox_core::{
+    database::{
+        DbErr, DbPool, ROOT_DATABASE_NAME,
+        models::{
+            file::File,
+            generated_file::GeneratedFile,
+            tenant::{Tenant, UpdateTenant},
+        },
+    },
+    secrets::{SecretManager, SecretManagerError},
+    storage::{
+        StorageLayer, StorageLayerError, StorageLayerFactory, UploadFileOptions,
+        encryption::StorageEncryptionKeys,
+        throttle::{StorageThrottle, StorageThrottleConfig},
+    },
+    tenant::{
+        tenant_options_ext::TenantOptionsExt,
+        tenant_storage_key::{TenantStorageKeyError, TenantStorageKeySecret},
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Number of files to load from the database at once when re-encrypting
+const REENCRYPT_PAGE_SIZE: u64 = 100;
+
+#[derive(Debug, Error)]
+pub enum RotateTenantStorageKeyError {
+    #[error("error connecting to root database: {0}")]
+    ConnectRootDatabase(DbErr),
+
+    #[error("error connecting to tenant database: {0}")]
+    ConnectTenantDatabase(DbErr),
+
+    #[error("secret_name must be specified when enabling encryption for a tenant")]
+    MissingSecretName,
+
+    #[error("storage key secret already exists")]
+    SecretAlreadyExists,
+
+    #[error("storage key secret is missing")]
+    MissingSecret,
+
+    #[error(transparent)]
+    GetSecret(SecretManagerError),
+
+    #[error(transparent)]
+    SetSecret(SecretManagerError),
+
+    #[error(transparent)]
+    InvalidSecret(TenantStorageKeyError),
+
+    #[error("error updating tenant: {0}")]
+    UpdateTenant(DbErr),
+
+    #[error("error querying tenant files: {0}")]
+    QueryFiles(DbErr),
+}
+
+/// Config for rotating the storage key of a tenant
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RotateTenantStorageKeyConfig {
+    /// Name of the secret to store the keys in, required when the tenant
+    /// does not already have encryption enabled
+    pub secret_name: Option<String>,
+
+    /// Whether to re-encrypt all existing files using the new key. When all
+    /// files are re-encrypted successfully the previous keys are removed
+    pub reencrypt: bool,
+
+    /// Rate limit for storage access while re-encrypting files
+    pub throttle: StorageThrottleConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RotateTenantStorageKeyOutcome {
+    /// Version of the new current key
+    pub key_version: u32,
+    /// Number of stored objects that were re-encrypted
+    pub reencrypted: usize,
+    /// Storage keys of objects that failed to re-encrypt
+    pub failed: Vec<String>,
+    /// Whether the previous keys were removed from the secret
+    pub pruned: bool,
+}
+
+/// Rotate the storage encryption key of a tenant, enabling encryption for
+/// the tenant when not already enabled.
+///
+/// Once rotated new files are encrypted with the new key, existing files are
+/// only re-encrypted when requested. The server tenant cache must be flushed
+/// for the change to take effect.
+#[tracing::instrument(skip(db_provider, secrets, storage_factory))]
+pub async fn rotate_tenant_storage_key(
+    db_provider: &impl DatabaseProvider,
+    secrets: &SecretManager,
+    storage_factory: &StorageLayerFactory,
+    tenant: &mut Tenant,
+    config: RotateTenantStorageKeyConfig,
+) -> Result<RotateTenantStorageKeyOutcome, RotateTenantStorageKeyError> {
+    let (secret_name, mut secret) = match tenant.storage_key_secret_name.clone() {
+        // Tenant already has encryption enabled, rotate the existing keys
+        Some(secret_name) => {
+            let mut secret: TenantStorageKeySecret = secrets
+                .parsed_secret(&secret_name)
+                .await
+                .map_err(RotateTenantStorageKeyError::GetSecret)?
+                .ok_or(RotateTenantStorageKeyError::MissingSecret)?;
+
+            secret.rotate();
+            (secret_name, secret)
+        }
+
+        // Enabling encryption for the first time
+        None => {
+            let secret_name = config
+                .secret_name
+                .clone()
+                .ok_or(RotateTenantStorageKeyError::MissingSecretName)?;
+
+            if secrets
+                .has_secret(&secret_name)
+                .await
+                .map_err(RotateTenantStorageKeyError::GetSecret)?
+            {
+                return Err(RotateTenantStorageKeyError::SecretAlreadyExists);
+            }
+
+            (secret_name, TenantStorageKeySecret::generate())
+        }
+    };
+
+    let keys = secret
+        .to_keys()
+        .map_err(RotateTenantStorageKeyError::InvalidSecret)?;
+
+    store_secret(secrets, &secret_name, &secret).await?;
+
+    if tenant.storage_key_secret_name.is_none() {
+        let root_db = db_provider
+            .connect(ROOT_DATABASE_NAME)
+            .await
+            .map_err(RotateTenantStorageKeyError::ConnectRootDatabase)?;
+
+        let _guard = close_pool_on_drop(&root_db);
+
+        tenant
+            .update(
+                &root_db,
+                UpdateTenant {
+                    storage_key_secret_name: Some(Some(secret_name.clone())),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(RotateTenantStorageKeyError::UpdateTenant)?;
+    }
+
+    let mut outcome = RotateTenantStorageKeyOutcome {
+        key_version: keys.current_version(),
+        ..Default::default()
+    };
+
+    if !config.reencrypt {
+        return Ok(outcome);
+    }
+
+    let tenant_db = db_provider
+        .connect(&tenant.db_name)
+        .await
+        .map_err(RotateTenantStorageKeyError::ConnectTenantDatabase)?;
+
+    let _tenant_guard = close_pool_on_drop(&tenant_db);
+
+    let throttle = StorageThrottle::from_config(&config.throttle);
+    reencrypt_tenant_files(
+        &tenant_db,
+        storage_factory,
+        tenant,
+        keys,
+        throttle,
+        &mut outcome,
+    )
+    .await?;
+
+    // Previous keys are no longer required once every file uses the current key
+    if outcome.failed.is_empty() {
+        secret.prune();
+        store_secret(secrets, &secret_name, &secret).await?;
+        outcome.pruned = true;
+    }
+
+    Ok(outcome)
+}
+
+async fn store_secret(
+    secrets: &SecretManager,
+    secret_name: &str,
+    secret: &TenantStorageKeySecret,
+) -> Result<(), RotateTenantStorageKeyError> {
+    // UNWRAP SAFETY: Secret only contains serializable primitives
+    let secret_value = serde_json::to_string(secret).unwrap();
+
+    secrets
+        .set_secret(secret_name, &secret_value)
+        .await
+        .map_err(RotateTenantStorageKeyError::SetSecret)?;
+
+    Ok(())
+}
+
+/// Re-encrypt all files and generated files of the tenant that are not
+/// already encrypted using the current key
+async fn reencrypt_tenant_files(
+    db: &DbPool,
+    storage_factory: &StorageLayerFactory,
+    tenant: &Tenant,
+    keys: StorageEncryptionKeys,
+    throttle: Option<StorageThrottle>,
+    outcome: &mut RotateTenantStorageKeyOutcome,
+) -> Result<(), RotateTenantStorageKeyError> {
+    let current_version = keys.current_version();
+
+    let mut options = tenant.storage_layer_options();
+    options.encryption = Some(Arc::new(keys));
+    let storage = storage_factory
+        .create_layer(options)
+        .with_throttle(throttle);
+
+    let mut offset = 0;
+
+    loop {
+        let files = File::all(db, offset, REENCRYPT_PAGE_SIZE)
+            .await
+            .map_err(RotateTenantStorageKeyError::QueryFiles)?;
+
+        if files.is_empty() {
+            break;
+        }
+
+        offset += files.len() as u64;
+
+        for file in files {
+            let file = file.file;
+
+            let generated_files = GeneratedFile::find_all(db, file.id)
+                .await
+                .map_err(RotateTenantStorageKeyError::QueryFiles)?;
+
+            let objects = std::iter::once((file.file_key, file.mime)).chain(
+                generated_files
+                    .into_iter()
+                    .map(|generated| (generated.file_key, generated.mime)),
+            );
+
+            for (file_key, mime) in objects {
+                match reencrypt_object(&storage, &file_key, mime, current_version).await {
+                    Ok(true) => outcome.reencrypted += 1,
+                    Ok(false) => {}
+                    Err(error) => {
+                        tracing::error!(?error, ?file_key, "failed to re-encrypt file");
+                        outcome.failed.push(file_key);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Re-encrypt a single stored object, provides whether the object
+/// needed to be re-encrypted
+async fn reencrypt_object(
+    storage: &StorageLayer,
+    file_key: &str,
+    content_type: String,
+    current_version: u32,
+) -> Result<bool, StorageLayerError> {
+    let metadata = storage.get_file_metadata(file_key).await?;
+    if metadata.encryption_key_version == Some(current_version) {
+        return Ok(false);
+    }
+
+    // Contents are decrypted using the key recorded with the object
+    let bytes = storage.get_file(file_key).await?.collect_bytes().await?;
+
+    storage
+        .upload_file(
+            file_key,
+            bytes,
+            UploadFileOptions {
+                content_type,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    Ok(true)
+}
@@ -0,0 +1,75 @@
+use crate::database::{DatabaseProvider, close_pool_on_drop};
+use docbox_core::{
+    database::{
+        DbErr, ROOT_DATABASE_NAME,
+        models::tenant::{Tenant, TenantEventConfig, TenantId, UpdateTenant},
+    },
+    events::TENANT_EVENT_NAMES,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SetTenantEventConfigError {
+    #[error("error connecting to root database: {0}")]
+    ConnectRootDatabase(DbErr),
+
+    #[error("error querying tenant: {0}")]
+    GetTenant(DbErr),
+
+    #[error("tenant not found")]
+    TenantNotFound,
+
+    #[error("unknown event \"{0}\"")]
+    UnknownEvent(String),
+
+    #[error("error updating tenant: {0}")]
+    UpdateTenant(DbErr),
+}
+
+/// Set the configuration for which events the tenant publishes, [None]
+/// to publish all events in full
+///
+/// Running servers will use the new config once the cached tenant expires or
+/// is invalidated using [invalidate_tenant_cache](super::invalidate_tenant_cache::invalidate_tenant_cache)
+#[tracing::instrument(skip(db_provider))]
+pub async fn set_tenant_event_config(
+    db_provider: &impl DatabaseProvider,
+    env: &str,
+    tenant_id: TenantId,
+    event_config: Option<TenantEventConfig>,
+) -> Result<Tenant, SetTenantEventConfigError> {
+    if let Some(event) = event_config.iter().find_map(|config| {
+        config
+            .include
+            .iter()
+            .chain(config.exclude.iter())
+            .find(|event| !TENANT_EVENT_NAMES.contains(&event.as_str()))
+    }) {
+        return Err(SetTenantEventConfigError::UnknownEvent(event.clone()));
+    }
+
+    let root_db = db_provider
+        .connect(ROOT_DATABASE_NAME)
+        .await
+        .map_err(SetTenantEventConfigError::ConnectRootDatabase)?;
+
+    let _guard = close_pool_on_drop(&root_db);
+
+    let mut tenant = Tenant::find_by_id(&root_db, tenant_id, env)
+        .await
+        .map_err(SetTenantEventConfigError::GetTenant)?
+        .ok_or(SetTenantEventConfigError::TenantNotFound)?;
+
+    tenant
+        .update(
+            &root_db,
+            UpdateTenant {
+                event_config: Some(event_config),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(SetTenantEventConfigError::UpdateTenant)?;
+
+    Ok(tenant)
+}
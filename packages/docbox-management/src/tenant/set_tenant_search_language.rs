@@ -0,0 +1,64 @@
+use crate::database::{DatabaseProvider, close_pool_on_drop};
+use docbox_core::{
+    database::{
+        DbErr, ROOT_DATABASE_NAME,
+        models::tenant::{Tenant, TenantId, UpdateTenant},
+    },
+    search::language::SearchLanguage,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SetTenantSearchLanguageError {
+    #[error("error connecting to root database: {0}")]
+    ConnectRootDatabase(DbErr),
+
+    #[error("error querying tenant: {0}")]
+    GetTenant(DbErr),
+
+    #[error("tenant not found")]
+    TenantNotFound,
+
+    #[error("error updating tenant: {0}")]
+    UpdateTenant(DbErr),
+}
+
+/// Set the language used to analyze the tenant search index content, [None]
+/// to use the default language of the search backend
+///
+/// The language is applied when the search index is created, existing indexes
+/// must be recreated and rebuilt for the new language to take effect. Running
+/// servers will use the new language once the cached tenant expires or is
+/// invalidated using [invalidate_tenant_cache](super::invalidate_tenant_cache::invalidate_tenant_cache)
+#[tracing::instrument(skip(db_provider))]
+pub async fn set_tenant_search_language(
+    db_provider: &impl DatabaseProvider,
+    env: &str,
+    tenant_id: TenantId,
+    language: Option<SearchLanguage>,
+) -> Result<Tenant, SetTenantSearchLanguageError> {
+    let root_db = db_provider
+        .connect(ROOT_DATABASE_NAME)
+        .await
+        .map_err(SetTenantSearchLanguageError::ConnectRootDatabase)?;
+
+    let _guard = close_pool_on_drop(&root_db);
+
+    let mut tenant = Tenant::find_by_id(&root_db, tenant_id, env)
+        .await
+        .map_err(SetTenantSearchLanguageError::GetTenant)?
+        .ok_or(SetTenantSearchLanguageError::TenantNotFound)?;
+
+    tenant
+        .update(
+            &root_db,
+            UpdateTenant {
+                search_language: Some(language.map(|language| language.to_string())),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(SetTenantSearchLanguageError::UpdateTenant)?;
+
+    Ok(tenant)
+}
@@ -0,0 +1,100 @@
+use crate::database::{DatabaseProvider, close_pool_on_drop};
+use chrono::Duration;
+use docbox_core::{
+    database::{
+        DbErr,
+        models::{
+            presigned_upload_task::{PresignedUploadTask, PresignedUploadTaskId},
+            tasks::{Task, TaskId},
+            tenant::Tenant,
+        },
+    },
+    tasks::stuck_tasks::{
+        ForceFailTaskError, StuckTasks, TaskCounts, find_stuck_tasks, force_fail_presigned_task,
+        force_fail_task, get_task_counts,
+    },
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TenantTasksError {
+    #[error("error connecting to tenant database: {0}")]
+    ConnectTenantDatabase(DbErr),
+
+    #[error("failed to query tasks: {0}")]
+    QueryTasks(DbErr),
+
+    #[error(transparent)]
+    ForceFail(ForceFailTaskError),
+}
+
+/// Get the tasks of a tenant that have been pending for longer than the `threshold`
+#[tracing::instrument(skip(db_provider))]
+pub async fn get_tenant_stuck_tasks(
+    db_provider: &impl DatabaseProvider,
+    tenant: &Tenant,
+    threshold: Duration,
+) -> Result<StuckTasks, TenantTasksError> {
+    let db = db_provider
+        .connect(&tenant.db_name)
+        .await
+        .map_err(TenantTasksError::ConnectTenantDatabase)?;
+    let _guard = close_pool_on_drop(&db);
+
+    find_stuck_tasks(&db, threshold)
+        .await
+        .map_err(TenantTasksError::QueryTasks)
+}
+
+/// Get the number of tasks of each type with each status for a tenant
+#[tracing::instrument(skip(db_provider))]
+pub async fn get_tenant_task_counts(
+    db_provider: &impl DatabaseProvider,
+    tenant: &Tenant,
+) -> Result<TaskCounts, TenantTasksError> {
+    let db = db_provider
+        .connect(&tenant.db_name)
+        .await
+        .map_err(TenantTasksError::ConnectTenantDatabase)?;
+    let _guard = close_pool_on_drop(&db);
+
+    get_task_counts(&db)
+        .await
+        .map_err(TenantTasksError::QueryTasks)
+}
+
+/// Force a pending background task of a tenant into the failed status
+#[tracing::instrument(skip(db_provider))]
+pub async fn fail_tenant_task(
+    db_provider: &impl DatabaseProvider,
+    tenant: &Tenant,
+    task_id: TaskId,
+) -> Result<Task, TenantTasksError> {
+    let db = db_provider
+        .connect(&tenant.db_name)
+        .await
+        .map_err(TenantTasksError::ConnectTenantDatabase)?;
+    let _guard = close_pool_on_drop(&db);
+
+    force_fail_task(&db, task_id)
+        .await
+        .map_err(TenantTasksError::ForceFail)
+}
+
+/// Force a pending presigned upload task of a tenant into the failed status
+#[tracing::instrument(skip(db_provider))]
+pub async fn fail_tenant_presigned_task(
+    db_provider: &impl DatabaseProvider,
+    tenant: &Tenant,
+    task_id: PresignedUploadTaskId,
+) -> Result<PresignedUploadTask, TenantTasksError> {
+    let db = db_provider
+        .connect(&tenant.db_name)
+        .await
+        .map_err(TenantTasksError::ConnectTenantDatabase)?;
+    let _guard = close_pool_on_drop(&db);
+
+    force_fail_presigned_task(&db, task_id)
+        .await
+        .map_err(TenantTasksError::ForceFail)
+}
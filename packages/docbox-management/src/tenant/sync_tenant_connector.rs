@@ -0,0 +1,101 @@
+use aws_config::SdkConfig;
+use docbox_core::{
+    Docbox,
+    connectors::{
+        s3::{S3Connector, S3ConnectorConfig},
+        sync_connector::ConnectorSyncOutput,
+    },
+    database::{
+        DbErr,
+        models::{
+            document_box::DocumentBoxScopeRaw,
+            folder::{Folder, FolderId},
+            tenant::Tenant,
+        },
+    },
+    docbox::DocboxError,
+    processing::ProcessingConfig,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SyncTenantConnectorError {
+    #[error(transparent)]
+    Docbox(#[from] DocboxError),
+
+    #[error("error querying document box root folder: {0}")]
+    QueryRootFolder(DbErr),
+
+    #[error("document box not found")]
+    UnknownDocumentBox,
+}
+
+/// Source to sync files from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "snake_case")]
+pub enum ConnectorSourceConfig {
+    /// Objects within a S3 bucket prefix
+    S3(S3ConnectorConfig),
+}
+
+/// Config for syncing a source connector into a tenant document box
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncTenantConnectorConfig {
+    /// Scope of the document box to sync into
+    pub scope: DocumentBoxScopeRaw,
+    /// Folder to sync into, defaults to the document box root folder
+    #[serde(default)]
+    pub folder_id: Option<FolderId>,
+    /// Source to sync from
+    pub source: ConnectorSourceConfig,
+    /// Config used when processing the synced files
+    #[serde(default)]
+    pub processing_config: Option<ProcessingConfig>,
+    /// Whether to delete files for objects removed from the source
+    #[serde(default)]
+    pub delete_removed: bool,
+}
+
+/// Sync the files from an external source into a document box of the tenant.
+///
+/// Only objects that are new or have changed since the previous sync of the
+/// same source are imported
+#[tracing::instrument(skip(docbox, aws_config, tenant), fields(tenant_id = %tenant.id))]
+pub async fn sync_tenant_connector(
+    docbox: &Docbox,
+    aws_config: &SdkConfig,
+    tenant: Tenant,
+    config: SyncTenantConnectorConfig,
+) -> Result<ConnectorSyncOutput, SyncTenantConnectorError> {
+    let tenant = docbox.tenant_from(tenant).await?;
+
+    let folder_id = match config.folder_id {
+        Some(folder_id) => folder_id,
+        None => {
+            Folder::find_root(tenant.db(), &config.scope)
+                .await
+                .map_err(SyncTenantConnectorError::QueryRootFolder)?
+                .ok_or(SyncTenantConnectorError::UnknownDocumentBox)?
+                .id
+        }
+    };
+
+    let output = match config.source {
+        ConnectorSourceConfig::S3(source) => {
+            let connector = S3Connector::from_config(aws_config, source);
+            tenant
+                .sync_connector(
+                    &config.scope,
+                    folder_id,
+                    &connector,
+                    None,
+                    config.processing_config,
+                    config.delete_removed,
+                )
+                .await?
+        }
+    };
+
+    Ok(output)
+}
@@ -0,0 +1,81 @@
+use crate::database::{DatabaseProvider, close_pool_on_drop};
+use docbox_core::{
+    database::{
+        DbErr, ROOT_DATABASE_NAME,
+        models::tenant::{Tenant, TenantId},
+    },
+    storage::{
+        BucketLifecyclePolicy, BucketTag, ObjectTag, StorageLayerError, StorageLayerFactory,
+    },
+    tenant::tenant_options_ext::TenantOptionsExt,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum UpdateBucketPoliciesError {
+    #[error("error connecting to root database: {0}")]
+    ConnectRootDatabase(DbErr),
+
+    #[error("error querying tenant: {0}")]
+    GetTenant(DbErr),
+
+    #[error("tenant not found")]
+    TenantNotFound,
+
+    #[error("failed to set bucket lifecycle policy: {0}")]
+    SetLifecyclePolicy(StorageLayerError),
+}
+
+/// Apply the bucket lifecycle `policy` to the storage bucket of an
+/// existing tenant, replacing any previously applied policy
+#[tracing::instrument(skip(db_provider, storage_factory))]
+pub async fn update_bucket_policies(
+    db_provider: &impl DatabaseProvider,
+    storage_factory: &StorageLayerFactory,
+    env: &str,
+    tenant_id: TenantId,
+    policy: BucketLifecyclePolicy,
+) -> Result<(), UpdateBucketPoliciesError> {
+    let root_db = db_provider
+        .connect(ROOT_DATABASE_NAME)
+        .await
+        .map_err(UpdateBucketPoliciesError::ConnectRootDatabase)?;
+
+    let _guard = close_pool_on_drop(&root_db);
+
+    let tenant = Tenant::find_by_id(&root_db, tenant_id, env)
+        .await
+        .map_err(UpdateBucketPoliciesError::GetTenant)?
+        .ok_or(UpdateBucketPoliciesError::TenantNotFound)?;
+
+    let storage = storage_factory.create_layer(tenant.storage_layer_options());
+    storage
+        .set_bucket_lifecycle_policy(&tenant_bucket_policy(&tenant, policy))
+        .await
+        .map_err(UpdateBucketPoliciesError::SetLifecyclePolicy)?;
+
+    Ok(())
+}
+
+/// Extend the `policy` with the tags identifying the bucket as belonging
+/// to the `tenant`, used to allocate storage costs to tenants
+pub(crate) fn tenant_bucket_policy(
+    tenant: &Tenant,
+    mut policy: BucketLifecyclePolicy,
+) -> BucketLifecyclePolicy {
+    let tenant_tags = [
+        (ObjectTag::TENANT_ID, tenant.id.to_string()),
+        (ObjectTag::TENANT_ENV, tenant.env.clone()),
+    ];
+
+    for (key, value) in tenant_tags {
+        if !policy.bucket_tags.iter().any(|tag| tag.key == key) {
+            policy.bucket_tags.push(BucketTag {
+                key: key.to_string(),
+                value,
+            });
+        }
+    }
+
+    policy
+}
@@ -0,0 +1,314 @@
+#![forbid(unsafe_code)]
+#![warn(missing_docs)]
+
+//! # Docbox Mime
+//!
+//! Mime type utilities shared across docbox, single source of truth for
+//! mapping between file extensions and mime types.
+//!
+//! Tenants can provide [MimeOverrides] to change the mime type used for
+//! specific file extensions (i.e treating ".dat" files as PDF files)
+
+use mime::Mime;
+use std::{collections::HashMap, path::Path};
+
+/// Extracts the extension portion of a file name
+pub fn get_file_name_ext(name: &str) -> Option<String> {
+    let path = Path::new(name);
+    let ext = path.extension()?;
+    let ext = ext.to_str()?;
+    Some(ext.to_string())
+}
+
+/// Finds the file extension to use for a file based on its mime type
+pub fn get_mime_ext(mime: &Mime) -> Option<&'static str> {
+    if let Some(known_match) = mime2ext::mime2ext(mime) {
+        return Some(known_match);
+    }
+
+    // Search the fallback extension types
+    OTHER_EXT_MAP.iter().find_map(|(other_mime, ext)| {
+        if *other_mime == *mime {
+            Some(*ext)
+        } else {
+            None
+        }
+    })
+}
+
+/// Guess the mime type for a file extension (without the leading dot)
+pub fn get_ext_mime(ext: &str) -> Option<Mime> {
+    mime_guess::from_ext(ext).first()
+}
+
+/// Guess the mime type for a file based on the extension of its `name`
+pub fn get_file_name_mime(name: &str) -> Option<Mime> {
+    get_file_name_ext(name).and_then(|ext| get_ext_mime(&ext))
+}
+
+/// Check if the provided mime type is for a PDF
+#[inline]
+pub fn is_pdf_mime(mime: &Mime) -> bool {
+    if mime.eq(&mime::APPLICATION_PDF) {
+        return true;
+    }
+
+    // Some outdated clients use application/x-pdf for pdf
+    if mime.type_() == mime::APPLICATION && mime.subtype().as_str() == "x-pdf" {
+        return true;
+    }
+
+    false
+}
+
+/// Checks if the provided mime is for an email
+pub fn is_mail_mime(mime: &Mime) -> bool {
+    mime.essence_str() == "message/rfc822"
+}
+
+/// Checks if the provided mime is for a document that can contain active
+/// content (i.e scripts) when rendered by a browser, such as HTML and SVG
+pub fn is_active_content_mime(mime: &Mime) -> bool {
+    matches!(
+        mime.essence_str(),
+        "text/html" | "application/xhtml+xml" | "image/svg+xml"
+    )
+}
+
+/// Fallback mapping for some more obscure mime types
+/// 
+/// Most of these are legacy types but supported by LibreOffice so
+/// we support them here as well
+#[rustfmt::skip]
+pub static OTHER_EXT_MAP: &[(&str, &str)] = &[
+    // Microsoft Excel Macro-Enabled Workbook
+    ("application/vnd.ms-excel.sheet.macroEnabled.12", "xlsm"),
+    // Flat OpenDocument Text file
+    ("application/vnd.oasis.opendocument.text-flat-xml", "fodt"),
+    // ClarisWorks document format (Legacy)
+    ("application/clarisworks", "cwk"),
+    // MacWrite II document format (Legacy)
+    ("application/macwriteii", "mw"),
+    // T602 word processor file (Legacy)
+    ("application/x-t602", "602"),
+    // Hangul Word Processor
+    ("application/x-hwp", "hwp"),
+    // FictionBook (FB2) e-book format
+    ("application/x-fictionbook+xml", "fb2"),
+    // AportisDoc eBook format (Legacy)
+    ("application/x-aportisdoc", "pdb"),
+    // Plucker eBook/Web content format (Legacy)
+    ("application/prs.plucker", "pdb"),
+    // Microsoft Pocket Word document (Legacy)
+    ("application/x-pocket-word", "psw"),
+    // Flat OpenDocument Spreadsheet
+    ("application/vnd.oasis.opendocument.spreadsheet-flat-xml", "fods"),
+    // OpenOffice Base files
+    ("application/vnd.sun.xml.base", "odb"),
+
+];
+
+/// Collection of mime types to use for specific file extensions, takes
+/// priority over the known extension mappings
+#[derive(Debug, Default, Clone)]
+pub struct MimeOverrides {
+    /// Mapping from the lowercase extension to the mime type
+    overrides: HashMap<String, Mime>,
+}
+
+impl MimeOverrides {
+    /// Add an override using `mime` for files with the extension `ext`,
+    /// the extension is matched case-insensitively
+    pub fn insert(&mut self, ext: &str, mime: Mime) {
+        let ext = ext.trim_start_matches('.').to_lowercase();
+        self.overrides.insert(ext, mime);
+    }
+
+    /// Get the overridden mime type for the extension `ext`
+    pub fn get_override(&self, ext: &str) -> Option<&Mime> {
+        self.overrides.get(&ext.to_lowercase())
+    }
+
+    /// Guess the mime type for a file extension, overrides take priority
+    /// over the known extension mappings
+    pub fn get_ext_mime(&self, ext: &str) -> Option<Mime> {
+        self.get_override(ext)
+            .cloned()
+            .or_else(|| get_ext_mime(ext))
+    }
+
+    /// Guess the mime type for a file based on the extension of its `name`,
+    /// overrides take priority over the known extension mappings
+    pub fn get_file_name_mime(&self, name: &str) -> Option<Mime> {
+        get_file_name_ext(name).and_then(|ext| self.get_ext_mime(&ext))
+    }
+
+    /// Resolve the mime type to use for a file `name` that was provided
+    /// with the `mime` type.
+    ///
+    /// Overrides for the file extension always take priority. When `sniff`
+    /// is enabled files provided as "application/octet-stream" (Likely from
+    /// old browsers) will have their mime type guessed from the extension
+    pub fn resolve_file_mime(&self, name: &str, mime: Mime, sniff: bool) -> Mime {
+        let Some(ext) = get_file_name_ext(name) else {
+            return mime;
+        };
+
+        if let Some(override_mime) = self.get_override(&ext) {
+            return override_mime.clone();
+        }
+
+        if sniff
+            && mime == mime::APPLICATION_OCTET_STREAM
+            && let Some(guessed_mime) = get_ext_mime(&ext)
+        {
+            return guessed_mime;
+        }
+
+        mime
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<(S, Mime)> for MimeOverrides {
+    fn from_iter<T: IntoIterator<Item = (S, Mime)>>(iter: T) -> Self {
+        let mut overrides = MimeOverrides::default();
+        for (ext, mime) in iter {
+            overrides.insert(ext.as_ref(), mime);
+        }
+        overrides
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use mime::Mime;
+
+    use crate::{
+        MimeOverrides, get_ext_mime, get_file_name_ext, get_file_name_mime, get_mime_ext,
+        is_active_content_mime, is_pdf_mime,
+    };
+
+    #[test]
+    fn test_get_file_name_ext_basic() {
+        let input = "file.txt";
+        assert_eq!(get_file_name_ext(input), Some("txt".to_string()));
+    }
+
+    #[test]
+    fn test_get_file_name_ext_no_ext() {
+        let input = "file";
+        assert_eq!(get_file_name_ext(input), None);
+    }
+
+    #[test]
+    fn test_get_file_name_ext_hidden_file() {
+        let input = ".hidden";
+        assert_eq!(get_file_name_ext(input), None);
+    }
+
+    #[test]
+    fn test_get_file_name_ext_multiple_dots() {
+        let input = "archive.tar.gz";
+        assert_eq!(get_file_name_ext(input), Some("gz".to_string()));
+    }
+
+    #[test]
+    fn test_get_mime_ext_known_mime() {
+        let mime: Mime = "image/png".parse().unwrap();
+        assert_eq!(get_mime_ext(&mime), Some("png"));
+    }
+
+    #[test]
+    fn test_get_mime_ext_unknown_mime() {
+        let mime: Mime = "unknown/mime".parse().unwrap();
+        assert_eq!(get_mime_ext(&mime), None);
+    }
+
+    #[test]
+    fn test_get_mime_ext_fallback_mime() {
+        let mime: Mime = "application/x-hwp".parse().unwrap();
+        assert_eq!(get_mime_ext(&mime), Some("hwp"));
+    }
+
+    #[test]
+    fn test_get_file_name_mime() {
+        assert_eq!(get_file_name_mime("test.pdf"), Some(mime::APPLICATION_PDF));
+        assert_eq!(get_file_name_mime("test"), None);
+    }
+
+    #[test]
+    fn test_is_pdf_mime() {
+        assert!(is_pdf_mime(&mime::APPLICATION_PDF));
+        assert!(is_pdf_mime(&"application/x-pdf".parse().unwrap()));
+        assert!(!is_pdf_mime(&mime::TEXT_PLAIN));
+    }
+
+    #[test]
+    fn test_overrides_take_priority() {
+        let overrides = MimeOverrides::from_iter([("dat", mime::APPLICATION_PDF)]);
+
+        assert_eq!(overrides.get_ext_mime("dat"), Some(mime::APPLICATION_PDF));
+        assert_eq!(overrides.get_ext_mime("DAT"), Some(mime::APPLICATION_PDF));
+        assert_eq!(
+            overrides.get_file_name_mime("test.dat"),
+            Some(mime::APPLICATION_PDF)
+        );
+
+        // Extensions without an override use the known mappings
+        assert_eq!(overrides.get_ext_mime("png"), get_ext_mime("png"));
+    }
+
+    #[test]
+    fn test_overrides_strip_leading_dot() {
+        let overrides = MimeOverrides::from_iter([(".dat", mime::APPLICATION_PDF)]);
+        assert_eq!(overrides.get_ext_mime("dat"), Some(mime::APPLICATION_PDF));
+    }
+
+    #[test]
+    fn test_resolve_file_mime() {
+        let overrides = MimeOverrides::from_iter([("dat", mime::APPLICATION_PDF)]);
+
+        // Overrides replace the provided mime type
+        assert_eq!(
+            overrides.resolve_file_mime("test.dat", mime::TEXT_PLAIN, false),
+            mime::APPLICATION_PDF
+        );
+
+        // Octet stream files are sniffed when enabled
+        assert_eq!(
+            overrides.resolve_file_mime("test.png", mime::APPLICATION_OCTET_STREAM, true),
+            mime::IMAGE_PNG
+        );
+
+        // Octet stream files are kept when sniffing is disabled
+        assert_eq!(
+            overrides.resolve_file_mime("test.png", mime::APPLICATION_OCTET_STREAM, false),
+            mime::APPLICATION_OCTET_STREAM
+        );
+
+        // Provided mime types are kept when not octet stream
+        assert_eq!(
+            overrides.resolve_file_mime("test.png", mime::TEXT_PLAIN, true),
+            mime::TEXT_PLAIN
+        );
+    }
+
+    #[test]
+    fn test_is_active_content_mime() {
+        let active = [
+            "text/html; charset=utf-8",
+            "application/xhtml+xml",
+            "image/svg+xml",
+        ];
+        for mime in active {
+            let mime: Mime = mime.parse().unwrap();
+            assert!(is_active_content_mime(&mime));
+        }
+
+        let inactive = ["text/plain", "image/png", "application/pdf"];
+        for mime in inactive {
+            let mime: Mime = mime.parse().unwrap();
+            assert!(!is_active_content_mime(&mime));
+        }
+    }
+}
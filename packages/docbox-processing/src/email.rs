@@ -2,12 +2,14 @@ use crate::{ProcessingConfig, QueuedUpload, html_to_text::html_to_text};
 use base64::{Engine, prelude::BASE64_STANDARD};
 use bytes::Bytes;
 use docbox_database::models::generated_file::GeneratedFileType;
+use docbox_mime::get_file_name_mime;
 use docbox_search::models::DocumentPage;
 use mail_parser::{
     Address, MessageParser, MimeHeaders, decoders::html::html_to_text as mail_html_to_text,
 };
 use mime::Mime;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use thiserror::Error;
 
 use super::{AdditionalProcessingFile, ProcessingError, ProcessingIndexMetadata, ProcessingOutput};
@@ -23,11 +25,6 @@ pub enum EmailProcessingError {
     MetadataSerialize(serde_json::Error),
 }
 
-/// Checks if the provided mime is for an email
-pub fn is_mail_mime(mime: &Mime) -> bool {
-    mime.essence_str() == "message/rfc822"
-}
-
 /// JSON document version of the email metadata, extracts
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EmailMetadataDocument {
@@ -57,6 +54,11 @@ pub struct EmailAttachment {
     pub name: String,
     pub length: usize,
     pub mime: String,
+    /// SHA256 hash of the attachment content, matches the hash of the file
+    /// created for the attachment. Identical attachments are only stored once
+    /// so multiple attachments may share the same hash
+    #[serde(default)]
+    pub hash: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,6 +101,35 @@ fn map_email_address(address: Option<&Address<'_>>) -> Vec<EmailEntity> {
     }
 }
 
+/// Maximum length in characters of a file name created from an email subject
+const MAX_EMAIL_FILE_NAME_LENGTH: usize = 200;
+
+/// Create a file name for a raw email based on its subject line, falls
+/// back to "email.eml" for emails without a usable subject
+pub fn email_file_name(file_bytes: &[u8]) -> String {
+    let subject = MessageParser::default()
+        .parse_headers(file_bytes)
+        .and_then(|message| message.subject().map(|value| value.to_string()))
+        .unwrap_or_default();
+
+    let name: String = subject
+        .chars()
+        .filter(|value| !value.is_control())
+        .map(|value| match value {
+            '/' | '\\' => '-',
+            value => value,
+        })
+        .take(MAX_EMAIL_FILE_NAME_LENGTH)
+        .collect();
+
+    let name = name.trim();
+    if name.is_empty() {
+        return "email.eml".to_string();
+    }
+
+    format!("{name}.eml")
+}
+
 pub fn process_email(
     config: &Option<ProcessingConfig>,
     file_bytes: &[u8],
@@ -154,6 +185,9 @@ pub fn process_email(
     let mut attachments: Vec<EmailAttachment> = Vec::new();
     let mut additional_files: Vec<AdditionalProcessingFile> = Vec::new();
 
+    // Hashes of the attachments that have been captured as additional files
+    let mut attachment_hashes: HashSet<String> = HashSet::new();
+
     let text_body = message
         .text_bodies()
         .next()
@@ -218,38 +252,56 @@ pub fn process_email(
             }
         };
 
+        // Attachments sent as application/octet-stream have their type guessed from the name
+        let mime = if mime == mime::APPLICATION_OCTET_STREAM {
+            get_file_name_mime(&name).unwrap_or(mime)
+        } else {
+            mime
+        };
+
         let is_inline = attachment
             .content_disposition()
             .is_some_and(|value| value.is_inline());
 
         // For inline attachments with a content_id we inline them as base64 strings
-        // directly into the email content
-        if let (true, Some(content_id), Some(html_body)) =
-            (is_inline, attachment.content_id(), html_body.as_mut())
-        {
-            // Create a data URL for the content
-            let data = attachment.contents();
-            let base64_data = BASE64_STANDARD.encode(data);
-            let data_uri = format!("data:{raw_mime};base64,{base64_data}");
-
+        // directly into the email content. Attachments referenced by the HTML body
+        // through their content_id are also treated as inline as some clients omit
+        // the inline content disposition
+        if let (Some(content_id), Some(html_body)) = (attachment.content_id(), html_body.as_mut()) {
             let key = format!("cid:{content_id}");
 
-            // Replace usages of the CID with the inline variant
-            let new_body = html_body.replace(&key, &data_uri);
-            *html_body = new_body;
-            continue;
+            if is_inline || html_body.contains(&key) {
+                // Create a data URL for the content
+                let data = attachment.contents();
+                let base64_data = BASE64_STANDARD.encode(data);
+                let data_uri = format!("data:{raw_mime};base64,{base64_data}");
+
+                // Replace usages of the CID with the inline variant
+                let new_body = html_body.replace(&key, &data_uri);
+                *html_body = new_body;
+                continue;
+            }
         }
 
+        let contents = attachment.contents();
+        let hash = sha256::digest(contents);
+
         attachments.push(EmailAttachment {
             name: name.clone(),
             length,
             mime: raw_mime,
+            hash: hash.clone(),
         });
 
+        // Identical attachments are only captured once
+        if !attachment_hashes.insert(hash) {
+            tracing::debug!(?name, "skipping duplicate email attachment");
+            continue;
+        }
+
         // Capture attachments if allowed
         if is_allowed_attachments {
-            let bytes = attachment.contents();
-            let bytes = Bytes::copy_from_slice(bytes);
+            let bytes = Bytes::copy_from_slice(contents);
             additional_files.push(AdditionalProcessingFile {
                 fixed_id: None,
                 name,
@@ -285,10 +337,15 @@ pub fn process_email(
         vec![DocumentPage {
             content: value.to_string(),
             page: 0,
+            words: None,
         }]
     });
 
-    let index_metadata = ProcessingIndexMetadata { pages };
+    let index_metadata = ProcessingIndexMetadata {
+        pages,
+        summary: None,
+        text_stats: None,
+    };
     let mut upload_queue = vec![QueuedUpload::new(
         mime::APPLICATION_JSON,
         GeneratedFileType::Metadata,
@@ -316,5 +373,24 @@ pub fn process_email(
         additional_files,
         index_metadata: Some(index_metadata),
         upload_queue,
+        pdf_metadata: None,
+        pii_analysis: None,
+        timings: Default::default(),
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::email_file_name;
+
+    #[test]
+    fn test_email_file_name() {
+        let email = b"From: test@example.com\r\nSubject: Invoice 2024/05\r\n\r\nBody";
+        assert_eq!(email_file_name(email), "Invoice 2024-05.eml");
+
+        let email = b"From: test@example.com\r\nSubject:   \r\n\r\nBody";
+        assert_eq!(email_file_name(email), "email.eml");
+
+        assert_eq!(email_file_name(b""), "email.eml");
+    }
+}
@@ -1,20 +1,37 @@
 #![forbid(unsafe_code)]
 
-use std::{num::ParseIntError, str::FromStr, time::Duration};
+use std::{
+    num::ParseIntError,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    email::{EmailProcessingError, is_mail_mime, process_email},
+    email::{EmailProcessingError, process_email},
     image::process_image_async,
-    office::{PdfConvertError, process_office},
+    office::{
+        PdfConvertError,
+        circuit_breaker::CircuitBreakerSettings,
+        guardrails::{ConvertLimitError, ConvertLimits},
+        process_office,
+    },
     pdf::{GeneratePdfImagesError, process_pdf},
+    pii::analyze_output_pii,
+    summary::{SummaryProcessor, summarize_output},
+    tables::extract_output_tables,
+    text_stats::analyze_output_text_stats,
 };
 use ::image::{ImageError, ImageFormat};
 use bytes::Bytes;
-use docbox_database::models::{file::FileId, generated_file::GeneratedFileType};
+use docbox_database::models::{
+    file::FileId, file_pdf_metadata::PdfMetadata, file_pii_analysis::PiiAnalysis,
+    file_processing::ProcessingTimings, file_text_stats::TextStats,
+    generated_file::GeneratedFileType,
+};
+use docbox_mime::{is_mail_mime, is_pdf_mime};
 use docbox_search::models::DocumentPage;
 use mime::Mime;
 use office::OfficeProcessingLayer;
-use pdf::is_pdf_file;
 use pdf_process::{PdfInfoError, PdfTextError};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -26,6 +43,12 @@ pub mod html_to_text;
 pub mod image;
 pub mod office;
 pub mod pdf;
+pub mod pdf_metadata;
+pub mod pdf_words;
+pub mod pii;
+pub mod summary;
+pub mod tables;
+pub mod text_stats;
 
 #[derive(Debug, Error)]
 pub enum ProcessingError {
@@ -37,6 +60,10 @@ pub enum ProcessingError {
     #[error("failed to convert file")]
     ConvertFile(#[from] PdfConvertError),
 
+    /// File exceeded the limits for office conversion
+    #[error("file cannot be converted: {0}")]
+    ConvertLimit(#[from] ConvertLimitError),
+
     /// Failed to read info about pdf file
     #[error("failed to read pdf info")]
     ReadPdfInfo(PdfInfoError),
@@ -78,6 +105,36 @@ pub struct ProcessingConfig {
     ///
     /// Default: 1 (Unpack Only the immediate children)
     pub max_unpack_iterations: Option<usize>,
+
+    /// Whether to analyze the extracted text for personally
+    /// identifiable information (Emails, phone numbers, IDs)
+    ///
+    /// Default: false
+    pub detect_pii: Option<bool>,
+
+    /// Whether to extract tables from PDF compatible files and
+    /// spreadsheets into CSV/JSON generated files
+    ///
+    /// Default: false
+    pub extract_tables: Option<bool>,
+}
+
+impl ProcessingConfig {
+    /// Merge the `other` config on top of this config, values present
+    /// in `other` take priority over the values from this config
+    pub fn merge(self, other: ProcessingConfig) -> ProcessingConfig {
+        let email = match (self.email, other.email) {
+            (Some(base), Some(other)) => Some(base.merge(other)),
+            (base, other) => other.or(base),
+        };
+
+        ProcessingConfig {
+            email,
+            max_unpack_iterations: other.max_unpack_iterations.or(self.max_unpack_iterations),
+            detect_pii: other.detect_pii.or(self.detect_pii),
+            extract_tables: other.extract_tables.or(self.extract_tables),
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize, ToSchema)]
@@ -87,16 +144,39 @@ pub struct EmailProcessingConfig {
     pub skip_attachments: Option<bool>,
 }
 
+impl EmailProcessingConfig {
+    /// Merge the `other` config on top of this config, values present
+    /// in `other` take priority over the values from this config
+    pub fn merge(self, other: EmailProcessingConfig) -> EmailProcessingConfig {
+        EmailProcessingConfig {
+            skip_attachments: other.skip_attachments.or(self.skip_attachments),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct QueuedUpload {
     pub mime: Mime,
     pub ty: GeneratedFileType,
     pub bytes: Bytes,
+    /// Page number for generated files produced per page
+    pub page: Option<i32>,
 }
 
 impl QueuedUpload {
     pub fn new(mime: Mime, ty: GeneratedFileType, bytes: Bytes) -> Self {
-        Self { mime, ty, bytes }
+        Self {
+            mime,
+            ty,
+            bytes,
+            page: None,
+        }
+    }
+
+    /// Set the page number the generated file was produced from
+    pub fn with_page(mut self, page: Option<i32>) -> Self {
+        self.page = page;
+        self
     }
 }
 
@@ -128,18 +208,36 @@ pub struct ProcessingOutput {
 
     /// Whether the file has be detected as encrypted
     pub encrypted: bool,
+
+    /// Page and outline metadata for PDF (or PDF converted) files
+    pub pdf_metadata: Option<PdfMetadata>,
+
+    /// PII findings for the extracted text, only present when
+    /// PII detection is enabled
+    pub pii_analysis: Option<PiiAnalysis>,
+
+    /// Time taken by each of the processing stages that ran
+    pub timings: ProcessingTimings,
 }
 
 #[derive(Debug, Default)]
 pub struct ProcessingIndexMetadata {
     /// Optional page text metadata extracted from the file
     pub pages: Option<Vec<DocumentPage>>,
+
+    /// Optional generated summary of the extracted text
+    pub summary: Option<String>,
+
+    /// Word count, character count and reading time of the extracted text
+    pub text_stats: Option<TextStats>,
 }
 
 #[derive(Clone)]
 pub struct ProcessingLayer {
     pub office: OfficeProcessingLayer,
     pub config: ProcessingLayerConfig,
+    /// Optional processor for generating summaries of extracted text
+    pub summary: Option<SummaryProcessor>,
 }
 
 #[derive(Debug, Default, Deserialize, Serialize, Clone)]
@@ -161,10 +259,59 @@ pub struct ProcessingLayerConfig {
     ///
     /// Default: 300s
     pub process_timeout: Option<Duration>,
+
+    /// Maximum size in bytes of an office file that will be sent
+    /// to the converter, larger files are rejected before conversion
+    ///
+    /// Default: No limit
+    pub max_convert_file_size: Option<u64>,
+
+    /// Maximum number of pages an office document can declare in its
+    /// metadata, documents with more pages are rejected before conversion
+    ///
+    /// Default: No limit
+    pub max_convert_pages: Option<u64>,
+
+    /// When set, images embedded within office documents that are larger
+    /// than this width or height (in pixels) are downsampled before the
+    /// document is sent to the converter
+    ///
+    /// Default: Images are not downsampled
+    pub convert_image_max_dimension: Option<u32>,
+
+    /// Number of pages from the start of PDF compatible files to
+    /// generate page preview images for
+    ///
+    /// Default: 0 (No page previews)
+    pub pdf_preview_pages: Option<u32>,
+
+    /// Number of consecutive office converter failures before conversions
+    /// fail fast without contacting the converter, zero disables this
+    ///
+    /// Default: 5
+    pub convert_failure_threshold: Option<u32>,
+
+    /// Duration conversions fail fast for before the converter is probed
+    /// for recovery
+    ///
+    /// Default: 30s
+    pub convert_circuit_reset_timeout: Option<Duration>,
 }
 
 pub const DEFAULT_PROCESS_TIMEOUT: Duration = Duration::from_secs(300);
 
+/// Version of the file processing pipeline, files are stamped with this
+/// version when processed.
+///
+/// Bump this when changes to processing would produce different output for
+/// existing files (i.e OCR or thumbnail improvements) so that outdated files
+/// can be found and reprocessed
+///
+/// - Version 2: Extract PDF page sizes and outline metadata
+/// - Version 3: Generate word bounding boxes for PDF compatible files
+/// - Version 4: Compute word count, character count and reading time
+pub const PROCESSING_PIPELINE_VERSION: i32 = 4;
+
 #[derive(Debug, Error)]
 pub enum ProcessingLayerConfigError {
     /// Value provided for max unpack iterations was invalid
@@ -173,6 +320,24 @@ pub enum ProcessingLayerConfigError {
     /// Invalid process timeout seconds
     #[error("DOCBOX_FILE_PROCESSING_TIMEOUT must be a number in seconds")]
     InvalidProcessTimeout(<u64 as FromStr>::Err),
+    /// Invalid max convert file size
+    #[error("DOCBOX_MAX_CONVERT_FILE_SIZE must be a number in bytes")]
+    InvalidMaxConvertFileSize(ParseIntError),
+    /// Invalid max convert pages
+    #[error("DOCBOX_MAX_CONVERT_PAGES must be a number")]
+    InvalidMaxConvertPages(ParseIntError),
+    /// Invalid convert image max dimension
+    #[error("DOCBOX_CONVERT_IMAGE_MAX_DIMENSION must be a number in pixels")]
+    InvalidConvertImageMaxDimension(ParseIntError),
+    /// Invalid pdf preview pages
+    #[error("DOCBOX_PDF_PREVIEW_PAGES must be a number")]
+    InvalidPdfPreviewPages(ParseIntError),
+    /// Invalid convert failure threshold
+    #[error("DOCBOX_CONVERT_FAILURE_THRESHOLD must be a number")]
+    InvalidConvertFailureThreshold(ParseIntError),
+    /// Invalid convert circuit reset timeout
+    #[error("DOCBOX_CONVERT_CIRCUIT_RESET_TIMEOUT must be a number in seconds")]
+    InvalidConvertCircuitResetTimeout(ParseIntError),
 }
 
 impl ProcessingLayerConfig {
@@ -196,11 +361,101 @@ impl ProcessingLayerConfig {
             })
             .transpose()?;
 
+        let max_convert_file_size = std::env::var("DOCBOX_MAX_CONVERT_FILE_SIZE")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(ProcessingLayerConfigError::InvalidMaxConvertFileSize)
+            })
+            .transpose()?;
+
+        let max_convert_pages = std::env::var("DOCBOX_MAX_CONVERT_PAGES")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(ProcessingLayerConfigError::InvalidMaxConvertPages)
+            })
+            .transpose()?;
+
+        let convert_image_max_dimension = std::env::var("DOCBOX_CONVERT_IMAGE_MAX_DIMENSION")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u32>()
+                    .map_err(ProcessingLayerConfigError::InvalidConvertImageMaxDimension)
+            })
+            .transpose()?;
+
+        let pdf_preview_pages = std::env::var("DOCBOX_PDF_PREVIEW_PAGES")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u32>()
+                    .map_err(ProcessingLayerConfigError::InvalidPdfPreviewPages)
+            })
+            .transpose()?;
+
+        let convert_failure_threshold = std::env::var("DOCBOX_CONVERT_FAILURE_THRESHOLD")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u32>()
+                    .map_err(ProcessingLayerConfigError::InvalidConvertFailureThreshold)
+            })
+            .transpose()?;
+
+        let convert_circuit_reset_timeout = std::env::var("DOCBOX_CONVERT_CIRCUIT_RESET_TIMEOUT")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(ProcessingLayerConfigError::InvalidConvertCircuitResetTimeout)
+                    .map(Duration::from_secs)
+            })
+            .transpose()?;
+
         Ok(ProcessingLayerConfig {
             max_unpack_iterations,
             process_timeout,
+            max_convert_file_size,
+            max_convert_pages,
+            convert_image_max_dimension,
+            pdf_preview_pages,
+            convert_failure_threshold,
+            convert_circuit_reset_timeout,
         })
     }
+
+    /// Limits to apply to office files before conversion
+    pub fn convert_limits(&self) -> ConvertLimits {
+        ConvertLimits {
+            max_file_size: self.max_convert_file_size,
+            max_pages: self.max_convert_pages,
+            image_max_dimension: self.convert_image_max_dimension,
+        }
+    }
+
+    /// Settings for the office converter circuit breaker
+    pub fn convert_circuit_breaker(&self) -> CircuitBreakerSettings {
+        let defaults = CircuitBreakerSettings::default();
+
+        CircuitBreakerSettings {
+            failure_threshold: self
+                .convert_failure_threshold
+                .unwrap_or(defaults.failure_threshold),
+            reset_timeout: self
+                .convert_circuit_reset_timeout
+                .unwrap_or(defaults.reset_timeout),
+        }
+    }
+}
+
+/// Milliseconds elapsed since `start`, used for recording processing
+/// stage timings
+pub fn elapsed_ms(start: Instant) -> i64 {
+    start.elapsed().as_millis().try_into().unwrap_or(i64::MAX)
 }
 
 /// Processes a file returning the generated processing output
@@ -215,33 +470,65 @@ pub async fn process_file(
     layer: &ProcessingLayer,
     bytes: Bytes,
     mime: &Mime,
+) -> Result<Option<ProcessingOutput>, ProcessingError> {
+    let mut output = process_file_type(config, layer, bytes.clone(), mime).await?;
+
+    // Summarize the extracted text when summarization is enabled
+    if let (Some(summary), Some(output)) = (layer.summary.as_ref(), output.as_mut()) {
+        summarize_output(summary, output).await;
+    }
+
+    if let Some(output) = output.as_mut() {
+        // Analyze the extracted text for PII when enabled
+        analyze_output_pii(config, output);
+
+        analyze_output_text_stats(output);
+
+        // Extract tables from the file when enabled
+        extract_output_tables(config, &bytes, mime, output);
+    }
+
+    Ok(output)
+}
+
+/// Processes a file using the processor for its specific file type
+async fn process_file_type(
+    config: &Option<ProcessingConfig>,
+    layer: &ProcessingLayer,
+    bytes: Bytes,
+    mime: &Mime,
 ) -> Result<Option<ProcessingOutput>, ProcessingError> {
     // File is a PDF
-    if is_pdf_file(mime) {
+    if is_pdf_mime(mime) {
         tracing::debug!("processing pdf file");
 
-        let output = process_pdf(&bytes).await?;
+        let preview_pages = layer.config.pdf_preview_pages.unwrap_or_default();
+        let output = process_pdf(&bytes, preview_pages).await?;
         Ok(Some(output))
     }
     // File can be converted to a PDF then processed
     else if layer.office.converter.is_convertable(mime) {
         tracing::debug!("processing office compatible file");
 
-        let output = process_office(&layer.office, bytes).await?;
+        let output = process_office(&layer.office, &layer.config, bytes).await?;
         Ok(Some(output))
     }
     // File is an email
     else if is_mail_mime(mime) {
         tracing::debug!("processing email file");
 
-        let output = process_email(config, &bytes)?;
+        let start = Instant::now();
+        let mut output = process_email(config, &bytes)?;
+        output.timings.text_extraction_ms = Some(elapsed_ms(start));
         Ok(Some(output))
     }
     // Process image files if the file type is known and can be processed
     else if let Some(image_format) = ImageFormat::from_mime_type(mime) {
         tracing::debug!("processing image file");
 
-        let output = process_image_async(bytes, image_format).await?;
+        let start = Instant::now();
+        let mut output = process_image_async(bytes, image_format).await?;
+        output.timings.thumbnail_ms = Some(elapsed_ms(start));
         Ok(Some(output))
     }
     // No processing for this file type
@@ -249,3 +536,40 @@ pub async fn process_file(
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{EmailProcessingConfig, ProcessingConfig};
+
+    #[test]
+    fn test_merge_processing_config() {
+        let base = ProcessingConfig {
+            email: Some(EmailProcessingConfig {
+                skip_attachments: Some(true),
+            }),
+            max_unpack_iterations: Some(2),
+            detect_pii: Some(true),
+            extract_tables: None,
+        };
+
+        let merged = base.clone().merge(ProcessingConfig {
+            email: Some(EmailProcessingConfig {
+                skip_attachments: None,
+            }),
+            max_unpack_iterations: Some(3),
+            detect_pii: None,
+            extract_tables: Some(true),
+        });
+
+        assert_eq!(
+            merged.email.and_then(|email| email.skip_attachments),
+            Some(true)
+        );
+        assert_eq!(merged.max_unpack_iterations, Some(3));
+        assert_eq!(merged.detect_pii, Some(true));
+        assert_eq!(merged.extract_tables, Some(true));
+
+        let merged = base.merge(ProcessingConfig::default());
+        assert_eq!(merged.max_unpack_iterations, Some(2));
+    }
+}
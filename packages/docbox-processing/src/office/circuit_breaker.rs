@@ -0,0 +1,253 @@
+//! # Converter Circuit Breaker
+//!
+//! Tracks consecutive failures of the office converter. Once the converter
+//! has failed enough times in a row the circuit opens and conversions fail
+//! immediately with [PdfConvertError::ConverterUnavailable] instead of every
+//! upload waiting on the timeouts of an unreachable converter.
+//!
+//! After the reset timeout has elapsed the next conversion probes the health
+//! of the converter (half-open), the circuit closes when the probe succeeds
+//! and re-opens for another reset timeout when it fails.
+
+use super::PdfConvertError;
+use serde::Serialize;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use utoipa::ToSchema;
+
+/// Default number of consecutive failures before the circuit opens
+pub const DEFAULT_CONVERT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Default duration the circuit stays open before probing for recovery
+pub const DEFAULT_CONVERT_CIRCUIT_RESET_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Settings for the circuit breaker
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerSettings {
+    /// Number of consecutive failures before the circuit opens, zero
+    /// disables the circuit breaker
+    pub failure_threshold: u32,
+    /// Duration the circuit stays open before probing for recovery
+    pub reset_timeout: Duration,
+}
+
+impl Default for CircuitBreakerSettings {
+    fn default() -> Self {
+        Self {
+            failure_threshold: DEFAULT_CONVERT_FAILURE_THRESHOLD,
+            reset_timeout: DEFAULT_CONVERT_CIRCUIT_RESET_TIMEOUT,
+        }
+    }
+}
+
+/// Circuit breaker around the office converter, clones share the same state
+#[derive(Clone, Default)]
+pub struct ConverterCircuitBreaker {
+    state: Arc<Mutex<BreakerState>>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    /// Number of conversions that have failed in a row
+    consecutive_failures: u32,
+    /// When the circuit was opened, [None] while the circuit is closed
+    opened_at: Option<Instant>,
+    /// When the current recovery probe started, [None] when no probe is running
+    probe_started_at: Option<Instant>,
+}
+
+/// Outcome of asking the circuit breaker for permission to convert
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitAdmission {
+    /// Circuit is closed, the conversion can proceed
+    Allow,
+    /// Circuit is half-open, the caller must probe the converter health
+    /// and report the outcome before converting
+    Probe,
+}
+
+/// Current state of the circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Conversions are sent to the converter
+    Closed,
+    /// Conversions fail immediately without contacting the converter
+    Open,
+    /// Reset timeout has elapsed, the next conversion probes for recovery
+    HalfOpen,
+}
+
+/// Snapshot of the circuit breaker state
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ConverterCircuitStatus {
+    /// Current state of the circuit
+    pub state: CircuitState,
+    /// Number of conversions that have failed in a row
+    pub consecutive_failures: u32,
+    /// Seconds until the circuit will probe for recovery, only present
+    /// while the circuit is open
+    pub retry_after_seconds: Option<u64>,
+}
+
+impl ConverterCircuitBreaker {
+    /// Request permission to perform a conversion, fails with
+    /// [PdfConvertError::ConverterUnavailable] while the circuit is open
+    pub fn admit(
+        &self,
+        settings: &CircuitBreakerSettings,
+    ) -> Result<CircuitAdmission, PdfConvertError> {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+
+        let Some(opened_at) = state.opened_at else {
+            return Ok(CircuitAdmission::Allow);
+        };
+
+        if opened_at.elapsed() < settings.reset_timeout {
+            return Err(PdfConvertError::ConverterUnavailable);
+        }
+
+        // Only a single probe runs at a time, probes that never reported an
+        // outcome (i.e the request was cancelled) are replaced after the timeout
+        if state
+            .probe_started_at
+            .is_some_and(|started_at| started_at.elapsed() < settings.reset_timeout)
+        {
+            return Err(PdfConvertError::ConverterUnavailable);
+        }
+
+        state.probe_started_at = Some(Instant::now());
+        Ok(CircuitAdmission::Probe)
+    }
+
+    /// Record a successful conversion or probe, closing the circuit
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+
+        if state.opened_at.is_some() {
+            tracing::info!("office converter recovered, closing circuit");
+        }
+
+        *state = BreakerState::default();
+    }
+
+    /// Record a failed conversion or probe, opening the circuit once the
+    /// failure threshold is reached
+    pub fn record_failure(&self, settings: &CircuitBreakerSettings) {
+        // Circuit breaker is disabled
+        if settings.failure_threshold == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        state.probe_started_at = None;
+
+        if state.opened_at.is_some() {
+            // Failed probe, stay open for another reset timeout
+            state.opened_at = Some(Instant::now());
+            tracing::warn!("office converter is still unavailable, keeping circuit open");
+        } else if state.consecutive_failures >= settings.failure_threshold {
+            state.opened_at = Some(Instant::now());
+            tracing::warn!(
+                consecutive_failures = state.consecutive_failures,
+                "office converter failed too many times, opening circuit"
+            );
+        }
+    }
+
+    /// Get a snapshot of the current circuit state
+    pub fn status(&self, settings: &CircuitBreakerSettings) -> ConverterCircuitStatus {
+        let state = self.state.lock().expect("circuit breaker lock poisoned");
+
+        let (state_kind, retry_after_seconds) = match state.opened_at {
+            None => (CircuitState::Closed, None),
+            Some(opened_at) => match settings.reset_timeout.checked_sub(opened_at.elapsed()) {
+                Some(remaining) if !remaining.is_zero() => {
+                    (CircuitState::Open, Some(remaining.as_secs()))
+                }
+                _ => (CircuitState::HalfOpen, None),
+            },
+        };
+
+        ConverterCircuitStatus {
+            state: state_kind,
+            consecutive_failures: state.consecutive_failures,
+            retry_after_seconds,
+        }
+    }
+}
+
+impl PdfConvertError {
+    /// Whether the error indicates the converter itself is failing rather
+    /// than the document being unconvertable
+    pub fn is_converter_failure(&self) -> bool {
+        match self {
+            // Converter replied with an error for the specific document
+            PdfConvertError::ConversionFailed(error) => error.is_retry(),
+            PdfConvertError::ConversionFailedLambda(_) => true,
+            PdfConvertError::MalformedDocument
+            | PdfConvertError::EncryptedDocument
+            | PdfConvertError::ConverterUnavailable => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CircuitAdmission, CircuitBreakerSettings, CircuitState, ConverterCircuitBreaker};
+    use crate::office::PdfConvertError;
+    use std::time::Duration;
+
+    /// Tests that the circuit opens after the failure threshold and fails
+    /// fast until the reset timeout allows a probe
+    #[test]
+    fn test_circuit_opens_and_probes() {
+        let breaker = ConverterCircuitBreaker::default();
+        let settings = CircuitBreakerSettings {
+            failure_threshold: 2,
+            reset_timeout: Duration::from_secs(60),
+        };
+
+        breaker.record_failure(&settings);
+        assert_eq!(breaker.admit(&settings).unwrap(), CircuitAdmission::Allow);
+
+        breaker.record_failure(&settings);
+        assert!(matches!(
+            breaker.admit(&settings),
+            Err(PdfConvertError::ConverterUnavailable)
+        ));
+        assert_eq!(breaker.status(&settings).state, CircuitState::Open);
+
+        // Reset timeout has elapsed, only a single probe is allowed through
+        let settings = CircuitBreakerSettings {
+            reset_timeout: Duration::ZERO,
+            ..settings
+        };
+        assert_eq!(breaker.status(&settings).state, CircuitState::HalfOpen);
+        assert_eq!(breaker.admit(&settings).unwrap(), CircuitAdmission::Probe);
+
+        breaker.record_success();
+        let status = breaker.status(&settings);
+        assert_eq!(status.state, CircuitState::Closed);
+        assert_eq!(status.consecutive_failures, 0);
+    }
+
+    /// Tests that a zero failure threshold disables the circuit breaker
+    #[test]
+    fn test_circuit_disabled() {
+        let breaker = ConverterCircuitBreaker::default();
+        let settings = CircuitBreakerSettings {
+            failure_threshold: 0,
+            reset_timeout: Duration::from_secs(60),
+        };
+
+        for _ in 0..10 {
+            breaker.record_failure(&settings);
+        }
+
+        assert_eq!(breaker.admit(&settings).unwrap(), CircuitAdmission::Allow);
+    }
+}
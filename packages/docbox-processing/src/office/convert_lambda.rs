@@ -109,6 +109,10 @@ pub enum OfficeConvertLambdaError {
     /// Error when converting
     #[error(transparent)]
     Convert(#[from] Box<ConvertError>),
+
+    /// Temporary bucket for conversion does not exist
+    #[error("temporary conversion bucket does not exist")]
+    MissingTmpBucket,
 }
 
 impl OfficeConverterLambda {
@@ -124,6 +128,10 @@ impl OfficeConverterLambda {
         let client = aws_sdk_lambda::Client::new(aws_config);
         let storage = storage.create_layer(StorageLayerOptions {
             bucket_name: config.tmp_bucket,
+            // Temporary bucket is read by the lambda so cannot be encrypted
+            encryption: None,
+            deduplicate: false,
+            tags: Vec::new(),
         });
 
         Ok(Self {
@@ -142,6 +150,17 @@ impl OfficeConverterLambda {
     }
 }
 
+impl OfficeConverterLambda {
+    /// Check that the temporary bucket used for conversion is accessible
+    pub async fn check_health(&self) -> Result<(), OfficeConvertLambdaError> {
+        if !self.storage.bucket_exists().await? {
+            return Err(OfficeConvertLambdaError::MissingTmpBucket);
+        }
+
+        Ok(())
+    }
+}
+
 impl ConvertToPdf for OfficeConverterLambda {
     async fn convert_to_pdf(&self, file_bytes: Bytes) -> Result<Bytes, PdfConvertError> {
         let bucket_name = self.storage.bucket_name();
@@ -68,6 +68,8 @@ impl OfficeConvertServerConfig {
 #[derive(Clone)]
 pub struct OfficeConverterServer {
     client: OfficeConverter,
+    /// Clients for the individual servers, used for health checks
+    clients: Vec<OfficeConvertClient>,
 }
 
 #[derive(Debug, Error)]
@@ -79,8 +81,12 @@ pub enum OfficeConvertServerError {
 }
 
 impl OfficeConverterServer {
-    pub fn new(client: OfficeConverter) -> Self {
-        Self { client }
+    pub fn new(clients: Vec<OfficeConvertClient>) -> Self {
+        // Create a convert load balancer
+        let load_balancer = OfficeConvertLoadBalancer::new(clients.clone());
+        let client = OfficeConverter::from_load_balancer(load_balancer);
+
+        Self { client, clients }
     }
 
     pub fn from_config(
@@ -123,11 +129,16 @@ impl OfficeConverterServer {
             return Err(OfficeConvertServerError::NoAddresses);
         }
 
-        // Create a convert load balancer
-        let load_balancer = OfficeConvertLoadBalancer::new(convert_clients);
-        Ok(Self::new(OfficeConverter::from_load_balancer(
-            load_balancer,
-        )))
+        Ok(Self::new(convert_clients))
+    }
+
+    /// Check that all of the convert servers are reachable
+    pub async fn check_health(&self) -> Result<(), RequestError> {
+        for client in &self.clients {
+            client.get_status().await?;
+        }
+
+        Ok(())
     }
 }
 
@@ -0,0 +1,329 @@
+//! # Conversion Guardrails
+//!
+//! Validation performed on office files before they are sent to the
+//! converter. Pathological documents (very large files, documents with
+//! thousands of pages or huge embedded images) can exhaust the memory
+//! of the converter server so these are rejected or reduced up front.
+
+use bytes::Bytes;
+use image::{ImageFormat, ImageReader, imageops::FilterType};
+use std::io::{Cursor, Read, Write};
+use thiserror::Error;
+use zip::{CompressionMethod, ZipArchive, ZipWriter, result::ZipError, write::SimpleFileOptions};
+
+/// Limits applied to office files before conversion
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ConvertLimits {
+    /// Maximum size in bytes of a file that can be converted
+    pub max_file_size: Option<u64>,
+    /// Maximum number of pages a document can declare
+    pub max_pages: Option<u64>,
+    /// Embedded images larger than this dimension are downsampled
+    pub image_max_dimension: Option<u32>,
+}
+
+#[derive(Debug, Error)]
+pub enum ConvertLimitError {
+    #[error("file is too large to convert ({size} bytes), the maximum is {max} bytes")]
+    FileTooLarge { size: u64, max: u64 },
+
+    #[error("document has too many pages to convert ({pages} pages), the maximum is {max} pages")]
+    TooManyPages { pages: u64, max: u64 },
+}
+
+/// Directories within office document archives that embedded images are stored in
+const EMBEDDED_MEDIA_DIRECTORIES: &[&str] =
+    &["word/media/", "ppt/media/", "xl/media/", "Pictures/"];
+
+/// Checks the provided `file_bytes` against the conversion `limits`
+pub fn validate_convert_input(
+    limits: &ConvertLimits,
+    file_bytes: &[u8],
+) -> Result<(), ConvertLimitError> {
+    if let Some(max) = limits.max_file_size {
+        let size = file_bytes.len() as u64;
+        if size > max {
+            return Err(ConvertLimitError::FileTooLarge { size, max });
+        }
+    }
+
+    if let Some(max) = limits.max_pages
+        && let Some(pages) = count_document_pages(file_bytes)
+        && pages > max
+    {
+        return Err(ConvertLimitError::TooManyPages { pages, max });
+    }
+
+    Ok(())
+}
+
+/// Reads the page count declared in the metadata of an OOXML (docx, pptx)
+/// or OpenDocument file.
+///
+/// Returns [None] for other formats or when the document does not declare a
+/// page count. The declared count is written by the authoring application
+/// so this is only used as a cheap pre-conversion check
+pub fn count_document_pages(file_bytes: &[u8]) -> Option<u64> {
+    let mut archive = ZipArchive::new(Cursor::new(file_bytes)).ok()?;
+
+    // OOXML documents store the page/slide count in the extended properties
+    if let Some(app) = read_archive_text(&mut archive, "docProps/app.xml") {
+        return read_xml_element(&app, "Pages").or_else(|| read_xml_element(&app, "Slides"));
+    }
+
+    // OpenDocument files store the page count in the document statistics
+    if let Some(meta) = read_archive_text(&mut archive, "meta.xml") {
+        return read_xml_attribute(&meta, "meta:page-count");
+    }
+
+    None
+}
+
+fn read_archive_text(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Option<String> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut value = String::new();
+    file.read_to_string(&mut value).ok()?;
+    Some(value)
+}
+
+/// Reads the numeric value of a simple `<name>value</name>` element
+fn read_xml_element(xml: &str, name: &str) -> Option<u64> {
+    let start_tag = format!("<{name}>");
+    let end_tag = format!("</{name}>");
+
+    let start = xml.find(&start_tag)? + start_tag.len();
+    let end = xml[start..].find(&end_tag)? + start;
+    xml[start..end].trim().parse().ok()
+}
+
+/// Reads the numeric value of a `name="value"` attribute
+fn read_xml_attribute(xml: &str, name: &str) -> Option<u64> {
+    let start_attr = format!("{name}=\"");
+
+    let start = xml.find(&start_attr)? + start_attr.len();
+    let end = xml[start..].find('"')? + start;
+    xml[start..end].trim().parse().ok()
+}
+
+/// Downsamples embedded images within an OOXML or OpenDocument archive that are
+/// larger than `max_dimension` on either axis.
+///
+/// Returns [None] when the file is not an archive or no images needed to be
+/// downsampled, in which case the original file should be used
+pub fn downsample_embedded_images(
+    file_bytes: &[u8],
+    max_dimension: u32,
+) -> Result<Option<Bytes>, ZipError> {
+    let Ok(mut archive) = ZipArchive::new(Cursor::new(file_bytes)) else {
+        return Ok(None);
+    };
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::with_capacity(file_bytes.len())));
+    let mut changed = false;
+
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let name = file.name().to_string();
+
+        let format = EMBEDDED_MEDIA_DIRECTORIES
+            .iter()
+            .any(|directory| name.starts_with(directory))
+            .then(|| ImageFormat::from_path(&name).ok())
+            .flatten()
+            .filter(|format| matches!(format, ImageFormat::Png | ImageFormat::Jpeg));
+
+        let Some(format) = format else {
+            writer.raw_copy_file(file)?;
+            continue;
+        };
+
+        let mut image_bytes = Vec::new();
+        file.read_to_end(&mut image_bytes)?;
+
+        match downsample_image(&image_bytes, format, max_dimension) {
+            Some(downsampled) => {
+                let options =
+                    SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+                writer.start_file(name, options)?;
+                writer.write_all(&downsampled)?;
+                changed = true;
+            }
+            None => {
+                // Copy the image unchanged
+                drop(file);
+                writer.raw_copy_file(archive.by_index(index)?)?;
+            }
+        }
+    }
+
+    if !changed {
+        return Ok(None);
+    }
+
+    let output = writer.finish()?.into_inner();
+    Ok(Some(Bytes::from(output)))
+}
+
+/// Resizes the image to fit within `max_dimension` returning the encoded image,
+/// returns [None] if the image is already small enough or could not be decoded
+fn downsample_image(
+    image_bytes: &[u8],
+    format: ImageFormat,
+    max_dimension: u32,
+) -> Option<Vec<u8>> {
+    let reader = ImageReader::with_format(Cursor::new(image_bytes), format);
+    let (width, height) = reader.into_dimensions().ok()?;
+    if width <= max_dimension && height <= max_dimension {
+        return None;
+    }
+
+    let image = ImageReader::with_format(Cursor::new(image_bytes), format)
+        .decode()
+        .inspect_err(|error| tracing::warn!(?error, "failed to decode embedded image"))
+        .ok()?;
+
+    let image = image.resize(max_dimension, max_dimension, FilterType::Triangle);
+
+    let mut output = Cursor::new(Vec::new());
+    image
+        .write_to(&mut output, format)
+        .inspect_err(|error| tracing::warn!(?error, "failed to encode downsampled image"))
+        .ok()?;
+
+    Some(output.into_inner())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        ConvertLimitError, ConvertLimits, count_document_pages, downsample_embedded_images,
+        validate_convert_input,
+    };
+    use image::{DynamicImage, ImageFormat, ImageReader};
+    use std::io::{Cursor, Read, Write};
+    use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+    fn make_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, bytes) in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(bytes).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn make_png(width: u32, height: u32) -> Vec<u8> {
+        let mut output = Cursor::new(Vec::new());
+        DynamicImage::new_rgb8(width, height)
+            .write_to(&mut output, ImageFormat::Png)
+            .unwrap();
+        output.into_inner()
+    }
+
+    #[test]
+    fn test_count_ooxml_pages() {
+        let archive = make_archive(&[(
+            "docProps/app.xml",
+            b"<Properties><Pages>12</Pages><Words>100</Words></Properties>",
+        )]);
+        assert_eq!(count_document_pages(&archive), Some(12));
+
+        let archive = make_archive(&[(
+            "docProps/app.xml",
+            b"<Properties><Slides>4</Slides></Properties>",
+        )]);
+        assert_eq!(count_document_pages(&archive), Some(4));
+    }
+
+    #[test]
+    fn test_count_opendocument_pages() {
+        let archive = make_archive(&[(
+            "meta.xml",
+            br#"<office:meta><meta:document-statistic meta:page-count="7" meta:word-count="10"/></office:meta>"#,
+        )]);
+        assert_eq!(count_document_pages(&archive), Some(7));
+    }
+
+    #[test]
+    fn test_count_pages_unknown_format() {
+        assert_eq!(count_document_pages(b"plain text file"), None);
+    }
+
+    #[test]
+    fn test_validate_convert_input() {
+        let archive = make_archive(&[(
+            "docProps/app.xml",
+            b"<Properties><Pages>12</Pages></Properties>",
+        )]);
+
+        let limits = ConvertLimits {
+            max_pages: Some(10),
+            ..Default::default()
+        };
+        assert!(matches!(
+            validate_convert_input(&limits, &archive),
+            Err(ConvertLimitError::TooManyPages { pages: 12, max: 10 })
+        ));
+
+        let limits = ConvertLimits {
+            max_file_size: Some(4),
+            ..Default::default()
+        };
+        assert!(matches!(
+            validate_convert_input(&limits, &archive),
+            Err(ConvertLimitError::FileTooLarge { max: 4, .. })
+        ));
+
+        assert!(validate_convert_input(&ConvertLimits::default(), &archive).is_ok());
+    }
+
+    #[test]
+    fn test_downsample_embedded_images() {
+        let large = make_png(400, 200);
+        let small = make_png(50, 50);
+        let archive = make_archive(&[
+            ("word/document.xml", b"<document/>"),
+            ("word/media/image1.png", &large),
+            ("word/media/image2.png", &small),
+        ]);
+
+        let output = downsample_embedded_images(&archive, 100)
+            .unwrap()
+            .expect("large image should be downsampled");
+
+        let mut archive = ZipArchive::new(Cursor::new(output.as_ref())).unwrap();
+
+        let mut document = String::new();
+        archive
+            .by_name("word/document.xml")
+            .unwrap()
+            .read_to_string(&mut document)
+            .unwrap();
+        assert_eq!(document, "<document/>");
+
+        for (name, expected) in [
+            ("word/media/image1.png", (100, 50)),
+            ("word/media/image2.png", (50, 50)),
+        ] {
+            let mut bytes = Vec::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_end(&mut bytes)
+                .unwrap();
+            let dimensions = ImageReader::with_format(Cursor::new(bytes), ImageFormat::Png)
+                .into_dimensions()
+                .unwrap();
+            assert_eq!(dimensions, expected);
+        }
+    }
+
+    #[test]
+    fn test_downsample_nothing_to_change() {
+        let small = make_png(50, 50);
+        let archive = make_archive(&[("word/media/image1.png", &small)]);
+        assert!(downsample_embedded_images(&archive, 100).unwrap().is_none());
+    }
+}
@@ -9,29 +9,35 @@
 //! See individual modules for service specific environment variables
 
 use crate::{
-    ProcessingError, ProcessingOutput, QueuedUpload,
+    ProcessingError, ProcessingLayerConfig, ProcessingOutput, QueuedUpload, elapsed_ms,
     office::{
+        circuit_breaker::{CircuitAdmission, CircuitBreakerSettings, ConverterCircuitBreaker},
         convert_lambda::{
             OfficeConvertLambdaConfig, OfficeConvertLambdaConfigError, OfficeConvertLambdaError,
             OfficeConverterLambda,
         },
         convert_server::{OfficeConvertServerConfig, OfficeConvertServerError},
+        guardrails::{downsample_embedded_images, validate_convert_input},
         libreoffice::is_known_libreoffice_pdf_convertable,
     },
-    pdf::{is_pdf_file, process_pdf},
+    pdf::process_pdf,
 };
 use aws_config::SdkConfig;
 use bytes::Bytes;
 use convert_server::OfficeConverterServer;
 use docbox_database::models::generated_file::GeneratedFileType;
+use docbox_mime::is_pdf_mime;
 use docbox_storage::StorageLayerFactory;
 use mime::Mime;
 use office_convert_client::RequestError;
 use serde::{Deserialize, Serialize};
+use std::time::Instant;
 use thiserror::Error;
 
+pub mod circuit_breaker;
 pub mod convert_lambda;
 pub mod convert_server;
+pub mod guardrails;
 pub mod libreoffice;
 
 const DISALLOW_MALFORMED_OFFICE: bool = true;
@@ -51,6 +57,10 @@ pub enum PdfConvertError {
 
     #[error("office document is password protected")]
     EncryptedDocument,
+
+    /// Converter has failed repeatedly and the circuit breaker is open
+    #[error("office converter is temporarily unavailable")]
+    ConverterUnavailable,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -101,6 +111,40 @@ pub enum OfficeConverterError {
 #[derive(Clone)]
 pub struct OfficeProcessingLayer {
     pub converter: OfficeConverter,
+    /// Circuit breaker tracking the failures of the `converter`
+    pub circuit_breaker: ConverterCircuitBreaker,
+}
+
+impl OfficeProcessingLayer {
+    /// Convert the provided `bytes` to a PDF through the circuit breaker,
+    /// fails fast with [PdfConvertError::ConverterUnavailable] while the
+    /// converter is considered unavailable
+    pub async fn convert_to_pdf(
+        &self,
+        settings: &CircuitBreakerSettings,
+        bytes: Bytes,
+    ) -> Result<Bytes, PdfConvertError> {
+        if self.circuit_breaker.admit(settings)? == CircuitAdmission::Probe {
+            if let Err(error) = self.converter.check_health().await {
+                tracing::warn!(?error, "office converter recovery probe failed");
+                self.circuit_breaker.record_failure(settings);
+                return Err(PdfConvertError::ConverterUnavailable);
+            }
+
+            self.circuit_breaker.record_success();
+        }
+
+        let result = self.converter.convert_to_pdf(bytes).await;
+
+        match &result {
+            Err(error) if error.is_converter_failure() => {
+                self.circuit_breaker.record_failure(settings)
+            }
+            _ => self.circuit_breaker.record_success(),
+        }
+
+        result
+    }
 }
 
 impl OfficeConverter {
@@ -140,6 +184,20 @@ impl OfficeConverter {
             OfficeConverter::ConverterLambda(inner) => inner.is_convertable(mime),
         }
     }
+
+    /// Check that the converter is reachable
+    pub async fn check_health(&self) -> Result<(), PdfConvertError> {
+        match self {
+            OfficeConverter::ConverterServer(inner) => inner
+                .check_health()
+                .await
+                .map_err(PdfConvertError::ConversionFailed),
+            OfficeConverter::ConverterLambda(inner) => inner
+                .check_health()
+                .await
+                .map_err(PdfConvertError::ConversionFailedLambda),
+        }
+    }
 }
 
 /// Trait for converting some file input bytes into some output bytes
@@ -153,17 +211,48 @@ pub(crate) trait ConvertToPdf {
 /// Checks if the provided mime type either is a PDF
 /// or can be converted to a PDF
 pub fn is_pdf_compatible(mime: &Mime) -> bool {
-    is_pdf_file(mime) || is_known_libreoffice_pdf_convertable(mime)
+    is_pdf_mime(mime) || is_known_libreoffice_pdf_convertable(mime)
 }
 
 /// Processes a PDF compatible office/other supported file format. Converts to
 /// PDF then processes as a PDF with [process_pdf]
+///
+/// Files exceeding the conversion limits from the `config` are rejected
+/// before being sent to the converter
 pub async fn process_office(
     layer: &OfficeProcessingLayer,
+    config: &ProcessingLayerConfig,
     file_bytes: Bytes,
 ) -> Result<ProcessingOutput, ProcessingError> {
+    let start = Instant::now();
+    let limits = config.convert_limits();
+
+    validate_convert_input(&limits, &file_bytes)?;
+
+    // Reduce the size of large embedded images before conversion
+    let file_bytes = match limits.image_max_dimension {
+        Some(max_dimension) => {
+            let original = file_bytes.clone();
+            let downsampled = tokio::task::spawn_blocking(move || {
+                downsample_embedded_images(&original, max_dimension)
+            })
+            .await?;
+
+            match downsampled {
+                Ok(Some(downsampled)) => downsampled,
+                Ok(None) => file_bytes,
+                Err(error) => {
+                    tracing::warn!(?error, "failed to downsample embedded images");
+                    file_bytes
+                }
+            }
+        }
+        None => file_bytes,
+    };
+
     // Convert file to a pdf
-    let file_bytes = match layer.converter.convert_to_pdf(file_bytes).await {
+    let circuit_breaker = config.convert_circuit_breaker();
+    let file_bytes = match layer.convert_to_pdf(&circuit_breaker, file_bytes).await {
         Ok(value) => value,
 
         // Encrypted document
@@ -185,6 +274,11 @@ pub async fn process_office(
             return Ok(ProcessingOutput::default());
         }
 
+        // Converter is unavailable, fail fast without logging every upload
+        Err(error @ PdfConvertError::ConverterUnavailable) => {
+            return Err(ProcessingError::ConvertFile(error));
+        }
+
         // Other error
         Err(error) => {
             tracing::error!(?error, "failed to convert document to pdf");
@@ -192,7 +286,11 @@ pub async fn process_office(
         }
     };
 
-    let mut output = process_pdf(&file_bytes).await?;
+    let conversion_ms = elapsed_ms(start);
+
+    let preview_pages = config.pdf_preview_pages.unwrap_or_default();
+    let mut output = process_pdf(&file_bytes, preview_pages).await?;
+    output.timings.conversion_ms = Some(conversion_ms);
 
     // Store the converted pdf file
     output.upload_queue.push(QueuedUpload::new(
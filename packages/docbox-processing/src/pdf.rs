@@ -1,17 +1,18 @@
 use crate::{
-    ProcessingError, ProcessingIndexMetadata, ProcessingOutput, QueuedUpload,
-    image::create_img_bytes,
+    ProcessingError, ProcessingIndexMetadata, ProcessingOutput, QueuedUpload, elapsed_ms,
+    image::create_img_bytes, pdf_metadata::extract_pdf_metadata, pdf_words::extract_pdf_words,
+};
+use docbox_database::models::{
+    file_processing::ProcessingTimings, generated_file::GeneratedFileType,
 };
-use docbox_database::models::generated_file::GeneratedFileType;
 use docbox_search::models::DocumentPage;
-use futures::TryFutureExt;
+use futures::{FutureExt, TryFutureExt};
 use image::{DynamicImage, ImageError, ImageFormat, ImageResult};
-use mime::Mime;
 use pdf_process::{
     OutputFormat, PdfInfo, PdfInfoArgs, PdfInfoError, PdfRenderError, PdfTextArgs, RenderArgs,
     pdf_info, render_single_page, text::PAGE_END_CHARACTER, text_all_pages_split,
 };
-use std::str::Split;
+use std::{str::Split, time::Instant};
 use thiserror::Error;
 use tokio::task::JoinError;
 
@@ -23,6 +24,8 @@ pub struct GeneratedPdfImages {
     /// Smaller 385x385 version of first page
     /// (Not actually 385x385 fits whatever the image aspect ratio inside those dimensions)
     pub large_thumbnail_jpeg: Vec<u8>,
+    /// Preview images for the first N pages in page order, starting at the cover page
+    pub page_previews_jpeg: Vec<Vec<u8>>,
 }
 
 #[derive(Debug, Error)]
@@ -44,8 +47,12 @@ pub enum GeneratePdfImagesError {
 /// thumbnails and a converted pdf version
 ///
 /// Extracts text from the PDF and creates multiple thumbnail preview images
-/// of the first page at various sizes
-pub async fn process_pdf(file_bytes: &[u8]) -> Result<ProcessingOutput, ProcessingError> {
+/// of the first page at various sizes, along with preview images for the
+/// first `preview_pages` pages
+pub async fn process_pdf(
+    file_bytes: &[u8],
+    preview_pages: u32,
+) -> Result<ProcessingOutput, ProcessingError> {
     let pdf_info_args = PdfInfoArgs::default();
 
     // Load the pdf information
@@ -93,16 +100,56 @@ pub async fn process_pdf(file_bytes: &[u8]) -> Result<ProcessingOutput, Processi
 
     let text_args = PdfTextArgs::default();
 
+    let start = Instant::now();
+
     // Extract pdf text
     let pages_text_future = text_all_pages_split(file_bytes, &text_args)
         // Match outer result type with inner type
-        .map_err(ProcessingError::ExtractFileText);
+        .map_err(ProcessingError::ExtractFileText)
+        .map_ok(|pages| (pages, elapsed_ms(start)));
 
     // Generate pdf thumbnails
-    let thumbnail_future = generate_pdf_images_async(&pdf_info, file_bytes)
-        .map_err(ProcessingError::GeneratePdfThumbnail);
+    let preview_pages = preview_pages.min(page_count);
+    let thumbnail_future = generate_pdf_images_async(&pdf_info, file_bytes, preview_pages)
+        .map_err(ProcessingError::GeneratePdfThumbnail)
+        .map_ok(|generated| (generated, elapsed_ms(start)));
+
+    // Extract page sizes and outline (Failure to extract metadata is not fatal)
+    let metadata_future = extract_pdf_metadata(file_bytes, page_count).map(|result| {
+        Ok::<_, ProcessingError>(
+            result
+                .inspect_err(|error| tracing::warn!(?error, "failed to extract pdf metadata"))
+                .ok(),
+        )
+    });
+
+    // Extract word bounding boxes (Failure to extract words is not fatal)
+    let words_future = extract_pdf_words(file_bytes).map(|result| {
+        Ok::<_, ProcessingError>(
+            result
+                .inspect_err(|error| tracing::warn!(?error, "failed to extract pdf words"))
+                .ok(),
+        )
+    });
 
-    let (pages, generated) = tokio::try_join!(pages_text_future, thumbnail_future)?;
+    let (pages_text, thumbnails, pdf_metadata, page_words) = tokio::try_join!(
+        pages_text_future,
+        thumbnail_future,
+        metadata_future,
+        words_future
+    )?;
+
+    let (pages, text_extraction_ms) = pages_text;
+    let (generated, thumbnail_ms) = thumbnails;
+
+    // Encode the word bounding boxes to store as a generated file
+    let page_words_json = page_words.as_ref().and_then(|page_words| {
+        serde_json::to_vec(page_words)
+            .inspect_err(|error| tracing::error!(?error, "failed to encode pdf words"))
+            .ok()
+    });
+
+    let mut page_words = page_words.map(Vec::into_iter);
 
     // Create a combined text content using the PDF page end character
     let page_end = PAGE_END_CHARACTER.to_string();
@@ -116,12 +163,18 @@ pub async fn process_pdf(file_bytes: &[u8]) -> Result<ProcessingOutput, Processi
                 .map(|(page, content)| DocumentPage {
                     page: page as u64,
                     content,
+                    words: page_words
+                        .as_mut()
+                        .and_then(|page_words| page_words.next())
+                        .map(|page_words| page_words.words),
                 })
                 .collect(),
         ),
+        summary: None,
+        text_stats: None,
     };
 
-    let upload_queue = vec![
+    let mut upload_queue = vec![
         QueuedUpload::new(
             mime::IMAGE_JPEG,
             GeneratedFileType::CoverPage,
@@ -144,61 +197,100 @@ pub async fn process_pdf(file_bytes: &[u8]) -> Result<ProcessingOutput, Processi
         ),
     ];
 
+    if let Some(page_words_json) = page_words_json {
+        upload_queue.push(QueuedUpload::new(
+            mime::APPLICATION_JSON,
+            GeneratedFileType::WordBoundingBoxes,
+            page_words_json.into(),
+        ));
+    }
+
+    upload_queue.extend(generated.page_previews_jpeg.into_iter().enumerate().map(
+        |(index, preview)| {
+            QueuedUpload::new(
+                mime::IMAGE_JPEG,
+                GeneratedFileType::PagePreview,
+                preview.into(),
+            )
+            .with_page(i32::try_from(index + 1).ok())
+        },
+    ));
+
     Ok(ProcessingOutput {
         encrypted: false,
         additional_files: Default::default(),
         index_metadata: Some(index_metadata),
         upload_queue,
+        pdf_metadata,
+        pii_analysis: None,
+        timings: ProcessingTimings {
+            text_extraction_ms: Some(text_extraction_ms),
+            thumbnail_ms: Some(thumbnail_ms),
+            ..Default::default()
+        },
     })
 }
 
-/// Check if the provided mime type is for a PDF
-#[inline]
-pub fn is_pdf_file(mime: &Mime) -> bool {
-    if mime.eq(&mime::APPLICATION_PDF) {
-        return true;
-    }
-
-    // Some outdated clients use application/x-pdf for pdf
-    if mime.type_() == mime::APPLICATION && mime.subtype().as_str() == "x-pdf" {
-        return true;
-    }
-
-    false
-}
-
-/// Renders the cover page for a PDF file
-async fn render_pdf_cover(pdf_info: &PdfInfo, pdf: &[u8]) -> Result<DynamicImage, PdfRenderError> {
+/// Renders a single page (Starting at 1) of a PDF file
+async fn render_pdf_page(
+    pdf_info: &PdfInfo,
+    pdf: &[u8],
+    page: u32,
+) -> Result<DynamicImage, PdfRenderError> {
     let args = RenderArgs::default();
-    let page = render_single_page(pdf, pdf_info, OutputFormat::Jpeg, 1, &args).await?;
+    let page = render_single_page(pdf, pdf_info, OutputFormat::Jpeg, page, &args).await?;
 
     Ok(page)
 }
 
-/// Asynchronously generate pdf cover image and its variants
+/// Asynchronously generate pdf cover image and its variants, along with
+/// the preview images for the first `preview_pages` pages
 async fn generate_pdf_images_async(
     pdf_info: &PdfInfo,
     pdf: &[u8],
+    preview_pages: u32,
 ) -> Result<GeneratedPdfImages, GeneratePdfImagesError> {
     tracing::debug!("rendering pdf cover");
-    let page = render_pdf_cover(pdf_info, pdf).await?;
+    let page = render_pdf_page(pdf_info, pdf, 1).await?;
+
+    // The cover page is reused as the first page preview
+    let mut other_pages = Vec::new();
+    for page in 2..=preview_pages {
+        tracing::debug!(page, "rendering pdf page preview");
+        other_pages.push(render_pdf_page(pdf_info, pdf, page).await?);
+    }
 
     tracing::debug!("rendering pdf image variants");
-    let result = generate_pdf_images_variants_async(page).await?;
+    let result = generate_pdf_images_variants_async(page, preview_pages, other_pages).await?;
     Ok(result)
 }
 
 /// Async wrapper around [generate_pdf_images_variants]
 async fn generate_pdf_images_variants_async(
     cover_page: DynamicImage,
+    preview_pages: u32,
+    other_pages: Vec<DynamicImage>,
 ) -> Result<GeneratedPdfImages, GeneratePdfImagesError> {
-    let result =
-        tokio::task::spawn_blocking(move || generate_pdf_images_variants(cover_page)).await??;
+    let result = tokio::task::spawn_blocking(move || {
+        generate_pdf_images_variants(cover_page, preview_pages, other_pages)
+    })
+    .await??;
     Ok(result)
 }
 
-/// Generates the various versions of the PDF cover images
-fn generate_pdf_images_variants(cover_page: DynamicImage) -> ImageResult<GeneratedPdfImages> {
+/// Creates a page preview image from a rendered page
+fn create_page_preview(page: &DynamicImage) -> ImageResult<Vec<u8>> {
+    let page_preview = page.resize(512, 512, image::imageops::FilterType::Triangle);
+    create_img_bytes(&page_preview, ImageFormat::Jpeg)
+}
+
+/// Generates the various versions of the PDF cover images and the
+/// page previews
+fn generate_pdf_images_variants(
+    cover_page: DynamicImage,
+    preview_pages: u32,
+    other_pages: Vec<DynamicImage>,
+) -> ImageResult<GeneratedPdfImages> {
     tracing::debug!("rendering pdf image variants");
     let cover_page_jpeg = create_img_bytes(&cover_page, ImageFormat::Jpeg)?;
 
@@ -207,15 +299,22 @@ fn generate_pdf_images_variants(cover_page: DynamicImage) -> ImageResult<Generat
         create_img_bytes(&thumbnail, ImageFormat::Jpeg)?
     };
 
-    let large_thumbnail_jpeg = {
-        let cover_page_preview = cover_page.resize(512, 512, image::imageops::FilterType::Triangle);
-        create_img_bytes(&cover_page_preview, ImageFormat::Jpeg)?
-    };
+    let large_thumbnail_jpeg = create_page_preview(&cover_page)?;
+
+    let mut page_previews_jpeg = Vec::with_capacity(preview_pages as usize);
+    if preview_pages > 0 {
+        page_previews_jpeg.push(large_thumbnail_jpeg.clone());
+    }
+
+    for page in &other_pages {
+        page_previews_jpeg.push(create_page_preview(page)?);
+    }
 
     Ok(GeneratedPdfImages {
         cover_page_jpeg,
         thumbnail_jpeg,
         large_thumbnail_jpeg,
+        page_previews_jpeg,
     })
 }
 
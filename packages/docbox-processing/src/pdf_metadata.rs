@@ -0,0 +1,341 @@
+//! # PDF Metadata
+//!
+//! Extracts the page dimensions and outline (bookmarks) of a PDF file using
+//! the poppler utilities so they can be persisted alongside the file
+
+use docbox_database::models::file_pdf_metadata::{PdfMetadata, PdfOutlineItem, PdfPageSize};
+use std::process::Stdio;
+use thiserror::Error;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+#[derive(Debug, Error)]
+pub enum PdfMetadataError {
+    #[error("failed to spawn {0}: {1}")]
+    SpawnProcess(&'static str, std::io::Error),
+
+    #[error("failed to write pdf bytes: {0}")]
+    WritePdf(std::io::Error),
+
+    #[error("failed to get output: {0}")]
+    WaitOutput(std::io::Error),
+
+    #[error("{0} failed: {1}")]
+    Failure(&'static str, String),
+}
+
+/// Extract the metadata for a PDF file with `page_count` pages
+///
+/// The outline is treated as optional, failing to extract it will only
+/// result in an empty outline
+pub async fn extract_pdf_metadata(
+    pdf: &[u8],
+    page_count: u32,
+) -> Result<PdfMetadata, PdfMetadataError> {
+    let (pages, outline) = tokio::join!(extract_page_sizes(pdf, page_count), extract_outline(pdf));
+
+    let pages = pages?;
+    let outline = outline.unwrap_or_else(|error| {
+        tracing::warn!(?error, "failed to extract pdf outline");
+        Vec::new()
+    });
+
+    Ok(PdfMetadata {
+        page_count: page_count.min(i32::MAX as u32) as i32,
+        pages,
+        outline,
+    })
+}
+
+/// Extract the size of every page using pdfinfo
+async fn extract_page_sizes(
+    pdf: &[u8],
+    page_count: u32,
+) -> Result<Vec<PdfPageSize>, PdfMetadataError> {
+    let last_page = page_count.to_string();
+    let output = run_poppler_command("pdfinfo", &["-f", "1", "-l", &last_page, "-"], pdf).await?;
+
+    Ok(parse_page_sizes(&output))
+}
+
+/// Extract the document outline using the XML output of pdftohtml
+///
+/// Only the first page is converted as only the outline is required
+async fn extract_outline(pdf: &[u8]) -> Result<Vec<PdfOutlineItem>, PdfMetadataError> {
+    let output = run_poppler_command(
+        "pdftohtml",
+        &["-xml", "-stdout", "-i", "-q", "-f", "1", "-l", "1", "-"],
+        pdf,
+    )
+    .await?;
+
+    Ok(parse_outline(&output))
+}
+
+/// Run a poppler CLI `program` passing the `pdf` through stdin, provides
+/// the stdout of the program
+pub(crate) async fn run_poppler_command(
+    program: &'static str,
+    args: &[&str],
+    pdf: &[u8],
+) -> Result<String, PdfMetadataError> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| PdfMetadataError::SpawnProcess(program, error))?;
+
+    // UNWRAP SAFETY: The child process is guaranteed to have a stdin as .stdin(Stdio::piped()) was called
+    let mut stdin = child.stdin.take().unwrap();
+    stdin
+        .write_all(pdf)
+        .await
+        .map_err(PdfMetadataError::WritePdf)?;
+    drop(stdin);
+
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(PdfMetadataError::WaitOutput)?;
+
+    if !output.status.success() {
+        let value = String::from_utf8_lossy(&output.stderr);
+        return Err(PdfMetadataError::Failure(program, value.to_string()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parse the per page sizes and rotations from pdfinfo output when
+/// a page range was requested:
+///
+/// ```text
+/// Page    1 size: 612 x 792 pts (letter)
+/// Page    1 rot:  0
+/// ```
+fn parse_page_sizes(output: &str) -> Vec<PdfPageSize> {
+    let mut pages: Vec<PdfPageSize> = Vec::new();
+
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        let Some(key) = key.strip_prefix("Page") else {
+            continue;
+        };
+
+        let mut key_parts = key.split_whitespace();
+        let (Some(page), Some(field), None) =
+            (key_parts.next(), key_parts.next(), key_parts.next())
+        else {
+            continue;
+        };
+
+        let Ok(page) = page.parse::<usize>() else {
+            continue;
+        };
+
+        match field {
+            "size" => {
+                let mut parts = value.split_whitespace();
+                let (Some(width), Some("x"), Some(height)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    continue;
+                };
+
+                let (Ok(width), Ok(height)) = (width.parse::<f64>(), height.parse::<f64>()) else {
+                    continue;
+                };
+
+                pages.push(PdfPageSize {
+                    width,
+                    height,
+                    rotation: 0,
+                });
+            }
+            "rot" => {
+                // Rotation applies to the most recently parsed page size
+                if pages.len() == page
+                    && let (Some(last), Ok(rotation)) =
+                        (pages.last_mut(), value.trim().parse::<i32>())
+                {
+                    last.rotation = rotation;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pages
+}
+
+/// Parse the outline from the pdftohtml XML output:
+///
+/// ```text
+/// <outline>
+/// <item page="1">Chapter 1</item>
+/// <outline>
+/// <item page="2">Section 1.1</item>
+/// </outline>
+/// </outline>
+/// ```
+///
+/// Nested outlines belong to the item directly preceding them
+fn parse_outline(output: &str) -> Vec<PdfOutlineItem> {
+    let Some(start) = output.find("<outline>") else {
+        return Vec::new();
+    };
+
+    let mut stack: Vec<Vec<PdfOutlineItem>> = Vec::new();
+    let mut remaining = &output[start..];
+
+    while let Some(tag_start) = remaining.find('<') {
+        remaining = &remaining[tag_start..];
+
+        let Some(tag_end) = remaining.find('>') else {
+            break;
+        };
+
+        let tag = &remaining[1..tag_end];
+        remaining = &remaining[tag_end + 1..];
+
+        if tag == "outline" {
+            stack.push(Vec::new());
+        } else if tag == "/outline" {
+            let Some(items) = stack.pop() else {
+                break;
+            };
+
+            match stack.last_mut() {
+                // Attach to the parent item
+                Some(parent) => match parent.last_mut() {
+                    Some(parent_item) => parent_item.children.extend(items),
+                    None => parent.extend(items),
+                },
+                // Finished the root outline
+                None => return items,
+            }
+        } else if tag == "item" || tag.starts_with("item ") {
+            let Some(text_end) = remaining.find("</item>") else {
+                break;
+            };
+
+            let title = html_escape::decode_html_entities(remaining[..text_end].trim()).to_string();
+            remaining = &remaining[text_end + "</item>".len()..];
+
+            let page = parse_attribute(tag, "page").and_then(|value| value.parse::<i32>().ok());
+
+            if let Some(items) = stack.last_mut() {
+                items.push(PdfOutlineItem {
+                    title,
+                    page,
+                    children: Vec::new(),
+                });
+            }
+        }
+    }
+
+    // Output ended without closing the outline, use what was parsed
+    stack.into_iter().next().unwrap_or_default()
+}
+
+/// Get the value of a double quoted `name` attribute from a XML `tag`
+pub(crate) fn parse_attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!("{name}=\"");
+    let start = tag.find(&pattern)? + pattern.len();
+    let length = tag[start..].find('"')?;
+    Some(&tag[start..start + length])
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_outline, parse_page_sizes};
+    use docbox_database::models::file_pdf_metadata::{PdfOutlineItem, PdfPageSize};
+
+    #[test]
+    fn test_parse_page_sizes() {
+        let output = r#"
+Pages:           2
+Page    1 size:  612 x 792 pts (letter)
+Page    1 rot:   0
+Page    2 size:  595.276 x 841.89 pts (A4)
+Page    2 rot:   90
+File size:       169205 bytes
+        "#;
+
+        assert_eq!(
+            parse_page_sizes(output),
+            vec![
+                PdfPageSize {
+                    width: 612.0,
+                    height: 792.0,
+                    rotation: 0
+                },
+                PdfPageSize {
+                    width: 595.276,
+                    height: 841.89,
+                    rotation: 90
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_outline() {
+        let output = r#"<?xml version="1.0" encoding="UTF-8"?>
+<pdf2xml producer="poppler" version="23.02.0">
+<page number="1" position="absolute" top="0" left="0" height="1188" width="918">
+</page>
+<outline>
+<item page="1">Introduction</item>
+<item page="2">Chapter 1 &amp; 2</item>
+<outline>
+<item page="3">Section 1.1</item>
+<item>External</item>
+</outline>
+<item page="5">Conclusion</item>
+</outline>
+</pdf2xml>
+"#;
+
+        assert_eq!(
+            parse_outline(output),
+            vec![
+                PdfOutlineItem {
+                    title: "Introduction".to_string(),
+                    page: Some(1),
+                    children: vec![]
+                },
+                PdfOutlineItem {
+                    title: "Chapter 1 & 2".to_string(),
+                    page: Some(2),
+                    children: vec![
+                        PdfOutlineItem {
+                            title: "Section 1.1".to_string(),
+                            page: Some(3),
+                            children: vec![]
+                        },
+                        PdfOutlineItem {
+                            title: "External".to_string(),
+                            page: None,
+                            children: vec![]
+                        }
+                    ]
+                },
+                PdfOutlineItem {
+                    title: "Conclusion".to_string(),
+                    page: Some(5),
+                    children: vec![]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_outline_missing() {
+        assert!(parse_outline("<pdf2xml></pdf2xml>").is_empty());
+    }
+}
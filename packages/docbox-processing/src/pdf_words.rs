@@ -0,0 +1,157 @@
+//! # PDF Words
+//!
+//! Extracts the bounding boxes of each word within a PDF file using the
+//! layout mode of pdftotext. The word positions are stored as a generated
+//! JSON file so that frontends can overlay search match highlights on top
+//! of the rendered PDF pages
+
+use crate::pdf_metadata::{PdfMetadataError, parse_attribute, run_poppler_command};
+use docbox_search::models::DocumentWord;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Word bounding boxes for a single page, the generated
+/// [WordBoundingBoxes](docbox_database::models::generated_file::GeneratedFileType::WordBoundingBoxes)
+/// file contains a JSON array of these in page order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PdfPageWords {
+    /// Page index (Starting at 0)
+    pub page: u64,
+    /// Width of the page in points
+    pub width: f64,
+    /// Height of the page in points
+    pub height: f64,
+    /// Words on the page
+    pub words: Vec<DocumentWord>,
+}
+
+/// Extract the word bounding boxes for every page of the `pdf`
+pub async fn extract_pdf_words(pdf: &[u8]) -> Result<Vec<PdfPageWords>, PdfMetadataError> {
+    let output = run_poppler_command("pdftotext", &["-bbox-layout", "-", "-"], pdf).await?;
+    Ok(parse_bbox_layout(&output))
+}
+
+/// Parse the pages and words from the pdftotext bbox layout output:
+///
+/// ```text
+/// <page width="612.000000" height="792.000000">
+///   <flow>
+///     <block xMin="72.0" yMin="71.2" xMax="113.9" yMax="84.5">
+///       <line xMin="72.0" yMin="71.2" xMax="113.9" yMax="84.5">
+///         <word xMin="72.0" yMin="71.2" xMax="113.9" yMax="84.5">Sample</word>
+/// ```
+fn parse_bbox_layout(output: &str) -> Vec<PdfPageWords> {
+    let mut pages: Vec<PdfPageWords> = Vec::new();
+    let mut remaining = output;
+
+    while let Some(tag_start) = remaining.find('<') {
+        remaining = &remaining[tag_start..];
+
+        let Some(tag_end) = remaining.find('>') else {
+            break;
+        };
+
+        let tag = &remaining[1..tag_end];
+        remaining = &remaining[tag_end + 1..];
+
+        if tag.starts_with("page ") {
+            pages.push(PdfPageWords {
+                page: pages.len() as u64,
+                width: parse_number_attribute(tag, "width"),
+                height: parse_number_attribute(tag, "height"),
+                words: Vec::new(),
+            });
+        } else if tag.starts_with("word ") {
+            let Some(text_end) = remaining.find("</word>") else {
+                break;
+            };
+
+            let text = html_escape::decode_html_entities(&remaining[..text_end]).to_string();
+            remaining = &remaining[text_end + "</word>".len()..];
+
+            if let Some(page) = pages.last_mut() {
+                page.words.push(DocumentWord {
+                    text,
+                    x_min: parse_number_attribute(tag, "xMin"),
+                    y_min: parse_number_attribute(tag, "yMin"),
+                    x_max: parse_number_attribute(tag, "xMax"),
+                    y_max: parse_number_attribute(tag, "yMax"),
+                });
+            }
+        }
+    }
+
+    pages
+}
+
+fn parse_number_attribute(tag: &str, name: &str) -> f64 {
+    parse_attribute(tag, name)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PdfPageWords, parse_bbox_layout};
+    use docbox_search::models::DocumentWord;
+
+    #[test]
+    fn test_parse_bbox_layout() {
+        let output = r#"<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml">
+<head>
+<title></title>
+</head>
+<body>
+<doc>
+  <page width="612.000000" height="792.000000">
+    <flow>
+      <block xMin="72.000000" yMin="71.280000" xMax="160.500000" yMax="84.576000">
+        <line xMin="72.000000" yMin="71.280000" xMax="160.500000" yMax="84.576000">
+          <word xMin="72.000000" yMin="71.280000" xMax="113.976000" yMax="84.576000">Sample</word>
+          <word xMin="116.500000" yMin="71.280000" xMax="160.500000" yMax="84.576000">A&amp;B</word>
+        </line>
+      </block>
+    </flow>
+  </page>
+  <page width="595.000000" height="842.000000">
+  </page>
+</doc>
+</body>
+</html>
+"#;
+
+        assert_eq!(
+            parse_bbox_layout(output),
+            vec![
+                PdfPageWords {
+                    page: 0,
+                    width: 612.0,
+                    height: 792.0,
+                    words: vec![
+                        DocumentWord {
+                            text: "Sample".to_string(),
+                            x_min: 72.0,
+                            y_min: 71.28,
+                            x_max: 113.976,
+                            y_max: 84.576,
+                        },
+                        DocumentWord {
+                            text: "A&B".to_string(),
+                            x_min: 116.5,
+                            y_min: 71.28,
+                            x_max: 160.5,
+                            y_max: 84.576,
+                        }
+                    ]
+                },
+                PdfPageWords {
+                    page: 1,
+                    width: 595.0,
+                    height: 842.0,
+                    words: vec![]
+                }
+            ]
+        );
+    }
+}
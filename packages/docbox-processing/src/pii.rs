@@ -0,0 +1,338 @@
+//! # PII
+//!
+//! Opt-in detection of personally identifiable information (PII) within the
+//! text extracted from files. Detection is enabled per upload or per folder
+//! using [ProcessingConfig::detect_pii](crate::ProcessingConfig::detect_pii).
+//!
+//! Detected kinds:
+//! - Email addresses
+//! - Phone numbers (Numbers starting with `+`, `(` or `0` with 8-15 digits)
+//! - Credit card numbers (13-19 digits passing the Luhn checksum)
+//! - New Zealand IRD numbers (8-9 digits passing the IRD checksum)
+//!
+//! Only a masked form of each detected value is stored, findings are used
+//! to flag files for compliance reviews and are not a guarantee that a file
+//! is free of PII
+
+use crate::{ProcessingConfig, ProcessingOutput};
+use docbox_database::models::file_pii_analysis::{PiiAnalysis, PiiFinding, PiiKind};
+use docbox_search::models::DocumentPage;
+use regex::Regex;
+use std::{collections::HashMap, sync::LazyLock};
+
+/// Maximum number of unique findings stored for a single file
+pub const MAX_PII_FINDINGS: usize = 1000;
+
+/// Number of trailing digits left unmasked for detected numbers
+const UNMASKED_DIGITS: usize = 4;
+
+static EMAIL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b")
+        .expect("email regex should be valid")
+});
+
+/// Runs of digits allowing short separators between them, candidates are
+/// then classified based on their digits and checksums
+static NUMBER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:\+ ?|\()?\b\d(?:[ ().-]{0,2}\d)*\b").expect("number regex should be valid")
+});
+
+/// Analyze the extracted text of the processing `output` for PII when
+/// enabled by the processing `config`
+pub fn analyze_output_pii(config: &Option<ProcessingConfig>, output: &mut ProcessingOutput) {
+    let enabled = config
+        .as_ref()
+        .and_then(|config| config.detect_pii)
+        .unwrap_or_default();
+
+    if !enabled {
+        return;
+    }
+
+    // Files without extracted text are not analyzed
+    let Some(pages) = output
+        .index_metadata
+        .as_ref()
+        .and_then(|metadata| metadata.pages.as_deref())
+    else {
+        return;
+    };
+
+    output.pii_analysis = Some(analyze_pages(pages));
+}
+
+/// Analyze the provided `pages` for PII
+pub fn analyze_pages(pages: &[DocumentPage]) -> PiiAnalysis {
+    let mut findings: Vec<PiiFinding> = Vec::new();
+
+    'pages: for page in pages {
+        // Index of each unique value within the findings for the current page
+        let mut page_findings: HashMap<(PiiKind, String), usize> = HashMap::new();
+
+        for (kind, value) in detect_pii(&page.content) {
+            if let Some(index) = page_findings.get(&(kind, value.clone())) {
+                findings[*index].count += 1;
+                continue;
+            }
+
+            if findings.len() >= MAX_PII_FINDINGS {
+                tracing::warn!("maximum number of pii findings reached, ignoring remaining");
+                break 'pages;
+            }
+
+            page_findings.insert((kind, value.clone()), findings.len());
+            findings.push(PiiFinding {
+                kind,
+                page: page.page,
+                value: mask_value(kind, &value),
+                count: 1,
+            });
+        }
+    }
+
+    PiiAnalysis {
+        contains_pii: !findings.is_empty(),
+        findings,
+    }
+}
+
+/// Detect all the PII values within the provided `text`
+fn detect_pii(text: &str) -> Vec<(PiiKind, String)> {
+    let emails = EMAIL_REGEX
+        .find_iter(text)
+        .map(|value| (PiiKind::EmailAddress, value.as_str().to_string()));
+
+    let numbers = NUMBER_REGEX.find_iter(text).filter_map(|value| {
+        let value = value.as_str();
+        classify_number(value).map(|kind| (kind, value.to_string()))
+    });
+
+    emails.chain(numbers).collect()
+}
+
+/// Determine the kind of PII a number `value` represents
+fn classify_number(value: &str) -> Option<PiiKind> {
+    let digits: Vec<u32> = value.chars().filter_map(|char| char.to_digit(10)).collect();
+    let phone_prefixed = value.starts_with(['+', '(', '0']);
+
+    if (13..=19).contains(&digits.len()) && !phone_prefixed && is_valid_luhn(&digits) {
+        return Some(PiiKind::CreditCardNumber);
+    }
+
+    if (8..=9).contains(&digits.len())
+        && !phone_prefixed
+        && is_ird_format(value)
+        && is_valid_ird(&digits)
+    {
+        return Some(PiiKind::IrdNumber);
+    }
+
+    if (8..=15).contains(&digits.len()) && phone_prefixed {
+        return Some(PiiKind::PhoneNumber);
+    }
+
+    None
+}
+
+/// Validate `digits` using the Luhn checksum
+fn is_valid_luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, digit)| {
+            if index % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                *digit
+            }
+        })
+        .sum();
+
+    sum.is_multiple_of(10)
+}
+
+/// Check the `value` is written as an IRD number (i.e 12-345-678, 123-456-789 or
+/// without separators) to avoid matching dates and other formatted numbers
+fn is_ird_format(value: &str) -> bool {
+    let groups: Vec<usize> = value
+        .split(|char: char| !char.is_ascii_digit())
+        .filter(|group| !group.is_empty())
+        .map(str::len)
+        .collect();
+
+    matches!(groups.as_slice(), [8] | [9] | [2 | 3, 3, 3])
+}
+
+/// Validate `digits` using the New Zealand IRD number checksum
+fn is_valid_ird(digits: &[u32]) -> bool {
+    const PRIMARY_WEIGHTS: [u32; 8] = [3, 2, 7, 6, 5, 4, 3, 2];
+    const SECONDARY_WEIGHTS: [u32; 8] = [7, 4, 3, 2, 5, 2, 7, 6];
+
+    let number = digits
+        .iter()
+        .fold(0u64, |acc, digit| acc * 10 + *digit as u64);
+    if !(10_000_000..=150_000_000).contains(&number) {
+        return false;
+    }
+
+    let Some((check_digit, base)) = digits.split_last() else {
+        return false;
+    };
+
+    // Base number is padded to 8 digits
+    let mut padded = [0u32; 8];
+    padded[8 - base.len()..].copy_from_slice(base);
+
+    let compute = |weights: [u32; 8]| {
+        let sum: u32 = padded
+            .iter()
+            .zip(weights)
+            .map(|(digit, weight)| digit * weight)
+            .sum();
+
+        match sum % 11 {
+            0 => 0,
+            remainder => 11 - remainder,
+        }
+    };
+
+    let check = match compute(PRIMARY_WEIGHTS) {
+        10 => compute(SECONDARY_WEIGHTS),
+        check => check,
+    };
+
+    check == *check_digit
+}
+
+/// Mask a detected `value` so that the full value is not stored
+fn mask_value(kind: PiiKind, value: &str) -> String {
+    match kind {
+        PiiKind::EmailAddress => {
+            let (local, domain) = value.split_once('@').unwrap_or((value, ""));
+            let first = local.chars().next().unwrap_or('*');
+            format!("{first}***@{domain}")
+        }
+        PiiKind::PhoneNumber | PiiKind::CreditCardNumber | PiiKind::IrdNumber => {
+            let total_digits = value.chars().filter(char::is_ascii_digit).count();
+            let mut seen_digits = 0;
+
+            value
+                .chars()
+                .map(|char| {
+                    if !char.is_ascii_digit() {
+                        return char;
+                    }
+
+                    seen_digits += 1;
+                    if seen_digits + UNMASKED_DIGITS > total_digits {
+                        char
+                    } else {
+                        '*'
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{analyze_pages, classify_number, detect_pii, mask_value};
+    use docbox_database::models::file_pii_analysis::PiiKind;
+    use docbox_search::models::DocumentPage;
+
+    #[test]
+    fn test_detect_email() {
+        let found = detect_pii("Contact jane.doe@example.co.nz for details");
+        assert_eq!(
+            found,
+            vec![(PiiKind::EmailAddress, "jane.doe@example.co.nz".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_classify_numbers() {
+        assert_eq!(
+            classify_number("4111 1111 1111 1111"),
+            Some(PiiKind::CreditCardNumber)
+        );
+        assert_eq!(classify_number("4111 1111 1111 1112"), None);
+        assert_eq!(classify_number("49-091-850"), Some(PiiKind::IrdNumber));
+        assert_eq!(classify_number("136-410-132"), Some(PiiKind::IrdNumber));
+        assert_eq!(classify_number("49-091-851"), None);
+        assert_eq!(
+            classify_number("+64 21 123 4567"),
+            Some(PiiKind::PhoneNumber)
+        );
+        assert_eq!(classify_number("(09) 123 4567"), Some(PiiKind::PhoneNumber));
+        assert_eq!(classify_number("021 123 4567"), Some(PiiKind::PhoneNumber));
+
+        // Dates and short numbers are not PII
+        assert_eq!(classify_number("2024-10-16"), None);
+        assert_eq!(classify_number("12345"), None);
+    }
+
+    #[test]
+    fn test_mask_value() {
+        assert_eq!(
+            mask_value(PiiKind::EmailAddress, "jane@example.com"),
+            "j***@example.com"
+        );
+        assert_eq!(
+            mask_value(PiiKind::CreditCardNumber, "4111 1111 1111 1234"),
+            "**** **** **** 1234"
+        );
+        assert_eq!(
+            mask_value(PiiKind::PhoneNumber, "+64 21 123 4567"),
+            "+** ** *** 4567"
+        );
+    }
+
+    #[test]
+    fn test_analyze_pages_groups_findings() {
+        let pages = vec![
+            DocumentPage {
+                page: 0,
+                content: "jane@example.com and jane@example.com".to_string(),
+                words: None,
+            },
+            DocumentPage {
+                page: 1,
+                content: "Call 021 123 4567 or email jane@example.com".to_string(),
+                words: None,
+            },
+            DocumentPage {
+                page: 2,
+                content: "Nothing to see here".to_string(),
+                words: None,
+            },
+        ];
+
+        let analysis = analyze_pages(&pages);
+        assert!(analysis.contains_pii);
+        assert_eq!(analysis.findings.len(), 3);
+
+        assert_eq!(analysis.findings[0].kind, PiiKind::EmailAddress);
+        assert_eq!(analysis.findings[0].page, 0);
+        assert_eq!(analysis.findings[0].count, 2);
+
+        assert_eq!(analysis.findings[1].kind, PiiKind::EmailAddress);
+        assert_eq!(analysis.findings[1].page, 1);
+        assert_eq!(analysis.findings[2].kind, PiiKind::PhoneNumber);
+        assert_eq!(analysis.findings[2].value, "*** *** 4567");
+    }
+
+    #[test]
+    fn test_analyze_pages_no_pii() {
+        let analysis = analyze_pages(&[DocumentPage {
+            page: 0,
+            content: "Invoice total 1200.50 due 2024-10-16".to_string(),
+            words: None,
+        }]);
+
+        assert!(!analysis.contains_pii);
+        assert!(analysis.findings.is_empty());
+    }
+}
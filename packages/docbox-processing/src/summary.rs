@@ -0,0 +1,324 @@
+//! # Summary
+//!
+//! Optional summarization of the text extracted from files, generates a short
+//! abstract of the file contents using a language model.
+//!
+//! Summaries are generated through an OpenAI compatible chat completions API,
+//! any provider that exposes this API (OpenAI, Azure OpenAI, Ollama, vLLM, LiteLLM)
+//! can be used as the summary provider.
+//!
+//! Summarization is disabled unless an endpoint is configured.
+//!
+//! ## Environment Variables
+//!
+//! * `DOCBOX_SUMMARY_ENDPOINT` - Base URL of the OpenAI compatible API (i.e https://api.openai.com/v1)
+//! * `DOCBOX_SUMMARY_MODEL` - Model to use when generating summaries
+//! * `DOCBOX_SUMMARY_API_KEY` - Optional API key sent as a bearer token to the endpoint
+//! * `DOCBOX_SUMMARY_MAX_TOKENS` - Maximum number of tokens to generate for a summary (Default: 256)
+//! * `DOCBOX_SUMMARY_TIMEOUT` - Maximum time in seconds to wait for a summary (Default: 60s)
+
+use crate::{ProcessingOutput, QueuedUpload};
+use bytes::Bytes;
+use docbox_database::models::generated_file::GeneratedFileType;
+use docbox_search::models::DocumentPage;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{num::ParseIntError, time::Duration};
+use thiserror::Error;
+
+/// Default maximum number of tokens to generate for a summary
+pub const DEFAULT_SUMMARY_MAX_TOKENS: u32 = 256;
+
+/// Default maximum time to wait for a summary to generate
+pub const DEFAULT_SUMMARY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Maximum length in bytes of the extracted text to provide when generating
+/// a summary, text beyond this length is not included in the summary
+pub const MAX_SUMMARY_INPUT_LENGTH: usize = 24_000;
+
+/// Instructions provided to the model when generating a summary
+const SUMMARY_SYSTEM_PROMPT: &str = "You summarize documents. Write a short, factual abstract \
+    of the provided document text in at most three sentences. Respond with only the abstract \
+    in the same language as the document.";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SummaryConfig {
+    /// Base URL of the OpenAI compatible API
+    pub endpoint: String,
+    /// Model to use when generating summaries
+    pub model: String,
+    /// Optional API key for the endpoint
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Maximum number of tokens to generate for a summary
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Maximum time to wait for a summary to generate
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Error)]
+pub enum SummaryConfigError {
+    /// Model must be specified when an endpoint is provided
+    #[error("DOCBOX_SUMMARY_MODEL must be set when DOCBOX_SUMMARY_ENDPOINT is provided")]
+    MissingModel,
+    /// Invalid max tokens value
+    #[error("DOCBOX_SUMMARY_MAX_TOKENS must be a number")]
+    InvalidMaxTokens(ParseIntError),
+    /// Invalid timeout seconds
+    #[error("DOCBOX_SUMMARY_TIMEOUT must be a number in seconds")]
+    InvalidTimeout(ParseIntError),
+}
+
+impl SummaryConfig {
+    /// Load the summary config from the environment, [None] is returned when
+    /// summarization is not enabled
+    pub fn from_env() -> Result<Option<SummaryConfig>, SummaryConfigError> {
+        let endpoint = match std::env::var("DOCBOX_SUMMARY_ENDPOINT") {
+            Ok(value) if !value.is_empty() => value,
+            _ => return Ok(None),
+        };
+
+        let model =
+            std::env::var("DOCBOX_SUMMARY_MODEL").map_err(|_| SummaryConfigError::MissingModel)?;
+        let api_key = std::env::var("DOCBOX_SUMMARY_API_KEY").ok();
+
+        let max_tokens = std::env::var("DOCBOX_SUMMARY_MAX_TOKENS")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u32>()
+                    .map_err(SummaryConfigError::InvalidMaxTokens)
+            })
+            .transpose()?;
+
+        let timeout = std::env::var("DOCBOX_SUMMARY_TIMEOUT")
+            .ok()
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .map_err(SummaryConfigError::InvalidTimeout)
+                    .map(Duration::from_secs)
+            })
+            .transpose()?;
+
+        Ok(Some(SummaryConfig {
+            endpoint,
+            model,
+            api_key,
+            max_tokens,
+            timeout,
+        }))
+    }
+}
+
+/// Generates summaries using an OpenAI compatible chat completions API
+#[derive(Clone)]
+pub struct SummaryProcessor {
+    client: Client,
+    config: SummaryConfig,
+}
+
+#[derive(Debug, Error)]
+pub enum SummaryError {
+    #[error("failed to build http client")]
+    BuildHttpClient(reqwest::Error),
+
+    #[error("failed to request summary: {0}")]
+    Request(reqwest::Error),
+
+    #[error("summary response did not contain a summary")]
+    EmptyResponse,
+}
+
+#[derive(Serialize)]
+struct ChatCompletionRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    messages: [ChatMessage<'a>; 2],
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatCompletionChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionChoice {
+    message: ChatCompletionMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionMessage {
+    content: Option<String>,
+}
+
+impl SummaryProcessor {
+    pub fn from_config(config: SummaryConfig) -> Result<SummaryProcessor, SummaryError> {
+        let client = Client::builder()
+            .timeout(config.timeout.unwrap_or(DEFAULT_SUMMARY_TIMEOUT))
+            .build()
+            .map_err(SummaryError::BuildHttpClient)?;
+
+        Ok(SummaryProcessor { client, config })
+    }
+
+    /// Generate a summary for the provided `text`
+    pub async fn summarize(&self, text: &str) -> Result<String, SummaryError> {
+        let url = format!(
+            "{}/chat/completions",
+            self.config.endpoint.trim_end_matches('/')
+        );
+
+        let mut request = self.client.post(url).json(&ChatCompletionRequest {
+            model: &self.config.model,
+            max_tokens: self.config.max_tokens.unwrap_or(DEFAULT_SUMMARY_MAX_TOKENS),
+            messages: [
+                ChatMessage {
+                    role: "system",
+                    content: SUMMARY_SYSTEM_PROMPT,
+                },
+                ChatMessage {
+                    role: "user",
+                    content: text,
+                },
+            ],
+        });
+
+        if let Some(api_key) = self.config.api_key.as_ref() {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response: ChatCompletionResponse = request
+            .send()
+            .await
+            .map_err(SummaryError::Request)?
+            .error_for_status()
+            .map_err(SummaryError::Request)?
+            .json()
+            .await
+            .map_err(SummaryError::Request)?;
+
+        response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .map(|content| content.trim().to_string())
+            .filter(|content| !content.is_empty())
+            .ok_or(SummaryError::EmptyResponse)
+    }
+}
+
+/// Create the text to summarize from the extracted `pages`, the text is
+/// truncated to [MAX_SUMMARY_INPUT_LENGTH]. Returns [None] when the pages
+/// contain no text
+pub fn create_summary_input(pages: &[DocumentPage]) -> Option<String> {
+    let mut input = String::new();
+
+    for page in pages {
+        let content = page.content.trim();
+        if content.is_empty() {
+            continue;
+        }
+
+        if !input.is_empty() {
+            input.push_str("\n\n");
+        }
+
+        input.push_str(content);
+
+        if input.len() >= MAX_SUMMARY_INPUT_LENGTH {
+            break;
+        }
+    }
+
+    if input.len() > MAX_SUMMARY_INPUT_LENGTH {
+        let mut end = MAX_SUMMARY_INPUT_LENGTH;
+        while !input.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        input.truncate(end);
+    }
+
+    (!input.is_empty()).then_some(input)
+}
+
+/// Generate a summary for the extracted text within the processing `output`
+/// storing the summary as a generated file and within the index metadata.
+///
+/// Failing to generate a summary does not fail processing, the file is
+/// processed without a summary instead
+pub async fn summarize_output(processor: &SummaryProcessor, output: &mut ProcessingOutput) {
+    let Some(index_metadata) = output.index_metadata.as_mut() else {
+        return;
+    };
+
+    let Some(input) = index_metadata
+        .pages
+        .as_deref()
+        .and_then(create_summary_input)
+    else {
+        return;
+    };
+
+    let summary = match processor.summarize(&input).await {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::warn!(?error, "failed to generate file summary");
+            return;
+        }
+    };
+
+    output.upload_queue.push(QueuedUpload::new(
+        mime::TEXT_PLAIN,
+        GeneratedFileType::Summary,
+        Bytes::from(summary.clone()),
+    ));
+
+    index_metadata.summary = Some(summary);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MAX_SUMMARY_INPUT_LENGTH, create_summary_input};
+    use docbox_search::models::DocumentPage;
+
+    fn page(page: u64, content: &str) -> DocumentPage {
+        DocumentPage {
+            page,
+            content: content.to_string(),
+            words: None,
+        }
+    }
+
+    #[test]
+    fn test_create_summary_input_joins_pages() {
+        let input = create_summary_input(&[page(0, " First "), page(1, ""), page(2, "Second")]);
+        assert_eq!(input.as_deref(), Some("First\n\nSecond"));
+    }
+
+    #[test]
+    fn test_create_summary_input_empty() {
+        assert_eq!(create_summary_input(&[]), None);
+        assert_eq!(create_summary_input(&[page(0, "  \n ")]), None);
+    }
+
+    #[test]
+    fn test_create_summary_input_truncates() {
+        // Multi-byte characters to ensure truncation respects char boundaries
+        let content = "é".repeat(MAX_SUMMARY_INPUT_LENGTH);
+        let input = create_summary_input(&[page(0, &content), page(1, "Ignored")]).unwrap();
+
+        assert!(input.len() <= MAX_SUMMARY_INPUT_LENGTH);
+        assert!(!input.contains("Ignored"));
+    }
+}
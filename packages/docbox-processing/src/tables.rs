@@ -0,0 +1,628 @@
+//! # Tables
+//!
+//! Opt-in extraction of tables from files. Extraction is enabled per upload
+//! or per folder using [ProcessingConfig::extract_tables](crate::ProcessingConfig::extract_tables).
+//!
+//! Supported sources:
+//! - Spreadsheets (xlsx) - Each worksheet with data is extracted as a table
+//! - PDF compatible files - Tables are detected from the word bounding boxes,
+//!   runs of consecutive lines that are split into the same aligned columns
+//!   are treated as a table
+//!
+//! The extracted tables are stored as a single [Tables](GeneratedFileType::Tables)
+//! JSON file along with a [TableCsv](GeneratedFileType::TableCsv) file for each
+//! table. Detection from PDFs is heuristic, tables without clear column spacing
+//! may not be detected
+
+use crate::{ProcessingConfig, ProcessingOutput, QueuedUpload};
+use docbox_database::models::generated_file::GeneratedFileType;
+use docbox_search::models::{DocumentPage, DocumentWord};
+use mime::Mime;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read};
+use utoipa::ToSchema;
+use zip::ZipArchive;
+
+/// Maximum number of tables extracted from a single file
+pub const MAX_TABLES: usize = 100;
+
+/// Maximum number of rows extracted for a single table
+pub const MAX_TABLE_ROWS: usize = 10_000;
+
+/// Minimum number of consecutive aligned lines for a PDF table
+const MIN_PDF_TABLE_ROWS: usize = 3;
+
+const XLSX_MIME: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// Table extracted from a file, the [Tables](GeneratedFileType::Tables)
+/// generated file contains a JSON array of these
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ExtractedTable {
+    /// Page index (Starting at 0) the table was found on, only present
+    /// for PDF compatible files
+    pub page: Option<u64>,
+    /// Name of the worksheet the table was read from, only present
+    /// for spreadsheets
+    pub sheet: Option<String>,
+    /// Cell values of each row of the table
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Extract the tables from the file when enabled by the processing `config`
+/// adding the generated table files to the `output`
+pub fn extract_output_tables(
+    config: &Option<ProcessingConfig>,
+    file_bytes: &[u8],
+    mime: &Mime,
+    output: &mut ProcessingOutput,
+) {
+    let enabled = config
+        .as_ref()
+        .and_then(|config| config.extract_tables)
+        .unwrap_or_default();
+
+    if !enabled {
+        return;
+    }
+
+    let tables = if mime.essence_str() == XLSX_MIME {
+        extract_xlsx_tables(file_bytes)
+    } else {
+        output
+            .index_metadata
+            .as_ref()
+            .and_then(|metadata| metadata.pages.as_deref())
+            .map(extract_pdf_tables)
+            .unwrap_or_default()
+    };
+
+    if tables.is_empty() {
+        return;
+    }
+
+    let tables_json = match serde_json::to_vec(&tables) {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::error!(?error, "failed to encode extracted tables");
+            return;
+        }
+    };
+
+    output.upload_queue.push(QueuedUpload::new(
+        mime::APPLICATION_JSON,
+        GeneratedFileType::Tables,
+        tables_json.into(),
+    ));
+
+    for (index, table) in tables.iter().enumerate() {
+        output.upload_queue.push(
+            QueuedUpload::new(
+                mime::TEXT_CSV,
+                GeneratedFileType::TableCsv,
+                table_to_csv(&table.rows).into(),
+            )
+            .with_page(i32::try_from(index + 1).ok()),
+        );
+    }
+}
+
+/// Encode the table `rows` as CSV
+pub fn table_to_csv(rows: &[Vec<String>]) -> String {
+    let mut output = String::new();
+
+    for row in rows {
+        let line = row
+            .iter()
+            .map(|value| {
+                if value.contains([',', '"', '\n', '\r']) {
+                    format!("\"{}\"", value.replace('"', "\"\""))
+                } else {
+                    value.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        output.push_str(&line);
+        output.push_str("\r\n");
+    }
+
+    output
+}
+
+/// Detect tables from the word bounding boxes of the PDF `pages`
+pub fn extract_pdf_tables(pages: &[DocumentPage]) -> Vec<ExtractedTable> {
+    let mut tables = Vec::new();
+
+    for page in pages {
+        let Some(words) = page.words.as_deref() else {
+            continue;
+        };
+
+        for rows in detect_tables(words) {
+            if tables.len() >= MAX_TABLES {
+                return tables;
+            }
+
+            tables.push(ExtractedTable {
+                page: Some(page.page),
+                sheet: None,
+                rows,
+            });
+        }
+    }
+
+    tables
+}
+
+/// Cell within a line of words
+struct LineCell {
+    x_min: f64,
+    x_max: f64,
+    text: String,
+}
+
+/// Detect the tables within the `words` of a single page
+fn detect_tables(words: &[DocumentWord]) -> Vec<Vec<Vec<String>>> {
+    let lines: Vec<Vec<LineCell>> = group_lines(words)
+        .into_iter()
+        .map(|line| split_cells(&line))
+        .collect();
+
+    let mut tables = Vec::new();
+    let mut run: Vec<&Vec<LineCell>> = Vec::new();
+
+    for line in &lines {
+        let continues_run = line.len() >= 2
+            && run
+                .last()
+                .is_none_or(|previous| columns_aligned(previous, line));
+
+        if continues_run {
+            run.push(line);
+            continue;
+        }
+
+        push_table_run(&mut tables, &run);
+        run.clear();
+
+        if line.len() >= 2 {
+            run.push(line);
+        }
+    }
+
+    push_table_run(&mut tables, &run);
+    tables
+}
+
+/// Add the `run` of aligned lines as a table if it is long enough
+fn push_table_run(tables: &mut Vec<Vec<Vec<String>>>, run: &[&Vec<LineCell>]) {
+    if run.len() < MIN_PDF_TABLE_ROWS {
+        return;
+    }
+
+    tables.push(
+        run.iter()
+            .take(MAX_TABLE_ROWS)
+            .map(|line| line.iter().map(|cell| cell.text.clone()).collect())
+            .collect(),
+    );
+}
+
+/// Lines are aligned when they have the same number of cells and each
+/// cell horizontally overlaps the cell in the same column of the other line
+fn columns_aligned(a: &[LineCell], b: &[LineCell]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a, b)| a.x_min <= b.x_max && b.x_min <= a.x_max)
+}
+
+/// Group the `words` into lines ordered from the top of the page, words
+/// within each line are ordered from left to right
+fn group_lines(words: &[DocumentWord]) -> Vec<Vec<&DocumentWord>> {
+    let mut sorted: Vec<&DocumentWord> = words.iter().collect();
+    sorted.sort_by(|a, b| a.y_min.total_cmp(&b.y_min));
+
+    let mut lines: Vec<Vec<&DocumentWord>> = Vec::new();
+
+    for word in sorted {
+        let center = (word.y_min + word.y_max) / 2.0;
+
+        // Words belong to the current line when they are vertically centered within it
+        let same_line = lines
+            .last()
+            .and_then(|line| line.first())
+            .is_some_and(|first| {
+                let half_height = (first.y_max - first.y_min) / 2.0;
+                let line_center = (first.y_min + first.y_max) / 2.0;
+                (center - line_center).abs() <= half_height
+            });
+
+        match lines.last_mut() {
+            Some(line) if same_line => line.push(word),
+            _ => lines.push(vec![word]),
+        }
+    }
+
+    for line in &mut lines {
+        line.sort_by(|a, b| a.x_min.total_cmp(&b.x_min));
+    }
+
+    lines
+}
+
+/// Split a line of words into cells, words separated by a gap wider
+/// than the height of the text start a new cell
+fn split_cells(line: &[&DocumentWord]) -> Vec<LineCell> {
+    let mut cells: Vec<LineCell> = Vec::new();
+
+    for word in line {
+        let height = word.y_max - word.y_min;
+
+        match cells.last_mut() {
+            Some(cell) if word.x_min - cell.x_max <= height => {
+                cell.text.push(' ');
+                cell.text.push_str(&word.text);
+                cell.x_max = cell.x_max.max(word.x_max);
+            }
+            _ => cells.push(LineCell {
+                x_min: word.x_min,
+                x_max: word.x_max,
+                text: word.text.clone(),
+            }),
+        }
+    }
+
+    cells
+}
+
+/// Extract each worksheet with data from a xlsx spreadsheet as a table
+pub fn extract_xlsx_tables(file_bytes: &[u8]) -> Vec<ExtractedTable> {
+    let Ok(mut archive) = ZipArchive::new(Cursor::new(file_bytes)) else {
+        return Vec::new();
+    };
+
+    let Some(workbook) = read_archive_text(&mut archive, "xl/workbook.xml") else {
+        return Vec::new();
+    };
+
+    let relationships =
+        read_archive_text(&mut archive, "xl/_rels/workbook.xml.rels").unwrap_or_default();
+
+    let shared_strings: Vec<String> = read_archive_text(&mut archive, "xl/sharedStrings.xml")
+        .map(|shared_strings| {
+            xml_elements(&shared_strings, "si")
+                .map(|(_, content)| content.map(xml_text).unwrap_or_default())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut tables = Vec::new();
+
+    for (attributes, _) in xml_elements(&workbook, "sheet") {
+        if tables.len() >= MAX_TABLES {
+            break;
+        }
+
+        let Some(name) = xml_attribute(attributes, "name") else {
+            continue;
+        };
+
+        // Resolve the worksheet path from the workbook relationships
+        let Some(target) = xml_attribute(attributes, "r:id").and_then(|id| {
+            xml_elements(&relationships, "Relationship")
+                .find(|(attributes, _)| xml_attribute(attributes, "Id") == Some(id))
+                .and_then(|(attributes, _)| xml_attribute(attributes, "Target"))
+        }) else {
+            continue;
+        };
+
+        let path = match target.strip_prefix('/') {
+            Some(path) => path.to_string(),
+            None => format!("xl/{target}"),
+        };
+
+        let Some(sheet) = read_archive_text(&mut archive, &path) else {
+            continue;
+        };
+
+        let rows = parse_sheet_rows(&sheet, &shared_strings);
+        if rows.is_empty() {
+            continue;
+        }
+
+        tables.push(ExtractedTable {
+            page: None,
+            sheet: Some(html_escape::decode_html_entities(name).to_string()),
+            rows,
+        });
+    }
+
+    tables
+}
+
+/// Parse the cell values of the rows within a worksheet
+fn parse_sheet_rows(sheet: &str, shared_strings: &[String]) -> Vec<Vec<String>> {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+
+    for (attributes, content) in xml_elements(sheet, "row") {
+        if rows.len() >= MAX_TABLE_ROWS {
+            break;
+        }
+
+        // Include the empty rows between rows with values
+        let row_number = xml_attribute(attributes, "r")
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(rows.len() + 1);
+        if row_number > MAX_TABLE_ROWS {
+            break;
+        }
+
+        let mut row: Vec<String> = Vec::new();
+
+        for (attributes, content) in content.into_iter().flat_map(|row| xml_elements(row, "c")) {
+            let value = match (xml_attribute(attributes, "t"), content) {
+                (Some("s"), Some(content)) => xml_elements(content, "v")
+                    .next()
+                    .and_then(|(_, value)| value)
+                    .and_then(|value| value.trim().parse::<usize>().ok())
+                    .and_then(|index| shared_strings.get(index).cloned())
+                    .unwrap_or_default(),
+                (Some("inlineStr"), Some(content)) => xml_text(content),
+                (_, Some(content)) => xml_elements(content, "v")
+                    .next()
+                    .and_then(|(_, value)| value)
+                    .map(|value| html_escape::decode_html_entities(value).to_string())
+                    .unwrap_or_default(),
+                (_, None) => String::new(),
+            };
+
+            let column = xml_attribute(attributes, "r")
+                .and_then(cell_column_index)
+                .unwrap_or(row.len());
+
+            if value.is_empty() {
+                continue;
+            }
+
+            if row.len() <= column {
+                row.resize(column + 1, String::new());
+            }
+            row[column] = value;
+        }
+
+        if row.is_empty() {
+            continue;
+        }
+
+        rows.resize(row_number - 1, Vec::new());
+        rows.push(row);
+    }
+
+    // Pad the rows to have the same number of columns
+    let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+    for row in &mut rows {
+        row.resize(columns, String::new());
+    }
+
+    rows
+}
+
+/// Get the column index (Starting at 0) from a cell reference (i.e "C4" -> 2)
+fn cell_column_index(reference: &str) -> Option<usize> {
+    let letters = reference
+        .chars()
+        .take_while(|value| value.is_ascii_alphabetic());
+
+    let mut index: usize = 0;
+    let mut length = 0;
+
+    for letter in letters {
+        index = index * 26 + (letter.to_ascii_uppercase() as usize - 'A' as usize + 1);
+        length += 1;
+    }
+
+    if length == 0 {
+        return None;
+    }
+
+    Some(index - 1)
+}
+
+fn read_archive_text(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Option<String> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut value = String::new();
+    file.read_to_string(&mut value).ok()?;
+    Some(value)
+}
+
+/// Iterates the elements named `name` within the `xml` yielding the attributes
+/// of each element and its inner content ([None] for self closing elements).
+///
+/// Elements nested within an element of the same name are not supported
+fn xml_elements<'a>(
+    xml: &'a str,
+    name: &str,
+) -> impl Iterator<Item = (&'a str, Option<&'a str>)> + 'a {
+    let open_tag = format!("<{name}");
+    let close_tag = format!("</{name}>");
+    let mut remaining = xml;
+
+    std::iter::from_fn(move || {
+        loop {
+            let start = remaining.find(&open_tag)? + open_tag.len();
+            let after = &remaining[start..];
+
+            // Skip elements that only start with the same name
+            if !after.starts_with([' ', '>', '/', '\t', '\r', '\n']) {
+                remaining = after;
+                continue;
+            }
+
+            let tag_end = after.find('>')?;
+            let tag = &after[..tag_end];
+            let content = &after[tag_end + 1..];
+
+            if let Some(attributes) = tag.strip_suffix('/') {
+                remaining = content;
+                return Some((attributes, None));
+            }
+
+            let content_end = content.find(&close_tag)?;
+            remaining = &content[content_end + close_tag.len()..];
+            return Some((tag, Some(&content[..content_end])));
+        }
+    })
+}
+
+/// Get the value of the attribute `name` from the element `attributes`
+fn xml_attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!(" {name}=\"");
+    let start = attributes.find(&pattern)? + pattern.len();
+    let length = attributes[start..].find('"')?;
+    Some(&attributes[start..start + length])
+}
+
+/// Combined text of the `<t>` elements within the `xml`
+fn xml_text(xml: &str) -> String {
+    xml_elements(xml, "t")
+        .filter_map(|(_, content)| content)
+        .map(|value| html_escape::decode_html_entities(value))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        ExtractedTable, cell_column_index, detect_tables, extract_xlsx_tables, table_to_csv,
+    };
+    use docbox_search::models::DocumentWord;
+    use std::io::{Cursor, Write};
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    fn word(text: &str, x_min: f64, y_min: f64) -> DocumentWord {
+        DocumentWord {
+            text: text.to_string(),
+            x_min,
+            y_min,
+            x_max: x_min + text.len() as f64 * 5.0,
+            y_max: y_min + 10.0,
+        }
+    }
+
+    #[test]
+    fn test_detect_pdf_table() {
+        let words = vec![
+            word("Quarterly", 72.0, 50.0),
+            word("report", 122.0, 50.0),
+            word("Item", 72.0, 100.0),
+            word("Amount", 200.0, 100.0),
+            word("Office", 72.0, 115.0),
+            word("chairs", 104.0, 115.0),
+            word("1,200", 205.0, 115.0),
+            word("Desks", 72.0, 130.0),
+            word("800", 210.0, 130.0),
+            word("Closing", 72.0, 180.0),
+            word("remarks", 112.0, 180.0),
+        ];
+
+        let tables = detect_tables(&words);
+        assert_eq!(
+            tables,
+            vec![vec![
+                vec!["Item".to_string(), "Amount".to_string()],
+                vec!["Office chairs".to_string(), "1,200".to_string()],
+                vec!["Desks".to_string(), "800".to_string()],
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_detect_pdf_table_too_short() {
+        let words = vec![
+            word("Item", 72.0, 100.0),
+            word("Amount", 200.0, 100.0),
+            word("Desks", 72.0, 115.0),
+            word("800", 210.0, 115.0),
+        ];
+
+        assert!(detect_tables(&words).is_empty());
+    }
+
+    #[test]
+    fn test_cell_column_index() {
+        assert_eq!(cell_column_index("A1"), Some(0));
+        assert_eq!(cell_column_index("C4"), Some(2));
+        assert_eq!(cell_column_index("AA10"), Some(26));
+        assert_eq!(cell_column_index("12"), None);
+    }
+
+    #[test]
+    fn test_table_to_csv() {
+        let rows = vec![
+            vec!["Name".to_string(), "Note".to_string()],
+            vec!["Desk".to_string(), "Oak, \"large\"".to_string()],
+        ];
+
+        assert_eq!(
+            table_to_csv(&rows),
+            "Name,Note\r\nDesk,\"Oak, \"\"large\"\"\"\r\n"
+        );
+    }
+
+    #[test]
+    fn test_extract_xlsx_tables() {
+        let files: &[(&str, &str)] = &[
+            (
+                "xl/workbook.xml",
+                r#"<workbook><sheets><sheet name="Costs &amp; Totals" sheetId="1" r:id="rId1"/><sheet name="Empty" sheetId="2" r:id="rId2"/></sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Target="/xl/worksheets/sheet2.xml"/></Relationships>"#,
+            ),
+            (
+                "xl/sharedStrings.xml",
+                r#"<sst><si><t>Item</t></si><si><r><t>Am</t></r><r><t>ount</t></r></si><si><t>Desks</t></si></sst>"#,
+            ),
+            (
+                "xl/worksheets/sheet1.xml",
+                r#"<worksheet><cols><col min="1"/></cols><sheetData><row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c></row><row r="3"><c r="A3" t="s"><v>2</v></c><c r="C3" s="1"><v>800</v></c><c r="D3" s="1"/></row><row r="4"><c r="B4" t="inlineStr"><is><t>Total</t></is></c></row></sheetData></worksheet>"#,
+            ),
+            (
+                "xl/worksheets/sheet2.xml",
+                r#"<worksheet><sheetData/></worksheet>"#,
+            ),
+        ];
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        let archive = writer.finish().unwrap().into_inner();
+
+        let tables = extract_xlsx_tables(&archive);
+        let expected: Vec<Vec<String>> = vec![
+            vec!["Item", "Amount", ""],
+            vec!["", "", ""],
+            vec!["Desks", "", "800"],
+            vec!["", "Total", ""],
+        ]
+        .into_iter()
+        .map(|row| row.into_iter().map(String::from).collect())
+        .collect();
+
+        assert_eq!(
+            tables,
+            vec![ExtractedTable {
+                page: None,
+                sheet: Some("Costs & Totals".to_string()),
+                rows: expected,
+            }]
+        );
+    }
+}
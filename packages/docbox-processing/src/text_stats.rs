@@ -0,0 +1,87 @@
+//! # Text Stats
+//!
+//! Word count, character count and estimated reading time computed from
+//! the text extracted from files
+
+use crate::ProcessingOutput;
+use docbox_database::models::file_text_stats::TextStats;
+use docbox_search::models::DocumentPage;
+
+/// Average silent reading speed used to estimate the reading time
+pub const READING_WORDS_PER_MINUTE: u64 = 238;
+
+/// Compute the text stats for the extracted text of the processing `output`,
+/// files without extracted text don't have text stats
+pub fn analyze_output_text_stats(output: &mut ProcessingOutput) {
+    let Some(metadata) = output.index_metadata.as_mut() else {
+        return;
+    };
+
+    metadata.text_stats = metadata.pages.as_deref().map(compute_text_stats);
+}
+
+/// Compute the text stats for the provided `pages`
+pub fn compute_text_stats(pages: &[DocumentPage]) -> TextStats {
+    let mut word_count: u64 = 0;
+    let mut character_count: u64 = 0;
+
+    for page in pages {
+        for word in page.content.split_whitespace() {
+            word_count += 1;
+            character_count += word.chars().count() as u64;
+        }
+    }
+
+    // Round up so that any text takes at least a second to read
+    let reading_time_seconds = (word_count * 60).div_ceil(READING_WORDS_PER_MINUTE);
+
+    TextStats {
+        word_count: word_count as i64,
+        character_count: character_count as i64,
+        reading_time_seconds: reading_time_seconds as i64,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::compute_text_stats;
+    use docbox_search::models::DocumentPage;
+
+    fn page(page: u64, content: &str) -> DocumentPage {
+        DocumentPage {
+            page,
+            content: content.to_string(),
+            words: None,
+        }
+    }
+
+    #[test]
+    fn test_compute_text_stats() {
+        let stats = compute_text_stats(&[
+            page(0, "Sample document\nThis is a second line"),
+            page(1, "  This is the second page\n\n"),
+        ]);
+
+        assert_eq!(stats.word_count, 12);
+        assert_eq!(stats.character_count, 50);
+        assert_eq!(stats.reading_time_seconds, 4);
+    }
+
+    #[test]
+    fn test_compute_text_stats_reading_time() {
+        let content = vec!["word"; 476].join(" ");
+        let stats = compute_text_stats(&[page(0, &content)]);
+
+        assert_eq!(stats.word_count, 476);
+        assert_eq!(stats.reading_time_seconds, 120);
+    }
+
+    #[test]
+    fn test_compute_text_stats_empty() {
+        let stats = compute_text_stats(&[page(0, "   \n")]);
+
+        assert_eq!(stats.word_count, 0);
+        assert_eq!(stats.character_count, 0);
+        assert_eq!(stats.reading_time_seconds, 0);
+    }
+}
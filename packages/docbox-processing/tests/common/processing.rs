@@ -35,7 +35,11 @@ pub async fn test_processing_layer(
     let converter = OfficeConverter::ConverterServer(converter_server);
 
     ProcessingLayer {
-        office: OfficeProcessingLayer { converter },
+        office: OfficeProcessingLayer {
+            converter,
+            circuit_breaker: Default::default(),
+        },
         config,
+        summary: None,
     }
 }
@@ -6,6 +6,30 @@ use std::path::Path;
 
 mod common;
 
+/// Test that page previews are generated for the first pages of a PDF file,
+/// limited to the number of pages within the file
+#[tokio::test]
+async fn test_process_pdf_page_previews() {
+    let output = process_sample_file_with_config(
+        "sample.pdf",
+        ProcessingLayerConfig {
+            pdf_preview_pages: Some(5),
+            ..Default::default()
+        },
+    )
+    .await
+    .expect("pdf should produce processing output");
+
+    let preview_pages: Vec<Option<i32>> = output
+        .upload_queue
+        .iter()
+        .filter(|upload| matches!(upload.ty, GeneratedFileType::PagePreview))
+        .map(|upload| upload.page)
+        .collect();
+
+    assert_eq!(preview_pages, vec![Some(1), Some(2)]);
+}
+
 /// Test processing a PDF file
 #[tokio::test]
 async fn test_process_pdf() {
@@ -21,8 +45,8 @@ async fn test_process_pdf() {
 
     assert_eq!(
         output.upload_queue.len(),
-        4,
-        "PDF file should produce 3 images and 1 text file"
+        5,
+        "PDF file should produce 3 images, 1 text file and 1 word bounding boxes file"
     );
 
     // Ensure the files match the expectations
@@ -42,6 +66,10 @@ async fn test_process_pdf() {
     assert_eq!(forth.mime, mime::TEXT_PLAIN);
     assert!(matches!(forth.ty, GeneratedFileType::TextContent));
 
+    let fifth = output.upload_queue.get(4).unwrap();
+    assert_eq!(fifth.mime, mime::APPLICATION_JSON);
+    assert!(matches!(fifth.ty, GeneratedFileType::WordBoundingBoxes));
+
     // Ensure the text content matches expectation
     let text_content = String::from_utf8_lossy(forth.bytes.as_ref());
     assert_eq!(
@@ -64,6 +92,13 @@ async fn test_process_pdf() {
         "Sample document\nThis is a second line\n\n"
     );
 
+    // Ensure the word bounding boxes were extracted for the page
+    let first_page_words = first_page
+        .words
+        .as_ref()
+        .expect("pdf page should have word bounding boxes");
+    assert_eq!(first_page_words.first().unwrap().text, "Sample");
+
     let second_page = pages.get(1).unwrap();
     assert_eq!(second_page.page, 1);
     assert_eq!(
@@ -224,8 +259,8 @@ async fn test_process_ods() {
 
     assert_eq!(
         output.upload_queue.len(),
-        5,
-        "office file should produce 1 pdf, 3 images and 1 text file"
+        6,
+        "office file should produce 1 pdf, 3 images, 1 text file and 1 word bounding boxes file"
     );
 
     // Ensure the files match the expectations
@@ -246,8 +281,12 @@ async fn test_process_ods() {
     assert!(matches!(forth.ty, GeneratedFileType::TextContent));
 
     let fifth = output.upload_queue.get(4).unwrap();
-    assert_eq!(fifth.mime, mime::APPLICATION_PDF);
-    assert!(matches!(fifth.ty, GeneratedFileType::Pdf));
+    assert_eq!(fifth.mime, mime::APPLICATION_JSON);
+    assert!(matches!(fifth.ty, GeneratedFileType::WordBoundingBoxes));
+
+    let sixth = output.upload_queue.get(5).unwrap();
+    assert_eq!(sixth.mime, mime::APPLICATION_PDF);
+    assert!(matches!(sixth.ty, GeneratedFileType::Pdf));
 
     // Ensure the text content matches expectation
     let text_content = String::from_utf8_lossy(forth.bytes.as_ref());
@@ -299,11 +338,17 @@ async fn test_process_xls_encrypted() {
 }
 
 async fn process_sample_file(sample_file: &str) -> Option<ProcessingOutput> {
+    process_sample_file_with_config(sample_file, ProcessingLayerConfig::default()).await
+}
+
+async fn process_sample_file_with_config(
+    sample_file: &str,
+    config: ProcessingLayerConfig,
+) -> Option<ProcessingOutput> {
     let container = test_office_convert_server_container().await;
 
     // Create the processing layer
-    let processing_layer =
-        test_processing_layer(&container, ProcessingLayerConfig::default()).await;
+    let processing_layer = test_processing_layer(&container, config).await;
 
     // Get the sample file
     let samples_path = Path::new("tests/samples/documents");
@@ -365,8 +410,8 @@ fn validate_document_output(output: &ProcessingOutput) {
 
     assert_eq!(
         output.upload_queue.len(),
-        5,
-        "office file should produce 1 pdf, 3 images and 1 text file"
+        6,
+        "office file should produce 1 pdf, 3 images, 1 text file and 1 word bounding boxes file"
     );
 
     // Ensure the files match the expectations
@@ -387,8 +432,12 @@ fn validate_document_output(output: &ProcessingOutput) {
     assert!(matches!(forth.ty, GeneratedFileType::TextContent));
 
     let fifth = output.upload_queue.get(4).unwrap();
-    assert_eq!(fifth.mime, mime::APPLICATION_PDF);
-    assert!(matches!(fifth.ty, GeneratedFileType::Pdf));
+    assert_eq!(fifth.mime, mime::APPLICATION_JSON);
+    assert!(matches!(fifth.ty, GeneratedFileType::WordBoundingBoxes));
+
+    let sixth = output.upload_queue.get(5).unwrap();
+    assert_eq!(sixth.mime, mime::APPLICATION_PDF);
+    assert!(matches!(sixth.ty, GeneratedFileType::Pdf));
 
     // Ensure the text content matches expectation
     let text_content = String::from_utf8_lossy(forth.bytes.as_ref());
@@ -443,8 +492,8 @@ fn validate_workbook_output(output: &ProcessingOutput) {
 
     assert_eq!(
         output.upload_queue.len(),
-        5,
-        "office file should produce 1 pdf, 3 images and 1 text file"
+        6,
+        "office file should produce 1 pdf, 3 images, 1 text file and 1 word bounding boxes file"
     );
 
     // Ensure the files match the expectations
@@ -465,8 +514,12 @@ fn validate_workbook_output(output: &ProcessingOutput) {
     assert!(matches!(forth.ty, GeneratedFileType::TextContent));
 
     let fifth = output.upload_queue.get(4).unwrap();
-    assert_eq!(fifth.mime, mime::APPLICATION_PDF);
-    assert!(matches!(fifth.ty, GeneratedFileType::Pdf));
+    assert_eq!(fifth.mime, mime::APPLICATION_JSON);
+    assert!(matches!(fifth.ty, GeneratedFileType::WordBoundingBoxes));
+
+    let sixth = output.upload_queue.get(5).unwrap();
+    assert_eq!(sixth.mime, mime::APPLICATION_PDF);
+    assert!(matches!(sixth.ty, GeneratedFileType::Pdf));
 
     // Ensure the text content matches expectation
     let text_content = String::from_utf8_lossy(forth.bytes.as_ref());
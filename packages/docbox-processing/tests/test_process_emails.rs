@@ -565,6 +565,46 @@ async fn test_process_email_with_attachment() {
     assert_eq!(additional_file.mime, mime::APPLICATION_PDF);
 }
 
+/// Test processing a email file where the same attachment is attached multiple
+/// times, only one additional file should be produced for the attachment
+#[tokio::test]
+async fn test_process_email_duplicate_attachment() {
+    // Process the file
+    let output = process_sample_file(None, "sample_duplicate_attachment.eml")
+        .await
+        .expect("eml should produce processing output");
+
+    let first = output.upload_queue.first().unwrap();
+    assert!(matches!(first.ty, GeneratedFileType::Metadata));
+
+    let metadata: EmailMetadataDocument =
+        serde_json::from_slice(first.bytes.as_ref()).expect("metadata should be valid json");
+
+    // Both attachments should be listed in the metadata with the same hash
+    assert_eq!(metadata.attachments.len(), 2);
+
+    let first_attachment = metadata.attachments.first().unwrap();
+    let second_attachment = metadata.attachments.get(1).unwrap();
+
+    assert_eq!(first_attachment.name, "notes.txt");
+    assert_eq!(second_attachment.name, "notes-copy.txt");
+    assert_eq!(first_attachment.hash, second_attachment.hash);
+
+    // Only a single copy of the attachment should be produced
+    assert_eq!(
+        output.additional_files.len(),
+        1,
+        "duplicate attachments should only produce one additional file"
+    );
+
+    let additional_file = output
+        .additional_files
+        .first()
+        .expect("should have one additional file");
+
+    assert_eq!(additional_file.name, "notes.txt");
+}
+
 async fn process_sample_file(
     config: Option<ProcessingConfig>,
     sample_file: &str,
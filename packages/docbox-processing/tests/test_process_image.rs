@@ -1,16 +1,9 @@
 use bytes::Bytes;
+use docbox_mime::get_file_name_ext;
 use docbox_processing::image::process_image_async;
 use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, metadata::Orientation};
 use std::{io::Cursor, path::Path};
 
-/// Extracts the extension portion of a file name
-pub fn get_file_name_ext(name: &str) -> Option<String> {
-    let path = Path::new(name);
-    let ext = path.extension()?;
-    let ext = ext.to_str()?;
-    Some(ext.to_string())
-}
-
 /// Tests that samples of supported image formats can be successfully processed
 #[tokio::test]
 async fn test_image_formats_supported() {
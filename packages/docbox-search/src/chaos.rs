@@ -0,0 +1,267 @@
+//! # Chaos
+//!
+//! Fault injection wrapper around another search index intended for testing,
+//! adds configurable latency and randomly fails operations to verify retry,
+//! rollback and partial failure behavior of code using the search index.
+//!
+//! The [ChaosSearchConfig] is shared between the [ChaosSearchIndexFactory]
+//! and all indexes created from it, updating the config using
+//! [ChaosSearchIndexFactory::set_config] applies to existing indexes.
+
+use crate::{
+    SearchError, SearchIndex, SearchIndexFactory, TenantSearchIndex,
+    language::SearchLanguage,
+    models::{
+        FileSearchRequest, FileSearchResults, SearchIndexData, SearchRequest, SearchResults,
+        SuggestRequest, SuggestResults, UpdateSearchIndexData,
+    },
+};
+use docbox_database::{
+    DbTransaction,
+    models::{
+        document_box::{DocumentBoxScopeRaw, DocumentBoxScopeRawRef},
+        file::FileId,
+        folder::FolderId,
+        tenant::Tenant,
+    },
+};
+use std::{
+    ops::RangeInclusive,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Search operations that faults can be injected into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchOperation {
+    CreateIndex,
+    IndexExists,
+    DeleteIndex,
+    SearchIndex,
+    SearchIndexFile,
+    SuggestIndex,
+    AddData,
+    UpdateData,
+    DeleteData,
+    DeleteByScope,
+    GetPendingMigrations,
+    ApplyMigration,
+}
+
+/// Configuration for the faults to inject
+#[derive(Debug, Clone, Default)]
+pub struct ChaosSearchConfig {
+    /// Latency added before each operation, a random duration within
+    /// the range is chosen for each operation
+    pub latency: Option<RangeInclusive<Duration>>,
+    /// Probability between 0.0 and 1.0 that an operation will fail
+    pub failure_rate: f64,
+    /// Operations to inject faults into, [None] to inject faults into
+    /// all operations
+    pub operations: Option<Vec<SearchOperation>>,
+}
+
+impl ChaosSearchConfig {
+    /// Config that fails every one of the provided `operations`
+    pub fn fail(operations: impl Into<Vec<SearchOperation>>) -> Self {
+        Self {
+            latency: None,
+            failure_rate: 1.0,
+            operations: Some(operations.into()),
+        }
+    }
+
+    fn applies_to(&self, operation: SearchOperation) -> bool {
+        self.operations
+            .as_ref()
+            .is_none_or(|operations| operations.contains(&operation))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ChaosSearchError {
+    #[error("injected search fault during {0:?}")]
+    InjectedFault(SearchOperation),
+}
+
+/// Factory wrapping another [SearchIndexFactory] to create [ChaosSearchIndex]'s
+#[derive(Clone)]
+pub struct ChaosSearchIndexFactory {
+    inner: Box<SearchIndexFactory>,
+    config: Arc<Mutex<ChaosSearchConfig>>,
+}
+
+impl ChaosSearchIndexFactory {
+    /// Wrap the `inner` factory injecting faults based on the `config`
+    pub fn new(inner: SearchIndexFactory, config: ChaosSearchConfig) -> Self {
+        Self {
+            inner: Box::new(inner),
+            config: Arc::new(Mutex::new(config)),
+        }
+    }
+
+    /// Replace the current fault config, applies to all indexes
+    /// created from this factory
+    pub fn set_config(&self, config: ChaosSearchConfig) {
+        *self.config.lock().unwrap_or_else(PoisonError::into_inner) = config;
+    }
+
+    /// Data residency region of the inner factory
+    pub fn data_region(&self) -> Option<&str> {
+        self.inner.data_region()
+    }
+
+    /// Default language of the inner factory
+    pub fn language(&self) -> Option<SearchLanguage> {
+        self.inner.language()
+    }
+
+    /// Clear the cached search credentials of the inner factory
+    pub async fn flush_credentials(&self) {
+        Box::pin(self.inner.flush_credentials()).await
+    }
+
+    /// Create a search index for the `tenant` wrapping an index from the inner factory
+    pub fn create_search_index(&self, tenant: &Tenant) -> ChaosSearchIndex {
+        ChaosSearchIndex {
+            inner: Box::new(self.inner.create_search_index(tenant)),
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// Search index injecting faults into the operations of an inner index
+#[derive(Clone)]
+pub struct ChaosSearchIndex {
+    inner: Box<TenantSearchIndex>,
+    config: Arc<Mutex<ChaosSearchConfig>>,
+}
+
+impl ChaosSearchIndex {
+    /// Apply the configured faults for the `operation`
+    async fn inject(&self, operation: SearchOperation) -> Result<(), SearchError> {
+        let (latency, fail) = {
+            let config = self.config.lock().unwrap_or_else(PoisonError::into_inner);
+            if !config.applies_to(operation) {
+                return Ok(());
+            }
+
+            let latency = config.latency.clone().map(rand::random_range);
+            let fail = rand::random_bool(config.failure_rate.clamp(0.0, 1.0));
+            (latency, fail)
+        };
+
+        if let Some(latency) = latency {
+            tokio::time::sleep(latency).await;
+        }
+
+        if fail {
+            tracing::debug!(?operation, "injecting search fault");
+            return Err(ChaosSearchError::InjectedFault(operation).into());
+        }
+
+        Ok(())
+    }
+}
+
+impl SearchIndex for ChaosSearchIndex {
+    async fn create_index(&self) -> Result<(), SearchError> {
+        self.inject(SearchOperation::CreateIndex).await?;
+        Box::pin(self.inner.create_index()).await
+    }
+
+    async fn index_exists(&self) -> Result<bool, SearchError> {
+        self.inject(SearchOperation::IndexExists).await?;
+        Box::pin(self.inner.index_exists()).await
+    }
+
+    async fn recreate_index(&self) -> Result<(), SearchError> {
+        self.inject(SearchOperation::CreateIndex).await?;
+        Box::pin(self.inner.recreate_index()).await
+    }
+
+    async fn heal_index(&self) -> Result<bool, SearchError> {
+        self.inject(SearchOperation::IndexExists).await?;
+        Box::pin(self.inner.heal_index()).await
+    }
+
+    async fn delete_index(&self) -> Result<(), SearchError> {
+        self.inject(SearchOperation::DeleteIndex).await?;
+        Box::pin(self.inner.delete_index()).await
+    }
+
+    async fn search_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        query: SearchRequest,
+        folder_children: Option<Vec<FolderId>>,
+    ) -> Result<SearchResults, SearchError> {
+        self.inject(SearchOperation::SearchIndex).await?;
+        Box::pin(self.inner.search_index(scope, query, folder_children)).await
+    }
+
+    async fn suggest_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        query: SuggestRequest,
+    ) -> Result<SuggestResults, SearchError> {
+        self.inject(SearchOperation::SuggestIndex).await?;
+        Box::pin(self.inner.suggest_index(scope, query)).await
+    }
+
+    async fn search_index_file(
+        &self,
+        scope: &DocumentBoxScopeRaw,
+        file_id: FileId,
+        query: FileSearchRequest,
+    ) -> Result<FileSearchResults, SearchError> {
+        self.inject(SearchOperation::SearchIndexFile).await?;
+        Box::pin(self.inner.search_index_file(scope, file_id, query)).await
+    }
+
+    async fn add_data(&self, data: Vec<SearchIndexData>) -> Result<(), SearchError> {
+        self.inject(SearchOperation::AddData).await?;
+        Box::pin(self.inner.add_data(data)).await
+    }
+
+    async fn update_data(
+        &self,
+        item_id: Uuid,
+        data: UpdateSearchIndexData,
+    ) -> Result<(), SearchError> {
+        self.inject(SearchOperation::UpdateData).await?;
+        Box::pin(self.inner.update_data(item_id, data)).await
+    }
+
+    async fn delete_data(&self, id: Uuid) -> Result<(), SearchError> {
+        self.inject(SearchOperation::DeleteData).await?;
+        Box::pin(self.inner.delete_data(id)).await
+    }
+
+    async fn delete_by_scope(&self, scope: DocumentBoxScopeRawRef<'_>) -> Result<(), SearchError> {
+        self.inject(SearchOperation::DeleteByScope).await?;
+        Box::pin(self.inner.delete_by_scope(scope)).await
+    }
+
+    async fn get_pending_migrations(
+        &self,
+        applied_names: Vec<String>,
+    ) -> Result<Vec<String>, SearchError> {
+        self.inject(SearchOperation::GetPendingMigrations).await?;
+        Box::pin(self.inner.get_pending_migrations(applied_names)).await
+    }
+
+    async fn apply_migration(
+        &self,
+        tenant: &Tenant,
+        root_t: &mut DbTransaction<'_>,
+        t: &mut DbTransaction<'_>,
+        name: &str,
+    ) -> Result<(), SearchError> {
+        self.inject(SearchOperation::ApplyMigration).await?;
+        // Applied migration is recorded by the outer index
+        Box::pin(self.inner.apply_backend_migration(tenant, root_t, t, name)).await
+    }
+}
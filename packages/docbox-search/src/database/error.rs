@@ -1,8 +1,12 @@
+use crate::language::UnknownSearchLanguage;
 use docbox_database::{DbConnectErr, DbErr};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
-pub enum DatabaseSearchIndexFactoryError {}
+pub enum DatabaseSearchIndexFactoryError {
+    #[error(transparent)]
+    InvalidLanguage(#[from] UnknownSearchLanguage),
+}
 
 #[derive(Debug, Error)]
 pub enum DatabaseSearchError {
@@ -15,6 +19,15 @@ pub enum DatabaseSearchError {
     #[error("failed to search index")]
     SearchIndex(DbErr),
 
+    #[error("failed to query search facets")]
+    SearchFacets(DbErr),
+
+    #[error("failed to serialize advanced query")]
+    SerializeAdvancedQuery(serde_json::Error),
+
+    #[error("failed to query suggestions")]
+    Suggest(DbErr),
+
     #[error("failed to search file pages")]
     SearchFilePages,
 
@@ -24,6 +37,9 @@ pub enum DatabaseSearchError {
     #[error("failed to apply migration")]
     ApplyMigration(DbErr),
 
+    #[error("failed to set search language")]
+    SetLanguage(DbErr),
+
     #[error("failed to add search data")]
     AddData(DbErr),
 }
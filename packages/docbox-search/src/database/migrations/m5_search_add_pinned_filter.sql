@@ -0,0 +1,132 @@
+-- Filter search results by the pinned state of the items
+ALTER TYPE docbox_search_filters ADD ATTRIBUTE "pinned" BOOLEAN;
+
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION docbox_search_links(p_query_text TEXT, p_query_ts tsquery, p_filters docbox_search_filters)
+RETURNS SETOF docbox_search_match
+LANGUAGE sql
+STABLE
+AS $$
+SELECT
+    'Link'::docbox_search_item_type AS "item_type",
+    "link"."id" AS "item_id",
+    "folder"."document_box" AS "document_box",
+    (p_filters.include_name AND "link"."name_tsv" @@ p_query_ts) AS "name_match_tsv",
+    ts_rank("link"."name_tsv", p_query_ts) AS "name_match_tsv_rank",
+    (p_filters.include_name AND "link"."name" ILIKE '%' || p_query_text || '%') AS "name_match",
+    (p_filters.include_content AND "link"."value" ILIKE '%' || p_query_text || '%') AS "content_match",
+    0::FLOAT8 as "content_rank",
+    0::INT8 AS "total_hits",
+    ARRAY[]::docbox_search_page_match[] AS "page_matches",
+    "link"."created_at" AS "created_at"
+FROM "docbox_links" "link"
+LEFT JOIN "docbox_folders" "folder" ON "link"."folder_id" = "folder"."id"
+WHERE "folder"."document_box" = ANY(p_filters.document_boxes)
+    AND ((p_filters).created_at.start IS NULL OR "link"."created_at" >= (p_filters).created_at.start)
+    AND ((p_filters).created_at.end IS NULL OR "link"."created_at" <= (p_filters).created_at.end)
+    AND (p_filters.created_by IS NULL OR "link"."created_by" = p_filters.created_by)
+    AND (p_filters.pinned IS NULL OR "link"."pinned" = p_filters.pinned)
+    AND (p_filters.folder_children IS NULL OR "link"."folder_id" = ANY(p_filters.folder_children))
+    AND (
+        (p_filters.include_name AND "link"."name" ILIKE '%' || p_query_text || '%')
+        OR (p_filters.include_name AND "link"."name_tsv" @@ p_query_ts)
+        OR (p_filters.include_content AND "link"."value" ILIKE '%' || p_query_text || '%')
+    )
+$$;
+
+COMMENT ON FUNCTION docbox_search_links(p_query_text TEXT, p_query_ts tsquery, p_filters docbox_search_filters)
+IS 'Query search results within the links table';
+
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION docbox_search_folders(query_text TEXT, query_ts tsquery, p_filters docbox_search_filters)
+RETURNS SETOF docbox_search_match
+LANGUAGE sql
+STABLE
+AS $$
+SELECT
+    'Folder'::docbox_search_item_type AS "item_type",
+    "folder"."id" AS "item_id",
+    "folder"."document_box" AS "document_box",
+    (p_filters.include_name AND "folder"."name_tsv" @@ query_ts) AS "name_match_tsv",
+    ts_rank("folder"."name_tsv", query_ts) AS "name_match_tsv_rank",
+    (p_filters.include_name AND "folder"."name" ILIKE '%' || query_text || '%') AS "name_match",
+    FALSE as "content_match",
+    0::FLOAT8 as "content_rank",
+    0::INT8 AS "total_hits",
+    ARRAY[]::docbox_search_page_match[] AS "page_matches",
+    "folder"."created_at" AS "created_at"
+FROM "docbox_folders" "folder"
+WHERE "folder"."document_box" = ANY(p_filters.document_boxes)
+    AND ((p_filters).created_at.start IS NULL OR "folder"."created_at" >= (p_filters).created_at.start)
+    AND ((p_filters).created_at.end IS NULL OR "folder"."created_at" <= (p_filters).created_at.end)
+    AND (p_filters.created_by IS NULL OR "folder"."created_by" = p_filters.created_by)
+    AND (p_filters.pinned IS NULL OR "folder"."pinned" = p_filters.pinned)
+    AND (p_filters.folder_children IS NULL OR "folder"."folder_id" = ANY(p_filters.folder_children))
+    AND (
+        (p_filters.include_name AND "folder"."name_tsv" @@ query_ts)
+        OR (p_filters.include_name AND "folder"."name" ILIKE '%' || query_text || '%')
+    )
+$$;
+
+COMMENT ON FUNCTION docbox_search_folders(query_text TEXT, query_ts tsquery, p_filters docbox_search_filters)
+IS 'Query search results within the folders table';
+
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION docbox_search_files(
+    p_query_text TEXT,
+    p_query_ts tsquery,
+    p_filters docbox_search_filters,
+    p_max_pages INT8,
+    p_pages_offset INT8
+)
+RETURNS SETOF docbox_search_match
+LANGUAGE sql
+STABLE
+AS $$
+SELECT
+    'File'::docbox_search_item_type AS "item_type",
+    "file"."id" AS "item_id",
+    "folder"."document_box" AS "document_box",
+    (p_filters.include_name AND "file"."name_tsv" @@ p_query_ts) AS "name_match_tsv",
+    ts_rank("file"."name_tsv", p_query_ts) AS "name_match_tsv_rank",
+    (p_filters.include_name AND "file"."name" ILIKE '%' || p_query_text || '%') AS "name_match",
+    (p_filters.include_content AND COUNT("pages"."page") > 0) AS "content_match",
+    COALESCE(AVG("pages"."content_match_rank"), 0) as "content_rank",
+    COALESCE(MAX("pages"."total_hits"), 0) AS "total_hits",
+    ARRAY_AGG("pages"::docbox_search_page_match ORDER BY "pages"."content_match_rank" DESC, "pages"."page" ASC) AS "page_matches",
+    "file"."created_at"
+FROM "docbox_files" "file"
+LEFT JOIN "docbox_folders" "folder"
+    ON "file"."folder_id" = "folder"."id" AND "folder"."document_box" = ANY(p_filters.document_boxes)
+LEFT JOIN LATERAL (
+    SELECT *
+    FROM docbox_search_file_pages("file"."id", p_query_text, p_query_ts)
+    LIMIT p_max_pages
+    OFFSET p_pages_offset
+) "pages" ON p_filters.include_content
+WHERE "folder"."document_box" = ANY(p_filters.document_boxes)
+    AND (p_filters.mime IS NULL OR "file"."mime" = p_filters.mime)
+    AND ((p_filters).created_at.start IS NULL OR "file"."created_at" >= (p_filters).created_at.start)
+    AND ((p_filters).created_at.end IS NULL OR "file"."created_at" <= (p_filters).created_at.end)
+    AND (p_filters.created_by IS NULL OR "file"."created_by" = p_filters.created_by)
+    AND (p_filters.pinned IS NULL OR "file"."pinned" = p_filters.pinned)
+    AND (p_filters.folder_children IS NULL OR "file"."folder_id" = ANY(p_filters.folder_children))
+    AND (
+        (p_filters.include_name AND "file"."name_tsv" @@ p_query_ts)
+        OR (p_filters.include_name AND "file"."name" ILIKE '%' || p_query_text || '%')
+        OR (p_filters.include_content AND docbox_file_has_matching_pages("file"."id", p_query_text, p_query_ts))
+    )
+GROUP BY file.id, folder.document_box, file.name_tsv, file.name, file.created_at
+$$;
+
+COMMENT ON FUNCTION docbox_search_files(
+    p_query_text TEXT,
+    p_query_ts tsquery,
+    p_filters docbox_search_filters,
+    p_max_pages INT8,
+    p_pages_offset INT8
+)
+IS 'Search for matches within the pages content for a file by ID, also ensures the file is within the p_document_box document box';
@@ -0,0 +1,87 @@
+CREATE TABLE "docbox_files_summaries"
+(
+    "file_id"      UUID NOT NULL,
+
+    "summary"      TEXT NOT NULL,
+
+    "summary_tsv"  tsvector GENERATED ALWAYS AS (to_tsvector('english', "summary")) STORED,
+
+    CONSTRAINT "PK_file_summary" PRIMARY KEY ("file_id")
+);
+
+-- Index summary values
+CREATE INDEX idx_docbox_files_summaries_summary ON "docbox_files_summaries" USING gin ("summary" gin_trgm_ops);
+CREATE INDEX idx_docbox_files_summaries_summary_tsv ON "docbox_files_summaries" USING gin ("summary_tsv");
+
+-- ================================================================
+
+-- Summary matches are boosted above page content matches as the summary
+-- is a condensed description of the entire file
+CREATE OR REPLACE FUNCTION docbox_search_files(
+    p_query_text TEXT,
+    p_query_ts tsquery,
+    p_filters docbox_search_filters,
+    p_max_pages INT8,
+    p_pages_offset INT8
+)
+RETURNS SETOF docbox_search_match
+LANGUAGE sql
+STABLE
+AS $$
+SELECT
+    'File'::docbox_search_item_type AS "item_type",
+    "file"."id" AS "item_id",
+    "folder"."document_box" AS "document_box",
+    (p_filters.include_name AND "file"."name_tsv" @@ p_query_ts) AS "name_match_tsv",
+    ts_rank("file"."name_tsv", p_query_ts) AS "name_match_tsv_rank",
+    (p_filters.include_name AND "file"."name" ILIKE '%' || p_query_text || '%') AS "name_match",
+    (p_filters.include_content AND (
+        COUNT("pages"."page") > 0
+        OR COALESCE("summary"."summary_tsv" @@ p_query_ts, FALSE)
+        OR COALESCE("summary"."summary" ILIKE '%' || p_query_text || '%', FALSE)
+    )) AS "content_match",
+    COALESCE(AVG("pages"."content_match_rank"), 0)
+        + CASE
+            WHEN p_filters.include_content AND "summary"."summary_tsv" @@ p_query_ts
+            THEN ts_rank("summary"."summary_tsv", p_query_ts) * 2
+            ELSE 0
+        END as "content_rank",
+    COALESCE(MAX("pages"."total_hits"), 0) AS "total_hits",
+    ARRAY_AGG("pages"::docbox_search_page_match ORDER BY "pages"."content_match_rank" DESC, "pages"."page" ASC) AS "page_matches",
+    "file"."created_at"
+FROM "docbox_files" "file"
+LEFT JOIN "docbox_folders" "folder"
+    ON "file"."folder_id" = "folder"."id" AND "folder"."document_box" = ANY(p_filters.document_boxes)
+LEFT JOIN "docbox_files_summaries" "summary"
+    ON "summary"."file_id" = "file"."id"
+LEFT JOIN LATERAL (
+    SELECT *
+    FROM docbox_search_file_pages("file"."id", p_query_text, p_query_ts)
+    LIMIT p_max_pages
+    OFFSET p_pages_offset
+) "pages" ON p_filters.include_content
+WHERE "folder"."document_box" = ANY(p_filters.document_boxes)
+    AND (p_filters.mime IS NULL OR "file"."mime" = p_filters.mime)
+    AND ((p_filters).created_at.start IS NULL OR "file"."created_at" >= (p_filters).created_at.start)
+    AND ((p_filters).created_at.end IS NULL OR "file"."created_at" <= (p_filters).created_at.end)
+    AND (p_filters.created_by IS NULL OR "file"."created_by" = p_filters.created_by)
+    AND (p_filters.pinned IS NULL OR "file"."pinned" = p_filters.pinned)
+    AND (p_filters.folder_children IS NULL OR "file"."folder_id" = ANY(p_filters.folder_children))
+    AND (
+        (p_filters.include_name AND "file"."name_tsv" @@ p_query_ts)
+        OR (p_filters.include_name AND "file"."name" ILIKE '%' || p_query_text || '%')
+        OR (p_filters.include_content AND docbox_file_has_matching_pages("file"."id", p_query_text, p_query_ts))
+        OR (p_filters.include_content AND "summary"."summary_tsv" @@ p_query_ts)
+        OR (p_filters.include_content AND "summary"."summary" ILIKE '%' || p_query_text || '%')
+    )
+GROUP BY file.id, folder.document_box, file.name_tsv, file.name, file.created_at, summary.summary, summary.summary_tsv
+$$;
+
+COMMENT ON FUNCTION docbox_search_files(
+    p_query_text TEXT,
+    p_query_ts tsquery,
+    p_filters docbox_search_filters,
+    p_max_pages INT8,
+    p_pages_offset INT8
+)
+IS 'Search for matches within the name, summary and pages content for files, also ensures the file is within the p_document_box document box';
@@ -0,0 +1,12 @@
+-- Version of the document schema each search row was written with, rows
+-- indexed before schema versioning are version 0
+ALTER TABLE "docbox_files_pages"
+    ADD COLUMN "schema_version" INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE "docbox_files_summaries"
+    ADD COLUMN "schema_version" INTEGER NOT NULL DEFAULT 0;
+
+-- Upgrade the existing rows to version 1, the row contents are unchanged
+-- between version 0 and version 1
+UPDATE "docbox_files_pages" SET "schema_version" = 1 WHERE "schema_version" < 1;
+UPDATE "docbox_files_summaries" SET "schema_version" = 1 WHERE "schema_version" < 1;
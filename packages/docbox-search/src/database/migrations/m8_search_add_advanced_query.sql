@@ -0,0 +1,147 @@
+-- Structured advanced query the search results must match
+ALTER TYPE docbox_search_filters ADD ATTRIBUTE "advanced" JSONB;
+
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION docbox_search_advanced_matches(
+    p_query JSONB,
+    p_name TEXT,
+    p_mime TEXT,
+    p_created_at TIMESTAMP WITH TIME ZONE,
+    p_created_by TEXT,
+    p_pinned BOOLEAN
+)
+RETURNS BOOLEAN
+LANGUAGE plpgsql
+IMMUTABLE
+AS $$
+DECLARE
+    v_query JSONB;
+BEGIN
+    CASE p_query->>'type'
+        WHEN 'and' THEN
+            FOR v_query IN SELECT jsonb_array_elements(p_query->'queries') LOOP
+                IF NOT docbox_search_advanced_matches(v_query, p_name, p_mime, p_created_at, p_created_by, p_pinned) THEN
+                    RETURN FALSE;
+                END IF;
+            END LOOP;
+            RETURN TRUE;
+        WHEN 'or' THEN
+            FOR v_query IN SELECT jsonb_array_elements(p_query->'queries') LOOP
+                IF docbox_search_advanced_matches(v_query, p_name, p_mime, p_created_at, p_created_by, p_pinned) THEN
+                    RETURN TRUE;
+                END IF;
+            END LOOP;
+            RETURN FALSE;
+        WHEN 'not' THEN
+            RETURN NOT docbox_search_advanced_matches(p_query->'query', p_name, p_mime, p_created_at, p_created_by, p_pinned);
+        WHEN 'name_contains' THEN
+            -- Escape the LIKE pattern characters within the value
+            RETURN p_name ILIKE '%' || replace(replace(replace(p_query->>'value', '\', '\\'), '%', '\%'), '_', '\_') || '%';
+        WHEN 'mime_in' THEN
+            RETURN COALESCE(p_mime IN (SELECT jsonb_array_elements_text(p_query->'values')), FALSE);
+        WHEN 'created_between' THEN
+            RETURN (p_query->>'start' IS NULL OR p_created_at >= (p_query->>'start')::TIMESTAMP WITH TIME ZONE)
+                AND (p_query->>'end' IS NULL OR p_created_at <= (p_query->>'end')::TIMESTAMP WITH TIME ZONE);
+        WHEN 'created_by_equals' THEN
+            RETURN COALESCE(p_created_by = p_query->>'value', FALSE);
+        WHEN 'pinned_equals' THEN
+            RETURN COALESCE(p_pinned = (p_query->>'value')::BOOLEAN, FALSE);
+        ELSE
+            RETURN FALSE;
+    END CASE;
+END;
+$$;
+
+COMMENT ON FUNCTION docbox_search_advanced_matches(
+    p_query JSONB,
+    p_name TEXT,
+    p_mime TEXT,
+    p_created_at TIMESTAMP WITH TIME ZONE,
+    p_created_by TEXT,
+    p_pinned BOOLEAN
+)
+IS 'Evaluate an advanced search query against the fields of an item';
+
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION docbox_search_item_matches_advanced(
+    p_item_type docbox_search_item_type,
+    p_item_id UUID,
+    p_query JSONB
+)
+RETURNS BOOLEAN
+LANGUAGE sql
+STABLE
+AS $$
+SELECT COALESCE((
+    SELECT docbox_search_advanced_matches(
+        p_query,
+        "item"."name",
+        "item"."mime",
+        "item"."created_at",
+        "item"."created_by",
+        "item"."pinned"
+    )
+    FROM (
+        SELECT "name", "mime", "created_at", "created_by", "pinned"
+        FROM "docbox_files"
+        WHERE p_item_type = 'File' AND "id" = p_item_id
+        UNION ALL
+        SELECT "name", NULL, "created_at", "created_by", "pinned"
+        FROM "docbox_links"
+        WHERE p_item_type = 'Link' AND "id" = p_item_id
+        UNION ALL
+        SELECT "name", NULL, "created_at", "created_by", "pinned"
+        FROM "docbox_folders"
+        WHERE p_item_type = 'Folder' AND "id" = p_item_id
+    ) "item"
+    LIMIT 1
+), FALSE)
+$$;
+
+COMMENT ON FUNCTION docbox_search_item_matches_advanced(
+    p_item_type docbox_search_item_type,
+    p_item_id UUID,
+    p_query JSONB
+)
+IS 'Evaluate an advanced search query against a file, link, or folder by ID';
+
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION docbox_search(
+    p_query_text TEXT,
+    p_query_ts tsquery,
+    p_filters docbox_search_filters,
+    p_max_pages INT8,
+    p_pages_offset INT8
+)
+RETURNS SETOF docbox_search_match_ranked
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT
+        "match"::docbox_search_match AS "search_match",
+        ("name_match_tsv_rank"
+        + "content_rank"
+        + CASE WHEN "name_match" THEN 1.0 ELSE 0 END -- Boost result for ILIKE name matches
+        + CASE WHEN "item_type" = 'Link' AND "content_match" THEN 1.0 ELSE 0 END -- Boost link content matches
+        ) AS "rank",
+        COUNT(*) OVER () as "total_count"
+    FROM (
+        SELECT * FROM docbox_search_links(p_query_text, p_query_ts, p_filters)
+        UNION ALL
+        SELECT * FROM docbox_search_folders(p_query_text, p_query_ts, p_filters)
+        UNION ALL
+        SELECT * FROM docbox_search_files(
+            p_query_text,
+            p_query_ts,
+            p_filters,
+            p_max_pages,
+            p_pages_offset
+        )
+    ) "match"
+    WHERE p_filters.advanced IS NULL
+        OR docbox_search_item_matches_advanced("match"."item_type", "match"."item_id", p_filters.advanced)
+    ORDER BY "rank" DESC, "created_at" DESC
+$$;
@@ -0,0 +1,187 @@
+-- Singular row table storing the text search configuration (language) used
+-- to analyze the searchable content
+CREATE TABLE "docbox_search_config"
+(
+    "id"                  BOOLEAN NOT NULL DEFAULT TRUE,
+
+    "text_search_config"  regconfig NOT NULL DEFAULT 'english',
+
+    CONSTRAINT "PK_search_config" PRIMARY KEY ("id"),
+    CONSTRAINT "CHK_search_config_singular" CHECK ("id")
+);
+
+INSERT INTO "docbox_search_config" DEFAULT VALUES;
+
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION docbox_search_text_config()
+RETURNS regconfig
+LANGUAGE sql
+STABLE
+AS $$
+SELECT "text_search_config" FROM "docbox_search_config"
+$$;
+
+COMMENT ON FUNCTION docbox_search_text_config()
+IS 'Text search configuration used to analyze the searchable content';
+
+-- ================================================================
+
+-- Generated columns can only use immutable expressions so the search vectors
+-- are converted to regular columns maintained by triggers that use the
+-- configured text search configuration
+ALTER TABLE "docbox_files" ALTER COLUMN "name_tsv" DROP EXPRESSION;
+ALTER TABLE "docbox_folders" ALTER COLUMN "name_tsv" DROP EXPRESSION;
+ALTER TABLE "docbox_links" ALTER COLUMN "name_tsv" DROP EXPRESSION;
+ALTER TABLE "docbox_files_pages" ALTER COLUMN "content_tsv" DROP EXPRESSION;
+ALTER TABLE "docbox_files_summaries" ALTER COLUMN "summary_tsv" DROP EXPRESSION;
+
+CREATE OR REPLACE FUNCTION docbox_search_update_name_tsv()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    NEW."name_tsv" := to_tsvector(docbox_search_text_config(), NEW."name");
+    RETURN NEW;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION docbox_search_update_content_tsv()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    NEW."content_tsv" := to_tsvector(docbox_search_text_config(), NEW."content");
+    RETURN NEW;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION docbox_search_update_summary_tsv()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    NEW."summary_tsv" := to_tsvector(docbox_search_text_config(), NEW."summary");
+    RETURN NEW;
+END;
+$$;
+
+CREATE TRIGGER "docbox_files_name_tsv"
+BEFORE INSERT OR UPDATE OF "name" ON "docbox_files"
+FOR EACH ROW EXECUTE FUNCTION docbox_search_update_name_tsv();
+
+CREATE TRIGGER "docbox_folders_name_tsv"
+BEFORE INSERT OR UPDATE OF "name" ON "docbox_folders"
+FOR EACH ROW EXECUTE FUNCTION docbox_search_update_name_tsv();
+
+CREATE TRIGGER "docbox_links_name_tsv"
+BEFORE INSERT OR UPDATE OF "name" ON "docbox_links"
+FOR EACH ROW EXECUTE FUNCTION docbox_search_update_name_tsv();
+
+CREATE TRIGGER "docbox_files_pages_content_tsv"
+BEFORE INSERT OR UPDATE OF "content" ON "docbox_files_pages"
+FOR EACH ROW EXECUTE FUNCTION docbox_search_update_content_tsv();
+
+CREATE TRIGGER "docbox_files_summaries_summary_tsv"
+BEFORE INSERT OR UPDATE OF "summary" ON "docbox_files_summaries"
+FOR EACH ROW EXECUTE FUNCTION docbox_search_update_summary_tsv();
+
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION docbox_search_set_text_config(p_config regconfig)
+RETURNS BOOLEAN
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    UPDATE "docbox_search_config"
+    SET "text_search_config" = p_config
+    WHERE "text_search_config" <> p_config;
+
+    IF NOT FOUND THEN
+        RETURN FALSE;
+    END IF;
+
+    UPDATE "docbox_files" SET "name_tsv" = to_tsvector(p_config, "name");
+    UPDATE "docbox_folders" SET "name_tsv" = to_tsvector(p_config, "name");
+    UPDATE "docbox_links" SET "name_tsv" = to_tsvector(p_config, "name");
+    UPDATE "docbox_files_pages" SET "content_tsv" = to_tsvector(p_config, "content");
+    UPDATE "docbox_files_summaries" SET "summary_tsv" = to_tsvector(p_config, "summary");
+
+    RETURN TRUE;
+END;
+$$;
+
+COMMENT ON FUNCTION docbox_search_set_text_config(p_config regconfig)
+IS 'Set the text search configuration and recompute the stored search vectors when it changes, returns whether the configuration changed';
+
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION docbox_search_file_pages(p_file_id UUID, p_query_text TEXT, p_query_ts tsquery)
+RETURNS SETOF docbox_search_page_match
+LANGUAGE sql
+STABLE
+AS $$
+SELECT
+    "p"."page" AS "page",
+    ts_headline(docbox_search_text_config(), "p"."content", p_query_ts, 'StartSel=<em>, StopSel=</em>') as "matched",
+    (ts_rank("p"."content_tsv", p_query_ts)
+       -- Boost result for ILIKE content matches
+        + CASE WHEN "p"."content" ILIKE '%' || p_query_text || '%' THEN 1.0 ELSE 0 END
+    ) AS "content_match_rank",
+   COUNT(*) OVER () AS "total_hits"
+FROM "docbox_files_pages" "p"
+WHERE "p"."file_id" = p_file_id
+    AND (
+        -- Vector matching
+        "p"."content_tsv" @@ p_query_ts
+        -- Case insensitive exact matches
+        OR "p"."content" ILIKE '%' || p_query_text || '%'
+    )
+ORDER BY "content_match_rank" DESC, "page" ASC
+$$;
+
+COMMENT ON FUNCTION docbox_search_file_pages(p_file_id UUID, p_query_text TEXT, p_query_ts tsquery)
+IS 'Search for matches within the pages content for a file by ID';
+
+-- ================================================================
+
+CREATE OR REPLACE FUNCTION docbox_search_file_pages_with_scope(
+    p_document_box TEXT,
+    p_file_id UUID,
+    p_query_text TEXT,
+    p_query_ts tsquery
+)
+RETURNS SETOF docbox_search_page_match
+LANGUAGE sql
+STABLE
+AS $$
+SELECT
+    "p"."page" AS "page",
+    ts_headline(docbox_search_text_config(), "p"."content", p_query_ts, 'StartSel=<em>, StopSel=</em>') as "matched",
+    (ts_rank("p"."content_tsv", p_query_ts)
+       -- Boost result for ILIKE content matches
+        + CASE WHEN "p"."content" ILIKE '%' || p_query_text || '%' THEN 1.0 ELSE 0 END
+    ) AS "content_match_rank",
+   COUNT(*) OVER () AS "total_hits"
+FROM "docbox_files_pages" "p"
+JOIN "docbox_files" "file" ON "file"."id" = p_file_id
+JOIN "docbox_folders" "folder" ON "folder"."id" = "file"."folder_id"
+WHERE "p"."file_id" = p_file_id
+    AND "folder"."document_box" = p_document_box
+    AND (
+        -- Vector matching
+        "p"."content_tsv" @@ p_query_ts
+        -- Case insensitive exact matches
+        OR "p"."content" ILIKE '%' || p_query_text || '%'
+    )
+ORDER BY "content_match_rank" DESC, "page" ASC
+$$;
+
+
+COMMENT ON FUNCTION docbox_search_file_pages_with_scope(
+    p_document_box TEXT,
+    p_file_id UUID,
+    p_query_text TEXT,
+    p_query_ts tsquery
+)
+IS 'Search for matches within the pages content for a file by ID, also ensures the file is within the p_document_box document box';
@@ -19,6 +19,26 @@ const TENANT_MIGRATIONS: &[(&str, &str)] = &[
         "m4_search_functions_and_types",
         include_str!("./m4_search_functions_and_types.sql"),
     ),
+    (
+        "m5_search_add_pinned_filter",
+        include_str!("./m5_search_add_pinned_filter.sql"),
+    ),
+    (
+        "m6_search_add_file_summaries",
+        include_str!("./m6_search_add_file_summaries.sql"),
+    ),
+    (
+        "m7_search_add_schema_version",
+        include_str!("./m7_search_add_schema_version.sql"),
+    ),
+    (
+        "m8_search_add_advanced_query",
+        include_str!("./m8_search_add_advanced_query.sql"),
+    ),
+    (
+        "m9_search_text_search_config",
+        include_str!("./m9_search_text_search_config.sql"),
+    ),
 ];
 
 pub fn get_pending_migrations(applied_names: Vec<String>) -> Vec<String> {
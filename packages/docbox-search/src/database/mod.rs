@@ -11,10 +11,13 @@
 //! inside your postgres database.
 
 use crate::{
-    SearchError, SearchIndex,
+    DEFAULT_SUGGEST_SIZE, MAX_FACET_BUCKETS, SearchError, SearchIndex,
+    language::SearchLanguage,
     models::{
-        FileSearchRequest, FileSearchResults, FlattenedItemResult, PageResult, SearchIndexData,
-        SearchIndexType, SearchRequest, SearchResults, SearchScore,
+        FileSearchRequest, FileSearchResults, FlattenedItemResult, PageResult,
+        SEARCH_SCHEMA_VERSION, SearchExplain, SearchFacet, SearchFacetBucket, SearchFacetField,
+        SearchIndexData, SearchIndexType, SearchRequest, SearchResults, SearchScore,
+        SearchSuggestion, SuggestRequest, SuggestResults,
     },
 };
 use docbox_database::{
@@ -24,17 +27,21 @@ use docbox_database::{
         file::FileId,
         folder::FolderId,
         search::{
-            DocboxSearchDateRange, DocboxSearchFilters, DocboxSearchItemType,
-            DocboxSearchMatchRanked, DocboxSearchPageMatch, SearchOptions,
-            delete_file_pages_by_file_id, delete_file_pages_by_scope, search, search_file_pages,
+            DocboxSearchDateRange, DocboxSearchFacetCount, DocboxSearchFilters,
+            DocboxSearchItemType, DocboxSearchMatchRanked, DocboxSearchPageMatch,
+            DocboxSuggestMatch, SEARCH_QUERY, SearchFacetOptions, SearchOptions,
+            delete_file_pages_by_file_id, delete_file_pages_by_scope,
+            delete_file_summaries_by_scope, delete_file_summary_by_file_id, search, search_facets,
+            search_file_pages, set_text_search_config, suggest,
         },
         tenant::Tenant,
     },
     sqlx,
+    utils::DatabaseErrorExt,
 };
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use std::{sync::Arc, vec};
+use std::{ops::DerefMut, sync::Arc, vec};
 
 pub use error::{DatabaseSearchError, DatabaseSearchIndexFactoryError};
 
@@ -43,12 +50,28 @@ mod migrations;
 
 /// Configuration for a database backend search index
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
-pub struct DatabaseSearchConfig {}
+pub struct DatabaseSearchConfig {
+    /// Data residency region the tenant databases are located within (i.e "eu-west"),
+    /// tenants assigned to a region can only use databases within that region
+    #[serde(default)]
+    pub data_region: Option<String>,
+
+    /// Default language used to analyze the indexed text content, the
+    /// "english" text search configuration is used when not set
+    #[serde(default)]
+    pub language: Option<SearchLanguage>,
+}
 
 impl DatabaseSearchConfig {
     /// Load the configuration from environment variables
     pub fn from_env() -> Result<Self, DatabaseSearchIndexFactoryError> {
-        Ok(Self {})
+        let data_region = std::env::var("DOCBOX_SEARCH_DATA_REGION").ok();
+        let language = SearchLanguage::from_env()?;
+
+        Ok(Self {
+            data_region,
+            language,
+        })
     }
 }
 
@@ -56,6 +79,8 @@ impl DatabaseSearchConfig {
 #[derive(Clone)]
 pub struct DatabaseSearchIndexFactory {
     db: Arc<DatabasePoolCache>,
+    data_region: Option<String>,
+    language: Option<SearchLanguage>,
 }
 
 impl DatabaseSearchIndexFactory {
@@ -65,27 +90,50 @@ impl DatabaseSearchIndexFactory {
         db: Arc<DatabasePoolCache>,
         config: DatabaseSearchConfig,
     ) -> Result<Self, DatabaseSearchIndexFactoryError> {
-        _ = config;
+        Ok(Self {
+            db,
+            data_region: config.data_region,
+            language: config.language,
+        })
+    }
 
-        Ok(Self { db })
+    /// Data residency region the tenant databases are located within
+    pub fn data_region(&self) -> Option<&str> {
+        self.data_region.as_deref()
+    }
+
+    /// Default language used to analyze the indexed text content
+    pub fn language(&self) -> Option<SearchLanguage> {
+        self.language
     }
 
     /// Create a search index for the provided `tenant`
-    pub fn create_search_index(&self, tenant: &Tenant) -> DatabaseSearchIndex {
+    pub fn create_search_index(
+        &self,
+        tenant: &Tenant,
+        language: Option<SearchLanguage>,
+    ) -> DatabaseSearchIndex {
         DatabaseSearchIndex {
             db: IndexDatabaseSource::Pools {
                 db: self.db.clone(),
                 tenant: Arc::new(tenant.clone()),
             },
+            language,
         }
     }
 }
 
+/// Migration that introduced the configurable text search configuration,
+/// the configured language is applied once this migration is applied
+const TEXT_SEARCH_CONFIG_MIGRATION: &str = "m9_search_text_search_config";
+
 /// Database backend search index
 #[derive(Clone)]
 pub struct DatabaseSearchIndex {
     /// Underlying database source
     db: IndexDatabaseSource,
+    /// Language used as the text search configuration
+    language: Option<SearchLanguage>,
 }
 
 #[derive(Clone)]
@@ -106,9 +154,16 @@ impl DatabaseSearchIndex {
     pub fn from_pool(db: DbPool) -> Self {
         Self {
             db: IndexDatabaseSource::Pool(db),
+            language: None,
         }
     }
 
+    /// Set the `language` used as the text search configuration
+    pub fn with_language(mut self, language: Option<SearchLanguage>) -> Self {
+        self.language = language;
+        self
+    }
+
     /// Acquire a database connection
     async fn acquire_db(&self) -> Result<DbPool, SearchError> {
         match &self.db {
@@ -153,6 +208,28 @@ impl SearchIndex for DatabaseSearchIndex {
         Ok(false)
     }
 
+    async fn recreate_index(&self) -> Result<(), SearchError> {
+        // The tables are managed by the tenant database migrations, only the
+        // language needs to be applied which recomputes the stored search
+        // vectors when it has changed
+        let Some(language) = self.language else {
+            return Ok(());
+        };
+
+        let db = self.acquire_db().await?;
+        set_text_search_config(&db, language.regconfig())
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to set search language"))
+            .map_err(DatabaseSearchError::SetLanguage)?;
+
+        Ok(())
+    }
+
+    async fn heal_index(&self) -> Result<bool, SearchError> {
+        // Index is part of the tenant database so it cannot be deleted out-of-band
+        Ok(false)
+    }
+
     async fn delete_index(&self) -> Result<(), SearchError> {
         // No-op
         Ok(())
@@ -167,6 +244,7 @@ impl SearchIndex for DatabaseSearchIndex {
     ) -> Result<crate::models::SearchResults, SearchError> {
         let db = self.acquire_db().await?;
 
+        let facet_fields = query.facet_fields();
         let query_text = query.query.unwrap_or_default();
 
         let mime = query.mime.map(|value| value.0.to_string());
@@ -176,6 +254,9 @@ impl SearchIndex for DatabaseSearchIndex {
 
         let limit = query.size.unwrap_or(50) as i64;
         let offset = query.offset.unwrap_or_default() as i64;
+        let timeout_ms = query.timeout_ms;
+        let explain = query.explain || query.dry_run;
+        let dry_run = query.dry_run;
 
         let filters = DocboxSearchFilters {
             document_boxes: scopes.to_vec(),
@@ -188,9 +269,47 @@ impl SearchIndex for DatabaseSearchIndex {
             }),
             created_by: query.created_by,
             mime,
+            pinned: query.pinned,
+            advanced: query
+                .advanced
+                .map(|value| {
+                    serde_json::to_value(value).map_err(DatabaseSearchError::SerializeAdvancedQuery)
+                })
+                .transpose()?,
         };
 
-        let results = search(
+        let explain = explain.then(|| SearchExplain {
+            backend: "database".to_string(),
+            query: serde_json::json!({
+                "sql": SEARCH_QUERY.trim(),
+                "binds": [query_text, filters, max_pages, pages_offset, limit, offset],
+                "timeout_ms": timeout_ms,
+            }),
+        });
+
+        if dry_run {
+            return Ok(SearchResults {
+                total_hits: 0,
+                results: Vec::new(),
+                timed_out: false,
+                explain,
+                facets: Vec::new(),
+            });
+        }
+
+        // Facet counts are queried separately as they cover every match
+        let facet_options = (!facet_fields.is_empty()).then(|| SearchFacetOptions {
+            query: query_text.clone(),
+            filters: filters.clone(),
+            fields: facet_fields
+                .iter()
+                .map(|field| field.field_name().to_string())
+                .collect(),
+            limit: MAX_FACET_BUCKETS as i64,
+            timeout_ms,
+        });
+
+        let results = match search(
             &db,
             SearchOptions {
                 query: query_text,
@@ -199,22 +318,113 @@ impl SearchIndex for DatabaseSearchIndex {
                 pages_offset,
                 limit,
                 offset,
+                timeout_ms,
             },
         )
         .await
-        .inspect_err(|error| tracing::error!(?error, "failed to search index"))
-        .map_err(DatabaseSearchError::SearchIndex)?;
+        {
+            Ok(value) => value,
+            // Statement timeout was exceeded, postgres does not provide partial
+            // results so an empty timed out result is provided instead
+            Err(error) if error.is_query_canceled() => {
+                tracing::warn!(?timeout_ms, "database search exceeded statement timeout");
+                return Ok(SearchResults {
+                    total_hits: 0,
+                    results: Vec::new(),
+                    timed_out: true,
+                    explain,
+                    facets: Vec::new(),
+                });
+            }
+            Err(error) => {
+                tracing::error!(?error, "failed to search index");
+                return Err(DatabaseSearchError::SearchIndex(error).into());
+            }
+        };
 
         let total_hits = results
             .first()
             .map(|result| result.total_count)
             .unwrap_or_default() as u64;
 
-        let results = results.into_iter().map(FlattenedItemResult::from).collect();
+        let results = results
+            .into_iter()
+            .map(|result| {
+                // Breakdown of the rank components from the search function
+                let explanation = explain.is_some().then(|| {
+                    serde_json::json!({
+                        "rank": result.rank,
+                        "name_match_tsv": result.search_match.name_match_tsv,
+                        "name_match_tsv_rank": result.search_match.name_match_tsv_rank,
+                        "name_match": result.search_match.name_match,
+                        "content_match": result.search_match.content_match,
+                        "content_rank": result.search_match.content_rank,
+                    })
+                });
+
+                FlattenedItemResult {
+                    explanation,
+                    ..FlattenedItemResult::from(result)
+                }
+            })
+            .collect();
+
+        let facets = match facet_options {
+            Some(facet_options) => match search_facets(&db, facet_options).await {
+                Ok(counts) => map_facet_counts(&facet_fields, counts),
+                // Statement timeout was exceeded, the results are still
+                // returned without the facet counts
+                Err(error) if error.is_query_canceled() => {
+                    tracing::warn!(
+                        ?timeout_ms,
+                        "database search facets exceeded statement timeout"
+                    );
+                    return Ok(SearchResults {
+                        total_hits,
+                        results,
+                        timed_out: true,
+                        explain,
+                        facets: Vec::new(),
+                    });
+                }
+                Err(error) => {
+                    tracing::error!(?error, "failed to query search facets");
+                    return Err(DatabaseSearchError::SearchFacets(error).into());
+                }
+            },
+            None => Vec::new(),
+        };
 
         Ok(SearchResults {
             total_hits,
             results,
+            timed_out: false,
+            explain,
+            facets,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn suggest_index(
+        &self,
+        scopes: &[DocumentBoxScopeRaw],
+        query: SuggestRequest,
+    ) -> Result<SuggestResults, SearchError> {
+        let db = self.acquire_db().await?;
+        let limit = query.size.unwrap_or(DEFAULT_SUGGEST_SIZE) as i64;
+
+        let suggestions = suggest(&db, scopes, query.query.trim(), limit)
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to query suggestions");
+                DatabaseSearchError::Suggest(error)
+            })?;
+
+        Ok(SuggestResults {
+            suggestions: suggestions
+                .into_iter()
+                .map(SearchSuggestion::from)
+                .collect(),
         })
     }
 
@@ -254,6 +464,19 @@ impl SearchIndex for DatabaseSearchIndex {
         let db = self.acquire_db().await?;
 
         for item in data {
+            if let Some(summary) = item.summary {
+                sqlx::query(
+                    r#"INSERT INTO "docbox_files_summaries" ("file_id", "summary", "schema_version") VALUES ($1, $2, $3)"#,
+                )
+                .bind(item.item_id)
+                .bind(summary)
+                .bind(SEARCH_SCHEMA_VERSION)
+                .execute(&db)
+                .await
+                .inspect_err(|error| tracing::error!(?error, "failed to add search summary"))
+                .map_err(DatabaseSearchError::AddData)?;
+            }
+
             let pages = match item.pages {
                 Some(value) => value,
                 // Skip anything without pages
@@ -267,16 +490,17 @@ impl SearchIndex for DatabaseSearchIndex {
             let values = pages
                 .iter()
                 .enumerate()
-                .map(|(index, _page)| format!("($1, ${}, ${})", 2 + index * 2, 3 + index * 2))
+                .map(|(index, _page)| format!("($1, $2, ${}, ${})", 3 + index * 2, 4 + index * 2))
                 .join(",");
 
             let query = format!(
-                r#"INSERT INTO "docbox_files_pages" ("file_id", "page", "content") VALUES {values}"#
+                r#"INSERT INTO "docbox_files_pages" ("file_id", "schema_version", "page", "content") VALUES {values}"#
             );
 
             let mut query = sqlx::query(&query)
                 // Shared amongst all values
-                .bind(item.item_id);
+                .bind(item.item_id)
+                .bind(SEARCH_SCHEMA_VERSION);
 
             for page in pages {
                 query = query.bind(page.page as i32).bind(page.content);
@@ -309,6 +533,10 @@ impl SearchIndex for DatabaseSearchIndex {
             .await
             .inspect_err(|error| tracing::error!(?error, "failed to delete search data by id"))
             .map_err(DatabaseSearchError::DeleteData)?;
+        delete_file_summary_by_file_id(&db, id)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to delete search summary by id"))
+            .map_err(DatabaseSearchError::DeleteData)?;
         Ok(())
     }
 
@@ -319,6 +547,12 @@ impl SearchIndex for DatabaseSearchIndex {
             .await
             .inspect_err(|error| tracing::error!(?error, "failed to delete search data by scope"))
             .map_err(DatabaseSearchError::DeleteData)?;
+        delete_file_summaries_by_scope(&db, scope)
+            .await
+            .inspect_err(|error| {
+                tracing::error!(?error, "failed to delete search summaries by scope")
+            })
+            .map_err(DatabaseSearchError::DeleteData)?;
         Ok(())
     }
 
@@ -339,7 +573,18 @@ impl SearchIndex for DatabaseSearchIndex {
         t: &mut docbox_database::DbTransaction<'_>,
         name: &str,
     ) -> Result<(), SearchError> {
-        migrations::apply_migration(t, name).await
+        migrations::apply_migration(t, name).await?;
+
+        if name == TEXT_SEARCH_CONFIG_MIGRATION
+            && let Some(language) = self.language
+        {
+            set_text_search_config(t.deref_mut(), language.regconfig())
+                .await
+                .inspect_err(|error| tracing::error!(?error, "failed to set search language"))
+                .map_err(DatabaseSearchError::SetLanguage)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -361,6 +606,48 @@ impl From<DocboxSearchMatchRanked> for FlattenedItemResult {
             score: SearchScore::Float(rank as f32),
             name_match: search_match.name_match,
             content_match: search_match.content_match,
+            explanation: None,
+        }
+    }
+}
+
+/// Group the facet `counts` into the facets for each of the requested `fields`,
+/// the counts are ordered largest first by the query
+fn map_facet_counts(
+    fields: &[SearchFacetField],
+    counts: Vec<DocboxSearchFacetCount>,
+) -> Vec<SearchFacet> {
+    let mut facets: Vec<SearchFacet> = fields
+        .iter()
+        .map(|field| SearchFacet {
+            field: *field,
+            buckets: Vec::new(),
+        })
+        .collect();
+
+    for count in counts {
+        let facet = facets
+            .iter_mut()
+            .find(|facet| facet.field.field_name() == count.field);
+
+        if let Some(facet) = facet {
+            facet.buckets.push(SearchFacetBucket {
+                value: count.value,
+                count: count.count as u64,
+            });
+        }
+    }
+
+    facets
+}
+
+impl From<DocboxSuggestMatch> for SearchSuggestion {
+    fn from(value: DocboxSuggestMatch) -> Self {
+        SearchSuggestion {
+            item_ty: value.item_type.into(),
+            item_id: value.item_id,
+            document_box: value.document_box,
+            name: value.name,
         }
     }
 }
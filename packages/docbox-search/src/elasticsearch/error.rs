@@ -0,0 +1,38 @@
+use crate::language::UnknownSearchLanguage;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ElasticsearchIndexFactoryError {
+    #[error("missing DOCBOX_ELASTICSEARCH_URL env")]
+    MissingUrl,
+    #[error("failed to parse elasticsearch url")]
+    InvalidUrl,
+    #[error("elasticsearch password must be provided with the username")]
+    MissingPassword,
+    #[error("failed to create http client")]
+    CreateClient,
+    #[error(transparent)]
+    InvalidLanguage(#[from] UnknownSearchLanguage),
+}
+
+#[derive(Debug, Error)]
+pub enum ElasticsearchSearchError {
+    #[error("failed to create index")]
+    CreateIndex,
+    #[error("failed to get index")]
+    GetIndex,
+    #[error("failed to delete index")]
+    DeleteIndex,
+    #[error("failed to search index")]
+    SearchIndex,
+    #[error("failed to add search data")]
+    AddData,
+    #[error("failed to update search data")]
+    UpdateData,
+    #[error("failed to delete search data")]
+    DeleteData,
+    #[error("failed to upgrade search documents")]
+    UpgradeDocuments,
+    #[error("migration not found")]
+    MigrationNotFound,
+}
@@ -0,0 +1,1439 @@
+//! Elasticsearch search backend
+//!
+//! Separate from the OpenSearch backend as Elasticsearch 8.x clusters are not
+//! compatible with the OpenSearch client, Elasticsearch authenticates using API
+//! keys or basic auth instead of AWS request signing and does not support some
+//! of the OpenSearch specific mapping options
+//!
+//! ## Environment Variables
+//!
+//! * `DOCBOX_ELASTICSEARCH_URL` - URL of the Elasticsearch server
+//! * `DOCBOX_ELASTICSEARCH_API_KEY` - Encoded API key to authenticate with
+//! * `DOCBOX_ELASTICSEARCH_USERNAME` - Username to authenticate with using basic auth
+//! * `DOCBOX_ELASTICSEARCH_PASSWORD` - Password to authenticate with using basic auth
+
+use crate::{
+    DEFAULT_SCROLL_SIZE, DEFAULT_SUGGEST_SIZE, MAX_FACET_BUCKETS, SearchError, SearchIndex,
+    language::SearchLanguage,
+    models::{
+        AdvancedSearchQuery, FileSearchRequest, FileSearchResults, FlattenedItemResult, PageResult,
+        SEARCH_SCHEMA_VERSION, SearchExplain, SearchFacet, SearchFacetBucket, SearchFacetField,
+        SearchIndexData, SearchRequest, SearchResults, SearchScore, SearchScrollCursor,
+        SearchScrollPage, SearchSuggestion, SuggestRequest, SuggestResults, UpdateSearchIndexData,
+    },
+};
+use docbox_database::{
+    DbTransaction,
+    models::{
+        document_box::{DocumentBoxScopeRaw, DocumentBoxScopeRawRef},
+        file::FileId,
+        folder::FolderId,
+        tenant::Tenant,
+    },
+};
+use models::{
+    BulkResponse, EsSearchIndexData, EsUpdateSearchIndexData, SearchResponse, SearchResponseHit,
+    SuggestResponse, TermsAggregation,
+};
+use reqwest::{Method, RequestBuilder, StatusCode, header::CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use serde_with::skip_serializing_none;
+use std::{collections::HashMap, sync::Arc};
+use uuid::Uuid;
+
+pub use error::{ElasticsearchIndexFactoryError, ElasticsearchSearchError};
+
+pub mod error;
+mod models;
+
+/// Migrations to apply against the index, the index is created with the
+/// complete mapping so migrations are only needed for future mapping changes
+const ELASTICSEARCH_MIGRATIONS: &[&str] = &[];
+
+/// Duration scroll contexts are kept alive between scroll requests
+const SCROLL_KEEP_ALIVE: &str = "2m";
+
+/// Content type for the newline delimited JSON bodies of the bulk API
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Script upgrading a document to the current [SEARCH_SCHEMA_VERSION]
+///
+/// Version 1: Documents have an explicit pinned state and schema version
+const UPGRADE_DOCUMENT_SCRIPT: &str = r#"
+if (ctx._source.pinned == null) { ctx._source.pinned = false; }
+ctx._source.schema_version = params.schema_version;
+"#;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ElasticsearchConfig {
+    /// URL of the Elasticsearch server
+    pub url: String,
+
+    /// Encoded API key to authenticate with (The "encoded" value returned
+    /// when creating the API key)
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// Username to authenticate with using basic auth
+    #[serde(default)]
+    pub username: Option<String>,
+
+    /// Password to authenticate with using basic auth
+    #[serde(default)]
+    pub password: Option<String>,
+
+    /// Data residency region the search index is located within (i.e "eu-west"),
+    /// tenants assigned to a region can only use search within that region
+    #[serde(default)]
+    pub data_region: Option<String>,
+
+    /// Default language used to analyze the indexed text content
+    #[serde(default)]
+    pub language: Option<SearchLanguage>,
+}
+
+impl ElasticsearchConfig {
+    pub fn from_env() -> Result<Self, ElasticsearchIndexFactoryError> {
+        let url = std::env::var("DOCBOX_ELASTICSEARCH_URL")
+            .or(std::env::var("ELASTICSEARCH_URL"))
+            .map_err(|_| ElasticsearchIndexFactoryError::MissingUrl)?;
+        let api_key = std::env::var("DOCBOX_ELASTICSEARCH_API_KEY").ok();
+        let username = std::env::var("DOCBOX_ELASTICSEARCH_USERNAME").ok();
+        let password = std::env::var("DOCBOX_ELASTICSEARCH_PASSWORD").ok();
+        let data_region = std::env::var("DOCBOX_SEARCH_DATA_REGION").ok();
+        let language = SearchLanguage::from_env()?;
+
+        Ok(Self {
+            url,
+            api_key,
+            username,
+            password,
+            data_region,
+            language,
+        })
+    }
+}
+
+/// Authentication for requests to Elasticsearch
+enum ElasticsearchAuth {
+    /// Authenticate using an encoded API key
+    ApiKey(String),
+    /// Authenticate using basic auth
+    Basic { username: String, password: String },
+}
+
+/// Shared client data (Base URL and authentication)
+struct ElasticsearchClientData {
+    base_url: String,
+    auth: Option<ElasticsearchAuth>,
+}
+
+#[derive(Clone)]
+pub struct ElasticsearchIndexFactory {
+    client: reqwest::Client,
+    client_data: Arc<ElasticsearchClientData>,
+    data_region: Option<String>,
+    language: Option<SearchLanguage>,
+}
+
+impl ElasticsearchIndexFactory {
+    pub fn from_config(
+        config: ElasticsearchConfig,
+    ) -> Result<Self, ElasticsearchIndexFactoryError> {
+        reqwest::Url::parse(&config.url).map_err(|error| {
+            tracing::error!(?error, "failed to parse elasticsearch url");
+            ElasticsearchIndexFactoryError::InvalidUrl
+        })?;
+
+        let auth = match (config.api_key, config.username, config.password) {
+            (Some(api_key), _, _) => Some(ElasticsearchAuth::ApiKey(api_key)),
+            (_, Some(username), Some(password)) => {
+                Some(ElasticsearchAuth::Basic { username, password })
+            }
+            (_, Some(_), None) => return Err(ElasticsearchIndexFactoryError::MissingPassword),
+            _ => None,
+        };
+
+        let client = reqwest::Client::builder()
+            // Don't try and proxy through the proxy
+            .no_proxy()
+            .build()
+            .map_err(|error| {
+                tracing::error!(?error, "failed to create elasticsearch http client");
+                ElasticsearchIndexFactoryError::CreateClient
+            })?;
+
+        let client_data = Arc::new(ElasticsearchClientData {
+            base_url: config.url.trim_end_matches('/').to_string(),
+            auth,
+        });
+
+        Ok(Self {
+            client,
+            client_data,
+            data_region: config.data_region,
+            language: config.language,
+        })
+    }
+
+    /// Data residency region the search index is located within
+    pub fn data_region(&self) -> Option<&str> {
+        self.data_region.as_deref()
+    }
+
+    /// Default language used to analyze the indexed text content
+    pub fn language(&self) -> Option<SearchLanguage> {
+        self.language
+    }
+
+    pub fn create_search_index(
+        &self,
+        tenant: &Tenant,
+        language: Option<SearchLanguage>,
+    ) -> ElasticsearchIndex {
+        ElasticsearchIndex {
+            client: self.client.clone(),
+            client_data: self.client_data.clone(),
+            index: tenant.os_index_name.clone(),
+            language,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ElasticsearchIndex {
+    client: reqwest::Client,
+    client_data: Arc<ElasticsearchClientData>,
+    index: String,
+    /// Language used to analyze the full text content fields
+    language: Option<SearchLanguage>,
+}
+
+impl SearchIndex for ElasticsearchIndex {
+    async fn create_index(&self) -> Result<(), SearchError> {
+        let text_field = self.text_field();
+
+        self.request(Method::PUT, &self.index)
+            .json(&json!({
+                "settings": {
+                    "analysis": {
+                        "tokenizer": {
+                            "edge_ngram_tokenizer": {
+                                "type": "edge_ngram",
+                                "min_gram": 1,
+                                "max_gram": 25,
+                                "token_chars": [
+                                    "letter",
+                                    "digit"
+                                ]
+                            }
+                        },
+                        "analyzer": {
+                            "edge_ngram_analyzer": {
+                                "type": "custom",
+                                "tokenizer": "edge_ngram_tokenizer"
+                            }
+                        }
+                    }
+                },
+                "mappings" : {
+                    "properties" : {
+                        // ID of the document / file / link
+                        "item_id": { "type": "keyword" },
+                        // Folder, File, Link
+                        "item_type": { "type": "keyword" },
+                        // Mime type for files
+                        "mime": { "type": "keyword" },
+                        // Full text file/folder/link name search
+                        "name" : { "type" : "text", "analyzer": "edge_ngram_analyzer" },
+                        // Full text file/link value content search
+                        "content" : text_field,
+                        // Full text generated summary search
+                        "summary": text_field,
+                        // Created at date search, Elasticsearch does not support
+                        // the "rfc3339_lenient" format used by OpenSearch
+                        "created_at": { "type": "date", "format": "strict_date_optional_time" },
+                        // Exact user for the creator user ID
+                        "created_by": { "type": "keyword" },
+                        // Exact search for the folder the item is within
+                        "folder_id": { "type": "keyword" },
+                        // Exact search for the document box the item is within
+                        "document_box": { "type": "keyword" },
+                        // Exact search for the pinned state
+                        "pinned": { "type": "boolean" },
+                        // Version of the document schema
+                        "schema_version": { "type": "integer" },
+                        // Text statistics for range filtering and sorting
+                        "text_stats": {
+                            "properties": {
+                                "word_count": { "type": "long" },
+                                "character_count": { "type": "long" },
+                                "reading_time_seconds": { "type": "long" }
+                            }
+                        },
+                        // Document pages for files
+                        "pages": {
+                            "type": "nested",
+                            "properties": {
+                                // Full text file/link value content search
+                                "content" : text_field,
+                                // Page number
+                                "page": { "type": "integer" },
+                            }
+                        }
+                    }
+                }
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| {
+                tracing::error!(?error, "failed to create index");
+                ElasticsearchSearchError::CreateIndex
+            })?;
+
+        Ok(())
+    }
+
+    async fn index_exists(&self) -> Result<bool, SearchError> {
+        let response = self
+            .request(Method::HEAD, &self.index)
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to get index");
+                ElasticsearchSearchError::GetIndex
+            })?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+
+        response.error_for_status().map_err(|error| {
+            tracing::error!(?error, "failed to get index (response)");
+            ElasticsearchSearchError::GetIndex
+        })?;
+
+        Ok(true)
+    }
+
+    async fn recreate_index(&self) -> Result<(), SearchError> {
+        self.create_index().await?;
+
+        // Apply the schema changes from all migrations
+        for migration_name in ELASTICSEARCH_MIGRATIONS {
+            self.apply_schema_migration(migration_name).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn delete_index(&self) -> Result<(), SearchError> {
+        let response = self
+            .request(Method::DELETE, &self.index)
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to delete index");
+                ElasticsearchSearchError::DeleteIndex
+            })?;
+
+        // Gracefully handle the index already not existing
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+
+        response.error_for_status().map_err(|error| {
+            tracing::error!(?error, "failed to delete search index (response)");
+            ElasticsearchSearchError::DeleteIndex
+        })?;
+
+        Ok(())
+    }
+
+    async fn suggest_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        query: SuggestRequest,
+    ) -> Result<SuggestResults, SearchError> {
+        let size = query.size.unwrap_or(DEFAULT_SUGGEST_SIZE);
+        let query = create_elasticsearch_suggest_query(query.query.trim(), scope, size);
+
+        tracing::debug!(%query, "suggesting with query");
+
+        let response = self
+            .request(Method::POST, &format!("{}/_search", self.index))
+            .json(&query)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| {
+                tracing::error!(?error, "failed to query suggestions");
+                ElasticsearchSearchError::SearchIndex
+            })?;
+
+        let response: SuggestResponse = response.json().await.map_err(|error| {
+            tracing::error!(?error, "failed to parse suggest response");
+            ElasticsearchSearchError::SearchIndex
+        })?;
+
+        let suggestions = response
+            .hits
+            .hits
+            .into_iter()
+            .map(|hit| SearchSuggestion {
+                item_ty: hit._source.item_type,
+                item_id: hit._source.item_id,
+                document_box: hit._source.document_box,
+                name: hit._source.name,
+            })
+            .collect();
+
+        Ok(SuggestResults { suggestions })
+    }
+
+    async fn search_index_file(
+        &self,
+        scope: &DocumentBoxScopeRaw,
+        file_id: FileId,
+        query: FileSearchRequest,
+    ) -> Result<FileSearchResults, SearchError> {
+        let query = create_elasticsearch_file_query(query, scope, file_id);
+
+        tracing::debug!(%query, "searching with query");
+
+        let response = self.search(&query, None, None).await?;
+
+        let (total_hits, results) = response
+            .hits
+            .hits
+            .into_iter()
+            .next()
+            .and_then(|item| item.inner_hits)
+            .map(|inner_hits| {
+                let total_hits = inner_hits.pages.hits.total.value;
+                let page_matches: Vec<PageResult> = inner_hits
+                    .pages
+                    .hits
+                    .hits
+                    .into_iter()
+                    .map(|value| PageResult {
+                        page: value._source.page,
+                        matches: value.highlight.content,
+                    })
+                    .collect();
+                (total_hits, page_matches)
+            })
+            .unwrap_or_default();
+
+        Ok(FileSearchResults {
+            total_hits,
+            results,
+        })
+    }
+
+    async fn search_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        query: SearchRequest,
+        folder_children: Option<Vec<FolderId>>,
+    ) -> Result<SearchResults, SearchError> {
+        let timeout = query.timeout_ms.map(|timeout_ms| format!("{timeout_ms}ms"));
+        let explain = query.explain || query.dry_run;
+        let dry_run = query.dry_run;
+        let facet_fields = query.facet_fields();
+        let mut query = create_elasticsearch_query(query, scope, folder_children);
+
+        if explain {
+            // Request scoring explanations for each of the hits
+            query["explain"] = json!(true);
+        }
+
+        if !facet_fields.is_empty() {
+            query["aggs"] = create_facet_aggregations(&facet_fields);
+        }
+
+        tracing::debug!(%query, "searching with query");
+
+        let explain = explain.then(|| SearchExplain {
+            backend: "elasticsearch".to_string(),
+            query: query.clone(),
+        });
+
+        if dry_run {
+            return Ok(SearchResults {
+                total_hits: 0,
+                results: Vec::new(),
+                timed_out: false,
+                explain,
+                facets: Vec::new(),
+            });
+        }
+
+        // Elasticsearch will return the partial results collected before the timeout
+        let response = self.search(&query, timeout.as_deref(), None).await?;
+
+        let total_hits = response.hits.total.value;
+        let timed_out = response.timed_out;
+
+        if timed_out {
+            tracing::warn!("elasticsearch search timed out, returning partial results");
+        }
+
+        let results = self.map_search_hits(response.hits.hits);
+        let facets = map_facet_aggregations(&facet_fields, response.aggregations);
+
+        Ok(SearchResults {
+            total_hits,
+            results,
+            timed_out,
+            explain,
+            facets,
+        })
+    }
+
+    async fn scroll_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        mut query: SearchRequest,
+        cursor: Option<SearchScrollCursor>,
+    ) -> Result<SearchScrollPage, SearchError> {
+        let response = match cursor {
+            None => {
+                query.size = Some(query.size.unwrap_or(DEFAULT_SCROLL_SIZE));
+                let query = create_elasticsearch_query(query, scope, None);
+                self.search(&query, None, Some(SCROLL_KEEP_ALIVE)).await?
+            }
+            Some(SearchScrollCursor::Scroll(scroll_id)) => self
+                .request(Method::POST, "_search/scroll")
+                .json(&json!({
+                    "scroll": SCROLL_KEEP_ALIVE,
+                    "scroll_id": scroll_id
+                }))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|error| {
+                    tracing::error!(?error, "failed to scroll index");
+                    ElasticsearchSearchError::SearchIndex
+                })?
+                .json()
+                .await
+                .map_err(|error| {
+                    tracing::error!(?error, "failed to parse scroll response");
+                    ElasticsearchSearchError::SearchIndex
+                })?,
+            Some(SearchScrollCursor::Offset(_)) => return Err(SearchError::InvalidScrollCursor),
+        };
+
+        let total_hits = response.hits.total.value;
+        let results = self.map_search_hits(response.hits.hits);
+
+        let next = match response._scroll_id {
+            // Release the scroll context once all the results have been read
+            Some(scroll_id) if results.is_empty() => {
+                self.clear_scroll(&scroll_id).await;
+                None
+            }
+            Some(scroll_id) => Some(SearchScrollCursor::Scroll(scroll_id)),
+            None => None,
+        };
+
+        Ok(SearchScrollPage {
+            results,
+            total_hits,
+            next,
+        })
+    }
+
+    async fn add_data(&self, data: Vec<SearchIndexData>) -> Result<(), SearchError> {
+        let mut body = String::new();
+
+        for data in data {
+            let data = EsSearchIndexData {
+                ty: data.ty,
+                folder_id: data.folder_id,
+                document_box: data.document_box,
+                item_id: data.item_id,
+                name: data.name,
+                mime: data.mime,
+                content: data.content,
+                created_at: data.created_at.to_rfc3339(),
+                created_by: data.created_by,
+                pinned: data.pinned,
+                pages: data.pages,
+                summary: data.summary,
+                text_stats: data.text_stats,
+                schema_version: SEARCH_SCHEMA_VERSION,
+            };
+
+            push_bulk_entry(&mut body, &json!({ "index": {} }), &data).map_err(|error| {
+                tracing::error!(?error, "failed to serialize bulk add data");
+                ElasticsearchSearchError::AddData
+            })?;
+        }
+
+        self.bulk(body).await.map_err(|error| {
+            tracing::error!(?error, "failed to bulk add data");
+            ElasticsearchSearchError::AddData
+        })?;
+
+        Ok(())
+    }
+
+    async fn update_data(
+        &self,
+        item_id: Uuid,
+        data: UpdateSearchIndexData,
+    ) -> Result<(), SearchError> {
+        let data = EsUpdateSearchIndexData {
+            folder_id: data.folder_id,
+            name: data.name,
+            pinned: data.pinned,
+            content: data.content,
+            pages: data.pages,
+            document_box: data.document_box,
+            schema_version: SEARCH_SCHEMA_VERSION,
+        };
+
+        let items = self.get_by_item_id(item_id).await.map_err(|error| {
+            tracing::error!(?error, "failed to find items to update");
+            ElasticsearchSearchError::UpdateData
+        })?;
+
+        // Nothing to update
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let mut body = String::new();
+
+        for _id in items {
+            push_bulk_entry(
+                &mut body,
+                &json!({ "update": { "_id": _id } }),
+                &json!({ "doc": &data }),
+            )
+            .map_err(|error| {
+                tracing::error!(?error, "failed to serialize bulk update data");
+                ElasticsearchSearchError::UpdateData
+            })?;
+        }
+
+        self.bulk(body).await.map_err(|error| {
+            tracing::error!(?error, "failed to update data");
+            ElasticsearchSearchError::UpdateData
+        })?;
+
+        Ok(())
+    }
+
+    async fn delete_data(&self, item_id: Uuid) -> Result<(), SearchError> {
+        self.delete_by_query(json!({
+            "term": { "item_id": item_id }
+        }))
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to delete data");
+            ElasticsearchSearchError::DeleteData
+        })?;
+
+        Ok(())
+    }
+
+    async fn delete_by_scope(&self, scope: DocumentBoxScopeRawRef<'_>) -> Result<(), SearchError> {
+        self.delete_by_query(json!({
+            "term": { "document_box": scope }
+        }))
+        .await
+        .map_err(|error| {
+            tracing::error!(?error, "failed to delete data by scope");
+            ElasticsearchSearchError::DeleteData
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_pending_migrations(
+        &self,
+        applied_names: Vec<String>,
+    ) -> Result<Vec<String>, SearchError> {
+        Ok(ELASTICSEARCH_MIGRATIONS
+            .iter()
+            .filter(|migration_name| !applied_names.iter().any(|name| name.eq(*migration_name)))
+            .map(|migration_name| migration_name.to_string())
+            .collect())
+    }
+
+    async fn apply_migration(
+        &self,
+        _tenant: &Tenant,
+        _root_t: &mut DbTransaction<'_>,
+        _t: &mut DbTransaction<'_>,
+        name: &str,
+    ) -> Result<(), SearchError> {
+        self.apply_schema_migration(name).await
+    }
+}
+
+impl ElasticsearchIndex {
+    /// Mapping for a full text content field, analyzed using the built-in
+    /// analyzer for the configured language
+    fn text_field(&self) -> serde_json::Value {
+        match self.language {
+            Some(language) => json!({ "type": "text", "analyzer": language.name() }),
+            None => json!({ "type": "text" }),
+        }
+    }
+
+    /// Create a request to the Elasticsearch `path` with authentication applied
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}/{}", self.client_data.base_url, path);
+        let request = self.client.request(method, url);
+
+        match &self.client_data.auth {
+            Some(ElasticsearchAuth::ApiKey(api_key)) => {
+                request.header("Authorization", format!("ApiKey {api_key}"))
+            }
+            Some(ElasticsearchAuth::Basic { username, password }) => {
+                request.basic_auth(username, Some(password))
+            }
+            None => request,
+        }
+    }
+
+    /// Search the index using the provided `query`, when `scroll` is provided a
+    /// scroll context is created that is kept alive for the `scroll` duration
+    async fn search(
+        &self,
+        query: &serde_json::Value,
+        timeout: Option<&str>,
+        scroll: Option<&str>,
+    ) -> Result<SearchResponse, ElasticsearchSearchError> {
+        let mut request = self.request(Method::POST, &format!("{}/_search", self.index));
+
+        if let Some(timeout) = timeout {
+            request = request.query(&[("timeout", timeout)]);
+        }
+
+        if let Some(scroll) = scroll {
+            request = request.query(&[("scroll", scroll)]);
+        }
+
+        let response = request
+            .json(query)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| {
+                tracing::error!(?error, "failed to search index");
+                ElasticsearchSearchError::SearchIndex
+            })?;
+
+        let response: serde_json::Value = response.json().await.map_err(|error| {
+            tracing::error!(?error, "failed to get search response");
+            ElasticsearchSearchError::SearchIndex
+        })?;
+
+        tracing::debug!(%response, "search response");
+
+        serde_json::from_value(response).map_err(|error| {
+            tracing::error!(?error, "failed to parse search response");
+            ElasticsearchSearchError::SearchIndex
+        })
+    }
+
+    /// Perform the newline delimited JSON bulk operations within `body`
+    async fn bulk(&self, body: String) -> Result<(), ElasticsearchBulkError> {
+        let response = self
+            .request(Method::POST, &format!("{}/_bulk", self.index))
+            .header(CONTENT_TYPE, NDJSON_CONTENT_TYPE)
+            .body(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())?;
+
+        let response: serde_json::Value = response.json().await?;
+
+        tracing::debug!(%response, "search index bulk response");
+
+        let bulk_response: BulkResponse = serde_json::from_value(response.clone())?;
+
+        // The bulk request succeeds even when the individual operations fail
+        if bulk_response.errors {
+            tracing::error!(%response, "bulk operation error response");
+            return Err(ElasticsearchBulkError::OperationFailed);
+        }
+
+        Ok(())
+    }
+
+    /// Delete all the documents matching the provided `query`
+    async fn delete_by_query(&self, query: serde_json::Value) -> Result<(), reqwest::Error> {
+        self.request(Method::POST, &format!("{}/_delete_by_query", self.index))
+            // Documents modified while deleting are still deleted by later requests
+            .query(&[("conflicts", "proceed")])
+            .json(&json!({ "query": query }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Apply the schema changes for the migration `name`
+    async fn apply_schema_migration(&self, name: &str) -> Result<(), SearchError> {
+        // No migrations exist yet, the index is created with the complete mapping
+        tracing::error!(%name, "unknown elasticsearch migration");
+        Err(ElasticsearchSearchError::MigrationNotFound.into())
+    }
+
+    /// Upgrade documents written with an older schema version to the current
+    /// [SEARCH_SCHEMA_VERSION], when `item_ids` is provided only the documents
+    /// for those items are upgraded
+    async fn upgrade_documents(
+        &self,
+        item_ids: Option<&[Uuid]>,
+    ) -> Result<(), ElasticsearchSearchError> {
+        let mut filters = vec![json!({
+            "bool": {
+                // Documents without a schema version are also outdated
+                "must_not": {
+                    "range": { "schema_version": { "gte": SEARCH_SCHEMA_VERSION } }
+                }
+            }
+        })];
+
+        if let Some(item_ids) = item_ids {
+            filters.push(json!({ "terms": { "item_id": item_ids } }));
+        }
+
+        self.request(Method::POST, &format!("{}/_update_by_query", self.index))
+            // Documents modified while upgrading are already up to date
+            .query(&[("conflicts", "proceed")])
+            .json(&json!({
+                "query": {
+                    "bool": { "filter": filters }
+                },
+                "script": {
+                    "source": UPGRADE_DOCUMENT_SCRIPT,
+                    "lang": "painless",
+                    "params": { "schema_version": SEARCH_SCHEMA_VERSION }
+                }
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| {
+                tracing::error!(?error, "failed to upgrade search documents");
+                ElasticsearchSearchError::UpgradeDocuments
+            })?;
+
+        Ok(())
+    }
+
+    /// Map the hits from a search response into flattened results, outdated
+    /// documents within the hits are lazily upgraded in the background
+    fn map_search_hits(&self, hits: Vec<SearchResponseHit>) -> Vec<FlattenedItemResult> {
+        // Lazily upgrade any outdated documents that were found in the background
+        let outdated_item_ids: Vec<Uuid> = hits
+            .iter()
+            .filter(|item| item._source.schema_version < SEARCH_SCHEMA_VERSION)
+            .map(|item| item._source.item_id)
+            .collect();
+
+        if !outdated_item_ids.is_empty() {
+            let index = self.clone();
+            tokio::spawn(async move {
+                if let Err(error) = index.upgrade_documents(Some(&outdated_item_ids)).await {
+                    tracing::error!(?error, "failed to lazily upgrade search documents");
+                }
+            });
+        }
+
+        const NAME_MATCH_KEYS: [&str; 2] = ["name_match_exact", "name_match_wildcard"];
+
+        hits.into_iter()
+            .map(|item| {
+                let (total_hits, page_matches) = match item.inner_hits {
+                    Some(inner_hits) => {
+                        let total_hits = inner_hits.pages.hits.total.value;
+                        let page_matches: Vec<PageResult> = inner_hits
+                            .pages
+                            .hits
+                            .hits
+                            .into_iter()
+                            .map(|value| PageResult {
+                                page: value._source.page,
+                                matches: value.highlight.content,
+                            })
+                            .collect();
+                        (total_hits, page_matches)
+                    }
+                    None => (0, vec![]),
+                };
+
+                let summary_match = item
+                    .matched_queries
+                    .as_ref()
+                    .is_some_and(|matches| matches.iter().any(|value| value == "summary_match"));
+                let name_match = item.matched_queries.is_some_and(|matches| {
+                    matches
+                        .iter()
+                        .any(|value| NAME_MATCH_KEYS.contains(&value.as_str()))
+                });
+                let content_match = !page_matches.is_empty() || summary_match;
+
+                FlattenedItemResult {
+                    item_ty: item._source.item_type,
+                    item_id: item._source.item_id,
+                    document_box: item._source.document_box,
+                    score: SearchScore::Float(item._score.unwrap_or_default()),
+                    page_matches,
+                    total_hits,
+                    name_match,
+                    content_match,
+                    explanation: item._explanation,
+                }
+            })
+            .collect()
+    }
+
+    /// Release a scroll context, failing to release the context is only
+    /// logged as it will expire on its own after [SCROLL_KEEP_ALIVE]
+    async fn clear_scroll(&self, scroll_id: &str) {
+        let result = self
+            .request(Method::DELETE, "_search/scroll")
+            .json(&json!({ "scroll_id": [scroll_id] }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        if let Err(error) = result {
+            tracing::warn!(?error, "failed to clear search scroll context");
+        }
+    }
+
+    /// Collect the document IDs of all records for the provided `item_id`
+    async fn get_by_item_id(&self, item_id: Uuid) -> Result<Vec<String>, ElasticsearchSearchError> {
+        #[derive(Debug, Deserialize)]
+        struct Response {
+            hits: Hits,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Hits {
+            hits: Vec<Hit>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Hit {
+            _id: String,
+        }
+
+        let response: Response = self
+            .request(Method::POST, &format!("{}/_search", self.index))
+            .json(&json!({
+                "query": {
+                   "term": { "item_id": item_id }
+                },
+                "from": 0,
+                "size": 10,
+                "_source": false
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| {
+                tracing::error!(?error, "failed to get search item by id");
+                ElasticsearchSearchError::SearchIndex
+            })?
+            .json()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to parse search item by id response");
+                ElasticsearchSearchError::SearchIndex
+            })?;
+
+        Ok(response.hits.hits.into_iter().map(|hit| hit._id).collect())
+    }
+}
+
+/// Errors that can occur when performing bulk operations
+#[derive(Debug, thiserror::Error)]
+enum ElasticsearchBulkError {
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+    #[error(transparent)]
+    Serialize(#[from] serde_json::Error),
+    #[error("one or more bulk operations failed")]
+    OperationFailed,
+}
+
+/// Append a bulk `action` line and its `source` line to the newline
+/// delimited JSON bulk request `body`
+fn push_bulk_entry<S: Serialize>(
+    body: &mut String,
+    action: &serde_json::Value,
+    source: &S,
+) -> Result<(), serde_json::Error> {
+    body.push_str(&serde_json::to_string(action)?);
+    body.push('\n');
+    body.push_str(&serde_json::to_string(source)?);
+    body.push('\n');
+    Ok(())
+}
+
+/// Date range filter
+#[skip_serializing_none]
+#[derive(Serialize)]
+struct DateRange {
+    gte: Option<String>,
+    lte: Option<String>,
+}
+
+/// Create the Elasticsearch query for a search request
+/// Create the terms aggregations counting the values of the facet `fields`
+fn create_facet_aggregations(fields: &[SearchFacetField]) -> serde_json::Value {
+    serde_json::Value::Object(
+        fields
+            .iter()
+            .map(|field| {
+                (
+                    field.field_name().to_string(),
+                    json!({
+                        "terms": {
+                            "field": field.field_name(),
+                            "size": MAX_FACET_BUCKETS
+                        }
+                    }),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Map the terms `aggregations` from a search response into the facets for
+/// the requested `fields`
+fn map_facet_aggregations(
+    fields: &[SearchFacetField],
+    mut aggregations: HashMap<String, TermsAggregation>,
+) -> Vec<SearchFacet> {
+    fields
+        .iter()
+        .map(|field| SearchFacet {
+            field: *field,
+            buckets: aggregations
+                .remove(field.field_name())
+                .map(|aggregation| {
+                    aggregation
+                        .buckets
+                        .into_iter()
+                        .map(|bucket| SearchFacetBucket {
+                            value: bucket.key,
+                            count: bucket.doc_count,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Create a query matching the names of items within `scopes` that start
+/// with the words of the `query`
+pub fn create_elasticsearch_suggest_query(
+    query: &str,
+    scopes: &[DocumentBoxScopeRaw],
+    size: u16,
+) -> serde_json::Value {
+    json!({
+        "size": size,
+        "_source": ["item_id", "item_type", "document_box", "name"],
+        "query": {
+            "bool": {
+                "filter": [
+                    { "terms": { "document_box": scopes } }
+                ],
+                "must": [
+                    {
+                        // Names are indexed as edge n-grams so matching every
+                        // query term gives prefix completions
+                        "match": {
+                            "name": {
+                                "query": query,
+                                "operator": "and"
+                            }
+                        }
+                    }
+                ]
+            }
+        }
+    })
+}
+
+pub fn create_elasticsearch_query(
+    req: SearchRequest,
+    scopes: &[DocumentBoxScopeRaw],
+    folder_children: Option<Vec<FolderId>>,
+) -> serde_json::Value {
+    let mut filters = vec![];
+    let mut should = Vec::new();
+
+    // Always filter to the specific document box scope
+    filters.push(json!({
+        "terms": { "document_box": scopes }
+    }));
+
+    let query = req
+        .query
+        // Filter out empty queries
+        .filter(|value| !value.is_empty());
+
+    if let Some(ref query) = query {
+        if req.include_name {
+            // Match name of documents
+            should.push(json!({
+                "term": {
+                    "name": {
+                        "value": query,
+                        "boost": 2,
+                        "_name": "name_match_exact",
+                        "case_insensitive": true
+                    }
+                }
+            }));
+            should.push(json!({
+                "wildcard": {
+                    "name": {
+                        "value": format!("*{}*", escape_wildcard(query)),
+                        "boost": 1.5,
+                        "_name": "name_match_wildcard",
+                        "case_insensitive": true
+                    }
+                }
+            }));
+        }
+
+        if req.include_content {
+            // Match content on the document itself (Link value)
+            should.push(json!({
+                "match": {
+                    "content": {
+                        "query": query,
+                        // Name the match for scoring later
+                        "_name": "content_match"
+                    },
+                }
+            }));
+
+            // Match the generated summary, boosted as the summary is a
+            // condensed description of the entire document
+            should.push(json!({
+                "match": {
+                    "summary": {
+                        "query": query,
+                        "boost": 2,
+                        // Name the match for scoring later
+                        "_name": "summary_match"
+                    },
+                }
+            }));
+
+            // Match content pages
+            should.push(create_pages_query(
+                query,
+                req.max_pages.unwrap_or(3),
+                req.pages_offset.unwrap_or(0),
+                3,
+            ));
+        }
+    }
+
+    if let Some(folder_children) = folder_children {
+        filters.push(json!({
+            "terms": { "folder_id": folder_children }
+        }));
+    }
+
+    if let Some(ref mime) = req.mime {
+        filters.push(json!({
+            "term": { "mime": mime }
+        }));
+    }
+
+    if let Some(ref created_at) = req.created_at {
+        let start = created_at.start.map(|value| value.to_rfc3339());
+        let end = created_at.end.map(|value| value.to_rfc3339());
+
+        if start.is_some() || end.is_some() {
+            filters.push(json!({
+                "range": {
+                    "created_at": DateRange {
+                        gte: start,
+                        lte: end
+                    }
+                }
+            }));
+        }
+    }
+
+    if let Some(ref created_by) = req.created_by {
+        filters.push(json!({
+            "term": { "created_by": created_by }
+        }));
+    }
+
+    if let Some(pinned) = req.pinned {
+        filters.push(json!({
+            "term": { "pinned": pinned }
+        }));
+    }
+
+    if let Some(ref advanced) = req.advanced {
+        filters.push(create_advanced_query(advanced));
+    }
+
+    // When a "should" is provided we must at least match one part of it
+    let minimum_should_match = if !should.is_empty() { 1 } else { 0 };
+
+    json!({
+        // Search query itself
+        "query": {
+            "bool": {
+                "filter": filters,
+                "should": should,
+                "minimum_should_match": minimum_should_match
+            },
+        },
+
+        // Maximum number of results to find
+        "size": req.size.unwrap_or(50),
+        // Offset within results
+        "from": req.offset.unwrap_or(0),
+
+        // Elasticsearch stops counting hits at 10,000 unless requested
+        "track_total_hits": true,
+
+        // Only include relevant source fields
+        "_source": [
+            "item_id",
+            "item_type",
+            "document_box",
+            "schema_version"
+        ],
+
+        // Sort results by match score
+        "sort": [
+            {
+                "_score": {
+                    "order": "desc"
+                }
+            }
+        ]
+    })
+}
+
+/// Create the nested query matching the content of the pages of a document
+/// with highlighted fragments for the best matching pages
+fn create_pages_query(query: &str, size: u16, from: u64, fragments: u16) -> serde_json::Value {
+    json!({
+        "nested": {
+            "path": "pages",
+            // Match nested page content
+            "query": {
+                "match": {
+                    "pages.content": {
+                        "query": query,
+                        // Name the match for scoring later
+                        "_name": "content_match"
+                    },
+                }
+            },
+            "inner_hits": {
+                "_source": ["pages.page"],
+                // Highlight
+                "highlight": {
+                    "fields": {
+                        "pages.content": {
+                            "fragment_size": 150,
+                            "number_of_fragments": fragments,
+                            "type": "unified"
+                        }
+                    }
+                },
+                // Order results by score
+                "sort": [
+                    {
+                        "_score": {
+                            "order": "desc"
+                        }
+                    }
+                ],
+                // Pagination
+                "size": size,
+                "from": from,
+            }
+        }
+    })
+}
+
+/// Escape the wildcard characters within a wildcard query `value`
+fn escape_wildcard(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('*', "\\*")
+        .replace('?', "\\?")
+}
+
+/// Compile an advanced query into an Elasticsearch query clause
+fn create_advanced_query(query: &AdvancedSearchQuery) -> serde_json::Value {
+    match query {
+        AdvancedSearchQuery::And { queries } => {
+            let queries: Vec<_> = queries.iter().map(create_advanced_query).collect();
+            json!({ "bool": { "filter": queries } })
+        }
+        AdvancedSearchQuery::Or { queries } => {
+            let queries: Vec<_> = queries.iter().map(create_advanced_query).collect();
+            json!({ "bool": { "should": queries, "minimum_should_match": 1 } })
+        }
+        AdvancedSearchQuery::Not { query } => {
+            json!({ "bool": { "must_not": [create_advanced_query(query)] } })
+        }
+        AdvancedSearchQuery::NameContains { value } => json!({
+            "wildcard": {
+                "name": {
+                    "value": format!("*{}*", escape_wildcard(value)),
+                    "case_insensitive": true
+                }
+            }
+        }),
+        AdvancedSearchQuery::MimeIn { values } => json!({
+            "terms": { "mime": values }
+        }),
+        AdvancedSearchQuery::CreatedBetween { start, end } => json!({
+            "range": {
+                "created_at": DateRange {
+                    gte: start.map(|value| value.to_rfc3339()),
+                    lte: end.map(|value| value.to_rfc3339()),
+                }
+            }
+        }),
+        AdvancedSearchQuery::CreatedByEquals { value } => json!({
+            "term": { "created_by": value }
+        }),
+        AdvancedSearchQuery::PinnedEquals { value } => json!({
+            "term": { "pinned": value }
+        }),
+    }
+}
+
+/// Create the Elasticsearch query for searching the pages of a specific file
+pub fn create_elasticsearch_file_query(
+    req: FileSearchRequest,
+    scope: &DocumentBoxScopeRaw,
+    file_id: FileId,
+) -> serde_json::Value {
+    let query = req.query.unwrap_or_default();
+
+    json!({
+        // Search query itself
+        "query": {
+            "bool": {
+                "filter": [
+                    {
+                        "term": { "document_box": scope }
+                    },
+                    {
+                        "term": { "item_id": file_id }
+                    }
+                ],
+                "should": [
+                    create_pages_query(
+                        &query,
+                        req.limit.unwrap_or(3),
+                        req.offset.unwrap_or(0),
+                        1,
+                    )
+                ],
+                "minimum_should_match": 1
+            },
+        },
+
+        "size": 1,
+        "from": 0,
+
+        // Only include relevant source fields
+        "_source": [
+            "item_id",
+            "item_type",
+            "document_box"
+        ],
+
+        // Sort results by match score
+        "sort": [
+            {
+                "_score": {
+                    "order": "desc"
+                }
+            }
+        ]
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{create_elasticsearch_file_query, create_elasticsearch_query};
+    use crate::models::{FileSearchRequest, SearchRequest};
+    use uuid::Uuid;
+
+    /// Tests that searches request accurate hit totals and escape wildcards
+    /// within the name query
+    #[test]
+    fn test_create_elasticsearch_query() {
+        let query = create_elasticsearch_query(
+            SearchRequest {
+                query: Some("report*".to_string()),
+                include_name: true,
+                ..Default::default()
+            },
+            &["scope".to_string()],
+            None,
+        );
+
+        assert_eq!(query["track_total_hits"], true);
+        assert_eq!(query["query"]["bool"]["minimum_should_match"], 1);
+        assert_eq!(
+            query["query"]["bool"]["filter"][0]["terms"]["document_box"][0],
+            "scope"
+        );
+        assert_eq!(
+            query["query"]["bool"]["should"][1]["wildcard"]["name"]["value"],
+            "*report\\**"
+        );
+    }
+
+    /// Tests that file searches paginate the matching pages of the file
+    #[test]
+    fn test_create_elasticsearch_file_query() {
+        let file_id = Uuid::nil();
+        let query = create_elasticsearch_file_query(
+            FileSearchRequest {
+                query: Some("invoice".to_string()),
+                offset: Some(6),
+                limit: Some(3),
+            },
+            &"scope".to_string(),
+            file_id,
+        );
+
+        let inner_hits = &query["query"]["bool"]["should"][0]["nested"]["inner_hits"];
+        assert_eq!(inner_hits["from"], 6);
+        assert_eq!(inner_hits["size"], 3);
+        assert_eq!(
+            query["query"]["bool"]["filter"][1]["term"]["item_id"],
+            file_id.to_string()
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use docbox_database::models::{
+    document_box::DocumentBoxScopeRaw, file_text_stats::TextStats, folder::FolderId, user::UserId,
+};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::{DocumentPage, SearchIndexType};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EsSearchIndexData {
+    /// Type of item the search index data is representing
+    #[serde(rename = "item_type")]
+    pub ty: SearchIndexType,
+
+    /// ID of the folder the indexed item is within.
+    pub folder_id: FolderId,
+    /// Document box scope that this item is within
+    pub document_box: DocumentBoxScopeRaw,
+
+    /// Unique ID for the actual document
+    pub item_id: Uuid,
+    /// Name of this item
+    pub name: String,
+    /// Mime type when working with file items (Otherwise none)
+    pub mime: Option<String>,
+    /// For files this is the file content (With an associated page number)
+    /// For links this is the link value
+    pub content: Option<String>,
+    /// Creation date for the item
+    pub created_at: String,
+    /// User who created the item
+    pub created_by: Option<UserId>,
+    /// Whether the item is pinned
+    #[serde(default)]
+    pub pinned: bool,
+    /// Optional pages of document content
+    pub pages: Option<Vec<DocumentPage>>,
+    /// Optional generated summary of the document content
+    pub summary: Option<String>,
+    /// Word count, character count and reading time of the document content
+    #[serde(default)]
+    pub text_stats: Option<TextStats>,
+    /// Version of the document schema the document was written with
+    pub schema_version: i32,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EsUpdateSearchIndexData {
+    pub folder_id: FolderId,
+    pub name: String,
+    pub pinned: bool,
+    pub content: Option<String>,
+    pub pages: Option<Vec<DocumentPage>>,
+    pub document_box: Option<DocumentBoxScopeRaw>,
+    /// Updated documents are upgraded to the current schema version
+    pub schema_version: i32,
+}
+
+/// Response from the bulk API, the request itself succeeds even when
+/// individual operations fail
+#[derive(Debug, Deserialize)]
+pub struct BulkResponse {
+    /// Whether any of the operations failed
+    pub errors: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchResponse {
+    /// Whether the search timed out before all shards responded
+    #[serde(default)]
+    pub timed_out: bool,
+    pub hits: Hits<SearchResponseHit>,
+    /// ID of the scroll context, only present for scrolled searches
+    #[serde(default)]
+    pub _scroll_id: Option<String>,
+    /// Facet aggregations by field name, only present when facets were requested
+    #[serde(default)]
+    pub aggregations: HashMap<String, TermsAggregation>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TermsAggregation {
+    pub buckets: Vec<TermsAggregationBucket>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TermsAggregationBucket {
+    pub key: String,
+    pub doc_count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Hits<H> {
+    pub total: HitsTotal,
+    pub hits: Vec<H>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HitsTotal {
+    pub value: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchResponseHit {
+    pub _id: String,
+    /// Score of the hit, Elasticsearch reports [None] for hits that
+    /// were only matched by filters
+    #[serde(default)]
+    pub _score: Option<f32>,
+    pub _source: SearchResponseHitSource,
+    pub inner_hits: Option<InnerHits>,
+    pub matched_queries: Option<Vec<String>>,
+    /// Scoring explanation, only present when requested
+    #[serde(default)]
+    pub _explanation: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchResponseHitSource {
+    pub item_id: Uuid,
+    pub item_type: SearchIndexType,
+    pub document_box: DocumentBoxScopeRaw,
+    /// Documents indexed before schema versioning have no version
+    #[serde(default)]
+    pub schema_version: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestResponse {
+    pub hits: Hits<SuggestResponseHit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestResponseHit {
+    pub _source: SuggestResponseHitSource,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestResponseHitSource {
+    pub item_id: Uuid,
+    pub item_type: SearchIndexType,
+    pub document_box: DocumentBoxScopeRaw,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InnerHits {
+    pub pages: InnerHitsPages,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InnerHitsPages {
+    pub hits: Hits<PagesHit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PagesHit {
+    pub _source: PagesHitSource,
+    /// Highlights are omitted by Elasticsearch when a page was matched
+    /// without any highlighted fragments
+    #[serde(default)]
+    pub highlight: PagesHighlight,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PagesHitSource {
+    pub page: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PagesHighlight {
+    #[serde(rename = "pages.content", default)]
+    pub content: Vec<String>,
+}
@@ -0,0 +1,217 @@
+//! Languages used to analyze the indexed text content
+//!
+//! The language determines the stemmer applied to names, content and pages
+//! when they are indexed and searched. Each backend maps the language onto
+//! its own analyzer:
+//!
+//! * OpenSearch / Elasticsearch - Built-in language analyzer (i.e "german")
+//! * Typesense - Field locale (i.e "de")
+//! * Database - Postgres text search configuration (i.e "german")
+//! * Tantivy - Stemmer for the language
+//!
+//! The language of an index is applied when the index is created, changing
+//! the language of an existing index requires the index to be recreated and
+//! rebuilt
+
+use docbox_database::models::tenant::Tenant;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Language used to analyze the indexed text content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchLanguage {
+    Arabic,
+    Danish,
+    Dutch,
+    English,
+    Finnish,
+    French,
+    German,
+    Greek,
+    Hungarian,
+    Italian,
+    Norwegian,
+    Portuguese,
+    Romanian,
+    Russian,
+    Spanish,
+    Swedish,
+    Turkish,
+}
+
+#[derive(Debug, Error)]
+#[error("unknown search language \"{0}\"")]
+pub struct UnknownSearchLanguage(pub String);
+
+impl SearchLanguage {
+    /// All the supported languages
+    pub const ALL: &[SearchLanguage] = &[
+        SearchLanguage::Arabic,
+        SearchLanguage::Danish,
+        SearchLanguage::Dutch,
+        SearchLanguage::English,
+        SearchLanguage::Finnish,
+        SearchLanguage::French,
+        SearchLanguage::German,
+        SearchLanguage::Greek,
+        SearchLanguage::Hungarian,
+        SearchLanguage::Italian,
+        SearchLanguage::Norwegian,
+        SearchLanguage::Portuguese,
+        SearchLanguage::Romanian,
+        SearchLanguage::Russian,
+        SearchLanguage::Spanish,
+        SearchLanguage::Swedish,
+        SearchLanguage::Turkish,
+    ];
+
+    /// Load the default search language from the `DOCBOX_SEARCH_LANGUAGE`
+    /// environment variable, [None] when not set
+    pub fn from_env() -> Result<Option<Self>, UnknownSearchLanguage> {
+        std::env::var("DOCBOX_SEARCH_LANGUAGE")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| value.parse())
+            .transpose()
+    }
+
+    /// Resolve the language for the index of the provided `tenant`, the tenant
+    /// language overrides the `default` language of the search backend
+    ///
+    /// Unknown tenant languages are ignored in favor of the `default`
+    pub fn for_tenant(tenant: &Tenant, default: Option<Self>) -> Option<Self> {
+        let Some(language) = tenant.search_language.as_deref() else {
+            return default;
+        };
+
+        match language.parse() {
+            Ok(language) => Some(language),
+            Err(error) => {
+                tracing::warn!(
+                    ?error,
+                    tenant_id = %tenant.id,
+                    "tenant has an unknown search language, using default"
+                );
+                default
+            }
+        }
+    }
+
+    /// Name of the language, also used as the OpenSearch / Elasticsearch
+    /// language analyzer name
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchLanguage::Arabic => "arabic",
+            SearchLanguage::Danish => "danish",
+            SearchLanguage::Dutch => "dutch",
+            SearchLanguage::English => "english",
+            SearchLanguage::Finnish => "finnish",
+            SearchLanguage::French => "french",
+            SearchLanguage::German => "german",
+            SearchLanguage::Greek => "greek",
+            SearchLanguage::Hungarian => "hungarian",
+            SearchLanguage::Italian => "italian",
+            SearchLanguage::Norwegian => "norwegian",
+            SearchLanguage::Portuguese => "portuguese",
+            SearchLanguage::Romanian => "romanian",
+            SearchLanguage::Russian => "russian",
+            SearchLanguage::Spanish => "spanish",
+            SearchLanguage::Swedish => "swedish",
+            SearchLanguage::Turkish => "turkish",
+        }
+    }
+
+    /// Name of the Postgres text search configuration (regconfig) for
+    /// the language
+    pub fn regconfig(&self) -> &'static str {
+        // Postgres ships a text search configuration for each supported
+        // language under the same name
+        self.name()
+    }
+
+    /// ISO 639-1 language code used as the Typesense field locale
+    pub fn locale(&self) -> &'static str {
+        match self {
+            SearchLanguage::Arabic => "ar",
+            SearchLanguage::Danish => "da",
+            SearchLanguage::Dutch => "nl",
+            SearchLanguage::English => "en",
+            SearchLanguage::Finnish => "fi",
+            SearchLanguage::French => "fr",
+            SearchLanguage::German => "de",
+            SearchLanguage::Greek => "el",
+            SearchLanguage::Hungarian => "hu",
+            SearchLanguage::Italian => "it",
+            SearchLanguage::Norwegian => "no",
+            SearchLanguage::Portuguese => "pt",
+            SearchLanguage::Romanian => "ro",
+            SearchLanguage::Russian => "ru",
+            SearchLanguage::Spanish => "es",
+            SearchLanguage::Swedish => "sv",
+            SearchLanguage::Turkish => "tr",
+        }
+    }
+}
+
+impl Display for SearchLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SearchLanguage {
+    type Err = UnknownSearchLanguage;
+
+    /// Parse a language from either its name (i.e "german") or its
+    /// ISO 639-1 code (i.e "de")
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_lowercase();
+        SearchLanguage::ALL
+            .iter()
+            .find(|language| language.name() == value || language.locale() == value)
+            .copied()
+            .ok_or(UnknownSearchLanguage(value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SearchLanguage;
+
+    #[test]
+    fn test_parse_search_language() {
+        assert_eq!(
+            "german".parse::<SearchLanguage>().unwrap(),
+            SearchLanguage::German
+        );
+        assert_eq!(
+            " French ".parse::<SearchLanguage>().unwrap(),
+            SearchLanguage::French
+        );
+        assert_eq!(
+            "de".parse::<SearchLanguage>().unwrap(),
+            SearchLanguage::German
+        );
+        assert!("klingon".parse::<SearchLanguage>().is_err());
+    }
+
+    #[test]
+    fn test_search_language_round_trip() {
+        for language in SearchLanguage::ALL {
+            assert_eq!(
+                language.name().parse::<SearchLanguage>().unwrap(),
+                *language
+            );
+            assert_eq!(
+                language.locale().parse::<SearchLanguage>().unwrap(),
+                *language
+            );
+
+            let serialized = serde_json::to_value(language).unwrap();
+            assert_eq!(serialized, serde_json::json!(language.name()));
+        }
+    }
+}
@@ -4,7 +4,14 @@
 //!
 //! ## Environment Variables
 //!
-//! * `DOCBOX_SEARCH_INDEX_FACTORY` - Which search index to use ("opensearch", "typesense", or "database")
+//! * `DOCBOX_SEARCH_INDEX_FACTORY` - Which search index to use ("opensearch", "elasticsearch", "typesense", "tantivy", or "database")
+//! * `DOCBOX_SEARCH_LANGUAGE` - Default language used to analyze the indexed text content (i.e "german"), see [language::SearchLanguage]
+//!
+//! ## Features
+//!
+//! * `memory` - Enables the in-memory search index for use in tests
+//! * `chaos` - Enables the fault injecting search index for use in tests
+//! * `tantivy` - Enables the embedded Tantivy search index stored on local disk
 
 use aws_config::SdkConfig;
 use chrono::Utc;
@@ -19,21 +26,29 @@ use docbox_database::{
     },
 };
 use docbox_secrets::SecretManager;
+use language::SearchLanguage;
 use models::{
     FileSearchRequest, FileSearchResults, SearchIndexData, SearchRequest, SearchResults,
-    UpdateSearchIndexData,
+    SearchScrollCursor, SearchScrollPage, SuggestRequest, SuggestResults, UpdateSearchIndexData,
 };
 use serde::{Deserialize, Serialize};
 use std::{ops::DerefMut, sync::Arc};
 use thiserror::Error;
 use uuid::Uuid;
 
+pub mod language;
 pub mod models;
+pub mod query_dsl;
+pub mod validation;
 
 pub use database::{
     DatabaseSearchConfig, DatabaseSearchError, DatabaseSearchIndex, DatabaseSearchIndexFactory,
     DatabaseSearchIndexFactoryError,
 };
+pub use elasticsearch::{
+    ElasticsearchConfig, ElasticsearchIndex, ElasticsearchIndexFactory,
+    ElasticsearchIndexFactoryError, ElasticsearchSearchError,
+};
 pub use opensearch::{
     OpenSearchConfig, OpenSearchIndex, OpenSearchIndexFactory, OpenSearchIndexFactoryError,
     OpenSearchSearchError,
@@ -43,8 +58,27 @@ pub use typesense::{
     TypesenseIndexFactory, TypesenseIndexFactoryError, TypesenseSearchConfig, TypesenseSearchError,
 };
 
+#[cfg(feature = "tantivy")]
+pub use self::tantivy::{
+    TantivyIndex, TantivyIndexFactory, TantivyIndexFactoryError, TantivySearchConfig,
+    TantivySearchError,
+};
+#[cfg(feature = "chaos")]
+pub use chaos::{
+    ChaosSearchConfig, ChaosSearchError, ChaosSearchIndex, ChaosSearchIndexFactory, SearchOperation,
+};
+#[cfg(feature = "memory")]
+pub use memory::{MemorySearchError, MemorySearchIndex, MemorySearchIndexFactory};
+
+#[cfg(feature = "chaos")]
+mod chaos;
 mod database;
+mod elasticsearch;
+#[cfg(feature = "memory")]
+mod memory;
 mod opensearch;
+#[cfg(feature = "tantivy")]
+mod tantivy;
 mod typesense;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -52,7 +86,10 @@ mod typesense;
 pub enum SearchIndexFactoryConfig {
     Typesense(typesense::TypesenseSearchConfig),
     OpenSearch(opensearch::OpenSearchConfig),
+    Elasticsearch(elasticsearch::ElasticsearchConfig),
     Database(database::DatabaseSearchConfig),
+    #[cfg(feature = "tantivy")]
+    Tantivy(self::tantivy::TantivySearchConfig),
 }
 
 impl Default for SearchIndexFactoryConfig {
@@ -68,7 +105,12 @@ pub enum SearchIndexFactoryError {
     #[error(transparent)]
     OpenSearch(#[from] opensearch::OpenSearchIndexFactoryError),
     #[error(transparent)]
+    Elasticsearch(#[from] elasticsearch::ElasticsearchIndexFactoryError),
+    #[error(transparent)]
     Database(#[from] database::DatabaseSearchIndexFactoryError),
+    #[cfg(feature = "tantivy")]
+    #[error(transparent)]
+    Tantivy(#[from] self::tantivy::TantivyIndexFactoryError),
     #[error("unknown search index factory type requested")]
     UnknownIndexFactory,
 }
@@ -83,6 +125,10 @@ impl SearchIndexFactoryConfig {
                 .map(Self::OpenSearch)
                 .map_err(SearchIndexFactoryError::OpenSearch),
 
+            "elastic_search" | "elasticsearch" => elasticsearch::ElasticsearchConfig::from_env()
+                .map(Self::Elasticsearch)
+                .map_err(SearchIndexFactoryError::Elasticsearch),
+
             "typesense" => typesense::TypesenseSearchConfig::from_env()
                 .map(Self::Typesense)
                 .map_err(SearchIndexFactoryError::Typesense),
@@ -91,6 +137,11 @@ impl SearchIndexFactoryConfig {
                 .map(Self::Database)
                 .map_err(SearchIndexFactoryError::Database),
 
+            #[cfg(feature = "tantivy")]
+            "tantivy" => self::tantivy::TantivySearchConfig::from_env()
+                .map(Self::Tantivy)
+                .map_err(SearchIndexFactoryError::Tantivy),
+
             // Unknown type requested
             _ => Err(SearchIndexFactoryError::UnknownIndexFactory),
         }
@@ -101,7 +152,14 @@ impl SearchIndexFactoryConfig {
 pub enum SearchIndexFactory {
     Typesense(typesense::TypesenseIndexFactory),
     OpenSearch(opensearch::OpenSearchIndexFactory),
+    Elasticsearch(elasticsearch::ElasticsearchIndexFactory),
     Database(database::DatabaseSearchIndexFactory),
+    #[cfg(feature = "tantivy")]
+    Tantivy(self::tantivy::TantivyIndexFactory),
+    #[cfg(feature = "memory")]
+    Memory(memory::MemorySearchIndexFactory),
+    #[cfg(feature = "chaos")]
+    Chaos(chaos::ChaosSearchIndexFactory),
 }
 
 impl SearchIndexFactory {
@@ -127,30 +185,121 @@ impl SearchIndexFactory {
                     .map_err(SearchIndexFactoryError::OpenSearch)
             }
 
+            SearchIndexFactoryConfig::Elasticsearch(config) => {
+                tracing::debug!("using elasticsearch search index");
+                elasticsearch::ElasticsearchIndexFactory::from_config(config)
+                    .map(SearchIndexFactory::Elasticsearch)
+                    .map_err(SearchIndexFactoryError::Elasticsearch)
+            }
+
             SearchIndexFactoryConfig::Database(config) => {
                 tracing::debug!("using opensearch search index");
                 database::DatabaseSearchIndexFactory::from_config(db, config)
                     .map(SearchIndexFactory::Database)
                     .map_err(SearchIndexFactoryError::Database)
             }
+
+            #[cfg(feature = "tantivy")]
+            SearchIndexFactoryConfig::Tantivy(config) => {
+                tracing::debug!("using tantivy search index");
+                self::tantivy::TantivyIndexFactory::from_config(config)
+                    .map(SearchIndexFactory::Tantivy)
+                    .map_err(SearchIndexFactoryError::Tantivy)
+            }
+        }
+    }
+
+    /// Data residency region the search backend is located within,
+    /// [None] when no region is configured
+    pub fn data_region(&self) -> Option<&str> {
+        match self {
+            SearchIndexFactory::Typesense(factory) => factory.data_region(),
+            SearchIndexFactory::OpenSearch(factory) => factory.data_region(),
+            SearchIndexFactory::Elasticsearch(factory) => factory.data_region(),
+            SearchIndexFactory::Database(factory) => factory.data_region(),
+            #[cfg(feature = "tantivy")]
+            SearchIndexFactory::Tantivy(factory) => factory.data_region(),
+            #[cfg(feature = "memory")]
+            SearchIndexFactory::Memory(_) => None,
+            #[cfg(feature = "chaos")]
+            SearchIndexFactory::Chaos(factory) => factory.data_region(),
+        }
+    }
+
+    /// Default language used to analyze the indexed text content, tenants
+    /// can override the language. [None] when no language is configured
+    pub fn language(&self) -> Option<SearchLanguage> {
+        match self {
+            SearchIndexFactory::Typesense(factory) => factory.language(),
+            SearchIndexFactory::OpenSearch(factory) => factory.language(),
+            SearchIndexFactory::Elasticsearch(factory) => factory.language(),
+            SearchIndexFactory::Database(factory) => factory.language(),
+            #[cfg(feature = "tantivy")]
+            SearchIndexFactory::Tantivy(factory) => factory.language(),
+            #[cfg(feature = "memory")]
+            SearchIndexFactory::Memory(_) => None,
+            #[cfg(feature = "chaos")]
+            SearchIndexFactory::Chaos(factory) => factory.language(),
+        }
+    }
+
+    /// Clear any cached search credentials so that rotated credentials
+    /// are loaded on next use
+    pub async fn flush_credentials(&self) {
+        match self {
+            SearchIndexFactory::Typesense(factory) => factory.api_key_provider().flush().await,
+            // OpenSearch uses the AWS credentials chain, Elasticsearch credentials
+            // come from the config and the remaining backends have no credentials
+            // of their own
+            SearchIndexFactory::OpenSearch(_)
+            | SearchIndexFactory::Elasticsearch(_)
+            | SearchIndexFactory::Database(_) => {}
+            #[cfg(feature = "tantivy")]
+            SearchIndexFactory::Tantivy(_) => {}
+            #[cfg(feature = "memory")]
+            SearchIndexFactory::Memory(_) => {}
+            #[cfg(feature = "chaos")]
+            SearchIndexFactory::Chaos(factory) => factory.flush_credentials().await,
         }
     }
 
     /// Create a new "OpenSearch" search index for the tenant
     pub fn create_search_index(&self, tenant: &Tenant) -> TenantSearchIndex {
+        let language = SearchLanguage::for_tenant(tenant, self.language());
+
         match self {
             SearchIndexFactory::Typesense(factory) => {
                 let search_index = tenant.os_index_name.clone();
-                TenantSearchIndex::Typesense(factory.create_search_index(search_index))
+                TenantSearchIndex::Typesense(factory.create_search_index(search_index, language))
             }
 
             SearchIndexFactory::OpenSearch(factory) => {
                 let search_index = opensearch::TenantSearchIndexName::from_tenant(tenant);
-                TenantSearchIndex::OpenSearch(factory.create_search_index(search_index))
+                TenantSearchIndex::OpenSearch(factory.create_search_index(search_index, language))
+            }
+
+            SearchIndexFactory::Elasticsearch(factory) => {
+                TenantSearchIndex::Elasticsearch(factory.create_search_index(tenant, language))
             }
 
             SearchIndexFactory::Database(factory) => {
-                TenantSearchIndex::Database(factory.create_search_index(tenant))
+                TenantSearchIndex::Database(factory.create_search_index(tenant, language))
+            }
+
+            #[cfg(feature = "tantivy")]
+            SearchIndexFactory::Tantivy(factory) => {
+                let search_index = tenant.os_index_name.clone();
+                TenantSearchIndex::Tantivy(factory.create_search_index(search_index, language))
+            }
+
+            #[cfg(feature = "memory")]
+            SearchIndexFactory::Memory(factory) => {
+                let search_index = tenant.os_index_name.clone();
+                TenantSearchIndex::Memory(factory.create_search_index(search_index))
+            }
+            #[cfg(feature = "chaos")]
+            SearchIndexFactory::Chaos(factory) => {
+                TenantSearchIndex::Chaos(factory.create_search_index(tenant))
             }
         }
     }
@@ -160,7 +309,14 @@ impl SearchIndexFactory {
 pub enum TenantSearchIndex {
     Typesense(typesense::TypesenseIndex),
     OpenSearch(opensearch::OpenSearchIndex),
+    Elasticsearch(elasticsearch::ElasticsearchIndex),
     Database(database::DatabaseSearchIndex),
+    #[cfg(feature = "tantivy")]
+    Tantivy(self::tantivy::TantivyIndex),
+    #[cfg(feature = "memory")]
+    Memory(memory::MemorySearchIndex),
+    #[cfg(feature = "chaos")]
+    Chaos(chaos::ChaosSearchIndex),
 }
 
 #[derive(Debug, Error)]
@@ -170,11 +326,35 @@ pub enum SearchError {
     #[error(transparent)]
     OpenSearch(#[from] opensearch::OpenSearchSearchError),
     #[error(transparent)]
+    Elasticsearch(#[from] elasticsearch::ElasticsearchSearchError),
+    #[error(transparent)]
     Database(#[from] database::DatabaseSearchError),
+    #[cfg(feature = "tantivy")]
+    #[error(transparent)]
+    Tantivy(#[from] self::tantivy::TantivySearchError),
+    #[cfg(feature = "memory")]
+    #[error(transparent)]
+    Memory(#[from] memory::MemorySearchError),
+    #[cfg(feature = "chaos")]
+    #[error(transparent)]
+    Chaos(#[from] chaos::ChaosSearchError),
+    #[error(transparent)]
+    Validation(#[from] validation::SearchValidationError),
     #[error("failed to perform migration")]
     Migration,
+    #[error("scroll cursor is not supported by the search backend")]
+    InvalidScrollCursor,
 }
 
+/// Default number of results requested per page when scrolling a search
+pub const DEFAULT_SCROLL_SIZE: u16 = 250;
+
+/// Default number of suggestions returned for a suggest request
+pub const DEFAULT_SUGGEST_SIZE: u16 = 10;
+
+/// Maximum number of values included for each facet of the search results
+pub const MAX_FACET_BUCKETS: u16 = 20;
+
 impl TenantSearchIndex {
     /// Creates a search index for the tenant
     #[tracing::instrument(skip(self))]
@@ -182,7 +362,14 @@ impl TenantSearchIndex {
         match self {
             TenantSearchIndex::Typesense(index) => index.create_index().await,
             TenantSearchIndex::OpenSearch(index) => index.create_index().await,
+            TenantSearchIndex::Elasticsearch(index) => index.create_index().await,
             TenantSearchIndex::Database(index) => index.create_index().await,
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => index.create_index().await,
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => index.create_index().await,
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.create_index().await,
         }
     }
 
@@ -192,7 +379,53 @@ impl TenantSearchIndex {
         match self {
             TenantSearchIndex::Typesense(index) => index.index_exists().await,
             TenantSearchIndex::OpenSearch(index) => index.index_exists().await,
+            TenantSearchIndex::Elasticsearch(index) => index.index_exists().await,
             TenantSearchIndex::Database(index) => index.index_exists().await,
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => index.index_exists().await,
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => index.index_exists().await,
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.index_exists().await,
+        }
+    }
+
+    /// Recreates the search index for the tenant with the current schema,
+    /// including the schema changes from all migrations
+    #[tracing::instrument(skip(self))]
+    pub async fn recreate_index(&self) -> Result<(), SearchError> {
+        match self {
+            TenantSearchIndex::Typesense(index) => index.recreate_index().await,
+            TenantSearchIndex::OpenSearch(index) => index.recreate_index().await,
+            TenantSearchIndex::Elasticsearch(index) => index.recreate_index().await,
+            TenantSearchIndex::Database(index) => index.recreate_index().await,
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => index.recreate_index().await,
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => index.recreate_index().await,
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.recreate_index().await,
+        }
+    }
+
+    /// Recreates the search index if it no longer exists (i.e it was deleted
+    /// out-of-band), returns whether the index was recreated
+    ///
+    /// The recreated index will be empty, the caller is responsible for
+    /// re-indexing the existing data
+    #[tracing::instrument(skip(self))]
+    pub async fn heal_index(&self) -> Result<bool, SearchError> {
+        match self {
+            TenantSearchIndex::Typesense(index) => index.heal_index().await,
+            TenantSearchIndex::OpenSearch(index) => index.heal_index().await,
+            TenantSearchIndex::Elasticsearch(index) => index.heal_index().await,
+            TenantSearchIndex::Database(index) => index.heal_index().await,
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => index.heal_index().await,
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => index.heal_index().await,
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.heal_index().await,
         }
     }
 
@@ -202,7 +435,14 @@ impl TenantSearchIndex {
         match self {
             TenantSearchIndex::Typesense(index) => index.delete_index().await,
             TenantSearchIndex::OpenSearch(index) => index.delete_index().await,
+            TenantSearchIndex::Elasticsearch(index) => index.delete_index().await,
             TenantSearchIndex::Database(index) => index.delete_index().await,
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => index.delete_index().await,
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => index.delete_index().await,
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.delete_index().await,
         }
     }
 
@@ -211,9 +451,11 @@ impl TenantSearchIndex {
     pub async fn search_index(
         &self,
         scope: &[DocumentBoxScopeRaw],
-        query: SearchRequest,
+        mut query: SearchRequest,
         folder_children: Option<Vec<FolderId>>,
     ) -> Result<SearchResults, SearchError> {
+        validation::validate_search_request(scope, &mut query)?;
+
         match self {
             TenantSearchIndex::Typesense(index) => {
                 index.search_index(scope, query, folder_children).await
@@ -221,9 +463,90 @@ impl TenantSearchIndex {
             TenantSearchIndex::OpenSearch(index) => {
                 index.search_index(scope, query, folder_children).await
             }
+            TenantSearchIndex::Elasticsearch(index) => {
+                index.search_index(scope, query, folder_children).await
+            }
             TenantSearchIndex::Database(index) => {
                 index.search_index(scope, query, folder_children).await
             }
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => {
+                index.search_index(scope, query, folder_children).await
+            }
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => {
+                index.search_index(scope, query, folder_children).await
+            }
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => {
+                index.search_index(scope, query, folder_children).await
+            }
+        }
+    }
+
+    /// Scrolls through the full set of results for a search, unlike
+    /// [TenantSearchIndex::search_index] this is not bound by the maximum
+    /// pagination offset
+    ///
+    /// Start with a [None] `cursor` and continue with the cursor from each
+    /// page until no next cursor is returned. The `query` must be the same
+    /// for every page, it is only validated for the first page
+    #[tracing::instrument(skip(self))]
+    pub async fn scroll_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        mut query: SearchRequest,
+        cursor: Option<SearchScrollCursor>,
+    ) -> Result<SearchScrollPage, SearchError> {
+        if cursor.is_none() {
+            validation::validate_search_request(scope, &mut query)?;
+        } else {
+            query_dsl::apply_query_dsl(&mut query)
+                .map_err(validation::SearchValidationError::InvalidQueryDsl)?;
+        }
+
+        // Scrolling always starts from the cursor and never explains
+        query.offset = None;
+        query.explain = false;
+        query.dry_run = false;
+
+        match self {
+            TenantSearchIndex::Typesense(index) => index.scroll_index(scope, query, cursor).await,
+            TenantSearchIndex::OpenSearch(index) => index.scroll_index(scope, query, cursor).await,
+            TenantSearchIndex::Elasticsearch(index) => {
+                index.scroll_index(scope, query, cursor).await
+            }
+            TenantSearchIndex::Database(index) => index.scroll_index(scope, query, cursor).await,
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => index.scroll_index(scope, query, cursor).await,
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => index.scroll_index(scope, query, cursor).await,
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.scroll_index(scope, query, cursor).await,
+        }
+    }
+
+    /// Suggests completions of item names starting with the query within
+    /// the provided scopes
+    #[tracing::instrument(skip(self))]
+    pub async fn suggest_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        query: SuggestRequest,
+    ) -> Result<SuggestResults, SearchError> {
+        validation::validate_suggest_request(scope, &query)?;
+
+        match self {
+            TenantSearchIndex::Typesense(index) => index.suggest_index(scope, query).await,
+            TenantSearchIndex::OpenSearch(index) => index.suggest_index(scope, query).await,
+            TenantSearchIndex::Elasticsearch(index) => index.suggest_index(scope, query).await,
+            TenantSearchIndex::Database(index) => index.suggest_index(scope, query).await,
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => index.suggest_index(scope, query).await,
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => index.suggest_index(scope, query).await,
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.suggest_index(scope, query).await,
         }
     }
 
@@ -235,6 +558,8 @@ impl TenantSearchIndex {
         file_id: FileId,
         query: FileSearchRequest,
     ) -> Result<FileSearchResults, SearchError> {
+        validation::validate_file_search_request(&query)?;
+
         match self {
             TenantSearchIndex::Typesense(index) => {
                 index.search_index_file(scope, file_id, query).await
@@ -242,9 +567,22 @@ impl TenantSearchIndex {
             TenantSearchIndex::OpenSearch(index) => {
                 index.search_index_file(scope, file_id, query).await
             }
+            TenantSearchIndex::Elasticsearch(index) => {
+                index.search_index_file(scope, file_id, query).await
+            }
             TenantSearchIndex::Database(index) => {
                 index.search_index_file(scope, file_id, query).await
             }
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => {
+                index.search_index_file(scope, file_id, query).await
+            }
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => {
+                index.search_index_file(scope, file_id, query).await
+            }
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.search_index_file(scope, file_id, query).await,
         }
     }
 
@@ -254,7 +592,14 @@ impl TenantSearchIndex {
         match self {
             TenantSearchIndex::Typesense(index) => index.add_data(data).await,
             TenantSearchIndex::OpenSearch(index) => index.add_data(data).await,
+            TenantSearchIndex::Elasticsearch(index) => index.add_data(data).await,
             TenantSearchIndex::Database(index) => index.add_data(data).await,
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => index.add_data(data).await,
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => index.add_data(data).await,
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.add_data(data).await,
         }
     }
 
@@ -268,7 +613,14 @@ impl TenantSearchIndex {
         match self {
             TenantSearchIndex::Typesense(index) => index.update_data(item_id, data).await,
             TenantSearchIndex::OpenSearch(index) => index.update_data(item_id, data).await,
+            TenantSearchIndex::Elasticsearch(index) => index.update_data(item_id, data).await,
             TenantSearchIndex::Database(index) => index.update_data(item_id, data).await,
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => index.update_data(item_id, data).await,
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => index.update_data(item_id, data).await,
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.update_data(item_id, data).await,
         }
     }
 
@@ -278,7 +630,14 @@ impl TenantSearchIndex {
         match self {
             TenantSearchIndex::Typesense(index) => index.delete_data(id).await,
             TenantSearchIndex::OpenSearch(index) => index.delete_data(id).await,
+            TenantSearchIndex::Elasticsearch(index) => index.delete_data(id).await,
             TenantSearchIndex::Database(index) => index.delete_data(id).await,
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => index.delete_data(id).await,
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => index.delete_data(id).await,
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.delete_data(id).await,
         }
     }
 
@@ -291,7 +650,14 @@ impl TenantSearchIndex {
         match self {
             TenantSearchIndex::Typesense(index) => index.delete_by_scope(scope).await,
             TenantSearchIndex::OpenSearch(index) => index.delete_by_scope(scope).await,
+            TenantSearchIndex::Elasticsearch(index) => index.delete_by_scope(scope).await,
             TenantSearchIndex::Database(index) => index.delete_by_scope(scope).await,
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => index.delete_by_scope(scope).await,
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => index.delete_by_scope(scope).await,
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.delete_by_scope(scope).await,
         }
     }
 
@@ -308,7 +674,16 @@ impl TenantSearchIndex {
             TenantSearchIndex::OpenSearch(index) => {
                 index.get_pending_migrations(applied_names).await
             }
+            TenantSearchIndex::Elasticsearch(index) => {
+                index.get_pending_migrations(applied_names).await
+            }
             TenantSearchIndex::Database(index) => index.get_pending_migrations(applied_names).await,
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => index.get_pending_migrations(applied_names).await,
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => index.get_pending_migrations(applied_names).await,
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => index.get_pending_migrations(applied_names).await,
         }
     }
 
@@ -322,25 +697,8 @@ impl TenantSearchIndex {
         name: &str,
     ) -> Result<(), SearchError> {
         // Apply migration logic
-        match self {
-            TenantSearchIndex::Typesense(index) => {
-                index
-                    .apply_migration(tenant, root_t, tenant_t, name)
-                    .await?
-            }
-
-            TenantSearchIndex::OpenSearch(index) => {
-                index
-                    .apply_migration(tenant, root_t, tenant_t, name)
-                    .await?
-            }
-
-            TenantSearchIndex::Database(index) => {
-                index
-                    .apply_migration(tenant, root_t, tenant_t, name)
-                    .await?
-            }
-        }
+        self.apply_backend_migration(tenant, root_t, tenant_t, name)
+            .await?;
 
         // Store the applied migration
         TenantMigration::create(
@@ -361,6 +719,49 @@ impl TenantSearchIndex {
         Ok(())
     }
 
+    /// Apply the backend specific logic for the migration `name` without
+    /// storing the migration as applied
+    pub(crate) async fn apply_backend_migration(
+        &self,
+        tenant: &Tenant,
+        root_t: &mut DbTransaction<'_>,
+        tenant_t: &mut DbTransaction<'_>,
+        name: &str,
+    ) -> Result<(), SearchError> {
+        match self {
+            TenantSearchIndex::Typesense(index) => {
+                index.apply_migration(tenant, root_t, tenant_t, name).await
+            }
+
+            TenantSearchIndex::OpenSearch(index) => {
+                index.apply_migration(tenant, root_t, tenant_t, name).await
+            }
+
+            TenantSearchIndex::Elasticsearch(index) => {
+                index.apply_migration(tenant, root_t, tenant_t, name).await
+            }
+
+            TenantSearchIndex::Database(index) => {
+                index.apply_migration(tenant, root_t, tenant_t, name).await
+            }
+
+            #[cfg(feature = "tantivy")]
+            TenantSearchIndex::Tantivy(index) => {
+                index.apply_migration(tenant, root_t, tenant_t, name).await
+            }
+
+            #[cfg(feature = "memory")]
+            TenantSearchIndex::Memory(index) => {
+                index.apply_migration(tenant, root_t, tenant_t, name).await
+            }
+
+            #[cfg(feature = "chaos")]
+            TenantSearchIndex::Chaos(index) => {
+                index.apply_migration(tenant, root_t, tenant_t, name).await
+            }
+        }
+    }
+
     /// Apply all pending migrations for a `tenant`
     ///
     /// When `target_migration_name` is specified only that target migration will
@@ -416,6 +817,18 @@ pub(crate) trait SearchIndex: Send + Sync + 'static {
 
     async fn index_exists(&self) -> Result<bool, SearchError>;
 
+    async fn recreate_index(&self) -> Result<(), SearchError>;
+
+    async fn heal_index(&self) -> Result<bool, SearchError> {
+        if self.index_exists().await? {
+            return Ok(false);
+        }
+
+        tracing::warn!("search index is missing, recreating index");
+        self.recreate_index().await?;
+        Ok(true)
+    }
+
     async fn delete_index(&self) -> Result<(), SearchError>;
 
     async fn search_index(
@@ -425,6 +838,41 @@ pub(crate) trait SearchIndex: Send + Sync + 'static {
         folder_children: Option<Vec<FolderId>>,
     ) -> Result<SearchResults, SearchError>;
 
+    /// Scroll through search results, backends without a native scroll API
+    /// page through [SearchIndex::search_index] by offset
+    async fn scroll_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        mut query: SearchRequest,
+        cursor: Option<SearchScrollCursor>,
+    ) -> Result<SearchScrollPage, SearchError> {
+        let offset = match cursor {
+            None => 0,
+            Some(SearchScrollCursor::Offset(offset)) => offset,
+            Some(SearchScrollCursor::Scroll(_)) => return Err(SearchError::InvalidScrollCursor),
+        };
+
+        query.offset = Some(offset);
+        query.size = Some(query.size.unwrap_or(DEFAULT_SCROLL_SIZE));
+
+        let results = self.search_index(scope, query, None).await?;
+        let next_offset = offset + results.results.len() as u64;
+        let next = (!results.results.is_empty() && next_offset < results.total_hits)
+            .then_some(SearchScrollCursor::Offset(next_offset));
+
+        Ok(SearchScrollPage {
+            results: results.results,
+            total_hits: results.total_hits,
+            next,
+        })
+    }
+
+    async fn suggest_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        query: SuggestRequest,
+    ) -> Result<SuggestResults, SearchError>;
+
     async fn search_index_file(
         &self,
         scope: &DocumentBoxScopeRaw,
@@ -0,0 +1,548 @@
+//! # Memory
+//!
+//! In-memory search index intended for testing, allows running code that depends
+//! on a [TenantSearchIndex](crate::TenantSearchIndex) without a search service.
+//!
+//! Matching is performed using a case-insensitive substring match of the query
+//! against the item names, summaries and page contents, results are scored by the
+//! number of matches found for the item. Summary matches are boosted and count
+//! as two matches.
+//!
+//! Indexes are shared between all search indexes created from the same
+//! [MemorySearchIndexFactory] and are lost when the factory is dropped.
+
+use crate::{
+    DEFAULT_SUGGEST_SIZE, SearchError, SearchIndex,
+    models::{
+        AdvancedSearchQuery, FileSearchRequest, FileSearchResults, FlattenedItemResult, PageResult,
+        SearchExplain, SearchFacet, SearchIndexData, SearchRequest, SearchResults, SearchScore,
+        SearchSuggestion, SuggestRequest, SuggestResults, UpdateSearchIndexData,
+    },
+};
+use docbox_database::{
+    DbTransaction,
+    models::{
+        document_box::{DocumentBoxScopeRaw, DocumentBoxScopeRawRef},
+        file::FileId,
+        folder::FolderId,
+        tenant::Tenant,
+    },
+};
+use std::{collections::HashMap, sync::Arc};
+use thiserror::Error;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Collection of indexes by name
+type MemoryIndexes = HashMap<String, MemoryIndex>;
+
+/// Collection of indexed items by item ID
+type MemoryIndex = HashMap<Uuid, SearchIndexData>;
+
+#[derive(Debug, Error)]
+pub enum MemorySearchError {
+    #[error("search index not found")]
+    IndexNotFound,
+}
+
+/// Factory for producing [MemorySearchIndex]'s that share the same underlying
+/// indexes
+#[derive(Clone, Default)]
+pub struct MemorySearchIndexFactory {
+    indexes: Arc<RwLock<MemoryIndexes>>,
+}
+
+impl MemorySearchIndexFactory {
+    /// Create a new factory with no indexes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a search index with the provided `index_name`
+    pub fn create_search_index(&self, index_name: String) -> MemorySearchIndex {
+        MemorySearchIndex {
+            index_name,
+            indexes: self.indexes.clone(),
+        }
+    }
+}
+
+/// Search index backed by memory
+#[derive(Clone)]
+pub struct MemorySearchIndex {
+    /// Name of the index this search index is for
+    index_name: String,
+    /// Indexes shared with the factory
+    indexes: Arc<RwLock<MemoryIndexes>>,
+}
+
+/// Match of an item against a search query
+struct MemoryMatch {
+    name_match: bool,
+    /// Pages that matched the query
+    page_matches: Vec<PageResult>,
+    /// Whether the non-page content matched the query
+    content_match: bool,
+    /// Whether the generated summary matched the query
+    summary_match: bool,
+}
+
+/// Number of hits a summary match counts as, boosting summary matches
+const SUMMARY_MATCH_HITS: u64 = 2;
+
+impl MemoryMatch {
+    fn total_hits(&self) -> u64 {
+        self.name_match as u64
+            + self.content_match as u64
+            + self.summary_match as u64 * SUMMARY_MATCH_HITS
+            + self.page_matches.len() as u64
+    }
+}
+
+/// Match the `item` against the lowercase `query`, when the query is
+/// empty all items are treated as matching
+fn match_item(item: &SearchIndexData, query: &str, request: &SearchRequest) -> Option<MemoryMatch> {
+    if query.is_empty() {
+        return Some(MemoryMatch {
+            name_match: false,
+            page_matches: Vec::new(),
+            content_match: false,
+            summary_match: false,
+        });
+    }
+
+    let name_match = request.include_name && item.name.to_lowercase().contains(query);
+
+    let (content_match, summary_match, page_matches) = if request.include_content {
+        let content_match = item
+            .content
+            .as_ref()
+            .is_some_and(|content| content.to_lowercase().contains(query));
+
+        let summary_match = item
+            .summary
+            .as_ref()
+            .is_some_and(|summary| summary.to_lowercase().contains(query));
+
+        let page_matches = item
+            .pages
+            .iter()
+            .flatten()
+            .filter(|page| page.content.to_lowercase().contains(query))
+            .map(|page| PageResult {
+                page: page.page,
+                matches: vec![page.content.clone()],
+            })
+            .collect();
+
+        (content_match, summary_match, page_matches)
+    } else {
+        (false, false, Vec::new())
+    };
+
+    let item_match = MemoryMatch {
+        name_match,
+        page_matches,
+        content_match,
+        summary_match,
+    };
+
+    (item_match.total_hits() > 0).then_some(item_match)
+}
+
+/// Check if the `item` passes the filters from the `request`
+fn filter_item(
+    item: &SearchIndexData,
+    scopes: &[DocumentBoxScopeRaw],
+    request: &SearchRequest,
+    folder_children: Option<&[FolderId]>,
+) -> bool {
+    if !matches_scopes(item, scopes) {
+        return false;
+    }
+
+    if folder_children.is_some_and(|children| !children.contains(&item.folder_id)) {
+        return false;
+    }
+
+    if let Some(mime) = request.mime.as_ref() {
+        let mime = mime.0.to_string();
+        if item.mime.as_ref().is_none_or(|value| value.ne(&mime)) {
+            return false;
+        }
+    }
+
+    if let Some(range) = request.created_at.as_ref() {
+        if range.start.is_some_and(|start| item.created_at < start) {
+            return false;
+        }
+
+        if range.end.is_some_and(|end| item.created_at > end) {
+            return false;
+        }
+    }
+
+    if let Some(created_by) = request.created_by.as_ref()
+        && item.created_by.as_ref() != Some(created_by)
+    {
+        return false;
+    }
+
+    if request.pinned.is_some_and(|pinned| item.pinned != pinned) {
+        return false;
+    }
+
+    if let Some(advanced) = request.advanced.as_ref()
+        && !matches_advanced_query(item, advanced)
+    {
+        return false;
+    }
+
+    true
+}
+
+/// Check if the `item` is within any of the `scopes`, scopes ending with a
+/// wildcard match all scopes starting with the prefix
+fn matches_scopes(item: &SearchIndexData, scopes: &[DocumentBoxScopeRaw]) -> bool {
+    scopes.iter().any(|scope| match scope.strip_suffix('*') {
+        Some(prefix) => item.document_box.starts_with(prefix),
+        None => item.document_box.eq(scope),
+    })
+}
+
+/// Check if the name of the `item` or any of the words within the name start
+/// with the lowercase `prefix`, returns whether the full name matched
+fn match_name_prefix(item: &SearchIndexData, prefix: &str) -> Option<bool> {
+    let name = item.name.to_lowercase();
+    if name.starts_with(prefix) {
+        return Some(true);
+    }
+
+    name.split(|char: char| !char.is_alphanumeric())
+        .any(|word| word.starts_with(prefix))
+        .then_some(false)
+}
+
+/// Evaluate the advanced `query` against the `item`
+fn matches_advanced_query(item: &SearchIndexData, query: &AdvancedSearchQuery) -> bool {
+    match query {
+        AdvancedSearchQuery::And { queries } => queries
+            .iter()
+            .all(|query| matches_advanced_query(item, query)),
+        AdvancedSearchQuery::Or { queries } => queries
+            .iter()
+            .any(|query| matches_advanced_query(item, query)),
+        AdvancedSearchQuery::Not { query } => !matches_advanced_query(item, query),
+        AdvancedSearchQuery::NameContains { value } => {
+            item.name.to_lowercase().contains(&value.to_lowercase())
+        }
+        AdvancedSearchQuery::MimeIn { values } => {
+            item.mime.as_ref().is_some_and(|mime| values.contains(mime))
+        }
+        AdvancedSearchQuery::CreatedBetween { start, end } => {
+            start.is_none_or(|start| item.created_at >= start)
+                && end.is_none_or(|end| item.created_at <= end)
+        }
+        AdvancedSearchQuery::CreatedByEquals { value } => item.created_by.as_ref() == Some(value),
+        AdvancedSearchQuery::PinnedEquals { value } => item.pinned == *value,
+    }
+}
+
+impl SearchIndex for MemorySearchIndex {
+    async fn create_index(&self) -> Result<(), SearchError> {
+        self.indexes
+            .write()
+            .await
+            .entry(self.index_name.clone())
+            .or_default();
+        Ok(())
+    }
+
+    async fn index_exists(&self) -> Result<bool, SearchError> {
+        Ok(self.indexes.read().await.contains_key(&self.index_name))
+    }
+
+    async fn recreate_index(&self) -> Result<(), SearchError> {
+        self.create_index().await
+    }
+
+    async fn delete_index(&self) -> Result<(), SearchError> {
+        self.indexes.write().await.remove(&self.index_name);
+        Ok(())
+    }
+
+    async fn search_index(
+        &self,
+        scopes: &[DocumentBoxScopeRaw],
+        query: SearchRequest,
+        folder_children: Option<Vec<FolderId>>,
+    ) -> Result<SearchResults, SearchError> {
+        let query_text = query
+            .query
+            .as_deref()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+
+        let max_pages = query.max_pages.unwrap_or(3) as usize;
+        let pages_offset = query.pages_offset.unwrap_or_default() as usize;
+
+        let limit = query.size.unwrap_or(50) as usize;
+        let offset = query.offset.unwrap_or_default() as usize;
+
+        let explain = (query.explain || query.dry_run).then(|| SearchExplain {
+            backend: "memory".to_string(),
+            query: serde_json::json!({
+                "query": query_text,
+                "include_name": query.include_name,
+                "include_content": query.include_content,
+                "advanced": query.advanced,
+            }),
+        });
+
+        if query.dry_run {
+            return Ok(SearchResults {
+                total_hits: 0,
+                results: Vec::new(),
+                timed_out: false,
+                explain,
+                facets: Vec::new(),
+            });
+        }
+
+        let indexes = self.indexes.read().await;
+        let index = indexes
+            .get(&self.index_name)
+            .ok_or(MemorySearchError::IndexNotFound)?;
+
+        let mut matches: Vec<(&SearchIndexData, MemoryMatch)> = index
+            .values()
+            .filter(|item| filter_item(item, scopes, &query, folder_children.as_deref()))
+            .filter_map(|item| {
+                match_item(item, &query_text, &query).map(|item_match| (item, item_match))
+            })
+            .collect();
+
+        // Highest number of hits first, newest items first when tied
+        matches.sort_by(|(a, a_match), (b, b_match)| {
+            b_match
+                .total_hits()
+                .cmp(&a_match.total_hits())
+                .then_with(|| b.created_at.cmp(&a.created_at))
+        });
+
+        let total_hits = matches.len() as u64;
+
+        let facets = query
+            .facet_fields()
+            .into_iter()
+            .map(|field| {
+                let mut counts: HashMap<String, u64> = HashMap::new();
+                for value in matches.iter().filter_map(|(item, _)| field.value(item)) {
+                    *counts.entry(value).or_default() += 1;
+                }
+                SearchFacet::from_counts(field, counts)
+            })
+            .collect();
+
+        let results = matches
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|(item, item_match)| {
+                let total_hits = item_match.total_hits();
+                let explanation = explain.is_some().then(|| {
+                    serde_json::json!({
+                        "name_match": item_match.name_match,
+                        "content_match": item_match.content_match,
+                        "summary_match": item_match.summary_match,
+                        "page_matches": item_match.page_matches.len(),
+                    })
+                });
+
+                FlattenedItemResult {
+                    item_ty: item.ty,
+                    item_id: item.item_id,
+                    document_box: item.document_box.clone(),
+                    content_match: item_match.content_match
+                        || item_match.summary_match
+                        || !item_match.page_matches.is_empty(),
+                    page_matches: item_match
+                        .page_matches
+                        .into_iter()
+                        .skip(pages_offset)
+                        .take(max_pages)
+                        .collect(),
+                    total_hits,
+                    score: SearchScore::Float(total_hits as f32),
+                    name_match: item_match.name_match,
+                    explanation,
+                }
+            })
+            .collect();
+
+        Ok(SearchResults {
+            total_hits,
+            results,
+            timed_out: false,
+            explain,
+            facets,
+        })
+    }
+
+    async fn suggest_index(
+        &self,
+        scopes: &[DocumentBoxScopeRaw],
+        query: SuggestRequest,
+    ) -> Result<SuggestResults, SearchError> {
+        let prefix = query.query.trim().to_lowercase();
+        let limit = query.size.unwrap_or(DEFAULT_SUGGEST_SIZE) as usize;
+
+        let indexes = self.indexes.read().await;
+        let index = indexes
+            .get(&self.index_name)
+            .ok_or(MemorySearchError::IndexNotFound)?;
+
+        let mut matches: Vec<(&SearchIndexData, bool)> = index
+            .values()
+            .filter(|item| matches_scopes(item, scopes))
+            .filter_map(|item| match_name_prefix(item, &prefix).map(|full| (item, full)))
+            .collect();
+
+        // Full name matches first, shorter names first when tied
+        matches.sort_by(|(a, a_full), (b, b_full)| {
+            b_full
+                .cmp(a_full)
+                .then_with(|| a.name.len().cmp(&b.name.len()))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        let suggestions = matches
+            .into_iter()
+            .take(limit)
+            .map(|(item, _)| SearchSuggestion {
+                item_ty: item.ty,
+                item_id: item.item_id,
+                document_box: item.document_box.clone(),
+                name: item.name.clone(),
+            })
+            .collect();
+
+        Ok(SuggestResults { suggestions })
+    }
+
+    async fn search_index_file(
+        &self,
+        scope: &DocumentBoxScopeRaw,
+        file_id: FileId,
+        query: FileSearchRequest,
+    ) -> Result<FileSearchResults, SearchError> {
+        let query_text = query
+            .query
+            .as_deref()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+
+        let limit = query.limit.unwrap_or(50) as usize;
+        let offset = query.offset.unwrap_or_default() as usize;
+
+        let indexes = self.indexes.read().await;
+        let index = indexes
+            .get(&self.index_name)
+            .ok_or(MemorySearchError::IndexNotFound)?;
+
+        let pages: Vec<PageResult> = index
+            .get(&file_id)
+            .filter(|item| item.document_box.eq(scope))
+            .and_then(|item| item.pages.as_ref())
+            .into_iter()
+            .flatten()
+            .filter(|page| page.content.to_lowercase().contains(&query_text))
+            .map(|page| PageResult {
+                page: page.page,
+                matches: vec![page.content.clone()],
+            })
+            .collect();
+
+        Ok(FileSearchResults {
+            total_hits: pages.len() as u64,
+            results: pages.into_iter().skip(offset).take(limit).collect(),
+        })
+    }
+
+    async fn add_data(&self, data: Vec<SearchIndexData>) -> Result<(), SearchError> {
+        let mut indexes = self.indexes.write().await;
+        let index = indexes
+            .get_mut(&self.index_name)
+            .ok_or(MemorySearchError::IndexNotFound)?;
+
+        for item in data {
+            index.insert(item.item_id, item);
+        }
+
+        Ok(())
+    }
+
+    async fn update_data(
+        &self,
+        item_id: Uuid,
+        data: UpdateSearchIndexData,
+    ) -> Result<(), SearchError> {
+        let mut indexes = self.indexes.write().await;
+        let index = indexes
+            .get_mut(&self.index_name)
+            .ok_or(MemorySearchError::IndexNotFound)?;
+
+        if let Some(item) = index.get_mut(&item_id) {
+            item.folder_id = data.folder_id;
+            item.name = data.name;
+            item.pinned = data.pinned;
+            item.content = data.content;
+
+            if let Some(document_box) = data.document_box {
+                item.document_box = document_box;
+            }
+
+            if let Some(pages) = data.pages {
+                item.pages = Some(pages);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn delete_data(&self, id: Uuid) -> Result<(), SearchError> {
+        if let Some(index) = self.indexes.write().await.get_mut(&self.index_name) {
+            index.remove(&id);
+        }
+
+        Ok(())
+    }
+
+    async fn delete_by_scope(&self, scope: DocumentBoxScopeRawRef<'_>) -> Result<(), SearchError> {
+        if let Some(index) = self.indexes.write().await.get_mut(&self.index_name) {
+            index.retain(|_, item| item.document_box.ne(scope));
+        }
+
+        Ok(())
+    }
+
+    async fn get_pending_migrations(
+        &self,
+        _applied_names: Vec<String>,
+    ) -> Result<Vec<String>, SearchError> {
+        // Memory search has no migrations
+        Ok(Vec::new())
+    }
+
+    async fn apply_migration(
+        &self,
+        _tenant: &Tenant,
+        _root_t: &mut DbTransaction<'_>,
+        _t: &mut DbTransaction<'_>,
+        _name: &str,
+    ) -> Result<(), SearchError> {
+        Ok(())
+    }
+}
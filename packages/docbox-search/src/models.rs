@@ -7,25 +7,46 @@ use chrono::{DateTime, Utc};
 use docbox_database::models::{
     document_box::{DocumentBoxScopeRaw, WithScope},
     file::FileWithExtra,
+    file_text_stats::TextStats,
     folder::{FolderId, FolderWithExtra},
     link::LinkWithExtra,
     shared::FolderPathSegment,
-    user::{User, UserId},
+    user::UserId,
 };
 use garde::Validate;
 use mime::Mime;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, skip_serializing_none};
+use std::collections::HashMap;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// Version of the document schema written to the search index by this version
+/// of docbox, documents indexed before schema versioning are treated as version 0
+///
+/// Outdated documents are upgraded lazily when they are read or updated and in
+/// bulk by the backend schema version migrations. Typesense entries track their
+/// version through the `version` field of their versioned entry format
+pub const SEARCH_SCHEMA_VERSION: i32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum SearchIndexType {
     File,
     Folder,
     Link,
 }
 
+impl SearchIndexType {
+    /// Name of the item type as it is stored within the search indexes
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchIndexType::File => "File",
+            SearchIndexType::Folder => "Folder",
+            SearchIndexType::Link => "Link",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchIndexData {
     /// Type of item the search index data is representing
@@ -57,14 +78,42 @@ pub struct SearchIndexData {
     pub created_at: DateTime<Utc>,
     /// User who created the item
     pub created_by: Option<UserId>,
+    /// Whether the item is pinned
+    #[serde(default)]
+    pub pinned: bool,
     /// Optional pages of document content
     pub pages: Option<Vec<DocumentPage>>,
+    /// Optional generated summary of the document content, matches
+    /// against the summary are boosted when searching content
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// Word count, character count and reading time of the document
+    /// content
+    #[serde(default)]
+    pub text_stats: Option<TextStats>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocumentPage {
     pub page: u64,
     pub content: String,
+    /// Bounding boxes for the words on the page, only present for PDF
+    /// compatible files. These are not stored in the search index, they
+    /// are persisted as a generated file instead
+    #[serde(skip)]
+    pub words: Option<Vec<DocumentWord>>,
+}
+
+/// Word within a page and its bounding box (In PDF points from the
+/// top left of the page)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DocumentWord {
+    /// Text content of the word
+    pub text: String,
+    pub x_min: f64,
+    pub y_min: f64,
+    pub x_max: f64,
+    pub y_max: f64,
 }
 
 #[skip_serializing_none]
@@ -72,8 +121,11 @@ pub struct DocumentPage {
 pub struct UpdateSearchIndexData {
     pub folder_id: FolderId,
     pub name: String,
+    pub pinned: bool,
     pub content: Option<String>,
     pub pages: Option<Vec<DocumentPage>>,
+    /// Moves the item to another document box when present
+    pub document_box: Option<DocumentBoxScopeRaw>,
 }
 
 /// Search results scoped to a specific file
@@ -89,6 +141,127 @@ pub struct FileSearchResults {
 pub struct SearchResults {
     pub results: Vec<FlattenedItemResult>,
     pub total_hits: u64,
+    /// Whether the search timed out before completing, when true the
+    /// results may only be partial
+    pub timed_out: bool,
+    /// Backend query details, only present when [SearchRequest::explain]
+    /// was requested
+    pub explain: Option<SearchExplain>,
+    /// Counts for each of the [SearchRequest::facets] that were requested
+    pub facets: Vec<SearchFacet>,
+}
+
+/// Field of the search results that can be counted as a facet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchFacetField {
+    /// Mime type of files
+    Mime,
+    /// Type of the item (File, Folder, Link)
+    ItemType,
+    /// User who created the item
+    CreatedBy,
+    /// Folder the item is within
+    FolderId,
+}
+
+impl SearchFacetField {
+    /// Name of the field within the search indexes
+    pub fn field_name(&self) -> &'static str {
+        match self {
+            SearchFacetField::Mime => "mime",
+            SearchFacetField::ItemType => "item_type",
+            SearchFacetField::CreatedBy => "created_by",
+            SearchFacetField::FolderId => "folder_id",
+        }
+    }
+
+    /// Find the facet field with the provided index `name`
+    pub fn from_field_name(name: &str) -> Option<SearchFacetField> {
+        match name {
+            "mime" => Some(SearchFacetField::Mime),
+            "item_type" => Some(SearchFacetField::ItemType),
+            "created_by" => Some(SearchFacetField::CreatedBy),
+            "folder_id" => Some(SearchFacetField::FolderId),
+            _ => None,
+        }
+    }
+
+    /// Value of the field for the indexed `data`, [None] when the
+    /// item does not have a value for the field
+    pub fn value(&self, data: &SearchIndexData) -> Option<String> {
+        match self {
+            SearchFacetField::Mime => data.mime.clone(),
+            SearchFacetField::ItemType => Some(data.ty.as_str().to_string()),
+            SearchFacetField::CreatedBy => data.created_by.clone(),
+            SearchFacetField::FolderId => Some(data.folder_id.to_string()),
+        }
+    }
+}
+
+/// Counts of the matching items for each value of a facet field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SearchFacet {
+    /// Field the counts are for
+    pub field: SearchFacetField,
+    /// Counts for each value, largest counts first
+    pub buckets: Vec<SearchFacetBucket>,
+}
+
+impl SearchFacet {
+    /// Create a facet from the `counts` of each value, only the
+    /// [MAX_FACET_BUCKETS](crate::MAX_FACET_BUCKETS) largest counts are kept
+    pub fn from_counts(field: SearchFacetField, counts: HashMap<String, u64>) -> SearchFacet {
+        let mut buckets: Vec<SearchFacetBucket> = counts
+            .into_iter()
+            .map(|(value, count)| SearchFacetBucket { value, count })
+            .collect();
+
+        buckets.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        buckets.truncate(crate::MAX_FACET_BUCKETS as usize);
+
+        SearchFacet { field, buckets }
+    }
+}
+
+/// Number of matching items with a specific facet value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SearchFacetBucket {
+    /// Value of the field
+    pub value: String,
+    /// Number of matching items with the value
+    pub count: u64,
+}
+
+/// Position to continue a scrolled search from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchScrollCursor {
+    /// Offset of the next result, used by backends without a native
+    /// scroll API
+    Offset(u64),
+    /// Backend native scroll context ID
+    Scroll(String),
+}
+
+/// Page of results from a scrolled search
+#[derive(Debug)]
+pub struct SearchScrollPage {
+    pub results: Vec<FlattenedItemResult>,
+    pub total_hits: u64,
+    /// Cursor to request the next page with, [None] once all the results
+    /// have been returned
+    pub next: Option<SearchScrollCursor>,
+}
+
+/// Details about the query that was sent to the search backend
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchExplain {
+    /// Name of the search backend that handled the query
+    pub backend: String,
+    /// Backend native query (OpenSearch / Elasticsearch query JSON, Typesense search
+    /// parameters, or the SQL and bound parameters for the database)
+    #[schema(value_type = Object)]
+    pub query: serde_json::Value,
 }
 
 /// Condensed version of a file result
@@ -112,18 +285,32 @@ pub struct FlattenedItemResult {
 
     /// Whether the name matches
     pub content_match: bool,
+
+    /// Backend specific scoring details, only present when
+    /// [SearchRequest::explain] was requested
+    pub explanation: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(untagged)]
 pub enum SearchScore {
     /// Typesense uses integer scoring
     Integer(u64),
-    /// OpenSearch and database use float scoring
+    /// OpenSearch, Elasticsearch and database use float scoring
     Float(f32),
 }
 
-#[derive(Debug, Serialize, ToSchema)]
+impl SearchScore {
+    /// Score as a float for comparing scores
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            SearchScore::Integer(value) => *value as f64,
+            SearchScore::Float(value) => *value as f64,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PageResult {
     pub page: u64,
     pub matches: Vec<String>,
@@ -141,6 +328,40 @@ pub struct AdminSearchRequest {
     #[serde(flatten)]
     #[garde(dive)]
     pub request: SearchRequest,
+
+    /// Include the backend native query and per result scoring
+    /// details in the response for debugging relevance
+    #[garde(skip)]
+    pub explain: bool,
+
+    /// Build the backend native query without executing it, the
+    /// response will contain no results only the query explanation
+    #[garde(skip)]
+    pub dry_run: bool,
+
+    /// Include archived document boxes in the search, archived
+    /// document boxes are excluded by default
+    #[garde(skip)]
+    pub include_archived: bool,
+}
+
+/// Request to export the full set of results for a search across
+/// multiple document boxes
+#[derive(Default, Debug, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct AdminSearchExportRequest {
+    #[garde(skip)]
+    #[schema(value_type = Vec<String>)]
+    pub scopes: Vec<DocumentBoxScopeRaw>,
+
+    #[serde(flatten)]
+    #[garde(dive)]
+    pub request: SearchRequest,
+
+    /// Include archived document boxes in the export, archived
+    /// document boxes are excluded by default
+    #[garde(skip)]
+    pub include_archived: bool,
 }
 
 /// Request to search within a file
@@ -160,6 +381,39 @@ pub struct FileSearchRequest {
     pub limit: Option<u16>,
 }
 
+/// Request for completions of item names within document boxes
+#[derive(Default, Debug, Clone, Validate, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct SuggestRequest {
+    /// Prefix of the item names to complete
+    #[garde(length(min = 1))]
+    #[schema(min_length = 1)]
+    pub query: String,
+
+    /// Maximum number of suggestions to return (Default: 10)
+    #[garde(skip)]
+    pub size: Option<u16>,
+}
+
+/// Item name completion for a suggest request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SearchSuggestion {
+    /// Type of item the suggestion is for
+    pub item_ty: SearchIndexType,
+    /// ID of the item
+    pub item_id: Uuid,
+    /// Scope the item is within
+    pub document_box: DocumentBoxScopeRaw,
+    /// Name of the item
+    pub name: String,
+}
+
+/// Suggestions for a suggest request, best matches first
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SuggestResults {
+    pub suggestions: Vec<SearchSuggestion>,
+}
+
 /// Wrapper around [Mime] to implement [Serialize] and [Deserialize]
 #[serde_as]
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -167,7 +421,7 @@ pub struct FileSearchRequest {
 pub struct StringMime(#[serde_as(as = "serde_with::DisplayFromStr")] pub Mime);
 
 /// Request to search within a document box
-#[derive(Default, Debug, Validate, Deserialize, Serialize, ToSchema)]
+#[derive(Default, Debug, Clone, Validate, Deserialize, Serialize, ToSchema)]
 #[serde(default)]
 pub struct SearchRequest {
     /// The search query
@@ -205,6 +459,32 @@ pub struct SearchRequest {
     #[schema(value_type = Option<Uuid>)]
     pub folder_id: Option<FolderId>,
 
+    /// Only include items with the provided pinned state
+    #[garde(skip)]
+    pub pinned: Option<bool>,
+
+    /// Structured query the items must match, applied in addition to
+    /// the other filters
+    #[garde(skip)]
+    pub advanced: Option<AdvancedSearchQuery>,
+
+    /// Structured query string the items must match, combined with
+    /// the advanced query (i.e "name:report AND mime:application/pdf NOT created_by:bob")
+    ///
+    /// See [crate::query_dsl] for the supported syntax
+    #[garde(skip)]
+    pub query_dsl: Option<String>,
+
+    /// Whether to boost links that are visited more often, the results
+    /// within the requested page are re-ranked using the link visit counts
+    #[garde(skip)]
+    pub boost_popular_links: bool,
+
+    /// Fields to count the matching items for, the counts for each value
+    /// of the fields are returned alongside the results
+    #[garde(skip)]
+    pub facets: Option<Vec<SearchFacetField>>,
+
     /// Number of items to include in the response
     #[garde(skip)]
     pub size: Option<u16>,
@@ -221,9 +501,77 @@ pub struct SearchRequest {
     /// Offset to start at when aggregating page results
     #[garde(skip)]
     pub pages_offset: Option<u64>,
+
+    /// Maximum time in milliseconds the search backend is allowed to spend
+    /// performing the search. When exceeded any partial results that were
+    /// collected are returned and `timed_out` is set on the response
+    #[garde(skip)]
+    pub timeout_ms: Option<u64>,
+
+    /// Whether to include backend query and scoring details in the results
+    ///
+    /// Not accepted from requests directly, only set through [AdminSearchRequest]
+    #[serde(skip)]
+    #[garde(skip)]
+    pub explain: bool,
+
+    /// Whether to only build the backend query without executing it
+    ///
+    /// Not accepted from requests directly, only set through [AdminSearchRequest]
+    #[serde(skip)]
+    #[garde(skip)]
+    pub dry_run: bool,
+}
+
+impl SearchRequest {
+    /// Unique facet fields that were requested, in the order they were requested
+    pub fn facet_fields(&self) -> Vec<SearchFacetField> {
+        let mut fields: Vec<SearchFacetField> = Vec::new();
+        for field in self.facets.iter().flatten() {
+            if !fields.contains(field) {
+                fields.push(*field);
+            }
+        }
+        fields
+    }
+}
+
+/// Structured query for advanced searches, boolean combinations of field
+/// predicates that are compiled to the native query of each search backend
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AdvancedSearchQuery {
+    /// Item must match all of the queries
+    And {
+        #[schema(no_recursion)]
+        queries: Vec<AdvancedSearchQuery>,
+    },
+    /// Item must match at least one of the queries
+    Or {
+        #[schema(no_recursion)]
+        queries: Vec<AdvancedSearchQuery>,
+    },
+    /// Item must not match the query
+    Not {
+        #[schema(no_recursion)]
+        query: Box<AdvancedSearchQuery>,
+    },
+    /// Item name contains the value (case-insensitive)
+    NameContains { value: String },
+    /// Item mime type is one of the values, only files have a mime type
+    MimeIn { values: Vec<String> },
+    /// Item was created within the range (inclusive)
+    CreatedBetween {
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    },
+    /// Item was created by the user
+    CreatedByEquals { value: UserId },
+    /// Item pinned state is the value
+    PinnedEquals { value: bool },
 }
 
-#[derive(Default, Debug, Deserialize, Serialize, ToSchema)]
+#[derive(Default, Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct SearchRange {
     pub start: Option<DateTime<Utc>>,
     pub end: Option<DateTime<Utc>>,
@@ -256,7 +604,7 @@ impl Validate for SearchRange {
     }
 }
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "type")]
 pub enum SearchResultData {
     File(FileWithExtra),
@@ -264,10 +612,15 @@ pub enum SearchResultData {
     Link(LinkWithExtra),
 }
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SearchResultResponse {
     pub total_hits: u64,
     pub results: Vec<SearchResultItem>,
+    /// Whether the search timed out, results may be partial
+    pub timed_out: bool,
+    /// Counts for the requested facet fields
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub facets: Vec<SearchFacet>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -280,9 +633,17 @@ pub struct FileSearchResultResponse {
 pub struct AdminSearchResultResponse {
     pub total_hits: u64,
     pub results: Vec<WithScope<SearchResultItem>>,
+    /// Whether the search timed out, results may be partial
+    pub timed_out: bool,
+    /// Backend query details when `explain` or `dry_run` was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explain: Option<SearchExplain>,
+    /// Counts for the requested facet fields
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub facets: Vec<SearchFacet>,
 }
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SearchResultItem {
     /// The result score
     pub score: SearchScore,
@@ -297,6 +658,11 @@ pub struct SearchResultItem {
 
     pub name_match: bool,
     pub content_match: bool,
+
+    /// Backend specific scoring details when `explain` was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub explanation: Option<serde_json::Value>,
 }
 
 /// Request to list users
@@ -310,12 +676,9 @@ pub struct UsersRequest {
     /// Number of items to include in the response
     #[garde(skip)]
     pub size: Option<u16>,
-}
 
-#[derive(Debug, Serialize)]
-pub struct AdminUsersResults {
-    /// The users
-    pub results: Vec<User>,
-    /// The total number of users
-    pub total: i64,
+    /// Cursor from a previous page to continue from, takes priority
+    /// over the offset
+    #[garde(skip)]
+    pub cursor: Option<String>,
 }
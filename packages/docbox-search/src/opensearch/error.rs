@@ -1,3 +1,4 @@
+use crate::language::UnknownSearchLanguage;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -10,6 +11,8 @@ pub enum OpenSearchIndexFactoryError {
     CreateAuthConfig,
     #[error("failed to build search transport")]
     BuildTransport,
+    #[error(transparent)]
+    InvalidLanguage(#[from] UnknownSearchLanguage),
 }
 
 #[derive(Debug, Error)]
@@ -28,4 +31,10 @@ pub enum OpenSearchSearchError {
     UpdateData,
     #[error("failed to delete search data")]
     DeleteData,
+    #[error("failed to update index mapping")]
+    UpdateMapping,
+    #[error("failed to upgrade search documents")]
+    UpgradeDocuments,
+    #[error("migration not found")]
+    MigrationNotFound,
 }
@@ -1,12 +1,21 @@
-use crate::SearchError;
+use crate::language::SearchLanguage;
 use crate::models::FileSearchRequest;
-use crate::opensearch::models::{OsSearchIndexData, OsUpdateSearchIndexData, SearchResponse};
+use crate::opensearch::models::{
+    OsSearchIndexData, OsUpdateSearchIndexData, SearchResponse, SearchResponseHit, SuggestResponse,
+    TermsAggregation,
+};
+use crate::{DEFAULT_SCROLL_SIZE, DEFAULT_SUGGEST_SIZE, MAX_FACET_BUCKETS, SearchError};
 
-use super::models::{FlattenedItemResult, PageResult, SearchScore};
+use super::models::{
+    AdvancedSearchQuery, FlattenedItemResult, PageResult, SEARCH_SCHEMA_VERSION, SearchExplain,
+    SearchScore, SearchScrollCursor, SearchScrollPage,
+};
 use super::{
     SearchIndex,
     models::{
-        FileSearchResults, SearchIndexData, SearchRequest, SearchResults, UpdateSearchIndexData,
+        FileSearchResults, SearchFacet, SearchFacetBucket, SearchFacetField, SearchIndexData,
+        SearchRequest, SearchResults, SearchSuggestion, SuggestRequest, SuggestResults,
+        UpdateSearchIndexData,
     },
 };
 use aws_config::SdkConfig;
@@ -16,9 +25,9 @@ use docbox_database::models::file::FileId;
 use docbox_database::models::{
     document_box::DocumentBoxScopeRaw, folder::FolderId, tenant::Tenant,
 };
-use opensearch::indices::IndicesGetParts;
+use opensearch::indices::{IndicesGetParts, IndicesPutMappingParts};
 use opensearch::{
-    DeleteByQueryParts, OpenSearch, SearchParts,
+    ClearScrollParts, DeleteByQueryParts, OpenSearch, ScrollParts, SearchParts, UpdateByQueryParts,
     http::{
         Url,
         request::JsonBody,
@@ -30,6 +39,7 @@ use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use serde_with::skip_serializing_none;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 pub use error::{OpenSearchIndexFactoryError, OpenSearchSearchError};
@@ -37,10 +47,39 @@ pub use error::{OpenSearchIndexFactoryError, OpenSearchSearchError};
 pub mod error;
 mod models;
 
+/// Migrations to apply against the index, items indexed before a migration
+/// added a field must be re-indexed to populate the field
+const OPENSEARCH_MIGRATIONS: &[&str] = &[
+    "m1_opensearch_add_pinned_field",
+    "m2_opensearch_add_summary_field",
+    "m3_opensearch_add_schema_version",
+    "m4_opensearch_add_text_stats_field",
+];
+
+/// Duration scroll contexts are kept alive between scroll requests
+const SCROLL_KEEP_ALIVE: &str = "2m";
+
+/// Script upgrading a document to the current [SEARCH_SCHEMA_VERSION]
+///
+/// Version 1: Documents have an explicit pinned state and schema version
+const UPGRADE_DOCUMENT_SCRIPT: &str = r#"
+if (ctx._source.pinned == null) { ctx._source.pinned = false; }
+ctx._source.schema_version = params.schema_version;
+"#;
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OpenSearchConfig {
     /// URL of the OpenSearch server
     pub url: String,
+
+    /// Data residency region the search index is located within (i.e "eu-west"),
+    /// tenants assigned to a region can only use search within that region
+    #[serde(default)]
+    pub data_region: Option<String>,
+
+    /// Default language used to analyze the indexed text content
+    #[serde(default)]
+    pub language: Option<SearchLanguage>,
 }
 
 impl OpenSearchConfig {
@@ -48,13 +87,22 @@ impl OpenSearchConfig {
         let url = std::env::var("OPENSEARCH_URL")
             .or(std::env::var("DOCBOX_OPENSEARCH_URL"))
             .map_err(|_| OpenSearchIndexFactoryError::MissingUrl)?;
-        Ok(Self { url })
+        let data_region = std::env::var("DOCBOX_SEARCH_DATA_REGION").ok();
+        let language = SearchLanguage::from_env()?;
+
+        Ok(Self {
+            url,
+            data_region,
+            language,
+        })
     }
 }
 
 #[derive(Clone)]
 pub struct OpenSearchIndexFactory {
     client: OpenSearch,
+    data_region: Option<String>,
+    language: Option<SearchLanguage>,
 }
 
 impl OpenSearchIndexFactory {
@@ -67,13 +115,32 @@ impl OpenSearchIndexFactory {
             OpenSearchIndexFactoryError::InvalidUrl
         })?;
         let client = create_open_search(aws_config, url)?;
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            data_region: config.data_region,
+            language: config.language,
+        })
+    }
+
+    /// Data residency region the search index is located within
+    pub fn data_region(&self) -> Option<&str> {
+        self.data_region.as_deref()
     }
 
-    pub fn create_search_index(&self, search_index: TenantSearchIndexName) -> OpenSearchIndex {
+    /// Default language used to analyze the indexed text content
+    pub fn language(&self) -> Option<SearchLanguage> {
+        self.language
+    }
+
+    pub fn create_search_index(
+        &self,
+        search_index: TenantSearchIndexName,
+        language: Option<SearchLanguage>,
+    ) -> OpenSearchIndex {
         OpenSearchIndex {
             client: self.client.clone(),
             search_index,
+            language,
         }
     }
 }
@@ -82,6 +149,8 @@ impl OpenSearchIndexFactory {
 pub struct OpenSearchIndex {
     client: OpenSearch,
     search_index: TenantSearchIndexName,
+    /// Language used to analyze the full text content fields
+    language: Option<SearchLanguage>,
 }
 
 /// Represents a search index name for a specific tenant
@@ -155,6 +224,8 @@ pub fn create_open_search_prod(
 
 impl SearchIndex for OpenSearchIndex {
     async fn create_index(&self) -> Result<(), SearchError> {
+        let text_field = self.text_field();
+
         // Create index for files
         let response = self
             .client
@@ -193,7 +264,7 @@ impl SearchIndex for OpenSearchIndex {
                         // Full text file/folder/link name search
                         "name" : { "type" : "text", "analyzer": "edge_ngram_analyzer" },
                         // Full text file/link value content search
-                        "content" : { "type" : "text" },
+                        "content" : text_field,
                         // Created at date search
                         "created_at": { "type": "date", "format": "rfc3339_lenient" },
                         // Exact user for the creator user ID
@@ -206,12 +277,14 @@ impl SearchIndex for OpenSearchIndex {
                         "version": {
                             "type": "keyword"
                         },
+                        // Version of the document schema
+                        "schema_version": { "type": "integer" },
                         // Document pages for files
                         "pages": {
                             "type": "nested",
                             "properties": {
                                 // Full text file/link value content search
-                                "content" : { "type" : "text" },
+                                "content" : text_field,
                                 // Page number
                                 "page": { "type": "integer" },
                             }
@@ -256,6 +329,17 @@ impl SearchIndex for OpenSearchIndex {
         Ok(true)
     }
 
+    async fn recreate_index(&self) -> Result<(), SearchError> {
+        self.create_index().await?;
+
+        // Apply the schema changes from all migrations
+        for migration_name in OPENSEARCH_MIGRATIONS {
+            self.apply_schema_migration(migration_name).await?;
+        }
+
+        Ok(())
+    }
+
     async fn delete_index(&self) -> Result<(), SearchError> {
         // Delete index for files
         let response = self
@@ -282,6 +366,49 @@ impl SearchIndex for OpenSearchIndex {
         Ok(())
     }
 
+    async fn suggest_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        query: SuggestRequest,
+    ) -> Result<SuggestResults, SearchError> {
+        let size = query.size.unwrap_or(DEFAULT_SUGGEST_SIZE);
+        let query = create_opensearch_suggest_query(query.query.trim(), scope, size);
+
+        tracing::debug!(%query, "suggesting with query");
+
+        let index = [self.search_index.0.as_str()];
+
+        let response = self
+            .client
+            .search(SearchParts::Index(&index))
+            .body(query)
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to query suggestions");
+                OpenSearchSearchError::SearchIndex
+            })?;
+
+        let response: SuggestResponse = response.json().await.map_err(|error| {
+            tracing::error!(?error, "failed to parse suggest response");
+            OpenSearchSearchError::SearchIndex
+        })?;
+
+        let suggestions = response
+            .hits
+            .hits
+            .into_iter()
+            .map(|hit| SearchSuggestion {
+                item_ty: hit._source.item_type,
+                item_id: hit._source.item_id,
+                document_box: hit._source.document_box,
+                name: hit._source.name,
+            })
+            .collect();
+
+        Ok(SuggestResults { suggestions })
+    }
+
     async fn search_index_file(
         &self,
         scope: &DocumentBoxScopeRaw,
@@ -353,22 +480,56 @@ impl SearchIndex for OpenSearchIndex {
         folder_children: Option<Vec<FolderId>>,
     ) -> Result<SearchResults, SearchError> {
         let offset = query.offset;
-        let query = create_opensearch_query(query, scope, folder_children);
+        let timeout = query.timeout_ms.map(|timeout_ms| format!("{timeout_ms}ms"));
+        let explain = query.explain || query.dry_run;
+        let dry_run = query.dry_run;
+        let facet_fields = query.facet_fields();
+        let mut query = create_opensearch_query(query, scope, folder_children);
+
+        if explain {
+            // Request scoring explanations for each of the hits
+            query["explain"] = json!(true);
+        }
+
+        if !facet_fields.is_empty() {
+            query["aggs"] = create_facet_aggregations(&facet_fields);
+        }
 
         tracing::debug!(%query, "searching with query");
 
+        let explain = explain.then(|| SearchExplain {
+            backend: "opensearch".to_string(),
+            query: query.clone(),
+        });
+
+        if dry_run {
+            return Ok(SearchResults {
+                total_hits: 0,
+                results: Vec::new(),
+                timed_out: false,
+                explain,
+                facets: Vec::new(),
+            });
+        }
+
+        let index = [self.search_index.0.as_str()];
+
         // Search for field in content
-        let response = self
+        let mut request = self
             .client
-            .search(SearchParts::Index(&[&self.search_index.0]))
+            .search(SearchParts::Index(&index))
             .from(offset.unwrap_or(0) as i64)
-            .body(query)
-            .send()
-            .await
-            .map_err(|error| {
-                tracing::error!(?error, "failed to search index");
-                OpenSearchSearchError::SearchIndex
-            })?;
+            .body(query);
+
+        // OpenSearch will return the partial results collected before the timeout
+        if let Some(timeout) = timeout.as_deref() {
+            request = request.timeout(timeout);
+        }
+
+        let response = request.send().await.map_err(|error| {
+            tracing::error!(?error, "failed to search index");
+            OpenSearchSearchError::SearchIndex
+        })?;
 
         let response: serde_json::Value = response.json().await.map_err(|error| {
             tracing::error!(?error, "failed to get search response");
@@ -382,55 +543,83 @@ impl SearchIndex for OpenSearchIndex {
             OpenSearchSearchError::SearchIndex
         })?;
         let total_hits = response.hits.total.value;
+        let timed_out = response.timed_out;
 
-        const NAME_MATCH_KEYS: [&str; 2] = ["name_match_exact", "name_match_wildcard"];
-
-        let results: Vec<FlattenedItemResult> = response
-            .hits
-            .hits
-            .into_iter()
-            .map(|item| {
-                let (total_hits, page_matches) = match item.inner_hits {
-                    Some(inner_hits) => {
-                        let total_hits = inner_hits.pages.hits.total.value;
-                        let page_matches: Vec<PageResult> = inner_hits
-                            .pages
-                            .hits
-                            .hits
-                            .into_iter()
-                            .map(|value| PageResult {
-                                page: value._source.page,
-                                matches: value.highlight.content,
-                            })
-                            .collect();
-                        (total_hits, page_matches)
-                    }
-                    None => (0, vec![]),
-                };
-
-                let name_match = item.matched_queries.is_some_and(|matches| {
-                    matches
-                        .iter()
-                        .any(|value| NAME_MATCH_KEYS.contains(&value.as_str()))
-                });
-                let content_match = !page_matches.is_empty();
+        if timed_out {
+            tracing::warn!("opensearch search timed out, returning partial results");
+        }
 
-                FlattenedItemResult {
-                    item_ty: item._source.item_type,
-                    item_id: item._source.item_id,
-                    document_box: item._source.document_box,
-                    score: SearchScore::Float(item._score),
-                    page_matches,
-                    total_hits,
-                    name_match,
-                    content_match,
-                }
-            })
-            .collect();
+        let results = self.map_search_hits(response.hits.hits);
+        let facets = map_facet_aggregations(&facet_fields, response.aggregations);
 
         Ok(SearchResults {
             total_hits,
             results,
+            timed_out,
+            explain,
+            facets,
+        })
+    }
+
+    async fn scroll_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        mut query: SearchRequest,
+        cursor: Option<SearchScrollCursor>,
+    ) -> Result<SearchScrollPage, SearchError> {
+        let response = match cursor {
+            None => {
+                query.size = Some(query.size.unwrap_or(DEFAULT_SCROLL_SIZE));
+                let query = create_opensearch_query(query, scope, None);
+                let index = [self.search_index.0.as_str()];
+
+                self.client
+                    .search(SearchParts::Index(&index))
+                    .scroll(SCROLL_KEEP_ALIVE)
+                    .body(query)
+                    .send()
+                    .await
+            }
+            Some(SearchScrollCursor::Scroll(scroll_id)) => {
+                self.client
+                    .scroll(ScrollParts::None)
+                    .body(json!({
+                        "scroll": SCROLL_KEEP_ALIVE,
+                        "scroll_id": scroll_id
+                    }))
+                    .send()
+                    .await
+            }
+            Some(SearchScrollCursor::Offset(_)) => return Err(SearchError::InvalidScrollCursor),
+        };
+
+        let response = response.map_err(|error| {
+            tracing::error!(?error, "failed to scroll index");
+            OpenSearchSearchError::SearchIndex
+        })?;
+
+        let response: SearchResponse = response.json().await.map_err(|error| {
+            tracing::error!(?error, "failed to parse scroll response");
+            OpenSearchSearchError::SearchIndex
+        })?;
+
+        let total_hits = response.hits.total.value;
+        let results = self.map_search_hits(response.hits.hits);
+
+        let next = match response._scroll_id {
+            // Release the scroll context once all the results have been read
+            Some(scroll_id) if results.is_empty() => {
+                self.clear_scroll(&scroll_id).await;
+                None
+            }
+            Some(scroll_id) => Some(SearchScrollCursor::Scroll(scroll_id)),
+            None => None,
+        };
+
+        Ok(SearchScrollPage {
+            results,
+            total_hits,
+            next,
         })
     }
 
@@ -448,7 +637,11 @@ impl SearchIndex for OpenSearchIndex {
                     content: data.content,
                     created_at: data.created_at.to_rfc3339(),
                     created_by: data.created_by,
+                    pinned: data.pinned,
                     pages: data.pages,
+                    summary: data.summary,
+                    text_stats: data.text_stats,
+                    schema_version: SEARCH_SCHEMA_VERSION,
                 })
             })
             .collect();
@@ -488,8 +681,11 @@ impl SearchIndex for OpenSearchIndex {
         let data = OsUpdateSearchIndexData {
             folder_id: data.folder_id,
             name: data.name,
+            pinned: data.pinned,
             content: data.content,
             pages: data.pages,
+            document_box: data.document_box,
+            schema_version: SEARCH_SCHEMA_VERSION,
         };
 
         let items = self.get_by_item_id(item_id).await.map_err(|error| {
@@ -597,9 +793,13 @@ impl SearchIndex for OpenSearchIndex {
 
     async fn get_pending_migrations(
         &self,
-        _applied_names: Vec<String>,
+        applied_names: Vec<String>,
     ) -> Result<Vec<String>, SearchError> {
-        Ok(Vec::new())
+        Ok(OPENSEARCH_MIGRATIONS
+            .iter()
+            .filter(|migration_name| !applied_names.iter().any(|name| name.eq(*migration_name)))
+            .map(|migration_name| migration_name.to_string())
+            .collect())
     }
 
     async fn apply_migration(
@@ -607,14 +807,226 @@ impl SearchIndex for OpenSearchIndex {
         _tenant: &Tenant,
         _root_t: &mut DbTransaction<'_>,
         _t: &mut DbTransaction<'_>,
-        _name: &str,
+        name: &str,
     ) -> Result<(), SearchError> {
-        Ok(())
+        self.apply_schema_migration(name).await
     }
 }
 
 impl OpenSearchIndex {
+    /// Mapping for a full text content field, analyzed using the built-in
+    /// analyzer for the configured language
+    fn text_field(&self) -> serde_json::Value {
+        match self.language {
+            Some(language) => json!({ "type": "text", "analyzer": language.name() }),
+            None => json!({ "type": "text" }),
+        }
+    }
+
+    /// Apply the schema changes for the migration `name`
+    async fn apply_schema_migration(&self, name: &str) -> Result<(), SearchError> {
+        match name {
+            "m1_opensearch_add_pinned_field" => {
+                self.put_mapping_properties(json!({
+                    // Exact search for the pinned state
+                    "pinned": { "type": "boolean" }
+                }))
+                .await?;
+            }
+            "m2_opensearch_add_summary_field" => {
+                self.put_mapping_properties(json!({
+                    // Full text generated summary search
+                    "summary": self.text_field()
+                }))
+                .await?;
+            }
+            "m3_opensearch_add_schema_version" => {
+                self.put_mapping_properties(json!({
+                    // Version of the document schema
+                    "schema_version": { "type": "integer" }
+                }))
+                .await?;
+
+                // Bulk upgrade all existing documents
+                self.upgrade_documents(None).await?;
+            }
+            "m4_opensearch_add_text_stats_field" => {
+                self.put_mapping_properties(json!({
+                    // Text statistics for range filtering and sorting
+                    "text_stats": {
+                        "properties": {
+                            "word_count": { "type": "long" },
+                            "character_count": { "type": "long" },
+                            "reading_time_seconds": { "type": "long" }
+                        }
+                    }
+                }))
+                .await?;
+            }
+            _ => return Err(OpenSearchSearchError::MigrationNotFound.into()),
+        }
+
+        Ok(())
+    }
+
+    /// Add new mapping `properties` to the index
+    async fn put_mapping_properties(
+        &self,
+        properties: serde_json::Value,
+    ) -> Result<(), OpenSearchSearchError> {
+        let response = self
+            .client
+            .indices()
+            .put_mapping(IndicesPutMappingParts::Index(&[&self.search_index.0]))
+            .body(json!({ "properties": properties }))
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to update index mapping");
+                OpenSearchSearchError::UpdateMapping
+            })?;
+
+        response.error_for_status_code().map_err(|error| {
+            tracing::error!(?error, "failed to update index mapping (response)");
+            OpenSearchSearchError::UpdateMapping
+        })?;
+
+        Ok(())
+    }
+
+    /// Upgrade documents written with an older schema version to the current
+    /// [SEARCH_SCHEMA_VERSION], when `item_ids` is provided only the documents
+    /// for those items are upgraded
+    async fn upgrade_documents(
+        &self,
+        item_ids: Option<&[Uuid]>,
+    ) -> Result<(), OpenSearchSearchError> {
+        let mut filters = vec![json!({
+            "bool": {
+                // Documents without a schema version are also outdated
+                "must_not": {
+                    "range": { "schema_version": { "gte": SEARCH_SCHEMA_VERSION } }
+                }
+            }
+        })];
+
+        if let Some(item_ids) = item_ids {
+            filters.push(json!({ "terms": { "item_id": item_ids } }));
+        }
+
+        let response = self
+            .client
+            .update_by_query(UpdateByQueryParts::Index(&[&self.search_index.0]))
+            // Documents modified while upgrading are already up to date
+            .conflicts(opensearch::params::Conflicts::Proceed)
+            .body(json!({
+                "query": {
+                    "bool": { "filter": filters }
+                },
+                "script": {
+                    "source": UPGRADE_DOCUMENT_SCRIPT,
+                    "lang": "painless",
+                    "params": { "schema_version": SEARCH_SCHEMA_VERSION }
+                }
+            }))
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to upgrade search documents");
+                OpenSearchSearchError::UpgradeDocuments
+            })?;
+
+        response.error_for_status_code().map_err(|error| {
+            tracing::error!(?error, "failed to upgrade search documents (response)");
+            OpenSearchSearchError::UpgradeDocuments
+        })?;
+
+        Ok(())
+    }
+
     /// Collect all records for the provided `item_id`
+    /// Map the hits from a search response into flattened results, outdated
+    /// documents within the hits are lazily upgraded in the background
+    fn map_search_hits(&self, hits: Vec<SearchResponseHit>) -> Vec<FlattenedItemResult> {
+        // Lazily upgrade any outdated documents that were found in the background
+        let outdated_item_ids: Vec<Uuid> = hits
+            .iter()
+            .filter(|item| item._source.schema_version < SEARCH_SCHEMA_VERSION)
+            .map(|item| item._source.item_id)
+            .collect();
+
+        if !outdated_item_ids.is_empty() {
+            let index = self.clone();
+            tokio::spawn(async move {
+                if let Err(error) = index.upgrade_documents(Some(&outdated_item_ids)).await {
+                    tracing::error!(?error, "failed to lazily upgrade search documents");
+                }
+            });
+        }
+
+        const NAME_MATCH_KEYS: [&str; 2] = ["name_match_exact", "name_match_wildcard"];
+
+        hits.into_iter()
+            .map(|item| {
+                let (total_hits, page_matches) = match item.inner_hits {
+                    Some(inner_hits) => {
+                        let total_hits = inner_hits.pages.hits.total.value;
+                        let page_matches: Vec<PageResult> = inner_hits
+                            .pages
+                            .hits
+                            .hits
+                            .into_iter()
+                            .map(|value| PageResult {
+                                page: value._source.page,
+                                matches: value.highlight.content,
+                            })
+                            .collect();
+                        (total_hits, page_matches)
+                    }
+                    None => (0, vec![]),
+                };
+
+                let summary_match = item
+                    .matched_queries
+                    .as_ref()
+                    .is_some_and(|matches| matches.iter().any(|value| value == "summary_match"));
+                let name_match = item.matched_queries.is_some_and(|matches| {
+                    matches
+                        .iter()
+                        .any(|value| NAME_MATCH_KEYS.contains(&value.as_str()))
+                });
+                let content_match = !page_matches.is_empty() || summary_match;
+
+                FlattenedItemResult {
+                    item_ty: item._source.item_type,
+                    item_id: item._source.item_id,
+                    document_box: item._source.document_box,
+                    score: SearchScore::Float(item._score),
+                    page_matches,
+                    total_hits,
+                    name_match,
+                    content_match,
+                    explanation: item._explanation,
+                }
+            })
+            .collect()
+    }
+
+    /// Release a scroll context, failing to release the context is only
+    /// logged as it will expire on its own after [SCROLL_KEEP_ALIVE]
+    async fn clear_scroll(&self, scroll_id: &str) {
+        let result = self
+            .client
+            .clear_scroll(ClearScrollParts::None)
+            .body(json!({ "scroll_id": [scroll_id] }))
+            .send()
+            .await;
+
+        if let Err(error) = result {
+            tracing::warn!(?error, "failed to clear search scroll context");
+        }
+    }
+
     async fn get_by_item_id(&self, item_id: Uuid) -> Result<Vec<String>, OpenSearchSearchError> {
         #[derive(Debug, Deserialize, Serialize)]
         struct Response {
@@ -666,6 +1078,85 @@ struct DateRange {
     lte: Option<String>,
 }
 
+/// Create the terms aggregations counting the values of the facet `fields`
+fn create_facet_aggregations(fields: &[SearchFacetField]) -> serde_json::Value {
+    serde_json::Value::Object(
+        fields
+            .iter()
+            .map(|field| {
+                (
+                    field.field_name().to_string(),
+                    json!({
+                        "terms": {
+                            "field": field.field_name(),
+                            "size": MAX_FACET_BUCKETS
+                        }
+                    }),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Map the terms `aggregations` from a search response into the facets for
+/// the requested `fields`
+fn map_facet_aggregations(
+    fields: &[SearchFacetField],
+    mut aggregations: HashMap<String, TermsAggregation>,
+) -> Vec<SearchFacet> {
+    fields
+        .iter()
+        .map(|field| SearchFacet {
+            field: *field,
+            buckets: aggregations
+                .remove(field.field_name())
+                .map(|aggregation| {
+                    aggregation
+                        .buckets
+                        .into_iter()
+                        .map(|bucket| SearchFacetBucket {
+                            value: bucket.key,
+                            count: bucket.doc_count,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Create a query matching the names of items within `scopes` that start
+/// with the words of the `query`
+pub fn create_opensearch_suggest_query(
+    query: &str,
+    scopes: &[DocumentBoxScopeRaw],
+    size: u16,
+) -> serde_json::Value {
+    json!({
+        "size": size,
+        "_source": ["item_id", "item_type", "document_box", "name"],
+        "query": {
+            "bool": {
+                "filter": [
+                    { "terms": { "document_box": scopes } }
+                ],
+                "must": [
+                    {
+                        // Names are indexed as edge n-grams so matching every
+                        // query term gives prefix completions
+                        "match": {
+                            "name": {
+                                "query": query,
+                                "operator": "and"
+                            }
+                        }
+                    }
+                ]
+            }
+        }
+    })
+}
+
 pub fn create_opensearch_query(
     req: SearchRequest,
     scopes: &[DocumentBoxScopeRaw],
@@ -721,6 +1212,19 @@ pub fn create_opensearch_query(
                 }
             }));
 
+            // Match the generated summary, boosted as the summary is a
+            // condensed description of the entire document
+            should.push(json!({
+                "match": {
+                    "summary": {
+                        "query": query,
+                        "boost": 2,
+                        // Name the match for scoring later
+                        "_name": "summary_match"
+                    },
+                }
+            }));
+
             // Match content pages
             should.push(json!({
                 "nested": {
@@ -797,6 +1301,16 @@ pub fn create_opensearch_query(
         }));
     }
 
+    if let Some(pinned) = req.pinned {
+        filters.push(json!({
+            "term": { "pinned": pinned }
+        }));
+    }
+
+    if let Some(ref advanced) = req.advanced {
+        filters.push(create_advanced_query(advanced));
+    }
+
     // When a "should" is provided we must at least match one part of it
     let minimum_should_match = if !should.is_empty() { 1 } else { 0 };
 
@@ -833,6 +1347,56 @@ pub fn create_opensearch_query(
     })
 }
 
+/// Compile an advanced query into an opensearch query clause
+fn create_advanced_query(query: &AdvancedSearchQuery) -> serde_json::Value {
+    match query {
+        AdvancedSearchQuery::And { queries } => {
+            let queries: Vec<_> = queries.iter().map(create_advanced_query).collect();
+            json!({ "bool": { "filter": queries } })
+        }
+        AdvancedSearchQuery::Or { queries } => {
+            let queries: Vec<_> = queries.iter().map(create_advanced_query).collect();
+            json!({ "bool": { "should": queries, "minimum_should_match": 1 } })
+        }
+        AdvancedSearchQuery::Not { query } => {
+            json!({ "bool": { "must_not": [create_advanced_query(query)] } })
+        }
+        AdvancedSearchQuery::NameContains { value } => {
+            // Escape the wildcard characters within the value itself
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('*', "\\*")
+                .replace('?', "\\?");
+
+            json!({
+                "wildcard": {
+                    "name": {
+                        "value": format!("*{value}*"),
+                        "case_insensitive": true
+                    }
+                }
+            })
+        }
+        AdvancedSearchQuery::MimeIn { values } => json!({
+            "terms": { "mime": values }
+        }),
+        AdvancedSearchQuery::CreatedBetween { start, end } => json!({
+            "range": {
+                "created_at": DateRange {
+                    gte: start.map(|value| value.to_rfc3339()),
+                    lte: end.map(|value| value.to_rfc3339()),
+                }
+            }
+        }),
+        AdvancedSearchQuery::CreatedByEquals { value } => json!({
+            "term": { "created_by": value }
+        }),
+        AdvancedSearchQuery::PinnedEquals { value } => json!({
+            "term": { "pinned": value }
+        }),
+    }
+}
+
 pub fn create_opensearch_file_query(
     req: FileSearchRequest,
     scope: &DocumentBoxScopeRaw,
@@ -1,6 +1,9 @@
-use docbox_database::models::{document_box::DocumentBoxScopeRaw, folder::FolderId, user::UserId};
+use docbox_database::models::{
+    document_box::DocumentBoxScopeRaw, file_text_stats::TextStats, folder::FolderId, user::UserId,
+};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::models::{DocumentPage, SearchIndexType};
@@ -36,8 +39,18 @@ pub struct OsSearchIndexData {
     pub created_at: String,
     /// User who created the item
     pub created_by: Option<UserId>,
+    /// Whether the item is pinned
+    #[serde(default)]
+    pub pinned: bool,
     /// Optional pages of document content
     pub pages: Option<Vec<DocumentPage>>,
+    /// Optional generated summary of the document content
+    pub summary: Option<String>,
+    /// Word count, character count and reading time of the document content
+    #[serde(default)]
+    pub text_stats: Option<TextStats>,
+    /// Version of the document schema the document was written with
+    pub schema_version: i32,
 }
 
 #[skip_serializing_none]
@@ -45,13 +58,37 @@ pub struct OsSearchIndexData {
 pub struct OsUpdateSearchIndexData {
     pub folder_id: FolderId,
     pub name: String,
+    pub pinned: bool,
     pub content: Option<String>,
     pub pages: Option<Vec<DocumentPage>>,
+    pub document_box: Option<DocumentBoxScopeRaw>,
+    /// Updated documents are upgraded to the current schema version
+    pub schema_version: i32,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SearchResponse {
+    /// Whether the search timed out before all shards responded
+    #[serde(default)]
+    pub timed_out: bool,
     pub hits: Hits<SearchResponseHit>,
+    /// ID of the scroll context, only present for scrolled searches
+    #[serde(default)]
+    pub _scroll_id: Option<String>,
+    /// Facet aggregations by field name, only present when facets were requested
+    #[serde(default)]
+    pub aggregations: HashMap<String, TermsAggregation>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TermsAggregation {
+    pub buckets: Vec<TermsAggregationBucket>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TermsAggregationBucket {
+    pub key: String,
+    pub doc_count: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -72,6 +109,9 @@ pub struct SearchResponseHit {
     pub _source: SearchResponseHitSource,
     pub inner_hits: Option<InnerHits>,
     pub matched_queries: Option<Vec<String>>,
+    /// Scoring explanation, only present when requested
+    #[serde(default)]
+    pub _explanation: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -79,6 +119,27 @@ pub struct SearchResponseHitSource {
     pub item_id: Uuid,
     pub item_type: SearchIndexType,
     pub document_box: DocumentBoxScopeRaw,
+    /// Documents indexed before schema versioning have no version
+    #[serde(default)]
+    pub schema_version: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestResponse {
+    pub hits: Hits<SuggestResponseHit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestResponseHit {
+    pub _source: SuggestResponseHitSource,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestResponseHitSource {
+    pub item_id: Uuid,
+    pub item_type: SearchIndexType,
+    pub document_box: DocumentBoxScopeRaw,
+    pub name: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -0,0 +1,600 @@
+//! # Query DSL
+//!
+//! Structured query syntax for searches, parsed into an [AdvancedSearchQuery]
+//! which each search backend compiles into its native query
+//!
+//! ```text
+//! name:report AND mime:application/pdf NOT created_by:bob
+//! (name:invoice OR name:receipt) pinned:true created:2024-01-01..
+//! ```
+//!
+//! - Terms are combined using `AND`, `OR` and `NOT` (or a leading `-`),
+//!   terms without an operator between them are combined using `AND`
+//! - `NOT` binds tighter than `AND` which binds tighter than `OR`,
+//!   parentheses can be used to group terms
+//! - Values containing spaces or parentheses can be quoted (`name:"annual report"`)
+//! - Terms without a field match against the item name
+//!
+//! ## Fields
+//!
+//! * `name` - Item name contains the value (case-insensitive)
+//! * `mime` - Item mime type is one of the comma separated values
+//! * `created_by` - Item was created by the user
+//! * `pinned` - Item pinned state is `true` or `false`
+//! * `created` - Item was created on the date or within the `start..end` range,
+//!   dates are either `YYYY-MM-DD` or RFC 3339 timestamps and either end of the
+//!   range can be omitted
+
+use crate::{
+    models::{AdvancedSearchQuery, SearchRequest},
+    validation::MAX_ADVANCED_QUERY_DEPTH,
+};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use std::{iter::Peekable, str::CharIndices};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QueryDslError {
+    #[error("query is empty")]
+    Empty,
+
+    #[error("unknown field \"{0}\"")]
+    UnknownField(String),
+
+    #[error("missing value for field \"{0}\"")]
+    MissingValue(String),
+
+    #[error("invalid value \"{value}\" for field \"{field}\"")]
+    InvalidValue { field: String, value: String },
+
+    #[error("unterminated quoted value")]
+    UnterminatedQuote,
+
+    #[error("unexpected {0}")]
+    UnexpectedToken(String),
+
+    #[error("unexpected end of query")]
+    UnexpectedEnd,
+
+    #[error("query exceeds the maximum nesting depth of {MAX_ADVANCED_QUERY_DEPTH}")]
+    TooDeep,
+}
+
+/// Parse the `input` query into an [AdvancedSearchQuery]
+pub fn parse_query_dsl(input: &str) -> Result<AdvancedSearchQuery, QueryDslError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err(QueryDslError::Empty);
+    }
+
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        depth: 0,
+    };
+    let query = parser.parse_or()?;
+
+    match parser.next() {
+        Some(token) => Err(QueryDslError::UnexpectedToken(token.to_string())),
+        None => Ok(query),
+    }
+}
+
+/// Parse the [SearchRequest::query_dsl] of the `request` and combine it with
+/// the [SearchRequest::advanced] query, search backends only handle the
+/// advanced query
+///
+/// Called by [validate_search_request](crate::validation::validate_search_request)
+/// so the combined query is what gets validated, requests that skip
+/// validation must call this directly
+pub fn apply_query_dsl(request: &mut SearchRequest) -> Result<(), QueryDslError> {
+    let Some(query_dsl) = request.query_dsl.take() else {
+        return Ok(());
+    };
+
+    let query = parse_query_dsl(&query_dsl)?;
+
+    request.advanced = Some(match request.advanced.take() {
+        Some(advanced) => AdvancedSearchQuery::And {
+            queries: vec![advanced, query],
+        },
+        None => query,
+    });
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    OpenParen,
+    CloseParen,
+    Term {
+        field: Option<String>,
+        value: String,
+    },
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::And => f.write_str("\"AND\""),
+            Token::Or => f.write_str("\"OR\""),
+            Token::Not => f.write_str("\"NOT\""),
+            Token::OpenParen => f.write_str("\"(\""),
+            Token::CloseParen => f.write_str("\")\""),
+            Token::Term {
+                field: Some(field),
+                value,
+            } => write!(f, "\"{field}:{value}\""),
+            Token::Term { field: None, value } => write!(f, "\"{value}\""),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryDslError> {
+    let mut chars = input.char_indices().peekable();
+    let mut tokens = Vec::new();
+
+    while let Some(&(_, char)) = chars.peek() {
+        match char {
+            char if char.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::OpenParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::CloseParen);
+            }
+            '-' => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            '"' => {
+                let value = read_quoted(&mut chars)?;
+                tokens.push(Token::Term { field: None, value });
+            }
+            _ => tokens.push(read_term(&mut chars)?),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Read a term, operator keyword or `field:value` pair
+fn read_term(chars: &mut Peekable<CharIndices<'_>>) -> Result<Token, QueryDslError> {
+    let mut word = String::new();
+
+    while let Some(&(_, char)) = chars.peek() {
+        if is_term_end(char) || char == ':' || char == '"' {
+            break;
+        }
+
+        word.push(char);
+        chars.next();
+    }
+
+    if chars.next_if(|&(_, char)| char == ':').is_none() {
+        return Ok(match word.as_str() {
+            "AND" => Token::And,
+            "OR" => Token::Or,
+            "NOT" => Token::Not,
+            _ => Token::Term {
+                field: None,
+                value: word,
+            },
+        });
+    }
+
+    let value = match chars.peek() {
+        Some((_, '"')) => read_quoted(chars)?,
+        _ => {
+            let mut value = String::new();
+            while let Some((_, char)) = chars.next_if(|&(_, char)| !is_term_end(char)) {
+                value.push(char);
+            }
+            value
+        }
+    };
+
+    if value.is_empty() {
+        return Err(QueryDslError::MissingValue(word));
+    }
+
+    Ok(Token::Term {
+        field: Some(word),
+        value,
+    })
+}
+
+/// Read a quoted value, quotes within the value can be escaped with `\`
+fn read_quoted(chars: &mut Peekable<CharIndices<'_>>) -> Result<String, QueryDslError> {
+    // Skip the opening quote
+    chars.next();
+
+    let mut value = String::new();
+
+    loop {
+        match chars.next() {
+            Some((_, '"')) => return Ok(value),
+            Some((_, '\\')) => match chars.next() {
+                Some((_, char)) => value.push(char),
+                None => return Err(QueryDslError::UnterminatedQuote),
+            },
+            Some((_, char)) => value.push(char),
+            None => return Err(QueryDslError::UnterminatedQuote),
+        }
+    }
+}
+
+fn is_term_end(char: char) -> bool {
+    char.is_whitespace() || char == '(' || char == ')'
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    /// Number of groups and negations the parser is currently within
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Parse a nested query using `parse`, nesting is bounded while parsing
+    /// so deeply nested input is rejected before it can exhaust the stack
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, QueryDslError>,
+    ) -> Result<T, QueryDslError> {
+        if self.depth >= MAX_ADVANCED_QUERY_DEPTH {
+            return Err(QueryDslError::TooDeep);
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_or(&mut self) -> Result<AdvancedSearchQuery, QueryDslError> {
+        let mut queries = vec![self.parse_and()?];
+
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            queries.push(self.parse_and()?);
+        }
+
+        Ok(combine(queries, |queries| AdvancedSearchQuery::Or {
+            queries,
+        }))
+    }
+
+    fn parse_and(&mut self) -> Result<AdvancedSearchQuery, QueryDslError> {
+        let mut queries = vec![self.parse_not()?];
+
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                // Adjacent terms are implicitly combined using AND
+                Some(Token::Not | Token::OpenParen | Token::Term { .. }) => {}
+                _ => break,
+            }
+
+            queries.push(self.parse_not()?);
+        }
+
+        Ok(combine(queries, |queries| AdvancedSearchQuery::And {
+            queries,
+        }))
+    }
+
+    fn parse_not(&mut self) -> Result<AdvancedSearchQuery, QueryDslError> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            let query = self.nested(Self::parse_not)?;
+            return Ok(AdvancedSearchQuery::Not {
+                query: Box::new(query),
+            });
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<AdvancedSearchQuery, QueryDslError> {
+        match self.next() {
+            Some(Token::OpenParen) => {
+                let query = self.nested(Self::parse_or)?;
+                match self.next() {
+                    Some(Token::CloseParen) => Ok(query),
+                    Some(token) => Err(QueryDslError::UnexpectedToken(token.to_string())),
+                    None => Err(QueryDslError::UnexpectedEnd),
+                }
+            }
+            Some(Token::Term { field, value }) => create_field_query(field, value),
+            Some(token) => Err(QueryDslError::UnexpectedToken(token.to_string())),
+            None => Err(QueryDslError::UnexpectedEnd),
+        }
+    }
+}
+
+/// Combine multiple queries using `create`, single queries are used as is
+fn combine(
+    mut queries: Vec<AdvancedSearchQuery>,
+    create: impl FnOnce(Vec<AdvancedSearchQuery>) -> AdvancedSearchQuery,
+) -> AdvancedSearchQuery {
+    if queries.len() == 1 {
+        return queries.remove(0);
+    }
+
+    create(queries)
+}
+
+fn create_field_query(
+    field: Option<String>,
+    value: String,
+) -> Result<AdvancedSearchQuery, QueryDslError> {
+    let Some(field) = field else {
+        return Ok(AdvancedSearchQuery::NameContains { value });
+    };
+
+    let invalid_value = |field: String, value: String| QueryDslError::InvalidValue { field, value };
+
+    match field.as_str() {
+        "name" => Ok(AdvancedSearchQuery::NameContains { value }),
+        "mime" => {
+            let values: Vec<String> = value
+                .split(',')
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .collect();
+
+            if values.is_empty() {
+                return Err(invalid_value(field, value));
+            }
+
+            Ok(AdvancedSearchQuery::MimeIn { values })
+        }
+        "created_by" => Ok(AdvancedSearchQuery::CreatedByEquals { value }),
+        "pinned" => match value.as_str() {
+            "true" => Ok(AdvancedSearchQuery::PinnedEquals { value: true }),
+            "false" => Ok(AdvancedSearchQuery::PinnedEquals { value: false }),
+            _ => Err(invalid_value(field, value)),
+        },
+        "created" => {
+            let (start, end) = match value.split_once("..") {
+                Some((start, end)) => (parse_date_bound(start, false), parse_date_bound(end, true)),
+                // Single date matches the whole day or the exact timestamp
+                None => (
+                    parse_date_bound(&value, false),
+                    parse_date_bound(&value, true),
+                ),
+            };
+
+            match (start, end) {
+                (Ok(start), Ok(end)) if start.is_some() || end.is_some() => {
+                    Ok(AdvancedSearchQuery::CreatedBetween { start, end })
+                }
+                _ => Err(invalid_value(field, value)),
+            }
+        }
+        _ => Err(QueryDslError::UnknownField(field)),
+    }
+}
+
+/// Parse one end of a date range, dates without a time are extended to the
+/// start of the day for the start of a range and end of the day for the end
+fn parse_date_bound(value: &str, end: bool) -> Result<Option<DateTime<Utc>>, ()> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let time = if end {
+            NaiveTime::from_hms_milli_opt(23, 59, 59, 999).ok_or(())?
+        } else {
+            NaiveTime::MIN
+        };
+
+        return Ok(Some(date.and_time(time).and_utc()));
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .map(|value| Some(value.with_timezone(&Utc)))
+        .map_err(|_| ())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{QueryDslError, apply_query_dsl, parse_query_dsl};
+    use crate::models::{AdvancedSearchQuery, SearchRequest};
+    use crate::validation::MAX_ADVANCED_QUERY_DEPTH;
+    use chrono::{TimeZone, Utc};
+
+    fn name(value: &str) -> AdvancedSearchQuery {
+        AdvancedSearchQuery::NameContains {
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_operators() {
+        let query =
+            parse_query_dsl("name:report AND mime:application/pdf NOT created_by:bob").unwrap();
+
+        assert_eq!(
+            query,
+            AdvancedSearchQuery::And {
+                queries: vec![
+                    name("report"),
+                    AdvancedSearchQuery::MimeIn {
+                        values: vec!["application/pdf".to_string()]
+                    },
+                    AdvancedSearchQuery::Not {
+                        query: Box::new(AdvancedSearchQuery::CreatedByEquals {
+                            value: "bob".to_string()
+                        })
+                    },
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_precedence_and_grouping() {
+        // AND binds tighter than OR
+        let query = parse_query_dsl("a OR b c").unwrap();
+        assert_eq!(
+            query,
+            AdvancedSearchQuery::Or {
+                queries: vec![
+                    name("a"),
+                    AdvancedSearchQuery::And {
+                        queries: vec![name("b"), name("c")]
+                    }
+                ]
+            }
+        );
+
+        let query = parse_query_dsl("(a OR b) -c").unwrap();
+        assert_eq!(
+            query,
+            AdvancedSearchQuery::And {
+                queries: vec![
+                    AdvancedSearchQuery::Or {
+                        queries: vec![name("a"), name("b")]
+                    },
+                    AdvancedSearchQuery::Not {
+                        query: Box::new(name("c"))
+                    }
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_field_values() {
+        assert_eq!(
+            parse_query_dsl(r#"name:"annual \"report\"""#).unwrap(),
+            name(r#"annual "report""#)
+        );
+        assert_eq!(
+            parse_query_dsl("mime:text/plain,application/pdf").unwrap(),
+            AdvancedSearchQuery::MimeIn {
+                values: vec!["text/plain".to_string(), "application/pdf".to_string()]
+            }
+        );
+        assert_eq!(
+            parse_query_dsl("pinned:false").unwrap(),
+            AdvancedSearchQuery::PinnedEquals { value: false }
+        );
+        assert_eq!(
+            parse_query_dsl("created:2024-01-01..").unwrap(),
+            AdvancedSearchQuery::CreatedBetween {
+                start: Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()),
+                end: None,
+            }
+        );
+        assert_eq!(
+            parse_query_dsl("created:..2024-01-01T10:00:00Z").unwrap(),
+            AdvancedSearchQuery::CreatedBetween {
+                start: None,
+                end: Some(Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_query_dsl("  "), Err(QueryDslError::Empty));
+        assert_eq!(
+            parse_query_dsl("owner:bob"),
+            Err(QueryDslError::UnknownField("owner".to_string()))
+        );
+        assert_eq!(
+            parse_query_dsl("pinned:maybe"),
+            Err(QueryDslError::InvalidValue {
+                field: "pinned".to_string(),
+                value: "maybe".to_string()
+            })
+        );
+        assert_eq!(
+            parse_query_dsl("created:.."),
+            Err(QueryDslError::InvalidValue {
+                field: "created".to_string(),
+                value: "..".to_string()
+            })
+        );
+        assert_eq!(
+            parse_query_dsl("name:"),
+            Err(QueryDslError::MissingValue("name".to_string()))
+        );
+        assert_eq!(
+            parse_query_dsl(r#"name:"report"#),
+            Err(QueryDslError::UnterminatedQuote)
+        );
+        assert_eq!(
+            parse_query_dsl("(a OR b"),
+            Err(QueryDslError::UnexpectedEnd)
+        );
+        assert_eq!(parse_query_dsl("a AND"), Err(QueryDslError::UnexpectedEnd));
+        assert_eq!(
+            parse_query_dsl("a)"),
+            Err(QueryDslError::UnexpectedToken("\")\"".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_nesting_bounded() {
+        let nested = |depth: usize| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert!(parse_query_dsl(&nested(MAX_ADVANCED_QUERY_DEPTH)).is_ok());
+        assert_eq!(
+            parse_query_dsl(&nested(MAX_ADVANCED_QUERY_DEPTH + 1)),
+            Err(QueryDslError::TooDeep)
+        );
+        assert_eq!(parse_query_dsl(&nested(512)), Err(QueryDslError::TooDeep));
+        assert_eq!(
+            parse_query_dsl(&"NOT ".repeat(256)),
+            Err(QueryDslError::TooDeep)
+        );
+    }
+
+    #[test]
+    fn test_apply_query_dsl_combines_advanced() {
+        let mut request = SearchRequest {
+            query_dsl: Some("pinned:true".to_string()),
+            advanced: Some(name("report")),
+            ..Default::default()
+        };
+
+        apply_query_dsl(&mut request).unwrap();
+
+        assert!(request.query_dsl.is_none());
+        assert_eq!(
+            request.advanced,
+            Some(AdvancedSearchQuery::And {
+                queries: vec![
+                    name("report"),
+                    AdvancedSearchQuery::PinnedEquals { value: true }
+                ]
+            })
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use crate::language::UnknownSearchLanguage;
+use std::num::ParseIntError;
+use thiserror::Error;
+use tokio::task::JoinError;
+
+#[derive(Debug, Error)]
+pub enum TantivyIndexFactoryError {
+    #[error("missing DOCBOX_TANTIVY_PATH env")]
+    MissingPath,
+    #[error("DOCBOX_TANTIVY_WRITER_MEMORY must be a number in bytes")]
+    InvalidWriterMemory(ParseIntError),
+    #[error("failed to create tantivy index directory")]
+    CreateDirectory(std::io::Error),
+    #[error(transparent)]
+    InvalidLanguage(#[from] UnknownSearchLanguage),
+}
+
+#[derive(Debug, Error)]
+pub enum TantivySearchError {
+    #[error("search index not found")]
+    IndexNotFound,
+    #[error("search index name is not a valid directory name")]
+    InvalidIndexName,
+    #[error("failed to create index")]
+    CreateIndex(::tantivy::TantivyError),
+    #[error("failed to open index")]
+    OpenIndex(::tantivy::TantivyError),
+    #[error("failed to delete index")]
+    DeleteIndex(std::io::Error),
+    #[error("failed to search index")]
+    SearchIndex(::tantivy::TantivyError),
+    #[error("failed to write search data")]
+    WriteIndex(::tantivy::TantivyError),
+    #[error("error waiting for search index operation")]
+    Threading(#[from] JoinError),
+    #[error("migration not found")]
+    MigrationNotFound,
+}
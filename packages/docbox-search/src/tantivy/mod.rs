@@ -0,0 +1,1373 @@
+//! # Tantivy
+//!
+//! Embedded search backend using [Tantivy](https://github.com/quickwit-oss/tantivy),
+//! each tenant has its own index stored on local disk within a directory named
+//! after the tenant search index. Intended for single instance deployments that
+//! don't want to run a separate search service.
+//!
+//! Items and each of their pages are stored as separate documents, see the
+//! [schema] module for details. Page matches are highlighted with `<em>` tags
+//! the same as the other backends.
+//!
+//! Only a single process can write to an index, the index directory must not be
+//! shared between multiple docbox instances.
+//!
+//! ## Environment Variables
+//!
+//! * `DOCBOX_TANTIVY_PATH` - Directory to store the tenant indexes within
+//! * `DOCBOX_TANTIVY_WRITER_MEMORY` - Memory budget in bytes for each index writer (Default: 50MB, Minimum: 15MB)
+
+use crate::{
+    DEFAULT_SUGGEST_SIZE, SearchError, SearchIndex,
+    language::SearchLanguage,
+    models::{
+        FileSearchRequest, FileSearchResults, FlattenedItemResult, PageResult, SearchExplain,
+        SearchFacet, SearchIndexData, SearchRequest, SearchResults, SearchScore, SearchSuggestion,
+        SuggestRequest, SuggestResults, UpdateSearchIndexData,
+    },
+};
+use ::tantivy::{
+    DocAddress, Index, IndexReader, IndexWriter, ReloadPolicy, Searcher, TantivyDocument, Term,
+    collector::{Count, DocSetCollector, TopDocs},
+    query::{AllQuery, Query, TermQuery},
+    schema::IndexRecordOption,
+    snippet::SnippetGenerator,
+};
+use docbox_database::{
+    DbTransaction,
+    models::{
+        document_box::{DocumentBoxScopeRaw, DocumentBoxScopeRawRef},
+        file::FileId,
+        folder::FolderId,
+        tenant::Tenant,
+    },
+};
+use schema::{KIND_ITEM, KIND_PAGE, TantivyFields};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+pub use error::{TantivyIndexFactoryError, TantivySearchError};
+
+pub mod error;
+mod query;
+mod schema;
+
+/// Migrations to apply against the index, indexes are created with the
+/// complete schema so migrations are only needed for future schema changes
+const TANTIVY_MIGRATIONS: &[&str] = &[];
+
+/// Default memory budget for each index writer
+const DEFAULT_WRITER_MEMORY_BYTES: usize = 50_000_000;
+
+/// File tantivy creates within the index directory to store the index metadata
+const META_FILE_NAME: &str = "meta.json";
+
+/// Maximum number of candidate documents collected for each part of a search,
+/// items are merged from the candidates before pagination
+const MAX_CANDIDATES: usize = 10_000;
+
+/// Maximum number of characters in a page highlight
+const HIGHLIGHT_MAX_CHARS: usize = 150;
+
+/// Collection of opened indexes by index name
+type TantivyIndexes = HashMap<String, Arc<TantivyIndexHandle>>;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TantivySearchConfig {
+    /// Directory to store the tenant indexes within
+    pub path: PathBuf,
+
+    /// Memory budget in bytes for each index writer
+    #[serde(default)]
+    pub writer_memory_bytes: Option<usize>,
+
+    /// Data residency region the search index is located within (i.e "eu-west"),
+    /// tenants assigned to a region can only use search within that region
+    #[serde(default)]
+    pub data_region: Option<String>,
+
+    /// Default language used to stem the indexed text content
+    #[serde(default)]
+    pub language: Option<SearchLanguage>,
+}
+
+impl TantivySearchConfig {
+    pub fn from_env() -> Result<Self, TantivyIndexFactoryError> {
+        let path = std::env::var("DOCBOX_TANTIVY_PATH")
+            .map_err(|_| TantivyIndexFactoryError::MissingPath)?;
+        let writer_memory_bytes = std::env::var("DOCBOX_TANTIVY_WRITER_MEMORY")
+            .ok()
+            .map(|value| value.parse::<usize>())
+            .transpose()
+            .map_err(TantivyIndexFactoryError::InvalidWriterMemory)?;
+        let data_region = std::env::var("DOCBOX_SEARCH_DATA_REGION").ok();
+        let language = SearchLanguage::from_env()?;
+
+        Ok(Self {
+            path: PathBuf::from(path),
+            writer_memory_bytes,
+            data_region,
+            language,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct TantivyIndexFactory {
+    /// Directory the tenant indexes are stored within
+    root: Arc<PathBuf>,
+    /// Memory budget for each index writer
+    writer_memory_bytes: usize,
+    /// Indexes that are currently open, shared between all the search indexes
+    /// so each index only has a single writer
+    indexes: Arc<Mutex<TantivyIndexes>>,
+    data_region: Option<String>,
+    language: Option<SearchLanguage>,
+}
+
+impl TantivyIndexFactory {
+    pub fn from_config(config: TantivySearchConfig) -> Result<Self, TantivyIndexFactoryError> {
+        std::fs::create_dir_all(&config.path).map_err(|error| {
+            tracing::error!(?error, path = %config.path.display(), "failed to create tantivy index directory");
+            TantivyIndexFactoryError::CreateDirectory(error)
+        })?;
+
+        Ok(Self {
+            root: Arc::new(config.path),
+            writer_memory_bytes: config
+                .writer_memory_bytes
+                .unwrap_or(DEFAULT_WRITER_MEMORY_BYTES),
+            indexes: Default::default(),
+            data_region: config.data_region,
+            language: config.language,
+        })
+    }
+
+    /// Data residency region the search index is located within
+    pub fn data_region(&self) -> Option<&str> {
+        self.data_region.as_deref()
+    }
+
+    /// Default language used to stem the indexed text content
+    pub fn language(&self) -> Option<SearchLanguage> {
+        self.language
+    }
+
+    /// Create a search index with the provided `index_name`
+    pub fn create_search_index(
+        &self,
+        index_name: String,
+        language: Option<SearchLanguage>,
+    ) -> TantivyIndex {
+        TantivyIndex {
+            index_name,
+            root: self.root.clone(),
+            writer_memory_bytes: self.writer_memory_bytes,
+            indexes: self.indexes.clone(),
+            language,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TantivyIndex {
+    /// Name of the index directory within the root
+    index_name: String,
+    root: Arc<PathBuf>,
+    writer_memory_bytes: usize,
+    indexes: Arc<Mutex<TantivyIndexes>>,
+    /// Language used to stem the full text fields
+    language: Option<SearchLanguage>,
+}
+
+/// Opened index along with its reader and writer
+struct TantivyIndexHandle {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    fields: TantivyFields,
+}
+
+/// Part of a search that an item was matched by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MatchKind {
+    /// No query was provided, matched by the filters alone
+    Filter,
+    Name,
+    Content,
+    Page,
+}
+
+/// Item merged from the hits of each part of a search
+struct ItemHit {
+    item_ty: crate::models::SearchIndexType,
+    item_id: Uuid,
+    document_box: DocumentBoxScopeRaw,
+    score: f32,
+    name_match: bool,
+    content_match: bool,
+    /// Address of the item document and the parts of the search it matched
+    item_matches: Vec<(MatchKind, DocAddress)>,
+    pages: Vec<PageHit>,
+    /// Values for each of the requested facet fields
+    facet_values: Vec<Option<String>>,
+}
+
+struct PageHit {
+    score: f32,
+    page: u64,
+    address: DocAddress,
+}
+
+impl TantivyIndex {
+    /// Path to the index directory, index names that could escape the root
+    /// directory are rejected
+    fn index_path(&self) -> Result<PathBuf, TantivySearchError> {
+        let name = self.index_name.as_str();
+        if name.is_empty()
+            || name == "."
+            || name == ".."
+            || name.contains(['/', '\\'])
+            || Path::new(name).is_absolute()
+        {
+            return Err(TantivySearchError::InvalidIndexName);
+        }
+
+        Ok(self.root.join(name))
+    }
+
+    /// Get the opened index, opening the index if it has not yet been opened.
+    /// Blocks while the index is opened
+    fn open(&self) -> Result<Arc<TantivyIndexHandle>, TantivySearchError> {
+        let mut indexes = self.indexes.lock().expect("tantivy index lock poisoned");
+        if let Some(handle) = indexes.get(&self.index_name) {
+            return Ok(handle.clone());
+        }
+
+        let path = self.index_path()?;
+        if !path.join(META_FILE_NAME).is_file() {
+            return Err(TantivySearchError::IndexNotFound);
+        }
+
+        let index = Index::open_in_dir(&path).map_err(TantivySearchError::OpenIndex)?;
+        let handle = Arc::new(
+            self.create_handle(index)
+                .map_err(TantivySearchError::OpenIndex)?,
+        );
+        indexes.insert(self.index_name.clone(), handle.clone());
+        Ok(handle)
+    }
+
+    fn create_handle(&self, index: Index) -> ::tantivy::Result<TantivyIndexHandle> {
+        let (_, fields) = TantivyFields::schema();
+        TantivyFields::register_tokenizers(&index, self.language);
+
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let writer = index.writer_with_num_threads(1, self.writer_memory_bytes)?;
+
+        Ok(TantivyIndexHandle {
+            index,
+            reader,
+            writer: Mutex::new(writer),
+            fields,
+        })
+    }
+
+    /// Run the blocking `action` against the opened index on the blocking
+    /// thread pool
+    async fn with_index<F, T>(&self, action: F) -> Result<T, SearchError>
+    where
+        F: FnOnce(&TantivyIndexHandle) -> Result<T, TantivySearchError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let index = self.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let handle = index.open()?;
+            action(&handle)
+        })
+        .await
+        .map_err(TantivySearchError::Threading)?;
+
+        Ok(result?)
+    }
+}
+
+impl SearchIndex for TantivyIndex {
+    async fn create_index(&self) -> Result<(), SearchError> {
+        let index = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let path = index.index_path()?;
+            std::fs::create_dir_all(&path)
+                .map_err(|error| TantivySearchError::CreateIndex(error.into()))?;
+
+            let (schema, _) = TantivyFields::schema();
+            let created = Index::create_in_dir(&path, schema).map_err(|error| {
+                tracing::error!(?error, "failed to create index");
+                TantivySearchError::CreateIndex(error)
+            })?;
+            let handle = index
+                .create_handle(created)
+                .map_err(TantivySearchError::CreateIndex)?;
+
+            index
+                .indexes
+                .lock()
+                .expect("tantivy index lock poisoned")
+                .insert(index.index_name.clone(), Arc::new(handle));
+
+            Ok::<_, TantivySearchError>(())
+        })
+        .await
+        .map_err(TantivySearchError::Threading)??;
+
+        Ok(())
+    }
+
+    async fn index_exists(&self) -> Result<bool, SearchError> {
+        let path = self.index_path()?;
+        let exists = tokio::fs::try_exists(path.join(META_FILE_NAME))
+            .await
+            .map_err(|error| TantivySearchError::OpenIndex(error.into()))?;
+        Ok(exists)
+    }
+
+    async fn recreate_index(&self) -> Result<(), SearchError> {
+        self.create_index().await
+    }
+
+    async fn delete_index(&self) -> Result<(), SearchError> {
+        let index = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let path = index.index_path()?;
+
+            // Close the index releasing the writer before removing the files
+            let handle = index
+                .indexes
+                .lock()
+                .expect("tantivy index lock poisoned")
+                .remove(&index.index_name);
+            drop(handle);
+
+            match std::fs::remove_dir_all(&path) {
+                Ok(_) => Ok(()),
+                // Index has already been deleted
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(error) => {
+                    tracing::error!(?error, "failed to delete index");
+                    Err(TantivySearchError::DeleteIndex(error))
+                }
+            }
+        })
+        .await
+        .map_err(TantivySearchError::Threading)??;
+
+        Ok(())
+    }
+
+    async fn search_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        query: SearchRequest,
+        folder_children: Option<Vec<FolderId>>,
+    ) -> Result<SearchResults, SearchError> {
+        let scope = scope.to_vec();
+        self.with_index(move |handle| handle.search(&scope, &query, folder_children.as_deref()))
+            .await
+    }
+
+    async fn suggest_index(
+        &self,
+        scope: &[DocumentBoxScopeRaw],
+        query: SuggestRequest,
+    ) -> Result<SuggestResults, SearchError> {
+        let scope = scope.to_vec();
+        self.with_index(move |handle| handle.suggest(&scope, &query))
+            .await
+    }
+
+    async fn search_index_file(
+        &self,
+        scope: &DocumentBoxScopeRaw,
+        file_id: FileId,
+        query: FileSearchRequest,
+    ) -> Result<FileSearchResults, SearchError> {
+        let scope = scope.clone();
+        self.with_index(move |handle| handle.search_file(&scope, file_id, &query))
+            .await
+    }
+
+    async fn add_data(&self, data: Vec<SearchIndexData>) -> Result<(), SearchError> {
+        self.with_index(move |handle| {
+            handle.write(|writer, fields| {
+                for data in &data {
+                    writer.delete_term(Term::from_field_text(
+                        fields.item_id,
+                        &data.item_id.to_string(),
+                    ));
+
+                    for document in fields.documents(data) {
+                        writer.add_document(document)?;
+                    }
+                }
+
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    async fn update_data(
+        &self,
+        item_id: Uuid,
+        data: UpdateSearchIndexData,
+    ) -> Result<(), SearchError> {
+        self.with_index(move |handle| {
+            // Tantivy has no partial updates, the stored documents are read
+            // back and rewritten with the changes applied
+            let Some(mut item) = handle.read_item(item_id)? else {
+                return Ok(());
+            };
+
+            item.folder_id = data.folder_id;
+            item.name = data.name;
+            item.pinned = data.pinned;
+            if let Some(content) = data.content {
+                item.content = Some(content);
+            }
+            if let Some(pages) = data.pages {
+                item.pages = Some(pages);
+            }
+            if let Some(document_box) = data.document_box {
+                item.document_box = document_box;
+            }
+
+            handle.write(|writer, fields| {
+                writer.delete_term(Term::from_field_text(fields.item_id, &item_id.to_string()));
+                for document in fields.documents(&item) {
+                    writer.add_document(document)?;
+                }
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    async fn delete_data(&self, id: Uuid) -> Result<(), SearchError> {
+        self.with_index(move |handle| {
+            handle.write(|writer, fields| {
+                writer.delete_term(Term::from_field_text(fields.item_id, &id.to_string()));
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    async fn delete_by_scope(&self, scope: DocumentBoxScopeRawRef<'_>) -> Result<(), SearchError> {
+        let scope = scope.to_string();
+        self.with_index(move |handle| {
+            handle.write(|writer, fields| {
+                writer.delete_term(Term::from_field_text(fields.document_box, &scope));
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    async fn get_pending_migrations(
+        &self,
+        applied_names: Vec<String>,
+    ) -> Result<Vec<String>, SearchError> {
+        Ok(TANTIVY_MIGRATIONS
+            .iter()
+            .filter(|migration_name| !applied_names.iter().any(|name| name.eq(*migration_name)))
+            .map(|migration_name| migration_name.to_string())
+            .collect())
+    }
+
+    async fn apply_migration(
+        &self,
+        _tenant: &Tenant,
+        _root_t: &mut DbTransaction<'_>,
+        _t: &mut DbTransaction<'_>,
+        _name: &str,
+    ) -> Result<(), SearchError> {
+        Err(TantivySearchError::MigrationNotFound.into())
+    }
+}
+
+impl TantivyIndexHandle {
+    /// Perform a write using the index writer, the changes are committed and
+    /// the reader is reloaded so the changes are immediately searchable
+    fn write<F>(&self, action: F) -> Result<(), TantivySearchError>
+    where
+        F: FnOnce(&mut IndexWriter, &TantivyFields) -> ::tantivy::Result<()>,
+    {
+        let mut writer = self.writer.lock().expect("tantivy writer lock poisoned");
+
+        let result = action(&mut writer, &self.fields).and_then(|_| writer.commit());
+        if let Err(error) = result {
+            tracing::error!(?error, "failed to write search data");
+
+            // Discard the partially written changes
+            if let Err(error) = writer.rollback() {
+                tracing::error!(?error, "failed to rollback search data");
+            }
+
+            return Err(TantivySearchError::WriteIndex(error));
+        }
+
+        self.reader.reload().map_err(TantivySearchError::WriteIndex)
+    }
+
+    /// Read back the stored item data for the item with the provided `item_id`
+    fn read_item(&self, item_id: Uuid) -> Result<Option<SearchIndexData>, TantivySearchError> {
+        let searcher = self.reader.searcher();
+        let query = TermQuery::new(
+            Term::from_field_text(self.fields.item_id, &item_id.to_string()),
+            IndexRecordOption::Basic,
+        );
+        let addresses = searcher
+            .search(&query, &DocSetCollector)
+            .map_err(TantivySearchError::SearchIndex)?;
+
+        let mut item = None;
+        let mut pages = Vec::new();
+
+        for address in addresses {
+            let document: TantivyDocument = searcher
+                .doc(address)
+                .map_err(TantivySearchError::SearchIndex)?;
+
+            match self.fields.read_page(&document) {
+                Some(page) => pages.push(page),
+                None => item = Some(document),
+            }
+        }
+
+        pages.sort_by_key(|page| page.page);
+
+        Ok(item.and_then(|item| self.fields.read_item(&item, pages)))
+    }
+
+    fn search(
+        &self,
+        scopes: &[DocumentBoxScopeRaw],
+        req: &SearchRequest,
+        folder_children: Option<&[FolderId]>,
+    ) -> Result<SearchResults, TantivySearchError> {
+        let fields = &self.fields;
+        let searcher = self.reader.searcher();
+        let query_text = req
+            .query
+            .as_deref()
+            .map(str::trim)
+            .filter(|query| !query.is_empty());
+
+        let item_filters =
+            || query::create_filters(fields, KIND_ITEM, req, scopes, folder_children);
+        let facet_fields = req.facet_fields();
+
+        // Page text query is kept separately for highlighting the page matches
+        let mut page_text_query: Option<Box<dyn Query>> = None;
+        let mut searches: Vec<(MatchKind, Box<dyn Query>)> = Vec::new();
+
+        match query_text {
+            None => {
+                searches.push((
+                    MatchKind::Filter,
+                    query::filtered_query(item_filters(), Box::new(AllQuery)),
+                ));
+            }
+            Some(query_text) => {
+                if req.include_name {
+                    searches.push((
+                        MatchKind::Name,
+                        query::filtered_query(
+                            item_filters(),
+                            query::create_name_query(fields, query_text),
+                        ),
+                    ));
+                }
+
+                if req.include_content {
+                    let content_query =
+                        query::create_content_query(&self.index, fields, query_text)
+                            .map_err(TantivySearchError::SearchIndex)?;
+                    searches.push((
+                        MatchKind::Content,
+                        query::filtered_query(item_filters(), content_query),
+                    ));
+
+                    let page_query =
+                        query::create_text_query(&self.index, fields.page_content, query_text)
+                            .map_err(TantivySearchError::SearchIndex)?;
+                    searches.push((
+                        MatchKind::Page,
+                        query::filtered_query(
+                            query::create_filters(fields, KIND_PAGE, req, scopes, folder_children),
+                            page_query.box_clone(),
+                        ),
+                    ));
+                    page_text_query = Some(page_query);
+                }
+            }
+        }
+
+        let explain = (req.explain || req.dry_run).then(|| SearchExplain {
+            backend: "tantivy".to_string(),
+            query: serde_json::Value::Object(
+                searches
+                    .iter()
+                    .map(|(kind, query)| {
+                        (
+                            format!("{kind:?}").to_lowercase(),
+                            json!(format!("{query:?}")),
+                        )
+                    })
+                    .collect(),
+            ),
+        });
+
+        if req.dry_run {
+            return Ok(SearchResults {
+                total_hits: 0,
+                results: Vec::new(),
+                timed_out: false,
+                explain,
+                facets: Vec::new(),
+            });
+        }
+
+        let mut items: HashMap<Uuid, ItemHit> = HashMap::new();
+
+        for (kind, query) in &searches {
+            let hits = searcher
+                .search(query.as_ref(), &TopDocs::with_limit(MAX_CANDIDATES))
+                .map_err(TantivySearchError::SearchIndex)?;
+
+            for (score, address) in hits {
+                let document: TantivyDocument = searcher
+                    .doc(address)
+                    .map_err(TantivySearchError::SearchIndex)?;
+
+                let (Some(item_id), Some(item_ty), Some(document_box)) = (
+                    fields.read_item_id(&document),
+                    fields.read_item_type(&document),
+                    fields.read_str(&document, fields.document_box),
+                ) else {
+                    tracing::warn!(?address, "skipping search document with missing fields");
+                    continue;
+                };
+
+                let item = items.entry(item_id).or_insert_with(|| ItemHit {
+                    item_ty,
+                    item_id,
+                    document_box: document_box.to_string(),
+                    score: 0.0,
+                    name_match: false,
+                    content_match: false,
+                    item_matches: Vec::new(),
+                    pages: Vec::new(),
+                    // Page documents hold the same field values as their item
+                    facet_values: facet_fields
+                        .iter()
+                        .map(|field| {
+                            fields
+                                .read_facet_value(&document, *field)
+                                .map(str::to_string)
+                        })
+                        .collect(),
+                });
+
+                item.score += score;
+
+                match kind {
+                    MatchKind::Filter => {}
+                    MatchKind::Name => item.name_match = true,
+                    MatchKind::Content => item.content_match = true,
+                    MatchKind::Page => {
+                        item.content_match = true;
+                        if let Some(page) = fields.read_page(&document) {
+                            item.pages.push(PageHit {
+                                score,
+                                page: page.page,
+                                address,
+                            });
+                        }
+                        continue;
+                    }
+                }
+
+                item.item_matches.push((*kind, address));
+            }
+        }
+
+        let mut items: Vec<ItemHit> = items.into_values().collect();
+        items.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.item_id.cmp(&b.item_id))
+        });
+
+        let facets = facet_fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let mut counts: HashMap<String, u64> = HashMap::new();
+                for value in items
+                    .iter()
+                    .filter_map(|item| item.facet_values[index].as_ref())
+                {
+                    *counts.entry(value.clone()).or_default() += 1;
+                }
+                SearchFacet::from_counts(*field, counts)
+            })
+            .collect();
+
+        let total_hits = items.len() as u64;
+        let offset = req.offset.unwrap_or(0) as usize;
+        let size = req.size.unwrap_or(50) as usize;
+        let max_pages = req.max_pages.unwrap_or(3) as usize;
+        let pages_offset = req.pages_offset.unwrap_or(0) as usize;
+
+        let snippet_generator = page_text_query
+            .as_ref()
+            .map(|page_query| {
+                let mut generator =
+                    SnippetGenerator::create(&searcher, page_query.as_ref(), fields.page_content)?;
+                generator.set_max_num_chars(HIGHLIGHT_MAX_CHARS);
+                Ok(generator)
+            })
+            .transpose()
+            .map_err(TantivySearchError::SearchIndex)?;
+
+        let results = items
+            .into_iter()
+            .skip(offset)
+            .take(size)
+            .map(|mut item| {
+                let explanation = req
+                    .explain
+                    .then(|| self.explain_item(&searcher, &searches, &item));
+
+                item.pages.sort_by(|a, b| {
+                    b.score
+                        .total_cmp(&a.score)
+                        .then_with(|| a.page.cmp(&b.page))
+                });
+
+                let total_hits = item.pages.len() as u64;
+                let page_matches = item
+                    .pages
+                    .iter()
+                    .skip(pages_offset)
+                    .take(max_pages)
+                    .map(|page| {
+                        let matches = match snippet_generator.as_ref() {
+                            Some(generator) => {
+                                self.highlight(&searcher, generator, page.address)?
+                            }
+                            None => Vec::new(),
+                        };
+
+                        Ok(PageResult {
+                            page: page.page,
+                            matches,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, TantivySearchError>>()?;
+
+                Ok(FlattenedItemResult {
+                    item_ty: item.item_ty,
+                    item_id: item.item_id,
+                    document_box: item.document_box,
+                    page_matches,
+                    total_hits,
+                    score: SearchScore::Float(item.score),
+                    name_match: item.name_match,
+                    content_match: item.content_match,
+                    explanation,
+                })
+            })
+            .collect::<Result<Vec<_>, TantivySearchError>>()?;
+
+        Ok(SearchResults {
+            results,
+            total_hits,
+            timed_out: false,
+            explain,
+            facets,
+        })
+    }
+
+    fn suggest(
+        &self,
+        scopes: &[DocumentBoxScopeRaw],
+        req: &SuggestRequest,
+    ) -> Result<SuggestResults, TantivySearchError> {
+        let fields = &self.fields;
+        let searcher = self.reader.searcher();
+        let size = req.size.unwrap_or(DEFAULT_SUGGEST_SIZE) as usize;
+        let query = query::create_suggest_query(fields, scopes, req.query.trim());
+
+        let hits = searcher
+            .search(query.as_ref(), &TopDocs::with_limit(MAX_CANDIDATES))
+            .map_err(TantivySearchError::SearchIndex)?;
+
+        let mut suggestions = Vec::with_capacity(hits.len());
+
+        for (score, address) in hits {
+            let document: TantivyDocument = searcher
+                .doc(address)
+                .map_err(TantivySearchError::SearchIndex)?;
+
+            let (Some(item_id), Some(item_ty), Some(document_box), Some(name)) = (
+                fields.read_item_id(&document),
+                fields.read_item_type(&document),
+                fields.read_str(&document, fields.document_box),
+                fields.read_str(&document, fields.name_raw),
+            ) else {
+                tracing::warn!(?address, "skipping suggest document with missing fields");
+                continue;
+            };
+
+            suggestions.push((
+                score,
+                SearchSuggestion {
+                    item_ty,
+                    item_id,
+                    document_box: document_box.to_string(),
+                    name: name.to_string(),
+                },
+            ));
+        }
+
+        // Names matching from the start first, then the shortest completions
+        suggestions.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .total_cmp(a_score)
+                .then_with(|| a.name.len().cmp(&b.name.len()))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Ok(SuggestResults {
+            suggestions: suggestions
+                .into_iter()
+                .take(size)
+                .map(|(_, suggestion)| suggestion)
+                .collect(),
+        })
+    }
+
+    fn search_file(
+        &self,
+        scope: &DocumentBoxScopeRaw,
+        file_id: FileId,
+        req: &FileSearchRequest,
+    ) -> Result<FileSearchResults, TantivySearchError> {
+        let fields = &self.fields;
+        let searcher = self.reader.searcher();
+
+        let page_query = match req
+            .query
+            .as_deref()
+            .map(str::trim)
+            .filter(|query| !query.is_empty())
+        {
+            Some(query_text) => {
+                query::create_text_query(&self.index, fields.page_content, query_text)
+                    .map_err(TantivySearchError::SearchIndex)?
+            }
+            None => Box::new(AllQuery),
+        };
+
+        let filters: Vec<Box<dyn Query>> = [
+            (fields.kind, KIND_PAGE.to_string()),
+            (fields.item_id, file_id.to_string()),
+            (fields.document_box, scope.to_string()),
+        ]
+        .into_iter()
+        .map(|(field, value)| {
+            Box::new(TermQuery::new(
+                Term::from_field_text(field, &value),
+                IndexRecordOption::Basic,
+            )) as Box<dyn Query>
+        })
+        .collect();
+        let query = query::filtered_query(filters, page_query.box_clone());
+
+        let offset = req.offset.unwrap_or(0) as usize;
+        let limit = req.limit.unwrap_or(3) as usize;
+
+        let (total_hits, hits) = searcher
+            .search(
+                query.as_ref(),
+                &(Count, TopDocs::with_limit(limit.max(1)).and_offset(offset)),
+            )
+            .map_err(TantivySearchError::SearchIndex)?;
+
+        let mut generator =
+            SnippetGenerator::create(&searcher, page_query.as_ref(), fields.page_content)
+                .map_err(TantivySearchError::SearchIndex)?;
+        generator.set_max_num_chars(HIGHLIGHT_MAX_CHARS);
+
+        let results = hits
+            .into_iter()
+            .take(limit)
+            .filter_map(|(_, address)| {
+                let document: TantivyDocument = match searcher.doc(address) {
+                    Ok(document) => document,
+                    Err(error) => return Some(Err(TantivySearchError::SearchIndex(error))),
+                };
+                let page = fields.read_page(&document)?;
+                let snippet = highlight_snippet(&generator, &document);
+                Some(Ok(PageResult {
+                    page: page.page,
+                    matches: snippet,
+                }))
+            })
+            .collect::<Result<Vec<_>, TantivySearchError>>()?;
+
+        Ok(FileSearchResults {
+            total_hits: total_hits as u64,
+            results,
+        })
+    }
+
+    /// Highlight the query matches within the page document at `address`
+    fn highlight(
+        &self,
+        searcher: &Searcher,
+        generator: &SnippetGenerator,
+        address: DocAddress,
+    ) -> Result<Vec<String>, TantivySearchError> {
+        let document: TantivyDocument = searcher
+            .doc(address)
+            .map_err(TantivySearchError::SearchIndex)?;
+        Ok(highlight_snippet(generator, &document))
+    }
+
+    /// Create the scoring explanation for the parts of the search the item
+    /// document and its best page matched
+    fn explain_item(
+        &self,
+        searcher: &Searcher,
+        searches: &[(MatchKind, Box<dyn Query>)],
+        item: &ItemHit,
+    ) -> serde_json::Value {
+        let best_page = item
+            .pages
+            .iter()
+            .max_by(|a, b| a.score.total_cmp(&b.score))
+            .map(|page| (MatchKind::Page, page.address));
+
+        serde_json::Value::Object(
+            item.item_matches
+                .iter()
+                .copied()
+                .chain(best_page)
+                .filter_map(|(kind, address)| {
+                    let (_, query) = searches
+                        .iter()
+                        .find(|(search_kind, _)| *search_kind == kind)?;
+                    let explanation = query.explain(searcher, address).ok()?;
+                    let explanation = serde_json::to_value(explanation).ok()?;
+                    Some((format!("{kind:?}").to_lowercase(), explanation))
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Create the highlights for the page `document`, matched terms are wrapped
+/// with `<em>` tags
+fn highlight_snippet(generator: &SnippetGenerator, document: &TantivyDocument) -> Vec<String> {
+    let mut snippet = generator.snippet_from_doc(document);
+    if snippet.is_empty() {
+        return Vec::new();
+    }
+
+    snippet.set_snippet_prefix_postfix("<em>", "</em>");
+    vec![snippet.to_html()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TantivyIndexFactory, TantivySearchConfig};
+    use crate::{
+        SearchIndex,
+        language::SearchLanguage,
+        models::{
+            AdvancedSearchQuery, DocumentPage, FileSearchRequest, SearchFacetField,
+            SearchIndexData, SearchIndexType, SearchRequest, SuggestRequest, UpdateSearchIndexData,
+        },
+    };
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    /// Create a factory storing the indexes in a temporary directory
+    fn test_factory() -> (TantivyIndexFactory, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("docbox-tantivy-{}", Uuid::new_v4()));
+        let factory = TantivyIndexFactory::from_config(TantivySearchConfig {
+            path: path.clone(),
+            writer_memory_bytes: None,
+            data_region: None,
+            language: None,
+        })
+        .unwrap();
+        (factory, path)
+    }
+
+    fn test_item(name: &str, pages: Vec<&str>) -> SearchIndexData {
+        SearchIndexData {
+            ty: SearchIndexType::File,
+            folder_id: Uuid::new_v4(),
+            document_box: "test".to_string(),
+            item_id: Uuid::new_v4(),
+            name: name.to_string(),
+            mime: Some("application/pdf".to_string()),
+            content: None,
+            created_at: Utc::now(),
+            created_by: None,
+            pinned: false,
+            pages: Some(
+                pages
+                    .into_iter()
+                    .enumerate()
+                    .map(|(page, content)| DocumentPage {
+                        page: page as u64,
+                        content: content.to_string(),
+                        words: None,
+                    })
+                    .collect(),
+            ),
+            summary: None,
+            text_stats: None,
+        }
+    }
+
+    fn content_request(query: &str) -> SearchRequest {
+        SearchRequest {
+            query: Some(query.to_string()),
+            include_name: true,
+            include_content: true,
+            ..Default::default()
+        }
+    }
+
+    /// Tests that page content matches are returned with highlights
+    #[tokio::test]
+    async fn test_search_page_highlight() {
+        let (factory, path) = test_factory();
+        let index = factory.create_search_index("test-index".to_string(), None);
+        index.create_index().await.unwrap();
+        assert!(index.index_exists().await.unwrap());
+
+        let item = test_item(
+            "Report.pdf",
+            vec!["nothing to see here", "the quarterly revenue grew"],
+        );
+        let item_id = item.item_id;
+        index.add_data(vec![item]).await.unwrap();
+
+        let scopes = vec!["test".to_string()];
+        let results = index
+            .search_index(&scopes, content_request("revenue"), None)
+            .await
+            .unwrap();
+
+        assert_eq!(results.total_hits, 1);
+        let result = &results.results[0];
+        assert_eq!(result.item_id, item_id);
+        assert!(result.content_match);
+        assert!(!result.name_match);
+        assert_eq!(result.page_matches.len(), 1);
+        assert_eq!(result.page_matches[0].page, 1);
+        assert!(result.page_matches[0].matches[0].contains("<em>revenue</em>"));
+
+        // Other scopes must not match
+        let results = index
+            .search_index(&["other".to_string()], content_request("revenue"), None)
+            .await
+            .unwrap();
+        assert_eq!(results.total_hits, 0);
+
+        // Prefix scopes match
+        let results = index
+            .search_index(&["te*".to_string()], content_request("revenue"), None)
+            .await
+            .unwrap();
+        assert_eq!(results.total_hits, 1);
+
+        let results = index
+            .search_index_file(
+                &"test".to_string(),
+                item_id,
+                FileSearchRequest {
+                    query: Some("revenue".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.total_hits, 1);
+        assert_eq!(results.results[0].page, 1);
+
+        index.delete_index().await.unwrap();
+        assert!(!index.index_exists().await.unwrap());
+        _ = std::fs::remove_dir_all(path);
+    }
+
+    /// Tests name matching, advanced queries, updates and deletes
+    #[tokio::test]
+    async fn test_search_name_update_delete() {
+        let (factory, path) = test_factory();
+        let index = factory.create_search_index("test-index".to_string(), None);
+        index.create_index().await.unwrap();
+
+        let item = test_item("Quarterly Report.pdf", vec![]);
+        let item_id = item.item_id;
+        let folder_id = item.folder_id;
+        index
+            .add_data(vec![item, test_item("Invoice.pdf", vec![])])
+            .await
+            .unwrap();
+
+        let scopes = vec!["test".to_string()];
+        let results = index
+            .search_index(&scopes, content_request("report"), None)
+            .await
+            .unwrap();
+        assert_eq!(results.total_hits, 1);
+        assert!(results.results[0].name_match);
+
+        let results = index
+            .search_index(
+                &scopes,
+                SearchRequest {
+                    advanced: Some(AdvancedSearchQuery::Not {
+                        query: Box::new(AdvancedSearchQuery::NameContains {
+                            value: "INVOICE".to_string(),
+                        }),
+                    }),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.total_hits, 1);
+        assert_eq!(results.results[0].item_id, item_id);
+
+        index
+            .update_data(
+                item_id,
+                UpdateSearchIndexData {
+                    folder_id,
+                    name: "Renamed.pdf".to_string(),
+                    pinned: true,
+                    content: None,
+                    pages: None,
+                    document_box: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        let results = index
+            .search_index(&scopes, content_request("renamed"), None)
+            .await
+            .unwrap();
+        assert_eq!(results.total_hits, 1);
+
+        let results = index
+            .search_index(
+                &scopes,
+                SearchRequest {
+                    pinned: Some(true),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.total_hits, 1);
+
+        index.delete_data(item_id).await.unwrap();
+        let results = index
+            .search_index(&scopes, SearchRequest::default(), None)
+            .await
+            .unwrap();
+        assert_eq!(results.total_hits, 1);
+
+        index.delete_by_scope("test").await.unwrap();
+        let results = index
+            .search_index(&scopes, SearchRequest::default(), None)
+            .await
+            .unwrap();
+        assert_eq!(results.total_hits, 0);
+
+        index.delete_index().await.unwrap();
+        _ = std::fs::remove_dir_all(path);
+    }
+
+    /// Tests that suggestions complete names and words within names,
+    /// preferring names that start with the query
+    #[tokio::test]
+    async fn test_suggest() {
+        let (factory, path) = test_factory();
+        let index = factory.create_search_index("test-index".to_string(), None);
+        index.create_index().await.unwrap();
+
+        index
+            .add_data(vec![
+                test_item("Quarterly Report.pdf", vec!["report content"]),
+                test_item("Report.pdf", vec![]),
+                test_item("Invoice.pdf", vec![]),
+            ])
+            .await
+            .unwrap();
+
+        let scopes = vec!["test".to_string()];
+        let results = index
+            .suggest_index(
+                &scopes,
+                SuggestRequest {
+                    query: "rep".to_string(),
+                    size: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        let names: Vec<&str> = results
+            .suggestions
+            .iter()
+            .map(|suggestion| suggestion.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Report.pdf", "Quarterly Report.pdf"]);
+
+        let results = index
+            .suggest_index(
+                &["other".to_string()],
+                SuggestRequest {
+                    query: "rep".to_string(),
+                    size: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert!(results.suggestions.is_empty());
+
+        index.delete_index().await.unwrap();
+        _ = std::fs::remove_dir_all(path);
+    }
+
+    /// Tests that facet counts cover all the matching items
+    #[tokio::test]
+    async fn test_search_facets() {
+        let (factory, path) = test_factory();
+        let index = factory.create_search_index("test-index".to_string(), None);
+        index.create_index().await.unwrap();
+
+        index
+            .add_data(vec![
+                test_item("Report.pdf", vec!["report content"]),
+                SearchIndexData {
+                    mime: Some("text/plain".to_string()),
+                    ..test_item("Report.txt", vec![])
+                },
+                test_item("Other Report.pdf", vec![]),
+                test_item("Invoice.pdf", vec![]),
+            ])
+            .await
+            .unwrap();
+
+        let results = index
+            .search_index(
+                &["test".to_string()],
+                SearchRequest {
+                    size: Some(1),
+                    facets: Some(vec![SearchFacetField::Mime, SearchFacetField::ItemType]),
+                    ..content_request("report")
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(results.total_hits, 3);
+        assert_eq!(results.results.len(), 1);
+        assert_eq!(results.facets.len(), 2);
+
+        let mime = &results.facets[0];
+        assert_eq!(mime.field, SearchFacetField::Mime);
+        let mime: Vec<(&str, u64)> = mime
+            .buckets
+            .iter()
+            .map(|bucket| (bucket.value.as_str(), bucket.count))
+            .collect();
+        assert_eq!(mime, vec![("application/pdf", 2), ("text/plain", 1)]);
+
+        let item_type = &results.facets[1];
+        assert_eq!(item_type.buckets.len(), 1);
+        assert_eq!(item_type.buckets[0].value, "File");
+        assert_eq!(item_type.buckets[0].count, 3);
+
+        index.delete_index().await.unwrap();
+        _ = std::fs::remove_dir_all(path);
+    }
+
+    /// Tests that page content is stemmed using the configured language
+    #[tokio::test]
+    async fn test_search_language_stemming() {
+        let (factory, path) = test_factory();
+
+        let index =
+            factory.create_search_index("stemmed-index".to_string(), Some(SearchLanguage::English));
+        index.create_index().await.unwrap();
+        index
+            .add_data(vec![test_item(
+                "Report.pdf",
+                vec!["the servers were running"],
+            )])
+            .await
+            .unwrap();
+
+        let scopes = vec!["test".to_string()];
+        let results = index
+            .search_index(&scopes, content_request("runs"), None)
+            .await
+            .unwrap();
+        assert_eq!(results.total_hits, 1);
+        assert!(results.results[0].content_match);
+
+        // Without a language terms are not stemmed
+        let index = factory.create_search_index("plain-index".to_string(), None);
+        index.create_index().await.unwrap();
+        index
+            .add_data(vec![test_item(
+                "Report.pdf",
+                vec!["the servers were running"],
+            )])
+            .await
+            .unwrap();
+
+        let results = index
+            .search_index(&scopes, content_request("runs"), None)
+            .await
+            .unwrap();
+        assert_eq!(results.total_hits, 0);
+
+        _ = std::fs::remove_dir_all(path);
+    }
+}
@@ -0,0 +1,313 @@
+//! Query builders for the Tantivy indexes
+
+use super::schema::{KIND_ITEM, TantivyFields};
+use crate::models::{AdvancedSearchQuery, SearchRequest};
+use ::tantivy::{
+    DateTime, Index, Term,
+    query::{
+        AllQuery, BooleanQuery, BoostQuery, ConstScoreQuery, EmptyQuery, Occur, Query, RangeQuery,
+        RegexQuery, TermQuery, TermSetQuery,
+    },
+    schema::{Field, IndexRecordOption},
+};
+use chrono::Utc;
+use docbox_database::models::{document_box::DocumentBoxScopeRaw, folder::FolderId};
+use std::ops::Bound;
+
+/// Create the filters from the search request that apply to both item and
+/// page documents, filters do not contribute to the score
+pub fn create_filters(
+    fields: &TantivyFields,
+    kind: &str,
+    req: &SearchRequest,
+    scopes: &[DocumentBoxScopeRaw],
+    folder_children: Option<&[FolderId]>,
+) -> Vec<Box<dyn Query>> {
+    let mut filters = vec![
+        term_query(fields.kind, kind),
+        create_scopes_query(fields, scopes),
+    ];
+
+    if let Some(folder_children) = folder_children {
+        filters.push(Box::new(TermSetQuery::new(folder_children.iter().map(
+            |folder_id| Term::from_field_text(fields.folder_id, &folder_id.to_string()),
+        ))));
+    }
+
+    if let Some(mime) = req.mime.as_ref() {
+        filters.push(term_query(fields.mime, mime.0.as_ref()));
+    }
+
+    if let Some(created_at) = req.created_at.as_ref() {
+        filters.push(created_range_query(
+            fields,
+            created_at.start,
+            created_at.end,
+        ));
+    }
+
+    if let Some(created_by) = req.created_by.as_deref() {
+        filters.push(term_query(fields.created_by, created_by));
+    }
+
+    if let Some(pinned) = req.pinned {
+        filters.push(Box::new(TermQuery::new(
+            Term::from_field_bool(fields.pinned, pinned),
+            IndexRecordOption::Basic,
+        )));
+    }
+
+    if let Some(advanced) = req.advanced.as_ref() {
+        filters.push(create_advanced_query(fields, advanced));
+    }
+
+    filters
+        .into_iter()
+        .map(|filter| Box::new(ConstScoreQuery::new(filter, 0.0)) as Box<dyn Query>)
+        .collect()
+}
+
+/// Combine the `filters` with a scoring `query` that must match
+pub fn filtered_query(filters: Vec<Box<dyn Query>>, query: Box<dyn Query>) -> Box<dyn Query> {
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = filters
+        .into_iter()
+        .map(|filter| (Occur::Must, filter))
+        .collect();
+    clauses.push((Occur::Must, query));
+    Box::new(BooleanQuery::new(clauses))
+}
+
+/// Create a query matching items with a name equal to or containing the
+/// `query`, exact matches are boosted
+pub fn create_name_query(fields: &TantivyFields, query: &str) -> Box<dyn Query> {
+    let query = query.to_lowercase();
+    let exact = term_query(fields.name_raw, &query);
+    let contains = contains_query(fields.name_raw, &query);
+
+    Box::new(BooleanQuery::new(vec![
+        (Occur::Should, Box::new(BoostQuery::new(exact, 2.0))),
+        (Occur::Should, Box::new(BoostQuery::new(contains, 1.5))),
+    ]))
+}
+
+/// Create a query matching items within the `scopes` with a name where the
+/// name or one of its words starts with the `prefix`, items where the entire
+/// name starts with the `prefix` are boosted
+pub fn create_suggest_query(
+    fields: &TantivyFields,
+    scopes: &[DocumentBoxScopeRaw],
+    prefix: &str,
+) -> Box<dyn Query> {
+    let prefix = prefix.to_lowercase();
+    let name_prefix = prefix_query(fields.name_raw, &prefix);
+    let word_prefix = contains_query(fields.name_raw, &format!(" {prefix}"));
+
+    let name_query: Box<dyn Query> = Box::new(BooleanQuery::new(vec![
+        (Occur::Should, Box::new(BoostQuery::new(name_prefix, 2.0))),
+        (Occur::Should, word_prefix),
+    ]));
+
+    let filters = vec![
+        term_query(fields.kind, KIND_ITEM),
+        create_scopes_query(fields, scopes),
+    ]
+    .into_iter()
+    .map(|filter| Box::new(ConstScoreQuery::new(filter, 0.0)) as Box<dyn Query>)
+    .collect();
+
+    filtered_query(filters, name_query)
+}
+
+/// Create a query matching the item content or the generated summary,
+/// summary matches are boosted as the summary is a condensed description
+/// of the entire document
+pub fn create_content_query(
+    index: &Index,
+    fields: &TantivyFields,
+    query: &str,
+) -> ::tantivy::Result<Box<dyn Query>> {
+    let content = create_text_query(index, fields.content, query)?;
+    let summary = create_text_query(index, fields.summary, query)?;
+
+    Ok(Box::new(BooleanQuery::new(vec![
+        (Occur::Should, content),
+        (Occur::Should, Box::new(BoostQuery::new(summary, 2.0))),
+    ])))
+}
+
+/// Create a query matching any of the terms from the `query` text within the
+/// full text `field`, the text is tokenized the same way as the field
+pub fn create_text_query(
+    index: &Index,
+    field: Field,
+    query: &str,
+) -> ::tantivy::Result<Box<dyn Query>> {
+    let mut tokenizer = index.tokenizer_for_field(field)?;
+    let mut token_stream = tokenizer.token_stream(query);
+    let mut terms = Vec::new();
+    token_stream.process(&mut |token| terms.push(Term::from_field_text(field, &token.text)));
+
+    if terms.is_empty() {
+        return Ok(Box::new(EmptyQuery));
+    }
+
+    Ok(Box::new(BooleanQuery::new(
+        terms
+            .into_iter()
+            .map(|term| {
+                let query: Box<dyn Query> =
+                    Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs));
+                (Occur::Should, query)
+            })
+            .collect(),
+    )))
+}
+
+/// Create a query matching any of the `scopes`, scopes ending with a
+/// wildcard match all scopes starting with the prefix
+fn create_scopes_query(fields: &TantivyFields, scopes: &[DocumentBoxScopeRaw]) -> Box<dyn Query> {
+    Box::new(BooleanQuery::new(
+        scopes
+            .iter()
+            .map(|scope| {
+                let query = match scope.strip_suffix('*') {
+                    Some(prefix) => prefix_query(fields.document_box, prefix),
+                    None => term_query(fields.document_box, scope),
+                };
+                (Occur::Should, query)
+            })
+            .collect(),
+    ))
+}
+
+/// Compile an advanced query into a Tantivy query
+fn create_advanced_query(fields: &TantivyFields, query: &AdvancedSearchQuery) -> Box<dyn Query> {
+    match query {
+        AdvancedSearchQuery::And { queries } => Box::new(BooleanQuery::new(
+            queries
+                .iter()
+                .map(|query| (Occur::Must, create_advanced_query(fields, query)))
+                .collect(),
+        )),
+        AdvancedSearchQuery::Or { queries } => Box::new(BooleanQuery::new(
+            queries
+                .iter()
+                .map(|query| (Occur::Should, create_advanced_query(fields, query)))
+                .collect(),
+        )),
+        AdvancedSearchQuery::Not { query } => Box::new(BooleanQuery::new(vec![
+            (Occur::Must, Box::new(AllQuery)),
+            (Occur::MustNot, create_advanced_query(fields, query)),
+        ])),
+        AdvancedSearchQuery::NameContains { value } => {
+            contains_query(fields.name_raw, &value.to_lowercase())
+        }
+        AdvancedSearchQuery::MimeIn { values } => Box::new(TermSetQuery::new(
+            values
+                .iter()
+                .map(|value| Term::from_field_text(fields.mime, value)),
+        )),
+        AdvancedSearchQuery::CreatedBetween { start, end } => {
+            created_range_query(fields, *start, *end)
+        }
+        AdvancedSearchQuery::CreatedByEquals { value } => term_query(fields.created_by, value),
+        AdvancedSearchQuery::PinnedEquals { value } => Box::new(TermQuery::new(
+            Term::from_field_bool(fields.pinned, *value),
+            IndexRecordOption::Basic,
+        )),
+    }
+}
+
+/// Create an inclusive range query against the creation date
+fn created_range_query(
+    fields: &TantivyFields,
+    start: Option<chrono::DateTime<Utc>>,
+    end: Option<chrono::DateTime<Utc>>,
+) -> Box<dyn Query> {
+    let bound = |value: Option<chrono::DateTime<Utc>>| match value {
+        Some(value) => Bound::Included(Term::from_field_date_for_search(
+            fields.created_at,
+            DateTime::from_timestamp_micros(value.timestamp_micros()),
+        )),
+        None => Bound::Unbounded,
+    };
+
+    Box::new(RangeQuery::new(bound(start), bound(end)))
+}
+
+/// Query matching the exact `value` of a keyword field
+fn term_query(field: Field, value: &str) -> Box<dyn Query> {
+    Box::new(TermQuery::new(
+        Term::from_field_text(field, value),
+        IndexRecordOption::Basic,
+    ))
+}
+
+/// Query matching keyword values starting with the `prefix`
+fn prefix_query(field: Field, prefix: &str) -> Box<dyn Query> {
+    regex_query(field, &format!("{}.*", escape_regex(prefix)))
+}
+
+/// Query matching keyword values containing the `value`
+fn contains_query(field: Field, value: &str) -> Box<dyn Query> {
+    regex_query(field, &format!(".*{}.*", escape_regex(value)))
+}
+
+fn regex_query(field: Field, pattern: &str) -> Box<dyn Query> {
+    match RegexQuery::from_pattern(pattern, field) {
+        Ok(query) => Box::new(query),
+        // Patterns are escaped so this should not occur
+        Err(error) => {
+            tracing::error!(?error, %pattern, "failed to create regex query");
+            Box::new(EmptyQuery)
+        }
+    }
+}
+
+/// Escape the regex meta characters within `value`
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for char in value.chars() {
+        if matches!(
+            char,
+            '\\' | '.'
+                | '+'
+                | '*'
+                | '?'
+                | '('
+                | ')'
+                | '|'
+                | '['
+                | ']'
+                | '{'
+                | '}'
+                | '^'
+                | '$'
+                | '#'
+                | '&'
+                | '-'
+                | '~'
+                | '"'
+                | '<'
+                | '>'
+                | '@'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::escape_regex;
+
+    /// Tests that regex meta characters are escaped
+    #[test]
+    fn test_escape_regex() {
+        assert_eq!(escape_regex("report.pdf"), "report\\.pdf");
+        assert_eq!(escape_regex("user:1:files"), "user:1:files");
+        assert_eq!(escape_regex("a*(b)"), "a\\*\\(b\\)");
+    }
+}
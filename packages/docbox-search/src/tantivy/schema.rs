@@ -0,0 +1,277 @@
+//! Schema for the Tantivy indexes
+//!
+//! Items and their pages are stored as separate documents distinguished by
+//! the `kind` field. The filterable fields of the item are copied onto each
+//! of its page documents so pages can be searched and highlighted individually
+//! while still respecting the search filters.
+
+use crate::{
+    language::SearchLanguage,
+    models::{DocumentPage, SearchFacetField, SearchIndexData, SearchIndexType},
+};
+use ::tantivy::{
+    DateTime, Index, TantivyDocument,
+    schema::{
+        DateOptions, Field, INDEXED, IndexRecordOption, STORED, STRING, Schema, TEXT,
+        TextFieldIndexing, TextOptions, Value,
+    },
+    tokenizer::{
+        Language, LowerCaser, RawTokenizer, RemoveLongFilter, SimpleTokenizer, Stemmer,
+        TextAnalyzer,
+    },
+};
+use chrono::{DateTime as ChronoDateTime, Utc};
+use docbox_database::models::user::UserId;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Tokenizer indexing the entire value as a single lowercase term, used for
+/// case-insensitive exact and substring name matching
+const LOWERCASE_RAW_TOKENIZER: &str = "lowercase_raw";
+
+/// Name of the tokenizer used by the full text fields
+const DEFAULT_TOKENIZER: &str = "default";
+
+/// Value of the `kind` field for item documents
+pub const KIND_ITEM: &str = "item";
+
+/// Value of the `kind` field for page documents
+pub const KIND_PAGE: &str = "page";
+
+/// Fields of the index schema
+#[derive(Clone, Copy)]
+pub struct TantivyFields {
+    /// Whether the document is an item or a page of an item
+    pub kind: Field,
+    pub item_id: Field,
+    pub item_type: Field,
+    pub document_box: Field,
+    pub folder_id: Field,
+    /// Full text name
+    pub name: Field,
+    /// Lowercase name indexed as a single term
+    pub name_raw: Field,
+    pub mime: Field,
+    pub content: Field,
+    pub summary: Field,
+    pub created_at: Field,
+    pub created_by: Field,
+    pub pinned: Field,
+    /// Page number for page documents
+    pub page: Field,
+    /// Page content for page documents
+    pub page_content: Field,
+}
+
+impl TantivyFields {
+    /// Create the index schema
+    pub fn schema() -> (Schema, TantivyFields) {
+        let mut builder = Schema::builder();
+
+        let name_raw_options = TextOptions::default()
+            .set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(LOWERCASE_RAW_TOKENIZER)
+                    .set_index_option(IndexRecordOption::Basic),
+            )
+            .set_stored();
+
+        let fields = TantivyFields {
+            kind: builder.add_text_field("kind", STRING),
+            item_id: builder.add_text_field("item_id", STRING | STORED),
+            item_type: builder.add_text_field("item_type", STRING | STORED),
+            document_box: builder.add_text_field("document_box", STRING | STORED),
+            folder_id: builder.add_text_field("folder_id", STRING | STORED),
+            name: builder.add_text_field("name", TEXT),
+            name_raw: builder.add_text_field("name_raw", name_raw_options),
+            mime: builder.add_text_field("mime", STRING | STORED),
+            content: builder.add_text_field("content", TEXT | STORED),
+            summary: builder.add_text_field("summary", TEXT | STORED),
+            created_at: builder
+                .add_date_field("created_at", DateOptions::from(INDEXED).set_stored()),
+            created_by: builder.add_text_field("created_by", STRING | STORED),
+            pinned: builder.add_bool_field("pinned", INDEXED | STORED),
+            page: builder.add_u64_field("page", STORED),
+            page_content: builder.add_text_field("page_content", TEXT | STORED),
+        };
+
+        (builder.build(), fields)
+    }
+
+    /// Register the custom tokenizers used by the schema, tokenizers are not
+    /// persisted so this must be done whenever an index is opened
+    ///
+    /// When a `language` is provided the default tokenizer used by the full
+    /// text fields is replaced with one that stems terms for the language
+    pub fn register_tokenizers(index: &Index, language: Option<SearchLanguage>) {
+        index.tokenizers().register(
+            LOWERCASE_RAW_TOKENIZER,
+            TextAnalyzer::builder(RawTokenizer::default())
+                .filter(LowerCaser)
+                .build(),
+        );
+
+        if let Some(language) = language {
+            index.tokenizers().register(
+                DEFAULT_TOKENIZER,
+                TextAnalyzer::builder(SimpleTokenizer::default())
+                    .filter(RemoveLongFilter::limit(40))
+                    .filter(LowerCaser)
+                    .filter(Stemmer::new(stemmer_language(language)))
+                    .build(),
+            );
+        }
+    }
+
+    /// Create the item document and page documents for the provided `data`
+    pub fn documents(&self, data: &SearchIndexData) -> Vec<TantivyDocument> {
+        let pages = data.pages.as_deref().unwrap_or_default();
+        let mut documents = Vec::with_capacity(pages.len() + 1);
+
+        let mut item = self.base_document(data, KIND_ITEM);
+        if let Some(content) = data.content.as_deref() {
+            item.add_text(self.content, content);
+        }
+        if let Some(summary) = data.summary.as_deref() {
+            item.add_text(self.summary, summary);
+        }
+        documents.push(item);
+
+        for page in pages {
+            let mut document = self.base_document(data, KIND_PAGE);
+            document.add_u64(self.page, page.page);
+            document.add_text(self.page_content, &page.content);
+            documents.push(document);
+        }
+
+        documents
+    }
+
+    /// Create a document with the fields shared by items and pages
+    fn base_document(&self, data: &SearchIndexData, kind: &str) -> TantivyDocument {
+        let mut document = TantivyDocument::new();
+        document.add_text(self.kind, kind);
+        document.add_text(self.item_id, data.item_id.to_string());
+        document.add_text(self.item_type, item_type_value(data.ty));
+        document.add_text(self.document_box, &data.document_box);
+        document.add_text(self.folder_id, data.folder_id.to_string());
+        document.add_text(self.name, &data.name);
+        document.add_text(self.name_raw, &data.name);
+        if let Some(mime) = data.mime.as_deref() {
+            document.add_text(self.mime, mime);
+        }
+        document.add_date(
+            self.created_at,
+            DateTime::from_timestamp_micros(data.created_at.timestamp_micros()),
+        );
+        if let Some(created_by) = data.created_by.as_deref() {
+            document.add_text(self.created_by, created_by);
+        }
+        document.add_bool(self.pinned, data.pinned);
+        document
+    }
+
+    /// Read back the item data from a stored item `document` and its stored
+    /// `pages`, used to rewrite documents as Tantivy has no partial updates
+    pub fn read_item(
+        &self,
+        document: &TantivyDocument,
+        pages: Vec<DocumentPage>,
+    ) -> Option<SearchIndexData> {
+        let created_at = document.get_first(self.created_at)?.as_datetime()?;
+        let created_at =
+            ChronoDateTime::<Utc>::from_timestamp_micros(created_at.into_timestamp_micros())?;
+
+        Some(SearchIndexData {
+            ty: self.read_item_type(document)?,
+            folder_id: Uuid::from_str(self.read_str(document, self.folder_id)?).ok()?,
+            document_box: self.read_str(document, self.document_box)?.to_string(),
+            item_id: self.read_item_id(document)?,
+            name: self.read_str(document, self.name_raw)?.to_string(),
+            mime: self.read_str(document, self.mime).map(str::to_string),
+            content: self.read_str(document, self.content).map(str::to_string),
+            created_at,
+            created_by: self.read_str(document, self.created_by).map(UserId::from),
+            pinned: document
+                .get_first(self.pinned)
+                .and_then(|value| value.as_bool())
+                .unwrap_or_default(),
+            pages: (!pages.is_empty()).then_some(pages),
+            summary: self.read_str(document, self.summary).map(str::to_string),
+            text_stats: None,
+        })
+    }
+
+    /// Read a stored page from a page `document`
+    pub fn read_page(&self, document: &TantivyDocument) -> Option<DocumentPage> {
+        Some(DocumentPage {
+            page: document.get_first(self.page)?.as_u64()?,
+            content: self.read_str(document, self.page_content)?.to_string(),
+            words: None,
+        })
+    }
+
+    pub fn read_item_id(&self, document: &TantivyDocument) -> Option<Uuid> {
+        Uuid::from_str(self.read_str(document, self.item_id)?).ok()
+    }
+
+    pub fn read_item_type(&self, document: &TantivyDocument) -> Option<SearchIndexType> {
+        match self.read_str(document, self.item_type)? {
+            "File" => Some(SearchIndexType::File),
+            "Folder" => Some(SearchIndexType::Folder),
+            "Link" => Some(SearchIndexType::Link),
+            _ => None,
+        }
+    }
+
+    /// Read the value of the facet `field` from an item or page `document`
+    pub fn read_facet_value<'a>(
+        &self,
+        document: &'a TantivyDocument,
+        field: SearchFacetField,
+    ) -> Option<&'a str> {
+        let field = match field {
+            SearchFacetField::Mime => self.mime,
+            SearchFacetField::ItemType => self.item_type,
+            SearchFacetField::CreatedBy => self.created_by,
+            SearchFacetField::FolderId => self.folder_id,
+        };
+        self.read_str(document, field)
+    }
+
+    pub fn read_str<'a>(&self, document: &'a TantivyDocument, field: Field) -> Option<&'a str> {
+        document.get_first(field).and_then(|value| value.as_str())
+    }
+}
+
+/// Value stored in the `item_type` field for the item type
+fn item_type_value(ty: SearchIndexType) -> &'static str {
+    match ty {
+        SearchIndexType::File => "File",
+        SearchIndexType::Folder => "Folder",
+        SearchIndexType::Link => "Link",
+    }
+}
+
+/// Stemmer language for the search `language`
+fn stemmer_language(language: SearchLanguage) -> Language {
+    match language {
+        SearchLanguage::Arabic => Language::Arabic,
+        SearchLanguage::Danish => Language::Danish,
+        SearchLanguage::Dutch => Language::Dutch,
+        SearchLanguage::English => Language::English,
+        SearchLanguage::Finnish => Language::Finnish,
+        SearchLanguage::French => Language::French,
+        SearchLanguage::German => Language::German,
+        SearchLanguage::Greek => Language::Greek,
+        SearchLanguage::Hungarian => Language::Hungarian,
+        SearchLanguage::Italian => Language::Italian,
+        SearchLanguage::Norwegian => Language::Norwegian,
+        SearchLanguage::Portuguese => Language::Portuguese,
+        SearchLanguage::Romanian => Language::Romanian,
+        SearchLanguage::Russian => Language::Russian,
+        SearchLanguage::Spanish => Language::Spanish,
+        SearchLanguage::Swedish => Language::Swedish,
+        SearchLanguage::Turkish => Language::Turkish,
+    }
+}
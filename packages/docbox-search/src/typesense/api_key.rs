@@ -10,6 +10,25 @@ pub enum TypesenseApiKeyProvider {
     Secret(TypesenseApiKeySecret),
 }
 
+impl TypesenseApiKeyProvider {
+    /// Name of the secret the API key is loaded from, [None] when
+    /// the API key is provided directly
+    pub fn secret_name(&self) -> Option<&str> {
+        match self {
+            TypesenseApiKeyProvider::ApiKey(_) => None,
+            TypesenseApiKeyProvider::Secret(value) => Some(&value.secret_name),
+        }
+    }
+
+    /// Clear any cached API key value so that the next request loads
+    /// the current API key
+    pub async fn flush(&self) {
+        if let TypesenseApiKeyProvider::Secret(value) = self {
+            value.flush().await;
+        }
+    }
+}
+
 impl ApiKeyProvider for TypesenseApiKeyProvider {
     async fn get_api_key(&self) -> Result<String, TypesenseSearchError> {
         match self {
@@ -44,6 +63,12 @@ impl TypesenseApiKeySecret {
             secret_value: Default::default(),
         }
     }
+
+    /// Clear the loaded secret value, the secret will be loaded again
+    /// from the secret manager on next use
+    pub async fn flush(&self) {
+        *self.secret_value.lock().await = None;
+    }
 }
 
 impl ApiKeyProvider for TypesenseApiKeySecret {
@@ -54,7 +79,10 @@ impl ApiKeyProvider for TypesenseApiKeySecret {
         }
 
         match self.secrets.get_secret(&self.secret_name).await {
-            Ok(Some(Secret::String(value))) => Ok(value),
+            Ok(Some(Secret::String(value))) => {
+                *secret_value = Some(value.clone());
+                Ok(value)
+            }
 
             Ok(Some(Secret::Binary(_))) => {
                 tracing::error!("expected string secret for typesense api key but got binary");
@@ -83,6 +111,10 @@ impl TypesenseApiKey {
     pub fn new(value: String) -> Self {
         Self(value)
     }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
 // Prevent the API key itself from appearing in logs
@@ -1,3 +1,4 @@
+use crate::language::UnknownSearchLanguage;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -10,6 +11,9 @@ pub enum TypesenseIndexFactoryError {
 
     #[error("failed to create http client")]
     CreateClient,
+
+    #[error(transparent)]
+    InvalidLanguage(#[from] UnknownSearchLanguage),
 }
 
 #[derive(Debug, Error)]
@@ -38,4 +42,10 @@ pub enum TypesenseSearchError {
     MissingRootEntry,
     #[error("failed to search index")]
     SearchIndex,
+    #[error("failed to update collection schema")]
+    UpdateSchema,
+    #[error("migration not found")]
+    MigrationNotFound,
+    #[error("failed to verify api key")]
+    VerifyApiKey,
 }
@@ -1,8 +1,11 @@
 use crate::{
-    SearchError, SearchIndex,
+    DEFAULT_SUGGEST_SIZE, MAX_FACET_BUCKETS, SearchError, SearchIndex,
+    language::SearchLanguage,
     models::{
-        DocumentPage, FileSearchRequest, FileSearchResults, FlattenedItemResult, PageResult,
-        SearchIndexData, SearchRequest, SearchResults, SearchScore, UpdateSearchIndexData,
+        AdvancedSearchQuery, DocumentPage, FileSearchRequest, FileSearchResults,
+        FlattenedItemResult, PageResult, SearchExplain, SearchFacet, SearchFacetBucket,
+        SearchFacetField, SearchIndexData, SearchRequest, SearchResults, SearchScore,
+        SearchSuggestion, SuggestRequest, SuggestResults, UpdateSearchIndexData,
     },
     typesense::{
         api_key::ApiKeyProvider,
@@ -27,7 +30,7 @@ use itertools::Itertools;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::{fmt::Debug, sync::Arc};
+use std::{fmt::Debug, sync::Arc, time::Duration};
 use uuid::Uuid;
 
 pub use api_key::{TypesenseApiKey, TypesenseApiKeyProvider, TypesenseApiKeySecret};
@@ -46,6 +49,15 @@ pub struct TypesenseSearchConfig {
 
     /// Config provides a secret manager key pointing to the API key
     pub api_key_secret_name: Option<String>,
+
+    /// Data residency region the search index is located within (i.e "eu-west"),
+    /// tenants assigned to a region can only use search within that region
+    #[serde(default)]
+    pub data_region: Option<String>,
+
+    /// Default language used to analyze the indexed text content
+    #[serde(default)]
+    pub language: Option<SearchLanguage>,
 }
 
 impl TypesenseSearchConfig {
@@ -61,11 +73,15 @@ impl TypesenseSearchConfig {
         let api_key_secret_name = std::env::var("DOCBOX_TYPESENSE_API_KEY_SECRET_NAME")
             .or(std::env::var("TYPESENSE_API_KEY_SECRET_NAME"))
             .ok();
+        let data_region = std::env::var("DOCBOX_SEARCH_DATA_REGION").ok();
+        let language = SearchLanguage::from_env()?;
 
         Ok(Self {
             url,
             api_key,
             api_key_secret_name,
+            data_region,
+            language,
         })
     }
 }
@@ -80,6 +96,8 @@ pub struct TypesenseClientData {
 pub struct TypesenseIndexFactory {
     client: reqwest::Client,
     client_data: Arc<TypesenseClientData>,
+    data_region: Option<String>,
+    language: Option<SearchLanguage>,
 }
 
 impl TypesenseIndexFactory {
@@ -116,14 +134,59 @@ impl TypesenseIndexFactory {
         Ok(Self {
             client,
             client_data,
+            data_region: config.data_region,
+            language: config.language,
         })
     }
 
-    pub fn create_search_index(&self, index: String) -> TypesenseIndex {
+    /// Data residency region the search index is located within
+    pub fn data_region(&self) -> Option<&str> {
+        self.data_region.as_deref()
+    }
+
+    /// Default language used to analyze the indexed text content
+    pub fn language(&self) -> Option<SearchLanguage> {
+        self.language
+    }
+
+    /// Provider for the API key used by the search indexes
+    pub fn api_key_provider(&self) -> &TypesenseApiKeyProvider {
+        &self.client_data.api_key_provider
+    }
+
+    /// Verify that typesense accepts the provided `api_key`
+    pub async fn verify_api_key(
+        &self,
+        api_key: &TypesenseApiKey,
+    ) -> Result<(), TypesenseSearchError> {
+        self.client
+            .get(format!("{}/collections", self.client_data.base_url))
+            .header("x-typesense-api-key", api_key.as_str())
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to verify api key");
+                TypesenseSearchError::VerifyApiKey
+            })?
+            .error_for_status()
+            .map_err(|error| {
+                tracing::error!(?error, "typesense rejected api key");
+                TypesenseSearchError::VerifyApiKey
+            })?;
+
+        Ok(())
+    }
+
+    pub fn create_search_index(
+        &self,
+        index: String,
+        language: Option<SearchLanguage>,
+    ) -> TypesenseIndex {
         TypesenseIndex {
             client: self.client.clone(),
             client_data: self.client_data.clone(),
             index,
+            language,
         }
     }
 }
@@ -133,8 +196,23 @@ pub struct TypesenseIndex {
     client: reqwest::Client,
     client_data: Arc<TypesenseClientData>,
     index: String,
+    /// Language used to analyze the text fields
+    language: Option<SearchLanguage>,
 }
 
+/// Migrations to apply against the typesense collection, items indexed before
+/// a migration added a field must be re-indexed to populate the field
+const TYPESENSE_MIGRATIONS: &[&str] = &[
+    "m1_typesense_add_pinned_field",
+    "m2_typesense_add_summary_field",
+    "m3_typesense_add_text_stats_fields",
+    "m4_typesense_facet_mime_field",
+];
+
+/// Additional time allowed on top of a requested search timeout before the
+/// HTTP request itself is abandoned
+const TIMEOUT_GRACE_PERIOD_MS: u64 = 1000;
+
 fn escape_typesense_value(input: &str) -> String {
     // Escape backticks within the text
     let escaped = input.replace('`', "\\`");
@@ -160,16 +238,18 @@ impl SearchIndex for TypesenseIndex {
 
             { "name": "item_type", "type": "string", "facet": true },
             { "name": "item_id", "type": "string", "facet": true },
-            { "name": "name", "type": "string" },
+            self.text_field(json!({ "name": "name", "type": "string" })),
 
-            { "name": "value", "type": "string", "optional": true },
+            self.text_field(json!({ "name": "value", "type": "string", "optional": true })),
             { "name": "mime", "type": "string", "optional": true },
 
             { "name": "created_at", "type": "int64", "facet": true },
             { "name": "created_by", "type": "string", "optional": true, "facet": true },
 
             { "name": "page", "type": "int32", "optional": true },
-            { "name": "page_content", "type": "string", "optional": true }
+            self.text_field(json!({ "name": "page_content", "type": "string", "optional": true }))
+
+            // Additional fields are added to the schema by migrations
           ]
         });
 
@@ -221,6 +301,17 @@ impl SearchIndex for TypesenseIndex {
         Ok(true)
     }
 
+    async fn recreate_index(&self) -> Result<(), SearchError> {
+        self.create_index().await?;
+
+        // Apply the schema changes from all migrations
+        for migration_name in TYPESENSE_MIGRATIONS {
+            self.apply_schema_migration(migration_name).await?;
+        }
+
+        Ok(())
+    }
+
     async fn delete_index(&self) -> Result<(), SearchError> {
         let api_key = self.client_data.api_key_provider.get_api_key().await?;
 
@@ -251,6 +342,89 @@ impl SearchIndex for TypesenseIndex {
         Ok(())
     }
 
+    async fn suggest_index(
+        &self,
+        scopes: &[DocumentBoxScopeRaw],
+        query: SuggestRequest,
+    ) -> Result<SuggestResults, SearchError> {
+        let api_key = self.client_data.api_key_provider.get_api_key().await?;
+
+        let limit = query.size.unwrap_or(DEFAULT_SUGGEST_SIZE);
+
+        // Only the root entries are matched so each item is suggested once
+        let filter_by = format!(
+            r#"{}&&entry_type:="Root""#,
+            create_search_filters(scopes, &SearchRequest::default(), None)
+        );
+
+        let has_wildcard = scopes.iter().any(|scope| scope.ends_with('*'));
+        let max_filter_by_candidates = if has_wildcard { 10_000 } else { 4 };
+
+        let query_json = json!({
+            "searches": [
+                {
+                    "collection": self.index,
+                    "q": query.query.trim(),
+                    "query_by": "name",
+                    // Match the last word of the query as a prefix
+                    "prefix": true,
+                    "limit": limit,
+                    "filter_by": filter_by,
+                    "include_fields": "version,entry_type,document_box,folder_id,item_type,item_id,name,created_at",
+                    "max_filter_by_candidates": max_filter_by_candidates
+                }
+            ]
+        });
+
+        let response = self
+            .client
+            .post(format!("{}/multi_search", self.client_data.base_url))
+            .header("x-typesense-api-key", api_key)
+            .json(&query_json)
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to query typesense multi_search");
+                TypesenseSearchError::SearchIndex
+            })?;
+
+        if let Err(error) = response.error_for_status_ref() {
+            let body = response.text().await;
+            tracing::error!(?error, ?body, "failed to get suggest results");
+            return Err(TypesenseSearchError::SearchIndex.into());
+        }
+
+        let search: SearchResponse<GenericSearchResponse> =
+            response.json().await.map_err(|error| {
+                tracing::error!(?error, "failed to parse suggest response JSON");
+                TypesenseSearchError::SearchIndex
+            })?;
+
+        let search = search
+            .results
+            .into_iter()
+            .next()
+            .ok_or(TypesenseSearchError::MissingSearchResult)?;
+
+        let suggestions = search
+            .hits
+            .into_iter()
+            .filter_map(|hit| match hit.document {
+                TypesenseDataEntry::V1(TypesenseDataEntryV1::Root(root)) => {
+                    Some(SearchSuggestion {
+                        item_ty: root.ty,
+                        item_id: root.item_id,
+                        document_box: root.document_box,
+                        name: root.name,
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+
+        Ok(SuggestResults { suggestions })
+    }
+
     async fn search_index_file(
         &self,
         scope: &DocumentBoxScopeRaw,
@@ -350,6 +524,7 @@ impl SearchIndex for TypesenseIndex {
         query: SearchRequest,
         folder_children: Option<Vec<FolderId>>,
     ) -> Result<SearchResults, SearchError> {
+        let facet_fields = query.facet_fields();
         let mut query_by = Vec::new();
 
         // Query file name
@@ -357,8 +532,11 @@ impl SearchIndex for TypesenseIndex {
             query_by.push("name");
         }
 
-        // Querying within content (link value and page content)
+        // Querying within content (summary, link value and page content)
         if query.include_content {
+            // Typesense weights fields by their order, the summary is placed
+            // first so that summary matches are boosted above page matches
+            query_by.push("summary");
             query_by.push("value");
             query_by.push("page_content");
         }
@@ -392,8 +570,11 @@ impl SearchIndex for TypesenseIndex {
         let max_pages = query.max_pages.unwrap_or(3);
 
         let query_by = query_by.join(",");
+        let timeout_ms = query.timeout_ms;
+        let explain = query.explain || query.dry_run;
+        let dry_run = query.dry_run;
 
-        let query_json = json!({
+        let mut query_json = json!({
             "searches": [
                 {
                     "collection": self.index,
@@ -404,8 +585,8 @@ impl SearchIndex for TypesenseIndex {
                     "offset": offset,
                     "limit": size,
                     "filter_by": filter_by,
-                    "exclude_fields": "page_content",
-                    "highlight_fields": "name,value,page_content",
+                    "exclude_fields": "page_content,summary",
+                    "highlight_fields": "name,summary,value,page_content",
                     "highlight_start_tag": "<em>",
                     "highlight_end_tag": "</em>",
                     "max_filter_by_candidates": max_filter_by_candidates
@@ -413,21 +594,72 @@ impl SearchIndex for TypesenseIndex {
             ]
         });
 
+        // Facet counts are computed over the grouped items
+        if !facet_fields.is_empty() {
+            let facet_by: Vec<&str> = facet_fields
+                .iter()
+                .map(|field| field.field_name())
+                .collect();
+            query_json["searches"][0]["facet_by"] = json!(facet_by.join(","));
+            query_json["searches"][0]["max_facet_values"] = json!(MAX_FACET_BUCKETS);
+        }
+
+        // Typesense will stop searching and return the partial results collected
+        // once the cutoff is reached
+        if let Some(timeout_ms) = timeout_ms {
+            query_json["searches"][0]["search_cutoff_ms"] = json!(timeout_ms);
+        }
+
         tracing::debug!(?query_json, "performing search query");
 
+        let explain = explain.then(|| SearchExplain {
+            backend: "typesense".to_string(),
+            query: query_json.clone(),
+        });
+
+        if dry_run {
+            return Ok(SearchResults {
+                total_hits: 0,
+                results: Vec::new(),
+                timed_out: false,
+                explain,
+                facets: Vec::new(),
+            });
+        }
+
         let api_key = self.client_data.api_key_provider.get_api_key().await?;
 
-        let response = self
+        let mut request = self
             .client
             .post(format!("{}/multi_search", self.client_data.base_url))
             .header("x-typesense-api-key", api_key)
-            .json(&query_json)
-            .send()
-            .await
-            .map_err(|error| {
+            .json(&query_json);
+
+        if let Some(timeout_ms) = timeout_ms {
+            // Allow a grace period on top of the search cutoff for the
+            // partial response to make its way back
+            request = request.timeout(Duration::from_millis(
+                timeout_ms.saturating_add(TIMEOUT_GRACE_PERIOD_MS),
+            ));
+        }
+
+        let response = match request.send().await {
+            Ok(value) => value,
+            Err(error) if error.is_timeout() => {
+                tracing::warn!(?timeout_ms, "typesense search request timed out");
+                return Ok(SearchResults {
+                    total_hits: 0,
+                    results: Vec::new(),
+                    timed_out: true,
+                    explain,
+                    facets: Vec::new(),
+                });
+            }
+            Err(error) => {
                 tracing::error!(?error, "failed to query typesense multi_search");
-                TypesenseSearchError::SearchIndex
-            })?;
+                return Err(TypesenseSearchError::SearchIndex.into());
+            }
+        };
 
         if let Err(error) = response.error_for_status_ref() {
             let body = response.text().await;
@@ -454,6 +686,29 @@ impl SearchIndex for TypesenseIndex {
             .ok_or(TypesenseSearchError::MissingSearchResult)?;
 
         let total_hits = search.found;
+        let timed_out = search.search_cutoff;
+
+        let facets = search
+            .facet_counts
+            .into_iter()
+            .filter_map(|facet| {
+                Some(SearchFacet {
+                    field: SearchFacetField::from_field_name(&facet.field_name)?,
+                    buckets: facet
+                        .counts
+                        .into_iter()
+                        .map(|count| SearchFacetBucket {
+                            value: count.value,
+                            count: count.count,
+                        })
+                        .collect(),
+                })
+            })
+            .collect();
+
+        if timed_out {
+            tracing::warn!("typesense search was cut off, returning partial results");
+        }
 
         let results = search
             .grouped_hits
@@ -483,7 +738,10 @@ impl SearchIndex for TypesenseIndex {
                         // Check for content matches
                         let content_match = group.hits.iter().any(|hit| {
                             hit.highlights.iter().any(|highlight| {
-                                highlight.field == "value" || highlight.field == "page_content"
+                                matches!(
+                                    highlight.field.as_str(),
+                                    "summary" | "value" | "page_content"
+                                )
                             })
                         });
 
@@ -507,6 +765,22 @@ impl SearchIndex for TypesenseIndex {
                             })
                             .collect();
 
+                        // Typesense provides the breakdown of the text match score
+                        // for each hit within the group
+                        let explanation = explain.is_some().then(|| {
+                            json!({
+                                "text_match": group_score,
+                                "hits": group
+                                    .hits
+                                    .iter()
+                                    .map(|hit| json!({
+                                        "text_match": hit.text_match,
+                                        "text_match_info": hit.text_match_info,
+                                    }))
+                                    .collect::<Vec<_>>(),
+                            })
+                        });
+
                         Some(FlattenedItemResult {
                             item_ty: root.ty,
                             item_id: root.item_id,
@@ -516,6 +790,7 @@ impl SearchIndex for TypesenseIndex {
                             score: SearchScore::Integer(group_score),
                             name_match,
                             content_match,
+                            explanation,
                         })
                     }
                 }
@@ -525,6 +800,9 @@ impl SearchIndex for TypesenseIndex {
         Ok(SearchResults {
             total_hits,
             results,
+            timed_out,
+            explain,
+            facets,
         })
     }
 
@@ -539,9 +817,14 @@ impl SearchIndex for TypesenseIndex {
                 item_id: data.item_id,
                 created_at: data.created_at.timestamp(),
                 created_by: data.created_by,
+                pinned: data.pinned,
                 value: data.content,
                 mime: data.mime,
                 name: data.name,
+                summary: data.summary,
+                word_count: data.text_stats.map(|stats| stats.word_count),
+                character_count: data.text_stats.map(|stats| stats.character_count),
+                reading_time_seconds: data.text_stats.map(|stats| stats.reading_time_seconds),
             };
 
             // When its a file with page data
@@ -594,6 +877,12 @@ impl SearchIndex for TypesenseIndex {
                 mime: root.mime.clone(),
                 created_at: root.created_at,
                 created_by: root.created_by.clone(),
+                pinned: data.pinned,
+                // Summaries are only stored on the root entry
+                summary: None,
+                word_count: root.word_count,
+                character_count: root.character_count,
+                reading_time_seconds: root.reading_time_seconds,
             };
 
             // Create the documents for the pages
@@ -659,9 +948,13 @@ impl SearchIndex for TypesenseIndex {
 
     async fn get_pending_migrations(
         &self,
-        _applied_names: Vec<String>,
+        applied_names: Vec<String>,
     ) -> Result<Vec<String>, SearchError> {
-        Ok(Vec::new())
+        Ok(TYPESENSE_MIGRATIONS
+            .iter()
+            .filter(|migration_name| !applied_names.iter().any(|name| name.eq(*migration_name)))
+            .map(|migration_name| migration_name.to_string())
+            .collect())
     }
 
     async fn apply_migration(
@@ -669,13 +962,90 @@ impl SearchIndex for TypesenseIndex {
         _tenant: &Tenant,
         _root_t: &mut DbTransaction<'_>,
         _t: &mut DbTransaction<'_>,
-        _name: &str,
+        name: &str,
     ) -> Result<(), SearchError> {
-        Ok(())
+        self.apply_schema_migration(name).await
     }
 }
 
 impl TypesenseIndex {
+    /// Add the locale and stemming options for the configured language
+    /// to the schema of a text `field`
+    fn text_field(&self, mut field: serde_json::Value) -> serde_json::Value {
+        if let (Some(language), Some(options)) = (self.language, field.as_object_mut()) {
+            options.insert("locale".to_string(), json!(language.locale()));
+            options.insert("stem".to_string(), json!(true));
+        }
+        field
+    }
+
+    /// Apply the schema changes for the migration `name`
+    async fn apply_schema_migration(&self, name: &str) -> Result<(), SearchError> {
+        match name {
+            "m1_typesense_add_pinned_field" => {
+                self.add_schema_fields(json!([
+                    { "name": "pinned", "type": "bool", "optional": true, "facet": true }
+                ]))
+                .await?;
+            }
+            "m2_typesense_add_summary_field" => {
+                self.add_schema_fields(json!([self.text_field(
+                    json!({ "name": "summary", "type": "string", "optional": true })
+                )]))
+                .await?;
+            }
+            "m3_typesense_add_text_stats_fields" => {
+                self.add_schema_fields(json!([
+                    { "name": "word_count", "type": "int64", "optional": true },
+                    { "name": "character_count", "type": "int64", "optional": true },
+                    { "name": "reading_time_seconds", "type": "int64", "optional": true }
+                ]))
+                .await?;
+            }
+            "m4_typesense_facet_mime_field" => {
+                // Fields cannot be altered, dropping and adding the field within
+                // the same request re-indexes the existing values
+                self.add_schema_fields(json!([
+                    { "name": "mime", "drop": true },
+                    { "name": "mime", "type": "string", "optional": true, "facet": true }
+                ]))
+                .await?;
+            }
+            _ => return Err(TypesenseSearchError::MigrationNotFound.into()),
+        }
+
+        Ok(())
+    }
+
+    /// Add new `fields` to the schema of the collection
+    async fn add_schema_fields(
+        &self,
+        fields: serde_json::Value,
+    ) -> Result<(), TypesenseSearchError> {
+        let api_key = self.client_data.api_key_provider.get_api_key().await?;
+
+        self.client
+            .patch(format!(
+                "{}/collections/{}",
+                self.client_data.base_url, self.index
+            ))
+            .header("x-typesense-api-key", api_key)
+            .json(&json!({ "fields": fields }))
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to update collection schema (request)");
+                TypesenseSearchError::UpdateSchema
+            })?
+            .error_for_status()
+            .map_err(|error| {
+                tracing::error!(?error, "failed to update collection schema (response)");
+                TypesenseSearchError::UpdateSchema
+            })?;
+
+        Ok(())
+    }
+
     /// Bulk insert typesense documents
     async fn bulk_add_documents(
         &self,
@@ -753,12 +1123,18 @@ impl TypesenseIndex {
     ) -> Result<(), TypesenseSearchError> {
         let api_key = self.client_data.api_key_provider.get_api_key().await?;
 
-        let request = json!({
+        let mut request = json!({
             "folder_id": update.folder_id,
             "name": update.name,
             "value": update.content,
+            "pinned": update.pinned,
         });
 
+        // Move the documents to the new document box
+        if let Some(document_box) = update.document_box.as_ref() {
+            request["document_box"] = json!(document_box);
+        }
+
         // Update all the existing items so they have the current root data
         self.client
             .patch(format!(
@@ -836,7 +1212,11 @@ fn create_item_page(root: &TypesenseDataEntryRootV1, page: DocumentPage) -> Type
     TypesenseEntry {
         id: Uuid::new_v4(),
         entry: TypesenseDataEntry::V1(TypesenseDataEntryV1::Page(TypesenseDataEntryPageV1 {
-            root: root.clone(),
+            // Summaries are only stored on the root entry
+            root: TypesenseDataEntryRootV1 {
+                summary: None,
+                ..root.clone()
+            },
             page: page.page,
             page_content: Some(page.content),
         })),
@@ -898,5 +1278,54 @@ fn create_search_filters(
         filter_parts.push(format!(r#"folder_id:="{folder_id}""#));
     }
 
+    if let Some(pinned) = query.pinned {
+        filter_parts.push(format!("pinned:={pinned}"));
+    }
+
+    if let Some(advanced) = query.advanced.as_ref() {
+        filter_parts.push(create_advanced_filter(advanced));
+    }
+
     filter_parts.join("&&")
 }
+
+/// Compile an advanced query into a typesense filter expression
+fn create_advanced_filter(query: &AdvancedSearchQuery) -> String {
+    match query {
+        AdvancedSearchQuery::And { queries } => {
+            let filters = queries.iter().map(create_advanced_filter).join("&&");
+            format!("({filters})")
+        }
+        AdvancedSearchQuery::Or { queries } => {
+            let filters = queries.iter().map(create_advanced_filter).join("||");
+            format!("({filters})")
+        }
+        AdvancedSearchQuery::Not { query } => {
+            format!("!({})", create_advanced_filter(query))
+        }
+        // Matches names containing the words of the value
+        AdvancedSearchQuery::NameContains { value } => {
+            format!("name:{}", escape_typesense_value(value))
+        }
+        AdvancedSearchQuery::MimeIn { values } => {
+            let values = values
+                .iter()
+                .map(|value| escape_typesense_value(value))
+                .join(", ");
+            format!("mime:=[{values}]")
+        }
+        AdvancedSearchQuery::CreatedBetween { start, end } => match (start, end) {
+            (Some(start), Some(end)) => {
+                format!("created_at:[{}..{}]", start.timestamp(), end.timestamp())
+            }
+            (Some(start), None) => format!("created_at:>={}", start.timestamp()),
+            (None, Some(end)) => format!("created_at:<={}", end.timestamp()),
+            // Unbounded range matches everything
+            (None, None) => "(created_at:>=0||created_at:<0)".to_string(),
+        },
+        AdvancedSearchQuery::CreatedByEquals { value } => {
+            format!("created_by:={}", escape_typesense_value(value))
+        }
+        AdvancedSearchQuery::PinnedEquals { value } => format!("pinned:={value}"),
+    }
+}
@@ -63,6 +63,22 @@ pub struct TypesenseDataEntryRootV1 {
     pub created_at: i64,
     /// User who created the item
     pub created_by: Option<UserId>,
+    /// Whether the item is pinned
+    #[serde(default)]
+    pub pinned: bool,
+    /// Generated summary of the item content, only present on the
+    /// root entry (Ignored when loading results back)
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// Number of words within the item content
+    #[serde(default)]
+    pub word_count: Option<i64>,
+    /// Number of characters within the item content
+    #[serde(default)]
+    pub character_count: Option<i64>,
+    /// Estimated time to read the item content in seconds
+    #[serde(default)]
+    pub reading_time_seconds: Option<i64>,
 }
 
 /// Page entry for an item page
@@ -96,6 +112,24 @@ pub struct GenericSearchResponse {
 pub struct GroupedSearchResponse {
     pub found: u64,
     pub grouped_hits: Vec<GroupedHits>,
+    /// Whether the search was cut off by the `search_cutoff_ms` parameter
+    #[serde(default)]
+    pub search_cutoff: bool,
+    /// Counts for the `facet_by` fields, only present when facets were requested
+    #[serde(default)]
+    pub facet_counts: Vec<FacetCounts>,
+}
+
+#[derive(Deserialize)]
+pub struct FacetCounts {
+    pub field_name: String,
+    pub counts: Vec<FacetCount>,
+}
+
+#[derive(Deserialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: u64,
 }
 
 #[derive(Deserialize)]
@@ -109,6 +143,9 @@ pub struct Hit {
     pub document: TypesenseDataEntry,
     pub highlights: Vec<Highlight>,
     pub text_match: u64,
+    /// Breakdown of the text match score
+    #[serde(default)]
+    pub text_match_info: Option<serde_json::Value>,
 }
 
 #[derive(Deserialize)]
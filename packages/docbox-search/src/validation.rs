@@ -0,0 +1,445 @@
+//! # Search Validation
+//!
+//! Centralized validation for search requests, applied before any request
+//! is forwarded to the underlying search backend so that malformed or abusive
+//! queries are rejected early with a typed [SearchValidationError]
+
+use crate::{
+    models::{AdvancedSearchQuery, FileSearchRequest, SearchRequest, SuggestRequest},
+    query_dsl::{QueryDslError, apply_query_dsl},
+};
+use docbox_database::models::document_box::DocumentBoxScopeRaw;
+use thiserror::Error;
+
+/// Maximum number of characters allowed within a search query
+pub const MAX_QUERY_LENGTH: usize = 1024;
+
+/// Maximum number of results that can be requested at once
+pub const MAX_SEARCH_SIZE: u16 = 500;
+
+/// Maximum number of suggestions that can be requested at once
+pub const MAX_SUGGEST_SIZE: u16 = 50;
+
+/// Maximum offset that can be used when paginating results
+pub const MAX_SEARCH_OFFSET: u64 = 10_000;
+
+/// Maximum number of document box scopes a single search can target
+pub const MAX_SEARCH_SCOPES: usize = 100;
+
+/// Maximum timeout that can be requested for a search
+pub const MAX_SEARCH_TIMEOUT_MS: u64 = 60_000;
+
+/// Maximum nesting depth of an advanced search query
+pub const MAX_ADVANCED_QUERY_DEPTH: usize = 8;
+
+/// Maximum number of nodes within an advanced search query
+pub const MAX_ADVANCED_QUERY_NODES: usize = 100;
+
+#[derive(Debug, Error)]
+pub enum SearchValidationError {
+    #[error("search query exceeds the maximum length of {MAX_QUERY_LENGTH} characters")]
+    QueryTooLong,
+
+    #[error("requested result size exceeds the maximum of {MAX_SEARCH_SIZE}")]
+    SizeTooLarge,
+
+    #[error("requested suggestion size exceeds the maximum of {MAX_SUGGEST_SIZE}")]
+    SuggestSizeTooLarge,
+
+    #[error("requested offset exceeds the maximum of {MAX_SEARCH_OFFSET}")]
+    OffsetTooLarge,
+
+    #[error("requested timeout exceeds the maximum of {MAX_SEARCH_TIMEOUT_MS}ms")]
+    TimeoutTooLarge,
+
+    #[error("search must target at least one scope")]
+    MissingScopes,
+
+    #[error("search cannot target more than {MAX_SEARCH_SCOPES} scopes")]
+    TooManyScopes,
+
+    #[error("invalid wildcard scope pattern \"{0}\", only a single trailing wildcard is allowed")]
+    InvalidWildcardScope(String),
+
+    #[error("advanced query exceeds the maximum depth of {MAX_ADVANCED_QUERY_DEPTH}")]
+    AdvancedQueryTooDeep,
+
+    #[error("advanced query exceeds the maximum of {MAX_ADVANCED_QUERY_NODES} nodes")]
+    AdvancedQueryTooLarge,
+
+    #[error("invalid advanced query: {0}")]
+    InvalidAdvancedQuery(&'static str),
+
+    #[error("invalid query dsl: {0}")]
+    InvalidQueryDsl(QueryDslError),
+}
+
+/// Validate a search request targeting the provided `scopes`
+///
+/// The [SearchRequest::query_dsl] is parsed and combined into the
+/// [SearchRequest::advanced] query as part of validation so that the
+/// limits apply to the exact query the search backend receives
+pub fn validate_search_request(
+    scopes: &[DocumentBoxScopeRaw],
+    request: &mut SearchRequest,
+) -> Result<(), SearchValidationError> {
+    validate_scopes(scopes)?;
+    validate_query(request.query.as_deref())?;
+
+    if request.size.is_some_and(|size| size > MAX_SEARCH_SIZE) {
+        return Err(SearchValidationError::SizeTooLarge);
+    }
+
+    if request
+        .offset
+        .is_some_and(|offset| offset > MAX_SEARCH_OFFSET)
+        || request
+            .pages_offset
+            .is_some_and(|offset| offset > MAX_SEARCH_OFFSET)
+    {
+        return Err(SearchValidationError::OffsetTooLarge);
+    }
+
+    if request
+        .timeout_ms
+        .is_some_and(|timeout_ms| timeout_ms > MAX_SEARCH_TIMEOUT_MS)
+    {
+        return Err(SearchValidationError::TimeoutTooLarge);
+    }
+
+    validate_query(request.query_dsl.as_deref())?;
+    apply_query_dsl(request).map_err(SearchValidationError::InvalidQueryDsl)?;
+
+    if let Some(advanced) = request.advanced.as_ref() {
+        let mut nodes = 0;
+        validate_advanced_query(advanced, 1, &mut nodes)?;
+    }
+
+    Ok(())
+}
+
+/// Validate a search request targeting a specific file
+pub fn validate_file_search_request(
+    request: &FileSearchRequest,
+) -> Result<(), SearchValidationError> {
+    validate_query(request.query.as_deref())?;
+
+    if request.limit.is_some_and(|limit| limit > MAX_SEARCH_SIZE) {
+        return Err(SearchValidationError::SizeTooLarge);
+    }
+
+    if request
+        .offset
+        .is_some_and(|offset| offset > MAX_SEARCH_OFFSET)
+    {
+        return Err(SearchValidationError::OffsetTooLarge);
+    }
+
+    Ok(())
+}
+
+/// Validate a suggest request targeting the provided `scopes`
+pub fn validate_suggest_request(
+    scopes: &[DocumentBoxScopeRaw],
+    request: &SuggestRequest,
+) -> Result<(), SearchValidationError> {
+    validate_scopes(scopes)?;
+    validate_query(Some(&request.query))?;
+
+    if request.size.is_some_and(|size| size > MAX_SUGGEST_SIZE) {
+        return Err(SearchValidationError::SuggestSizeTooLarge);
+    }
+
+    Ok(())
+}
+
+fn validate_query(query: Option<&str>) -> Result<(), SearchValidationError> {
+    if query.is_some_and(|query| query.chars().count() > MAX_QUERY_LENGTH) {
+        return Err(SearchValidationError::QueryTooLong);
+    }
+
+    Ok(())
+}
+
+fn validate_advanced_query(
+    query: &AdvancedSearchQuery,
+    depth: usize,
+    nodes: &mut usize,
+) -> Result<(), SearchValidationError> {
+    if depth > MAX_ADVANCED_QUERY_DEPTH {
+        return Err(SearchValidationError::AdvancedQueryTooDeep);
+    }
+
+    *nodes += 1;
+    if *nodes > MAX_ADVANCED_QUERY_NODES {
+        return Err(SearchValidationError::AdvancedQueryTooLarge);
+    }
+
+    match query {
+        AdvancedSearchQuery::And { queries } | AdvancedSearchQuery::Or { queries } => {
+            if queries.is_empty() {
+                return Err(SearchValidationError::InvalidAdvancedQuery(
+                    "boolean queries must contain at least one query",
+                ));
+            }
+
+            for query in queries {
+                validate_advanced_query(query, depth + 1, nodes)?;
+            }
+        }
+        AdvancedSearchQuery::Not { query } => {
+            validate_advanced_query(query, depth + 1, nodes)?;
+        }
+        AdvancedSearchQuery::NameContains { value } => {
+            if value.is_empty() {
+                return Err(SearchValidationError::InvalidAdvancedQuery(
+                    "name contains value cannot be empty",
+                ));
+            }
+
+            validate_query(Some(value))?;
+        }
+        AdvancedSearchQuery::MimeIn { values } => {
+            if values.is_empty() {
+                return Err(SearchValidationError::InvalidAdvancedQuery(
+                    "mime values cannot be empty",
+                ));
+            }
+        }
+        AdvancedSearchQuery::CreatedBetween { start, end } => match (start, end) {
+            (None, None) => {
+                return Err(SearchValidationError::InvalidAdvancedQuery(
+                    "date range must have a start or end point",
+                ));
+            }
+            (Some(start), Some(end)) if start > end => {
+                return Err(SearchValidationError::InvalidAdvancedQuery(
+                    "date range start cannot be after end",
+                ));
+            }
+            _ => {}
+        },
+        AdvancedSearchQuery::CreatedByEquals { .. } | AdvancedSearchQuery::PinnedEquals { .. } => {}
+    }
+
+    Ok(())
+}
+
+fn validate_scopes(scopes: &[DocumentBoxScopeRaw]) -> Result<(), SearchValidationError> {
+    if scopes.is_empty() {
+        return Err(SearchValidationError::MissingScopes);
+    }
+
+    if scopes.len() > MAX_SEARCH_SCOPES {
+        return Err(SearchValidationError::TooManyScopes);
+    }
+
+    for scope in scopes {
+        if !is_valid_scope_pattern(scope) {
+            return Err(SearchValidationError::InvalidWildcardScope(scope.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Wildcards are only supported as a single trailing "*" following a non-empty
+/// prefix (i.e "user:1:*"), a bare "*" would match every scope in the tenant
+fn is_valid_scope_pattern(scope: &str) -> bool {
+    match scope.find('*') {
+        None => true,
+        Some(index) => index > 0 && index == scope.len() - 1,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        MAX_ADVANCED_QUERY_DEPTH, MAX_QUERY_LENGTH, MAX_SEARCH_SCOPES, MAX_SUGGEST_SIZE,
+        SearchValidationError, is_valid_scope_pattern, validate_search_request,
+        validate_suggest_request,
+    };
+    use crate::models::{AdvancedSearchQuery, SearchRequest, SuggestRequest};
+
+    #[test]
+    fn test_scope_patterns() {
+        assert!(is_valid_scope_pattern("user:1:files"));
+        assert!(is_valid_scope_pattern("user:1:*"));
+        assert!(!is_valid_scope_pattern("*"));
+        assert!(!is_valid_scope_pattern("user:*:files"));
+        assert!(!is_valid_scope_pattern("user:1:**"));
+    }
+
+    #[test]
+    fn test_query_too_long() {
+        let mut request = SearchRequest {
+            query: Some("a".repeat(MAX_QUERY_LENGTH + 1)),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            validate_search_request(&["test".to_string()], &mut request),
+            Err(SearchValidationError::QueryTooLong)
+        ));
+    }
+
+    #[test]
+    fn test_suggest_size_too_large() {
+        let request = SuggestRequest {
+            query: "rep".to_string(),
+            size: Some(MAX_SUGGEST_SIZE + 1),
+        };
+
+        assert!(matches!(
+            validate_suggest_request(&["test".to_string()], &request),
+            Err(SearchValidationError::SuggestSizeTooLarge)
+        ));
+    }
+
+    #[test]
+    fn test_too_many_scopes() {
+        let scopes: Vec<String> = (0..=MAX_SEARCH_SCOPES)
+            .map(|index| format!("scope:{index}"))
+            .collect();
+
+        assert!(matches!(
+            validate_search_request(&scopes, &mut SearchRequest::default()),
+            Err(SearchValidationError::TooManyScopes)
+        ));
+    }
+
+    #[test]
+    fn test_advanced_query() {
+        let mut request = SearchRequest {
+            advanced: Some(AdvancedSearchQuery::And {
+                queries: vec![
+                    AdvancedSearchQuery::NameContains {
+                        value: "report".to_string(),
+                    },
+                    AdvancedSearchQuery::Not {
+                        query: Box::new(AdvancedSearchQuery::PinnedEquals { value: true }),
+                    },
+                ],
+            }),
+            ..Default::default()
+        };
+
+        validate_search_request(&["test".to_string()], &mut request).unwrap();
+    }
+
+    #[test]
+    fn test_advanced_query_invalid() {
+        let mut request = SearchRequest {
+            advanced: Some(AdvancedSearchQuery::Or {
+                queries: Vec::new(),
+            }),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            validate_search_request(&["test".to_string()], &mut request),
+            Err(SearchValidationError::InvalidAdvancedQuery(_))
+        ));
+
+        let mut request = SearchRequest {
+            advanced: Some(AdvancedSearchQuery::CreatedBetween {
+                start: None,
+                end: None,
+            }),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            validate_search_request(&["test".to_string()], &mut request),
+            Err(SearchValidationError::InvalidAdvancedQuery(_))
+        ));
+    }
+
+    #[test]
+    fn test_advanced_query_too_deep() {
+        let mut query = AdvancedSearchQuery::PinnedEquals { value: true };
+        for _ in 0..MAX_ADVANCED_QUERY_DEPTH {
+            query = AdvancedSearchQuery::Not {
+                query: Box::new(query),
+            };
+        }
+
+        let mut request = SearchRequest {
+            advanced: Some(query),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            validate_search_request(&["test".to_string()], &mut request),
+            Err(SearchValidationError::AdvancedQueryTooDeep)
+        ));
+    }
+
+    #[test]
+    fn test_query_dsl_invalid() {
+        let mut request = SearchRequest {
+            query_dsl: Some("owner:bob".to_string()),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            validate_search_request(&["test".to_string()], &mut request),
+            Err(SearchValidationError::InvalidQueryDsl(_))
+        ));
+
+        let mut request = SearchRequest {
+            query_dsl: Some("-".repeat(MAX_ADVANCED_QUERY_DEPTH) + "pinned:true"),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            validate_search_request(&["test".to_string()], &mut request),
+            Err(SearchValidationError::AdvancedQueryTooDeep)
+        ));
+    }
+
+    #[test]
+    fn test_query_dsl_combined_too_deep() {
+        let mut query = AdvancedSearchQuery::PinnedEquals { value: true };
+        for _ in 1..MAX_ADVANCED_QUERY_DEPTH {
+            query = AdvancedSearchQuery::Not {
+                query: Box::new(query),
+            };
+        }
+
+        let mut request = SearchRequest {
+            advanced: Some(query.clone()),
+            ..Default::default()
+        };
+
+        validate_search_request(&["test".to_string()], &mut request).unwrap();
+
+        // Combining with the query dsl wraps the advanced query in another level
+        let mut request = SearchRequest {
+            advanced: Some(query),
+            query_dsl: Some("pinned:true".to_string()),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            validate_search_request(&["test".to_string()], &mut request),
+            Err(SearchValidationError::AdvancedQueryTooDeep)
+        ));
+    }
+
+    #[test]
+    fn test_query_dsl_applied() {
+        let mut request = SearchRequest {
+            query_dsl: Some("pinned:true".to_string()),
+            ..Default::default()
+        };
+
+        validate_search_request(&["test".to_string()], &mut request).unwrap();
+
+        assert!(request.query_dsl.is_none());
+        assert_eq!(
+            request.advanced,
+            Some(AdvancedSearchQuery::PinnedEquals { value: true })
+        );
+    }
+}
@@ -0,0 +1,69 @@
+#![cfg(all(feature = "chaos", feature = "memory"))]
+
+use crate::common::tenant::test_tenant;
+use chrono::Utc;
+use docbox_search::{
+    ChaosSearchConfig, ChaosSearchError, ChaosSearchIndexFactory, MemorySearchIndexFactory,
+    SearchError, SearchIndexFactory, SearchOperation,
+    models::{SearchIndexData, SearchIndexType, SearchRequest},
+};
+use uuid::Uuid;
+
+mod common;
+
+/// Tests that configured operations fail without modifying the index and
+/// recover once the config is cleared
+#[tokio::test]
+async fn test_chaos_search_injected_failure() {
+    let chaos = ChaosSearchIndexFactory::new(
+        SearchIndexFactory::Memory(MemorySearchIndexFactory::new()),
+        ChaosSearchConfig::fail([SearchOperation::AddData]),
+    );
+    let index = SearchIndexFactory::Chaos(chaos.clone()).create_search_index(&test_tenant());
+
+    // Operations not included in the config are unaffected
+    index.create_index().await.unwrap();
+
+    let data = SearchIndexData {
+        ty: SearchIndexType::File,
+        folder_id: Uuid::new_v4(),
+        document_box: "test".to_string(),
+        item_id: Uuid::new_v4(),
+        name: "test.txt".to_string(),
+        mime: Some("text/plain".to_string()),
+        content: None,
+        created_at: Utc::now(),
+        created_by: None,
+        pinned: false,
+        pages: None,
+        summary: None,
+        text_stats: None,
+    };
+
+    let error = index.add_data(vec![data.clone()]).await.unwrap_err();
+    assert!(matches!(
+        error,
+        SearchError::Chaos(ChaosSearchError::InjectedFault(SearchOperation::AddData))
+    ));
+
+    let request = || SearchRequest {
+        query: Some("test".to_string()),
+        include_name: true,
+        ..Default::default()
+    };
+
+    let results = index
+        .search_index(&["test".to_string()], request(), None)
+        .await
+        .unwrap();
+    assert_eq!(results.total_hits, 0);
+
+    chaos.set_config(ChaosSearchConfig::default());
+    index.add_data(vec![data]).await.unwrap();
+
+    let results = index
+        .search_index(&["test".to_string()], request(), None)
+        .await
+        .unwrap();
+    assert_eq!(results.total_hits, 1);
+}
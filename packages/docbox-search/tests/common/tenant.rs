@@ -13,5 +13,13 @@ pub fn test_tenant() -> Tenant {
         os_index_name: "test".to_string(),
         env: "Development".to_string(),
         event_queue_url: None,
+        storage_key_secret_name: None,
+        storage_deduplication: false,
+        event_config: None,
+        data_region: None,
+        maintenance_mode: false,
+        active_content_policy: Default::default(),
+        deleted_at: None,
+        search_language: None,
     }
 }
@@ -0,0 +1,572 @@
+#![cfg(feature = "memory")]
+
+use crate::common::tenant::test_tenant;
+use chrono::Utc;
+use docbox_search::{
+    MemorySearchIndexFactory, SearchError, SearchIndexFactory,
+    models::{
+        AdvancedSearchQuery, DocumentPage, FileSearchRequest, SearchFacetBucket, SearchFacetField,
+        SearchIndexData, SearchIndexType, SearchRequest, SuggestRequest, UpdateSearchIndexData,
+    },
+};
+use uuid::Uuid;
+
+mod common;
+
+fn test_file_data(document_box: &str, name: &str, pages: &[&str]) -> SearchIndexData {
+    SearchIndexData {
+        ty: SearchIndexType::File,
+        folder_id: Uuid::new_v4(),
+        document_box: document_box.to_string(),
+        item_id: Uuid::new_v4(),
+        name: name.to_string(),
+        mime: Some("text/plain".to_string()),
+        content: None,
+        created_at: Utc::now(),
+        created_by: None,
+        pinned: false,
+        pages: Some(
+            pages
+                .iter()
+                .enumerate()
+                .map(|(page, content)| DocumentPage {
+                    page: page as u64,
+                    content: content.to_string(),
+                    words: None,
+                })
+                .collect(),
+        ),
+        summary: None,
+        text_stats: None,
+    }
+}
+
+/// Tests that index_exists() reports the correct state for an index
+#[tokio::test]
+async fn test_memory_index_exists() {
+    let search = SearchIndexFactory::Memory(MemorySearchIndexFactory::new());
+    let index = search.create_search_index(&test_tenant());
+
+    assert!(!index.index_exists().await.unwrap());
+
+    index.create_index().await.unwrap();
+    assert!(index.index_exists().await.unwrap());
+
+    index.delete_index().await.unwrap();
+    assert!(!index.index_exists().await.unwrap());
+}
+
+/// Tests that heal_index() only recreates an index that no longer exists
+#[tokio::test]
+async fn test_memory_heal_index() {
+    let search = SearchIndexFactory::Memory(MemorySearchIndexFactory::new());
+    let index = search.create_search_index(&test_tenant());
+
+    index.create_index().await.unwrap();
+    assert!(!index.heal_index().await.unwrap());
+
+    // Index deleted out-of-band
+    index.delete_index().await.unwrap();
+
+    assert!(index.heal_index().await.unwrap());
+    assert!(index.index_exists().await.unwrap());
+    assert!(!index.heal_index().await.unwrap());
+}
+
+/// Tests searching names and content of indexed items
+#[tokio::test]
+async fn test_memory_search_index() {
+    let search = SearchIndexFactory::Memory(MemorySearchIndexFactory::new());
+    let index = search.create_search_index(&test_tenant());
+    index.create_index().await.unwrap();
+
+    let invoice = test_file_data("user:1:files", "Invoice.txt", &["Total due", "Paid"]);
+    let report = test_file_data("user:1:files", "Report.txt", &["Invoice summary"]);
+    let other = test_file_data("user:2:files", "Invoice.txt", &["Total due"]);
+    let invoice_id = invoice.item_id;
+    let report_id = report.item_id;
+
+    index.add_data(vec![invoice, report, other]).await.unwrap();
+
+    let results = index
+        .search_index(
+            &["user:1:files".to_string()],
+            SearchRequest {
+                query: Some("invoice".to_string()),
+                include_name: true,
+                include_content: true,
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(results.total_hits, 2);
+    let result_ids: Vec<Uuid> = results.results.iter().map(|item| item.item_id).collect();
+    assert!(result_ids.contains(&invoice_id));
+    assert!(result_ids.contains(&report_id));
+
+    // Wildcard scopes match all scopes with the prefix
+    let results = index
+        .search_index(
+            &["user:*".to_string()],
+            SearchRequest {
+                query: Some("total".to_string()),
+                include_content: true,
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.total_hits, 2);
+
+    let results = index
+        .search_index_file(
+            &"user:1:files".to_string(),
+            invoice_id,
+            FileSearchRequest {
+                query: Some("paid".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.total_hits, 1);
+    assert_eq!(results.results[0].page, 1);
+
+    index.delete_by_scope("user:1:files").await.unwrap();
+
+    let results = index
+        .search_index(
+            &["user:1:files".to_string()],
+            SearchRequest::default(),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.total_hits, 0);
+}
+
+/// Tests that matches against the generated summary are boosted above page matches
+#[tokio::test]
+async fn test_memory_search_index_summary_boosted() {
+    let search = SearchIndexFactory::Memory(MemorySearchIndexFactory::new());
+    let index = search.create_search_index(&test_tenant());
+    index.create_index().await.unwrap();
+
+    let contract = test_file_data("user:1:files", "Contract.pdf", &["Lease terms"]);
+    let agreement = SearchIndexData {
+        summary: Some("Residential lease agreement for a two bedroom unit".to_string()),
+        ..test_file_data("user:1:files", "Agreement.pdf", &["Signed by both parties"])
+    };
+    let contract_id = contract.item_id;
+    let agreement_id = agreement.item_id;
+
+    index.add_data(vec![contract, agreement]).await.unwrap();
+
+    let scopes = ["user:1:files".to_string()];
+    let search_lease = |include_content| {
+        index.search_index(
+            &scopes,
+            SearchRequest {
+                query: Some("lease".to_string()),
+                include_content,
+                ..Default::default()
+            },
+            None,
+        )
+    };
+
+    let results = search_lease(true).await.unwrap();
+    let result_ids: Vec<Uuid> = results.results.iter().map(|item| item.item_id).collect();
+    assert_eq!(result_ids, vec![agreement_id, contract_id]);
+    assert!(results.results[0].content_match);
+    assert!(results.results[0].page_matches.is_empty());
+
+    // Summaries are only searched when searching content
+    let results = search_lease(false).await.unwrap();
+    assert_eq!(results.total_hits, 0);
+}
+
+/// Tests filtering search results by the pinned state
+#[tokio::test]
+async fn test_memory_search_index_pinned() {
+    let search = SearchIndexFactory::Memory(MemorySearchIndexFactory::new());
+    let index = search.create_search_index(&test_tenant());
+    index.create_index().await.unwrap();
+
+    let invoice = test_file_data("user:1:files", "Invoice.txt", &[]);
+    let report = SearchIndexData {
+        pinned: true,
+        ..test_file_data("user:1:files", "Report.txt", &[])
+    };
+    let invoice_id = invoice.item_id;
+    let report_id = report.item_id;
+
+    index.add_data(vec![invoice, report]).await.unwrap();
+
+    let scopes = ["user:1:files".to_string()];
+    let search_pinned = |pinned| {
+        index.search_index(
+            &scopes,
+            SearchRequest {
+                query: Some("txt".to_string()),
+                include_name: true,
+                pinned,
+                ..Default::default()
+            },
+            None,
+        )
+    };
+
+    let results = search_pinned(Some(true)).await.unwrap();
+    assert_eq!(results.total_hits, 1);
+    assert_eq!(results.results[0].item_id, report_id);
+
+    let results = search_pinned(Some(false)).await.unwrap();
+    assert_eq!(results.total_hits, 1);
+    assert_eq!(results.results[0].item_id, invoice_id);
+
+    let results = search_pinned(None).await.unwrap();
+    assert_eq!(results.total_hits, 2);
+
+    // Updating the item updates the pinned state
+    index
+        .update_data(
+            invoice_id,
+            UpdateSearchIndexData {
+                folder_id: Uuid::new_v4(),
+                name: "Invoice.txt".to_string(),
+                pinned: true,
+                content: None,
+                pages: None,
+                document_box: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    let results = search_pinned(Some(true)).await.unwrap();
+    assert_eq!(results.total_hits, 2);
+}
+
+/// Tests that updating the document box of an item moves it to the new scope
+#[tokio::test]
+async fn test_memory_search_index_update_scope() {
+    let search = SearchIndexFactory::Memory(MemorySearchIndexFactory::new());
+    let index = search.create_search_index(&test_tenant());
+    index.create_index().await.unwrap();
+
+    let invoice = test_file_data("user:1:files", "Invoice.txt", &[]);
+    let invoice_id = invoice.item_id;
+    let folder_id = invoice.folder_id;
+
+    index.add_data(vec![invoice]).await.unwrap();
+
+    index
+        .update_data(
+            invoice_id,
+            UpdateSearchIndexData {
+                folder_id,
+                name: "Invoice.txt".to_string(),
+                pinned: false,
+                content: None,
+                pages: None,
+                document_box: Some("user:2:files".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+
+    let request = || SearchRequest {
+        query: Some("Invoice".to_string()),
+        include_name: true,
+        ..Default::default()
+    };
+
+    let results = index
+        .search_index(&["user:1:files".to_string()], request(), None)
+        .await
+        .unwrap();
+    assert_eq!(results.total_hits, 0);
+
+    let results = index
+        .search_index(&["user:2:files".to_string()], request(), None)
+        .await
+        .unwrap();
+    assert_eq!(results.total_hits, 1);
+    assert_eq!(results.results[0].item_id, invoice_id);
+    assert_eq!(results.results[0].document_box, "user:2:files");
+}
+
+/// Tests filtering search results using an advanced query
+#[tokio::test]
+async fn test_memory_search_index_advanced() {
+    let search = SearchIndexFactory::Memory(MemorySearchIndexFactory::new());
+    let index = search.create_search_index(&test_tenant());
+    index.create_index().await.unwrap();
+
+    let invoice = test_file_data("user:1:files", "Invoice.txt", &[]);
+    let report = SearchIndexData {
+        mime: Some("application/pdf".to_string()),
+        pinned: true,
+        ..test_file_data("user:1:files", "Report.pdf", &[])
+    };
+    let notes = test_file_data("user:1:files", "Report Notes.txt", &[]);
+    let invoice_id = invoice.item_id;
+    let report_id = report.item_id;
+    let notes_id = notes.item_id;
+
+    index.add_data(vec![invoice, report, notes]).await.unwrap();
+
+    let scopes = ["user:1:files".to_string()];
+    let search_advanced = |advanced| {
+        index.search_index(
+            &scopes,
+            SearchRequest {
+                include_name: true,
+                advanced: Some(advanced),
+                ..Default::default()
+            },
+            None,
+        )
+    };
+
+    // Name contains "report" and is not pinned
+    let results = search_advanced(AdvancedSearchQuery::And {
+        queries: vec![
+            AdvancedSearchQuery::NameContains {
+                value: "REPORT".to_string(),
+            },
+            AdvancedSearchQuery::Not {
+                query: Box::new(AdvancedSearchQuery::PinnedEquals { value: true }),
+            },
+        ],
+    })
+    .await
+    .unwrap();
+    assert_eq!(results.total_hits, 1);
+    assert_eq!(results.results[0].item_id, notes_id);
+
+    // Mime is a pdf or name contains "invoice"
+    let results = search_advanced(AdvancedSearchQuery::Or {
+        queries: vec![
+            AdvancedSearchQuery::MimeIn {
+                values: vec!["application/pdf".to_string()],
+            },
+            AdvancedSearchQuery::NameContains {
+                value: "invoice".to_string(),
+            },
+        ],
+    })
+    .await
+    .unwrap();
+    assert_eq!(results.total_hits, 2);
+    assert!(results.results.iter().any(|item| item.item_id == report_id));
+    assert!(
+        results
+            .results
+            .iter()
+            .any(|item| item.item_id == invoice_id)
+    );
+
+    // Created in the future
+    let results = search_advanced(AdvancedSearchQuery::CreatedBetween {
+        start: Some(Utc::now() + chrono::Duration::days(1)),
+        end: None,
+    })
+    .await
+    .unwrap();
+    assert_eq!(results.total_hits, 0);
+}
+
+/// Tests filtering search results using a query dsl string
+#[tokio::test]
+async fn test_memory_search_index_query_dsl() {
+    let search = SearchIndexFactory::Memory(MemorySearchIndexFactory::new());
+    let index = search.create_search_index(&test_tenant());
+    index.create_index().await.unwrap();
+
+    let invoice = test_file_data("user:1:files", "Invoice.txt", &[]);
+    let report = SearchIndexData {
+        mime: Some("application/pdf".to_string()),
+        pinned: true,
+        ..test_file_data("user:1:files", "Report.pdf", &[])
+    };
+    let notes = test_file_data("user:1:files", "Report Notes.txt", &[]);
+    let invoice_id = invoice.item_id;
+    let notes_id = notes.item_id;
+
+    index.add_data(vec![invoice, report, notes]).await.unwrap();
+
+    let scopes = ["user:1:files".to_string()];
+    let search_dsl = |query_dsl: &str| {
+        index.search_index(
+            &scopes,
+            SearchRequest {
+                include_name: true,
+                query_dsl: Some(query_dsl.to_string()),
+                ..Default::default()
+            },
+            None,
+        )
+    };
+
+    let results = search_dsl("name:report NOT mime:application/pdf")
+        .await
+        .unwrap();
+    assert_eq!(results.total_hits, 1);
+    assert_eq!(results.results[0].item_id, notes_id);
+
+    let results = search_dsl("invoice OR (pinned:true -name:report)")
+        .await
+        .unwrap();
+    assert_eq!(results.total_hits, 1);
+    assert_eq!(results.results[0].item_id, invoice_id);
+
+    let result = search_dsl("owner:bob").await;
+    assert!(matches!(result, Err(SearchError::Validation(_))));
+}
+
+/// Tests that suggestions complete item names by prefix within the scopes
+#[tokio::test]
+async fn test_memory_suggest_index() {
+    let search = SearchIndexFactory::Memory(MemorySearchIndexFactory::new());
+    let index = search.create_search_index(&test_tenant());
+    index.create_index().await.unwrap();
+
+    let report = test_file_data("test", "Report.pdf", &[]);
+    let yearly = test_file_data("test", "Yearly report 2024.pdf", &[]);
+    let other = test_file_data("test", "Invoice.pdf", &[]);
+    let other_scope = test_file_data("other", "Report.pdf", &[]);
+
+    index
+        .add_data(vec![
+            report.clone(),
+            yearly.clone(),
+            other.clone(),
+            other_scope.clone(),
+        ])
+        .await
+        .unwrap();
+
+    let results = index
+        .suggest_index(
+            &["test".to_string()],
+            SuggestRequest {
+                query: "rep".to_string(),
+                size: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    // Full name prefix matches are suggested before word prefix matches
+    let ids: Vec<Uuid> = results
+        .suggestions
+        .iter()
+        .map(|suggestion| suggestion.item_id)
+        .collect();
+    assert_eq!(ids, vec![report.item_id, yearly.item_id]);
+    assert_eq!(results.suggestions[0].name, "Report.pdf");
+
+    // Number of suggestions is limited by the size
+    let results = index
+        .suggest_index(
+            &["test".to_string()],
+            SuggestRequest {
+                query: "rep".to_string(),
+                size: Some(1),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.suggestions.len(), 1);
+}
+
+/// Tests that facet counts are returned for all the matching items
+#[tokio::test]
+async fn test_memory_search_index_facets() {
+    let search = SearchIndexFactory::Memory(MemorySearchIndexFactory::new());
+    let index = search.create_search_index(&test_tenant());
+    index.create_index().await.unwrap();
+
+    let pdf = SearchIndexData {
+        mime: Some("application/pdf".to_string()),
+        ..test_file_data("test", "Report.pdf", &[])
+    };
+    let link = SearchIndexData {
+        ty: SearchIndexType::Link,
+        mime: None,
+        ..test_file_data("test", "Report link", &[])
+    };
+
+    index
+        .add_data(vec![
+            pdf,
+            link,
+            test_file_data("test", "Report.txt", &[]),
+            test_file_data("test", "Notes.txt", &[]),
+            test_file_data("test", "Invoice.txt", &[]),
+        ])
+        .await
+        .unwrap();
+
+    let results = index
+        .search_index(
+            &["test".to_string()],
+            SearchRequest {
+                query: Some("report".to_string()),
+                include_name: true,
+                size: Some(1),
+                facets: Some(vec![
+                    SearchFacetField::Mime,
+                    SearchFacetField::ItemType,
+                    SearchFacetField::Mime,
+                ]),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    // Facets count all the matches not just the returned page
+    assert_eq!(results.total_hits, 3);
+    assert_eq!(results.results.len(), 1);
+
+    // Duplicate facet fields are only counted once
+    assert_eq!(results.facets.len(), 2);
+
+    assert_eq!(results.facets[0].field, SearchFacetField::Mime);
+    assert_eq!(
+        results.facets[0].buckets,
+        vec![
+            SearchFacetBucket {
+                value: "application/pdf".to_string(),
+                count: 1
+            },
+            SearchFacetBucket {
+                value: "text/plain".to_string(),
+                count: 1
+            },
+        ]
+    );
+
+    assert_eq!(results.facets[1].field, SearchFacetField::ItemType);
+    assert_eq!(
+        results.facets[1].buckets,
+        vec![
+            SearchFacetBucket {
+                value: "File".to_string(),
+                count: 2
+            },
+            SearchFacetBucket {
+                value: "Link".to_string(),
+                count: 1
+            },
+        ]
+    );
+}
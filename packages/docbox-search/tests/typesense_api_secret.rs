@@ -26,6 +26,8 @@ async fn test_typesense_api_secret() {
         url,
         api_key: None,
         api_key_secret_name: Some(secret_name.to_string()),
+        data_region: None,
+        language: None,
     };
 
     // Make a secret manager with the required secret
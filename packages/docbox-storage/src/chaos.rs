@@ -0,0 +1,322 @@
+//! # Chaos Storage Backend
+//!
+//! Fault injection wrapper around another storage backend intended for testing,
+//! adds configurable latency and randomly fails operations to verify retry,
+//! rollback and partial failure behavior of code using the storage layer.
+//!
+//! The [ChaosStorageConfig] is shared between the [ChaosStorageLayerFactory]
+//! and all layers created from it, updating the config using
+//! [ChaosStorageLayerFactory::set_config] applies to existing layers.
+
+use crate::{
+    BucketLifecyclePolicy, CreateBucketOutcome, FileStream, ObjectMetadata, ObjectTag,
+    PresignedDownloadOptions, StorageClass, StorageLayer, StorageLayerError, StorageLayerFactory,
+    StorageLayerImpl, StorageLayerOptions, UploadFileOptions,
+};
+use aws_sdk_s3::presigning::PresignedRequest;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use std::{
+    ops::{Range, RangeInclusive},
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+use thiserror::Error;
+
+/// Storage operations that faults can be injected into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageOperation {
+    /// [StorageLayer::create_bucket]
+    CreateBucket,
+    /// [StorageLayer::bucket_exists]
+    BucketExists,
+    /// [StorageLayer::delete_bucket]
+    DeleteBucket,
+    /// [StorageLayer::create_presigned]
+    CreatePresigned,
+    /// [StorageLayer::create_presigned_download]
+    CreatePresignedDownload,
+    /// [StorageLayer::upload_file]
+    UploadFile,
+    /// [StorageLayer::add_bucket_notifications]
+    AddBucketNotifications,
+    /// [StorageLayer::set_bucket_cors_origins]
+    SetBucketCorsOrigins,
+    /// [StorageLayer::set_bucket_lifecycle_policy]
+    SetBucketLifecyclePolicy,
+    /// [StorageLayer::delete_file]
+    DeleteFile,
+    /// [StorageLayer::get_file]
+    GetFile,
+    /// [StorageLayer::get_file_metadata]
+    GetFileMetadata,
+    /// [StorageLayer::get_file_size]
+    GetFileSize,
+    /// [StorageLayer::get_file_range]
+    GetFileRange,
+    /// [StorageLayer::put_object_tags]
+    PutObjectTags,
+    /// [StorageLayer::get_object_tags]
+    GetObjectTags,
+    /// [StorageLayer::set_storage_class]
+    SetStorageClass,
+    /// [StorageLayer::get_pending_migrations]
+    GetPendingMigrations,
+    /// [StorageLayer::apply_migration]
+    ApplyMigration,
+}
+
+/// Configuration for the faults to inject
+#[derive(Debug, Clone, Default)]
+pub struct ChaosStorageConfig {
+    /// Latency added before each operation, a random duration within
+    /// the range is chosen for each operation
+    pub latency: Option<RangeInclusive<Duration>>,
+    /// Probability between 0.0 and 1.0 that an operation will fail
+    pub failure_rate: f64,
+    /// Operations to inject faults into, [None] to inject faults into
+    /// all operations
+    pub operations: Option<Vec<StorageOperation>>,
+}
+
+impl ChaosStorageConfig {
+    /// Config that fails every one of the provided `operations`
+    pub fn fail(operations: impl Into<Vec<StorageOperation>>) -> Self {
+        Self {
+            latency: None,
+            failure_rate: 1.0,
+            operations: Some(operations.into()),
+        }
+    }
+
+    fn applies_to(&self, operation: StorageOperation) -> bool {
+        self.operations
+            .as_ref()
+            .is_none_or(|operations| operations.contains(&operation))
+    }
+}
+
+/// Errors produced by the chaos storage backend
+#[derive(Debug, Error)]
+pub enum ChaosStorageError {
+    /// Operation was failed by fault injection
+    #[error("injected storage fault during {0:?}")]
+    InjectedFault(StorageOperation),
+}
+
+/// Factory wrapping another [StorageLayerFactory] to create [ChaosStorageLayer]s
+#[derive(Clone)]
+pub struct ChaosStorageLayerFactory {
+    inner: Box<StorageLayerFactory>,
+    config: Arc<Mutex<ChaosStorageConfig>>,
+}
+
+impl ChaosStorageLayerFactory {
+    /// Wrap the `inner` factory injecting faults based on the `config`
+    pub fn new(inner: StorageLayerFactory, config: ChaosStorageConfig) -> Self {
+        Self {
+            inner: Box::new(inner),
+            config: Arc::new(Mutex::new(config)),
+        }
+    }
+
+    /// Replace the current fault config, applies to all layers
+    /// created from this factory
+    pub fn set_config(&self, config: ChaosStorageConfig) {
+        *self.config.lock().unwrap_or_else(PoisonError::into_inner) = config;
+    }
+
+    /// Data residency region of the inner factory
+    pub fn data_region(&self) -> Option<&str> {
+        self.inner.data_region()
+    }
+
+    /// Create a new storage layer wrapping a layer from the inner factory
+    pub fn create_storage_layer(&self, options: StorageLayerOptions) -> ChaosStorageLayer {
+        // Encryption is handled by the outer layer
+        let inner = self.inner.create_layer(StorageLayerOptions {
+            encryption: None,
+            ..options
+        });
+
+        ChaosStorageLayer {
+            inner: Box::new(inner),
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// Storage layer injecting faults into the operations of an inner layer
+#[derive(Clone)]
+pub struct ChaosStorageLayer {
+    inner: Box<StorageLayer>,
+    config: Arc<Mutex<ChaosStorageConfig>>,
+}
+
+impl ChaosStorageLayer {
+    /// Apply the configured faults for the `operation`
+    async fn inject(&self, operation: StorageOperation) -> Result<(), StorageLayerError> {
+        let (latency, fail) = {
+            let config = self.config.lock().unwrap_or_else(PoisonError::into_inner);
+            if !config.applies_to(operation) {
+                return Ok(());
+            }
+
+            let latency = config.latency.clone().map(rand::random_range);
+            let fail = rand::random_bool(config.failure_rate.clamp(0.0, 1.0));
+            (latency, fail)
+        };
+
+        if let Some(latency) = latency {
+            tokio::time::sleep(latency).await;
+        }
+
+        if fail {
+            tracing::debug!(?operation, "injecting storage fault");
+            return Err(ChaosStorageError::InjectedFault(operation).into());
+        }
+
+        Ok(())
+    }
+}
+
+impl StorageLayerImpl for ChaosStorageLayer {
+    fn bucket_name(&self) -> String {
+        self.inner.bucket_name()
+    }
+
+    async fn create_bucket(&self) -> Result<CreateBucketOutcome, StorageLayerError> {
+        self.inject(StorageOperation::CreateBucket).await?;
+        Box::pin(self.inner.create_bucket()).await
+    }
+
+    async fn bucket_exists(&self) -> Result<bool, StorageLayerError> {
+        self.inject(StorageOperation::BucketExists).await?;
+        Box::pin(self.inner.bucket_exists()).await
+    }
+
+    async fn delete_bucket(&self) -> Result<(), StorageLayerError> {
+        self.inject(StorageOperation::DeleteBucket).await?;
+        Box::pin(self.inner.delete_bucket()).await
+    }
+
+    async fn create_presigned(
+        &self,
+        key: &str,
+        size: i64,
+    ) -> Result<(PresignedRequest, DateTime<Utc>), StorageLayerError> {
+        self.inject(StorageOperation::CreatePresigned).await?;
+        Box::pin(self.inner.create_presigned(key, size)).await
+    }
+
+    async fn create_presigned_download(
+        &self,
+        key: &str,
+        expires_in: Duration,
+        options: PresignedDownloadOptions,
+    ) -> Result<(PresignedRequest, DateTime<Utc>), StorageLayerError> {
+        self.inject(StorageOperation::CreatePresignedDownload)
+            .await?;
+        Box::pin(
+            self.inner
+                .create_presigned_download(key, expires_in, options),
+        )
+        .await
+    }
+
+    async fn upload_file(
+        &self,
+        key: &str,
+        body: Bytes,
+        options: UploadFileOptions,
+        metadata: ObjectMetadata,
+    ) -> Result<(), StorageLayerError> {
+        self.inject(StorageOperation::UploadFile).await?;
+        Box::pin(self.inner.upload_object(key, body, options, metadata)).await
+    }
+
+    async fn add_bucket_notifications(&self, sns_arn: &str) -> Result<(), StorageLayerError> {
+        self.inject(StorageOperation::AddBucketNotifications)
+            .await?;
+        Box::pin(self.inner.add_bucket_notifications(sns_arn)).await
+    }
+
+    async fn set_bucket_cors_origins(&self, origins: Vec<String>) -> Result<(), StorageLayerError> {
+        self.inject(StorageOperation::SetBucketCorsOrigins).await?;
+        Box::pin(self.inner.set_bucket_cors_origins(origins)).await
+    }
+
+    async fn set_bucket_lifecycle_policy(
+        &self,
+        policy: &BucketLifecyclePolicy,
+    ) -> Result<(), StorageLayerError> {
+        self.inject(StorageOperation::SetBucketLifecyclePolicy)
+            .await?;
+        Box::pin(self.inner.set_bucket_lifecycle_policy(policy)).await
+    }
+
+    async fn delete_file(&self, key: &str) -> Result<(), StorageLayerError> {
+        self.inject(StorageOperation::DeleteFile).await?;
+        Box::pin(self.inner.delete_file(key)).await
+    }
+
+    async fn get_file(&self, key: &str) -> Result<(FileStream, ObjectMetadata), StorageLayerError> {
+        self.inject(StorageOperation::GetFile).await?;
+        Box::pin(self.inner.get_object(key)).await
+    }
+
+    async fn get_file_metadata(&self, key: &str) -> Result<ObjectMetadata, StorageLayerError> {
+        self.inject(StorageOperation::GetFileMetadata).await?;
+        Box::pin(self.inner.get_file_metadata(key)).await
+    }
+
+    async fn get_file_size(&self, key: &str) -> Result<u64, StorageLayerError> {
+        self.inject(StorageOperation::GetFileSize).await?;
+        Box::pin(self.inner.get_file_size(key)).await
+    }
+
+    async fn get_file_range(
+        &self,
+        key: &str,
+        range: Range<u64>,
+    ) -> Result<FileStream, StorageLayerError> {
+        self.inject(StorageOperation::GetFileRange).await?;
+        Box::pin(self.inner.get_file_range(key, range)).await
+    }
+
+    async fn put_object_tags(
+        &self,
+        key: &str,
+        tags: Vec<ObjectTag>,
+    ) -> Result<(), StorageLayerError> {
+        self.inject(StorageOperation::PutObjectTags).await?;
+        Box::pin(self.inner.put_object_tags(key, tags)).await
+    }
+
+    async fn get_object_tags(&self, key: &str) -> Result<Vec<ObjectTag>, StorageLayerError> {
+        self.inject(StorageOperation::GetObjectTags).await?;
+        Box::pin(self.inner.get_object_tags(key)).await
+    }
+
+    async fn set_storage_class(
+        &self,
+        key: &str,
+        storage_class: StorageClass,
+    ) -> Result<(), StorageLayerError> {
+        self.inject(StorageOperation::SetStorageClass).await?;
+        Box::pin(self.inner.set_storage_class(key, storage_class)).await
+    }
+
+    async fn get_pending_migrations(
+        &self,
+        applied_names: Vec<String>,
+    ) -> Result<Vec<String>, StorageLayerError> {
+        self.inject(StorageOperation::GetPendingMigrations).await?;
+        Box::pin(self.inner.get_pending_migrations(applied_names)).await
+    }
+
+    async fn apply_migration(&self, name: &str) -> Result<(), StorageLayerError> {
+        self.inject(StorageOperation::ApplyMigration).await?;
+        Box::pin(self.inner.apply_migration(name)).await
+    }
+}
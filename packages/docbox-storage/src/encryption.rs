@@ -0,0 +1,296 @@
+//! # Encryption
+//!
+//! Optional client-side encryption for stored files. When a [StorageLayer](crate::StorageLayer)
+//! is created with [StorageEncryptionKeys] file contents are encrypted with AES-256-GCM
+//! before being uploaded and are transparently decrypted when loaded.
+//!
+//! Encrypted objects are stored with the following layout:
+//!
+//! ```text
+//! | MAGIC (6 bytes) | FORMAT VERSION (1 byte) | KEY VERSION (4 bytes, BE) | NONCE (12 bytes) | CIPHERTEXT + TAG |
+//! ```
+//!
+//! The key version allows previous keys to be kept within the key ring after a key
+//! rotation so that files encrypted before the rotation can still be decrypted.
+//!
+//! The key version is also recorded in the metadata of each encrypted object, objects
+//! stored without it are treated as plaintext regardless of their contents. This
+//! allows encryption to be enabled for a tenant with existing unencrypted files.
+
+use aes_gcm::{
+    Aes256Gcm, Key, KeyInit, Nonce,
+    aead::{Aead, AeadCore, OsRng},
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use std::{collections::BTreeMap, fmt::Debug};
+use thiserror::Error;
+
+/// Magic bytes prefixing encrypted objects
+const ENCRYPTED_MAGIC: &[u8; 6] = b"DBXENC";
+
+/// Version of the encrypted object format
+const ENCRYPTED_FORMAT_VERSION: u8 = 1;
+
+/// Length of the AES-GCM nonce
+const NONCE_LENGTH: usize = 12;
+
+/// Length of the header prefixing encrypted objects
+const HEADER_LENGTH: usize = ENCRYPTED_MAGIC.len() + 1 + 4 + NONCE_LENGTH;
+
+/// Length in bytes of a data key
+pub const STORAGE_KEY_LENGTH: usize = 32;
+
+/// Errors that can occur when encrypting or decrypting files
+#[derive(Debug, Error)]
+pub enum StorageEncryptionError {
+    /// Failed to encrypt the file contents
+    #[error("failed to encrypt file contents")]
+    Encrypt,
+
+    /// Failed to decrypt the file contents, contents are corrupted or
+    /// were encrypted using a different key
+    #[error("failed to decrypt file contents")]
+    Decrypt,
+
+    /// Encrypted file uses a format version that is not supported
+    #[error("unsupported encrypted file format version {0}")]
+    UnsupportedFormat(u8),
+
+    /// File was encrypted with a key that is not present in the key ring
+    #[error("file was encrypted with unknown key version {0}")]
+    UnknownKeyVersion(u32),
+
+    /// Key ring does not contain the current key version
+    #[error("current key version {0} is missing from the key ring")]
+    MissingCurrentKey(u32),
+}
+
+/// 256-bit data key used to encrypt files
+#[derive(Clone, PartialEq, Eq)]
+pub struct StorageEncryptionKey([u8; STORAGE_KEY_LENGTH]);
+
+impl Debug for StorageEncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StorageEncryptionKey(******)")
+    }
+}
+
+impl StorageEncryptionKey {
+    /// Generate a new random data key
+    pub fn generate() -> Self {
+        let key = Aes256Gcm::generate_key(OsRng);
+        Self(key.into())
+    }
+
+    /// Create a key from its raw bytes
+    pub fn from_bytes(bytes: [u8; STORAGE_KEY_LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    /// Get the raw bytes of the key
+    pub fn as_bytes(&self) -> &[u8; STORAGE_KEY_LENGTH] {
+        &self.0
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.0))
+    }
+}
+
+/// Collection of versioned data keys, new files are always encrypted
+/// with the current key version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageEncryptionKeys {
+    /// Version of the key currently used for encryption
+    current_version: u32,
+    /// All known keys by version
+    keys: BTreeMap<u32, StorageEncryptionKey>,
+}
+
+impl StorageEncryptionKeys {
+    /// Create a key ring from the `current_version` and collection of `keys`
+    pub fn new(
+        current_version: u32,
+        keys: BTreeMap<u32, StorageEncryptionKey>,
+    ) -> Result<Self, StorageEncryptionError> {
+        if !keys.contains_key(&current_version) {
+            return Err(StorageEncryptionError::MissingCurrentKey(current_version));
+        }
+
+        Ok(Self {
+            current_version,
+            keys,
+        })
+    }
+
+    /// Get the version of the key currently used for encryption
+    pub fn current_version(&self) -> u32 {
+        self.current_version
+    }
+
+    /// Get all the keys within the key ring
+    pub fn keys(&self) -> &BTreeMap<u32, StorageEncryptionKey> {
+        &self.keys
+    }
+
+    fn current_key(&self) -> &StorageEncryptionKey {
+        // Presence of the current key is checked when the key ring is created
+        &self.keys[&self.current_version]
+    }
+
+    /// Encrypt the provided `plaintext` using the current key
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Bytes, StorageEncryptionError> {
+        let cipher = self.current_key().cipher();
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| StorageEncryptionError::Encrypt)?;
+
+        let mut output = BytesMut::with_capacity(HEADER_LENGTH + ciphertext.len());
+        output.put_slice(ENCRYPTED_MAGIC);
+        output.put_u8(ENCRYPTED_FORMAT_VERSION);
+        output.put_u32(self.current_version);
+        output.put_slice(nonce.as_slice());
+        output.put_slice(&ciphertext);
+
+        Ok(output.freeze())
+    }
+
+    /// Decrypt the provided encrypted `data`
+    pub fn decrypt(&self, data: Bytes) -> Result<Bytes, StorageEncryptionError> {
+        if !is_encrypted(&data) || data.len() < HEADER_LENGTH {
+            return Err(StorageEncryptionError::Decrypt);
+        }
+
+        let (header, ciphertext) = data.split_at(HEADER_LENGTH);
+        let header = &header[ENCRYPTED_MAGIC.len()..];
+
+        let format_version = header[0];
+        if format_version != ENCRYPTED_FORMAT_VERSION {
+            return Err(StorageEncryptionError::UnsupportedFormat(format_version));
+        }
+
+        let mut key_version = [0u8; 4];
+        key_version.copy_from_slice(&header[1..5]);
+        let key_version = u32::from_be_bytes(key_version);
+
+        let key = self
+            .keys
+            .get(&key_version)
+            .ok_or(StorageEncryptionError::UnknownKeyVersion(key_version))?;
+
+        let nonce = Nonce::from_slice(&header[5..]);
+
+        let plaintext = key
+            .cipher()
+            .decrypt(nonce, ciphertext)
+            .map_err(|_| StorageEncryptionError::Decrypt)?;
+
+        Ok(Bytes::from(plaintext))
+    }
+}
+
+/// Check if the provided `data` is an encrypted object
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(ENCRYPTED_MAGIC)
+}
+
+/// Get the version of the key used to encrypt `data`, [None] if the
+/// data is not encrypted
+pub fn encrypted_key_version(data: &[u8]) -> Option<u32> {
+    if !is_encrypted(data) || data.len() < HEADER_LENGTH {
+        return None;
+    }
+
+    let start = ENCRYPTED_MAGIC.len() + 1;
+    let mut key_version = [0u8; 4];
+    key_version.copy_from_slice(&data[start..start + 4]);
+    Some(u32::from_be_bytes(key_version))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        StorageEncryptionError, StorageEncryptionKey, StorageEncryptionKeys, encrypted_key_version,
+        is_encrypted,
+    };
+    use bytes::Bytes;
+    use std::collections::BTreeMap;
+
+    fn key_ring(current_version: u32, versions: &[u32]) -> StorageEncryptionKeys {
+        let keys: BTreeMap<u32, StorageEncryptionKey> = versions
+            .iter()
+            .map(|version| (*version, StorageEncryptionKey::generate()))
+            .collect();
+
+        StorageEncryptionKeys::new(current_version, keys).unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let keys = key_ring(1, &[1]);
+        let encrypted = keys.encrypt(b"test content").unwrap();
+
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(encrypted_key_version(&encrypted), Some(1));
+        assert_ne!(encrypted.as_ref(), b"test content");
+
+        let decrypted = keys.decrypt(encrypted).unwrap();
+        assert_eq!(decrypted.as_ref(), b"test content");
+    }
+
+    #[test]
+    fn test_decrypt_plaintext() {
+        let keys = key_ring(1, &[1]);
+        let error = keys.decrypt(Bytes::from_static(b"plain")).unwrap_err();
+        assert!(matches!(error, StorageEncryptionError::Decrypt));
+    }
+
+    #[test]
+    fn test_decrypt_previous_key() {
+        let mut keys = key_ring(1, &[1]);
+        let encrypted = keys.encrypt(b"test content").unwrap();
+
+        // Rotate to a new key keeping the previous key
+        keys.keys.insert(2, StorageEncryptionKey::generate());
+        keys.current_version = 2;
+
+        let decrypted = keys.decrypt(encrypted).unwrap();
+        assert_eq!(decrypted.as_ref(), b"test content");
+    }
+
+    #[test]
+    fn test_decrypt_unknown_key() {
+        let encrypted = key_ring(1, &[1]).encrypt(b"test content").unwrap();
+        let other_keys = key_ring(2, &[2]);
+
+        assert!(matches!(
+            other_keys.decrypt(encrypted),
+            Err(StorageEncryptionError::UnknownKeyVersion(1))
+        ));
+    }
+
+    #[test]
+    fn test_decrypt_tampered() {
+        let keys = key_ring(1, &[1]);
+        let encrypted = keys.encrypt(b"test content").unwrap();
+
+        let mut tampered = encrypted.to_vec();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0xFF;
+
+        assert!(matches!(
+            keys.decrypt(Bytes::from(tampered)),
+            Err(StorageEncryptionError::Decrypt)
+        ));
+    }
+
+    #[test]
+    fn test_missing_current_key() {
+        assert!(matches!(
+            StorageEncryptionKeys::new(2, BTreeMap::new()),
+            Err(StorageEncryptionError::MissingCurrentKey(2))
+        ));
+    }
+}
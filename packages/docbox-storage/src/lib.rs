@@ -9,6 +9,19 @@
 //! # Environment Variables
 //!
 //! See [s3] this is currently the only available backend for storage
+//!
+//! # Features
+//!
+//! * `memory` - Enables the in-memory storage backend for use in tests
+//! * `chaos` - Enables the fault injecting storage backend for use in tests
+//!
+//! # Encryption
+//!
+//! Storage layers can optionally encrypt file contents at rest, see [encryption]
+//!
+//! # Throttling
+//!
+//! Storage layers used by bulk operations can be rate limited, see [throttle]
 
 use aws_config::SdkConfig;
 use aws_sdk_s3::presigning::PresignedRequest;
@@ -17,10 +30,21 @@ use bytes_utils::SegmentedBuf;
 use chrono::{DateTime, Utc};
 use futures::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
-use std::{fmt::Debug, pin::Pin, time::Duration};
+use std::{fmt::Debug, ops::Range, pin::Pin, sync::Arc, time::Duration};
 use thiserror::Error;
 
+use crate::{
+    encryption::{StorageEncryptionError, StorageEncryptionKeys},
+    throttle::StorageThrottle,
+};
+
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod encryption;
+#[cfg(feature = "memory")]
+pub mod memory;
 pub mod s3;
+pub mod throttle;
 
 /// Configuration for a storage layer factory
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -45,6 +69,15 @@ pub enum StorageLayerFactoryConfigError {
     S3(#[from] s3::S3StorageLayerFactoryConfigError),
 }
 
+/// Errors that could occur when creating a storage layer factory
+/// from its configuration
+#[derive(Debug, Error)]
+pub enum StorageLayerFactoryError {
+    /// Error from the S3 layer factory
+    #[error(transparent)]
+    S3(#[from] s3::S3StorageLayerFactoryError),
+}
+
 impl StorageLayerFactoryConfig {
     /// Load the configuration from the current environment variables
     pub fn from_env() -> Result<Self, StorageLayerFactoryConfigError> {
@@ -60,6 +93,12 @@ impl StorageLayerFactoryConfig {
 pub enum StorageLayerFactory {
     /// S3 storage backend
     S3(s3::S3StorageLayerFactory),
+    /// In-memory storage backend for testing
+    #[cfg(feature = "memory")]
+    Memory(memory::MemoryStorageLayerFactory),
+    /// Fault injecting storage backend for testing
+    #[cfg(feature = "chaos")]
+    Chaos(chaos::ChaosStorageLayerFactory),
 }
 
 /// Errors that can occur when using a storage layer
@@ -69,9 +108,37 @@ pub enum StorageLayerError {
     #[error(transparent)]
     S3(Box<s3::S3StorageError>),
 
+    /// Error from the memory layer
+    #[cfg(feature = "memory")]
+    #[error(transparent)]
+    Memory(#[from] memory::MemoryStorageError),
+
+    /// Error injected by the chaos layer
+    #[cfg(feature = "chaos")]
+    #[error(transparent)]
+    Chaos(#[from] chaos::ChaosStorageError),
+
     /// Error collecting streamed response bytes
     #[error("failed to collect file contents")]
     CollectBytes,
+
+    /// Error encrypting or decrypting file contents
+    #[error(transparent)]
+    Encryption(#[from] StorageEncryptionError),
+
+    /// Presigned downloads cannot be used when the storage is encrypted
+    /// as the stored objects are not readable without decryption
+    #[error("presigned downloads are not supported for encrypted storage")]
+    PresignedDownloadEncrypted,
+
+    /// Ranged downloads cannot be used when the storage is encrypted as
+    /// the file contents can only be decrypted as a whole
+    #[error("ranged downloads are not supported for encrypted storage")]
+    RangedDownloadEncrypted,
+
+    /// Bucket lifecycle policy contained an invalid rule
+    #[error("invalid bucket lifecycle policy: {0}")]
+    InvalidLifecyclePolicy(&'static str),
 }
 
 impl From<s3::S3StorageError> for StorageLayerError {
@@ -85,14 +152,27 @@ impl From<s3::S3StorageError> for StorageLayerError {
 pub struct StorageLayerOptions {
     /// Name of the storage bucket
     pub bucket_name: String,
+    /// Optional keys to encrypt stored files with
+    pub encryption: Option<Arc<StorageEncryptionKeys>>,
+    /// Whether files should be stored as content addressed objects
+    /// shared between files with identical contents
+    pub deduplicate: bool,
+    /// Tags applied to every file uploaded through the layer (i.e the
+    /// tenant that owns the files)
+    pub tags: Vec<ObjectTag>,
 }
 
 impl StorageLayerFactory {
     /// Create a [StorageLayerFactory] from the provided config
-    pub fn from_config(aws_config: &SdkConfig, config: StorageLayerFactoryConfig) -> Self {
+    pub fn from_config(
+        aws_config: &SdkConfig,
+        config: StorageLayerFactoryConfig,
+    ) -> Result<Self, StorageLayerFactoryError> {
         match config {
             StorageLayerFactoryConfig::S3(config) => {
-                Self::S3(s3::S3StorageLayerFactory::from_config(aws_config, config))
+                s3::S3StorageLayerFactory::from_config(aws_config, config)
+                    .map(Self::S3)
+                    .map_err(StorageLayerFactoryError::S3)
             }
         }
     }
@@ -102,15 +182,58 @@ impl StorageLayerFactory {
     pub fn create_test_layer(&self) -> StorageLayer {
         self.create_layer(StorageLayerOptions {
             bucket_name: "test".to_string(),
+            encryption: None,
+            deduplicate: false,
+            tags: Vec::new(),
         })
     }
 
+    /// Data residency region the storage backend is located within,
+    /// [None] when no region is configured
+    pub fn data_region(&self) -> Option<&str> {
+        match self {
+            StorageLayerFactory::S3(s3) => s3.data_region(),
+            #[cfg(feature = "memory")]
+            StorageLayerFactory::Memory(_) => None,
+            #[cfg(feature = "chaos")]
+            StorageLayerFactory::Chaos(chaos) => chaos.data_region(),
+        }
+    }
+
     /// Create a storage layer from the provided `options`
     pub fn create_layer(&self, options: StorageLayerOptions) -> StorageLayer {
         match self {
             StorageLayerFactory::S3(s3) => {
                 let layer = s3.create_storage_layer(options.bucket_name);
-                StorageLayer::S3(layer)
+                StorageLayer {
+                    backend: StorageLayerBackend::S3(layer),
+                    encryption: options.encryption,
+                    deduplicate: options.deduplicate,
+                    tags: options.tags,
+                    throttle: None,
+                }
+            }
+            #[cfg(feature = "memory")]
+            StorageLayerFactory::Memory(memory) => {
+                let layer = memory.create_storage_layer(options.bucket_name);
+                StorageLayer {
+                    backend: StorageLayerBackend::Memory(layer),
+                    encryption: options.encryption,
+                    deduplicate: options.deduplicate,
+                    tags: options.tags,
+                    throttle: None,
+                }
+            }
+            #[cfg(feature = "chaos")]
+            StorageLayerFactory::Chaos(chaos) => {
+                let layer = chaos.create_storage_layer(options.clone());
+                StorageLayer {
+                    backend: StorageLayerBackend::Chaos(layer),
+                    encryption: options.encryption,
+                    deduplicate: options.deduplicate,
+                    tags: options.tags,
+                    throttle: None,
+                }
             }
         }
     }
@@ -118,9 +241,32 @@ impl StorageLayerFactory {
 
 /// Storage layer for a tenant with different underlying backends
 #[derive(Clone)]
-pub enum StorageLayer {
+pub struct StorageLayer {
+    /// Underlying storage backend
+    backend: StorageLayerBackend,
+    /// Keys used to encrypt file contents, [None] when files
+    /// are stored unencrypted
+    encryption: Option<Arc<StorageEncryptionKeys>>,
+    /// Whether files should be stored as content addressed objects,
+    /// callers use this to decide how file keys are created
+    deduplicate: bool,
+    /// Tags applied to every uploaded file
+    tags: Vec<ObjectTag>,
+    /// Optional rate limit for requests made through the layer
+    throttle: Option<StorageThrottle>,
+}
+
+/// Backend implementation for a [StorageLayer]
+#[derive(Clone)]
+enum StorageLayerBackend {
     /// Storage layer backed by S3
     S3(s3::S3StorageLayer),
+    /// Storage layer backed by memory
+    #[cfg(feature = "memory")]
+    Memory(memory::MemoryStorageLayer),
+    /// Storage layer injecting faults into another layer
+    #[cfg(feature = "chaos")]
+    Chaos(chaos::ChaosStorageLayer),
 }
 
 /// Outcome from creating a bucket
@@ -139,6 +285,189 @@ pub struct UploadFileOptions {
     pub content_type: String,
     /// Tags to append to the file
     pub tags: Option<Vec<UploadFileTag>>,
+    /// Key value tags classifying the file, used for storage cost
+    /// allocation reports and lifecycle rules keyed on tags
+    pub object_tags: Vec<ObjectTag>,
+}
+
+/// Metadata stored alongside an object by the storage layer
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectMetadata {
+    /// Version of the key the object contents were encrypted with,
+    /// [None] when the object is stored unencrypted
+    pub encryption_key_version: Option<u32>,
+}
+
+impl ObjectMetadata {
+    /// Metadata key storing the encryption key version
+    pub(crate) const ENCRYPTION_KEY_VERSION: &str = "docbox-encryption-key-version";
+}
+
+/// Options for a presigned file download
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PresignedDownloadOptions {
+    /// Content-Disposition header the download should be served with,
+    /// used to give the downloaded file a name
+    pub content_disposition: Option<String>,
+    /// Content-Type header the download should be served with, overrides
+    /// the content type stored with the file
+    pub content_type: Option<String>,
+}
+
+/// Key value tag attached to a stored object
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ObjectTag {
+    /// Key of the tag
+    pub key: String,
+    /// Value of the tag
+    pub value: String,
+}
+
+/// Maximum length of an object tag value
+const MAX_OBJECT_TAG_VALUE_LENGTH: usize = 256;
+
+impl ObjectTag {
+    /// Tag key for the ID of the tenant owning the object
+    pub const TENANT_ID: &str = "docbox-tenant-id";
+    /// Tag key for the environment of the tenant owning the object
+    pub const TENANT_ENV: &str = "docbox-tenant-env";
+    /// Tag key for the document box the object belongs to
+    pub const DOCUMENT_BOX: &str = "docbox-document-box";
+    /// Tag key for the type of item the object stores
+    pub const ITEM_TYPE: &str = "docbox-item-type";
+
+    /// Create a new tag, characters in the `value` that are not allowed
+    /// within tag values are replaced with underscores
+    pub fn new(key: impl Into<String>, value: &str) -> Self {
+        let value = value
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == ' ' || "+-=._:/@".contains(c) {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(MAX_OBJECT_TAG_VALUE_LENGTH)
+            .collect();
+
+        Self {
+            key: key.into(),
+            value,
+        }
+    }
+}
+
+impl From<UploadFileTag> for ObjectTag {
+    fn from(value: UploadFileTag) -> Self {
+        match value {
+            UploadFileTag::ExpireDays1 => ObjectTag::new("expire", "1d"),
+            UploadFileTag::ExpireDays30 => ObjectTag::new("expire", "30d"),
+        }
+    }
+}
+
+/// Storage class to store a file object using
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageClass {
+    /// Default storage class for frequently accessed files
+    Standard,
+    /// Lower cost storage class for rarely accessed archived files,
+    /// files in this class can still be read without being restored
+    Archive,
+}
+
+/// Default size of each chunk for [StorageLayer::get_file_parallel] (8MB)
+pub const DEFAULT_PARALLEL_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Default number of concurrent requests for [StorageLayer::get_file_parallel]
+pub const DEFAULT_PARALLEL_CONCURRENCY: usize = 4;
+
+/// Default number of days before incomplete multipart uploads are aborted
+pub const DEFAULT_ABORT_INCOMPLETE_MULTIPART_UPLOAD_DAYS: i32 = 7;
+
+/// Bucket hygiene rules managed by docbox for a storage bucket
+///
+/// Applying a policy replaces any rules from a previously applied policy,
+/// other lifecycle rules on the bucket are left untouched
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BucketLifecyclePolicy {
+    /// Days after an incomplete multipart upload was started before its
+    /// uploaded parts are aborted and removed, [None] to keep them
+    pub abort_incomplete_multipart_upload_days: Option<i32>,
+
+    /// Object key prefixes to expire (i.e temporary or generated files)
+    pub expire_prefixes: Vec<BucketPrefixExpiration>,
+
+    /// Tags to apply to the bucket, used for cost allocation reports
+    pub bucket_tags: Vec<BucketTag>,
+}
+
+impl Default for BucketLifecyclePolicy {
+    fn default() -> Self {
+        Self {
+            abort_incomplete_multipart_upload_days: Some(
+                DEFAULT_ABORT_INCOMPLETE_MULTIPART_UPLOAD_DAYS,
+            ),
+            expire_prefixes: Vec::new(),
+            bucket_tags: Vec::new(),
+        }
+    }
+}
+
+/// Expiration rule for objects under a key prefix
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BucketPrefixExpiration {
+    /// Key prefix the rule applies to
+    pub prefix: String,
+    /// Days after creation before the objects are expired
+    pub days: i32,
+}
+
+/// Key value tag for a bucket
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BucketTag {
+    /// Key of the tag
+    pub key: String,
+    /// Value of the tag
+    pub value: String,
+}
+
+impl BucketLifecyclePolicy {
+    /// Validate the rules within the policy
+    pub fn validate(&self) -> Result<(), StorageLayerError> {
+        if self
+            .abort_incomplete_multipart_upload_days
+            .is_some_and(|days| days < 1)
+        {
+            return Err(StorageLayerError::InvalidLifecyclePolicy(
+                "abort_incomplete_multipart_upload_days must be at least 1",
+            ));
+        }
+
+        for expiration in &self.expire_prefixes {
+            if expiration.prefix.is_empty() {
+                return Err(StorageLayerError::InvalidLifecyclePolicy(
+                    "expire_prefixes prefix must not be empty",
+                ));
+            }
+
+            if expiration.days < 1 {
+                return Err(StorageLayerError::InvalidLifecyclePolicy(
+                    "expire_prefixes days must be at least 1",
+                ));
+            }
+        }
+
+        if self.bucket_tags.iter().any(|tag| tag.key.is_empty()) {
+            return Err(StorageLayerError::InvalidLifecyclePolicy(
+                "bucket_tags key must not be empty",
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 /// Additional behavioral tags to use when uploading the file
@@ -151,10 +480,44 @@ pub enum UploadFileTag {
 }
 
 impl StorageLayer {
+    /// Rate limit the requests and transferred bytes of this layer using
+    /// the provided `throttle`, used for bulk operations
+    pub fn with_throttle(mut self, throttle: Option<StorageThrottle>) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    /// Wait for the throttle (if any) to allow another request
+    async fn throttle_request(&self) {
+        if let Some(throttle) = self.throttle.as_ref() {
+            throttle.acquire_request().await;
+        }
+    }
+
+    /// Check if file contents are encrypted by this storage layer
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
+
+    /// Check if files should be stored as content addressed objects
+    /// that are shared between files with identical contents
+    pub fn is_deduplicated(&self) -> bool {
+        self.deduplicate
+    }
+
+    /// Get the keys used to encrypt file contents
+    pub fn encryption_keys(&self) -> Option<&StorageEncryptionKeys> {
+        self.encryption.as_deref()
+    }
+
     /// Get the name of the bucket
     pub fn bucket_name(&self) -> String {
-        match self {
-            StorageLayer::S3(layer) => layer.bucket_name(),
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.bucket_name(),
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.bucket_name(),
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.bucket_name(),
         }
     }
 
@@ -164,16 +527,24 @@ impl StorageLayer {
     /// [`Ok`] result rather than an error
     #[tracing::instrument(skip(self))]
     pub async fn create_bucket(&self) -> Result<CreateBucketOutcome, StorageLayerError> {
-        match self {
-            StorageLayer::S3(layer) => layer.create_bucket().await,
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.create_bucket().await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.create_bucket().await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.create_bucket().await,
         }
     }
 
     /// Checks if the bucket exists
     #[tracing::instrument(skip(self))]
     pub async fn bucket_exists(&self) -> Result<bool, StorageLayerError> {
-        match self {
-            StorageLayer::S3(layer) => layer.bucket_exists().await,
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.bucket_exists().await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.bucket_exists().await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.bucket_exists().await,
         }
     }
 
@@ -183,8 +554,12 @@ impl StorageLayer {
     /// function this is treated as an [`Ok`] result
     #[tracing::instrument(skip(self))]
     pub async fn delete_bucket(&self) -> Result<(), StorageLayerError> {
-        match self {
-            StorageLayer::S3(layer) => layer.delete_bucket().await,
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.delete_bucket().await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.delete_bucket().await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.delete_bucket().await,
         }
     }
 
@@ -195,8 +570,12 @@ impl StorageLayer {
         key: &str,
         size: i64,
     ) -> Result<(PresignedRequest, DateTime<Utc>), StorageLayerError> {
-        match self {
-            StorageLayer::S3(layer) => layer.create_presigned(key, size).await,
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.create_presigned(key, size).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.create_presigned(key, size).await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.create_presigned(key, size).await,
         }
     }
 
@@ -209,30 +588,98 @@ impl StorageLayer {
         &self,
         key: &str,
         expires_in: Duration,
+        options: PresignedDownloadOptions,
     ) -> Result<(PresignedRequest, DateTime<Utc>), StorageLayerError> {
-        match self {
-            StorageLayer::S3(layer) => layer.create_presigned_download(key, expires_in).await,
+        if self.is_encrypted() {
+            return Err(StorageLayerError::PresignedDownloadEncrypted);
+        }
+
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => {
+                layer
+                    .create_presigned_download(key, expires_in, options)
+                    .await
+            }
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => {
+                layer
+                    .create_presigned_download(key, expires_in, options)
+                    .await
+            }
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => {
+                layer
+                    .create_presigned_download(key, expires_in, options)
+                    .await
+            }
         }
     }
 
     /// Uploads a file to the S3 bucket for the tenant
+    ///
+    /// When encryption is enabled the `body` is encrypted before upload and
+    /// the version of the key used is recorded in the object metadata
     #[tracing::instrument(skip(self, body), fields(body_length = body.len()))]
     pub async fn upload_file(
+        &self,
+        key: &str,
+        body: Bytes,
+        mut options: UploadFileOptions,
+    ) -> Result<(), StorageLayerError> {
+        // Apply the layer tags, tags provided for the upload take priority
+        for tag in &self.tags {
+            if !options.object_tags.iter().any(|other| other.key == tag.key) {
+                options.object_tags.push(tag.clone());
+            }
+        }
+
+        let mut metadata = ObjectMetadata::default();
+        let body = match self.encryption.as_ref() {
+            Some(keys) => {
+                metadata.encryption_key_version = Some(keys.current_version());
+                keys.encrypt(&body)?
+            }
+            None => body,
+        };
+
+        self.upload_object(key, body, options, metadata).await
+    }
+
+    /// Uploads the `body` as-is along with the object `metadata`
+    pub(crate) async fn upload_object(
         &self,
         key: &str,
         body: Bytes,
         options: UploadFileOptions,
+        metadata: ObjectMetadata,
     ) -> Result<(), StorageLayerError> {
-        match self {
-            StorageLayer::S3(layer) => layer.upload_file(key, body, options).await,
+        if let Some(throttle) = self.throttle.as_ref() {
+            throttle.acquire_request().await;
+            throttle.acquire_bytes(body.len()).await;
+        }
+
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.upload_file(key, body, options, metadata).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => {
+                layer.upload_file(key, body, options, metadata).await
+            }
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => {
+                layer.upload_file(key, body, options, metadata).await
+            }
         }
     }
 
     /// Add the SNS notification to a bucket
     #[tracing::instrument(skip(self))]
     pub async fn add_bucket_notifications(&self, sns_arn: &str) -> Result<(), StorageLayerError> {
-        match self {
-            StorageLayer::S3(layer) => layer.add_bucket_notifications(sns_arn).await,
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.add_bucket_notifications(sns_arn).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.add_bucket_notifications(sns_arn).await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.add_bucket_notifications(sns_arn).await,
         }
     }
 
@@ -242,8 +689,30 @@ impl StorageLayer {
         &self,
         origins: Vec<String>,
     ) -> Result<(), StorageLayerError> {
-        match self {
-            StorageLayer::S3(layer) => layer.set_bucket_cors_origins(origins).await,
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.set_bucket_cors_origins(origins).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.set_bucket_cors_origins(origins).await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.set_bucket_cors_origins(origins).await,
+        }
+    }
+
+    /// Applies the bucket lifecycle rules and bucket tags from the `policy`,
+    /// replacing any rules from a previously applied policy
+    #[tracing::instrument(skip(self))]
+    pub async fn set_bucket_lifecycle_policy(
+        &self,
+        policy: &BucketLifecyclePolicy,
+    ) -> Result<(), StorageLayerError> {
+        policy.validate()?;
+
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.set_bucket_lifecycle_policy(policy).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.set_bucket_lifecycle_policy(policy).await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.set_bucket_lifecycle_policy(policy).await,
         }
     }
 
@@ -253,16 +722,222 @@ impl StorageLayer {
     /// function this is treated as an [`Ok`] result
     #[tracing::instrument(skip(self))]
     pub async fn delete_file(&self, key: &str) -> Result<(), StorageLayerError> {
-        match self {
-            StorageLayer::S3(layer) => layer.delete_file(key).await,
+        self.throttle_request().await;
+
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.delete_file(key).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.delete_file(key).await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.delete_file(key).await,
         }
     }
 
     /// Gets a byte stream for a file from S3
+    ///
+    /// Files stored encrypted are loaded completely and decrypted before
+    /// being provided as a stream, files stored before encryption was
+    /// enabled are provided as-is
     #[tracing::instrument(skip(self))]
     pub async fn get_file(&self, key: &str) -> Result<FileStream, StorageLayerError> {
-        match self {
-            StorageLayer::S3(layer) => layer.get_file(key).await,
+        let (stream, metadata) = self.get_object(key).await?;
+
+        // Objects without a key version were stored unencrypted, layers
+        // without keys provide the stored contents as-is
+        let (Some(keys), Some(_)) = (self.encryption.as_ref(), metadata.encryption_key_version)
+        else {
+            return Ok(stream);
+        };
+
+        let bytes = stream.collect_bytes().await?;
+        let bytes = keys.decrypt(bytes).inspect_err(|error| {
+            tracing::error!(?error, "failed to decrypt file contents");
+        })?;
+
+        Ok(FileStream::from_bytes(bytes))
+    }
+
+    /// Gets a byte stream for the stored contents of an object as-is
+    /// along with the object metadata
+    pub(crate) async fn get_object(
+        &self,
+        key: &str,
+    ) -> Result<(FileStream, ObjectMetadata), StorageLayerError> {
+        self.throttle_request().await;
+
+        let (stream, metadata) = match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.get_file(key).await?,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.get_file(key).await?,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.get_file(key).await?,
+        };
+
+        let stream = match self.throttle.as_ref() {
+            Some(throttle) => stream.throttled(throttle.clone()),
+            None => stream,
+        };
+
+        Ok((stream, metadata))
+    }
+
+    /// Gets the metadata stored alongside the object for the provided `key`
+    #[tracing::instrument(skip(self))]
+    pub async fn get_file_metadata(&self, key: &str) -> Result<ObjectMetadata, StorageLayerError> {
+        self.throttle_request().await;
+
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.get_file_metadata(key).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.get_file_metadata(key).await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.get_file_metadata(key).await,
+        }
+    }
+
+    /// Gets the size in bytes of the stored object for the provided `key`,
+    /// when encryption is enabled this is the size of the encrypted contents
+    #[tracing::instrument(skip(self))]
+    pub async fn get_file_size(&self, key: &str) -> Result<u64, StorageLayerError> {
+        self.throttle_request().await;
+
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.get_file_size(key).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.get_file_size(key).await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.get_file_size(key).await,
+        }
+    }
+
+    /// Gets a byte stream for the `range` of bytes from a file
+    ///
+    /// Not supported when encryption is enabled
+    #[tracing::instrument(skip(self))]
+    pub async fn get_file_range(
+        &self,
+        key: &str,
+        range: Range<u64>,
+    ) -> Result<FileStream, StorageLayerError> {
+        if self.is_encrypted() {
+            return Err(StorageLayerError::RangedDownloadEncrypted);
+        }
+
+        self.throttle_request().await;
+
+        let stream = match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.get_file_range(key, range).await?,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.get_file_range(key, range).await?,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.get_file_range(key, range).await?,
+        };
+
+        Ok(match self.throttle.as_ref() {
+            Some(throttle) => stream.throttled(throttle.clone()),
+            None => stream,
+        })
+    }
+
+    /// Gets a byte stream for a file by downloading chunks of `chunk_size` bytes
+    /// using up to `concurrency` concurrent ranged requests, the chunks are
+    /// provided by the stream in order
+    ///
+    /// Useful for loading large files faster than a single request would allow.
+    /// Falls back to [StorageLayer::get_file] when encryption is enabled or
+    /// the file fits within a single chunk
+    #[tracing::instrument(skip(self))]
+    pub async fn get_file_parallel(
+        &self,
+        key: &str,
+        chunk_size: u64,
+        concurrency: usize,
+    ) -> Result<FileStream, StorageLayerError> {
+        if self.is_encrypted() {
+            return self.get_file(key).await;
+        }
+
+        let chunk_size = chunk_size.max(1);
+        let size = self.get_file_size(key).await?;
+        if size <= chunk_size {
+            return self.get_file(key).await;
+        }
+
+        let ranges = (0..size)
+            .step_by(chunk_size as usize)
+            .map(move |start| start..(start + chunk_size).min(size));
+
+        let layer = self.clone();
+        let key = key.to_string();
+
+        let stream = futures::stream::iter(ranges)
+            .map(move |range| {
+                let layer = layer.clone();
+                let key = key.clone();
+                async move {
+                    layer
+                        .get_file_range(&key, range)
+                        .await?
+                        .collect_bytes()
+                        .await
+                }
+            })
+            .buffered(concurrency.max(1))
+            .map(|result| result.map_err(std::io::Error::other));
+
+        Ok(FileStream {
+            stream: Box::pin(stream),
+        })
+    }
+
+    /// Replaces the tags of the file with the provided `key`, tags from
+    /// the upload that are not included in `tags` are removed
+    #[tracing::instrument(skip(self))]
+    pub async fn put_object_tags(
+        &self,
+        key: &str,
+        tags: Vec<ObjectTag>,
+    ) -> Result<(), StorageLayerError> {
+        self.throttle_request().await;
+
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.put_object_tags(key, tags).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.put_object_tags(key, tags).await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.put_object_tags(key, tags).await,
+        }
+    }
+
+    /// Gets the tags of the file with the provided `key`
+    #[tracing::instrument(skip(self))]
+    pub async fn get_object_tags(&self, key: &str) -> Result<Vec<ObjectTag>, StorageLayerError> {
+        self.throttle_request().await;
+
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.get_object_tags(key).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.get_object_tags(key).await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.get_object_tags(key).await,
+        }
+    }
+
+    /// Changes the storage class of the file with the provided `key`
+    #[tracing::instrument(skip(self))]
+    pub async fn set_storage_class(
+        &self,
+        key: &str,
+        storage_class: StorageClass,
+    ) -> Result<(), StorageLayerError> {
+        self.throttle_request().await;
+
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.set_storage_class(key, storage_class).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.set_storage_class(key, storage_class).await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.set_storage_class(key, storage_class).await,
         }
     }
 
@@ -273,16 +948,24 @@ impl StorageLayer {
         &self,
         applied_names: Vec<String>,
     ) -> Result<Vec<String>, StorageLayerError> {
-        match self {
-            StorageLayer::S3(layer) => layer.get_pending_migrations(applied_names).await,
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.get_pending_migrations(applied_names).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.get_pending_migrations(applied_names).await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.get_pending_migrations(applied_names).await,
         }
     }
 
     /// Apply a migration by name
     #[tracing::instrument(skip(self))]
     pub async fn apply_migration(&self, name: &str) -> Result<(), StorageLayerError> {
-        match self {
-            StorageLayer::S3(layer) => layer.apply_migration(name).await,
+        match &self.backend {
+            StorageLayerBackend::S3(layer) => layer.apply_migration(name).await,
+            #[cfg(feature = "memory")]
+            StorageLayerBackend::Memory(layer) => layer.apply_migration(name).await,
+            #[cfg(feature = "chaos")]
+            StorageLayerBackend::Chaos(layer) => layer.apply_migration(name).await,
         }
     }
 }
@@ -307,6 +990,7 @@ pub(crate) trait StorageLayerImpl {
         &self,
         key: &str,
         expires_in: Duration,
+        options: PresignedDownloadOptions,
     ) -> Result<(PresignedRequest, DateTime<Utc>), StorageLayerError>;
 
     async fn upload_file(
@@ -314,15 +998,45 @@ pub(crate) trait StorageLayerImpl {
         key: &str,
         body: Bytes,
         options: UploadFileOptions,
+        metadata: ObjectMetadata,
     ) -> Result<(), StorageLayerError>;
 
     async fn add_bucket_notifications(&self, sns_arn: &str) -> Result<(), StorageLayerError>;
 
     async fn set_bucket_cors_origins(&self, origins: Vec<String>) -> Result<(), StorageLayerError>;
 
+    async fn set_bucket_lifecycle_policy(
+        &self,
+        policy: &BucketLifecyclePolicy,
+    ) -> Result<(), StorageLayerError>;
+
     async fn delete_file(&self, key: &str) -> Result<(), StorageLayerError>;
 
-    async fn get_file(&self, key: &str) -> Result<FileStream, StorageLayerError>;
+    async fn get_file(&self, key: &str) -> Result<(FileStream, ObjectMetadata), StorageLayerError>;
+
+    async fn get_file_metadata(&self, key: &str) -> Result<ObjectMetadata, StorageLayerError>;
+
+    async fn get_file_size(&self, key: &str) -> Result<u64, StorageLayerError>;
+
+    async fn get_file_range(
+        &self,
+        key: &str,
+        range: Range<u64>,
+    ) -> Result<FileStream, StorageLayerError>;
+
+    async fn put_object_tags(
+        &self,
+        key: &str,
+        tags: Vec<ObjectTag>,
+    ) -> Result<(), StorageLayerError>;
+
+    async fn get_object_tags(&self, key: &str) -> Result<Vec<ObjectTag>, StorageLayerError>;
+
+    async fn set_storage_class(
+        &self,
+        key: &str,
+        storage_class: StorageClass,
+    ) -> Result<(), StorageLayerError>;
 
     async fn get_pending_migrations(
         &self,
@@ -356,6 +1070,32 @@ impl Stream for FileStream {
 }
 
 impl FileStream {
+    /// Create a stream from an already loaded buffer of `bytes`
+    pub fn from_bytes(bytes: Bytes) -> Self {
+        Self {
+            stream: Box::pin(futures::stream::once(async move { Ok(bytes) })),
+        }
+    }
+
+    /// Wrap the stream so that each chunk waits for the `throttle`
+    /// to allow its bytes to be transferred
+    fn throttled(self, throttle: StorageThrottle) -> Self {
+        let stream = self.stream.then(move |result| {
+            let throttle = throttle.clone();
+            async move {
+                if let Ok(chunk) = result.as_ref() {
+                    throttle.acquire_bytes(chunk.len()).await;
+                }
+
+                result
+            }
+        });
+
+        Self {
+            stream: Box::pin(stream),
+        }
+    }
+
     /// Collect the stream to completion as a single [Bytes] buffer
     pub async fn collect_bytes(mut self) -> Result<Bytes, StorageLayerError> {
         let mut output = SegmentedBuf::new();
@@ -371,4 +1111,28 @@ impl FileStream {
 
         Ok(output.copy_to_bytes(output.remaining()))
     }
+
+    /// Collect the stream to completion as a single [Bytes] buffer, stops
+    /// collecting and returns [None] if the stream is larger than `max_size`
+    pub async fn collect_bytes_max(
+        mut self,
+        max_size: usize,
+    ) -> Result<Option<Bytes>, StorageLayerError> {
+        let mut output = SegmentedBuf::new();
+
+        while let Some(result) = self.next().await {
+            let chunk = result.map_err(|error| {
+                tracing::error!(?error, "failed to collect file stream bytes");
+                StorageLayerError::CollectBytes
+            })?;
+
+            if output.remaining() + chunk.len() > max_size {
+                return Ok(None);
+            }
+
+            output.push(chunk);
+        }
+
+        Ok(Some(output.copy_to_bytes(output.remaining())))
+    }
 }
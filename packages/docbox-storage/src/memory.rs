@@ -0,0 +1,301 @@
+//! # Memory Storage Backend
+//!
+//! In-memory storage backend intended for testing, allows running code that
+//! depends on a [StorageLayer](crate::StorageLayer) without a S3 compatible
+//! storage service available.
+//!
+//! Buckets and their files are shared between all layers created from the
+//! same [MemoryStorageLayerFactory] and are lost when the factory is dropped.
+//!
+//! Presigned uploads and downloads are not supported by this backend as there
+//! is no server to receive the requests.
+
+use crate::{
+    BucketLifecyclePolicy, CreateBucketOutcome, FileStream, ObjectMetadata, ObjectTag,
+    PresignedDownloadOptions, StorageClass, StorageLayerError, StorageLayerImpl, UploadFileOptions,
+};
+use aws_sdk_s3::presigning::PresignedRequest;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use std::{
+    collections::HashMap,
+    ops::Range,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+use thiserror::Error;
+
+/// Collection of buckets by name
+type MemoryBuckets = HashMap<String, MemoryBucket>;
+
+/// Collection of files within a bucket by key
+type MemoryBucket = HashMap<String, MemoryObject>;
+
+/// File stored within a memory bucket
+struct MemoryObject {
+    /// Contents of the file
+    body: Bytes,
+    /// Tags attached to the file
+    tags: Vec<ObjectTag>,
+    /// Metadata stored with the file
+    metadata: ObjectMetadata,
+}
+
+/// Errors that can occur when using the memory storage backend
+#[derive(Debug, Error)]
+pub enum MemoryStorageError {
+    /// Bucket does not exist
+    #[error("storage bucket not found")]
+    BucketNotFound,
+
+    /// File does not exist within the bucket
+    #[error("file not found")]
+    FileNotFound,
+
+    /// Presigned requests cannot be created for memory storage
+    #[error("presigned requests are not supported by memory storage")]
+    PresignedUnsupported,
+}
+
+/// Factory for creating [MemoryStorageLayer]s that share the same
+/// underlying buckets
+#[derive(Clone, Default)]
+pub struct MemoryStorageLayerFactory {
+    buckets: Arc<Mutex<MemoryBuckets>>,
+}
+
+impl MemoryStorageLayerFactory {
+    /// Create a new factory with no buckets
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new storage layer for the bucket with the provided `bucket_name`
+    pub fn create_storage_layer(&self, bucket_name: String) -> MemoryStorageLayer {
+        MemoryStorageLayer {
+            bucket_name,
+            buckets: self.buckets.clone(),
+        }
+    }
+}
+
+/// Storage layer backed by in-memory buckets
+#[derive(Clone)]
+pub struct MemoryStorageLayer {
+    /// Name of the bucket this layer is for
+    bucket_name: String,
+    /// Buckets shared with the factory
+    buckets: Arc<Mutex<MemoryBuckets>>,
+}
+
+impl MemoryStorageLayer {
+    /// Lock the shared buckets, the stored data is always left in a valid
+    /// state so a poisoned lock can still be used
+    fn buckets(&self) -> MutexGuard<'_, MemoryBuckets> {
+        self.buckets.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl StorageLayerImpl for MemoryStorageLayer {
+    fn bucket_name(&self) -> String {
+        self.bucket_name.clone()
+    }
+
+    async fn create_bucket(&self) -> Result<CreateBucketOutcome, StorageLayerError> {
+        let mut buckets = self.buckets();
+        if buckets.contains_key(&self.bucket_name) {
+            return Ok(CreateBucketOutcome::Existing);
+        }
+
+        buckets.insert(self.bucket_name.clone(), MemoryBucket::new());
+        Ok(CreateBucketOutcome::New)
+    }
+
+    async fn bucket_exists(&self) -> Result<bool, StorageLayerError> {
+        Ok(self.buckets().contains_key(&self.bucket_name))
+    }
+
+    async fn delete_bucket(&self) -> Result<(), StorageLayerError> {
+        self.buckets().remove(&self.bucket_name);
+        Ok(())
+    }
+
+    async fn create_presigned(
+        &self,
+        _key: &str,
+        _size: i64,
+    ) -> Result<(PresignedRequest, DateTime<Utc>), StorageLayerError> {
+        Err(MemoryStorageError::PresignedUnsupported.into())
+    }
+
+    async fn create_presigned_download(
+        &self,
+        _key: &str,
+        _expires_in: Duration,
+        _options: PresignedDownloadOptions,
+    ) -> Result<(PresignedRequest, DateTime<Utc>), StorageLayerError> {
+        Err(MemoryStorageError::PresignedUnsupported.into())
+    }
+
+    async fn upload_file(
+        &self,
+        key: &str,
+        body: Bytes,
+        options: UploadFileOptions,
+        metadata: ObjectMetadata,
+    ) -> Result<(), StorageLayerError> {
+        let mut buckets = self.buckets();
+        let bucket = buckets
+            .get_mut(&self.bucket_name)
+            .ok_or(MemoryStorageError::BucketNotFound)?;
+
+        let tags = options
+            .tags
+            .into_iter()
+            .flatten()
+            .map(ObjectTag::from)
+            .chain(options.object_tags)
+            .collect();
+
+        bucket.insert(
+            key.to_string(),
+            MemoryObject {
+                body,
+                tags,
+                metadata,
+            },
+        );
+        Ok(())
+    }
+
+    async fn add_bucket_notifications(&self, _sns_arn: &str) -> Result<(), StorageLayerError> {
+        // No-op, memory storage has no notifications
+        Ok(())
+    }
+
+    async fn set_bucket_cors_origins(
+        &self,
+        _origins: Vec<String>,
+    ) -> Result<(), StorageLayerError> {
+        // No-op, memory storage is not accessible from the frontend
+        Ok(())
+    }
+
+    async fn set_bucket_lifecycle_policy(
+        &self,
+        _policy: &BucketLifecyclePolicy,
+    ) -> Result<(), StorageLayerError> {
+        // No-op, memory storage objects do not expire
+        Ok(())
+    }
+
+    async fn delete_file(&self, key: &str) -> Result<(), StorageLayerError> {
+        if let Some(bucket) = self.buckets().get_mut(&self.bucket_name) {
+            bucket.remove(key);
+        }
+
+        Ok(())
+    }
+
+    async fn get_file(&self, key: &str) -> Result<(FileStream, ObjectMetadata), StorageLayerError> {
+        let buckets = self.buckets();
+        let bucket = buckets
+            .get(&self.bucket_name)
+            .ok_or(MemoryStorageError::BucketNotFound)?;
+        let file = bucket.get(key).ok_or(MemoryStorageError::FileNotFound)?;
+
+        Ok((FileStream::from_bytes(file.body.clone()), file.metadata))
+    }
+
+    async fn get_file_metadata(&self, key: &str) -> Result<ObjectMetadata, StorageLayerError> {
+        let buckets = self.buckets();
+        let bucket = buckets
+            .get(&self.bucket_name)
+            .ok_or(MemoryStorageError::BucketNotFound)?;
+        let file = bucket.get(key).ok_or(MemoryStorageError::FileNotFound)?;
+
+        Ok(file.metadata)
+    }
+
+    async fn get_file_size(&self, key: &str) -> Result<u64, StorageLayerError> {
+        let buckets = self.buckets();
+        let bucket = buckets
+            .get(&self.bucket_name)
+            .ok_or(MemoryStorageError::BucketNotFound)?;
+        let file = bucket.get(key).ok_or(MemoryStorageError::FileNotFound)?;
+
+        Ok(file.body.len() as u64)
+    }
+
+    async fn get_file_range(
+        &self,
+        key: &str,
+        range: Range<u64>,
+    ) -> Result<FileStream, StorageLayerError> {
+        let buckets = self.buckets();
+        let bucket = buckets
+            .get(&self.bucket_name)
+            .ok_or(MemoryStorageError::BucketNotFound)?;
+        let file = bucket.get(key).ok_or(MemoryStorageError::FileNotFound)?;
+
+        let length = file.body.len();
+        let start = (range.start as usize).min(length);
+        let end = (range.end as usize).clamp(start, length);
+
+        Ok(FileStream::from_bytes(file.body.slice(start..end)))
+    }
+
+    async fn put_object_tags(
+        &self,
+        key: &str,
+        tags: Vec<ObjectTag>,
+    ) -> Result<(), StorageLayerError> {
+        let mut buckets = self.buckets();
+        let bucket = buckets
+            .get_mut(&self.bucket_name)
+            .ok_or(MemoryStorageError::BucketNotFound)?;
+        let file = bucket
+            .get_mut(key)
+            .ok_or(MemoryStorageError::FileNotFound)?;
+
+        file.tags = tags;
+        Ok(())
+    }
+
+    async fn get_object_tags(&self, key: &str) -> Result<Vec<ObjectTag>, StorageLayerError> {
+        let buckets = self.buckets();
+        let bucket = buckets
+            .get(&self.bucket_name)
+            .ok_or(MemoryStorageError::BucketNotFound)?;
+        let file = bucket.get(key).ok_or(MemoryStorageError::FileNotFound)?;
+
+        Ok(file.tags.clone())
+    }
+
+    async fn set_storage_class(
+        &self,
+        key: &str,
+        _storage_class: StorageClass,
+    ) -> Result<(), StorageLayerError> {
+        // Memory storage has a single storage class, only ensure the file exists
+        let buckets = self.buckets();
+        let bucket = buckets
+            .get(&self.bucket_name)
+            .ok_or(MemoryStorageError::BucketNotFound)?;
+        bucket.get(key).ok_or(MemoryStorageError::FileNotFound)?;
+
+        Ok(())
+    }
+
+    async fn get_pending_migrations(
+        &self,
+        _applied_names: Vec<String>,
+    ) -> Result<Vec<String>, StorageLayerError> {
+        // Memory storage has no migrations
+        Ok(Vec::new())
+    }
+
+    async fn apply_migration(&self, _name: &str) -> Result<(), StorageLayerError> {
+        Ok(())
+    }
+}
@@ -8,38 +8,51 @@
 //! * `DOCBOX_S3_EXTERNAL_ENDPOINT` - Alternative "external" user facing endpoint, useful when running the server in docker with a different endpoint
 //! * `DOCBOX_S3_ACCESS_KEY_ID` - Access key ID when using a custom S3 endpoint
 //! * `DOCBOX_S3_ACCESS_KEY_SECRET` - Access key secret when using a custom S3 endpoint
+//! * `DOCBOX_S3_FORCE_PATH_STYLE` - Whether to force "path" style bucket access (Default: true for custom endpoints, false for AWS)
+//! * `DOCBOX_S3_ACCELERATE` - Whether to use the S3 transfer acceleration endpoint (AWS only)
+//! * `DOCBOX_S3_REQUEST_TIMEOUT` - Timeout in milliseconds for a complete S3 operation including retries
+//! * `DOCBOX_S3_MAX_ATTEMPTS` - Maximum number of attempts for a S3 request before giving up
+//! * `DOCBOX_S3_INITIAL_BACKOFF` - Initial backoff in milliseconds between retried S3 requests
+//! * `DOCBOX_S3_MAX_BACKOFF` - Maximum backoff in milliseconds between retried S3 requests
 
 use crate::{
-    CreateBucketOutcome, FileStream, StorageLayerError, StorageLayerImpl, UploadFileOptions,
-    UploadFileTag,
+    BucketLifecyclePolicy, CreateBucketOutcome, FileStream, ObjectMetadata, ObjectTag,
+    PresignedDownloadOptions, StorageClass, StorageLayerError, StorageLayerImpl, UploadFileOptions,
 };
 use aws_config::SdkConfig;
 use aws_sdk_s3::{
-    config::Credentials,
+    config::{Credentials, retry::RetryConfig, timeout::TimeoutConfig},
     error::SdkError,
     operation::{
-        create_bucket::CreateBucketError, delete_bucket::DeleteBucketError,
+        copy_object::CopyObjectError, create_bucket::CreateBucketError,
+        delete_bucket::DeleteBucketError, delete_bucket_lifecycle::DeleteBucketLifecycleError,
         delete_object::DeleteObjectError,
         get_bucket_lifecycle_configuration::GetBucketLifecycleConfigurationError,
-        get_object::GetObjectError, head_bucket::HeadBucketError,
+        get_object::GetObjectError, get_object_tagging::GetObjectTaggingError,
+        head_bucket::HeadBucketError, head_object::HeadObjectError,
         put_bucket_cors::PutBucketCorsError,
         put_bucket_lifecycle_configuration::PutBucketLifecycleConfigurationError,
         put_bucket_notification_configuration::PutBucketNotificationConfigurationError,
-        put_object::PutObjectError,
+        put_bucket_tagging::PutBucketTaggingError, put_object::PutObjectError,
+        put_object_tagging::PutObjectTaggingError,
     },
     presigning::{PresignedRequest, PresigningConfig},
     primitives::ByteStream,
     types::{
-        BucketLifecycleConfiguration, BucketLocationConstraint, CorsConfiguration, CorsRule,
-        CreateBucketConfiguration, LifecycleExpiration, LifecycleRule, LifecycleRuleFilter,
-        NotificationConfiguration, QueueConfiguration, Tag,
+        AbortIncompleteMultipartUpload, BucketLifecycleConfiguration, BucketLocationConstraint,
+        CorsConfiguration, CorsRule, CreateBucketConfiguration, LifecycleExpiration, LifecycleRule,
+        LifecycleRuleFilter, NotificationConfiguration, QueueConfiguration,
+        StorageClass as S3StorageClass, Tag, Tagging,
     },
 };
 use bytes::Bytes;
 use chrono::{DateTime, TimeDelta, Utc};
 use futures::Stream;
 use serde::{Deserialize, Serialize};
-use std::{error::Error, fmt::Debug, time::Duration};
+use std::{
+    collections::HashMap, error::Error, fmt::Debug, num::ParseIntError, ops::Range,
+    str::ParseBoolError, time::Duration,
+};
 use thiserror::Error;
 
 type S3Client = aws_sdk_s3::Client;
@@ -50,6 +63,44 @@ type S3Client = aws_sdk_s3::Client;
 pub struct S3StorageLayerFactoryConfig {
     /// Endpoint to use for requests
     pub endpoint: S3Endpoint,
+    /// Data residency region the storage is located within (i.e "eu-west"),
+    /// tenants assigned to a region can only use storage within that region
+    pub data_region: Option<String>,
+
+    /// Whether to force "path" style bucket access (i.e http://host/bucket/key)
+    /// instead of "virtual hosted" style access (i.e http://bucket.host/key),
+    /// required by most self hosted S3 compatible services (MinIO)
+    ///
+    /// Default: true for custom endpoints, false for AWS
+    pub force_path_style: Option<bool>,
+
+    /// Whether to use the S3 transfer acceleration endpoint for requests, the
+    /// bucket must have transfer acceleration enabled. Only supported by AWS
+    ///
+    /// Default: false
+    pub accelerate: bool,
+
+    /// Timeout in milliseconds for a complete S3 operation (Including any
+    /// retry attempts) before it is cancelled
+    ///
+    /// Default: No timeout
+    pub request_timeout: Option<u64>,
+
+    /// Maximum number of attempts (Including the initial request) for a
+    /// S3 request before giving up
+    ///
+    /// Default: SDK configuration (3 attempts)
+    pub max_attempts: Option<u32>,
+
+    /// Initial backoff in milliseconds between retried S3 requests
+    ///
+    /// Default: SDK configuration (1s)
+    pub initial_backoff: Option<u64>,
+
+    /// Maximum backoff in milliseconds between retried S3 requests
+    ///
+    /// Default: SDK configuration (20s)
+    pub max_backoff: Option<u64>,
 }
 
 /// Errors that could occur when loading the S3 storage layer configuration
@@ -62,14 +113,185 @@ pub enum S3StorageLayerFactoryConfigError {
     /// Using a custom endpoint but didn't specify the access key secret
     #[error("cannot use DOCBOX_S3_ENDPOINT without specifying DOCBOX_S3_ACCESS_KEY_SECRET")]
     MissingAccessKeySecret,
+
+    /// Force path style value was not a valid boolean
+    #[error("invalid DOCBOX_S3_FORCE_PATH_STYLE environment variable")]
+    InvalidForcePathStyle(ParseBoolError),
+
+    /// Accelerate value was not a valid boolean
+    #[error("invalid DOCBOX_S3_ACCELERATE environment variable")]
+    InvalidAccelerate(ParseBoolError),
+
+    /// Request timeout was not a valid number
+    #[error("invalid DOCBOX_S3_REQUEST_TIMEOUT environment variable")]
+    InvalidRequestTimeout(ParseIntError),
+
+    /// Max attempts was not a valid number
+    #[error("invalid DOCBOX_S3_MAX_ATTEMPTS environment variable")]
+    InvalidMaxAttempts(ParseIntError),
+
+    /// Initial backoff was not a valid number
+    #[error("invalid DOCBOX_S3_INITIAL_BACKOFF environment variable")]
+    InvalidInitialBackoff(ParseIntError),
+
+    /// Max backoff was not a valid number
+    #[error("invalid DOCBOX_S3_MAX_BACKOFF environment variable")]
+    InvalidMaxBackoff(ParseIntError),
+}
+
+/// Errors that could occur when creating a [S3StorageLayerFactory] from
+/// an invalid configuration
+#[derive(Debug, Error)]
+pub enum S3StorageLayerFactoryError {
+    /// Transfer acceleration is only available when using AWS
+    #[error("accelerate cannot be used with a custom S3 endpoint")]
+    AccelerateCustomEndpoint,
+
+    /// Transfer acceleration requires virtual hosted style bucket access
+    #[error("accelerate cannot be used with force_path_style")]
+    AcceleratePathStyle,
+
+    /// Request timeout must be non zero
+    #[error("request_timeout must be greater than zero")]
+    InvalidRequestTimeout,
+
+    /// At least one attempt must be allowed
+    #[error("max_attempts must be greater than zero")]
+    InvalidMaxAttempts,
+
+    /// Initial backoff cannot exceed the maximum backoff
+    #[error("initial_backoff must not be greater than max_backoff")]
+    InvalidBackoff,
 }
 
 impl S3StorageLayerFactoryConfig {
     /// Load a [S3StorageLayerFactoryConfig] from the current environment
     pub fn from_env() -> Result<Self, S3StorageLayerFactoryConfigError> {
         let endpoint = S3Endpoint::from_env()?;
+        let data_region = std::env::var("DOCBOX_S3_DATA_REGION").ok();
+
+        let force_path_style = std::env::var("DOCBOX_S3_FORCE_PATH_STYLE")
+            .ok()
+            .map(|value| value.parse::<bool>())
+            .transpose()
+            .map_err(S3StorageLayerFactoryConfigError::InvalidForcePathStyle)?;
+
+        let accelerate = std::env::var("DOCBOX_S3_ACCELERATE")
+            .ok()
+            .map(|value| value.parse::<bool>())
+            .transpose()
+            .map_err(S3StorageLayerFactoryConfigError::InvalidAccelerate)?
+            .unwrap_or_default();
+
+        let request_timeout = std::env::var("DOCBOX_S3_REQUEST_TIMEOUT")
+            .ok()
+            .map(|value| value.parse::<u64>())
+            .transpose()
+            .map_err(S3StorageLayerFactoryConfigError::InvalidRequestTimeout)?;
+
+        let max_attempts = std::env::var("DOCBOX_S3_MAX_ATTEMPTS")
+            .ok()
+            .map(|value| value.parse::<u32>())
+            .transpose()
+            .map_err(S3StorageLayerFactoryConfigError::InvalidMaxAttempts)?;
+
+        let initial_backoff = std::env::var("DOCBOX_S3_INITIAL_BACKOFF")
+            .ok()
+            .map(|value| value.parse::<u64>())
+            .transpose()
+            .map_err(S3StorageLayerFactoryConfigError::InvalidInitialBackoff)?;
+
+        let max_backoff = std::env::var("DOCBOX_S3_MAX_BACKOFF")
+            .ok()
+            .map(|value| value.parse::<u64>())
+            .transpose()
+            .map_err(S3StorageLayerFactoryConfigError::InvalidMaxBackoff)?;
+
+        Ok(Self {
+            endpoint,
+            data_region,
+            force_path_style,
+            accelerate,
+            request_timeout,
+            max_attempts,
+            initial_backoff,
+            max_backoff,
+        })
+    }
+
+    /// Whether "path" style bucket access should be used, custom endpoints
+    /// default to path style access
+    pub fn force_path_style(&self) -> bool {
+        self.force_path_style
+            .unwrap_or(matches!(self.endpoint, S3Endpoint::Custom { .. }))
+    }
+
+    /// Validate that the tuning options are compatible with each other
+    pub fn validate(&self) -> Result<(), S3StorageLayerFactoryError> {
+        if self.accelerate {
+            if matches!(self.endpoint, S3Endpoint::Custom { .. }) {
+                return Err(S3StorageLayerFactoryError::AccelerateCustomEndpoint);
+            }
+
+            if self.force_path_style() {
+                return Err(S3StorageLayerFactoryError::AcceleratePathStyle);
+            }
+        }
+
+        if self.request_timeout == Some(0) {
+            return Err(S3StorageLayerFactoryError::InvalidRequestTimeout);
+        }
+
+        if self.max_attempts == Some(0) {
+            return Err(S3StorageLayerFactoryError::InvalidMaxAttempts);
+        }
+
+        if let (Some(initial_backoff), Some(max_backoff)) = (self.initial_backoff, self.max_backoff)
+            && initial_backoff > max_backoff
+        {
+            return Err(S3StorageLayerFactoryError::InvalidBackoff);
+        }
 
-        Ok(Self { endpoint })
+        Ok(())
+    }
+
+    /// Create the retry configuration for the client, options that are
+    /// not specified are inherited from the `base` SDK configuration
+    fn retry_config(&self, base: Option<&RetryConfig>) -> Option<RetryConfig> {
+        if self.max_attempts.is_none()
+            && self.initial_backoff.is_none()
+            && self.max_backoff.is_none()
+        {
+            return None;
+        }
+
+        let mut retry_config = base.cloned().unwrap_or_else(RetryConfig::standard);
+
+        if let Some(max_attempts) = self.max_attempts {
+            retry_config = retry_config.with_max_attempts(max_attempts);
+        }
+
+        if let Some(initial_backoff) = self.initial_backoff {
+            retry_config =
+                retry_config.with_initial_backoff(Duration::from_millis(initial_backoff));
+        }
+
+        if let Some(max_backoff) = self.max_backoff {
+            retry_config = retry_config.with_max_backoff(Duration::from_millis(max_backoff));
+        }
+
+        Some(retry_config)
+    }
+
+    /// Create the timeout configuration for the client, timeouts that are
+    /// not specified are inherited from the `base` SDK configuration
+    fn timeout_config(&self, base: Option<&TimeoutConfig>) -> Option<TimeoutConfig> {
+        let request_timeout = self.request_timeout?;
+        let mut builder = base
+            .map(TimeoutConfig::to_builder)
+            .unwrap_or_else(TimeoutConfig::builder);
+        builder.set_operation_timeout(Some(Duration::from_millis(request_timeout)));
+        Some(builder.build())
     }
 }
 
@@ -137,15 +359,35 @@ pub struct S3StorageLayerFactory {
     client: S3Client,
     /// Optional different client for creating presigned external requests
     external_client: Option<S3Client>,
+    /// Data residency region the storage is located within
+    data_region: Option<String>,
 }
 
 impl S3StorageLayerFactory {
-    /// Create a [S3StorageLayerFactory] from a config
-    pub fn from_config(aws_config: &SdkConfig, config: S3StorageLayerFactoryConfig) -> Self {
+    /// Create a [S3StorageLayerFactory] from a config, fails if the
+    /// provided tuning options are invalid
+    pub fn from_config(
+        aws_config: &SdkConfig,
+        config: S3StorageLayerFactoryConfig,
+    ) -> Result<Self, S3StorageLayerFactoryError> {
+        config.validate()?;
+
+        let mut config_builder = aws_sdk_s3::config::Builder::from(aws_config)
+            .force_path_style(config.force_path_style())
+            .accelerate(config.accelerate);
+
+        if let Some(retry_config) = config.retry_config(aws_config.retry_config()) {
+            config_builder = config_builder.retry_config(retry_config);
+        }
+
+        if let Some(timeout_config) = config.timeout_config(aws_config.timeout_config()) {
+            config_builder = config_builder.timeout_config(timeout_config);
+        }
+
         let (client, external_client) = match config.endpoint {
             S3Endpoint::Aws => {
                 tracing::debug!("using aws s3 storage layer");
-                (S3Client::new(aws_config), None)
+                (S3Client::from_conf(config_builder.build()), None)
             }
             S3Endpoint::Custom {
                 endpoint,
@@ -162,9 +404,7 @@ impl S3StorageLayerFactory {
                     "docbox_key_provider",
                 );
 
-                // Enforces the "path" style for S3 bucket access
-                let config_builder = aws_sdk_s3::config::Builder::from(aws_config)
-                    .force_path_style(true)
+                let config_builder = config_builder
                     .endpoint_url(endpoint)
                     .credentials_provider(credentials);
 
@@ -188,10 +428,16 @@ impl S3StorageLayerFactory {
             }
         };
 
-        Self {
+        Ok(Self {
             client,
             external_client,
-        }
+            data_region: config.data_region,
+        })
+    }
+
+    /// Data residency region the storage is located within
+    pub fn data_region(&self) -> Option<&str> {
+        self.data_region.as_deref()
     }
 
     /// Create a [S3StorageLayer] for the provided `bucket_name`
@@ -227,10 +473,10 @@ impl S3StorageLayer {
         }
     }
 
-    /// Migration to add storage lifecycle rules tags to the storage bucket
-    /// to allow expiring objects
-    async fn m1_storage_lifecycle_rules(&self) -> Result<(), StorageLayerError> {
-        let existing_lifecycle_configuration_rules = match self
+    /// Get the existing lifecycle rules for the bucket, [None] when the
+    /// bucket has no lifecycle configuration
+    async fn get_lifecycle_rules(&self) -> Result<Option<Vec<LifecycleRule>>, StorageLayerError> {
+        match self
             .client
             .get_bucket_lifecycle_configuration()
             .bucket(&self.bucket_name)
@@ -242,7 +488,7 @@ impl S3StorageLayer {
                     "failed to get existing bucket lifecycle configuration"
                 )
             }) {
-            Ok(value) => value.rules,
+            Ok(value) => Ok(value.rules),
             Err(error) => match error.as_service_error() {
                 // Tolerate NoSuchLifecycleConfiguration error for buckets that have no rules yet
                 Some(error)
@@ -251,12 +497,18 @@ impl S3StorageLayer {
                         .code()
                         .is_some_and(|code| code == "NoSuchLifecycleConfiguration") =>
                 {
-                    None
+                    Ok(None)
                 }
 
-                _ => return Err(S3StorageError::GetBucketLifecycleConfiguration(error).into()),
+                _ => Err(S3StorageError::GetBucketLifecycleConfiguration(error).into()),
             },
-        };
+        }
+    }
+
+    /// Migration to add storage lifecycle rules tags to the storage bucket
+    /// to allow expiring objects
+    async fn m1_storage_lifecycle_rules(&self) -> Result<(), StorageLayerError> {
+        let existing_lifecycle_configuration_rules = self.get_lifecycle_rules().await?;
 
         self.client
             .put_bucket_lifecycle_configuration()
@@ -393,6 +645,30 @@ pub enum S3StorageError {
     #[error("failed to get file storage object")]
     GetObject(SdkError<GetObjectError>),
 
+    /// Failed to get the file storage object metadata
+    #[error("failed to get file storage object metadata")]
+    HeadObject(SdkError<HeadObjectError>),
+
+    /// File storage object metadata did not include the object size
+    #[error("file storage object size is unknown")]
+    MissingContentLength,
+
+    /// Failed to make the object tagging
+    #[error("failed to create file object tagging")]
+    CreateObjectTagging,
+
+    /// Failed to put the file object tags
+    #[error("failed to set file object tags")]
+    PutObjectTagging(SdkError<PutObjectTaggingError>),
+
+    /// Failed to get the file object tags
+    #[error("failed to get file object tags")]
+    GetObjectTagging(SdkError<GetObjectTaggingError>),
+
+    /// Failed to copy a file object to change its storage class
+    #[error("failed to change file object storage class")]
+    CopyObject(SdkError<CopyObjectError>),
+
     /// Failed to get the existing bucket lifecycle configuration
     ///
     /// This error is allowed to expose the inner error details as
@@ -408,10 +684,60 @@ pub enum S3StorageError {
     /// helpful for management
     #[error("failed to put bucket lifecycle configuration: {0}")]
     PutBucketLifecycleConfiguration(SdkError<PutBucketLifecycleConfigurationError>),
+
+    /// Failed to make the lifecycle config or rules
+    #[error("failed to create bucket lifecycle config")]
+    CreateLifecycleConfig,
+
+    /// Failed to delete the bucket lifecycle configuration
+    ///
+    /// This error is allowed to expose the inner error details as
+    /// it is only used by the management layer and these errors are
+    /// helpful for management
+    #[error("failed to delete bucket lifecycle configuration: {0}")]
+    DeleteBucketLifecycle(SdkError<DeleteBucketLifecycleError>),
+
+    /// Failed to make the bucket tagging
+    #[error("failed to create bucket tagging")]
+    CreateBucketTagging,
+
+    /// Failed to put the bucket tagging
+    ///
+    /// This error is allowed to expose the inner error details as
+    /// it is only used by the management layer and these errors are
+    /// helpful for management
+    #[error("failed to put bucket tagging: {0}")]
+    PutBucketTagging(SdkError<PutBucketTaggingError>),
 }
 
+/// Prefix for the IDs of lifecycle rules created from a [BucketLifecyclePolicy]
+const POLICY_RULE_ID_PREFIX: &str = "docbox-policy-";
+
 const MIGRATION_NAMES: &[&str] = &["m1_storage_lifecycle_rules"];
 
+impl ObjectMetadata {
+    /// Create the S3 user metadata for the object, [None] when there
+    /// is no metadata to store
+    fn to_s3_metadata(self) -> Option<HashMap<String, String>> {
+        let key_version = self.encryption_key_version?;
+        Some(HashMap::from([(
+            Self::ENCRYPTION_KEY_VERSION.to_string(),
+            key_version.to_string(),
+        )]))
+    }
+
+    /// Read the metadata from the S3 user metadata of an object
+    fn from_s3_metadata(metadata: Option<&HashMap<String, String>>) -> Self {
+        let encryption_key_version = metadata
+            .and_then(|metadata| metadata.get(Self::ENCRYPTION_KEY_VERSION))
+            .and_then(|value| value.parse().ok());
+
+        Self {
+            encryption_key_version,
+        }
+    }
+}
+
 impl StorageLayerImpl for S3StorageLayer {
     fn bucket_name(&self) -> String {
         self.bucket_name.clone()
@@ -511,14 +837,27 @@ impl StorageLayerImpl for S3StorageLayer {
         key: &str,
         body: Bytes,
         options: UploadFileOptions,
+        metadata: ObjectMetadata,
     ) -> Result<(), StorageLayerError> {
-        let tagging = options.tags.map(|tags| {
+        let tags: Vec<ObjectTag> = options
+            .tags
+            .into_iter()
+            .flatten()
+            .map(ObjectTag::from)
+            .chain(options.object_tags)
+            .collect();
+
+        // Tagging is provided as URL query parameters
+        let tagging = (!tags.is_empty()).then(|| {
             use itertools::Itertools;
 
-            tags.into_iter()
-                .map(|tag| match tag {
-                    UploadFileTag::ExpireDays1 => "expire=1d",
-                    UploadFileTag::ExpireDays30 => "expire=30d",
+            tags.iter()
+                .map(|tag| {
+                    format!(
+                        "{}={}",
+                        urlencoding::encode(&tag.key),
+                        urlencoding::encode(&tag.value)
+                    )
                 })
                 .join("&")
         });
@@ -529,6 +868,7 @@ impl StorageLayerImpl for S3StorageLayer {
             .content_type(options.content_type)
             .key(key)
             .set_tagging(tagging)
+            .set_metadata(metadata.to_s3_metadata())
             .body(body.into())
             .send()
             .await
@@ -582,6 +922,7 @@ impl StorageLayerImpl for S3StorageLayer {
         &self,
         key: &str,
         expires_in: Duration,
+        options: PresignedDownloadOptions,
     ) -> Result<(PresignedRequest, DateTime<Utc>), StorageLayerError> {
         let expires_at = Utc::now()
             .checked_add_signed(TimeDelta::seconds(expires_in.as_secs() as i64))
@@ -596,6 +937,8 @@ impl StorageLayerImpl for S3StorageLayer {
             .get_object()
             .bucket(&self.bucket_name)
             .key(key)
+            .set_response_content_disposition(options.content_disposition)
+            .set_response_content_type(options.content_type)
             .presigned(PresigningConfig::expires_in(expires_in).map_err(|error| {
                 tracing::error!(?error, "failed to create presigned download config");
                 S3StorageError::PresignedConfig
@@ -686,6 +1029,128 @@ impl StorageLayerImpl for S3StorageLayer {
         Ok(())
     }
 
+    async fn set_bucket_lifecycle_policy(
+        &self,
+        policy: &BucketLifecyclePolicy,
+    ) -> Result<(), StorageLayerError> {
+        // Keep any rules that were not created from a previous policy
+        let mut rules: Vec<LifecycleRule> = self
+            .get_lifecycle_rules()
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|rule| {
+                !rule
+                    .id()
+                    .is_some_and(|id| id.starts_with(POLICY_RULE_ID_PREFIX))
+            })
+            .collect();
+
+        if let Some(days) = policy.abort_incomplete_multipart_upload_days {
+            rules.push(
+                LifecycleRule::builder()
+                    .id(format!("{POLICY_RULE_ID_PREFIX}abort-multipart"))
+                    .status(aws_sdk_s3::types::ExpirationStatus::Enabled)
+                    .filter(LifecycleRuleFilter::builder().prefix("").build())
+                    .abort_incomplete_multipart_upload(
+                        AbortIncompleteMultipartUpload::builder()
+                            .days_after_initiation(days)
+                            .build(),
+                    )
+                    .build()
+                    .map_err(|error| {
+                        tracing::error!(?error, "failed to create lifecycle rule");
+                        S3StorageError::CreateLifecycleConfig
+                    })?,
+            );
+        }
+
+        for (index, expiration) in policy.expire_prefixes.iter().enumerate() {
+            rules.push(
+                LifecycleRule::builder()
+                    .id(format!("{POLICY_RULE_ID_PREFIX}expire-{index}"))
+                    .status(aws_sdk_s3::types::ExpirationStatus::Enabled)
+                    .filter(
+                        LifecycleRuleFilter::builder()
+                            .prefix(&expiration.prefix)
+                            .build(),
+                    )
+                    .expiration(LifecycleExpiration::builder().days(expiration.days).build())
+                    .build()
+                    .map_err(|error| {
+                        tracing::error!(?error, "failed to create lifecycle rule");
+                        S3StorageError::CreateLifecycleConfig
+                    })?,
+            );
+        }
+
+        if rules.is_empty() {
+            // Lifecycle configurations cannot be empty, remove it entirely instead
+            self.client
+                .delete_bucket_lifecycle()
+                .bucket(&self.bucket_name)
+                .send()
+                .await
+                .inspect_err(|error| {
+                    tracing::error!(?error, "failed to delete bucket lifecycle configuration")
+                })
+                .map_err(S3StorageError::DeleteBucketLifecycle)?;
+        } else {
+            self.client
+                .put_bucket_lifecycle_configuration()
+                .bucket(&self.bucket_name)
+                .lifecycle_configuration(
+                    BucketLifecycleConfiguration::builder()
+                        .set_rules(Some(rules))
+                        .build()
+                        .map_err(|error| {
+                            tracing::error!(?error, "failed to create lifecycle config");
+                            S3StorageError::CreateLifecycleConfig
+                        })?,
+                )
+                .send()
+                .await
+                .inspect_err(|error| {
+                    tracing::error!(?error, "failed to put bucket lifecycle configuration")
+                })
+                .map_err(S3StorageError::PutBucketLifecycleConfiguration)?;
+        }
+
+        // Existing bucket tags are left as is when no tags are specified
+        if policy.bucket_tags.is_empty() {
+            return Ok(());
+        }
+
+        let tags = policy
+            .bucket_tags
+            .iter()
+            .map(|tag| Tag::builder().key(&tag.key).value(&tag.value).build())
+            .collect::<Result<Vec<Tag>, _>>()
+            .map_err(|error| {
+                tracing::error!(?error, "failed to create bucket tag");
+                S3StorageError::CreateBucketTagging
+            })?;
+
+        self.client
+            .put_bucket_tagging()
+            .bucket(&self.bucket_name)
+            .tagging(
+                Tagging::builder()
+                    .set_tag_set(Some(tags))
+                    .build()
+                    .map_err(|error| {
+                        tracing::error!(?error, "failed to create bucket tagging");
+                        S3StorageError::CreateBucketTagging
+                    })?,
+            )
+            .send()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "failed to put bucket tagging"))
+            .map_err(S3StorageError::PutBucketTagging)?;
+
+        Ok(())
+    }
+
     async fn delete_file(&self, key: &str) -> Result<(), StorageLayerError> {
         if let Err(error) = self
             .client
@@ -713,7 +1178,7 @@ impl StorageLayerImpl for S3StorageLayer {
         Ok(())
     }
 
-    async fn get_file(&self, key: &str) -> Result<FileStream, StorageLayerError> {
+    async fn get_file(&self, key: &str) -> Result<(FileStream, ObjectMetadata), StorageLayerError> {
         let object = self
             .client
             .get_object()
@@ -726,6 +1191,74 @@ impl StorageLayerImpl for S3StorageLayer {
                 S3StorageError::GetObject(error)
             })?;
 
+        let metadata = ObjectMetadata::from_s3_metadata(object.metadata.as_ref());
+        let stream = FileStream {
+            stream: Box::pin(AwsFileStream { inner: object.body }),
+        };
+
+        Ok((stream, metadata))
+    }
+
+    async fn get_file_metadata(&self, key: &str) -> Result<ObjectMetadata, StorageLayerError> {
+        let object = self
+            .client
+            .head_object()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to get file storage object metadata");
+                S3StorageError::HeadObject(error)
+            })?;
+
+        Ok(ObjectMetadata::from_s3_metadata(object.metadata.as_ref()))
+    }
+
+    async fn get_file_size(&self, key: &str) -> Result<u64, StorageLayerError> {
+        let object = self
+            .client
+            .head_object()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to get file storage object metadata");
+                S3StorageError::HeadObject(error)
+            })?;
+
+        let size = object
+            .content_length()
+            .and_then(|length| u64::try_from(length).ok())
+            .ok_or(S3StorageError::MissingContentLength)?;
+
+        Ok(size)
+    }
+
+    async fn get_file_range(
+        &self,
+        key: &str,
+        range: Range<u64>,
+    ) -> Result<FileStream, StorageLayerError> {
+        if range.is_empty() {
+            return Ok(FileStream::from_bytes(Bytes::new()));
+        }
+
+        // HTTP byte ranges are inclusive of the end byte
+        let object = self
+            .client
+            .get_object()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .range(format!("bytes={}-{}", range.start, range.end - 1))
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to get file storage object range");
+                S3StorageError::GetObject(error)
+            })?;
+
         let stream = FileStream {
             stream: Box::pin(AwsFileStream { inner: object.body }),
         };
@@ -733,6 +1266,97 @@ impl StorageLayerImpl for S3StorageLayer {
         Ok(stream)
     }
 
+    async fn put_object_tags(
+        &self,
+        key: &str,
+        tags: Vec<ObjectTag>,
+    ) -> Result<(), StorageLayerError> {
+        let tags = tags
+            .into_iter()
+            .map(|tag| Tag::builder().key(tag.key).value(tag.value).build())
+            .collect::<Result<Vec<Tag>, _>>()
+            .map_err(|error| {
+                tracing::error!(?error, "failed to create object tag");
+                S3StorageError::CreateObjectTagging
+            })?;
+
+        self.client
+            .put_object_tagging()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .tagging(
+                Tagging::builder()
+                    .set_tag_set(Some(tags))
+                    .build()
+                    .map_err(|error| {
+                        tracing::error!(?error, "failed to create object tagging");
+                        S3StorageError::CreateObjectTagging
+                    })?,
+            )
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to put file object tags");
+                S3StorageError::PutObjectTagging(error)
+            })?;
+
+        Ok(())
+    }
+
+    async fn get_object_tags(&self, key: &str) -> Result<Vec<ObjectTag>, StorageLayerError> {
+        let output = self
+            .client
+            .get_object_tagging()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to get file object tags");
+                S3StorageError::GetObjectTagging(error)
+            })?;
+
+        Ok(output
+            .tag_set
+            .into_iter()
+            .map(|tag| ObjectTag {
+                key: tag.key,
+                value: tag.value,
+            })
+            .collect())
+    }
+
+    async fn set_storage_class(
+        &self,
+        key: &str,
+        storage_class: StorageClass,
+    ) -> Result<(), StorageLayerError> {
+        let storage_class = match storage_class {
+            StorageClass::Standard => S3StorageClass::Standard,
+            // Instant retrieval keeps archived files readable without a restore
+            StorageClass::Archive => S3StorageClass::GlacierIr,
+        };
+
+        // Copying the object onto itself replaces the storage class while
+        // keeping the existing metadata and tags
+        let copy_source = format!("{}/{}", self.bucket_name, urlencoding::encode(key));
+
+        self.client
+            .copy_object()
+            .bucket(&self.bucket_name)
+            .key(key)
+            .copy_source(copy_source)
+            .storage_class(storage_class)
+            .send()
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, "failed to change file storage class");
+                S3StorageError::CopyObject(error)
+            })?;
+
+        Ok(())
+    }
+
     async fn get_pending_migrations(
         &self,
         applied_names: Vec<String>,
@@ -0,0 +1,177 @@
+//! # Throttle
+//!
+//! Rate limiting for storage layers used by bulk operations (i.e rebuilding
+//! the search index or reprocessing files) so that they don't saturate the
+//! storage bandwidth available to production traffic.
+//!
+//! A [StorageThrottle] can be attached to a [StorageLayer](crate::StorageLayer)
+//! using [StorageLayer::with_throttle](crate::StorageLayer::with_throttle), all
+//! layers sharing the same throttle share the same limits.
+//!
+//! ## Environment Variables
+//!
+//! * `DOCBOX_BULK_STORAGE_BYTES_PER_SECOND` - Maximum bytes per second transferred by bulk operations
+//! * `DOCBOX_BULK_STORAGE_REQUESTS_PER_SECOND` - Maximum requests per second made by bulk operations
+
+use serde::{Deserialize, Serialize};
+use std::{
+    num::ParseIntError,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::time::Instant;
+
+/// Configuration for a [StorageThrottle]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StorageThrottleConfig {
+    /// Maximum number of bytes per second to upload or download
+    ///
+    /// Default: Unlimited
+    pub bytes_per_second: Option<u64>,
+
+    /// Maximum number of storage requests per second
+    ///
+    /// Default: Unlimited
+    pub requests_per_second: Option<u64>,
+}
+
+/// Errors that could occur when loading the throttle configuration
+/// from the environment
+#[derive(Debug, Error)]
+pub enum StorageThrottleConfigError {
+    /// Bytes per second was not a valid number
+    #[error("invalid DOCBOX_BULK_STORAGE_BYTES_PER_SECOND environment variable")]
+    InvalidBytesPerSecond(ParseIntError),
+
+    /// Requests per second was not a valid number
+    #[error("invalid DOCBOX_BULK_STORAGE_REQUESTS_PER_SECOND environment variable")]
+    InvalidRequestsPerSecond(ParseIntError),
+}
+
+impl StorageThrottleConfig {
+    /// Load the throttle configuration from the current environment variables
+    pub fn from_env() -> Result<Self, StorageThrottleConfigError> {
+        let bytes_per_second = std::env::var("DOCBOX_BULK_STORAGE_BYTES_PER_SECOND")
+            .ok()
+            .map(|value| value.parse::<u64>())
+            .transpose()
+            .map_err(StorageThrottleConfigError::InvalidBytesPerSecond)?;
+
+        let requests_per_second = std::env::var("DOCBOX_BULK_STORAGE_REQUESTS_PER_SECOND")
+            .ok()
+            .map(|value| value.parse::<u64>())
+            .transpose()
+            .map_err(StorageThrottleConfigError::InvalidRequestsPerSecond)?;
+
+        Ok(Self {
+            bytes_per_second,
+            requests_per_second,
+        })
+    }
+
+    /// Check if the config does not limit anything
+    pub fn is_unlimited(&self) -> bool {
+        self.bytes_per_second.is_none_or(|value| value == 0)
+            && self.requests_per_second.is_none_or(|value| value == 0)
+    }
+}
+
+/// Shared rate limiter for storage requests and transferred bytes
+#[derive(Clone)]
+pub struct StorageThrottle {
+    /// Limit for transferred bytes
+    bytes: Option<Arc<TokenBucket>>,
+    /// Limit for requests
+    requests: Option<Arc<TokenBucket>>,
+}
+
+impl StorageThrottle {
+    /// Create a throttle from the provided `config`, returns [None] when
+    /// the config does not limit anything
+    pub fn from_config(config: &StorageThrottleConfig) -> Option<Self> {
+        if config.is_unlimited() {
+            return None;
+        }
+
+        Some(Self {
+            bytes: config
+                .bytes_per_second
+                .filter(|rate| *rate > 0)
+                .map(|rate| Arc::new(TokenBucket::new(rate))),
+            requests: config
+                .requests_per_second
+                .filter(|rate| *rate > 0)
+                .map(|rate| Arc::new(TokenBucket::new(rate))),
+        })
+    }
+
+    /// Wait until another request is allowed to be made
+    pub async fn acquire_request(&self) {
+        if let Some(requests) = self.requests.as_ref() {
+            requests.acquire(1).await;
+        }
+    }
+
+    /// Wait until `length` bytes are allowed to be transferred
+    pub async fn acquire_bytes(&self, length: usize) {
+        if let Some(bytes) = self.bytes.as_ref() {
+            bytes.acquire(length as u64).await;
+        }
+    }
+}
+
+/// Token bucket allowing up to one second worth of burst at the `rate`,
+/// acquiring more than the available tokens borrows against future tokens
+/// so large transfers are delayed proportionally
+struct TokenBucket {
+    /// Tokens added per second
+    rate: u64,
+    /// Current state of the bucket
+    state: Mutex<TokenBucketState>,
+}
+
+struct TokenBucketState {
+    /// Available tokens, negative when tokens have been borrowed
+    tokens: f64,
+    /// Last time tokens were added to the bucket
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate,
+            state: Mutex::new(TokenBucketState {
+                tokens: rate as f64,
+                updated_at: Instant::now(),
+            }),
+        }
+    }
+
+    async fn acquire(&self, amount: u64) {
+        let wait = {
+            let mut state = match self.state.lock() {
+                Ok(value) => value,
+                Err(error) => error.into_inner(),
+            };
+
+            let rate = self.rate as f64;
+            let now = Instant::now();
+            let elapsed = now.duration_since(state.updated_at).as_secs_f64();
+
+            state.tokens = (state.tokens + elapsed * rate).min(rate);
+            state.updated_at = now;
+            state.tokens -= amount as f64;
+
+            if state.tokens >= 0.0 {
+                return;
+            }
+
+            Duration::from_secs_f64(-state.tokens / rate)
+        };
+
+        tokio::time::sleep(wait).await;
+    }
+}
@@ -0,0 +1,92 @@
+use crate::common::minio::{test_minio_container, test_storage_factory};
+use docbox_storage::{BucketLifecyclePolicy, BucketPrefixExpiration, BucketTag, StorageLayerError};
+
+mod common;
+
+/// Tests that applying a lifecycle policy succeeds and can be re-applied
+#[tokio::test]
+async fn test_set_bucket_lifecycle_policy_minio() {
+    let container = test_minio_container().await;
+    let storage_factory = test_storage_factory(&container).await;
+    let storage = storage_factory.create_test_layer();
+
+    storage.create_bucket().await.unwrap();
+
+    let policy = BucketLifecyclePolicy {
+        abort_incomplete_multipart_upload_days: Some(3),
+        expire_prefixes: vec![BucketPrefixExpiration {
+            prefix: "tmp/".to_string(),
+            days: 1,
+        }],
+        bucket_tags: vec![BucketTag {
+            key: "docbox-tenant-env".to_string(),
+            value: "Development".to_string(),
+        }],
+    };
+
+    storage.set_bucket_lifecycle_policy(&policy).await.unwrap();
+    storage.set_bucket_lifecycle_policy(&policy).await.unwrap();
+}
+
+/// Tests that a policy without any rules removes the previous policy rules
+#[tokio::test]
+async fn test_set_empty_bucket_lifecycle_policy_minio() {
+    let container = test_minio_container().await;
+    let storage_factory = test_storage_factory(&container).await;
+    let storage = storage_factory.create_test_layer();
+
+    storage.create_bucket().await.unwrap();
+
+    storage
+        .set_bucket_lifecycle_policy(&BucketLifecyclePolicy::default())
+        .await
+        .unwrap();
+
+    storage
+        .set_bucket_lifecycle_policy(&BucketLifecyclePolicy {
+            abort_incomplete_multipart_upload_days: None,
+            expire_prefixes: Vec::new(),
+            bucket_tags: Vec::new(),
+        })
+        .await
+        .unwrap();
+}
+
+/// Tests that policies with invalid rules are rejected
+#[test]
+fn test_bucket_lifecycle_policy_invalid() {
+    BucketLifecyclePolicy::default().validate().unwrap();
+
+    let policy = BucketLifecyclePolicy {
+        abort_incomplete_multipart_upload_days: Some(0),
+        ..Default::default()
+    };
+    assert!(matches!(
+        policy.validate(),
+        Err(StorageLayerError::InvalidLifecyclePolicy(_))
+    ));
+
+    let policy = BucketLifecyclePolicy {
+        expire_prefixes: vec![BucketPrefixExpiration {
+            prefix: String::new(),
+            days: 1,
+        }],
+        ..Default::default()
+    };
+    assert!(matches!(
+        policy.validate(),
+        Err(StorageLayerError::InvalidLifecyclePolicy(_))
+    ));
+
+    let policy = BucketLifecyclePolicy {
+        expire_prefixes: vec![BucketPrefixExpiration {
+            prefix: "tmp/".to_string(),
+            days: 0,
+        }],
+        ..Default::default()
+    };
+    assert!(matches!(
+        policy.validate(),
+        Err(StorageLayerError::InvalidLifecyclePolicy(_))
+    ));
+}
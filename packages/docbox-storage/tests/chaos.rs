@@ -0,0 +1,72 @@
+#![cfg(all(feature = "chaos", feature = "memory"))]
+
+use docbox_storage::{
+    StorageLayerError, StorageLayerFactory, UploadFileOptions,
+    chaos::{ChaosStorageConfig, ChaosStorageError, ChaosStorageLayerFactory, StorageOperation},
+    memory::MemoryStorageLayerFactory,
+};
+use std::time::{Duration, Instant};
+
+fn test_upload_options() -> UploadFileOptions {
+    UploadFileOptions {
+        content_type: "text/plain".to_string(),
+        ..Default::default()
+    }
+}
+
+/// Tests that configured operations fail and recover once the config is cleared
+#[tokio::test]
+async fn test_chaos_storage_injected_failure() {
+    let chaos = ChaosStorageLayerFactory::new(
+        StorageLayerFactory::Memory(MemoryStorageLayerFactory::new()),
+        ChaosStorageConfig::fail([StorageOperation::UploadFile]),
+    );
+    let storage = StorageLayerFactory::Chaos(chaos.clone()).create_test_layer();
+
+    // Operations not included in the config are unaffected
+    storage.create_bucket().await.unwrap();
+
+    let error = storage
+        .upload_file("test.txt", "test".into(), test_upload_options())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        StorageLayerError::Chaos(ChaosStorageError::InjectedFault(
+            StorageOperation::UploadFile
+        ))
+    ));
+
+    chaos.set_config(ChaosStorageConfig::default());
+
+    storage
+        .upload_file("test.txt", "test".into(), test_upload_options())
+        .await
+        .unwrap();
+
+    let bytes = storage
+        .get_file("test.txt")
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert_eq!(bytes.as_ref(), b"test");
+}
+
+/// Tests that configured latency is applied to operations
+#[tokio::test]
+async fn test_chaos_storage_latency() {
+    let chaos = ChaosStorageLayerFactory::new(
+        StorageLayerFactory::Memory(MemoryStorageLayerFactory::new()),
+        ChaosStorageConfig {
+            latency: Some(Duration::from_millis(50)..=Duration::from_millis(60)),
+            ..Default::default()
+        },
+    );
+    let storage = StorageLayerFactory::Chaos(chaos).create_test_layer();
+
+    let start = Instant::now();
+    storage.create_bucket().await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(50));
+}
@@ -0,0 +1,169 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use docbox_storage::{
+    PresignedDownloadOptions, StorageLayerError, StorageLayerOptions, UploadFileOptions,
+    encryption::{StorageEncryptionKey, StorageEncryptionKeys, is_encrypted},
+};
+
+use crate::common::minio::{test_minio_container, test_storage_factory};
+
+mod common;
+
+fn test_encryption_keys() -> Arc<StorageEncryptionKeys> {
+    let keys = BTreeMap::from([(1, StorageEncryptionKey::generate())]);
+    Arc::new(StorageEncryptionKeys::new(1, keys).unwrap())
+}
+
+/// Tests that files uploaded to an encrypted storage layer are stored
+/// encrypted and are decrypted when loaded
+#[tokio::test]
+async fn test_encrypted_upload_get_file_minio() {
+    let container = test_minio_container().await;
+    let storage_factory = test_storage_factory(&container).await;
+    let raw_storage = storage_factory.create_test_layer();
+    let storage = storage_factory.create_layer(StorageLayerOptions {
+        bucket_name: "test".to_string(),
+        encryption: Some(test_encryption_keys()),
+        deduplicate: false,
+        tags: Vec::new(),
+    });
+
+    storage.create_bucket().await.unwrap();
+    storage
+        .upload_file(
+            "test.txt",
+            "test".into(),
+            UploadFileOptions {
+                content_type: "text/plain".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    // Stored object should be encrypted
+    let stored = raw_storage
+        .get_file("test.txt")
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert!(is_encrypted(&stored));
+
+    let metadata = storage.get_file_metadata("test.txt").await.unwrap();
+    assert_eq!(metadata.encryption_key_version, Some(1));
+
+    let contents = storage
+        .get_file("test.txt")
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert_eq!(contents.as_ref(), b"test");
+}
+
+/// Tests that existing unencrypted files can still be loaded from an
+/// encrypted storage layer
+#[tokio::test]
+async fn test_encrypted_get_plaintext_file_minio() {
+    let container = test_minio_container().await;
+    let storage_factory = test_storage_factory(&container).await;
+    let raw_storage = storage_factory.create_test_layer();
+    let storage = storage_factory.create_layer(StorageLayerOptions {
+        bucket_name: "test".to_string(),
+        encryption: Some(test_encryption_keys()),
+        deduplicate: false,
+        tags: Vec::new(),
+    });
+
+    raw_storage.create_bucket().await.unwrap();
+    raw_storage
+        .upload_file(
+            "test.txt",
+            "test".into(),
+            UploadFileOptions {
+                content_type: "text/plain".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let contents = storage
+        .get_file("test.txt")
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert_eq!(contents.as_ref(), b"test");
+}
+
+/// Tests that existing unencrypted files with contents that resemble an
+/// encrypted object are loaded as-is from an encrypted storage layer
+#[tokio::test]
+async fn test_encrypted_get_plaintext_magic_file_minio() {
+    let container = test_minio_container().await;
+    let storage_factory = test_storage_factory(&container).await;
+    let raw_storage = storage_factory.create_test_layer();
+    let storage = storage_factory.create_layer(StorageLayerOptions {
+        bucket_name: "test".to_string(),
+        encryption: Some(test_encryption_keys()),
+        deduplicate: false,
+        tags: Vec::new(),
+    });
+
+    raw_storage.create_bucket().await.unwrap();
+    raw_storage
+        .upload_file(
+            "test.txt",
+            "DBXENC plaintext".into(),
+            UploadFileOptions {
+                content_type: "text/plain".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let metadata = storage.get_file_metadata("test.txt").await.unwrap();
+    assert_eq!(metadata.encryption_key_version, None);
+
+    let contents = storage
+        .get_file("test.txt")
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert_eq!(contents.as_ref(), b"DBXENC plaintext");
+}
+
+/// Tests that presigned downloads are rejected for encrypted storage
+#[tokio::test]
+async fn test_encrypted_presigned_download_minio() {
+    let container = test_minio_container().await;
+    let storage_factory = test_storage_factory(&container).await;
+    let storage = storage_factory.create_layer(StorageLayerOptions {
+        bucket_name: "test".to_string(),
+        encryption: Some(test_encryption_keys()),
+        deduplicate: false,
+        tags: Vec::new(),
+    });
+
+    let error = storage
+        .create_presigned_download(
+            "test.txt",
+            std::time::Duration::from_secs(60),
+            PresignedDownloadOptions::default(),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        error,
+        StorageLayerError::PresignedDownloadEncrypted
+    ));
+}
@@ -45,3 +45,39 @@ async fn test_get_unknown_file_minio() {
     storage.create_bucket().await.unwrap();
     storage.get_file("test.txt").await.unwrap_err();
 }
+
+/// Tests getting a file's content using parallel ranged requests matches
+/// the uploaded content
+#[tokio::test]
+async fn test_get_file_parallel_minio() {
+    let container = test_minio_container().await;
+    let storage_factory = test_storage_factory(&container).await;
+    let storage = storage_factory.create_test_layer();
+
+    storage.create_bucket().await.unwrap();
+
+    let contents: Vec<u8> = (0..10_000u32).map(|value| (value % 251) as u8).collect();
+    storage
+        .upload_file(
+            "test.bin",
+            contents.clone().into(),
+            UploadFileOptions {
+                content_type: "application/octet-stream".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(storage.get_file_size("test.bin").await.unwrap(), 10_000);
+
+    let bytes = storage
+        .get_file_parallel("test.bin", 999, 4)
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+
+    assert_eq!(bytes.as_ref(), contents.as_slice());
+}
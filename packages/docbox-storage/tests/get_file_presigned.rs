@@ -1,6 +1,6 @@
 use crate::common::minio::{test_minio_container, test_storage_factory};
 use aws_sdk_s3::presigning::PresignedRequest;
-use docbox_storage::UploadFileOptions;
+use docbox_storage::{PresignedDownloadOptions, UploadFileOptions};
 use reqwest::{
     Response,
     header::{HeaderName, HeaderValue},
@@ -51,7 +51,11 @@ async fn test_get_file_presigned_minio() {
         .unwrap();
 
     let (request, _date) = storage
-        .create_presigned_download("test.txt", Duration::from_secs(60))
+        .create_presigned_download(
+            "test.txt",
+            Duration::from_secs(60),
+            PresignedDownloadOptions::default(),
+        )
         .await
         .unwrap();
 
@@ -77,7 +81,11 @@ async fn test_get_unknown_file_presigned_minio() {
 
     storage.create_bucket().await.unwrap();
     let (request, _date) = storage
-        .create_presigned_download("test.txt", Duration::from_secs(60))
+        .create_presigned_download(
+            "test.txt",
+            Duration::from_secs(60),
+            PresignedDownloadOptions::default(),
+        )
         .await
         .unwrap();
 
@@ -0,0 +1,270 @@
+#![cfg(feature = "memory")]
+
+use docbox_storage::{
+    CreateBucketOutcome, ObjectTag, StorageLayerError, StorageLayerFactory, StorageLayerOptions,
+    UploadFileOptions, UploadFileTag,
+    encryption::{StorageEncryptionKey, StorageEncryptionKeys},
+    memory::{MemoryStorageError, MemoryStorageLayerFactory},
+};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Tests the memory storage layer bucket lifecycle
+#[tokio::test]
+async fn test_bucket_lifecycle_memory() {
+    let storage_factory = StorageLayerFactory::Memory(MemoryStorageLayerFactory::new());
+    let storage = storage_factory.create_test_layer();
+
+    assert!(!storage.bucket_exists().await.unwrap());
+
+    let outcome = storage.create_bucket().await.unwrap();
+    assert_eq!(outcome, CreateBucketOutcome::New);
+    assert!(storage.bucket_exists().await.unwrap());
+
+    let outcome = storage.create_bucket().await.unwrap();
+    assert_eq!(outcome, CreateBucketOutcome::Existing);
+
+    storage.delete_bucket().await.unwrap();
+    assert!(!storage.bucket_exists().await.unwrap());
+}
+
+/// Tests uploading, getting and deleting a file from memory storage
+#[tokio::test]
+async fn test_file_lifecycle_memory() {
+    let storage_factory = StorageLayerFactory::Memory(MemoryStorageLayerFactory::new());
+    let storage = storage_factory.create_test_layer();
+
+    storage.create_bucket().await.unwrap();
+    storage
+        .upload_file(
+            "test.txt",
+            "test".into(),
+            UploadFileOptions {
+                content_type: "text/plain".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    // Layers from the same factory share the same buckets
+    let contents = storage_factory
+        .create_test_layer()
+        .get_file("test.txt")
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert_eq!(contents.as_ref(), b"test");
+
+    storage.delete_file("test.txt").await.unwrap();
+
+    let error = storage.get_file("test.txt").await.unwrap_err();
+    assert!(matches!(
+        error,
+        StorageLayerError::Memory(MemoryStorageError::FileNotFound)
+    ));
+}
+
+/// Tests uploading to a missing bucket fails
+#[tokio::test]
+async fn test_upload_file_missing_bucket_memory() {
+    let storage_factory = StorageLayerFactory::Memory(MemoryStorageLayerFactory::new());
+    let storage = storage_factory.create_test_layer();
+
+    let error = storage
+        .upload_file("test.txt", "test".into(), Default::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        StorageLayerError::Memory(MemoryStorageError::BucketNotFound)
+    ));
+}
+
+/// Tests that presigned requests are rejected by memory storage
+#[tokio::test]
+async fn test_presigned_unsupported_memory() {
+    let storage_factory = StorageLayerFactory::Memory(MemoryStorageLayerFactory::new());
+    let storage = storage_factory.create_test_layer();
+
+    storage.create_bucket().await.unwrap();
+
+    let error = storage.create_presigned("test.txt", 4).await.unwrap_err();
+    assert!(matches!(
+        error,
+        StorageLayerError::Memory(MemoryStorageError::PresignedUnsupported)
+    ));
+}
+
+/// Tests that uploaded files are tagged with the upload and layer tags
+#[tokio::test]
+async fn test_object_tags_memory() {
+    let storage_factory = StorageLayerFactory::Memory(MemoryStorageLayerFactory::new());
+    let storage = storage_factory.create_layer(StorageLayerOptions {
+        bucket_name: "test".to_string(),
+        encryption: None,
+        deduplicate: false,
+        tags: vec![ObjectTag::new(ObjectTag::TENANT_ENV, "Development")],
+    });
+
+    storage.create_bucket().await.unwrap();
+    storage
+        .upload_file(
+            "test.txt",
+            "test".into(),
+            UploadFileOptions {
+                content_type: "text/plain".to_string(),
+                tags: Some(vec![UploadFileTag::ExpireDays1]),
+                object_tags: vec![ObjectTag::new(ObjectTag::ITEM_TYPE, "file")],
+            },
+        )
+        .await
+        .unwrap();
+
+    let tags = storage.get_object_tags("test.txt").await.unwrap();
+    assert_eq!(
+        tags,
+        vec![
+            ObjectTag::new("expire", "1d"),
+            ObjectTag::new(ObjectTag::ITEM_TYPE, "file"),
+            ObjectTag::new(ObjectTag::TENANT_ENV, "Development"),
+        ]
+    );
+
+    storage
+        .put_object_tags("test.txt", vec![ObjectTag::new("expire", "30d")])
+        .await
+        .unwrap();
+
+    let tags = storage.get_object_tags("test.txt").await.unwrap();
+    assert_eq!(tags, vec![ObjectTag::new("expire", "30d")]);
+}
+
+/// Tests that parallel chunked downloads reassemble the file in order
+#[tokio::test]
+async fn test_get_file_parallel_memory() {
+    let storage_factory = StorageLayerFactory::Memory(MemoryStorageLayerFactory::new());
+    let storage = storage_factory.create_test_layer();
+
+    storage.create_bucket().await.unwrap();
+
+    let contents: Vec<u8> = (0..10_000u32).map(|value| (value % 251) as u8).collect();
+    storage
+        .upload_file(
+            "test.bin",
+            contents.clone().into(),
+            UploadFileOptions {
+                content_type: "application/octet-stream".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(storage.get_file_size("test.bin").await.unwrap(), 10_000);
+
+    let range = storage
+        .get_file_range("test.bin", 100..200)
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert_eq!(range.as_ref(), &contents[100..200]);
+
+    // Chunk size that does not evenly divide the file size
+    let bytes = storage
+        .get_file_parallel("test.bin", 999, 4)
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert_eq!(bytes.as_ref(), contents.as_slice());
+
+    // File within a single chunk
+    let bytes = storage
+        .get_file_parallel("test.bin", 20_000, 4)
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert_eq!(bytes.as_ref(), contents.as_slice());
+
+    let error = storage
+        .get_file_parallel("missing.bin", 999, 4)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        StorageLayerError::Memory(MemoryStorageError::FileNotFound)
+    ));
+}
+
+/// Tests that encrypted files record the key version in their metadata and that
+/// plaintext files are loaded as-is even when they resemble encrypted contents
+#[tokio::test]
+async fn test_encrypted_metadata_memory() {
+    let storage_factory = StorageLayerFactory::Memory(MemoryStorageLayerFactory::new());
+    let raw_storage = storage_factory.create_test_layer();
+    let keys = BTreeMap::from([(3, StorageEncryptionKey::generate())]);
+    let storage = storage_factory.create_layer(StorageLayerOptions {
+        bucket_name: raw_storage.bucket_name(),
+        encryption: Some(Arc::new(StorageEncryptionKeys::new(3, keys).unwrap())),
+        deduplicate: false,
+        tags: Vec::new(),
+    });
+
+    raw_storage.create_bucket().await.unwrap();
+
+    // Plaintext file stored before encryption was enabled
+    raw_storage
+        .upload_file(
+            "plain.txt",
+            "DBXENC plaintext".into(),
+            UploadFileOptions {
+                content_type: "text/plain".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    storage
+        .upload_file(
+            "encrypted.txt",
+            "test".into(),
+            UploadFileOptions {
+                content_type: "text/plain".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let metadata = storage.get_file_metadata("plain.txt").await.unwrap();
+    assert_eq!(metadata.encryption_key_version, None);
+
+    let metadata = storage.get_file_metadata("encrypted.txt").await.unwrap();
+    assert_eq!(metadata.encryption_key_version, Some(3));
+
+    let contents = storage
+        .get_file("plain.txt")
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert_eq!(contents.as_ref(), b"DBXENC plaintext");
+
+    let contents = storage
+        .get_file("encrypted.txt")
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+    assert_eq!(contents.as_ref(), b"test");
+}
@@ -0,0 +1,55 @@
+use docbox_storage::{ObjectTag, UploadFileOptions, UploadFileTag};
+
+use crate::common::minio::{test_minio_container, test_storage_factory};
+
+mod common;
+
+/// Tests that tags provided when uploading a file are stored and can be replaced
+#[tokio::test]
+async fn test_object_tags_minio() {
+    let container = test_minio_container().await;
+    let storage_factory = test_storage_factory(&container).await;
+    let storage = storage_factory.create_test_layer();
+
+    storage.create_bucket().await.unwrap();
+    storage
+        .upload_file(
+            "test.txt",
+            "test".into(),
+            UploadFileOptions {
+                content_type: "text/plain".to_string(),
+                tags: Some(vec![UploadFileTag::ExpireDays1]),
+                object_tags: vec![ObjectTag::new(ObjectTag::DOCUMENT_BOX, "user:1 files")],
+            },
+        )
+        .await
+        .unwrap();
+
+    let mut tags = storage.get_object_tags("test.txt").await.unwrap();
+    tags.sort_by(|a, b| a.key.cmp(&b.key));
+    assert_eq!(
+        tags,
+        vec![
+            ObjectTag::new(ObjectTag::DOCUMENT_BOX, "user:1 files"),
+            ObjectTag::new("expire", "1d"),
+        ]
+    );
+
+    storage
+        .put_object_tags("test.txt", vec![ObjectTag::new("expire", "30d")])
+        .await
+        .unwrap();
+
+    let tags = storage.get_object_tags("test.txt").await.unwrap();
+    assert_eq!(tags, vec![ObjectTag::new("expire", "30d")]);
+}
+
+/// Tests that disallowed characters are replaced in tag values
+#[test]
+fn test_object_tag_value_sanitized() {
+    let tag = ObjectTag::new(ObjectTag::DOCUMENT_BOX, "user#1?files&more");
+    assert_eq!(tag.value, "user_1_files_more");
+
+    let tag = ObjectTag::new(ObjectTag::DOCUMENT_BOX, &"a".repeat(300));
+    assert_eq!(tag.value.len(), 256);
+}
@@ -0,0 +1,108 @@
+use docbox_storage::s3::{S3Endpoint, S3StorageLayerFactoryConfig, S3StorageLayerFactoryError};
+
+fn custom_endpoint() -> S3Endpoint {
+    S3Endpoint::Custom {
+        endpoint: "http://localhost:9000".to_string(),
+        external_endpoint: None,
+        access_key_id: "minioadmin".to_string(),
+        access_key_secret: "minioadmin".to_string(),
+    }
+}
+
+/// Tests that path style access defaults to enabled only for custom endpoints
+#[test]
+fn test_s3_config_force_path_style_default() {
+    let aws = S3StorageLayerFactoryConfig::default();
+    assert!(!aws.force_path_style());
+
+    let custom = S3StorageLayerFactoryConfig {
+        endpoint: custom_endpoint(),
+        ..Default::default()
+    };
+    assert!(custom.force_path_style());
+
+    let custom = S3StorageLayerFactoryConfig {
+        endpoint: custom_endpoint(),
+        force_path_style: Some(false),
+        ..Default::default()
+    };
+    assert!(!custom.force_path_style());
+}
+
+/// Tests that the default configuration is valid
+#[test]
+fn test_s3_config_default_valid() {
+    S3StorageLayerFactoryConfig::default().validate().unwrap();
+}
+
+/// Tests that accelerate is rejected for custom endpoints and path style access
+#[test]
+fn test_s3_config_accelerate_invalid() {
+    let config = S3StorageLayerFactoryConfig {
+        endpoint: custom_endpoint(),
+        force_path_style: Some(false),
+        accelerate: true,
+        ..Default::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(S3StorageLayerFactoryError::AccelerateCustomEndpoint)
+    ));
+
+    let config = S3StorageLayerFactoryConfig {
+        force_path_style: Some(true),
+        accelerate: true,
+        ..Default::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(S3StorageLayerFactoryError::AcceleratePathStyle)
+    ));
+
+    let config = S3StorageLayerFactoryConfig {
+        accelerate: true,
+        ..Default::default()
+    };
+    config.validate().unwrap();
+}
+
+/// Tests that invalid retry and timeout options are rejected
+#[test]
+fn test_s3_config_retry_invalid() {
+    let config = S3StorageLayerFactoryConfig {
+        max_attempts: Some(0),
+        ..Default::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(S3StorageLayerFactoryError::InvalidMaxAttempts)
+    ));
+
+    let config = S3StorageLayerFactoryConfig {
+        request_timeout: Some(0),
+        ..Default::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(S3StorageLayerFactoryError::InvalidRequestTimeout)
+    ));
+
+    let config = S3StorageLayerFactoryConfig {
+        initial_backoff: Some(5000),
+        max_backoff: Some(1000),
+        ..Default::default()
+    };
+    assert!(matches!(
+        config.validate(),
+        Err(S3StorageLayerFactoryError::InvalidBackoff)
+    ));
+
+    let config = S3StorageLayerFactoryConfig {
+        max_attempts: Some(5),
+        initial_backoff: Some(100),
+        max_backoff: Some(1000),
+        request_timeout: Some(30000),
+        ..Default::default()
+    };
+    config.validate().unwrap();
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "memory")]
+
+use docbox_storage::{
+    StorageLayerFactory, UploadFileOptions,
+    memory::MemoryStorageLayerFactory,
+    throttle::{StorageThrottle, StorageThrottleConfig},
+};
+use std::time::{Duration, Instant};
+
+/// Tests that a config without any limits does not create a throttle
+#[test]
+fn test_throttle_unlimited() {
+    assert!(StorageThrottle::from_config(&StorageThrottleConfig::default()).is_none());
+
+    let config = StorageThrottleConfig {
+        bytes_per_second: Some(0),
+        requests_per_second: Some(0),
+    };
+    assert!(StorageThrottle::from_config(&config).is_none());
+}
+
+/// Tests that requests beyond the allowed rate are delayed
+#[tokio::test]
+async fn test_throttle_requests_memory() {
+    let throttle = StorageThrottle::from_config(&StorageThrottleConfig {
+        bytes_per_second: None,
+        requests_per_second: Some(10),
+    });
+
+    let storage_factory = StorageLayerFactory::Memory(MemoryStorageLayerFactory::new());
+    let storage = storage_factory.create_test_layer().with_throttle(throttle);
+
+    storage.create_bucket().await.unwrap();
+
+    // First 10 requests use the initial burst, the next 5 must wait
+    let start = Instant::now();
+    for _ in 0..15 {
+        storage.delete_file("test.txt").await.unwrap();
+    }
+
+    assert!(start.elapsed() >= Duration::from_millis(400));
+}
+
+/// Tests that transferred bytes beyond the allowed rate are delayed
+#[tokio::test]
+async fn test_throttle_bytes_memory() {
+    let throttle = StorageThrottle::from_config(&StorageThrottleConfig {
+        bytes_per_second: Some(1000),
+        requests_per_second: None,
+    });
+
+    let storage_factory = StorageLayerFactory::Memory(MemoryStorageLayerFactory::new());
+    let storage = storage_factory.create_test_layer().with_throttle(throttle);
+
+    storage.create_bucket().await.unwrap();
+
+    // Upload uses the initial burst, the download must wait for the bytes
+    let start = Instant::now();
+    storage
+        .upload_file(
+            "test.txt",
+            vec![0u8; 1000].into(),
+            UploadFileOptions {
+                content_type: "text/plain".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let bytes = storage
+        .get_file("test.txt")
+        .await
+        .unwrap()
+        .collect_bytes()
+        .await
+        .unwrap();
+
+    assert_eq!(bytes.len(), 1000);
+    assert!(start.elapsed() >= Duration::from_millis(900));
+}
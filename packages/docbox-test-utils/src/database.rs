@@ -0,0 +1,63 @@
+//! Postgres container and database pool cache for tests
+
+use docbox_http::core::{
+    database::{DatabasePoolCache, DatabasePoolCacheConfig, ROOT_DATABASE_NAME},
+    secrets::{Secret, SecretManager, memory::MemorySecretManager},
+};
+use std::{collections::HashMap, sync::Arc};
+use testcontainers::{ContainerAsync, ImageExt, runners::AsyncRunner};
+use testcontainers_modules::postgres::Postgres;
+
+pub const TEST_DB_USER: &str = "docbox";
+pub const TEST_DB_PASSWORD: &str = "docbox";
+
+/// Secret name used for the root database credentials
+pub const TEST_ROOT_SECRET_NAME: &str = "root";
+
+/// Create a new [Postgres](https://www.postgresql.org/) container for testing, the
+/// container is created with the root docbox database
+pub async fn test_database_container() -> ContainerAsync<Postgres> {
+    Postgres::default()
+        .with_db_name(ROOT_DATABASE_NAME)
+        .with_user(TEST_DB_USER)
+        .with_password(TEST_DB_PASSWORD)
+        .with_tag("18.1-alpine")
+        .start()
+        .await
+        .unwrap()
+}
+
+/// Create an in-memory secret manager where every secret resolves to the
+/// credentials for the test database user
+pub fn test_secrets() -> SecretManager {
+    let credentials = serde_json::json!({
+        "username": TEST_DB_USER,
+        "password": TEST_DB_PASSWORD,
+    });
+
+    SecretManager::Memory(MemorySecretManager::new(
+        HashMap::new(),
+        Some(Secret::String(credentials.to_string())),
+    ))
+}
+
+/// Create a database pool cache connecting to the provided postgres container
+pub async fn test_db_cache(
+    container: &ContainerAsync<Postgres>,
+    aws_config: &aws_config::SdkConfig,
+    secrets: SecretManager,
+) -> Arc<DatabasePoolCache> {
+    let host = container.get_host().await.unwrap();
+    let port = container.get_host_port_ipv4(5432).await.unwrap();
+
+    Arc::new(DatabasePoolCache::from_config(
+        aws_config.clone(),
+        DatabasePoolCacheConfig {
+            host: host.to_string(),
+            port,
+            root_secret_name: Some(TEST_ROOT_SECRET_NAME.to_string()),
+            ..Default::default()
+        },
+        secrets,
+    ))
+}
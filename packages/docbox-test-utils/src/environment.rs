@@ -0,0 +1,220 @@
+//! Test environment wiring together all the docbox dependencies
+
+use crate::{
+    database::{test_database_container, test_db_cache, test_secrets},
+    minio::{test_minio_container, test_sdk_config, test_storage_factory},
+    processing::{
+        test_office_convert_server_container, test_processing_layer,
+        test_unavailable_processing_layer,
+    },
+    server::TestServer,
+    tenant::provision_test_tenant,
+    typesense::{test_search_factory, test_typesense_container},
+};
+use aws_config::SdkConfig;
+use axum::{Extension, Router};
+use docbox_http::{
+    core::{
+        aws::SqsClient,
+        database::{DatabasePoolCache, models::tenant::Tenant},
+        events::{
+            EventPublisherFactory, sqs::SqsEventPublisherFactory,
+            webhook::WebhookEventPublisherFactory,
+        },
+        files::access_stats::FileAccessRecorder,
+        links::resolve_website::{ResolveWebsiteConfig, ResolveWebsiteService},
+        processing::{ProcessingLayer, ProcessingLayerConfig},
+        search::{DatabaseSearchIndexFactory, SearchIndexFactory},
+        secrets::SecretManager,
+        shutdown::ShutdownCoordinator,
+        storage::StorageLayerFactory,
+        tenant::{tenant_cache::TenantCache, tenant_storage_key::TenantStorageKeyCache},
+        web_scraper::{WebsiteMetaService, WebsiteMetaServiceConfig},
+    },
+    extensions::{
+        bulk_storage_throttle::BulkStorageThrottle, max_file_size::MaxFileSizeBytes,
+        search_auto_heal::SearchAutoHeal, server_version::ServerVersion,
+    },
+    routes::router,
+};
+use std::sync::Arc;
+use testcontainers::{ContainerAsync, GenericImage};
+use testcontainers_modules::{minio::MinIO, postgres::Postgres};
+use tokio::net::TcpListener;
+
+/// Version reported by the test server
+pub const TEST_SERVER_VERSION: &str = "test";
+
+/// Max file size allowed by the test server (100MB)
+pub const TEST_MAX_FILE_SIZE: i32 = 100 * 1000 * 1024;
+
+/// Search backend to use for the test environment
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TestSearchBackend {
+    /// Typesense search running in a container
+    #[default]
+    Typesense,
+    /// Search using the postgres database directly
+    Database,
+}
+
+/// Configuration for which dependencies the test environment starts
+#[derive(Debug)]
+pub struct TestEnvironmentConfig {
+    /// Search backend to use
+    pub search: TestSearchBackend,
+    /// Whether to start the office converter, when disabled any office
+    /// conversion will fail
+    pub office_converter: bool,
+    /// Configuration for the processing layer
+    pub processing: ProcessingLayerConfig,
+}
+
+impl Default for TestEnvironmentConfig {
+    fn default() -> Self {
+        Self {
+            search: TestSearchBackend::default(),
+            office_converter: true,
+            processing: ProcessingLayerConfig::default(),
+        }
+    }
+}
+
+/// Containers kept alive for the lifetime of the environment
+struct TestContainers {
+    _database: ContainerAsync<Postgres>,
+    _storage: ContainerAsync<MinIO>,
+    _search: Option<ContainerAsync<GenericImage>>,
+    _office_converter: Option<ContainerAsync<GenericImage>>,
+}
+
+/// Running set of docbox dependencies along with a provisioned tenant
+pub struct TestEnvironment {
+    pub aws_config: SdkConfig,
+    pub secrets: SecretManager,
+    pub db_cache: Arc<DatabasePoolCache>,
+    pub search: SearchIndexFactory,
+    pub storage: StorageLayerFactory,
+    pub processing: ProcessingLayer,
+    pub shutdown: ShutdownCoordinator,
+    /// Tenant provisioned for the tests
+    pub tenant: Tenant,
+    _containers: TestContainers,
+}
+
+impl TestEnvironment {
+    /// Start a test environment with all dependencies
+    pub async fn start() -> TestEnvironment {
+        Self::start_with(TestEnvironmentConfig::default()).await
+    }
+
+    /// Start a test environment using the provided `config`
+    pub async fn start_with(config: TestEnvironmentConfig) -> TestEnvironment {
+        // Boxed to keep the size of the callers future small, the startup
+        // future is deeply nested
+        Box::pin(Self::start_inner(config)).await
+    }
+
+    async fn start_inner(config: TestEnvironmentConfig) -> TestEnvironment {
+        let aws_config = test_sdk_config();
+        let secrets = test_secrets();
+
+        let (db_container, storage_container, search_container, office_converter_container) = tokio::join!(
+            test_database_container(),
+            test_minio_container(),
+            async {
+                match config.search {
+                    TestSearchBackend::Typesense => Some(test_typesense_container().await),
+                    TestSearchBackend::Database => None,
+                }
+            },
+            async {
+                match config.office_converter {
+                    true => Some(test_office_convert_server_container().await),
+                    false => None,
+                }
+            }
+        );
+
+        let db_cache = test_db_cache(&db_container, &aws_config, secrets.clone()).await;
+
+        let search = match search_container.as_ref() {
+            Some(container) => test_search_factory(container, secrets.clone()).await,
+            None => SearchIndexFactory::Database(
+                DatabaseSearchIndexFactory::from_config(db_cache.clone(), Default::default())
+                    .unwrap(),
+            ),
+        };
+
+        let storage = test_storage_factory(&storage_container, &aws_config).await;
+
+        let processing = match office_converter_container.as_ref() {
+            Some(container) => test_processing_layer(container, config.processing).await,
+            None => test_unavailable_processing_layer(config.processing),
+        };
+
+        let tenant = provision_test_tenant(&db_cache, &search, &storage).await;
+
+        TestEnvironment {
+            aws_config,
+            secrets,
+            db_cache,
+            search,
+            storage,
+            processing,
+            shutdown: ShutdownCoordinator::new(),
+            tenant,
+            _containers: TestContainers {
+                _database: db_container,
+                _storage: storage_container,
+                _search: search_container,
+                _office_converter: office_converter_container,
+            },
+        }
+    }
+
+    /// Create the docbox HTTP router with all the extensions required
+    /// by the routes
+    pub fn router(&self) -> Router {
+        let event_publisher_factory = EventPublisherFactory::new(
+            SqsEventPublisherFactory::new(SqsClient::new(&self.aws_config), self.shutdown.clone())
+                .with_claim_check(self.db_cache.clone()),
+        )
+        .with_webhooks(
+            // Test webhook receivers are served on local addresses
+            WebhookEventPublisherFactory::new(self.db_cache.clone(), self.shutdown.clone())
+                .with_allow_private_urls(true),
+        );
+        let file_access_recorder = FileAccessRecorder::new(self.db_cache.clone(), &self.shutdown);
+        let website_meta_service = Arc::new(ResolveWebsiteService::from_client_with_config(
+            WebsiteMetaService::from_config(WebsiteMetaServiceConfig::default()).unwrap(),
+            ResolveWebsiteConfig::default(),
+        ));
+
+        router::<true, true, true, true>()
+            .layer(Extension(self.search.clone()))
+            .layer(Extension(self.storage.clone()))
+            .layer(Extension(self.db_cache.clone()))
+            .layer(Extension(website_meta_service))
+            .layer(Extension(event_publisher_factory))
+            .layer(Extension(self.processing.clone()))
+            .layer(Extension(Arc::new(TenantCache::new())))
+            .layer(Extension(TenantStorageKeyCache::new(self.secrets.clone())))
+            .layer(Extension(file_access_recorder))
+            .layer(Extension(ServerVersion(TEST_SERVER_VERSION)))
+            .layer(Extension(MaxFileSizeBytes::new(TEST_MAX_FILE_SIZE)))
+            .layer(Extension(BulkStorageThrottle::default()))
+            .layer(Extension(SearchAutoHeal::default()))
+    }
+
+    /// Serve the docbox HTTP router on a random local port
+    pub async fn serve(self) -> TestServer {
+        let app = self.router();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        TestServer::new(address, self)
+    }
+}
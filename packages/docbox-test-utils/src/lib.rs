@@ -0,0 +1,31 @@
+#![forbid(unsafe_code)]
+#![recursion_limit = "256"]
+//! # Docbox Test Utils
+//!
+//! Test harness for running end-to-end tests against docbox using real
+//! dependencies. Postgres, MinIO, Typesense and the office converter are
+//! started using [testcontainers] and a tenant is provisioned against them.
+//!
+//! Requires that the test runner have docker available.
+//!
+//! ```ignore
+//! let server = TestEnvironment::start().await.serve().await;
+//!
+//! let response = server
+//!     .post("/box")
+//!     .json(&serde_json::json!({ "scope": "test" }))
+//!     .send()
+//!     .await
+//!     .unwrap();
+//! ```
+
+pub mod database;
+pub mod environment;
+pub mod minio;
+pub mod processing;
+pub mod server;
+pub mod tenant;
+pub mod typesense;
+
+pub use environment::{TestEnvironment, TestEnvironmentConfig, TestSearchBackend};
+pub use server::TestServer;
@@ -0,0 +1,56 @@
+//! Office converter container and processing layer for tests
+
+use docbox_http::core::processing::{
+    ProcessingLayer, ProcessingLayerConfig,
+    office::{OfficeConverter, OfficeProcessingLayer, convert_server::OfficeConverterServer},
+};
+use testcontainers::{
+    ContainerAsync, GenericImage,
+    core::{IntoContainerPort, WaitFor, wait::HttpWaitStrategy},
+    runners::AsyncRunner,
+};
+
+/// Create a test container for the office convert server
+pub async fn test_office_convert_server_container() -> ContainerAsync<GenericImage> {
+    GenericImage::new("jacobtread/office-convert-server", "0.2.2")
+        .with_exposed_port(3000.tcp())
+        .with_wait_for(WaitFor::seconds(5))
+        .with_wait_for(WaitFor::http(
+            HttpWaitStrategy::new("/status").with_expected_status_code(200u16),
+        ))
+        .start()
+        .await
+        .unwrap()
+}
+
+/// Create a processing layer from the provided office convert server container
+pub async fn test_processing_layer(
+    container: &ContainerAsync<GenericImage>,
+    config: ProcessingLayerConfig,
+) -> ProcessingLayer {
+    let host = container.get_host().await.unwrap();
+    let host_port = container.get_host_port_ipv4(3000).await.unwrap();
+    let client_url = format!("http://{host}:{host_port}");
+
+    processing_layer_from_address(&client_url, config)
+}
+
+/// Create a processing layer with a converter that cannot be reached, for
+/// tests that do not perform office conversion
+pub fn test_unavailable_processing_layer(config: ProcessingLayerConfig) -> ProcessingLayer {
+    processing_layer_from_address("http://127.0.0.1:1", config)
+}
+
+fn processing_layer_from_address(address: &str, config: ProcessingLayerConfig) -> ProcessingLayer {
+    let converter_server = OfficeConverterServer::from_addresses([address], false).unwrap();
+    let converter = OfficeConverter::ConverterServer(converter_server);
+
+    ProcessingLayer {
+        office: OfficeProcessingLayer {
+            converter,
+            circuit_breaker: Default::default(),
+        },
+        config,
+        summary: None,
+    }
+}
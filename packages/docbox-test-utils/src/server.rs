@@ -0,0 +1,64 @@
+//! Running docbox HTTP server for end-to-end tests
+
+use crate::environment::TestEnvironment;
+use docbox_http::middleware::tenant::{TENANT_ENV_HEADER, TENANT_ID_HEADER};
+use reqwest::{Method, RequestBuilder};
+use std::net::SocketAddr;
+
+/// Docbox HTTP server running against a [TestEnvironment]
+pub struct TestServer {
+    /// Address the server is listening on
+    pub address: SocketAddr,
+    /// Environment the server is running against
+    pub environment: TestEnvironment,
+    http: reqwest::Client,
+}
+
+impl TestServer {
+    pub(crate) fn new(address: SocketAddr, environment: TestEnvironment) -> Self {
+        Self {
+            address,
+            environment,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Base URL for the server
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Full URL for the provided `path` on the server
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url())
+    }
+
+    /// Create a request to `path` with the test tenant headers
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let tenant = &self.environment.tenant;
+        self.http
+            .request(method, self.url(path))
+            .header(TENANT_ID_HEADER, tenant.id.to_string())
+            .header(TENANT_ENV_HEADER, &tenant.env)
+    }
+
+    /// Create a GET request to `path` with the test tenant headers
+    pub fn get(&self, path: &str) -> RequestBuilder {
+        self.request(Method::GET, path)
+    }
+
+    /// Create a POST request to `path` with the test tenant headers
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        self.request(Method::POST, path)
+    }
+
+    /// Create a PUT request to `path` with the test tenant headers
+    pub fn put(&self, path: &str) -> RequestBuilder {
+        self.request(Method::PUT, path)
+    }
+
+    /// Create a DELETE request to `path` with the test tenant headers
+    pub fn delete(&self, path: &str) -> RequestBuilder {
+        self.request(Method::DELETE, path)
+    }
+}
@@ -0,0 +1,79 @@
+//! Tenant provisioning for tests
+
+use docbox_http::core::{
+    database::{
+        DatabasePoolCache, ROOT_DATABASE_NAME,
+        migrations::{apply_root_migrations, apply_tenant_migrations, initialize_root_migrations},
+        models::tenant::{CreateTenant, Tenant},
+    },
+    search::SearchIndexFactory,
+    storage::StorageLayerFactory,
+    tenant::tenant_options_ext::TenantOptionsExt,
+};
+use uuid::Uuid;
+
+/// Environment name used for the test tenant
+pub const TEST_TENANT_ENV: &str = "Development";
+
+/// Provision a tenant for testing, applies the root migrations then creates
+/// the tenant along with its database tables, search index and storage bucket
+///
+/// The tenant shares the root database to avoid creating an additional database
+pub async fn provision_test_tenant(
+    db_cache: &DatabasePoolCache,
+    search: &SearchIndexFactory,
+    storage: &StorageLayerFactory,
+) -> Tenant {
+    let root_db = db_cache.get_root_pool().await.unwrap();
+    initialize_root_migrations(&root_db).await.unwrap();
+    {
+        let mut root_t = root_db.begin().await.unwrap();
+        apply_root_migrations(&mut root_t, None).await.unwrap();
+        root_t.commit().await.unwrap();
+    }
+
+    let tenant = Tenant::create(
+        &root_db,
+        CreateTenant {
+            id: Uuid::new_v4(),
+            name: "test".to_string(),
+            db_name: ROOT_DATABASE_NAME.to_string(),
+            db_iam_user_name: None,
+            db_secret_name: Some("test".to_string()),
+            s3_name: "test".to_string(),
+            os_index_name: "test".to_string(),
+            event_queue_url: None,
+            env: TEST_TENANT_ENV.to_string(),
+            data_region: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let search = search.create_search_index(&tenant);
+    search.create_index().await.unwrap();
+
+    // Apply the tenant and search migrations
+    {
+        let tenant_db = db_cache.get_tenant_pool(&tenant).await.unwrap();
+        let mut root_t = root_db.begin().await.unwrap();
+        let mut tenant_t = tenant_db.begin().await.unwrap();
+        apply_tenant_migrations(&mut root_t, &mut tenant_t, &tenant, None)
+            .await
+            .unwrap();
+        search
+            .apply_migrations(&tenant, &mut root_t, &mut tenant_t, None)
+            .await
+            .unwrap();
+        tenant_t.commit().await.unwrap();
+        root_t.commit().await.unwrap();
+    }
+
+    let storage = storage.create_layer(tenant.storage_layer_options());
+    storage.create_bucket().await.unwrap();
+    for migration in storage.get_pending_migrations(vec![]).await.unwrap() {
+        storage.apply_migration(&migration).await.unwrap();
+    }
+
+    tenant
+}
@@ -32,6 +32,7 @@ mod url_validation;
 
 pub use document::Favicon;
 pub use reqwest::Url;
+pub use url_validation::{PublicDomainResolver, is_allowed_public_url};
 
 use crate::{document::is_allowed_robots_txt, download_image::ImageStream};
 
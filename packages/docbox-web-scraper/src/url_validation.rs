@@ -2,7 +2,7 @@
 //!
 //! Validation for allowed URLs to enforce security requirements
 
-use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
 use url::{Host, Url};
 
@@ -32,6 +32,35 @@ impl reqwest::dns::Resolve for TokioDomainResolver {
     }
 }
 
+/// Domain resolver for HTTP clients that only provides globally reachable
+/// addresses. Clients connect to the addresses that were checked, so a domain
+/// that resolves to a different address after [is_allowed_public_url] was
+/// checked (DNS rebinding) cannot be used to reach internal addresses
+pub struct PublicDomainResolver;
+
+impl reqwest::dns::Resolve for PublicDomainResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host_with_port = format!("{}:0", name.as_str());
+
+        Box::pin(async move {
+            let resolved: Vec<SocketAddr> = tokio::net::lookup_host(host_with_port)
+                .await?
+                .filter(|addr| is_ip_global(addr.ip()))
+                .collect();
+
+            if resolved.is_empty() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    "domain does not resolve to a publicly reachable address",
+                )
+                .into());
+            }
+
+            Ok(Box::new(resolved.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
 impl DomainResolver for TokioDomainResolver {
     async fn resolve_domain(
         host: &str,
@@ -97,29 +126,31 @@ pub async fn is_allowed_url<D: DomainResolver>(url: &Url) -> bool {
     let mut any_valid = false;
 
     for addr in host_addresses {
-        let ip = addr.ip();
-
-        match ip {
-            std::net::IpAddr::V4(addr) => {
-                if !is_ipv4_global(addr) {
-                    return false;
-                }
-
-                any_valid = true;
-            }
-            std::net::IpAddr::V6(addr) => {
-                if !is_ipv6_global(addr) {
-                    return false;
-                }
-
-                any_valid = true;
-            }
+        if !is_ip_global(addr.ip()) {
+            return false;
         }
+
+        any_valid = true;
     }
 
     any_valid
 }
 
+/// Validates that the provided `url` is allowed to be requested by the
+/// server, resolving the domain using the system resolver. See [is_allowed_url]
+/// for the requirements
+pub async fn is_allowed_public_url(url: &Url) -> bool {
+    is_allowed_url::<TokioDomainResolver>(url).await
+}
+
+/// Check if the provided address is globally reachable
+fn is_ip_global(addr: IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(addr) => is_ipv4_global(addr),
+        IpAddr::V6(addr) => is_ipv6_global(addr),
+    }
+}
+
 /// Sourced from the unstable rust standard library [`Ipv4Addr::is_global`]
 ///
 /// Used to check if the provided IPv4 address is globally reachable
@@ -186,8 +217,12 @@ fn is_ipv6_global(addr: Ipv6Addr) -> bool {
 
 #[cfg(test)]
 mod test {
-    use crate::url_validation::is_allowed_url;
-    use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+    use crate::url_validation::{PublicDomainResolver, is_allowed_url};
+    use reqwest::dns::{Name, Resolve};
+    use std::{
+        net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+        str::FromStr,
+    };
     use url::Url;
 
     use super::DomainResolver;
@@ -309,4 +344,11 @@ mod test {
             assert!(!is_allowed_url::<MockDomainResolver>(&Url::parse(host).unwrap()).await);
         }
     }
+
+    /// Checks that the public resolver does not provide local addresses
+    #[tokio::test]
+    async fn test_public_resolver_local_host() {
+        let name = Name::from_str("localhost").unwrap();
+        assert!(PublicDomainResolver.resolve(name).await.is_err());
+    }
 }
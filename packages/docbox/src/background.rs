@@ -1,10 +1,17 @@
 use docbox_http::core::{
     database::DatabasePoolCache,
+    events::EventPublisherFactory,
     purge::{
+        purge_expired_event_payloads::safe_purge_expired_event_payloads,
         purge_expired_presigned_tasks::safe_purge_expired_presigned_tasks,
         purge_expired_tasks::safe_purge_expired_tasks,
         purge_expired_website_metadata::safe_purge_expired_website_metadata,
     },
+    shutdown::ShutdownCoordinator,
+    stats::{
+        reconcile_document_box_stats::safe_reconcile_document_box_stats,
+        rollup_usage_stats::safe_rollup_usage_stats,
+    },
     storage::StorageLayerFactory,
 };
 use futures::StreamExt;
@@ -22,14 +29,29 @@ pub enum BackgroundEvent {
 
     /// Task to purge expired tasks
     PurgeExpiredTasks,
+
+    /// Task to purge expired claim check event payloads
+    PurgeExpiredEventPayloads,
+
+    /// Task to update the daily usage stats rollup
+    RollupUsageStats,
+
+    /// Task to reconcile the document box stats counters
+    ReconcileDocumentBoxStats,
+
+    /// Task to close database pools that are no longer in use
+    EvictExpiredDatabasePools,
 }
 
 pub struct BackgroundTaskData {
     pub db_cache: Arc<DatabasePoolCache>,
     pub storage: StorageLayerFactory,
+    pub events: EventPublisherFactory,
 }
 
-pub async fn perform_background_tasks(data: BackgroundTaskData) {
+/// Runs the scheduled background tasks until `shutdown` is requested, tasks
+/// that are already running are tracked by `shutdown` so they can complete
+pub async fn perform_background_tasks(data: BackgroundTaskData, shutdown: ShutdownCoordinator) {
     let events = vec![
         SchedulerQueueEvent {
             event: BackgroundEvent::PurgeExpiredPresigned,
@@ -43,26 +65,72 @@ pub async fn perform_background_tasks(data: BackgroundTaskData) {
             event: BackgroundEvent::PurgeExpiredTasks,
             interval: 60 * 60,
         },
+        SchedulerQueueEvent {
+            event: BackgroundEvent::PurgeExpiredEventPayloads,
+            interval: 60 * 60,
+        },
+        SchedulerQueueEvent {
+            event: BackgroundEvent::RollupUsageStats,
+            interval: 60 * 60,
+        },
+        SchedulerQueueEvent {
+            event: BackgroundEvent::ReconcileDocumentBoxStats,
+            interval: 60 * 60,
+        },
+        SchedulerQueueEvent {
+            event: BackgroundEvent::EvictExpiredDatabasePools,
+            interval: 60,
+        },
     ];
 
     let mut events = SchedulerEventStream::new(events);
 
-    while let Some(event) = events.next().await {
+    loop {
+        let event = tokio::select! {
+            event = events.next() => event,
+            _ = shutdown.cancelled() => {
+                tracing::debug!("shutdown requested, stopped scheduling background tasks");
+                return;
+            }
+        };
+
+        let Some(event) = event else {
+            return;
+        };
+
         match event {
             BackgroundEvent::PurgeExpiredPresigned => {
                 tracing::debug!("performing background purge for presigned tasks");
-                tokio::spawn(safe_purge_expired_presigned_tasks(
+                shutdown.spawn(safe_purge_expired_presigned_tasks(
                     data.db_cache.clone(),
                     data.storage.clone(),
+                    data.events.clone(),
                 ));
             }
             BackgroundEvent::PurgeExpiredWebsiteMetadata => {
                 tracing::debug!("purging expired website metadata");
-                tokio::spawn(safe_purge_expired_website_metadata(data.db_cache.clone()));
+                shutdown.spawn(safe_purge_expired_website_metadata(data.db_cache.clone()));
             }
             BackgroundEvent::PurgeExpiredTasks => {
                 tracing::debug!("purging expired tasks");
-                tokio::spawn(safe_purge_expired_tasks(data.db_cache.clone()));
+                shutdown.spawn(safe_purge_expired_tasks(data.db_cache.clone()));
+            }
+            BackgroundEvent::PurgeExpiredEventPayloads => {
+                tracing::debug!("purging expired event payloads");
+                shutdown.spawn(safe_purge_expired_event_payloads(data.db_cache.clone()));
+            }
+            BackgroundEvent::RollupUsageStats => {
+                tracing::debug!("updating usage stats rollup");
+                shutdown.spawn(safe_rollup_usage_stats(data.db_cache.clone()));
+            }
+            BackgroundEvent::ReconcileDocumentBoxStats => {
+                tracing::debug!("reconciling document box stats");
+                shutdown.spawn(safe_reconcile_document_box_stats(data.db_cache.clone()));
+            }
+            BackgroundEvent::EvictExpiredDatabasePools => {
+                tracing::debug!("evicting expired database pools");
+                let db_cache = data.db_cache.clone();
+                shutdown.spawn(async move { db_cache.evict_expired_pools().await });
             }
         }
     }
@@ -0,0 +1,1024 @@
+//! # Config
+//!
+//! Optional configuration file for the server, allows the configuration that
+//! is otherwise loaded from environment variables to be provided through a
+//! single `docbox.toml` or `docbox.yaml` file.
+//!
+//! The file is loaded from the path provided by the `--config` argument,
+//! otherwise `docbox.toml`, `docbox.yaml` or `docbox.yml` is loaded from the
+//! current directory when present.
+//!
+//! Environment variables take precedence over the file, when any environment
+//! variable for a section is set that section is loaded from the environment
+//! variables instead of the file.
+//!
+//! The `server.max_file_size_bytes` and `logging.format` settings can be changed
+//! while the server is running by reloading the config, see [crate::reload]
+
+use crate::logging::config::{LoggingConfig, LoggingConfigError};
+use axum::http::HeaderValue;
+use docbox_http::core::{
+    mailbox::ImapIngestConfig,
+    mailer::{MAX_LINK_EXPIRY_SECONDS, MailProviderConfig, MailerConfig, MailerConfigError},
+    notifications::NotificationConfig,
+    processing::{
+        ProcessingLayerConfig, ProcessingLayerConfigError,
+        office::{OfficeConverterConfig, OfficeConverterConfigError},
+        summary::{SummaryConfig, SummaryConfigError},
+    },
+    search::{SearchIndexFactoryConfig, SearchIndexFactoryError},
+    secrets::{SecretsManagerConfig, SecretsManagerConfigError, aws::AwsSecretsEndpoint},
+    storage::{StorageLayerFactoryConfig, StorageLayerFactoryConfigError, s3::S3Endpoint},
+    tenant::tenant_cache::{TenantCacheConfig, TenantCacheConfigError},
+};
+use docbox_http::extensions::cache_control::CacheControlPolicy;
+use serde::Deserialize;
+use std::{
+    num::ParseIntError,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use tracing_subscriber::EnvFilter;
+
+/// Files checked for in the current directory when no `--config` is provided
+const DEFAULT_CONFIG_PATHS: [&str; 3] = ["docbox.toml", "docbox.yaml", "docbox.yml"];
+
+/// Default max file size in bytes (100MB)
+const DEFAULT_MAX_FILE_SIZE_BYTES: i32 = 100 * 1000 * 1024;
+
+/// Environment variables for the search section
+const SEARCH_ENV: &[&str] = &[
+    "DOCBOX_SEARCH_INDEX_FACTORY",
+    "DOCBOX_TYPESENSE_URL",
+    "TYPESENSE_URL",
+    "DOCBOX_TYPESENSE_API_KEY",
+    "TYPESENSE_API_KEY",
+    "DOCBOX_TYPESENSE_API_KEY_SECRET_NAME",
+    "TYPESENSE_API_KEY_SECRET_NAME",
+    "DOCBOX_OPENSEARCH_URL",
+    "OPENSEARCH_URL",
+    "DOCBOX_ELASTICSEARCH_URL",
+    "ELASTICSEARCH_URL",
+    "DOCBOX_ELASTICSEARCH_API_KEY",
+    "DOCBOX_ELASTICSEARCH_USERNAME",
+    "DOCBOX_ELASTICSEARCH_PASSWORD",
+    "DOCBOX_TANTIVY_PATH",
+    "DOCBOX_TANTIVY_WRITER_MEMORY",
+    "DOCBOX_SEARCH_DATA_REGION",
+    "DOCBOX_SEARCH_LANGUAGE",
+];
+
+/// Environment variables for the storage section
+const STORAGE_ENV: &[&str] = &[
+    "DOCBOX_S3_ENDPOINT",
+    "DOCBOX_S3_EXTERNAL_ENDPOINT",
+    "DOCBOX_S3_ACCESS_KEY_ID",
+    "DOCBOX_S3_ACCESS_KEY_SECRET",
+    "DOCBOX_S3_DATA_REGION",
+];
+
+/// Environment variables for the secrets section
+const SECRETS_ENV: &[&str] = &[
+    "DOCBOX_SECRET_MANAGER",
+    "DOCBOX_SECRET_MANAGER_MEMORY_DEFAULT",
+    "DOCBOX_SECRET_MANAGER_MEMORY_SECRETS",
+    "DOCBOX_SECRETS_ENDPOINT",
+    "DOCBOX_SECRETS_ACCESS_KEY_ID",
+    "DOCBOX_SECRETS_ACCESS_KEY_SECRET",
+];
+
+/// Environment variables for the processing section
+const PROCESSING_ENV: &[&str] = &[
+    "DOCBOX_MAX_FILE_UNPACK_ITERATIONS",
+    "DOCBOX_FILE_PROCESSING_TIMEOUT",
+    "DOCBOX_MAX_CONVERT_FILE_SIZE",
+    "DOCBOX_MAX_CONVERT_PAGES",
+    "DOCBOX_CONVERT_IMAGE_MAX_DIMENSION",
+    "DOCBOX_PDF_PREVIEW_PAGES",
+    "DOCBOX_CONVERT_FAILURE_THRESHOLD",
+    "DOCBOX_CONVERT_CIRCUIT_RESET_TIMEOUT",
+];
+
+/// Environment variables for the office converter section
+const OFFICE_CONVERTER_ENV: &[&str] = &[
+    "DOCBOX_OFFICE_CONVERTER",
+    "DOCBOX_CONVERT_SERVER_ADDRESS",
+    "CONVERT_SERVER_ADDRESS",
+    "DOCBOX_CONVERT_SERVER_USE_PROXY",
+    "CONVERT_SERVER_USE_PROXY",
+    "DOCBOX_CONVERT_LAMBDA_FUNCTION_NAME",
+    "DOCBOX_CONVERT_LAMBDA_QUALIFIER",
+    "DOCBOX_CONVERT_LAMBDA_TENANT_ID",
+    "DOCBOX_CONVERT_LAMBDA_RETRY_ATTEMPTS",
+    "DOCBOX_CONVERT_LAMBDA_RETRY_WAIT",
+    "DOCBOX_CONVERT_LAMBDA_TMP_BUCKET",
+];
+
+/// Environment variables for the summary section
+const SUMMARY_ENV: &[&str] = &[
+    "DOCBOX_SUMMARY_ENDPOINT",
+    "DOCBOX_SUMMARY_MODEL",
+    "DOCBOX_SUMMARY_API_KEY",
+    "DOCBOX_SUMMARY_MAX_TOKENS",
+    "DOCBOX_SUMMARY_TIMEOUT",
+];
+
+/// Environment variables for the mail section
+const MAIL_ENV: &[&str] = &[
+    "DOCBOX_MAIL_PROVIDER",
+    "DOCBOX_MAIL_FROM",
+    "DOCBOX_MAIL_SMTP_HOST",
+    "DOCBOX_MAIL_SMTP_PORT",
+    "DOCBOX_MAIL_SMTP_TLS",
+    "DOCBOX_MAIL_SMTP_USERNAME",
+    "DOCBOX_MAIL_SMTP_PASSWORD",
+    "DOCBOX_MAIL_SES_REGION",
+    "DOCBOX_MAIL_MAX_ATTACHMENT_SIZE_BYTES",
+    "DOCBOX_MAIL_LINK_EXPIRY_SECONDS",
+];
+
+/// Environment variables for the notifications section
+const NOTIFICATIONS_ENV: &[&str] = &["DOCBOX_MPSC_QUEUE", "DOCBOX_SQS_URL"];
+
+/// Environment variables for the tenant cache section
+const TENANT_CACHE_ENV: &[&str] = &[
+    "DOCBOX_TENANT_CACHE_TTL",
+    "DOCBOX_TENANT_CACHE_IDLE",
+    "DOCBOX_TENANT_CACHE_CAPACITY",
+];
+
+/// Environment variables for the logging section
+const LOGGING_ENV: &[&str] = &[
+    "DOCBOX_LOGGING_FORMAT",
+    "DOCBOX_LOGGING_ALLOW_NOISY",
+    "SENTRY_DSN",
+    "DOCBOX_SENTRY_DSN",
+    "DOCBOX_CLOUDWATCH_LOG_GROUP_NAME",
+    "DOCBOX_CLOUDWATCH_LOG_STREAM_NAME",
+    "DOCBOX_CLOUDWATCH_LOG_BATCH_SIZE",
+    "DOCBOX_CLOUDWATCH_LOG_INTERVAL_SECONDS",
+];
+
+/// Server configuration file, sections that are not provided are
+/// loaded from the environment variables
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfigFile {
+    /// Path the config was loaded from, [None] when no config file was loaded
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+
+    pub server: Option<ServerSettingsConfig>,
+    pub search: Option<SearchIndexFactoryConfig>,
+    pub storage: Option<StorageLayerFactoryConfig>,
+    pub secrets: Option<SecretsManagerConfig>,
+    pub processing: Option<ProcessingLayerConfig>,
+    pub office_converter: Option<OfficeConverterConfig>,
+    pub summary: Option<SummaryConfig>,
+    pub mail: Option<MailerConfig>,
+    pub notifications: Option<NotificationConfig>,
+    pub tenant_cache: Option<TenantCacheConfig>,
+    pub logging: Option<LoggingConfig>,
+    pub imap: Option<ImapIngestConfig>,
+    #[cfg(feature = "sftp-gateway")]
+    pub sftp_gateway: Option<crate::sftp::config::SftpGatewayConfig>,
+}
+
+/// General server settings
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettingsConfig {
+    /// Maximum allowed file size in bytes
+    pub max_file_size_bytes: Option<i32>,
+    /// Cache-Control header values for each class of route, classes
+    /// that are not provided use the defaults
+    pub cache_control: Option<CacheControlPolicy>,
+}
+
+#[derive(Debug, Error)]
+pub enum ServerConfigFileError {
+    #[error("missing value for --config argument")]
+    MissingConfigArgument,
+
+    #[error("failed to read config file {0}: {1}")]
+    Read(PathBuf, std::io::Error),
+
+    #[error("unsupported config file format {0}, expected .toml, .yaml or .yml")]
+    UnsupportedFormat(PathBuf),
+
+    #[error("invalid config file {0}: {1}")]
+    ParseToml(PathBuf, toml::de::Error),
+
+    #[error("invalid config file {0}: {1}")]
+    ParseYaml(PathBuf, serde_norway::Error),
+
+    #[error("invalid config file value for `{key}`: {reason}")]
+    InvalidValue {
+        key: &'static str,
+        reason: &'static str,
+    },
+}
+
+impl ServerConfigFile {
+    /// Load the config file from the `--config` argument or one of the default
+    /// config paths, when no config file is available an empty config is used
+    pub fn load(args: impl IntoIterator<Item = String>) -> Result<Self, ServerConfigFileError> {
+        let path = match config_path_from_args(args)? {
+            Some(path) => path,
+            None => match DEFAULT_CONFIG_PATHS
+                .into_iter()
+                .map(PathBuf::from)
+                .find(|path| path.is_file())
+            {
+                Some(path) => path,
+                None => return Ok(Self::default()),
+            },
+        };
+
+        Self::from_path(&path)
+    }
+
+    /// Load and validate the config file at `path`
+    pub fn from_path(path: &Path) -> Result<Self, ServerConfigFileError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| ServerConfigFileError::Read(path.to_path_buf(), error))?;
+
+        let extension = path.extension().and_then(|value| value.to_str());
+        let mut config: ServerConfigFile = match extension {
+            Some("toml") => toml::from_str(&contents)
+                .map_err(|error| ServerConfigFileError::ParseToml(path.to_path_buf(), error))?,
+            Some("yaml" | "yml") => serde_norway::from_str(&contents)
+                .map_err(|error| ServerConfigFileError::ParseYaml(path.to_path_buf(), error))?,
+            _ => return Err(ServerConfigFileError::UnsupportedFormat(path.to_path_buf())),
+        };
+
+        config.validate()?;
+        config.path = Some(path.to_path_buf());
+
+        Ok(config)
+    }
+
+    /// Validate values that cannot be checked when deserializing
+    pub fn validate(&self) -> Result<(), ServerConfigFileError> {
+        let invalid = |key, reason| Err(ServerConfigFileError::InvalidValue { key, reason });
+
+        if let Some(ServerSettingsConfig {
+            max_file_size_bytes: Some(max_file_size_bytes),
+            ..
+        }) = &self.server
+            && *max_file_size_bytes <= 0
+        {
+            return invalid("server.max_file_size_bytes", "must be a positive number");
+        }
+
+        if let Some(ServerSettingsConfig {
+            cache_control: Some(cache_control),
+            ..
+        }) = &self.server
+        {
+            let values = [
+                ("server.cache_control.api", &cache_control.api),
+                ("server.cache_control.raw", &cache_control.raw),
+                ("server.cache_control.preview", &cache_control.preview),
+                ("server.cache_control.generated", &cache_control.generated),
+                ("server.cache_control.website", &cache_control.website),
+            ];
+
+            for (key, value) in values {
+                if HeaderValue::from_str(value).is_err() {
+                    return invalid(key, "must be a valid header value");
+                }
+            }
+        }
+
+        match &self.search {
+            Some(SearchIndexFactoryConfig::Typesense(config)) => {
+                if config.url.is_empty() {
+                    return invalid("search.url", "must not be empty");
+                }
+
+                if config.api_key.is_none() && config.api_key_secret_name.is_none() {
+                    return invalid(
+                        "search.api_key",
+                        "either api_key or api_key_secret_name must be provided",
+                    );
+                }
+            }
+            Some(SearchIndexFactoryConfig::OpenSearch(config)) => {
+                if config.url.is_empty() {
+                    return invalid("search.url", "must not be empty");
+                }
+            }
+            Some(SearchIndexFactoryConfig::Elasticsearch(config)) => {
+                if config.url.is_empty() {
+                    return invalid("search.url", "must not be empty");
+                }
+
+                if config.username.is_some() && config.password.is_none() {
+                    return invalid(
+                        "search.password",
+                        "must be provided when username is provided",
+                    );
+                }
+            }
+            #[cfg(feature = "tantivy")]
+            Some(SearchIndexFactoryConfig::Tantivy(config)) => {
+                if config.path.as_os_str().is_empty() {
+                    return invalid("search.path", "must not be empty");
+                }
+            }
+            Some(SearchIndexFactoryConfig::Database(_)) | None => {}
+        }
+
+        if let Some(StorageLayerFactoryConfig::S3(config)) = &self.storage
+            && let S3Endpoint::Custom {
+                endpoint,
+                access_key_id,
+                access_key_secret,
+                ..
+            } = &config.endpoint
+        {
+            if endpoint.is_empty() {
+                return invalid("storage.endpoint.endpoint", "must not be empty");
+            }
+
+            if access_key_id.is_empty() {
+                return invalid("storage.endpoint.access_key_id", "must not be empty");
+            }
+
+            if access_key_secret.is_empty() {
+                return invalid("storage.endpoint.access_key_secret", "must not be empty");
+            }
+        }
+
+        if let Some(SecretsManagerConfig::Aws(config)) = &self.secrets
+            && let AwsSecretsEndpoint::Custom {
+                endpoint,
+                access_key_id,
+                access_key_secret,
+            } = &config.endpoint
+        {
+            if endpoint.is_empty() {
+                return invalid("secrets.endpoint.endpoint", "must not be empty");
+            }
+
+            if access_key_id.is_empty() {
+                return invalid("secrets.endpoint.access_key_id", "must not be empty");
+            }
+
+            if access_key_secret.is_empty() {
+                return invalid("secrets.endpoint.access_key_secret", "must not be empty");
+            }
+        }
+
+        match &self.office_converter {
+            Some(OfficeConverterConfig::ConverterServer(config)) if config.addresses.is_empty() => {
+                return invalid(
+                    "office_converter.ConverterServer.addresses",
+                    "at least one address must be provided",
+                );
+            }
+            Some(OfficeConverterConfig::ConverterLambda(config))
+                if config.function_name.is_empty() =>
+            {
+                return invalid(
+                    "office_converter.ConverterLambda.function_name",
+                    "must not be empty",
+                );
+            }
+            _ => {}
+        }
+
+        if let Some(summary) = &self.summary {
+            if summary.endpoint.is_empty() {
+                return invalid("summary.endpoint", "must not be empty");
+            }
+
+            if summary.model.is_empty() {
+                return invalid("summary.model", "must not be empty");
+            }
+        }
+
+        if let Some(mail) = &self.mail {
+            if mail.from.is_empty() {
+                return invalid("mail.from", "must not be empty");
+            }
+
+            if mail.link_expiry_seconds > MAX_LINK_EXPIRY_SECONDS {
+                return invalid("mail.link_expiry_seconds", "must not be longer than 7 days");
+            }
+
+            match &mail.provider {
+                MailProviderConfig::Smtp { host, .. } if host.is_empty() => {
+                    return invalid("mail.host", "must not be empty");
+                }
+                MailProviderConfig::Ses { region, .. } if region.is_empty() => {
+                    return invalid("mail.region", "must not be empty");
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(NotificationConfig::Sqs { queue_url }) = &self.notifications
+            && queue_url.is_empty()
+        {
+            return invalid("notifications.queue_url", "must not be empty");
+        }
+
+        if let Some(tenant_cache) = &self.tenant_cache
+            && tenant_cache.ttl_seconds == Some(0)
+        {
+            return invalid("tenant_cache.ttl_seconds", "must be a positive number");
+        }
+
+        if let Some(filter) = self
+            .logging
+            .as_ref()
+            .and_then(|logging| logging.format.filter.as_ref())
+            && EnvFilter::try_new(filter).is_err()
+        {
+            return invalid("logging.format.filter", "invalid filter directives");
+        }
+
+        if let Some(imap) = &self.imap {
+            if imap.poll_interval_seconds == 0 {
+                return invalid("imap.poll_interval_seconds", "must be a positive number");
+            }
+
+            for mailbox in &imap.mailboxes {
+                if mailbox.host.is_empty() {
+                    return invalid("imap.mailboxes.host", "must not be empty");
+                }
+
+                if mailbox.username.is_empty() {
+                    return invalid("imap.mailboxes.username", "must not be empty");
+                }
+
+                if mailbox.password_secret_name.is_empty() {
+                    return invalid("imap.mailboxes.password_secret_name", "must not be empty");
+                }
+            }
+        }
+
+        #[cfg(feature = "sftp-gateway")]
+        if let Some(sftp_gateway) = &self.sftp_gateway
+            && let Err((key, reason)) = sftp_gateway.validate()
+        {
+            return invalid(key, reason);
+        }
+
+        Ok(())
+    }
+
+    /// Get the max file size, the DOCBOX_MAX_FILE_SIZE_BYTES environment
+    /// variable takes precedence over the config file
+    pub fn max_file_size_bytes(&self) -> Result<i32, ParseIntError> {
+        if let Ok(value) = std::env::var("DOCBOX_MAX_FILE_SIZE_BYTES") {
+            return value.parse::<i32>();
+        }
+
+        Ok(self
+            .server
+            .as_ref()
+            .and_then(|server| server.max_file_size_bytes)
+            .unwrap_or(DEFAULT_MAX_FILE_SIZE_BYTES))
+    }
+
+    /// Get the Cache-Control header values for each class of route
+    pub fn cache_control(&self) -> CacheControlPolicy {
+        self.server
+            .as_ref()
+            .and_then(|server| server.cache_control.clone())
+            .unwrap_or_default()
+    }
+
+    /// Take the search config, falls back to the environment variables
+    pub fn search(&mut self) -> Result<SearchIndexFactoryConfig, SearchIndexFactoryError> {
+        match self.search.take() {
+            Some(config) if !any_env_set(SEARCH_ENV) => Ok(config),
+            _ => SearchIndexFactoryConfig::from_env(),
+        }
+    }
+
+    /// Take the storage config, falls back to the environment variables
+    pub fn storage(&mut self) -> Result<StorageLayerFactoryConfig, StorageLayerFactoryConfigError> {
+        match self.storage.take() {
+            Some(config) if !any_env_set(STORAGE_ENV) => Ok(config),
+            _ => StorageLayerFactoryConfig::from_env(),
+        }
+    }
+
+    /// Take the secrets config, falls back to the environment variables
+    pub fn secrets(&mut self) -> Result<SecretsManagerConfig, SecretsManagerConfigError> {
+        match self.secrets.take() {
+            Some(config) if !any_env_set(SECRETS_ENV) => Ok(config),
+            _ => SecretsManagerConfig::from_env(),
+        }
+    }
+
+    /// Take the processing config, falls back to the environment variables
+    pub fn processing(&mut self) -> Result<ProcessingLayerConfig, ProcessingLayerConfigError> {
+        match self.processing.take() {
+            Some(config) if !any_env_set(PROCESSING_ENV) => Ok(config),
+            _ => ProcessingLayerConfig::from_env(),
+        }
+    }
+
+    /// Take the office converter config, falls back to the environment variables
+    pub fn office_converter(
+        &mut self,
+    ) -> Result<OfficeConverterConfig, OfficeConverterConfigError> {
+        match self.office_converter.take() {
+            Some(config) if !any_env_set(OFFICE_CONVERTER_ENV) => Ok(config),
+            _ => OfficeConverterConfig::from_env(),
+        }
+    }
+
+    /// Take the summary config, falls back to the environment variables. [None]
+    /// when summarization is not enabled
+    pub fn summary(&mut self) -> Result<Option<SummaryConfig>, SummaryConfigError> {
+        match self.summary.take() {
+            Some(config) if !any_env_set(SUMMARY_ENV) => Ok(Some(config)),
+            _ => SummaryConfig::from_env(),
+        }
+    }
+
+    /// Take the mail config, falls back to the environment variables. [None]
+    /// when sending emails is not enabled
+    pub fn mail(&mut self) -> Result<Option<MailerConfig>, MailerConfigError> {
+        match self.mail.take() {
+            Some(config) if !any_env_set(MAIL_ENV) => Ok(Some(config)),
+            _ => MailerConfig::from_env(),
+        }
+    }
+
+    /// Take the notifications config, falls back to the environment variables
+    pub fn notifications(&mut self) -> NotificationConfig {
+        match self.notifications.take() {
+            Some(config) if !any_env_set(NOTIFICATIONS_ENV) => config,
+            _ => NotificationConfig::from_env(),
+        }
+    }
+
+    /// Take the tenant cache config, falls back to the environment variables
+    pub fn tenant_cache(&mut self) -> Result<TenantCacheConfig, TenantCacheConfigError> {
+        match self.tenant_cache.take() {
+            Some(config) if !any_env_set(TENANT_CACHE_ENV) => Ok(config),
+            _ => TenantCacheConfig::from_env(),
+        }
+    }
+
+    /// Take the logging config, falls back to the environment variables
+    pub fn logging(&mut self) -> Result<LoggingConfig, LoggingConfigError> {
+        match self.logging.take() {
+            Some(config) if !any_env_set(LOGGING_ENV) => Ok(config),
+            _ => LoggingConfig::from_env(),
+        }
+    }
+}
+
+/// Find the config file path from the `--config <path>` or `--config=<path>`
+/// command line arguments
+fn config_path_from_args(
+    args: impl IntoIterator<Item = String>,
+) -> Result<Option<PathBuf>, ServerConfigFileError> {
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args
+                .next()
+                .map(|value| Some(PathBuf::from(value)))
+                .ok_or(ServerConfigFileError::MissingConfigArgument);
+        }
+
+        if let Some(value) = arg.strip_prefix("--config=") {
+            return Ok(Some(PathBuf::from(value)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Check if any of the environment `variables` are set
+fn any_env_set(variables: &[&str]) -> bool {
+    variables
+        .iter()
+        .any(|variable| std::env::var_os(variable).is_some())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ServerConfigFile, ServerConfigFileError, config_path_from_args};
+    use docbox_http::core::{
+        mailer::MailProviderConfig, notifications::NotificationConfig,
+        search::SearchIndexFactoryConfig, storage::StorageLayerFactoryConfig,
+    };
+    use docbox_http::extensions::cache_control::CacheControlPolicy;
+    use std::path::PathBuf;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_config_path_from_args() {
+        assert_eq!(
+            config_path_from_args(args(&["docbox", "--config", "docbox.toml"])).unwrap(),
+            Some(PathBuf::from("docbox.toml"))
+        );
+        assert_eq!(
+            config_path_from_args(args(&["docbox", "--config=docbox.yaml"])).unwrap(),
+            Some(PathBuf::from("docbox.yaml"))
+        );
+        assert_eq!(config_path_from_args(args(&["docbox"])).unwrap(), None);
+        assert!(matches!(
+            config_path_from_args(args(&["docbox", "--config"])),
+            Err(ServerConfigFileError::MissingConfigArgument)
+        ));
+    }
+
+    #[test]
+    fn test_parse_toml_config() {
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [search]
+            provider = "typesense"
+            url = "http://localhost:8108"
+            api_key_secret_name = "typesense"
+
+            [storage]
+            provider = "s3"
+
+            [notifications]
+            provider = "sqs"
+            queue_url = "http://localhost:4566/queue"
+
+            [logging.format]
+            format = "text"
+            allow_noisy = true
+            "#,
+        )
+        .unwrap();
+
+        config.validate().unwrap();
+
+        assert!(matches!(
+            config.search,
+            Some(SearchIndexFactoryConfig::Typesense(_))
+        ));
+        assert!(matches!(
+            config.storage,
+            Some(StorageLayerFactoryConfig::S3(_))
+        ));
+        assert!(matches!(
+            config.notifications,
+            Some(NotificationConfig::Sqs { .. })
+        ));
+        assert!(config.logging.unwrap().format.allow_noisy);
+    }
+
+    #[test]
+    fn test_parse_yaml_config() {
+        let config: ServerConfigFile = serde_norway::from_str(
+            r#"
+            search:
+              provider: database
+            processing:
+              max_unpack_iterations: 2
+              max_convert_pages: 500
+            "#,
+        )
+        .unwrap();
+
+        config.validate().unwrap();
+
+        assert!(matches!(
+            config.search,
+            Some(SearchIndexFactoryConfig::Database(_))
+        ));
+        let processing = config.processing.unwrap();
+        assert_eq!(processing.max_unpack_iterations, Some(2));
+        assert_eq!(processing.max_convert_pages, Some(500));
+    }
+
+    #[test]
+    fn test_parse_imap_config() {
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [imap]
+            poll_interval_seconds = 30
+
+            [[imap.mailboxes]]
+            host = "imap.example.com"
+            username = "invoices@example.com"
+            password_secret_name = "docbox/imap/invoices"
+            env = "Development"
+            tenant_id = "00000000-0000-0000-0000-000000000000"
+            scope = "invoices"
+            "#,
+        )
+        .unwrap();
+
+        config.validate().unwrap();
+
+        let imap = config.imap.unwrap();
+        assert_eq!(imap.poll_interval_seconds, 30);
+
+        let mailbox = &imap.mailboxes[0];
+        assert_eq!(mailbox.port, 993);
+        assert!(mailbox.tls);
+        assert_eq!(mailbox.mailbox, "INBOX");
+        assert_eq!(
+            mailbox.mailbox_id(),
+            "imap://invoices@example.com@imap.example.com:993/INBOX"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sftp-gateway")]
+    fn test_parse_sftp_gateway_config() {
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [sftp_gateway]
+            host_key_path = "/etc/docbox/ssh_host_ed25519_key"
+
+            [[sftp_gateway.drops]]
+            username = "scanner"
+            password_secret_name = "docbox/sftp/scanner"
+            env = "Development"
+            tenant_id = "00000000-0000-0000-0000-000000000000"
+            scope = "invoices"
+
+            [[sftp_gateway.drops]]
+            username = "scanner"
+            password_secret_name = "docbox/sftp/scanner-2"
+            env = "Development"
+            tenant_id = "00000000-0000-0000-0000-000000000000"
+            scope = "receipts"
+            "#,
+        )
+        .unwrap();
+
+        let sftp_gateway = config.sftp_gateway.as_ref().unwrap();
+        assert_eq!(sftp_gateway.address.port(), 2222);
+        assert_eq!(sftp_gateway.drops[0].folder_id, None);
+
+        assert!(matches!(
+            config.validate(),
+            Err(ServerConfigFileError::InvalidValue {
+                key: "sftp_gateway.drops.username",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_unknown_key_is_named() {
+        let error = serde_norway::from_str::<ServerConfigFile>("serach:\n  provider: database\n")
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("serach"));
+
+        let error =
+            serde_norway::from_str::<ServerConfigFile>("logging:\n  format:\n    colour: true\n")
+                .err()
+                .unwrap();
+        assert!(error.to_string().contains("logging.format"));
+    }
+
+    #[test]
+    fn test_validate_reloadable_settings() {
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [server]
+            max_file_size_bytes = 1024
+
+            [logging.format]
+            filter = "info,docbox=debug"
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [server]
+            max_file_size_bytes = 0
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(ServerConfigFileError::InvalidValue {
+                key: "server.max_file_size_bytes",
+                ..
+            })
+        ));
+
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [logging.format]
+            filter = "docbox=loud"
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(ServerConfigFileError::InvalidValue {
+                key: "logging.format.filter",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_cache_control_config() {
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [server.cache_control]
+            generated = "private, max-age=86400"
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+
+        let cache_control = config.cache_control();
+        assert_eq!(cache_control.generated, "private, max-age=86400");
+        assert_eq!(cache_control.api, CacheControlPolicy::default().api);
+
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [server.cache_control]
+            raw = "no-cache\n"
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(ServerConfigFileError::InvalidValue {
+                key: "server.cache_control.raw",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_tenant_cache_config() {
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [tenant_cache]
+            ttl_seconds = 60
+            capacity = 500
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+
+        let tenant_cache = config.tenant_cache.as_ref().unwrap();
+        assert_eq!(tenant_cache.ttl_seconds, Some(60));
+        assert_eq!(tenant_cache.idle_seconds, None);
+        assert_eq!(tenant_cache.capacity, Some(500));
+
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [tenant_cache]
+            ttl_seconds = 0
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(ServerConfigFileError::InvalidValue {
+                key: "tenant_cache.ttl_seconds",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_mail_config() {
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [mail]
+            provider = "smtp"
+            host = "smtp.example.com"
+            port = 465
+            tls = "tls"
+            from = "Docbox <docbox@example.com>"
+
+            [mail.templates.file_link]
+            subject = "{{file_name}}"
+            body = "{{link}}"
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+
+        let mail = config.mail.unwrap();
+        assert!(matches!(
+            mail.provider,
+            MailProviderConfig::Smtp {
+                port: Some(465),
+                ..
+            }
+        ));
+        assert_eq!(mail.templates.file_link.subject, "{{file_name}}");
+
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [mail]
+            provider = "ses"
+            region = "ap-southeast-2"
+            username = "user"
+            password = "password"
+            from = "docbox@example.com"
+            link_expiry_seconds = 1209600
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(ServerConfigFileError::InvalidValue {
+                key: "mail.link_expiry_seconds",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_summary_config() {
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [summary]
+            endpoint = "http://localhost:11434/v1"
+            model = "llama3.2"
+            max_tokens = 128
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+
+        let summary = config.summary.unwrap();
+        assert_eq!(summary.model, "llama3.2");
+        assert_eq!(summary.max_tokens, Some(128));
+        assert_eq!(summary.api_key, None);
+
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [summary]
+            endpoint = "http://localhost:11434/v1"
+            model = ""
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(ServerConfigFileError::InvalidValue {
+                key: "summary.model",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validate_names_key() {
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [search]
+            provider = "typesense"
+            url = "http://localhost:8108"
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            config.validate(),
+            Err(ServerConfigFileError::InvalidValue {
+                key: "search.api_key",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validate_elasticsearch_password() {
+        let config: ServerConfigFile = toml::from_str(
+            r#"
+            [search]
+            provider = "elasticsearch"
+            url = "http://localhost:9200"
+            username = "elastic"
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            config.validate(),
+            Err(ServerConfigFileError::InvalidValue {
+                key: "search.password",
+                ..
+            })
+        ));
+    }
+}
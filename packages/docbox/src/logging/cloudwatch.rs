@@ -4,13 +4,15 @@ use std::{
 };
 
 use aws_config::SdkConfig;
+use serde::Deserialize;
 use thiserror::Error;
 use tracing::Subscriber;
 use tracing_cloudwatch::CloudWatchWorkerGuard;
 use tracing_subscriber::{Layer, registry::LookupSpan};
 
 /// Configuration for cloudwatch logging
-#[derive(Default)]
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct CloudwatchLoggingConfig {
     pub log_group_name: Option<String>,
     pub log_stream_name: Option<String>,
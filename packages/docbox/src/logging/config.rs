@@ -1,3 +1,4 @@
+use serde::Deserialize;
 use thiserror::Error;
 
 use crate::logging::{
@@ -7,7 +8,8 @@ use crate::logging::{
 };
 
 /// Configuration for logging
-#[derive(Default)]
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct LoggingConfig {
     pub format: LoggingFormatConfig,
     pub sentry: SentryLoggingConfig,
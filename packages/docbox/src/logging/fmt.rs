@@ -1,11 +1,18 @@
 use std::str::{FromStr, ParseBoolError};
 
+use serde::Deserialize;
 use thiserror::Error;
 use tracing::Subscriber;
-use tracing_subscriber::{EnvFilter, Layer, fmt, registry::LookupSpan};
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry, filter::ParseError, fmt, registry::LookupSpan, reload,
+};
+
+/// Handle for replacing the active logging filter
+pub type FilterHandle = reload::Handle<EnvFilter, Registry>;
 
 /// Logging format to use
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum LoggingFormat {
     Text,
     #[default]
@@ -39,10 +46,14 @@ impl LoggingFormat {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct LoggingFormatConfig {
     pub format: LoggingFormat,
     pub allow_noisy: bool,
+    /// Filter directives (i.e "info,docbox=debug"), when not
+    /// specified the RUST_LOG environment variable is used
+    pub filter: Option<String>,
 }
 
 #[derive(Debug, Error)]
@@ -66,6 +77,7 @@ impl LoggingFormatConfig {
         Ok(Self {
             format,
             allow_noisy,
+            filter: None,
         })
     }
 }
@@ -106,12 +118,17 @@ pub fn json_fmt_layer<S>()
         .with_target(false)
 }
 
-pub fn filter_layer(allow_noisy: bool) -> EnvFilter {
+pub fn filter_layer(allow_noisy: bool, filter: Option<&str>) -> Result<EnvFilter, ParseError> {
+    let filter = match filter {
+        Some(filter) => EnvFilter::try_new(filter)?,
+        None => EnvFilter::from_default_env(),
+    };
+
     if allow_noisy {
-        return EnvFilter::from_default_env();
+        return Ok(filter);
     }
 
-    EnvFilter::from_default_env()
+    Ok(filter
         // Increase logging requirements for noisy dependencies
         .add_directive(
             "aws_sdk_secretsmanager=info"
@@ -126,5 +143,5 @@ pub fn filter_layer(allow_noisy: bool) -> EnvFilter {
         )
         .add_directive("hyper_util=info".parse().expect("directive was invalid"))
         .add_directive("aws_sdk_sqs=info".parse().expect("directive was invalid"))
-        .add_directive("h2=info".parse().expect("directive was invalid"))
+        .add_directive("h2=info".parse().expect("directive was invalid")))
 }
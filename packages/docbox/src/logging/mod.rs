@@ -2,12 +2,12 @@ use std::error::Error;
 
 use aws_config::SdkConfig;
 use tracing_cloudwatch::CloudWatchWorkerGuard;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt};
 
 use crate::logging::{
     cloudwatch::cloudwatch_layer,
     config::LoggingConfig,
-    fmt::{filter_layer, fmt_layer},
+    fmt::{FilterHandle, filter_layer, fmt_layer},
     sentry::sentry_layer,
 };
 
@@ -31,13 +31,16 @@ impl LoggingGuards {
     }
 }
 
+/// Initialize logging, provides the guards for the active loggers and
+/// a handle for replacing the logging filter
 pub fn init_logging(
     aws_config: &SdkConfig,
     config: LoggingConfig,
-) -> Result<LoggingGuards, Box<dyn Error>> {
+) -> Result<(LoggingGuards, FilterHandle), Box<dyn Error>> {
     let mut guards = LoggingGuards::default();
 
-    let filter_layer = filter_layer(config.format.allow_noisy);
+    let filter_layer = filter_layer(config.format.allow_noisy, config.format.filter.as_deref())?;
+    let (filter_layer, filter_handle) = reload::Layer::new(filter_layer);
 
     let mut sentry = None;
     let mut cloudwatch = None;
@@ -54,11 +57,11 @@ pub fn init_logging(
     }
 
     tracing_subscriber::registry()
+        .with(filter_layer)
         .with(fmt_layer(config.format))
         .with(sentry)
         .with(cloudwatch)
-        .with(filter_layer)
         .init();
 
-    Ok(guards)
+    Ok((guards, filter_handle))
 }
@@ -1,11 +1,13 @@
 use sentry_tracing::SentryLayer;
+use serde::Deserialize;
 use tracing::{Level, Subscriber};
 use tracing_subscriber::registry::LookupSpan;
 
 use crate::logging::config::LoggingConfigError;
 
 /// Configuration for sentry logging
-#[derive(Default)]
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct SentryLoggingConfig {
     pub dsn: Option<String>,
 }
@@ -3,33 +3,53 @@
 
 use crate::{
     background::{BackgroundTaskData, perform_background_tasks},
-    logging::config::LoggingConfig,
+    config::ServerConfigFile,
+    logging::fmt::FilterHandle,
+    preflight::{PreflightDependencies, PreflightFailed, run_preflight},
+    reload::ServerConfigReloader,
 };
 use aws_config::SdkConfig;
 use axum::{Extension, extract::DefaultBodyLimit};
 use axum_server::tls_rustls::RustlsConfig;
 use docbox_http::{
     core::{
+        Docbox,
         aws::{SqsClient, aws_config},
         database::{DatabasePoolCache, DatabasePoolCacheConfig},
-        events::{EventPublisherFactory, sqs::SqsEventPublisherFactory},
+        events::{
+            EventPublisherFactory, sqs::SqsEventPublisherFactory,
+            webhook::WebhookEventPublisherFactory,
+        },
+        files::access_stats::FileAccessRecorder,
         links::resolve_website::{ResolveWebsiteConfig, ResolveWebsiteService},
+        mailbox::process_imap_mailboxes,
+        mailer::Mailer,
         notifications::{
-            AppNotificationQueue, NotificationConfig,
+            AppNotificationQueue,
             process::{NotificationQueueData, process_notification_queue},
         },
         processing::{
-            ProcessingLayer, ProcessingLayerConfig,
-            office::{OfficeConverter, OfficeConverterConfig, OfficeProcessingLayer},
+            ProcessingLayer,
+            office::{OfficeConverter, OfficeProcessingLayer},
+            summary::SummaryProcessor,
+        },
+        search::SearchIndexFactory,
+        secrets::SecretManager,
+        shutdown::ShutdownCoordinator,
+        stats::request_usage::RequestUsageRecorder,
+        storage::{
+            StorageLayerFactory,
+            throttle::{StorageThrottle, StorageThrottleConfig},
         },
-        search::{SearchIndexFactory, SearchIndexFactoryConfig},
-        secrets::{SecretManager, SecretsManagerConfig},
-        storage::{StorageLayerFactory, StorageLayerFactoryConfig},
-        tenant::tenant_cache::TenantCache,
+        tenant::{tenant_cache::TenantCache, tenant_storage_key::TenantStorageKeyCache},
         web_scraper::{WebsiteMetaService, WebsiteMetaServiceConfig},
     },
-    extensions::{max_file_size::MaxFileSizeBytes, server_version::ServerVersion},
-    middleware::api_key::ApiKeyLayer,
+    extensions::{
+        bulk_storage_throttle::BulkStorageThrottle, config_reload::ConfigReloadHandle,
+        inbound_email_domain::InboundEmailDomain, max_file_size::MaxFileSizeBytes,
+        search_auto_heal::SearchAutoHeal, server_version::ServerVersion,
+    },
+    middleware::{api_key::ApiKeyLayer, body_limit::MaxFileSizeLayer},
     routes::router,
 };
 use logging::init_logging;
@@ -37,12 +57,18 @@ use std::{
     error::Error,
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     sync::Arc,
+    time::Duration,
 };
-use tower_http::{limit::RequestBodyLimitLayer, trace::TraceLayer};
+use tower_http::trace::TraceLayer;
 use tracing::debug;
 
 mod background;
+mod config;
 mod logging;
+mod preflight;
+mod reload;
+#[cfg(feature = "sftp-gateway")]
+mod sftp;
 
 /// The server version extracted from the Cargo.toml
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -55,6 +81,10 @@ const DEFAULT_SERVER_ADDRESS_HTTP: SocketAddr =
 const DEFAULT_SERVER_ADDRESS_HTTPS: SocketAddr =
     SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 8443));
 
+/// Default time to wait for in-flight background work to complete
+/// when shutting down
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
 fn main() -> Result<(), Box<dyn Error>> {
     _ = dotenvy::dotenv();
 
@@ -63,13 +93,19 @@ fn main() -> Result<(), Box<dyn Error>> {
         .build()
         .expect("Failed building the Runtime")
         .block_on(async move {
+            // Load the optional config file
+            let mut config = ServerConfigFile::load(std::env::args().skip(1))?;
+
+            // Only run the preflight checks then exit
+            let check_only = std::env::args().skip(1).any(|arg| arg == "--check");
+
             // Load AWS configuration
             let aws_config = aws_config().await;
 
-            let logging_config = LoggingConfig::from_env()?;
-            let logging_guards = init_logging(&aws_config, logging_config)?;
+            let logging_config = config.logging()?;
+            let (logging_guards, filter_handle) = init_logging(&aws_config, logging_config)?;
 
-            if let Err(error) = server(aws_config).await {
+            if let Err(error) = server(aws_config, config, filter_handle, check_only).await {
                 tracing::error!(?error, message = %error, "error running server");
                 return Err(error);
             }
@@ -80,12 +116,21 @@ fn main() -> Result<(), Box<dyn Error>> {
         })
 }
 
-async fn server(aws_config: SdkConfig) -> Result<(), Box<dyn Error>> {
-    let max_file_size_bytes = match std::env::var("DOCBOX_MAX_FILE_SIZE_BYTES") {
-        Ok(value) => value.parse::<i32>()?,
-        // Default max file size in bytes (100MB)
-        Err(_) => 100 * 1000 * 1024,
-    };
+async fn server(
+    aws_config: SdkConfig,
+    mut config: ServerConfigFile,
+    filter_handle: FilterHandle,
+    check_only: bool,
+) -> Result<(), Box<dyn Error>> {
+    let max_file_size = MaxFileSizeBytes::new(config.max_file_size_bytes()?);
+    let cache_control = Arc::new(config.cache_control());
+
+    // Setup config reloading for the settings that can change at runtime
+    let config_reloader =
+        ServerConfigReloader::new(config.path.clone(), max_file_size.clone(), filter_handle);
+
+    #[cfg(unix)]
+    tokio::spawn(reload::reload_on_hangup(config_reloader.clone()));
 
     // Create website scraping service
     let website_meta_service_config = WebsiteMetaServiceConfig::from_env()?;
@@ -98,15 +143,28 @@ async fn server(aws_config: SdkConfig) -> Result<(), Box<dyn Error>> {
     ));
 
     // Create secrets manager
-    let secrets_config = SecretsManagerConfig::from_env()?;
+    let secrets_config = config.secrets()?;
     let secrets = SecretManager::from_config(&aws_config, secrets_config);
 
     // Load database credentials
     let db_pool_config = DatabasePoolCacheConfig::from_env()?;
+    let root_secret_name = db_pool_config
+        .root_secret_name
+        .clone()
+        .filter(|_| !db_pool_config.root_iam);
 
     // API key
     let api_key = std::env::var("DOCBOX_API_KEY").ok();
 
+    // Time to wait for background work to complete when shutting down
+    let shutdown_timeout = match std::env::var("DOCBOX_SHUTDOWN_TIMEOUT_SECS") {
+        Ok(value) => Duration::from_secs(value.parse::<u64>()?),
+        Err(_) => DEFAULT_SHUTDOWN_TIMEOUT,
+    };
+
+    // Coordinator for draining background work on shutdown
+    let shutdown = ShutdownCoordinator::new();
+
     // Setup database cache / connector
     let db_cache = Arc::new(DatabasePoolCache::from_config(
         aws_config.clone(),
@@ -119,40 +177,120 @@ async fn server(aws_config: SdkConfig) -> Result<(), Box<dyn Error>> {
     let sqs_client = SqsClient::new(&aws_config);
 
     // Setup event publisher factories
-    let sqs_publisher_factory = SqsEventPublisherFactory::new(sqs_client.clone());
-    let event_publisher_factory = EventPublisherFactory::new(sqs_publisher_factory);
+    let sqs_publisher_factory = SqsEventPublisherFactory::new(sqs_client.clone(), shutdown.clone())
+        .with_claim_check(db_cache.clone());
+    let webhook_publisher_factory =
+        WebhookEventPublisherFactory::new(db_cache.clone(), shutdown.clone());
+    let event_publisher_factory =
+        EventPublisherFactory::new(sqs_publisher_factory).with_webhooks(webhook_publisher_factory);
+
+    // Create tenant storage encryption key cache
+    let storage_key_cache = TenantStorageKeyCache::new(secrets.clone());
+
+    // Create file access recorder
+    let file_access_recorder = FileAccessRecorder::new(db_cache.clone(), &shutdown);
+
+    // Create request usage recorder
+    let request_usage_recorder = RequestUsageRecorder::new(db_cache.clone(), &shutdown);
 
     // Setup search index factory
-    let search_config = SearchIndexFactoryConfig::from_env()?;
-    let search_index_factory =
-        SearchIndexFactory::from_config(&aws_config, secrets, db_cache.clone(), search_config)?;
+    let search_config = config.search()?;
+    let search_index_factory = SearchIndexFactory::from_config(
+        &aws_config,
+        secrets.clone(),
+        db_cache.clone(),
+        search_config,
+    )?;
 
     // Setup storage factory
-    let storage_factory_config = StorageLayerFactoryConfig::from_env()?;
-    let storage_factory = StorageLayerFactory::from_config(&aws_config, storage_factory_config);
+    let storage_factory_config = config.storage()?;
+    let storage_factory = StorageLayerFactory::from_config(&aws_config, storage_factory_config)?;
+
+    // Setup the rate limit for storage access from bulk admin operations
+    let bulk_storage_throttle_config = StorageThrottleConfig::from_env()?;
+    let bulk_storage_throttle =
+        BulkStorageThrottle(StorageThrottle::from_config(&bulk_storage_throttle_config));
+
+    // Recreating search indexes deleted out-of-band is opt-in
+    let search_auto_heal = match std::env::var("DOCBOX_SEARCH_AUTO_HEAL") {
+        Ok(value) => SearchAutoHeal(value.parse::<bool>()?),
+        Err(_) => SearchAutoHeal::default(),
+    };
+
+    // Inbound email addresses can only be created when a receiving domain is configured
+    let inbound_email_domain =
+        InboundEmailDomain(std::env::var("DOCBOX_INBOUND_EMAIL_DOMAIN").ok());
 
     // Create the converter
-    let converter_config = OfficeConverterConfig::from_env()?;
+    let converter_config = config.office_converter()?;
     let converter = OfficeConverter::from_config(&aws_config, &storage_factory, converter_config)?;
 
     // Load the config for the processing layer
-    let processing_layer_config = ProcessingLayerConfig::from_env()?;
+    let processing_layer_config = config.processing()?;
+
+    // Create the optional summary processor
+    let summary = config
+        .summary()?
+        .map(SummaryProcessor::from_config)
+        .transpose()?;
+
+    // Create the optional mailer for sending files by email
+    let mailer = config.mail()?.map(Mailer::from_config).transpose()?;
 
     // Setup processing layer
     let processing = ProcessingLayer {
-        office: OfficeProcessingLayer { converter },
+        office: OfficeProcessingLayer {
+            converter,
+            circuit_breaker: Default::default(),
+        },
         config: processing_layer_config,
+        summary,
     };
 
     // Create tenant cache
-    let tenant_cache = Arc::new(TenantCache::new());
+    let tenant_cache = Arc::new(TenantCache::from_config(config.tenant_cache()?));
 
     // Setup notification queue
-    let notification_config = NotificationConfig::from_env();
+    let notification_config = config.notifications();
+
+    // Preflight checks can be skipped to avoid slowing down startup
+    let disable_preflight = match std::env::var("DOCBOX_DISABLE_PREFLIGHT") {
+        Ok(value) => value.parse::<bool>()?,
+        Err(_) => false,
+    };
+
+    // Verify the dependencies are accessible before accepting requests
+    if check_only || !disable_preflight {
+        let report = run_preflight(PreflightDependencies {
+            db_cache: &db_cache,
+            secrets: &secrets,
+            root_secret_name: root_secret_name.as_deref(),
+            search: &search_index_factory,
+            storage: &storage_factory,
+            converter: &processing.office.converter,
+            sqs: &sqs_client,
+            notifications: &notification_config,
+        })
+        .await;
+
+        if check_only {
+            println!("{}", report.table());
+            db_cache.close_all().await;
+
+            if report.has_failures() {
+                return Err(PreflightFailed.into());
+            }
+
+            return Ok(());
+        }
+
+        report.log();
+    }
+
     let mut notification_queue = AppNotificationQueue::from_config(sqs_client, notification_config);
 
     // Setup router
-    let mut app = router::<true, true, true>();
+    let mut app = router::<true, true, true, true>();
 
     if let AppNotificationQueue::Mpsc(queue) = &mut notification_queue {
         let sender = queue.take_sender().ok_or_else(|| {
@@ -163,16 +301,22 @@ async fn server(aws_config: SdkConfig) -> Result<(), Box<dyn Error>> {
         app = app.layer(Extension(sender));
     }
 
+    if let Some(mailer) = mailer {
+        app = app.layer(Extension(mailer));
+    }
+
     // Spawn background task to process notification queue messages
-    tokio::spawn(process_notification_queue(
+    shutdown.spawn(process_notification_queue(
         notification_queue,
         NotificationQueueData {
             db_cache: db_cache.clone(),
             search: search_index_factory.clone(),
             storage: storage_factory.clone(),
+            storage_keys: storage_key_cache.clone(),
             events: event_publisher_factory.clone(),
             processing: processing.clone(),
         },
+        shutdown.clone(),
     ));
 
     // When operating in an environment where multiple servers are running we may want to
@@ -190,10 +334,46 @@ async fn server(aws_config: SdkConfig) -> Result<(), Box<dyn Error>> {
         tracing::debug!("scheduling background tasks");
 
         // Spawn background scheduled tasks
-        tokio::spawn(perform_background_tasks(BackgroundTaskData {
-            db_cache: db_cache.clone(),
-            storage: storage_factory.clone(),
-        }));
+        shutdown.spawn(perform_background_tasks(
+            BackgroundTaskData {
+                db_cache: db_cache.clone(),
+                storage: storage_factory.clone(),
+                events: event_publisher_factory.clone(),
+            },
+            shutdown.clone(),
+        ));
+    }
+
+    // Embedded docbox instance for the optional ingestion workers
+    let docbox = Docbox::builder()
+        .db_cache(db_cache.clone())
+        .secrets(secrets.clone())
+        .search(search_index_factory.clone())
+        .storage(storage_factory.clone())
+        .processing(processing.clone())
+        .events(event_publisher_factory.clone())
+        .build()?;
+
+    // Start polling the configured IMAP mailboxes
+    if let Some(imap_config) = config.imap.take() {
+        shutdown.spawn(process_imap_mailboxes(
+            imap_config,
+            docbox.clone(),
+            secrets.clone(),
+            shutdown.clone(),
+        ));
+    }
+
+    // Start the optional SFTP ingestion gateway
+    #[cfg(feature = "sftp-gateway")]
+    if let Some(sftp_gateway_config) = config.sftp_gateway.take() {
+        shutdown.spawn(sftp::serve(
+            sftp_gateway_config,
+            docbox.clone(),
+            secrets.clone(),
+            max_file_size.clone(),
+            shutdown.clone(),
+        ));
     }
 
     // Setup app layers and extension
@@ -205,10 +385,18 @@ async fn server(aws_config: SdkConfig) -> Result<(), Box<dyn Error>> {
         .layer(Extension(event_publisher_factory))
         .layer(Extension(processing))
         .layer(Extension(tenant_cache))
+        .layer(Extension(storage_key_cache))
+        .layer(Extension(file_access_recorder))
+        .layer(Extension(request_usage_recorder))
         .layer(Extension(ServerVersion(VERSION)))
-        .layer(Extension(MaxFileSizeBytes(max_file_size_bytes)))
+        .layer(Extension(max_file_size.clone()))
+        .layer(Extension(cache_control))
+        .layer(Extension(bulk_storage_throttle))
+        .layer(Extension(search_auto_heal))
+        .layer(Extension(inbound_email_domain))
+        .layer(Extension(ConfigReloadHandle(config_reloader)))
         .layer(DefaultBodyLimit::disable())
-        .layer(RequestBodyLimitLayer::new(max_file_size_bytes as usize))
+        .layer(MaxFileSizeLayer::new(max_file_size))
         .layer(TraceLayer::new_for_http());
 
     if let Some(api_key) = api_key {
@@ -225,14 +413,12 @@ async fn server(aws_config: SdkConfig) -> Result<(), Box<dyn Error>> {
 
     let handle = axum_server::Handle::default();
 
-    // Handle graceful shutdown on CTRL+C
+    // Handle graceful shutdown on CTRL+C or SIGTERM
     tokio::spawn({
         let handle = handle.clone();
         async move {
-            _ = tokio::signal::ctrl_c().await;
+            shutdown_signal().await;
             handle.graceful_shutdown(None);
-
-            db_cache.close_all().await;
         }
     });
 
@@ -298,5 +484,41 @@ async fn server(aws_config: SdkConfig) -> Result<(), Box<dyn Error>> {
             .await?;
     }
 
+    // Server has stopped accepting requests, stop the background workers and
+    // wait for their in-flight work to complete
+    debug!("server stopped, waiting for background work to complete");
+    if !shutdown.shutdown(shutdown_timeout).await {
+        tracing::warn!("timed out waiting for background work to complete");
+    }
+
+    db_cache.close_all().await;
+
     Ok(())
 }
+
+/// Wait for a signal requesting shutdown (CTRL+C or SIGTERM)
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut sigterm = match signal(SignalKind::terminate()) {
+            Ok(value) => value,
+            Err(error) => {
+                tracing::error!(?error, "failed to listen for SIGTERM");
+                _ = tokio::signal::ctrl_c().await;
+                return;
+            }
+        };
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = sigterm.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        _ = tokio::signal::ctrl_c().await;
+    }
+}
@@ -0,0 +1,391 @@
+//! # Preflight
+//!
+//! Startup checks verifying that the dependencies of the server are reachable
+//! and accessible before the server starts accepting requests:
+//!
+//! - Database: Connecting to the root database and each tenant database
+//! - Secrets: Reading the root database credentials secret
+//! - Search: Search index exists for each tenant
+//! - Storage: Storage bucket is accessible for each tenant
+//! - Region: Storage and search backends match the region of each tenant
+//! - Converter: Office converter is reachable
+//! - Notifications: SQS notification queue is accessible
+//!
+//! Running the server with `--check` runs the checks, prints the results and
+//! exits. Otherwise the checks are run automatically on startup and failures
+//! are logged, set `DOCBOX_DISABLE_PREFLIGHT=true` to skip the checks
+
+use docbox_http::core::{
+    aws::SqsClient,
+    database::{DatabasePoolCache, models::tenant::Tenant},
+    notifications::NotificationConfig,
+    processing::office::OfficeConverter,
+    search::SearchIndexFactory,
+    secrets::SecretManager,
+    storage::{StorageLayerFactory, StorageLayerOptions},
+    tenant::tenant_region::verify_tenant_region,
+};
+use std::{future::Future, time::Duration};
+use thiserror::Error;
+
+/// Maximum time to wait for an individual check to complete
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Error returned when running with `--check` and a check failed
+#[derive(Debug, Error)]
+#[error("preflight checks failed")]
+pub struct PreflightFailed;
+
+/// Dependencies to check
+pub struct PreflightDependencies<'a> {
+    pub db_cache: &'a DatabasePoolCache,
+    pub secrets: &'a SecretManager,
+    pub root_secret_name: Option<&'a str>,
+    pub search: &'a SearchIndexFactory,
+    pub storage: &'a StorageLayerFactory,
+    pub converter: &'a OfficeConverter,
+    pub sqs: &'a SqsClient,
+    pub notifications: &'a NotificationConfig,
+}
+
+/// Outcome of a single check
+pub enum PreflightStatus {
+    Pass,
+    Skipped(String),
+    Fail(String),
+}
+
+/// Result of a single dependency check
+pub struct PreflightCheck {
+    /// Dependency that was checked
+    pub dependency: &'static str,
+    /// Target of the check (i.e a specific tenant)
+    pub target: String,
+    /// Outcome of the check
+    pub status: PreflightStatus,
+}
+
+/// Results of all the checks
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    /// Whether any of the checks failed
+    pub fn has_failures(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| matches!(check.status, PreflightStatus::Fail(_)))
+    }
+
+    /// Render the results as a table
+    pub fn table(&self) -> String {
+        let rows: Vec<[String; 4]> = self
+            .checks
+            .iter()
+            .map(|check| {
+                let (status, details) = match &check.status {
+                    PreflightStatus::Pass => ("PASS", String::new()),
+                    PreflightStatus::Skipped(reason) => ("SKIP", reason.clone()),
+                    PreflightStatus::Fail(reason) => ("FAIL", reason.clone()),
+                };
+
+                [
+                    check.dependency.to_string(),
+                    check.target.clone(),
+                    status.to_string(),
+                    details,
+                ]
+            })
+            .collect();
+
+        let header = ["DEPENDENCY", "TARGET", "STATUS", "DETAILS"];
+        let mut widths = header.map(str::len);
+        for row in &rows {
+            for (width, value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(value.len());
+            }
+        }
+
+        let format_row = |values: [&str; 4]| {
+            values
+                .iter()
+                .zip(widths)
+                .map(|(value, width)| format!("{value:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let mut output = format_row(header);
+        for row in &rows {
+            output.push('\n');
+            output.push_str(&format_row([&row[0], &row[1], &row[2], &row[3]]));
+        }
+
+        output
+    }
+
+    /// Log the results of the checks
+    pub fn log(&self) {
+        for check in &self.checks {
+            match &check.status {
+                PreflightStatus::Pass => tracing::info!(
+                    dependency = check.dependency,
+                    target = %check.target,
+                    "preflight check passed"
+                ),
+                PreflightStatus::Skipped(reason) => tracing::info!(
+                    dependency = check.dependency,
+                    target = %check.target,
+                    %reason,
+                    "preflight check skipped"
+                ),
+                PreflightStatus::Fail(reason) => tracing::warn!(
+                    dependency = check.dependency,
+                    target = %check.target,
+                    %reason,
+                    "preflight check failed"
+                ),
+            }
+        }
+    }
+}
+
+/// Run all the preflight checks
+pub async fn run_preflight(deps: PreflightDependencies<'_>) -> PreflightReport {
+    let mut checks = Vec::new();
+
+    // Secrets
+    checks.push(match deps.root_secret_name {
+        Some(secret_name) => {
+            let status = run_check(async {
+                match deps.secrets.has_secret(secret_name).await {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err("secret does not exist".to_string()),
+                    Err(error) => Err(error.to_string()),
+                }
+            })
+            .await;
+
+            PreflightCheck {
+                dependency: "secrets",
+                target: secret_name.to_string(),
+                status,
+            }
+        }
+        None => PreflightCheck {
+            dependency: "secrets",
+            target: "root database credentials".to_string(),
+            status: PreflightStatus::Skipped("using IAM database authentication".to_string()),
+        },
+    });
+
+    // Root database
+    let mut tenants = None;
+    let status = run_check(async {
+        let db = deps
+            .db_cache
+            .get_root_pool()
+            .await
+            .map_err(|error| error.to_string())?;
+        let all = Tenant::all(&db).await.map_err(|error| error.to_string())?;
+        tenants = Some(all);
+        Ok(())
+    })
+    .await;
+    checks.push(PreflightCheck {
+        dependency: "database",
+        target: "root".to_string(),
+        status,
+    });
+
+    // Tenant level dependencies
+    match tenants {
+        Some(tenants) => {
+            for tenant in &tenants {
+                checks.extend(check_tenant(&deps, tenant).await);
+            }
+        }
+        None => {
+            for dependency in ["search", "storage"] {
+                checks.push(PreflightCheck {
+                    dependency,
+                    target: "tenants".to_string(),
+                    status: PreflightStatus::Skipped(
+                        "tenants could not be loaded from the root database".to_string(),
+                    ),
+                });
+            }
+        }
+    }
+
+    // Office converter
+    checks.push(PreflightCheck {
+        dependency: "converter",
+        target: "office converter".to_string(),
+        status: run_check(async {
+            deps.converter
+                .check_health()
+                .await
+                .map_err(|error| error.to_string())
+        })
+        .await,
+    });
+
+    // Notification queue
+    checks.push(match deps.notifications {
+        NotificationConfig::Sqs { queue_url } => PreflightCheck {
+            dependency: "notifications",
+            target: queue_url.clone(),
+            status: run_check(async {
+                deps.sqs
+                    .get_queue_attributes()
+                    .queue_url(queue_url)
+                    .send()
+                    .await
+                    .map(|_| ())
+                    .map_err(|error| display_error_chain(&error))
+            })
+            .await,
+        },
+        NotificationConfig::Noop | NotificationConfig::Mpsc => PreflightCheck {
+            dependency: "notifications",
+            target: "notification queue".to_string(),
+            status: PreflightStatus::Skipped("not using an SQS queue".to_string()),
+        },
+    });
+
+    PreflightReport { checks }
+}
+
+/// Check the dependencies specific to a tenant
+async fn check_tenant(deps: &PreflightDependencies<'_>, tenant: &Tenant) -> Vec<PreflightCheck> {
+    let target = format!("{} ({})", tenant.name, tenant.env);
+
+    let database = run_check(async {
+        deps.db_cache
+            .get_tenant_pool(tenant)
+            .await
+            .map(|_| ())
+            .map_err(|error| error.to_string())
+    })
+    .await;
+
+    let search = run_check(async {
+        let index = deps.search.create_search_index(tenant);
+        match index.index_exists().await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("search index does not exist".to_string()),
+            Err(error) => Err(error.to_string()),
+        }
+    })
+    .await;
+
+    let storage = run_check(async {
+        let layer = deps.storage.create_layer(StorageLayerOptions {
+            bucket_name: tenant.s3_name.clone(),
+            // Encryption is not required to check the bucket
+            encryption: None,
+            deduplicate: tenant.storage_deduplication,
+            tags: Vec::new(),
+        });
+
+        match layer.bucket_exists().await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("bucket does not exist or is not accessible".to_string()),
+            Err(error) => Err(error.to_string()),
+        }
+    })
+    .await;
+
+    let region = match tenant.data_region.as_deref() {
+        Some(region) => match verify_tenant_region(Some(region), deps.storage, deps.search) {
+            Ok(()) => PreflightStatus::Pass,
+            Err(error) => PreflightStatus::Fail(error.to_string()),
+        },
+        None => PreflightStatus::Skipped("tenant has no data region".to_string()),
+    };
+
+    vec![
+        PreflightCheck {
+            dependency: "database",
+            target: target.clone(),
+            status: database,
+        },
+        PreflightCheck {
+            dependency: "search",
+            target: target.clone(),
+            status: search,
+        },
+        PreflightCheck {
+            dependency: "storage",
+            target: target.clone(),
+            status: storage,
+        },
+        PreflightCheck {
+            dependency: "region",
+            target,
+            status: region,
+        },
+    ]
+}
+
+/// Run a check with the [CHECK_TIMEOUT] applied
+async fn run_check<F>(check: F) -> PreflightStatus
+where
+    F: Future<Output = Result<(), String>>,
+{
+    match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(())) => PreflightStatus::Pass,
+        Ok(Err(reason)) => PreflightStatus::Fail(reason),
+        Err(_) => PreflightStatus::Fail(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+    }
+}
+
+/// Format an error along with its sources, AWS SDK errors only provide
+/// a generic message at the top level
+fn display_error_chain(error: &(dyn std::error::Error + 'static)) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+
+    message
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PreflightCheck, PreflightReport, PreflightStatus};
+
+    #[test]
+    fn test_preflight_table() {
+        let report = PreflightReport {
+            checks: vec![
+                PreflightCheck {
+                    dependency: "database",
+                    target: "root".to_string(),
+                    status: PreflightStatus::Pass,
+                },
+                PreflightCheck {
+                    dependency: "notifications",
+                    target: "queue".to_string(),
+                    status: PreflightStatus::Fail("access denied".to_string()),
+                },
+            ],
+        };
+
+        assert!(report.has_failures());
+        assert_eq!(
+            report.table(),
+            "DEPENDENCY     TARGET  STATUS  DETAILS\n\
+             database       root    PASS\n\
+             notifications  queue   FAIL    access denied"
+        );
+    }
+}
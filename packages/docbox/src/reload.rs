@@ -0,0 +1,123 @@
+//! # Reload
+//!
+//! Reloading of the server config file while the server is running. Only
+//! settings that are safe to change without restarting are applied:
+//!
+//! - `server.max_file_size_bytes` Maximum allowed file size
+//! - `logging.format` Logging filter directives
+//!
+//! Reloading is triggered by sending the server a SIGHUP signal or through
+//! the `/admin/reload-config` endpoint
+
+use crate::{
+    config::{ServerConfigFile, ServerConfigFileError},
+    logging::{
+        config::LoggingConfigError,
+        fmt::{FilterHandle, filter_layer},
+    },
+};
+use docbox_http::extensions::{
+    config_reload::{ConfigReloader, ReloadedSettings},
+    max_file_size::MaxFileSizeBytes,
+};
+use std::{error::Error, num::ParseIntError, path::PathBuf, sync::Arc};
+use thiserror::Error;
+use tracing_subscriber::{filter::ParseError, reload};
+
+#[derive(Debug, Error)]
+pub enum ConfigReloadError {
+    #[error("server was not started with a config file")]
+    NoConfigFile,
+
+    #[error(transparent)]
+    ConfigFile(#[from] ServerConfigFileError),
+
+    #[error("invalid DOCBOX_MAX_FILE_SIZE_BYTES: {0}")]
+    MaxFileSize(ParseIntError),
+
+    #[error(transparent)]
+    Logging(#[from] LoggingConfigError),
+
+    #[error("invalid logging filter: {0}")]
+    LoggingFilter(#[from] ParseError),
+
+    #[error("failed to replace logging filter: {0}")]
+    ReplaceFilter(#[from] reload::Error),
+}
+
+/// Reloads the config file applying the settings that can be changed at runtime
+pub struct ServerConfigReloader {
+    /// Path to the config file
+    path: Option<PathBuf>,
+    /// Current max file size
+    max_file_size: MaxFileSizeBytes,
+    /// Handle to the logging filter
+    filter: FilterHandle,
+}
+
+impl ServerConfigReloader {
+    pub fn new(
+        path: Option<PathBuf>,
+        max_file_size: MaxFileSizeBytes,
+        filter: FilterHandle,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            path,
+            max_file_size,
+            filter,
+        })
+    }
+
+    /// Re-read the config file and apply the settings
+    pub fn reload(&self) -> Result<ReloadedSettings, ConfigReloadError> {
+        let path = self.path.as_ref().ok_or(ConfigReloadError::NoConfigFile)?;
+
+        // Load and validate the config before applying any changes
+        let mut config = ServerConfigFile::from_path(path)?;
+        let max_file_size = config
+            .max_file_size_bytes()
+            .map_err(ConfigReloadError::MaxFileSize)?;
+        let logging = config.logging()?;
+        let filter = filter_layer(logging.format.allow_noisy, logging.format.filter.as_deref())?;
+
+        self.filter.reload(filter)?;
+        self.max_file_size.set(max_file_size);
+
+        tracing::info!(
+            max_file_size,
+            log_filter = ?logging.format.filter,
+            "reloaded server config"
+        );
+
+        Ok(ReloadedSettings {
+            max_file_size,
+            log_filter: logging.format.filter,
+        })
+    }
+}
+
+impl ConfigReloader for ServerConfigReloader {
+    fn reload_config(&self) -> Result<ReloadedSettings, Box<dyn Error + Send + Sync>> {
+        self.reload().map_err(|error| error.into())
+    }
+}
+
+/// Reload the config whenever the server receives a SIGHUP signal
+#[cfg(unix)]
+pub async fn reload_on_hangup(reloader: Arc<ServerConfigReloader>) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::error!(?error, "failed to listen for SIGHUP");
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        if let Err(error) = reloader.reload() {
+            tracing::error!(?error, "failed to reload config");
+        }
+    }
+}
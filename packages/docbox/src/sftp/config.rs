@@ -0,0 +1,90 @@
+//! # Config
+//!
+//! Configuration for the SFTP gateway, provided through the `sftp_gateway`
+//! section of the server config file
+
+use docbox_http::core::database::models::{
+    document_box::DocumentBoxScopeRaw, folder::FolderId, tenant::TenantId,
+};
+use serde::Deserialize;
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    path::PathBuf,
+};
+
+/// Default address for the SFTP gateway connections
+const DEFAULT_SFTP_GATEWAY_ADDRESS: SocketAddr =
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 2222));
+
+fn default_address() -> SocketAddr {
+    DEFAULT_SFTP_GATEWAY_ADDRESS
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SftpGatewayConfig {
+    /// Address to accept SSH connections on
+    #[serde(default = "default_address")]
+    pub address: SocketAddr,
+
+    /// Path to the OpenSSH encoded private host key of the server
+    pub host_key_path: PathBuf,
+
+    /// Drops that clients can log in to
+    #[serde(default)]
+    pub drops: Vec<SftpDropConfig>,
+}
+
+/// Login mapping uploaded files into a document box folder
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SftpDropConfig {
+    /// Username for the drop
+    pub username: String,
+    /// Name of the secret containing the password for the drop
+    pub password_secret_name: String,
+    /// Environment of the tenant
+    pub env: String,
+    /// ID of the tenant
+    pub tenant_id: TenantId,
+    /// Scope of the document box to upload into
+    pub scope: DocumentBoxScopeRaw,
+    /// Folder to upload into, defaults to the document box root folder
+    #[serde(default)]
+    pub folder_id: Option<FolderId>,
+    /// User recorded as the creator of the uploaded files
+    #[serde(default)]
+    pub created_by: Option<String>,
+}
+
+impl SftpGatewayConfig {
+    /// Validate values that cannot be checked when deserializing, provides
+    /// the key and reason for the first invalid value
+    pub fn validate(&self) -> Result<(), (&'static str, &'static str)> {
+        if self.host_key_path.as_os_str().is_empty() {
+            return Err(("sftp_gateway.host_key_path", "must not be empty"));
+        }
+
+        for (index, drop) in self.drops.iter().enumerate() {
+            if drop.username.is_empty() {
+                return Err(("sftp_gateway.drops.username", "must not be empty"));
+            }
+
+            if drop.password_secret_name.is_empty() {
+                return Err((
+                    "sftp_gateway.drops.password_secret_name",
+                    "must not be empty",
+                ));
+            }
+
+            if self.drops[..index]
+                .iter()
+                .any(|other| other.username == drop.username)
+            {
+                return Err(("sftp_gateway.drops.username", "must be unique"));
+            }
+        }
+
+        Ok(())
+    }
+}
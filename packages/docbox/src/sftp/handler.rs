@@ -0,0 +1,354 @@
+//! # Handler
+//!
+//! SFTP subsystem for a client logged in to a drop. Drops are write-only, files
+//! can only be written into the root directory and directory listings are always
+//! empty. Written files are buffered until the client closes the file and are
+//! then uploaded into the drop folder
+
+use crate::sftp::{DropUploader, config::SftpDropConfig};
+use bytes::Bytes;
+use docbox_http::{core::shutdown::ShutdownGuard, extensions::max_file_size::MaxFileSizeBytes};
+use russh_sftp::{
+    protocol::{Attrs, File, FileAttributes, Handle, Name, OpenFlags, Status, StatusCode, Version},
+    server::{Handler, StatusReply},
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// Maximum number of handles a client can have open at once
+const MAX_OPEN_HANDLES: usize = 16;
+
+/// Handle opened by the client
+enum OpenHandle {
+    /// Root directory handle
+    Directory,
+    /// File being written by the client
+    File(PendingFile),
+}
+
+/// File being written by the client, uploaded once closed
+struct PendingFile {
+    /// Name of the file
+    name: String,
+    /// Contents written so far
+    bytes: Vec<u8>,
+    /// Whether a write exceeded the maximum file size
+    too_large: bool,
+}
+
+/// SFTP session for a client logged in to `drop`
+pub struct DropSftpHandler<U> {
+    drop: SftpDropConfig,
+    uploader: Arc<U>,
+    max_file_size: MaxFileSizeBytes,
+    /// Version negotiated with the client
+    version: Option<u32>,
+    /// Counter for creating handle identifiers
+    next_handle: u64,
+    /// Handles currently open
+    handles: HashMap<String, OpenHandle>,
+    /// Guard holding shutdown until the session has ended
+    _shutdown_guard: ShutdownGuard,
+}
+
+impl<U> DropSftpHandler<U> {
+    pub fn new(
+        drop: SftpDropConfig,
+        uploader: Arc<U>,
+        max_file_size: MaxFileSizeBytes,
+        shutdown_guard: ShutdownGuard,
+    ) -> Self {
+        Self {
+            drop,
+            uploader,
+            max_file_size,
+            version: None,
+            next_handle: 0,
+            handles: HashMap::new(),
+            _shutdown_guard: shutdown_guard,
+        }
+    }
+
+    /// Store a new open handle, provides the handle identifier
+    fn insert_handle(&mut self, handle: OpenHandle) -> Result<String, StatusReply> {
+        if self.handles.len() >= MAX_OPEN_HANDLES {
+            return Err(StatusCode::Failure.with_message("Too many open handles"));
+        }
+
+        self.next_handle += 1;
+        let id = self.next_handle.to_string();
+        self.handles.insert(id.clone(), handle);
+        Ok(id)
+    }
+
+    /// Current maximum file size in bytes
+    fn max_file_size(&self) -> usize {
+        self.max_file_size.get().max(0) as usize
+    }
+}
+
+impl<U: DropUploader> Handler for DropSftpHandler<U> {
+    type Error = StatusReply;
+
+    fn unimplemented(&self) -> Self::Error {
+        StatusCode::OpUnsupported.into()
+    }
+
+    async fn init(
+        &mut self,
+        version: u32,
+        _extensions: HashMap<String, String>,
+    ) -> Result<Version, Self::Error> {
+        if self.version.is_some() {
+            return Err(StatusCode::ConnectionLost.into());
+        }
+
+        self.version = Some(version);
+        Ok(Version::new())
+    }
+
+    async fn realpath(&mut self, id: u32, path: String) -> Result<Name, Self::Error> {
+        let path = if is_root_path(&path) {
+            "/".to_string()
+        } else {
+            let name = drop_file_name(&path).ok_or(StatusCode::NoSuchFile)?;
+            format!("/{name}")
+        };
+
+        Ok(Name {
+            id,
+            files: vec![File::dummy(path)],
+        })
+    }
+
+    async fn stat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+        if !is_root_path(&path) {
+            return Err(StatusCode::NoSuchFile.into());
+        }
+
+        Ok(Attrs {
+            id,
+            attrs: directory_attributes(),
+        })
+    }
+
+    async fn lstat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+        self.stat(id, path).await
+    }
+
+    async fn fstat(&mut self, id: u32, handle: String) -> Result<Attrs, Self::Error> {
+        let attrs = match self.handles.get(&handle) {
+            Some(OpenHandle::Directory) => directory_attributes(),
+            Some(OpenHandle::File(file)) => {
+                let mut attrs = FileAttributes::empty();
+                attrs.set_regular(true);
+                attrs.size = Some(file.bytes.len() as u64);
+                attrs
+            }
+            None => return Err(invalid_handle()),
+        };
+
+        Ok(Attrs { id, attrs })
+    }
+
+    async fn setstat(
+        &mut self,
+        id: u32,
+        _path: String,
+        _attrs: FileAttributes,
+    ) -> Result<Status, Self::Error> {
+        // Attributes (i.e modified time) are not stored, accepted so
+        // clients preserving attributes don't fail the upload
+        Ok(ok_status(id))
+    }
+
+    async fn fsetstat(
+        &mut self,
+        id: u32,
+        handle: String,
+        _attrs: FileAttributes,
+    ) -> Result<Status, Self::Error> {
+        if !self.handles.contains_key(&handle) {
+            return Err(invalid_handle());
+        }
+
+        Ok(ok_status(id))
+    }
+
+    async fn opendir(&mut self, id: u32, path: String) -> Result<Handle, Self::Error> {
+        if !is_root_path(&path) {
+            return Err(StatusCode::NoSuchFile.into());
+        }
+
+        let handle = self.insert_handle(OpenHandle::Directory)?;
+        Ok(Handle { id, handle })
+    }
+
+    async fn readdir(&mut self, _id: u32, handle: String) -> Result<Name, Self::Error> {
+        match self.handles.get(&handle) {
+            // Drops are write-only, listings are always empty
+            Some(OpenHandle::Directory) => Err(StatusCode::Eof.into()),
+            Some(OpenHandle::File(_)) | None => Err(invalid_handle()),
+        }
+    }
+
+    async fn open(
+        &mut self,
+        id: u32,
+        filename: String,
+        pflags: OpenFlags,
+        _attrs: FileAttributes,
+    ) -> Result<Handle, Self::Error> {
+        if pflags.contains(OpenFlags::READ) || !pflags.contains(OpenFlags::WRITE) {
+            return Err(StatusCode::PermissionDenied.with_message("Drop is write-only"));
+        }
+
+        let name = drop_file_name(&filename)
+            .ok_or_else(|| StatusCode::PermissionDenied.with_message("File name not allowed"))?;
+
+        let handle = self.insert_handle(OpenHandle::File(PendingFile {
+            name: name.to_string(),
+            bytes: Vec::new(),
+            too_large: false,
+        }))?;
+
+        Ok(Handle { id, handle })
+    }
+
+    async fn write(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        data: Vec<u8>,
+    ) -> Result<Status, Self::Error> {
+        let max_file_size = self.max_file_size();
+        let Some(OpenHandle::File(file)) = self.handles.get_mut(&handle) else {
+            return Err(invalid_handle());
+        };
+
+        let end = usize::try_from(offset)
+            .ok()
+            .and_then(|offset| offset.checked_add(data.len()).map(|end| (offset, end)));
+
+        let (offset, end) = match end {
+            Some(value) if !file.too_large && value.1 <= max_file_size => value,
+            _ => {
+                // Contents are discarded, the file is rejected once closed
+                file.too_large = true;
+                file.bytes = Vec::new();
+                return Err(file_too_large());
+            }
+        };
+
+        // Writes can arrive out of order when clients pipeline requests
+        if file.bytes.len() < end {
+            file.bytes.resize(end, 0);
+        }
+
+        file.bytes[offset..end].copy_from_slice(&data);
+
+        Ok(ok_status(id))
+    }
+
+    async fn close(&mut self, id: u32, handle: String) -> Result<Status, Self::Error> {
+        let file = match self.handles.remove(&handle) {
+            Some(OpenHandle::Directory) => return Ok(ok_status(id)),
+            Some(OpenHandle::File(file)) => file,
+            None => return Err(invalid_handle()),
+        };
+
+        if file.too_large {
+            return Err(file_too_large());
+        }
+
+        let name = file.name;
+
+        self.uploader
+            .upload(&self.drop, name.clone(), Bytes::from(file.bytes))
+            .await
+            .map_err(|error| {
+                tracing::error!(?error, %name, username = %self.drop.username, "failed to upload sftp file");
+                StatusCode::Failure.with_message("Failed to store file")
+            })?;
+
+        Ok(ok_status(id))
+    }
+}
+
+/// Whether the `path` refers to the root directory of the drop
+fn is_root_path(path: &str) -> bool {
+    path.split('/').all(|part| part.is_empty() || part == ".")
+}
+
+/// Get the name of the file a client is writing to `path`. Drops do not support
+/// directories so the path must refer to a file directly within the root directory
+///
+/// Provides [None] when the path does not contain a usable file name
+pub fn drop_file_name(path: &str) -> Option<&str> {
+    let mut parts = path
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".");
+
+    let name = parts.next()?;
+
+    if parts.next().is_some()
+        || name == ".."
+        || name.trim().is_empty()
+        || name.chars().any(char::is_control)
+    {
+        return None;
+    }
+
+    Some(name)
+}
+
+fn directory_attributes() -> FileAttributes {
+    let mut attrs = FileAttributes::empty();
+    attrs.set_dir(true);
+    attrs
+}
+
+fn ok_status(id: u32) -> Status {
+    Status {
+        id,
+        status_code: StatusCode::Ok,
+        error_message: "Ok".to_string(),
+        language_tag: "en-US".to_string(),
+    }
+}
+
+fn invalid_handle() -> StatusReply {
+    StatusCode::Failure.with_message("Invalid handle")
+}
+
+fn file_too_large() -> StatusReply {
+    StatusCode::Failure.with_message("File exceeds the maximum file size")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{drop_file_name, is_root_path};
+
+    #[test]
+    fn test_drop_file_name() {
+        assert_eq!(drop_file_name("invoice.pdf"), Some("invoice.pdf"));
+        assert_eq!(drop_file_name("/invoice 1.pdf"), Some("invoice 1.pdf"));
+        assert_eq!(drop_file_name("./scan.tiff"), Some("scan.tiff"));
+        assert_eq!(drop_file_name("/uploads/invoice.pdf"), None);
+        assert_eq!(drop_file_name("../invoice.pdf"), None);
+        assert_eq!(drop_file_name(".."), None);
+        assert_eq!(drop_file_name("/"), None);
+        assert_eq!(drop_file_name(""), None);
+        assert_eq!(drop_file_name("bad\nname.pdf"), None);
+    }
+
+    #[test]
+    fn test_is_root_path() {
+        assert!(is_root_path(""));
+        assert!(is_root_path("."));
+        assert!(is_root_path("/"));
+        assert!(is_root_path("/./"));
+        assert!(!is_root_path("/uploads"));
+        assert!(!is_root_path(".."));
+    }
+}
@@ -0,0 +1,654 @@
+//! # SFTP Gateway
+//!
+//! Optional SFTP ingestion frontend for legacy integrations that can only
+//! push files over SFTP (i.e scanners and line of business exports). Enabled
+//! with the `sftp-gateway` feature and the `sftp_gateway` config file section.
+//!
+//! Each configured drop maps a login onto a document box folder, files written
+//! by the client are uploaded through the standard upload pipeline. Drops are
+//! write-only, directory listings are always empty and only the root directory
+//! is available.
+//!
+//! Clients authenticate using the password of the drop, only the `sftp`
+//! subsystem is available (No shell, exec or forwarding)
+
+use crate::sftp::{
+    config::{SftpDropConfig, SftpGatewayConfig},
+    handler::DropSftpHandler,
+};
+use bytes::Bytes;
+use docbox_http::{
+    core::{
+        Docbox,
+        database::models::folder::Folder,
+        docbox::DocboxError,
+        files::{mime_overrides::get_mime_overrides, upload_file::UploadFile},
+        secrets::{Secret, SecretManager},
+        shutdown::ShutdownCoordinator,
+    },
+    extensions::max_file_size::MaxFileSizeBytes,
+};
+use russh::{
+    Channel, ChannelId, ChannelOpenFailure, Disconnect, MethodKind, MethodSet,
+    keys::PrivateKey,
+    server::{Auth, ChannelOpenHandle, Msg, Session},
+};
+use std::{collections::HashMap, future::Future, io, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::net::TcpListener;
+
+pub mod config;
+mod handler;
+
+/// Time a client can be idle before the connection is closed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Delay before responding to a failed login to slow down guessing
+const FAILED_LOGIN_DELAY: Duration = Duration::from_secs(1);
+
+/// Maximum number of authentication attempts for a single connection
+const MAX_AUTH_ATTEMPTS: usize = 3;
+
+/// Maximum number of channels a single connection can have open at once
+const MAX_CONNECTION_CHANNELS: usize = 4;
+
+/// Stores the files written to a drop
+pub trait DropUploader: Send + Sync + 'static {
+    /// Upload a file written by the client into the drop folder
+    fn upload(
+        &self,
+        drop: &SftpDropConfig,
+        name: String,
+        file_bytes: Bytes,
+    ) -> impl Future<Output = Result<(), DocboxError>> + Send;
+}
+
+impl DropUploader for Docbox {
+    async fn upload(
+        &self,
+        drop: &SftpDropConfig,
+        name: String,
+        file_bytes: Bytes,
+    ) -> Result<(), DocboxError> {
+        let tenant = self.tenant(drop.env.clone(), drop.tenant_id).await?;
+
+        let folder_id = match drop.folder_id {
+            Some(folder_id) => folder_id,
+            None => {
+                Folder::find_root(tenant.db(), &drop.scope)
+                    .await?
+                    .ok_or(DocboxError::UnknownFolder)?
+                    .id
+            }
+        };
+
+        let mime = get_mime_overrides(tenant.db()).await?.resolve_file_mime(
+            &name,
+            mime::APPLICATION_OCTET_STREAM,
+            true,
+        );
+
+        tenant
+            .upload_file(UploadFile {
+                fixed_id: None,
+                parent_id: None,
+                folder_id,
+                document_box: drop.scope.clone(),
+                name,
+                mime,
+                file_bytes,
+                created_by: drop.created_by.clone(),
+                file_key: None,
+                processing_config: None,
+                task_id: None,
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Shared state for the gateway connections
+struct SftpGateway<U> {
+    drops: Vec<SftpDropConfig>,
+    secrets: SecretManager,
+    uploader: Arc<U>,
+    max_file_size: MaxFileSizeBytes,
+}
+
+impl<U> SftpGateway<U> {
+    /// Find the drop for the provided login
+    async fn authenticate(&self, username: &str, password: &str) -> Option<SftpDropConfig> {
+        let drop = self.drops.iter().find(|drop| drop.username == username)?;
+
+        let secret = match self.secrets.get_secret(&drop.password_secret_name).await {
+            Ok(Some(secret)) => secret,
+            Ok(None) => {
+                tracing::error!(%username, "sftp drop password secret is missing");
+                return None;
+            }
+            Err(error) => {
+                tracing::error!(?error, %username, "failed to load sftp drop password");
+                return None;
+            }
+        };
+
+        let expected = match &secret {
+            Secret::String(value) => value.as_bytes(),
+            Secret::Binary(value) => value.as_slice(),
+        };
+
+        constant_time_eq(expected, password.as_bytes()).then(|| drop.clone())
+    }
+}
+
+/// Accept SFTP connections on the configured address until shutdown is requested
+pub async fn serve(
+    config: SftpGatewayConfig,
+    docbox: Docbox,
+    secrets: SecretManager,
+    max_file_size: MaxFileSizeBytes,
+    shutdown: ShutdownCoordinator,
+) -> io::Result<()> {
+    let host_key = russh::keys::load_secret_key(&config.host_key_path, None)
+        .inspect_err(|error| tracing::error!(?error, "failed to load sftp gateway host key"))
+        .map_err(io::Error::other)?;
+
+    let listener = TcpListener::bind(config.address).await?;
+    tracing::debug!(address = %config.address, "sftp gateway started");
+
+    let gateway = Arc::new(SftpGateway {
+        drops: config.drops,
+        secrets,
+        uploader: Arc::new(docbox),
+        max_file_size,
+    });
+
+    serve_listener(listener, host_key, gateway, shutdown).await;
+    Ok(())
+}
+
+/// Accept connections from the `listener` until shutdown is requested
+async fn serve_listener<U: DropUploader>(
+    listener: TcpListener,
+    host_key: PrivateKey,
+    gateway: Arc<SftpGateway<U>>,
+    shutdown: ShutdownCoordinator,
+) {
+    let config = Arc::new(russh::server::Config {
+        methods: MethodSet::from(&[MethodKind::Password][..]),
+        auth_rejection_time: FAILED_LOGIN_DELAY,
+        // Clients try the "none" method first, that shouldn't be delayed
+        auth_rejection_time_initial: Some(Duration::ZERO),
+        max_auth_attempts: MAX_AUTH_ATTEMPTS,
+        inactivity_timeout: Some(IDLE_TIMEOUT),
+        keys: vec![host_key],
+        ..Default::default()
+    });
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            result = listener.accept() => match result {
+                Ok(value) => value,
+                Err(error) => {
+                    tracing::warn!(?error, "failed to accept sftp connection");
+                    continue;
+                }
+            }
+        };
+
+        let connection = SshConnection {
+            gateway: gateway.clone(),
+            shutdown: shutdown.clone(),
+            peer,
+            drop: None,
+            channels: HashMap::new(),
+        };
+
+        let config = config.clone();
+        let shutdown = shutdown.clone();
+
+        shutdown.clone().spawn(async move {
+            let session = match russh::server::run_stream(config, stream, connection).await {
+                Ok(value) => value,
+                Err(error) => {
+                    tracing::debug!(?error, %peer, "failed to start sftp session");
+                    return;
+                }
+            };
+
+            let handle = session.handle();
+            tokio::pin!(session);
+
+            let result = tokio::select! {
+                result = &mut session => result,
+                _ = shutdown.cancelled() => {
+                    _ = handle
+                        .disconnect(
+                            Disconnect::ByApplication,
+                            "Server shutting down".to_string(),
+                            String::new(),
+                        )
+                        .await;
+                    session.await
+                }
+            };
+
+            if let Err(error) = result {
+                tracing::debug!(?error, %peer, "sftp connection closed with error");
+            }
+        });
+    }
+}
+
+/// State for a single SSH connection
+struct SshConnection<U> {
+    gateway: Arc<SftpGateway<U>>,
+    shutdown: ShutdownCoordinator,
+    /// Address of the connected client
+    peer: SocketAddr,
+    /// Drop the client has logged in to
+    drop: Option<SftpDropConfig>,
+    /// Session channels open on the connection, the channel is taken once
+    /// the client starts the SFTP subsystem on it
+    channels: HashMap<ChannelId, Option<Channel<Msg>>>,
+}
+
+impl<U: DropUploader> russh::server::Handler for SshConnection<U> {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+        match self.gateway.authenticate(user, password).await {
+            Some(drop) => {
+                tracing::debug!(username = %user, peer = %self.peer, "sftp client logged in");
+                self.drop = Some(drop);
+                Ok(Auth::Accept)
+            }
+            None => {
+                tracing::debug!(username = %user, peer = %self.peer, "sftp login rejected");
+                Ok(Auth::reject())
+            }
+        }
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: Channel<Msg>,
+        reply: ChannelOpenHandle,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if self.channels.len() >= MAX_CONNECTION_CHANNELS {
+            tracing::debug!(peer = %self.peer, "sftp channel limit reached");
+            reply.reject(ChannelOpenFailure::ResourceShortage).await;
+            return Ok(());
+        }
+
+        self.channels.insert(channel.id(), Some(channel));
+        reply.accept().await;
+        Ok(())
+    }
+
+    async fn subsystem_request(
+        &mut self,
+        channel_id: ChannelId,
+        name: &str,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        let (Some(drop), "sftp") = (self.drop.as_ref(), name) else {
+            session.channel_failure(channel_id)?;
+            return Ok(());
+        };
+
+        let Some(channel) = self
+            .channels
+            .get_mut(&channel_id)
+            .and_then(|channel| channel.take())
+        else {
+            session.channel_failure(channel_id)?;
+            return Ok(());
+        };
+
+        session.channel_success(channel_id)?;
+
+        // The subsystem runs on its own task, the handler holds a shutdown
+        // guard so shutdown waits for the subsystem to end
+        let handler = DropSftpHandler::new(
+            drop.clone(),
+            self.gateway.uploader.clone(),
+            self.gateway.max_file_size.clone(),
+            self.shutdown.guard(),
+        );
+
+        russh_sftp::server::run(channel.into_stream(), handler).await;
+        Ok(())
+    }
+
+    async fn channel_eof(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.close(channel)?;
+        Ok(())
+    }
+
+    async fn channel_close(
+        &mut self,
+        channel: ChannelId,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.channels.remove(&channel);
+        Ok(())
+    }
+}
+
+/// Compare two values in constant time (Relative to their length)
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        DropUploader, MAX_CONNECTION_CHANNELS, SftpGateway, config::SftpDropConfig,
+        constant_time_eq, serve_listener,
+    };
+    use bytes::Bytes;
+    use docbox_http::{
+        core::{
+            database::models::tenant::TenantId,
+            docbox::DocboxError,
+            secrets::{Secret, SecretManager, memory::MemorySecretManager},
+            shutdown::ShutdownCoordinator,
+        },
+        extensions::max_file_size::MaxFileSizeBytes,
+    };
+    use russh::{
+        client,
+        keys::{Algorithm, PrivateKey, PublicKeyOrCertificate},
+    };
+    use russh_sftp::{client::SftpSession, protocol::OpenFlags};
+    use std::{
+        collections::HashMap,
+        net::SocketAddr,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+    use tokio::net::TcpListener;
+
+    /// Uploader recording the uploaded files
+    #[derive(Default)]
+    struct TestUploader {
+        files: Mutex<Vec<(String, Bytes)>>,
+    }
+
+    impl TestUploader {
+        fn files(&self) -> Vec<(String, Bytes)> {
+            self.files.lock().unwrap().clone()
+        }
+    }
+
+    impl DropUploader for TestUploader {
+        async fn upload(
+            &self,
+            drop: &SftpDropConfig,
+            name: String,
+            file_bytes: Bytes,
+        ) -> Result<(), DocboxError> {
+            assert_eq!(drop.username, "scanner");
+            self.files.lock().unwrap().push((name, file_bytes));
+            Ok(())
+        }
+    }
+
+    struct TestClient;
+
+    impl client::Handler for TestClient {
+        type Error = russh::Error;
+
+        async fn check_server_key(
+            &mut self,
+            _key: &PublicKeyOrCertificate,
+        ) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+    }
+
+    /// Start a gateway with a single "scanner" drop on a local address
+    async fn start_gateway(
+        max_file_size: i32,
+    ) -> (SocketAddr, Arc<TestUploader>, ShutdownCoordinator) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let uploader = Arc::new(TestUploader::default());
+        let secrets = SecretManager::Memory(MemorySecretManager::new(
+            HashMap::from([(
+                "sftp/scanner".to_string(),
+                Secret::String("password".to_string()),
+            )]),
+            None,
+        ));
+
+        let gateway = Arc::new(SftpGateway {
+            drops: vec![SftpDropConfig {
+                username: "scanner".to_string(),
+                password_secret_name: "sftp/scanner".to_string(),
+                env: "Development".to_string(),
+                tenant_id: TenantId::nil(),
+                scope: "test".to_string(),
+                folder_id: None,
+                created_by: None,
+            }],
+            secrets,
+            uploader: uploader.clone(),
+            max_file_size: MaxFileSizeBytes::new(max_file_size),
+        });
+
+        let host_key = PrivateKey::random(&mut rand::rng(), Algorithm::Ed25519).unwrap();
+        let shutdown = ShutdownCoordinator::new();
+        tokio::spawn(serve_listener(
+            listener,
+            host_key,
+            gateway,
+            shutdown.clone(),
+        ));
+
+        (address, uploader, shutdown)
+    }
+
+    /// Connect to the gateway, provides whether the login was accepted
+    async fn login(
+        address: SocketAddr,
+        username: &str,
+        password: &str,
+    ) -> (client::Handle<TestClient>, bool) {
+        let mut session = client::connect(Arc::new(client::Config::default()), address, TestClient)
+            .await
+            .unwrap();
+
+        let success = session
+            .authenticate_password(username, password)
+            .await
+            .unwrap()
+            .success();
+
+        (session, success)
+    }
+
+    /// Log in to the drop and start an SFTP session
+    async fn open_sftp(address: SocketAddr) -> (client::Handle<TestClient>, SftpSession) {
+        let (session, success) = login(address, "scanner", "password").await;
+        assert!(success);
+
+        let channel = session.channel_open_session().await.unwrap();
+        channel.request_subsystem(true, "sftp").await.unwrap();
+        let sftp = SftpSession::new(channel.into_stream()).await.unwrap();
+
+        (session, sftp)
+    }
+
+    #[tokio::test]
+    async fn test_login() {
+        let (address, _uploader, _shutdown) = start_gateway(1024).await;
+
+        let (_session, success) = login(address, "scanner", "password").await;
+        assert!(success);
+
+        let (_session, success) = login(address, "scanner", "incorrect").await;
+        assert!(!success);
+
+        let (_session, success) = login(address, "unknown", "password").await;
+        assert!(!success);
+    }
+
+    #[tokio::test]
+    async fn test_upload_file() {
+        let (address, uploader, _shutdown) = start_gateway(1024).await;
+        let (_session, sftp) = open_sftp(address).await;
+
+        assert_eq!(sftp.canonicalize(".").await.unwrap(), "/");
+
+        sftp.write("/invoice.pdf", b"invoice contents")
+            .await
+            .unwrap();
+        sftp.write("scan.tiff", b"scan contents").await.unwrap();
+
+        assert_eq!(
+            uploader.files(),
+            vec![
+                (
+                    "invoice.pdf".to_string(),
+                    Bytes::from_static(b"invoice contents")
+                ),
+                (
+                    "scan.tiff".to_string(),
+                    Bytes::from_static(b"scan contents")
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_listing_empty() {
+        let (address, uploader, _shutdown) = start_gateway(1024).await;
+        let (_session, sftp) = open_sftp(address).await;
+
+        sftp.write("/invoice.pdf", b"invoice contents")
+            .await
+            .unwrap();
+        assert_eq!(uploader.files().len(), 1);
+
+        assert_eq!(sftp.read_dir("/").await.unwrap().count(), 0);
+        assert!(sftp.metadata("/").await.unwrap().is_dir());
+        assert!(sftp.metadata("/invoice.pdf").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bad_file_names_rejected() {
+        let (address, uploader, _shutdown) = start_gateway(1024).await;
+        let (_session, sftp) = open_sftp(address).await;
+
+        for name in [
+            "/",
+            "..",
+            "../escape.pdf",
+            "/uploads/invoice.pdf",
+            "bad\nname.pdf",
+        ] {
+            assert!(sftp.write(name, b"contents").await.is_err(), "{name:?}");
+        }
+
+        assert!(uploader.files().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_rejected() {
+        let (address, uploader, _shutdown) = start_gateway(1024).await;
+        let (_session, sftp) = open_sftp(address).await;
+
+        assert!(sftp.open("/invoice.pdf").await.is_err());
+        assert!(
+            sftp.open_with_flags("/invoice.pdf", OpenFlags::READ | OpenFlags::WRITE)
+                .await
+                .is_err()
+        );
+        assert!(sftp.remove_file("/invoice.pdf").await.is_err());
+        assert!(sftp.create_dir("/uploads").await.is_err());
+
+        assert!(uploader.files().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_size_limit() {
+        let (address, uploader, _shutdown) = start_gateway(16).await;
+        let (_session, sftp) = open_sftp(address).await;
+
+        assert!(sftp.write("/large.pdf", &[0; 32]).await.is_err());
+        sftp.write("/small.pdf", &[0; 16]).await.unwrap();
+
+        assert_eq!(
+            uploader.files(),
+            vec![("small.pdf".to_string(), Bytes::from_static(&[0; 16]))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subsystem_required() {
+        let (address, _uploader, _shutdown) = start_gateway(1024).await;
+        let (session, success) = login(address, "scanner", "password").await;
+        assert!(success);
+
+        let channel = session.channel_open_session().await.unwrap();
+        channel.exec(true, "ls").await.unwrap();
+        let channel = session.channel_open_session().await.unwrap();
+        channel.request_subsystem(true, "shell").await.unwrap();
+        assert!(SftpSession::new(channel.into_stream()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_channel_limit() {
+        let (address, _uploader, _shutdown) = start_gateway(1024).await;
+        let (session, success) = login(address, "scanner", "password").await;
+        assert!(success);
+
+        let mut channels = Vec::new();
+        for _ in 0..MAX_CONNECTION_CHANNELS {
+            let channel = session.channel_open_session().await.unwrap();
+            channel.request_subsystem(true, "sftp").await.unwrap();
+            channels.push(channel);
+        }
+
+        assert!(session.channel_open_session().await.is_err());
+
+        // Closing a channel allows another to be opened
+        channels.pop().unwrap().close().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        session.channel_open_session().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_ends_sessions() {
+        let (address, uploader, shutdown) = start_gateway(1024).await;
+        let (_session, sftp) = open_sftp(address).await;
+
+        sftp.write("/invoice.pdf", b"invoice contents")
+            .await
+            .unwrap();
+        assert!(shutdown.shutdown(Duration::from_secs(5)).await);
+
+        assert!(sftp.write("/scan.tiff", b"scan contents").await.is_err());
+        assert_eq!(uploader.files().len(), 1);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"password", b"password"));
+        assert!(!constant_time_eq(b"password", b"passwore"));
+        assert!(!constant_time_eq(b"password", b"pass"));
+    }
+}